@@ -2,6 +2,7 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
+use crate::canvas_data::font_style_from_str;
 use crate::canvas_data::{
     Backend, CanvasPaintState, Color, CompositionOp, DrawOptions, ExtendMode, Filter,
     GenericDrawTarget, GenericPathBuilder, GradientStop, GradientStops, Path, Pattern,
@@ -130,6 +131,10 @@ impl<'a> CanvasPaintState<'a> {
             shadow_offset_y: 0.0,
             shadow_blur: 0.0,
             shadow_color: Color::Azure(azure_hl::Color::transparent()),
+            font_style: font_style_from_str("10px sans-serif").unwrap(),
+            text_align: TextAlign::default(),
+            text_baseline: TextBaseline::default(),
+            direction: Direction::default(),
         }
     }
 }
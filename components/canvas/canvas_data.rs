@@ -3,15 +3,40 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
 use crate::canvas_paint_thread::AntialiasMode;
+use app_units::Au;
 use canvas_traits::canvas::*;
 use cssparser::RGBA;
 use euclid::default::{Point2D, Rect, Size2D, Transform2D, Vector2D};
+use euclid::Scale;
+use font_kit::font::Font as GlyphOutlineFont;
+use font_kit::hinting::HintingOptions;
+use gfx::font::{FontHandleMethods, FontRef, ShapingFlags, ShapingOptions};
+use gfx::font_cache_thread::FontCacheThread;
+use gfx::font_context::FontContext;
+use gfx::text::glyph::ByteIndex;
 use ipc_channel::ipc::{IpcSender, IpcSharedMemory};
+use lyon_path::builder::{FlatPathBuilder, PathBuilder};
+use lyon_path::math::{Angle, Point, Vector};
 use num_traits::ToPrimitive;
+use ordered_float::NotNan;
+use range::Range;
+use servo_arc::Arc as ServoArc;
+use servo_url::ServoUrl;
+use std::collections::HashMap;
 #[allow(unused_imports)]
 use std::marker::PhantomData;
 use std::mem;
+use std::rc::Rc;
 use std::sync::Arc;
+use style::context::QuirksMode;
+use style::media_queries::{Device, MediaType};
+use style::properties::style_structs::Font as FontStyleStruct;
+use style::properties::{parse_one_declaration_into, SourcePropertyDeclaration};
+use style::properties::{ComputedValues, Importance, PropertyDeclaration};
+use style::properties::{PropertyDeclarationBlock, PropertyId, ShorthandId};
+use style::values::computed::{Context, ToComputedValue};
+use style_traits::ParsingMode;
+use unicode_script::Script;
 use webrender::api::DirtyRect;
 use webrender_api::units::RectExt;
 
@@ -200,6 +225,69 @@ impl<'a> PathBuilderRef<'a> {
         );
     }
 
+    fn arc_to(&mut self, cp1: &Point2D<f32>, cp2: &Point2D<f32>, radius: f32) {
+        let cp0 = match self.current_point() {
+            Some(p) => p,
+            None => return,
+        };
+        let cp1 = *cp1;
+        let cp2 = *cp2;
+
+        if (cp0.x == cp1.x && cp0.y == cp1.y) || cp1 == cp2 || radius == 0.0 {
+            self.line_to(&cp1);
+            return;
+        }
+
+        // if all three control points lie on a single straight line,
+        // connect the first two by a straight line
+        let direction = (cp2.x - cp1.x) * (cp0.y - cp1.y) + (cp2.y - cp1.y) * (cp1.x - cp0.x);
+        if direction == 0.0 {
+            self.line_to(&cp1);
+            return;
+        }
+
+        // otherwise, draw the Arc
+        let a2 = (cp0.x - cp1.x).powi(2) + (cp0.y - cp1.y).powi(2);
+        let b2 = (cp1.x - cp2.x).powi(2) + (cp1.y - cp2.y).powi(2);
+        let d = {
+            let c2 = (cp0.x - cp2.x).powi(2) + (cp0.y - cp2.y).powi(2);
+            let cosx = (a2 + b2 - c2) / (2.0 * (a2 * b2).sqrt());
+            let sinx = (1.0 - cosx.powi(2)).sqrt();
+            radius / ((1.0 - cosx) / sinx)
+        };
+
+        // first tangent point
+        let anx = (cp1.x - cp0.x) / a2.sqrt();
+        let any = (cp1.y - cp0.y) / a2.sqrt();
+        let tp1 = Point2D::new(cp1.x - anx * d, cp1.y - any * d);
+
+        // second tangent point
+        let bnx = (cp1.x - cp2.x) / b2.sqrt();
+        let bny = (cp1.y - cp2.y) / b2.sqrt();
+        let tp2 = Point2D::new(cp1.x - bnx * d, cp1.y - bny * d);
+
+        // arc center and angles
+        let anticlockwise = direction < 0.0;
+        let cx = tp1.x + any * radius * if anticlockwise { 1.0 } else { -1.0 };
+        let cy = tp1.y - anx * radius * if anticlockwise { 1.0 } else { -1.0 };
+        let angle_start = (tp1.y - cy).atan2(tp1.x - cx);
+        let angle_end = (tp2.y - cy).atan2(tp2.x - cx);
+
+        self.line_to(&tp1);
+        if [cx, cy, angle_start, angle_end]
+            .iter()
+            .all(|x| x.is_finite())
+        {
+            self.arc(
+                &Point2D::new(cx, cy),
+                radius,
+                angle_start,
+                angle_end,
+                anticlockwise,
+            );
+        }
+    }
+
     fn current_point(&mut self) -> Option<Point2D<f32>> {
         let inverse = match self.transform.inverse() {
             Some(i) => i,
@@ -208,6 +296,31 @@ impl<'a> PathBuilderRef<'a> {
         let current_point = self.builder.get_current_point();
         Some(inverse.transform_point(Point2D::new(current_point.x, current_point.y)))
     }
+
+    /// Replays the segments recorded by a `Path2D` object onto this builder.
+    fn add_segments(&mut self, segments: &[PathSegment]) {
+        for segment in segments {
+            match *segment {
+                PathSegment::ClosePath => self.builder.close(),
+                PathSegment::MoveTo(ref point) => self.move_to(point),
+                PathSegment::LineTo(ref point) => self.line_to(point),
+                PathSegment::QuadraticCurveTo(ref cp, ref point) => {
+                    self.quadratic_curve_to(cp, point)
+                },
+                PathSegment::BezierCurveTo(ref cp1, ref cp2, ref point) => {
+                    self.bezier_curve_to(cp1, cp2, point)
+                },
+                PathSegment::ArcTo(ref cp1, ref cp2, radius) => self.arc_to(cp1, cp2, radius),
+                PathSegment::Arc(ref center, radius, start, end, ccw) => {
+                    self.arc(center, radius, start, end, ccw)
+                },
+                PathSegment::Ellipse(ref center, radius_x, radius_y, rotation, start, end, ccw) => {
+                    self.ellipse(center, radius_x, radius_y, rotation, start, end, ccw)
+                },
+                PathSegment::Rect(ref rect) => self.rect(rect),
+            }
+        }
+    }
 }
 
 // TODO(pylbrecht)
@@ -247,7 +360,7 @@ pub trait GenericDrawTarget {
         draw_options: &DrawOptions,
     );
     fn draw_surface_with_shadow(
-        &self,
+        &mut self,
         surface: SourceSurface,
         dest: &Point2D<f32>,
         color: &Color,
@@ -340,7 +453,7 @@ pub enum SourceSurface {
     #[cfg(feature = "canvas2d-azure")]
     Azure(azure::azure_hl::SourceSurface),
     #[cfg(feature = "canvas2d-raqote")]
-    Raqote(Vec<u8>), // TODO: See if we can avoid the alloc (probably?)
+    Raqote(Vec<u8>, Size2D<i32>), // TODO: See if we can avoid the alloc (probably?)
 }
 
 #[derive(Clone)]
@@ -400,6 +513,10 @@ pub struct CanvasData<'a> {
     old_image_key: Option<webrender_api::ImageKey>,
     /// An old webrender image key that can be deleted when the current epoch ends.
     very_old_image_key: Option<webrender_api::ImageKey>,
+    font_context: FontContext<FontCacheThread>,
+    /// Fonts parsed by font-kit to read glyph outlines from, keyed by the
+    /// identifier of the gfx font they were loaded for.
+    glyph_outline_fonts: HashMap<String, Option<GlyphOutlineFont>>,
     pub canvas_id: CanvasId,
 }
 
@@ -419,6 +536,7 @@ impl<'a> CanvasData<'a> {
         webrender_api_sender: webrender_api::RenderApiSender,
         antialias: AntialiasMode,
         canvas_id: CanvasId,
+        font_cache_thread: FontCacheThread,
     ) -> CanvasData<'a> {
         let backend = create_backend();
         let draw_target = backend.create_drawtarget(size);
@@ -433,6 +551,8 @@ impl<'a> CanvasData<'a> {
             image_key: None,
             old_image_key: None,
             very_old_image_key: None,
+            font_context: FontContext::new(font_cache_thread),
+            glyph_outline_fonts: HashMap::new(),
             canvas_id: canvas_id,
         }
     }
@@ -473,7 +593,7 @@ impl<'a> CanvasData<'a> {
             );
 
             // TODO(pylbrecht) pass another closure for raqote
-            self.draw_with_shadow(&rect, writer);
+            self.draw_with_shadow(&rect, |new_draw_target, _| writer(new_draw_target));
         } else {
             writer(&mut *self.drawtarget);
         }
@@ -491,11 +611,298 @@ impl<'a> CanvasData<'a> {
         }
     }
 
-    pub fn fill_text(&self, text: String, x: f64, y: f64, max_width: Option<f64>) {
-        error!(
-            "Unimplemented canvas2d.fillText. Values received: {}, {}, {}, {:?}.",
-            text, x, y, max_width
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-filltext
+    pub fn fill_text(&mut self, text: String, x: f64, y: f64, max_width: Option<f64>) {
+        if self.state.fill_style.is_zero_size_gradient() {
+            return; // Paint nothing if gradient size is zero.
+        }
+
+        let (path, bounds) = match self.text_path(&text, x as f32, y as f32, max_width) {
+            Some(text_path) => text_path,
+            None => return,
+        };
+
+        if self.need_to_draw_shadow() {
+            self.draw_with_shadow(
+                &bounds,
+                |new_draw_target: &mut dyn GenericDrawTarget, state: &CanvasPaintState| {
+                    new_draw_target.fill(&path, state.fill_style.clone(), &state.draw_options);
+                },
+            );
+        } else {
+            self.drawtarget.fill(
+                &path,
+                self.state.fill_style.clone(),
+                &self.state.draw_options,
+            );
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-stroketext
+    pub fn stroke_text(&mut self, text: String, x: f64, y: f64, max_width: Option<f64>) {
+        if self.state.stroke_style.is_zero_size_gradient() {
+            return; // Paint nothing if gradient size is zero.
+        }
+
+        let (path, bounds) = match self.text_path(&text, x as f32, y as f32, max_width) {
+            Some(text_path) => text_path,
+            None => return,
+        };
+
+        if self.need_to_draw_shadow() {
+            self.draw_with_shadow(
+                &bounds,
+                |new_draw_target: &mut dyn GenericDrawTarget, state: &CanvasPaintState| {
+                    new_draw_target.stroke(
+                        &path,
+                        state.stroke_style.clone(),
+                        &state.stroke_opts,
+                        &state.draw_options,
+                    );
+                },
+            );
+        } else {
+            self.drawtarget.stroke(
+                &path,
+                self.state.stroke_style.clone(),
+                &self.state.stroke_opts,
+                &self.state.draw_options,
+            );
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-measuretext
+    pub fn measure_text(&mut self, text: String, chan: IpcSender<TextMetrics>) {
+        let metrics = self.text_metrics(&text);
+        chan.send(metrics).unwrap();
+    }
+
+    /// Shapes `text` with the current font and returns its glyphs, positioned along the
+    /// alphabetic baseline relative to the left edge of the text, along with the width
+    /// of the text. The glyphs are in visual order.
+    /// <https://html.spec.whatwg.org/multipage/#text-preparation-algorithm>
+    fn shape_text(&mut self, text: &str) -> (Vec<PositionedGlyph>, f32) {
+        // Step 1: replace all ASCII whitespace with spaces.
+        let text: String = text
+            .chars()
+            .map(|c| if c.is_ascii_whitespace() { ' ' } else { c })
+            .collect();
+
+        // Split the text into runs that are rendered with the same font, falling
+        // back to other fonts for the characters the first one has no glyph for.
+        let font_group = self.font_context.font_group(self.state.font_style.clone());
+        let mut runs: Vec<(FontRef, String)> = vec![];
+        for c in text.chars() {
+            let font = match font_group
+                .borrow_mut()
+                .find_by_codepoint(&mut self.font_context, c)
+            {
+                Some(font) => font,
+                None => continue,
+            };
+            if let Some(run) = runs.last_mut() {
+                if Rc::ptr_eq(&run.0, &font) {
+                    run.1.push(c);
+                    continue;
+                }
+            }
+            runs.push((font, c.to_string()));
+        }
+
+        let mut flags = ShapingFlags::empty();
+        if self.state.direction == Direction::Rtl {
+            flags.insert(ShapingFlags::RTL_FLAG);
+            runs.reverse();
+        }
+        let options = ShapingOptions {
+            letter_spacing: None,
+            word_spacing: (Au(0), NotNan::new(0.).unwrap()),
+            script: Script::Common,
+            flags,
+        };
+
+        let mut glyphs = vec![];
+        let mut advance = Au(0);
+        for (font, run) in runs {
+            let glyph_store = font.borrow_mut().shape_text(&run, &options);
+            let range = Range::new(ByteIndex(0), glyph_store.len());
+            for glyph in glyph_store.iter_glyphs_for_byte_range(&range) {
+                let (offset_x, offset_y) = glyph.offset().map_or((Au(0), Au(0)), |o| (o.x, o.y));
+                glyphs.push(PositionedGlyph {
+                    font: font.clone(),
+                    id: glyph.id(),
+                    origin: Point2D::new((advance + offset_x).to_f32_px(), offset_y.to_f32_px()),
+                });
+                advance += glyph.advance();
+            }
+        }
+        (glyphs, advance.to_f32_px())
+    }
+
+    /// The metrics of the first available font, used to place the baselines.
+    fn first_font_metrics(&mut self) -> Option<BaselineMetrics> {
+        let font_group = self.font_context.font_group(self.state.font_style.clone());
+        let font = font_group.borrow_mut().first(&mut self.font_context)?;
+        let font = font.borrow();
+        let ascent = font.metrics.ascent.to_f32_px();
+        let descent = font.metrics.descent.to_f32_px();
+        let em_size = font.metrics.em_size.to_f32_px();
+        let em_ascent = if ascent + descent > 0. {
+            em_size * ascent / (ascent + descent)
+        } else {
+            em_size
+        };
+        Some(BaselineMetrics {
+            ascent,
+            descent,
+            em_ascent,
+            em_descent: em_size - em_ascent,
+        })
+    }
+
+    /// The horizontal offset of the left edge of a text of the given width from the
+    /// point it is anchored at, according to `textAlign` and `direction`.
+    fn text_anchor_offset(&self, width: f32) -> f32 {
+        let is_rtl = self.state.direction == Direction::Rtl;
+        match self.state.text_align {
+            TextAlign::Left => 0.,
+            TextAlign::Right => -width,
+            TextAlign::Center => -width / 2.,
+            TextAlign::Start if is_rtl => -width,
+            TextAlign::Start => 0.,
+            TextAlign::End if is_rtl => 0.,
+            TextAlign::End => -width,
+        }
+    }
+
+    /// Converts the glyphs of `text` into a path in user space, and returns it with
+    /// the rectangle the text occupies.
+    /// <https://html.spec.whatwg.org/multipage/#text-preparation-algorithm>
+    fn text_path(
+        &mut self,
+        text: &str,
+        x: f32,
+        y: f32,
+        max_width: Option<f64>,
+    ) -> Option<(Path, Rect<f32>)> {
+        // A maximum width that is zero or less leaves nothing to draw.
+        if max_width.map_or(false, |max_width| !(max_width > 0.)) {
+            return None;
+        }
+
+        let metrics = self.first_font_metrics()?;
+        let (glyphs, width) = self.shape_text(text);
+
+        // Squeeze the text horizontally if it is wider than the maximum width.
+        let scale_x = match max_width {
+            Some(max_width) if width > max_width as f32 => max_width as f32 / width,
+            _ => 1.,
+        };
+        let left = x + self.text_anchor_offset(width * scale_x);
+        let baseline = y + metrics.baseline_offset(self.state.text_baseline);
+
+        let mut builder = self.drawtarget.create_path_builder();
+        for glyph in &glyphs {
+            let font = glyph.font.borrow();
+            let outline_font = match self.glyph_outline_font(&font.handle) {
+                Some(outline_font) => outline_font,
+                None => continue,
+            };
+            // Outlines are in font units with the y axis pointing up.
+            let scale =
+                font.actual_pt_size.to_f32_px() / outline_font.metrics().units_per_em as f32;
+            let transform = Transform2D::row_major(
+                scale * scale_x,
+                0.,
+                0.,
+                -scale,
+                left + glyph.origin.x * scale_x,
+                baseline + glyph.origin.y,
+            );
+            let mut outline_builder = GlyphOutlineBuilder::new(&mut *builder, transform);
+            if outline_font
+                .outline(glyph.id, HintingOptions::None, &mut outline_builder)
+                .is_err()
+            {
+                debug!("Couldn't load the outline of glyph {}.", glyph.id);
+            }
+        }
+
+        let bounds = Rect::new(
+            Point2D::new(left, baseline - metrics.ascent),
+            Size2D::new(width * scale_x, metrics.ascent + metrics.descent),
         );
+        Some((builder.finish(), bounds))
+    }
+
+    /// Returns the font-kit font to read the glyph outlines of a gfx font from,
+    /// parsing the font data the first time it is needed.
+    fn glyph_outline_font<H: FontHandleMethods>(
+        &mut self,
+        handle: &H,
+    ) -> Option<&GlyphOutlineFont> {
+        let template = handle.template();
+        self.glyph_outline_fonts
+            .entry(template.identifier.to_string())
+            .or_insert_with(|| {
+                GlyphOutlineFont::from_bytes(Arc::new(template.bytes()), 0)
+                    .map_err(|_| warn!("Couldn't load {} for canvas text.", template.identifier))
+                    .ok()
+            })
+            .as_ref()
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#dom-context-2d-measuretext>
+    fn text_metrics(&mut self, text: &str) -> TextMetrics {
+        let metrics = match self.first_font_metrics() {
+            Some(metrics) => metrics,
+            None => return TextMetrics::default(),
+        };
+        let (glyphs, width) = self.shape_text(text);
+
+        // The actual bounding box of the glyphs, relative to the left edge of the
+        // text on the alphabetic baseline, with y pointing up.
+        let mut ink_bounds: Option<(f32, f32, f32, f32)> = None;
+        for glyph in &glyphs {
+            let font = glyph.font.borrow();
+            let outline_font = match self.glyph_outline_font(&font.handle) {
+                Some(outline_font) => outline_font,
+                None => continue,
+            };
+            let bounds = match outline_font.typographic_bounds(glyph.id) {
+                Ok(bounds) if bounds.size.width > 0. || bounds.size.height > 0. => bounds,
+                _ => continue,
+            };
+            let scale =
+                font.actual_pt_size.to_f32_px() / outline_font.metrics().units_per_em as f32;
+            let left = glyph.origin.x + bounds.origin.x * scale;
+            let right = left + bounds.size.width * scale;
+            let bottom = bounds.origin.y * scale - glyph.origin.y;
+            let top = bottom + bounds.size.height * scale;
+            ink_bounds = Some(match ink_bounds {
+                Some((l, r, t, b)) => (l.min(left), r.max(right), t.max(top), b.min(bottom)),
+                None => (left, right, top, bottom),
+            });
+        }
+        let (ink_left, ink_right, ink_top, ink_bottom) = ink_bounds.unwrap_or((0., 0., 0., 0.));
+
+        let anchor = self.text_anchor_offset(width);
+        // How far the `textBaseline` line is above the alphabetic baseline.
+        let baseline = -metrics.baseline_offset(self.state.text_baseline);
+        TextMetrics {
+            width: width as f64,
+            actual_boundingbox_left: -(anchor + ink_left) as f64,
+            actual_boundingbox_right: (anchor + ink_right) as f64,
+            font_boundingbox_ascent: (metrics.ascent - baseline) as f64,
+            font_boundingbox_descent: (metrics.descent + baseline) as f64,
+            actual_boundingbox_ascent: (ink_top - baseline) as f64,
+            actual_boundingbox_descent: (baseline - ink_bottom) as f64,
+            em_height_ascent: (metrics.em_ascent - baseline) as f64,
+            em_height_descent: (metrics.em_descent + baseline) as f64,
+            hanging_baseline: (metrics.em_ascent - baseline) as f64,
+            alphabetic_baseline: -baseline as f64,
+            ideographic_baseline: (-metrics.em_descent - baseline) as f64,
+        }
     }
 
     pub fn fill_rect(&mut self, rect: &Rect<f32>) {
@@ -511,13 +918,16 @@ impl<'a> CanvasData<'a> {
         );
 
         if self.need_to_draw_shadow() {
-            self.draw_with_shadow(&draw_rect, |new_draw_target: &mut dyn GenericDrawTarget| {
-                new_draw_target.fill_rect(
-                    &draw_rect,
-                    self.state.fill_style.clone(),
-                    Some(&self.state.draw_options),
-                );
-            });
+            self.draw_with_shadow(
+                &draw_rect,
+                |new_draw_target: &mut dyn GenericDrawTarget, state: &CanvasPaintState| {
+                    new_draw_target.fill_rect(
+                        &draw_rect,
+                        state.fill_style.clone(),
+                        Some(&state.draw_options),
+                    );
+                },
+            );
         } else {
             self.drawtarget.fill_rect(
                 &draw_rect,
@@ -537,14 +947,17 @@ impl<'a> CanvasData<'a> {
         }
 
         if self.need_to_draw_shadow() {
-            self.draw_with_shadow(&rect, |new_draw_target: &mut dyn GenericDrawTarget| {
-                new_draw_target.stroke_rect(
-                    rect,
-                    self.state.stroke_style.clone(),
-                    &self.state.stroke_opts,
-                    &self.state.draw_options,
-                );
-            });
+            self.draw_with_shadow(
+                &rect,
+                |new_draw_target: &mut dyn GenericDrawTarget, state: &CanvasPaintState| {
+                    new_draw_target.stroke_rect(
+                        rect,
+                        state.stroke_style.clone(),
+                        &state.stroke_opts,
+                        &state.draw_options,
+                    );
+                },
+            );
         } else if rect.size.width == 0. || rect.size.height == 0. {
             let mut stroke_opts = self.state.stroke_opts.clone();
             stroke_opts.set_line_cap(LineCapStyle::Butt);
@@ -672,6 +1085,50 @@ impl<'a> CanvasData<'a> {
         self.drawtarget.push_clip(&path);
     }
 
+    /// Builds a user-space path out of the segments recorded by a `Path2D` object,
+    /// leaving the current default path untouched.
+    fn path_from_segments(&self, segments: &[PathSegment]) -> Path {
+        let mut builder = self.drawtarget.create_path_builder();
+        PathBuilderRef {
+            builder: &mut builder,
+            transform: Transform2D::identity(),
+        }
+        .add_segments(segments);
+        builder.finish()
+    }
+
+    pub fn fill_path(&mut self, segments: &[PathSegment]) {
+        if self.state.fill_style.is_zero_size_gradient() {
+            return; // Paint nothing if gradient size is zero.
+        }
+
+        let path = self.path_from_segments(segments);
+        self.drawtarget.fill(
+            &path,
+            self.state.fill_style.clone(),
+            &self.state.draw_options,
+        );
+    }
+
+    pub fn stroke_path(&mut self, segments: &[PathSegment]) {
+        if self.state.stroke_style.is_zero_size_gradient() {
+            return; // Paint nothing if gradient size is zero.
+        }
+
+        let path = self.path_from_segments(segments);
+        self.drawtarget.stroke(
+            &path,
+            self.state.stroke_style.clone(),
+            &self.state.stroke_opts,
+            &self.state.draw_options,
+        );
+    }
+
+    pub fn clip_path(&mut self, segments: &[PathSegment]) {
+        let path = self.path_from_segments(segments);
+        self.drawtarget.push_clip(&path);
+    }
+
     pub fn is_point_in_path(
         &mut self,
         x: f64,
@@ -691,6 +1148,19 @@ impl<'a> CanvasData<'a> {
         chan.send(result).unwrap();
     }
 
+    pub fn is_point_in_path_path(
+        &mut self,
+        segments: &[PathSegment],
+        x: f64,
+        y: f64,
+        _fill_rule: FillRule,
+        chan: IpcSender<bool>,
+    ) {
+        let path = self.path_from_segments(segments);
+        let result = path.contains_point(x, y, &self.drawtarget.get_transform());
+        chan.send(result).unwrap();
+    }
+
     pub fn move_to(&mut self, point: &Point2D<f32>) {
         self.path_builder().move_to(point);
     }
@@ -794,66 +1264,7 @@ impl<'a> CanvasData<'a> {
     }
 
     pub fn arc_to(&mut self, cp1: &Point2D<f32>, cp2: &Point2D<f32>, radius: f32) {
-        let cp0 = match self.path_builder().current_point() {
-            Some(p) => p,
-            None => return,
-        };
-        let cp1 = *cp1;
-        let cp2 = *cp2;
-
-        if (cp0.x == cp1.x && cp0.y == cp1.y) || cp1 == cp2 || radius == 0.0 {
-            self.line_to(&cp1);
-            return;
-        }
-
-        // if all three control points lie on a single straight line,
-        // connect the first two by a straight line
-        let direction = (cp2.x - cp1.x) * (cp0.y - cp1.y) + (cp2.y - cp1.y) * (cp1.x - cp0.x);
-        if direction == 0.0 {
-            self.line_to(&cp1);
-            return;
-        }
-
-        // otherwise, draw the Arc
-        let a2 = (cp0.x - cp1.x).powi(2) + (cp0.y - cp1.y).powi(2);
-        let b2 = (cp1.x - cp2.x).powi(2) + (cp1.y - cp2.y).powi(2);
-        let d = {
-            let c2 = (cp0.x - cp2.x).powi(2) + (cp0.y - cp2.y).powi(2);
-            let cosx = (a2 + b2 - c2) / (2.0 * (a2 * b2).sqrt());
-            let sinx = (1.0 - cosx.powi(2)).sqrt();
-            radius / ((1.0 - cosx) / sinx)
-        };
-
-        // first tangent point
-        let anx = (cp1.x - cp0.x) / a2.sqrt();
-        let any = (cp1.y - cp0.y) / a2.sqrt();
-        let tp1 = Point2D::new(cp1.x - anx * d, cp1.y - any * d);
-
-        // second tangent point
-        let bnx = (cp1.x - cp2.x) / b2.sqrt();
-        let bny = (cp1.y - cp2.y) / b2.sqrt();
-        let tp2 = Point2D::new(cp1.x - bnx * d, cp1.y - bny * d);
-
-        // arc center and angles
-        let anticlockwise = direction < 0.0;
-        let cx = tp1.x + any * radius * if anticlockwise { 1.0 } else { -1.0 };
-        let cy = tp1.y - anx * radius * if anticlockwise { 1.0 } else { -1.0 };
-        let angle_start = (tp1.y - cy).atan2(tp1.x - cx);
-        let angle_end = (tp2.y - cy).atan2(tp2.x - cx);
-
-        self.line_to(&tp1);
-        if [cx, cy, angle_start, angle_end]
-            .iter()
-            .all(|x| x.is_finite())
-        {
-            self.arc(
-                &Point2D::new(cx, cy),
-                radius,
-                angle_start,
-                angle_end,
-                anticlockwise,
-            );
-        }
+        self.path_builder().arc_to(cp1, cp2, radius);
     }
 
     pub fn ellipse(
@@ -954,6 +1365,36 @@ impl<'a> CanvasData<'a> {
         });
     }
 
+    /// Returns a copy of the current bitmap, as premultiplied BGRA, along with its size.
+    pub fn bitmap(&self) -> (Vec<u8>, Size2D<u32>) {
+        let size = self.drawtarget.get_size();
+        (
+            self.drawtarget.snapshot_data_owned(),
+            Size2D::new(size.width as u32, size.height as u32),
+        )
+    }
+
+    /// Replaces the bitmap with `data`, premultiplied BGRA of the given size,
+    /// recreating the draw target if the size changed.
+    pub fn replace_bitmap(&mut self, data: &[u8], size: Size2D<u32>) {
+        if self.drawtarget.get_size() != size.to_i32() {
+            self.recreate(size);
+        }
+        let source_surface = match self.drawtarget.create_source_surface_from_data(
+            data,
+            size.to_i32(),
+            size.width as i32 * 4,
+        ) {
+            Some(source_surface) => source_surface,
+            None => return,
+        };
+        self.drawtarget.copy_surface(
+            source_surface,
+            Rect::from_size(size.to_i32()),
+            Point2D::zero(),
+        );
+    }
+
     pub fn send_data(&mut self, chan: IpcSender<CanvasImageData>) {
         let size = self.drawtarget.get_size();
 
@@ -1032,6 +1473,25 @@ impl<'a> CanvasData<'a> {
         self.backend.set_shadow_color(value, &mut self.state);
     }
 
+    pub fn set_font(&mut self, font: String) {
+        match font_style_from_str(&font) {
+            Some(font_style) => self.state.font_style = font_style,
+            None => warn!("Couldn't compute canvas font {}.", font),
+        }
+    }
+
+    pub fn set_text_align(&mut self, text_align: TextAlign) {
+        self.state.text_align = text_align;
+    }
+
+    pub fn set_text_baseline(&mut self, text_baseline: TextBaseline) {
+        self.state.text_baseline = text_baseline;
+    }
+
+    pub fn set_direction(&mut self, direction: Direction) {
+        self.state.direction = direction;
+    }
+
     // https://html.spec.whatwg.org/multipage/#when-shadows-are-drawn
     fn need_to_draw_shadow(&self) -> bool {
         self.backend.need_to_draw_shadow(&self.state.shadow_color) &&
@@ -1055,13 +1515,13 @@ impl<'a> CanvasData<'a> {
         draw_target
     }
 
-    fn draw_with_shadow<F>(&self, rect: &Rect<f32>, draw_shadow_source: F)
+    fn draw_with_shadow<F>(&mut self, rect: &Rect<f32>, draw_shadow_source: F)
     where
-        F: FnOnce(&mut dyn GenericDrawTarget),
+        F: FnOnce(&mut dyn GenericDrawTarget, &CanvasPaintState<'a>),
     {
         let shadow_src_rect = self.state.transform.transform_rect(rect);
         let mut new_draw_target = self.create_draw_target_for_shadow(&shadow_src_rect);
-        draw_shadow_source(&mut *new_draw_target);
+        draw_shadow_source(&mut *new_draw_target, &self.state);
         self.drawtarget.draw_surface_with_shadow(
             new_draw_target.snapshot(),
             &Point2D::new(
@@ -1124,6 +1584,175 @@ pub struct CanvasPaintState<'a> {
     pub shadow_offset_y: f64,
     pub shadow_blur: f64,
     pub shadow_color: Color,
+    pub font_style: ServoArc<FontStyleStruct>,
+    pub text_align: TextAlign,
+    pub text_baseline: TextBaseline,
+    pub direction: Direction,
+}
+
+/// A glyph of a shaped text, with its origin relative to the left edge of the text
+/// on the alphabetic baseline.
+struct PositionedGlyph {
+    font: FontRef,
+    id: u32,
+    origin: Point2D<f32>,
+}
+
+/// The vertical metrics of the first available font of a canvas font, in pixels.
+struct BaselineMetrics {
+    ascent: f32,
+    descent: f32,
+    em_ascent: f32,
+    em_descent: f32,
+}
+
+impl BaselineMetrics {
+    /// How far below the line given by `textBaseline` the alphabetic baseline is.
+    /// The hanging baseline is approximated by the top of the em square.
+    fn baseline_offset(&self, text_baseline: TextBaseline) -> f32 {
+        match text_baseline {
+            TextBaseline::Top | TextBaseline::Hanging => self.em_ascent,
+            TextBaseline::Middle => (self.em_ascent - self.em_descent) / 2.,
+            TextBaseline::Alphabetic => 0.,
+            TextBaseline::Ideographic | TextBaseline::Bottom => -self.em_descent,
+        }
+    }
+}
+
+/// Computes the font given by a serialized value of the `font` attribute. The script
+/// thread has already resolved relative values against the style of the canvas element.
+/// <https://html.spec.whatwg.org/multipage/#dom-context-2d-font>
+pub fn font_style_from_str(font: &str) -> Option<ServoArc<FontStyleStruct>> {
+    let url = ServoUrl::parse("about:blank").unwrap();
+    let mut declarations = SourcePropertyDeclaration::new();
+    parse_one_declaration_into(
+        &mut declarations,
+        PropertyId::Shorthand(ShorthandId::Font),
+        font,
+        &url,
+        None,
+        ParsingMode::DEFAULT,
+        QuirksMode::NoQuirks,
+    )
+    .ok()?;
+    let mut block = PropertyDeclarationBlock::new();
+    block.extend(declarations.drain(), Importance::Normal);
+
+    let device = Device::new(MediaType::screen(), euclid::Size2D::zero(), Scale::new(1.0));
+    let mut font_style = ComputedValues::initial_values().get_font().clone();
+    Context::for_media_query_evaluation(&device, QuirksMode::NoQuirks, |context| {
+        for declaration in block.declarations() {
+            match *declaration {
+                PropertyDeclaration::FontFamily(ref value) => {
+                    font_style.set_font_family(value.to_computed_value(context))
+                },
+                PropertyDeclaration::FontSize(ref value) => {
+                    font_style.set_font_size(value.to_computed_value(context))
+                },
+                PropertyDeclaration::FontStretch(ref value) => {
+                    font_style.set_font_stretch(value.to_computed_value(context))
+                },
+                PropertyDeclaration::FontStyle(ref value) => {
+                    font_style.set_font_style(value.to_computed_value(context))
+                },
+                PropertyDeclaration::FontVariantCaps(ref value) => {
+                    font_style.set_font_variant_caps(value.to_computed_value(context))
+                },
+                PropertyDeclaration::FontWeight(ref value) => {
+                    font_style.set_font_weight(value.to_computed_value(context))
+                },
+                // `line-height` has no effect on canvas text.
+                _ => {},
+            }
+        }
+    });
+    font_style.compute_font_hash();
+    Some(ServoArc::new(font_style))
+}
+
+/// Receives the outline of a glyph from font-kit, in font units, and adds it to a
+/// canvas path builder, transforming every point on the way.
+struct GlyphOutlineBuilder<'a> {
+    builder: &'a mut dyn GenericPathBuilder,
+    transform: Transform2D<f32>,
+    current_position: Point,
+    first_position: Point,
+}
+
+impl<'a> GlyphOutlineBuilder<'a> {
+    fn new(builder: &'a mut dyn GenericPathBuilder, transform: Transform2D<f32>) -> Self {
+        GlyphOutlineBuilder {
+            builder,
+            transform,
+            current_position: Point::zero(),
+            first_position: Point::zero(),
+        }
+    }
+}
+
+impl<'a> FlatPathBuilder for GlyphOutlineBuilder<'a> {
+    type PathType = ();
+
+    fn move_to(&mut self, to: Point) {
+        self.current_position = to;
+        self.first_position = to;
+        self.builder.move_to(self.transform.transform_point(to));
+    }
+
+    fn line_to(&mut self, to: Point) {
+        self.current_position = to;
+        self.builder.line_to(self.transform.transform_point(to));
+    }
+
+    fn close(&mut self) {
+        self.current_position = self.first_position;
+        self.builder.close();
+    }
+
+    fn build(self) {}
+
+    fn build_and_reset(&mut self) {}
+
+    fn current_position(&self) -> Point {
+        self.current_position
+    }
+}
+
+impl<'a> PathBuilder for GlyphOutlineBuilder<'a> {
+    fn quadratic_bezier_to(&mut self, ctrl: Point, to: Point) {
+        self.current_position = to;
+        self.builder.quadratic_curve_to(
+            &self.transform.transform_point(ctrl),
+            &self.transform.transform_point(to),
+        );
+    }
+
+    fn cubic_bezier_to(&mut self, ctrl1: Point, ctrl2: Point, to: Point) {
+        self.current_position = to;
+        self.builder.bezier_curve_to(
+            &self.transform.transform_point(ctrl1),
+            &self.transform.transform_point(ctrl2),
+            &self.transform.transform_point(to),
+        );
+    }
+
+    fn arc(&mut self, center: Point, radii: Vector, sweep_angle: Angle, x_rotation: Angle) {
+        // Glyph outlines are made of lines and curves only, so this is never
+        // expected to be called; approximate the arc with line segments anyway.
+        const SEGMENTS: u32 = 16;
+        let (sin_rotation, cos_rotation) = x_rotation.radians.sin_cos();
+        let start = self.current_position - center;
+        let start_angle = ((cos_rotation * start.y - sin_rotation * start.x) / radii.y)
+            .atan2((cos_rotation * start.x + sin_rotation * start.y) / radii.x);
+        for i in 1..=SEGMENTS {
+            let angle = start_angle + sweep_angle.radians * i as f32 / SEGMENTS as f32;
+            let (x, y) = (radii.x * angle.cos(), radii.y * angle.sin());
+            self.line_to(Point::new(
+                center.x + x * cos_rotation - y * sin_rotation,
+                center.y + x * sin_rotation + y * cos_rotation,
+            ));
+        }
+    }
 }
 
 /// It writes an image to the destination target
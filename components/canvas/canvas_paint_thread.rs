@@ -5,6 +5,7 @@
 use crate::canvas_data::*;
 use canvas_traits::canvas::*;
 use euclid::default::Size2D;
+use gfx::font_cache_thread::FontCacheThread;
 use ipc_channel::ipc::{self, IpcSender};
 use std::borrow::ToOwned;
 use std::collections::HashMap;
@@ -18,24 +19,27 @@ pub enum AntialiasMode {
 pub struct CanvasPaintThread<'a> {
     canvases: HashMap<CanvasId, CanvasData<'a>>,
     next_canvas_id: CanvasId,
+    /// The font cache thread the canvases get the fonts they draw text with from.
+    font_cache_thread: FontCacheThread,
 }
 
 impl<'a> CanvasPaintThread<'a> {
-    fn new() -> CanvasPaintThread<'a> {
+    fn new(font_cache_thread: FontCacheThread) -> CanvasPaintThread<'a> {
         CanvasPaintThread {
             canvases: HashMap::new(),
             next_canvas_id: CanvasId(0),
+            font_cache_thread,
         }
     }
 
     /// Creates a new `CanvasPaintThread` and returns an `IpcSender` to
     /// communicate with it.
-    pub fn start() -> IpcSender<CanvasMsg> {
+    pub fn start(font_cache_thread: FontCacheThread) -> IpcSender<CanvasMsg> {
         let (sender, receiver) = ipc::channel::<CanvasMsg>().unwrap();
         thread::Builder::new()
             .name("CanvasThread".to_owned())
             .spawn(move || {
-                let mut canvas_paint_thread = CanvasPaintThread::new();
+                let mut canvas_paint_thread = CanvasPaintThread::new(font_cache_thread);
                 loop {
                     match receiver.recv() {
                         Ok(msg) => match msg {
@@ -60,6 +64,10 @@ impl<'a> CanvasPaintThread<'a> {
                                 FromScriptMsg::SendPixels(chan) => {
                                     canvas_paint_thread.canvas(canvas_id).send_pixels(chan);
                                 },
+                                FromScriptMsg::CommitToPlaceholder(placeholder_id) => {
+                                    canvas_paint_thread
+                                        .commit_to_placeholder(canvas_id, placeholder_id);
+                                },
                             },
                             CanvasMsg::FromLayout(message, canvas_id) => match message {
                                 FromLayoutMsg::SendData(chan) => {
@@ -94,7 +102,13 @@ impl<'a> CanvasPaintThread<'a> {
         let canvas_id = self.next_canvas_id.clone();
         self.next_canvas_id.0 += 1;
 
-        let canvas_data = CanvasData::new(size, webrender_api_sender, antialias, canvas_id.clone());
+        let canvas_data = CanvasData::new(
+            size,
+            webrender_api_sender,
+            antialias,
+            canvas_id.clone(),
+            self.font_cache_thread.clone(),
+        );
         self.canvases.insert(canvas_id.clone(), canvas_data);
 
         canvas_id
@@ -105,6 +119,10 @@ impl<'a> CanvasPaintThread<'a> {
             Canvas2dMsg::FillText(text, x, y, max_width) => {
                 self.canvas(canvas_id).fill_text(text, x, y, max_width)
             },
+            Canvas2dMsg::StrokeText(text, x, y, max_width) => {
+                self.canvas(canvas_id).stroke_text(text, x, y, max_width)
+            },
+            Canvas2dMsg::MeasureText(text, chan) => self.canvas(canvas_id).measure_text(text, chan),
             Canvas2dMsg::FillRect(ref rect) => self.canvas(canvas_id).fill_rect(rect),
             Canvas2dMsg::StrokeRect(ref rect) => self.canvas(canvas_id).stroke_rect(rect),
             Canvas2dMsg::ClearRect(ref rect) => self.canvas(canvas_id).clear_rect(rect),
@@ -113,9 +131,15 @@ impl<'a> CanvasPaintThread<'a> {
             Canvas2dMsg::Fill => self.canvas(canvas_id).fill(),
             Canvas2dMsg::Stroke => self.canvas(canvas_id).stroke(),
             Canvas2dMsg::Clip => self.canvas(canvas_id).clip(),
+            Canvas2dMsg::FillPath(segments) => self.canvas(canvas_id).fill_path(&segments),
+            Canvas2dMsg::StrokePath(segments) => self.canvas(canvas_id).stroke_path(&segments),
+            Canvas2dMsg::ClipPath(segments) => self.canvas(canvas_id).clip_path(&segments),
             Canvas2dMsg::IsPointInPath(x, y, fill_rule, chan) => self
                 .canvas(canvas_id)
                 .is_point_in_path(x, y, fill_rule, chan),
+            Canvas2dMsg::IsPointInPathPath(segments, x, y, fill_rule, chan) => self
+                .canvas(canvas_id)
+                .is_point_in_path_path(&segments, x, y, fill_rule, chan),
             Canvas2dMsg::DrawImage(
                 imagedata,
                 image_size,
@@ -200,6 +224,25 @@ impl<'a> CanvasPaintThread<'a> {
             },
             Canvas2dMsg::SetShadowBlur(value) => self.canvas(canvas_id).set_shadow_blur(value),
             Canvas2dMsg::SetShadowColor(color) => self.canvas(canvas_id).set_shadow_color(color),
+            Canvas2dMsg::SetFont(font) => self.canvas(canvas_id).set_font(font),
+            Canvas2dMsg::SetTextAlign(align) => self.canvas(canvas_id).set_text_align(align),
+            Canvas2dMsg::SetTextBaseline(baseline) => {
+                self.canvas(canvas_id).set_text_baseline(baseline)
+            },
+            Canvas2dMsg::SetDirection(direction) => self.canvas(canvas_id).set_direction(direction),
+        }
+    }
+
+    /// Copies the bitmap of an `OffscreenCanvas` to the canvas backing its placeholder
+    /// `<canvas>` element, which layout gets the image key it displays from.
+    fn commit_to_placeholder(&mut self, canvas_id: CanvasId, placeholder_id: CanvasId) {
+        let (data, size) = self.canvas(canvas_id).bitmap();
+        match self.canvases.get_mut(&placeholder_id) {
+            Some(placeholder) => placeholder.replace_bitmap(&data, size),
+            None => warn!(
+                "Committing to a closed placeholder canvas {:?}.",
+                placeholder_id
+            ),
         }
     }
 
@@ -2,6 +2,7 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
+use crate::canvas_data::font_style_from_str;
 use crate::canvas_data::{
     Backend, CanvasPaintState, Color, CompositionOp, DrawOptions, ExtendMode, Filter,
     GenericDrawTarget, GenericPathBuilder, GradientStop, GradientStops, Path, Pattern,
@@ -11,6 +12,8 @@ use crate::canvas_paint_thread::AntialiasMode;
 use canvas_traits::canvas::*;
 use cssparser::RGBA;
 use euclid::default::{Point2D, Rect, Size2D, Transform2D, Vector2D};
+use euclid::Angle;
+use lyon_path::geom::Arc;
 use raqote::PathOp;
 use std::marker::PhantomData;
 
@@ -104,6 +107,10 @@ impl<'a> CanvasPaintState<'a> {
                 b: 0,
                 a: 0,
             }),
+            font_style: font_style_from_str("10px sans-serif").unwrap(),
+            text_align: TextAlign::default(),
+            text_baseline: TextBaseline::default(),
+            direction: Direction::default(),
         }
     }
 }
@@ -179,14 +186,41 @@ impl DrawOptions {
 impl Path {
     pub fn transformed_copy_to_builder(
         &self,
-        _transform: &Transform2D<f32>,
+        transform: &Transform2D<f32>,
     ) -> Box<dyn GenericPathBuilder> {
-        unimplemented!()
+        let path = self.as_raqote();
+        let ops = path
+            .ops
+            .iter()
+            .map(|op| match *op {
+                PathOp::MoveTo(point) => PathOp::MoveTo(transform.transform_point(point)),
+                PathOp::LineTo(point) => PathOp::LineTo(transform.transform_point(point)),
+                PathOp::QuadTo(control, point) => PathOp::QuadTo(
+                    transform.transform_point(control),
+                    transform.transform_point(point),
+                ),
+                PathOp::CubicTo(control1, control2, point) => PathOp::CubicTo(
+                    transform.transform_point(control1),
+                    transform.transform_point(control2),
+                    transform.transform_point(point),
+                ),
+                PathOp::Close => PathOp::Close,
+            })
+            .collect();
+        Box::new(PathBuilder(Some(raqote::PathBuilder::from(raqote::Path {
+            ops,
+            winding: path.winding,
+        }))))
     }
 
-    pub fn contains_point(&self, x: f64, y: f64, _path_transform: &Transform2D<f32>) -> bool {
+    pub fn contains_point(&self, x: f64, y: f64, path_transform: &Transform2D<f32>) -> bool {
+        // The point is in device space, while the path is in user space.
+        let point = match path_transform.inverse() {
+            Some(inverse) => inverse.transform_point(Point2D::new(x as f32, y as f32)),
+            None => return false,
+        };
         let path = self.as_raqote();
-        path.contains_point(0.1, path.winding, x as f32, y as f32)
+        path.contains_point(0.1, path.winding, point.x, point.y)
     }
 
     pub fn copy_to_builder(&self) -> Box<dyn GenericPathBuilder> {
@@ -233,7 +267,7 @@ impl GenericDrawTarget for raqote::DrawTarget {
         destination: Point2D<i32>,
     ) {
         let mut dt = raqote::DrawTarget::new(source.size.width, source.size.height);
-        let data = surface.as_raqote();
+        let (data, _) = surface.as_raqote();
         let s = unsafe { std::slice::from_raw_parts(data.as_ptr() as *const u32, data.len() / 4) };
         dt.get_data_mut().copy_from_slice(s);
         raqote::DrawTarget::copy_surface(self, &dt, source.to_box2d(), destination);
@@ -262,10 +296,10 @@ impl GenericDrawTarget for raqote::DrawTarget {
     fn create_source_surface_from_data(
         &self,
         data: &[u8],
-        _size: Size2D<i32>,
+        size: Size2D<i32>,
         _stride: i32,
     ) -> Option<SourceSurface> {
-        Some(SourceSurface::Raqote(data.to_vec()))
+        Some(SourceSurface::Raqote(data.to_vec(), size))
     }
     #[allow(unsafe_code)]
     fn draw_surface(
@@ -273,40 +307,75 @@ impl GenericDrawTarget for raqote::DrawTarget {
         surface: SourceSurface,
         dest: Rect<f64>,
         source: Rect<f64>,
-        _filter: Filter,
+        filter: Filter,
         draw_options: &DrawOptions,
     ) {
-        let v = surface.as_raqote();
+        let (v, _) = surface.as_raqote();
         let image = raqote::Image {
             width: source.size.width as i32,
             height: source.size.height as i32,
             data: unsafe {
                 std::slice::from_raw_parts(
                     v.as_ptr() as *const u32,
-                    v.len() * std::mem::size_of::<u8>(),
+                    v.len() / std::mem::size_of::<u32>(),
                 )
             },
         };
-        raqote::DrawTarget::draw_image_with_size_at(
-            self,
-            dest.size.width as f32,
-            dest.size.height as f32,
+        // The transform of an image source maps user space to image space.
+        let transform =
+            Transform2D::create_translation(-dest.origin.x as f32, -dest.origin.y as f32)
+                .post_scale(
+                    (source.size.width / dest.size.width) as f32,
+                    (source.size.height / dest.size.height) as f32,
+                );
+        let source = raqote::Source::Image(
+            image,
+            raqote::ExtendMode::Pad,
+            filter.to_raqote_style(),
+            transform,
+        );
+        let mut pb = raqote::PathBuilder::new();
+        pb.rect(
             dest.origin.x as f32,
             dest.origin.y as f32,
-            &image,
-            draw_options.as_raqote(),
+            dest.size.width as f32,
+            dest.size.height as f32,
         );
+        raqote::DrawTarget::fill(self, &pb.finish(), &source, draw_options.as_raqote());
     }
+    #[allow(unsafe_code)]
     fn draw_surface_with_shadow(
-        &self,
-        _surface: SourceSurface,
-        _dest: &Point2D<f32>,
-        _color: &Color,
-        _offset: &Vector2D<f32>,
-        _sigma: f32,
-        _operator: CompositionOp,
+        &mut self,
+        surface: SourceSurface,
+        dest: &Point2D<f32>,
+        color: &Color,
+        offset: &Vector2D<f32>,
+        sigma: f32,
+        operator: CompositionOp,
     ) {
-        unimplemented!();
+        let (data, size) = surface.as_raqote();
+        let pixels =
+            unsafe { std::slice::from_raw_parts(data.as_ptr() as *const u32, data.len() / 4) };
+        let shadow = Shadow::new(pixels, *size, sigma, color.as_raqote());
+        let image = raqote::Image {
+            width: shadow.size.width,
+            height: shadow.size.height,
+            data: &shadow.data,
+        };
+
+        // Shadows are positioned in device space, regardless of the current transform.
+        let transform = *raqote::DrawTarget::get_transform(self);
+        raqote::DrawTarget::set_transform(self, &Transform2D::identity());
+        let mut options = raqote::DrawOptions::new();
+        options.blend_mode = operator.as_raqote();
+        raqote::DrawTarget::draw_image_at(
+            self,
+            dest.x + offset.x - shadow.margin as f32,
+            dest.y + offset.y - shadow.margin as f32,
+            &image,
+            &options,
+        );
+        raqote::DrawTarget::set_transform(self, &transform);
     }
     fn fill(&mut self, path: &Path, pattern: Pattern, draw_options: &DrawOptions) {
         self.fill(
@@ -355,7 +424,10 @@ impl GenericDrawTarget for raqote::DrawTarget {
         self.set_transform(matrix);
     }
     fn snapshot(&self) -> SourceSurface {
-        unimplemented!();
+        SourceSurface::Raqote(
+            self.snapshot_data_owned(),
+            Size2D::new(self.width(), self.height()),
+        )
     }
     fn stroke(
         &mut self,
@@ -441,12 +513,108 @@ impl GenericDrawTarget for raqote::DrawTarget {
     }
 }
 
+/// A shadow of a surface: its alpha channel blurred and filled with the shadow
+/// color, with a margin around it for the blur to spread into.
+/// <https://html.spec.whatwg.org/multipage/#when-shadows-are-drawn>
+struct Shadow {
+    data: Vec<u32>,
+    size: Size2D<i32>,
+    margin: i32,
+}
+
+impl Shadow {
+    fn new(pixels: &[u32], size: Size2D<i32>, sigma: f32, color: &raqote::SolidSource) -> Shadow {
+        let margin = (3. * sigma).ceil().max(0.) as i32;
+        let width = (size.width + 2 * margin) as usize;
+        let height = (size.height + 2 * margin) as usize;
+
+        let mut alpha = vec![0u8; width * height];
+        for y in 0..size.height.max(0) as usize {
+            for x in 0..size.width.max(0) as usize {
+                let pixel = pixels
+                    .get(y * size.width as usize + x)
+                    .cloned()
+                    .unwrap_or(0);
+                alpha[(y + margin as usize) * width + x + margin as usize] = (pixel >> 24) as u8;
+            }
+        }
+
+        // Three successive box blurs approximate a gaussian blur.
+        // https://drafts.fxtf.org/filter-effects/#feGaussianBlurElement
+        let radius = (sigma * 3. * (2. * std::f32::consts::PI).sqrt() / 8. + 0.25) as usize;
+        if radius > 0 {
+            for _ in 0..3 {
+                box_blur(&mut alpha, height, width, 1, width, radius);
+                box_blur(&mut alpha, width, height, width, 1, radius);
+            }
+        }
+
+        let data = alpha
+            .iter()
+            .map(|&alpha| {
+                let channel = |value: u8| (value as u32 * alpha as u32 + 127) / 255;
+                channel(color.a) << 24 |
+                    channel(color.r) << 16 |
+                    channel(color.g) << 8 |
+                    channel(color.b)
+            })
+            .collect();
+        Shadow {
+            data,
+            size: Size2D::new(width as i32, height as i32),
+            margin,
+        }
+    }
+}
+
+/// Replaces every value of `count` lines of `length` values of an alpha mask by
+/// the average of the values at most `radius` away from it on the same line.
+fn box_blur(
+    alpha: &mut [u8],
+    count: usize,
+    length: usize,
+    value_stride: usize,
+    line_stride: usize,
+    radius: usize,
+) {
+    let window = 2 * radius as u32 + 1;
+    let mut line = vec![0u8; length];
+    for start in (0..count).map(|index| index * line_stride) {
+        for (i, value) in line.iter_mut().enumerate() {
+            *value = alpha[start + i * value_stride];
+        }
+        let mut sum: u32 = line.iter().take(radius).map(|&value| value as u32).sum();
+        for i in 0..length {
+            if i + radius < length {
+                sum += line[i + radius] as u32;
+            }
+            alpha[start + i * value_stride] = ((sum + window / 2) / window) as u8;
+            if i >= radius {
+                sum -= line[i - radius] as u32;
+            }
+        }
+    }
+}
+
 struct PathBuilder(Option<raqote::PathBuilder>);
 
 impl PathBuilder {
     fn new() -> PathBuilder {
         PathBuilder(Some(raqote::PathBuilder::new()))
     }
+
+    /// The last point of the path being built, if it has any.
+    fn current_point(&mut self) -> Option<Point2D<f32>> {
+        let path = self.0.take().unwrap().finish();
+        let point = path.ops.iter().rev().find_map(|op| match *op {
+            PathOp::MoveTo(point) | PathOp::LineTo(point) => Some(point),
+            PathOp::CubicTo(_, _, point) => Some(point),
+            PathOp::QuadTo(_, point) => Some(point),
+            PathOp::Close => None,
+        });
+        self.0 = Some(raqote::PathBuilder::from(path));
+        point
+    }
 }
 
 impl GenericPathBuilder for PathBuilder {
@@ -456,12 +624,17 @@ impl GenericPathBuilder for PathBuilder {
         radius: f32,
         start_angle: f32,
         end_angle: f32,
-        _anticlockwise: bool,
+        anticlockwise: bool,
     ) {
-        self.0
-            .as_mut()
-            .unwrap()
-            .arc(origin.x, origin.y, radius, start_angle, end_angle);
+        self.ellipse(
+            origin,
+            radius,
+            radius,
+            0.,
+            start_angle,
+            end_angle,
+            anticlockwise,
+        );
     }
     fn bezier_curve_to(
         &mut self,
@@ -481,32 +654,57 @@ impl GenericPathBuilder for PathBuilder {
     fn close(&mut self) {
         self.0.as_mut().unwrap().close();
     }
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-ellipse
     fn ellipse(
         &mut self,
-        _origin: Point2D<f32>,
-        _radius_x: f32,
-        _radius_y: f32,
-        _rotation_angle: f32,
-        _start_angle: f32,
-        _end_angle: f32,
-        _anticlockwise: bool,
+        origin: Point2D<f32>,
+        radius_x: f32,
+        radius_y: f32,
+        rotation_angle: f32,
+        start_angle: f32,
+        end_angle: f32,
+        anticlockwise: bool,
     ) {
-        unimplemented!();
-    }
-    fn get_current_point(&mut self) -> Point2D<f32> {
-        let path = self.finish();
+        let start = Angle::radians(start_angle);
+        let end = Angle::radians(end_angle);
+
+        // A sweep of a full turn or more draws the whole ellipse; otherwise the
+        // angles are wrapped so that the arc goes in the requested direction.
+        let sweep = if !anticlockwise && end - start >= Angle::two_pi() {
+            Angle::two_pi()
+        } else if anticlockwise && start - end >= Angle::two_pi() {
+            -Angle::two_pi()
+        } else {
+            let mut sweep = (end - start).radians % (2. * std::f32::consts::PI);
+            if !anticlockwise && sweep < 0. {
+                sweep += 2. * std::f32::consts::PI;
+            } else if anticlockwise && sweep > 0. {
+                sweep -= 2. * std::f32::consts::PI;
+            }
+            Angle::radians(sweep)
+        };
 
-        for op in path.as_raqote().ops.iter().rev() {
-            match op {
-                PathOp::MoveTo(point) | PathOp::LineTo(point) => {
-                    return Point2D::new(point.x, point.y)
-                },
-                PathOp::CubicTo(_, _, point) => return Point2D::new(point.x, point.y),
-                PathOp::QuadTo(_, point) => return Point2D::new(point.x, point.y),
-                PathOp::Close => {},
-            };
+        let arc: Arc<f32> = Arc {
+            center: origin,
+            radii: Vector2D::new(radius_x, radius_y),
+            start_angle: start,
+            sweep_angle: sweep,
+            x_rotation: Angle::radians(rotation_angle),
+        };
+
+        // The arc is connected to the current point, if there is one.
+        if self.current_point().is_some() {
+            self.line_to(arc.from());
+        } else {
+            self.move_to(arc.from());
         }
-        panic!("dead end");
+
+        arc.for_each_quadratic_bezier(&mut |segment| {
+            self.quadratic_curve_to(&segment.ctrl, &segment.to);
+        });
+    }
+    fn get_current_point(&mut self) -> Point2D<f32> {
+        self.current_point().unwrap_or_else(Point2D::zero)
     }
     fn line_to(&mut self, point: Point2D<f32>) {
         self.0.as_mut().unwrap().line_to(point.x, point.y);
@@ -567,14 +765,49 @@ impl<'a> ToRaqoteSource<'a> for FillOrStrokeStyle {
         use canvas_traits::canvas::FillOrStrokeStyle::*;
 
         match self {
-            Color(rgba) => Some(raqote::Source::Solid(raqote::SolidSource {
-                r: rgba.red,
-                g: rgba.green,
-                b: rgba.blue,
-                a: rgba.alpha,
-            })),
-            LinearGradient(_) => unimplemented!(),
-            RadialGradient(_) => unimplemented!(),
+            Color(rgba) => Some(raqote::Source::Solid(rgba.to_raqote_style())),
+            LinearGradient(style) => {
+                // https://html.spec.whatwg.org/multipage/#dom-context-2d-createlineargradient
+                // Nothing is painted when both points are the same, which an empty
+                // gradient represents.
+                let stops = if style.x0 == style.x1 && style.y0 == style.y1 {
+                    vec![]
+                } else {
+                    style
+                        .stops
+                        .iter()
+                        .map(|stop| stop.to_raqote_style())
+                        .collect()
+                };
+                Some(raqote::Source::new_linear_gradient(
+                    raqote::Gradient { stops },
+                    Point2D::new(style.x0 as f32, style.y0 as f32),
+                    Point2D::new(style.x1 as f32, style.y1 as f32),
+                    raqote::Spread::Pad,
+                ))
+            },
+            RadialGradient(style) => {
+                // https://html.spec.whatwg.org/multipage/#dom-context-2d-createradialgradient
+                // Nothing is painted when both circles are the same.
+                let stops = if style.x0 == style.x1 && style.y0 == style.y1 && style.r0 == style.r1
+                {
+                    vec![]
+                } else {
+                    style
+                        .stops
+                        .iter()
+                        .map(|stop| stop.to_raqote_style())
+                        .collect()
+                };
+                Some(raqote::Source::new_two_circle_radial_gradient(
+                    raqote::Gradient { stops },
+                    Point2D::new(style.x0 as f32, style.y0 as f32),
+                    style.r0 as f32,
+                    Point2D::new(style.x1 as f32, style.y1 as f32),
+                    style.r1 as f32,
+                    raqote::Spread::Pad,
+                ))
+            },
             Surface(ref surface) => {
                 let data = &surface.surface_data[..];
                 Some(raqote::Source::Image(
@@ -605,16 +838,49 @@ impl Color {
 impl ToRaqoteStyle for RGBA {
     type Target = raqote::SolidSource;
 
+    /// Solid sources are premultiplied by their alpha.
     fn to_raqote_style(self) -> Self::Target {
         raqote::SolidSource {
-            r: self.red,
-            g: self.green,
-            b: self.blue,
+            r: premultiply(self.red, self.alpha),
+            g: premultiply(self.green, self.alpha),
+            b: premultiply(self.blue, self.alpha),
             a: self.alpha,
         }
     }
 }
 
+impl ToRaqoteStyle for &CanvasGradientStop {
+    type Target = raqote::GradientStop;
+
+    /// Unlike solid sources, gradient stops are not premultiplied.
+    fn to_raqote_style(self) -> Self::Target {
+        raqote::GradientStop {
+            position: self.offset as f32,
+            color: raqote::Color::new(
+                self.color.alpha,
+                self.color.red,
+                self.color.green,
+                self.color.blue,
+            ),
+        }
+    }
+}
+
+impl ToRaqoteStyle for Filter {
+    type Target = raqote::FilterMode;
+
+    fn to_raqote_style(self) -> Self::Target {
+        match self {
+            Filter::Linear => raqote::FilterMode::Bilinear,
+            Filter::Point => raqote::FilterMode::Nearest,
+        }
+    }
+}
+
+fn premultiply(component: u8, alpha: u8) -> u8 {
+    ((component as u32 * alpha as u32 + 127) / 255) as u8
+}
+
 impl ToRaqoteStyle for CompositionOrBlending {
     type Target = raqote::BlendMode;
 
@@ -645,7 +911,10 @@ impl ToRaqoteStyle for BlendingStyle {
             BlendingStyle::Saturation => raqote::BlendMode::Saturation,
             BlendingStyle::Color => raqote::BlendMode::Color,
             BlendingStyle::Luminosity => raqote::BlendMode::Luminosity,
-            BlendingStyle::ColorBurn => unimplemented!("raqote doesn't support colorburn"),
+            BlendingStyle::ColorBurn => {
+                warn!("raqote doesn't support colorburn, using source-over instead");
+                raqote::BlendMode::SrcOver
+            },
         }
     }
 }
@@ -671,9 +940,17 @@ impl ToRaqoteStyle for CompositionStyle {
 }
 
 impl SourceSurface {
-    fn as_raqote(&self) -> &Vec<u8> {
+    fn as_raqote(&self) -> (&Vec<u8>, &Size2D<i32>) {
+        match self {
+            SourceSurface::Raqote(data, size) => (data, size),
+        }
+    }
+}
+
+impl CompositionOp {
+    fn as_raqote(&self) -> raqote::BlendMode {
         match self {
-            SourceSurface::Raqote(s) => s,
+            CompositionOp::Raqote(op) => *op,
         }
     }
 }
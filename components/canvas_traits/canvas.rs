@@ -49,14 +49,18 @@ pub enum Canvas2dMsg {
     BezierCurveTo(Point2D<f32>, Point2D<f32>, Point2D<f32>),
     ClearRect(Rect<f32>),
     Clip,
+    ClipPath(Vec<PathSegment>),
     ClosePath,
     Ellipse(Point2D<f32>, f32, f32, f32, f32, f32, bool),
     Fill,
+    FillPath(Vec<PathSegment>),
     FillText(String, f64, f64, Option<f64>),
     FillRect(Rect<f32>),
     GetImageData(Rect<u32>, Size2D<u32>, IpcBytesSender),
     IsPointInPath(f64, f64, FillRule, IpcSender<bool>),
+    IsPointInPathPath(Vec<PathSegment>, f64, f64, FillRule, IpcSender<bool>),
     LineTo(Point2D<f32>),
+    MeasureText(String, IpcSender<TextMetrics>),
     MoveTo(Point2D<f32>),
     PutImageData(Rect<u32>, IpcBytesReceiver),
     QuadraticCurveTo(Point2D<f32>, Point2D<f32>),
@@ -65,6 +69,8 @@ pub enum Canvas2dMsg {
     SaveContext,
     StrokeRect(Rect<f32>),
     Stroke,
+    StrokePath(Vec<PathSegment>),
+    StrokeText(String, f64, f64, Option<f64>),
     SetFillStyle(FillOrStrokeStyle),
     SetStrokeStyle(FillOrStrokeStyle),
     SetLineWidth(f32),
@@ -78,6 +84,25 @@ pub enum Canvas2dMsg {
     SetShadowOffsetY(f64),
     SetShadowBlur(f64),
     SetShadowColor(RGBA),
+    SetFont(String),
+    SetTextAlign(TextAlign),
+    SetTextBaseline(TextBaseline),
+    SetDirection(Direction),
+}
+
+/// A path operation recorded by a `Path2D` object, replayed by the canvas paint
+/// thread when the path is filled, stroked, clipped or hit-tested.
+#[derive(Clone, Debug, Deserialize, MallocSizeOf, Serialize)]
+pub enum PathSegment {
+    ClosePath,
+    MoveTo(Point2D<f32>),
+    LineTo(Point2D<f32>),
+    QuadraticCurveTo(Point2D<f32>, Point2D<f32>),
+    BezierCurveTo(Point2D<f32>, Point2D<f32>, Point2D<f32>),
+    ArcTo(Point2D<f32>, Point2D<f32>, f32),
+    Arc(Point2D<f32>, f32, f32, f32, bool),
+    Ellipse(Point2D<f32>, f32, f32, f32, f32, f32, bool),
+    Rect(Rect<f32>),
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -88,6 +113,8 @@ pub enum FromLayoutMsg {
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub enum FromScriptMsg {
     SendPixels(IpcSender<IpcSharedMemory>),
+    /// Copies the current bitmap of the canvas to the placeholder canvas with the given id.
+    CommitToPlaceholder(CanvasId),
 }
 
 #[derive(Clone, Debug, Deserialize, MallocSizeOf, Serialize)]
@@ -228,6 +255,100 @@ impl FromStr for LineJoinStyle {
     }
 }
 
+#[derive(Clone, Copy, Debug, Deserialize, MallocSizeOf, PartialEq, Serialize)]
+pub enum TextAlign {
+    Start,
+    End,
+    Left,
+    Right,
+    Center,
+}
+
+impl FromStr for TextAlign {
+    type Err = ();
+
+    fn from_str(string: &str) -> Result<TextAlign, ()> {
+        match string {
+            "start" => Ok(TextAlign::Start),
+            "end" => Ok(TextAlign::End),
+            "left" => Ok(TextAlign::Left),
+            "right" => Ok(TextAlign::Right),
+            "center" => Ok(TextAlign::Center),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Default for TextAlign {
+    fn default() -> TextAlign {
+        TextAlign::Start
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, MallocSizeOf, PartialEq, Serialize)]
+pub enum TextBaseline {
+    Top,
+    Hanging,
+    Middle,
+    Alphabetic,
+    Ideographic,
+    Bottom,
+}
+
+impl FromStr for TextBaseline {
+    type Err = ();
+
+    fn from_str(string: &str) -> Result<TextBaseline, ()> {
+        match string {
+            "top" => Ok(TextBaseline::Top),
+            "hanging" => Ok(TextBaseline::Hanging),
+            "middle" => Ok(TextBaseline::Middle),
+            "alphabetic" => Ok(TextBaseline::Alphabetic),
+            "ideographic" => Ok(TextBaseline::Ideographic),
+            "bottom" => Ok(TextBaseline::Bottom),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Default for TextBaseline {
+    fn default() -> TextBaseline {
+        TextBaseline::Alphabetic
+    }
+}
+
+/// The direction text is drawn in, once `inherit` has been resolved
+/// against the canvas element.
+#[derive(Clone, Copy, Debug, Deserialize, MallocSizeOf, PartialEq, Serialize)]
+pub enum Direction {
+    Ltr,
+    Rtl,
+}
+
+impl Default for Direction {
+    fn default() -> Direction {
+        Direction::Ltr
+    }
+}
+
+/// The result of measuring a string with `measureText`, in CSS pixels.
+/// <https://html.spec.whatwg.org/multipage/#textmetrics>
+#[derive(Clone, Debug, Default, Deserialize, MallocSizeOf, Serialize)]
+pub struct TextMetrics {
+    pub width: f64,
+    pub actual_boundingbox_left: f64,
+    pub actual_boundingbox_right: f64,
+    pub font_boundingbox_ascent: f64,
+    pub font_boundingbox_descent: f64,
+    pub actual_boundingbox_ascent: f64,
+    pub actual_boundingbox_descent: f64,
+    pub em_height_ascent: f64,
+    pub em_height_descent: f64,
+    pub hanging_baseline: f64,
+    pub alphabetic_baseline: f64,
+    pub ideographic_baseline: f64,
+}
+
 #[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
 pub enum RepetitionStyle {
     Repeat,
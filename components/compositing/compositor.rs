@@ -1384,6 +1384,7 @@ impl<Window: WindowMethods + ?Sized> IOCompositor<Window> {
                     format: PixelFormat::RGB8,
                     bytes: ipc::IpcSharedMemory::from_bytes(&*img),
                     id: None,
+                    animation: None,
                 })
             },
             #[cfg(feature = "gl")]
@@ -190,6 +190,9 @@ mod gen {
                 gamepad: {
                     enabled: bool,
                 },
+                indexeddb: {
+                    enabled: bool,
+                },
                 microdata: {
                     testing: {
                         enabled: bool,
@@ -437,6 +440,12 @@ mod gen {
                 columns: {
                     enabled: bool,
                 },
+                css: {
+                    clip_path_path: {
+                        #[serde(rename = "layout.css.clip-path-path.enabled")]
+                        enabled: bool,
+                    }
+                },
                 #[serde(default = "default_layout_threads")]
                 threads: i64,
                 viewport: {
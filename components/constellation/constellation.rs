@@ -126,12 +126,15 @@ use log::{Level, LevelFilter, Log, Metadata, Record};
 use media::{GLPlayerThreads, WindowGLContext};
 use msg::constellation_msg::{BackgroundHangMonitorRegister, HangMonitorAlert, SamplerControlMsg};
 use msg::constellation_msg::{
-    BrowsingContextGroupId, BrowsingContextId, HistoryStateId, PipelineId,
-    TopLevelBrowsingContextId,
+    BroadcastChannelRouterId, MessagePortId, MessagePortRouterId, PipelineId,
+};
+use msg::constellation_msg::{
+    BrowsingContextGroupId, BrowsingContextId, HistoryStateId, TopLevelBrowsingContextId,
 };
 use msg::constellation_msg::{
     PipelineNamespace, PipelineNamespaceId, PipelineNamespaceRequest, TraversalDirection,
 };
+use net_traits::indexeddb_thread::IndexedDBThreadMsg;
 use net_traits::pub_domains::reg_host;
 use net_traits::request::RequestBuilder;
 use net_traits::storage_thread::{StorageThreadMsg, StorageType};
@@ -144,6 +147,7 @@ use script_traits::{webdriver_msg, LogEntry, ScriptToConstellationChan, ServiceW
 use script_traits::{
     AnimationState, AnimationTickType, AuxiliaryBrowsingContextLoadInfo, CompositorEvent,
 };
+use script_traits::{BroadcastMsg, MessagePortMsg, PortMessageTask, StructuredSerializedData};
 use script_traits::{
     ConstellationControlMsg, ConstellationMsg as FromCompositorMsg, DiscardBrowsingContext,
 };
@@ -205,6 +209,15 @@ struct BrowsingContextGroup {
     event_loops: HashMap<Host, Weak<EventLoop>>,
 }
 
+/// Where a message-port currently lives.
+enum TransferState {
+    /// The port is managed by the global with the given router.
+    Managed(MessagePortRouterId),
+    /// The port is being transferred to another global. Messages sent to it
+    /// in the meantime are buffered here.
+    TransferInProgress(VecDeque<PortMessageTask>),
+}
+
 /// The `Constellation` itself. In the servo browser, there is one
 /// constellation, which maintains all of the browser global data.
 /// In embedded applications, there may be more than one constellation,
@@ -347,6 +360,19 @@ pub struct Constellation<Message, LTF, STF> {
     /// The set of all the browsing contexts in the browser.
     browsing_contexts: HashMap<BrowsingContextId, BrowsingContext>,
 
+    /// The senders to the message-port routers of all globals using ports.
+    message_port_routers: HashMap<MessagePortRouterId, IpcSender<MessagePortMsg>>,
+
+    /// The location of every message-port in use.
+    message_ports: HashMap<MessagePortId, TransferState>,
+
+    /// The senders to the broadcast-channel routers of all globals using channels.
+    broadcast_routers: HashMap<BroadcastChannelRouterId, IpcSender<BroadcastMsg>>,
+
+    /// For each origin and channel name, the routers with at least one channel
+    /// of that name.
+    broadcast_channels: HashMap<ImmutableOrigin, HashMap<String, Vec<BroadcastChannelRouterId>>>,
+
     /// A user agent holds a a set of browsing context groups.
     ///
     /// https://html.spec.whatwg.org/multipage/#browsing-context-group-set
@@ -716,6 +742,8 @@ where
 
                 let (network_listener_sender, network_listener_receiver) = unbounded();
 
+                let canvas_chan = CanvasPaintThread::start(state.font_cache_thread.clone());
+
                 let swmanager_receiver =
                     route_ipc_receiver_to_new_mpsc_receiver_preserving_errors(swmanager_receiver);
 
@@ -753,6 +781,10 @@ where
                     browsing_context_group_next_id: Default::default(),
                     pipelines: HashMap::new(),
                     browsing_contexts: HashMap::new(),
+                    message_port_routers: HashMap::new(),
+                    message_ports: HashMap::new(),
+                    broadcast_routers: HashMap::new(),
+                    broadcast_channels: HashMap::new(),
                     pending_changes: vec![],
                     // We initialize the namespace at 2, since we reserved
                     // namespace 0 for the embedder, and 0 for the constellation
@@ -781,7 +813,7 @@ where
                     webgl_threads: state.webgl_threads,
                     webvr_chan: state.webvr_chan,
                     webxr_registry: state.webxr_registry,
-                    canvas_chan: CanvasPaintThread::start(),
+                    canvas_chan,
                     pending_approval_navigations: HashMap::new(),
                     pressed_mouse_buttons: 0,
                     is_running_problem_test,
@@ -1487,6 +1519,39 @@ where
         };
 
         match content {
+            FromScriptMsg::NewMessagePortRouter(router_id, sender) => {
+                self.handle_new_messageport_router(router_id, sender);
+            },
+            FromScriptMsg::RemoveMessagePortRouter(router_id) => {
+                self.handle_remove_messageport_router(router_id);
+            },
+            FromScriptMsg::NewMessagePort(router_id, port_id) => {
+                self.handle_new_messageport(router_id, port_id);
+            },
+            FromScriptMsg::MessagePortShipped(port_id) => {
+                self.handle_messageport_shipped(port_id);
+            },
+            FromScriptMsg::RemoveMessagePort(port_id) => {
+                self.message_ports.remove(&port_id);
+            },
+            FromScriptMsg::RerouteMessagePort(port_id, task) => {
+                self.handle_reroute_messageport(port_id, task);
+            },
+            FromScriptMsg::NewBroadcastChannelRouter(router_id, sender, origin) => {
+                self.handle_new_broadcast_channel_router(router_id, sender, origin);
+            },
+            FromScriptMsg::RemoveBroadcastChannelRouter(router_id, origin) => {
+                self.handle_remove_broadcast_channel_router(router_id, origin);
+            },
+            FromScriptMsg::NewBroadcastChannelNameInRouter(router_id, name, origin) => {
+                self.handle_new_broadcast_channel_name_in_router(router_id, name, origin);
+            },
+            FromScriptMsg::RemoveBroadcastChannelNameInRouter(router_id, name, origin) => {
+                self.handle_remove_broadcast_channel_name_in_router(router_id, name, origin);
+            },
+            FromScriptMsg::ScheduleBroadcast(router_id, message) => {
+                self.handle_schedule_broadcast(router_id, message);
+            },
             FromScriptMsg::ForwardToEmbedder(embedder_msg) => {
                 self.embedder_proxy
                     .send((Some(source_top_ctx_id), embedder_msg));
@@ -1665,6 +1730,161 @@ where
         }
     }
 
+    fn handle_new_messageport_router(
+        &mut self,
+        router_id: MessagePortRouterId,
+        sender: IpcSender<MessagePortMsg>,
+    ) {
+        self.message_port_routers.insert(router_id, sender);
+    }
+
+    fn handle_remove_messageport_router(&mut self, router_id: MessagePortRouterId) {
+        // Dropping the sender ends the route in the global.
+        self.message_port_routers.remove(&router_id);
+        self.message_ports.retain(|_, state| match *state {
+            TransferState::Managed(id) => id != router_id,
+            TransferState::TransferInProgress(_) => true,
+        });
+    }
+
+    fn handle_new_messageport(&mut self, router_id: MessagePortRouterId, port_id: MessagePortId) {
+        let buffered = match self
+            .message_ports
+            .insert(port_id, TransferState::Managed(router_id))
+        {
+            Some(TransferState::TransferInProgress(buffer)) => buffer,
+            _ => return,
+        };
+        // The port was received as part of a transfer,
+        // hand over the messages that arrived while it was in transit.
+        let result = match self.message_port_routers.get(&router_id) {
+            Some(sender) => sender.send(MessagePortMsg::CompleteTransfer(port_id, buffered)),
+            None => return warn!("No message-port router {:?}.", router_id),
+        };
+        if let Err(e) = result {
+            warn!(
+                "Failed to complete the transfer of port {:?} ({}).",
+                port_id, e
+            );
+        }
+    }
+
+    fn handle_messageport_shipped(&mut self, port_id: MessagePortId) {
+        match self.message_ports.get_mut(&port_id) {
+            Some(state) => *state = TransferState::TransferInProgress(VecDeque::new()),
+            None => warn!("Shipping unknown message-port {:?}.", port_id),
+        }
+    }
+
+    fn handle_reroute_messageport(&mut self, port_id: MessagePortId, task: PortMessageTask) {
+        let router_id = match self.message_ports.get_mut(&port_id) {
+            Some(TransferState::Managed(router_id)) => *router_id,
+            Some(TransferState::TransferInProgress(buffer)) => return buffer.push_back(task),
+            None => return debug!("Dropping message for closed port {:?}.", port_id),
+        };
+        let result = match self.message_port_routers.get(&router_id) {
+            Some(sender) => sender.send(MessagePortMsg::NewTask(port_id, task)),
+            None => return warn!("No message-port router {:?}.", router_id),
+        };
+        if let Err(e) = result {
+            warn!("Failed to route a message to port {:?} ({}).", port_id, e);
+        }
+    }
+
+    fn handle_new_broadcast_channel_router(
+        &mut self,
+        router_id: BroadcastChannelRouterId,
+        sender: IpcSender<BroadcastMsg>,
+        origin: ImmutableOrigin,
+    ) {
+        self.broadcast_routers.insert(router_id, sender);
+        self.broadcast_channels
+            .entry(origin)
+            .or_insert_with(HashMap::new);
+    }
+
+    fn handle_remove_broadcast_channel_router(
+        &mut self,
+        router_id: BroadcastChannelRouterId,
+        origin: ImmutableOrigin,
+    ) {
+        self.broadcast_routers.remove(&router_id);
+        if let Some(channels) = self.broadcast_channels.get_mut(&origin) {
+            for routers in channels.values_mut() {
+                routers.retain(|id| *id != router_id);
+            }
+            channels.retain(|_, routers| !routers.is_empty());
+        }
+    }
+
+    fn handle_new_broadcast_channel_name_in_router(
+        &mut self,
+        router_id: BroadcastChannelRouterId,
+        channel_name: String,
+        origin: ImmutableOrigin,
+    ) {
+        let routers = self
+            .broadcast_channels
+            .entry(origin)
+            .or_insert_with(HashMap::new)
+            .entry(channel_name)
+            .or_insert_with(Vec::new);
+        if !routers.contains(&router_id) {
+            routers.push(router_id);
+        }
+    }
+
+    fn handle_remove_broadcast_channel_name_in_router(
+        &mut self,
+        router_id: BroadcastChannelRouterId,
+        channel_name: String,
+        origin: ImmutableOrigin,
+    ) {
+        let channels = match self.broadcast_channels.get_mut(&origin) {
+            Some(channels) => channels,
+            None => return warn!("No broadcast channels for origin {:?}.", origin),
+        };
+        if let Entry::Occupied(mut entry) = channels.entry(channel_name) {
+            entry.get_mut().retain(|id| *id != router_id);
+            if entry.get().is_empty() {
+                entry.remove();
+            }
+        }
+    }
+
+    fn handle_schedule_broadcast(
+        &self,
+        source_router_id: BroadcastChannelRouterId,
+        message: BroadcastMsg,
+    ) {
+        let routers = match self
+            .broadcast_channels
+            .get(&message.origin)
+            .and_then(|channels| channels.get(&message.channel_name))
+        {
+            Some(routers) => routers,
+            None => return,
+        };
+        // The source global has already dispatched the message to its own channels.
+        for router_id in routers.iter().filter(|id| **id != source_router_id) {
+            let sender = match self.broadcast_routers.get(router_id) {
+                Some(sender) => sender,
+                None => {
+                    warn!("No broadcast-channel router {:?}.", router_id);
+                    continue;
+                },
+            };
+            let message = BroadcastMsg {
+                origin: message.origin.clone(),
+                channel_name: message.channel_name.clone(),
+                data: StructuredSerializedData::without_transfer(message.data.serialized.clone()),
+            };
+            if let Err(e) = sender.send(message) {
+                warn!("Failed to broadcast message to {:?} ({}).", router_id, e);
+            }
+        }
+    }
+
     fn handle_request_from_layout(&mut self, message: FromLayoutMsg) {
         debug!("Constellation got {:?} message", message);
         match message {
@@ -1792,6 +2012,7 @@ where
         let (core_sender, core_receiver) = ipc::channel().expect("Failed to create IPC channel!");
         let (storage_sender, storage_receiver) =
             ipc::channel().expect("Failed to create IPC channel!");
+        let (idb_sender, idb_receiver) = ipc::channel().expect("Failed to create IPC channel!");
 
         debug!("Exiting core resource threads.");
         if let Err(e) = self
@@ -1821,6 +2042,14 @@ where
             warn!("Exit storage thread failed ({})", e);
         }
 
+        debug!("Exiting indexeddb resource threads.");
+        if let Err(e) = self
+            .public_resource_threads
+            .send(IndexedDBThreadMsg::Exit(idb_sender))
+        {
+            warn!("Exit indexeddb thread failed ({})", e);
+        }
+
         debug!("Exiting bluetooth thread.");
         if let Err(e) = self.bluetooth_thread.send(BluetoothRequest::Exit) {
             warn!("Exit bluetooth thread failed ({})", e);
@@ -1874,6 +2103,9 @@ where
         if let Err(e) = storage_receiver.recv() {
             warn!("Exit storage thread failed ({})", e);
         }
+        if let Err(e) = idb_receiver.recv() {
+            warn!("Exit indexeddb thread failed ({})", e);
+        }
 
         debug!("Asking compositor to complete shutdown.");
         self.compositor_proxy
@@ -3203,7 +3435,7 @@ where
         browsing_context_id: BrowsingContextId,
         source_pipeline: PipelineId,
         origin: Option<ImmutableOrigin>,
-        data: Vec<u8>,
+        data: StructuredSerializedData,
     ) {
         let pipeline_id = match self.browsing_contexts.get(&browsing_context_id) {
             None => {
@@ -158,6 +158,8 @@ pub enum EmbedderMsg {
     ShowIME(InputMethodType),
     /// Request to hide the IME when the editable element is blurred.
     HideIME,
+    /// Show a validation message for a form control that failed constraint validation.
+    ShowValidationMessage(String),
     /// Servo has shut down
     Shutdown,
     /// Report a complete sampled profile
@@ -190,6 +192,7 @@ impl Debug for EmbedderMsg {
             EmbedderMsg::SelectFiles(..) => write!(f, "SelectFiles"),
             EmbedderMsg::ShowIME(..) => write!(f, "ShowIME"),
             EmbedderMsg::HideIME => write!(f, "HideIME"),
+            EmbedderMsg::ShowValidationMessage(..) => write!(f, "ShowValidationMessage"),
             EmbedderMsg::Shutdown => write!(f, "Shutdown"),
             EmbedderMsg::AllowOpeningBrowser(..) => write!(f, "AllowOpeningBrowser"),
             EmbedderMsg::BrowserCreated(..) => write!(f, "BrowserCreated"),
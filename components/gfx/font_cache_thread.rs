@@ -11,6 +11,7 @@ use crate::platform::font_list::for_each_variation;
 use crate::platform::font_list::system_default_family;
 use crate::platform::font_list::SANS_SERIF_FONT_FAMILY;
 use crate::platform::font_template::FontTemplateData;
+use crate::woff;
 use app_units::Au;
 use ipc_channel::ipc::{self, IpcReceiver, IpcSender};
 use net_traits::request::{Destination, RequestBuilder};
@@ -113,8 +114,9 @@ pub enum Command {
         Au,
         IpcSender<webrender_api::FontInstanceKey>,
     ),
-    AddWebFont(LowercaseString, EffectiveSources, IpcSender<()>),
-    AddDownloadedWebFont(LowercaseString, ServoUrl, Vec<u8>, IpcSender<()>),
+    AddWebFont(LowercaseString, EffectiveSources, IpcSender<bool>),
+    AddWebFontData(LowercaseString, Vec<u8>, IpcSender<bool>),
+    AddDownloadedWebFont(LowercaseString, ServoUrl, Vec<u8>, IpcSender<bool>),
     Exit(IpcSender<()>),
     Ping,
 }
@@ -138,6 +140,9 @@ struct FontCache {
     webrender_api: webrender_api::RenderApi,
     webrender_fonts: HashMap<Atom, webrender_api::FontKey>,
     font_instances: HashMap<(webrender_api::FontKey, Au), webrender_api::FontInstanceKey>,
+    /// The number of web fonts that were added from in-memory data, used to give
+    /// each of them a unique identifier.
+    web_font_data_count: usize,
 }
 
 fn populate_generic_fonts() -> HashMap<FontFamilyName, LowercaseString> {
@@ -167,6 +172,20 @@ fn populate_generic_fonts() -> HashMap<FontFamilyName, LowercaseString> {
     generic_fonts
 }
 
+/// Decodes the given downloaded web font data if it is a WOFF or WOFF2 font,
+/// and sanitizes it.
+fn decode_web_font(bytes: Vec<u8>) -> Option<Vec<u8>> {
+    let bytes = match woff::decode(bytes) {
+        Ok(bytes) => bytes,
+        Err(error) => {
+            debug!("Failed to decode web font: {}", error);
+            return None;
+        },
+    };
+    // FIXME(servo/fontsan#1): get an error message
+    fontsan::process(&bytes).ok()
+}
+
 impl FontCache {
     fn run(&mut self) {
         loop {
@@ -198,10 +217,13 @@ impl FontCache {
                 Command::AddWebFont(family_name, sources, result) => {
                     self.handle_add_web_font(family_name, sources, result);
                 },
+                Command::AddWebFontData(family_name, bytes, result) => {
+                    self.handle_add_web_font_data(family_name, bytes, result);
+                },
                 Command::AddDownloadedWebFont(family_name, url, bytes, result) => {
                     let templates = &mut self.web_families.get_mut(&family_name).unwrap();
                     templates.add_template(Atom::from(url.to_string()), Some(bytes));
-                    drop(result.send(()));
+                    drop(result.send(true));
                 },
                 Command::Ping => (),
                 Command::Exit(result) => {
@@ -216,12 +238,12 @@ impl FontCache {
         &mut self,
         family_name: LowercaseString,
         mut sources: EffectiveSources,
-        sender: IpcSender<()>,
+        sender: IpcSender<bool>,
     ) {
         let src = if let Some(src) = sources.next() {
             src
         } else {
-            sender.send(()).unwrap();
+            sender.send(false).unwrap();
             return;
         };
 
@@ -235,7 +257,11 @@ impl FontCache {
                 // https://drafts.csswg.org/css-fonts/#font-fetching-requirements
                 let url = match url_source.url.url() {
                     Some(url) => url.clone(),
-                    None => return,
+                    None => {
+                        let msg = Command::AddWebFont(family_name, sources, sender);
+                        self.channel_to_self.send(msg).unwrap();
+                        return;
+                    },
                 };
 
                 let request = RequestBuilder::new(url.clone()).destination(Destination::Font);
@@ -244,8 +270,10 @@ impl FontCache {
                 let bytes = Mutex::new(Vec::new());
                 let response_valid = Mutex::new(false);
                 debug!("Loading @font-face {} from {}", family_name, url);
-                fetch_async(request, &self.core_resource_thread, move |response| {
-                    match response {
+                fetch_async(
+                    request,
+                    &self.core_resource_thread,
+                    move |response| match response {
                         FetchResponseMsg::ProcessRequestBody |
                         FetchResponseMsg::ProcessRequestEOF => (),
                         FetchResponseMsg::ProcessResponse(meta_result) => {
@@ -275,13 +303,11 @@ impl FontCache {
                             }
                             let bytes = mem::replace(&mut *bytes.lock().unwrap(), vec![]);
                             trace!("@font-face {} data={:?}", family_name, bytes);
-                            let bytes = match fontsan::process(&bytes) {
-                                Ok(san) => san,
-                                Err(_) => {
-                                    // FIXME(servo/fontsan#1): get an error message
+                            let bytes = match decode_web_font(bytes) {
+                                Some(bytes) => bytes,
+                                None => {
                                     debug!(
-                                        "Sanitiser rejected web font: \
-                                         family={} url={:?}",
+                                        "Rejected web font: family={} url={:?}",
                                         family_name, url
                                     );
                                     let msg = Command::AddWebFont(
@@ -301,8 +327,8 @@ impl FontCache {
                             );
                             channel_to_self.send(command).unwrap();
                         },
-                    }
-                });
+                    },
+                );
             },
             Source::Local(ref font) => {
                 let font_face_name = LowercaseString::new(&font.name);
@@ -313,7 +339,7 @@ impl FontCache {
                     templates.add_template(Atom::from(&*path), None);
                 });
                 if found {
-                    sender.send(()).unwrap();
+                    sender.send(true).unwrap();
                 } else {
                     let msg = Command::AddWebFont(family_name, sources, sender);
                     self.channel_to_self.send(msg).unwrap();
@@ -322,6 +348,30 @@ impl FontCache {
         }
     }
 
+    fn handle_add_web_font_data(
+        &mut self,
+        family_name: LowercaseString,
+        bytes: Vec<u8>,
+        sender: IpcSender<bool>,
+    ) {
+        let bytes = match decode_web_font(bytes) {
+            Some(bytes) => bytes,
+            None => {
+                debug!("Rejected web font data: family={}", family_name);
+                let _ = sender.send(false);
+                return;
+            },
+        };
+
+        self.web_font_data_count += 1;
+        let identifier = Atom::from(format!("data:{}#{}", family_name, self.web_font_data_count));
+        self.web_families
+            .entry(family_name)
+            .or_insert_with(FontTemplates::new)
+            .add_template(identifier, Some(bytes));
+        let _ = sender.send(true);
+    }
+
     fn refresh_local_families(&mut self) {
         self.local_families.clear();
         for_each_available_family(|family_name| {
@@ -463,6 +513,7 @@ impl FontCacheThread {
                     webrender_api,
                     webrender_fonts: HashMap::new(),
                     font_instances: HashMap::new(),
+                    web_font_data_count: 0,
                 };
 
                 cache.refresh_local_families();
@@ -473,11 +524,13 @@ impl FontCacheThread {
         FontCacheThread { chan: chan }
     }
 
+    /// Loads a web font from the given sources, reporting through `sender`
+    /// whether any of them could be loaded.
     pub fn add_web_font(
         &self,
         family: FamilyName,
         sources: EffectiveSources,
-        sender: IpcSender<()>,
+        sender: IpcSender<bool>,
     ) {
         self.chan
             .send(Command::AddWebFont(
@@ -488,6 +541,18 @@ impl FontCacheThread {
             .unwrap();
     }
 
+    /// Adds a web font from in-memory font data, reporting through `sender`
+    /// whether the data was accepted.
+    pub fn add_web_font_data(&self, family: FamilyName, bytes: Vec<u8>, sender: IpcSender<bool>) {
+        self.chan
+            .send(Command::AddWebFontData(
+                LowercaseString::new(&family.name),
+                bytes,
+                sender,
+            ))
+            .unwrap();
+    }
+
     pub fn exit(&self) {
         let (response_chan, response_port) = ipc::channel().unwrap();
         self.chan
@@ -21,6 +21,7 @@ pub mod font;
 pub mod font_cache_thread;
 pub mod font_context;
 pub mod font_template;
+pub mod woff;
 
 // Platform-specific implementations.
 #[allow(unsafe_code)]
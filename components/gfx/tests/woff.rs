@@ -0,0 +1,275 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use gfx::woff;
+use std::fs::File;
+use std::io::prelude::*;
+use std::path::PathBuf;
+
+fn read_sfnt(filename: &str) -> Vec<u8> {
+    let path: PathBuf = [
+        env!("CARGO_MANIFEST_DIR"),
+        "tests",
+        "support",
+        "CSSTest",
+        filename,
+    ]
+    .iter()
+    .collect();
+    let mut bytes = vec![];
+    File::open(path)
+        .expect("Couldn't open font file")
+        .read_to_end(&mut bytes)
+        .unwrap();
+    bytes
+}
+
+fn read_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_be_bytes([data[offset], data[offset + 1]])
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes([
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    ])
+}
+
+fn push_u16(data: &mut Vec<u8>, value: u16) {
+    data.extend_from_slice(&value.to_be_bytes());
+}
+
+fn push_u32(data: &mut Vec<u8>, value: u32) {
+    data.extend_from_slice(&value.to_be_bytes());
+}
+
+fn checksum(data: &[u8]) -> u32 {
+    data.chunks(4).fold(0u32, |sum, chunk| {
+        let mut word = [0; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        sum.wrapping_add(u32::from_be_bytes(word))
+    })
+}
+
+/// Returns the tables of an sfnt font sorted by tag, with the checksum
+/// adjustment of the head table cleared since it depends on the table layout.
+fn sfnt_tables(sfnt: &[u8]) -> Vec<(u32, Vec<u8>)> {
+    let num_tables = read_u16(sfnt, 4) as usize;
+    let mut tables: Vec<_> = (0..num_tables)
+        .map(|index| {
+            let entry = 12 + 16 * index;
+            let tag = read_u32(sfnt, entry);
+            let offset = read_u32(sfnt, entry + 8) as usize;
+            let length = read_u32(sfnt, entry + 12) as usize;
+            let mut data = sfnt[offset..offset + length].to_vec();
+            if &tag.to_be_bytes() == b"head" {
+                data[8..12].copy_from_slice(&[0; 4]);
+            }
+            (tag, data)
+        })
+        .collect();
+    tables.sort_by_key(|&(tag, _)| tag);
+    tables
+}
+
+/// Checks that the table directory and the checksums of a decoded font are consistent.
+fn assert_valid_sfnt(sfnt: &[u8]) {
+    let num_tables = read_u16(sfnt, 4) as usize;
+    let mut previous_tag = 0;
+    for index in 0..num_tables {
+        let entry = 12 + 16 * index;
+        let tag = read_u32(sfnt, entry);
+        assert!(tag > previous_tag, "tables are not sorted by tag");
+        previous_tag = tag;
+        let offset = read_u32(sfnt, entry + 8) as usize;
+        assert_eq!(offset % 4, 0, "table is not aligned");
+        let length = read_u32(sfnt, entry + 12) as usize;
+        let mut data = sfnt[offset..offset + length].to_vec();
+        if &tag.to_be_bytes() == b"head" {
+            data[8..12].copy_from_slice(&[0; 4]);
+        }
+        assert_eq!(read_u32(sfnt, entry + 4), checksum(&data));
+    }
+    assert_eq!(checksum(sfnt), 0xB1B0_AFBA);
+}
+
+fn sfnt_size(tables: &[(u32, Vec<u8>)]) -> u32 {
+    let data_size: usize = tables.iter().map(|(_, data)| (data.len() + 3) & !3).sum();
+    (12 + 16 * tables.len() + data_size) as u32
+}
+
+/// <https://www.w3.org/TR/WOFF/#WOFFHeader>
+fn encode_woff(sfnt: &[u8]) -> Vec<u8> {
+    let tables = sfnt_tables(sfnt);
+    let compressed: Vec<Vec<u8>> = tables
+        .iter()
+        .map(|(_, data)| {
+            let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+            encoder.write_all(data).unwrap();
+            let compressed = encoder.finish().unwrap();
+            if compressed.len() < data.len() {
+                compressed
+            } else {
+                data.clone()
+            }
+        })
+        .collect();
+
+    let mut directory = vec![];
+    let mut table_data = vec![];
+    let data_offset = 44 + 20 * tables.len();
+    for ((tag, data), compressed) in tables.iter().zip(&compressed) {
+        push_u32(&mut directory, *tag);
+        push_u32(&mut directory, (data_offset + table_data.len()) as u32);
+        push_u32(&mut directory, compressed.len() as u32);
+        push_u32(&mut directory, data.len() as u32);
+        push_u32(&mut directory, checksum(data));
+        table_data.extend_from_slice(compressed);
+        table_data.resize((table_data.len() + 3) & !3, 0);
+    }
+
+    let mut woff = vec![];
+    woff.extend_from_slice(b"wOFF");
+    woff.extend_from_slice(&sfnt[0..4]);
+    push_u32(&mut woff, (data_offset + table_data.len()) as u32);
+    push_u16(&mut woff, tables.len() as u16);
+    push_u16(&mut woff, 0);
+    push_u32(&mut woff, sfnt_size(&tables));
+    push_u16(&mut woff, 1);
+    push_u16(&mut woff, 0);
+    // No metadata nor private data.
+    woff.extend_from_slice(&[0; 20]);
+    woff.extend_from_slice(&directory);
+    woff.extend_from_slice(&table_data);
+    woff
+}
+
+fn push_base128(data: &mut Vec<u8>, value: u32) {
+    let mut bytes = vec![(value & 0x7F) as u8];
+    let mut value = value >> 7;
+    while value != 0 {
+        bytes.push((value & 0x7F) as u8 | 0x80);
+        value >>= 7;
+    }
+    data.extend(bytes.iter().rev());
+}
+
+/// Encodes a WOFF2 font without transforming any table.
+///
+/// <https://www.w3.org/TR/WOFF2/#woff20Header>
+fn encode_woff2(sfnt: &[u8]) -> Vec<u8> {
+    const KNOWN_TAGS: [&[u8; 4]; 12] = [
+        b"cmap", b"head", b"hhea", b"hmtx", b"maxp", b"name", b"OS/2", b"post", b"cvt ", b"fpgm",
+        b"glyf", b"loca",
+    ];
+
+    let tables = sfnt_tables(sfnt);
+    let mut directory = vec![];
+    let mut stream = vec![];
+    for (tag, data) in &tables {
+        let tag_bytes = tag.to_be_bytes();
+        // Version 3 is the null transform of glyf and loca, version 0 the one of
+        // the other tables.
+        let version = if &tag_bytes == b"glyf" || &tag_bytes == b"loca" {
+            3 << 6
+        } else {
+            0
+        };
+        match KNOWN_TAGS.iter().position(|known| **known == tag_bytes) {
+            Some(index) => directory.push(version | index as u8),
+            None => {
+                directory.push(version | 0x3F);
+                push_u32(&mut directory, *tag);
+            },
+        }
+        push_base128(&mut directory, data.len() as u32);
+        stream.extend_from_slice(data);
+    }
+
+    let mut compressed = vec![];
+    {
+        let mut compressor = brotli::CompressorWriter::new(&mut compressed, 4096, 11, 22);
+        compressor.write_all(&stream).unwrap();
+    }
+
+    let mut woff2 = vec![];
+    woff2.extend_from_slice(b"wOF2");
+    woff2.extend_from_slice(&sfnt[0..4]);
+    push_u32(&mut woff2, (48 + directory.len() + compressed.len()) as u32);
+    push_u16(&mut woff2, tables.len() as u16);
+    push_u16(&mut woff2, 0);
+    push_u32(&mut woff2, sfnt_size(&tables));
+    push_u32(&mut woff2, compressed.len() as u32);
+    push_u16(&mut woff2, 1);
+    push_u16(&mut woff2, 0);
+    // No metadata nor private data.
+    woff2.extend_from_slice(&[0; 20]);
+    woff2.extend_from_slice(&directory);
+    woff2.extend_from_slice(&compressed);
+    woff2
+}
+
+#[test]
+fn test_woff_round_trip() {
+    let sfnt = read_sfnt("csstest-ascii.ttf");
+    let decoded = woff::decode(encode_woff(&sfnt)).expect("Couldn't decode WOFF font");
+    assert_valid_sfnt(&decoded);
+    assert_eq!(&decoded[0..4], &sfnt[0..4]);
+    assert_eq!(sfnt_tables(&decoded), sfnt_tables(&sfnt));
+}
+
+#[test]
+fn test_woff2_round_trip() {
+    let sfnt = read_sfnt("csstest-basic-regular.ttf");
+    let decoded = woff::decode(encode_woff2(&sfnt)).expect("Couldn't decode WOFF2 font");
+    assert_valid_sfnt(&decoded);
+    assert_eq!(&decoded[0..4], &sfnt[0..4]);
+    assert_eq!(sfnt_tables(&decoded), sfnt_tables(&sfnt));
+}
+
+#[test]
+fn test_sfnt_is_untouched() {
+    let sfnt = read_sfnt("csstest-ascii.ttf");
+    assert_eq!(woff::decode(sfnt.clone()).unwrap(), sfnt);
+}
+
+#[test]
+fn test_truncated_woff_is_rejected() {
+    let sfnt = read_sfnt("csstest-ascii.ttf");
+    for encoded in vec![encode_woff(&sfnt), encode_woff2(&sfnt)] {
+        let length = encoded.len();
+        assert!(woff::decode(encoded[..length - 1].to_vec()).is_err());
+        assert!(woff::decode(encoded[..40].to_vec()).is_err());
+    }
+}
+
+#[test]
+fn test_corrupt_woff_is_rejected() {
+    let sfnt = read_sfnt("csstest-ascii.ttf");
+
+    // Overwrite the start of the data of the compressed glyf table.
+    let mut woff = encode_woff(&sfnt);
+    let num_tables = read_u16(&woff, 12) as usize;
+    let glyf = (0..num_tables)
+        .map(|index| 44 + 20 * index)
+        .find(|&entry| &woff[entry..entry + 4] == b"glyf")
+        .unwrap();
+    assert!(read_u32(&woff, glyf + 8) < read_u32(&woff, glyf + 12));
+    let offset = read_u32(&woff, glyf + 4) as usize;
+    woff[offset..offset + 8].copy_from_slice(&[0xFF; 8]);
+    assert!(woff::decode(woff).is_err());
+
+    // Overwrite the whole brotli stream.
+    let mut woff2 = encode_woff2(&sfnt);
+    let length = woff2.len();
+    let compressed_length = read_u32(&woff2, 20) as usize;
+    for byte in &mut woff2[length - compressed_length..] {
+        *byte = 0xFF;
+    }
+    assert!(woff::decode(woff2).is_err());
+}
@@ -0,0 +1,820 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Decoding of [WOFF] and [WOFF2] web fonts into the plain sfnt (TrueType or
+//! OpenType) data that the platform font backends understand.
+//!
+//! [WOFF]: https://www.w3.org/TR/WOFF/
+//! [WOFF2]: https://www.w3.org/TR/WOFF2/
+
+use brotli::Decompressor;
+use flate2::read::ZlibDecoder;
+use std::fmt;
+use std::io::Read;
+
+/// The signature of WOFF files, "wOFF".
+const WOFF_SIGNATURE: u32 = 0x774F_4646;
+/// The signature of WOFF2 files, "wOF2".
+const WOFF2_SIGNATURE: u32 = 0x774F_4632;
+/// The flavor of font collections, "ttcf".
+const COLLECTION_FLAVOR: u32 = 0x7474_6366;
+
+const GLYF_TAG: u32 = 0x676C_7966;
+const HEAD_TAG: u32 = 0x6865_6164;
+const HHEA_TAG: u32 = 0x6868_6561;
+const HMTX_TAG: u32 = 0x686D_7478;
+const LOCA_TAG: u32 = 0x6C6F_6361;
+const MAXP_TAG: u32 = 0x6D61_7870;
+
+/// The maximum size of a decoded font, so that a small compressed font cannot
+/// make us allocate an arbitrary amount of memory.
+const MAX_SFNT_SIZE: usize = 30 * 1024 * 1024;
+
+/// The tags of the tables that a WOFF2 table directory can refer to by index.
+///
+/// <https://www.w3.org/TR/WOFF2/#table_dir_format>
+const KNOWN_TABLE_TAGS: [&[u8; 4]; 63] = [
+    b"cmap", b"head", b"hhea", b"hmtx", b"maxp", b"name", b"OS/2", b"post", b"cvt ", b"fpgm",
+    b"glyf", b"loca", b"prep", b"CFF ", b"VORG", b"EBDT", b"EBLC", b"gasp", b"hdmx", b"kern",
+    b"LTSH", b"PCLT", b"VDMX", b"vhea", b"vmtx", b"BASE", b"GDEF", b"GPOS", b"GSUB", b"EBSC",
+    b"JSTF", b"MATH", b"CBDT", b"CBLC", b"COLR", b"CPAL", b"SVG ", b"sbix", b"acnt", b"avar",
+    b"bdat", b"bloc", b"bsln", b"cvar", b"fdsc", b"feat", b"fmtx", b"fvar", b"gvar", b"hsty",
+    b"just", b"lcar", b"mort", b"morx", b"opbd", b"prop", b"trak", b"Zapf", b"Silf", b"Glat",
+    b"Gloc", b"Feat", b"Sill",
+];
+
+/// An error that occurred while decoding a web font.
+#[derive(Debug)]
+pub enum DecodeError {
+    /// The font data ended unexpectedly.
+    Truncated,
+    /// The font data is not well-formed.
+    Malformed(&'static str),
+    /// The font data uses a feature that we do not support.
+    Unsupported(&'static str),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DecodeError::Truncated => write!(f, "unexpected end of font data"),
+            DecodeError::Malformed(reason) => write!(f, "malformed font data: {}", reason),
+            DecodeError::Unsupported(reason) => write!(f, "unsupported font data: {}", reason),
+        }
+    }
+}
+
+/// Decodes the given web font data into sfnt data if it is a WOFF or WOFF2
+/// font, and returns it untouched otherwise.
+pub fn decode(bytes: Vec<u8>) -> Result<Vec<u8>, DecodeError> {
+    if bytes.len() < 4 {
+        return Ok(bytes);
+    }
+    match Reader::new(&bytes).read_u32()? {
+        WOFF_SIGNATURE => decode_woff(&bytes),
+        WOFF2_SIGNATURE => decode_woff2(&bytes),
+        _ => Ok(bytes),
+    }
+}
+
+/// A table of the decoded font.
+struct Table {
+    tag: u32,
+    data: Vec<u8>,
+}
+
+/// <https://www.w3.org/TR/WOFF/#conform-decode>
+fn decode_woff(bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    let mut header = Reader::new(bytes);
+    header.skip(4)?;
+    let flavor = header.read_u32()?;
+    if flavor == COLLECTION_FLAVOR {
+        return Err(DecodeError::Unsupported("font collections"));
+    }
+    let length = header.read_u32()? as usize;
+    if length != bytes.len() {
+        return Err(DecodeError::Malformed(
+            "length does not match the file size",
+        ));
+    }
+    let num_tables = header.read_u16()?;
+    if num_tables == 0 {
+        return Err(DecodeError::Malformed("no tables"));
+    }
+    header.skip(2)?;
+    let total_sfnt_size = header.read_u32()? as usize;
+    if total_sfnt_size > MAX_SFNT_SIZE {
+        return Err(DecodeError::Unsupported("decoded font is too large"));
+    }
+    // The version, metadata and private data blocks don't matter for decoding.
+    header.skip(24)?;
+
+    let mut tables = Vec::with_capacity(num_tables as usize);
+    let mut decoded_size = 0;
+    for _ in 0..num_tables {
+        let tag = header.read_u32()?;
+        let offset = header.read_u32()? as usize;
+        let compressed_length = header.read_u32()? as usize;
+        let original_length = header.read_u32()? as usize;
+        header.skip(4)?;
+
+        decoded_size += original_length;
+        if decoded_size > total_sfnt_size {
+            return Err(DecodeError::Malformed("tables are larger than the font"));
+        }
+        let compressed = Reader::new(bytes).slice(offset, compressed_length)?;
+        let data = if compressed_length < original_length {
+            let mut data = Vec::with_capacity(original_length);
+            ZlibDecoder::new(compressed)
+                .take(original_length as u64)
+                .read_to_end(&mut data)
+                .map_err(|_| DecodeError::Malformed("invalid zlib stream"))?;
+            if data.len() != original_length {
+                return Err(DecodeError::Malformed("table length mismatch"));
+            }
+            data
+        } else if compressed_length == original_length {
+            compressed.to_vec()
+        } else {
+            return Err(DecodeError::Malformed(
+                "compressed table is larger than the original",
+            ));
+        };
+        tables.push(Table { tag, data });
+    }
+
+    Ok(build_sfnt(flavor, tables))
+}
+
+/// An entry of the table directory of a WOFF2 font.
+struct Woff2TableEntry {
+    tag: u32,
+    original_length: usize,
+    transformed: bool,
+    /// The length of the table in the decompressed font data stream.
+    stream_length: usize,
+}
+
+/// <https://www.w3.org/TR/WOFF2/#conform-decoder>
+fn decode_woff2(bytes: &[u8]) -> Result<Vec<u8>, DecodeError> {
+    let mut reader = Reader::new(bytes);
+    reader.skip(4)?;
+    let flavor = reader.read_u32()?;
+    if flavor == COLLECTION_FLAVOR {
+        return Err(DecodeError::Unsupported("font collections"));
+    }
+    let length = reader.read_u32()? as usize;
+    if length != bytes.len() {
+        return Err(DecodeError::Malformed(
+            "length does not match the file size",
+        ));
+    }
+    let num_tables = reader.read_u16()?;
+    if num_tables == 0 {
+        return Err(DecodeError::Malformed("no tables"));
+    }
+    reader.skip(6)?;
+    let total_compressed_size = reader.read_u32()? as usize;
+    // The version, metadata and private data blocks don't matter for decoding.
+    reader.skip(24)?;
+
+    // https://www.w3.org/TR/WOFF2/#table_dir_format
+    let mut entries = Vec::with_capacity(num_tables as usize);
+    let mut stream_size = 0;
+    for _ in 0..num_tables {
+        let flags = reader.read_u8()?;
+        let tag = match flags & 0x3F {
+            0x3F => reader.read_u32()?,
+            index => u32::from_be_bytes(*KNOWN_TABLE_TAGS[index as usize]),
+        };
+        let version = flags >> 6;
+        let transformed = match tag {
+            GLYF_TAG | LOCA_TAG => version == 0,
+            _ => version != 0,
+        };
+        if transformed && tag != GLYF_TAG && tag != LOCA_TAG && tag != HMTX_TAG {
+            return Err(DecodeError::Unsupported("unknown table transformation"));
+        }
+        let original_length = reader.read_base128()? as usize;
+        let stream_length = if transformed {
+            reader.read_base128()? as usize
+        } else {
+            original_length
+        };
+        if tag == LOCA_TAG && transformed && stream_length != 0 {
+            return Err(DecodeError::Malformed(
+                "transformed loca table is not empty",
+            ));
+        }
+        stream_size += stream_length;
+        if stream_size > MAX_SFNT_SIZE {
+            return Err(DecodeError::Unsupported("decoded font is too large"));
+        }
+        entries.push(Woff2TableEntry {
+            tag,
+            original_length,
+            transformed,
+            stream_length,
+        });
+    }
+
+    // https://www.w3.org/TR/WOFF2/#table_format
+    let compressed = reader.read_bytes(total_compressed_size)?;
+    let mut stream = Vec::with_capacity(stream_size);
+    // Read one more byte than expected to reject streams that are too long.
+    Decompressor::new(compressed, 4096)
+        .take(stream_size as u64 + 1)
+        .read_to_end(&mut stream)
+        .map_err(|_| DecodeError::Malformed("invalid brotli stream"))?;
+    if stream.len() != stream_size {
+        return Err(DecodeError::Malformed("font data stream length mismatch"));
+    }
+
+    let mut tables = Vec::with_capacity(entries.len());
+    let mut transformed_hmtx = None;
+    let mut offset = 0;
+    for entry in &entries {
+        let data = &stream[offset..offset + entry.stream_length];
+        offset += entry.stream_length;
+        if !entry.transformed {
+            tables.push(Table {
+                tag: entry.tag,
+                data: data.to_vec(),
+            });
+        } else if entry.tag == GLYF_TAG {
+            let loca = entries
+                .iter()
+                .find(|entry| entry.tag == LOCA_TAG && entry.transformed)
+                .ok_or(DecodeError::Malformed(
+                    "transformed glyf table without loca",
+                ))?;
+            let glyf = reconstruct_glyf(data)?;
+            if glyf.loca.len() != loca.original_length {
+                return Err(DecodeError::Malformed("loca table length mismatch"));
+            }
+            tables.push(Table {
+                tag: GLYF_TAG,
+                data: glyf.glyf,
+            });
+            tables.push(Table {
+                tag: LOCA_TAG,
+                data: glyf.loca,
+            });
+        } else if entry.tag == HMTX_TAG {
+            transformed_hmtx = Some(data);
+        }
+    }
+
+    // The hmtx table can only be reconstructed once the glyf table is, since
+    // its left side bearings may need to be computed from the glyphs.
+    if let Some(data) = transformed_hmtx {
+        tables.push(Table {
+            tag: HMTX_TAG,
+            data: reconstruct_hmtx(data, &tables)?,
+        });
+    }
+
+    if tables.len() != entries.len() {
+        return Err(DecodeError::Malformed(
+            "transformed loca table without glyf",
+        ));
+    }
+    Ok(build_sfnt(flavor, tables))
+}
+
+/// The reconstructed glyf and loca tables.
+struct Glyf {
+    glyf: Vec<u8>,
+    loca: Vec<u8>,
+}
+
+const ON_CURVE_POINT: u8 = 0x01;
+const X_SHORT_VECTOR: u8 = 0x02;
+const Y_SHORT_VECTOR: u8 = 0x04;
+const REPEAT_FLAG: u8 = 0x08;
+const X_IS_SAME_OR_POSITIVE_X_SHORT_VECTOR: u8 = 0x10;
+const Y_IS_SAME_OR_POSITIVE_Y_SHORT_VECTOR: u8 = 0x20;
+const OVERLAP_SIMPLE: u8 = 0x40;
+
+const ARG_1_AND_2_ARE_WORDS: u16 = 0x0001;
+const WE_HAVE_A_SCALE: u16 = 0x0008;
+const MORE_COMPONENTS: u16 = 0x0020;
+const WE_HAVE_AN_X_AND_Y_SCALE: u16 = 0x0040;
+const WE_HAVE_A_TWO_BY_TWO: u16 = 0x0080;
+const WE_HAVE_INSTRUCTIONS: u16 = 0x0100;
+
+/// <https://www.w3.org/TR/WOFF2/#glyf_table_format>
+fn reconstruct_glyf(data: &[u8]) -> Result<Glyf, DecodeError> {
+    let mut header = Reader::new(data);
+    header.skip(2)?;
+    let option_flags = header.read_u16()?;
+    let num_glyphs = header.read_u16()? as usize;
+    let long_loca = header.read_u16()? != 0;
+    let mut stream_lengths = [0; 7];
+    for length in stream_lengths.iter_mut() {
+        *length = header.read_u32()? as usize;
+    }
+    let mut streams = Vec::with_capacity(stream_lengths.len());
+    for &length in &stream_lengths {
+        streams.push(Reader::new(header.read_bytes(length)?));
+    }
+    let overlap_bitmap = if option_flags & 1 != 0 {
+        Some(header.read_bytes((num_glyphs + 7) / 8)?)
+    } else {
+        None
+    };
+    let mut instruction_stream = streams.pop().unwrap();
+    let mut bbox_stream = streams.pop().unwrap();
+    let mut composite_stream = streams.pop().unwrap();
+    let mut glyph_stream = streams.pop().unwrap();
+    let mut flag_stream = streams.pop().unwrap();
+    let mut n_points_stream = streams.pop().unwrap();
+    let mut n_contour_stream = streams.pop().unwrap();
+    let bbox_bitmap = bbox_stream.read_bytes(4 * ((num_glyphs + 31) / 32))?;
+    let has_bit = |bitmap: &[u8], index: usize| bitmap[index >> 3] & (0x80 >> (index & 7)) != 0;
+
+    let mut glyf = vec![];
+    let mut loca = Vec::with_capacity((num_glyphs + 1) * if long_loca { 4 } else { 2 });
+    for index in 0..num_glyphs {
+        write_loca_offset(&mut loca, glyf.len(), long_loca)?;
+        let has_bbox = has_bit(bbox_bitmap, index);
+        let num_contours = n_contour_stream.read_i16()?;
+        if num_contours == 0 {
+            if has_bbox {
+                return Err(DecodeError::Malformed("empty glyph with a bounding box"));
+            }
+            continue;
+        }
+
+        if num_contours < 0 {
+            // https://www.w3.org/TR/WOFF2/#glyf_table_format, composite glyphs.
+            if !has_bbox {
+                return Err(DecodeError::Malformed(
+                    "composite glyph without a bounding box",
+                ));
+            }
+            let start = composite_stream.offset;
+            let mut have_instructions = false;
+            loop {
+                let flags = composite_stream.read_u16()?;
+                let mut length = 2 + if flags & ARG_1_AND_2_ARE_WORDS != 0 {
+                    4
+                } else {
+                    2
+                };
+                if flags & WE_HAVE_A_SCALE != 0 {
+                    length += 2;
+                } else if flags & WE_HAVE_AN_X_AND_Y_SCALE != 0 {
+                    length += 4;
+                } else if flags & WE_HAVE_A_TWO_BY_TWO != 0 {
+                    length += 8;
+                }
+                composite_stream.skip(length)?;
+                have_instructions |= flags & WE_HAVE_INSTRUCTIONS != 0;
+                if flags & MORE_COMPONENTS == 0 {
+                    break;
+                }
+            }
+            let components = &composite_stream.data[start..composite_stream.offset];
+
+            push_i16(&mut glyf, num_contours);
+            glyf.extend_from_slice(bbox_stream.read_bytes(8)?);
+            glyf.extend_from_slice(components);
+            if have_instructions {
+                let instruction_length = glyph_stream.read_255_u16()?;
+                push_u16(&mut glyf, instruction_length);
+                glyf.extend_from_slice(instruction_stream.read_bytes(instruction_length as usize)?);
+            }
+        } else {
+            // https://www.w3.org/TR/WOFF2/#glyf_table_format, simple glyphs.
+            let mut end_points = Vec::with_capacity(num_contours as usize);
+            let mut num_points = 0u32;
+            for _ in 0..num_contours {
+                num_points += n_points_stream.read_255_u16()? as u32;
+                if num_points == 0 || num_points > 0x10000 {
+                    return Err(DecodeError::Malformed("invalid number of points"));
+                }
+                end_points.push((num_points - 1) as u16);
+            }
+
+            let mut points = Vec::with_capacity(num_points as usize);
+            let (mut x, mut y) = (0i32, 0i32);
+            for _ in 0..num_points {
+                let (on_curve, dx, dy) = read_triplet(flag_stream.read_u8()?, &mut glyph_stream)?;
+                x += dx;
+                y += dy;
+                points.push(Point {
+                    x,
+                    y,
+                    dx,
+                    dy,
+                    on_curve,
+                });
+            }
+            let instruction_length = glyph_stream.read_255_u16()?;
+
+            push_i16(&mut glyf, num_contours);
+            if has_bbox {
+                glyf.extend_from_slice(bbox_stream.read_bytes(8)?);
+            } else {
+                let x_min = points.iter().map(|point| point.x).min().unwrap_or(0);
+                let y_min = points.iter().map(|point| point.y).min().unwrap_or(0);
+                let x_max = points.iter().map(|point| point.x).max().unwrap_or(0);
+                let y_max = points.iter().map(|point| point.y).max().unwrap_or(0);
+                for &value in &[x_min, y_min, x_max, y_max] {
+                    push_i16(&mut glyf, value as i16);
+                }
+            }
+            for &end_point in &end_points {
+                push_u16(&mut glyf, end_point);
+            }
+            push_u16(&mut glyf, instruction_length);
+            glyf.extend_from_slice(instruction_stream.read_bytes(instruction_length as usize)?);
+            let overlaps = overlap_bitmap.map_or(false, |bitmap| has_bit(bitmap, index));
+            write_points(&mut glyf, &points, overlaps)?;
+        }
+
+        // Pad the glyph so that it can be addressed by short loca offsets.
+        while glyf.len() % 4 != 0 {
+            glyf.push(0);
+        }
+    }
+    write_loca_offset(&mut loca, glyf.len(), long_loca)?;
+
+    Ok(Glyf { glyf, loca })
+}
+
+fn write_loca_offset(
+    loca: &mut Vec<u8>,
+    offset: usize,
+    long_loca: bool,
+) -> Result<(), DecodeError> {
+    if long_loca {
+        push_u32(loca, offset as u32);
+    } else {
+        if offset / 2 > u16::max_value() as usize {
+            return Err(DecodeError::Malformed(
+                "glyf table too large for short loca offsets",
+            ));
+        }
+        push_u16(loca, (offset / 2) as u16);
+    }
+    Ok(())
+}
+
+/// A point of a simple glyph, with both its absolute coordinates and its
+/// offset from the previous point.
+struct Point {
+    x: i32,
+    y: i32,
+    dx: i32,
+    dy: i32,
+    on_curve: bool,
+}
+
+/// Decodes a point offset encoded with the triplet encoding.
+///
+/// <https://www.w3.org/TR/WOFF2/#triplet_decoding>
+fn read_triplet(flag: u8, glyph_stream: &mut Reader) -> Result<(bool, i32, i32), DecodeError> {
+    let on_curve = flag & 0x80 == 0;
+    let flag = flag & 0x7F;
+    let with_sign = |flag: u8, value: i32| if flag & 1 != 0 { value } else { -value };
+    let (dx, dy) = if flag < 10 {
+        let b0 = glyph_stream.read_u8()? as i32;
+        (0, with_sign(flag, ((flag as i32 & 14) << 7) + b0))
+    } else if flag < 20 {
+        let b0 = glyph_stream.read_u8()? as i32;
+        (with_sign(flag, (((flag as i32 - 10) & 14) << 7) + b0), 0)
+    } else if flag < 84 {
+        let b0 = flag as i32 - 20;
+        let b1 = glyph_stream.read_u8()? as i32;
+        (
+            with_sign(flag, 1 + (b0 & 0x30) + (b1 >> 4)),
+            with_sign(flag >> 1, 1 + ((b0 & 0x0C) << 2) + (b1 & 0x0F)),
+        )
+    } else if flag < 120 {
+        let b0 = flag as i32 - 84;
+        let b1 = glyph_stream.read_u8()? as i32;
+        let b2 = glyph_stream.read_u8()? as i32;
+        (
+            with_sign(flag, 1 + ((b0 / 12) << 8) + b1),
+            with_sign(flag >> 1, 1 + (((b0 % 12) >> 2) << 8) + b2),
+        )
+    } else if flag < 124 {
+        let b1 = glyph_stream.read_u8()? as i32;
+        let b2 = glyph_stream.read_u8()? as i32;
+        let b3 = glyph_stream.read_u8()? as i32;
+        (
+            with_sign(flag, (b1 << 4) + (b2 >> 4)),
+            with_sign(flag >> 1, ((b2 & 0x0F) << 8) + b3),
+        )
+    } else {
+        let dx = glyph_stream.read_u16()? as i32;
+        let dy = glyph_stream.read_u16()? as i32;
+        (with_sign(flag, dx), with_sign(flag >> 1, dy))
+    };
+    Ok((on_curve, dx, dy))
+}
+
+/// Writes the flags and coordinates of the points of a simple glyph in the
+/// format of the TrueType glyf table.
+///
+/// <https://docs.microsoft.com/en-us/typography/opentype/spec/glyf#simple-glyph-description>
+fn write_points(glyf: &mut Vec<u8>, points: &[Point], overlaps: bool) -> Result<(), DecodeError> {
+    let mut flags = Vec::with_capacity(points.len());
+    let mut x_coordinates = vec![];
+    let mut y_coordinates = vec![];
+    let mut last_flag = None;
+    let mut repeat_count = 0u8;
+    for (index, point) in points.iter().enumerate() {
+        let mut flag = if point.on_curve { ON_CURVE_POINT } else { 0 };
+        if overlaps && index == 0 {
+            flag |= OVERLAP_SIMPLE;
+        }
+        flag |= write_coordinate(
+            &mut x_coordinates,
+            point.dx,
+            X_SHORT_VECTOR,
+            X_IS_SAME_OR_POSITIVE_X_SHORT_VECTOR,
+        )?;
+        flag |= write_coordinate(
+            &mut y_coordinates,
+            point.dy,
+            Y_SHORT_VECTOR,
+            Y_IS_SAME_OR_POSITIVE_Y_SHORT_VECTOR,
+        )?;
+
+        if last_flag == Some(flag) && repeat_count != u8::max_value() {
+            if repeat_count == 0 {
+                *flags.last_mut().unwrap() |= REPEAT_FLAG;
+                flags.push(0);
+            }
+            repeat_count += 1;
+            *flags.last_mut().unwrap() = repeat_count;
+        } else {
+            flags.push(flag);
+            repeat_count = 0;
+        }
+        last_flag = Some(flag);
+    }
+    glyf.extend_from_slice(&flags);
+    glyf.extend_from_slice(&x_coordinates);
+    glyf.extend_from_slice(&y_coordinates);
+    Ok(())
+}
+
+/// Writes the given coordinate offset in its shortest form, and returns the
+/// flags describing it.
+fn write_coordinate(
+    coordinates: &mut Vec<u8>,
+    delta: i32,
+    short_flag: u8,
+    same_or_positive_flag: u8,
+) -> Result<u8, DecodeError> {
+    if delta == 0 {
+        Ok(same_or_positive_flag)
+    } else if delta.abs() <= 0xFF {
+        coordinates.push(delta.abs() as u8);
+        Ok(short_flag | if delta > 0 { same_or_positive_flag } else { 0 })
+    } else if delta >= i16::min_value() as i32 && delta <= i16::max_value() as i32 {
+        push_i16(coordinates, delta as i16);
+        Ok(0)
+    } else {
+        Err(DecodeError::Malformed("point coordinate out of range"))
+    }
+}
+
+/// <https://www.w3.org/TR/WOFF2/#hmtx_table_format>
+fn reconstruct_hmtx(data: &[u8], tables: &[Table]) -> Result<Vec<u8>, DecodeError> {
+    let mut hhea = find_table(tables, HHEA_TAG)?;
+    hhea.skip(34)?;
+    let num_h_metrics = hhea.read_u16()? as usize;
+    let mut maxp = find_table(tables, MAXP_TAG)?;
+    maxp.skip(4)?;
+    let num_glyphs = maxp.read_u16()? as usize;
+    if num_h_metrics == 0 || num_h_metrics > num_glyphs {
+        return Err(DecodeError::Malformed(
+            "invalid number of horizontal metrics",
+        ));
+    }
+
+    let mut reader = Reader::new(data);
+    let flags = reader.read_u8()?;
+    if flags & 0x03 == 0 || flags & 0xFC != 0 {
+        return Err(DecodeError::Malformed("invalid hmtx transform flags"));
+    }
+    let advance_widths = reader.read_bytes(2 * num_h_metrics)?;
+    // The omitted left side bearings are the minimum x coordinates of the glyphs.
+    let x_mins = glyph_x_mins(tables)?;
+    let mut left_side_bearing = |index: usize, omitted: bool| -> Result<i16, DecodeError> {
+        if omitted {
+            Ok(x_mins.get(index).cloned().unwrap_or(0))
+        } else {
+            reader.read_i16()
+        }
+    };
+
+    let mut hmtx = Vec::with_capacity(4 * num_h_metrics + 2 * (num_glyphs - num_h_metrics));
+    for index in 0..num_h_metrics {
+        hmtx.extend_from_slice(&advance_widths[2 * index..2 * index + 2]);
+        push_i16(&mut hmtx, left_side_bearing(index, flags & 0x01 != 0)?);
+    }
+    for index in num_h_metrics..num_glyphs {
+        push_i16(&mut hmtx, left_side_bearing(index, flags & 0x02 != 0)?);
+    }
+    Ok(hmtx)
+}
+
+/// Returns the minimum x coordinate of each glyph of the given font tables.
+fn glyph_x_mins(tables: &[Table]) -> Result<Vec<i16>, DecodeError> {
+    let mut head = find_table(tables, HEAD_TAG)?;
+    head.skip(50)?;
+    let long_loca = head.read_i16()? != 0;
+    let glyf = find_table(tables, GLYF_TAG)?;
+    let mut loca = find_table(tables, LOCA_TAG)?;
+
+    let mut read_offset = || -> Result<usize, DecodeError> {
+        if long_loca {
+            Ok(loca.read_u32()? as usize)
+        } else {
+            Ok(loca.read_u16()? as usize * 2)
+        }
+    };
+    let mut x_mins = vec![];
+    let mut start = read_offset()?;
+    while let Ok(end) = read_offset() {
+        let x_min = if end > start {
+            glyf.slice(start + 2, 2)
+                .map(|bytes| i16::from_be_bytes([bytes[0], bytes[1]]))?
+        } else {
+            0
+        };
+        x_mins.push(x_min);
+        start = end;
+    }
+    Ok(x_mins)
+}
+
+fn find_table(tables: &[Table], tag: u32) -> Result<Reader, DecodeError> {
+    tables
+        .iter()
+        .find(|table| table.tag == tag)
+        .map(|table| Reader::new(&table.data))
+        .ok_or(DecodeError::Malformed("missing required table"))
+}
+
+/// Builds an sfnt font from the given flavor and tables.
+///
+/// <https://docs.microsoft.com/en-us/typography/opentype/spec/otff#table-directory>
+fn build_sfnt(flavor: u32, mut tables: Vec<Table>) -> Vec<u8> {
+    tables.sort_by_key(|table| table.tag);
+
+    let num_tables = tables.len() as u32;
+    let mut entry_selector = 0;
+    while 2u32.pow(entry_selector + 1) <= num_tables {
+        entry_selector += 1;
+    }
+    let search_range = 2u32.pow(entry_selector) * 16;
+
+    let header_length = 12 + 16 * tables.len();
+    let total_length = header_length +
+        tables
+            .iter()
+            .map(|table| padded_length(table.data.len()))
+            .sum::<usize>();
+    let mut sfnt = Vec::with_capacity(total_length);
+    push_u32(&mut sfnt, flavor);
+    push_u16(&mut sfnt, num_tables as u16);
+    push_u16(&mut sfnt, search_range as u16);
+    push_u16(&mut sfnt, entry_selector as u16);
+    push_u16(&mut sfnt, (num_tables * 16 - search_range) as u16);
+
+    let mut offset = header_length;
+    for table in &mut tables {
+        if table.tag == HEAD_TAG && table.data.len() >= 12 {
+            // The checksum adjustment is computed once the whole font is built.
+            table.data[8..12].copy_from_slice(&[0; 4]);
+        }
+        push_u32(&mut sfnt, table.tag);
+        push_u32(&mut sfnt, checksum(&table.data));
+        push_u32(&mut sfnt, offset as u32);
+        push_u32(&mut sfnt, table.data.len() as u32);
+        offset += padded_length(table.data.len());
+    }
+
+    let mut head_offset = None;
+    for table in &tables {
+        if table.tag == HEAD_TAG && table.data.len() >= 12 {
+            head_offset = Some(sfnt.len());
+        }
+        sfnt.extend_from_slice(&table.data);
+        sfnt.resize(padded_length(sfnt.len()), 0);
+    }
+
+    if let Some(head_offset) = head_offset {
+        let adjustment = 0xB1B0_AFBAu32.wrapping_sub(checksum(&sfnt));
+        sfnt[head_offset + 8..head_offset + 12].copy_from_slice(&adjustment.to_be_bytes());
+    }
+    sfnt
+}
+
+fn padded_length(length: usize) -> usize {
+    (length + 3) & !3
+}
+
+/// <https://docs.microsoft.com/en-us/typography/opentype/spec/otff#calculating-checksums>
+fn checksum(data: &[u8]) -> u32 {
+    data.chunks(4).fold(0u32, |sum, chunk| {
+        let mut word = [0; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        sum.wrapping_add(u32::from_be_bytes(word))
+    })
+}
+
+fn push_u16(data: &mut Vec<u8>, value: u16) {
+    data.extend_from_slice(&value.to_be_bytes());
+}
+
+fn push_i16(data: &mut Vec<u8>, value: i16) {
+    data.extend_from_slice(&value.to_be_bytes());
+}
+
+fn push_u32(data: &mut Vec<u8>, value: u32) {
+    data.extend_from_slice(&value.to_be_bytes());
+}
+
+/// A cursor over big-endian font data.
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Reader<'a> {
+        Reader { data, offset: 0 }
+    }
+
+    /// Returns the given range of the data, independently of the cursor.
+    fn slice(&self, offset: usize, length: usize) -> Result<&'a [u8], DecodeError> {
+        let end = offset.checked_add(length).ok_or(DecodeError::Truncated)?;
+        self.data.get(offset..end).ok_or(DecodeError::Truncated)
+    }
+
+    fn read_bytes(&mut self, length: usize) -> Result<&'a [u8], DecodeError> {
+        let bytes = self.slice(self.offset, length)?;
+        self.offset += length;
+        Ok(bytes)
+    }
+
+    fn skip(&mut self, length: usize) -> Result<(), DecodeError> {
+        self.read_bytes(length).map(|_| ())
+    }
+
+    fn read_u8(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    fn read_u16(&mut self) -> Result<u16, DecodeError> {
+        let bytes = self.read_bytes(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn read_i16(&mut self) -> Result<i16, DecodeError> {
+        Ok(self.read_u16()? as i16)
+    }
+
+    fn read_u32(&mut self) -> Result<u32, DecodeError> {
+        let bytes = self.read_bytes(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// <https://www.w3.org/TR/WOFF2/#DataTypes>, UIntBase128.
+    fn read_base128(&mut self) -> Result<u32, DecodeError> {
+        let mut value = 0u32;
+        for index in 0..5 {
+            let byte = self.read_u8()?;
+            if index == 0 && byte == 0x80 {
+                return Err(DecodeError::Malformed("UIntBase128 with leading zeros"));
+            }
+            if value & 0xFE00_0000 != 0 {
+                return Err(DecodeError::Malformed("UIntBase128 overflow"));
+            }
+            value = (value << 7) | (byte & 0x7F) as u32;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(DecodeError::Malformed("UIntBase128 longer than 5 bytes"))
+    }
+
+    /// <https://www.w3.org/TR/WOFF2/#DataTypes>, 255UInt16.
+    fn read_255_u16(&mut self) -> Result<u16, DecodeError> {
+        match self.read_u8()? {
+            253 => self.read_u16(),
+            254 => Ok(self.read_u8()? as u16 + 253 * 2),
+            255 => Ok(self.read_u8()? as u16 + 253),
+            code => Ok(code as u16),
+        }
+    }
+}
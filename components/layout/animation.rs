@@ -12,9 +12,10 @@ use crossbeam_channel::Receiver;
 use fxhash::{FxHashMap, FxHashSet};
 use ipc_channel::ipc::IpcSender;
 use msg::constellation_msg::PipelineId;
+use script_layout_interface::{CSSAnimationKind, NewCSSAnimation};
 use script_traits::UntrustedNodeAddress;
 use script_traits::{AnimationState, ConstellationControlMsg, LayoutMsg as ConstellationMsg};
-use style::animation::{update_style_for_animation, Animation};
+use style::animation::{update_style_for_animation, Animation, KeyframesIterationState};
 use style::dom::TElement;
 use style::font_metrics::ServoMetricsProvider;
 use style::selector_parser::RestyleDamage;
@@ -30,9 +31,11 @@ pub fn update_animation_state<E>(
     expired_animations: &mut FxHashMap<OpaqueNode, Vec<Animation>>,
     mut keys_to_remove: FxHashSet<OpaqueNode>,
     mut newly_transitioning_nodes: Option<&mut Vec<UntrustedNodeAddress>>,
+    mut new_css_animations: Option<&mut Vec<NewCSSAnimation>>,
     new_animations_receiver: &Receiver<Animation>,
     pipeline_id: PipelineId,
     timer: &Timer,
+    animating_images: bool,
 ) where
     E: TElement,
 {
@@ -140,13 +143,17 @@ pub fn update_animation_state<E>(
             }
         }
 
+        if let Some(ref mut animations) = new_css_animations {
+            animations.push(new_css_animation(&new_running_animation, now));
+        }
+
         running_animations
             .entry(*new_running_animation.node())
             .or_insert_with(Vec::new)
             .push(new_running_animation)
     }
 
-    let animation_state = if running_animations.is_empty() {
+    let animation_state = if running_animations.is_empty() && !animating_images {
         AnimationState::NoAnimationsPresent
     } else {
         AnimationState::AnimationsPresent
@@ -160,6 +167,34 @@ pub fn update_animation_state<E>(
         .unwrap();
 }
 
+/// Describes a newly started animation so that script can expose it.
+fn new_css_animation(animation: &Animation, now: f64) -> NewCSSAnimation {
+    let (kind, started_at, duration) = match *animation {
+        Animation::Transition(_, started_at, ref frame) => (
+            CSSAnimationKind::Transition(frame.property_animation.property_name().into()),
+            started_at,
+            frame.duration,
+        ),
+        Animation::Keyframes(_, _, ref name, ref state) => {
+            let iterations = match state.iteration_state {
+                KeyframesIterationState::Finite(_, max) => max as f64,
+                KeyframesIterationState::Infinite => f64::INFINITY,
+            };
+            (
+                CSSAnimationKind::Animation(name.to_string(), iterations),
+                state.started_at,
+                state.duration,
+            )
+        },
+    };
+    NewCSSAnimation {
+        node: animation.node().to_untrusted_node_address(),
+        kind,
+        delay: started_at - now,
+        duration,
+    }
+}
+
 /// Recalculates style for a set of animations. This does *not* run with the DOM
 /// lock held. Returns a set of nodes associated with animations that are no longer
 /// valid.
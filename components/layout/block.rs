@@ -1695,7 +1695,8 @@ impl BlockFlow {
             Display::TableRowGroup |
             Display::Table |
             Display::InlineBlock |
-            Display::Flex => FormattingContextType::Other,
+            Display::Flex |
+            Display::Grid => FormattingContextType::Other,
             _ if style.get_box().overflow_x != StyleOverflow::Visible ||
                 style.get_box().overflow_y != StyleOverflow::Visible ||
                 style.is_multicol() =>
@@ -1829,8 +1830,10 @@ impl BlockFlow {
     }
 
     fn is_inline_block_or_inline_flex(&self) -> bool {
-        self.fragment.style().get_box().display == Display::InlineBlock ||
-            self.fragment.style().get_box().display == Display::InlineFlex
+        match self.fragment.style().get_box().display {
+            Display::InlineBlock | Display::InlineFlex | Display::InlineGrid => true,
+            _ => false,
+        }
     }
 
     /// Computes the content portion (only) of the intrinsic inline sizes of this flow. This is
@@ -5,16 +5,19 @@
 //! Data needed by the layout thread.
 
 use crate::display_list::items::{OpaqueNode, WebRenderImageInfo};
+use crate::display_list::MaskImageCache;
 use crate::opaque_node::OpaqueNodeMethods;
+use crate::svg::SvgImageCache;
 use fnv::FnvHasher;
 use gfx::font_cache_thread::FontCacheThread;
 use gfx::font_context::FontContext;
 use malloc_size_of::{MallocSizeOf, MallocSizeOfOps};
 use msg::constellation_msg::PipelineId;
+use net_traits::image::base::Image;
 use net_traits::image_cache::{CanRequestImages, ImageCache, ImageState};
 use net_traits::image_cache::{ImageOrMetadataAvailable, UsePlaceholder};
 use parking_lot::RwLock;
-use script_layout_interface::{PendingImage, PendingImageState};
+use script_layout_interface::{NewCSSAnimation, PendingImage, PendingImageState};
 use script_traits::Painter;
 use script_traits::UntrustedNodeAddress;
 use servo_atoms::Atom;
@@ -22,10 +25,12 @@ use servo_url::ServoUrl;
 use std::cell::{RefCell, RefMut};
 use std::collections::HashMap;
 use std::hash::BuildHasherDefault;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use style::context::RegisteredSpeculativePainter;
 use style::context::SharedStyleContext;
+use webrender_api::ImageKey;
 
 pub type LayoutFontContext = FontContext<FontCacheThread>;
 
@@ -76,6 +81,21 @@ pub struct LayoutContext<'a> {
         >,
     >,
 
+    /// The time at which each animated image was first painted, keyed by the
+    /// WebRender image key of its first frame.
+    pub animated_image_start_times:
+        Arc<RwLock<HashMap<ImageKey, f64, BuildHasherDefault<FnvHasher>>>>,
+
+    /// Whether an animated image that has not finished playing was painted
+    /// during this layout.
+    pub painted_animated_image: AtomicBool,
+
+    /// The images the shapes of `<svg>` elements are rasterized into.
+    pub svg_image_cache: Arc<SvgImageCache>,
+
+    /// The images of the masks of `clip-path` and `mask-image`.
+    pub mask_image_cache: Arc<MaskImageCache>,
+
     /// Paint worklets
     pub registered_painters: &'a dyn RegisteredPainters,
 
@@ -86,6 +106,10 @@ pub struct LayoutContext<'a> {
     /// A list of nodes that have just initiated a CSS transition.
     /// A None value means that this layout was not initiated by the script thread.
     pub newly_transitioning_nodes: Option<Mutex<Vec<UntrustedNodeAddress>>>,
+
+    /// A list of CSS animations and transitions that have just started running.
+    /// A None value means that this layout was not initiated by the script thread.
+    pub new_css_animations: Option<Mutex<Vec<NewCSSAnimation>>>,
 }
 
 impl<'a> Drop for LayoutContext<'a> {
@@ -177,8 +201,13 @@ impl<'a> LayoutContext<'a> {
 
         match self.get_or_request_image_or_meta(node, url.clone(), use_placeholder) {
             Some(ImageOrMetadataAvailable::ImageAvailable(image, _)) => {
-                let image_info = WebRenderImageInfo::from_image(&*image);
-                if image_info.key.is_none() {
+                let mut image_info = WebRenderImageInfo::from_image(&*image);
+                if image.is_animated() {
+                    // The frame to paint changes over time, so animated images
+                    // are not cached.
+                    image_info.key = self.current_frame_key(&*image);
+                    Some(image_info)
+                } else if image_info.key.is_none() {
                     Some(image_info)
                 } else {
                     let mut webrender_image_cache = self.webrender_image_cache.write();
@@ -189,6 +218,27 @@ impl<'a> LayoutContext<'a> {
             None | Some(ImageOrMetadataAvailable::MetadataAvailable(_)) => None,
         }
     }
+
+    /// Returns the WebRender image key of the frame of the given image to paint
+    /// now, starting the animation of animated images painted for the first
+    /// time.
+    pub fn current_frame_key(&self, image: &Image) -> Option<ImageKey> {
+        let animation = match image.animation {
+            Some(ref animation) => animation,
+            None => return image.id,
+        };
+        let now = self.style_context.timer.seconds();
+        let start_time = *self
+            .animated_image_start_times
+            .write()
+            .entry(image.id?)
+            .or_insert(now);
+        let (index, running) = animation.frame_at(((now - start_time) * 1000.) as u64);
+        if running {
+            self.painted_animated_image.store(true, Ordering::Relaxed);
+        }
+        animation.frames[index].id
+    }
 }
 
 /// A registered painter
@@ -46,7 +46,7 @@ pub fn get_cyclic<T>(arr: &[T], index: usize) -> &T {
 
 /// For a given area and an image compute how big the
 /// image should be displayed on the background.
-fn compute_background_image_size(
+pub fn compute_background_image_size(
     bg_size: BackgroundSize,
     bounds_size: Size2D<Au>,
     intrinsic_size: Option<Size2D<Au>>,
@@ -292,7 +292,7 @@ fn tile_image(position: &mut Au, size: &mut Au, absolute_anchor_origin: Au, imag
 /// For either the x or the y axis ajust various values to account for tiling.
 ///
 /// This is done separately for both axes because the repeat keywords may differ.
-fn tile_image_axis(
+pub fn tile_image_axis(
     repeat: BackgroundRepeatKeyword,
     position: &mut Au,
     size: &mut Au,
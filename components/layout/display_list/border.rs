@@ -8,6 +8,7 @@ use euclid::default::{Rect, SideOffsets2D as UntypedSideOffsets2D, Size2D as Unt
 use euclid::{SideOffsets2D, Size2D};
 use style::computed_values::border_image_outset::T as BorderImageOutset;
 use style::properties::style_structs::Border;
+use style::values::computed::BorderRadius as ComputedBorderRadius;
 use style::values::computed::NumberOrPercentage;
 use style::values::computed::{BorderCornerRadius, BorderImageWidth};
 use style::values::computed::{BorderImageSideWidth, NonNegativeLengthOrNumber};
@@ -103,6 +104,19 @@ pub fn radii(abs_bounds: Rect<Au>, border_style: &Border) -> BorderRadius {
     )
 }
 
+/// Calculates the radii of the rounded corners of an `inset()` basic shape.
+pub fn shape_radii(abs_bounds: Rect<Au>, radius: &ComputedBorderRadius) -> BorderRadius {
+    overlapping_radii(
+        abs_bounds.size.to_layout(),
+        BorderRadius {
+            top_left: corner_radius(radius.top_left, abs_bounds.size).to_layout(),
+            top_right: corner_radius(radius.top_right, abs_bounds.size).to_layout(),
+            bottom_right: corner_radius(radius.bottom_right, abs_bounds.size).to_layout(),
+            bottom_left: corner_radius(radius.bottom_left, abs_bounds.size).to_layout(),
+        },
+    )
+}
+
 /// Calculates radii for the inner side.
 ///
 /// Radii usually describe the outer side of a border but for the lines to look nice
@@ -127,6 +141,24 @@ pub fn inner_radii(mut radii: BorderRadius, offsets: UntypedSideOffsets2D<Au>) -
     radii
 }
 
+/// Calculates radii for a box enclosing the border box, like the margin box.
+///
+/// Square corners stay square while rounded ones grow with the offsets.
+pub fn outer_radii(mut radii: BorderRadius, offsets: UntypedSideOffsets2D<Au>) -> BorderRadius {
+    fn outer_size(mut size: LayoutSize, horizontal: Au, vertical: Au) -> LayoutSize {
+        if size.width > 0. && size.height > 0. {
+            size.width = 0_f32.max(size.width + horizontal.to_f32_px());
+            size.height = 0_f32.max(size.height + vertical.to_f32_px());
+        }
+        size
+    }
+    radii.top_left = outer_size(radii.top_left, offsets.left, offsets.top);
+    radii.top_right = outer_size(radii.top_right, offsets.right, offsets.top);
+    radii.bottom_right = outer_size(radii.bottom_right, offsets.right, offsets.bottom);
+    radii.bottom_left = outer_size(radii.bottom_left, offsets.left, offsets.bottom);
+    radii
+}
+
 /// Creates a four-sided border with square corners and uniform color and width.
 pub fn simple(color: ColorF, style: BorderStyle) -> NormalBorder {
     let side = BorderSide { color, style };
@@ -21,6 +21,7 @@ use crate::display_list::items::{IframeDisplayItem, OpaqueNode};
 use crate::display_list::items::{PopAllTextShadowsDisplayItem, PushTextShadowDisplayItem};
 use crate::display_list::items::{StackingContext, StackingContextType, StickyFrameData};
 use crate::display_list::items::{TextOrientation, WebRenderImageInfo};
+use crate::display_list::masking::{self, ReferenceBoxes};
 use crate::display_list::ToLayout;
 use crate::flow::{BaseFlow, Flow, FlowFlags};
 use crate::flow_ref::FlowRef;
@@ -28,6 +29,7 @@ use crate::fragment::SpecificFragmentInfo;
 use crate::fragment::{CanvasFragmentSource, CoordinateSystem, Fragment, ScannedTextFragmentInfo};
 use crate::inline::InlineFragmentNodeFlags;
 use crate::model::MaybeAuto;
+use crate::svg::text_fill_color;
 use crate::table_cell::CollapsedBordersForCell;
 use app_units::{Au, AU_PER_PX};
 use canvas_traits::canvas::{CanvasMsg, FromLayoutMsg};
@@ -67,6 +69,7 @@ use style::values::generics::image::{GradientKind, PaintWorklet};
 use style::values::specified::ui::CursorKind;
 use style::values::{Either, RGBA};
 use style_traits::ToCss;
+use svg::tree::TextAnchor;
 use webrender_api::units::{LayoutRect, LayoutSize, LayoutTransform, LayoutVector2D};
 use webrender_api::{self, BorderDetails, BorderRadius, BorderSide, BoxShadowClipMode, ColorF};
 use webrender_api::{ColorU, ExternalScrollId, FilterOp, GlyphInstance, ImageRendering, LineStyle};
@@ -150,7 +153,10 @@ impl StackingContextInfo {
     }
 }
 
-pub struct StackingContextCollectionState {
+pub struct StackingContextCollectionState<'a> {
+    /// A LayoutContext reference important for creating WebRender images.
+    pub layout_context: &'a LayoutContext<'a>,
+
     /// The PipelineId of this stacking context collection.
     pub pipeline_id: PipelineId,
 
@@ -197,8 +203,8 @@ pub struct StackingContextCollectionState {
     parent_stacking_relative_content_box: Rect<Au>,
 }
 
-impl StackingContextCollectionState {
-    pub fn new(pipeline_id: PipelineId) -> StackingContextCollectionState {
+impl<'a> StackingContextCollectionState<'a> {
+    pub fn new(layout_context: &'a LayoutContext) -> StackingContextCollectionState<'a> {
         let root_clip_indices =
             ClippingAndScrolling::simple(ClipScrollNodeIndex::root_scroll_node());
 
@@ -216,7 +222,8 @@ impl StackingContextCollectionState {
         let clip_scroll_nodes = vec![ClipScrollNode::placeholder(), ClipScrollNode::placeholder()];
 
         StackingContextCollectionState {
-            pipeline_id: pipeline_id,
+            layout_context,
+            pipeline_id: layout_context.id,
             root_stacking_context: StackingContext::root(),
             stacking_context_info,
             clip_scroll_nodes,
@@ -635,6 +642,7 @@ impl Fragment {
 
         let current_stacking_context_id = state.current_stacking_context_id;
         let stacking_context = self.create_stacking_context(
+            state.layout_context,
             self.stacking_context_id,
             &base,
             StackingContextType::Real,
@@ -1784,8 +1792,92 @@ impl Fragment {
             SpecificFragmentInfo::InlineBlock(_) |
             SpecificFragmentInfo::InlineAbsoluteHypothetical(_) |
             SpecificFragmentInfo::InlineAbsolute(_) |
-            SpecificFragmentInfo::TruncatedFragment(_) |
-            SpecificFragmentInfo::Svg(_) => {
+            SpecificFragmentInfo::TruncatedFragment(_) => {
+                if opts::get().show_debug_fragment_borders {
+                    self.build_debug_borders_around_fragment(
+                        state,
+                        stacking_relative_border_box,
+                        clip,
+                    );
+                }
+            },
+            SpecificFragmentInfo::Svg(ref fragment_info) => {
+                if let Some(ref content) = fragment_info.content {
+                    let viewport = euclid::Size2D::new(
+                        stacking_relative_content_box.size.width.to_f32_px(),
+                        stacking_relative_content_box.size.height.to_f32_px(),
+                    );
+                    let device_pixel_ratio = state
+                        .layout_context
+                        .style_context
+                        .device_pixel_ratio()
+                        .get();
+
+                    // The shapes are rasterized into an image.
+                    let image_key = state.layout_context.svg_image_cache.image_key(
+                        self.node,
+                        &content.tree,
+                        viewport,
+                        device_pixel_ratio,
+                    );
+                    if let Some(image_key) = image_key {
+                        let base = create_base_display_item(state);
+                        state.add_image_item(
+                            base,
+                            webrender_api::ImageDisplayItem {
+                                bounds: stacking_relative_content_box.to_layout(),
+                                common: items::empty_common_item_properties(),
+                                image_key,
+                                stretch_size: stacking_relative_content_box.size.to_layout(),
+                                tile_spacing: LayoutSize::zero(),
+                                image_rendering: ImageRendering::Auto,
+                                alpha_type: webrender_api::AlphaType::PremultipliedAlpha,
+                                color: webrender_api::ColorF::WHITE,
+                            },
+                        );
+                    }
+
+                    // The text is drawn on top of it, with the fonts of the page.
+                    content.tree.for_each_text(viewport, |text, position| {
+                        let run = match content.texts.get(text.index) {
+                            Some(&Some(ref run)) => run,
+                            _ => return,
+                        };
+                        let color = match text_fill_color(text) {
+                            Some(color) => color,
+                            None => return,
+                        };
+                        let range = Range::new(ByteIndex(0), ByteIndex(run.text.len() as isize));
+                        let anchor_offset = match text.anchor {
+                            TextAnchor::Start => Au(0),
+                            TextAnchor::Middle => run.advance_for_range(&range) / 2,
+                            TextAnchor::End => run.advance_for_range(&range),
+                        };
+                        let baseline_origin = Point2D::new(
+                            stacking_relative_content_box.origin.x + Au::from_f32_px(position.x) -
+                                anchor_offset,
+                            stacking_relative_content_box.origin.y + Au::from_f32_px(position.y),
+                        );
+                        let glyphs =
+                            convert_text_run_to_glyphs(run.clone(), range, baseline_origin);
+                        if glyphs.is_empty() {
+                            return;
+                        }
+                        let base = create_base_display_item(state);
+                        state.add_display_item(DisplayItem::Text(CommonDisplayItem::with_data(
+                            base,
+                            webrender_api::TextDisplayItem {
+                                bounds: stacking_relative_content_box.to_layout(),
+                                common: items::empty_common_item_properties(),
+                                font_key: run.font_key,
+                                color: color.to_layout(),
+                                glyph_options: None,
+                            },
+                            glyphs,
+                        )));
+                    });
+                }
+
                 if opts::get().show_debug_fragment_borders {
                     self.build_debug_borders_around_fragment(
                         state,
@@ -1826,24 +1918,14 @@ impl Fragment {
             SpecificFragmentInfo::Image(ref image_fragment) => {
                 // Place the image into the display list.
                 if let Some(ref image) = image_fragment.image {
-                    if let Some(id) = image.id {
+                    if let Some(id) = state.layout_context.current_frame_key(image) {
                         let base = create_base_display_item(state);
-                        state.add_image_item(
+                        self.add_replaced_image_item(
+                            state,
                             base,
-                            webrender_api::ImageDisplayItem {
-                                bounds: stacking_relative_content_box.to_layout(),
-                                common: items::empty_common_item_properties(),
-                                image_key: id,
-                                stretch_size: stacking_relative_content_box.size.to_layout(),
-                                tile_spacing: LayoutSize::zero(),
-                                image_rendering: self
-                                    .style
-                                    .get_inherited_box()
-                                    .image_rendering
-                                    .to_layout(),
-                                alpha_type: webrender_api::AlphaType::PremultipliedAlpha,
-                                color: webrender_api::ColorF::WHITE,
-                            },
+                            stacking_relative_content_box,
+                            id,
+                            self.style.get_inherited_box().image_rendering.to_layout(),
                         );
                     }
                 }
@@ -1851,18 +1933,12 @@ impl Fragment {
             SpecificFragmentInfo::Media(ref fragment_info) => {
                 if let Some((ref image_key, _, _)) = fragment_info.current_frame {
                     let base = create_base_display_item(state);
-                    state.add_image_item(
+                    self.add_replaced_image_item(
+                        state,
                         base,
-                        webrender_api::ImageDisplayItem {
-                            bounds: stacking_relative_content_box.to_layout(),
-                            common: items::empty_common_item_properties(),
-                            image_key: *image_key,
-                            stretch_size: stacking_relative_border_box.size.to_layout(),
-                            tile_spacing: LayoutSize::zero(),
-                            image_rendering: ImageRendering::Auto,
-                            alpha_type: webrender_api::AlphaType::PremultipliedAlpha,
-                            color: webrender_api::ColorF::WHITE,
-                        },
+                        stacking_relative_content_box,
+                        *image_key,
+                        ImageRendering::Auto,
                     );
                 }
             },
@@ -1886,18 +1962,13 @@ impl Fragment {
                 };
 
                 let base = create_base_display_item(state);
-                let display_item = webrender_api::ImageDisplayItem {
-                    bounds: stacking_relative_border_box.to_layout(),
-                    common: items::empty_common_item_properties(),
+                self.add_replaced_image_item(
+                    state,
+                    base,
+                    stacking_relative_content_box,
                     image_key,
-                    stretch_size: stacking_relative_content_box.size.to_layout(),
-                    tile_spacing: LayoutSize::zero(),
-                    image_rendering: ImageRendering::Auto,
-                    alpha_type: webrender_api::AlphaType::PremultipliedAlpha,
-                    color: webrender_api::ColorF::WHITE,
-                };
-
-                state.add_image_item(base, display_item);
+                    ImageRendering::Auto,
+                );
             },
             SpecificFragmentInfo::UnscannedText(_) => {
                 panic!("Shouldn't see unscanned fragments here.")
@@ -1908,9 +1979,43 @@ impl Fragment {
         }
     }
 
+    /// Adds the image of a replaced element, sized and positioned within its content box
+    /// according to `object-fit` and `object-position`, and clipped to that content box.
+    fn add_replaced_image_item(
+        &self,
+        state: &mut DisplayListBuildState,
+        mut base: BaseDisplayItem,
+        stacking_relative_content_box: Rect<Au>,
+        image_key: webrender_api::ImageKey,
+        image_rendering: ImageRendering,
+    ) {
+        let object_rect = self.stacking_relative_object_rect(stacking_relative_content_box);
+        base.clip_rect = match base
+            .clip_rect
+            .intersection(&stacking_relative_content_box.to_layout())
+        {
+            Some(clip_rect) => clip_rect,
+            None => return,
+        };
+        state.add_image_item(
+            base,
+            webrender_api::ImageDisplayItem {
+                bounds: object_rect.to_layout(),
+                common: items::empty_common_item_properties(),
+                image_key,
+                stretch_size: object_rect.size.to_layout(),
+                tile_spacing: LayoutSize::zero(),
+                image_rendering,
+                alpha_type: webrender_api::AlphaType::PremultipliedAlpha,
+                color: webrender_api::ColorF::WHITE,
+            },
+        );
+    }
+
     /// Creates a stacking context for associated fragment.
     fn create_stacking_context(
         &self,
+        layout_context: &LayoutContext,
         id: StackingContextId,
         base_flow: &BaseFlow,
         context_type: StackingContextType,
@@ -1945,6 +2050,23 @@ impl Fragment {
             filters.push(FilterOp::Opacity(effects.opacity.into(), effects.opacity));
         }
 
+        // Pseudo-stacking contexts can't be clipped as a whole, and are never
+        // established by `clip-path` or `mask-image` anyway.
+        let clips = if context_type == StackingContextType::Real && self.has_clip_path_or_mask() {
+            let writing_mode = self.style.writing_mode;
+            let border_box = Rect::new(Point2D::zero(), border_box.size);
+            let boxes = ReferenceBoxes {
+                border_box,
+                border: self.border_width().to_physical(writing_mode),
+                border_padding: self.border_padding.to_physical(writing_mode),
+                margin: self.margin.to_physical(writing_mode),
+                border_radii: border::radii(border_box, self.style.get_border()),
+            };
+            masking::stacking_context_clips(layout_context, self.node, &self.style, &boxes)
+        } else {
+            vec![]
+        };
+
         StackingContext::new(
             id,
             context_type,
@@ -1954,6 +2076,7 @@ impl Fragment {
             self.style().get_box()._servo_top_layer,
             filters,
             self.style().get_effects().mix_blend_mode.to_layout(),
+            clips,
             self.transform_matrix(&border_box),
             self.style().get_used_transform_style().to_layout(),
             self.perspective_matrix(&border_box),
@@ -2074,6 +2197,7 @@ impl Fragment {
         if !glyphs.is_empty() {
             let indexable_text = IndexableTextItem {
                 origin: stacking_relative_content_box.origin,
+                size: stacking_relative_content_box.size,
                 text_run: text_fragment.run.clone(),
                 range: text_fragment.range,
                 baseline_origin,
@@ -2685,6 +2809,7 @@ impl BlockFlow {
         state: &mut StackingContextCollectionState,
     ) {
         let new_context = self.fragment.create_stacking_context(
+            state.layout_context,
             self.base.stacking_context_id,
             &self.base,
             stacking_context_type,
@@ -2718,6 +2843,7 @@ impl BlockFlow {
         state: &mut StackingContextCollectionState,
     ) {
         let stacking_context = self.fragment.create_stacking_context(
+            state.layout_context,
             self.base.stacking_context_id,
             &self.base,
             StackingContextType::Real,
@@ -2978,6 +3104,8 @@ fn convert_text_run_to_glyphs(
 pub struct IndexableTextItem {
     /// The placement of the text item on the plane.
     pub origin: Point2D<Au>,
+    /// The size of the content box of the text item.
+    pub size: Size2D<Au>,
     /// The text run.
     pub text_run: Arc<TextRun>,
     /// The range of text within the text run.
@@ -3013,6 +3141,42 @@ impl IndexableText {
                 .range_index_of_advance(&item[0].range, offset.x),
         )
     }
+
+    /// Returns the index of the character within a text node closest to a point
+    /// in stacking-relative coordinates, looking at every line of the node.
+    pub fn text_index_at_point(&self, node: OpaqueNode, point: Point2D<Au>) -> Option<usize> {
+        let items = self.inner.get(&node)?;
+
+        // Pick the item on the line containing the point which is horizontally
+        // closest to it, falling back to the last line if the point is past
+        // every line of the node.
+        let horizontal_distance = |item: &IndexableTextItem| {
+            if point.x < item.origin.x {
+                item.origin.x - point.x
+            } else if point.x > item.origin.x + item.size.width {
+                point.x - item.origin.x - item.size.width
+            } else {
+                Au(0)
+            }
+        };
+        let item = items
+            .iter()
+            .filter(|item| point.y >= item.origin.y && point.y < item.origin.y + item.size.height)
+            .min_by_key(|item| horizontal_distance(item))
+            .or_else(|| {
+                if point.y < items[0].origin.y {
+                    items.first()
+                } else {
+                    items.last()
+                }
+            })?;
+
+        let offset = point - item.baseline_origin;
+        let preceding_chars = item.text_run.text[..item.range.begin().to_usize()]
+            .chars()
+            .count();
+        Some(preceding_chars + item.text_run.range_index_of_advance(&item.range, offset.x))
+    }
 }
 
 trait ToF32Px {
@@ -27,7 +27,7 @@ use webrender_api as wr;
 use webrender_api::units::{LayoutPixel, LayoutPoint, LayoutRect, LayoutSize, LayoutTransform};
 use webrender_api::{BorderRadius, ClipId, ClipMode, CommonItemProperties, ComplexClipRegion};
 use webrender_api::{ExternalScrollId, FilterOp, GlyphInstance, GradientStop, ImageKey};
-use webrender_api::{MixBlendMode, ScrollSensitivity, Shadow, SpatialId};
+use webrender_api::{ImageMask, MixBlendMode, ScrollSensitivity, Shadow, SpatialId};
 use webrender_api::{StickyOffsetBounds, TransformStyle};
 
 pub use style::dom::OpaqueNode;
@@ -171,6 +171,16 @@ pub enum StackingContextType {
     PseudoFloat,
 }
 
+/// A clip applied to a whole stacking context, in coordinates relative to the origin of its
+/// border box.
+#[derive(Clone, Debug, Serialize)]
+pub struct StackingContextClip {
+    /// The region outside of which nothing is painted.
+    pub region: ClippingRegion,
+    /// An alpha mask further restricting the painted area, if any.
+    pub image_mask: Option<ImageMask>,
+}
+
 #[derive(Clone, Serialize)]
 /// Represents one CSS stacking context, which may or may not have a hardware layer.
 pub struct StackingContext {
@@ -198,6 +208,10 @@ pub struct StackingContext {
     /// The blend mode with which this stacking context blends with its backdrop.
     pub mix_blend_mode: MixBlendMode,
 
+    /// The `clip-path` and `mask-image` clips to be applied to this stacking context. All of
+    /// them are intersected.
+    pub clips: Vec<StackingContextClip>,
+
     /// A transform to be applied to this stacking context.
     pub transform: Option<LayoutTransform>,
 
@@ -226,6 +240,7 @@ impl StackingContext {
         in_top_layer: InTopLayer,
         filters: Vec<FilterOp>,
         mix_blend_mode: MixBlendMode,
+        clips: Vec<StackingContextClip>,
         transform: Option<LayoutTransform>,
         transform_style: TransformStyle,
         perspective: Option<LayoutTransform>,
@@ -241,6 +256,7 @@ impl StackingContext {
             in_top_layer,
             filters,
             mix_blend_mode,
+            clips,
             transform,
             transform_style,
             perspective,
@@ -260,6 +276,7 @@ impl StackingContext {
             InTopLayer::None,
             vec![],
             MixBlendMode::Normal,
+            vec![],
             None,
             TransformStyle::Flat,
             None,
@@ -0,0 +1,613 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Clipping and masking of stacking contexts with `clip-path` and `mask-image`.
+//!
+//! Boxes, insets, circles and ellipses become WebRender clip regions.
+//! Polygons, paths and mask images become alpha masks, which are kept in
+//! WebRender images that are only updated when they change.
+//!
+//! <https://drafts.fxtf.org/css-masking/>
+
+use crate::context::LayoutContext;
+use crate::display_list::background::{self, get_cyclic};
+use crate::display_list::border;
+use crate::display_list::items::{ClippingRegion, OpaqueNode, StackingContextClip};
+use crate::display_list::ToLayout;
+use app_units::Au;
+use euclid::default::{Point2D, Rect, SideOffsets2D, Size2D, Transform2D};
+use fnv::FnvHashMap;
+use net_traits::image::base::Image;
+use net_traits::image_cache::{ImageOrMetadataAvailable, UsePlaceholder};
+use parking_lot::RwLock;
+use pixels::PixelFormat;
+use std::f32::consts::SQRT_2;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use style::computed_values::mask_clip::single_value::T as MaskClip;
+use style::computed_values::mask_mode::single_value::T as MaskMode;
+use style::computed_values::mask_origin::single_value::T as MaskOrigin;
+use style::properties::style_structs::SVG;
+use style::properties::ComputedValues;
+use style::values::computed::basic_shape::{BasicShape, FillRule, ShapeRadius};
+use style::values::computed::image::{Image as StyleImage, ImageLayer};
+use style::values::generics::basic_shape::{GeometryBox, ShapeBox, ShapeSource};
+use svg::path::Path;
+use webrender_api::units::{DeviceIntSize, LayoutPoint, LayoutRect, LayoutSize};
+use webrender_api::{BorderRadius, DirtyRect, ImageData, ImageDescriptor, ImageFormat};
+use webrender_api::{ImageKey, ImageMask, RenderApi, Transaction};
+
+/// The largest width or height of the image a shape is rasterized into, in
+/// device pixels.
+const MAX_MASK_SIZE: f32 = 4096.;
+
+/// The boxes of a fragment, relative to the origin of its border box.
+pub struct ReferenceBoxes {
+    pub border_box: Rect<Au>,
+    pub border: SideOffsets2D<Au>,
+    pub border_padding: SideOffsets2D<Au>,
+    pub margin: SideOffsets2D<Au>,
+    pub border_radii: BorderRadius,
+}
+
+impl ReferenceBoxes {
+    fn shape_box(&self, shape_box: ShapeBox) -> (Rect<Au>, BorderRadius) {
+        match shape_box {
+            ShapeBox::MarginBox => (
+                self.border_box.outer_rect(self.margin),
+                border::outer_radii(self.border_radii, self.margin),
+            ),
+            ShapeBox::BorderBox => (self.border_box, self.border_radii),
+            ShapeBox::PaddingBox => (
+                self.border_box.inner_rect(self.border),
+                border::inner_radii(self.border_radii, self.border),
+            ),
+            ShapeBox::ContentBox => (
+                self.border_box.inner_rect(self.border_padding),
+                border::inner_radii(self.border_radii, self.border_padding),
+            ),
+        }
+    }
+
+    /// Elements without an associated SVG viewport use their content box as
+    /// `fill-box` and their border box as `stroke-box` and `view-box`.
+    ///
+    /// <https://drafts.fxtf.org/css-masking/#typedef-geometry-box>
+    fn geometry_box(&self, geometry_box: GeometryBox) -> (Rect<Au>, BorderRadius) {
+        match geometry_box {
+            GeometryBox::ShapeBox(shape_box) => self.shape_box(shape_box),
+            GeometryBox::FillBox => self.shape_box(ShapeBox::ContentBox),
+            GeometryBox::StrokeBox | GeometryBox::ViewBox => self.shape_box(ShapeBox::BorderBox),
+        }
+    }
+}
+
+/// Computes the clips that `clip-path` and `mask-image` apply to the
+/// stacking context of the fragment of `node`.
+pub fn stacking_context_clips(
+    layout_context: &LayoutContext,
+    node: OpaqueNode,
+    style: &ComputedValues,
+    boxes: &ReferenceBoxes,
+) -> Vec<StackingContextClip> {
+    let mut clips = Vec::new();
+    if let Some(clip) = clip_path(layout_context, node, style, boxes) {
+        clips.push(clip);
+    }
+
+    // The layers are intersected, as `mask-composite` isn't supported and
+    // WebRender only intersects clips.
+    let svg = style.get_svg();
+    for (index, layer) in svg.mask_image.0.iter().enumerate() {
+        let image = match *layer {
+            ImageLayer::None => continue,
+            ImageLayer::Image(ref image) => image,
+        };
+        if let Some(clip) = image_mask(layout_context, node, svg, boxes, image, index) {
+            clips.push(clip);
+        }
+    }
+    clips
+}
+
+fn clip_path(
+    layout_context: &LayoutContext,
+    node: OpaqueNode,
+    style: &ComputedValues,
+    boxes: &ReferenceBoxes,
+) -> Option<StackingContextClip> {
+    match style.get_svg().clip_path {
+        ShapeSource::None => None,
+        // FIXME: references to `<clipPath>` elements aren't supported yet.
+        ShapeSource::ImageOrUrl(_) => None,
+        ShapeSource::Box(geometry_box) => {
+            let (rect, radii) = boxes.geometry_box(geometry_box);
+            Some(rounded_rect_clip(rect, radii))
+        },
+        ShapeSource::Shape(ref shape, reference_box) => {
+            let reference_box = reference_box.unwrap_or(GeometryBox::ShapeBox(ShapeBox::BorderBox));
+            let (reference_box, _) = boxes.geometry_box(reference_box);
+            Some(basic_shape_clip(layout_context, node, shape, reference_box))
+        },
+        ShapeSource::Path(ref path) => Some(path_clip(
+            layout_context,
+            node,
+            Path::from_path_data(&path.path),
+            path.fill,
+            boxes.border_box.origin,
+        )),
+    }
+}
+
+fn basic_shape_clip(
+    layout_context: &LayoutContext,
+    node: OpaqueNode,
+    shape: &BasicShape,
+    reference_box: Rect<Au>,
+) -> StackingContextClip {
+    let size = reference_box.size;
+    match *shape {
+        BasicShape::Inset(ref inset) => {
+            let offsets = SideOffsets2D::new(
+                inset.rect.0.to_used_value(size.height),
+                inset.rect.1.to_used_value(size.width),
+                inset.rect.2.to_used_value(size.height),
+                inset.rect.3.to_used_value(size.width),
+            );
+            let mut rect = reference_box.inner_rect(offsets);
+            rect.size.width = rect.size.width.max(Au(0));
+            rect.size.height = rect.size.height.max(Au(0));
+            rounded_rect_clip(rect, border::shape_radii(rect, &inset.round))
+        },
+        BasicShape::Circle(ref circle) => {
+            let center = Point2D::new(
+                circle.position.horizontal.to_used_value(size.width),
+                circle.position.vertical.to_used_value(size.height),
+            );
+            // Percentages refer to the normalized diagonal of the reference box.
+            let diagonal = size.width.to_f32_px().hypot(size.height.to_f32_px()) / SQRT_2;
+            let radius = shape_radius(
+                &circle.radius,
+                Au::from_f32_px(diagonal),
+                &[
+                    center.x,
+                    size.width - center.x,
+                    center.y,
+                    size.height - center.y,
+                ],
+            );
+            ellipse_clip(
+                reference_box.origin + center.to_vector(),
+                Size2D::new(radius, radius),
+            )
+        },
+        BasicShape::Ellipse(ref ellipse) => {
+            let center = Point2D::new(
+                ellipse.position.horizontal.to_used_value(size.width),
+                ellipse.position.vertical.to_used_value(size.height),
+            );
+            let radii = Size2D::new(
+                shape_radius(
+                    &ellipse.semiaxis_x,
+                    size.width,
+                    &[center.x, size.width - center.x],
+                ),
+                shape_radius(
+                    &ellipse.semiaxis_y,
+                    size.height,
+                    &[center.y, size.height - center.y],
+                ),
+            );
+            ellipse_clip(reference_box.origin + center.to_vector(), radii)
+        },
+        BasicShape::Polygon(ref polygon) => {
+            let points: Vec<_> = polygon
+                .coordinates
+                .iter()
+                .map(|coordinates| {
+                    Point2D::new(
+                        coordinates.0.to_used_value(size.width).to_f32_px(),
+                        coordinates.1.to_used_value(size.height).to_f32_px(),
+                    )
+                })
+                .collect();
+            path_clip(
+                layout_context,
+                node,
+                Path::polyline(&points, true),
+                polygon.fill,
+                reference_box.origin,
+            )
+        },
+    }
+}
+
+/// Resolves the radius of a circle or ellipse, given the distances from its
+/// center to the relevant sides of the reference box.
+fn shape_radius(radius: &ShapeRadius, percentage_basis: Au, distances: &[Au]) -> Au {
+    let distances = distances.iter().map(|distance| Au(distance.0.abs()));
+    match *radius {
+        ShapeRadius::Length(ref length) => length.0.to_used_value(percentage_basis),
+        ShapeRadius::ClosestSide => distances.min().unwrap_or(Au(0)),
+        ShapeRadius::FarthestSide => distances.max().unwrap_or(Au(0)),
+    }
+}
+
+fn rounded_rect_clip(rect: Rect<Au>, radii: BorderRadius) -> StackingContextClip {
+    let rect = rect.to_layout();
+    let mut region = ClippingRegion::from_rect(rect);
+    if !radii.is_zero() {
+        region.intersect_with_rounded_rect(rect, radii);
+    }
+    StackingContextClip {
+        region,
+        image_mask: None,
+    }
+}
+
+fn ellipse_clip(center: Point2D<Au>, radii: Size2D<Au>) -> StackingContextClip {
+    let rect = Rect::new(
+        Point2D::new(center.x - radii.width, center.y - radii.height),
+        Size2D::new(radii.width * 2, radii.height * 2),
+    );
+    let radius = radii.to_layout();
+    let radii = BorderRadius {
+        top_left: radius,
+        top_right: radius,
+        bottom_right: radius,
+        bottom_left: radius,
+    };
+    rounded_rect_clip(rect, radii)
+}
+
+/// A clip that hides everything.
+fn empty_clip() -> StackingContextClip {
+    StackingContextClip {
+        region: ClippingRegion::empty(),
+        image_mask: None,
+    }
+}
+
+/// Clips to the area filled by a path whose coordinates are relative to
+/// `origin`, which is rasterized into a mask.
+fn path_clip(
+    layout_context: &LayoutContext,
+    node: OpaqueNode,
+    path: Path,
+    fill: FillRule,
+    origin: Point2D<Au>,
+) -> StackingContextClip {
+    let bounds = path.bounds();
+    let device_pixel_ratio = layout_context.style_context.device_pixel_ratio().get();
+    let largest_side = bounds.size.width.max(bounds.size.height) * device_pixel_ratio;
+    let scale = if largest_side > MAX_MASK_SIZE {
+        device_pixel_ratio * MAX_MASK_SIZE / largest_side
+    } else {
+        device_pixel_ratio
+    };
+    let size = DeviceIntSize::new(
+        (bounds.size.width * scale).ceil() as i32,
+        (bounds.size.height * scale).ceil() as i32,
+    );
+    if size.width <= 0 || size.height <= 0 {
+        return empty_clip();
+    }
+
+    let rule = match fill {
+        FillRule::Nonzero => svg::tree::FillRule::NonZero,
+        FillRule::Evenodd => svg::tree::FillRule::EvenOdd,
+    };
+    let source = MaskSource::Shape { path, rule, scale };
+    let key = layout_context
+        .mask_image_cache
+        .image_key(node, 0, source, size, |source| match *source {
+            MaskSource::Shape {
+                ref path,
+                rule,
+                scale,
+            } => {
+                let transform = Transform2D::create_translation(-bounds.origin.x, -bounds.origin.y)
+                    .post_scale(scale, scale);
+                Some(svg::fill_mask(
+                    path,
+                    rule,
+                    &transform,
+                    size.width as u32,
+                    size.height as u32,
+                ))
+            },
+            MaskSource::Image { .. } => None,
+        });
+    let key = match key {
+        Some(key) => key,
+        None => return empty_clip(),
+    };
+
+    let rect = LayoutRect::new(
+        LayoutPoint::new(
+            origin.x.to_f32_px() + bounds.origin.x,
+            origin.y.to_f32_px() + bounds.origin.y,
+        ),
+        LayoutSize::new(size.width as f32 / scale, size.height as f32 / scale),
+    );
+    StackingContextClip {
+        region: ClippingRegion::from_rect(rect),
+        image_mask: Some(ImageMask {
+            image: key,
+            rect,
+            repeat: false,
+        }),
+    }
+}
+
+/// Masks with a layer of `mask-image`, placed like a background image.
+///
+/// Returns `None` if the image can't be used as a mask.
+fn image_mask(
+    layout_context: &LayoutContext,
+    node: OpaqueNode,
+    svg: &SVG,
+    boxes: &ReferenceBoxes,
+    image: &StyleImage,
+    index: usize,
+) -> Option<StackingContextClip> {
+    let url = match *image {
+        StyleImage::Url(ref image_url) => image_url.url()?.clone(),
+        // FIXME: gradients and other images aren't supported as masks yet.
+        _ => return None,
+    };
+    // Images that aren't available count as transparent black, which hides
+    // everything.
+    let image = match layout_context.get_or_request_image_or_meta(node, url, UsePlaceholder::No) {
+        Some(ImageOrMetadataAvailable::ImageAvailable(image, _)) => image,
+        _ => return Some(empty_clip()),
+    };
+    let size = DeviceIntSize::new(image.width as i32, image.height as i32);
+    if size.width <= 0 || size.height <= 0 {
+        return Some(empty_clip());
+    }
+
+    let (clip_rect, clip_radii) = boxes.shape_box(match *get_cyclic(&svg.mask_clip.0, index) {
+        MaskClip::BorderBox => ShapeBox::BorderBox,
+        MaskClip::PaddingBox => ShapeBox::PaddingBox,
+        MaskClip::ContentBox => ShapeBox::ContentBox,
+    });
+    let (mut bounds, _) = boxes.shape_box(match *get_cyclic(&svg.mask_origin.0, index) {
+        MaskOrigin::BorderBox => ShapeBox::BorderBox,
+        MaskOrigin::PaddingBox => ShapeBox::PaddingBox,
+        MaskOrigin::ContentBox => ShapeBox::ContentBox,
+    });
+
+    let intrinsic_size = Size2D::new(Au::from_px(size.width), Au::from_px(size.height));
+    let mut tile_size = background::compute_background_image_size(
+        *get_cyclic(&svg.mask_size.0, index),
+        bounds.size,
+        Some(intrinsic_size),
+    );
+    if tile_size.width <= Au(0) || tile_size.height <= Au(0) {
+        return Some(empty_clip());
+    }
+
+    // FIXME: WebRender repeats masks without spacing between them, so
+    // `mask-repeat: space` is off.
+    let mut tile_spacing = Size2D::zero();
+    let own_position = bounds.size - tile_size;
+    let pos_x = get_cyclic(&svg.mask_position_x.0, index).to_used_value(own_position.width);
+    let pos_y = get_cyclic(&svg.mask_position_y.0, index).to_used_value(own_position.height);
+    let repeat = get_cyclic(&svg.mask_repeat.0, index);
+    background::tile_image_axis(
+        repeat.0,
+        &mut bounds.origin.x,
+        &mut bounds.size.width,
+        &mut tile_size.width,
+        &mut tile_spacing.width,
+        pos_x,
+        clip_rect.origin.x,
+        clip_rect.size.width,
+    );
+    background::tile_image_axis(
+        repeat.1,
+        &mut bounds.origin.y,
+        &mut bounds.size.height,
+        &mut tile_size.height,
+        &mut tile_spacing.height,
+        pos_y,
+        clip_rect.origin.y,
+        clip_rect.size.height,
+    );
+
+    let luminance = *get_cyclic(&svg.mask_mode.0, index) == MaskMode::Luminance;
+    let source = MaskSource::Image {
+        image: image.clone(),
+        luminance,
+    };
+    let key = layout_context
+        .mask_image_cache
+        .image_key(node, index + 1, source, size, |_| {
+            mask_pixels(&image, luminance)
+        })?;
+
+    let painted_rect = clip_rect.intersection(&bounds).unwrap_or(Rect::zero());
+    let mut region = ClippingRegion::from_rect(painted_rect.to_layout());
+    if !clip_radii.is_zero() {
+        region.intersect_with_rounded_rect(clip_rect.to_layout(), clip_radii);
+    }
+    Some(StackingContextClip {
+        region,
+        image_mask: Some(ImageMask {
+            image: key,
+            rect: Rect::new(bounds.origin, tile_size).to_layout(),
+            repeat: true,
+        }),
+    })
+}
+
+/// The alpha or the luminance of the pixels of the first frame of an image,
+/// with one byte per pixel.
+///
+/// <https://drafts.fxtf.org/css-masking/#the-mask-mode>
+fn mask_pixels(image: &Image, luminance: bool) -> Option<Vec<u8>> {
+    let (bytes_per_pixel, red, blue) = match image.format {
+        PixelFormat::BGRA8 => (4, 2, 0),
+        PixelFormat::RGB8 => (3, 0, 2),
+        PixelFormat::K8 | PixelFormat::KA8 | PixelFormat::RGBA8 => return None,
+    };
+    let pixels = image
+        .first_frame()
+        .chunks(bytes_per_pixel)
+        .map(|pixel| {
+            let alpha = pixel.get(3).cloned().unwrap_or(255);
+            if !luminance {
+                return alpha;
+            }
+            let luminance =
+                0.2125 * pixel[red] as f32 + 0.7154 * pixel[1] as f32 + 0.0721 * pixel[blue] as f32;
+            (luminance * alpha as f32 / 255. + 0.5) as u8
+        })
+        .collect();
+    Some(pixels)
+}
+
+/// What the image of a mask is made of.
+enum MaskSource {
+    /// A path, in CSS pixels, rasterized at the given scale.
+    Shape {
+        path: Path,
+        rule: svg::tree::FillRule,
+        scale: f32,
+    },
+    /// The alpha or luminance of an image.
+    Image { image: Arc<Image>, luminance: bool },
+}
+
+impl PartialEq for MaskSource {
+    fn eq(&self, other: &MaskSource) -> bool {
+        match (self, other) {
+            (
+                MaskSource::Shape { path, rule, scale },
+                MaskSource::Shape {
+                    path: other_path,
+                    rule: other_rule,
+                    scale: other_scale,
+                },
+            ) => path == other_path && rule == other_rule && scale == other_scale,
+            (
+                MaskSource::Image { image, luminance },
+                MaskSource::Image {
+                    image: other_image,
+                    luminance: other_luminance,
+                },
+            ) => Arc::ptr_eq(image, other_image) && luminance == other_luminance,
+            _ => false,
+        }
+    }
+}
+
+/// An image holding a mask.
+struct MaskImage {
+    source: MaskSource,
+    size: DeviceIntSize,
+    key: ImageKey,
+    /// Whether the image was painted since the last eviction.
+    painted: AtomicBool,
+}
+
+/// The images of the masks of a document, which are only rendered again when
+/// their source or size changes.
+///
+/// They are keyed by element and by an index which is zero for `clip-path`,
+/// and one more than the index of the layer for `mask-image`.
+pub struct MaskImageCache {
+    webrender_api: Mutex<RenderApi>,
+    images: RwLock<FnvHashMap<(OpaqueNode, usize), MaskImage>>,
+}
+
+impl MaskImageCache {
+    pub fn new(webrender_api: RenderApi) -> MaskImageCache {
+        MaskImageCache {
+            webrender_api: Mutex::new(webrender_api),
+            images: RwLock::new(FnvHashMap::default()),
+        }
+    }
+
+    /// Returns the key of the image of a mask, calling `render` for its
+    /// pixels if it changed since it was last painted.
+    fn image_key<F>(
+        &self,
+        node: OpaqueNode,
+        index: usize,
+        source: MaskSource,
+        size: DeviceIntSize,
+        render: F,
+    ) -> Option<ImageKey>
+    where
+        F: FnOnce(&MaskSource) -> Option<Vec<u8>>,
+    {
+        let old_image = match self.images.read().get(&(node, index)) {
+            Some(image) if image.size == size && image.source == source => {
+                image.painted.store(true, Ordering::Relaxed);
+                return Some(image.key);
+            },
+            Some(image) => Some((image.key, image.size)),
+            None => None,
+        };
+
+        // WebRender reads the coverage of image masks from their first channel.
+        let descriptor = ImageDescriptor {
+            size,
+            stride: None,
+            format: ImageFormat::R8,
+            offset: 0,
+            is_opaque: false,
+            allow_mipmaps: false,
+        };
+        let data = ImageData::new(render(&source)?);
+
+        let webrender_api = self.webrender_api.lock().unwrap();
+        let mut txn = Transaction::new();
+        let key = match old_image {
+            Some((key, old_size)) if old_size == size => {
+                txn.update_image(key, descriptor, data, &DirtyRect::All);
+                key
+            },
+            old_image => {
+                if let Some((old_key, _)) = old_image {
+                    txn.delete_image(old_key);
+                }
+                let key = webrender_api.generate_image_key();
+                txn.add_image(key, descriptor, data, None);
+                key
+            },
+        };
+        webrender_api.update_resources(txn.resource_updates);
+
+        self.images.write().insert(
+            (node, index),
+            MaskImage {
+                source,
+                size,
+                key,
+                painted: AtomicBool::new(true),
+            },
+        );
+        Some(key)
+    }
+
+    /// Deletes the images that weren't painted since the last call, which
+    /// belong to elements that were removed or aren't masked anymore.
+    pub fn evict_unpainted_images(&self) {
+        let mut txn = Transaction::new();
+        self.images.write().retain(|_, image| {
+            if image.painted.swap(false, Ordering::Relaxed) {
+                return true;
+            }
+            txn.delete_image(image.key);
+            false
+        });
+        if !txn.resource_updates.is_empty() {
+            let webrender_api = self.webrender_api.lock().unwrap();
+            webrender_api.update_resources(txn.resource_updates);
+        }
+    }
+}
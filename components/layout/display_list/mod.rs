@@ -8,6 +8,7 @@ pub use self::builder::IndexableText;
 pub use self::builder::StackingContextCollectionFlags;
 pub use self::builder::StackingContextCollectionState;
 pub use self::conversions::ToLayout;
+pub use self::masking::MaskImageCache;
 pub use self::webrender_helpers::WebRenderDisplayListConverter;
 
 mod background;
@@ -16,4 +17,5 @@ mod builder;
 mod conversions;
 mod gradient;
 pub mod items;
+mod masking;
 mod webrender_helpers;
@@ -8,10 +8,12 @@
 //           completely converting layout to directly generate WebRender display lists, for example.
 
 use crate::display_list::items::{BaseDisplayItem, ClipScrollNode, ClipScrollNodeType};
-use crate::display_list::items::{DisplayItem, DisplayList, StackingContextType};
+use crate::display_list::items::{DisplayItem, DisplayList};
+use crate::display_list::items::{StackingContextClip, StackingContextType};
 use msg::constellation_msg::PipelineId;
-use webrender_api::units::LayoutPoint;
+use webrender_api::units::{LayoutPoint, LayoutVector2D};
 use webrender_api::{self, ClipId, CommonItemProperties, DisplayItem as WrDisplayItem};
+use webrender_api::{ComplexClipRegion, ImageMask};
 use webrender_api::{DisplayListBuilder, PropertyBinding, PushStackingContextDisplayItem};
 use webrender_api::{
     RasterSpace, ReferenceFrameKind, SpaceAndClipInfo, SpatialId, StackingContext,
@@ -219,6 +221,22 @@ impl WebRenderDisplayItemConverter for DisplayItem {
                     builder.push_iter(&stacking_context.filters);
                 }
 
+                let clip_id = if stacking_context.clips.is_empty() {
+                    None
+                } else {
+                    let parent = SpaceAndClipInfo {
+                        spatial_id,
+                        clip_id: ClipId::root(builder.pipeline_id),
+                    };
+                    let offset = bounds.origin.to_vector();
+                    let clip_ids: Vec<_> = stacking_context
+                        .clips
+                        .iter()
+                        .map(|clip| define_stacking_context_clip(builder, &parent, clip, offset))
+                        .collect();
+                    Some(ClipId::ClipChain(builder.define_clip_chain(None, clip_ids)))
+                };
+
                 let wr_item = PushStackingContextDisplayItem {
                     origin: bounds.origin,
                     spatial_id,
@@ -226,7 +244,7 @@ impl WebRenderDisplayItemConverter for DisplayItem {
                     stacking_context: StackingContext {
                         transform_style: stacking_context.transform_style,
                         mix_blend_mode: stacking_context.mix_blend_mode,
-                        clip_id: None,
+                        clip_id,
                         raster_space: RasterSpace::Screen,
                         // TODO(pcwalton): Enable picture caching?
                         cache_tiles: false,
@@ -303,6 +321,35 @@ impl WebRenderDisplayItemConverter for DisplayItem {
     }
 }
 
+/// Defines a WebRender clip for a `clip-path` or `mask-image` clip of a stacking context whose
+/// border box starts at `offset`.
+fn define_stacking_context_clip(
+    builder: &mut DisplayListBuilder,
+    parent: &SpaceAndClipInfo,
+    clip: &StackingContextClip,
+    offset: LayoutVector2D,
+) -> ClipId {
+    let complex = clip
+        .region
+        .complex
+        .iter()
+        .map(|region| ComplexClipRegion {
+            rect: region.rect.translate(offset),
+            ..*region
+        })
+        .collect::<Vec<_>>();
+    let image_mask = clip.image_mask.map(|mask| ImageMask {
+        rect: mask.rect.translate(offset),
+        ..mask
+    });
+    builder.define_clip(
+        parent,
+        clip.region.main.translate(offset),
+        complex,
+        image_mask,
+    )
+}
+
 fn build_common_item_properties(
     base: &BaseDisplayItem,
     state: &ClipScrollState,
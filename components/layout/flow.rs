@@ -34,6 +34,7 @@ use crate::floats::{Floats, SpeculatedFloatPlacement};
 use crate::flow_list::{FlowList, FlowListIterator, MutFlowListIterator};
 use crate::flow_ref::{FlowRef, WeakFlowRef};
 use crate::fragment::{CoordinateSystem, Fragment, FragmentBorderBoxIterator, Overflow};
+use crate::grid::GridFlow;
 use crate::inline::InlineFlow;
 use crate::model::{CollapsibleMargins, IntrinsicISizes};
 use crate::parallel::FlowParallelInfo;
@@ -127,6 +128,11 @@ pub trait Flow: HasBaseFlow + fmt::Debug + Sync + Send + 'static {
         panic!("called as_flex() on a non-flex flow")
     }
 
+    /// If this is a grid flow, returns the underlying object. Fails otherwise.
+    fn as_grid(&self) -> &GridFlow {
+        panic!("called as_grid() on a non-grid flow")
+    }
+
     /// If this is an inline flow, returns the underlying object. Fails otherwise.
     fn as_inline(&self) -> &InlineFlow {
         panic!("called as_inline() on a non-inline flow")
@@ -573,6 +579,7 @@ pub enum FlowClass {
     Multicol,
     MulticolColumn,
     Flex,
+    Grid,
 }
 
 impl FlowClass {
@@ -586,7 +593,8 @@ impl FlowClass {
             FlowClass::TableCaption |
             FlowClass::TableCell |
             FlowClass::TableWrapper |
-            FlowClass::Flex => true,
+            FlowClass::Flex |
+            FlowClass::Grid => true,
             _ => false,
         }
     }
@@ -16,6 +16,7 @@ use crate::inline::{InlineMetrics, LineMetrics};
 use crate::layout_debug;
 use crate::model::style_length;
 use crate::model::{self, IntrinsicISizes, IntrinsicISizesContribution, MaybeAuto, SizeConstraint};
+use crate::svg::SvgContent;
 use crate::text;
 use crate::text::TextRunScanner;
 use crate::wrapper::ThreadSafeLayoutNodeHelpers;
@@ -48,6 +49,7 @@ use style::computed_values::clear::T as Clear;
 use style::computed_values::color::T as Color;
 use style::computed_values::display::T as Display;
 use style::computed_values::mix_blend_mode::T as MixBlendMode;
+use style::computed_values::object_fit::T as ObjectFit;
 use style::computed_values::overflow_wrap::T as OverflowWrap;
 use style::computed_values::overflow_x::T as StyleOverflow;
 use style::computed_values::position::T as Position;
@@ -61,7 +63,9 @@ use style::selector_parser::RestyleDamage;
 use style::servo::restyle_damage::ServoRestyleDamage;
 use style::str::char_is_whitespace;
 use style::values::computed::counters::ContentItem;
+use style::values::computed::image::ImageLayer;
 use style::values::computed::{LengthPercentage, LengthPercentageOrAuto, Size, VerticalAlign};
+use style::values::generics::basic_shape::ShapeSource;
 use style::values::generics::box_::{Perspective, VerticalAlignKeyword};
 use style::values::generics::transform;
 use style::Zero;
@@ -384,13 +388,21 @@ impl MediaFragmentInfo {
 pub struct SvgFragmentInfo {
     pub dom_width: Au,
     pub dom_height: Au,
+    /// The shapes and text of the element, or `None` if it has none.
+    pub content: Option<Arc<SvgContent>>,
 }
 
 impl SvgFragmentInfo {
-    pub fn new(data: SVGSVGData) -> SvgFragmentInfo {
+    pub fn new<N: ThreadSafeLayoutNode>(
+        node: &N,
+        data: SVGSVGData,
+        layout_context: &LayoutContext,
+    ) -> SvgFragmentInfo {
+        let viewport = Size2D::new(data.width as f32, data.height as f32);
         SvgFragmentInfo {
             dom_width: Au::from_px(data.width as i32),
             dom_height: Au::from_px(data.height as i32),
+            content: SvgContent::new(node, viewport, layout_context).map(Arc::new),
         }
     }
 }
@@ -2698,6 +2710,73 @@ impl Fragment {
         )
     }
 
+    /// Given the stacking-context-relative content box of this replaced element, returns the
+    /// stacking-context-relative rectangle its contents are painted into, according to
+    /// `object-fit` and `object-position`.
+    ///
+    /// <https://drafts.csswg.org/css-images/#the-object-fit>
+    pub fn stacking_relative_object_rect(
+        &self,
+        stacking_relative_content_box: Rect<Au>,
+    ) -> Rect<Au> {
+        let content_size = stacking_relative_content_box.size;
+        let intrinsic_size = Size2D::new(self.intrinsic_width(), self.intrinsic_height());
+        if intrinsic_size.width == Au(0) || intrinsic_size.height == Au(0) {
+            return stacking_relative_content_box;
+        }
+
+        let intrinsic_ratio = intrinsic_size.width.to_f32_px() / intrinsic_size.height.to_f32_px();
+        let content_ratio = content_size.width.to_f32_px() / content_size.height.to_f32_px();
+        let contain_size = if intrinsic_ratio > content_ratio {
+            Size2D::new(
+                content_size.width,
+                content_size.width.scale_by(intrinsic_ratio.recip()),
+            )
+        } else {
+            Size2D::new(
+                content_size.height.scale_by(intrinsic_ratio),
+                content_size.height,
+            )
+        };
+        let cover_size = if intrinsic_ratio > content_ratio {
+            Size2D::new(
+                content_size.height.scale_by(intrinsic_ratio),
+                content_size.height,
+            )
+        } else {
+            Size2D::new(
+                content_size.width,
+                content_size.width.scale_by(intrinsic_ratio.recip()),
+            )
+        };
+        let object_size = match self.style.get_position().object_fit {
+            ObjectFit::Fill => content_size,
+            ObjectFit::Contain => contain_size,
+            ObjectFit::Cover => cover_size,
+            ObjectFit::None => intrinsic_size,
+            // Both sizes have the intrinsic aspect ratio, so comparing widths is enough.
+            ObjectFit::ScaleDown => {
+                if intrinsic_size.width < contain_size.width {
+                    intrinsic_size
+                } else {
+                    contain_size
+                }
+            },
+        };
+
+        let object_position = &self.style.get_position().object_position;
+        let free_space = content_size - object_size;
+        Rect::new(
+            Point2D::new(
+                stacking_relative_content_box.origin.x +
+                    object_position.horizontal.to_used_value(free_space.width),
+                stacking_relative_content_box.origin.y +
+                    object_position.vertical.to_used_value(free_space.height),
+            ),
+            object_size,
+        )
+    }
+
     /// Returns true if this fragment may establish a reference frame.
     pub fn can_establish_reference_frame(&self) -> bool {
         !self.style().get_box().transform.0.is_empty() ||
@@ -2711,6 +2790,20 @@ impl Fragment {
             self.style().get_box().perspective != Perspective::None
     }
 
+    /// Returns true if this fragment is clipped by `clip-path` or masked by `mask-image`.
+    pub fn has_clip_path_or_mask(&self) -> bool {
+        let svg = self.style().get_svg();
+        let has_clip_path = match svg.clip_path {
+            ShapeSource::None => false,
+            _ => true,
+        };
+        has_clip_path ||
+            svg.mask_image.0.iter().any(|layer| match *layer {
+                ImageLayer::None => false,
+                ImageLayer::Image(_) => true,
+            })
+    }
+
     /// Returns true if this fragment establishes a new stacking context and false otherwise.
     pub fn establishes_stacking_context(&self) -> bool {
         // Text fragments shouldn't create stacking contexts.
@@ -2733,6 +2826,10 @@ impl Fragment {
             return true;
         }
 
+        if self.has_clip_path_or_mask() {
+            return true;
+        }
+
         if self.style().get_box().transform_style == TransformStyle::Preserve3d ||
             self.style().overrides_transform_style()
         {
@@ -2773,7 +2870,7 @@ impl Fragment {
         }
 
         match self.style().get_box().display {
-            Display::Flex => self.style().get_position().z_index.integer_or(0),
+            Display::Flex | Display::Grid => self.style().get_position().z_index.integer_or(0),
             _ => 0,
         }
     }
@@ -3396,8 +3493,9 @@ impl Overflow {
 
 bitflags! {
     pub struct FragmentFlags: u8 {
-        // TODO(stshine): find a better name since these flags can also be used for grid item.
-        /// Whether this fragment represents a child in a row flex container.
+        // TODO(stshine): find a better name since these flags are also used for grid items.
+        /// Whether this fragment represents a child in a row flex container or in a grid
+        /// container.
         const IS_INLINE_FLEX_ITEM = 0b0000_0001;
         /// Whether this fragment represents a child in a column flex container.
         const IS_BLOCK_FLEX_ITEM = 0b0000_0010;
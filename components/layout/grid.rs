@@ -0,0 +1,1703 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Layout for elements with a CSS `display` property of `grid`.
+
+use crate::block::{AbsoluteAssignBSizesTraversal, BlockFlow};
+use crate::context::LayoutContext;
+use crate::display_list::{
+    BorderPaintingMode, DisplayListBuildState, StackingContextCollectionState,
+};
+use crate::floats::FloatKind;
+use crate::flow::{Flow, FlowClass, FlowFlags, GetBaseFlow, OpaqueFlow};
+use crate::fragment::{Fragment, FragmentBorderBoxIterator, Overflow};
+use crate::layout_debug;
+use crate::model::{self, AdjoiningMargins, CollapsibleMargins, SizeConstraint};
+use crate::traversal::PreorderFlowTraversal;
+use app_units::Au;
+use euclid::default::Point2D;
+use fnv::FnvHashMap;
+use servo_atoms::Atom;
+use std::cmp::{max, min};
+use std::ops::Range;
+use style::computed_values::align_content::T as AlignContent;
+use style::computed_values::align_items::T as AlignItems;
+use style::computed_values::align_self::T as AlignSelf;
+use style::computed_values::justify_content::T as JustifyContent;
+use style::computed_values::justify_items::T as JustifyItems;
+use style::computed_values::justify_self::T as JustifySelf;
+use style::logical_geometry::{Direction, LogicalSize};
+use style::properties::ComputedValues;
+use style::servo::restyle_damage::ServoRestyleDamage;
+use style::values::computed::length::NonNegativeLengthPercentageOrNormal;
+use style::values::computed::position::{GridAutoFlow, GridTemplateAreas};
+use style::values::computed::{GridLine, GridTemplateComponent, Size, TrackBreadth, TrackSize};
+use style::values::generics::grid::{RepeatCount, TrackListValue, MAX_GRID_LINE};
+use style::values::specified::position::AutoFlow;
+use style::values::CustomIdent;
+
+/// The lower bound of a track sizing function, with percentages resolved.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum MinSizing {
+    Fixed(Au),
+    MinContent,
+    MaxContent,
+    Auto,
+}
+
+/// The upper bound of a track sizing function, with percentages resolved.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum MaxSizing {
+    Fixed(Au),
+    MinContent,
+    MaxContent,
+    Auto,
+    FitContent(Au),
+    Flex(f32),
+}
+
+/// The sizing function of a track.
+/// https://drafts.csswg.org/css-grid/#algo-terms
+#[derive(Clone, Copy, Debug)]
+struct TrackSizing {
+    min: MinSizing,
+    max: MaxSizing,
+}
+
+impl TrackSizing {
+    /// Resolves a track size against the size of the grid in the same axis. Percentages behave
+    /// as `auto` when that size is indefinite.
+    fn new(size: &TrackSize, basis: Option<Au>) -> TrackSizing {
+        match *size {
+            TrackSize::Breadth(ref breadth) => TrackSizing {
+                min: min_sizing(breadth, basis),
+                max: max_sizing(breadth, basis),
+            },
+            TrackSize::Minmax(ref min, ref max) => TrackSizing {
+                min: min_sizing(min, basis),
+                max: max_sizing(max, basis),
+            },
+            TrackSize::FitContent(ref breadth) => TrackSizing {
+                min: MinSizing::Auto,
+                max: match max_sizing(breadth, basis) {
+                    MaxSizing::Fixed(limit) => MaxSizing::FitContent(limit),
+                    _ => MaxSizing::MaxContent,
+                },
+            },
+        }
+    }
+
+    fn flex_factor(&self) -> Option<f32> {
+        match self.max {
+            MaxSizing::Flex(factor) => Some(factor),
+            _ => None,
+        }
+    }
+
+    /// Returns the size of the track if it is definite without laying out any item, which is
+    /// used to find how many times an `auto-fill` repetition repeats.
+    fn definite_size(&self) -> Option<Au> {
+        match (self.min, self.max) {
+            (MinSizing::Fixed(min), MaxSizing::Fixed(max)) => Some(::std::cmp::max(min, max)),
+            (_, MaxSizing::Fixed(size)) | (MinSizing::Fixed(size), _) => Some(size),
+            _ => None,
+        }
+    }
+}
+
+fn min_sizing(breadth: &TrackBreadth, basis: Option<Au>) -> MinSizing {
+    match *breadth {
+        TrackBreadth::Breadth(ref lp) => match lp.maybe_to_used_value(basis) {
+            Some(size) => MinSizing::Fixed(max(size, Au(0))),
+            None => MinSizing::Auto,
+        },
+        TrackBreadth::Fr(_) | TrackBreadth::Auto => MinSizing::Auto,
+        TrackBreadth::MinContent => MinSizing::MinContent,
+        TrackBreadth::MaxContent => MinSizing::MaxContent,
+    }
+}
+
+fn max_sizing(breadth: &TrackBreadth, basis: Option<Au>) -> MaxSizing {
+    match *breadth {
+        TrackBreadth::Breadth(ref lp) => match lp.maybe_to_used_value(basis) {
+            Some(size) => MaxSizing::Fixed(max(size, Au(0))),
+            None => MaxSizing::Auto,
+        },
+        TrackBreadth::Fr(factor) => MaxSizing::Flex(factor),
+        TrackBreadth::Auto => MaxSizing::Auto,
+        TrackBreadth::MinContent => MaxSizing::MinContent,
+        TrackBreadth::MaxContent => MaxSizing::MaxContent,
+    }
+}
+
+/// Returns the used value of `column-gap` or `row-gap`, where `normal` is zero in grids.
+fn used_gap(gap: &NonNegativeLengthPercentageOrNormal, basis: Option<Au>) -> Au {
+    match *gap {
+        NonNegativeLengthPercentageOrNormal::LengthPercentage(ref lp) => {
+            lp.maybe_to_used_value(basis).unwrap_or(Au(0))
+        },
+        NonNegativeLengthPercentageOrNormal::Normal => Au(0),
+    }
+}
+
+/// Returns the number of times the `auto-fill` or `auto-fit` repetition of a track list repeats
+/// in a grid of the given size.
+/// https://drafts.csswg.org/css-grid/#auto-repeat
+fn auto_repetitions(template: &GridTemplateComponent, size: Option<Au>, gap: Au) -> usize {
+    let list = match *template {
+        GridTemplateComponent::TrackList(ref list) if list.has_auto_repeat() => list,
+        _ => return 1,
+    };
+    let size = match size {
+        Some(size) => size,
+        None => return 1,
+    };
+
+    let mut other_size = Au(0);
+    let mut other_count = 0;
+    let mut repeated_size = Au(0);
+    let mut repeated_count = 0;
+    for (index, value) in list.values.iter().enumerate() {
+        let definite_size = |track: &TrackSize| TrackSizing::new(track, Some(size)).definite_size();
+        match *value {
+            TrackListValue::TrackSize(ref track) => {
+                other_size += definite_size(track).unwrap_or(Au(0));
+                other_count += 1;
+            },
+            TrackListValue::TrackRepeat(ref repeat) if index == list.auto_repeat_index => {
+                for track in repeat.track_sizes.iter() {
+                    repeated_size += definite_size(track).unwrap_or(Au(0));
+                    repeated_count += 1;
+                }
+            },
+            TrackListValue::TrackRepeat(ref repeat) => {
+                let count = match repeat.count {
+                    RepeatCount::Number(count) => max(count, 1),
+                    RepeatCount::AutoFill | RepeatCount::AutoFit => 1,
+                };
+                for track in repeat.track_sizes.iter() {
+                    other_size += definite_size(track).unwrap_or(Au(0)) * count;
+                    other_count += count;
+                }
+            },
+        }
+    }
+
+    // The largest number of repetitions that doesn't overflow the grid, gaps included.
+    let repetition_size = repeated_size + gap * repeated_count;
+    if repetition_size <= Au(0) {
+        return 1;
+    }
+    let free_space = size + gap - other_size - gap * other_count;
+    max(min(free_space.0 / repetition_size.0, MAX_GRID_LINE), 1) as usize
+}
+
+/// The tracks of the explicit grid in one axis, as given by `grid-template-rows` or
+/// `grid-template-columns`, with repetitions expanded.
+struct ExplicitTracks {
+    /// The size of each track.
+    sizes: Vec<TrackSize>,
+    /// The names of each line, which there is one more of than tracks.
+    line_names: Vec<Vec<Atom>>,
+    /// The tracks that come from a `repeat(auto-fit, ...)`, which collapse when no item is
+    /// placed in them.
+    auto_fit: Range<usize>,
+}
+
+impl ExplicitTracks {
+    fn new(template: &GridTemplateComponent, repetitions: usize) -> ExplicitTracks {
+        let mut tracks = ExplicitTracks {
+            sizes: vec![],
+            line_names: vec![vec![]],
+            auto_fit: 0..0,
+        };
+        // FIXME: `subgrid` is treated as `none`.
+        let list = match *template {
+            GridTemplateComponent::TrackList(ref list) => list,
+            _ => return tracks,
+        };
+
+        tracks.add_line_names(&list.line_names[0]);
+        for (index, value) in list.values.iter().enumerate() {
+            match *value {
+                TrackListValue::TrackSize(ref size) => tracks.add_track(size),
+                TrackListValue::TrackRepeat(ref repeat) => {
+                    let count = match repeat.count {
+                        RepeatCount::Number(count) => max(count, 1) as usize,
+                        RepeatCount::AutoFill | RepeatCount::AutoFit => repetitions,
+                    };
+                    let start = tracks.sizes.len();
+                    for _ in 0..count {
+                        if tracks.sizes.len() >= MAX_GRID_LINE as usize {
+                            break;
+                        }
+                        for (size, names) in repeat.track_sizes.iter().zip(repeat.line_names.iter())
+                        {
+                            tracks.add_line_names(names);
+                            tracks.add_track(size);
+                        }
+                        if let Some(names) = repeat.line_names.get(repeat.track_sizes.len()) {
+                            tracks.add_line_names(names);
+                        }
+                    }
+                    if repeat.count == RepeatCount::AutoFit {
+                        tracks.auto_fit = start..tracks.sizes.len();
+                    }
+                },
+            }
+            tracks.add_line_names(&list.line_names[index + 1]);
+        }
+        tracks
+    }
+
+    fn add_track(&mut self, size: &TrackSize) {
+        self.sizes.push(size.clone());
+        self.line_names.push(vec![]);
+    }
+
+    fn add_line_names(&mut self, names: &[CustomIdent]) {
+        let last = self.line_names.last_mut().unwrap();
+        last.extend(names.iter().map(|name| name.0.clone()));
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Side {
+    Start,
+    End,
+}
+
+/// The placement of an item in one axis of the grid.
+#[derive(Clone, Copy, Debug)]
+enum AxisPlacement {
+    /// The item spans the lines in this range, where line zero is the first line of the
+    /// explicit grid.
+    Definite(i32, i32),
+    /// The item is auto-placed and spans this number of tracks.
+    Auto(i32),
+}
+
+/// The explicit grid in one axis.
+struct GridAxis {
+    tracks: ExplicitTracks,
+    /// The number of explicit tracks, which `grid-template-areas` may make larger than the
+    /// number of tracks in the template.
+    explicit_count: usize,
+    /// The explicit lines that have each name, in order.
+    line_names: FnvHashMap<Atom, Vec<i32>>,
+    /// The sizes of the implicit tracks, from `grid-auto-rows` or `grid-auto-columns`.
+    implicit_sizes: Vec<TrackSize>,
+}
+
+impl GridAxis {
+    fn new(style: &ComputedValues, direction: Direction, repetitions: usize) -> GridAxis {
+        let position = style.get_position();
+        let (template, implicit_sizes) = match direction {
+            Direction::Inline => (&position.grid_template_columns, &position.grid_auto_columns),
+            Direction::Block => (&position.grid_template_rows, &position.grid_auto_rows),
+        };
+        let tracks = ExplicitTracks::new(template, repetitions);
+
+        let mut line_names = FnvHashMap::default();
+        for (line, names) in tracks.line_names.iter().enumerate() {
+            for name in names {
+                line_names
+                    .entry(name.clone())
+                    .or_insert(vec![])
+                    .push(line as i32);
+            }
+        }
+
+        // Each named area defines implicitly named lines at its edges.
+        // https://drafts.csswg.org/css-grid/#implicit-named-lines
+        let mut explicit_count = tracks.sizes.len();
+        if let GridTemplateAreas::Areas(ref areas) = position.grid_template_areas {
+            let area_count = match direction {
+                Direction::Inline => areas.0.width as usize,
+                Direction::Block => areas.0.strings.len(),
+            };
+            explicit_count = max(explicit_count, area_count);
+            for area in areas.0.areas.iter() {
+                let range = match direction {
+                    Direction::Inline => &area.columns,
+                    Direction::Block => &area.rows,
+                };
+                for &(suffix, line) in &[("-start", range.start), ("-end", range.end)] {
+                    line_names
+                        .entry(Atom::from(format!("{}{}", area.name, suffix)))
+                        .or_insert(vec![])
+                        .push(line as i32 - 1);
+                }
+            }
+        }
+        for lines in line_names.values_mut() {
+            lines.sort();
+            lines.dedup();
+        }
+
+        let implicit_sizes = if implicit_sizes.0.is_empty() {
+            vec![TrackSize::default()]
+        } else {
+            implicit_sizes.0.iter().cloned().collect()
+        };
+
+        GridAxis {
+            tracks,
+            explicit_count,
+            line_names,
+            implicit_sizes,
+        }
+    }
+
+    /// Returns the size of a track, where track zero is the first track of the explicit grid.
+    fn track_size(&self, index: i32) -> &TrackSize {
+        let template_count = self.tracks.sizes.len() as i32;
+        if index >= 0 && index < template_count {
+            return &self.tracks.sizes[index as usize];
+        }
+        // The implicit tracks repeat the pattern of `grid-auto-*` forwards after the template,
+        // and backwards before it.
+        let count = self.implicit_sizes.len() as i32;
+        let implicit_index = if index < 0 {
+            count - 1 - (-index - 1) % count
+        } else {
+            (index - template_count) % count
+        };
+        &self.implicit_sizes[implicit_index as usize]
+    }
+
+    /// Returns the `nth` line named `name`, counting from the end when `nth` is negative. All
+    /// the implicit lines are assumed to have that name if there aren't enough such lines.
+    fn named_line(&self, name: &Atom, nth: i32) -> i32 {
+        let lines = self
+            .line_names
+            .get(name)
+            .map_or(&[][..], |lines| &lines[..]);
+        let count = lines.len() as i32;
+        if nth > 0 {
+            if nth <= count {
+                lines[nth as usize - 1]
+            } else {
+                self.explicit_count as i32 + nth - count
+            }
+        } else if -nth <= count {
+            lines[(count + nth) as usize]
+        } else {
+            nth + count
+        }
+    }
+
+    /// Resolves a definite line of a placement property, or returns `None` for `auto` and spans.
+    /// https://drafts.csswg.org/css-grid/#line-placement
+    fn resolve_line(&self, line: &GridLine, side: Side) -> Option<i32> {
+        if line.is_span || line.is_auto() {
+            return None;
+        }
+        if line.ident.is_empty() {
+            return Some(if line.line_num > 0 {
+                line.line_num - 1
+            } else {
+                self.explicit_count as i32 + 1 + line.line_num
+            });
+        }
+        if line.line_num != 0 {
+            return Some(self.named_line(&line.ident, line.line_num));
+        }
+
+        // A lone name refers to the edge of the named area, or to the first line of that name.
+        let suffix = if side == Side::Start {
+            "-start"
+        } else {
+            "-end"
+        };
+        let area_line = Atom::from(format!("{}{}", line.ident, suffix));
+        if let Some(lines) = self.line_names.get(&area_line) {
+            return Some(lines[0]);
+        }
+        Some(self.named_line(&line.ident, 1))
+    }
+
+    /// Resolves the placement of an item from its start and end lines.
+    ///
+    /// FIXME: Spans to named lines are treated as spans of that many tracks.
+    fn resolve_placement(&self, start: &GridLine, end: &GridLine) -> AxisPlacement {
+        let span = |line: &GridLine| {
+            if line.is_span {
+                max(line.line_num, 1)
+            } else {
+                1
+            }
+        };
+        match (
+            self.resolve_line(start, Side::Start),
+            self.resolve_line(end, Side::End),
+        ) {
+            (Some(start), Some(end)) if start == end => AxisPlacement::Definite(start, start + 1),
+            (Some(start), Some(end)) => AxisPlacement::Definite(min(start, end), max(start, end)),
+            (Some(start), None) => AxisPlacement::Definite(start, start + span(end)),
+            (None, Some(end)) => AxisPlacement::Definite(end - span(start), end),
+            // When both lines are spans, the end line behaves as `auto`.
+            (None, None) if start.is_span => AxisPlacement::Auto(span(start)),
+            (None, None) => AxisPlacement::Auto(span(end)),
+        }
+    }
+}
+
+/// The cells of the grid that are occupied by items, indexed by the track in the major axis of
+/// the auto-placement and then by the track in its minor axis, and the area of each item once
+/// it's placed.
+struct Occupancy {
+    cells: Vec<Vec<bool>>,
+    areas: Vec<Option<(Range<i32>, Range<i32>)>>,
+}
+
+impl Occupancy {
+    fn is_free(&self, major: Range<i32>, minor: Range<i32>) -> bool {
+        major
+            .filter_map(|major| self.cells.get(major as usize))
+            .all(|cells| {
+                minor
+                    .clone()
+                    .all(|minor| !cells.get(minor as usize).cloned().unwrap_or(false))
+            })
+    }
+
+    fn place(&mut self, index: usize, major: Range<i32>, minor: Range<i32>) {
+        if self.cells.len() < major.end as usize {
+            self.cells.resize(major.end as usize, vec![]);
+        }
+        for cells in &mut self.cells[major.start as usize..major.end as usize] {
+            if cells.len() < minor.end as usize {
+                cells.resize(minor.end as usize, false);
+            }
+            for cell in &mut cells[minor.start as usize..minor.end as usize] {
+                *cell = true;
+            }
+        }
+        self.areas[index] = Some((major, minor));
+    }
+}
+
+/// The tracks spanned by each item in the major and minor axes of the auto-placement, indexed
+/// from the start of the implicit grid, together with the number of tracks and the index of the
+/// first explicit track in each axis.
+struct Placement {
+    major: Vec<Range<usize>>,
+    minor: Vec<Range<usize>>,
+    major_count: usize,
+    minor_count: usize,
+    major_offset: usize,
+    minor_offset: usize,
+}
+
+/// Places items in the grid, given their placement in the major and minor axes of the
+/// auto-placement and the number of explicit tracks in those axes.
+/// https://drafts.csswg.org/css-grid/#auto-placement-algo
+fn place_items(
+    major: &[AxisPlacement],
+    minor: &[AxisPlacement],
+    explicit_major: usize,
+    explicit_minor: usize,
+    dense: bool,
+) -> Placement {
+    // Shift the lines so that no definite line is before the start of the implicit grid.
+    let offset = |placements: &[AxisPlacement]| {
+        -placements
+            .iter()
+            .filter_map(|placement| match *placement {
+                AxisPlacement::Definite(start, _) => Some(min(start, 0)),
+                AxisPlacement::Auto(_) => None,
+            })
+            .min()
+            .unwrap_or(0)
+    };
+    let shift = |placement: AxisPlacement, offset: i32| match placement {
+        AxisPlacement::Definite(start, end) => {
+            AxisPlacement::Definite(start + offset, end + offset)
+        },
+        auto => auto,
+    };
+    let (major_offset, minor_offset) = (offset(major), offset(minor));
+    let major: Vec<_> = major.iter().map(|&p| shift(p, major_offset)).collect();
+    let minor: Vec<_> = minor.iter().map(|&p| shift(p, minor_offset)).collect();
+
+    let mut occupancy = Occupancy {
+        cells: vec![],
+        areas: vec![None; major.len()],
+    };
+
+    // Place the items that are locked to tracks in the major axis. Those placed in both axes
+    // have already been positioned by the author.
+    let mut major_cursors = FnvHashMap::default();
+    for index in 0..major.len() {
+        if let (AxisPlacement::Definite(major_start, major_end), placement) =
+            (major[index], minor[index])
+        {
+            if let AxisPlacement::Definite(minor_start, minor_end) = placement {
+                occupancy.place(index, major_start..major_end, minor_start..minor_end);
+            }
+        }
+    }
+    for index in 0..major.len() {
+        if let (AxisPlacement::Definite(major_start, major_end), AxisPlacement::Auto(span)) =
+            (major[index], minor[index])
+        {
+            let mut cursor = if dense {
+                0
+            } else {
+                major_cursors.get(&major_start).cloned().unwrap_or(0)
+            };
+            while !occupancy.is_free(major_start..major_end, cursor..cursor + span) {
+                cursor += 1;
+            }
+            occupancy.place(index, major_start..major_end, cursor..cursor + span);
+            major_cursors.insert(major_start, cursor + span);
+        }
+    }
+
+    // The implicit grid must be wide enough in the minor axis for all the items.
+    let mut minor_count = explicit_minor as i32 + minor_offset;
+    for (area, placement) in occupancy.areas.iter().zip(minor.iter()) {
+        minor_count = max(
+            minor_count,
+            match (area, *placement) {
+                (&Some((_, ref minor)), _) => minor.end,
+                (&None, AxisPlacement::Definite(_, end)) => end,
+                (&None, AxisPlacement::Auto(span)) => span,
+            },
+        );
+    }
+
+    // Place the remaining items with a cursor that advances in the minor axis first.
+    let (mut cursor_major, mut cursor_minor) = (0, 0);
+    for index in 0..major.len() {
+        if occupancy.areas[index].is_some() {
+            continue;
+        }
+        let major_span = match major[index] {
+            AxisPlacement::Auto(span) => span,
+            AxisPlacement::Definite(start, end) => end - start,
+        };
+        if dense {
+            cursor_major = 0;
+            cursor_minor = 0;
+        }
+        match minor[index] {
+            AxisPlacement::Definite(start, end) => {
+                if start < cursor_minor && !dense {
+                    cursor_major += 1;
+                }
+                cursor_minor = start;
+                while !occupancy.is_free(cursor_major..cursor_major + major_span, start..end) {
+                    cursor_major += 1;
+                }
+                occupancy.place(index, cursor_major..cursor_major + major_span, start..end);
+            },
+            AxisPlacement::Auto(span) => {
+                loop {
+                    if cursor_minor + span > minor_count {
+                        cursor_major += 1;
+                        cursor_minor = 0;
+                    } else if occupancy.is_free(
+                        cursor_major..cursor_major + major_span,
+                        cursor_minor..cursor_minor + span,
+                    ) {
+                        break;
+                    } else {
+                        cursor_minor += 1;
+                    }
+                }
+                occupancy.place(
+                    index,
+                    cursor_major..cursor_major + major_span,
+                    cursor_minor..cursor_minor + span,
+                );
+                cursor_minor += span;
+            },
+        }
+    }
+
+    let mut placement = Placement {
+        major: vec![],
+        minor: vec![],
+        major_count: explicit_major + major_offset as usize,
+        minor_count: minor_count as usize,
+        major_offset: major_offset as usize,
+        minor_offset: minor_offset as usize,
+    };
+    for area in occupancy.areas {
+        let (major, minor) = area.unwrap();
+        placement.major_count = max(placement.major_count, major.end as usize);
+        placement
+            .major
+            .push(major.start as usize..major.end as usize);
+        placement
+            .minor
+            .push(minor.start as usize..minor.end as usize);
+    }
+    placement
+}
+
+/// The space available to size the tracks of one axis of the grid in.
+#[derive(Clone, Copy, PartialEq)]
+enum AvailableSpace {
+    Definite(Au),
+    MinContent,
+    MaxContent,
+}
+
+/// The contribution of an item to the size of the tracks it spans in one axis, margins
+/// included.
+struct Contribution {
+    tracks: Range<usize>,
+    min_content: Au,
+    max_content: Au,
+}
+
+/// Returns the total size of the gaps between the tracks that aren't collapsed.
+fn gutters(collapsed: &[bool], gap: Au) -> Au {
+    let count = collapsed.iter().filter(|collapsed| !**collapsed).count() as i32;
+    gap * max(count - 1, 0)
+}
+
+/// Grows the tracks in `range` that `eligible` accepts equally, so that their sizes plus the
+/// gaps between them add up to at least `size`.
+fn distribute_extra_space(
+    sizes: &mut [Au],
+    range: Range<usize>,
+    size: Au,
+    gap: Au,
+    eligible: &dyn Fn(usize) -> bool,
+) {
+    let current =
+        range.clone().fold(Au(0), |sum, index| sum + sizes[index]) + gap * (range.len() as i32 - 1);
+    let tracks: Vec<usize> = range.filter(|&index| eligible(index)).collect();
+    if size <= current || tracks.is_empty() {
+        return;
+    }
+    let share = (size - current) / tracks.len() as i32;
+    for index in tracks {
+        sizes[index] += share;
+    }
+}
+
+/// Returns the used size of the flexible length `1fr` for the tracks in `range`, given the
+/// space to fill.
+/// https://drafts.csswg.org/css-grid/#algo-find-fr-size
+fn fr_size(tracks: &[TrackSizing], base_sizes: &[Au], range: Range<usize>, space: Au) -> f32 {
+    let mut inflexible: Vec<bool> = tracks.iter().map(|t| t.flex_factor().is_none()).collect();
+    loop {
+        let mut leftover = space;
+        let mut flex_sum = 0.;
+        for index in range.clone() {
+            match tracks[index].flex_factor() {
+                Some(factor) if !inflexible[index] => flex_sum += factor,
+                _ => leftover -= base_sizes[index],
+            }
+        }
+        let hypothetical = leftover.to_f32_px().max(0.) / flex_sum.max(1.);
+
+        // Flexible tracks that would shrink below their base size are treated as inflexible.
+        let mut done = true;
+        for index in range.clone() {
+            if let Some(factor) = tracks[index].flex_factor() {
+                if !inflexible[index] && base_sizes[index].to_f32_px() > hypothetical * factor {
+                    inflexible[index] = true;
+                    done = false;
+                }
+            }
+        }
+        if done {
+            return hypothetical;
+        }
+    }
+}
+
+/// Sizes the tracks of one axis of the grid. Collapsed tracks are sized to zero, and auto
+/// tracks are stretched to fill the available space if `stretch` is true.
+/// https://drafts.csswg.org/css-grid/#algo-track-sizing
+fn size_tracks(
+    tracks: &[TrackSizing],
+    collapsed: &[bool],
+    contributions: &[Contribution],
+    available: AvailableSpace,
+    gap: Au,
+    stretch: bool,
+) -> Vec<Au> {
+    // Initialize each track's base size and growth limit.
+    let mut base_sizes: Vec<Au> = tracks
+        .iter()
+        .map(|track| match track.min {
+            MinSizing::Fixed(size) => size,
+            _ => Au(0),
+        })
+        .collect();
+    let mut limits: Vec<Option<Au>> = tracks
+        .iter()
+        .zip(base_sizes.iter())
+        .map(|(track, base_size)| match track.max {
+            MaxSizing::Fixed(size) => Some(max(size, *base_size)),
+            _ => None,
+        })
+        .collect();
+    let is_collapsed = |index: usize| collapsed[index];
+    for index in (0..tracks.len()).filter(|&index| is_collapsed(index)) {
+        base_sizes[index] = Au(0);
+        limits[index] = Some(Au(0));
+    }
+
+    // Resolve the intrinsic track sizes from the items that don't span flexible tracks, those
+    // spanning fewer tracks first.
+    let mut contributions: Vec<&Contribution> = contributions.iter().collect();
+    contributions.sort_by_key(|contribution| contribution.tracks.len());
+    let spans_flexible_track = |contribution: &Contribution| {
+        contribution
+            .tracks
+            .clone()
+            .any(|index| tracks[index].flex_factor().is_some())
+    };
+    for contribution in contributions.iter() {
+        let range = contribution.tracks.clone();
+        let min_content = contribution.min_content;
+        let max_content = match available {
+            AvailableSpace::MinContent => contribution.min_content,
+            _ => contribution.max_content,
+        };
+        if spans_flexible_track(contribution) {
+            // Flexible tracks have an automatic minimum, so they must fit the item's
+            // minimum contribution.
+            distribute_extra_space(&mut base_sizes, range, min_content, gap, &|index| {
+                !is_collapsed(index) && tracks[index].flex_factor().is_some()
+            });
+            continue;
+        }
+
+        distribute_extra_space(&mut base_sizes, range.clone(), min_content, gap, &|index| {
+            !is_collapsed(index) &&
+                (tracks[index].min == MinSizing::MinContent ||
+                    tracks[index].min == MinSizing::Auto)
+        });
+        distribute_extra_space(&mut base_sizes, range.clone(), max_content, gap, &|index| {
+            !is_collapsed(index) && tracks[index].min == MinSizing::MaxContent
+        });
+
+        let mut limit_sizes: Vec<Au> = limits
+            .iter()
+            .zip(base_sizes.iter())
+            .map(|(limit, base_size)| limit.unwrap_or(*base_size))
+            .collect();
+        distribute_extra_space(
+            &mut limit_sizes,
+            range.clone(),
+            min_content,
+            gap,
+            &|index| !is_collapsed(index) && tracks[index].max == MaxSizing::MinContent,
+        );
+        distribute_extra_space(
+            &mut limit_sizes,
+            range.clone(),
+            max_content,
+            gap,
+            &|index| {
+                !is_collapsed(index) &&
+                    match tracks[index].max {
+                        MaxSizing::MaxContent | MaxSizing::Auto | MaxSizing::FitContent(_) => true,
+                        _ => false,
+                    }
+            },
+        );
+        for index in range {
+            if tracks[index].max != MaxSizing::MinContent || limits[index].is_some() {
+                if limit_sizes[index] != limits[index].unwrap_or(base_sizes[index]) {
+                    limits[index] = Some(limit_sizes[index]);
+                }
+            } else {
+                limits[index] = Some(limit_sizes[index]);
+            }
+        }
+    }
+
+    // Tracks that no item contributed to are limited to their base size, and growth limits are
+    // never smaller than base sizes.
+    let limits: Vec<Au> = limits
+        .iter()
+        .enumerate()
+        .map(|(index, limit)| {
+            let base_size = base_sizes[index];
+            let limit = max(limit.unwrap_or(base_size), base_size);
+            match tracks[index].max {
+                MaxSizing::FitContent(fit) => max(min(limit, fit), base_size),
+                _ => limit,
+            }
+        })
+        .collect();
+    let gutters = gutters(collapsed, gap);
+
+    // Grow the inflexible tracks up to their growth limits.
+    // https://drafts.csswg.org/css-grid/#algo-grow-tracks
+    match available {
+        AvailableSpace::Definite(size) => {
+            let mut free_space =
+                size - gutters - base_sizes.iter().fold(Au(0), |sum, size| sum + *size);
+            loop {
+                let growable: Vec<usize> = (0..tracks.len())
+                    .filter(|&index| {
+                        tracks[index].flex_factor().is_none() && base_sizes[index] < limits[index]
+                    })
+                    .collect();
+                if growable.is_empty() || free_space <= Au(0) {
+                    break;
+                }
+                let share = max(free_space / growable.len() as i32, Au(1));
+                for index in growable {
+                    let growth = min(min(share, limits[index] - base_sizes[index]), free_space);
+                    base_sizes[index] += growth;
+                    free_space -= growth;
+                }
+            }
+        },
+        AvailableSpace::MaxContent => {
+            for index in 0..tracks.len() {
+                if tracks[index].flex_factor().is_none() {
+                    base_sizes[index] = limits[index];
+                }
+            }
+        },
+        AvailableSpace::MinContent => {},
+    }
+
+    // Expand the flexible tracks.
+    // https://drafts.csswg.org/css-grid/#algo-flex-tracks
+    if tracks.iter().any(|track| track.flex_factor().is_some()) {
+        let fr = match available {
+            AvailableSpace::Definite(size) => {
+                fr_size(tracks, &base_sizes, 0..tracks.len(), size - gutters)
+            },
+            AvailableSpace::MaxContent => {
+                let mut fr: f32 = 0.;
+                for (track, base_size) in tracks.iter().zip(base_sizes.iter()) {
+                    if let Some(factor) = track.flex_factor() {
+                        fr = fr.max(base_size.to_f32_px() / factor.max(1.));
+                    }
+                }
+                for contribution in contributions.iter().filter(|c| spans_flexible_track(c)) {
+                    let range = contribution.tracks.clone();
+                    let space = contribution.max_content - gap * (range.len() as i32 - 1);
+                    fr = fr.max(fr_size(tracks, &base_sizes, range, space));
+                }
+                fr
+            },
+            AvailableSpace::MinContent => 0.,
+        };
+        for (track, base_size) in tracks.iter().zip(base_sizes.iter_mut()) {
+            if let Some(factor) = track.flex_factor() {
+                *base_size = max(*base_size, Au::from_f32_px(fr * factor));
+            }
+        }
+    }
+
+    // Stretch the `auto` tracks to fill the rest of the available space.
+    // https://drafts.csswg.org/css-grid/#algo-stretch
+    if let AvailableSpace::Definite(size) = available {
+        let free_space = size - gutters - base_sizes.iter().fold(Au(0), |sum, size| sum + *size);
+        let auto_tracks: Vec<usize> = (0..tracks.len())
+            .filter(|&index| !is_collapsed(index) && tracks[index].max == MaxSizing::Auto)
+            .collect();
+        if stretch && free_space > Au(0) && !auto_tracks.is_empty() {
+            let share = free_space / auto_tracks.len() as i32;
+            for index in auto_tracks {
+                base_sizes[index] += share;
+            }
+        }
+    }
+    base_sizes
+}
+
+/// The distribution of the free space of a grid between its tracks.
+/// https://drafts.csswg.org/css-align/#content-distribution
+#[derive(Clone, Copy, PartialEq)]
+enum ContentDistribution {
+    Start,
+    End,
+    Center,
+    SpaceBetween,
+    SpaceAround,
+    Stretch,
+}
+
+/// The alignment of an item in its grid area.
+/// https://drafts.csswg.org/css-align/#self-alignment
+#[derive(Clone, Copy, PartialEq)]
+enum SelfAlignment {
+    Start,
+    End,
+    Center,
+    Stretch,
+}
+
+/// A track of the grid.
+#[derive(Clone, Copy, Debug, Serialize)]
+struct Track {
+    /// The offset of the track from the content edge of the grid.
+    position: Au,
+    size: Au,
+    /// Whether this is an empty `auto-fit` track, which is sized to zero along with its gaps.
+    collapsed: bool,
+}
+
+/// Positions the tracks of one axis of the grid, given their sizes, distributing the free space
+/// of the grid between them.
+fn position_tracks(
+    tracks: &mut [Track],
+    sizes: &[Au],
+    gap: Au,
+    size: Au,
+    distribution: ContentDistribution,
+) {
+    let collapsed: Vec<bool> = tracks.iter().map(|track| track.collapsed).collect();
+    let count = collapsed.iter().filter(|collapsed| !**collapsed).count() as i32;
+    let free_space =
+        size - gutters(&collapsed, gap) - sizes.iter().fold(Au(0), |sum, size| sum + *size);
+    let (offset, spacing) = match distribution {
+        ContentDistribution::Start | ContentDistribution::Stretch => (Au(0), Au(0)),
+        ContentDistribution::End => (free_space, Au(0)),
+        ContentDistribution::SpaceBetween if free_space > Au(0) && count > 1 => {
+            (Au(0), free_space / (count - 1))
+        },
+        ContentDistribution::SpaceBetween => (Au(0), Au(0)),
+        ContentDistribution::SpaceAround if free_space > Au(0) && count > 0 => {
+            (free_space / (count * 2), free_space / count)
+        },
+        ContentDistribution::Center | ContentDistribution::SpaceAround => (free_space / 2, Au(0)),
+    };
+
+    let mut position = offset;
+    for (track, size) in tracks.iter_mut().zip(sizes.iter()) {
+        track.position = position;
+        track.size = *size;
+        if !track.collapsed {
+            position += *size + gap + spacing;
+        }
+    }
+}
+
+/// Returns the offset from the content edge of the grid and the size of the area covered by a
+/// range of tracks.
+fn area(tracks: &[Track], range: Range<usize>) -> (Au, Au) {
+    let start = tracks[range.start].position;
+    let last = &tracks[range.end - 1];
+    (start, last.position + last.size - start)
+}
+
+/// Resolves the auto margins of an item in one axis of its grid area, given the space its
+/// margin box leaves free in the area, and returns the offset of its margin box in the area.
+/// https://drafts.csswg.org/css-grid/#auto-margins
+fn align_in_area(
+    free_space: Au,
+    margin_start: &mut Au,
+    margin_end: &mut Au,
+    auto_margins: (bool, bool),
+    alignment: SelfAlignment,
+) -> Au {
+    let positive_free_space = max(free_space, Au(0));
+    match auto_margins {
+        (true, true) => {
+            *margin_start += positive_free_space / 2;
+            *margin_end += positive_free_space - positive_free_space / 2;
+            Au(0)
+        },
+        (true, false) => {
+            *margin_start += positive_free_space;
+            Au(0)
+        },
+        (false, true) => {
+            *margin_end += positive_free_space;
+            Au(0)
+        },
+        (false, false) => match alignment {
+            SelfAlignment::Start | SelfAlignment::Stretch => Au(0),
+            SelfAlignment::End => free_space,
+            SelfAlignment::Center => free_space / 2,
+        },
+    }
+}
+
+/// A child of the grid, with the tracks of the area it's placed in.
+#[derive(Debug, Serialize)]
+struct GridItem {
+    /// The index of the item among the children of the grid.
+    index: usize,
+    /// The columns spanned by the item.
+    columns: Range<usize>,
+    /// The rows spanned by the item.
+    rows: Range<usize>,
+}
+
+/// The items of a grid placed in its implicit grid.
+#[derive(Debug, Default, Serialize)]
+struct Grid {
+    /// The in-flow children of the grid, in order-modified document order.
+    items: Vec<GridItem>,
+    columns: Vec<Track>,
+    rows: Vec<Track>,
+    /// The index of the first explicit column in `columns`.
+    explicit_column_start: usize,
+    /// The index of the first explicit row in `rows`.
+    explicit_row_start: usize,
+    /// The number of times the `auto-fill` or `auto-fit` repetition of
+    /// `grid-template-columns` repeats.
+    column_repetitions: usize,
+    /// The number of times the `auto-fill` or `auto-fit` repetition of
+    /// `grid-template-rows` repeats.
+    row_repetitions: usize,
+}
+
+#[allow(unsafe_code)]
+unsafe impl crate::flow::HasBaseFlow for GridFlow {}
+
+/// A block with the CSS `display` property equal to `grid`.
+#[derive(Debug, Serialize)]
+#[repr(C)]
+pub struct GridFlow {
+    /// Data common to all block flows.
+    block_flow: BlockFlow,
+    /// The grid as placed and sized by the last layout.
+    grid: Grid,
+}
+
+impl GridFlow {
+    pub fn from_fragment(fragment: Fragment, flotation: Option<FloatKind>) -> GridFlow {
+        GridFlow {
+            block_flow: BlockFlow::from_fragment_and_float_kind(fragment, flotation),
+            grid: Grid::default(),
+        }
+    }
+
+    /// Places the in-flow children of the grid, given the size of its content box, which
+    /// determines how many times `auto-fill` repetitions repeat.
+    fn place_items(&self, inline_size: Option<Au>, block_size: Option<Au>) -> Grid {
+        let style = &*self.block_flow.fragment.style;
+        let position = style.get_position();
+        let column_repetitions = auto_repetitions(
+            &position.grid_template_columns,
+            inline_size,
+            used_gap(&position.column_gap, inline_size),
+        );
+        let row_repetitions = auto_repetitions(
+            &position.grid_template_rows,
+            block_size,
+            used_gap(&position.row_gap, block_size),
+        );
+        let columns = GridAxis::new(style, Direction::Inline, column_repetitions);
+        let rows = GridAxis::new(style, Direction::Block, row_repetitions);
+
+        // FIXME: This should be done during flow construction, as for flex items.
+        let mut children: Vec<(usize, i32, AxisPlacement, AxisPlacement)> = self
+            .block_flow
+            .base
+            .children
+            .iter()
+            .enumerate()
+            .filter(|&(_, flow)| {
+                !flow
+                    .base()
+                    .flags
+                    .contains(FlowFlags::IS_ABSOLUTELY_POSITIONED)
+            })
+            .map(|(index, flow)| {
+                let position = flow.as_block().fragment.style.get_position();
+                (
+                    index,
+                    position.order,
+                    rows.resolve_placement(&position.grid_row_start, &position.grid_row_end),
+                    columns
+                        .resolve_placement(&position.grid_column_start, &position.grid_column_end),
+                )
+            })
+            .collect();
+        children.sort_by_key(|&(_, order, _, _)| order);
+
+        let GridAutoFlow { autoflow, dense } = position.grid_auto_flow;
+        let row_placements: Vec<_> = children.iter().map(|child| child.2).collect();
+        let column_placements: Vec<_> = children.iter().map(|child| child.3).collect();
+        let placement;
+        let (item_rows, item_columns, row_count, column_count, row_offset, column_offset) =
+            match autoflow {
+                AutoFlow::Row => {
+                    placement = place_items(
+                        &row_placements,
+                        &column_placements,
+                        rows.explicit_count,
+                        columns.explicit_count,
+                        dense,
+                    );
+                    (
+                        &placement.major,
+                        &placement.minor,
+                        placement.major_count,
+                        placement.minor_count,
+                        placement.major_offset,
+                        placement.minor_offset,
+                    )
+                },
+                AutoFlow::Column => {
+                    placement = place_items(
+                        &column_placements,
+                        &row_placements,
+                        columns.explicit_count,
+                        rows.explicit_count,
+                        dense,
+                    );
+                    (
+                        &placement.minor,
+                        &placement.major,
+                        placement.minor_count,
+                        placement.major_count,
+                        placement.minor_offset,
+                        placement.major_offset,
+                    )
+                },
+            };
+
+        let items: Vec<GridItem> = children
+            .iter()
+            .enumerate()
+            .map(|(index, child)| GridItem {
+                index: child.0,
+                columns: item_columns[index].clone(),
+                rows: item_rows[index].clone(),
+            })
+            .collect();
+
+        // Empty `auto-fit` tracks collapse.
+        let tracks = |count: usize, offset: usize, auto_fit: &Range<usize>, is_row: bool| {
+            (0..count)
+                .map(|index| {
+                    let fits = index >= offset && auto_fit.contains(&(index - offset));
+                    let is_empty = || {
+                        items.iter().all(|item| {
+                            !(if is_row { &item.rows } else { &item.columns }).contains(&index)
+                        })
+                    };
+                    Track {
+                        position: Au(0),
+                        size: Au(0),
+                        collapsed: fits && is_empty(),
+                    }
+                })
+                .collect()
+        };
+        let columns = tracks(column_count, column_offset, &columns.tracks.auto_fit, false);
+        let rows = tracks(row_count, row_offset, &rows.tracks.auto_fit, true);
+
+        Grid {
+            items,
+            columns,
+            rows,
+            explicit_column_start: column_offset,
+            explicit_row_start: row_offset,
+            column_repetitions,
+            row_repetitions,
+        }
+    }
+
+    /// Sizes the columns of a grid from the intrinsic inline sizes of its items.
+    fn size_columns(&self, grid: &Grid, available: AvailableSpace) -> Vec<Au> {
+        let style = &*self.block_flow.fragment.style;
+        let basis = match available {
+            AvailableSpace::Definite(size) => Some(size),
+            _ => None,
+        };
+        let axis = GridAxis::new(style, Direction::Inline, grid.column_repetitions);
+        let tracks: Vec<TrackSizing> = (0..grid.columns.len())
+            .map(|index| {
+                let index = index as i32 - grid.explicit_column_start as i32;
+                TrackSizing::new(axis.track_size(index), basis)
+            })
+            .collect();
+        let collapsed: Vec<bool> = grid.columns.iter().map(|track| track.collapsed).collect();
+
+        let kids: Vec<&dyn Flow> = self.block_flow.base.children.iter().collect();
+        let contributions: Vec<Contribution> = grid
+            .items
+            .iter()
+            .map(|item| {
+                let sizes = kids[item.index].base().intrinsic_inline_sizes;
+                Contribution {
+                    tracks: item.columns.clone(),
+                    min_content: sizes.minimum_inline_size,
+                    max_content: sizes.preferred_inline_size,
+                }
+            })
+            .collect();
+
+        // Servo doesn't support `justify-content: normal`, which stretches the `auto` tracks of
+        // grids, so its initial value does as well.
+        let stretch = match style.get_position().justify_content {
+            JustifyContent::Stretch | JustifyContent::FlexStart => true,
+            _ => false,
+        };
+        size_tracks(
+            &tracks,
+            &collapsed,
+            &contributions,
+            available,
+            used_gap(&style.get_position().column_gap, basis),
+            stretch,
+        )
+    }
+
+    /// Sizes and positions the rows of the grid from the block sizes of its items, and returns
+    /// the block size of the content box of the grid.
+    fn size_rows(&mut self, content_block_size: Option<Au>, size_constraint: SizeConstraint) -> Au {
+        let style = self.block_flow.fragment.style.clone();
+        let position = style.get_position();
+        let axis = GridAxis::new(&style, Direction::Block, self.grid.row_repetitions);
+        let grid = &mut self.grid;
+        let tracks: Vec<TrackSizing> = (0..grid.rows.len())
+            .map(|index| {
+                let index = index as i32 - grid.explicit_row_start as i32;
+                TrackSizing::new(axis.track_size(index), content_block_size)
+            })
+            .collect();
+        let collapsed: Vec<bool> = grid.rows.iter().map(|track| track.collapsed).collect();
+
+        let kids: Vec<&dyn Flow> = self.block_flow.base.children.iter().collect();
+        let contributions: Vec<Contribution> = grid
+            .items
+            .iter()
+            .map(|item| {
+                let fragment = &kids[item.index].as_block().fragment;
+                let size = fragment.border_box.size.block + fragment.margin.block_start_end();
+                Contribution {
+                    tracks: item.rows.clone(),
+                    min_content: size,
+                    max_content: size,
+                }
+            })
+            .collect();
+
+        let gap = used_gap(&position.row_gap, content_block_size);
+        let available = match content_block_size {
+            Some(size) => AvailableSpace::Definite(size),
+            None => AvailableSpace::MaxContent,
+        };
+        let sizes = size_tracks(
+            &tracks,
+            &collapsed,
+            &contributions,
+            available,
+            gap,
+            position.align_content == AlignContent::Stretch,
+        );
+
+        let size = content_block_size.unwrap_or_else(|| {
+            let used_size = sizes.iter().fold(Au(0), |sum, size| sum + *size);
+            size_constraint.clamp(used_size + gutters(&collapsed, gap))
+        });
+        let distribution = match position.align_content {
+            AlignContent::Stretch => ContentDistribution::Stretch,
+            AlignContent::FlexStart => ContentDistribution::Start,
+            AlignContent::FlexEnd => ContentDistribution::End,
+            AlignContent::Center => ContentDistribution::Center,
+            AlignContent::SpaceBetween => ContentDistribution::SpaceBetween,
+            AlignContent::SpaceAround => ContentDistribution::SpaceAround,
+        };
+        position_tracks(&mut grid.rows, &sizes, gap, size, distribution);
+        size
+    }
+}
+
+impl Flow for GridFlow {
+    fn class(&self) -> FlowClass {
+        FlowClass::Grid
+    }
+
+    fn as_grid(&self) -> &GridFlow {
+        self
+    }
+
+    fn as_block(&self) -> &BlockFlow {
+        &self.block_flow
+    }
+
+    fn as_mut_block(&mut self) -> &mut BlockFlow {
+        &mut self.block_flow
+    }
+
+    fn mark_as_root(&mut self) {
+        self.block_flow.mark_as_root();
+    }
+
+    fn bubble_inline_sizes(&mut self) {
+        let _scope = layout_debug_scope!(
+            "grid::bubble_inline_sizes {:x}",
+            self.block_flow.base.debug_id()
+        );
+
+        // FIXME(emilio): This doesn't handle at all writing-modes.
+        let fixed_width =
+            !model::style_length(self.block_flow.fragment.style().get_position().width, None)
+                .is_auto();
+
+        let mut computation = self.block_flow.fragment.compute_intrinsic_inline_sizes();
+        if !fixed_width {
+            // Intrinsic sizes are found with a single repetition of `auto-fill` tracks and
+            // percentage gaps resolved to zero.
+            let grid = self.place_items(None, None);
+            let collapsed: Vec<bool> = grid.columns.iter().map(|track| track.collapsed).collect();
+            let gap = used_gap(
+                &self.block_flow.fragment.style.get_position().column_gap,
+                None,
+            );
+            let content_size = |available| {
+                let sizes = self.size_columns(&grid, available);
+                sizes.iter().fold(Au(0), |sum, size| sum + *size) + gutters(&collapsed, gap)
+            };
+            let sizes = &mut computation.content_intrinsic_sizes;
+            sizes.minimum_inline_size = max(
+                sizes.minimum_inline_size,
+                content_size(AvailableSpace::MinContent),
+            );
+            sizes.preferred_inline_size = max(
+                sizes.preferred_inline_size,
+                content_size(AvailableSpace::MaxContent),
+            );
+        }
+        self.block_flow.base.intrinsic_inline_sizes = computation.finish();
+    }
+
+    fn assign_inline_sizes(&mut self, layout_context: &LayoutContext) {
+        let _scope = layout_debug_scope!(
+            "grid::assign_inline_sizes {:x}",
+            self.block_flow.base.debug_id()
+        );
+        debug!("assign_inline_sizes");
+
+        if !self
+            .block_flow
+            .base
+            .restyle_damage
+            .intersects(ServoRestyleDamage::REFLOW_OUT_OF_FLOW | ServoRestyleDamage::REFLOW)
+        {
+            return;
+        }
+
+        self.block_flow
+            .initialize_container_size_for_root(layout_context.shared_context());
+
+        // Our inline-size was set to the inline-size of the containing block by the flow's parent.
+        // Now compute the real value.
+        let containing_block_inline_size = self.block_flow.base.block_container_inline_size;
+        self.block_flow.compute_used_inline_size(
+            layout_context.shared_context(),
+            containing_block_inline_size,
+        );
+        if self.block_flow.base.flags.is_float() {
+            self.block_flow
+                .float
+                .as_mut()
+                .unwrap()
+                .containing_inline_size = containing_block_inline_size
+        }
+
+        // Move in from the inline-start border edge.
+        let inline_start_content_edge = self.block_flow.fragment.border_box.start.i +
+            self.block_flow.fragment.border_padding.inline_start;
+        let padding_and_borders = self.block_flow.fragment.border_padding.inline_start_end();
+        let content_inline_size =
+            self.block_flow.fragment.border_box.size.inline - padding_and_borders;
+
+        // https://drafts.csswg.org/css-ui-3/#box-sizing
+        let box_border = self
+            .block_flow
+            .fragment
+            .box_sizing_boundary(Direction::Block);
+        let parent_container_size = self
+            .block_flow
+            .explicit_block_containing_size(layout_context.shared_context());
+        let explicit_content_size = self
+            .block_flow
+            .explicit_block_size(parent_container_size)
+            .map(|x| max(x - box_border, Au(0)));
+
+        let mut grid = self.place_items(Some(content_inline_size), explicit_content_size);
+        let sizes = self.size_columns(&grid, AvailableSpace::Definite(content_inline_size));
+        let style = self.block_flow.fragment.style.clone();
+        let distribution = match style.get_position().justify_content {
+            JustifyContent::FlexStart => ContentDistribution::Start,
+            JustifyContent::Stretch => ContentDistribution::Stretch,
+            JustifyContent::FlexEnd => ContentDistribution::End,
+            JustifyContent::Center => ContentDistribution::Center,
+            JustifyContent::SpaceBetween => ContentDistribution::SpaceBetween,
+            JustifyContent::SpaceAround => ContentDistribution::SpaceAround,
+        };
+        position_tracks(
+            &mut grid.columns,
+            &sizes,
+            used_gap(&style.get_position().column_gap, Some(content_inline_size)),
+            content_inline_size,
+            distribution,
+        );
+
+        let writing_mode = self.block_flow.base.writing_mode;
+        let containing_block_text_align = style.get_inherited_text().text_align;
+        let justify_items = style.get_position().justify_items;
+        let mut children = self.block_flow.base.children.random_access_mut();
+        for item in &grid.items {
+            let (area_start, area_size) = area(&grid.columns, item.columns.clone());
+            let block = children.get(item.index).as_mut_block();
+
+            block.base.block_container_writing_mode = writing_mode;
+            block.base.block_container_inline_size = area_size;
+            block.base.block_container_explicit_block_size = None;
+            // Per CSS 2.1 § 16.3.1, text alignment propagates to all children in flow.
+            //
+            // TODO(#2265, pcwalton): Do this in the cascade instead.
+            block.base.flags.set_text_align(containing_block_text_align);
+
+            // These methods compute auto margins to zero length, which are resolved below.
+            block.fragment.compute_border_and_padding(area_size);
+            block.fragment.compute_inline_direction_margins(area_size);
+            block.fragment.compute_block_direction_margins(area_size);
+
+            let alignment = match block.fragment.style().get_position().justify_self {
+                JustifySelf::Auto => match justify_items {
+                    JustifyItems::Stretch => SelfAlignment::Stretch,
+                    JustifyItems::Start => SelfAlignment::Start,
+                    JustifyItems::End => SelfAlignment::End,
+                    JustifyItems::Center => SelfAlignment::Center,
+                },
+                JustifySelf::Stretch => SelfAlignment::Stretch,
+                JustifySelf::Start => SelfAlignment::Start,
+                JustifySelf::End => SelfAlignment::End,
+                JustifySelf::Center => SelfAlignment::Center,
+            };
+            let margin = block.fragment.style().logical_margin();
+            let auto_margins = (margin.inline_start.is_auto(), margin.inline_end.is_auto());
+            let mut margin_inline_start = block.fragment.margin.inline_start;
+            let mut margin_inline_end = block.fragment.margin.inline_end;
+            let border_padding = block.fragment.border_padding.inline_start_end();
+            let box_sizing_boundary = block.fragment.box_sizing_boundary(Direction::Inline);
+            let available_size = max(
+                area_size - margin_inline_start - margin_inline_end - border_padding,
+                Au(0),
+            );
+
+            // Items without a specified inline size stretch to fill their area, or are sized to
+            // fit their content.
+            let content_size = match block.fragment.style().content_inline_size() {
+                Size::LengthPercentage(ref lp) => lp.to_used_value(area_size) - box_sizing_boundary,
+                Size::Auto => {
+                    if alignment == SelfAlignment::Stretch &&
+                        auto_margins == (false, false) &&
+                        !block.fragment.is_replaced()
+                    {
+                        available_size
+                    } else {
+                        let (border_padding, margin) =
+                            block.fragment.surrounding_intrinsic_inline_size();
+                        let sizes = block.base.intrinsic_inline_sizes;
+                        let min_content = sizes.minimum_inline_size - border_padding - margin;
+                        let max_content = sizes.preferred_inline_size - border_padding - margin;
+                        min(max(min_content, available_size), max_content)
+                    }
+                },
+            };
+            let size_constraint = SizeConstraint::new(
+                Some(area_size),
+                block.fragment.style().min_inline_size(),
+                block.fragment.style().max_inline_size(),
+                Some(box_sizing_boundary),
+            );
+            let item_inline_size = size_constraint.clamp(content_size) + border_padding;
+
+            let free_space = area_size - item_inline_size - margin_inline_start - margin_inline_end;
+            let offset = align_in_area(
+                free_space,
+                &mut margin_inline_start,
+                &mut margin_inline_end,
+                auto_margins,
+                alignment,
+            );
+            block.fragment.margin.inline_start = margin_inline_start;
+            block.fragment.margin.inline_end = margin_inline_end;
+            block.fragment.border_box.start.i = margin_inline_start;
+            block.fragment.border_box.size.inline = item_inline_size;
+            block.base.position.start.i = inline_start_content_edge + area_start + offset;
+            block.base.position.size.inline =
+                item_inline_size + margin_inline_start + margin_inline_end;
+        }
+        self.grid = grid;
+    }
+
+    fn assign_block_size(&mut self, layout_context: &LayoutContext) {
+        let _scope = layout_debug_scope!(
+            "grid::assign_block_size {:x}",
+            self.block_flow.base.debug_id()
+        );
+
+        // https://drafts.csswg.org/css-ui-3/#box-sizing
+        let box_border = self
+            .block_flow
+            .fragment
+            .box_sizing_boundary(Direction::Block);
+        let parent_container_size = self
+            .block_flow
+            .explicit_block_containing_size(layout_context.shared_context());
+        let explicit_content_size = self
+            .block_flow
+            .explicit_block_size(parent_container_size)
+            .map(|x| max(x - box_border, Au(0)));
+        let size_constraint = {
+            let style = self.block_flow.fragment.style();
+            SizeConstraint::new(
+                parent_container_size,
+                style.min_block_size(),
+                style.max_block_size(),
+                Some(box_border),
+            )
+        };
+        let content_block_size = self.size_rows(explicit_content_size, size_constraint);
+
+        let block_start_content_edge = self.block_flow.fragment.border_padding.block_start;
+        let align_items = self.block_flow.fragment.style().get_position().align_items;
+        let mut children = self.block_flow.base.children.random_access_mut();
+        for item in &self.grid.items {
+            let (area_start, area_size) = area(&self.grid.rows, item.rows.clone());
+            let block = children.get(item.index).as_mut_block();
+
+            // TODO: Support baseline alignment.
+            let alignment = match block.fragment.style().get_position().align_self {
+                AlignSelf::Auto => match align_items {
+                    AlignItems::Stretch => SelfAlignment::Stretch,
+                    AlignItems::FlexStart | AlignItems::Baseline => SelfAlignment::Start,
+                    AlignItems::FlexEnd => SelfAlignment::End,
+                    AlignItems::Center => SelfAlignment::Center,
+                },
+                AlignSelf::Stretch => SelfAlignment::Stretch,
+                AlignSelf::FlexStart | AlignSelf::Baseline => SelfAlignment::Start,
+                AlignSelf::FlexEnd => SelfAlignment::End,
+                AlignSelf::Center => SelfAlignment::Center,
+            };
+            let margin = block.fragment.style().logical_margin();
+            let auto_margins = (margin.block_start.is_auto(), margin.block_end.is_auto());
+            let mut margin_block_start = block.fragment.margin.block_start;
+            let mut margin_block_end = block.fragment.margin.block_end;
+
+            if alignment == SelfAlignment::Stretch &&
+                auto_margins == (false, false) &&
+                block.fragment.style().content_block_size().is_auto() &&
+                !block.fragment.is_replaced()
+            {
+                block.base.block_container_explicit_block_size = Some(area_size);
+                block.base.position.size.block =
+                    max(area_size - margin_block_start - margin_block_end, Au(0));
+                block.fragment.border_box.size.block = block.base.position.size.block;
+                // FIXME(stshine): item with 'align-self: stretch' and auto cross size should act
+                // as if it has a fixed cross size, all child blocks should resolve against it.
+            }
+
+            let free_space =
+                area_size - block.base.position.size.block - margin_block_start - margin_block_end;
+            let offset = align_in_area(
+                free_space,
+                &mut margin_block_start,
+                &mut margin_block_end,
+                auto_margins,
+                alignment,
+            );
+            block.fragment.margin.block_start = margin_block_start;
+            block.fragment.margin.block_end = margin_block_end;
+            block.base.position.start.b =
+                block_start_content_edge + area_start + offset + margin_block_start;
+        }
+
+        let total_block_size =
+            content_block_size + self.block_flow.fragment.border_padding.block_start_end();
+        self.block_flow.fragment.border_box.size.block = total_block_size;
+        self.block_flow.base.position.size.block = total_block_size;
+
+        let block_start =
+            AdjoiningMargins::from_margin(self.block_flow.fragment.margin.block_start);
+        let block_end = AdjoiningMargins::from_margin(self.block_flow.fragment.margin.block_end);
+        self.block_flow.base.collapsible_margins =
+            CollapsibleMargins::Collapse(block_start, block_end);
+
+        // TODO: assign proper static position for absolute descendants.
+        if (&*self as &dyn Flow).contains_roots_of_absolute_flow_tree() {
+            // Assign block-sizes for all flows in this absolute flow tree.
+            // This is preorder because the block-size of an absolute flow may depend on
+            // the block-size of its containing block, which may also be an absolute flow.
+            let assign_abs_b_sizes = AbsoluteAssignBSizesTraversal(layout_context.shared_context());
+            assign_abs_b_sizes.traverse_absolute_flows(&mut *self);
+        }
+    }
+
+    fn compute_stacking_relative_position(&mut self, layout_context: &LayoutContext) {
+        self.block_flow
+            .compute_stacking_relative_position(layout_context)
+    }
+
+    fn place_float_if_applicable<'a>(&mut self) {
+        self.block_flow.place_float_if_applicable()
+    }
+
+    fn update_late_computed_inline_position_if_necessary(&mut self, inline_position: Au) {
+        self.block_flow
+            .update_late_computed_inline_position_if_necessary(inline_position)
+    }
+
+    fn update_late_computed_block_position_if_necessary(&mut self, block_position: Au) {
+        self.block_flow
+            .update_late_computed_block_position_if_necessary(block_position)
+    }
+
+    fn build_display_list(&mut self, state: &mut DisplayListBuildState) {
+        // Draw the rest of the block.
+        self.as_mut_block()
+            .build_display_list_for_block(state, BorderPaintingMode::Separate)
+    }
+
+    fn collect_stacking_contexts(&mut self, state: &mut StackingContextCollectionState) {
+        self.block_flow.collect_stacking_contexts(state);
+    }
+
+    fn repair_style(&mut self, new_style: &crate::ServoArc<ComputedValues>) {
+        self.block_flow.repair_style(new_style)
+    }
+
+    fn compute_overflow(&self) -> Overflow {
+        self.block_flow.compute_overflow()
+    }
+
+    fn contains_roots_of_absolute_flow_tree(&self) -> bool {
+        self.block_flow.contains_roots_of_absolute_flow_tree()
+    }
+
+    fn is_absolute_containing_block(&self) -> bool {
+        self.block_flow.is_absolute_containing_block()
+    }
+
+    fn generated_containing_block_size(&self, flow: OpaqueFlow) -> LogicalSize<Au> {
+        self.block_flow.generated_containing_block_size(flow)
+    }
+
+    fn iterate_through_fragment_border_boxes(
+        &self,
+        iterator: &mut dyn FragmentBorderBoxIterator,
+        level: i32,
+        stacking_context_position: &Point2D<Au>,
+    ) {
+        self.block_flow.iterate_through_fragment_border_boxes(
+            iterator,
+            level,
+            stacking_context_position,
+        );
+    }
+
+    fn mutate_fragments(&mut self, mutator: &mut dyn FnMut(&mut Fragment)) {
+        self.block_flow.mutate_fragments(mutator);
+    }
+}
@@ -1333,6 +1333,7 @@ impl InlineFlow {
                 (Display::Inline, VerticalAlignKeyword::Top) |
                 (Display::Block, VerticalAlignKeyword::Top) |
                 (Display::InlineFlex, VerticalAlignKeyword::Top) |
+                (Display::InlineGrid, VerticalAlignKeyword::Top) |
                 (Display::InlineBlock, VerticalAlignKeyword::Top)
                     if inline_metrics.space_above_baseline >= Au(0) =>
                 {
@@ -1344,6 +1345,7 @@ impl InlineFlow {
                 (Display::Inline, VerticalAlignKeyword::Bottom) |
                 (Display::Block, VerticalAlignKeyword::Bottom) |
                 (Display::InlineFlex, VerticalAlignKeyword::Bottom) |
+                (Display::InlineGrid, VerticalAlignKeyword::Bottom) |
                 (Display::InlineBlock, VerticalAlignKeyword::Bottom)
                     if inline_metrics.space_below_baseline >= Au(0) =>
                 {
@@ -33,6 +33,7 @@ mod flow_list;
 pub mod flow_ref;
 mod fragment;
 mod generated_content;
+mod grid;
 pub mod incremental;
 mod inline;
 mod linked_list;
@@ -44,6 +45,7 @@ pub mod parallel;
 mod persistent_list;
 pub mod query;
 pub mod sequential;
+pub mod svg;
 mod table;
 mod table_caption;
 mod table_cell;
@@ -1088,7 +1088,11 @@ fn inner_text_collection_steps<N: LayoutNode>(
                     "\u{000A}", /* line feed */
                 )));
             },
-            Display::Block | Display::Flex | Display::TableCaption | Display::Table => {
+            Display::Block |
+            Display::Flex |
+            Display::Grid |
+            Display::TableCaption |
+            Display::Table => {
                 // Step 9.
                 items.insert(0, InnerTextItem::RequiredLineBreakCount(1));
                 items.push(InnerTextItem::RequiredLineBreakCount(1));
@@ -76,7 +76,7 @@ pub fn build_display_list_for_subtree<'a>(
     background_color: webrender_api::ColorF,
     client_size: Size2D<Au>,
 ) -> DisplayListBuildState<'a> {
-    let mut state = StackingContextCollectionState::new(layout_context.id);
+    let mut state = StackingContextCollectionState::new(layout_context);
     flow_root.collect_stacking_contexts(&mut state);
 
     let mut state = DisplayListBuildState::new(layout_context, state);
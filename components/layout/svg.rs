@@ -0,0 +1,412 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Painting of inline `<svg>` elements.
+//!
+//! The content of an `<svg>` element is turned into a render tree when its
+//! fragment is constructed. Its shapes are rasterized into an image when the
+//! display list is built, while its text is drawn with the fonts of the page.
+
+use crate::context::{with_thread_local_font_context, LayoutContext};
+use crate::display_list::items::OpaqueNode;
+use app_units::Au;
+use euclid::default::Size2D;
+use fnv::FnvHashMap;
+use gfx::font::{ShapingFlags, ShapingOptions};
+use gfx::text::text_run::TextRun;
+use parking_lot::RwLock;
+use script_layout_interface::wrapper_traits::GetLayoutData;
+use script_layout_interface::wrapper_traits::{ThreadSafeLayoutElement, ThreadSafeLayoutNode};
+use script_layout_interface::{LayoutNodeType, PseudoElementType};
+use selectors::Element;
+use servo_arc::Arc as ServoArc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use style::computed_values::display::T as Display;
+use style::computed_values::stroke_linecap::T as StrokeLinecap;
+use style::computed_values::stroke_linejoin::T as StrokeLinejoin;
+use style::computed_values::text_anchor::T as TextAnchor;
+use style::computed_values::visibility::T as Visibility;
+use style::properties::ComputedValues;
+use style::values::computed::{FillRule, LengthPercentage, SVGOpacity, SVGPaint, SVGPaintKind};
+use style::values::generics::svg::{SVGLength, SVGPaintFallback, SVGStrokeDashArray};
+use style::values::RGBA;
+use svg::attr::Length;
+use svg::tree::{Paint, TextNode, Tree};
+use svg::{PaintValue, Style, SvgElement};
+use unicode_bidi as bidi;
+use unicode_script::Script;
+use webrender_api::units::DeviceIntSize;
+use webrender_api::{DirtyRect, ImageData, ImageDescriptor, ImageFormat, ImageKey};
+use webrender_api::{RenderApi, Transaction};
+
+/// The largest width or height of the image the shapes of an `<svg>` element
+/// are rasterized into, in device pixels.
+const MAX_IMAGE_SIZE: f32 = 4096.;
+
+/// The content of an `<svg>` element.
+pub struct SvgContent {
+    pub tree: Arc<Tree>,
+    /// The text runs of the text nodes of the tree, in the order of their
+    /// index.
+    pub texts: Vec<Option<Arc<TextRun>>>,
+}
+
+impl SvgContent {
+    /// Builds the content of an `<svg>` element, resolving percentages
+    /// against the given viewport size.
+    pub fn new<N: ThreadSafeLayoutNode>(
+        node: &N,
+        viewport: Size2D<f32>,
+        layout_context: &LayoutContext,
+    ) -> Option<SvgContent> {
+        let root = LayoutSvgElement {
+            element: node.as_element()?,
+        };
+        let (tree, text_elements) = svg::build(&root, viewport);
+        let mut texts = vec![None; text_elements.len()];
+        tree.for_each_text(viewport, |node, _| {
+            texts[node.index] = text_elements[node.index]
+                .computed_style()
+                .and_then(|style| shape_text(layout_context, &style, &node.text));
+        });
+        Some(SvgContent {
+            tree: Arc::new(tree),
+            texts,
+        })
+    }
+}
+
+/// Shapes the text of a `<text>` element with its font.
+fn shape_text(
+    layout_context: &LayoutContext,
+    style: &ComputedValues,
+    text: &str,
+) -> Option<Arc<TextRun>> {
+    if text.is_empty() {
+        return None;
+    }
+    with_thread_local_font_context(layout_context, |font_context| {
+        let font_group = font_context.font_group(style.clone_font());
+        let font = font_group.borrow_mut().first(font_context)?;
+        let inherited_text_style = style.get_inherited_text();
+        let options = ShapingOptions {
+            letter_spacing: if inherited_text_style.letter_spacing.0.px() == 0. {
+                None
+            } else {
+                Some(Au::from(inherited_text_style.letter_spacing.0))
+            },
+            word_spacing: inherited_text_style.word_spacing.to_hash_key(),
+            script: Script::Common,
+            flags: ShapingFlags::empty(),
+        };
+        let (run, _) = TextRun::new(
+            &mut *font.borrow_mut(),
+            text.to_owned(),
+            &options,
+            bidi::Level::ltr(),
+            &mut None,
+        );
+        Some(Arc::new(run))
+    })
+}
+
+/// The color text is painted with. Text is only painted with solid colors, so
+/// gradients are replaced by their first stop.
+pub fn text_fill_color(node: &TextNode) -> Option<RGBA> {
+    let fill = node.fill.as_ref()?;
+    let mut color = match fill.paint {
+        Paint::Color(color) => color,
+        Paint::LinearGradient(ref gradient) => gradient.gradient.stops.first()?.color,
+        Paint::RadialGradient(ref gradient) => gradient.gradient.stops.first()?.color,
+    };
+    color.alpha = (color.alpha as f32 * fill.opacity.max(0.).min(1.)) as u8;
+    Some(color)
+}
+
+/// An element in the subtree of an `<svg>` element, as seen by the builder of
+/// render trees.
+#[derive(Clone)]
+struct LayoutSvgElement<E> {
+    element: E,
+}
+
+impl<E: ThreadSafeLayoutElement> LayoutSvgElement<E> {
+    /// Whether the element has been styled. Elements in `display: none`
+    /// subtrees, like the stops of a gradient defined in such a subtree,
+    /// aren't.
+    fn is_styled(&self) -> bool {
+        self.element.get_style_and_layout_data().is_some()
+    }
+
+    fn computed_style(&self) -> Option<ServoArc<ComputedValues>> {
+        if !self.is_styled() {
+            return None;
+        }
+        self.element.style_data().styles.primary.clone()
+    }
+}
+
+impl<E: ThreadSafeLayoutElement> SvgElement for LayoutSvgElement<E> {
+    fn local_name(&self) -> &str {
+        &**Element::local_name(&self.element)
+    }
+
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.element.get_attr(&ns!(), &name.into())
+    }
+
+    fn href(&self) -> Option<&str> {
+        self.element
+            .get_attr(&ns!(), &local_name!("href"))
+            .or_else(|| self.element.get_attr(&ns!(xlink), &local_name!("href")))
+    }
+
+    fn children(&self) -> Vec<Self> {
+        // Iterating over the children of an element looks at the style of its
+        // pseudo-elements.
+        if !self.is_styled() {
+            return vec![];
+        }
+        self.element
+            .as_node()
+            .children()
+            .filter(|child| child.get_pseudo_element_type() == PseudoElementType::Normal)
+            .filter_map(|child| child.as_element())
+            .map(|element| LayoutSvgElement { element })
+            .collect()
+    }
+
+    fn text_content(&self) -> String {
+        if !self.is_styled() {
+            return String::new();
+        }
+        let mut text = String::new();
+        for child in self.element.as_node().children() {
+            if child.get_pseudo_element_type() != PseudoElementType::Normal {
+                continue;
+            }
+            match child.type_id() {
+                Some(LayoutNodeType::Element(_)) => {
+                    if let Some(element) = child.as_element() {
+                        text.push_str(&LayoutSvgElement { element }.text_content());
+                    }
+                },
+                Some(LayoutNodeType::Text) => text.push_str(&child.node_text_content()),
+                None => {},
+            }
+        }
+        text
+    }
+
+    fn style(&self, parent: &Style) -> Style {
+        match self.computed_style() {
+            Some(style) => svg_style(&style),
+            None => parent.clone(),
+        }
+    }
+}
+
+/// Converts the computed values of an element to the style of the builder.
+fn svg_style(style: &ComputedValues) -> Style {
+    let inherited_svg = style.get_inherited_svg();
+    let svg = style.get_svg();
+    let color = style.get_inherited_text().color;
+    let paint = |paint: &SVGPaint| match paint.kind {
+        SVGPaintKind::Color(ref value) => PaintValue::Color(value.to_rgba(color)),
+        SVGPaintKind::PaintServer(ref url) => {
+            let fallback = match paint.fallback {
+                SVGPaintFallback::Color(ref value) => Some(value.to_rgba(color)),
+                SVGPaintFallback::None | SVGPaintFallback::Unset => None,
+            };
+            match url.url().and_then(|url| url.fragment()) {
+                Some(id) => PaintValue::Url(id.to_owned(), fallback),
+                None => fallback.map_or(PaintValue::None, PaintValue::Color),
+            }
+        },
+        SVGPaintKind::None | SVGPaintKind::ContextFill | SVGPaintKind::ContextStroke => {
+            PaintValue::None
+        },
+    };
+    let opacity = |opacity: &SVGOpacity| match *opacity {
+        SVGOpacity::Opacity(value) => value,
+        _ => 1.,
+    };
+    Style {
+        displayed: style.get_box().display != Display::None,
+        visible: style.get_inherited_box().visibility == Visibility::Visible,
+        opacity: style.get_effects().opacity,
+        color,
+        fill: paint(&inherited_svg.fill),
+        fill_opacity: opacity(&inherited_svg.fill_opacity),
+        fill_rule: match inherited_svg.fill_rule {
+            FillRule::Nonzero => svg::tree::FillRule::NonZero,
+            FillRule::Evenodd => svg::tree::FillRule::EvenOdd,
+        },
+        stroke: paint(&inherited_svg.stroke),
+        stroke_opacity: opacity(&inherited_svg.stroke_opacity),
+        stroke_width: match inherited_svg.stroke_width {
+            SVGLength::LengthPercentage(ref width) => length(&width.0),
+            SVGLength::ContextValue => Length::new(1.),
+        },
+        stroke_line_cap: match inherited_svg.stroke_linecap {
+            StrokeLinecap::Butt => svg::tree::LineCap::Butt,
+            StrokeLinecap::Round => svg::tree::LineCap::Round,
+            StrokeLinecap::Square => svg::tree::LineCap::Square,
+        },
+        stroke_line_join: match inherited_svg.stroke_linejoin {
+            StrokeLinejoin::Miter => svg::tree::LineJoin::Miter,
+            StrokeLinejoin::Round => svg::tree::LineJoin::Round,
+            StrokeLinejoin::Bevel => svg::tree::LineJoin::Bevel,
+        },
+        stroke_miter_limit: inherited_svg.stroke_miterlimit.0,
+        stroke_dash_array: match inherited_svg.stroke_dasharray {
+            SVGStrokeDashArray::Values(ref values) => {
+                values.iter().map(|value| length(&value.0)).collect()
+            },
+            SVGStrokeDashArray::ContextValue => vec![],
+        },
+        stroke_dash_offset: match inherited_svg.stroke_dashoffset {
+            SVGLength::LengthPercentage(ref offset) => length(offset),
+            SVGLength::ContextValue => Length::default(),
+        },
+        stop_color: svg.stop_color.to_rgba(color),
+        stop_opacity: svg.stop_opacity,
+        text_anchor: match inherited_svg.text_anchor {
+            TextAnchor::Start => svg::tree::TextAnchor::Start,
+            TextAnchor::Middle => svg::tree::TextAnchor::Middle,
+            TextAnchor::End => svg::tree::TextAnchor::End,
+        },
+        font_size: style.get_font().font_size.size().to_f32_px(),
+    }
+}
+
+fn length(value: &LengthPercentage) -> Length {
+    Length {
+        absolute: value.length_component().px(),
+        percentage: value.percentage(),
+    }
+}
+
+/// An image holding the rasterized shapes of an `<svg>` element.
+struct SvgImage {
+    tree: Arc<Tree>,
+    size: DeviceIntSize,
+    key: ImageKey,
+    /// Whether the image was painted since the last eviction.
+    painted: AtomicBool,
+}
+
+/// The images of the `<svg>` elements of a document, which are only
+/// rasterized again when their content or size changes.
+pub struct SvgImageCache {
+    webrender_api: Mutex<RenderApi>,
+    images: RwLock<FnvHashMap<OpaqueNode, SvgImage>>,
+}
+
+impl SvgImageCache {
+    pub fn new(webrender_api: RenderApi) -> SvgImageCache {
+        SvgImageCache {
+            webrender_api: Mutex::new(webrender_api),
+            images: RwLock::new(FnvHashMap::default()),
+        }
+    }
+
+    /// Returns the key of the image of the shapes of an `<svg>` element whose
+    /// viewport has the given size, in CSS pixels.
+    pub fn image_key(
+        &self,
+        node: OpaqueNode,
+        tree: &Arc<Tree>,
+        viewport: Size2D<f32>,
+        device_pixel_ratio: f32,
+    ) -> Option<ImageKey> {
+        let largest_side = viewport.width.max(viewport.height) * device_pixel_ratio;
+        let scale = if largest_side > MAX_IMAGE_SIZE {
+            device_pixel_ratio * MAX_IMAGE_SIZE / largest_side
+        } else {
+            device_pixel_ratio
+        };
+        let size = DeviceIntSize::new(
+            (viewport.width * scale).ceil() as i32,
+            (viewport.height * scale).ceil() as i32,
+        );
+        if size.width <= 0 || size.height <= 0 {
+            return None;
+        }
+
+        let old_image = match self.images.read().get(&node) {
+            Some(image)
+                if image.size == size &&
+                    (Arc::ptr_eq(&image.tree, tree) || image.tree == *tree) =>
+            {
+                image.painted.store(true, Ordering::Relaxed);
+                return Some(image.key);
+            }
+            Some(image) => Some((image.key, image.size)),
+            None => None,
+        };
+
+        let mut pixmap = tree.render(viewport, scale);
+        // WebRender wants premultiplied BGRA pixels.
+        for pixel in pixmap.data.chunks_mut(4) {
+            pixel.swap(0, 2);
+        }
+        let descriptor = ImageDescriptor {
+            size,
+            stride: None,
+            format: ImageFormat::BGRA8,
+            offset: 0,
+            is_opaque: false,
+            allow_mipmaps: false,
+        };
+        let data = ImageData::new(pixmap.data);
+
+        let webrender_api = self.webrender_api.lock().unwrap();
+        let mut txn = Transaction::new();
+        let key = match old_image {
+            Some((key, old_size)) if old_size == size => {
+                txn.update_image(key, descriptor, data, &DirtyRect::All);
+                key
+            },
+            old_image => {
+                if let Some((old_key, _)) = old_image {
+                    txn.delete_image(old_key);
+                }
+                let key = webrender_api.generate_image_key();
+                txn.add_image(key, descriptor, data, None);
+                key
+            },
+        };
+        webrender_api.update_resources(txn.resource_updates);
+
+        self.images.write().insert(
+            node,
+            SvgImage {
+                tree: tree.clone(),
+                size,
+                key,
+                painted: AtomicBool::new(true),
+            },
+        );
+        Some(key)
+    }
+
+    /// Deletes the images that weren't painted since the last call, which
+    /// belong to elements that were removed or aren't rendered anymore.
+    pub fn evict_unpainted_images(&self) {
+        let mut txn = Transaction::new();
+        self.images.write().retain(|_, image| {
+            if image.painted.swap(false, Ordering::Relaxed) {
+                return true;
+            }
+            txn.delete_image(image.key);
+            false
+        });
+        if !txn.resource_updates.is_empty() {
+            let webrender_api = self.webrender_api.lock().unwrap();
+            webrender_api.update_resources(txn.resource_updates);
+        }
+    }
+}
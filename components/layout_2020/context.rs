@@ -2,12 +2,37 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
+use gfx::font_cache_thread::FontCacheThread;
+use gfx::font_context::FontContext;
 use msg::constellation_msg::PipelineId;
+use std::cell::{RefCell, RefMut};
+use std::sync::Mutex;
 use style::context::SharedStyleContext;
 
+pub type LayoutFontContext = FontContext<FontCacheThread>;
+
+thread_local!(static FONT_CONTEXT_KEY: RefCell<Option<LayoutFontContext>> = RefCell::new(None));
+
+pub fn with_thread_local_font_context<F, R>(layout_context: &LayoutContext, f: F) -> R
+where
+    F: FnOnce(&mut LayoutFontContext) -> R,
+{
+    FONT_CONTEXT_KEY.with(|k| {
+        let mut font_context = k.borrow_mut();
+        if font_context.is_none() {
+            let font_cache_thread = layout_context.font_cache_thread.lock().unwrap().clone();
+            *font_context = Some(FontContext::new(font_cache_thread));
+        }
+        f(&mut RefMut::map(font_context, |x| x.as_mut().unwrap()))
+    })
+}
+
 pub struct LayoutContext<'a> {
     pub id: PipelineId,
     pub style_context: SharedStyleContext<'a>,
+
+    /// Interface to the font cache thread.
+    pub font_cache_thread: Mutex<FontCacheThread>,
 }
 
 impl<'a> LayoutContext<'a> {
@@ -2,7 +2,264 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
-pub use self::webrender_helpers::WebRenderDisplayListConverter;
+//! Painting the fragment tree into a WebRender display list.
+
+use crate::fragments::{BoxFragment, Fragment, TextFragment};
+use crate::geom::physical::Rect;
+use app_units::Au;
+use euclid::default::Point2D;
+use gfx::text::glyph::{ByteIndex, GlyphStore};
+use range::Range;
+use std::sync::Arc;
+use style::logical_geometry::WritingMode;
+use style::properties::ComputedValues;
+use style::values::computed::{BorderStyle, Color};
+use style::values::RGBA;
+use webrender_api::units::{LayoutPoint, LayoutRect, LayoutSideOffsets, LayoutSize};
+use webrender_api::{self as wr, BorderDetails, BorderRadius, BorderSide, NormalBorder};
+use webrender_api::{ClipId, CommonItemProperties, GlyphInstance, SpatialId};
 
 pub mod items;
-mod webrender_helpers;
+
+pub struct DisplayListBuilder {
+    pipeline_id: wr::PipelineId,
+    pub wr: wr::DisplayListBuilder,
+    /// Whether anything that counts for first-contentful-paint was painted.
+    pub is_contentful: bool,
+}
+
+impl DisplayListBuilder {
+    pub fn new(pipeline_id: wr::PipelineId, viewport_size: LayoutSize) -> Self {
+        DisplayListBuilder {
+            pipeline_id,
+            wr: wr::DisplayListBuilder::with_capacity(
+                pipeline_id,
+                viewport_size,
+                1024 * 1024, // 1 MB of space
+            ),
+            is_contentful: false,
+        }
+    }
+
+    fn common_properties(&self, clip_rect: LayoutRect) -> CommonItemProperties {
+        CommonItemProperties {
+            clip_rect,
+            spatial_id: SpatialId::root_scroll_node(self.pipeline_id),
+            clip_id: ClipId::root(self.pipeline_id),
+            is_backface_visible: true,
+            hit_info: None,
+        }
+    }
+
+    fn push_rectangle(&mut self, rect: LayoutRect, color: wr::ColorF) {
+        let common = self.common_properties(rect);
+        self.wr
+            .push_item(&wr::DisplayItem::Rectangle(wr::RectangleDisplayItem {
+                common,
+                color,
+            }));
+    }
+
+    /// Paints the background of the root element, or of the body if the root
+    /// element has none, over the whole canvas.
+    ///
+    /// https://drafts.csswg.org/css-backgrounds/#special-backgrounds
+    pub(crate) fn build_canvas_background(
+        &mut self,
+        fragments: &[Fragment],
+        containing_block: &Rect<Au>,
+    ) {
+        let root = fragments.iter().filter_map(as_box_fragment).next();
+        let root = match root {
+            Some(root) => root,
+            None => return,
+        };
+        let mut color = background_color(&root.style);
+        if color.a == 0. {
+            let body = root.children.iter().filter_map(as_box_fragment).next();
+            if let Some(body) = body {
+                color = background_color(&body.style);
+            }
+        }
+        if color.a > 0. {
+            self.push_rectangle(containing_block.to_webrender(), color);
+        }
+    }
+}
+
+impl gfx_traits::DisplayList for DisplayListBuilder {
+    fn is_contentful(&self) -> bool {
+        self.is_contentful
+    }
+}
+
+impl Fragment {
+    /// Paints this fragment and its descendants, `containing_block` being the
+    /// physical rectangle its own rectangle is relative to.
+    pub(crate) fn build_display_list(
+        &self,
+        builder: &mut DisplayListBuilder,
+        containing_block: &Rect<Au>,
+    ) {
+        match self {
+            Fragment::Box(b) => b.build_display_list(builder, containing_block),
+            Fragment::Anonymous(a) => {
+                // FIXME: use the writing mode of the box the fragment
+                // belongs to.
+                let rect = a.rect.to_physical(WritingMode::empty(), containing_block);
+                for child in &a.children {
+                    child.build_display_list(builder, &rect)
+                }
+            },
+            Fragment::Text(t) => t.build_display_list(builder, containing_block),
+        }
+    }
+}
+
+impl BoxFragment {
+    fn build_display_list(&self, builder: &mut DisplayListBuilder, containing_block: &Rect<Au>) {
+        let mode = self.style.writing_mode;
+        let border_rect = self
+            .border_rect()
+            .to_physical(mode, containing_block)
+            .to_webrender();
+
+        let background_color = background_color(&self.style);
+        if background_color.a > 0. {
+            builder.push_rectangle(border_rect, background_color);
+            builder.is_contentful = true;
+        }
+        self.build_border(builder, border_rect);
+
+        let content_rect = self.content_rect.to_physical(mode, containing_block);
+        for child in &self.children {
+            child.build_display_list(builder, &content_rect)
+        }
+    }
+
+    fn build_border(&self, builder: &mut DisplayListBuilder, border_rect: LayoutRect) {
+        let border = self.style.get_border();
+        let widths = self.border.to_physical(self.style.writing_mode);
+        if widths.top == Au(0) &&
+            widths.right == Au(0) &&
+            widths.bottom == Au(0) &&
+            widths.left == Au(0)
+        {
+            return;
+        }
+        let side = |color: Color, style: BorderStyle| BorderSide {
+            color: rgba(self.style.resolve_color(color)),
+            style: border_style(style),
+        };
+        let details = BorderDetails::Normal(NormalBorder {
+            top: side(border.border_top_color, border.border_top_style),
+            right: side(border.border_right_color, border.border_right_style),
+            bottom: side(border.border_bottom_color, border.border_bottom_style),
+            left: side(border.border_left_color, border.border_left_style),
+            radius: BorderRadius::zero(),
+            do_aa: true,
+        });
+        let common = builder.common_properties(border_rect);
+        builder
+            .wr
+            .push_item(&wr::DisplayItem::Border(wr::BorderDisplayItem {
+                bounds: border_rect,
+                common,
+                widths: LayoutSideOffsets::new(
+                    widths.top.to_f32_px(),
+                    widths.right.to_f32_px(),
+                    widths.bottom.to_f32_px(),
+                    widths.left.to_f32_px(),
+                ),
+                details,
+            }));
+    }
+}
+
+impl TextFragment {
+    fn build_display_list(&self, builder: &mut DisplayListBuilder, containing_block: &Rect<Au>) {
+        let mode = self.parent_style.writing_mode;
+        let rect = self.content_rect.to_physical(mode, containing_block);
+        let baseline_origin = LayoutPoint::new(
+            rect.top_left.x.to_f32_px(),
+            (rect.top_left.y + self.ascent).to_f32_px(),
+        );
+        let glyphs = glyphs(&self.glyphs, baseline_origin);
+        if glyphs.is_empty() {
+            return;
+        }
+        builder.is_contentful = true;
+        let bounds = rect.to_webrender();
+        let common = builder.common_properties(bounds);
+        let color = self.parent_style.get_inherited_text().clone_color();
+        builder
+            .wr
+            .push_item(&wr::DisplayItem::Text(wr::TextDisplayItem {
+                bounds,
+                common,
+                font_key: self.font_key,
+                color: rgba(color),
+                glyph_options: None,
+            }));
+        builder.wr.push_iter(glyphs.iter());
+    }
+}
+
+impl Rect<Au> {
+    fn to_webrender(&self) -> LayoutRect {
+        LayoutRect::new(
+            LayoutPoint::new(self.top_left.x.to_f32_px(), self.top_left.y.to_f32_px()),
+            LayoutSize::new(self.size.x.to_f32_px(), self.size.y.to_f32_px()),
+        )
+    }
+}
+
+fn as_box_fragment(fragment: &Fragment) -> Option<&BoxFragment> {
+    match fragment {
+        Fragment::Box(b) => Some(b),
+        _ => None,
+    }
+}
+
+fn glyphs(glyph_runs: &[Arc<GlyphStore>], mut origin: LayoutPoint) -> Vec<GlyphInstance> {
+    let mut glyphs = vec![];
+    for run in glyph_runs {
+        for glyph in run.iter_glyphs_for_byte_range(&Range::new(ByteIndex(0), run.len())) {
+            if !run.is_whitespace() {
+                let glyph_offset = glyph.offset().unwrap_or(Point2D::zero());
+                let point = LayoutPoint::new(
+                    origin.x + glyph_offset.x.to_f32_px(),
+                    origin.y + glyph_offset.y.to_f32_px(),
+                );
+                glyphs.push(GlyphInstance {
+                    index: glyph.id(),
+                    point,
+                });
+            }
+            origin.x += glyph.advance().to_f32_px();
+        }
+    }
+    glyphs
+}
+
+fn background_color(style: &ComputedValues) -> wr::ColorF {
+    rgba(style.resolve_color(style.get_background().background_color))
+}
+
+fn rgba(rgba: RGBA) -> wr::ColorF {
+    wr::ColorF::new(
+        rgba.red_f32(),
+        rgba.green_f32(),
+        rgba.blue_f32(),
+        rgba.alpha_f32(),
+    )
+}
+
+/// The other border styles are not enabled in style for layout 2020 yet.
+fn border_style(style: BorderStyle) -> wr::BorderStyle {
+    match style {
+        BorderStyle::None => wr::BorderStyle::None,
+        BorderStyle::Solid => wr::BorderStyle::Solid,
+        BorderStyle::Hidden => wr::BorderStyle::Hidden,
+    }
+}
@@ -0,0 +1,118 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Walking the DOM to build the box tree.
+
+use crate::context::LayoutContext;
+use crate::display_list::items::OpaqueNode;
+use crate::style_ext::{Display, DisplayGeneratingBox};
+use crate::ServoArc;
+use script_layout_interface::wrapper_traits::{PseudoElementType, ThreadSafeLayoutNode};
+use script_layout_interface::LayoutNodeType;
+use style::properties::ComputedValues;
+use style::values::computed::counters::{Content, ContentItem};
+
+/// What a box generated by an element or pseudo-element contains.
+pub enum Contents<Node> {
+    /// The children of an element, including its `::before` and `::after`
+    /// pseudo-elements.
+    OfElement(Node),
+    /// The text generated by the `content` property of a `::before` or
+    /// `::after` pseudo-element.
+    OfPseudoElement(String),
+}
+
+pub trait TraversalHandler<Node> {
+    fn handle_text(
+        &mut self,
+        text: String,
+        parent_style: &ServoArc<ComputedValues>,
+        tag: OpaqueNode,
+    );
+
+    /// Or pseudo-element
+    fn handle_element(
+        &mut self,
+        style: &ServoArc<ComputedValues>,
+        display: DisplayGeneratingBox,
+        contents: Contents<Node>,
+        tag: OpaqueNode,
+    );
+}
+
+pub fn traverse_children_of<Node>(
+    parent: Node,
+    context: &LayoutContext,
+    handler: &mut impl TraversalHandler<Node>,
+) where
+    Node: ThreadSafeLayoutNode,
+{
+    for child in parent.children() {
+        if child.type_id() == Some(LayoutNodeType::Text) {
+            let style = child.style(context.shared_context());
+            handler.handle_text(child.node_text_content(), &style, child.opaque());
+        } else if child.as_element().is_some() {
+            traverse_element(child, context, handler);
+        }
+    }
+}
+
+fn traverse_element<Node>(
+    element: Node,
+    context: &LayoutContext,
+    handler: &mut impl TraversalHandler<Node>,
+) where
+    Node: ThreadSafeLayoutNode,
+{
+    let style = element.style(context.shared_context());
+    let display = match Display::from(style.get_box().display) {
+        Display::None => return,
+        Display::GeneratingBox(display) => display,
+    };
+    let contents = match element.get_pseudo_element_type() {
+        PseudoElementType::Before | PseudoElementType::After => match pseudo_element_text(&style) {
+            Some(text) => Contents::OfPseudoElement(text),
+            None => return,
+        },
+        _ => Contents::OfElement(element),
+    };
+    handler.handle_element(&style, display, contents, element.opaque())
+}
+
+/// The text of a `::before` or `::after` pseudo-element, if it generates a
+/// box at all.
+///
+/// FIXME: counters, quotes and images are not supported.
+fn pseudo_element_text(style: &ComputedValues) -> Option<String> {
+    match style.get_counters().content {
+        Content::Normal | Content::None => None,
+        Content::Items(ref items) => Some(
+            items
+                .iter()
+                .filter_map(|item| match *item {
+                    ContentItem::String(ref s) => Some(&**s),
+                    _ => None,
+                })
+                .collect(),
+        ),
+    }
+}
+
+impl<Node> Contents<Node>
+where
+    Node: ThreadSafeLayoutNode,
+{
+    pub fn traverse(
+        self,
+        context: &LayoutContext,
+        style: &ServoArc<ComputedValues>,
+        tag: OpaqueNode,
+        handler: &mut impl TraversalHandler<Node>,
+    ) {
+        match self {
+            Contents::OfElement(node) => traverse_children_of(node, context, handler),
+            Contents::OfPseudoElement(text) => handler.handle_text(text, style, tag),
+        }
+    }
+}
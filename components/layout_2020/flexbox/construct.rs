@@ -0,0 +1,138 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Building the box tree of flex containers.
+//!
+//! https://drafts.csswg.org/css-flexbox/#flex-items
+
+use super::{FlexContainer, FlexLevelBox};
+use crate::context::LayoutContext;
+use crate::display_list::items::OpaqueNode;
+use crate::dom_traversal::{Contents, TraversalHandler};
+use crate::flow::inline::TextRun;
+use crate::flow::BlockFormattingContext;
+use crate::formatting_contexts::IndependentFormattingContext;
+use crate::positioned::AbsolutelyPositionedBox;
+use crate::style_ext::{ComputedValuesExt, DisplayGeneratingBox};
+use crate::ServoArc;
+use script_layout_interface::wrapper_traits::ThreadSafeLayoutNode;
+use std::mem;
+use style::properties::ComputedValues;
+use style::selector_parser::PseudoElement;
+
+impl FlexContainer {
+    pub fn construct<Node>(
+        context: &LayoutContext,
+        style: &ServoArc<ComputedValues>,
+        contents: Contents<Node>,
+        tag: OpaqueNode,
+    ) -> Self
+    where
+        Node: ThreadSafeLayoutNode,
+    {
+        let mut builder = FlexContainerBuilder {
+            context,
+            style,
+            tag,
+            children: Vec::new(),
+            contiguous_text_runs: Vec::new(),
+        };
+        contents.traverse(context, style, tag, &mut builder);
+        builder.finish::<Node>()
+    }
+}
+
+struct FlexContainerBuilder<'a> {
+    context: &'a LayoutContext<'a>,
+    style: &'a ServoArc<ComputedValues>,
+    tag: OpaqueNode,
+    children: Vec<FlexLevelBox>,
+
+    /// The text directly inside the flex container since the last child
+    /// element, which goes in an anonymous flex item.
+    contiguous_text_runs: Vec<TextRun>,
+}
+
+impl<'a, Node> TraversalHandler<Node> for FlexContainerBuilder<'a>
+where
+    Node: ThreadSafeLayoutNode,
+{
+    fn handle_text(
+        &mut self,
+        text: String,
+        parent_style: &ServoArc<ComputedValues>,
+        tag: OpaqueNode,
+    ) {
+        self.contiguous_text_runs.push(TextRun {
+            tag,
+            parent_style: parent_style.clone(),
+            text,
+        })
+    }
+
+    /// Children of a flex container are blockified by style, and floats
+    /// don't apply to them.
+    fn handle_element(
+        &mut self,
+        style: &ServoArc<ComputedValues>,
+        display: DisplayGeneratingBox,
+        contents: Contents<Node>,
+        tag: OpaqueNode,
+    ) {
+        self.wrap_any_text_in_anonymous_block_container::<Node>();
+        let DisplayGeneratingBox::OutsideInside { inside, .. } = display;
+        let contents = IndependentFormattingContext::construct(
+            self.context,
+            style.clone(),
+            inside,
+            contents,
+            tag,
+        );
+        self.children.push(if style.is_absolutely_positioned() {
+            FlexLevelBox::OutOfFlowAbsolutelyPositionedBox(AbsolutelyPositionedBox { contents })
+        } else {
+            FlexLevelBox::FlexItem(contents)
+        });
+    }
+}
+
+impl<'a> FlexContainerBuilder<'a> {
+    /// Each contiguous sequence of text runs is wrapped in an anonymous flex
+    /// item, unless it only contains white space.
+    fn wrap_any_text_in_anonymous_block_container<Node>(&mut self)
+    where
+        Node: ThreadSafeLayoutNode,
+    {
+        let runs = mem::replace(&mut self.contiguous_text_runs, Vec::new());
+        if runs
+            .iter()
+            .all(|run| run.text.chars().all(char::is_whitespace))
+        {
+            return;
+        }
+        let shared_context = self.context.shared_context();
+        let anonymous_style = shared_context
+            .stylist
+            .style_for_anonymous::<Node::ConcreteElement>(
+                &shared_context.guards,
+                &PseudoElement::ServoAnonymousBlock,
+                self.style,
+            );
+        let contents =
+            BlockFormattingContext::construct_for_text_runs(&anonymous_style, runs.into_iter());
+        self.children.push(FlexLevelBox::FlexItem(
+            IndependentFormattingContext::new_anonymous_flow(self.tag, anonymous_style, contents),
+        ));
+    }
+
+    fn finish<Node>(mut self) -> FlexContainer
+    where
+        Node: ThreadSafeLayoutNode,
+    {
+        self.wrap_any_text_in_anonymous_block_container::<Node>();
+        FlexContainer {
+            children: self.children,
+        }
+    }
+}
@@ -0,0 +1,201 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Geometry in flex-relative coordinates.
+//!
+//! https://drafts.csswg.org/css-flexbox/#box-model
+
+use crate::geom::flow_relative::{Rect, Sides, Vec2};
+use app_units::Au;
+use std::ops::Add;
+use style::computed_values::flex_direction::T as FlexDirection;
+use style::computed_values::flex_wrap::T as FlexWrap;
+
+#[derive(Clone, Copy, Debug)]
+pub(super) struct FlexRelativeVec2<T> {
+    pub main: T,
+    pub cross: T,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(super) struct FlexRelativeSides<T> {
+    pub main_start: T,
+    pub main_end: T,
+    pub cross_start: T,
+    pub cross_end: T,
+}
+
+/// A rectangle whose start corner is relative to the main-start and
+/// cross-start edges of the flex container.
+pub(super) struct FlexRelativeRect<T> {
+    pub start_corner: FlexRelativeVec2<T>,
+    pub size: FlexRelativeVec2<T>,
+}
+
+impl<T: Add<Output = T> + Copy> FlexRelativeSides<T> {
+    pub fn sum_by_axis(&self) -> FlexRelativeVec2<T> {
+        FlexRelativeVec2 {
+            main: self.main_start + self.main_end,
+            cross: self.cross_start + self.cross_end,
+        }
+    }
+}
+
+/// Which flow-relative axis the main axis of a flex container is.
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub(super) enum FlexAxis {
+    Row,
+    Column,
+}
+
+/// Which flow-relative sides the main-start and cross-start sides of a flex
+/// container are.
+#[derive(Clone, Copy)]
+pub(super) struct FlexSides {
+    pub axis: FlexAxis,
+    main_reversed: bool,
+    cross_reversed: bool,
+}
+
+// FIXME: this assumes that the flex container and its items have the same
+// writing mode.
+impl FlexSides {
+    pub fn new(flex_direction: FlexDirection, flex_wrap: FlexWrap) -> Self {
+        let (axis, main_reversed) = match flex_direction {
+            FlexDirection::Row => (FlexAxis::Row, false),
+            FlexDirection::RowReverse => (FlexAxis::Row, true),
+            FlexDirection::Column => (FlexAxis::Column, false),
+            FlexDirection::ColumnReverse => (FlexAxis::Column, true),
+        };
+        FlexSides {
+            axis,
+            main_reversed,
+            cross_reversed: flex_wrap == FlexWrap::WrapReverse,
+        }
+    }
+
+    pub fn vec2_to_flex_relative<T>(&self, vec: Vec2<T>) -> FlexRelativeVec2<T> {
+        let Vec2 { inline, block } = vec;
+        match self.axis {
+            FlexAxis::Row => FlexRelativeVec2 {
+                main: inline,
+                cross: block,
+            },
+            FlexAxis::Column => FlexRelativeVec2 {
+                main: block,
+                cross: inline,
+            },
+        }
+    }
+
+    pub fn vec2_to_flow_relative<T>(&self, vec: FlexRelativeVec2<T>) -> Vec2<T> {
+        let FlexRelativeVec2 { main, cross } = vec;
+        match self.axis {
+            FlexAxis::Row => Vec2 {
+                inline: main,
+                block: cross,
+            },
+            FlexAxis::Column => Vec2 {
+                inline: cross,
+                block: main,
+            },
+        }
+    }
+
+    pub fn sides_to_flex_relative<T>(&self, sides: Sides<T>) -> FlexRelativeSides<T> {
+        let Sides {
+            inline_start,
+            inline_end,
+            block_start,
+            block_end,
+        } = sides;
+        let (main_start, main_end, cross_start, cross_end) = match self.axis {
+            FlexAxis::Row => (inline_start, inline_end, block_start, block_end),
+            FlexAxis::Column => (block_start, block_end, inline_start, inline_end),
+        };
+        let (main_start, main_end) = if self.main_reversed {
+            (main_end, main_start)
+        } else {
+            (main_start, main_end)
+        };
+        let (cross_start, cross_end) = if self.cross_reversed {
+            (cross_end, cross_start)
+        } else {
+            (cross_start, cross_end)
+        };
+        FlexRelativeSides {
+            main_start,
+            main_end,
+            cross_start,
+            cross_end,
+        }
+    }
+
+    pub fn sides_to_flow_relative<T>(&self, sides: FlexRelativeSides<T>) -> Sides<T> {
+        let FlexRelativeSides {
+            main_start,
+            main_end,
+            cross_start,
+            cross_end,
+        } = sides;
+        let (main_start, main_end) = if self.main_reversed {
+            (main_end, main_start)
+        } else {
+            (main_start, main_end)
+        };
+        let (cross_start, cross_end) = if self.cross_reversed {
+            (cross_end, cross_start)
+        } else {
+            (cross_start, cross_end)
+        };
+        match self.axis {
+            FlexAxis::Row => Sides {
+                inline_start: main_start,
+                inline_end: main_end,
+                block_start: cross_start,
+                block_end: cross_end,
+            },
+            FlexAxis::Column => Sides {
+                inline_start: cross_start,
+                inline_end: cross_end,
+                block_start: main_start,
+                block_end: main_end,
+            },
+        }
+    }
+
+    /// Converts a rectangle to flow-relative coordinates, given the size of
+    /// the content box of the flex container.
+    pub fn rect_to_flow_relative(
+        &self,
+        container_size: &FlexRelativeVec2<Au>,
+        rect: FlexRelativeRect<Au>,
+    ) -> Rect<Au> {
+        let flip = |reversed: bool, start: Au, size: Au, container_size: Au| {
+            if reversed {
+                container_size - start - size
+            } else {
+                start
+            }
+        };
+        let start_corner = FlexRelativeVec2 {
+            main: flip(
+                self.main_reversed,
+                rect.start_corner.main,
+                rect.size.main,
+                container_size.main,
+            ),
+            cross: flip(
+                self.cross_reversed,
+                rect.start_corner.cross,
+                rect.size.cross,
+                container_size.cross,
+            ),
+        };
+        Rect {
+            start_corner: self.vec2_to_flow_relative(start_corner),
+            size: self.vec2_to_flow_relative(rect.size),
+        }
+    }
+}
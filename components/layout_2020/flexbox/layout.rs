@@ -0,0 +1,829 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! The flex layout algorithm.
+//!
+//! https://drafts.csswg.org/css-flexbox/#layout-algorithm
+
+use super::geom::{FlexAxis, FlexRelativeRect, FlexRelativeSides, FlexRelativeVec2, FlexSides};
+use super::{FlexContainer, FlexLevelBox};
+use crate::context::LayoutContext;
+use crate::formatting_contexts::{IndependentFormattingContext, IndependentLayout};
+use crate::fragments::{BoxFragment, CollapsedBlockMargins, Fragment};
+use crate::geom::flow_relative::{Sides, Vec2};
+use crate::positioned::AbsolutelyPositionedFragment;
+use crate::positioned::{adjust_static_positions, relative_adjustement};
+use crate::sizing::{clamp_between_extremums, ContentSizes};
+use crate::style_ext::ComputedValuesExt;
+use crate::ContainingBlock;
+use app_units::Au;
+use style::computed_values::align_content::T as AlignContent;
+use style::computed_values::align_items::T as AlignItems;
+use style::computed_values::align_self::T as AlignSelf;
+use style::computed_values::box_sizing::T as BoxSizing;
+use style::computed_values::flex_wrap::T as FlexWrap;
+use style::computed_values::justify_content::T as JustifyContent;
+use style::properties::ComputedValues;
+use style::values::computed::flex::FlexBasis;
+use style::values::computed::Size;
+
+/// What the layout of the items of a flex container depends on.
+struct FlexContext<'a> {
+    layout_context: &'a LayoutContext<'a>,
+    /// The content box of the flex container.
+    containing_block: &'a ContainingBlock,
+    sides: FlexSides,
+    container_is_single_line: bool,
+    container_definite_inner_size: FlexRelativeVec2<Option<Au>>,
+    align_items: AlignItems,
+}
+
+/// A flex item, with the values used by the flex layout algorithm.
+struct FlexItem<'a> {
+    box_: &'a IndependentFormattingContext,
+    content_box_size: FlexRelativeVec2<Option<Au>>,
+    content_min_size: FlexRelativeVec2<Au>,
+    content_max_size: FlexRelativeVec2<Option<Au>>,
+    padding: Sides<Au>,
+    border: Sides<Au>,
+    /// `None` for `auto` margins.
+    margin: Sides<Option<Au>>,
+
+    /// The sum of the padding, border and margins in each axis, `auto`
+    /// margins being zero.
+    pbm_auto_is_zero: FlexRelativeVec2<Au>,
+
+    /// The inline size the item is laid out with in a column flex container,
+    /// before it is stretched.
+    inline_size_in_column: Option<Au>,
+
+    flex_base_size: Au,
+    hypothetical_main_size: Au,
+    flex_grow: f32,
+    flex_shrink: f32,
+    align_self: AlignItems,
+}
+
+/// The result of laying out a flex item with a given main size.
+struct FlexItemLayoutResult<'a> {
+    hypothetical_cross_size: Au,
+    fragments: Vec<Fragment>,
+    absolutely_positioned_fragments: Vec<AbsolutelyPositionedFragment<'a>>,
+}
+
+/// A flex line, once the sizes of its items in both axes are known.
+struct FlexLine<'a> {
+    items: Vec<FlexItem<'a>>,
+    item_used_main_sizes: Vec<Au>,
+    item_layout_results: Vec<FlexItemLayoutResult<'a>>,
+    cross_size: Au,
+}
+
+impl FlexContainer {
+    /// https://drafts.csswg.org/css-flexbox/#intrinsic-sizes
+    pub(crate) fn inline_content_sizes(
+        &self,
+        layout_context: &LayoutContext,
+        style: &ComputedValues,
+    ) -> ContentSizes {
+        let position = style.get_position();
+        let sides = FlexSides::new(position.flex_direction, position.flex_wrap);
+        let is_single_line = position.flex_wrap == FlexWrap::Nowrap;
+        let mut sizes = ContentSizes::zero();
+        for child in &self.children {
+            let item_sizes = match child {
+                FlexLevelBox::FlexItem(box_) => box_.outer_inline_content_sizes(layout_context),
+                FlexLevelBox::OutOfFlowAbsolutelyPositionedBox(_) => continue,
+            };
+            match sides.axis {
+                FlexAxis::Row => {
+                    sizes.max_content += item_sizes.max_content;
+                    if is_single_line {
+                        sizes.min_content += item_sizes.min_content
+                    } else {
+                        sizes.min_content = sizes.min_content.max(item_sizes.min_content)
+                    }
+                },
+                FlexAxis::Column => sizes.max_assign(&item_sizes),
+            }
+        }
+        sizes
+    }
+
+    pub(crate) fn layout<'a>(
+        &'a self,
+        layout_context: &LayoutContext,
+        containing_block: &ContainingBlock,
+        style: &ComputedValues,
+        absolutely_positioned_fragments: &mut Vec<AbsolutelyPositionedFragment<'a>>,
+    ) -> IndependentLayout {
+        let position = style.get_position();
+        let sides = FlexSides::new(position.flex_direction, position.flex_wrap);
+        let flex_context = FlexContext {
+            layout_context,
+            containing_block,
+            sides,
+            container_is_single_line: position.flex_wrap == FlexWrap::Nowrap,
+            container_definite_inner_size: sides.vec2_to_flex_relative(Vec2 {
+                inline: Some(containing_block.inline_size),
+                block: containing_block.block_size,
+            }),
+            align_items: position.align_items,
+        };
+
+        let mut flex_item_boxes = Vec::new();
+        for child in &self.children {
+            match child {
+                FlexLevelBox::FlexItem(box_) => flex_item_boxes.push(box_),
+                // FIXME: the static position should be where the box would be
+                // if it were the sole flex item.
+                FlexLevelBox::OutOfFlowAbsolutelyPositionedBox(box_) => {
+                    absolutely_positioned_fragments.push(box_.layout(Vec2::zero()))
+                },
+            }
+        }
+        // https://drafts.csswg.org/css-flexbox/#order-modified-document-order
+        flex_item_boxes.sort_by_key(|box_| box_.style.get_position().order);
+        let flex_items = flex_item_boxes
+            .into_iter()
+            .map(|box_| FlexItem::new(&flex_context, box_))
+            .collect();
+
+        let mut flex_lines = collect_flex_lines(&flex_context, flex_items)
+            .into_iter()
+            .map(|items| layout_flex_line(&flex_context, items))
+            .collect::<Vec<_>>();
+
+        let container_main_size = flex_context
+            .container_definite_inner_size
+            .main
+            .unwrap_or_else(|| {
+                flex_lines
+                    .iter()
+                    .map(|line| outer_main_sizes_sum(&line.items, &line.item_used_main_sizes))
+                    .fold(Au(0), Au::max)
+            });
+
+        // https://drafts.csswg.org/css-flexbox/#algo-cross-line
+        if flex_context.container_is_single_line {
+            if let Some(size) = flex_context.container_definite_inner_size.cross {
+                flex_lines[0].cross_size = size;
+            }
+        }
+        let lines_cross_size = flex_lines
+            .iter()
+            .fold(Au(0), |sum, line| sum + line.cross_size);
+        let container_cross_size = flex_context
+            .container_definite_inner_size
+            .cross
+            .unwrap_or(lines_cross_size);
+        let align_content = if flex_context.container_is_single_line {
+            AlignContent::Stretch
+        } else {
+            position.align_content
+        };
+        let cross_free_space = container_cross_size - lines_cross_size;
+        if align_content == AlignContent::Stretch && cross_free_space > Au(0) {
+            let line_count = flex_lines.len() as i32;
+            for line in &mut flex_lines {
+                line.cross_size += cross_free_space / line_count;
+            }
+        }
+        let (mut line_cross_start, line_gap) = distribute_free_space(
+            align_content_as_justify_content(align_content),
+            cross_free_space,
+            flex_lines.len(),
+        );
+
+        let container_inner_size = FlexRelativeVec2 {
+            main: container_main_size,
+            cross: container_cross_size,
+        };
+        let mut fragments = Vec::new();
+        for mut line in flex_lines {
+            line.stretch_items(&flex_context);
+            line.place_items(
+                &flex_context,
+                position.justify_content,
+                line_cross_start,
+                &container_inner_size,
+                &mut fragments,
+                absolutely_positioned_fragments,
+            );
+            line_cross_start += line.cross_size + line_gap;
+        }
+
+        let content_block_size = sides.vec2_to_flow_relative(container_inner_size).block;
+        IndependentLayout {
+            fragments,
+            content_block_size,
+        }
+    }
+}
+
+impl<'a> FlexItem<'a> {
+    fn new(flex_context: &FlexContext, box_: &'a IndependentFormattingContext) -> Self {
+        let containing_block = flex_context.containing_block;
+        let sides = flex_context.sides;
+        let style = &box_.style;
+        let cbis = containing_block.inline_size;
+        let padding = style.padding().percentages_relative_to(cbis);
+        let border = style.border_width();
+        let margin = style.margin().percentages_relative_to(cbis);
+        let pb = &padding + &border;
+        let pbm_auto_is_zero = sides
+            .sides_to_flex_relative(&pb + &margin.map(|m| m.unwrap_or(Au(0))))
+            .sum_by_axis();
+        let content_box_size = sides.vec2_to_flex_relative(style.content_box_size(
+            cbis,
+            containing_block.block_size,
+            &pb,
+        ));
+        let content_min_size = sides.vec2_to_flex_relative(style.content_min_box_size(
+            cbis,
+            containing_block.block_size,
+            &pb,
+        ));
+        let content_max_size = sides.vec2_to_flex_relative(style.content_max_box_size(
+            cbis,
+            containing_block.block_size,
+            &pb,
+        ));
+        let align_self = match style.get_position().align_self {
+            AlignSelf::Auto => flex_context.align_items,
+            AlignSelf::Stretch => AlignItems::Stretch,
+            AlignSelf::FlexStart => AlignItems::FlexStart,
+            AlignSelf::FlexEnd => AlignItems::FlexEnd,
+            AlignSelf::Center => AlignItems::Center,
+            AlignSelf::Baseline => AlignItems::Baseline,
+        };
+        let has_auto_cross_margin = {
+            let margin = sides.sides_to_flex_relative(margin.clone());
+            margin.cross_start.is_none() || margin.cross_end.is_none()
+        };
+
+        let mut item = FlexItem {
+            box_,
+            content_box_size,
+            content_min_size,
+            content_max_size,
+            padding,
+            border,
+            margin,
+            pbm_auto_is_zero,
+            inline_size_in_column: None,
+            flex_base_size: Au(0),
+            hypothetical_main_size: Au(0),
+            flex_grow: style.get_position().flex_grow.into(),
+            flex_shrink: style.get_position().flex_shrink.into(),
+            align_self,
+        };
+
+        if sides.axis == FlexAxis::Column {
+            // The cross size of an item that will be stretched is definite:
+            // https://drafts.csswg.org/css-flexbox/#definite-sizes
+            let available = cbis - item.pbm_auto_is_zero.cross;
+            let inline_size = item.content_box_size.cross.unwrap_or_else(|| {
+                if align_self == AlignItems::Stretch &&
+                    !has_auto_cross_margin &&
+                    flex_context.container_is_single_line
+                {
+                    available
+                } else {
+                    box_.inline_content_sizes(flex_context.layout_context)
+                        .shrink_to_fit(available)
+                }
+            });
+            item.inline_size_in_column = Some(clamp_between_extremums(
+                inline_size,
+                item.content_min_size.cross,
+                item.content_max_size.cross,
+            ));
+        }
+
+        // https://drafts.csswg.org/css-flexbox/#algo-main-item
+        let flex_basis = match style.get_position().flex_basis {
+            FlexBasis::Content => None,
+            FlexBasis::Size(Size::Auto) => item.content_box_size.main,
+            FlexBasis::Size(Size::LengthPercentage(ref lp)) => lp
+                .maybe_to_used_value(flex_context.container_definite_inner_size.main)
+                .map(|size| match style.get_position().box_sizing {
+                    BoxSizing::ContentBox => size,
+                    BoxSizing::BorderBox => {
+                        let pb = sides.sides_to_flex_relative(pb.clone()).sum_by_axis();
+                        (size - pb.main).max(Au(0))
+                    },
+                }),
+        };
+
+        // FIXME: the automatic minimum size should only apply to items whose
+        // `overflow` is `visible`.
+        //
+        // https://drafts.csswg.org/css-flexbox/#min-size-auto
+        let min_size_is_auto = match sides.vec2_to_flex_relative(style.min_box_size()).main {
+            Size::Auto => true,
+            Size::LengthPercentage(_) => false,
+        };
+        let content_main_sizes = if flex_basis.is_none() || min_size_is_auto {
+            item.content_main_sizes(flex_context)
+        } else {
+            ContentSizes::zero()
+        };
+        let flex_base_size = flex_basis.unwrap_or(content_main_sizes.max_content);
+        if min_size_is_auto {
+            let mut content_size_suggestion = content_main_sizes.min_content;
+            if let Some(specified) = item.content_box_size.main {
+                content_size_suggestion = content_size_suggestion.min(specified)
+            }
+            if let Some(max) = item.content_max_size.main {
+                content_size_suggestion = content_size_suggestion.min(max)
+            }
+            item.content_min_size.main = item.content_min_size.main.max(content_size_suggestion);
+        }
+
+        item.flex_base_size = flex_base_size;
+        item.hypothetical_main_size = clamp_between_extremums(
+            flex_base_size,
+            item.content_min_size.main,
+            item.content_max_size.main,
+        );
+        item
+    }
+
+    /// The intrinsic sizes of the contents of this item in the main axis.
+    ///
+    /// In a column flex container, both are the block size of the item when
+    /// laid out with its inline size.
+    fn content_main_sizes(&self, flex_context: &FlexContext) -> ContentSizes {
+        let layout_context = flex_context.layout_context;
+        match self.inline_size_in_column {
+            None => self.box_.inline_content_sizes(layout_context),
+            Some(inline_size) => {
+                let layout = self.box_.layout(
+                    layout_context,
+                    &ContainingBlock {
+                        inline_size,
+                        block_size: None,
+                        mode: self.box_.style.writing_mode,
+                    },
+                    &mut Vec::new(),
+                );
+                ContentSizes {
+                    min_content: layout.content_block_size,
+                    max_content: layout.content_block_size,
+                }
+            },
+        }
+    }
+
+    /// Lays out this item with the given used main size, and the given used
+    /// cross size if it's already known.
+    fn layout(
+        &self,
+        flex_context: &FlexContext,
+        used_main_size: Au,
+        used_cross_size: Option<Au>,
+    ) -> FlexItemLayoutResult<'a> {
+        let mut absolutely_positioned_fragments = Vec::new();
+        let (inline_size, block_size) = match self.inline_size_in_column {
+            None => (
+                used_main_size,
+                used_cross_size.or_else(|| {
+                    self.content_box_size.cross.map(|size| {
+                        clamp_between_extremums(
+                            size,
+                            self.content_min_size.cross,
+                            self.content_max_size.cross,
+                        )
+                    })
+                }),
+            ),
+            Some(inline_size) => (used_cross_size.unwrap_or(inline_size), Some(used_main_size)),
+        };
+        let layout = self.box_.layout(
+            flex_context.layout_context,
+            &ContainingBlock {
+                inline_size,
+                block_size,
+                mode: self.box_.style.writing_mode,
+            },
+            &mut absolutely_positioned_fragments,
+        );
+        let hypothetical_cross_size = match self.inline_size_in_column {
+            None => block_size.unwrap_or_else(|| {
+                clamp_between_extremums(
+                    layout.content_block_size,
+                    self.content_min_size.cross,
+                    self.content_max_size.cross,
+                )
+            }),
+            Some(_) => inline_size,
+        };
+        FlexItemLayoutResult {
+            hypothetical_cross_size,
+            fragments: layout.fragments,
+            absolutely_positioned_fragments,
+        }
+    }
+
+    fn flex_relative_margin(&self, flex_context: &FlexContext) -> FlexRelativeSides<Option<Au>> {
+        flex_context
+            .sides
+            .sides_to_flex_relative(self.margin.clone())
+    }
+}
+
+/// https://drafts.csswg.org/css-flexbox/#algo-line-break
+fn collect_flex_lines<'a>(
+    flex_context: &FlexContext,
+    flex_items: Vec<FlexItem<'a>>,
+) -> Vec<Vec<FlexItem<'a>>> {
+    let available_main_size = match flex_context.container_definite_inner_size.main {
+        Some(size) if !flex_context.container_is_single_line => size,
+        _ => return vec![flex_items],
+    };
+    let mut lines = Vec::new();
+    let mut line: Vec<FlexItem> = Vec::new();
+    let mut line_main_size = Au(0);
+    for item in flex_items {
+        let outer_size = item.hypothetical_main_size + item.pbm_auto_is_zero.main;
+        if !line.is_empty() && line_main_size + outer_size > available_main_size {
+            lines.push(std::mem::replace(&mut line, Vec::new()));
+            line_main_size = Au(0);
+        }
+        line_main_size += outer_size;
+        line.push(item);
+    }
+    lines.push(line);
+    lines
+}
+
+/// Resolves the main sizes of the items of a line, and lays them out.
+fn layout_flex_line<'a>(flex_context: &FlexContext, items: Vec<FlexItem<'a>>) -> FlexLine<'a> {
+    let item_used_main_sizes = match flex_context.container_definite_inner_size.main {
+        Some(container_main_size) => resolve_flexible_lengths(&items, container_main_size),
+        None => items
+            .iter()
+            .map(|item| item.hypothetical_main_size)
+            .collect(),
+    };
+    let item_layout_results = items
+        .iter()
+        .zip(&item_used_main_sizes)
+        .map(|(item, &used_main_size)| item.layout(flex_context, used_main_size, None))
+        .collect::<Vec<_>>();
+    // FIXME: baseline alignment is treated like `flex-start`.
+    //
+    // https://drafts.csswg.org/css-flexbox/#algo-cross-line
+    let cross_size = items
+        .iter()
+        .zip(&item_layout_results)
+        .map(|(item, result)| result.hypothetical_cross_size + item.pbm_auto_is_zero.cross)
+        .fold(Au(0), Au::max);
+    FlexLine {
+        items,
+        item_used_main_sizes,
+        item_layout_results,
+        cross_size,
+    }
+}
+
+/// https://drafts.csswg.org/css-flexbox/#resolve-flexible-lengths
+fn resolve_flexible_lengths(items: &[FlexItem], container_main_size: Au) -> Vec<Au> {
+    let mut target_main_sizes: Vec<Au> = items.iter().map(|item| item.flex_base_size).collect();
+    let mut frozen = vec![false; items.len()];
+
+    let outer_hypothetical_main_sizes_sum = items.iter().fold(Au(0), |sum, item| {
+        sum + item.hypothetical_main_size + item.pbm_auto_is_zero.main
+    });
+    let grow = outer_hypothetical_main_sizes_sum < container_main_size;
+    let flex_factor = |item: &FlexItem| {
+        if grow {
+            item.flex_grow
+        } else {
+            item.flex_shrink
+        }
+    };
+
+    // Size inflexible items.
+    for (i, item) in items.iter().enumerate() {
+        if flex_factor(item) == 0. ||
+            (grow && item.flex_base_size > item.hypothetical_main_size) ||
+            (!grow && item.flex_base_size < item.hypothetical_main_size)
+        {
+            target_main_sizes[i] = item.hypothetical_main_size;
+            frozen[i] = true;
+        }
+    }
+
+    let remaining_free_space = |target_main_sizes: &[Au], frozen: &[bool]| {
+        items
+            .iter()
+            .enumerate()
+            .fold(container_main_size, |space, (i, item)| {
+                let size = if frozen[i] {
+                    target_main_sizes[i]
+                } else {
+                    item.flex_base_size
+                };
+                space - size - item.pbm_auto_is_zero.main
+            })
+    };
+    let initial_free_space = remaining_free_space(&target_main_sizes, &frozen);
+
+    while frozen.iter().any(|frozen| !frozen) {
+        let mut remaining_free_space = remaining_free_space(&target_main_sizes, &frozen);
+        let unfrozen_flex_factors_sum: f32 = items
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| !frozen[i])
+            .map(|(_, item)| flex_factor(item))
+            .sum();
+        if unfrozen_flex_factors_sum < 1. {
+            let scaled = initial_free_space.scale_by(unfrozen_flex_factors_sum);
+            if scaled.0.abs() < remaining_free_space.0.abs() {
+                remaining_free_space = scaled
+            }
+        }
+
+        if remaining_free_space != Au(0) {
+            if grow {
+                for (i, item) in items.iter().enumerate() {
+                    if !frozen[i] {
+                        let ratio = item.flex_grow / unfrozen_flex_factors_sum;
+                        target_main_sizes[i] =
+                            item.flex_base_size + remaining_free_space.scale_by(ratio);
+                    }
+                }
+            } else {
+                let scaled_flex_shrink_factor =
+                    |item: &FlexItem| item.flex_shrink * item.flex_base_size.to_f32_px();
+                let scaled_flex_shrink_factors_sum: f32 = items
+                    .iter()
+                    .enumerate()
+                    .filter(|&(i, _)| !frozen[i])
+                    .map(|(_, item)| scaled_flex_shrink_factor(item))
+                    .sum();
+                for (i, item) in items.iter().enumerate() {
+                    if !frozen[i] {
+                        let ratio = if scaled_flex_shrink_factors_sum > 0. {
+                            scaled_flex_shrink_factor(item) / scaled_flex_shrink_factors_sum
+                        } else {
+                            0.
+                        };
+                        target_main_sizes[i] =
+                            item.flex_base_size + remaining_free_space.scale_by(ratio);
+                    }
+                }
+            }
+        }
+
+        // Fix min/max violations.
+        let mut total_violation = Au(0);
+        let mut violations = vec![Au(0); items.len()];
+        for (i, item) in items.iter().enumerate() {
+            if !frozen[i] {
+                let unclamped = target_main_sizes[i];
+                let clamped = clamp_between_extremums(
+                    unclamped,
+                    item.content_min_size.main,
+                    item.content_max_size.main,
+                )
+                .max(Au(0));
+                violations[i] = clamped - unclamped;
+                total_violation += violations[i];
+                target_main_sizes[i] = clamped;
+            }
+        }
+
+        // Freeze over-flexed items.
+        for i in 0..items.len() {
+            if frozen[i] {
+                continue;
+            }
+            if total_violation == Au(0) ||
+                (total_violation > Au(0) && violations[i] > Au(0)) ||
+                (total_violation < Au(0) && violations[i] < Au(0))
+            {
+                frozen[i] = true;
+            }
+        }
+    }
+    target_main_sizes
+}
+
+fn outer_main_sizes_sum(items: &[FlexItem], used_main_sizes: &[Au]) -> Au {
+    items
+        .iter()
+        .zip(used_main_sizes)
+        .fold(Au(0), |sum, (item, &size)| {
+            sum + size + item.pbm_auto_is_zero.main
+        })
+}
+
+impl<'a> FlexLine<'a> {
+    /// https://drafts.csswg.org/css-flexbox/#algo-stretch
+    fn stretch_items(&mut self, flex_context: &FlexContext) {
+        for (i, item) in self.items.iter().enumerate() {
+            let margin = item.flex_relative_margin(flex_context);
+            if item.align_self != AlignItems::Stretch ||
+                item.content_box_size.cross.is_some() ||
+                margin.cross_start.is_none() ||
+                margin.cross_end.is_none()
+            {
+                continue;
+            }
+            let stretched_size = clamp_between_extremums(
+                self.cross_size - item.pbm_auto_is_zero.cross,
+                item.content_min_size.cross,
+                item.content_max_size.cross,
+            );
+            if stretched_size != self.item_layout_results[i].hypothetical_cross_size {
+                self.item_layout_results[i] = item.layout(
+                    flex_context,
+                    self.item_used_main_sizes[i],
+                    Some(stretched_size),
+                );
+            }
+        }
+    }
+
+    /// Aligns the items of this line in both axes, and adds their fragments.
+    ///
+    /// https://drafts.csswg.org/css-flexbox/#main-alignment
+    /// https://drafts.csswg.org/css-flexbox/#cross-alignment
+    fn place_items(
+        self,
+        flex_context: &FlexContext,
+        justify_content: JustifyContent,
+        line_cross_start: Au,
+        container_inner_size: &FlexRelativeVec2<Au>,
+        fragments: &mut Vec<Fragment>,
+        absolutely_positioned_fragments: &mut Vec<AbsolutelyPositionedFragment<'a>>,
+    ) {
+        let sides = flex_context.sides;
+        let mut main_free_space = container_inner_size.main -
+            outer_main_sizes_sum(&self.items, &self.item_used_main_sizes);
+        let auto_main_margins_count = self
+            .items
+            .iter()
+            .map(|item| {
+                let margin = item.flex_relative_margin(flex_context);
+                margin.main_start.is_none() as i32 + margin.main_end.is_none() as i32
+            })
+            .sum::<i32>();
+        let auto_main_margin = if main_free_space > Au(0) && auto_main_margins_count > 0 {
+            let size = main_free_space / auto_main_margins_count;
+            main_free_space = Au(0);
+            size
+        } else {
+            Au(0)
+        };
+        let (mut main_position, main_gap) =
+            distribute_free_space(justify_content, main_free_space, self.items.len());
+
+        let FlexLine {
+            items,
+            item_used_main_sizes,
+            item_layout_results,
+            cross_size: line_cross_size,
+        } = self;
+        for ((item, used_main_size), layout_result) in items
+            .iter()
+            .zip(item_used_main_sizes)
+            .zip(item_layout_results)
+        {
+            let style = &item.box_.style;
+            let margin = item.flex_relative_margin(flex_context);
+            let pb = sides.sides_to_flex_relative(&item.padding + &item.border);
+            let used_cross_size = layout_result.hypothetical_cross_size;
+
+            let cross_free_space = line_cross_size - used_cross_size - item.pbm_auto_is_zero.cross;
+            let (margin_cross_start, margin_cross_end) =
+                match (margin.cross_start, margin.cross_end) {
+                    (None, None) => {
+                        let half = (cross_free_space / 2).max(Au(0));
+                        (half, (cross_free_space - half).max(Au(0)))
+                    },
+                    (None, Some(end)) => (cross_free_space.max(Au(0)), end),
+                    (Some(start), None) => (start, cross_free_space.max(Au(0))),
+                    (Some(start), Some(end)) => (start, end),
+                };
+            let cross_offset = if margin.cross_start.is_some() && margin.cross_end.is_some() {
+                match item.align_self {
+                    AlignItems::FlexStart | AlignItems::Stretch | AlignItems::Baseline => Au(0),
+                    AlignItems::FlexEnd => cross_free_space,
+                    AlignItems::Center => cross_free_space / 2,
+                }
+            } else {
+                Au(0)
+            };
+
+            let margin = FlexRelativeSides {
+                main_start: margin.main_start.unwrap_or(auto_main_margin),
+                main_end: margin.main_end.unwrap_or(auto_main_margin),
+                cross_start: margin_cross_start,
+                cross_end: margin_cross_end,
+            };
+            let mut content_rect = sides.rect_to_flow_relative(
+                container_inner_size,
+                FlexRelativeRect {
+                    start_corner: FlexRelativeVec2 {
+                        main: main_position + margin.main_start + pb.main_start,
+                        cross: line_cross_start +
+                            cross_offset +
+                            margin.cross_start +
+                            pb.cross_start,
+                    },
+                    size: FlexRelativeVec2 {
+                        main: used_main_size,
+                        cross: used_cross_size,
+                    },
+                },
+            );
+            main_position += margin.main_start +
+                pb.main_start +
+                used_main_size +
+                pb.main_end +
+                margin.main_end +
+                main_gap;
+
+            content_rect.start_corner +=
+                &relative_adjustement(style, flex_context.containing_block);
+            let mut item_fragments = layout_result.fragments;
+            let mut item_absolutely_positioned_fragments =
+                layout_result.absolutely_positioned_fragments;
+            if style.establishes_containing_block() {
+                AbsolutelyPositionedFragment::in_positioned_containing_block(
+                    flex_context.layout_context,
+                    &item_absolutely_positioned_fragments,
+                    &mut item_fragments,
+                    &content_rect.size,
+                    &item.padding,
+                    style.writing_mode,
+                )
+            } else {
+                adjust_static_positions(
+                    &mut item_absolutely_positioned_fragments,
+                    &content_rect.start_corner,
+                );
+                absolutely_positioned_fragments.extend(item_absolutely_positioned_fragments);
+            }
+            let margin = sides.sides_to_flow_relative(margin);
+            fragments.push(Fragment::Box(BoxFragment {
+                tag: item.box_.tag,
+                style: style.clone(),
+                children: item_fragments,
+                content_rect,
+                padding: item.padding.clone(),
+                border: item.border.clone(),
+                block_margins_collapsed_with_children: CollapsedBlockMargins::from_margin(&margin),
+                margin,
+            }));
+        }
+    }
+}
+
+/// The offset of the first box and the gap between boxes, for the given
+/// distribution of free space among a number of boxes.
+///
+/// https://drafts.csswg.org/css-align/#distribution-values
+fn distribute_free_space(
+    justify_content: JustifyContent,
+    free_space: Au,
+    box_count: usize,
+) -> (Au, Au) {
+    let box_count = box_count as i32;
+    match justify_content {
+        JustifyContent::FlexStart | JustifyContent::Stretch => (Au(0), Au(0)),
+        JustifyContent::FlexEnd => (free_space, Au(0)),
+        JustifyContent::Center => (free_space / 2, Au(0)),
+        JustifyContent::SpaceBetween => {
+            if free_space > Au(0) && box_count > 1 {
+                (Au(0), free_space / (box_count - 1))
+            } else {
+                (Au(0), Au(0))
+            }
+        },
+        JustifyContent::SpaceAround => {
+            if free_space > Au(0) && box_count > 0 {
+                let gap = free_space / box_count;
+                (gap / 2, gap)
+            } else {
+                (free_space / 2, Au(0))
+            }
+        },
+    }
+}
+
+fn align_content_as_justify_content(align_content: AlignContent) -> JustifyContent {
+    match align_content {
+        AlignContent::Stretch => JustifyContent::Stretch,
+        AlignContent::FlexStart => JustifyContent::FlexStart,
+        AlignContent::FlexEnd => JustifyContent::FlexEnd,
+        AlignContent::Center => JustifyContent::Center,
+        AlignContent::SpaceBetween => JustifyContent::SpaceBetween,
+        AlignContent::SpaceAround => JustifyContent::SpaceAround,
+    }
+}
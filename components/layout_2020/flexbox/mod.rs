@@ -0,0 +1,23 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Flex layout.
+//!
+//! https://drafts.csswg.org/css-flexbox/
+
+use crate::formatting_contexts::IndependentFormattingContext;
+use crate::positioned::AbsolutelyPositionedBox;
+
+mod construct;
+mod geom;
+mod layout;
+
+pub(crate) struct FlexContainer {
+    children: Vec<FlexLevelBox>,
+}
+
+pub(crate) enum FlexLevelBox {
+    FlexItem(IndependentFormattingContext),
+    OutOfFlowAbsolutelyPositionedBox(AbsolutelyPositionedBox),
+}
@@ -0,0 +1,376 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Building the box tree of block containers.
+
+use crate::context::LayoutContext;
+use crate::display_list::items::OpaqueNode;
+use crate::dom_traversal::{Contents, TraversalHandler};
+use crate::flow::float::FloatBox;
+use crate::flow::inline::{InlineBox, InlineFormattingContext, InlineLevelBox, TextRun};
+use crate::flow::{BlockContainer, BlockFormattingContext, BlockLevelBox};
+use crate::formatting_contexts::IndependentFormattingContext;
+use crate::positioned::AbsolutelyPositionedBox;
+use crate::style_ext::{ComputedValuesExt, DisplayGeneratingBox, DisplayInside, DisplayOutside};
+use crate::ServoArc;
+use script_layout_interface::wrapper_traits::ThreadSafeLayoutNode;
+use std::mem;
+use style::computed_values::white_space::T as WhiteSpace;
+use style::properties::ComputedValues;
+use style::selector_parser::PseudoElement;
+use style::values::computed::Float;
+
+impl BlockFormattingContext {
+    pub fn construct<Node>(
+        context: &LayoutContext,
+        style: &ServoArc<ComputedValues>,
+        contents: Contents<Node>,
+        tag: OpaqueNode,
+    ) -> Self
+    where
+        Node: ThreadSafeLayoutNode,
+    {
+        BlockFormattingContext {
+            contents: BlockContainer::construct(context, style, contents, tag),
+        }
+    }
+
+    /// An anonymous block formatting context containing the given text runs,
+    /// of which white space hasn't been processed yet.
+    pub fn construct_for_text_runs(
+        style: &ServoArc<ComputedValues>,
+        runs: impl Iterator<Item = TextRun>,
+    ) -> Self {
+        let mut at_collapsible_white_space = true;
+        let inline_level_boxes = runs
+            .filter_map(|run| {
+                let white_space = run.parent_style.get_inherited_text().white_space;
+                let text = collapse_white_space(&run.text, white_space, at_collapsible_white_space);
+                if text.is_empty() {
+                    return None;
+                }
+                at_collapsible_white_space = !white_space.preserve_spaces() &&
+                    text.ends_with(|c: char| c == ' ' || c == '\n');
+                Some(InlineLevelBox::TextRun(TextRun { text, ..run }))
+            })
+            .collect();
+        BlockFormattingContext {
+            contents: BlockContainer::InlineFormattingContext(InlineFormattingContext {
+                style: style.clone(),
+                inline_level_boxes,
+            }),
+        }
+    }
+}
+
+impl BlockContainer {
+    pub fn construct<Node>(
+        context: &LayoutContext,
+        block_container_style: &ServoArc<ComputedValues>,
+        contents: Contents<Node>,
+        tag: OpaqueNode,
+    ) -> Self
+    where
+        Node: ThreadSafeLayoutNode,
+    {
+        let mut builder = BlockContainerBuilder {
+            context,
+            block_container_style,
+            tag,
+            block_level_boxes: Vec::new(),
+            ongoing_inline_formatting_context: Vec::new(),
+            ongoing_inline_boxes_stack: Vec::new(),
+            anonymous_style: None,
+            at_collapsible_white_space: true,
+        };
+        contents.traverse(context, block_container_style, tag, &mut builder);
+        builder.finish::<Node>()
+    }
+}
+
+struct BlockContainerBuilder<'a> {
+    context: &'a LayoutContext<'a>,
+    block_container_style: &'a ServoArc<ComputedValues>,
+    tag: OpaqueNode,
+
+    block_level_boxes: Vec<BlockLevelBox>,
+
+    /// The inline-level boxes of the inline formatting context being built,
+    /// if any, which goes in an anonymous block box when a block-level box
+    /// follows it.
+    ongoing_inline_formatting_context: Vec<InlineLevelBox>,
+
+    /// The inline boxes the next inline-level box goes in, innermost last.
+    ongoing_inline_boxes_stack: Vec<InlineBox>,
+
+    /// The style of the anonymous block boxes, computed lazily.
+    anonymous_style: Option<ServoArc<ComputedValues>>,
+
+    /// Whether the ongoing inline formatting context starts here or ends with
+    /// white space, so that collapsible white space that follows is removed.
+    at_collapsible_white_space: bool,
+}
+
+impl<'a, Node> TraversalHandler<Node> for BlockContainerBuilder<'a>
+where
+    Node: ThreadSafeLayoutNode,
+{
+    fn handle_text(
+        &mut self,
+        text: String,
+        parent_style: &ServoArc<ComputedValues>,
+        tag: OpaqueNode,
+    ) {
+        let white_space = parent_style.get_inherited_text().white_space;
+        let text = collapse_white_space(&text, white_space, self.at_collapsible_white_space);
+        if text.is_empty() {
+            return;
+        }
+        self.at_collapsible_white_space =
+            !white_space.preserve_spaces() && text.ends_with(|c: char| c == ' ' || c == '\n');
+        self.current_inline_level_boxes()
+            .push(InlineLevelBox::TextRun(TextRun {
+                tag,
+                parent_style: parent_style.clone(),
+                text,
+            }));
+    }
+
+    fn handle_element(
+        &mut self,
+        style: &ServoArc<ComputedValues>,
+        display: DisplayGeneratingBox,
+        contents: Contents<Node>,
+        tag: OpaqueNode,
+    ) {
+        let DisplayGeneratingBox::OutsideInside { outside, inside } = display;
+        if style.is_absolutely_positioned() {
+            let box_ = AbsolutelyPositionedBox {
+                contents: IndependentFormattingContext::construct(
+                    self.context,
+                    style.clone(),
+                    inside,
+                    contents,
+                    tag,
+                ),
+            };
+            if self.has_ongoing_inline_formatting_context() {
+                self.current_inline_level_boxes()
+                    .push(InlineLevelBox::OutOfFlowAbsolutelyPositionedBox(box_));
+            } else {
+                self.block_level_boxes
+                    .push(BlockLevelBox::OutOfFlowAbsolutelyPositionedBox(box_));
+            }
+            return;
+        }
+        if style.get_box().float != Float::None {
+            let box_ = FloatBox {
+                contents: IndependentFormattingContext::construct(
+                    self.context,
+                    style.clone(),
+                    inside,
+                    contents,
+                    tag,
+                ),
+            };
+            if self.has_ongoing_inline_formatting_context() {
+                self.current_inline_level_boxes()
+                    .push(InlineLevelBox::OutOfFlowFloatBox(box_));
+            } else {
+                self.block_level_boxes
+                    .push(BlockLevelBox::OutOfFlowFloatBox(box_));
+            }
+            return;
+        }
+
+        match (outside, inside) {
+            (DisplayOutside::Inline, DisplayInside::Flow) => {
+                self.ongoing_inline_boxes_stack.push(InlineBox {
+                    tag,
+                    style: style.clone(),
+                    first_fragment: true,
+                    last_fragment: false,
+                    children: Vec::new(),
+                });
+                contents.traverse(self.context, style, tag, self);
+                let mut inline_box = self.ongoing_inline_boxes_stack.pop().unwrap();
+                inline_box.last_fragment = true;
+                self.current_inline_level_boxes()
+                    .push(InlineLevelBox::InlineBox(inline_box));
+            },
+            (DisplayOutside::Inline, DisplayInside::FlowRoot) |
+            (DisplayOutside::Inline, DisplayInside::Flex) => {
+                let box_ = IndependentFormattingContext::construct(
+                    self.context,
+                    style.clone(),
+                    inside,
+                    contents,
+                    tag,
+                );
+                self.current_inline_level_boxes()
+                    .push(InlineLevelBox::Atomic(box_));
+                self.at_collapsible_white_space = false;
+            },
+            (DisplayOutside::Block, _) => {
+                self.end_ongoing_inline_formatting_context::<Node>();
+                let box_ = match inside {
+                    DisplayInside::Flow => BlockLevelBox::SameFormattingContextBlock {
+                        tag,
+                        style: style.clone(),
+                        contents: BlockContainer::construct(self.context, style, contents, tag),
+                    },
+                    DisplayInside::FlowRoot | DisplayInside::Flex => {
+                        BlockLevelBox::Independent(IndependentFormattingContext::construct(
+                            self.context,
+                            style.clone(),
+                            inside,
+                            contents,
+                            tag,
+                        ))
+                    },
+                };
+                self.block_level_boxes.push(box_);
+            },
+        }
+    }
+}
+
+impl<'a> BlockContainerBuilder<'a> {
+    fn has_ongoing_inline_formatting_context(&self) -> bool {
+        !self.ongoing_inline_formatting_context.is_empty() ||
+            !self.ongoing_inline_boxes_stack.is_empty()
+    }
+
+    fn current_inline_level_boxes(&mut self) -> &mut Vec<InlineLevelBox> {
+        match self.ongoing_inline_boxes_stack.last_mut() {
+            Some(inline_box) => &mut inline_box.children,
+            None => &mut self.ongoing_inline_formatting_context,
+        }
+    }
+
+    fn anonymous_style<Node>(&mut self) -> ServoArc<ComputedValues>
+    where
+        Node: ThreadSafeLayoutNode,
+    {
+        let context = self.context;
+        let block_container_style = self.block_container_style;
+        self.anonymous_style
+            .get_or_insert_with(|| {
+                let shared_context = context.shared_context();
+                shared_context
+                    .stylist
+                    .style_for_anonymous::<Node::ConcreteElement>(
+                        &shared_context.guards,
+                        &PseudoElement::ServoAnonymousBlock,
+                        block_container_style,
+                    )
+            })
+            .clone()
+    }
+
+    /// Wraps the inline formatting context built so far in an anonymous
+    /// block box, splitting the inline boxes it is in.
+    ///
+    /// https://drafts.csswg.org/css2/visuren.html#anonymous-block-level
+    fn end_ongoing_inline_formatting_context<Node>(&mut self)
+    where
+        Node: ThreadSafeLayoutNode,
+    {
+        if !self.has_ongoing_inline_formatting_context() {
+            return;
+        }
+
+        // The parts of the inline boxes before the block-level box end here,
+        // and the parts after it start with empty children.
+        let mut fragmented: Option<InlineBox> = None;
+        for inline_box in self.ongoing_inline_boxes_stack.iter_mut().rev() {
+            let mut fragmented_box = InlineBox {
+                tag: inline_box.tag,
+                style: inline_box.style.clone(),
+                first_fragment: inline_box.first_fragment,
+                last_fragment: false,
+                children: mem::replace(&mut inline_box.children, Vec::new()),
+            };
+            inline_box.first_fragment = false;
+            if let Some(child) = fragmented.take() {
+                fragmented_box
+                    .children
+                    .push(InlineLevelBox::InlineBox(child));
+            }
+            fragmented = Some(fragmented_box);
+        }
+        if let Some(fragmented) = fragmented {
+            self.ongoing_inline_formatting_context
+                .push(InlineLevelBox::InlineBox(fragmented));
+        }
+
+        let inline_level_boxes =
+            mem::replace(&mut self.ongoing_inline_formatting_context, Vec::new());
+        let anonymous_style = self.anonymous_style::<Node>();
+        self.block_level_boxes
+            .push(BlockLevelBox::SameFormattingContextBlock {
+                tag: self.tag,
+                contents: BlockContainer::InlineFormattingContext(InlineFormattingContext {
+                    style: anonymous_style.clone(),
+                    inline_level_boxes,
+                }),
+                style: anonymous_style,
+            });
+        self.at_collapsible_white_space = true;
+    }
+
+    fn finish<Node>(mut self) -> BlockContainer
+    where
+        Node: ThreadSafeLayoutNode,
+    {
+        debug_assert!(self.ongoing_inline_boxes_stack.is_empty());
+        if self.block_level_boxes.is_empty() {
+            return BlockContainer::InlineFormattingContext(InlineFormattingContext {
+                style: self.block_container_style.clone(),
+                inline_level_boxes: self.ongoing_inline_formatting_context,
+            });
+        }
+        self.end_ongoing_inline_formatting_context::<Node>();
+        BlockContainer::BlockLevelBoxes(self.block_level_boxes)
+    }
+}
+
+/// Processes white space in text as the `white-space` property says.
+///
+/// https://drafts.csswg.org/css-text/#white-space-phase-1
+fn collapse_white_space(
+    text: &str,
+    white_space: WhiteSpace,
+    mut at_collapsible_white_space: bool,
+) -> String {
+    if white_space.preserve_spaces() {
+        return text.to_owned();
+    }
+    let preserve_newlines = white_space.preserve_newlines();
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\n' if preserve_newlines => {
+                // Spaces before a preserved newline are removed, and so are
+                // the spaces after it.
+                while result.ends_with(' ') {
+                    result.pop();
+                }
+                result.push('\n');
+                at_collapsible_white_space = true;
+            },
+            ' ' | '\t' | '\n' | '\r' | '\x0C' => {
+                if !at_collapsible_white_space {
+                    result.push(' ');
+                    at_collapsible_white_space = true;
+                }
+            },
+            _ => {
+                result.push(c);
+                at_collapsible_white_space = false;
+            },
+        }
+    }
+    result
+}
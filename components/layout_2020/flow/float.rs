@@ -0,0 +1,306 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Floats, and the space they take away from line boxes and from block-level
+//! boxes establishing an independent formatting context.
+//!
+//! https://drafts.csswg.org/css2/visuren.html#floats
+
+use crate::context::LayoutContext;
+use crate::formatting_contexts::IndependentFormattingContext;
+use crate::fragments::{BoxFragment, CollapsedBlockMargins, CollapsedMargin, Fragment};
+use crate::geom::flow_relative::{Rect, Vec2};
+use crate::positioned::AbsolutelyPositionedFragment;
+use crate::sizing::clamp_between_extremums;
+use crate::style_ext::ComputedValuesExt;
+use crate::ContainingBlock;
+use app_units::Au;
+use style::values::computed::{Clear, Float};
+
+pub(crate) struct FloatBox {
+    pub contents: IndependentFormattingContext,
+}
+
+struct PlacedFloat {
+    side: Float,
+    /// The margin box of the float, relative to the content box of the
+    /// formatting context root.
+    rect: Rect<Au>,
+}
+
+/// The floats of a block formatting context, and where the box currently
+/// being laid out goes.
+///
+/// All positions are relative to the content box of the formatting context
+/// root.
+pub(crate) struct FloatContext {
+    floats: Vec<PlacedFloat>,
+
+    /// Where the next box goes, before the adjoining margins in
+    /// `pending_margin`. The inline position is the start of the content box
+    /// of its containing block.
+    pub position: Vec2<Au>,
+
+    /// The margins that may still collapse with the start margin of the next
+    /// box.
+    pub pending_margin: CollapsedMargin,
+
+    /// The top of a float can't be higher than the top of an earlier float.
+    ceiling: Au,
+
+    /// The fragments of the placed floats.
+    pub fragments: Vec<Fragment>,
+}
+
+impl FloatContext {
+    pub fn new() -> Self {
+        FloatContext {
+            floats: Vec::new(),
+            position: Vec2::zero(),
+            pending_margin: CollapsedMargin::zero(),
+            ceiling: Au(0),
+            fragments: Vec::new(),
+        }
+    }
+
+    /// Where the next box goes, assuming its start margin is zero.
+    pub fn current_block_position(&self) -> Au {
+        self.position.block + self.pending_margin.solve()
+    }
+
+    fn overlapping_floats<'a>(
+        &'a self,
+        block_start: Au,
+        block_size: Au,
+    ) -> impl Iterator<Item = &'a PlacedFloat> + 'a {
+        self.floats.iter().filter(move |float| {
+            let float_start = float.rect.start_corner.block;
+            let float_end = float_start + float.rect.size.block;
+            if block_size == Au(0) {
+                float_start <= block_start && block_start < float_end
+            } else {
+                float_start < block_start + block_size && block_start < float_end
+            }
+        })
+    }
+
+    /// The part of the given inline range that isn't covered by the floats
+    /// between `block_start` and `block_start + block_size`.
+    pub fn available_inline_range(
+        &self,
+        block_start: Au,
+        block_size: Au,
+        inline_start: Au,
+        inline_size: Au,
+    ) -> (Au, Au) {
+        let mut start = inline_start;
+        let mut end = inline_start + inline_size;
+        for float in self.overlapping_floats(block_start, block_size) {
+            match float.side {
+                Float::Left => {
+                    start = start.max(float.rect.start_corner.inline + float.rect.size.inline)
+                },
+                Float::Right => end = end.min(float.rect.start_corner.inline),
+                Float::None => unreachable!(),
+            }
+        }
+        (start, end.max(start))
+    }
+
+    /// The closest block position after `block_start` where one of the
+    /// floats between `block_start` and `block_start + block_size` ends,
+    /// if any.
+    pub fn next_float_end(&self, block_start: Au, block_size: Au) -> Option<Au> {
+        self.overlapping_floats(block_start, block_size)
+            .map(|float| float.rect.start_corner.block + float.rect.size.block)
+            .min()
+    }
+
+    /// Where the border box of a box with the given `clear` value has to
+    /// start, if it has to move below some floats.
+    pub fn clearance_position(&self, clear: Clear) -> Option<Au> {
+        self.floats
+            .iter()
+            .filter(|float| match (clear, float.side) {
+                (Clear::Both, _) | (Clear::Left, Float::Left) | (Clear::Right, Float::Right) => {
+                    true
+                },
+                _ => false,
+            })
+            .map(|float| float.rect.start_corner.block + float.rect.size.block)
+            .max()
+    }
+
+    /// Where the last float ends.
+    pub fn floats_block_end(&self) -> Au {
+        self.floats
+            .iter()
+            .map(|float| float.rect.start_corner.block + float.rect.size.block)
+            .max()
+            .unwrap_or(Au(0))
+    }
+
+    /// Places a float with the given margin box size, as high as possible
+    /// and then as far as possible towards its side, and returns the
+    /// position of its margin box.
+    ///
+    /// https://drafts.csswg.org/css2/visuren.html#float-position
+    pub fn place(
+        &mut self,
+        side: Float,
+        clear: Clear,
+        margin_box_size: &Vec2<Au>,
+        block_start: Au,
+        inline_start: Au,
+        inline_size: Au,
+    ) -> Vec2<Au> {
+        let mut block_position = block_start.max(self.ceiling);
+        if let Some(clearance_position) = self.clearance_position(clear) {
+            block_position = block_position.max(clearance_position);
+        }
+        let (start, end) = loop {
+            let (start, end) = self.available_inline_range(
+                block_position,
+                margin_box_size.block,
+                inline_start,
+                inline_size,
+            );
+            if end - start >= margin_box_size.inline {
+                break (start, end);
+            }
+            match self.next_float_end(block_position, margin_box_size.block) {
+                Some(next_position) => block_position = next_position,
+                // Too wide for its containing block even without floats.
+                None => break (start, end),
+            }
+        };
+        let position = Vec2 {
+            inline: match side {
+                Float::Right => end - margin_box_size.inline,
+                Float::Left | Float::None => start,
+            },
+            block: block_position,
+        };
+        self.ceiling = block_position;
+        self.floats.push(PlacedFloat {
+            side,
+            rect: Rect {
+                start_corner: position.clone(),
+                size: margin_box_size.clone(),
+            },
+        });
+        position
+    }
+}
+
+impl FloatBox {
+    /// Lays out and places this float at the current position of the float
+    /// context, and returns where its content box starts.
+    ///
+    /// Its fragment is added to the float context rather than to the
+    /// fragments of its parent.
+    ///
+    /// https://drafts.csswg.org/css2/visudet.html#float-width
+    /// https://drafts.csswg.org/css2/visudet.html#block-root-margin
+    pub fn layout<'a>(
+        &'a self,
+        layout_context: &LayoutContext,
+        containing_block: &ContainingBlock,
+        absolutely_positioned_fragments: &mut Vec<AbsolutelyPositionedFragment<'a>>,
+        float_context: &mut FloatContext,
+    ) -> Vec2<Au> {
+        let style = &self.contents.style;
+        let cbis = containing_block.inline_size;
+        let padding = style.padding().percentages_relative_to(cbis);
+        let border = style.border_width();
+        let margin = style
+            .margin()
+            .percentages_relative_to(cbis)
+            .map(|m| m.unwrap_or(Au(0)));
+        let pb = &padding + &border;
+        let box_size = style.content_box_size(cbis, containing_block.block_size, &pb);
+        let min_box_size = style.content_min_box_size(cbis, containing_block.block_size, &pb);
+        let max_box_size = style.content_max_box_size(cbis, containing_block.block_size, &pb);
+
+        let inline_size = box_size.inline.unwrap_or_else(|| {
+            let available_size = cbis - pb.inline_sum() - margin.inline_sum();
+            self.contents
+                .inline_content_sizes(layout_context)
+                .shrink_to_fit(available_size)
+        });
+        let inline_size =
+            clamp_between_extremums(inline_size, min_box_size.inline, max_box_size.inline);
+        let block_size = box_size
+            .block
+            .map(|size| clamp_between_extremums(size, min_box_size.block, max_box_size.block));
+        let containing_block_for_children = ContainingBlock {
+            inline_size,
+            block_size,
+            mode: style.writing_mode,
+        };
+
+        let mut nested_abspos = vec![];
+        let establishes_containing_block = style.establishes_containing_block();
+        let mut independent_layout = self.contents.layout(
+            layout_context,
+            &containing_block_for_children,
+            if establishes_containing_block {
+                &mut nested_abspos
+            } else {
+                absolutely_positioned_fragments
+            },
+        );
+        let block_size = block_size.unwrap_or_else(|| {
+            clamp_between_extremums(
+                independent_layout.content_block_size,
+                min_box_size.block,
+                max_box_size.block,
+            )
+        });
+
+        let margin_box_size = Vec2 {
+            inline: inline_size + pb.inline_sum() + margin.inline_sum(),
+            block: block_size + pb.block_sum() + margin.block_sum(),
+        };
+        let block_start = float_context.current_block_position();
+        let inline_start = float_context.position.inline;
+        let margin_box_start = float_context.place(
+            style.get_box().float,
+            style.get_box().clear,
+            &margin_box_size,
+            block_start,
+            inline_start,
+            cbis,
+        );
+        let content_rect = Rect {
+            start_corner: &(&margin_box_start + &margin.start_corner()) + &pb.start_corner(),
+            size: Vec2 {
+                inline: inline_size,
+                block: block_size,
+            },
+        };
+        if establishes_containing_block {
+            AbsolutelyPositionedFragment::in_positioned_containing_block(
+                layout_context,
+                &nested_abspos,
+                &mut independent_layout.fragments,
+                &content_rect.size,
+                &padding,
+                style.writing_mode,
+            )
+        }
+        let content_start = content_rect.start_corner.clone();
+        float_context.fragments.push(Fragment::Box(BoxFragment {
+            tag: self.contents.tag,
+            style: style.clone(),
+            children: independent_layout.fragments,
+            content_rect,
+            padding,
+            border,
+            block_margins_collapsed_with_children: CollapsedBlockMargins::from_margin(&margin),
+            margin,
+        }));
+        content_start
+    }
+}
@@ -0,0 +1,919 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Inline formatting contexts: breaking inline-level content into lines.
+//!
+//! https://drafts.csswg.org/css2/visuren.html#inline-formatting
+
+use crate::context::{with_thread_local_font_context, LayoutContext};
+use crate::display_list::items::OpaqueNode;
+use crate::flow::float::{FloatBox, FloatContext};
+use crate::flow::FlowLayout;
+use crate::formatting_contexts::IndependentFormattingContext;
+use crate::fragments::{AnonymousFragment, BoxFragment, CollapsedBlockMargins, CollapsedMargin};
+use crate::fragments::{Fragment, TextFragment};
+use crate::geom::flow_relative::{Rect, Sides, Vec2};
+use crate::positioned::AbsolutelyPositionedFragment;
+use crate::positioned::{relative_adjustement, AbsolutelyPositionedBox};
+use crate::sizing::{clamp_between_extremums, ContentSizes};
+use crate::style_ext::ComputedValuesExt;
+use crate::{ContainingBlock, ServoArc};
+use app_units::Au;
+use gfx::font::{FontMetrics, ShapingFlags, ShapingOptions};
+use gfx::text::glyph::{ByteIndex, GlyphStore};
+use gfx::text::text_run::{GlyphRun, TextRun as ShapedTextRun};
+use ordered_float::NotNan;
+use range::Range;
+use std::mem;
+use std::sync::Arc;
+use style::properties::ComputedValues;
+use style::values::computed::TextAlign;
+use style::values::generics::text::LineHeight;
+use unicode_script::Script;
+use webrender_api::FontInstanceKey;
+
+pub(crate) struct InlineFormattingContext {
+    /// The style of the block container establishing this context, which
+    /// gives the strut of its lines and their alignment.
+    pub style: ServoArc<ComputedValues>,
+    pub inline_level_boxes: Vec<InlineLevelBox>,
+}
+
+pub(crate) enum InlineLevelBox {
+    InlineBox(InlineBox),
+    TextRun(TextRun),
+    OutOfFlowAbsolutelyPositionedBox(AbsolutelyPositionedBox),
+    OutOfFlowFloatBox(FloatBox),
+    Atomic(IndependentFormattingContext),
+}
+
+/// An inline box, or the part of it on one side of a block-level box that
+/// splits it.
+pub(crate) struct InlineBox {
+    pub tag: OpaqueNode,
+    pub style: ServoArc<ComputedValues>,
+    pub first_fragment: bool,
+    pub last_fragment: bool,
+    pub children: Vec<InlineLevelBox>,
+}
+
+/// Text with white space already processed.
+pub(crate) struct TextRun {
+    pub tag: OpaqueNode,
+    pub parent_style: ServoArc<ComputedValues>,
+    pub text: String,
+}
+
+/// A text run shaped with the first available font of its style.
+struct ShapedText {
+    metrics: FontMetrics,
+    font_key: FontInstanceKey,
+    /// Glyph runs between forced line breaks. Consecutive glyph runs are
+    /// separated by soft wrap opportunities.
+    segments: Vec<Vec<GlyphRun>>,
+}
+
+/// The space a box takes above and below the baseline, leading included.
+///
+/// https://drafts.csswg.org/css2/visudet.html#leading
+#[derive(Clone, Copy)]
+struct Strut {
+    above: Au,
+    below: Au,
+}
+
+impl Strut {
+    fn new(style: &ComputedValues, metrics: &FontMetrics) -> Self {
+        let line_height = line_height(style, metrics);
+        let half_leading = (line_height - (metrics.ascent + metrics.descent)) / 2;
+        let above = metrics.ascent + half_leading;
+        Strut {
+            above,
+            below: line_height - above,
+        }
+    }
+}
+
+fn line_height(style: &ComputedValues, metrics: &FontMetrics) -> Au {
+    let font_size = style.get_font().font_size.size();
+    match style.get_inherited_text().line_height {
+        LineHeight::Normal => metrics.line_gap,
+        LineHeight::Number(n) => font_size.scale_by(n.0),
+        LineHeight::Length(l) => Au::from(l),
+    }
+}
+
+fn font_metrics(layout_context: &LayoutContext, style: &ComputedValues) -> Option<FontMetrics> {
+    with_thread_local_font_context(layout_context, |font_context| {
+        let font_group = font_context.font_group(style.clone_font());
+        let font = font_group.borrow_mut().first(font_context)?;
+        let metrics = font.borrow().metrics.clone();
+        Some(metrics)
+    })
+}
+
+fn glyph_store_advance(glyph_store: &GlyphStore) -> Au {
+    glyph_store.advance_for_byte_range(&Range::new(ByteIndex(0), glyph_store.len()), Au(0))
+}
+
+impl TextRun {
+    fn shape(&self, layout_context: &LayoutContext) -> Option<ShapedText> {
+        let preserve_newlines = self
+            .parent_style
+            .get_inherited_text()
+            .white_space
+            .preserve_newlines();
+        with_thread_local_font_context(layout_context, |font_context| {
+            let font_group = font_context.font_group(self.parent_style.clone_font());
+            let font = font_group.borrow_mut().first(font_context)?;
+            let mut font = font.borrow_mut();
+            let options = ShapingOptions {
+                letter_spacing: None,
+                word_spacing: (Au(0), NotNan::new(0.).unwrap()),
+                script: Script::Common,
+                flags: ShapingFlags::empty(),
+            };
+            let segments = if preserve_newlines {
+                self.text
+                    .split('\n')
+                    .map(|segment| {
+                        ShapedTextRun::break_and_shape(&mut font, segment, &options, &mut None).0
+                    })
+                    .collect()
+            } else {
+                vec![ShapedTextRun::break_and_shape(&mut font, &self.text, &options, &mut None).0]
+            };
+            Some(ShapedText {
+                metrics: font.metrics.clone(),
+                font_key: font.font_key,
+                segments,
+            })
+        })
+    }
+}
+
+/// Text glyphs accumulated on the current line, not yet made into a
+/// fragment.
+struct PendingText {
+    start: Au,
+    width: Au,
+    glyphs: Vec<Arc<GlyphStore>>,
+}
+
+/// The part of an inline box on the current line.
+///
+/// Its inline positions are relative to the start of the line, and its
+/// block positions to the baseline.
+struct PartialInlineBoxFragment {
+    tag: OpaqueNode,
+    style: ServoArc<ComputedValues>,
+    last_box_tree_fragment: bool,
+    start_position: Au,
+    ascent: Au,
+    descent: Au,
+    strut: Strut,
+    padding: Sides<Au>,
+    border: Sides<Au>,
+    margin: Sides<Au>,
+    children: Vec<Fragment>,
+}
+
+struct Line {
+    /// The position of the line, relative to the content box of the block
+    /// container.
+    block_start: Au,
+    inline_start: Au,
+    available_inline_size: Au,
+    /// The inline position of the next item, relative to the start of the line.
+    position: Au,
+    /// The size of the collapsible white space at the end of the line,
+    /// which hangs rather than being counted for alignment.
+    trailing_whitespace: Au,
+    has_content: bool,
+    max_above: Au,
+    max_below: Au,
+    /// Inline positions relative to the start of the line, block positions
+    /// to the baseline.
+    fragments: Vec<Fragment>,
+    /// Indices of the absolutely positioned boxes whose static position is
+    /// relative to this line, and whether it is relative to its top rather
+    /// than its baseline.
+    absolutely_positioned: Vec<(usize, bool)>,
+}
+
+struct InlineFormattingContextState<'box_tree, 'a> {
+    containing_block: &'a ContainingBlock,
+    absolutely_positioned_fragments: &'a mut Vec<AbsolutelyPositionedFragment<'box_tree>>,
+    float_context: &'a mut FloatContext,
+    /// Where the content box of the block container starts, relative to the
+    /// formatting context root.
+    origin: Vec2<Au>,
+    strut: Strut,
+    text_align: TextAlign,
+    allow_wrap: bool,
+    stack: Vec<PartialInlineBoxFragment>,
+    line: Line,
+    lines: Vec<Fragment>,
+    pending_floats: Vec<&'box_tree FloatBox>,
+}
+
+impl InlineFormattingContext {
+    pub(super) fn layout<'a>(
+        &'a self,
+        layout_context: &LayoutContext,
+        containing_block: &ContainingBlock,
+        absolutely_positioned_fragments: &mut Vec<AbsolutelyPositionedFragment<'a>>,
+        float_context: &mut FloatContext,
+    ) -> FlowLayout {
+        let strut = font_metrics(layout_context, &self.style)
+            .map(|metrics| Strut::new(&self.style, &metrics))
+            .unwrap_or(Strut {
+                above: Au(0),
+                below: Au(0),
+            });
+        let origin = Vec2 {
+            inline: float_context.position.inline,
+            block: float_context.current_block_position(),
+        };
+        let inherited_text = self.style.get_inherited_text();
+        let text_indent = inherited_text
+            .text_indent
+            .to_used_value(containing_block.inline_size);
+        let mut state = InlineFormattingContextState {
+            containing_block,
+            absolutely_positioned_fragments,
+            float_context,
+            origin,
+            strut,
+            text_align: inherited_text.text_align,
+            allow_wrap: inherited_text.white_space.allow_wrap(),
+            stack: Vec::new(),
+            line: Line {
+                block_start: Au(0),
+                inline_start: Au(0),
+                available_inline_size: Au(0),
+                position: text_indent,
+                trailing_whitespace: Au(0),
+                has_content: false,
+                max_above: Au(0),
+                max_below: Au(0),
+                fragments: Vec::new(),
+                absolutely_positioned: Vec::new(),
+            },
+            lines: Vec::new(),
+            pending_floats: Vec::new(),
+        };
+        state.update_line_inline_range();
+        state.layout_boxes(layout_context, &self.inline_level_boxes);
+        state.finish_line(layout_context);
+
+        let content_block_size = state.line.block_start;
+        FlowLayout {
+            fragments: state.lines,
+            content_block_size,
+            collapsible_margins_in_children: CollapsedBlockMargins {
+                collapsed_through: content_block_size == Au(0),
+                ..CollapsedBlockMargins::zero()
+            },
+        }
+    }
+
+    pub(super) fn inline_content_sizes(&self, layout_context: &LayoutContext) -> ContentSizes {
+        let mut computation = ContentSizesComputation {
+            layout_context,
+            result: ContentSizes::zero(),
+            current_line: Au(0),
+            current_unbreakable: Au(0),
+        };
+        computation.traverse(&self.inline_level_boxes);
+        computation.forced_line_break();
+        computation.result
+    }
+}
+
+impl<'box_tree, 'a> InlineFormattingContextState<'box_tree, 'a> {
+    fn layout_boxes(&mut self, layout_context: &LayoutContext, boxes: &'box_tree [InlineLevelBox]) {
+        for box_ in boxes {
+            match box_ {
+                InlineLevelBox::InlineBox(inline_box) => {
+                    self.start_inline_box(layout_context, inline_box);
+                    self.layout_boxes(layout_context, &inline_box.children);
+                    self.finish_inline_box();
+                },
+                InlineLevelBox::TextRun(run) => self.layout_text_run(layout_context, run),
+                InlineLevelBox::Atomic(atomic) => self.layout_atomic(layout_context, atomic),
+                InlineLevelBox::OutOfFlowAbsolutelyPositionedBox(box_) => {
+                    let index = self.absolutely_positioned_fragments.len();
+                    self.absolutely_positioned_fragments.push(box_.layout(Vec2 {
+                        inline: self.line.position,
+                        block: Au(0),
+                    }));
+                    self.line.absolutely_positioned.push((index, true));
+                },
+                InlineLevelBox::OutOfFlowFloatBox(float_box) => {
+                    if self.line.has_content {
+                        self.pending_floats.push(float_box);
+                    } else {
+                        self.place_float(layout_context, float_box);
+                        self.update_line_inline_range();
+                    }
+                },
+            }
+        }
+    }
+
+    /// Places a float at the top of the current line.
+    fn place_float(&mut self, layout_context: &LayoutContext, float_box: &'box_tree FloatBox) {
+        self.float_context.position = Vec2 {
+            inline: self.origin.inline,
+            block: self.origin.block + self.line.block_start,
+        };
+        self.float_context.pending_margin = CollapsedMargin::zero();
+        let tree_rank = self.absolutely_positioned_fragments.len();
+        let content_start = float_box.layout(
+            layout_context,
+            self.containing_block,
+            self.absolutely_positioned_fragments,
+            self.float_context,
+        );
+        let offset = &content_start - &self.origin;
+        for abspos in &mut self.absolutely_positioned_fragments[tree_rank..] {
+            abspos.static_position += &offset;
+        }
+    }
+
+    /// Makes the current line avoid the floats next to it.
+    fn update_line_inline_range(&mut self) {
+        let (start, end) = self.float_context.available_inline_range(
+            self.origin.block + self.line.block_start,
+            self.strut.above + self.strut.below,
+            self.origin.inline,
+            self.containing_block.inline_size,
+        );
+        self.line.inline_start = start - self.origin.inline;
+        self.line.available_inline_size = end - start;
+    }
+
+    /// Moves the current line, which must be empty, below floats until
+    /// an item of the given size fits on it.
+    fn fit_on_empty_line(&mut self, inline_size: Au) {
+        while self.line.position + inline_size > self.line.available_inline_size {
+            let next_float_end = self.float_context.next_float_end(
+                self.origin.block + self.line.block_start,
+                self.strut.above + self.strut.below,
+            );
+            match next_float_end {
+                Some(block_position) => {
+                    self.line.block_start = block_position - self.origin.block;
+                    self.update_line_inline_range();
+                },
+                None => break,
+            }
+        }
+    }
+
+    /// Breaks the current line if an item of the given size doesn't fit on
+    /// it.
+    fn break_line_before(
+        &mut self,
+        layout_context: &LayoutContext,
+        inline_size: Au,
+        allow_wrap: bool,
+    ) {
+        if allow_wrap &&
+            self.line.has_content &&
+            self.line.position + inline_size > self.line.available_inline_size
+        {
+            self.finish_line(layout_context);
+        }
+        if !self.line.has_content {
+            self.fit_on_empty_line(inline_size);
+        }
+    }
+
+    fn push_fragment(&mut self, fragment: Fragment) {
+        match self.stack.last_mut() {
+            Some(partial) => partial.children.push(fragment),
+            None => self.line.fragments.push(fragment),
+        }
+    }
+
+    fn add_to_line_height(&mut self, strut: Strut) {
+        self.line.max_above = self.line.max_above.max(strut.above);
+        self.line.max_below = self.line.max_below.max(strut.below);
+    }
+
+    fn start_inline_box(&mut self, layout_context: &LayoutContext, inline_box: &InlineBox) {
+        let style = &inline_box.style;
+        let cbis = self.containing_block.inline_size;
+        let mut padding = style.padding().percentages_relative_to(cbis);
+        let mut border = style.border_width();
+        let mut margin = style
+            .margin()
+            .percentages_relative_to(cbis)
+            .map(|margin| margin.unwrap_or(Au(0)));
+        // Vertical margins don't apply to inline boxes.
+        margin.block_start = Au(0);
+        margin.block_end = Au(0);
+        if !inline_box.first_fragment {
+            padding.inline_start = Au(0);
+            border.inline_start = Au(0);
+            margin.inline_start = Au(0);
+        }
+        if !inline_box.last_fragment {
+            padding.inline_end = Au(0);
+            border.inline_end = Au(0);
+            margin.inline_end = Au(0);
+        }
+        let pbm_start = padding.inline_start + border.inline_start + margin.inline_start;
+        let pbm_end = padding.inline_end + border.inline_end + margin.inline_end;
+        if pbm_start != Au(0) || pbm_end != Au(0) {
+            self.break_line_before(layout_context, pbm_start, self.allow_wrap);
+            self.line.has_content = true;
+            self.line.trailing_whitespace = Au(0);
+        }
+        self.line.position += pbm_start;
+
+        let metrics = font_metrics(layout_context, style);
+        let (ascent, descent, strut) = match metrics {
+            Some(ref metrics) => (metrics.ascent, metrics.descent, Strut::new(style, metrics)),
+            None => (Au(0), Au(0), self.strut),
+        };
+        self.add_to_line_height(strut);
+        self.stack.push(PartialInlineBoxFragment {
+            tag: inline_box.tag,
+            style: style.clone(),
+            last_box_tree_fragment: inline_box.last_fragment,
+            start_position: self.line.position,
+            ascent,
+            descent,
+            strut,
+            padding,
+            border,
+            margin,
+            children: Vec::new(),
+        });
+    }
+
+    fn finish_inline_box(&mut self) {
+        let partial = self.stack.pop().unwrap();
+        let pbm_end =
+            partial.padding.inline_end + partial.border.inline_end + partial.margin.inline_end;
+        let fragment = partial.finish(self.line.position, true, self.containing_block);
+        self.push_fragment(Fragment::Box(fragment));
+        self.line.position += pbm_end;
+    }
+
+    fn layout_text_run(&mut self, layout_context: &LayoutContext, run: &TextRun) {
+        let shaped = match run.shape(layout_context) {
+            Some(shaped) => shaped,
+            None => return,
+        };
+        let white_space = run.parent_style.get_inherited_text().white_space;
+        let strut = Strut::new(&run.parent_style, &shaped.metrics);
+        let flush = |state: &mut Self, pending: &mut Option<PendingText>| {
+            if let Some(pending) = pending.take() {
+                state.add_to_line_height(strut);
+                state.push_fragment(Fragment::Text(TextFragment {
+                    tag: run.tag,
+                    parent_style: run.parent_style.clone(),
+                    content_rect: Rect {
+                        start_corner: Vec2 {
+                            inline: pending.start,
+                            block: -shaped.metrics.ascent,
+                        },
+                        size: Vec2 {
+                            inline: pending.width,
+                            block: shaped.metrics.ascent + shaped.metrics.descent,
+                        },
+                    },
+                    ascent: shaped.metrics.ascent,
+                    font_key: shaped.font_key,
+                    glyphs: pending.glyphs,
+                }));
+            }
+        };
+
+        let mut pending = None;
+        for (i, segment) in shaped.segments.iter().enumerate() {
+            if i > 0 {
+                // A preserved newline.
+                flush(self, &mut pending);
+                self.line.has_content = true;
+                self.add_to_line_height(strut);
+                self.finish_line(layout_context);
+            }
+            for glyph_run in segment {
+                let glyph_store = &glyph_run.glyph_store;
+                let width = glyph_store_advance(glyph_store);
+                if glyph_store.is_whitespace() {
+                    if white_space.preserve_spaces() {
+                        self.line.has_content = true;
+                    } else if !self.line.has_content {
+                        // Collapsible white space at the start of a line is
+                        // removed.
+                        continue;
+                    }
+                } else {
+                    if white_space.allow_wrap() &&
+                        self.line.has_content &&
+                        self.line.position + width > self.line.available_inline_size
+                    {
+                        flush(self, &mut pending);
+                        self.finish_line(layout_context);
+                    }
+                    if !self.line.has_content {
+                        self.fit_on_empty_line(width);
+                    }
+                    self.line.has_content = true;
+                }
+                let pending_text = pending.get_or_insert_with(|| PendingText {
+                    start: self.line.position,
+                    width: Au(0),
+                    glyphs: Vec::new(),
+                });
+                pending_text.width += width;
+                pending_text.glyphs.push(glyph_store.clone());
+                self.line.position += width;
+                if glyph_store.is_whitespace() && !white_space.preserve_spaces() {
+                    self.line.trailing_whitespace += width;
+                } else {
+                    self.line.trailing_whitespace = Au(0);
+                }
+            }
+        }
+        flush(self, &mut pending);
+    }
+
+    /// Lays out an atomic inline-level box, like an `inline-block`, with a
+    /// shrink-to-fit inline size.
+    ///
+    /// https://drafts.csswg.org/css2/visudet.html#inlineblock-width
+    fn layout_atomic(
+        &mut self,
+        layout_context: &LayoutContext,
+        atomic: &'box_tree IndependentFormattingContext,
+    ) {
+        let style = &atomic.style;
+        let cbis = self.containing_block.inline_size;
+        let padding = style.padding().percentages_relative_to(cbis);
+        let border = style.border_width();
+        let margin = style
+            .margin()
+            .percentages_relative_to(cbis)
+            .map(|margin| margin.unwrap_or(Au(0)));
+        let pb = &padding + &border;
+        let box_size = style.content_box_size(cbis, self.containing_block.block_size, &pb);
+        let min_box_size = style.content_min_box_size(cbis, self.containing_block.block_size, &pb);
+        let max_box_size = style.content_max_box_size(cbis, self.containing_block.block_size, &pb);
+
+        let inline_size = box_size.inline.unwrap_or_else(|| {
+            let available_size = cbis - pb.inline_sum() - margin.inline_sum();
+            atomic
+                .inline_content_sizes(layout_context)
+                .shrink_to_fit(available_size)
+        });
+        let inline_size =
+            clamp_between_extremums(inline_size, min_box_size.inline, max_box_size.inline);
+        let block_size = box_size
+            .block
+            .map(|size| clamp_between_extremums(size, min_box_size.block, max_box_size.block));
+        let containing_block_for_children = ContainingBlock {
+            inline_size,
+            block_size,
+            mode: style.writing_mode,
+        };
+
+        let mut nested_abspos = vec![];
+        let establishes_containing_block = style.establishes_containing_block();
+        let tree_rank = self.absolutely_positioned_fragments.len();
+        let mut independent_layout = atomic.layout(
+            layout_context,
+            &containing_block_for_children,
+            if establishes_containing_block {
+                &mut nested_abspos
+            } else {
+                &mut *self.absolutely_positioned_fragments
+            },
+        );
+        let block_size = block_size.unwrap_or_else(|| {
+            clamp_between_extremums(
+                independent_layout.content_block_size,
+                min_box_size.block,
+                max_box_size.block,
+            )
+        });
+
+        let margin_box_size = Vec2 {
+            inline: inline_size + pb.inline_sum() + margin.inline_sum(),
+            block: block_size + pb.block_sum() + margin.block_sum(),
+        };
+        self.break_line_before(layout_context, margin_box_size.inline, self.allow_wrap);
+
+        // FIXME: the baseline is the bottom margin edge, even if the box has
+        // in-flow line boxes.
+        let relative_adjustement = relative_adjustement(style, self.containing_block);
+        let content_rect = Rect {
+            start_corner: Vec2 {
+                inline: self.line.position +
+                    margin.inline_start +
+                    pb.inline_start +
+                    relative_adjustement.inline,
+                block: -margin_box_size.block +
+                    margin.block_start +
+                    pb.block_start +
+                    relative_adjustement.block,
+            },
+            size: Vec2 {
+                inline: inline_size,
+                block: block_size,
+            },
+        };
+        if establishes_containing_block {
+            AbsolutelyPositionedFragment::in_positioned_containing_block(
+                layout_context,
+                &nested_abspos,
+                &mut independent_layout.fragments,
+                &content_rect.size,
+                &padding,
+                style.writing_mode,
+            )
+        } else {
+            for index in tree_rank..self.absolutely_positioned_fragments.len() {
+                self.absolutely_positioned_fragments[index].static_position +=
+                    &content_rect.start_corner;
+                self.line.absolutely_positioned.push((index, false));
+            }
+        }
+        self.push_fragment(Fragment::Box(BoxFragment {
+            tag: atomic.tag,
+            style: style.clone(),
+            children: independent_layout.fragments,
+            content_rect,
+            padding,
+            border,
+            block_margins_collapsed_with_children: CollapsedBlockMargins::from_margin(&margin),
+            margin,
+        }));
+        self.add_to_line_height(Strut {
+            above: margin_box_size.block,
+            below: Au(0),
+        });
+        self.line.position += margin_box_size.inline;
+        self.line.has_content = true;
+        self.line.trailing_whitespace = Au(0);
+    }
+
+    /// Finishes the current line, if it has anything on it, and starts the
+    /// next one.
+    fn finish_line(&mut self, layout_context: &LayoutContext) {
+        // The inline boxes that continue on the next line are split.
+        for i in (0..self.stack.len()).rev() {
+            let fragment = {
+                let partial = &mut self.stack[i];
+                let fragment = PartialInlineBoxFragment {
+                    tag: partial.tag,
+                    style: partial.style.clone(),
+                    last_box_tree_fragment: false,
+                    start_position: partial.start_position,
+                    ascent: partial.ascent,
+                    descent: partial.descent,
+                    strut: partial.strut,
+                    padding: partial.padding.clone(),
+                    border: partial.border.clone(),
+                    margin: partial.margin.clone(),
+                    children: mem::replace(&mut partial.children, Vec::new()),
+                }
+                .finish(self.line.position, false, self.containing_block);
+                partial.start_position = Au(0);
+                partial.padding.inline_start = Au(0);
+                partial.border.inline_start = Au(0);
+                partial.margin.inline_start = Au(0);
+                fragment
+            };
+            match i.checked_sub(1) {
+                Some(parent) => self.stack[parent].children.push(Fragment::Box(fragment)),
+                None => self.line.fragments.push(Fragment::Box(fragment)),
+            }
+        }
+
+        let line = &mut self.line;
+        let (above, block_size) = if line.has_content {
+            let above = line.max_above.max(self.strut.above);
+            (above, above + line.max_below.max(self.strut.below))
+        } else {
+            (Au(0), Au(0))
+        };
+        let free_space = line.available_inline_size - (line.position - line.trailing_whitespace);
+        let alignment_offset = match self.text_align {
+            TextAlign::Start | TextAlign::Left | TextAlign::ServoLeft | TextAlign::Justify => Au(0),
+            TextAlign::End | TextAlign::Right | TextAlign::ServoRight => free_space,
+            TextAlign::Center | TextAlign::ServoCenter => free_space / 2,
+        }
+        .max(Au(0));
+
+        for &(index, relative_to_line_top) in &line.absolutely_positioned {
+            let static_position = &mut self.absolutely_positioned_fragments[index].static_position;
+            static_position.inline += line.inline_start + alignment_offset;
+            static_position.block += line.block_start;
+            if !relative_to_line_top {
+                static_position.block += above;
+            }
+        }
+        if !line.fragments.is_empty() {
+            let mut children = mem::replace(&mut line.fragments, Vec::new());
+            for child in &mut children {
+                translate_fragment(
+                    child,
+                    &Vec2 {
+                        inline: Au(0),
+                        block: above,
+                    },
+                );
+            }
+            self.lines.push(Fragment::Anonymous(AnonymousFragment {
+                rect: Rect {
+                    start_corner: Vec2 {
+                        inline: line.inline_start + alignment_offset,
+                        block: line.block_start,
+                    },
+                    size: Vec2 {
+                        inline: line.position,
+                        block: block_size,
+                    },
+                },
+                children,
+            }));
+        }
+
+        line.block_start += block_size;
+        line.position = Au(0);
+        line.trailing_whitespace = Au(0);
+        line.has_content = false;
+        line.max_above = Au(0);
+        line.max_below = Au(0);
+        line.absolutely_positioned.clear();
+
+        for float_box in mem::replace(&mut self.pending_floats, Vec::new()) {
+            self.place_float(layout_context, float_box);
+        }
+        self.update_line_inline_range();
+    }
+}
+
+impl PartialInlineBoxFragment {
+    fn finish(
+        self,
+        end_position: Au,
+        is_last_fragment: bool,
+        containing_block: &ContainingBlock,
+    ) -> BoxFragment {
+        let mut padding = self.padding;
+        let mut border = self.border;
+        let mut margin = self.margin;
+        if !(is_last_fragment && self.last_box_tree_fragment) {
+            padding.inline_end = Au(0);
+            border.inline_end = Au(0);
+            margin.inline_end = Au(0);
+        }
+        let mut content_rect = Rect {
+            start_corner: Vec2 {
+                inline: self.start_position,
+                block: -self.ascent,
+            },
+            size: Vec2 {
+                inline: end_position - self.start_position,
+                block: self.ascent + self.descent,
+            },
+        };
+        let mut children = self.children;
+        let offset = Vec2 {
+            inline: -content_rect.start_corner.inline,
+            block: -content_rect.start_corner.block,
+        };
+        for child in &mut children {
+            translate_fragment(child, &offset);
+        }
+        content_rect.start_corner += &relative_adjustement(&self.style, containing_block);
+        BoxFragment {
+            tag: self.tag,
+            style: self.style,
+            children,
+            content_rect,
+            padding,
+            border,
+            margin,
+            block_margins_collapsed_with_children: CollapsedBlockMargins::zero(),
+        }
+    }
+}
+
+fn translate_fragment(fragment: &mut Fragment, offset: &Vec2<Au>) {
+    match fragment {
+        Fragment::Box(fragment) => fragment.content_rect.start_corner += offset,
+        Fragment::Anonymous(fragment) => fragment.rect.start_corner += offset,
+        Fragment::Text(fragment) => fragment.content_rect.start_corner += offset,
+    }
+}
+
+struct ContentSizesComputation<'a, 'b> {
+    layout_context: &'a LayoutContext<'b>,
+    result: ContentSizes,
+    /// The max-content size of the current line.
+    current_line: Au,
+    /// The size of what can't be broken up on the current line.
+    current_unbreakable: Au,
+}
+
+impl<'a, 'b> ContentSizesComputation<'a, 'b> {
+    fn traverse(&mut self, inline_level_boxes: &[InlineLevelBox]) {
+        for box_ in inline_level_boxes {
+            match box_ {
+                InlineLevelBox::InlineBox(inline_box) => {
+                    let (start, end) = inline_box_pbm_without_percentages(inline_box);
+                    self.add_unbreakable(start);
+                    self.traverse(&inline_box.children);
+                    self.add_unbreakable(end);
+                },
+                InlineLevelBox::TextRun(run) => self.add_text_run(run),
+                InlineLevelBox::Atomic(atomic) => {
+                    let sizes = atomic.outer_inline_content_sizes(self.layout_context);
+                    self.soft_wrap_opportunity();
+                    self.result.min_content = self.result.min_content.max(sizes.min_content);
+                    self.current_line += sizes.max_content;
+                    self.soft_wrap_opportunity();
+                },
+                InlineLevelBox::OutOfFlowFloatBox(float_box) => {
+                    let sizes = float_box
+                        .contents
+                        .outer_inline_content_sizes(self.layout_context);
+                    self.result.max_assign(&sizes);
+                },
+                InlineLevelBox::OutOfFlowAbsolutelyPositionedBox(_) => {},
+            }
+        }
+    }
+
+    fn add_text_run(&mut self, run: &TextRun) {
+        let shaped = match run.shape(self.layout_context) {
+            Some(shaped) => shaped,
+            None => return,
+        };
+        let white_space = run.parent_style.get_inherited_text().white_space;
+        for (i, segment) in shaped.segments.iter().enumerate() {
+            if i > 0 {
+                self.forced_line_break();
+            }
+            for glyph_run in segment {
+                let width = glyph_store_advance(&glyph_run.glyph_store);
+                if white_space.allow_wrap() {
+                    self.soft_wrap_opportunity();
+                }
+                if glyph_run.glyph_store.is_whitespace() && !white_space.preserve_spaces() {
+                    // Collapsible white space can hang at the end of a line.
+                    self.current_line += width;
+                } else {
+                    self.add_unbreakable(width);
+                }
+            }
+        }
+    }
+
+    fn add_unbreakable(&mut self, size: Au) {
+        self.current_line += size;
+        self.current_unbreakable += size;
+        self.result.min_content = self.result.min_content.max(self.current_unbreakable);
+    }
+
+    fn soft_wrap_opportunity(&mut self) {
+        self.current_unbreakable = Au(0);
+    }
+
+    fn forced_line_break(&mut self) {
+        self.result.max_content = self.result.max_content.max(self.current_line);
+        self.current_line = Au(0);
+        self.current_unbreakable = Au(0);
+    }
+}
+
+fn inline_box_pbm_without_percentages(inline_box: &InlineBox) -> (Au, Au) {
+    let style = &inline_box.style;
+    let padding = style.padding().percentages_relative_to(Au(0));
+    let border = style.border_width();
+    let margin = style
+        .margin()
+        .percentages_relative_to(Au(0))
+        .map(|m| m.unwrap_or(Au(0)));
+    let start = if inline_box.first_fragment {
+        padding.inline_start + border.inline_start + margin.inline_start
+    } else {
+        Au(0)
+    };
+    let end = if inline_box.last_fragment {
+        padding.inline_end + border.inline_end + margin.inline_end
+    } else {
+        Au(0)
+    };
+    (start, end)
+}
@@ -0,0 +1,613 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Flow layout, also known as block-and-inline layout.
+
+use crate::context::LayoutContext;
+use crate::display_list::items::OpaqueNode;
+use crate::flow::float::{FloatBox, FloatContext};
+use crate::flow::inline::InlineFormattingContext;
+use crate::formatting_contexts::{IndependentFormattingContext, IndependentLayout};
+use crate::fragments::{BoxFragment, CollapsedBlockMargins, CollapsedMargin, Fragment};
+use crate::geom::flow_relative::{Rect, Sides, Vec2};
+use crate::positioned::{adjust_static_positions, relative_adjustement};
+use crate::positioned::{AbsolutelyPositionedBox, AbsolutelyPositionedFragment};
+use crate::sizing::{clamp_between_extremums, outer_inline_content_sizes, ContentSizes};
+use crate::style_ext::ComputedValuesExt;
+use crate::{ContainingBlock, ServoArc};
+use app_units::Au;
+use style::properties::ComputedValues;
+
+mod construct;
+mod float;
+pub mod inline;
+mod root;
+
+pub use root::{BoxTreeRoot, FragmentTreeRoot};
+
+pub(crate) struct BlockFormattingContext {
+    pub contents: BlockContainer,
+}
+
+pub(crate) enum BlockContainer {
+    BlockLevelBoxes(Vec<BlockLevelBox>),
+    InlineFormattingContext(InlineFormattingContext),
+}
+
+pub(crate) enum BlockLevelBox {
+    SameFormattingContextBlock {
+        tag: OpaqueNode,
+        style: ServoArc<ComputedValues>,
+        contents: BlockContainer,
+    },
+    OutOfFlowAbsolutelyPositionedBox(AbsolutelyPositionedBox),
+    OutOfFlowFloatBox(FloatBox),
+    Independent(IndependentFormattingContext),
+}
+
+pub(super) struct FlowLayout {
+    pub fragments: Vec<Fragment>,
+    pub content_block_size: Au,
+    pub collapsible_margins_in_children: CollapsedBlockMargins,
+}
+
+#[derive(Clone, Copy)]
+struct CollapsibleWithParentStartMargin(bool);
+
+impl BlockFormattingContext {
+    pub(super) fn layout<'a>(
+        &'a self,
+        layout_context: &LayoutContext,
+        containing_block: &ContainingBlock,
+        absolutely_positioned_fragments: &mut Vec<AbsolutelyPositionedFragment<'a>>,
+    ) -> IndependentLayout {
+        let mut float_context = FloatContext::new();
+        let flow_layout = self.contents.layout(
+            layout_context,
+            containing_block,
+            absolutely_positioned_fragments,
+            &mut float_context,
+            CollapsibleWithParentStartMargin(false),
+        );
+        let content_block_size = flow_layout.content_block_size +
+            flow_layout.collapsible_margins_in_children.end.solve();
+
+        // Floats are painted after the in-flow block-level boxes of their
+        // formatting context.
+        let mut fragments = flow_layout.fragments;
+        fragments.append(&mut float_context.fragments);
+        IndependentLayout {
+            fragments,
+            // https://drafts.csswg.org/css2/visudet.html#root-height
+            content_block_size: content_block_size.max(float_context.floats_block_end()),
+        }
+    }
+}
+
+impl BlockContainer {
+    fn layout<'a>(
+        &'a self,
+        layout_context: &LayoutContext,
+        containing_block: &ContainingBlock,
+        absolutely_positioned_fragments: &mut Vec<AbsolutelyPositionedFragment<'a>>,
+        float_context: &mut FloatContext,
+        collapsible_with_parent_start_margin: CollapsibleWithParentStartMargin,
+    ) -> FlowLayout {
+        match self {
+            BlockContainer::BlockLevelBoxes(child_boxes) => layout_block_level_children(
+                layout_context,
+                child_boxes,
+                containing_block,
+                absolutely_positioned_fragments,
+                float_context,
+                collapsible_with_parent_start_margin,
+            ),
+            BlockContainer::InlineFormattingContext(ifc) => ifc.layout(
+                layout_context,
+                containing_block,
+                absolutely_positioned_fragments,
+                float_context,
+            ),
+        }
+    }
+
+    pub(super) fn inline_content_sizes(&self, layout_context: &LayoutContext) -> ContentSizes {
+        match self {
+            BlockContainer::BlockLevelBoxes(boxes) => boxes
+                .iter()
+                .map(|box_| box_.inline_content_sizes(layout_context))
+                .fold(ContentSizes::zero(), ContentSizes::max),
+            BlockContainer::InlineFormattingContext(ifc) => {
+                ifc.inline_content_sizes(layout_context)
+            },
+        }
+    }
+}
+
+impl BlockLevelBox {
+    fn inline_content_sizes(&self, layout_context: &LayoutContext) -> ContentSizes {
+        match self {
+            BlockLevelBox::SameFormattingContextBlock {
+                style, contents, ..
+            } => outer_inline_content_sizes(style, &contents.inline_content_sizes(layout_context)),
+            BlockLevelBox::Independent(contents) => {
+                contents.outer_inline_content_sizes(layout_context)
+            },
+            BlockLevelBox::OutOfFlowFloatBox(float_box) => float_box
+                .contents
+                .outer_inline_content_sizes(layout_context),
+            BlockLevelBox::OutOfFlowAbsolutelyPositionedBox(_) => ContentSizes::zero(),
+        }
+    }
+}
+
+/// Where in-flow block-level boxes go, and how their margins collapse.
+///
+/// https://drafts.csswg.org/css2/box.html#collapsing-margins
+struct PlacementState {
+    next_in_flow_margin_collapses_with_parent_start_margin: bool,
+    start_margin: CollapsedMargin,
+    current_margin: CollapsedMargin,
+    current_block_direction_position: Au,
+}
+
+impl PlacementState {
+    fn place_fragment(&mut self, fragment: &mut BoxFragment) {
+        let fragment_block_margins = &fragment.block_margins_collapsed_with_children;
+        let fragment_block_size = fragment.padding.block_sum() +
+            fragment.border.block_sum() +
+            fragment.content_rect.size.block;
+
+        if self.next_in_flow_margin_collapses_with_parent_start_margin {
+            debug_assert_eq!(self.current_margin.solve(), Au(0));
+            self.start_margin
+                .adjoin_assign(&fragment_block_margins.start);
+            if fragment_block_margins.collapsed_through {
+                self.start_margin.adjoin_assign(&fragment_block_margins.end);
+                return;
+            }
+            self.next_in_flow_margin_collapses_with_parent_start_margin = false;
+        } else {
+            self.current_margin
+                .adjoin_assign(&fragment_block_margins.start);
+        }
+        fragment.content_rect.start_corner.block +=
+            self.current_margin.solve() + self.current_block_direction_position;
+        if fragment_block_margins.collapsed_through {
+            self.current_margin
+                .adjoin_assign(&fragment_block_margins.end);
+            return;
+        }
+        self.current_block_direction_position += self.current_margin.solve() + fragment_block_size;
+        self.current_margin = fragment_block_margins.end;
+    }
+
+    /// The block position where the next out-of-flow box would go, relative
+    /// to the content box of the parent.
+    fn static_block_position(&self) -> Au {
+        if self.next_in_flow_margin_collapses_with_parent_start_margin {
+            Au(0)
+        } else {
+            self.current_block_direction_position + self.current_margin.solve()
+        }
+    }
+
+    /// Where the content box of the parent starts, relative to the
+    /// formatting context root, given the position of the parent before its
+    /// collapsible start margins.
+    fn container_origin(
+        &self,
+        container_start: &Vec2<Au>,
+        pending_margin: &CollapsedMargin,
+    ) -> Vec2<Au> {
+        Vec2 {
+            inline: container_start.inline,
+            block: container_start.block + pending_margin.adjoin(&self.start_margin).solve(),
+        }
+    }
+
+    /// Tells the float context where the next child goes.
+    fn set_float_frame(
+        &self,
+        float_context: &mut FloatContext,
+        container_start: &Vec2<Au>,
+        pending_margin: &CollapsedMargin,
+    ) {
+        if self.next_in_flow_margin_collapses_with_parent_start_margin {
+            float_context.position = container_start.clone();
+            float_context.pending_margin = pending_margin.adjoin(&self.start_margin);
+        } else {
+            let origin = self.container_origin(container_start, pending_margin);
+            float_context.position = Vec2 {
+                inline: origin.inline,
+                block: origin.block + self.current_block_direction_position,
+            };
+            float_context.pending_margin = self.current_margin;
+        }
+    }
+
+    /// Moves the next child below the floats it has to clear, if any.
+    ///
+    /// https://drafts.csswg.org/css2/visuren.html#clearance
+    fn apply_clearance(
+        &mut self,
+        float_context: &mut FloatContext,
+        style: &ComputedValues,
+        containing_block: &ContainingBlock,
+        container_start: &Vec2<Au>,
+        pending_margin: &CollapsedMargin,
+    ) {
+        let clearance_position = match float_context.clearance_position(style.get_box().clear) {
+            Some(position) => position,
+            None => return,
+        };
+        let margin_block_start = style
+            .margin()
+            .block_start
+            .to_used_value(containing_block.inline_size)
+            .unwrap_or(Au(0));
+        let hypothetical_border_edge = float_context.position.block +
+            float_context
+                .pending_margin
+                .adjoin(&CollapsedMargin::new(margin_block_start))
+                .solve();
+        if clearance_position <= hypothetical_border_edge {
+            return;
+        }
+        let origin = self.container_origin(container_start, pending_margin);
+        self.next_in_flow_margin_collapses_with_parent_start_margin = false;
+        self.current_margin = CollapsedMargin::zero();
+        self.current_block_direction_position =
+            clearance_position - origin.block - margin_block_start;
+        self.set_float_frame(float_context, container_start, pending_margin);
+    }
+}
+
+fn layout_block_level_children<'a>(
+    layout_context: &LayoutContext,
+    child_boxes: &'a [BlockLevelBox],
+    containing_block: &ContainingBlock,
+    absolutely_positioned_fragments: &mut Vec<AbsolutelyPositionedFragment<'a>>,
+    float_context: &mut FloatContext,
+    collapsible_with_parent_start_margin: CollapsibleWithParentStartMargin,
+) -> FlowLayout {
+    let container_start = float_context.position.clone();
+    let pending_margin = float_context.pending_margin;
+    let mut placement_state = PlacementState {
+        next_in_flow_margin_collapses_with_parent_start_margin:
+            collapsible_with_parent_start_margin.0,
+        start_margin: CollapsedMargin::zero(),
+        current_margin: CollapsedMargin::zero(),
+        current_block_direction_position: Au(0),
+    };
+    let mut fragments = Vec::new();
+    for child in child_boxes {
+        if let BlockLevelBox::OutOfFlowAbsolutelyPositionedBox(box_) = child {
+            let static_position = Vec2 {
+                inline: Au(0),
+                block: placement_state.static_block_position(),
+            };
+            absolutely_positioned_fragments.push(box_.layout(static_position));
+            continue;
+        }
+
+        placement_state.set_float_frame(float_context, &container_start, &pending_margin);
+        let tree_rank = absolutely_positioned_fragments.len();
+        if let BlockLevelBox::OutOfFlowFloatBox(box_) = child {
+            let content_start = box_.layout(
+                layout_context,
+                containing_block,
+                absolutely_positioned_fragments,
+                float_context,
+            );
+            let origin = placement_state.container_origin(&container_start, &pending_margin);
+            adjust_static_positions(
+                &mut absolutely_positioned_fragments[tree_rank..],
+                &(&content_start - &origin),
+            );
+            continue;
+        }
+
+        let mut fragment = match child {
+            BlockLevelBox::SameFormattingContextBlock {
+                tag,
+                style,
+                contents,
+            } => {
+                placement_state.apply_clearance(
+                    float_context,
+                    style,
+                    containing_block,
+                    &container_start,
+                    &pending_margin,
+                );
+                layout_in_flow_non_replaced_block_level(
+                    layout_context,
+                    containing_block,
+                    absolutely_positioned_fragments,
+                    float_context,
+                    *tag,
+                    style,
+                    NonReplacedContents::SameFormattingContextBlock(contents),
+                )
+            },
+            BlockLevelBox::Independent(contents) => {
+                placement_state.apply_clearance(
+                    float_context,
+                    &contents.style,
+                    containing_block,
+                    &container_start,
+                    &pending_margin,
+                );
+                layout_in_flow_independent_block_level(
+                    layout_context,
+                    containing_block,
+                    absolutely_positioned_fragments,
+                    float_context,
+                    contents,
+                )
+            },
+            BlockLevelBox::OutOfFlowAbsolutelyPositionedBox(_) |
+            BlockLevelBox::OutOfFlowFloatBox(_) => unreachable!(),
+        };
+        placement_state.place_fragment(&mut fragment);
+        adjust_static_positions(
+            &mut absolutely_positioned_fragments[tree_rank..],
+            &fragment.content_rect.start_corner,
+        );
+        fragments.push(Fragment::Box(fragment));
+    }
+
+    float_context.position = container_start;
+    float_context.pending_margin = pending_margin;
+    FlowLayout {
+        fragments,
+        content_block_size: placement_state.current_block_direction_position,
+        collapsible_margins_in_children: CollapsedBlockMargins {
+            collapsed_through: placement_state
+                .next_in_flow_margin_collapses_with_parent_start_margin,
+            start: placement_state.start_margin,
+            end: placement_state.current_margin,
+        },
+    }
+}
+
+enum NonReplacedContents<'a> {
+    SameFormattingContextBlock(&'a BlockContainer),
+    EstablishesAnIndependentFormattingContext(&'a IndependentFormattingContext),
+}
+
+/// Lays out a block-level box that establishes an independent formatting
+/// context next to the floats of its parent formatting context, rather than
+/// over them.
+///
+/// https://drafts.csswg.org/css2/visuren.html#bfc-next-to-float
+fn layout_in_flow_independent_block_level<'a>(
+    layout_context: &LayoutContext,
+    containing_block: &ContainingBlock,
+    absolutely_positioned_fragments: &mut Vec<AbsolutelyPositionedFragment<'a>>,
+    float_context: &mut FloatContext,
+    contents: &'a IndependentFormattingContext,
+) -> BoxFragment {
+    let margin_block_start = contents
+        .style
+        .margin()
+        .block_start
+        .to_used_value(containing_block.inline_size)
+        .unwrap_or(Au(0));
+    let border_edge = float_context.position.block +
+        float_context
+            .pending_margin
+            .adjoin(&CollapsedMargin::new(margin_block_start))
+            .solve();
+    // FIXME: this only avoids the floats next to the start of the box.
+    let (inline_start, inline_end) = float_context.available_inline_range(
+        border_edge,
+        Au(0),
+        float_context.position.inline,
+        containing_block.inline_size,
+    );
+    let offset = inline_start - float_context.position.inline;
+    let narrowed_containing_block = ContainingBlock {
+        inline_size: inline_end - inline_start,
+        block_size: containing_block.block_size,
+        mode: containing_block.mode,
+    };
+    let mut fragment = layout_in_flow_non_replaced_block_level(
+        layout_context,
+        &narrowed_containing_block,
+        absolutely_positioned_fragments,
+        float_context,
+        contents.tag,
+        &contents.style,
+        NonReplacedContents::EstablishesAnIndependentFormattingContext(contents),
+    );
+    fragment.content_rect.start_corner.inline += offset;
+    fragment
+}
+
+/// https://drafts.csswg.org/css2/visudet.html#blockwidth
+/// https://drafts.csswg.org/css2/visudet.html#normal-block
+fn layout_in_flow_non_replaced_block_level<'a>(
+    layout_context: &LayoutContext,
+    containing_block: &ContainingBlock,
+    absolutely_positioned_fragments: &mut Vec<AbsolutelyPositionedFragment<'a>>,
+    float_context: &mut FloatContext,
+    tag: OpaqueNode,
+    style: &ServoArc<ComputedValues>,
+    block_level_kind: NonReplacedContents<'a>,
+) -> BoxFragment {
+    let cbis = containing_block.inline_size;
+    let padding = style.padding().percentages_relative_to(cbis);
+    let border = style.border_width();
+    let computed_margin = style.margin().percentages_relative_to(cbis);
+    let pb = &padding + &border;
+    let box_size = style.content_box_size(cbis, containing_block.block_size, &pb);
+    let min_box_size = style.content_min_box_size(cbis, containing_block.block_size, &pb);
+    let max_box_size = style.content_max_box_size(cbis, containing_block.block_size, &pb);
+
+    let inline_size = box_size.inline.unwrap_or_else(|| {
+        cbis - pb.inline_sum() -
+            computed_margin.inline_start.unwrap_or(Au(0)) -
+            computed_margin.inline_end.unwrap_or(Au(0))
+    });
+    let inline_size =
+        clamp_between_extremums(inline_size, min_box_size.inline, max_box_size.inline);
+
+    // In an over-constrained situation, `auto` margins are zero and the end
+    // margin is ignored.
+    let available = cbis - inline_size - pb.inline_sum();
+    let (margin_inline_start, margin_inline_end) =
+        match (computed_margin.inline_start, computed_margin.inline_end) {
+            (None, None) if available > Au(0) => (available / 2, available - available / 2),
+            (None, Some(end)) if available > end => (available - end, end),
+            (Some(start), _) => (start, available - start),
+            (None, _) => (Au(0), available),
+        };
+    let margin = Sides {
+        inline_start: margin_inline_start,
+        inline_end: margin_inline_end,
+        block_start: computed_margin.block_start.unwrap_or(Au(0)),
+        block_end: computed_margin.block_end.unwrap_or(Au(0)),
+    };
+
+    let block_size = box_size
+        .block
+        .map(|size| clamp_between_extremums(size, min_box_size.block, max_box_size.block));
+    let containing_block_for_children = ContainingBlock {
+        inline_size,
+        block_size,
+        mode: style.writing_mode,
+    };
+    let same_formatting_context = match block_level_kind {
+        NonReplacedContents::SameFormattingContextBlock(_) => true,
+        NonReplacedContents::EstablishesAnIndependentFormattingContext(_) => false,
+    };
+    let this_start_margin_can_collapse_with_children =
+        CollapsibleWithParentStartMargin(same_formatting_context && pb.block_start == Au(0));
+    let this_end_margin_can_collapse_with_children =
+        same_formatting_context && pb.block_end == Au(0) && block_size.is_none();
+
+    let mut nested_abspos = vec![];
+    let establishes_containing_block = style.establishes_containing_block();
+    let mut flow_layout = {
+        let absolutely_positioned_fragments = if establishes_containing_block {
+            &mut nested_abspos
+        } else {
+            absolutely_positioned_fragments
+        };
+        match block_level_kind {
+            NonReplacedContents::SameFormattingContextBlock(contents) => {
+                let saved_position = float_context.position.clone();
+                let saved_pending_margin = float_context.pending_margin;
+                float_context.position.inline += margin.inline_start + pb.inline_start;
+                let margin_block_start = CollapsedMargin::new(margin.block_start);
+                if this_start_margin_can_collapse_with_children.0 {
+                    float_context
+                        .pending_margin
+                        .adjoin_assign(&margin_block_start);
+                } else {
+                    float_context.position.block += float_context
+                        .pending_margin
+                        .adjoin(&margin_block_start)
+                        .solve() +
+                        pb.block_start;
+                    float_context.pending_margin = CollapsedMargin::zero();
+                }
+                let flow_layout = contents.layout(
+                    layout_context,
+                    &containing_block_for_children,
+                    absolutely_positioned_fragments,
+                    float_context,
+                    this_start_margin_can_collapse_with_children,
+                );
+                float_context.position = saved_position;
+                float_context.pending_margin = saved_pending_margin;
+                flow_layout
+            },
+            NonReplacedContents::EstablishesAnIndependentFormattingContext(contents) => {
+                let independent_layout = contents.layout(
+                    layout_context,
+                    &containing_block_for_children,
+                    absolutely_positioned_fragments,
+                );
+                FlowLayout {
+                    fragments: independent_layout.fragments,
+                    content_block_size: independent_layout.content_block_size,
+                    collapsible_margins_in_children: CollapsedBlockMargins::zero(),
+                }
+            },
+        }
+    };
+
+    let mut block_margins_collapsed_with_children = CollapsedBlockMargins::from_margin(&margin);
+    if this_start_margin_can_collapse_with_children.0 {
+        block_margins_collapsed_with_children
+            .start
+            .adjoin_assign(&flow_layout.collapsible_margins_in_children.start);
+        if flow_layout
+            .collapsible_margins_in_children
+            .collapsed_through
+        {
+            block_margins_collapsed_with_children
+                .start
+                .adjoin_assign(&std::mem::replace(
+                    &mut flow_layout.collapsible_margins_in_children.end,
+                    CollapsedMargin::zero(),
+                ));
+        }
+    }
+    if this_end_margin_can_collapse_with_children {
+        block_margins_collapsed_with_children
+            .end
+            .adjoin_assign(&flow_layout.collapsible_margins_in_children.end);
+    } else {
+        flow_layout.content_block_size += flow_layout.collapsible_margins_in_children.end.solve();
+    }
+    block_margins_collapsed_with_children.collapsed_through =
+        this_start_margin_can_collapse_with_children.0 &&
+            this_end_margin_can_collapse_with_children &&
+            flow_layout
+                .collapsible_margins_in_children
+                .collapsed_through;
+
+    let block_size = block_size.unwrap_or_else(|| {
+        clamp_between_extremums(
+            flow_layout.content_block_size,
+            min_box_size.block,
+            max_box_size.block,
+        )
+    });
+    let relative_adjustement = relative_adjustement(style, containing_block);
+    let content_rect = Rect {
+        start_corner: Vec2 {
+            block: pb.block_start + relative_adjustement.block,
+            inline: pb.inline_start + relative_adjustement.inline + margin.inline_start,
+        },
+        size: Vec2 {
+            block: block_size,
+            inline: inline_size,
+        },
+    };
+    if establishes_containing_block {
+        AbsolutelyPositionedFragment::in_positioned_containing_block(
+            layout_context,
+            &nested_abspos,
+            &mut flow_layout.fragments,
+            &content_rect.size,
+            &padding,
+            style.writing_mode,
+        )
+    }
+    BoxFragment {
+        tag,
+        style: style.clone(),
+        children: flow_layout.fragments,
+        content_rect,
+        padding,
+        border,
+        margin,
+        block_margins_collapsed_with_children,
+    }
+}
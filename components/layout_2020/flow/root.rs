@@ -0,0 +1,131 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::context::LayoutContext;
+use crate::display_list::DisplayListBuilder;
+use crate::dom_traversal::Contents;
+use crate::flow::float::FloatBox;
+use crate::flow::{BlockContainer, BlockFormattingContext, BlockLevelBox};
+use crate::formatting_contexts::IndependentFormattingContext;
+use crate::fragments::Fragment;
+use crate::geom::flow_relative::Vec2;
+use crate::geom::physical;
+use crate::positioned::AbsolutelyPositionedBox;
+use crate::style_ext::{ComputedValuesExt, Display, DisplayGeneratingBox};
+use crate::{ContainingBlock, DefiniteContainingBlock};
+use app_units::Au;
+use euclid::default::Size2D;
+use script_layout_interface::wrapper_traits::ThreadSafeLayoutNode;
+use style::logical_geometry::WritingMode;
+use style::values::computed::Float;
+
+/// The box tree of a document.
+pub struct BoxTreeRoot(BlockFormattingContext);
+
+/// The fragment tree of a document, relative to the initial containing
+/// block.
+pub struct FragmentTreeRoot(Vec<Fragment>);
+
+impl BoxTreeRoot {
+    pub fn construct<Node>(context: &LayoutContext, root_element: Node) -> Self
+    where
+        Node: ThreadSafeLayoutNode,
+    {
+        BoxTreeRoot(BlockFormattingContext {
+            contents: BlockContainer::BlockLevelBoxes(construct_for_root_element(
+                context,
+                root_element,
+            )),
+        })
+    }
+
+    /// Lays out the document in the initial containing block, which has the
+    /// size of the viewport.
+    ///
+    /// https://drafts.csswg.org/css2/visudet.html#containing-block-details
+    pub fn layout(&self, layout_context: &LayoutContext, viewport: Size2D<Au>) -> FragmentTreeRoot {
+        // FIXME: use the writing mode of the root element.
+        let mode = WritingMode::empty();
+        let initial_containing_block = DefiniteContainingBlock {
+            size: Vec2 {
+                inline: viewport.width,
+                block: viewport.height,
+            },
+            mode,
+        };
+        let containing_block = ContainingBlock {
+            inline_size: viewport.width,
+            block_size: Some(viewport.height),
+            mode,
+        };
+
+        let mut absolutely_positioned_fragments = vec![];
+        let mut independent_layout = self.0.layout(
+            layout_context,
+            &containing_block,
+            &mut absolutely_positioned_fragments,
+        );
+        let abspos_fragments: Vec<_> = absolutely_positioned_fragments
+            .iter()
+            .map(|a| {
+                a.layout(
+                    layout_context,
+                    &initial_containing_block,
+                    &a.static_position,
+                )
+            })
+            .collect();
+        independent_layout.fragments.extend(abspos_fragments);
+        FragmentTreeRoot(independent_layout.fragments)
+    }
+}
+
+/// The root element always establishes a block formatting context.
+fn construct_for_root_element<Node>(
+    context: &LayoutContext,
+    root_element: Node,
+) -> Vec<BlockLevelBox>
+where
+    Node: ThreadSafeLayoutNode,
+{
+    let style = root_element.style(context.shared_context());
+    let display_inside = match Display::from(style.get_box().display) {
+        Display::None => return Vec::new(),
+        Display::GeneratingBox(DisplayGeneratingBox::OutsideInside { inside, .. }) => inside,
+    };
+    let is_absolutely_positioned = style.is_absolutely_positioned();
+    let is_floating = style.get_box().float != Float::None;
+    let tag = root_element.opaque();
+    let contents = IndependentFormattingContext::construct(
+        context,
+        style,
+        display_inside,
+        Contents::OfElement(root_element),
+        tag,
+    );
+    let box_ = if is_absolutely_positioned {
+        BlockLevelBox::OutOfFlowAbsolutelyPositionedBox(AbsolutelyPositionedBox { contents })
+    } else if is_floating {
+        BlockLevelBox::OutOfFlowFloatBox(FloatBox { contents })
+    } else {
+        BlockLevelBox::Independent(contents)
+    };
+    vec![box_]
+}
+
+impl FragmentTreeRoot {
+    pub fn build_display_list(&self, builder: &mut DisplayListBuilder, viewport_size: Size2D<Au>) {
+        let containing_block = physical::Rect {
+            top_left: physical::Vec2::zero(),
+            size: physical::Vec2 {
+                x: viewport_size.width,
+                y: viewport_size.height,
+            },
+        };
+        builder.build_canvas_background(&self.0, &containing_block);
+        for fragment in &self.0 {
+            fragment.build_display_list(builder, &containing_block)
+        }
+    }
+}
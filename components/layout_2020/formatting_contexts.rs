@@ -0,0 +1,118 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Boxes that establish an independent formatting context.
+//!
+//! https://drafts.csswg.org/css-display/#independent-formatting-context
+
+use crate::context::LayoutContext;
+use crate::display_list::items::OpaqueNode;
+use crate::dom_traversal::Contents;
+use crate::flexbox::FlexContainer;
+use crate::flow::BlockFormattingContext;
+use crate::fragments::Fragment;
+use crate::positioned::AbsolutelyPositionedFragment;
+use crate::sizing::{outer_inline_content_sizes, ContentSizes};
+use crate::style_ext::DisplayInside;
+use crate::{ContainingBlock, ServoArc};
+use app_units::Au;
+use script_layout_interface::wrapper_traits::ThreadSafeLayoutNode;
+use style::properties::ComputedValues;
+
+pub(crate) struct IndependentFormattingContext {
+    pub tag: OpaqueNode,
+    pub style: ServoArc<ComputedValues>,
+    contents: IndependentFormattingContextContents,
+}
+
+pub(crate) struct IndependentLayout {
+    pub fragments: Vec<Fragment>,
+    pub content_block_size: Au,
+}
+
+enum IndependentFormattingContextContents {
+    Flow(BlockFormattingContext),
+    Flex(FlexContainer),
+}
+
+impl IndependentFormattingContext {
+    pub fn construct<Node>(
+        context: &LayoutContext,
+        style: ServoArc<ComputedValues>,
+        display_inside: DisplayInside,
+        contents: Contents<Node>,
+        tag: OpaqueNode,
+    ) -> Self
+    where
+        Node: ThreadSafeLayoutNode,
+    {
+        let contents = match display_inside {
+            DisplayInside::Flow | DisplayInside::FlowRoot => {
+                IndependentFormattingContextContents::Flow(BlockFormattingContext::construct(
+                    context, &style, contents, tag,
+                ))
+            },
+            DisplayInside::Flex => IndependentFormattingContextContents::Flex(
+                FlexContainer::construct(context, &style, contents, tag),
+            ),
+        };
+        IndependentFormattingContext {
+            tag,
+            style,
+            contents,
+        }
+    }
+
+    /// An anonymous box establishing a block formatting context, such as the
+    /// ones wrapping text directly inside a flex container.
+    pub fn new_anonymous_flow(
+        tag: OpaqueNode,
+        style: ServoArc<ComputedValues>,
+        contents: BlockFormattingContext,
+    ) -> Self {
+        IndependentFormattingContext {
+            tag,
+            style,
+            contents: IndependentFormattingContextContents::Flow(contents),
+        }
+    }
+
+    pub fn layout<'a>(
+        &'a self,
+        layout_context: &LayoutContext,
+        containing_block: &ContainingBlock,
+        absolutely_positioned_fragments: &mut Vec<AbsolutelyPositionedFragment<'a>>,
+    ) -> IndependentLayout {
+        match &self.contents {
+            IndependentFormattingContextContents::Flow(bfc) => bfc.layout(
+                layout_context,
+                containing_block,
+                absolutely_positioned_fragments,
+            ),
+            IndependentFormattingContextContents::Flex(flex_container) => flex_container.layout(
+                layout_context,
+                containing_block,
+                &self.style,
+                absolutely_positioned_fragments,
+            ),
+        }
+    }
+
+    /// The intrinsic inline sizes of the content box.
+    pub fn inline_content_sizes(&self, layout_context: &LayoutContext) -> ContentSizes {
+        match &self.contents {
+            IndependentFormattingContextContents::Flow(bfc) => {
+                bfc.contents.inline_content_sizes(layout_context)
+            },
+            IndependentFormattingContextContents::Flex(flex_container) => {
+                flex_container.inline_content_sizes(layout_context, &self.style)
+            },
+        }
+    }
+
+    /// The intrinsic inline sizes of the margin box.
+    pub fn outer_inline_content_sizes(&self, layout_context: &LayoutContext) -> ContentSizes {
+        outer_inline_content_sizes(&self.style, &self.inline_content_sizes(layout_context))
+    }
+}
@@ -0,0 +1,129 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Fragments, the output of layout, which are then painted.
+//!
+//! The rectangles of a fragment are relative to the content box of its
+//! parent, or to the rectangle of its parent for anonymous fragments.
+
+use crate::display_list::items::OpaqueNode;
+use crate::geom::flow_relative::{Rect, Sides};
+use crate::ServoArc;
+use app_units::Au;
+use gfx::text::glyph::GlyphStore;
+use std::sync::Arc;
+use style::properties::ComputedValues;
+use webrender_api::FontInstanceKey;
+
+pub enum Fragment {
+    Box(BoxFragment),
+    Anonymous(AnonymousFragment),
+    Text(TextFragment),
+}
+
+pub struct BoxFragment {
+    pub tag: OpaqueNode,
+    pub style: ServoArc<ComputedValues>,
+    pub children: Vec<Fragment>,
+
+    /// The content box, relative to the content box of the parent fragment.
+    ///
+    /// FIXME: this is wrong for orthogonal flows:
+    /// https://drafts.csswg.org/css-writing-modes/#orthogonal-flows
+    pub content_rect: Rect<Au>,
+
+    pub padding: Sides<Au>,
+    pub border: Sides<Au>,
+    pub margin: Sides<Au>,
+
+    pub block_margins_collapsed_with_children: CollapsedBlockMargins,
+}
+
+pub struct CollapsedBlockMargins {
+    pub collapsed_through: bool,
+    pub start: CollapsedMargin,
+    pub end: CollapsedMargin,
+}
+
+/// The result of collapsing adjoining margins, per CSS 2.1 § 8.3.1.
+#[derive(Clone, Copy, Debug)]
+pub struct CollapsedMargin {
+    max_positive: Au,
+    min_negative: Au,
+}
+
+/// Can contain child fragments with relative coordinates, but does not
+/// contribute to painting itself.
+pub struct AnonymousFragment {
+    pub rect: Rect<Au>,
+    pub children: Vec<Fragment>,
+}
+
+pub struct TextFragment {
+    pub tag: OpaqueNode,
+    pub parent_style: ServoArc<ComputedValues>,
+    pub content_rect: Rect<Au>,
+    pub ascent: Au,
+    pub font_key: FontInstanceKey,
+    pub glyphs: Vec<Arc<GlyphStore>>,
+}
+
+impl BoxFragment {
+    pub fn padding_rect(&self) -> Rect<Au> {
+        self.content_rect.inflate(&self.padding)
+    }
+
+    pub fn border_rect(&self) -> Rect<Au> {
+        self.padding_rect().inflate(&self.border)
+    }
+}
+
+impl CollapsedBlockMargins {
+    pub fn from_margin(margin: &Sides<Au>) -> Self {
+        CollapsedBlockMargins {
+            collapsed_through: false,
+            start: CollapsedMargin::new(margin.block_start),
+            end: CollapsedMargin::new(margin.block_end),
+        }
+    }
+
+    pub fn zero() -> Self {
+        CollapsedBlockMargins {
+            collapsed_through: false,
+            start: CollapsedMargin::zero(),
+            end: CollapsedMargin::zero(),
+        }
+    }
+}
+
+impl CollapsedMargin {
+    pub fn zero() -> Self {
+        CollapsedMargin {
+            max_positive: Au(0),
+            min_negative: Au(0),
+        }
+    }
+
+    pub fn new(margin: Au) -> Self {
+        CollapsedMargin {
+            max_positive: margin.max(Au(0)),
+            min_negative: margin.min(Au(0)),
+        }
+    }
+
+    pub fn adjoin(&self, other: &Self) -> Self {
+        CollapsedMargin {
+            max_positive: self.max_positive.max(other.max_positive),
+            min_negative: self.min_negative.min(other.min_negative),
+        }
+    }
+
+    pub fn adjoin_assign(&mut self, other: &Self) {
+        *self = self.adjoin(other);
+    }
+
+    pub fn solve(&self) -> Au {
+        self.max_positive + self.min_negative
+    }
+}
@@ -0,0 +1,280 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Geometry in flow-relative and physical coordinates.
+//!
+//! Layout works with flow-relative values (inline and block axes), which are
+//! converted to physical ones (x and y) when building the display list.
+
+use app_units::Au;
+use std::ops::{Add, AddAssign, Sub};
+use style::logical_geometry::WritingMode;
+use style::values::computed::{LengthPercentage, LengthPercentageOrAuto, Size};
+
+pub mod flow_relative {
+    #[derive(Clone, Debug)]
+    pub struct Vec2<T> {
+        pub inline: T,
+        pub block: T,
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct Rect<T> {
+        pub start_corner: Vec2<T>,
+        pub size: Vec2<T>,
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct Sides<T> {
+        pub inline_start: T,
+        pub inline_end: T,
+        pub block_start: T,
+        pub block_end: T,
+    }
+}
+
+pub mod physical {
+    #[derive(Clone, Debug)]
+    pub struct Vec2<T> {
+        pub x: T,
+        pub y: T,
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct Rect<T> {
+        pub top_left: Vec2<T>,
+        pub size: Vec2<T>,
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct Sides<T> {
+        pub top: T,
+        pub left: T,
+        pub bottom: T,
+        pub right: T,
+    }
+}
+
+use self::flow_relative::{Rect, Sides, Vec2};
+
+impl<T: Clone> Vec2<T> {
+    pub fn map<U>(&self, f: impl Fn(&T) -> U) -> Vec2<U> {
+        Vec2 {
+            inline: f(&self.inline),
+            block: f(&self.block),
+        }
+    }
+}
+
+impl Vec2<Au> {
+    pub fn zero() -> Self {
+        Vec2 {
+            inline: Au(0),
+            block: Au(0),
+        }
+    }
+
+    // FIXME: only horizontal-tb left-to-right is supported.
+    pub fn to_physical(&self, _mode: WritingMode) -> physical::Vec2<Au> {
+        physical::Vec2 {
+            x: self.inline,
+            y: self.block,
+        }
+    }
+}
+
+impl<'a, T: Add<Output = T> + Copy> Add<&'a Vec2<T>> for &'a Vec2<T> {
+    type Output = Vec2<T>;
+
+    fn add(self, other: &'a Vec2<T>) -> Vec2<T> {
+        Vec2 {
+            inline: self.inline + other.inline,
+            block: self.block + other.block,
+        }
+    }
+}
+
+impl<'a, T: Sub<Output = T> + Copy> Sub<&'a Vec2<T>> for &'a Vec2<T> {
+    type Output = Vec2<T>;
+
+    fn sub(self, other: &'a Vec2<T>) -> Vec2<T> {
+        Vec2 {
+            inline: self.inline - other.inline,
+            block: self.block - other.block,
+        }
+    }
+}
+
+impl<'a, T: AddAssign<T> + Copy> AddAssign<&'a Vec2<T>> for Vec2<T> {
+    fn add_assign(&mut self, other: &'a Vec2<T>) {
+        self.inline += other.inline;
+        self.block += other.block;
+    }
+}
+
+impl Vec2<Size> {
+    /// Resolves the percentages of a `width`/`height` pair, `auto` and
+    /// block-axis percentages against an indefinite size becoming `None`.
+    pub fn percentages_relative_to(
+        &self,
+        inline_size: Au,
+        block_size: Option<Au>,
+    ) -> Vec2<Option<Au>> {
+        Vec2 {
+            inline: match self.inline {
+                Size::Auto => None,
+                Size::LengthPercentage(ref lp) => Some(lp.to_used_value(inline_size)),
+            },
+            block: match self.block {
+                Size::Auto => None,
+                Size::LengthPercentage(ref lp) => lp.maybe_to_used_value(block_size),
+            },
+        }
+    }
+}
+
+impl Rect<Au> {
+    pub fn zero() -> Self {
+        Rect {
+            start_corner: Vec2::zero(),
+            size: Vec2::zero(),
+        }
+    }
+
+    pub fn inflate(&self, sides: &Sides<Au>) -> Self {
+        Rect {
+            start_corner: Vec2 {
+                inline: self.start_corner.inline - sides.inline_start,
+                block: self.start_corner.block - sides.block_start,
+            },
+            size: Vec2 {
+                inline: self.size.inline + sides.inline_sum(),
+                block: self.size.block + sides.block_sum(),
+            },
+        }
+    }
+
+    /// Converts this rectangle to physical coordinates, relative to the
+    /// physical rectangle of its containing block.
+    pub fn to_physical(
+        &self,
+        mode: WritingMode,
+        containing_block: &physical::Rect<Au>,
+    ) -> physical::Rect<Au> {
+        let top_left = self.start_corner.to_physical(mode);
+        physical::Rect {
+            top_left: physical::Vec2 {
+                x: containing_block.top_left.x + top_left.x,
+                y: containing_block.top_left.y + top_left.y,
+            },
+            size: self.size.to_physical(mode),
+        }
+    }
+}
+
+impl<T> Sides<T> {
+    pub fn map<U>(&self, f: impl Fn(&T) -> U) -> Sides<U> {
+        Sides {
+            inline_start: f(&self.inline_start),
+            inline_end: f(&self.inline_end),
+            block_start: f(&self.block_start),
+            block_end: f(&self.block_end),
+        }
+    }
+}
+
+impl<T: Add<Output = T> + Copy> Sides<T> {
+    pub fn inline_sum(&self) -> T {
+        self.inline_start + self.inline_end
+    }
+
+    pub fn block_sum(&self) -> T {
+        self.block_start + self.block_end
+    }
+
+    pub fn start_corner(&self) -> Vec2<T> {
+        Vec2 {
+            inline: self.inline_start,
+            block: self.block_start,
+        }
+    }
+}
+
+impl Sides<Au> {
+    pub fn zero() -> Self {
+        Sides {
+            inline_start: Au(0),
+            inline_end: Au(0),
+            block_start: Au(0),
+            block_end: Au(0),
+        }
+    }
+
+    // FIXME: only horizontal-tb left-to-right is supported.
+    pub fn to_physical(&self, _mode: WritingMode) -> physical::Sides<Au> {
+        physical::Sides {
+            top: self.block_start,
+            left: self.inline_start,
+            bottom: self.block_end,
+            right: self.inline_end,
+        }
+    }
+}
+
+impl<'a, T: Add<Output = T> + Copy> Add<&'a Sides<T>> for &'a Sides<T> {
+    type Output = Sides<T>;
+
+    fn add(self, other: &'a Sides<T>) -> Sides<T> {
+        Sides {
+            inline_start: self.inline_start + other.inline_start,
+            inline_end: self.inline_end + other.inline_end,
+            block_start: self.block_start + other.block_start,
+            block_end: self.block_end + other.block_end,
+        }
+    }
+}
+
+impl Sides<LengthPercentage> {
+    /// Resolves percentages against the inline size of the containing block,
+    /// as padding does in both axes.
+    pub fn percentages_relative_to(&self, basis: Au) -> Sides<Au> {
+        self.map(|lp| lp.to_used_value(basis))
+    }
+}
+
+impl Sides<LengthPercentageOrAuto> {
+    /// Resolves percentages against the inline size of the containing block,
+    /// as margins do in both axes.
+    pub fn percentages_relative_to(&self, basis: Au) -> Sides<Option<Au>> {
+        self.map(|value| value.to_used_value(basis))
+    }
+}
+
+impl physical::Vec2<Au> {
+    pub fn zero() -> Self {
+        physical::Vec2 { x: Au(0), y: Au(0) }
+    }
+}
+
+impl physical::Rect<Au> {
+    pub fn zero() -> Self {
+        physical::Rect {
+            top_left: physical::Vec2::zero(),
+            size: physical::Vec2::zero(),
+        }
+    }
+
+    pub fn deflate(&self, sides: &physical::Sides<Au>) -> Self {
+        physical::Rect {
+            top_left: physical::Vec2 {
+                x: self.top_left.x + sides.left,
+                y: self.top_left.y + sides.top,
+            },
+            size: physical::Vec2 {
+                x: self.size.x - sides.left - sides.right,
+                y: self.size.y - sides.top - sides.bottom,
+            },
+        }
+    }
+}
@@ -10,13 +10,40 @@ extern crate serde;
 pub mod context;
 pub mod data;
 pub mod display_list;
-mod fragment;
+mod dom_traversal;
+mod flexbox;
+mod flow;
+mod formatting_contexts;
+mod fragments;
+mod geom;
 pub mod opaque_node;
+mod positioned;
 pub mod query;
+mod sizing;
+mod style_ext;
 pub mod traversal;
 pub mod wrapper;
 
-// For unit tests:
-pub use crate::fragment::Fragment;
+pub use flow::{BoxTreeRoot, FragmentTreeRoot};
 
+use crate::geom::flow_relative::Vec2;
+use app_units::Au;
 use servo_arc::Arc as ServoArc;
+use style::logical_geometry::WritingMode;
+
+/// The containing block of a box, of which the block size may not be known
+/// yet.
+///
+/// https://drafts.csswg.org/css2/visudet.html#containing-block-details
+pub(crate) struct ContainingBlock {
+    pub inline_size: Au,
+    pub block_size: Option<Au>,
+    pub mode: WritingMode,
+}
+
+/// The containing block of an absolutely positioned box, which is always
+/// fully known.
+pub(crate) struct DefiniteContainingBlock {
+    pub size: Vec2<Au>,
+    pub mode: WritingMode,
+}
@@ -0,0 +1,362 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Relatively and absolutely positioned boxes.
+//!
+//! Absolutely positioned boxes are laid out once the size of their
+//! containing block is known: until then, their static position is carried
+//! up the fragment tree by `AbsolutelyPositionedFragment`.
+
+use crate::context::LayoutContext;
+use crate::formatting_contexts::IndependentFormattingContext;
+use crate::fragments::{AnonymousFragment, BoxFragment, CollapsedBlockMargins, Fragment};
+use crate::geom::flow_relative::{Rect, Sides, Vec2};
+use crate::sizing::clamp_between_extremums;
+use crate::style_ext::ComputedValuesExt;
+use crate::{ContainingBlock, DefiniteContainingBlock};
+use app_units::Au;
+use style::computed_values::position::T as Position;
+use style::logical_geometry::WritingMode;
+use style::properties::ComputedValues;
+use style::values::computed::LengthPercentageOrAuto;
+
+pub(crate) struct AbsolutelyPositionedBox {
+    pub contents: IndependentFormattingContext,
+}
+
+pub(crate) struct AbsolutelyPositionedFragment<'box_> {
+    absolutely_positioned_box: &'box_ AbsolutelyPositionedBox,
+
+    /// The position the box would have if it were `position: static`,
+    /// relative to the content box of the fragment currently holding it.
+    pub static_position: Vec2<Au>,
+}
+
+impl AbsolutelyPositionedBox {
+    pub fn layout(&self, static_position: Vec2<Au>) -> AbsolutelyPositionedFragment {
+        AbsolutelyPositionedFragment {
+            absolutely_positioned_box: self,
+            static_position,
+        }
+    }
+}
+
+impl<'a> AbsolutelyPositionedFragment<'a> {
+    /// Lays out the absolutely positioned descendants of a box that
+    /// establishes their containing block, and adds their fragments to the
+    /// children of that box.
+    ///
+    /// The containing block is formed by the padding box:
+    /// https://drafts.csswg.org/css2/visudet.html#containing-block-details
+    pub(crate) fn in_positioned_containing_block(
+        layout_context: &LayoutContext,
+        absolute: &[Self],
+        fragments: &mut Vec<Fragment>,
+        content_rect_size: &Vec2<Au>,
+        padding: &Sides<Au>,
+        mode: WritingMode,
+    ) {
+        if absolute.is_empty() {
+            return;
+        }
+        let padding_rect = Rect {
+            size: content_rect_size.clone(),
+            start_corner: Vec2::zero(),
+        }
+        .inflate(padding);
+        let containing_block = DefiniteContainingBlock {
+            size: padding_rect.size.clone(),
+            mode,
+        };
+        let padding_start = padding.start_corner();
+        fragments.push(Fragment::Anonymous(AnonymousFragment {
+            children: absolute
+                .iter()
+                .map(|a| {
+                    a.layout(
+                        layout_context,
+                        &containing_block,
+                        &(&a.static_position + &padding_start),
+                    )
+                })
+                .collect(),
+            rect: padding_rect,
+        }))
+    }
+
+    /// Lays out this box against its containing block, the static position
+    /// being relative to it.
+    ///
+    /// FIXME: fixed positioning is treated like absolute positioning.
+    ///
+    /// https://drafts.csswg.org/css2/visudet.html#abs-non-replaced-width
+    /// https://drafts.csswg.org/css2/visudet.html#abs-non-replaced-height
+    pub(crate) fn layout(
+        &self,
+        layout_context: &LayoutContext,
+        containing_block: &DefiniteContainingBlock,
+        static_position: &Vec2<Au>,
+    ) -> Fragment {
+        let contents = &self.absolutely_positioned_box.contents;
+        let style = &contents.style;
+        let cbis = containing_block.size.inline;
+        let cbbs = containing_block.size.block;
+
+        let padding = style.padding().percentages_relative_to(cbis);
+        let border = style.border_width();
+        let computed_margin = style.margin().percentages_relative_to(cbis);
+        let pb = &padding + &border;
+        let box_offsets = style.box_offsets();
+        let box_size = style.content_box_size(cbis, Some(cbbs), &pb);
+        let min_box_size = style.content_min_box_size(cbis, Some(cbbs), &pb);
+        let max_box_size = style.content_max_box_size(cbis, Some(cbbs), &pb);
+
+        let inline_axis = solve_axis(
+            AxisConstraints {
+                containing_size: cbis,
+                static_position: static_position.inline,
+                start: box_offsets.inline_start.to_used_value(cbis),
+                end: box_offsets.inline_end.to_used_value(cbis),
+                margin_start: computed_margin.inline_start,
+                margin_end: computed_margin.inline_end,
+                padding_border_start: pb.inline_start,
+                padding_border_end: pb.inline_end,
+                size: box_size.inline,
+                min_size: min_box_size.inline,
+                max_size: max_box_size.inline,
+            },
+            |available_size| {
+                contents
+                    .inline_content_sizes(layout_context)
+                    .shrink_to_fit(available_size)
+            },
+        );
+
+        let block_start = box_offsets.block_start.to_used_value(cbbs);
+        let block_end = box_offsets.block_end.to_used_value(cbbs);
+        let block_size = match (box_size.block, block_start, block_end) {
+            (Some(size), _, _) => Some(size),
+            (None, Some(start), Some(end)) => Some(
+                (cbbs -
+                    start -
+                    end -
+                    pb.block_sum() -
+                    computed_margin.block_start.unwrap_or(Au(0)) -
+                    computed_margin.block_end.unwrap_or(Au(0)))
+                .max(Au(0)),
+            ),
+            (None, _, _) => None,
+        }
+        .map(|size| clamp_between_extremums(size, min_box_size.block, max_box_size.block));
+
+        let containing_block_for_children = ContainingBlock {
+            inline_size: inline_axis.size,
+            block_size,
+            mode: style.writing_mode,
+        };
+        // Absolutely positioned boxes are containing blocks for their own
+        // absolutely positioned descendants.
+        let mut nested_abspos = vec![];
+        let mut independent_layout = contents.layout(
+            layout_context,
+            &containing_block_for_children,
+            &mut nested_abspos,
+        );
+
+        let content_block_size = independent_layout.content_block_size;
+        let block_axis = solve_axis(
+            AxisConstraints {
+                containing_size: cbbs,
+                static_position: static_position.block,
+                start: block_start,
+                end: block_end,
+                margin_start: computed_margin.block_start,
+                margin_end: computed_margin.block_end,
+                padding_border_start: pb.block_start,
+                padding_border_end: pb.block_end,
+                size: block_size,
+                min_size: min_box_size.block,
+                max_size: max_box_size.block,
+            },
+            |_| content_block_size,
+        );
+
+        let content_rect = Rect {
+            start_corner: Vec2 {
+                inline: inline_axis.content_start,
+                block: block_axis.content_start,
+            },
+            size: Vec2 {
+                inline: inline_axis.size,
+                block: block_axis.size,
+            },
+        };
+        AbsolutelyPositionedFragment::in_positioned_containing_block(
+            layout_context,
+            &nested_abspos,
+            &mut independent_layout.fragments,
+            &content_rect.size,
+            &padding,
+            style.writing_mode,
+        );
+        let margin = Sides {
+            inline_start: inline_axis.margin_start,
+            inline_end: inline_axis.margin_end,
+            block_start: block_axis.margin_start,
+            block_end: block_axis.margin_end,
+        };
+        Fragment::Box(BoxFragment {
+            tag: contents.tag,
+            style: style.clone(),
+            children: independent_layout.fragments,
+            content_rect,
+            padding,
+            border,
+            block_margins_collapsed_with_children: CollapsedBlockMargins::from_margin(&margin),
+            margin,
+        })
+    }
+}
+
+struct AxisConstraints {
+    containing_size: Au,
+    static_position: Au,
+    start: Option<Au>,
+    end: Option<Au>,
+    margin_start: Option<Au>,
+    margin_end: Option<Au>,
+    padding_border_start: Au,
+    padding_border_end: Au,
+    size: Option<Au>,
+    min_size: Au,
+    max_size: Option<Au>,
+}
+
+struct AxisSolution {
+    margin_start: Au,
+    margin_end: Au,
+    size: Au,
+    /// Relative to the containing block.
+    content_start: Au,
+}
+
+/// Solves the constraint of one axis of an absolutely positioned box:
+///
+/// start + margin-start + padding-and-border + size + margin-end + end =
+/// size of the containing block
+///
+/// `auto_size` gives the size of the box when it doesn't follow from the
+/// other values, given the space available to it.
+fn solve_axis(constraints: AxisConstraints, auto_size: impl FnOnce(Au) -> Au) -> AxisSolution {
+    let AxisConstraints {
+        containing_size,
+        static_position,
+        start,
+        end,
+        margin_start,
+        margin_end,
+        padding_border_start,
+        padding_border_end,
+        size,
+        min_size,
+        max_size,
+    } = constraints;
+    let padding_border = padding_border_start + padding_border_end;
+    let auto_margins_as_zero =
+        margin_start.unwrap_or(Au(0)) + margin_end.unwrap_or(Au(0)) + padding_border;
+
+    let size = size.unwrap_or_else(|| match (start, end) {
+        (Some(start), Some(end)) => {
+            (containing_size - start - end - auto_margins_as_zero).max(Au(0))
+        },
+        _ => {
+            let start = start.unwrap_or(if end.is_none() {
+                static_position
+            } else {
+                Au(0)
+            });
+            let available_size =
+                containing_size - start - end.unwrap_or(Au(0)) - auto_margins_as_zero;
+            auto_size(available_size.max(Au(0)))
+        },
+    });
+    let size = clamp_between_extremums(size, min_size, max_size);
+
+    let (margin_start, margin_end) = match (start, end) {
+        (Some(start), Some(end)) => {
+            let free = containing_size - start - end - padding_border - size;
+            match (margin_start, margin_end) {
+                (None, None) if free >= Au(0) => (free / 2, free - free / 2),
+                (None, None) => (Au(0), free),
+                (None, Some(margin_end)) => (free - margin_end, margin_end),
+                (Some(margin_start), None) => (margin_start, free - margin_start),
+                // Over-constrained: the end offset is ignored.
+                (Some(margin_start), Some(margin_end)) => (margin_start, margin_end),
+            }
+        },
+        _ => (margin_start.unwrap_or(Au(0)), margin_end.unwrap_or(Au(0))),
+    };
+    let margin_box_start = match (start, end) {
+        (Some(start), _) => start,
+        (None, Some(end)) => {
+            containing_size - end - margin_end - padding_border - size - margin_start
+        },
+        (None, None) => static_position,
+    };
+    AxisSolution {
+        margin_start,
+        margin_end,
+        size,
+        content_start: margin_box_start + margin_start + padding_border_start,
+    }
+}
+
+pub(crate) fn adjust_static_positions(
+    absolutely_positioned_fragments: &mut [AbsolutelyPositionedFragment],
+    child_fragment_rect_start: &Vec2<Au>,
+) {
+    for abspos_fragment in absolutely_positioned_fragments {
+        abspos_fragment.static_position += child_fragment_rect_start;
+    }
+}
+
+/// The offset of a relatively positioned box from its normal position.
+///
+/// https://drafts.csswg.org/css2/visuren.html#relative-positioning
+pub(crate) fn relative_adjustement(
+    style: &ComputedValues,
+    containing_block: &ContainingBlock,
+) -> Vec2<Au> {
+    if style.get_box().position != Position::Relative {
+        return Vec2::zero();
+    }
+    fn adjust(start: Option<Au>, end: Option<Au>) -> Au {
+        match (start, end) {
+            (None, None) => Au(0),
+            (Some(start), _) => start,
+            (None, Some(end)) => -end,
+        }
+    }
+    let resolve_block = |value: &LengthPercentageOrAuto| match *value {
+        LengthPercentageOrAuto::Auto => None,
+        LengthPercentageOrAuto::LengthPercentage(ref lp) => {
+            lp.maybe_to_used_value(containing_block.block_size)
+        },
+    };
+    let box_offsets = style.box_offsets();
+    Vec2 {
+        inline: adjust(
+            box_offsets
+                .inline_start
+                .to_used_value(containing_block.inline_size),
+            box_offsets
+                .inline_end
+                .to_used_value(containing_block.inline_size),
+        ),
+        block: adjust(
+            resolve_block(&box_offsets.block_start),
+            resolve_block(&box_offsets.block_end),
+        ),
+    }
+}
@@ -0,0 +1,137 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Intrinsic inline sizes, as used by shrink-to-fit.
+//!
+//! https://drafts.csswg.org/css-sizing/#intrinsic-sizes
+
+use crate::style_ext::ComputedValuesExt;
+use app_units::Au;
+use style::computed_values::box_sizing::T as BoxSizing;
+use style::properties::ComputedValues;
+use style::values::computed::{LengthPercentageOrAuto, MaxSize, Size};
+
+#[derive(Clone, Debug)]
+pub(crate) struct ContentSizes {
+    pub min_content: Au,
+    pub max_content: Au,
+}
+
+impl ContentSizes {
+    pub fn zero() -> Self {
+        ContentSizes {
+            min_content: Au(0),
+            max_content: Au(0),
+        }
+    }
+
+    pub fn max(self, other: Self) -> Self {
+        ContentSizes {
+            min_content: self.min_content.max(other.min_content),
+            max_content: self.max_content.max(other.max_content),
+        }
+    }
+
+    pub fn max_assign(&mut self, other: &Self) {
+        self.min_content = self.min_content.max(other.min_content);
+        self.max_content = self.max_content.max(other.max_content);
+    }
+
+    /// https://drafts.csswg.org/css2/visudet.html#shrink-to-fit-float
+    pub fn shrink_to_fit(&self, available_size: Au) -> Au {
+        available_size.max(self.min_content).min(self.max_content)
+    }
+}
+
+/// Applies `min-*` and `max-*` to a used size, `min-*` winning.
+///
+/// https://drafts.csswg.org/css2/visudet.html#min-max-widths
+pub(crate) fn clamp_between_extremums(size: Au, min: Au, max: Option<Au>) -> Au {
+    let size = match max {
+        Some(max) => size.min(max),
+        None => size,
+    };
+    size.max(min)
+}
+
+/// The outer (margin box) intrinsic inline sizes of a box, given the
+/// intrinsic sizes of its contents.
+///
+/// Percentages can't be resolved while computing intrinsic sizes: they are
+/// treated as zero for margins and paddings, and as `auto` (or `none`) for
+/// sizes.
+pub(crate) fn outer_inline_content_sizes(
+    style: &ComputedValues,
+    inner_content_sizes: &ContentSizes,
+) -> ContentSizes {
+    let padding = style.padding();
+    let border = style.border_width();
+    let margin = style.margin();
+    let pb = padding
+        .inline_start
+        .maybe_to_used_value(None)
+        .unwrap_or(Au(0)) +
+        padding
+            .inline_end
+            .maybe_to_used_value(None)
+            .unwrap_or(Au(0)) +
+        border.inline_sum();
+    let m = margin_without_percentages(&margin.inline_start) +
+        margin_without_percentages(&margin.inline_end);
+
+    let box_sizing_adjustment = match style.get_position().box_sizing {
+        BoxSizing::ContentBox => Au(0),
+        BoxSizing::BorderBox => pb,
+    };
+    let size = match style.box_size().inline {
+        Size::Auto => None,
+        Size::LengthPercentage(ref lp) => lp.maybe_to_used_value(None),
+    };
+    let min_size = match style.min_box_size().inline {
+        Size::Auto => None,
+        Size::LengthPercentage(ref lp) => lp.maybe_to_used_value(None),
+    };
+    let max_size = match style.max_box_size().inline {
+        MaxSize::None => None,
+        MaxSize::LengthPercentage(ref lp) => lp.maybe_to_used_value(None),
+    };
+    let content_box = |size: Au| (size - box_sizing_adjustment).max(Au(0));
+    let min_size = min_size.map_or(Au(0), content_box);
+    let max_size = max_size.map(content_box);
+
+    let inner = match size {
+        Some(size) => {
+            let size = clamp_between_extremums(content_box(size), min_size, max_size);
+            ContentSizes {
+                min_content: size,
+                max_content: size,
+            }
+        },
+        None => ContentSizes {
+            min_content: clamp_between_extremums(
+                inner_content_sizes.min_content,
+                min_size,
+                max_size,
+            ),
+            max_content: clamp_between_extremums(
+                inner_content_sizes.max_content,
+                min_size,
+                max_size,
+            ),
+        },
+    };
+    ContentSizes {
+        min_content: inner.min_content + pb + m,
+        max_content: inner.max_content + pb + m,
+    }
+}
+
+fn margin_without_percentages(margin: &LengthPercentageOrAuto) -> Au {
+    match *margin {
+        LengthPercentageOrAuto::Auto => Au(0),
+        LengthPercentageOrAuto::LengthPercentage(ref lp) => {
+            lp.maybe_to_used_value(None).unwrap_or(Au(0))
+        },
+    }
+}
@@ -0,0 +1,229 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Flow-relative accessors for the computed values used by layout.
+
+use crate::geom::flow_relative::{Sides, Vec2};
+use app_units::Au;
+use style::computed_values::box_sizing::T as BoxSizing;
+use style::computed_values::position::T as Position;
+use style::properties::ComputedValues;
+use style::values::computed::{LengthPercentage, LengthPercentageOrAuto, MaxSize, Size};
+use style::values::specified::box_ as stylo;
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub enum Display {
+    None,
+    GeneratingBox(DisplayGeneratingBox),
+}
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub enum DisplayGeneratingBox {
+    OutsideInside {
+        outside: DisplayOutside,
+        inside: DisplayInside,
+    },
+}
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub enum DisplayOutside {
+    Block,
+    Inline,
+}
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub enum DisplayInside {
+    Flow,
+    FlowRoot,
+    Flex,
+}
+
+pub trait ComputedValuesExt {
+    fn box_offsets(&self) -> Sides<LengthPercentageOrAuto>;
+    fn box_size(&self) -> Vec2<Size>;
+    fn min_box_size(&self) -> Vec2<Size>;
+    fn max_box_size(&self) -> Vec2<MaxSize>;
+    fn padding(&self) -> Sides<LengthPercentage>;
+    fn border_width(&self) -> Sides<Au>;
+    fn margin(&self) -> Sides<LengthPercentageOrAuto>;
+    fn establishes_containing_block(&self) -> bool;
+    fn is_absolutely_positioned(&self) -> bool;
+    fn content_box_size(
+        &self,
+        containing_block_inline_size: Au,
+        containing_block_block_size: Option<Au>,
+        pbm: &Sides<Au>,
+    ) -> Vec2<Option<Au>>;
+    fn content_min_box_size(
+        &self,
+        containing_block_inline_size: Au,
+        containing_block_block_size: Option<Au>,
+        pbm: &Sides<Au>,
+    ) -> Vec2<Au>;
+    fn content_max_box_size(
+        &self,
+        containing_block_inline_size: Au,
+        containing_block_block_size: Option<Au>,
+        pbm: &Sides<Au>,
+    ) -> Vec2<Option<Au>>;
+}
+
+impl ComputedValuesExt for ComputedValues {
+    fn box_offsets(&self) -> Sides<LengthPercentageOrAuto> {
+        let position = self.logical_position();
+        Sides {
+            inline_start: position.inline_start,
+            inline_end: position.inline_end,
+            block_start: position.block_start,
+            block_end: position.block_end,
+        }
+    }
+
+    fn box_size(&self) -> Vec2<Size> {
+        Vec2 {
+            inline: self.content_inline_size(),
+            block: self.content_block_size(),
+        }
+    }
+
+    fn min_box_size(&self) -> Vec2<Size> {
+        Vec2 {
+            inline: self.min_inline_size(),
+            block: self.min_block_size(),
+        }
+    }
+
+    fn max_box_size(&self) -> Vec2<MaxSize> {
+        Vec2 {
+            inline: self.max_inline_size(),
+            block: self.max_block_size(),
+        }
+    }
+
+    fn padding(&self) -> Sides<LengthPercentage> {
+        let padding = self.logical_padding();
+        Sides {
+            inline_start: padding.inline_start,
+            inline_end: padding.inline_end,
+            block_start: padding.block_start,
+            block_end: padding.block_end,
+        }
+    }
+
+    fn border_width(&self) -> Sides<Au> {
+        let border = self.logical_border_width();
+        Sides {
+            inline_start: border.inline_start,
+            inline_end: border.inline_end,
+            block_start: border.block_start,
+            block_end: border.block_end,
+        }
+    }
+
+    fn margin(&self) -> Sides<LengthPercentageOrAuto> {
+        let margin = self.logical_margin();
+        Sides {
+            inline_start: margin.inline_start,
+            inline_end: margin.inline_end,
+            block_start: margin.block_start,
+            block_end: margin.block_end,
+        }
+    }
+
+    fn establishes_containing_block(&self) -> bool {
+        self.get_box().position != Position::Static
+    }
+
+    fn is_absolutely_positioned(&self) -> bool {
+        match self.get_box().position {
+            Position::Absolute | Position::Fixed => true,
+            Position::Static | Position::Relative => false,
+        }
+    }
+
+    /// The used `width` and `height` of the content box, if they aren't
+    /// `auto`, taking `box-sizing` into account.
+    fn content_box_size(
+        &self,
+        containing_block_inline_size: Au,
+        containing_block_block_size: Option<Au>,
+        pb: &Sides<Au>,
+    ) -> Vec2<Option<Au>> {
+        let size = self
+            .box_size()
+            .percentages_relative_to(containing_block_inline_size, containing_block_block_size);
+        match self.get_position().box_sizing {
+            BoxSizing::ContentBox => size,
+            BoxSizing::BorderBox => Vec2 {
+                inline: size
+                    .inline
+                    .map(|inline| (inline - pb.inline_sum()).max(Au(0))),
+                block: size.block.map(|block| (block - pb.block_sum()).max(Au(0))),
+            },
+        }
+    }
+
+    fn content_min_box_size(
+        &self,
+        containing_block_inline_size: Au,
+        containing_block_block_size: Option<Au>,
+        pb: &Sides<Au>,
+    ) -> Vec2<Au> {
+        let size = self
+            .min_box_size()
+            .percentages_relative_to(containing_block_inline_size, containing_block_block_size);
+        let (inline_pb, block_pb) = match self.get_position().box_sizing {
+            BoxSizing::ContentBox => (Au(0), Au(0)),
+            BoxSizing::BorderBox => (pb.inline_sum(), pb.block_sum()),
+        };
+        Vec2 {
+            inline: size.inline.map_or(Au(0), |s| (s - inline_pb).max(Au(0))),
+            block: size.block.map_or(Au(0), |s| (s - block_pb).max(Au(0))),
+        }
+    }
+
+    fn content_max_box_size(
+        &self,
+        containing_block_inline_size: Au,
+        containing_block_block_size: Option<Au>,
+        pb: &Sides<Au>,
+    ) -> Vec2<Option<Au>> {
+        let max_size = self.max_box_size();
+        let (inline_pb, block_pb) = match self.get_position().box_sizing {
+            BoxSizing::ContentBox => (Au(0), Au(0)),
+            BoxSizing::BorderBox => (pb.inline_sum(), pb.block_sum()),
+        };
+        Vec2 {
+            inline: max_size
+                .inline
+                .to_used_value(containing_block_inline_size)
+                .map(|s| (s - inline_pb).max(Au(0))),
+            block: match max_size.block {
+                MaxSize::None => None,
+                MaxSize::LengthPercentage(ref lp) => lp
+                    .maybe_to_used_value(containing_block_block_size)
+                    .map(|s| (s - block_pb).max(Au(0))),
+            },
+        }
+    }
+}
+
+impl From<stylo::Display> for Display {
+    fn from(packed: stylo::Display) -> Self {
+        let inside = match packed.inside() {
+            stylo::DisplayInside::None => return Display::None,
+            stylo::DisplayInside::Block | stylo::DisplayInside::Inline => DisplayInside::Flow,
+            stylo::DisplayInside::Flex => DisplayInside::Flex,
+            // FIXME: grid and table layout aren't implemented yet, their
+            // containers are laid out like `display: flow-root`.
+            _ => DisplayInside::FlowRoot,
+        };
+        let outside = match packed.outside() {
+            stylo::DisplayOutside::None => return Display::None,
+            stylo::DisplayOutside::Inline => DisplayOutside::Inline,
+            _ => DisplayOutside::Block,
+        };
+        Display::GeneratingBox(DisplayGeneratingBox::OutsideInside { outside, inside })
+    }
+}
@@ -46,6 +46,7 @@ use script::layout_exports::{
     TextTypeId,
 };
 use script::layout_exports::{Document, Element, Node, Text};
+use script::layout_exports::{HTMLSlotElement, LayoutHTMLSlotElementHelpers};
 use script::layout_exports::{LayoutCharacterDataHelpers, LayoutDocumentHelpers};
 use script::layout_exports::{
     LayoutDom, LayoutElementHelpers, LayoutNodeHelpers, LayoutShadowRootHelpers,
@@ -72,9 +73,12 @@ use std::fmt;
 use std::fmt::Debug;
 use std::hash::{Hash, Hasher};
 use std::marker::PhantomData;
+use std::mem;
 use std::ptr::NonNull;
+use std::slice;
 use std::sync::atomic::Ordering;
 use std::sync::Arc as StdArc;
+use style::animation::KeyframeEffectSample;
 use style::applicable_declarations::ApplicableDeclarationBlock;
 use style::attr::AttrValue;
 use style::context::SharedStyleContext;
@@ -101,6 +105,7 @@ pub unsafe fn drop_style_and_layout_data(data: OpaqueStyleAndLayoutData) {
 }
 
 #[derive(Clone, Copy)]
+#[repr(transparent)]
 pub struct ServoLayoutNode<'a> {
     /// The wrapped node.
     node: LayoutDom<Node>,
@@ -274,6 +279,10 @@ impl<'ln> TNode for ServoLayoutNode<'ln> {
     }
 
     fn traversal_parent(&self) -> Option<ServoLayoutElement<'ln>> {
+        // Slotted nodes are children of their slot in the flat tree.
+        if let Some(slot) = unsafe { self.node.assigned_slot_for_layout() } {
+            return Some(ServoLayoutElement::from_layout_js(slot));
+        }
         let parent = self.parent_node()?;
         if let Some(shadow) = parent.as_shadow_root() {
             return Some(shadow.host());
@@ -480,9 +489,28 @@ impl<'le> fmt::Debug for ServoLayoutElement<'le> {
     }
 }
 
+/// The children of an element in the flat tree: the nodes assigned to it if it
+/// is a slot with assigned nodes, and otherwise the children of either its shadow
+/// root or itself.
+pub enum ServoTraversalChildren<'a> {
+    Slotted(slice::Iter<'a, ServoLayoutNode<'a>>),
+    Dom(DomChildren<ServoLayoutNode<'a>>),
+}
+
+impl<'a> Iterator for ServoTraversalChildren<'a> {
+    type Item = ServoLayoutNode<'a>;
+
+    fn next(&mut self) -> Option<ServoLayoutNode<'a>> {
+        match *self {
+            ServoTraversalChildren::Slotted(ref mut iter) => iter.next().cloned(),
+            ServoTraversalChildren::Dom(ref mut iter) => iter.next(),
+        }
+    }
+}
+
 impl<'le> TElement for ServoLayoutElement<'le> {
     type ConcreteNode = ServoLayoutNode<'le>;
-    type TraversalChildrenIterator = DomChildren<Self::ConcreteNode>;
+    type TraversalChildrenIterator = ServoTraversalChildren<'le>;
 
     type FontMetricsProvider = ServoMetricsProvider;
 
@@ -491,13 +519,24 @@ impl<'le> TElement for ServoLayoutElement<'le> {
     }
 
     fn traversal_children(&self) -> LayoutIterator<Self::TraversalChildrenIterator> {
+        let assigned_nodes = self.assigned_nodes();
         LayoutIterator(if let Some(shadow) = self.shadow_root() {
-            shadow.as_node().dom_children()
+            ServoTraversalChildren::Dom(shadow.as_node().dom_children())
+        } else if !assigned_nodes.is_empty() {
+            ServoTraversalChildren::Slotted(assigned_nodes.iter())
         } else {
-            self.as_node().dom_children()
+            ServoTraversalChildren::Dom(self.as_node().dom_children())
         })
     }
 
+    fn inheritance_parent(&self) -> Option<Self> {
+        self.traversal_parent()
+    }
+
+    fn slotted_nodes(&self) -> &[Self::ConcreteNode] {
+        self.assigned_nodes()
+    }
+
     fn is_html_element(&self) -> bool {
         unsafe { self.element.is_html_element() }
     }
@@ -522,6 +561,10 @@ impl<'le> TElement for ServoLayoutElement<'le> {
         }
     }
 
+    fn keyframe_effect_samples(&self) -> &[KeyframeEffectSample] {
+        unsafe { self.element.keyframe_effect_samples() }
+    }
+
     fn state(&self) -> ElementState {
         self.element.get_state_for_layout()
     }
@@ -635,8 +678,9 @@ impl<'le> TElement for ServoLayoutElement<'le> {
     fn has_animations(&self) -> bool {
         // We use this function not only for Gecko but also for Servo to know if this element has
         // animations, so we maybe try to get the important rules of this element. This is used for
-        // off-main thread animations, but we don't support it on Servo, so return false directly.
-        false
+        // off-main thread animations, but we don't support it on Servo, so only account for the
+        // animations script runs on this element.
+        !self.keyframe_effect_samples().is_empty()
     }
 
     fn has_css_animations(&self) -> bool {
@@ -751,6 +795,20 @@ impl<'le> ServoLayoutElement<'le> {
         }
     }
 
+    /// The nodes assigned to this element if it is a slot.
+    fn assigned_nodes(&self) -> &'le [ServoLayoutNode<'le>] {
+        let slot = match self.element.downcast::<HTMLSlotElement>() {
+            Some(slot) => slot,
+            None => return &[],
+        };
+        unsafe {
+            // `ServoLayoutNode` is a transparent wrapper around `LayoutDom<Node>`.
+            mem::transmute::<&[LayoutDom<Node>], &'le [ServoLayoutNode<'le>]>(
+                slot.assigned_nodes_for_layout(),
+            )
+        }
+    }
+
     #[inline]
     fn get_attr_enum(&self, namespace: &Namespace, name: &LocalName) -> Option<&AttrValue> {
         unsafe { (*self.element.unsafe_get()).get_attr_for_layout(namespace, name) }
@@ -789,7 +847,7 @@ impl<'le> ServoLayoutElement<'le> {
             // we get that wrong.  I have in-flight patches to fix all this
             // stuff up, so we just always propagate this bit for now.
             el.set_dirty_descendants();
-            current = el.parent_element();
+            current = el.traversal_parent();
         }
     }
 }
@@ -957,6 +1015,8 @@ impl<'le> ::selectors::Element for ServoLayoutElement<'le> {
             NonTSPseudoClass::Indeterminate |
             NonTSPseudoClass::ReadWrite |
             NonTSPseudoClass::PlaceholderShown |
+            NonTSPseudoClass::Valid |
+            NonTSPseudoClass::Invalid |
             NonTSPseudoClass::Target => self
                 .element
                 .get_state_for_layout()
@@ -1008,6 +1068,15 @@ impl<'le> ::selectors::Element for ServoLayoutElement<'le> {
         unsafe { self.element.is_html_element() && self.local_name() == &local_name!("slot") }
     }
 
+    fn assigned_slot(&self) -> Option<Self> {
+        unsafe {
+            self.element
+                .upcast()
+                .assigned_slot_for_layout()
+                .map(ServoLayoutElement::from_layout_js)
+        }
+    }
+
     fn is_html_element_in_html_document(&self) -> bool {
         unsafe {
             if !self.element.is_html_element() {
@@ -1038,11 +1107,25 @@ impl<'a> PartialEq for ServoThreadSafeLayoutNode<'a> {
 
 impl<'ln> DangerousThreadSafeLayoutNode for ServoThreadSafeLayoutNode<'ln> {
     unsafe fn dangerous_first_child(&self) -> Option<Self> {
+        // A slot with assigned nodes renders them instead of its own children.
+        if let Some(element) = self.node.as_element() {
+            if let Some(first) = element.assigned_nodes().first() {
+                return Some(self.new_with_this_lifetime(&first.node));
+            }
+        }
         self.get_jsmanaged()
             .first_child_ref()
             .map(|node| self.new_with_this_lifetime(&node))
     }
     unsafe fn dangerous_next_sibling(&self) -> Option<Self> {
+        // Slotted nodes are siblings of the other nodes assigned to their slot.
+        if let Some(slot) = self.get_jsmanaged().assigned_slot_for_layout() {
+            let assigned_nodes = ServoLayoutElement::from_layout_js(slot).assigned_nodes();
+            let index = assigned_nodes.iter().position(|node| *node == self.node)?;
+            return assigned_nodes
+                .get(index + 1)
+                .map(|node| self.new_with_this_lifetime(&node.node));
+        }
         self.get_jsmanaged()
             .next_sibling_ref()
             .map(|node| self.new_with_this_lifetime(&node))
@@ -1105,11 +1188,22 @@ impl<'ln> ThreadSafeLayoutNode for ServoThreadSafeLayoutNode<'ln> {
     }
 
     fn parent_style(&self) -> Arc<ComputedValues> {
-        let parent = self.node.parent_node().unwrap().as_element().unwrap();
+        let parent = self.node.traversal_parent().unwrap();
         let parent_data = parent.get_data().unwrap().borrow();
         parent_data.styles.primary().clone()
     }
 
+    fn parent_selected_style(&self) -> Arc<ComputedValues> {
+        let parent = self.node.traversal_parent().unwrap();
+        let parent_data = parent.get_data().unwrap().borrow();
+        parent_data
+            .styles
+            .pseudos
+            .get(&PseudoElement::Selection)
+            .unwrap_or(parent_data.styles.primary())
+            .clone()
+    }
+
     fn debug_id(self) -> usize {
         self.node.debug_id()
     }
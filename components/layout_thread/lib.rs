@@ -45,7 +45,8 @@ use layout::context::LayoutContext;
 use layout::context::RegisteredPainter;
 use layout::context::RegisteredPainters;
 use layout::display_list::items::{OpaqueNode, WebRenderImageInfo};
-use layout::display_list::{IndexableText, ToLayout, WebRenderDisplayListConverter};
+use layout::display_list::WebRenderDisplayListConverter;
+use layout::display_list::{IndexableText, MaskImageCache, ToLayout};
 use layout::flow::{Flow, GetBaseFlow, ImmutableFlowUtils, MutableOwnedFlowUtils};
 use layout::flow_ref::FlowRef;
 use layout::incremental::{RelayoutMode, SpecialRestyleDamage};
@@ -60,6 +61,7 @@ use layout::query::{
     process_offset_parent_query, process_resolved_style_request, process_style_query,
 };
 use layout::sequential;
+use layout::svg::SvgImageCache;
 use layout::traversal::{
     ComputeStackingRelativePositions, PreorderFlowTraversal, RecalcStyleAndConstructFlows,
 };
@@ -78,13 +80,15 @@ use parking_lot::RwLock;
 use profile_traits::mem::{self as profile_mem, Report, ReportKind, ReportsChan};
 use profile_traits::time::{self as profile_time, profile, TimerMetadata};
 use profile_traits::time::{TimerMetadataFrameType, TimerMetadataReflowType};
+use script_layout_interface::message::WebFontSource;
 use script_layout_interface::message::{LayoutThreadInit, Msg, NodesFromPointQueryType, Reflow};
 use script_layout_interface::message::{QueryMsg, ReflowComplete, ReflowGoal, ScriptReflow};
 use script_layout_interface::rpc::TextIndexResponse;
 use script_layout_interface::rpc::{LayoutRPC, OffsetParentResponse, StyleResponse};
 use script_layout_interface::wrapper_traits::LayoutNode;
+use script_traits::LayoutMsg as ConstellationMsg;
 use script_traits::Painter;
-use script_traits::{ConstellationControlMsg, LayoutControlMsg, LayoutMsg as ConstellationMsg};
+use script_traits::{AnimationState, ConstellationControlMsg, LayoutControlMsg};
 use script_traits::{DrawAPaintImageResult, IFrameSizeMsg, PaintWorkletError, WindowSizeType};
 use script_traits::{ScrollState, UntrustedNodeAddress};
 use selectors::Element;
@@ -153,10 +157,10 @@ pub struct LayoutThread {
     pipeline_port: Receiver<LayoutControlMsg>,
 
     /// The port on which we receive messages from the font cache thread.
-    font_cache_receiver: Receiver<()>,
+    font_cache_receiver: Receiver<bool>,
 
     /// The channel on which the font cache can send messages to us.
-    font_cache_sender: IpcSender<()>,
+    font_cache_sender: IpcSender<bool>,
 
     /// A means of communication with the background hang monitor.
     background_hang_monitor: Box<dyn BackgroundHangMonitor>,
@@ -226,6 +230,19 @@ pub struct LayoutThread {
 
     webrender_image_cache: Arc<RwLock<FnvHashMap<(ServoUrl, UsePlaceholder), WebRenderImageInfo>>>,
 
+    /// The time at which each animated image started playing.
+    animated_image_start_times: Arc<RwLock<FnvHashMap<webrender_api::ImageKey, f64>>>,
+
+    /// Whether the last display list contains animated images that have not
+    /// finished playing.
+    animating_images: Cell<bool>,
+
+    /// The images the shapes of `<svg>` elements are rasterized into.
+    svg_image_cache: Arc<SvgImageCache>,
+
+    /// The images of the masks of `clip-path` and `mask-image`.
+    mask_image_cache: Arc<MaskImageCache>,
+
     /// The executors for paint worklets.
     registered_painters: RegisteredPaintersImpl,
 
@@ -478,7 +495,7 @@ fn add_font_face_rules(
     guard: &SharedRwLockReadGuard,
     device: &Device,
     font_cache_thread: &FontCacheThread,
-    font_cache_sender: &IpcSender<()>,
+    font_cache_sender: &IpcSender<bool>,
     outstanding_web_fonts_counter: &Arc<AtomicUsize>,
     load_webfonts_synchronously: bool,
 ) {
@@ -611,6 +628,10 @@ impl LayoutThread {
                 element_inner_text_response: String::new(),
             })),
             webrender_image_cache: Arc::new(RwLock::new(FnvHashMap::default())),
+            animated_image_start_times: Arc::new(RwLock::new(FnvHashMap::default())),
+            animating_images: Cell::new(false),
+            svg_image_cache: Arc::new(SvgImageCache::new(webrender_api_sender.create_api())),
+            mask_image_cache: Arc::new(MaskImageCache::new(webrender_api_sender.create_api())),
             timer: if pref!(layout.animations.test.enabled) {
                 Timer::test_mode()
             } else {
@@ -675,6 +696,10 @@ impl LayoutThread {
             image_cache: self.image_cache.clone(),
             font_cache_thread: Mutex::new(self.font_cache_thread.clone()),
             webrender_image_cache: self.webrender_image_cache.clone(),
+            animated_image_start_times: self.animated_image_start_times.clone(),
+            painted_animated_image: AtomicBool::new(false),
+            svg_image_cache: self.svg_image_cache.clone(),
+            mask_image_cache: self.mask_image_cache.clone(),
             pending_images: if script_initiated_layout {
                 Some(Mutex::new(vec![]))
             } else {
@@ -685,6 +710,11 @@ impl LayoutThread {
             } else {
                 None
             },
+            new_css_animations: if script_initiated_layout {
+                Some(Mutex::new(vec![]))
+            } else {
+                None
+            },
             registered_painters: &self.registered_painters,
         }
     }
@@ -713,6 +743,7 @@ impl LayoutThread {
             Msg::RegisterPaint(..) => LayoutHangAnnotation::RegisterPaint,
             Msg::SetNavigationStart(..) => LayoutHangAnnotation::SetNavigationStart,
             Msg::GetRunningAnimations(..) => LayoutHangAnnotation::GetRunningAnimations,
+            Msg::AddWebFont(..) => LayoutHangAnnotation::AddWebFont,
         };
         self.background_hang_monitor
             .notify_activity(HangAnnotation::Layout(hang_annotation));
@@ -891,6 +922,14 @@ impl LayoutThread {
             Msg::GetRunningAnimations(sender) => {
                 let _ = sender.send(self.running_animations.read().len());
             },
+            Msg::AddWebFont(family, source, sender) => match source {
+                WebFontSource::Sources(sources) => {
+                    self.font_cache_thread.add_web_font(family, sources, sender)
+                },
+                WebFontSource::Data(bytes) => self
+                    .font_cache_thread
+                    .add_web_font_data(family, bytes, sender),
+            },
         }
 
         true
@@ -1204,6 +1243,13 @@ impl LayoutThread {
                             IndexableText::default(),
                         );
                         rw_data.display_list = Some(build_state.to_display_list());
+                        self.update_animating_images(
+                            layout_context
+                                .painted_animated_image
+                                .load(Ordering::Relaxed),
+                        );
+                        self.svg_image_cache.evict_unpainted_images();
+                        self.mask_image_cache.evict_unpainted_images();
                     }
                 }
 
@@ -1267,6 +1313,25 @@ impl LayoutThread {
         );
     }
 
+    /// Lets the compositor know whether it should keep ticking animations for
+    /// the animated images of the new display list.
+    fn update_animating_images(&self, animating_images: bool) {
+        if animating_images == self.animating_images.replace(animating_images) {
+            return;
+        }
+        let animation_state = if animating_images || !self.running_animations.read().is_empty() {
+            AnimationState::AnimationsPresent
+        } else {
+            AnimationState::NoAnimationsPresent
+        };
+        self.constellation_chan
+            .send(ConstellationMsg::ChangeRunningAnimationsState(
+                self.id,
+                animation_state,
+            ))
+            .unwrap();
+    }
+
     /// The high-level routine that performs layout threads.
     fn handle_reflow<'a, 'b>(
         &mut self,
@@ -1326,7 +1391,7 @@ impl LayoutThread {
                         &QueryMsg::StyleQuery(_) => {
                             rw_data.style_response = StyleResponse(None);
                         },
-                        &QueryMsg::TextIndexQuery(..) => {
+                        &QueryMsg::TextIndexQuery(..) | &QueryMsg::TextIndexAtPointQuery(..) => {
                             rw_data.text_index_response = TextIndexResponse(None);
                         },
                         &QueryMsg::ElementInnerTextQuery(_) => {
@@ -1465,7 +1530,7 @@ impl LayoutThread {
             // Propagate the descendant bit up the ancestors. Do this before
             // the restyle calculation so that we can also do it for new
             // unstyled nodes, which the descendants bit helps us find.
-            if let Some(parent) = el.parent_element() {
+            if let Some(parent) = el.traversal_parent() {
                 unsafe { parent.note_dirty_descendant() };
             }
 
@@ -1611,6 +1676,12 @@ impl LayoutThread {
         };
         reflow_result.newly_transitioning_nodes = newly_transitioning_nodes;
 
+        let new_css_animations = match context.new_css_animations {
+            Some(ref animations) => std::mem::replace(&mut *animations.lock().unwrap(), vec![]),
+            None => vec![],
+        };
+        reflow_result.new_css_animations = new_css_animations;
+
         let mut root_flow = match self.root_flow.borrow().clone() {
             Some(root_flow) => root_flow,
             None => return,
@@ -1632,6 +1703,11 @@ impl LayoutThread {
                     rw_data.text_index_response =
                         TextIndexResponse(rw_data.indexable_text.text_index(node, point_in_node));
                 },
+                &QueryMsg::TextIndexAtPointQuery(node, point) => {
+                    let point = Point2D::new(Au::from_f32_px(point.x), Au::from_f32_px(point.y));
+                    rw_data.text_index_response =
+                        TextIndexResponse(rw_data.indexable_text.text_index_at_point(node, point));
+                },
                 &QueryMsg::NodeGeometryQuery(node) => {
                     rw_data.client_rect_response = process_node_geometry_request(node, root_flow);
                 },
@@ -1747,6 +1823,12 @@ impl LayoutThread {
             let snapshots = SnapshotMap::new();
             let mut layout_context = self.build_layout_context(guards, false, &snapshots);
 
+            // Animated images advance to their next frame when the display
+            // list is rebuilt.
+            if self.animating_images.get() {
+                rw_data.display_list = None;
+            }
+
             let invalid_nodes = {
                 // Perform an abbreviated style recalc that operates without access to the DOM.
                 let animations = self.running_animations.read();
@@ -1774,6 +1856,7 @@ impl LayoutThread {
             );
             assert!(layout_context.pending_images.is_none());
             assert!(layout_context.newly_transitioning_nodes.is_none());
+            assert!(layout_context.new_css_animations.is_none());
         }
     }
 
@@ -1794,6 +1877,13 @@ impl LayoutThread {
                 .map(|nodes| nodes.lock().unwrap());
             let newly_transitioning_nodes =
                 newly_transitioning_nodes.as_mut().map(|nodes| &mut **nodes);
+            let mut new_css_animations = context
+                .new_css_animations
+                .as_ref()
+                .map(|animations| animations.lock().unwrap());
+            let new_css_animations = new_css_animations
+                .as_mut()
+                .map(|animations| &mut **animations);
             // Kick off animations if any were triggered, expire completed ones.
             animation::update_animation_state::<ServoLayoutElement>(
                 &self.constellation_chan,
@@ -1802,9 +1892,11 @@ impl LayoutThread {
                 &mut *self.expired_animations.write(),
                 invalid_nodes,
                 newly_transitioning_nodes,
+                new_css_animations,
                 &self.new_animations_receiver,
                 self.id,
                 &self.timer,
+                self.animating_images.get(),
             );
         }
 
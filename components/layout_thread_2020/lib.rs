@@ -37,8 +37,7 @@ use histogram::Histogram;
 use ipc_channel::ipc::{self, IpcReceiver, IpcSender};
 use ipc_channel::router::ROUTER;
 use layout::context::LayoutContext;
-use layout::display_list::items::DisplayList;
-use layout::display_list::WebRenderDisplayListConverter;
+use layout::display_list::DisplayListBuilder;
 use layout::query::{
     process_content_box_request, process_content_boxes_request, LayoutRPCImpl, LayoutThreadData,
 };
@@ -49,6 +48,7 @@ use layout::query::{
     process_text_index_request,
 };
 use layout::traversal::RecalcStyleAndConstructFlows;
+use layout::{BoxTreeRoot, FragmentTreeRoot};
 use layout_traits::LayoutThreadFactory;
 use libc::c_void;
 use malloc_size_of::{MallocSizeOf, MallocSizeOfOps};
@@ -62,10 +62,12 @@ use net_traits::image_cache::ImageCache;
 use profile_traits::mem::{self as profile_mem, Report, ReportKind, ReportsChan};
 use profile_traits::time::{self as profile_time, profile, TimerMetadata};
 use profile_traits::time::{TimerMetadataFrameType, TimerMetadataReflowType};
+use script_layout_interface::message::WebFontSource;
 use script_layout_interface::message::{LayoutThreadInit, Msg, NodesFromPointQueryType};
 use script_layout_interface::message::{QueryMsg, ReflowComplete, ReflowGoal, ScriptReflow};
 use script_layout_interface::rpc::TextIndexResponse;
 use script_layout_interface::rpc::{LayoutRPC, OffsetParentResponse, StyleResponse};
+use script_layout_interface::wrapper_traits::LayoutNode;
 use script_traits::Painter;
 use script_traits::{ConstellationControlMsg, LayoutControlMsg, LayoutMsg as ConstellationMsg};
 use script_traits::{DrawAPaintImageResult, PaintWorkletError};
@@ -133,10 +135,10 @@ pub struct LayoutThread {
     pipeline_port: Receiver<LayoutControlMsg>,
 
     /// The port on which we receive messages from the font cache thread.
-    font_cache_receiver: Receiver<()>,
+    font_cache_receiver: Receiver<bool>,
 
     /// The channel on which the font cache can send messages to us.
-    font_cache_sender: IpcSender<()>,
+    font_cache_sender: IpcSender<bool>,
 
     /// A means of communication with the background hang monitor.
     background_hang_monitor: Box<dyn BackgroundHangMonitor>,
@@ -183,6 +185,9 @@ pub struct LayoutThread {
     /// constraints.
     viewport_size: UntypedSize2D<Au>,
 
+    /// The fragment tree of the document, as of the last layout.
+    fragment_tree_root: RefCell<Option<FragmentTreeRoot>>,
+
     /// A mutex to allow for fast, read-only RPC of layout's internal data
     /// structures, while still letting the LayoutThread modify them.
     ///
@@ -407,7 +412,7 @@ fn add_font_face_rules(
     guard: &SharedRwLockReadGuard,
     device: &Device,
     font_cache_thread: &FontCacheThread,
-    font_cache_sender: &IpcSender<()>,
+    font_cache_sender: &IpcSender<bool>,
     outstanding_web_fonts_counter: &Arc<AtomicUsize>,
     load_webfonts_synchronously: bool,
 ) {
@@ -506,6 +511,7 @@ impl LayoutThread {
             document_shared_lock: None,
             epoch: Cell::new(Epoch(0)),
             viewport_size: Size2D::new(Au(0), Au(0)),
+            fragment_tree_root: RefCell::new(None),
             webrender_api: webrender_api_sender.create_api(),
             webrender_document,
             stylist: Stylist::new(device, QuirksMode::NoQuirks),
@@ -577,6 +583,7 @@ impl LayoutThread {
                 traversal_flags: TraversalFlags::empty(),
                 snapshot_map: snapshot_map,
             },
+            font_cache_thread: Mutex::new(self.font_cache_thread.clone()),
         }
     }
 
@@ -604,6 +611,7 @@ impl LayoutThread {
             Msg::RegisterPaint(..) => LayoutHangAnnotation::RegisterPaint,
             Msg::SetNavigationStart(..) => LayoutHangAnnotation::SetNavigationStart,
             Msg::GetRunningAnimations(..) => LayoutHangAnnotation::GetRunningAnimations,
+            Msg::AddWebFont(..) => LayoutHangAnnotation::AddWebFont,
         };
         self.background_hang_monitor
             .notify_activity(HangAnnotation::Layout(hang_annotation));
@@ -766,6 +774,14 @@ impl LayoutThread {
             Msg::GetRunningAnimations(sender) => {
                 let _ = sender.send(0);
             },
+            Msg::AddWebFont(family, source, sender) => match source {
+                WebFontSource::Sources(sources) => {
+                    self.font_cache_thread.add_web_font(family, sources, sender)
+                },
+                WebFontSource::Data(bytes) => self
+                    .font_cache_thread
+                    .add_web_font_data(family, bytes, sender),
+            },
         }
 
         true
@@ -961,7 +977,7 @@ impl LayoutThread {
                         &QueryMsg::StyleQuery(_) => {
                             rw_data.style_response = StyleResponse(None);
                         },
-                        &QueryMsg::TextIndexQuery(..) => {
+                        &QueryMsg::TextIndexQuery(..) | &QueryMsg::TextIndexAtPointQuery(..) => {
                             rw_data.text_index_response = TextIndexResponse(None);
                         },
                         &QueryMsg::ElementInnerTextQuery(_) => {
@@ -1094,7 +1110,7 @@ impl LayoutThread {
             // Propagate the descendant bit up the ancestors. Do this before
             // the restyle calculation so that we can also do it for new
             // unstyled nodes, which the descendants bit helps us find.
-            if let Some(parent) = el.parent_element() {
+            if let Some(parent) = el.traversal_parent() {
                 unsafe { parent.note_dirty_descendant() };
             }
 
@@ -1179,6 +1195,10 @@ impl LayoutThread {
 
         layout_context = traversal.destroy();
 
+        let box_tree = BoxTreeRoot::construct(&layout_context, element.as_node().to_threadsafe());
+        let fragment_tree = box_tree.layout(&layout_context, self.viewport_size);
+        *self.fragment_tree_root.borrow_mut() = Some(fragment_tree);
+
         // GC the rule tree if some heuristics are met.
         unsafe {
             layout_context.style_context.stylist.rule_tree().maybe_gc();
@@ -1212,6 +1232,10 @@ impl LayoutThread {
                     );
                     rw_data.text_index_response = process_text_index_request(node, point_in_node);
                 },
+                &QueryMsg::TextIndexAtPointQuery(node, point) => {
+                    let point = Point2D::new(Au::from_f32_px(point.x), Au::from_f32_px(point.y));
+                    rw_data.text_index_response = process_text_index_request(node, point);
+                },
                 &QueryMsg::NodeGeometryQuery(node) => {
                     rw_data.client_rect_response = process_node_geometry_request(node);
                 },
@@ -1338,13 +1362,8 @@ impl LayoutThread {
             document.will_paint();
         }
 
-        let mut display_list = DisplayList {};
-
         debug!("Layout done!");
 
-        // TODO: Avoid the temporary conversion and build webrender sc/dl directly!
-        let builder = display_list.convert_to_webrender(self.id);
-
         let viewport_size = Size2D::new(
             self.viewport_size.width.to_f32_px(),
             self.viewport_size.height.to_f32_px(),
@@ -1356,6 +1375,11 @@ impl LayoutThread {
 
         let viewport_size = webrender_api::units::LayoutSize::from_untyped(viewport_size);
 
+        let mut display_list = DisplayListBuilder::new(self.id.to_webrender(), viewport_size);
+        if let Some(fragment_tree) = &*self.fragment_tree_root.borrow() {
+            fragment_tree.build_display_list(&mut display_list, self.viewport_size);
+        }
+
         // Observe notifications about rendered frames if needed right before
         // sending the display list to WebRender in order to set time related
         // Progressive Web Metrics.
@@ -1367,7 +1391,7 @@ impl LayoutThread {
             webrender_api::Epoch(epoch.0),
             None,
             viewport_size,
-            builder.finalize(),
+            display_list.wr.finalize(),
             true,
         );
         txn.generate_frame();
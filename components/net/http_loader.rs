@@ -106,6 +106,7 @@ pub fn set_default_accept(destination: Destination, headers: &mut HeaderMap) {
         // Step 3.3.
         Destination::Image => vec![
             QualityItem::new(mime::IMAGE_PNG, Quality::from_u16(1000)),
+            QualityItem::new("image/webp".parse().unwrap(), Quality::from_u16(1000)),
             QualityItem::new(mime::IMAGE_SVG, Quality::from_u16(1000)),
             QualityItem::new(mime::IMAGE_STAR, Quality::from_u16(800)),
             QualityItem::new(mime::STAR_STAR, Quality::from_u16(500)),
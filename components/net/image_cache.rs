@@ -4,6 +4,7 @@
 
 use embedder_traits::resources::{self, Resource};
 use immeta::load_from_buf;
+use ipc_channel::ipc::IpcSharedMemory;
 use net_traits::image::base::{load_from_memory, Image, ImageMetadata};
 use net_traits::image_cache::{CanRequestImages, ImageCache, ImageResponder};
 use net_traits::image_cache::{ImageOrMetadataAvailable, ImageResponse, ImageState};
@@ -34,13 +35,41 @@ use webrender_api::units::DeviceIntSize;
 // ======================================================================
 
 fn decode_bytes_sync(key: LoadKey, bytes: &[u8]) -> DecoderMsg {
-    let image = load_from_memory(bytes);
+    let image = decode_image(bytes);
     DecoderMsg {
         key: key,
         image: image,
     }
 }
 
+/// Decodes an image, which may be an SVG document.
+pub fn decode_image(bytes: &[u8]) -> Option<Image> {
+    if svg::is_svg(bytes) {
+        load_svg(bytes)
+    } else {
+        load_from_memory(bytes)
+    }
+}
+
+/// Rasterizes an SVG image at its intrinsic size.
+fn load_svg(bytes: &[u8]) -> Option<Image> {
+    let document = svg::Document::parse(bytes)?;
+    let mut pixmap = document.render();
+    if pixmap.width == 0 || pixmap.height == 0 {
+        return None;
+    }
+    pixmap.unpremultiply();
+    pixels::rgba8_byte_swap_colors_inplace(&mut pixmap.data);
+    Some(Image {
+        width: pixmap.width,
+        height: pixmap.height,
+        format: PixelFormat::BGRA8,
+        bytes: IpcSharedMemory::from_bytes(&pixmap.data),
+        id: None,
+        animation: None,
+    })
+}
+
 fn get_placeholder_image(
     webrender_api: &webrender_api::RenderApi,
     data: &[u8],
@@ -54,15 +83,37 @@ fn set_webrender_image_key(webrender_api: &webrender_api::RenderApi, image: &mut
     if image.id.is_some() {
         return;
     }
+    // Every frame of an animated image gets its own key, so that layout can
+    // switch between them without uploading pixels again.
+    let frame_count = image
+        .animation
+        .as_ref()
+        .map_or(1, |animation| animation.frames.len());
+    let ids: Vec<_> = (0..frame_count)
+        .map(|index| add_webrender_image(webrender_api, image, image.frame_bytes(index)))
+        .collect();
+    image.id = Some(ids[0]);
+    if let Some(ref mut animation) = image.animation {
+        for (frame, id) in animation.frames.iter_mut().zip(ids) {
+            frame.id = Some(id);
+        }
+    }
+}
+
+fn add_webrender_image(
+    webrender_api: &webrender_api::RenderApi,
+    image: &Image,
+    pixels: &[u8],
+) -> webrender_api::ImageKey {
     let mut bytes = Vec::new();
     let is_opaque = match image.format {
         PixelFormat::BGRA8 => {
-            bytes.extend_from_slice(&*image.bytes);
+            bytes.extend_from_slice(pixels);
             pixels::rgba8_premultiply_inplace(bytes.as_mut_slice())
         },
         PixelFormat::RGB8 => {
-            bytes.reserve(image.bytes.len() / 3 * 4);
-            for bgr in image.bytes.chunks(3) {
+            bytes.reserve(pixels.len() / 3 * 4);
+            for bgr in pixels.chunks(3) {
                 bytes.extend_from_slice(&[bgr[2], bgr[1], bgr[0], 0xff]);
             }
 
@@ -85,7 +136,7 @@ fn set_webrender_image_key(webrender_api: &webrender_api::RenderApi, image: &mut
     let mut txn = webrender_api::Transaction::new();
     txn.add_image(image_key, descriptor, data, None);
     webrender_api.update_resources(txn.resource_updates);
-    image.id = Some(image_key);
+    image_key
 }
 
 // ======================================================================
@@ -0,0 +1,1193 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use ipc_channel::ipc::{self, IpcReceiver, IpcSender};
+use net_traits::indexeddb_thread::{AsyncOperation, IndexedDBConnectionEvent};
+use net_traits::indexeddb_thread::{IndexedDBConnectionId, IndexedDBCursorDirection};
+use net_traits::indexeddb_thread::{IndexedDBError, IndexedDBIndexInfo, IndexedDBKeyPath};
+use net_traits::indexeddb_thread::{IndexedDBKeyRange, IndexedDBKeyType, IndexedDBObjectStoreInfo};
+use net_traits::indexeddb_thread::{IndexedDBOpenReply, IndexedDBRecord, IndexedDBResult};
+use net_traits::indexeddb_thread::{IndexedDBThreadMsg, IndexedDBTxnId, IndexedDBTxnMode};
+use openssl::sha::sha256;
+use servo_url::ImmutableOrigin;
+use std::borrow::ToOwned;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::fs;
+use std::io;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+/// <https://w3c.github.io/IndexedDB/#key-generator-construct>
+const MAX_GENERATED_KEY: u64 = 1 << 53;
+
+pub trait IndexedDBThreadFactory {
+    fn new(config_dir: Option<PathBuf>) -> Self;
+}
+
+impl IndexedDBThreadFactory for IpcSender<IndexedDBThreadMsg> {
+    /// Create an indexeddb thread
+    fn new(config_dir: Option<PathBuf>) -> IpcSender<IndexedDBThreadMsg> {
+        let (chan, port) = ipc::channel().unwrap();
+        thread::Builder::new()
+            .name("IndexedDBManager".to_owned())
+            .spawn(move || {
+                IndexedDBManager::new(port, config_dir).start();
+            })
+            .expect("Thread spawning failed");
+        chan
+    }
+}
+
+/// JSON objects can only be keyed by strings, so records are persisted
+/// as a sequence of (key, value) pairs.
+mod records_as_pairs {
+    use net_traits::indexeddb_thread::IndexedDBKeyType;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::collections::BTreeMap;
+
+    pub fn serialize<S>(
+        records: &BTreeMap<IndexedDBKeyType, Vec<u8>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_seq(records.iter())
+    }
+
+    pub fn deserialize<'de, D>(
+        deserializer: D,
+    ) -> Result<BTreeMap<IndexedDBKeyType, Vec<u8>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let pairs: Vec<(IndexedDBKeyType, Vec<u8>)> = Deserialize::deserialize(deserializer)?;
+        Ok(pairs.into_iter().collect())
+    }
+}
+
+/// <https://w3c.github.io/IndexedDB/#index-construct>
+#[derive(Clone, Deserialize, Serialize)]
+struct Index {
+    info: IndexedDBIndexInfo,
+    /// The (index key, primary key) pairs of the index, in index order.
+    entries: BTreeSet<(IndexedDBKeyType, IndexedDBKeyType)>,
+}
+
+/// <https://w3c.github.io/IndexedDB/#object-store-construct>
+#[derive(Clone, Deserialize, Serialize)]
+struct ObjectStore {
+    key_path: Option<IndexedDBKeyPath>,
+    auto_increment: bool,
+    /// <https://w3c.github.io/IndexedDB/#key-generator-current-number>
+    current_number: u64,
+    #[serde(with = "records_as_pairs")]
+    records: BTreeMap<IndexedDBKeyType, Vec<u8>>,
+    indexes: BTreeMap<String, Index>,
+}
+
+impl ObjectStore {
+    fn info(&self, name: &str) -> IndexedDBObjectStoreInfo {
+        IndexedDBObjectStoreInfo {
+            name: name.to_owned(),
+            key_path: self.key_path.clone(),
+            auto_increment: self.auto_increment,
+            indexes: self
+                .indexes
+                .values()
+                .map(|index| index.info.clone())
+                .collect(),
+        }
+    }
+
+    /// The (key, primary key) pairs of the object store or of one of its
+    /// indexes, in ascending order.
+    fn entries(
+        &self,
+        index: &Option<String>,
+    ) -> Result<Vec<(IndexedDBKeyType, IndexedDBKeyType)>, IndexedDBError> {
+        match *index {
+            Some(ref name) => self
+                .indexes
+                .get(name)
+                .map(|index| index.entries.iter().cloned().collect())
+                .ok_or(IndexedDBError::NotFound),
+            None => Ok(self
+                .records
+                .keys()
+                .map(|key| (key.clone(), key.clone()))
+                .collect()),
+        }
+    }
+
+    fn record(&self, key: IndexedDBKeyType, primary_key: IndexedDBKeyType) -> IndexedDBRecord {
+        let value = self.records.get(&primary_key).cloned().unwrap_or_default();
+        IndexedDBRecord {
+            key,
+            primary_key,
+            value,
+        }
+    }
+
+    fn remove_record(&mut self, key: &IndexedDBKeyType) {
+        self.records.remove(key);
+        for index in self.indexes.values_mut() {
+            index
+                .entries
+                .retain(|&(_, ref primary_key)| primary_key != key);
+        }
+    }
+}
+
+/// <https://w3c.github.io/IndexedDB/#database-construct>
+#[derive(Clone, Deserialize, Serialize)]
+struct Database {
+    version: u64,
+    object_stores: BTreeMap<String, ObjectStore>,
+}
+
+impl Database {
+    fn object_store_infos(&self) -> Vec<IndexedDBObjectStoreInfo> {
+        self.object_stores
+            .iter()
+            .map(|(name, store)| store.info(name))
+            .collect()
+    }
+}
+
+struct Transaction {
+    mode: IndexedDBTxnMode,
+    scope: Vec<String>,
+    /// Whether this transaction holds the locks over the object stores in its scope.
+    locked: bool,
+    /// The object stores modified by a readwrite transaction, as they were
+    /// before the first modification. `None` means the object store did not exist.
+    snapshot: HashMap<String, Option<ObjectStore>>,
+    /// The database as it was before a versionchange transaction started.
+    /// `None` means the database did not exist.
+    database_snapshot: Option<Option<Database>>,
+}
+
+/// An open or delete request waiting in the connection queue.
+/// <https://w3c.github.io/IndexedDB/#connection-queue>
+enum PendingRequest {
+    Open {
+        sender: IpcSender<IndexedDBOpenReply>,
+        events: IpcSender<IndexedDBConnectionEvent>,
+        version: Option<u64>,
+        blocked: bool,
+    },
+    Delete {
+        sender: IpcSender<IndexedDBOpenReply>,
+        blocked: bool,
+    },
+}
+
+/// An operation waiting for another transaction to release its locks.
+struct BlockedOperation {
+    sender: IpcSender<Result<IndexedDBResult, IndexedDBError>>,
+    store_name: String,
+    txn: IndexedDBTxnId,
+    operation: AsyncOperation,
+}
+
+#[derive(Default)]
+struct DatabaseState {
+    connections: HashMap<IndexedDBConnectionId, IpcSender<IndexedDBConnectionEvent>>,
+    pending: VecDeque<PendingRequest>,
+    /// The running versionchange transaction, if any.
+    upgrade: Option<IndexedDBTxnId>,
+    transactions: HashMap<IndexedDBTxnId, Transaction>,
+    /// The readwrite transaction that holds each object store.
+    locks: HashMap<String, IndexedDBTxnId>,
+    /// The readonly transactions that read each object store.
+    shared_locks: HashMap<String, HashSet<IndexedDBTxnId>>,
+    blocked: VecDeque<BlockedOperation>,
+}
+
+impl DatabaseState {
+    /// Readonly transactions share their object stores with each other, while
+    /// readwrite transactions wait for every other transaction whose scope
+    /// overlaps theirs, so that nothing reads uncommitted modifications.
+    ///
+    /// <https://w3c.github.io/IndexedDB/#transaction-scheduling>
+    fn acquire_locks(&mut self, txn_id: IndexedDBTxnId) -> bool {
+        if self.blocked.iter().any(|op| op.txn == txn_id) {
+            // Keep the operations of a transaction in order.
+            return false;
+        }
+        let txn = match self.transactions.get_mut(&txn_id) {
+            Some(txn) => txn,
+            None => return true,
+        };
+        if txn.locked || txn.mode == IndexedDBTxnMode::Versionchange {
+            return true;
+        }
+        let readwrite = txn.mode == IndexedDBTxnMode::Readwrite;
+        let locks = &mut self.locks;
+        let shared_locks = &mut self.shared_locks;
+        let available = txn.scope.iter().all(|store| {
+            locks.get(store).map_or(true, |owner| *owner == txn_id) &&
+                (!readwrite ||
+                    shared_locks
+                        .get(store)
+                        .map_or(true, |readers| readers.iter().all(|id| *id == txn_id)))
+        });
+        if !available {
+            return false;
+        }
+        for store in &txn.scope {
+            if readwrite {
+                locks.insert(store.clone(), txn_id);
+            } else {
+                shared_locks
+                    .entry(store.clone())
+                    .or_insert_with(HashSet::new)
+                    .insert(txn_id);
+            }
+        }
+        txn.locked = true;
+        true
+    }
+
+    fn release_locks(&mut self, txn_id: IndexedDBTxnId) {
+        self.locks.retain(|_, owner| *owner != txn_id);
+        for readers in self.shared_locks.values_mut() {
+            readers.remove(&txn_id);
+        }
+        self.shared_locks.retain(|_, readers| !readers.is_empty());
+    }
+}
+
+struct IndexedDBManager {
+    port: IpcReceiver<IndexedDBThreadMsg>,
+    config_dir: Option<PathBuf>,
+    /// The databases of each origin, keyed by their name.
+    databases: HashMap<String, HashMap<String, Database>>,
+    states: HashMap<(String, String), DatabaseState>,
+    next_id: u64,
+}
+
+impl IndexedDBManager {
+    fn new(port: IpcReceiver<IndexedDBThreadMsg>, config_dir: Option<PathBuf>) -> IndexedDBManager {
+        IndexedDBManager {
+            port: port,
+            config_dir: config_dir,
+            databases: HashMap::new(),
+            states: HashMap::new(),
+            next_id: 0,
+        }
+    }
+}
+
+impl IndexedDBManager {
+    fn start(&mut self) {
+        loop {
+            match self.port.recv().unwrap() {
+                IndexedDBThreadMsg::OpenDatabase {
+                    sender,
+                    events,
+                    origin,
+                    name,
+                    version,
+                } => {
+                    let key = self.state_key(&origin, name);
+                    self.state(&key).pending.push_back(PendingRequest::Open {
+                        sender,
+                        events,
+                        version,
+                        blocked: false,
+                    });
+                    self.process_pending(&key);
+                },
+                IndexedDBThreadMsg::DeleteDatabase(sender, origin, name) => {
+                    let key = self.state_key(&origin, name);
+                    self.state(&key).pending.push_back(PendingRequest::Delete {
+                        sender,
+                        blocked: false,
+                    });
+                    self.process_pending(&key);
+                },
+                IndexedDBThreadMsg::CloseConnection(origin, name, connection) => {
+                    let key = self.state_key(&origin, name);
+                    self.state(&key).connections.remove(&connection);
+                    self.process_pending(&key);
+                },
+                IndexedDBThreadMsg::StartTransaction(sender, origin, name, mode, scope) => {
+                    let key = self.state_key(&origin, name);
+                    let id = self.next_id();
+                    self.state(&key).transactions.insert(
+                        id,
+                        Transaction {
+                            mode,
+                            scope,
+                            locked: false,
+                            snapshot: HashMap::new(),
+                            database_snapshot: None,
+                        },
+                    );
+                    let _ = sender.send(id);
+                },
+                IndexedDBThreadMsg::CreateObjectStore {
+                    sender,
+                    origin,
+                    name,
+                    txn,
+                    store_name,
+                    key_path,
+                    auto_increment,
+                } => {
+                    let key = self.state_key(&origin, name);
+                    let result = self.with_upgrade(&key, txn, |db| {
+                        if db.object_stores.contains_key(&store_name) {
+                            return Err(IndexedDBError::Constraint);
+                        }
+                        db.object_stores.insert(
+                            store_name,
+                            ObjectStore {
+                                key_path,
+                                auto_increment,
+                                current_number: 1,
+                                records: BTreeMap::new(),
+                                indexes: BTreeMap::new(),
+                            },
+                        );
+                        Ok(())
+                    });
+                    let _ = sender.send(result);
+                },
+                IndexedDBThreadMsg::DeleteObjectStore(sender, origin, name, txn, store_name) => {
+                    let key = self.state_key(&origin, name);
+                    let result = self.with_upgrade(&key, txn, |db| {
+                        db.object_stores
+                            .remove(&store_name)
+                            .map(|_| ())
+                            .ok_or(IndexedDBError::NotFound)
+                    });
+                    let _ = sender.send(result);
+                },
+                IndexedDBThreadMsg::GetAllRecords(sender, origin, name, txn, store_name) => {
+                    let key = self.state_key(&origin, name);
+                    let records =
+                        self.visible_store(&key, txn, &store_name)
+                            .map_or(vec![], |store| {
+                                store
+                                    .records
+                                    .keys()
+                                    .map(|key| store.record(key.clone(), key.clone()))
+                                    .collect()
+                            });
+                    let _ = sender.send(records);
+                },
+                IndexedDBThreadMsg::CreateIndex {
+                    sender,
+                    origin,
+                    name,
+                    txn,
+                    store_name,
+                    info,
+                    keys,
+                } => {
+                    let key = self.state_key(&origin, name);
+                    let result = self.with_upgrade(&key, txn, |db| {
+                        let store = db
+                            .object_stores
+                            .get_mut(&store_name)
+                            .ok_or(IndexedDBError::NotFound)?;
+                        if store.indexes.contains_key(&info.name) {
+                            return Err(IndexedDBError::Constraint);
+                        }
+                        let mut entries = BTreeSet::new();
+                        for (primary_key, index_keys) in keys {
+                            for index_key in index_keys {
+                                if info.unique && entries.iter().any(|&(ref k, _)| *k == index_key)
+                                {
+                                    return Err(IndexedDBError::Constraint);
+                                }
+                                entries.insert((index_key, primary_key.clone()));
+                            }
+                        }
+                        store
+                            .indexes
+                            .insert(info.name.clone(), Index { info, entries });
+                        Ok(())
+                    });
+                    let _ = sender.send(result);
+                },
+                IndexedDBThreadMsg::DeleteIndex(sender, origin, name, txn, store_name, index) => {
+                    let key = self.state_key(&origin, name);
+                    let result = self.with_upgrade(&key, txn, |db| {
+                        db.object_stores
+                            .get_mut(&store_name)
+                            .and_then(|store| store.indexes.remove(&index))
+                            .map(|_| ())
+                            .ok_or(IndexedDBError::NotFound)
+                    });
+                    let _ = sender.send(result);
+                },
+                IndexedDBThreadMsg::Async(sender, origin, name, store_name, txn, operation) => {
+                    let key = self.state_key(&origin, name);
+                    self.handle_async(
+                        &key,
+                        BlockedOperation {
+                            sender,
+                            store_name,
+                            txn,
+                            operation,
+                        },
+                    );
+                },
+                IndexedDBThreadMsg::Commit(sender, origin, name, txn) => {
+                    let key = self.state_key(&origin, name);
+                    let result = self.commit(&key, txn);
+                    let _ = sender.send(result);
+                    self.process_blocked(&key);
+                    self.process_pending(&key);
+                },
+                IndexedDBThreadMsg::Abort(sender, origin, name, txn) => {
+                    let key = self.state_key(&origin, name);
+                    self.abort(&key, txn);
+                    let _ = sender.send(());
+                    self.process_blocked(&key);
+                    self.process_pending(&key);
+                },
+                IndexedDBThreadMsg::Exit(sender) => {
+                    // Nothing to do since we save committed transactions eagerly.
+                    let _ = sender.send(());
+                    break;
+                },
+            }
+        }
+    }
+
+    fn next_id(&mut self) -> u64 {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    /// Returns the key of the state of a database, loading the databases
+    /// of its origin from disk if needed.
+    fn state_key(&mut self, origin: &ImmutableOrigin, name: String) -> (String, String) {
+        let origin = origin.ascii_serialization();
+        if !self.databases.contains_key(&origin) {
+            let databases = match self.config_dir {
+                Some(ref config_dir) => load_databases(config_dir, &origin),
+                None => HashMap::new(),
+            };
+            self.databases.insert(origin.clone(), databases);
+        }
+        (origin, name)
+    }
+
+    fn state(&mut self, key: &(String, String)) -> &mut DatabaseState {
+        self.states
+            .entry(key.clone())
+            .or_insert_with(Default::default)
+    }
+
+    fn database(&self, key: &(String, String)) -> Option<&Database> {
+        self.databases
+            .get(&key.0)
+            .and_then(|databases| databases.get(&key.1))
+    }
+
+    /// Returns the object store `store_name` as seen by the transaction `txn_id`:
+    /// with its own pending modifications, but without those of other transactions.
+    fn visible_store(
+        &self,
+        key: &(String, String),
+        txn_id: IndexedDBTxnId,
+        store_name: &str,
+    ) -> Option<&ObjectStore> {
+        if let Some(state) = self.states.get(key) {
+            for (id, txn) in &state.transactions {
+                if *id == txn_id {
+                    continue;
+                }
+                if let Some(store) = txn.snapshot.get(store_name) {
+                    return store.as_ref();
+                }
+            }
+        }
+        self.database(key)
+            .and_then(|db| db.object_stores.get(store_name))
+    }
+
+    /// Returns the database without the modifications of the transactions
+    /// that are still running, except those of `committing`.
+    fn committed_database(
+        &self,
+        key: &(String, String),
+        committing: Option<IndexedDBTxnId>,
+    ) -> Option<Database> {
+        let mut db = self.database(key).cloned();
+        let state = match self.states.get(key) {
+            Some(state) => state,
+            None => return db,
+        };
+        for (id, txn) in &state.transactions {
+            if Some(*id) == committing {
+                continue;
+            }
+            if let Some(ref database_snapshot) = txn.database_snapshot {
+                return database_snapshot.clone();
+            }
+            if let Some(ref mut db) = db {
+                for (name, store) in &txn.snapshot {
+                    match *store {
+                        Some(ref store) => db.object_stores.insert(name.clone(), store.clone()),
+                        None => db.object_stores.remove(name),
+                    };
+                }
+            }
+        }
+        db
+    }
+
+    /// Persist the committed state of a single database, including the
+    /// modifications of the transaction `committing`.
+    fn save_database(
+        &self,
+        key: &(String, String),
+        committing: Option<IndexedDBTxnId>,
+    ) -> io::Result<()> {
+        let config_dir = match self.config_dir {
+            Some(ref config_dir) => config_dir,
+            None => return Ok(()),
+        };
+        let path = config_dir.join(file_name_for_database(&key.0, &key.1));
+        match self.committed_database(key, committing) {
+            Some(database) => {
+                fs::create_dir_all(config_dir.join(directory_for_origin(&key.0)))?;
+                let file = DatabaseFile {
+                    name: key.1.clone(),
+                    database,
+                };
+                let json = serde_json::to_vec(&file)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                // Write to a temporary file first so that a failed write
+                // doesn't lose the previously committed state.
+                let temp_path = path.with_extension("json.tmp");
+                fs::write(&temp_path, json)?;
+                fs::rename(&temp_path, &path)
+            },
+            None if path.exists() => fs::remove_file(&path),
+            None => Ok(()),
+        }
+    }
+
+    /// Run a schema modification on behalf of the versionchange transaction `txn`.
+    fn with_upgrade<F>(
+        &mut self,
+        key: &(String, String),
+        txn: IndexedDBTxnId,
+        f: F,
+    ) -> Result<(), IndexedDBError>
+    where
+        F: FnOnce(&mut Database) -> Result<(), IndexedDBError>,
+    {
+        if self.state(key).upgrade != Some(txn) {
+            return Err(IndexedDBError::Unknown);
+        }
+        match self
+            .databases
+            .get_mut(&key.0)
+            .and_then(|databases| databases.get_mut(&key.1))
+        {
+            Some(db) => f(db),
+            None => Err(IndexedDBError::Unknown),
+        }
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#connection-queue>
+    fn process_pending(&mut self, key: &(String, String)) {
+        loop {
+            let old_version = self.database(key).map(|db| db.version);
+            let next_id = self.next_id + 1;
+            let state = self.states.get_mut(key).expect("no database state");
+            if state.upgrade.is_some() {
+                return;
+            }
+            let has_connections = !state.connections.is_empty();
+            let request = match state.pending.front_mut() {
+                Some(request) => request,
+                None => return,
+            };
+
+            match *request {
+                PendingRequest::Open {
+                    ref sender,
+                    version,
+                    ref mut blocked,
+                    ..
+                } => {
+                    let version = version.unwrap_or(old_version.unwrap_or(1));
+                    let current = old_version.unwrap_or(0);
+                    if version < current {
+                        let _ = sender.send(IndexedDBOpenReply::Error(IndexedDBError::Version));
+                        state.pending.pop_front();
+                        continue;
+                    }
+                    if version > current && has_connections {
+                        // https://w3c.github.io/IndexedDB/#run-an-upgrade-transaction
+                        // Wait for the other connections to close.
+                        if !*blocked {
+                            *blocked = true;
+                            for events in state.connections.values() {
+                                let _ = events.send(IndexedDBConnectionEvent::VersionChange {
+                                    old_version: current,
+                                    new_version: Some(version),
+                                });
+                            }
+                            let _ = sender.send(IndexedDBOpenReply::Blocked {
+                                old_version: current,
+                                new_version: Some(version),
+                            });
+                        }
+                        return;
+                    }
+                },
+                PendingRequest::Delete {
+                    ref sender,
+                    ref mut blocked,
+                } => {
+                    if has_connections {
+                        if !*blocked {
+                            *blocked = true;
+                            let current = old_version.unwrap_or(0);
+                            for events in state.connections.values() {
+                                let _ = events.send(IndexedDBConnectionEvent::VersionChange {
+                                    old_version: current,
+                                    new_version: None,
+                                });
+                            }
+                            let _ = sender.send(IndexedDBOpenReply::Blocked {
+                                old_version: current,
+                                new_version: None,
+                            });
+                        }
+                        return;
+                    }
+                },
+            }
+
+            // The request can proceed.
+            let request = state.pending.pop_front().unwrap();
+            match request {
+                PendingRequest::Open {
+                    sender,
+                    events,
+                    version,
+                    ..
+                } => {
+                    let connection = next_id;
+                    self.next_id = next_id;
+                    state.connections.insert(connection, events);
+                    let version = version.unwrap_or(old_version.unwrap_or(1));
+                    let current = old_version.unwrap_or(0);
+                    if version == current {
+                        let object_stores = self
+                            .database(key)
+                            .map_or(vec![], |db| db.object_store_infos());
+                        let _ = sender.send(IndexedDBOpenReply::Opened {
+                            connection,
+                            version,
+                            object_stores,
+                        });
+                        continue;
+                    }
+
+                    // https://w3c.github.io/IndexedDB/#upgrade-transaction-steps
+                    let txn = self.next_id();
+                    let databases = self.databases.get_mut(&key.0).expect("origin not loaded");
+                    let database_snapshot = databases.get(&key.1).cloned();
+                    let db = databases.entry(key.1.clone()).or_insert_with(|| Database {
+                        version: 0,
+                        object_stores: BTreeMap::new(),
+                    });
+                    db.version = version;
+                    let object_stores = db.object_store_infos();
+                    let state = self.states.get_mut(key).expect("no database state");
+                    state.transactions.insert(
+                        txn,
+                        Transaction {
+                            mode: IndexedDBTxnMode::Versionchange,
+                            scope: vec![],
+                            locked: true,
+                            snapshot: HashMap::new(),
+                            database_snapshot: Some(database_snapshot),
+                        },
+                    );
+                    state.upgrade = Some(txn);
+                    let _ = sender.send(IndexedDBOpenReply::Upgrade {
+                        connection,
+                        transaction: txn,
+                        old_version: current,
+                        new_version: version,
+                        object_stores,
+                    });
+                    return;
+                },
+                PendingRequest::Delete { sender, .. } => {
+                    let old_version = self
+                        .databases
+                        .get_mut(&key.0)
+                        .and_then(|databases| databases.remove(&key.1))
+                        .map_or(0, |db| db.version);
+                    if let Err(e) = self.save_database(key, None) {
+                        warn!("Could not remove indexeddb database ({})", e);
+                    }
+                    let _ = sender.send(IndexedDBOpenReply::Deleted { old_version });
+                },
+            }
+        }
+    }
+
+    fn handle_async(&mut self, key: &(String, String), op: BlockedOperation) {
+        let state = self.state(key);
+        if !state.acquire_locks(op.txn) {
+            state.blocked.push_back(op);
+            return;
+        }
+        let txn = match state.transactions.get_mut(&op.txn) {
+            Some(txn) => txn,
+            None => {
+                let _ = op.sender.send(Err(IndexedDBError::Abort));
+                return;
+            },
+        };
+        let result = match self
+            .databases
+            .get_mut(&key.0)
+            .and_then(|databases| databases.get_mut(&key.1))
+        {
+            Some(db) => execute(db, txn, &op.store_name, op.operation),
+            None => Err(IndexedDBError::Unknown),
+        };
+        let _ = op.sender.send(result);
+    }
+
+    fn process_blocked(&mut self, key: &(String, String)) {
+        let blocked = mem::replace(&mut self.state(key).blocked, VecDeque::new());
+        for op in blocked {
+            self.handle_async(key, op);
+        }
+    }
+
+    fn finish_transaction(
+        &mut self,
+        key: &(String, String),
+        txn_id: IndexedDBTxnId,
+    ) -> Option<Transaction> {
+        let state = self.state(key);
+        let txn = state.transactions.remove(&txn_id)?;
+        state.release_locks(txn_id);
+        if state.upgrade == Some(txn_id) {
+            state.upgrade = None;
+        }
+        Some(txn)
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#commit-a-transaction>
+    fn commit(
+        &mut self,
+        key: &(String, String),
+        txn_id: IndexedDBTxnId,
+    ) -> Result<(), IndexedDBError> {
+        let mode = match self.state(key).transactions.get(&txn_id) {
+            Some(txn) => txn.mode,
+            None => return Ok(()),
+        };
+        if mode != IndexedDBTxnMode::Readonly {
+            // Step 5: abort the transaction if its changes can't be written.
+            if let Err(e) = self.save_database(key, Some(txn_id)) {
+                warn!("Could not save indexeddb database ({})", e);
+                self.abort(key, txn_id);
+                return Err(IndexedDBError::Unknown);
+            }
+        }
+        self.finish_transaction(key, txn_id);
+        Ok(())
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#abort-a-transaction>
+    fn abort(&mut self, key: &(String, String), txn_id: IndexedDBTxnId) {
+        let txn = match self.finish_transaction(key, txn_id) {
+            Some(txn) => txn,
+            None => return,
+        };
+
+        // Operations that never ran are aborted too.
+        let state = self.state(key);
+        let (aborted, remaining) = mem::replace(&mut state.blocked, VecDeque::new())
+            .into_iter()
+            .partition::<VecDeque<_>, _>(|op| op.txn == txn_id);
+        state.blocked = remaining;
+        for op in aborted {
+            let _ = op.sender.send(Err(IndexedDBError::Abort));
+        }
+
+        let databases = self.databases.get_mut(&key.0).expect("origin not loaded");
+        if let Some(database_snapshot) = txn.database_snapshot {
+            match database_snapshot {
+                Some(db) => databases.insert(key.1.clone(), db),
+                None => databases.remove(&key.1),
+            };
+        } else if let Some(db) = databases.get_mut(&key.1) {
+            for (name, store) in txn.snapshot {
+                match store {
+                    Some(store) => db.object_stores.insert(name, store),
+                    None => db.object_stores.remove(&name),
+                };
+            }
+        }
+    }
+}
+
+/// A persisted database, with its name since file names are hashes.
+#[derive(Deserialize, Serialize)]
+struct DatabaseFile {
+    name: String,
+    database: Database,
+}
+
+/// Files are named after a SHA-256 hash, since database names can be of any
+/// length. Origins contain no NUL, which makes the hashed bytes unambiguous.
+fn hash_file_name(origin: &str, name: Option<&str>) -> String {
+    let mut data = origin.as_bytes().to_vec();
+    if let Some(name) = name {
+        data.push(0);
+        data.extend_from_slice(name.as_bytes());
+    }
+    sha256(&data).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn directory_for_origin(origin: &str) -> String {
+    format!("indexeddb/{}", hash_file_name(origin, None))
+}
+
+fn file_name_for_database(origin: &str, name: &str) -> String {
+    format!(
+        "{}/{}.json",
+        directory_for_origin(origin),
+        hash_file_name(origin, Some(name))
+    )
+}
+
+/// Load every database of `origin` persisted in `config_dir`.
+fn load_databases(config_dir: &Path, origin: &str) -> HashMap<String, Database> {
+    let mut databases = HashMap::new();
+    let entries = match fs::read_dir(config_dir.join(directory_for_origin(origin))) {
+        Ok(entries) => entries,
+        Err(_) => return databases,
+    };
+    for entry in entries.filter_map(Result::ok) {
+        let path = entry.path();
+        if path
+            .extension()
+            .map_or(true, |extension| extension != "json")
+        {
+            continue;
+        }
+        let file: DatabaseFile = match fs::read(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|bytes| serde_json::from_slice(&bytes).map_err(|e| e.to_string()))
+        {
+            Ok(file) => file,
+            Err(e) => {
+                warn!("Could not load {} ({})", path.display(), e);
+                continue;
+            },
+        };
+        databases.insert(file.name, file.database);
+    }
+    databases
+}
+
+/// Returns the object store `name`, saving its current state in the transaction
+/// the first time it is modified.
+fn store_for_write<'a>(
+    db: &'a mut Database,
+    txn: &mut Transaction,
+    name: &str,
+) -> Result<&'a mut ObjectStore, IndexedDBError> {
+    if txn.mode == IndexedDBTxnMode::Readonly {
+        return Err(IndexedDBError::Unknown);
+    }
+    if txn.mode == IndexedDBTxnMode::Readwrite && !txn.snapshot.contains_key(name) {
+        txn.snapshot
+            .insert(name.to_owned(), db.object_stores.get(name).cloned());
+    }
+    db.object_stores
+        .get_mut(name)
+        .ok_or(IndexedDBError::NotFound)
+}
+
+fn take_count<T>(items: impl Iterator<Item = T>, count: Option<u32>) -> Vec<T> {
+    match count {
+        Some(count) if count > 0 => items.take(count as usize).collect(),
+        _ => items.collect(),
+    }
+}
+
+fn execute(
+    db: &mut Database,
+    txn: &mut Transaction,
+    store_name: &str,
+    operation: AsyncOperation,
+) -> Result<IndexedDBResult, IndexedDBError> {
+    match operation {
+        AsyncOperation::GetItem { index, range } => {
+            let store = db
+                .object_stores
+                .get(store_name)
+                .ok_or(IndexedDBError::NotFound)?;
+            let record = store
+                .entries(&index)?
+                .into_iter()
+                .find(|&(ref key, _)| range.contains(key))
+                .map(|(key, primary_key)| store.record(key, primary_key));
+            Ok(IndexedDBResult::Record(record))
+        },
+        AsyncOperation::GetKey { index, range } => {
+            let store = db
+                .object_stores
+                .get(store_name)
+                .ok_or(IndexedDBError::NotFound)?;
+            let key = store
+                .entries(&index)?
+                .into_iter()
+                .find(|&(ref key, _)| range.contains(key))
+                .map(|(_, primary_key)| primary_key);
+            Ok(IndexedDBResult::Key(key))
+        },
+        AsyncOperation::GetAll {
+            index,
+            range,
+            count,
+        } => {
+            let store = db
+                .object_stores
+                .get(store_name)
+                .ok_or(IndexedDBError::NotFound)?;
+            let entries = store
+                .entries(&index)?
+                .into_iter()
+                .filter(|&(ref key, _)| range.contains(key))
+                .map(|(key, primary_key)| store.record(key, primary_key));
+            Ok(IndexedDBResult::Records(take_count(entries, count)))
+        },
+        AsyncOperation::GetAllKeys {
+            index,
+            range,
+            count,
+        } => {
+            let store = db
+                .object_stores
+                .get(store_name)
+                .ok_or(IndexedDBError::NotFound)?;
+            let keys = store
+                .entries(&index)?
+                .into_iter()
+                .filter(|&(ref key, _)| range.contains(key))
+                .map(|(_, primary_key)| primary_key);
+            Ok(IndexedDBResult::Keys(take_count(keys, count)))
+        },
+        AsyncOperation::Count { index, range } => {
+            let store = db
+                .object_stores
+                .get(store_name)
+                .ok_or(IndexedDBError::NotFound)?;
+            let count = store
+                .entries(&index)?
+                .iter()
+                .filter(|&&(ref key, _)| range.contains(key))
+                .count();
+            Ok(IndexedDBResult::Count(count as u64))
+        },
+        AsyncOperation::PutItem {
+            key,
+            value,
+            index_keys,
+            overwrite,
+        } => {
+            // https://w3c.github.io/IndexedDB/#store-a-record-into-an-object-store
+            let store = store_for_write(db, txn, store_name)?;
+            let key = match key {
+                Some(key) => {
+                    // https://w3c.github.io/IndexedDB/#possibly-update-the-key-generator
+                    if let IndexedDBKeyType::Number(number) = key {
+                        if store.auto_increment && number >= store.current_number as f64 {
+                            store.current_number = (number.floor() as u64)
+                                .saturating_add(1)
+                                .min(MAX_GENERATED_KEY + 1);
+                        }
+                    }
+                    key
+                },
+                None => {
+                    // https://w3c.github.io/IndexedDB/#generate-a-key
+                    if store.current_number > MAX_GENERATED_KEY {
+                        return Err(IndexedDBError::Constraint);
+                    }
+                    let key = IndexedDBKeyType::Number(store.current_number as f64);
+                    store.current_number += 1;
+                    key
+                },
+            };
+            if !overwrite && store.records.contains_key(&key) {
+                return Err(IndexedDBError::Constraint);
+            }
+            for &(ref name, ref keys) in &index_keys {
+                let index = match store.indexes.get(name) {
+                    Some(index) => index,
+                    None => continue,
+                };
+                if index.info.unique &&
+                    index
+                        .entries
+                        .iter()
+                        .any(|&(ref index_key, ref primary_key)| {
+                            *primary_key != key && keys.contains(index_key)
+                        })
+                {
+                    return Err(IndexedDBError::Constraint);
+                }
+            }
+            store.remove_record(&key);
+            store.records.insert(key.clone(), value);
+            for (name, keys) in index_keys {
+                if let Some(index) = store.indexes.get_mut(&name) {
+                    for index_key in keys {
+                        index.entries.insert((index_key, key.clone()));
+                    }
+                }
+            }
+            Ok(IndexedDBResult::Key(Some(key)))
+        },
+        AsyncOperation::RemoveItem(range) => {
+            let store = store_for_write(db, txn, store_name)?;
+            let keys: Vec<IndexedDBKeyType> = store
+                .records
+                .keys()
+                .filter(|key| range.contains(key))
+                .cloned()
+                .collect();
+            for key in keys {
+                store.remove_record(&key);
+            }
+            Ok(IndexedDBResult::Done)
+        },
+        AsyncOperation::Clear => {
+            let store = store_for_write(db, txn, store_name)?;
+            store.records.clear();
+            for index in store.indexes.values_mut() {
+                index.entries.clear();
+            }
+            Ok(IndexedDBResult::Done)
+        },
+        AsyncOperation::Iterate {
+            index,
+            range,
+            direction,
+            position,
+            target,
+            count,
+        } => {
+            let store = db
+                .object_stores
+                .get(store_name)
+                .ok_or(IndexedDBError::NotFound)?;
+            let record = iterate(
+                store.entries(&index)?,
+                &range,
+                direction,
+                position,
+                target,
+                count,
+            )
+            .map(|(key, primary_key)| store.record(key, primary_key));
+            Ok(IndexedDBResult::Record(record))
+        },
+    }
+}
+
+/// <https://w3c.github.io/IndexedDB/#iterate-a-cursor>
+fn iterate(
+    entries: Vec<(IndexedDBKeyType, IndexedDBKeyType)>,
+    range: &IndexedDBKeyRange,
+    direction: IndexedDBCursorDirection,
+    position: Option<(IndexedDBKeyType, IndexedDBKeyType)>,
+    target: Option<(IndexedDBKeyType, Option<IndexedDBKeyType>)>,
+    count: u32,
+) -> Option<(IndexedDBKeyType, IndexedDBKeyType)> {
+    let mut entries: Vec<_> = entries
+        .into_iter()
+        .filter(|&(ref key, _)| range.contains(key))
+        .collect();
+    match direction {
+        IndexedDBCursorDirection::Next => {},
+        IndexedDBCursorDirection::NextUnique => {
+            // Entries are sorted, so this keeps the lowest primary key of each key.
+            entries.dedup_by(|a, b| a.0 == b.0);
+        },
+        IndexedDBCursorDirection::Prev => entries.reverse(),
+        IndexedDBCursorDirection::PrevUnique => {
+            entries.dedup_by(|a, b| a.0 == b.0);
+            entries.reverse();
+        },
+    }
+    let forward = match direction {
+        IndexedDBCursorDirection::Next | IndexedDBCursorDirection::NextUnique => true,
+        IndexedDBCursorDirection::Prev | IndexedDBCursorDirection::PrevUnique => false,
+    };
+    let unique = match direction {
+        IndexedDBCursorDirection::NextUnique | IndexedDBCursorDirection::PrevUnique => true,
+        IndexedDBCursorDirection::Next | IndexedDBCursorDirection::Prev => false,
+    };
+
+    let mut matching = entries.into_iter().filter(|&(ref key, ref primary_key)| {
+        let after_position = match position {
+            Some((ref position_key, _)) if unique => {
+                if forward {
+                    key > position_key
+                } else {
+                    key < position_key
+                }
+            },
+            Some((ref position_key, ref position_primary_key)) => {
+                let entry = (key, primary_key);
+                let position = (position_key, position_primary_key);
+                if forward {
+                    entry > position
+                } else {
+                    entry < position
+                }
+            },
+            None => true,
+        };
+        let reaches_target = match target {
+            Some((ref target_key, Some(ref target_primary_key))) => {
+                let entry = (key, primary_key);
+                let target = (target_key, target_primary_key);
+                if forward {
+                    entry >= target
+                } else {
+                    entry <= target
+                }
+            },
+            Some((ref target_key, None)) => {
+                if forward {
+                    key >= target_key
+                } else {
+                    key <= target_key
+                }
+            },
+            None => true,
+        };
+        after_position && reaches_target
+    });
+    matching.nth(count.max(1) as usize - 1)
+}
@@ -467,6 +467,7 @@ impl GroupedClassifier {
                 Box::new(ByteMatcher::image_gif89a()),
                 Box::new(ByteMatcher::image_gif87a()),
                 Box::new(ByteMatcher::image_webp()),
+                Box::new(ByteMatcher::image_avif()),
                 Box::new(ByteMatcher::image_png()),
                 Box::new(ByteMatcher::image_jpeg()),
             ],
@@ -731,6 +732,15 @@ impl ByteMatcher {
             leading_ignore: &[],
         }
     }
+    //Four bytes followed by the string "ftypavif", an AVIF file type box.
+    fn image_avif() -> ByteMatcher {
+        ByteMatcher {
+            pattern: b"\x00\x00\x00\x00ftypavif",
+            mask: b"\x00\x00\x00\x00\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF",
+            content_type: "image/avif".parse().unwrap(),
+            leading_ignore: &[],
+        }
+    }
     //An error-checking byte followed by the string "PNG" followed by CR LF SUB LF, the PNG
     //signature.
     fn image_png() -> ByteMatcher {
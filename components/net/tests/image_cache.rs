@@ -0,0 +1,24 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use net::image_cache::decode_image;
+
+#[test]
+fn test_svg_image() {
+    let svg = br#"<?xml version="1.0"?>
+        <svg xmlns="http://www.w3.org/2000/svg" width="4" height="2">
+          <rect width="2" height="2" fill="red"/>
+        </svg>"#;
+
+    let image = decode_image(svg).unwrap();
+    assert_eq!((image.width, image.height), (4, 2));
+    assert_eq!(&image.bytes[..4], &[0, 0, 255, 255]);
+    assert_eq!(&image.bytes[12..16], &[0, 0, 0, 0]);
+}
+
+#[test]
+fn test_html_is_not_an_image() {
+    let html = b"<!DOCTYPE html><html><body><svg></svg></body></html>";
+    assert!(decode_image(html).is_none());
+}
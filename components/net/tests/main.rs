@@ -16,6 +16,7 @@ mod filemanager_thread;
 mod hsts;
 mod http_cache;
 mod http_loader;
+mod image_cache;
 mod mime_classifier;
 mod resource_thread;
 mod subresource_integrity;
@@ -155,6 +155,11 @@ fn test_sniff_webp() {
     test_sniff_classification_sup("test.webp", "image/webp".parse().unwrap());
 }
 
+#[test]
+fn test_sniff_avif() {
+    test_sniff_classification_sup("test.avif", "image/avif".parse().unwrap());
+}
+
 #[test]
 fn test_sniff_png() {
     test_sniff_classification_sup("test.png", mime::IMAGE_PNG);
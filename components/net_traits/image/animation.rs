@@ -0,0 +1,400 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Decoding of the frames of animated GIF, PNG and WebP images.
+
+use crate::image::webp;
+use gif::SetParameter;
+use piston_image::{ImageFormat, RgbaImage};
+use std::io::Cursor;
+
+/// A frame of an animated image, composited over the previous ones so that it
+/// covers the whole image.
+pub struct DecodedFrame {
+    pub image: RgbaImage,
+    /// How long the frame is displayed, in milliseconds.
+    pub delay: u32,
+}
+
+/// The frames of an animated image.
+pub struct DecodedAnimation {
+    pub frames: Vec<DecodedFrame>,
+    /// How many times the animation plays, or `None` if it loops forever.
+    pub plays: Option<u32>,
+}
+
+/// Decodes all the frames of the given image, or returns `None` if it is not
+/// an animated image that we can decode.
+pub fn decode_animation(buffer: &[u8], format: ImageFormat) -> Option<DecodedAnimation> {
+    let animation = match format {
+        ImageFormat::GIF => decode_gif(buffer),
+        ImageFormat::PNG => decode_apng(buffer),
+        ImageFormat::WEBP => decode_animated_webp(buffer),
+        _ => None,
+    }?;
+    // Still images are better decoded as such, except for WebP images whose
+    // single frame can only be decoded as part of an animation.
+    let min_frames = if format == ImageFormat::WEBP { 1 } else { 2 };
+    if animation.frames.len() < min_frames {
+        return None;
+    }
+    Some(animation)
+}
+
+/// Returns the delay of a frame as browsers display it: very short delays are
+/// ignored by most animations encoders, so they are displayed for 100ms.
+fn frame_delay(delay: u32) -> u32 {
+    if delay <= 10 {
+        100
+    } else {
+        delay
+    }
+}
+
+fn decode_gif(buffer: &[u8]) -> Option<DecodedAnimation> {
+    let mut decoder = gif::Decoder::new(Cursor::new(buffer));
+    decoder.set(gif::ColorOutput::RGBA);
+    let mut reader = decoder
+        .read_info()
+        .map_err(|error| debug!("GIF decoding error: {:?}", error))
+        .ok()?;
+    let (width, height) = (reader.width() as u32, reader.height() as u32);
+    let mut frames = vec![];
+    loop {
+        let frame = match reader.read_next_frame() {
+            Ok(Some(frame)) => frame,
+            Ok(None) => break,
+            Err(error) => {
+                debug!("GIF frame decoding error: {:?}", error);
+                return None;
+            },
+        };
+        let control = FrameControl {
+            x: frame.left as u32,
+            y: frame.top as u32,
+            width: frame.width as u32,
+            height: frame.height as u32,
+            delay: frame_delay(frame.delay as u32 * 10),
+            dispose_op: match frame.dispose {
+                gif::DisposalMethod::Any | gif::DisposalMethod::Keep => DisposeOp::None,
+                gif::DisposalMethod::Background => DisposeOp::Background,
+                gif::DisposalMethod::Previous => DisposeOp::Previous,
+            },
+            blend: true,
+        };
+        let image = RgbaImage::from_raw(control.width, control.height, frame.buffer.to_vec())?;
+        frames.push((control, image));
+    }
+    Some(DecodedAnimation {
+        frames: composite_frames(width, height, frames)?,
+        plays: gif_plays(buffer),
+    })
+}
+
+/// Returns how many times a GIF animation plays, according to its NETSCAPE2.0
+/// application extension.
+///
+/// Without this extension, the animation plays only once, and the loop count it
+/// holds is the number of times the animation repeats after the first time.
+fn gif_plays(buffer: &[u8]) -> Option<u32> {
+    const EXTENSION: &[u8] = b"\x21\xFF\x0BNETSCAPE2.0\x03\x01";
+    let loop_count = buffer
+        .windows(EXTENSION.len() + 2)
+        .find(|window| window.starts_with(EXTENSION))
+        .map(|window| u16::from_le_bytes([window[EXTENSION.len()], window[EXTENSION.len() + 1]]));
+    match loop_count {
+        None => Some(1),
+        Some(0) => None,
+        Some(loop_count) => Some(loop_count as u32 + 1),
+    }
+}
+
+/// How the area of a frame is disposed of before rendering the next frame.
+#[derive(Clone, Copy, PartialEq)]
+enum DisposeOp {
+    None,
+    Background,
+    Previous,
+}
+
+/// The placement of a frame of an animated image on its canvas.
+struct FrameControl {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    delay: u32,
+    dispose_op: DisposeOp,
+    /// Whether the frame is alpha-blended over the canvas, rather than
+    /// replacing its area.
+    blend: bool,
+}
+
+/// Renders the frames of an animated image on a canvas of the
+/// given size, and returns the state of the canvas after each frame.
+fn composite_frames(
+    width: u32,
+    height: u32,
+    frames: Vec<(FrameControl, RgbaImage)>,
+) -> Option<Vec<DecodedFrame>> {
+    let mut canvas = RgbaImage::new(width, height);
+    let mut decoded_frames = Vec::with_capacity(frames.len());
+    for (control, image) in frames {
+        if control.x.checked_add(control.width)? > width ||
+            control.y.checked_add(control.height)? > height ||
+            image.dimensions() != (control.width, control.height)
+        {
+            return None;
+        }
+
+        let previous = if control.dispose_op == DisposeOp::Previous {
+            Some(canvas.clone())
+        } else {
+            None
+        };
+        for (x, y, source) in image.enumerate_pixels() {
+            let destination = canvas.get_pixel_mut(control.x + x, control.y + y);
+            if control.blend {
+                blend_over(&mut destination.0, source.0);
+            } else {
+                *destination = *source;
+            }
+        }
+        decoded_frames.push(DecodedFrame {
+            image: canvas.clone(),
+            delay: control.delay,
+        });
+
+        match control.dispose_op {
+            DisposeOp::None => {},
+            DisposeOp::Background => {
+                for y in control.y..control.y + control.height {
+                    for x in control.x..control.x + control.width {
+                        canvas.get_pixel_mut(x, y).0 = [0; 4];
+                    }
+                }
+            },
+            DisposeOp::Previous => canvas = previous.unwrap(),
+        }
+    }
+    Some(decoded_frames)
+}
+
+/// Composites a non-premultiplied source pixel over a destination one.
+fn blend_over(destination: &mut [u8; 4], source: [u8; 4]) {
+    let source_alpha = source[3] as u32;
+    if source_alpha == 255 {
+        *destination = source;
+        return;
+    }
+    if source_alpha == 0 {
+        return;
+    }
+    let destination_alpha = destination[3] as u32 * (255 - source_alpha) / 255;
+    let alpha = source_alpha + destination_alpha;
+    for channel in 0..3 {
+        destination[channel] = ((source[channel] as u32 * source_alpha +
+            destination[channel] as u32 * destination_alpha) /
+            alpha) as u8;
+    }
+    destination[3] = alpha as u8;
+}
+
+/// The chunks of an APNG image that describe a frame.
+struct ApngFrame {
+    control: FrameControl,
+    /// The compressed image data of the frame, as found in IDAT chunks.
+    data: Vec<u8>,
+}
+
+/// <https://wiki.mozilla.org/APNG_Specification>
+fn decode_apng(buffer: &[u8]) -> Option<DecodedAnimation> {
+    const SIGNATURE_LENGTH: usize = 8;
+    let mut offset = SIGNATURE_LENGTH;
+    let mut header = None;
+    let mut animated = false;
+    let mut plays = None;
+    let mut shared_chunks = vec![];
+    let mut frames: Vec<ApngFrame> = vec![];
+    let mut seen_image_data = false;
+    while offset + 12 <= buffer.len() {
+        let length = read_u32_be(buffer, offset)? as usize;
+        let chunk_type = buffer.get(offset + 4..offset + 8)?;
+        let data = buffer.get(offset + 8..offset.checked_add(8 + length)?)?;
+        let chunk = buffer.get(offset..offset + 12 + length)?;
+        offset += 12 + length;
+        match chunk_type {
+            b"IHDR" => header = Some(data),
+            b"acTL" => {
+                animated = true;
+                plays = match read_u32_be(data, 4)? {
+                    0 => None,
+                    plays => Some(plays),
+                };
+            },
+            b"fcTL" => {
+                if data.len() < 26 {
+                    return None;
+                }
+                let delay_numerator = read_u16_be(data, 20)? as u32;
+                let delay_denominator = match read_u16_be(data, 22)? {
+                    0 => 100,
+                    denominator => denominator as u32,
+                };
+                let dispose_op = match data[24] {
+                    0 => DisposeOp::None,
+                    1 => DisposeOp::Background,
+                    2 if !frames.is_empty() => DisposeOp::Previous,
+                    // Disposing the first frame to the previous state means
+                    // clearing it.
+                    2 => DisposeOp::Background,
+                    _ => return None,
+                };
+                frames.push(ApngFrame {
+                    control: FrameControl {
+                        width: read_u32_be(data, 4)?,
+                        height: read_u32_be(data, 8)?,
+                        x: read_u32_be(data, 12)?,
+                        y: read_u32_be(data, 16)?,
+                        delay: frame_delay(delay_numerator * 1000 / delay_denominator),
+                        dispose_op,
+                        blend: data[25] == 1,
+                    },
+                    data: vec![],
+                });
+            },
+            b"IDAT" => {
+                seen_image_data = true;
+                // The default image is only part of the animation if a fcTL
+                // chunk precedes it.
+                if let Some(frame) = frames.last_mut() {
+                    frame.data.extend_from_slice(data);
+                }
+            },
+            b"fdAT" => frames.last_mut()?.data.extend_from_slice(data.get(4..)?),
+            b"IEND" => break,
+            _ if !seen_image_data => shared_chunks.push(chunk),
+            _ => {},
+        }
+    }
+
+    // Without an acTL chunk, this is a plain PNG image.
+    let header = header?;
+    if !animated || header.len() != 13 {
+        return None;
+    }
+
+    let (width, height) = (read_u32_be(header, 0)?, read_u32_be(header, 4)?);
+    let mut decoded_frames = Vec::with_capacity(frames.len());
+    for frame in frames {
+        // Decode each frame as a standalone PNG image.
+        let mut frame_header = header.to_vec();
+        frame_header[0..4].copy_from_slice(&frame.control.width.to_be_bytes());
+        frame_header[4..8].copy_from_slice(&frame.control.height.to_be_bytes());
+        let mut png = buffer[..SIGNATURE_LENGTH].to_vec();
+        write_png_chunk(&mut png, b"IHDR", &frame_header);
+        for chunk in &shared_chunks {
+            png.extend_from_slice(chunk);
+        }
+        write_png_chunk(&mut png, b"IDAT", &frame.data);
+        write_png_chunk(&mut png, b"IEND", &[]);
+        let image = piston_image::load_from_memory_with_format(&png, ImageFormat::PNG)
+            .map_err(|error| debug!("APNG frame decoding error: {:?}", error))
+            .ok()?;
+        decoded_frames.push((frame.control, image.to_rgba()));
+    }
+
+    Some(DecodedAnimation {
+        frames: composite_frames(width, height, decoded_frames)?,
+        plays,
+    })
+}
+
+fn write_png_chunk(png: &mut Vec<u8>, chunk_type: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(chunk_type);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// <https://www.w3.org/TR/PNG/#D-CRCAppendix>
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                0xEDB8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// <https://developers.google.com/speed/webp/docs/riff_container#animation>
+fn decode_animated_webp(buffer: &[u8]) -> Option<DecodedAnimation> {
+    let chunks = webp::chunks(buffer.get(12..)?)?;
+    let (_, extended_header) = chunks.iter().find(|&&(name, _)| name == b"VP8X")?;
+    if extended_header.len() < 10 || extended_header[0] & webp::ANIMATION_FLAG == 0 {
+        return None;
+    }
+    let width = read_u24_le(extended_header, 4)? + 1;
+    let height = read_u24_le(extended_header, 7)? + 1;
+    let plays = match chunks.iter().find(|&&(name, _)| name == b"ANIM") {
+        Some(&(_, animation)) => match read_u16_le(animation, 4)? {
+            0 => None,
+            plays => Some(plays as u32),
+        },
+        None => None,
+    };
+
+    let mut frames = vec![];
+    for &(_, frame) in chunks.iter().filter(|&&(name, _)| name == b"ANMF") {
+        let flags = *frame.get(15)?;
+        let control = FrameControl {
+            x: read_u24_le(frame, 0)? * 2,
+            y: read_u24_le(frame, 3)? * 2,
+            width: read_u24_le(frame, 6)? + 1,
+            height: read_u24_le(frame, 9)? + 1,
+            delay: frame_delay(read_u24_le(frame, 12)?),
+            dispose_op: if flags & 0x01 != 0 {
+                DisposeOp::Background
+            } else {
+                DisposeOp::None
+            },
+            blend: flags & 0x02 == 0,
+        };
+        let image = webp::decode_frame(frame.get(16..)?)?;
+        frames.push((control, image));
+    }
+
+    Some(DecodedAnimation {
+        frames: composite_frames(width, height, frames)?,
+        plays,
+    })
+}
+
+fn read_u16_be(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset + 2)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32_be(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn read_u16_le(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset + 2)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u24_le(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes = data.get(offset..offset + 3)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0]))
+}
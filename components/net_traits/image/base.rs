@@ -2,8 +2,10 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
+use crate::image::animation::{self, DecodedFrame};
+use crate::image::webp;
 use ipc_channel::ipc::IpcSharedMemory;
-use piston_image::{DynamicImage, ImageFormat};
+use piston_image::{DynamicImage, ImageError, ImageFormat};
 use pixels::PixelFormat;
 use std::fmt;
 
@@ -12,8 +14,76 @@ pub struct Image {
     pub width: u32,
     pub height: u32,
     pub format: PixelFormat,
+    /// The pixels of the image. For animated images, this holds the pixels of
+    /// every frame, one after the other.
     #[ignore_malloc_size_of = "Defined in ipc-channel"]
     pub bytes: IpcSharedMemory,
+    /// The key of the image in WebRender. For animated images, this is the key
+    /// of the first frame.
+    #[ignore_malloc_size_of = "Defined in webrender_api"]
+    pub id: Option<webrender_api::ImageKey>,
+    pub animation: Option<ImageAnimation>,
+}
+
+impl Image {
+    pub fn is_animated(&self) -> bool {
+        self.animation.is_some()
+    }
+
+    /// Returns the pixels of the given frame of the image.
+    pub fn frame_bytes(&self, index: usize) -> &[u8] {
+        let frame_length = match self.animation {
+            Some(ref animation) => self.bytes.len() / animation.frames.len(),
+            None => return &*self.bytes,
+        };
+        &self.bytes[index * frame_length..(index + 1) * frame_length]
+    }
+
+    /// Returns the pixels of the first frame of the image, which is what is
+    /// used when an animated image is painted into a canvas.
+    pub fn first_frame(&self) -> &[u8] {
+        self.frame_bytes(0)
+    }
+}
+
+#[derive(Clone, Deserialize, MallocSizeOf, Serialize)]
+pub struct ImageAnimation {
+    pub frames: Vec<ImageFrame>,
+    /// How many times the animation plays, or `None` if it loops forever.
+    pub plays: Option<u32>,
+}
+
+impl ImageAnimation {
+    /// Returns the index of the frame displayed once the animation has run for
+    /// the given number of milliseconds, and whether the animation is still
+    /// running at that point.
+    pub fn frame_at(&self, elapsed: u64) -> (usize, bool) {
+        let duration = self
+            .frames
+            .iter()
+            .map(|frame| frame.delay as u64)
+            .sum::<u64>()
+            .max(1);
+        if let Some(plays) = self.plays {
+            if elapsed / duration >= plays as u64 {
+                return (self.frames.len() - 1, false);
+            }
+        }
+        let mut time = elapsed % duration;
+        for (index, frame) in self.frames.iter().enumerate() {
+            if time < frame.delay as u64 {
+                return (index, true);
+            }
+            time -= frame.delay as u64;
+        }
+        (self.frames.len() - 1, true)
+    }
+}
+
+#[derive(Clone, Deserialize, MallocSizeOf, Serialize)]
+pub struct ImageFrame {
+    /// How long the frame is displayed, in milliseconds.
+    pub delay: u32,
     #[ignore_malloc_size_of = "Defined in webrender_api"]
     pub id: Option<webrender_api::ImageKey>,
 }
@@ -22,8 +92,14 @@ impl fmt::Debug for Image {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
-            "Image {{ width: {}, height: {}, format: {:?}, ..., id: {:?} }}",
-            self.width, self.height, self.format, self.id
+            "Image {{ width: {}, height: {}, format: {:?}, ..., id: {:?}, frames: {} }}",
+            self.width,
+            self.height,
+            self.format,
+            self.id,
+            self.animation
+                .as_ref()
+                .map_or(1, |animation| animation.frames.len())
         )
     }
 }
@@ -48,29 +124,76 @@ pub fn load_from_memory(buffer: &[u8]) -> Option<Image> {
             debug!("{}", msg);
             None
         },
-        Ok(_) => match piston_image::load_from_memory(buffer) {
-            Ok(image) => {
-                let mut rgba = match image {
-                    DynamicImage::ImageRgba8(rgba) => rgba,
-                    image => image.to_rgba(),
-                };
-                pixels::rgba8_byte_swap_colors_inplace(&mut *rgba);
-                Some(Image {
-                    width: rgba.width(),
-                    height: rgba.height(),
-                    format: PixelFormat::BGRA8,
-                    bytes: IpcSharedMemory::from_bytes(&*rgba),
-                    id: None,
-                })
-            },
-            Err(e) => {
-                debug!("Image decoding error: {:?}", e);
-                None
-            },
+        Ok(format) => {
+            if let Some(animation) = animation::decode_animation(buffer, format) {
+                return Some(load_animation(animation.frames, animation.plays));
+            }
+            // The WebP decoder of the image crate only supports grayscale
+            // images, so use our own.
+            let decoded = match format {
+                ImageFormat::WEBP => webp::decode(buffer)
+                    .map(DynamicImage::ImageRgba8)
+                    .ok_or_else(|| ImageError::FormatError("Invalid WebP image".to_owned())),
+                _ => piston_image::load_from_memory(buffer),
+            };
+            match decoded {
+                Ok(image) => {
+                    let mut rgba = match image {
+                        DynamicImage::ImageRgba8(rgba) => rgba,
+                        image => image.to_rgba(),
+                    };
+                    pixels::rgba8_byte_swap_colors_inplace(&mut *rgba);
+                    Some(Image {
+                        width: rgba.width(),
+                        height: rgba.height(),
+                        format: PixelFormat::BGRA8,
+                        bytes: IpcSharedMemory::from_bytes(&*rgba),
+                        id: None,
+                        animation: None,
+                    })
+                },
+                Err(e) => {
+                    debug!("Image decoding error: {:?}", e);
+                    None
+                },
+            }
         },
     }
 }
 
+/// Packs the decoded frames of an animated image into a single image, which
+/// is a still image if there is only one frame.
+fn load_animation(frames: Vec<DecodedFrame>, plays: Option<u32>) -> Image {
+    let (width, height) = frames[0].image.dimensions();
+    let mut bytes = Vec::with_capacity(frames.len() * width as usize * height as usize * 4);
+    let mut image_frames = Vec::with_capacity(frames.len());
+    for frame in frames {
+        let start = bytes.len();
+        bytes.extend_from_slice(&*frame.image);
+        pixels::rgba8_byte_swap_colors_inplace(&mut bytes[start..]);
+        image_frames.push(ImageFrame {
+            delay: frame.delay,
+            id: None,
+        });
+    }
+    let animation = if image_frames.len() > 1 {
+        Some(ImageAnimation {
+            frames: image_frames,
+            plays,
+        })
+    } else {
+        None
+    };
+    Image {
+        width,
+        height,
+        format: PixelFormat::BGRA8,
+        bytes: IpcSharedMemory::from_bytes(&bytes),
+        id: None,
+        animation,
+    }
+}
+
 // https://developer.mozilla.org/en-US/docs/Web/HTML/Element/img
 pub fn detect_image_format(buffer: &[u8]) -> Result<ImageFormat, &str> {
     if is_gif(buffer) {
@@ -83,6 +206,8 @@ pub fn detect_image_format(buffer: &[u8]) -> Result<ImageFormat, &str> {
         Ok(ImageFormat::BMP)
     } else if is_ico(buffer) {
         Ok(ImageFormat::ICO)
+    } else if is_webp(buffer) {
+        Ok(ImageFormat::WEBP)
     } else {
         Err("Image Format Not Supported")
     }
@@ -107,3 +232,7 @@ fn is_bmp(buffer: &[u8]) -> bool {
 fn is_ico(buffer: &[u8]) -> bool {
     buffer.starts_with(&[0x00, 0x00, 0x01, 0x00])
 }
+
+fn is_webp(buffer: &[u8]) -> bool {
+    buffer.starts_with(b"RIFF") && buffer.len() >= 12 && &buffer[8..12] == b"WEBP"
+}
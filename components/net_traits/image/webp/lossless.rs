@@ -0,0 +1,615 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Decoding of lossless WebP images.
+//!
+//! <https://developers.google.com/speed/webp/docs/webp_lossless_bitstream_specification>
+
+const SIGNATURE: u8 = 0x2F;
+
+/// The number of literal symbols of the green alphabet.
+const LITERAL_COUNT: usize = 256;
+
+/// The number of backward reference length prefixes of the green alphabet.
+const LENGTH_PREFIX_COUNT: usize = 24;
+
+const DISTANCE_PREFIX_COUNT: usize = 40;
+
+const MAX_CODE_LENGTH: usize = 15;
+
+const CODE_LENGTH_CODE_ORDER: [usize; 19] = [
+    17, 18, 0, 1, 2, 3, 4, 5, 16, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15,
+];
+
+/// The (x, y) offsets of the pixels referred to by the 120 smallest distance
+/// codes.
+#[rustfmt::skip]
+const DISTANCE_MAP: [(i8, i8); 120] = [
+    (0, 1),  (1, 0),  (1, 1),  (-1, 1), (0, 2),  (2, 0),  (1, 2),  (-1, 2),
+    (2, 1),  (-2, 1), (2, 2),  (-2, 2), (0, 3),  (3, 0),  (1, 3),  (-1, 3),
+    (3, 1),  (-3, 1), (2, 3),  (-2, 3), (3, 2),  (-3, 2), (0, 4),  (4, 0),
+    (1, 4),  (-1, 4), (4, 1),  (-4, 1), (3, 3),  (-3, 3), (2, 4),  (-2, 4),
+    (4, 2),  (-4, 2), (0, 5),  (3, 4),  (-3, 4), (4, 3),  (-4, 3), (5, 0),
+    (1, 5),  (-1, 5), (5, 1),  (-5, 1), (2, 5),  (-2, 5), (5, 2),  (-5, 2),
+    (4, 4),  (-4, 4), (3, 5),  (-3, 5), (5, 3),  (-5, 3), (0, 6),  (6, 0),
+    (1, 6),  (-1, 6), (6, 1),  (-6, 1), (2, 6),  (-2, 6), (6, 2),  (-6, 2),
+    (4, 5),  (-4, 5), (5, 4),  (-5, 4), (3, 6),  (-3, 6), (6, 3),  (-6, 3),
+    (0, 7),  (7, 0),  (1, 7),  (-1, 7), (5, 5),  (-5, 5), (7, 1),  (-7, 1),
+    (4, 6),  (-4, 6), (6, 4),  (-6, 4), (2, 7),  (-2, 7), (7, 2),  (-7, 2),
+    (3, 7),  (-3, 7), (7, 3),  (-7, 3), (5, 6),  (-5, 6), (6, 5),  (-6, 5),
+    (8, 0),  (4, 7),  (-4, 7), (7, 4),  (-7, 4), (8, 1),  (8, 2),  (6, 6),
+    (-6, 6), (8, 3),  (5, 7),  (-5, 7), (7, 5),  (-7, 5), (8, 4),  (6, 7),
+    (-6, 7), (7, 6),  (-7, 6), (8, 5),  (7, 7),  (-7, 7), (8, 6),  (8, 7),
+];
+
+/// A lossless image, as ARGB pixels.
+pub struct LosslessImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u32>,
+}
+
+/// Decodes the data of a VP8L chunk.
+pub fn decode(data: &[u8]) -> Option<LosslessImage> {
+    let mut reader = BitReader::new(data);
+    if reader.read_bits(8)? != SIGNATURE as u32 {
+        return None;
+    }
+    let width = reader.read_bits(14)? + 1;
+    let height = reader.read_bits(14)? + 1;
+    let _alpha_is_used = reader.read_bits(1)?;
+    if reader.read_bits(3)? != 0 {
+        return None;
+    }
+    let pixels = decode_image_stream(&mut reader, width, height)?;
+    Some(LosslessImage {
+        width,
+        height,
+        pixels,
+    })
+}
+
+/// Decodes an image stream without the VP8L header, as found in the ALPH
+/// chunks of lossy images with an alpha channel.
+pub fn decode_headerless(data: &[u8], width: u32, height: u32) -> Option<Vec<u32>> {
+    decode_image_stream(&mut BitReader::new(data), width, height)
+}
+
+/// Reads the bits of a lossless image stream, least significant bit first.
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    buffer: u64,
+    buffered_bits: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> BitReader<'a> {
+        BitReader {
+            data,
+            position: 0,
+            buffer: 0,
+            buffered_bits: 0,
+        }
+    }
+
+    fn read_bits(&mut self, count: u32) -> Option<u32> {
+        debug_assert!(count <= 32);
+        while self.buffered_bits < count {
+            let byte = *self.data.get(self.position)?;
+            self.position += 1;
+            self.buffer |= (byte as u64) << self.buffered_bits;
+            self.buffered_bits += 8;
+        }
+        let value = (self.buffer & ((1u64 << count) - 1)) as u32;
+        self.buffer >>= count;
+        self.buffered_bits -= count;
+        Some(value)
+    }
+
+    fn read_flag(&mut self) -> Option<bool> {
+        Some(self.read_bits(1)? == 1)
+    }
+}
+
+/// A canonical prefix code.
+struct PrefixCode {
+    /// The number of codes of each length.
+    counts: [u16; MAX_CODE_LENGTH + 1],
+    /// The symbols, sorted by code.
+    symbols: Vec<u16>,
+}
+
+impl PrefixCode {
+    fn from_lengths(lengths: &[u8]) -> Option<PrefixCode> {
+        let mut counts = [0u16; MAX_CODE_LENGTH + 1];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        counts[0] = 0;
+
+        let symbol_count: u16 = counts.iter().sum();
+        if symbol_count == 0 {
+            return None;
+        }
+        if symbol_count > 1 {
+            // Only complete codes are valid.
+            let mut left = 1i32;
+            for &count in &counts[1..] {
+                left = (left << 1) - count as i32;
+                if left < 0 {
+                    return None;
+                }
+            }
+            if left != 0 {
+                return None;
+            }
+        }
+
+        let mut offsets = [0u16; MAX_CODE_LENGTH + 2];
+        for length in 1..=MAX_CODE_LENGTH {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+        let mut symbols = vec![0; symbol_count as usize];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length != 0 {
+                symbols[offsets[length as usize] as usize] = symbol as u16;
+                offsets[length as usize] += 1;
+            }
+        }
+        Some(PrefixCode { counts, symbols })
+    }
+
+    fn read_symbol(&self, reader: &mut BitReader) -> Option<u16> {
+        // A code with a single symbol takes no bits.
+        if self.symbols.len() == 1 {
+            return Some(self.symbols[0]);
+        }
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for &count in &self.counts[1..] {
+            code |= reader.read_bits(1)? as i32;
+            let count = count as i32;
+            if code - count < first {
+                return Some(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        None
+    }
+}
+
+fn read_prefix_code(reader: &mut BitReader, alphabet_size: usize) -> Option<PrefixCode> {
+    let mut lengths = vec![0u8; alphabet_size];
+    if reader.read_flag()? {
+        // A simple code, with one or two symbols.
+        let symbol_count = reader.read_bits(1)? + 1;
+        let first_symbol_bits = if reader.read_flag()? { 8 } else { 1 };
+        let first_symbol = reader.read_bits(first_symbol_bits)? as usize;
+        *lengths.get_mut(first_symbol)? = 1;
+        if symbol_count == 2 {
+            *lengths.get_mut(reader.read_bits(8)? as usize)? = 1;
+        }
+        return PrefixCode::from_lengths(&lengths);
+    }
+
+    let mut code_length_code_lengths = [0u8; 19];
+    let code_length_count = reader.read_bits(4)? as usize + 4;
+    for &symbol in &CODE_LENGTH_CODE_ORDER[..code_length_count] {
+        code_length_code_lengths[symbol] = reader.read_bits(3)? as u8;
+    }
+    let code_length_code = PrefixCode::from_lengths(&code_length_code_lengths)?;
+
+    let mut max_symbol = if reader.read_flag()? {
+        let length_bits = 2 + 2 * reader.read_bits(3)?;
+        let max_symbol = 2 + reader.read_bits(length_bits)? as usize;
+        if max_symbol > alphabet_size {
+            return None;
+        }
+        max_symbol
+    } else {
+        alphabet_size
+    };
+
+    let mut symbol = 0;
+    let mut previous_length = 8;
+    while symbol < alphabet_size {
+        if max_symbol == 0 {
+            break;
+        }
+        max_symbol -= 1;
+        let length = code_length_code.read_symbol(reader)? as u8;
+        if length < 16 {
+            lengths[symbol] = length;
+            symbol += 1;
+            if length != 0 {
+                previous_length = length;
+            }
+            continue;
+        }
+        let (repeat, repeated_length) = match length {
+            16 => (3 + reader.read_bits(2)?, previous_length),
+            17 => (3 + reader.read_bits(3)?, 0),
+            _ => (11 + reader.read_bits(7)?, 0),
+        };
+        let end = symbol + repeat as usize;
+        if end > alphabet_size {
+            return None;
+        }
+        for length in &mut lengths[symbol..end] {
+            *length = repeated_length;
+        }
+        symbol = end;
+    }
+    PrefixCode::from_lengths(&lengths)
+}
+
+/// The five prefix codes used to decode the pixels of a region of an image.
+struct PrefixCodeGroup {
+    green: PrefixCode,
+    red: PrefixCode,
+    blue: PrefixCode,
+    alpha: PrefixCode,
+    distance: PrefixCode,
+}
+
+fn read_prefix_code_group(reader: &mut BitReader, cache_size: usize) -> Option<PrefixCodeGroup> {
+    Some(PrefixCodeGroup {
+        green: read_prefix_code(reader, LITERAL_COUNT + LENGTH_PREFIX_COUNT + cache_size)?,
+        red: read_prefix_code(reader, LITERAL_COUNT)?,
+        blue: read_prefix_code(reader, LITERAL_COUNT)?,
+        alpha: read_prefix_code(reader, LITERAL_COUNT)?,
+        distance: read_prefix_code(reader, DISTANCE_PREFIX_COUNT)?,
+    })
+}
+
+enum Transform {
+    Predictor { bits: u32, modes: Vec<u32> },
+    CrossColor { bits: u32, elements: Vec<u32> },
+    SubtractGreen,
+    ColorIndexing { width_bits: u32, palette: Vec<u32> },
+}
+
+fn subsample_size(size: u32, bits: u32) -> u32 {
+    (size + (1 << bits) - 1) >> bits
+}
+
+fn decode_image_stream(reader: &mut BitReader, width: u32, height: u32) -> Option<Vec<u32>> {
+    // Each transform is stored with the width of the image it produces.
+    let mut transforms = vec![];
+    let mut seen_transforms = [false; 4];
+    let mut coded_width = width;
+    while reader.read_flag()? {
+        let transform_type = reader.read_bits(2)? as usize;
+        if seen_transforms[transform_type] {
+            return None;
+        }
+        seen_transforms[transform_type] = true;
+        let transform = match transform_type {
+            0 | 1 => {
+                let bits = reader.read_bits(3)? + 2;
+                let data = decode_entropy_coded_image(
+                    reader,
+                    subsample_size(coded_width, bits),
+                    subsample_size(height, bits),
+                    false,
+                )?;
+                if transform_type == 0 {
+                    Transform::Predictor { bits, modes: data }
+                } else {
+                    Transform::CrossColor {
+                        bits,
+                        elements: data,
+                    }
+                }
+            },
+            2 => Transform::SubtractGreen,
+            _ => {
+                let palette_size = reader.read_bits(8)? + 1;
+                let mut palette = decode_entropy_coded_image(reader, palette_size, 1, false)?;
+                for index in 1..palette.len() {
+                    palette[index] = add_pixels(palette[index], palette[index - 1]);
+                }
+                let width_bits = match palette_size {
+                    0..=2 => 3,
+                    3..=4 => 2,
+                    5..=16 => 1,
+                    _ => 0,
+                };
+                Transform::ColorIndexing {
+                    width_bits,
+                    palette,
+                }
+            },
+        };
+        transforms.push((transform, coded_width));
+        if let Some(&(Transform::ColorIndexing { width_bits, .. }, _)) = transforms.last() {
+            coded_width = subsample_size(coded_width, width_bits);
+        }
+    }
+
+    let mut pixels = decode_entropy_coded_image(reader, coded_width, height, true)?;
+    for (transform, width) in transforms.into_iter().rev() {
+        match transform {
+            Transform::Predictor { bits, modes } => {
+                apply_predictor_transform(&mut pixels, width, height, bits, &modes)
+            },
+            Transform::CrossColor { bits, elements } => {
+                apply_cross_color_transform(&mut pixels, width, bits, &elements)
+            },
+            Transform::SubtractGreen => apply_subtract_green_transform(&mut pixels),
+            Transform::ColorIndexing {
+                width_bits,
+                palette,
+            } => {
+                pixels =
+                    apply_color_indexing_transform(&pixels, width, height, width_bits, &palette)
+            },
+        }
+    }
+    Some(pixels)
+}
+
+/// Decodes the pixels of an image. `is_main_image` is false for the images
+/// that hold the data of transforms and prefix code groups.
+fn decode_entropy_coded_image(
+    reader: &mut BitReader,
+    width: u32,
+    height: u32,
+    is_main_image: bool,
+) -> Option<Vec<u32>> {
+    let cache_bits = if reader.read_flag()? {
+        let bits = reader.read_bits(4)?;
+        if bits < 1 || bits > 11 {
+            return None;
+        }
+        bits
+    } else {
+        0
+    };
+    let cache_size = if cache_bits > 0 { 1 << cache_bits } else { 0 };
+
+    let mut prefix_bits = 0;
+    let mut prefix_image = None;
+    let mut group_count = 1;
+    if is_main_image && reader.read_flag()? {
+        prefix_bits = reader.read_bits(3)? + 2;
+        let image = decode_entropy_coded_image(
+            reader,
+            subsample_size(width, prefix_bits),
+            subsample_size(height, prefix_bits),
+            false,
+        )?;
+        let groups: Vec<u32> = image.iter().map(|&pixel| (pixel >> 8) & 0xFFFF).collect();
+        group_count = *groups.iter().max()? as usize + 1;
+        prefix_image = Some(groups);
+    }
+    let mut groups = Vec::with_capacity(group_count);
+    for _ in 0..group_count {
+        groups.push(read_prefix_code_group(reader, cache_size)?);
+    }
+
+    let (width, height) = (width as usize, height as usize);
+    let pixel_count = width.checked_mul(height)?;
+    let prefix_width = subsample_size(width as u32, prefix_bits) as usize;
+    let mut pixels = vec![0u32; pixel_count];
+    let mut cache = vec![0u32; cache_size];
+    let mut cached_pixels = 0;
+    let mut index = 0;
+    while index < pixel_count {
+        let group = match prefix_image {
+            Some(ref prefix_image) => {
+                let (x, y) = (index % width, index / width);
+                let group = prefix_image[(y >> prefix_bits) * prefix_width + (x >> prefix_bits)];
+                &groups[group as usize]
+            },
+            None => &groups[0],
+        };
+        let symbol = group.green.read_symbol(reader)? as usize;
+        if symbol < LITERAL_COUNT {
+            let red = group.red.read_symbol(reader)? as u32;
+            let blue = group.blue.read_symbol(reader)? as u32;
+            let alpha = group.alpha.read_symbol(reader)? as u32;
+            pixels[index] = (alpha << 24) | (red << 16) | ((symbol as u32) << 8) | blue;
+            index += 1;
+        } else if symbol < LITERAL_COUNT + LENGTH_PREFIX_COUNT {
+            let length = read_prefix_value(reader, (symbol - LITERAL_COUNT) as u32)? as usize;
+            let distance_symbol = group.distance.read_symbol(reader)?;
+            let distance_code = read_prefix_value(reader, distance_symbol as u32)?;
+            let distance = distance_from_code(distance_code, width);
+            if distance > index || length > pixel_count - index {
+                return None;
+            }
+            for offset in index..index + length {
+                pixels[offset] = pixels[offset - distance];
+            }
+            index += length;
+        } else {
+            let cache_index = symbol - LITERAL_COUNT - LENGTH_PREFIX_COUNT;
+            pixels[index] = *cache.get(cache_index)?;
+            index += 1;
+        }
+
+        if cache_bits > 0 {
+            for &pixel in &pixels[cached_pixels..index] {
+                cache[(0x1E35_A7BDu32.wrapping_mul(pixel) >> (32 - cache_bits)) as usize] = pixel;
+            }
+            cached_pixels = index;
+        }
+    }
+    Some(pixels)
+}
+
+/// Reads the value of a backward reference length or distance.
+fn read_prefix_value(reader: &mut BitReader, prefix: u32) -> Option<u32> {
+    if prefix < 4 {
+        return Some(prefix + 1);
+    }
+    let extra_bits = (prefix - 2) >> 1;
+    let offset = (2 + (prefix & 1)) << extra_bits;
+    Some(offset + reader.read_bits(extra_bits)? + 1)
+}
+
+fn distance_from_code(code: u32, width: usize) -> usize {
+    if code as usize > DISTANCE_MAP.len() {
+        return code as usize - DISTANCE_MAP.len();
+    }
+    let (x, y) = DISTANCE_MAP[code as usize - 1];
+    let distance = x as isize + y as isize * width as isize;
+    if distance < 1 {
+        1
+    } else {
+        distance as usize
+    }
+}
+
+/// Adds two pixels component-wise, modulo 256.
+fn add_pixels(a: u32, b: u32) -> u32 {
+    let alpha_and_green = (a & 0xFF00_FF00).wrapping_add(b & 0xFF00_FF00);
+    let red_and_blue = (a & 0x00FF_00FF).wrapping_add(b & 0x00FF_00FF);
+    (alpha_and_green & 0xFF00_FF00) | (red_and_blue & 0x00FF_00FF)
+}
+
+fn map_channels(a: u32, b: u32, f: impl Fn(i32, i32) -> i32) -> u32 {
+    (0..4).fold(0, |pixel, channel| {
+        let shift = channel * 8;
+        let value = f(((a >> shift) & 0xFF) as i32, ((b >> shift) & 0xFF) as i32);
+        pixel | ((value as u32 & 0xFF) << shift)
+    })
+}
+
+fn average2(a: u32, b: u32) -> u32 {
+    map_channels(a, b, |a, b| (a + b) / 2)
+}
+
+fn select(left: u32, top: u32, top_left: u32) -> u32 {
+    let distance = |a: u32, b: u32| {
+        (0..4)
+            .map(|channel| {
+                let shift = channel * 8;
+                (((a >> shift) & 0xFF) as i32 - ((b >> shift) & 0xFF) as i32).abs()
+            })
+            .sum::<i32>()
+    };
+    // The distances of the left and top pixels to the gradient estimate
+    // left + top - top_left.
+    if distance(top, top_left) < distance(left, top_left) {
+        left
+    } else {
+        top
+    }
+}
+
+fn clamp_add_subtract_full(a: u32, b: u32, c: u32) -> u32 {
+    let channel = |pixel: u32, shift: u32| ((pixel >> shift) & 0xFF) as i32;
+    (0..4).fold(0, |pixel, index| {
+        let shift = index * 8;
+        let value = channel(a, shift) + channel(b, shift) - channel(c, shift);
+        pixel | ((value.max(0).min(255) as u32) << shift)
+    })
+}
+
+fn clamp_add_subtract_half(a: u32, b: u32) -> u32 {
+    map_channels(a, b, |a, b| (a + (a - b) / 2).max(0).min(255))
+}
+
+fn apply_predictor_transform(
+    pixels: &mut [u32],
+    width: u32,
+    height: u32,
+    bits: u32,
+    modes: &[u32],
+) {
+    let (width, height) = (width as usize, height as usize);
+    let modes_width = subsample_size(width as u32, bits) as usize;
+
+    pixels[0] = add_pixels(pixels[0], 0xFF00_0000);
+    for x in 1..width {
+        pixels[x] = add_pixels(pixels[x], pixels[x - 1]);
+    }
+    for y in 1..height {
+        let row = y * width;
+        pixels[row] = add_pixels(pixels[row], pixels[row - width]);
+        for x in 1..width {
+            let index = row + x;
+            let mode = (modes[(y >> bits) * modes_width + (x >> bits)] >> 8) & 0xF;
+            let left = pixels[index - 1];
+            let top = pixels[index - width];
+            let top_left = pixels[index - width - 1];
+            // The top-right pixel of the last column is the first pixel of the
+            // current row.
+            let top_right = pixels[index - width + 1];
+            let prediction = match mode {
+                1 => left,
+                2 => top,
+                3 => top_right,
+                4 => top_left,
+                5 => average2(average2(left, top_right), top),
+                6 => average2(left, top_left),
+                7 => average2(left, top),
+                8 => average2(top_left, top),
+                9 => average2(top, top_right),
+                10 => average2(average2(left, top_left), average2(top, top_right)),
+                11 => select(left, top, top_left),
+                12 => clamp_add_subtract_full(left, top, top_left),
+                13 => clamp_add_subtract_half(average2(left, top), top_left),
+                _ => 0xFF00_0000,
+            };
+            pixels[index] = add_pixels(pixels[index], prediction);
+        }
+    }
+}
+
+fn apply_cross_color_transform(pixels: &mut [u32], width: u32, bits: u32, elements: &[u32]) {
+    let width = width as usize;
+    let elements_width = subsample_size(width as u32, bits) as usize;
+    // Multiplies the two signed 8-bit values, in 3.5 fixed point.
+    let delta = |transform: u32, color: u32| {
+        ((transform as u8 as i8 as i32 * color as u8 as i8 as i32) >> 5) as u32
+    };
+    for (index, pixel) in pixels.iter_mut().enumerate() {
+        let (x, y) = (index % width, index / width);
+        let element = elements[(y >> bits) * elements_width + (x >> bits)];
+        let (green_to_red, green_to_blue, red_to_blue) = (element, element >> 8, element >> 16);
+        let green = *pixel >> 8;
+        let red = (*pixel >> 16).wrapping_add(delta(green_to_red, green)) & 0xFF;
+        let blue = pixel
+            .wrapping_add(delta(green_to_blue, green))
+            .wrapping_add(delta(red_to_blue, red)) &
+            0xFF;
+        *pixel = (*pixel & 0xFF00_FF00) | (red << 16) | blue;
+    }
+}
+
+fn apply_subtract_green_transform(pixels: &mut [u32]) {
+    for pixel in pixels {
+        let green = (*pixel >> 8) & 0xFF;
+        *pixel = add_pixels(*pixel, (green << 16) | green);
+    }
+}
+
+fn apply_color_indexing_transform(
+    pixels: &[u32],
+    width: u32,
+    height: u32,
+    width_bits: u32,
+    palette: &[u32],
+) -> Vec<u32> {
+    let (width, height) = (width as usize, height as usize);
+    let packed_width = subsample_size(width as u32, width_bits) as usize;
+    let bits_per_pixel = 8 >> width_bits;
+    let pixels_per_byte_mask = (1 << width_bits) - 1;
+    let index_mask = (1 << bits_per_pixel) - 1;
+    let mut output = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            let packed = pixels[y * packed_width + (x >> width_bits)] >> 8;
+            let index = (packed >> ((x & pixels_per_byte_mask) * bits_per_pixel)) & index_mask;
+            // Indices outside of the palette are transparent black.
+            output.push(palette.get(index as usize).cloned().unwrap_or(0));
+        }
+    }
+    output
+}
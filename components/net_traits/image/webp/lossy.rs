@@ -0,0 +1,1866 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Decoding of lossy WebP images, which are VP8 key frames.
+//!
+//! <https://tools.ietf.org/html/rfc6386>
+
+use std::cmp;
+
+const DC_PRED: u8 = 0;
+const V_PRED: u8 = 1;
+const H_PRED: u8 = 2;
+const TM_PRED: u8 = 3;
+
+const B_DC_PRED: u8 = 0;
+const B_TM_PRED: u8 = 1;
+const B_VE_PRED: u8 = 2;
+const B_HE_PRED: u8 = 3;
+const B_LD_PRED: u8 = 4;
+const B_RD_PRED: u8 = 5;
+const B_VR_PRED: u8 = 6;
+const B_VL_PRED: u8 = 7;
+const B_HD_PRED: u8 = 8;
+const B_HU_PRED: u8 = 9;
+
+/// The tree of subblock intra modes, in the format of section 8.1.
+#[rustfmt::skip]
+const BPRED_MODE_TREE: [i8; 18] = [
+    -(B_DC_PRED as i8), 2, -(B_TM_PRED as i8), 4, -(B_VE_PRED as i8), 6, 8, 12,
+    -(B_HE_PRED as i8), 10, -(B_RD_PRED as i8), -(B_VR_PRED as i8), -(B_LD_PRED as i8), 14,
+    -(B_VL_PRED as i8), 16, -(B_HD_PRED as i8), -(B_HU_PRED as i8),
+];
+
+/// The probabilities of the subblock intra modes of key frames, indexed by
+/// the modes of the subblocks above and to the left.
+const BPRED_MODE_PROBS: [[[u8; 9]; 10]; 10] = [
+    [
+        [231, 120, 48, 89, 115, 113, 120, 152, 112],
+        [152, 179, 64, 126, 170, 118, 46, 70, 95],
+        [175, 69, 143, 80, 85, 82, 72, 155, 103],
+        [56, 58, 10, 171, 218, 189, 17, 13, 152],
+        [144, 71, 10, 38, 171, 213, 144, 34, 26],
+        [114, 26, 17, 163, 44, 195, 21, 10, 173],
+        [121, 24, 80, 195, 26, 62, 44, 64, 85],
+        [170, 46, 55, 19, 136, 160, 33, 206, 71],
+        [63, 20, 8, 114, 114, 208, 12, 9, 226],
+        [81, 40, 11, 96, 182, 84, 29, 16, 36],
+    ],
+    [
+        [134, 183, 89, 137, 98, 101, 106, 165, 148],
+        [72, 187, 100, 130, 157, 111, 32, 75, 80],
+        [66, 102, 167, 99, 74, 62, 40, 234, 128],
+        [41, 53, 9, 178, 241, 141, 26, 8, 107],
+        [104, 79, 12, 27, 217, 255, 87, 17, 7],
+        [74, 43, 26, 146, 73, 166, 49, 23, 157],
+        [65, 38, 105, 160, 51, 52, 31, 115, 128],
+        [87, 68, 71, 44, 114, 51, 15, 186, 23],
+        [47, 41, 14, 110, 182, 183, 21, 17, 194],
+        [66, 45, 25, 102, 197, 189, 23, 18, 22],
+    ],
+    [
+        [88, 88, 147, 150, 42, 46, 45, 196, 205],
+        [43, 97, 183, 117, 85, 38, 35, 179, 61],
+        [39, 53, 200, 87, 26, 21, 43, 232, 171],
+        [56, 34, 51, 104, 114, 102, 29, 93, 77],
+        [107, 54, 32, 26, 51, 1, 81, 43, 31],
+        [39, 28, 85, 171, 58, 165, 90, 98, 64],
+        [34, 22, 116, 206, 23, 34, 43, 166, 73],
+        [68, 25, 106, 22, 64, 171, 36, 225, 114],
+        [34, 19, 21, 102, 132, 188, 16, 76, 124],
+        [62, 18, 78, 95, 85, 57, 50, 48, 51],
+    ],
+    [
+        [193, 101, 35, 159, 215, 111, 89, 46, 111],
+        [60, 148, 31, 172, 219, 228, 21, 18, 111],
+        [112, 113, 77, 85, 179, 255, 38, 120, 114],
+        [40, 42, 1, 196, 245, 209, 10, 25, 109],
+        [100, 80, 8, 43, 154, 1, 51, 26, 71],
+        [88, 43, 29, 140, 166, 213, 37, 43, 154],
+        [61, 63, 30, 155, 67, 45, 68, 1, 209],
+        [142, 78, 78, 16, 255, 128, 34, 197, 171],
+        [41, 40, 5, 102, 211, 183, 4, 1, 221],
+        [51, 50, 17, 168, 209, 192, 23, 25, 82],
+    ],
+    [
+        [125, 98, 42, 88, 104, 85, 117, 175, 82],
+        [95, 84, 53, 89, 128, 100, 113, 101, 45],
+        [75, 79, 123, 47, 51, 128, 81, 171, 1],
+        [57, 17, 5, 71, 102, 57, 53, 41, 49],
+        [115, 21, 2, 10, 102, 255, 166, 23, 6],
+        [38, 33, 13, 121, 57, 73, 26, 1, 85],
+        [41, 10, 67, 138, 77, 110, 90, 47, 114],
+        [101, 29, 16, 10, 85, 128, 101, 196, 26],
+        [57, 18, 10, 102, 102, 213, 34, 20, 43],
+        [117, 20, 15, 36, 163, 128, 68, 1, 26],
+    ],
+    [
+        [138, 31, 36, 171, 27, 166, 38, 44, 229],
+        [67, 87, 58, 169, 82, 115, 26, 59, 179],
+        [63, 59, 90, 180, 59, 166, 93, 73, 154],
+        [40, 40, 21, 116, 143, 209, 34, 39, 175],
+        [57, 46, 22, 24, 128, 1, 54, 17, 37],
+        [47, 15, 16, 183, 34, 223, 49, 45, 183],
+        [46, 17, 33, 183, 6, 98, 15, 32, 183],
+        [65, 32, 73, 115, 28, 128, 23, 128, 205],
+        [40, 3, 9, 115, 51, 192, 18, 6, 223],
+        [87, 37, 9, 115, 59, 77, 64, 21, 47],
+    ],
+    [
+        [104, 55, 44, 218, 9, 54, 53, 130, 226],
+        [64, 90, 70, 205, 40, 41, 23, 26, 57],
+        [54, 57, 112, 184, 5, 41, 38, 166, 213],
+        [30, 34, 26, 133, 152, 116, 10, 32, 134],
+        [75, 32, 12, 51, 192, 255, 160, 43, 51],
+        [39, 19, 53, 221, 26, 114, 32, 73, 255],
+        [31, 9, 65, 234, 2, 15, 1, 118, 73],
+        [88, 31, 35, 67, 102, 85, 55, 186, 85],
+        [56, 21, 23, 111, 59, 205, 45, 37, 192],
+        [55, 38, 70, 124, 73, 102, 1, 34, 98],
+    ],
+    [
+        [102, 61, 71, 37, 34, 53, 31, 243, 192],
+        [69, 60, 71, 38, 73, 119, 28, 222, 37],
+        [68, 45, 128, 34, 1, 47, 11, 245, 171],
+        [62, 17, 19, 70, 146, 85, 55, 62, 70],
+        [75, 15, 9, 9, 64, 255, 184, 119, 16],
+        [37, 43, 37, 154, 100, 163, 85, 160, 1],
+        [63, 9, 92, 136, 28, 64, 32, 201, 85],
+        [86, 6, 28, 5, 64, 255, 25, 248, 1],
+        [56, 8, 17, 132, 137, 255, 55, 116, 128],
+        [58, 15, 20, 82, 135, 57, 26, 121, 40],
+    ],
+    [
+        [164, 50, 31, 137, 154, 133, 25, 35, 218],
+        [51, 103, 44, 131, 131, 123, 31, 6, 158],
+        [86, 40, 64, 135, 148, 224, 45, 183, 128],
+        [22, 26, 17, 131, 240, 154, 14, 1, 209],
+        [83, 12, 13, 54, 192, 255, 68, 47, 28],
+        [45, 16, 21, 91, 64, 222, 7, 1, 197],
+        [56, 21, 39, 155, 60, 138, 23, 102, 213],
+        [85, 26, 85, 85, 128, 128, 32, 146, 171],
+        [18, 11, 7, 63, 144, 171, 4, 4, 246],
+        [35, 27, 10, 146, 174, 171, 12, 26, 128],
+    ],
+    [
+        [190, 80, 35, 99, 180, 80, 126, 54, 45],
+        [85, 126, 47, 87, 176, 51, 41, 20, 32],
+        [101, 75, 128, 139, 118, 146, 116, 128, 85],
+        [56, 41, 15, 176, 236, 85, 37, 9, 62],
+        [146, 36, 19, 30, 171, 255, 97, 27, 20],
+        [71, 30, 17, 119, 118, 255, 17, 18, 138],
+        [101, 38, 60, 138, 55, 70, 43, 26, 142],
+        [138, 45, 61, 62, 219, 1, 81, 188, 64],
+        [32, 41, 20, 117, 151, 142, 20, 21, 163],
+        [112, 19, 12, 61, 195, 128, 48, 4, 24],
+    ],
+];
+
+type TokenProbs = [[[[u8; 11]; 3]; 8]; 4];
+
+const COEFF_UPDATE_PROBS: TokenProbs = [
+    [
+        [
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [176, 246, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [223, 241, 252, 255, 255, 255, 255, 255, 255, 255, 255],
+            [249, 253, 253, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 244, 252, 255, 255, 255, 255, 255, 255, 255, 255],
+            [234, 254, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+            [253, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 246, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+            [239, 253, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+            [254, 255, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 248, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+            [251, 255, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 253, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+            [251, 254, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+            [254, 255, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 254, 253, 255, 254, 255, 255, 255, 255, 255, 255],
+            [250, 255, 254, 255, 254, 255, 255, 255, 255, 255, 255],
+            [254, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+    ],
+    [
+        [
+            [217, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [225, 252, 241, 253, 255, 255, 254, 255, 255, 255, 255],
+            [234, 250, 241, 250, 253, 255, 253, 254, 255, 255, 255],
+        ],
+        [
+            [255, 254, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [223, 254, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+            [238, 253, 254, 254, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 248, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+            [249, 254, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 253, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [247, 254, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 253, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+            [252, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 254, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+            [253, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 254, 253, 255, 255, 255, 255, 255, 255, 255, 255],
+            [250, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [254, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+    ],
+    [
+        [
+            [186, 251, 250, 255, 255, 255, 255, 255, 255, 255, 255],
+            [234, 251, 244, 254, 255, 255, 255, 255, 255, 255, 255],
+            [251, 251, 243, 253, 254, 255, 254, 255, 255, 255, 255],
+        ],
+        [
+            [255, 253, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+            [236, 253, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+            [251, 253, 253, 254, 254, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 254, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+            [254, 254, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 254, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [254, 254, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [254, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [254, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+    ],
+    [
+        [
+            [248, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [250, 254, 252, 254, 255, 255, 255, 255, 255, 255, 255],
+            [248, 254, 249, 253, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 253, 253, 255, 255, 255, 255, 255, 255, 255, 255],
+            [246, 253, 253, 255, 255, 255, 255, 255, 255, 255, 255],
+            [252, 254, 251, 254, 254, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 254, 252, 255, 255, 255, 255, 255, 255, 255, 255],
+            [248, 254, 253, 255, 255, 255, 255, 255, 255, 255, 255],
+            [253, 255, 254, 254, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 251, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+            [245, 251, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+            [253, 253, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 251, 253, 255, 255, 255, 255, 255, 255, 255, 255],
+            [252, 253, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 254, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 252, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [249, 255, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 254, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 255, 253, 255, 255, 255, 255, 255, 255, 255, 255],
+            [250, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+        [
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [254, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+            [255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255],
+        ],
+    ],
+];
+
+const COEFF_PROBS: TokenProbs = [
+    [
+        [
+            [128, 128, 128, 128, 128, 128, 128, 128, 128, 128, 128],
+            [128, 128, 128, 128, 128, 128, 128, 128, 128, 128, 128],
+            [128, 128, 128, 128, 128, 128, 128, 128, 128, 128, 128],
+        ],
+        [
+            [253, 136, 254, 255, 228, 219, 128, 128, 128, 128, 128],
+            [189, 129, 242, 255, 227, 213, 255, 219, 128, 128, 128],
+            [106, 126, 227, 252, 214, 209, 255, 255, 128, 128, 128],
+        ],
+        [
+            [1, 98, 248, 255, 236, 226, 255, 255, 128, 128, 128],
+            [181, 133, 238, 254, 221, 234, 255, 154, 128, 128, 128],
+            [78, 134, 202, 247, 198, 180, 255, 219, 128, 128, 128],
+        ],
+        [
+            [1, 185, 249, 255, 243, 255, 128, 128, 128, 128, 128],
+            [184, 150, 247, 255, 236, 224, 128, 128, 128, 128, 128],
+            [77, 110, 216, 255, 236, 230, 128, 128, 128, 128, 128],
+        ],
+        [
+            [1, 101, 251, 255, 241, 255, 128, 128, 128, 128, 128],
+            [170, 139, 241, 252, 236, 209, 255, 255, 128, 128, 128],
+            [37, 116, 196, 243, 228, 255, 255, 255, 128, 128, 128],
+        ],
+        [
+            [1, 204, 254, 255, 245, 255, 128, 128, 128, 128, 128],
+            [207, 160, 250, 255, 238, 128, 128, 128, 128, 128, 128],
+            [102, 103, 231, 255, 211, 171, 128, 128, 128, 128, 128],
+        ],
+        [
+            [1, 152, 252, 255, 240, 255, 128, 128, 128, 128, 128],
+            [177, 135, 243, 255, 234, 225, 128, 128, 128, 128, 128],
+            [80, 129, 211, 255, 194, 224, 128, 128, 128, 128, 128],
+        ],
+        [
+            [1, 1, 255, 128, 128, 128, 128, 128, 128, 128, 128],
+            [246, 1, 255, 128, 128, 128, 128, 128, 128, 128, 128],
+            [255, 128, 128, 128, 128, 128, 128, 128, 128, 128, 128],
+        ],
+    ],
+    [
+        [
+            [198, 35, 237, 223, 193, 187, 162, 160, 145, 155, 62],
+            [131, 45, 198, 221, 172, 176, 220, 157, 252, 221, 1],
+            [68, 47, 146, 208, 149, 167, 221, 162, 255, 223, 128],
+        ],
+        [
+            [1, 149, 241, 255, 221, 224, 255, 255, 128, 128, 128],
+            [184, 141, 234, 253, 222, 220, 255, 199, 128, 128, 128],
+            [81, 99, 181, 242, 176, 190, 249, 202, 255, 255, 128],
+        ],
+        [
+            [1, 129, 232, 253, 214, 197, 242, 196, 255, 255, 128],
+            [99, 121, 210, 250, 201, 198, 255, 202, 128, 128, 128],
+            [23, 91, 163, 242, 170, 187, 247, 210, 255, 255, 128],
+        ],
+        [
+            [1, 200, 246, 255, 234, 255, 128, 128, 128, 128, 128],
+            [109, 178, 241, 255, 231, 245, 255, 255, 128, 128, 128],
+            [44, 130, 201, 253, 205, 192, 255, 255, 128, 128, 128],
+        ],
+        [
+            [1, 132, 239, 251, 219, 209, 255, 165, 128, 128, 128],
+            [94, 136, 225, 251, 218, 190, 255, 255, 128, 128, 128],
+            [22, 100, 174, 245, 186, 161, 255, 199, 128, 128, 128],
+        ],
+        [
+            [1, 182, 249, 255, 232, 235, 128, 128, 128, 128, 128],
+            [124, 143, 241, 255, 227, 234, 128, 128, 128, 128, 128],
+            [35, 77, 181, 251, 193, 211, 255, 205, 128, 128, 128],
+        ],
+        [
+            [1, 157, 247, 255, 236, 231, 255, 255, 128, 128, 128],
+            [121, 141, 235, 255, 225, 227, 255, 255, 128, 128, 128],
+            [45, 99, 188, 251, 195, 217, 255, 224, 128, 128, 128],
+        ],
+        [
+            [1, 1, 251, 255, 213, 255, 128, 128, 128, 128, 128],
+            [203, 1, 248, 255, 255, 128, 128, 128, 128, 128, 128],
+            [137, 1, 177, 255, 224, 255, 128, 128, 128, 128, 128],
+        ],
+    ],
+    [
+        [
+            [253, 9, 248, 251, 207, 208, 255, 192, 128, 128, 128],
+            [175, 13, 224, 243, 193, 185, 249, 198, 255, 255, 128],
+            [73, 17, 171, 221, 161, 179, 236, 167, 255, 234, 128],
+        ],
+        [
+            [1, 95, 247, 253, 212, 183, 255, 255, 128, 128, 128],
+            [239, 90, 244, 250, 211, 209, 255, 255, 128, 128, 128],
+            [155, 77, 195, 248, 188, 195, 255, 255, 128, 128, 128],
+        ],
+        [
+            [1, 24, 239, 251, 218, 219, 255, 205, 128, 128, 128],
+            [201, 51, 219, 255, 196, 186, 128, 128, 128, 128, 128],
+            [69, 46, 190, 239, 201, 218, 255, 228, 128, 128, 128],
+        ],
+        [
+            [1, 191, 251, 255, 255, 128, 128, 128, 128, 128, 128],
+            [223, 165, 249, 255, 213, 255, 128, 128, 128, 128, 128],
+            [141, 124, 248, 255, 255, 128, 128, 128, 128, 128, 128],
+        ],
+        [
+            [1, 16, 248, 255, 255, 128, 128, 128, 128, 128, 128],
+            [190, 36, 230, 255, 236, 255, 128, 128, 128, 128, 128],
+            [149, 1, 255, 128, 128, 128, 128, 128, 128, 128, 128],
+        ],
+        [
+            [1, 226, 255, 128, 128, 128, 128, 128, 128, 128, 128],
+            [247, 192, 255, 128, 128, 128, 128, 128, 128, 128, 128],
+            [240, 128, 255, 128, 128, 128, 128, 128, 128, 128, 128],
+        ],
+        [
+            [1, 134, 252, 255, 255, 128, 128, 128, 128, 128, 128],
+            [213, 62, 250, 255, 255, 128, 128, 128, 128, 128, 128],
+            [55, 93, 255, 128, 128, 128, 128, 128, 128, 128, 128],
+        ],
+        [
+            [128, 128, 128, 128, 128, 128, 128, 128, 128, 128, 128],
+            [128, 128, 128, 128, 128, 128, 128, 128, 128, 128, 128],
+            [128, 128, 128, 128, 128, 128, 128, 128, 128, 128, 128],
+        ],
+    ],
+    [
+        [
+            [202, 24, 213, 235, 186, 191, 220, 160, 240, 175, 255],
+            [126, 38, 182, 232, 169, 184, 228, 174, 255, 187, 128],
+            [61, 46, 138, 219, 151, 178, 240, 170, 255, 216, 128],
+        ],
+        [
+            [1, 112, 230, 250, 199, 191, 247, 159, 255, 255, 128],
+            [166, 109, 228, 252, 211, 215, 255, 174, 128, 128, 128],
+            [39, 77, 162, 232, 172, 180, 245, 178, 255, 255, 128],
+        ],
+        [
+            [1, 52, 220, 246, 198, 199, 249, 220, 255, 255, 128],
+            [124, 74, 191, 243, 183, 193, 250, 221, 255, 255, 128],
+            [24, 71, 130, 219, 154, 170, 243, 182, 255, 255, 128],
+        ],
+        [
+            [1, 182, 225, 249, 219, 240, 255, 224, 128, 128, 128],
+            [149, 150, 226, 252, 216, 205, 255, 171, 128, 128, 128],
+            [28, 108, 170, 242, 183, 194, 254, 223, 255, 255, 128],
+        ],
+        [
+            [1, 81, 230, 252, 204, 203, 255, 192, 128, 128, 128],
+            [123, 102, 209, 247, 188, 196, 255, 233, 128, 128, 128],
+            [20, 95, 153, 243, 164, 173, 255, 203, 128, 128, 128],
+        ],
+        [
+            [1, 222, 248, 255, 216, 213, 128, 128, 128, 128, 128],
+            [168, 175, 246, 252, 235, 205, 255, 255, 128, 128, 128],
+            [47, 116, 215, 255, 211, 212, 255, 255, 128, 128, 128],
+        ],
+        [
+            [1, 121, 236, 253, 212, 214, 255, 255, 128, 128, 128],
+            [141, 84, 213, 252, 201, 202, 255, 219, 128, 128, 128],
+            [42, 80, 160, 240, 162, 185, 255, 205, 128, 128, 128],
+        ],
+        [
+            [1, 1, 255, 128, 128, 128, 128, 128, 128, 128, 128],
+            [244, 1, 255, 128, 128, 128, 128, 128, 128, 128, 128],
+            [238, 1, 255, 128, 128, 128, 128, 128, 128, 128, 128],
+        ],
+    ],
+];
+
+/// The probabilities of the extra bits of the DCT_CAT3 to DCT_CAT6 tokens.
+const DCT_CAT_PROBS: [&[u8]; 4] = [
+    &[173, 148, 140],
+    &[176, 155, 140, 135],
+    &[180, 157, 141, 134, 130],
+    &[254, 254, 243, 230, 196, 177, 153, 140, 133, 130, 129],
+];
+
+const COEFF_BANDS: [usize; 16] = [0, 1, 2, 3, 6, 4, 5, 6, 6, 6, 6, 6, 6, 6, 6, 7];
+
+const ZIGZAG: [usize; 16] = [0, 1, 4, 8, 5, 2, 3, 6, 9, 12, 13, 10, 7, 11, 14, 15];
+
+#[rustfmt::skip]
+const DC_QUANT: [i32; 128] = [
+      4,   5,   6,   7,   8,   9,  10,  10,
+     11,  12,  13,  14,  15,  16,  17,  17,
+     18,  19,  20,  20,  21,  21,  22,  22,
+     23,  23,  24,  25,  25,  26,  27,  28,
+     29,  30,  31,  32,  33,  34,  35,  36,
+     37,  37,  38,  39,  40,  41,  42,  43,
+     44,  45,  46,  46,  47,  48,  49,  50,
+     51,  52,  53,  54,  55,  56,  57,  58,
+     59,  60,  61,  62,  63,  64,  65,  66,
+     67,  68,  69,  70,  71,  72,  73,  74,
+     75,  76,  76,  77,  78,  79,  80,  81,
+     82,  83,  84,  85,  86,  87,  88,  89,
+     91,  93,  95,  96,  98, 100, 101, 102,
+    104, 106, 108, 110, 112, 114, 116, 118,
+    122, 124, 126, 128, 130, 132, 134, 136,
+    138, 140, 143, 145, 148, 151, 154, 157,
+];
+
+#[rustfmt::skip]
+const AC_QUANT: [i32; 128] = [
+      4,   5,   6,   7,   8,    9,  10,  11,
+      12,  13,  14,  15,  16,  17,  18,  19,
+      20,  21,  22,  23,  24,  25,  26,  27,
+      28,  29,  30,  31,  32,  33,  34,  35,
+      36,  37,  38,  39,  40,  41,  42,  43,
+      44,  45,  46,  47,  48,  49,  50,  51,
+      52,  53,  54,  55,  56,  57,  58,  60,
+      62,  64,  66,  68,  70,  72,  74,  76,
+      78,  80,  82,  84,  86,  88,  90,  92,
+      94,  96,  98, 100, 102, 104, 106, 108,
+     110, 112, 114, 116, 119, 122, 125, 128,
+     131, 134, 137, 140, 143, 146, 149, 152,
+     155, 158, 161, 164, 167, 170, 173, 177,
+     181, 185, 189, 193, 197, 201, 205, 209,
+     213, 217, 221, 225, 229, 234, 239, 245,
+     249, 254, 259, 264, 269, 274, 279, 284,
+];
+
+/// The stride of the luma prediction workspace: one column of left pixels,
+/// the 16 pixels of the macroblock and four above-right pixels.
+const LUMA_STRIDE: usize = 21;
+
+/// The stride of the chroma prediction workspaces.
+const CHROMA_STRIDE: usize = 9;
+
+/// A lossy image, as RGBA pixels.
+pub struct LossyImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+/// Decodes the data of a VP8 chunk.
+pub fn decode(data: &[u8]) -> Option<LossyImage> {
+    if data.len() < 10 {
+        return None;
+    }
+    let tag = data[0] as usize | (data[1] as usize) << 8 | (data[2] as usize) << 16;
+    let key_frame = tag & 1 == 0;
+    let version = (tag >> 1) & 7;
+    let show_frame = tag & 0x10 != 0;
+    let first_partition_size = tag >> 5;
+    if !key_frame || version > 3 || !show_frame || data[3..6] != [0x9d, 0x01, 0x2a] {
+        return None;
+    }
+    let width = (data[6] as u32 | (data[7] as u32) << 8) & 0x3fff;
+    let height = (data[8] as u32 | (data[9] as u32) << 8) & 0x3fff;
+    if width == 0 || height == 0 {
+        return None;
+    }
+    let first_partition = data.get(10..10 + first_partition_size)?;
+    // The macroblock headers follow the frame header in the first partition.
+    let mut reader = BoolDecoder::new(first_partition);
+    let frame = FrameHeader::read(&mut reader, &data[10 + first_partition_size..])?;
+    let mut decoder = Decoder::new(width, height, frame);
+    decoder.decode_macroblocks(&mut reader);
+    decoder.filter();
+    Some(LossyImage {
+        width,
+        height,
+        pixels: decoder.to_rgba(),
+    })
+}
+
+/// The boolean entropy decoder of section 7.
+struct BoolDecoder<'a> {
+    data: &'a [u8],
+    position: usize,
+    value: u32,
+    range: u32,
+    bit_count: u32,
+}
+
+impl<'a> BoolDecoder<'a> {
+    fn new(data: &'a [u8]) -> BoolDecoder<'a> {
+        let mut decoder = BoolDecoder {
+            data,
+            position: 0,
+            value: 0,
+            range: 255,
+            bit_count: 0,
+        };
+        decoder.value = (decoder.next_byte() as u32) << 8 | decoder.next_byte() as u32;
+        decoder
+    }
+
+    /// Returns the next byte of the data, or zero past its end like libvpx.
+    fn next_byte(&mut self) -> u8 {
+        let byte = self.data.get(self.position).cloned().unwrap_or(0);
+        self.position += 1;
+        byte
+    }
+
+    fn read_bool(&mut self, probability: u8) -> bool {
+        let split = 1 + (((self.range - 1) * probability as u32) >> 8);
+        let big_split = split << 8;
+        let bit = if self.value >= big_split {
+            self.range -= split;
+            self.value -= big_split;
+            true
+        } else {
+            self.range = split;
+            false
+        };
+        while self.range < 128 {
+            self.value <<= 1;
+            self.range <<= 1;
+            self.bit_count += 1;
+            if self.bit_count == 8 {
+                self.bit_count = 0;
+                self.value |= self.next_byte() as u32;
+            }
+        }
+        bit
+    }
+
+    fn read_flag(&mut self) -> bool {
+        self.read_bool(128)
+    }
+
+    fn read_literal(&mut self, bits: u32) -> i32 {
+        (0..bits).fold(0, |value, _| value << 1 | self.read_flag() as i32)
+    }
+
+    fn read_signed(&mut self, bits: u32) -> i32 {
+        let value = self.read_literal(bits);
+        if self.read_flag() {
+            -value
+        } else {
+            value
+        }
+    }
+
+    fn read_optional_signed(&mut self, bits: u32) -> i32 {
+        if self.read_flag() {
+            self.read_signed(bits)
+        } else {
+            0
+        }
+    }
+
+    fn read_tree(&mut self, tree: &[i8], probabilities: &[u8]) -> u8 {
+        let mut index = 0;
+        loop {
+            let bit = self.read_bool(probabilities[index >> 1]);
+            let next = tree[index + bit as usize];
+            if next <= 0 {
+                return -next as u8;
+            }
+            index = next as usize;
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct Quantizer {
+    y1: [i32; 2],
+    y2: [i32; 2],
+    uv: [i32; 2],
+}
+
+#[derive(Clone, Copy, Default)]
+struct FilterParameters {
+    /// The edge limit, or zero if the macroblock isn't filtered.
+    limit: i32,
+    interior_limit: i32,
+    hev_threshold: i32,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum FilterType {
+    None,
+    Simple,
+    Normal,
+}
+
+/// The frame header of section 9, read from the first partition.
+struct FrameHeader<'a> {
+    segments_enabled: bool,
+    segment_map_updated: bool,
+    segment_probs: [u8; 3],
+    filter_type: FilterType,
+    quantizers: [Quantizer; 4],
+    /// The filter parameters of each segment, without and with B_PRED.
+    filter_parameters: [[FilterParameters; 2]; 4],
+    partitions: Vec<BoolDecoder<'a>>,
+    token_probs: TokenProbs,
+    skip_prob: Option<u8>,
+}
+
+impl<'a> FrameHeader<'a> {
+    fn read(reader: &mut BoolDecoder, partition_data: &'a [u8]) -> Option<FrameHeader<'a>> {
+        // The color space and clamping type don't affect decoding.
+        reader.read_flag();
+        reader.read_flag();
+
+        let segments_enabled = reader.read_flag();
+        let mut segment_map_updated = false;
+        // Like libwebp, segment values are absolute unless the header says
+        // otherwise.
+        let mut absolute_segment_values = true;
+        let mut segment_quantizers = [0; 4];
+        let mut segment_filter_levels = [0; 4];
+        let mut segment_probs = [255; 3];
+        if segments_enabled {
+            segment_map_updated = reader.read_flag();
+            if reader.read_flag() {
+                absolute_segment_values = reader.read_flag();
+                for quantizer in &mut segment_quantizers {
+                    *quantizer = reader.read_optional_signed(7);
+                }
+                for level in &mut segment_filter_levels {
+                    *level = reader.read_optional_signed(6);
+                }
+            }
+            if segment_map_updated {
+                for probability in &mut segment_probs {
+                    *probability = if reader.read_flag() {
+                        reader.read_literal(8) as u8
+                    } else {
+                        255
+                    };
+                }
+            }
+        }
+
+        let simple_filter = reader.read_flag();
+        let filter_level = reader.read_literal(6);
+        let sharpness = reader.read_literal(3);
+        let mut reference_delta = 0;
+        let mut mode_delta = 0;
+        let filter_deltas_enabled = reader.read_flag();
+        if filter_deltas_enabled && reader.read_flag() {
+            // Only the deltas of the intra frame and of B_PRED apply to key
+            // frames.
+            for index in 0..4 {
+                let delta = reader.read_optional_signed(6);
+                if index == 0 {
+                    reference_delta = delta;
+                }
+            }
+            for index in 0..4 {
+                let delta = reader.read_optional_signed(6);
+                if index == 0 {
+                    mode_delta = delta;
+                }
+            }
+        }
+
+        let partition_count = 1 << reader.read_literal(2);
+        let sizes_length = 3 * (partition_count - 1);
+        if partition_data.len() < sizes_length {
+            return None;
+        }
+        let mut partitions = Vec::with_capacity(partition_count);
+        let mut start = sizes_length;
+        for index in 0..partition_count {
+            let end = if index + 1 < partition_count {
+                let size = &partition_data[3 * index..3 * index + 3];
+                start + (size[0] as usize | (size[1] as usize) << 8 | (size[2] as usize) << 16)
+            } else {
+                partition_data.len()
+            };
+            let end = cmp::min(end, partition_data.len());
+            partitions.push(BoolDecoder::new(&partition_data[cmp::min(start, end)..end]));
+            start = end;
+        }
+
+        let base_quantizer = reader.read_literal(7);
+        let y1_dc_delta = reader.read_optional_signed(4);
+        let y2_dc_delta = reader.read_optional_signed(4);
+        let y2_ac_delta = reader.read_optional_signed(4);
+        let uv_dc_delta = reader.read_optional_signed(4);
+        let uv_ac_delta = reader.read_optional_signed(4);
+        let mut quantizers = [Quantizer::default(); 4];
+        for (segment, quantizer) in quantizers.iter_mut().enumerate() {
+            let index = if !segments_enabled {
+                base_quantizer
+            } else if absolute_segment_values {
+                segment_quantizers[segment]
+            } else {
+                segment_quantizers[segment] + base_quantizer
+            };
+            let dc = |delta: i32, max: i32| DC_QUANT[clamp(index + delta, 0, max) as usize];
+            let ac = |delta: i32| AC_QUANT[clamp(index + delta, 0, 127) as usize];
+            *quantizer = Quantizer {
+                y1: [dc(y1_dc_delta, 127), ac(0)],
+                y2: [
+                    dc(y2_dc_delta, 127) * 2,
+                    cmp::max(ac(y2_ac_delta) * 155 / 100, 8),
+                ],
+                uv: [dc(uv_dc_delta, 117), ac(uv_ac_delta)],
+            };
+        }
+
+        // The frame is decoded on its own, so whether its probabilities
+        // persist is irrelevant.
+        reader.read_flag();
+
+        let mut token_probs = COEFF_PROBS;
+        for i in 0..4 {
+            for j in 0..8 {
+                for k in 0..3 {
+                    for l in 0..11 {
+                        if reader.read_bool(COEFF_UPDATE_PROBS[i][j][k][l]) {
+                            token_probs[i][j][k][l] = reader.read_literal(8) as u8;
+                        }
+                    }
+                }
+            }
+        }
+        let skip_prob = if reader.read_flag() {
+            Some(reader.read_literal(8) as u8)
+        } else {
+            None
+        };
+
+        let filter_type = if filter_level == 0 {
+            FilterType::None
+        } else if simple_filter {
+            FilterType::Simple
+        } else {
+            FilterType::Normal
+        };
+        let mut filter_parameters = [[FilterParameters::default(); 2]; 4];
+        for (segment, parameters) in filter_parameters.iter_mut().enumerate() {
+            let base_level = if !segments_enabled {
+                filter_level
+            } else if absolute_segment_values {
+                segment_filter_levels[segment]
+            } else {
+                segment_filter_levels[segment] + filter_level
+            };
+            for (bpred, parameters) in parameters.iter_mut().enumerate() {
+                let mut level = base_level;
+                if filter_deltas_enabled {
+                    level += reference_delta;
+                    if bpred == 1 {
+                        level += mode_delta;
+                    }
+                }
+                let level = clamp(level, 0, 63);
+                if level == 0 {
+                    continue;
+                }
+                let mut interior_limit = level;
+                if sharpness > 0 {
+                    interior_limit >>= if sharpness > 4 { 2 } else { 1 };
+                    interior_limit = cmp::min(interior_limit, 9 - sharpness);
+                }
+                let interior_limit = cmp::max(interior_limit, 1);
+                *parameters = FilterParameters {
+                    limit: 2 * level + interior_limit,
+                    interior_limit,
+                    hev_threshold: if level >= 40 {
+                        2
+                    } else if level >= 15 {
+                        1
+                    } else {
+                        0
+                    },
+                };
+            }
+        }
+
+        Some(FrameHeader {
+            segments_enabled,
+            segment_map_updated,
+            segment_probs,
+            filter_type,
+            quantizers,
+            filter_parameters,
+            partitions,
+            token_probs,
+            skip_prob,
+        })
+    }
+}
+
+/// The prediction modes and loop filter state of a macroblock.
+#[derive(Clone, Copy)]
+struct MacroBlock {
+    segment: usize,
+    luma_mode: Option<u8>,
+    subblock_modes: [u8; 16],
+    chroma_mode: u8,
+    skip: bool,
+    filter_inner_edges: bool,
+}
+
+/// Whether the blocks next to a macroblock had non-zero coefficients, which
+/// is the context of their first token.
+#[derive(Clone, Copy, Default)]
+struct NonZeroContext {
+    y: [bool; 4],
+    u: [bool; 2],
+    v: [bool; 2],
+    y2: bool,
+}
+
+struct Decoder<'a> {
+    width: u32,
+    height: u32,
+    mb_width: usize,
+    mb_height: usize,
+    frame: FrameHeader<'a>,
+    macroblocks: Vec<MacroBlock>,
+    y: Vec<u8>,
+    u: Vec<u8>,
+    v: Vec<u8>,
+}
+
+impl<'a> Decoder<'a> {
+    fn new(width: u32, height: u32, frame: FrameHeader<'a>) -> Decoder<'a> {
+        let mb_width = (width as usize + 15) / 16;
+        let mb_height = (height as usize + 15) / 16;
+        Decoder {
+            width,
+            height,
+            mb_width,
+            mb_height,
+            frame,
+            macroblocks: Vec::with_capacity(mb_width * mb_height),
+            y: vec![0; mb_width * mb_height * 256],
+            u: vec![0; mb_width * mb_height * 64],
+            v: vec![0; mb_width * mb_height * 64],
+        }
+    }
+
+    fn decode_macroblocks(&mut self, reader: &mut BoolDecoder) {
+        let mut intra_top = vec![[B_DC_PRED; 4]; self.mb_width];
+        let mut non_zero_top = vec![NonZeroContext::default(); self.mb_width];
+        for mby in 0..self.mb_height {
+            let mut intra_left = [B_DC_PRED; 4];
+            let mut non_zero_left = NonZeroContext::default();
+            let partition_index = mby & (self.frame.partitions.len() - 1);
+            for mbx in 0..self.mb_width {
+                let mut macroblock =
+                    self.read_macroblock_header(reader, &mut intra_top[mbx], &mut intra_left);
+                let mut coefficients = [0i16; 384];
+                let non_zero = if macroblock.skip {
+                    non_zero_top[mbx].reset(macroblock.luma_mode.is_none());
+                    non_zero_left.reset(macroblock.luma_mode.is_none());
+                    false
+                } else {
+                    read_residuals(
+                        &mut self.frame.partitions[partition_index],
+                        &self.frame.token_probs,
+                        &self.frame.quantizers[macroblock.segment],
+                        macroblock.luma_mode.is_none(),
+                        &mut non_zero_top[mbx],
+                        &mut non_zero_left,
+                        &mut coefficients,
+                    )
+                };
+                macroblock.filter_inner_edges = macroblock.luma_mode.is_none() || non_zero;
+                self.reconstruct_luma(mbx, mby, &macroblock, &coefficients);
+                self.reconstruct_chroma(mbx, mby, &macroblock, &coefficients);
+                self.macroblocks.push(macroblock);
+            }
+        }
+    }
+
+    fn read_macroblock_header(
+        &self,
+        reader: &mut BoolDecoder,
+        intra_top: &mut [u8; 4],
+        intra_left: &mut [u8; 4],
+    ) -> MacroBlock {
+        let probs = &self.frame.segment_probs;
+        let segment = if self.frame.segments_enabled && self.frame.segment_map_updated {
+            if !reader.read_bool(probs[0]) {
+                reader.read_bool(probs[1]) as usize
+            } else {
+                2 + reader.read_bool(probs[2]) as usize
+            }
+        } else {
+            0
+        };
+        let skip = match self.frame.skip_prob {
+            Some(probability) => reader.read_bool(probability),
+            None => false,
+        };
+
+        let mut subblock_modes = [B_DC_PRED; 16];
+        let luma_mode = if reader.read_bool(145) {
+            let mode = if reader.read_bool(156) {
+                if reader.read_bool(128) {
+                    TM_PRED
+                } else {
+                    H_PRED
+                }
+            } else if reader.read_bool(163) {
+                V_PRED
+            } else {
+                DC_PRED
+            };
+            // Later B_PRED macroblocks use the subblock mode implied by this
+            // mode as context.
+            let implied = match mode {
+                DC_PRED => B_DC_PRED,
+                V_PRED => B_VE_PRED,
+                H_PRED => B_HE_PRED,
+                _ => B_TM_PRED,
+            };
+            *intra_top = [implied; 4];
+            *intra_left = [implied; 4];
+            Some(mode)
+        } else {
+            for y in 0..4 {
+                let mut left = intra_left[y];
+                for x in 0..4 {
+                    let probs = &BPRED_MODE_PROBS[intra_top[x] as usize][left as usize];
+                    let mode = reader.read_tree(&BPRED_MODE_TREE, probs);
+                    subblock_modes[y * 4 + x] = mode;
+                    intra_top[x] = mode;
+                    left = mode;
+                }
+                intra_left[y] = left;
+            }
+            None
+        };
+
+        let chroma_mode = if !reader.read_bool(142) {
+            DC_PRED
+        } else if !reader.read_bool(114) {
+            V_PRED
+        } else if reader.read_bool(183) {
+            TM_PRED
+        } else {
+            H_PRED
+        };
+
+        MacroBlock {
+            segment,
+            luma_mode,
+            subblock_modes,
+            chroma_mode,
+            skip,
+            filter_inner_edges: false,
+        }
+    }
+
+    fn reconstruct_luma(
+        &mut self,
+        mbx: usize,
+        mby: usize,
+        macroblock: &MacroBlock,
+        coefficients: &[i16; 384],
+    ) {
+        let stride = self.mb_width * 16;
+        let mut ws = [0u8; LUMA_STRIDE * 17];
+        fill_edges(&self.y, stride, mbx, mby, 16, LUMA_STRIDE, &mut ws);
+        // Subblocks on the right edge use the pixels above and to the right
+        // of the macroblock, so replicate them down the workspace.
+        if mby > 0 {
+            let above = (mby * 16 - 1) * stride + mbx * 16;
+            for x in 0..4 {
+                ws[17 + x] = if mbx + 1 < self.mb_width {
+                    self.y[above + 16 + x]
+                } else {
+                    self.y[above + 15]
+                };
+            }
+        } else {
+            for x in 0..4 {
+                ws[17 + x] = 127;
+            }
+        }
+        for row in &[4, 8, 12] {
+            let (top, rest) = ws.split_at_mut(row * LUMA_STRIDE);
+            rest[17..21].copy_from_slice(&top[17..21]);
+        }
+
+        match macroblock.luma_mode {
+            Some(mode) => {
+                predict_block(&mut ws, LUMA_STRIDE, 16, mode, mbx, mby);
+                for index in 0..16 {
+                    let offset = (index / 4 * 4 + 1) * LUMA_STRIDE + index % 4 * 4 + 1;
+                    let block = &coefficients[index * 16..index * 16 + 16];
+                    add_residual(&mut ws, offset, LUMA_STRIDE, block);
+                }
+            },
+            None => {
+                for index in 0..16 {
+                    let offset = (index / 4 * 4 + 1) * LUMA_STRIDE + index % 4 * 4 + 1;
+                    let mode = macroblock.subblock_modes[index];
+                    predict_subblock(&mut ws, offset, LUMA_STRIDE, mode);
+                    let block = &coefficients[index * 16..index * 16 + 16];
+                    add_residual(&mut ws, offset, LUMA_STRIDE, block);
+                }
+            },
+        }
+
+        for y in 0..16 {
+            let start = (mby * 16 + y) * stride + mbx * 16;
+            let ws_start = (y + 1) * LUMA_STRIDE + 1;
+            self.y[start..start + 16].copy_from_slice(&ws[ws_start..ws_start + 16]);
+        }
+    }
+
+    fn reconstruct_chroma(
+        &mut self,
+        mbx: usize,
+        mby: usize,
+        macroblock: &MacroBlock,
+        coefficients: &[i16; 384],
+    ) {
+        let stride = self.mb_width * 8;
+        for (plane, coefficients) in [&mut self.u, &mut self.v]
+            .iter_mut()
+            .zip(coefficients[256..].chunks(64))
+        {
+            let mut ws = [0u8; CHROMA_STRIDE * 9];
+            fill_edges(plane, stride, mbx, mby, 8, CHROMA_STRIDE, &mut ws);
+            predict_block(&mut ws, CHROMA_STRIDE, 8, macroblock.chroma_mode, mbx, mby);
+            for index in 0..4 {
+                let offset = (index / 2 * 4 + 1) * CHROMA_STRIDE + index % 2 * 4 + 1;
+                let block = &coefficients[index * 16..index * 16 + 16];
+                add_residual(&mut ws, offset, CHROMA_STRIDE, block);
+            }
+            for y in 0..8 {
+                let start = (mby * 8 + y) * stride + mbx * 8;
+                let ws_start = (y + 1) * CHROMA_STRIDE + 1;
+                plane[start..start + 8].copy_from_slice(&ws[ws_start..ws_start + 8]);
+            }
+        }
+    }
+
+    /// Runs the loop filter of section 15 over the reconstructed frame.
+    fn filter(&mut self) {
+        if self.frame.filter_type == FilterType::None {
+            return;
+        }
+        let y_stride = self.mb_width * 16;
+        let uv_stride = self.mb_width * 8;
+        for mby in 0..self.mb_height {
+            for mbx in 0..self.mb_width {
+                let macroblock = self.macroblocks[mby * self.mb_width + mbx];
+                let bpred = macroblock.luma_mode.is_none() as usize;
+                let parameters = self.frame.filter_parameters[macroblock.segment][bpred];
+                if parameters.limit == 0 {
+                    continue;
+                }
+                let inner = macroblock.filter_inner_edges;
+                let y = (mby * 16) * y_stride + mbx * 16;
+                if self.frame.filter_type == FilterType::Simple {
+                    let plane = &mut self.y;
+                    if mbx > 0 {
+                        simple_filter(plane, y, 1, y_stride, parameters.limit + 4);
+                    }
+                    if inner {
+                        for x in &[4, 8, 12] {
+                            simple_filter(plane, y + x, 1, y_stride, parameters.limit);
+                        }
+                    }
+                    if mby > 0 {
+                        simple_filter(plane, y, y_stride, 1, parameters.limit + 4);
+                    }
+                    if inner {
+                        for row in &[4, 8, 12] {
+                            simple_filter(plane, y + row * y_stride, y_stride, 1, parameters.limit);
+                        }
+                    }
+                    continue;
+                }
+                let uv = (mby * 8) * uv_stride + mbx * 8;
+                let edges = (mbx > 0, mby > 0, inner);
+                normal_filter(&mut self.y, y, y_stride, 16, edges, &parameters);
+                normal_filter(&mut self.u, uv, uv_stride, 8, edges, &parameters);
+                normal_filter(&mut self.v, uv, uv_stride, 8, edges, &parameters);
+            }
+        }
+    }
+
+    /// Converts the frame to RGBA, upsampling the chroma planes like libwebp.
+    fn to_rgba(&self) -> Vec<u8> {
+        let width = self.width as usize;
+        let height = self.height as usize;
+        let y_stride = self.mb_width * 16;
+        let uv_stride = self.mb_width * 8;
+        let mut pixels = vec![0; width * height * 4];
+        let y_row = |y: usize| &self.y[y * y_stride..y * y_stride + width];
+        let uv_row = |y: usize| {
+            (
+                &self.u[y * uv_stride..(y + 1) * uv_stride],
+                &self.v[y * uv_stride..(y + 1) * uv_stride],
+            )
+        };
+        let mut upsample = |y: usize, far_uv, near_uv| {
+            let row = &mut pixels[y * width * 4..(y + 1) * width * 4];
+            upsample_row(y_row(y), far_uv, near_uv, row);
+        };
+        // Each row of chroma samples is shared by two rows of luma samples,
+        // and blended with the row above or below depending on which is
+        // closer.
+        upsample(0, uv_row(0), uv_row(0));
+        for y in (1..height).step_by(2) {
+            let top_uv = uv_row(y / 2);
+            if y + 1 < height {
+                let bottom_uv = uv_row(y / 2 + 1);
+                upsample(y, bottom_uv, top_uv);
+                upsample(y + 1, top_uv, bottom_uv);
+            } else {
+                upsample(y, top_uv, top_uv);
+            }
+        }
+        pixels
+    }
+}
+
+impl NonZeroContext {
+    /// Resets the context after a macroblock without coefficients, which
+    /// only affects the Y2 context if the macroblock has a Y2 block.
+    fn reset(&mut self, bpred: bool) {
+        let y2 = self.y2;
+        *self = NonZeroContext::default();
+        if bpred {
+            self.y2 = y2;
+        }
+    }
+}
+
+fn clamp(value: i32, min: i32, max: i32) -> i32 {
+    cmp::max(min, cmp::min(value, max))
+}
+
+fn clip_pixel(value: i32) -> u8 {
+    clamp(value, 0, 255) as u8
+}
+
+/// Reads the DCT coefficients of a macroblock, dequantized and in raster
+/// order, and returns whether any of its blocks has non-zero coefficients.
+fn read_residuals(
+    reader: &mut BoolDecoder,
+    probs: &TokenProbs,
+    quantizer: &Quantizer,
+    bpred: bool,
+    top: &mut NonZeroContext,
+    left: &mut NonZeroContext,
+    coefficients: &mut [i16; 384],
+) -> bool {
+    let mut non_zero = false;
+    let (first, y_plane) = if bpred {
+        (0, 3)
+    } else {
+        let mut y2 = [0i16; 16];
+        let context = top.y2 as usize + left.y2 as usize;
+        let count = read_coefficients(reader, &probs[1], context, quantizer.y2, 0, &mut y2);
+        top.y2 = count > 0;
+        left.y2 = count > 0;
+        if count > 1 {
+            inverse_walsh_hadamard_transform(&y2, coefficients);
+        } else {
+            let dc = ((y2[0] as i32 + 3) >> 3) as i16;
+            for index in 0..16 {
+                coefficients[index * 16] = dc;
+            }
+        }
+        (1, 0)
+    };
+
+    for y in 0..4 {
+        let mut l = left.y[y];
+        for x in 0..4 {
+            let context = l as usize + top.y[x] as usize;
+            let block = &mut coefficients[(y * 4 + x) * 16..(y * 4 + x) * 16 + 16];
+            let count =
+                read_coefficients(reader, &probs[y_plane], context, quantizer.y1, first, block);
+            l = count > first;
+            top.y[x] = l;
+            non_zero |= count > 1 || block[0] != 0;
+        }
+        left.y[y] = l;
+    }
+
+    for (plane, (top, left)) in [(&mut top.u, &mut left.u), (&mut top.v, &mut left.v)]
+        .iter_mut()
+        .enumerate()
+    {
+        for y in 0..2 {
+            let mut l = left[y];
+            for x in 0..2 {
+                let context = l as usize + top[x] as usize;
+                let start = 256 + plane * 64 + (y * 2 + x) * 16;
+                let block = &mut coefficients[start..start + 16];
+                let count = read_coefficients(reader, &probs[2], context, quantizer.uv, 0, block);
+                l = count > 0;
+                top[x] = l;
+                non_zero |= count > 1 || block[0] != 0;
+            }
+            left[y] = l;
+        }
+    }
+    non_zero
+}
+
+/// Reads the tokens of a block from position `first`, and returns the
+/// position after the last one read.
+fn read_coefficients(
+    reader: &mut BoolDecoder,
+    probs: &[[[u8; 11]; 3]; 8],
+    context: usize,
+    quantizer: [i32; 2],
+    first: usize,
+    block: &mut [i16],
+) -> usize {
+    let mut n = first;
+    let mut p = &probs[COEFF_BANDS[n]][context];
+    while n < 16 {
+        if !reader.read_bool(p[0]) {
+            // The end of block token.
+            return n;
+        }
+        while !reader.read_bool(p[1]) {
+            n += 1;
+            if n == 16 {
+                return 16;
+            }
+            p = &probs[COEFF_BANDS[n]][0];
+        }
+        let (value, next_context) = if !reader.read_bool(p[2]) {
+            (1, 1)
+        } else {
+            (read_large_value(reader, p), 2)
+        };
+        let value = if reader.read_flag() { -value } else { value };
+        let factor = if n > 0 { quantizer[1] } else { quantizer[0] };
+        block[ZIGZAG[n]] = (value * factor) as i16;
+        n += 1;
+        if n < 16 {
+            p = &probs[COEFF_BANDS[n]][next_context];
+        }
+    }
+    16
+}
+
+/// Reads the value of a token greater than one.
+fn read_large_value(reader: &mut BoolDecoder, p: &[u8; 11]) -> i32 {
+    if !reader.read_bool(p[3]) {
+        if !reader.read_bool(p[4]) {
+            2
+        } else {
+            3 + reader.read_bool(p[5]) as i32
+        }
+    } else if !reader.read_bool(p[6]) {
+        if !reader.read_bool(p[7]) {
+            5 + reader.read_bool(159) as i32
+        } else {
+            7 + 2 * reader.read_bool(165) as i32 + reader.read_bool(145) as i32
+        }
+    } else {
+        let high = reader.read_bool(p[8]) as usize;
+        let low = reader.read_bool(p[9 + high]) as usize;
+        let category = 2 * high + low;
+        let extra = DCT_CAT_PROBS[category]
+            .iter()
+            .fold(0, |value, &probability| {
+                value << 1 | reader.read_bool(probability) as i32
+            });
+        extra + 3 + (8 << category)
+    }
+}
+
+/// Distributes the second order luma DC coefficients to the DC of each
+/// luma block.
+fn inverse_walsh_hadamard_transform(input: &[i16; 16], coefficients: &mut [i16; 384]) {
+    let mut temp = [0i32; 16];
+    for i in 0..4 {
+        let a0 = input[i] as i32 + input[12 + i] as i32;
+        let a1 = input[4 + i] as i32 + input[8 + i] as i32;
+        let a2 = input[4 + i] as i32 - input[8 + i] as i32;
+        let a3 = input[i] as i32 - input[12 + i] as i32;
+        temp[i] = a0 + a1;
+        temp[8 + i] = a0 - a1;
+        temp[4 + i] = a3 + a2;
+        temp[12 + i] = a3 - a2;
+    }
+    for i in 0..4 {
+        let dc = temp[i * 4] + 3;
+        let a0 = dc + temp[3 + i * 4];
+        let a1 = temp[1 + i * 4] + temp[2 + i * 4];
+        let a2 = temp[1 + i * 4] - temp[2 + i * 4];
+        let a3 = dc - temp[3 + i * 4];
+        coefficients[(i * 4) * 16] = ((a0 + a1) >> 3) as i16;
+        coefficients[(i * 4 + 1) * 16] = ((a3 + a2) >> 3) as i16;
+        coefficients[(i * 4 + 2) * 16] = ((a0 - a1) >> 3) as i16;
+        coefficients[(i * 4 + 3) * 16] = ((a3 - a2) >> 3) as i16;
+    }
+}
+
+/// Adds the inverse DCT of a block to the predicted pixels at `offset`.
+fn add_residual(ws: &mut [u8], offset: usize, stride: usize, block: &[i16]) {
+    if block.iter().all(|&coefficient| coefficient == 0) {
+        return;
+    }
+    // Only invalid streams have coefficients large enough to overflow.
+    let multiply1 = |a: i32| (a.wrapping_mul(20091) >> 16) + a;
+    let multiply2 = |a: i32| a.wrapping_mul(35468) >> 16;
+    let mut temp = [0i32; 16];
+    for i in 0..4 {
+        let a = block[i] as i32 + block[8 + i] as i32;
+        let b = block[i] as i32 - block[8 + i] as i32;
+        let c = multiply2(block[4 + i] as i32) - multiply1(block[12 + i] as i32);
+        let d = multiply1(block[4 + i] as i32) + multiply2(block[12 + i] as i32);
+        temp[i * 4] = a + d;
+        temp[i * 4 + 1] = b + c;
+        temp[i * 4 + 2] = b - c;
+        temp[i * 4 + 3] = a - d;
+    }
+    for i in 0..4 {
+        let dc = temp[i] + 4;
+        let a = dc + temp[8 + i];
+        let b = dc - temp[8 + i];
+        let c = multiply2(temp[4 + i]) - multiply1(temp[12 + i]);
+        let d = multiply1(temp[4 + i]) + multiply2(temp[12 + i]);
+        let row = offset + i * stride;
+        for (x, value) in [a + d, b + c, b - c, a - d].iter().enumerate() {
+            ws[row + x] = clip_pixel(ws[row + x] as i32 + (value >> 3));
+        }
+    }
+}
+
+/// Fills the top row and left column of a prediction workspace from the
+/// reconstructed neighbours of a macroblock, or with the values of
+/// section 12.2 outside of the frame.
+fn fill_edges(
+    plane: &[u8],
+    stride: usize,
+    mbx: usize,
+    mby: usize,
+    size: usize,
+    ws_stride: usize,
+    ws: &mut [u8],
+) {
+    let x = mbx * size;
+    let y = mby * size;
+    if mby == 0 {
+        for value in &mut ws[..size + 1] {
+            *value = 127;
+        }
+    } else {
+        let above = (y - 1) * stride + x;
+        ws[1..size + 1].copy_from_slice(&plane[above..above + size]);
+        ws[0] = if mbx == 0 { 129 } else { plane[above - 1] };
+    }
+    for row in 0..size {
+        ws[(row + 1) * ws_stride] = if mbx == 0 {
+            129
+        } else {
+            plane[(y + row) * stride + x - 1]
+        };
+    }
+}
+
+/// Predicts a whole 16x16 luma or 8x8 chroma block.
+fn predict_block(ws: &mut [u8], stride: usize, size: usize, mode: u8, mbx: usize, mby: usize) {
+    match mode {
+        DC_PRED => {
+            let top: u32 = (1..size + 1).map(|x| ws[x] as u32).sum();
+            let left: u32 = (1..size + 1).map(|y| ws[y * stride] as u32).sum();
+            let shift = if size == 16 { 5 } else { 4 };
+            let dc = match (mbx > 0, mby > 0) {
+                (true, true) => (top + left + (1 << (shift - 1))) >> shift,
+                (false, true) => (top + (1 << (shift - 2))) >> (shift - 1),
+                (true, false) => (left + (1 << (shift - 2))) >> (shift - 1),
+                (false, false) => 128,
+            };
+            fill_block(ws, stride, size, |_, _| dc as u8);
+        },
+        V_PRED => {
+            let top = ws[1..size + 1].to_vec();
+            fill_block(ws, stride, size, |x, _| top[x]);
+        },
+        H_PRED => {
+            let left: Vec<u8> = (1..size + 1).map(|y| ws[y * stride]).collect();
+            fill_block(ws, stride, size, |_, y| left[y]);
+        },
+        _ => {
+            let top = ws[1..size + 1].to_vec();
+            let left: Vec<u8> = (1..size + 1).map(|y| ws[y * stride]).collect();
+            let top_left = ws[0] as i32;
+            fill_block(ws, stride, size, |x, y| {
+                clip_pixel(top[x] as i32 + left[y] as i32 - top_left)
+            });
+        },
+    }
+}
+
+fn fill_block(ws: &mut [u8], stride: usize, size: usize, value: impl Fn(usize, usize) -> u8) {
+    for y in 0..size {
+        for x in 0..size {
+            ws[(y + 1) * stride + x + 1] = value(x, y);
+        }
+    }
+}
+
+/// Predicts the 4x4 luma subblock at `offset`, from the pixels above, to
+/// the above right and to the left of it.
+fn predict_subblock(ws: &mut [u8], offset: usize, stride: usize, mode: u8) {
+    let above = offset - stride;
+    let a: Vec<i32> = ws[above..above + 8].iter().map(|&p| p as i32).collect();
+    let l: Vec<i32> = (0..4).map(|y| ws[offset + y * stride - 1] as i32).collect();
+    let x = ws[above - 1] as i32;
+    let avg2 = |a: i32, b: i32| ((a + b + 1) >> 1) as u8;
+    let avg3 = |a: i32, b: i32, c: i32| ((a + 2 * b + c + 2) >> 2) as u8;
+
+    // The predicted pixels, as B[row][column] in section 12.3.
+    let mut b = [[0u8; 4]; 4];
+    match mode {
+        B_DC_PRED => {
+            let dc = (a[..4].iter().sum::<i32>() + l.iter().sum::<i32>() + 4) >> 3;
+            b = [[dc as u8; 4]; 4];
+        },
+        B_TM_PRED => {
+            for r in 0..4 {
+                for c in 0..4 {
+                    b[r][c] = clip_pixel(l[r] + a[c] - x);
+                }
+            }
+        },
+        B_VE_PRED => {
+            let row = [
+                avg3(x, a[0], a[1]),
+                avg3(a[0], a[1], a[2]),
+                avg3(a[1], a[2], a[3]),
+                avg3(a[2], a[3], a[4]),
+            ];
+            b = [row; 4];
+        },
+        B_HE_PRED => {
+            let column = [
+                avg3(x, l[0], l[1]),
+                avg3(l[0], l[1], l[2]),
+                avg3(l[1], l[2], l[3]),
+                avg3(l[2], l[3], l[3]),
+            ];
+            for r in 0..4 {
+                b[r] = [column[r]; 4];
+            }
+        },
+        B_LD_PRED => {
+            for r in 0..4 {
+                for c in 0..4 {
+                    let i = r + c;
+                    b[r][c] = if i < 6 {
+                        avg3(a[i], a[i + 1], a[i + 2])
+                    } else {
+                        avg3(a[6], a[7], a[7])
+                    };
+                }
+            }
+        },
+        B_RD_PRED => {
+            // The edge from the bottom left to the top right.
+            let e = [l[3], l[2], l[1], l[0], x, a[0], a[1], a[2], a[3]];
+            for r in 0..4 {
+                for c in 0..4 {
+                    let i = 3 - r + c;
+                    b[r][c] = avg3(e[i], e[i + 1], e[i + 2]);
+                }
+            }
+        },
+        B_VR_PRED => {
+            let e = [l[3], l[2], l[1], l[0], x, a[0], a[1], a[2], a[3]];
+            b[3][0] = avg3(e[1], e[2], e[3]);
+            b[2][0] = avg3(e[2], e[3], e[4]);
+            b[3][1] = avg3(e[3], e[4], e[5]);
+            b[1][0] = b[3][1];
+            b[2][1] = avg2(e[4], e[5]);
+            b[0][0] = b[2][1];
+            b[3][2] = avg3(e[4], e[5], e[6]);
+            b[1][1] = b[3][2];
+            b[2][2] = avg2(e[5], e[6]);
+            b[0][1] = b[2][2];
+            b[3][3] = avg3(e[5], e[6], e[7]);
+            b[1][2] = b[3][3];
+            b[2][3] = avg2(e[6], e[7]);
+            b[0][2] = b[2][3];
+            b[1][3] = avg3(e[6], e[7], e[8]);
+            b[0][3] = avg2(e[7], e[8]);
+        },
+        B_VL_PRED => {
+            b[0][0] = avg2(a[0], a[1]);
+            b[1][0] = avg3(a[0], a[1], a[2]);
+            b[2][0] = avg2(a[1], a[2]);
+            b[0][1] = b[2][0];
+            b[1][1] = avg3(a[1], a[2], a[3]);
+            b[3][0] = b[1][1];
+            b[2][1] = avg2(a[2], a[3]);
+            b[0][2] = b[2][1];
+            b[3][1] = avg3(a[2], a[3], a[4]);
+            b[1][2] = b[3][1];
+            b[2][2] = avg2(a[3], a[4]);
+            b[0][3] = b[2][2];
+            b[3][2] = avg3(a[3], a[4], a[5]);
+            b[1][3] = b[3][2];
+            // The last two values don't follow the pattern.
+            b[2][3] = avg3(a[4], a[5], a[6]);
+            b[3][3] = avg3(a[5], a[6], a[7]);
+        },
+        B_HD_PRED => {
+            let e = [l[3], l[2], l[1], l[0], x, a[0], a[1], a[2], a[3]];
+            b[3][0] = avg2(e[0], e[1]);
+            b[3][1] = avg3(e[0], e[1], e[2]);
+            b[2][0] = avg2(e[1], e[2]);
+            b[3][2] = b[2][0];
+            b[2][1] = avg3(e[1], e[2], e[3]);
+            b[3][3] = b[2][1];
+            b[2][2] = avg2(e[2], e[3]);
+            b[1][0] = b[2][2];
+            b[2][3] = avg3(e[2], e[3], e[4]);
+            b[1][1] = b[2][3];
+            b[1][2] = avg2(e[3], e[4]);
+            b[0][0] = b[1][2];
+            b[1][3] = avg3(e[3], e[4], e[5]);
+            b[0][1] = b[1][3];
+            b[0][2] = avg3(e[4], e[5], e[6]);
+            b[0][3] = avg3(e[5], e[6], e[7]);
+        },
+        _ => {
+            b[0][0] = avg2(l[0], l[1]);
+            b[0][1] = avg3(l[0], l[1], l[2]);
+            b[0][2] = avg2(l[1], l[2]);
+            b[1][0] = b[0][2];
+            b[0][3] = avg3(l[1], l[2], l[3]);
+            b[1][1] = b[0][3];
+            b[1][2] = avg2(l[2], l[3]);
+            b[2][0] = b[1][2];
+            b[1][3] = avg3(l[2], l[3], l[3]);
+            b[2][1] = b[1][3];
+            b[2][2] = l[3] as u8;
+            b[2][3] = b[2][2];
+            b[3] = [b[2][2]; 4];
+        },
+    }
+    for r in 0..4 {
+        ws[offset + r * stride..offset + r * stride + 4].copy_from_slice(&b[r]);
+    }
+}
+
+/// The filter value of section 15.2, clamped to a signed byte.
+fn clamp_signed(value: i32) -> i32 {
+    clamp(value, -128, 127)
+}
+
+/// Reads the four pixels on each side of an edge, `step` apart.
+fn edge_pixels(plane: &[u8], position: usize, step: usize) -> [i32; 8] {
+    let mut pixels = [0; 8];
+    for (index, pixel) in pixels.iter_mut().enumerate() {
+        *pixel = plane[position + index * step - 4 * step] as i32;
+    }
+    pixels
+}
+
+/// Adjusts the two pixels closest to an edge, returning the adjustment.
+fn common_adjust(plane: &mut [u8], position: usize, step: usize, outer_taps: bool) -> i32 {
+    let p = edge_pixels(plane, position, step);
+    let (p1, p0, q0, q1) = (p[2], p[3], p[4], p[5]);
+    let mut a = 3 * (q0 - p0);
+    if outer_taps {
+        a += clamp_signed(p1 - q1);
+    }
+    let a = clamp_signed(a);
+    let f1 = clamp_signed(a + 4) >> 3;
+    let f2 = clamp_signed(a + 3) >> 3;
+    plane[position] = clip_pixel(q0 - f1);
+    plane[position - step] = clip_pixel(p0 + f2);
+    f1
+}
+
+/// Filters the 16 edge pixels from `position`, `along` apart, with the
+/// pixels across the edge `step` apart.
+fn simple_filter(plane: &mut [u8], position: usize, step: usize, along: usize, limit: i32) {
+    for i in 0..16 {
+        let position = position + i * along;
+        let p = edge_pixels(plane, position, step);
+        if (p[3] - p[4]).abs() * 2 + (p[2] - p[5]).abs() / 2 <= limit {
+            common_adjust(plane, position, step, true);
+        }
+    }
+}
+
+/// Filters the edges of the `size` by `size` block of a macroblock at
+/// `start`, given whether its left, top and inner edges are filtered.
+fn normal_filter(
+    plane: &mut [u8],
+    start: usize,
+    stride: usize,
+    size: usize,
+    (left, top, inner): (bool, bool, bool),
+    parameters: &FilterParameters,
+) {
+    if left {
+        macroblock_filter(plane, start, 1, stride, size, parameters);
+    }
+    if inner {
+        for x in (4..size).step_by(4) {
+            subblock_filter(plane, start + x, 1, stride, size, parameters);
+        }
+    }
+    if top {
+        macroblock_filter(plane, start, stride, 1, size, parameters);
+    }
+    if inner {
+        for y in (4..size).step_by(4) {
+            subblock_filter(plane, start + y * stride, stride, 1, size, parameters);
+        }
+    }
+}
+
+fn normal_filter_applies(p: &[i32; 8], parameters: &FilterParameters, limit: i32) -> bool {
+    let interior = parameters.interior_limit;
+    (p[3] - p[4]).abs() * 2 + (p[2] - p[5]).abs() / 2 <= limit &&
+        (p[0] - p[1]).abs() <= interior &&
+        (p[1] - p[2]).abs() <= interior &&
+        (p[2] - p[3]).abs() <= interior &&
+        (p[7] - p[6]).abs() <= interior &&
+        (p[6] - p[5]).abs() <= interior &&
+        (p[5] - p[4]).abs() <= interior
+}
+
+fn high_edge_variance(p: &[i32; 8], threshold: i32) -> bool {
+    (p[2] - p[3]).abs() > threshold || (p[5] - p[4]).abs() > threshold
+}
+
+fn subblock_filter(
+    plane: &mut [u8],
+    position: usize,
+    step: usize,
+    along: usize,
+    size: usize,
+    parameters: &FilterParameters,
+) {
+    for i in 0..size {
+        let position = position + i * along;
+        let p = edge_pixels(plane, position, step);
+        if !normal_filter_applies(&p, parameters, parameters.limit) {
+            continue;
+        }
+        let hev = high_edge_variance(&p, parameters.hev_threshold);
+        let a = (common_adjust(plane, position, step, hev) + 1) >> 1;
+        if !hev {
+            plane[position + step] = clip_pixel(p[5] - a);
+            plane[position - 2 * step] = clip_pixel(p[2] + a);
+        }
+    }
+}
+
+fn macroblock_filter(
+    plane: &mut [u8],
+    position: usize,
+    step: usize,
+    along: usize,
+    size: usize,
+    parameters: &FilterParameters,
+) {
+    let limit = parameters.limit + 4;
+    for i in 0..size {
+        let position = position + i * along;
+        let p = edge_pixels(plane, position, step);
+        if !normal_filter_applies(&p, parameters, limit) {
+            continue;
+        }
+        if high_edge_variance(&p, parameters.hev_threshold) {
+            common_adjust(plane, position, step, true);
+            continue;
+        }
+        let w = clamp_signed(clamp_signed(p[2] - p[5]) + 3 * (p[4] - p[3]));
+        for (tap, weight) in [27, 18, 9].iter().enumerate() {
+            let a = clamp_signed((weight * w + 63) >> 7);
+            plane[position + tap * step] = clip_pixel(p[4 + tap] - a);
+            plane[position - (tap + 1) * step] = clip_pixel(p[3 - tap] + a);
+        }
+    }
+}
+
+/// Converts a row of pixels to RGBA, interpolating each chroma sample from
+/// the nearest samples of `current_uv` and the other row `far_uv`.
+fn upsample_row(y: &[u8], far_uv: (&[u8], &[u8]), current_uv: (&[u8], &[u8]), out: &mut [u8]) {
+    let width = y.len();
+    let sample = |(u, v): (&[u8], &[u8]), x: usize| (u[x] as i32, v[x] as i32);
+    let mut put = |x: usize, (u, v): (i32, i32)| {
+        let pixel = yuv_to_rgba(y[x] as i32, u, v);
+        out[x * 4..x * 4 + 4].copy_from_slice(&pixel);
+    };
+    let blend = |near: (i32, i32), far: (i32, i32)| {
+        ((3 * near.0 + far.0 + 2) >> 2, (3 * near.1 + far.1 + 2) >> 2)
+    };
+
+    let mut far_left = sample(far_uv, 0);
+    let mut left = sample(current_uv, 0);
+    put(0, blend(left, far_left));
+    for x in 1..(width + 1) / 2 {
+        let far = sample(far_uv, x);
+        let near = sample(current_uv, x);
+        // The samples weighted 9, 3, 3 and 1, like the fancy upsampler of
+        // libwebp.
+        let diagonal = |a: i32, b: i32, c: i32, d: i32| {
+            let average = a + b + c + d + 8;
+            ((average + 2 * (a + d)) >> 3, (average + 2 * (b + c)) >> 3)
+        };
+        let (u_near_left, u_near) = diagonal(far_left.0, far.0, left.0, near.0);
+        let (v_near_left, v_near) = diagonal(far_left.1, far.1, left.1, near.1);
+        put(
+            2 * x - 1,
+            ((u_near_left + left.0) >> 1, (v_near_left + left.1) >> 1),
+        );
+        put(2 * x, ((u_near + near.0) >> 1, (v_near + near.1) >> 1));
+        far_left = far;
+        left = near;
+    }
+    if width % 2 == 0 {
+        put(width - 1, blend(left, far_left));
+    }
+}
+
+/// Converts a pixel to RGB with the fixed point arithmetic of libwebp.
+fn yuv_to_rgba(y: i32, u: i32, v: i32) -> [u8; 4] {
+    let multiply = |value: i32, coefficient: i32| (value * coefficient) >> 8;
+    let clip = |value: i32| clamp(value >> 6, 0, 255) as u8;
+    let y = multiply(y, 19077);
+    [
+        clip(y + multiply(v, 26149) - 14234),
+        clip(y - multiply(u, 6419) - multiply(v, 13320) + 8708),
+        clip(y + multiply(u, 33050) - 17685),
+        255,
+    ]
+}
@@ -0,0 +1,123 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Decoding of still WebP images, and of the frames of animated ones.
+//!
+//! <https://developers.google.com/speed/webp/docs/riff_container>
+
+mod lossless;
+mod lossy;
+
+use piston_image::RgbaImage;
+
+pub const ANIMATION_FLAG: u8 = 0x02;
+
+/// Decodes a WebP image, or returns `None` if it is invalid or animated.
+pub fn decode(buffer: &[u8]) -> Option<RgbaImage> {
+    if buffer.get(..4)? != b"RIFF" || buffer.get(8..12)? != b"WEBP" {
+        return None;
+    }
+    let chunks = chunks(&buffer[12..])?;
+    if let Some(&(_, header)) = chunks.iter().find(|&&(name, _)| name == b"VP8X") {
+        if header.first()? & ANIMATION_FLAG != 0 {
+            return None;
+        }
+    }
+    decode_image(&chunks)
+}
+
+/// Decodes the image data of a frame of an animated WebP image.
+pub fn decode_frame(data: &[u8]) -> Option<RgbaImage> {
+    decode_image(&chunks(data)?)
+}
+
+/// Splits the given RIFF data into its chunks.
+pub fn chunks(mut data: &[u8]) -> Option<Vec<(&[u8], &[u8])>> {
+    let mut chunks = vec![];
+    while data.len() >= 8 {
+        let length = u32::from_le_bytes([data[4], data[5], data[6], data[7]]) as usize;
+        chunks.push((&data[..4], data.get(8..8 + length)?));
+        // Chunks are padded to an even length.
+        data = data.get((8 + length + 1) & !1..).unwrap_or(&[]);
+    }
+    Some(chunks)
+}
+
+/// Decodes the lossy or lossless bitstream among the given chunks, along with
+/// the alpha channel of a lossy one.
+fn decode_image(chunks: &[(&[u8], &[u8])]) -> Option<RgbaImage> {
+    for &(name, data) in chunks {
+        match name {
+            b"VP8 " => {
+                let image = lossy::decode(data)?;
+                let mut pixels = image.pixels;
+                if let Some(&(_, alpha)) = chunks.iter().find(|&&(name, _)| name == b"ALPH") {
+                    let alpha = decode_alpha(alpha, image.width, image.height)?;
+                    for (pixel, alpha) in pixels.chunks_mut(4).zip(alpha) {
+                        pixel[3] = alpha;
+                    }
+                }
+                return RgbaImage::from_raw(image.width, image.height, pixels);
+            },
+            b"VP8L" => {
+                let image = lossless::decode(data)?;
+                let mut pixels = Vec::with_capacity(image.pixels.len() * 4);
+                for argb in image.pixels {
+                    let [a, r, g, b] = argb.to_be_bytes();
+                    pixels.extend_from_slice(&[r, g, b, a]);
+                }
+                return RgbaImage::from_raw(image.width, image.height, pixels);
+            },
+            _ => {},
+        }
+    }
+    None
+}
+
+/// Decodes the data of an ALPH chunk.
+///
+/// <https://developers.google.com/speed/webp/docs/riff_container#alpha>
+fn decode_alpha(data: &[u8], width: u32, height: u32) -> Option<Vec<u8>> {
+    let header = *data.first()?;
+    let size = width as usize * height as usize;
+    let mut alpha = match header & 0x03 {
+        0 => data.get(1..1 + size)?.to_vec(),
+        // The alpha values are the green channel of a lossless image.
+        1 => lossless::decode_headerless(&data[1..], width, height)?
+            .into_iter()
+            .map(|argb| (argb >> 8) as u8)
+            .collect(),
+        _ => return None,
+    };
+    let filter = (header >> 2) & 0x03;
+    if filter != 0 {
+        unfilter_alpha(&mut alpha, width as usize, filter);
+    }
+    Some(alpha)
+}
+
+/// Reverts the horizontal, vertical or gradient filter of an alpha channel.
+fn unfilter_alpha(alpha: &mut [u8], width: usize, filter: u8) {
+    for index in 1..alpha.len() {
+        let (x, y) = (index % width, index / width);
+        let predictor = if y == 0 {
+            alpha[index - 1]
+        } else if x == 0 {
+            alpha[index - width]
+        } else {
+            let left = alpha[index - 1];
+            let top = alpha[index - width];
+            match filter {
+                1 => left,
+                2 => top,
+                _ => {
+                    let top_left = alpha[index - width - 1] as i32;
+                    let gradient = left as i32 + top as i32 - top_left;
+                    gradient.max(0).min(255) as u8
+                },
+            }
+        };
+        alpha[index] = alpha[index].wrapping_add(predictor);
+    }
+}
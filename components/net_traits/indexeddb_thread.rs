@@ -0,0 +1,382 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use ipc_channel::ipc::IpcSender;
+use servo_url::ImmutableOrigin;
+use std::cmp::Ordering;
+
+/// Identifies a transaction within the indexeddb thread.
+pub type IndexedDBTxnId = u64;
+
+/// Identifies an open connection to a database.
+pub type IndexedDBConnectionId = u64;
+
+/// <https://w3c.github.io/IndexedDB/#transaction-mode>
+#[derive(Clone, Copy, Debug, Deserialize, Eq, MallocSizeOf, PartialEq, Serialize)]
+pub enum IndexedDBTxnMode {
+    Readonly,
+    Readwrite,
+    Versionchange,
+}
+
+/// <https://w3c.github.io/IndexedDB/#key-type>
+#[derive(Clone, Debug, Deserialize, MallocSizeOf, Serialize)]
+pub enum IndexedDBKeyType {
+    Number(f64),
+    Date(f64),
+    String(String),
+    Binary(Vec<u8>),
+    Array(Vec<IndexedDBKeyType>),
+}
+
+impl IndexedDBKeyType {
+    fn type_order(&self) -> u8 {
+        match *self {
+            IndexedDBKeyType::Number(_) => 0,
+            IndexedDBKeyType::Date(_) => 1,
+            IndexedDBKeyType::String(_) => 2,
+            IndexedDBKeyType::Binary(_) => 3,
+            IndexedDBKeyType::Array(_) => 4,
+        }
+    }
+}
+
+// https://w3c.github.io/IndexedDB/#compare-two-keys
+impl Ord for IndexedDBKeyType {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (IndexedDBKeyType::Number(a), IndexedDBKeyType::Number(b)) |
+            (IndexedDBKeyType::Date(a), IndexedDBKeyType::Date(b)) => {
+                // Keys are never NaN, see https://w3c.github.io/IndexedDB/#convert-value-to-key
+                a.partial_cmp(b).unwrap_or(Ordering::Equal)
+            },
+            (IndexedDBKeyType::String(a), IndexedDBKeyType::String(b)) => {
+                // Strings are compared by code unit.
+                a.encode_utf16().cmp(b.encode_utf16())
+            },
+            (IndexedDBKeyType::Binary(a), IndexedDBKeyType::Binary(b)) => a.cmp(b),
+            (IndexedDBKeyType::Array(a), IndexedDBKeyType::Array(b)) => a.cmp(b),
+            _ => self.type_order().cmp(&other.type_order()),
+        }
+    }
+}
+
+impl PartialOrd for IndexedDBKeyType {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for IndexedDBKeyType {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for IndexedDBKeyType {}
+
+/// <https://w3c.github.io/IndexedDB/#range-construct>
+#[derive(Clone, Debug, Default, Deserialize, MallocSizeOf, Serialize)]
+pub struct IndexedDBKeyRange {
+    pub lower: Option<IndexedDBKeyType>,
+    pub upper: Option<IndexedDBKeyType>,
+    pub lower_open: bool,
+    pub upper_open: bool,
+}
+
+impl IndexedDBKeyRange {
+    /// A range matching every key.
+    pub fn unbounded() -> IndexedDBKeyRange {
+        IndexedDBKeyRange::default()
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#only>
+    pub fn only(key: IndexedDBKeyType) -> IndexedDBKeyRange {
+        IndexedDBKeyRange {
+            lower: Some(key.clone()),
+            upper: Some(key),
+            lower_open: false,
+            upper_open: false,
+        }
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#in>
+    pub fn contains(&self, key: &IndexedDBKeyType) -> bool {
+        let in_lower_bound = match self.lower {
+            Some(ref lower) if self.lower_open => key > lower,
+            Some(ref lower) => key >= lower,
+            None => true,
+        };
+        let in_upper_bound = match self.upper {
+            Some(ref upper) if self.upper_open => key < upper,
+            Some(ref upper) => key <= upper,
+            None => true,
+        };
+        in_lower_bound && in_upper_bound
+    }
+}
+
+/// <https://w3c.github.io/IndexedDB/#key-path-construct>
+#[derive(Clone, Debug, Deserialize, MallocSizeOf, PartialEq, Serialize)]
+pub enum IndexedDBKeyPath {
+    String(String),
+    Sequence(Vec<String>),
+}
+
+/// <https://w3c.github.io/IndexedDB/#enumdef-idbcursordirection>
+#[derive(Clone, Copy, Debug, Deserialize, MallocSizeOf, PartialEq, Serialize)]
+pub enum IndexedDBCursorDirection {
+    Next,
+    NextUnique,
+    Prev,
+    PrevUnique,
+}
+
+/// The metadata of an index, as seen by script.
+#[derive(Clone, Debug, Deserialize, MallocSizeOf, Serialize)]
+pub struct IndexedDBIndexInfo {
+    pub name: String,
+    pub key_path: IndexedDBKeyPath,
+    pub unique: bool,
+    pub multi_entry: bool,
+}
+
+/// The metadata of an object store, as seen by script.
+#[derive(Clone, Debug, Deserialize, MallocSizeOf, Serialize)]
+pub struct IndexedDBObjectStoreInfo {
+    pub name: String,
+    pub key_path: Option<IndexedDBKeyPath>,
+    pub auto_increment: bool,
+    pub indexes: Vec<IndexedDBIndexInfo>,
+}
+
+/// A record as returned by a read operation. The value is a serialized structured clone.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct IndexedDBRecord {
+    /// The key of the record in its source, which is the index key when reading from an index.
+    pub key: IndexedDBKeyType,
+    /// The key of the record in the object store.
+    pub primary_key: IndexedDBKeyType,
+    pub value: Vec<u8>,
+}
+
+/// Errors reported by the indexeddb thread, mapped to DOMExceptions by script.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub enum IndexedDBError {
+    Abort,
+    Constraint,
+    Data,
+    NotFound,
+    Version,
+    Unknown,
+}
+
+/// An operation performed against an object store (or one of its indexes)
+/// as part of a transaction.
+#[derive(Debug, Deserialize, Serialize)]
+pub enum AsyncOperation {
+    /// Get the first record in the range.
+    GetItem {
+        index: Option<String>,
+        range: IndexedDBKeyRange,
+    },
+    /// Get the primary key of the first record in the range.
+    GetKey {
+        index: Option<String>,
+        range: IndexedDBKeyRange,
+    },
+    /// Get up to `count` records in the range.
+    GetAll {
+        index: Option<String>,
+        range: IndexedDBKeyRange,
+        count: Option<u32>,
+    },
+    /// Get up to `count` primary keys in the range.
+    GetAllKeys {
+        index: Option<String>,
+        range: IndexedDBKeyRange,
+        count: Option<u32>,
+    },
+    /// Count the records in the range.
+    Count {
+        index: Option<String>,
+        range: IndexedDBKeyRange,
+    },
+    /// Store a record. `key` is `None` when a key has to be generated.
+    /// `index_keys` holds the keys already extracted from the value for each index.
+    PutItem {
+        key: Option<IndexedDBKeyType>,
+        value: Vec<u8>,
+        index_keys: Vec<(String, Vec<IndexedDBKeyType>)>,
+        overwrite: bool,
+    },
+    /// Remove all records in the range.
+    RemoveItem(IndexedDBKeyRange),
+    /// Remove all records.
+    Clear,
+    /// Find the next record a cursor should move to, skipping `count - 1`
+    /// matching records.
+    Iterate {
+        index: Option<String>,
+        range: IndexedDBKeyRange,
+        direction: IndexedDBCursorDirection,
+        position: Option<(IndexedDBKeyType, IndexedDBKeyType)>,
+        target: Option<(IndexedDBKeyType, Option<IndexedDBKeyType>)>,
+        count: u32,
+    },
+}
+
+/// The successful result of an `AsyncOperation`.
+#[derive(Debug, Deserialize, Serialize)]
+pub enum IndexedDBResult {
+    Key(Option<IndexedDBKeyType>),
+    Keys(Vec<IndexedDBKeyType>),
+    Record(Option<IndexedDBRecord>),
+    Records(Vec<IndexedDBRecord>),
+    Count(u64),
+    Done,
+}
+
+/// Events delivered to an open connection.
+#[derive(Debug, Deserialize, Serialize)]
+pub enum IndexedDBConnectionEvent {
+    /// Another connection wants to upgrade or delete the database.
+    VersionChange {
+        old_version: u64,
+        new_version: Option<u64>,
+    },
+}
+
+/// The replies to an open or delete request. `Blocked` may precede the final reply.
+#[derive(Debug, Deserialize, Serialize)]
+pub enum IndexedDBOpenReply {
+    /// Other connections are still open, the request is waiting for them to close.
+    Blocked {
+        old_version: u64,
+        new_version: Option<u64>,
+    },
+    /// The connection has been opened at the requested version.
+    Opened {
+        connection: IndexedDBConnectionId,
+        version: u64,
+        object_stores: Vec<IndexedDBObjectStoreInfo>,
+    },
+    /// The connection has been opened and a versionchange transaction started.
+    Upgrade {
+        connection: IndexedDBConnectionId,
+        transaction: IndexedDBTxnId,
+        old_version: u64,
+        new_version: u64,
+        object_stores: Vec<IndexedDBObjectStoreInfo>,
+    },
+    /// The database has been deleted.
+    Deleted {
+        old_version: u64,
+    },
+    Error(IndexedDBError),
+}
+
+/// Request operations on the databases of a particular origin.
+#[derive(Debug, Deserialize, Serialize)]
+pub enum IndexedDBThreadMsg {
+    /// <https://w3c.github.io/IndexedDB/#open-a-database>
+    OpenDatabase {
+        sender: IpcSender<IndexedDBOpenReply>,
+        events: IpcSender<IndexedDBConnectionEvent>,
+        origin: ImmutableOrigin,
+        name: String,
+        version: Option<u64>,
+    },
+
+    /// <https://w3c.github.io/IndexedDB/#delete-a-database>
+    DeleteDatabase(IpcSender<IndexedDBOpenReply>, ImmutableOrigin, String),
+
+    /// <https://w3c.github.io/IndexedDB/#close-a-database-connection>
+    CloseConnection(ImmutableOrigin, String, IndexedDBConnectionId),
+
+    /// Start a transaction over the given object stores, replying with its id.
+    StartTransaction(
+        IpcSender<IndexedDBTxnId>,
+        ImmutableOrigin,
+        String,
+        IndexedDBTxnMode,
+        Vec<String>,
+    ),
+
+    /// Create an object store inside a versionchange transaction.
+    CreateObjectStore {
+        sender: IpcSender<Result<(), IndexedDBError>>,
+        origin: ImmutableOrigin,
+        name: String,
+        txn: IndexedDBTxnId,
+        store_name: String,
+        key_path: Option<IndexedDBKeyPath>,
+        auto_increment: bool,
+    },
+
+    /// Delete an object store inside a versionchange transaction.
+    DeleteObjectStore(
+        IpcSender<Result<(), IndexedDBError>>,
+        ImmutableOrigin,
+        String,
+        IndexedDBTxnId,
+        String,
+    ),
+
+    /// Get every record of an object store as seen by a transaction,
+    /// used to populate a new index.
+    GetAllRecords(
+        IpcSender<Vec<IndexedDBRecord>>,
+        ImmutableOrigin,
+        String,
+        IndexedDBTxnId,
+        String,
+    ),
+
+    /// Create an index inside a versionchange transaction, along with the
+    /// index keys of every existing record.
+    CreateIndex {
+        sender: IpcSender<Result<(), IndexedDBError>>,
+        origin: ImmutableOrigin,
+        name: String,
+        txn: IndexedDBTxnId,
+        store_name: String,
+        info: IndexedDBIndexInfo,
+        keys: Vec<(IndexedDBKeyType, Vec<IndexedDBKeyType>)>,
+    },
+
+    /// Delete an index inside a versionchange transaction.
+    DeleteIndex(
+        IpcSender<Result<(), IndexedDBError>>,
+        ImmutableOrigin,
+        String,
+        IndexedDBTxnId,
+        String,
+        String,
+    ),
+
+    /// Perform an operation on an object store as part of a transaction.
+    Async(
+        IpcSender<Result<IndexedDBResult, IndexedDBError>>,
+        ImmutableOrigin,
+        String,
+        String,
+        IndexedDBTxnId,
+        AsyncOperation,
+    ),
+
+    /// <https://w3c.github.io/IndexedDB/#commit-a-transaction>
+    Commit(
+        IpcSender<Result<(), IndexedDBError>>,
+        ImmutableOrigin,
+        String,
+        IndexedDBTxnId,
+    ),
+
+    /// <https://w3c.github.io/IndexedDB/#abort-a-transaction>
+    Abort(IpcSender<()>, ImmutableOrigin, String, IndexedDBTxnId),
+
+    /// Send a reply when done cleaning up thread resources and then shut it down.
+    Exit(IpcSender<()>),
+}
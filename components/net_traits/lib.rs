@@ -16,6 +16,7 @@ extern crate malloc_size_of_derive;
 extern crate serde;
 
 use crate::filemanager_thread::FileManagerThreadMsg;
+use crate::indexeddb_thread::IndexedDBThreadMsg;
 use crate::request::{Request, RequestBuilder};
 use crate::response::{HttpsState, Response, ResponseInit};
 use crate::storage_thread::StorageThreadMsg;
@@ -37,6 +38,7 @@ use time::precise_time_ns;
 pub mod blob_url_store;
 pub mod filemanager_thread;
 pub mod image_cache;
+pub mod indexeddb_thread;
 pub mod pub_domains;
 pub mod quality;
 pub mod request;
@@ -49,7 +51,9 @@ pub mod storage_thread;
 /// However, image handling is generally very integrated with the network stack (especially where
 /// caching is involved) and as a result it must live in here.
 pub mod image {
+    mod animation;
     pub mod base;
+    mod webp;
 }
 
 /// A loading context, for context-specific sniffing, as defined in
@@ -297,13 +301,19 @@ where
 pub struct ResourceThreads {
     core_thread: CoreResourceThread,
     storage_thread: IpcSender<StorageThreadMsg>,
+    idb_thread: IpcSender<IndexedDBThreadMsg>,
 }
 
 impl ResourceThreads {
-    pub fn new(c: CoreResourceThread, s: IpcSender<StorageThreadMsg>) -> ResourceThreads {
+    pub fn new(
+        c: CoreResourceThread,
+        s: IpcSender<StorageThreadMsg>,
+        i: IpcSender<IndexedDBThreadMsg>,
+    ) -> ResourceThreads {
         ResourceThreads {
             core_thread: c,
             storage_thread: s,
+            idb_thread: i,
         }
     }
 }
@@ -328,6 +338,16 @@ impl IpcSend<StorageThreadMsg> for ResourceThreads {
     }
 }
 
+impl IpcSend<IndexedDBThreadMsg> for ResourceThreads {
+    fn send(&self, msg: IndexedDBThreadMsg) -> IpcSendResult {
+        self.idb_thread.send(msg)
+    }
+
+    fn sender(&self) -> IpcSender<IndexedDBThreadMsg> {
+        self.idb_thread.clone()
+    }
+}
+
 // Ignore the sub-fields
 malloc_size_of_is_0!(ResourceThreads);
 
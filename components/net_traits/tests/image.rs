@@ -2,7 +2,45 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
-use net_traits::image::base::detect_image_format;
+use net_traits::image::base::{detect_image_format, load_from_memory, Image};
+use std::fs::File;
+use std::io::Read;
+use std::path::PathBuf;
+
+fn load_webp(filename: &str) -> Image {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "support", filename]
+        .iter()
+        .collect();
+    let mut bytes = vec![];
+    File::open(path)
+        .expect("Couldn't open image file")
+        .read_to_end(&mut bytes)
+        .unwrap();
+    load_from_memory(&bytes).expect("Couldn't decode image")
+}
+
+/// Returns the pixel at the given position, in RGBA order.
+fn pixel(image: &Image, x: usize, y: usize) -> [u8; 4] {
+    let offset = (y * image.width as usize + x) * 4;
+    let bgra = &image.bytes[offset..offset + 4];
+    [bgra[2], bgra[1], bgra[0], bgra[3]]
+}
+
+/// Lossy images are only compared against the output of libwebp with some
+/// tolerance, since the conversion from YUV is allowed to differ slightly.
+fn assert_pixel_near(image: &Image, x: usize, y: usize, expected: [u8; 4]) {
+    let actual = pixel(image, x, y);
+    for (a, e) in actual.iter().zip(&expected) {
+        assert!(
+            (*a as i32 - *e as i32).abs() <= 4,
+            "pixel ({}, {}) is {:?}, expected {:?}",
+            x,
+            y,
+            actual,
+            expected
+        );
+    }
+}
 
 #[test]
 fn test_supported_images() {
@@ -12,6 +50,7 @@ fn test_supported_images() {
     let png = [0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A];
     let bmp = [0x42, 0x4D];
     let ico = [0x00, 0x00, 0x01, 0x00];
+    let webp = *b"RIFF\x1a\x00\x00\x00WEBPVP8 ";
     let junk_format = [0x01, 0x02, 0x03, 0x04, 0x05];
 
     assert!(detect_image_format(&gif1).is_ok());
@@ -20,5 +59,55 @@ fn test_supported_images() {
     assert!(detect_image_format(&png).is_ok());
     assert!(detect_image_format(&bmp).is_ok());
     assert!(detect_image_format(&ico).is_ok());
+    assert!(detect_image_format(&webp).is_ok());
     assert!(detect_image_format(&junk_format).is_err());
 }
+
+#[test]
+fn test_webp_lossless_image() {
+    let image = load_webp("lossless.webp");
+    assert_eq!((image.width, image.height), (4, 2));
+
+    let red = [255, 0, 0, 255];
+    let green = [0, 255, 0, 255];
+    let translucent_red = [255, 0, 0, 128];
+    let translucent_green = [0, 255, 0, 128];
+    let expected = [
+        [red, green, translucent_red, translucent_green],
+        [green, red, translucent_green, translucent_red],
+    ];
+    for (y, row) in expected.iter().enumerate() {
+        for (x, expected) in row.iter().enumerate() {
+            assert_eq!(pixel(&image, x, y), *expected);
+        }
+    }
+}
+
+#[test]
+fn test_webp_lossy_image() {
+    let image = load_webp("lossy.webp");
+    assert_eq!((image.width, image.height), (20, 20));
+    assert_pixel_near(&image, 0, 0, [155, 27, 27, 255]);
+    assert_pixel_near(&image, 4, 4, [155, 27, 27, 255]);
+    assert_pixel_near(&image, 15, 4, [27, 155, 28, 255]);
+    assert_pixel_near(&image, 4, 15, [28, 28, 153, 255]);
+    assert_pixel_near(&image, 15, 15, [28, 26, 29, 255]);
+    assert_pixel_near(&image, 19, 19, [27, 27, 27, 255]);
+}
+
+#[test]
+fn test_webp_lossy_image_with_alpha() {
+    let image = load_webp("lossy-alpha.webp");
+    assert_eq!((image.width, image.height), (20, 20));
+    assert_pixel_near(&image, 4, 4, [227, 31, 31, 128]);
+    assert_pixel_near(&image, 15, 4, [31, 226, 31, 128]);
+    assert_pixel_near(&image, 4, 15, [33, 30, 227, 128]);
+    assert_pixel_near(&image, 15, 15, [31, 31, 31, 128]);
+
+    // The alpha channel is losslessly compressed.
+    for y in 0..20 {
+        for x in 0..20 {
+            assert_eq!(pixel(&image, x, y)[3], 128);
+        }
+    }
+}
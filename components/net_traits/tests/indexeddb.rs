@@ -0,0 +1,51 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use net_traits::indexeddb_thread::{IndexedDBKeyRange, IndexedDBKeyType};
+
+#[test]
+fn test_key_ordering_across_types() {
+    let number = IndexedDBKeyType::Number(100.0);
+    let date = IndexedDBKeyType::Date(0.0);
+    let string = IndexedDBKeyType::String("a".to_owned());
+    let binary = IndexedDBKeyType::Binary(vec![0]);
+    let array = IndexedDBKeyType::Array(vec![]);
+
+    assert!(number < date);
+    assert!(date < string);
+    assert!(string < binary);
+    assert!(binary < array);
+}
+
+#[test]
+fn test_key_ordering_within_types() {
+    assert!(IndexedDBKeyType::Number(-1.0) < IndexedDBKeyType::Number(1.0));
+    assert!(IndexedDBKeyType::String("a".to_owned()) < IndexedDBKeyType::String("b".to_owned()));
+    assert!(IndexedDBKeyType::Binary(vec![1]) < IndexedDBKeyType::Binary(vec![1, 0]));
+    assert!(
+        IndexedDBKeyType::Array(vec![IndexedDBKeyType::Number(1.0)]) <
+            IndexedDBKeyType::Array(vec![IndexedDBKeyType::String("".to_owned())])
+    );
+    assert_eq!(IndexedDBKeyType::Number(1.0), IndexedDBKeyType::Number(1.0));
+}
+
+#[test]
+fn test_key_range_contains() {
+    let range = IndexedDBKeyRange {
+        lower: Some(IndexedDBKeyType::Number(1.0)),
+        upper: Some(IndexedDBKeyType::Number(5.0)),
+        lower_open: true,
+        upper_open: false,
+    };
+    assert!(!range.contains(&IndexedDBKeyType::Number(1.0)));
+    assert!(range.contains(&IndexedDBKeyType::Number(3.0)));
+    assert!(range.contains(&IndexedDBKeyType::Number(5.0)));
+    assert!(!range.contains(&IndexedDBKeyType::String("3".to_owned())));
+
+    let only = IndexedDBKeyRange::only(IndexedDBKeyType::String("key".to_owned()));
+    assert!(only.contains(&IndexedDBKeyType::String("key".to_owned())));
+    assert!(!only.contains(&IndexedDBKeyType::String("keys".to_owned())));
+
+    assert!(IndexedDBKeyRange::unbounded().contains(&IndexedDBKeyType::Array(vec![])));
+}
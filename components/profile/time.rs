@@ -124,6 +124,7 @@ impl Formattable for ProfilerCategory {
             ProfilerCategory::ImageSaving => "Image Saving",
             ProfilerCategory::ScriptAttachLayout => "Script Attach Layout",
             ProfilerCategory::ScriptConstellationMsg => "Script Constellation Msg",
+            ProfilerCategory::ScriptDatabaseAccessEvent => "Script Database Access Event",
             ProfilerCategory::ScriptDevtoolsMsg => "Script Devtools Msg",
             ProfilerCategory::ScriptDocumentEvent => "Script Document Event",
             ProfilerCategory::ScriptDomEvent => "Script Dom Event",
@@ -136,6 +137,7 @@ impl Formattable for ProfilerCategory {
             ProfilerCategory::ScriptParseHTML => "Script Parse HTML",
             ProfilerCategory::ScriptParseXML => "Script Parse XML",
             ProfilerCategory::ScriptPlannedNavigation => "Script Planned Navigation",
+            ProfilerCategory::ScriptPortMessage => "Script Port Message",
             ProfilerCategory::ScriptResize => "Script Resize",
             ProfilerCategory::ScriptEvent => "Script Event",
             ProfilerCategory::ScriptUpdateReplacedElement => "Script Update Replaced Element",
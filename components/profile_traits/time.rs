@@ -108,6 +108,8 @@ pub enum ProfilerCategory {
     ScriptWorkletEvent = 0x7a,
     ScriptPerformanceEvent = 0x7b,
     ScriptHistoryEvent = 0x7c,
+    ScriptDatabaseAccessEvent = 0x7d,
+    ScriptPortMessage = 0x7e,
     TimeToFirstPaint = 0x80,
     TimeToFirstContentfulPaint = 0x81,
     TimeToInteractive = 0x82,
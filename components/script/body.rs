@@ -3,18 +3,24 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
 use crate::compartments::{AlreadyInCompartment, InCompartment};
+use crate::dom::bindings::cell::DomRefCell;
 use crate::dom::bindings::codegen::Bindings::FormDataBinding::FormDataMethods;
 use crate::dom::bindings::error::{Error, Fallible};
 use crate::dom::bindings::reflector::DomObject;
-use crate::dom::bindings::root::DomRoot;
+use crate::dom::bindings::root::{Dom, DomRoot};
 use crate::dom::bindings::str::USVString;
 use crate::dom::bindings::trace::RootedTraceableBox;
 use crate::dom::blob::{Blob, BlobImpl};
 use crate::dom::formdata::FormData;
 use crate::dom::globalscope::GlobalScope;
 use crate::dom::promise::Promise;
+use crate::dom::promisenativehandler::Callback;
+use crate::dom::readablestream::{ReadableStream, RejectWithReason};
+use crate::dom::readablestreamdefaultreader::ReadableStreamDefaultReader;
 use crate::script_runtime::JSContext;
+use crate::streams::{read_result_value, upon_settlement};
 use js::jsapi::Heap;
+use js::jsapi::JSContext as RawJSContext;
 use js::jsapi::JSObject;
 use js::jsapi::JS_ClearPendingException;
 use js::jsapi::Value as JSValue;
@@ -22,9 +28,11 @@ use js::jsval::JSVal;
 use js::jsval::UndefinedValue;
 use js::rust::wrappers::JS_GetPendingException;
 use js::rust::wrappers::JS_ParseJSON;
-use js::typedarray::{ArrayBuffer, CreateWith};
+use js::rust::HandleValue;
+use js::typedarray::{ArrayBuffer, CreateWith, Uint8Array};
 use mime::{self, Mime};
 use std::cell::Ref;
+use std::mem;
 use std::ptr;
 use std::rc::Rc;
 use std::str;
@@ -65,10 +73,13 @@ pub fn consume_body<T: BodyOperations + DomObject>(object: &T, body_type: BodyTy
         return promise;
     }
 
-    object.set_body_promise(&promise, body_type);
-
     // Steps 2-4
-    // TODO: Body does not yet have a stream.
+    if let Some(stream) = object.get_stream() {
+        consume_stream(&stream, body_type, &promise, &object.get_mime_type());
+        return promise;
+    }
+
+    object.set_body_promise(&promise, body_type);
 
     consume_body_with_promise(object, body_type, &promise);
 
@@ -88,8 +99,24 @@ pub fn consume_body_with_promise<T: BodyOperations + DomObject>(
         None => return,
     };
 
-    let pkg_data_results =
-        run_package_data_algorithm(object, body, body_type, object.get_mime_type());
+    resolve_with_package_data(
+        &object.global(),
+        promise,
+        body,
+        body_type,
+        &object.get_mime_type(),
+    );
+}
+
+/// Settle `promise` with the result of packaging `bytes` as `body_type`.
+fn resolve_with_package_data(
+    global: &GlobalScope,
+    promise: &Promise,
+    bytes: Vec<u8>,
+    body_type: BodyType,
+    mime_type: &[u8],
+) {
+    let pkg_data_results = run_package_data_algorithm(global, bytes, body_type, mime_type);
 
     match pkg_data_results {
         Ok(results) => {
@@ -107,21 +134,18 @@ pub fn consume_body_with_promise<T: BodyOperations + DomObject>(
 }
 
 // https://fetch.spec.whatwg.org/#concept-body-package-data
-#[allow(unsafe_code)]
-fn run_package_data_algorithm<T: BodyOperations + DomObject>(
-    object: &T,
+fn run_package_data_algorithm(
+    global: &GlobalScope,
     bytes: Vec<u8>,
     body_type: BodyType,
-    mime_type: Ref<Vec<u8>>,
+    mime: &[u8],
 ) -> Fallible<FetchedData> {
-    let global = object.global();
     let cx = global.get_cx();
-    let mime = &*mime_type;
     match body_type {
         BodyType::Text => run_text_data_algorithm(bytes),
         BodyType::Json => run_json_data_algorithm(cx, bytes),
-        BodyType::Blob => run_blob_data_algorithm(&global, bytes, mime),
-        BodyType::FormData => run_form_data_algorithm(&global, bytes, mime),
+        BodyType::Blob => run_blob_data_algorithm(global, bytes, mime),
+        BodyType::FormData => run_form_data_algorithm(global, bytes, mime),
         BodyType::ArrayBuffer => run_array_buffer_data_algorithm(cx, bytes),
     }
 }
@@ -224,4 +248,99 @@ pub trait BodyOperations {
     fn take_body(&self) -> Option<Vec<u8>>;
     fn is_locked(&self) -> bool;
     fn get_mime_type(&self) -> Ref<Vec<u8>>;
+    /// Returns the stream of the body, if one has been created for it.
+    fn get_stream(&self) -> Option<DomRoot<ReadableStream>>;
+}
+
+/// The state of reading all the bytes of a body's stream.
+#[derive(JSTraceable, MallocSizeOf)]
+#[must_root]
+struct StreamConsumer {
+    reader: Dom<ReadableStreamDefaultReader>,
+    bytes: DomRefCell<Vec<u8>>,
+    #[ignore_malloc_size_of = "Rc"]
+    promise: Rc<Promise>,
+    body_type: BodyType,
+    mime_type: Vec<u8>,
+}
+
+impl StreamConsumer {
+    #[allow(unrooted_must_root)]
+    fn read_next_chunk(consumer: Rc<StreamConsumer>) {
+        let global = consumer.reader.global();
+        let read_promise = consumer.reader.read();
+        upon_settlement(
+            &global,
+            &read_promise,
+            Box::new(StreamChunkRead(consumer.clone())),
+            Box::new(RejectWithReason(consumer.promise.clone())),
+        );
+    }
+}
+
+/// <https://fetch.spec.whatwg.org/#concept-read-all-bytes-from-readablestream>
+#[allow(unrooted_must_root)]
+fn consume_stream(
+    stream: &ReadableStream,
+    body_type: BodyType,
+    promise: &Rc<Promise>,
+    mime_type: &[u8],
+) {
+    let reader = match stream.acquire_default_reader() {
+        Ok(reader) => reader,
+        Err(error) => return promise.reject_error(error),
+    };
+    let consumer = Rc::new(StreamConsumer {
+        reader: Dom::from_ref(&*reader),
+        bytes: DomRefCell::new(vec![]),
+        promise: promise.clone(),
+        body_type,
+        mime_type: mime_type.to_vec(),
+    });
+    StreamConsumer::read_next_chunk(consumer);
+}
+
+#[derive(JSTraceable, MallocSizeOf)]
+#[must_root]
+struct StreamChunkRead(#[ignore_malloc_size_of = "Rc"] Rc<StreamConsumer>);
+
+impl Callback for StreamChunkRead {
+    #[allow(unsafe_code)]
+    fn callback(&self, cx: *mut RawJSContext, v: HandleValue) {
+        let consumer = &self.0;
+        let global = consumer.reader.global();
+        rooted!(in(cx) let mut chunk = UndefinedValue());
+        if read_result_value(&global, v, chunk.handle_mut()) {
+            let bytes = mem::replace(&mut *consumer.bytes.borrow_mut(), vec![]);
+            return resolve_with_package_data(
+                &global,
+                &consumer.promise,
+                bytes,
+                consumer.body_type,
+                &consumer.mime_type,
+            );
+        }
+
+        if !chunk.is_object() {
+            return consumer
+                .promise
+                .reject_error(Error::Type("A body chunk is not a Uint8Array".to_owned()));
+        }
+        let object = chunk.to_object();
+        typedarray!(in(cx) let array: Uint8Array = object);
+        match array {
+            Ok(array) => unsafe {
+                consumer
+                    .bytes
+                    .borrow_mut()
+                    .extend_from_slice(array.as_slice());
+            },
+            Err(()) => {
+                return consumer
+                    .promise
+                    .reject_error(Error::Type("A body chunk is not a Uint8Array".to_owned()));
+            },
+        }
+        StreamConsumer::read_next_chunk(consumer.clone());
+    }
 }
@@ -0,0 +1,53 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::abortsignal::AbortSignal;
+use crate::dom::bindings::codegen::Bindings::AbortControllerBinding;
+use crate::dom::bindings::codegen::Bindings::AbortControllerBinding::AbortControllerMethods;
+use crate::dom::bindings::error::Fallible;
+use crate::dom::bindings::reflector::{reflect_dom_object, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot};
+use crate::dom::globalscope::GlobalScope;
+use dom_struct::dom_struct;
+
+#[dom_struct]
+pub struct AbortController {
+    reflector_: Reflector,
+    /// <https://dom.spec.whatwg.org/#abortcontroller-signal>
+    signal: Dom<AbortSignal>,
+}
+
+impl AbortController {
+    fn new_inherited(signal: &AbortSignal) -> AbortController {
+        AbortController {
+            reflector_: Reflector::new(),
+            signal: Dom::from_ref(signal),
+        }
+    }
+
+    fn new(global: &GlobalScope) -> DomRoot<AbortController> {
+        reflect_dom_object(
+            Box::new(AbortController::new_inherited(&AbortSignal::new(global))),
+            global,
+            AbortControllerBinding::Wrap,
+        )
+    }
+
+    // https://dom.spec.whatwg.org/#dom-abortcontroller-abortcontroller
+    pub fn Constructor(global: &GlobalScope) -> Fallible<DomRoot<AbortController>> {
+        Ok(AbortController::new(global))
+    }
+}
+
+impl AbortControllerMethods for AbortController {
+    // https://dom.spec.whatwg.org/#dom-abortcontroller-signal
+    fn Signal(&self) -> DomRoot<AbortSignal> {
+        DomRoot::from_ref(&*self.signal)
+    }
+
+    // https://dom.spec.whatwg.org/#dom-abortcontroller-abort
+    fn Abort(&self) {
+        self.signal.signal_abort();
+    }
+}
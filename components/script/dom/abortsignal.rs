@@ -0,0 +1,191 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::AbortSignalBinding;
+use crate::dom::bindings::codegen::Bindings::AbortSignalBinding::AbortSignalMethods;
+use crate::dom::bindings::codegen::Bindings::EventListenerBinding::EventListener;
+use crate::dom::bindings::codegen::Bindings::EventTargetBinding::EventListenerOptions;
+use crate::dom::bindings::error::Error;
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::reflector::reflect_dom_object;
+use crate::dom::bindings::root::{Dom, DomRoot};
+use crate::dom::bindings::str::DOMString;
+use crate::dom::eventtarget::EventTarget;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::promise::Promise;
+use crate::dom::readablestream::PipeState;
+use crate::dom::response::Response;
+use dom_struct::dom_struct;
+use ipc_channel::ipc::IpcSender;
+use std::cell::Cell;
+use std::mem;
+use std::rc::Rc;
+
+/// <https://dom.spec.whatwg.org/#abortsignal-abort-algorithms>
+#[derive(JSTraceable)]
+#[must_root]
+pub enum AbortAlgorithm {
+    /// Remove an event listener that was added with this signal.
+    /// <https://dom.spec.whatwg.org/#add-an-event-listener>
+    DomEventListener {
+        target: Dom<EventTarget>,
+        ty: DOMString,
+        listener: Rc<EventListener>,
+        capture: bool,
+    },
+    /// Signal abort on a signal that follows this one.
+    /// <https://dom.spec.whatwg.org/#abortsignal-follow>
+    Follow(Dom<AbortSignal>),
+    /// Abort an ongoing fetch.
+    /// <https://fetch.spec.whatwg.org/#abort-fetch>
+    Fetch {
+        promise: Rc<Promise>,
+        response: Dom<Response>,
+        cancel_chan: IpcSender<()>,
+    },
+    /// Abort a pipe between a readable and a writable stream.
+    /// <https://streams.spec.whatwg.org/#readable-stream-pipe-to>
+    StreamPipe(Rc<PipeState>),
+}
+
+impl AbortAlgorithm {
+    #[allow(unrooted_must_root)]
+    fn run(self) {
+        match self {
+            AbortAlgorithm::DomEventListener {
+                target,
+                ty,
+                listener,
+                capture,
+            } => target.remove_event_listener(ty, Some(listener), EventListenerOptions { capture }),
+            AbortAlgorithm::Follow(signal) => signal.signal_abort(),
+            AbortAlgorithm::Fetch {
+                promise,
+                response,
+                cancel_chan,
+            } => {
+                // Step 1.
+                if !promise.is_fulfilled() {
+                    promise.reject_error(Error::Abort);
+                }
+                // Steps 3-5.
+                response.error_body(Error::Abort);
+                // The receiver is gone if the fetch has already completed,
+                // in which case there is nothing to cancel.
+                let _ = cancel_chan.send(());
+            },
+            AbortAlgorithm::StreamPipe(state) => state.abort(),
+        }
+    }
+}
+
+#[dom_struct]
+pub struct AbortSignal {
+    eventtarget: EventTarget,
+    /// <https://dom.spec.whatwg.org/#abortsignal-aborted-flag>
+    aborted: Cell<bool>,
+    /// <https://dom.spec.whatwg.org/#abortsignal-abort-algorithms>
+    #[ignore_malloc_size_of = "Rc"]
+    abort_algorithms: DomRefCell<Vec<AbortAlgorithm>>,
+}
+
+impl AbortSignal {
+    fn new_inherited() -> AbortSignal {
+        AbortSignal {
+            eventtarget: EventTarget::new_inherited(),
+            aborted: Cell::new(false),
+            abort_algorithms: DomRefCell::new(vec![]),
+        }
+    }
+
+    pub fn new(global: &GlobalScope) -> DomRoot<AbortSignal> {
+        reflect_dom_object(
+            Box::new(AbortSignal::new_inherited()),
+            global,
+            AbortSignalBinding::Wrap,
+        )
+    }
+
+    pub fn aborted(&self) -> bool {
+        self.aborted.get()
+    }
+
+    /// <https://dom.spec.whatwg.org/#abortsignal-add>
+    #[allow(unrooted_must_root)]
+    pub fn add_algorithm(&self, algorithm: AbortAlgorithm) {
+        // Step 1.
+        if self.aborted.get() {
+            return;
+        }
+
+        // Step 2.
+        self.abort_algorithms.borrow_mut().push(algorithm);
+    }
+
+    /// Remove the algorithm aborting the fetch of `response`, once that fetch
+    /// has finished.
+    /// <https://dom.spec.whatwg.org/#abortsignal-remove>
+    #[allow(unrooted_must_root)]
+    pub fn remove_fetch_algorithm(&self, response: &Response) {
+        self.abort_algorithms
+            .borrow_mut()
+            .retain(|algorithm| match *algorithm {
+                AbortAlgorithm::Fetch {
+                    response: ref fetched,
+                    ..
+                } => &**fetched as *const Response != response as *const Response,
+                _ => true,
+            });
+    }
+
+    /// <https://dom.spec.whatwg.org/#abortsignal-signal-abort>
+    #[allow(unrooted_must_root)]
+    pub fn signal_abort(&self) {
+        // Step 1.
+        if self.aborted.get() {
+            return;
+        }
+
+        // Step 2.
+        self.aborted.set(true);
+
+        // Steps 3-4. Algorithms may add or run further algorithms, so the
+        // list is taken out of the signal before running them.
+        let algorithms = mem::replace(&mut *self.abort_algorithms.borrow_mut(), vec![]);
+        for algorithm in algorithms {
+            algorithm.run();
+        }
+
+        // Step 5.
+        self.upcast::<EventTarget>().fire_event(atom!("abort"));
+    }
+
+    /// <https://dom.spec.whatwg.org/#abortsignal-follow>
+    #[allow(unrooted_must_root)]
+    pub fn follow(&self, parent: &AbortSignal) {
+        // Step 1.
+        if self.aborted.get() {
+            return;
+        }
+
+        // Step 2.
+        if parent.aborted() {
+            return self.signal_abort();
+        }
+
+        // Step 3.
+        parent.add_algorithm(AbortAlgorithm::Follow(Dom::from_ref(self)));
+    }
+}
+
+impl AbortSignalMethods for AbortSignal {
+    // https://dom.spec.whatwg.org/#dom-abortsignal-aborted
+    fn Aborted(&self) -> bool {
+        self.aborted.get()
+    }
+
+    // https://dom.spec.whatwg.org/#dom-abortsignal-onabort
+    event_handler!(abort, GetOnabort, SetOnabort);
+}
@@ -91,6 +91,7 @@ pub trait WorkerEventLoopMethods {
     fn from_worker_msg(&self, msg: Self::WorkerMsg) -> Self::Event;
     fn from_timer_msg(&self, msg: Self::TimerMsg) -> Self::Event;
     fn from_devtools_msg(&self, msg: DevtoolScriptControlMsg) -> Self::Event;
+    fn update_the_rendering(&self);
 }
 
 // https://html.spec.whatwg.org/multipage/#worker-event-loop
@@ -155,4 +156,7 @@ pub fn run_worker_event_loop<T, TimerMsg, WorkerMsg, Event>(
             .upcast::<GlobalScope>()
             .perform_a_microtask_checkpoint();
     }
+    // https://html.spec.whatwg.org/multipage/#event-loop-processing-model
+    // Update the rendering of a dedicated worker.
+    worker_scope.update_the_rendering();
 }
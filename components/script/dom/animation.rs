@@ -0,0 +1,969 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::animationeffect::AnimationEffect;
+use crate::dom::animationplaybackevent::AnimationPlaybackEvent;
+use crate::dom::animationtimeline::AnimationTimeline;
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::AnimationBinding;
+use crate::dom::bindings::codegen::Bindings::AnimationBinding::{
+    AnimationMethods, AnimationPlayState,
+};
+use crate::dom::bindings::codegen::Bindings::AnimationPlaybackEventBinding::AnimationPlaybackEventInit;
+use crate::dom::bindings::codegen::Bindings::EventBinding::EventInit;
+use crate::dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
+use crate::dom::bindings::error::{Error, ErrorResult, Fallible};
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::num::Finite;
+use crate::dom::bindings::refcounted::Trusted;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject};
+use crate::dom::bindings::root::{DomRoot, MutNullableDom};
+use crate::dom::bindings::str::DOMString;
+use crate::dom::event::Event;
+use crate::dom::eventtarget::EventTarget;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::promise::Promise;
+use crate::dom::window::Window;
+use crate::microtask::{Microtask, MicrotaskRunnable};
+use crate::script_thread::ScriptThread;
+use crate::streams::mark_as_handled;
+use crate::task_source::TaskSource;
+use dom_struct::dom_struct;
+use servo_atoms::Atom;
+use std::cell::Cell;
+use std::rc::Rc;
+
+/// The kind of task an animation is waiting on to be ready.
+///
+/// <https://drafts.csswg.org/web-animations/#pending-play-task>
+/// <https://drafts.csswg.org/web-animations/#pending-pause-task>
+#[derive(Clone, Copy, Debug, JSTraceable, MallocSizeOf, PartialEq)]
+enum PendingTask {
+    Play,
+    Pause,
+}
+
+/// <https://drafts.csswg.org/web-animations/#the-animation-interface>
+#[dom_struct]
+pub struct Animation {
+    eventtarget: EventTarget,
+    id: DomRefCell<DOMString>,
+    effect: MutNullableDom<AnimationEffect>,
+    timeline: MutNullableDom<AnimationTimeline>,
+    /// <https://drafts.csswg.org/web-animations/#animation-start-time>
+    start_time: Cell<Option<f64>>,
+    /// <https://drafts.csswg.org/web-animations/#animation-hold-time>
+    hold_time: Cell<Option<f64>>,
+    /// <https://drafts.csswg.org/web-animations/#previous-current-time>
+    previous_current_time: Cell<Option<f64>>,
+    /// <https://drafts.csswg.org/web-animations/#playback-rate>
+    playback_rate: Cell<f64>,
+    /// <https://drafts.csswg.org/web-animations/#pending-playback-rate>
+    pending_playback_rate: Cell<Option<f64>>,
+    pending_task: Cell<Option<PendingTask>>,
+    /// <https://drafts.csswg.org/web-animations/#current-ready-promise>
+    #[ignore_malloc_size_of = "Rc"]
+    ready_promise: DomRefCell<Rc<Promise>>,
+    /// <https://drafts.csswg.org/web-animations/#current-finished-promise>
+    #[ignore_malloc_size_of = "Rc"]
+    finished_promise: DomRefCell<Rc<Promise>>,
+    /// Incremented to abort a queued microtask running the finish notification steps.
+    finish_notification_id: Cell<u32>,
+    /// Whether a microtask running the finish notification steps is queued.
+    finish_notification_queued: Cell<bool>,
+}
+
+impl Animation {
+    pub fn new_inherited(global: &GlobalScope) -> Animation {
+        Animation {
+            eventtarget: EventTarget::new_inherited(),
+            id: DomRefCell::new(DOMString::new()),
+            effect: Default::default(),
+            timeline: Default::default(),
+            start_time: Cell::new(None),
+            hold_time: Cell::new(None),
+            previous_current_time: Cell::new(None),
+            playback_rate: Cell::new(1.),
+            pending_playback_rate: Cell::new(None),
+            pending_task: Cell::new(None),
+            ready_promise: DomRefCell::new(Promise::new(global)),
+            finished_promise: DomRefCell::new(Promise::new(global)),
+            finish_notification_id: Cell::new(0),
+            finish_notification_queued: Cell::new(false),
+        }
+    }
+
+    pub fn new(
+        window: &Window,
+        effect: Option<&AnimationEffect>,
+        timeline: Option<&AnimationTimeline>,
+    ) -> DomRoot<Animation> {
+        let animation = reflect_dom_object(
+            Box::new(Animation::new_inherited(window.upcast())),
+            window,
+            AnimationBinding::Wrap,
+        );
+        animation.setup(effect, timeline);
+        animation
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#dom-animation-animation>
+    pub fn Constructor(
+        window: &Window,
+        effect: Option<&AnimationEffect>,
+        timeline: Option<Option<&AnimationTimeline>>,
+    ) -> Fallible<DomRoot<Animation>> {
+        let document = window.Document();
+        let default_timeline = document.timeline();
+        let timeline = timeline.unwrap_or(Some(default_timeline.upcast()));
+        Ok(Animation::new(window, effect, timeline))
+    }
+
+    /// Finishes the initialization of a newly reflected animation.
+    pub fn setup(&self, effect: Option<&AnimationEffect>, timeline: Option<&AnimationTimeline>) {
+        // The current ready promise is initially resolved.
+        self.ready_promise.borrow().resolve_native(self);
+        self.set_timeline(timeline);
+        self.set_effect(effect);
+    }
+
+    pub fn effect(&self) -> Option<DomRoot<AnimationEffect>> {
+        self.effect.get()
+    }
+
+    pub fn timeline(&self) -> Option<DomRoot<AnimationTimeline>> {
+        self.timeline.get()
+    }
+
+    pub fn playback_rate(&self) -> f64 {
+        self.playback_rate.get()
+    }
+
+    pub fn set_id(&self, id: DOMString) {
+        *self.id.borrow_mut() = id;
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#pending>
+    pub fn is_pending(&self) -> bool {
+        self.pending_task.get().is_some()
+    }
+
+    fn timeline_time(&self) -> Option<f64> {
+        self.timeline
+            .get()
+            .and_then(|timeline| timeline.current_time())
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#effective-playback-rate>
+    fn effective_playback_rate(&self) -> f64 {
+        self.pending_playback_rate
+            .get()
+            .unwrap_or(self.playback_rate.get())
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#associated-effect-end>
+    fn effect_end(&self) -> f64 {
+        self.effect
+            .get()
+            .map_or(0., |effect| effect.timing().end_time())
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#animation-current-time>
+    pub fn current_time(&self) -> Option<f64> {
+        self.current_time_with_hold_time(self.hold_time.get())
+    }
+
+    fn current_time_with_hold_time(&self, hold_time: Option<f64>) -> Option<f64> {
+        if hold_time.is_some() {
+            return hold_time;
+        }
+        let timeline_time = self.timeline_time()?;
+        let start_time = self.start_time.get()?;
+        Some((timeline_time - start_time) * self.playback_rate.get())
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#play-state>
+    pub fn play_state(&self) -> AnimationPlayState {
+        let current_time = self.current_time();
+        if current_time.is_none() && self.start_time.get().is_none() && !self.is_pending() {
+            return AnimationPlayState::Idle;
+        }
+        if self.pending_task.get() == Some(PendingTask::Pause) ||
+            (self.start_time.get().is_none() &&
+                self.pending_task.get() != Some(PendingTask::Play))
+        {
+            return AnimationPlayState::Paused;
+        }
+        if let Some(current_time) = current_time {
+            let playback_rate = self.playback_rate.get();
+            if (playback_rate > 0. && current_time >= self.effect_end()) ||
+                (playback_rate < 0. && current_time <= 0.)
+            {
+                return AnimationPlayState::Finished;
+            }
+        }
+        AnimationPlayState::Running
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#relevant-animation>
+    pub fn is_relevant(&self) -> bool {
+        self.effect
+            .get()
+            .map_or(false, |effect| effect.is_current() || effect.is_in_effect())
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#apply-any-pending-playback-rate>
+    fn apply_pending_playback_rate(&self) {
+        if let Some(playback_rate) = self.pending_playback_rate.take() {
+            self.playback_rate.set(playback_rate);
+        }
+    }
+
+    fn resolve_ready_promise(&self) {
+        self.ready_promise.borrow().resolve_native(self);
+    }
+
+    fn replace_ready_promise(&self) {
+        *self.ready_promise.borrow_mut() = Promise::new(&self.global());
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#silently-set-the-current-time>
+    fn silently_set_current_time(&self, seek_time: Option<f64>) -> ErrorResult {
+        // Step 1.
+        let seek_time = match seek_time {
+            Some(seek_time) => seek_time,
+            None => {
+                if self.current_time().is_some() {
+                    return Err(Error::Type("Cannot unset the current time".to_owned()));
+                }
+                return Ok(());
+            },
+        };
+
+        // Step 2.
+        let timeline_time = self.timeline_time();
+        let playback_rate = self.playback_rate.get();
+        match timeline_time {
+            Some(timeline_time)
+                if self.hold_time.get().is_none() &&
+                    self.start_time.get().is_some() &&
+                    playback_rate != 0. =>
+            {
+                self.start_time
+                    .set(Some(timeline_time - seek_time / playback_rate));
+            }
+            _ => self.hold_time.set(Some(seek_time)),
+        }
+
+        // Step 3.
+        if timeline_time.is_none() {
+            self.start_time.set(None);
+        }
+
+        // Step 4.
+        self.previous_current_time.set(None);
+        Ok(())
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#set-the-current-time>
+    fn set_current_time(&self, seek_time: Option<f64>) -> ErrorResult {
+        // Step 1.
+        self.silently_set_current_time(seek_time)?;
+
+        // Step 2.
+        if self.pending_task.get() == Some(PendingTask::Pause) {
+            self.hold_time.set(seek_time);
+            self.apply_pending_playback_rate();
+            self.start_time.set(None);
+            self.pending_task.set(None);
+            self.resolve_ready_promise();
+        }
+
+        // Step 3.
+        self.update_finished_state(true, false);
+        Ok(())
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#set-the-start-time>
+    fn set_start_time(&self, new_start_time: Option<f64>) {
+        // Steps 1-2.
+        if self.timeline_time().is_none() && new_start_time.is_some() {
+            self.hold_time.set(None);
+        }
+
+        // Step 3.
+        let previous_current_time = self.current_time();
+
+        // Step 4.
+        self.apply_pending_playback_rate();
+
+        // Step 5.
+        self.start_time.set(new_start_time);
+
+        // Step 6.
+        if new_start_time.is_some() {
+            if self.playback_rate.get() != 0. {
+                self.hold_time.set(None);
+            }
+        } else {
+            self.hold_time.set(previous_current_time);
+        }
+
+        // Step 7.
+        if self.pending_task.take().is_some() {
+            self.resolve_ready_promise();
+        }
+
+        // Step 8.
+        self.update_finished_state(true, false);
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#set-the-timeline-of-an-animation>
+    pub fn set_timeline(&self, new_timeline: Option<&AnimationTimeline>) {
+        // Steps 1-2.
+        if self.timeline.get().as_deref() == new_timeline {
+            return;
+        }
+
+        // Step 3.
+        self.timeline.set(new_timeline);
+
+        // Step 4.
+        if self.start_time.get().is_some() {
+            self.hold_time.set(None);
+        }
+
+        // Step 5.
+        self.update_finished_state(false, false);
+        self.timing_changed();
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#setting-the-associated-effect>
+    pub fn set_effect(&self, new_effect: Option<&AnimationEffect>) {
+        // Steps 1-2.
+        let old_effect = self.effect.get();
+        if old_effect.as_deref() == new_effect {
+            return;
+        }
+
+        // Step 3.
+        if new_effect.is_none() {
+            self.reset_pending_tasks();
+        }
+
+        // Step 5.
+        if let Some(new_effect) = new_effect {
+            if let Some(previous_animation) = new_effect.animation() {
+                previous_animation.set_effect(None);
+            }
+        }
+
+        // Step 6.
+        if let Some(old_effect) = old_effect {
+            old_effect.set_animation(None);
+        }
+        if let Some(new_effect) = new_effect {
+            new_effect.set_animation(Some(self));
+        }
+        self.effect.set(new_effect);
+
+        // Step 7.
+        self.update_finished_state(false, false);
+        self.timing_changed();
+    }
+
+    /// Called when the timing properties, keyframes or target of the
+    /// associated effect change.
+    pub fn effect_changed(&self) {
+        self.update_finished_state(false, false);
+        self.timing_changed();
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#reset-an-animations-pending-tasks>
+    fn reset_pending_tasks(&self) {
+        // Steps 1-3.
+        if self.pending_task.take().is_none() {
+            return;
+        }
+
+        // Step 4.
+        self.apply_pending_playback_rate();
+
+        // Steps 5-6.
+        let global = self.global();
+        let ready_promise = self.ready_promise.borrow().clone();
+        ready_promise.reject_error(Error::Abort);
+        mark_as_handled(&global, &ready_promise);
+
+        // Step 7.
+        self.replace_ready_promise();
+        self.resolve_ready_promise();
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#playing-an-animation-section>
+    pub fn play(&self, auto_rewind: bool) -> ErrorResult {
+        // Step 1.
+        let aborted_pause = self.pending_task.get() == Some(PendingTask::Pause);
+
+        // Step 2.
+        let mut has_pending_ready_promise = false;
+
+        // Step 3.
+        let mut seek_time = None;
+
+        // Steps 4-5.
+        let playback_rate = self.effective_playback_rate();
+        let current_time = self.current_time();
+        let effect_end = self.effect_end();
+        if auto_rewind {
+            if playback_rate >= 0. &&
+                current_time.map_or(true, |time| time < 0. || time >= effect_end)
+            {
+                seek_time = Some(0.);
+            } else if playback_rate < 0. &&
+                current_time.map_or(true, |time| time <= 0. || time > effect_end)
+            {
+                if effect_end.is_infinite() {
+                    return Err(Error::InvalidState);
+                }
+                seek_time = Some(effect_end);
+            }
+        }
+
+        // Step 6.
+        if seek_time.is_none() && self.start_time.get().is_none() && current_time.is_none() {
+            seek_time = Some(0.);
+        }
+
+        // Step 7.
+        if seek_time.is_some() {
+            self.hold_time.set(seek_time);
+        }
+
+        // Step 8.
+        if self.hold_time.get().is_some() {
+            self.start_time.set(None);
+        }
+
+        // Step 9.
+        if self.pending_task.take().is_some() {
+            has_pending_ready_promise = true;
+        }
+
+        // Step 10.
+        if self.hold_time.get().is_none() &&
+            seek_time.is_none() &&
+            !aborted_pause &&
+            self.pending_playback_rate.get().is_none()
+        {
+            return Ok(());
+        }
+
+        // Step 11.
+        if !has_pending_ready_promise {
+            self.replace_ready_promise();
+        }
+
+        // Step 12.
+        self.pending_task.set(Some(PendingTask::Play));
+
+        // Step 13.
+        self.update_finished_state(false, false);
+        self.timing_changed();
+        Ok(())
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#pausing-an-animation-section>
+    fn pause(&self) -> ErrorResult {
+        // Steps 1-2.
+        if self.pending_task.get() == Some(PendingTask::Pause) ||
+            self.play_state() == AnimationPlayState::Paused
+        {
+            return Ok(());
+        }
+
+        // Steps 3-4.
+        if self.current_time().is_none() {
+            if self.playback_rate.get() >= 0. {
+                self.hold_time.set(Some(0.));
+            } else {
+                let effect_end = self.effect_end();
+                if effect_end.is_infinite() {
+                    return Err(Error::InvalidState);
+                }
+                self.hold_time.set(Some(effect_end));
+            }
+        }
+
+        // Step 6.
+        if self.pending_task.take().is_none() {
+            self.replace_ready_promise();
+        }
+
+        // Step 7.
+        self.pending_task.set(Some(PendingTask::Pause));
+
+        // Step 8.
+        self.update_finished_state(false, false);
+        self.timing_changed();
+        Ok(())
+    }
+
+    /// Runs the pending play or pause task of this animation, if any, now that
+    /// the animation is ready.
+    pub fn run_pending_task(&self, ready_time: f64) {
+        match self.pending_task.get() {
+            Some(PendingTask::Play) => self.run_pending_play_task(ready_time),
+            Some(PendingTask::Pause) => self.run_pending_pause_task(ready_time),
+            None => {},
+        }
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#playing-an-animation-section>
+    fn run_pending_play_task(&self, ready_time: f64) {
+        self.pending_task.set(None);
+
+        // Steps 2-3.
+        if let Some(hold_time) = self.hold_time.get() {
+            self.apply_pending_playback_rate();
+            let playback_rate = self.playback_rate.get();
+            if playback_rate == 0. {
+                self.start_time.set(Some(ready_time));
+            } else {
+                self.start_time
+                    .set(Some(ready_time - hold_time / playback_rate));
+                self.hold_time.set(None);
+            }
+        } else if let Some(start_time) = self.start_time.get() {
+            if let Some(pending_playback_rate) = self.pending_playback_rate.get() {
+                let current_time_to_match = (ready_time - start_time) * self.playback_rate.get();
+                self.apply_pending_playback_rate();
+                if pending_playback_rate == 0. {
+                    self.hold_time.set(Some(current_time_to_match));
+                } else {
+                    self.start_time.set(Some(
+                        ready_time - current_time_to_match / pending_playback_rate,
+                    ));
+                }
+            }
+        }
+
+        // Step 4.
+        self.resolve_ready_promise();
+
+        // Step 5.
+        self.update_finished_state(false, false);
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#pausing-an-animation-section>
+    fn run_pending_pause_task(&self, ready_time: f64) {
+        self.pending_task.set(None);
+
+        // Step 1.
+        if let (Some(start_time), None) = (self.start_time.get(), self.hold_time.get()) {
+            self.hold_time
+                .set(Some((ready_time - start_time) * self.playback_rate.get()));
+        }
+
+        // Step 2.
+        self.apply_pending_playback_rate();
+
+        // Step 3.
+        self.start_time.set(None);
+
+        // Step 4.
+        self.resolve_ready_promise();
+
+        // Step 5.
+        self.update_finished_state(false, false);
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#update-an-animations-finished-state>
+    pub fn update_finished_state(&self, did_seek: bool, synchronously_notify: bool) {
+        // Step 1.
+        let hold_time = if did_seek { self.hold_time.get() } else { None };
+        let unconstrained_current_time = self.current_time_with_hold_time(hold_time);
+
+        // Step 2.
+        if let (Some(current_time), Some(_), false) = (
+            unconstrained_current_time,
+            self.start_time.get(),
+            self.pending_task.get() == Some(PendingTask::Play),
+        ) {
+            let playback_rate = self.playback_rate.get();
+            let effect_end = self.effect_end();
+            let previous_current_time = self.previous_current_time.get();
+            if playback_rate > 0. && current_time >= effect_end {
+                if did_seek {
+                    self.hold_time.set(Some(current_time));
+                } else {
+                    let previous = previous_current_time.map_or(effect_end, |t| t.max(effect_end));
+                    self.hold_time.set(Some(previous));
+                }
+            } else if playback_rate < 0. && current_time <= 0. {
+                if did_seek {
+                    self.hold_time.set(Some(current_time));
+                } else {
+                    let previous = previous_current_time.map_or(0., |t| t.min(0.));
+                    self.hold_time.set(Some(previous));
+                }
+            } else if playback_rate != 0. {
+                if let Some(timeline_time) = self.timeline_time() {
+                    if let (true, Some(hold_time)) = (did_seek, self.hold_time.get()) {
+                        self.start_time
+                            .set(Some(timeline_time - hold_time / playback_rate));
+                    }
+                    self.hold_time.set(None);
+                }
+            }
+        }
+
+        // Step 3.
+        self.previous_current_time.set(self.current_time());
+
+        // Step 4.
+        let finished = self.play_state() == AnimationPlayState::Finished;
+
+        // Step 5.
+        let finished_promise = self.finished_promise.borrow().clone();
+        if finished && !finished_promise.is_fulfilled() {
+            if synchronously_notify {
+                self.finish_notification_id
+                    .set(self.finish_notification_id.get().wrapping_add(1));
+                self.finish_notification_queued.set(false);
+                self.finish_notification_steps();
+            } else if !self.finish_notification_queued.get() {
+                self.finish_notification_queued.set(true);
+                ScriptThread::enqueue_microtask(Microtask::Animation(
+                    AnimationMicrotask::FinishNotificationSteps {
+                        animation: DomRoot::from_ref(self),
+                        id: self.finish_notification_id.get(),
+                    },
+                ));
+            }
+        }
+
+        // Step 6.
+        if !finished && finished_promise.is_fulfilled() {
+            *self.finished_promise.borrow_mut() = Promise::new(&self.global());
+        }
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#finish-notification-steps>
+    fn finish_notification_steps(&self) {
+        // Step 1.
+        if self.play_state() != AnimationPlayState::Finished {
+            return;
+        }
+
+        // Step 2.
+        self.finished_promise.borrow().resolve_native(self);
+
+        // Step 3.
+        self.queue_playback_event(atom!("finish"), self.current_time());
+    }
+
+    /// Queues a task to fire an `AnimationPlaybackEvent` at this animation.
+    fn queue_playback_event(&self, type_: Atom, current_time: Option<f64>) {
+        let timeline_time = self.timeline_time();
+        let global = self.global();
+        let window = global.as_window();
+        let animation = Trusted::new(self);
+        let _ = window.task_manager().dom_manipulation_task_source().queue(
+            task!(fire_animation_playback_event: move || {
+                let animation = animation.root();
+                let global = animation.global();
+                let init = AnimationPlaybackEventInit {
+                    parent: EventInit::default(),
+                    currentTime: current_time.map(Finite::wrap),
+                    timelineTime: timeline_time.map(Finite::wrap),
+                };
+                let event = AnimationPlaybackEvent::new(global.as_window(), type_, &init);
+                event.upcast::<Event>().fire(animation.upcast());
+            }),
+            window.upcast(),
+        );
+    }
+
+    /// Makes sure the document reflects a change to the timing of this
+    /// animation, and keeps ticking its timeline while it is running.
+    fn timing_changed(&self) {
+        let global = self.global();
+        let document = global.as_window().Document();
+        if let Some(timeline) = self.timeline.get() {
+            timeline.associate_animation(self);
+            if self.is_pending() || self.play_state() == AnimationPlayState::Running {
+                document.request_animation_ticks();
+            }
+        }
+        document.sample_animations();
+    }
+}
+
+impl AnimationMethods for Animation {
+    // https://drafts.csswg.org/web-animations/#dom-animation-id
+    fn Id(&self) -> DOMString {
+        self.id.borrow().clone()
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-animation-id
+    fn SetId(&self, id: DOMString) {
+        self.set_id(id);
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-animation-effect
+    fn GetEffect(&self) -> Option<DomRoot<AnimationEffect>> {
+        self.effect.get()
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-animation-effect
+    fn SetEffect(&self, effect: Option<&AnimationEffect>) {
+        self.set_effect(effect);
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-animation-timeline
+    fn GetTimeline(&self) -> Option<DomRoot<AnimationTimeline>> {
+        self.timeline.get()
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-animation-timeline
+    fn SetTimeline(&self, timeline: Option<&AnimationTimeline>) {
+        self.set_timeline(timeline);
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-animation-starttime
+    fn GetStartTime(&self) -> Option<Finite<f64>> {
+        self.start_time.get().map(Finite::wrap)
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-animation-starttime
+    fn SetStartTime(&self, start_time: Option<Finite<f64>>) {
+        self.set_start_time(start_time.map(|time| *time));
+        self.timing_changed();
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-animation-currenttime
+    fn GetCurrentTime(&self) -> Option<Finite<f64>> {
+        self.current_time().map(Finite::wrap)
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-animation-currenttime
+    fn SetCurrentTime(&self, current_time: Option<Finite<f64>>) -> ErrorResult {
+        self.set_current_time(current_time.map(|time| *time))?;
+        self.timing_changed();
+        Ok(())
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-animation-playbackrate
+    fn PlaybackRate(&self) -> Finite<f64> {
+        Finite::wrap(self.playback_rate.get())
+    }
+
+    // https://drafts.csswg.org/web-animations/#set-the-playback-rate
+    fn SetPlaybackRate(&self, playback_rate: Finite<f64>) {
+        // Step 1.
+        self.pending_playback_rate.set(None);
+
+        // Step 2.
+        let previous_time = self.current_time();
+
+        // Step 3.
+        self.playback_rate.set(*playback_rate);
+
+        // Step 4.
+        if previous_time.is_some() {
+            let _ = self.set_current_time(previous_time);
+        }
+        self.timing_changed();
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-animation-playstate
+    fn PlayState(&self) -> AnimationPlayState {
+        self.play_state()
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-animation-pending
+    fn Pending(&self) -> bool {
+        self.is_pending()
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-animation-ready
+    fn Ready(&self) -> Rc<Promise> {
+        self.ready_promise.borrow().clone()
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-animation-finished
+    fn Finished(&self) -> Rc<Promise> {
+        self.finished_promise.borrow().clone()
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-animation-onfinish
+    event_handler!(finish, GetOnfinish, SetOnfinish);
+
+    // https://drafts.csswg.org/web-animations/#dom-animation-oncancel
+    event_handler!(cancel, GetOncancel, SetOncancel);
+
+    // https://drafts.csswg.org/web-animations/#cancel-an-animation
+    fn Cancel(&self) {
+        // Step 1.
+        if self.play_state() != AnimationPlayState::Idle {
+            // Step 1.1.
+            self.reset_pending_tasks();
+
+            // Steps 1.2-1.4.
+            let global = self.global();
+            let finished_promise = self.finished_promise.borrow().clone();
+            finished_promise.reject_error(Error::Abort);
+            mark_as_handled(&global, &finished_promise);
+
+            // Step 1.5.
+            *self.finished_promise.borrow_mut() = Promise::new(&global);
+
+            // Steps 1.6-1.7.
+            self.queue_playback_event(atom!("cancel"), None);
+        }
+
+        // Steps 2-3.
+        self.hold_time.set(None);
+        self.start_time.set(None);
+        self.timing_changed();
+    }
+
+    // https://drafts.csswg.org/web-animations/#finishing-an-animation-section
+    fn Finish(&self) -> ErrorResult {
+        // Step 1.
+        let effective_playback_rate = self.effective_playback_rate();
+        let effect_end = self.effect_end();
+        if effective_playback_rate == 0. ||
+            (effective_playback_rate > 0. && effect_end.is_infinite())
+        {
+            return Err(Error::InvalidState);
+        }
+
+        // Step 2.
+        self.apply_pending_playback_rate();
+
+        // Step 3.
+        let playback_rate = self.playback_rate.get();
+        let limit = if playback_rate > 0. { effect_end } else { 0. };
+
+        // Step 4.
+        self.silently_set_current_time(Some(limit))?;
+
+        // Step 5.
+        if self.start_time.get().is_none() {
+            if let Some(timeline_time) = self.timeline_time() {
+                self.start_time
+                    .set(Some(timeline_time - limit / playback_rate));
+            }
+        }
+
+        // Steps 6-7.
+        if self.start_time.get().is_some() {
+            if self.pending_task.get() == Some(PendingTask::Pause) {
+                self.hold_time.set(None);
+            }
+            if self.pending_task.take().is_some() {
+                self.resolve_ready_promise();
+            }
+        }
+
+        // Step 8.
+        self.update_finished_state(true, true);
+        self.timing_changed();
+        Ok(())
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-animation-play
+    fn Play(&self) -> ErrorResult {
+        self.play(true)
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-animation-pause
+    fn Pause(&self) -> ErrorResult {
+        self.pause()
+    }
+
+    // https://drafts.csswg.org/web-animations/#seamlessly-update-the-playback-rate
+    fn UpdatePlaybackRate(&self, playback_rate: Finite<f64>) {
+        // Step 1.
+        let previous_play_state = self.play_state();
+
+        // Step 2.
+        self.pending_playback_rate.set(Some(*playback_rate));
+
+        // Step 3.
+        if self.is_pending() {
+            return;
+        }
+
+        // Step 4.
+        match previous_play_state {
+            AnimationPlayState::Idle | AnimationPlayState::Paused => {
+                self.apply_pending_playback_rate();
+            },
+            AnimationPlayState::Finished => {
+                let unconstrained_current_time = self.current_time_with_hold_time(None);
+                if let Some(timeline_time) = self.timeline_time() {
+                    if *playback_rate == 0. {
+                        self.start_time.set(Some(timeline_time));
+                    } else if let Some(current_time) = unconstrained_current_time {
+                        self.start_time
+                            .set(Some(timeline_time - current_time / *playback_rate));
+                    }
+                }
+                self.apply_pending_playback_rate();
+                self.update_finished_state(false, false);
+            },
+            AnimationPlayState::Running => {
+                let _ = self.play(false);
+            },
+        }
+        self.timing_changed();
+    }
+
+    // https://drafts.csswg.org/web-animations/#reversing-an-animation-section
+    fn Reverse(&self) -> ErrorResult {
+        // Step 1.
+        if self.timeline_time().is_none() {
+            return Err(Error::InvalidState);
+        }
+
+        // Step 2.
+        let original_pending_playback_rate = self.pending_playback_rate.get();
+
+        // Step 3.
+        self.pending_playback_rate
+            .set(Some(-self.effective_playback_rate()));
+
+        // Step 4.
+        let result = self.play(true);
+        if result.is_err() {
+            self.pending_playback_rate
+                .set(original_pending_playback_rate);
+        }
+        result
+    }
+}
+
+#[derive(JSTraceable, MallocSizeOf)]
+pub enum AnimationMicrotask {
+    FinishNotificationSteps {
+        animation: DomRoot<Animation>,
+        id: u32,
+    },
+}
+
+impl MicrotaskRunnable for AnimationMicrotask {
+    fn handler(&self) {
+        match self {
+            &AnimationMicrotask::FinishNotificationSteps { ref animation, id } => {
+                if id == animation.finish_notification_id.get() {
+                    animation.finish_notification_queued.set(false);
+                    animation.finish_notification_steps();
+                }
+            },
+        }
+    }
+}
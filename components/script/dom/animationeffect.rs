@@ -0,0 +1,482 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::animation::Animation;
+use crate::dom::bindings::codegen::Bindings::AnimationBinding::AnimationPlayState;
+use crate::dom::bindings::codegen::Bindings::AnimationEffectBinding::{
+    AnimationEffectMethods, ComputedEffectTiming, EffectTiming, FillMode, OptionalEffectTiming,
+    PlaybackDirection,
+};
+use crate::dom::bindings::codegen::UnionTypes::UnrestrictedDoubleOrString;
+use crate::dom::bindings::error::{Error, Fallible};
+use crate::dom::bindings::num::Finite;
+use crate::dom::bindings::reflector::{DomObject, Reflector};
+use crate::dom::bindings::root::{DomRoot, MutNullableDom};
+use crate::dom::bindings::str::DOMString;
+use crate::dom::window::Window;
+use cssparser::{Parser, ParserInput};
+use dom_struct::dom_struct;
+use std::cell::Cell;
+use std::f64;
+use style::animation::timing_function_output;
+use style::context::QuirksMode;
+use style::parser::{Parse, ParserContext};
+use style::stylesheets::CssRuleType;
+use style::values::computed::TimingFunction;
+use style::values::generics::easing::{TimingFunction as GenericTimingFunction, TimingKeyword};
+use style::values::specified::easing::TimingFunction as SpecifiedTimingFunction;
+use style_traits::{ParsingMode, ToCss};
+
+/// The timing properties of an animation effect.
+///
+/// <https://drafts.csswg.org/web-animations/#timing-properties>
+#[derive(Clone, Copy, JSTraceable, MallocSizeOf)]
+pub struct Timing {
+    pub delay: f64,
+    pub end_delay: f64,
+    pub fill: FillMode,
+    pub iteration_start: f64,
+    pub iterations: f64,
+    /// The iteration duration in milliseconds, `None` meaning `auto`.
+    pub duration: Option<f64>,
+    pub direction: PlaybackDirection,
+    pub easing: TimingFunction,
+}
+
+impl Default for Timing {
+    fn default() -> Timing {
+        Timing {
+            delay: 0.,
+            end_delay: 0.,
+            fill: FillMode::Auto,
+            iteration_start: 0.,
+            iterations: 1.,
+            duration: None,
+            direction: PlaybackDirection::Normal,
+            easing: GenericTimingFunction::Keyword(TimingKeyword::Linear),
+        }
+    }
+}
+
+impl Timing {
+    /// <https://drafts.csswg.org/web-animations/#update-the-timing-properties-of-an-animation-effect>
+    pub fn from_effect_timing(window: &Window, timing: &EffectTiming) -> Fallible<Timing> {
+        let mut result = Timing::default();
+        result.update(
+            window,
+            &OptionalEffectTiming {
+                delay: Some(timing.delay),
+                endDelay: Some(timing.endDelay),
+                fill: Some(timing.fill),
+                iterationStart: Some(timing.iterationStart),
+                iterations: Some(timing.iterations),
+                duration: timing.duration.as_ref().map(|duration| match *duration {
+                    UnrestrictedDoubleOrString::UnrestrictedDouble(duration) => {
+                        UnrestrictedDoubleOrString::UnrestrictedDouble(duration)
+                    },
+                    UnrestrictedDoubleOrString::String(ref duration) => {
+                        UnrestrictedDoubleOrString::String(duration.clone())
+                    },
+                }),
+                direction: Some(timing.direction),
+                easing: Some(timing.easing.clone()),
+            },
+        )?;
+        Ok(result)
+    }
+
+    /// Creates timing properties with the given iteration duration, as when
+    /// passing a number as the options of a keyframe effect.
+    pub fn from_duration(window: &Window, duration: f64) -> Fallible<Timing> {
+        let mut result = Timing::default();
+        result.update(
+            window,
+            &OptionalEffectTiming {
+                delay: None,
+                endDelay: None,
+                fill: None,
+                iterationStart: None,
+                iterations: None,
+                duration: Some(UnrestrictedDoubleOrString::UnrestrictedDouble(duration)),
+                direction: None,
+                easing: None,
+            },
+        )?;
+        Ok(result)
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#update-the-timing-properties-of-an-animation-effect>
+    pub fn update(&mut self, window: &Window, timing: &OptionalEffectTiming) -> Fallible<()> {
+        // Step 1.
+        if let Some(iteration_start) = timing.iterationStart {
+            if *iteration_start < 0. {
+                return Err(Error::Type(
+                    "iterationStart must not be negative".to_owned(),
+                ));
+            }
+        }
+        if let Some(iterations) = timing.iterations {
+            if iterations < 0. || iterations.is_nan() {
+                return Err(Error::Type(
+                    "iterations must be a positive number".to_owned(),
+                ));
+            }
+        }
+        let duration = match timing.duration {
+            Some(UnrestrictedDoubleOrString::UnrestrictedDouble(duration)) => {
+                if duration < 0. || duration.is_nan() {
+                    return Err(Error::Type("duration must be a positive number".to_owned()));
+                }
+                Some(Some(duration))
+            },
+            Some(UnrestrictedDoubleOrString::String(ref duration)) => {
+                if &**duration != "auto" {
+                    return Err(Error::Type("duration must be a number or auto".to_owned()));
+                }
+                Some(None)
+            },
+            None => None,
+        };
+
+        // Step 2.
+        let easing = match timing.easing {
+            Some(ref easing) => Some(parse_easing(window, easing)?),
+            None => None,
+        };
+
+        // Step 3.
+        if let Some(delay) = timing.delay {
+            self.delay = *delay;
+        }
+        if let Some(end_delay) = timing.endDelay {
+            self.end_delay = *end_delay;
+        }
+        if let Some(fill) = timing.fill {
+            self.fill = fill;
+        }
+        if let Some(iteration_start) = timing.iterationStart {
+            self.iteration_start = *iteration_start;
+        }
+        if let Some(iterations) = timing.iterations {
+            self.iterations = iterations;
+        }
+        if let Some(duration) = duration {
+            self.duration = duration;
+        }
+        if let Some(direction) = timing.direction {
+            self.direction = direction;
+        }
+        if let Some(easing) = easing {
+            self.easing = easing;
+        }
+        Ok(())
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#iteration-duration>
+    pub fn iteration_duration(&self) -> f64 {
+        self.duration.unwrap_or(0.)
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#active-duration>
+    pub fn active_duration(&self) -> f64 {
+        let duration = self.iteration_duration();
+        if duration == 0. || self.iterations == 0. {
+            return 0.;
+        }
+        duration * self.iterations
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#end-time>
+    pub fn end_time(&self) -> f64 {
+        (self.delay + self.active_duration() + self.end_delay).max(0.)
+    }
+
+    fn to_effect_timing(&self) -> EffectTiming {
+        let duration = match self.duration {
+            Some(duration) => UnrestrictedDoubleOrString::UnrestrictedDouble(duration),
+            None => UnrestrictedDoubleOrString::String(DOMString::from("auto")),
+        };
+        EffectTiming {
+            delay: Finite::wrap(self.delay),
+            endDelay: Finite::wrap(self.end_delay),
+            fill: self.fill,
+            iterationStart: Finite::wrap(self.iteration_start),
+            iterations: self.iterations,
+            duration: Some(duration),
+            direction: self.direction,
+            easing: DOMString::from(self.easing.to_css_string()),
+        }
+    }
+}
+
+/// Parses an `easing` member into a timing function.
+///
+/// <https://drafts.csswg.org/web-animations/#dom-effecttiming-easing>
+pub fn parse_easing(window: &Window, easing: &str) -> Fallible<TimingFunction> {
+    let url = window.get_url();
+    let context = ParserContext::new_for_cssom(
+        &url,
+        Some(CssRuleType::Style),
+        ParsingMode::DEFAULT,
+        QuirksMode::NoQuirks,
+        None,
+        None,
+    );
+    let mut input = ParserInput::new(easing);
+    let mut parser = Parser::new(&mut input);
+    parser
+        .parse_entirely(|parser| SpecifiedTimingFunction::parse(&context, parser))
+        .map(|easing| easing.to_computed_value_without_context())
+        .map_err(|_| Error::Type(format!("'{}' is not a valid easing", easing)))
+}
+
+/// <https://drafts.csswg.org/web-animations/#animation-effect-phases-and-states>
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Phase {
+    Before,
+    Active,
+    After,
+    Idle,
+}
+
+/// <https://drafts.csswg.org/web-animations/#the-animationeffect-interface>
+#[dom_struct]
+pub struct AnimationEffect {
+    reflector_: Reflector,
+    timing: Cell<Timing>,
+    /// The animation this effect is associated with, if any.
+    animation: MutNullableDom<Animation>,
+}
+
+impl AnimationEffect {
+    pub fn new_inherited(timing: Timing) -> AnimationEffect {
+        AnimationEffect {
+            reflector_: Reflector::new(),
+            timing: Cell::new(timing),
+            animation: Default::default(),
+        }
+    }
+
+    pub fn timing(&self) -> Timing {
+        self.timing.get()
+    }
+
+    pub fn set_timing(&self, timing: Timing) {
+        self.timing.set(timing);
+        if let Some(animation) = self.animation.get() {
+            animation.effect_changed();
+        }
+    }
+
+    pub fn animation(&self) -> Option<DomRoot<Animation>> {
+        self.animation.get()
+    }
+
+    pub fn set_animation(&self, animation: Option<&Animation>) {
+        self.animation.set(animation);
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#local-time>
+    pub fn local_time(&self) -> Option<f64> {
+        self.animation
+            .get()
+            .and_then(|animation| animation.current_time())
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#animation-effect-phases-and-states>
+    pub fn phase(&self) -> Phase {
+        let local_time = match self.local_time() {
+            Some(local_time) => local_time,
+            None => return Phase::Idle,
+        };
+        let timing = self.timing();
+        let end_time = timing.end_time();
+        let active_duration = timing.active_duration();
+        let before_active_boundary_time = timing.delay.min(end_time).max(0.);
+        let active_after_boundary_time = (timing.delay + active_duration).min(end_time).max(0.);
+        let backwards = self
+            .animation
+            .get()
+            .map_or(false, |animation| animation.playback_rate() < 0.);
+
+        if local_time < before_active_boundary_time ||
+            (backwards && local_time == before_active_boundary_time)
+        {
+            return Phase::Before;
+        }
+        if local_time > active_after_boundary_time ||
+            (!backwards && local_time == active_after_boundary_time)
+        {
+            return Phase::After;
+        }
+        Phase::Active
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#in-play>
+    pub fn is_in_play(&self) -> bool {
+        self.phase() == Phase::Active &&
+            self.animation.get().map_or(false, |animation| {
+                animation.play_state() != AnimationPlayState::Finished
+            })
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#current>
+    pub fn is_current(&self) -> bool {
+        if self.is_in_play() {
+            return true;
+        }
+        let animation = match self.animation.get() {
+            Some(animation) => animation,
+            None => return false,
+        };
+        let phase = self.phase();
+        (animation.playback_rate() > 0. && phase == Phase::Before) ||
+            (animation.playback_rate() < 0. && phase == Phase::After)
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#in-effect>
+    pub fn is_in_effect(&self) -> bool {
+        self.active_time().is_some()
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#calculating-the-active-time>
+    pub fn active_time(&self) -> Option<f64> {
+        let timing = self.timing();
+        let local_time = self.local_time()?;
+        // The "auto" fill mode is equivalent to "none" for keyframe effects.
+        let fills_backwards = timing.fill == FillMode::Backwards || timing.fill == FillMode::Both;
+        let fills_forwards = timing.fill == FillMode::Forwards || timing.fill == FillMode::Both;
+        match self.phase() {
+            Phase::Before if fills_backwards => Some((local_time - timing.delay).max(0.)),
+            Phase::Active => Some(local_time - timing.delay),
+            Phase::After if fills_forwards => Some(
+                (local_time - timing.delay)
+                    .min(timing.active_duration())
+                    .max(0.),
+            ),
+            _ => None,
+        }
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#calculating-the-overall-progress>
+    fn overall_progress(&self) -> Option<f64> {
+        let timing = self.timing();
+        let active_time = self.active_time()?;
+        let duration = timing.iteration_duration();
+        if duration == 0. {
+            if self.phase() == Phase::Before {
+                return Some(timing.iteration_start);
+            }
+            return Some(timing.iteration_start + timing.iterations);
+        }
+        Some(active_time / duration + timing.iteration_start)
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#calculating-the-simple-iteration-progress>
+    fn simple_iteration_progress(&self) -> Option<f64> {
+        let timing = self.timing();
+        let overall_progress = self.overall_progress()?;
+        let mut progress = if overall_progress.is_infinite() {
+            timing.iteration_start % 1.
+        } else {
+            overall_progress % 1.
+        };
+        let phase = self.phase();
+        if progress == 0. &&
+            (phase == Phase::Active || phase == Phase::After) &&
+            self.active_time() == Some(timing.active_duration()) &&
+            timing.iterations != 0.
+        {
+            progress = 1.;
+        }
+        Some(progress)
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#calculating-the-current-iteration>
+    pub fn current_iteration(&self) -> Option<f64> {
+        let timing = self.timing();
+        self.active_time()?;
+        if self.phase() == Phase::After && timing.iterations.is_infinite() {
+            return Some(f64::INFINITY);
+        }
+        let overall_progress = self.overall_progress()?;
+        if self.simple_iteration_progress() == Some(1.) {
+            return Some(overall_progress.floor() - 1.);
+        }
+        Some(overall_progress.floor())
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#calculating-the-directed-progress>
+    fn directed_progress(&self) -> Option<f64> {
+        let progress = self.simple_iteration_progress()?;
+        let forwards = match self.timing().direction {
+            PlaybackDirection::Normal => true,
+            PlaybackDirection::Reverse => false,
+            direction => {
+                let mut iteration = self.current_iteration()?;
+                if direction == PlaybackDirection::Alternate_reverse {
+                    iteration += 1.;
+                }
+                iteration.is_infinite() || iteration % 2. == 0.
+            },
+        };
+        if forwards {
+            Some(progress)
+        } else {
+            Some(1. - progress)
+        }
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#calculating-the-transformed-progress>
+    pub fn transformed_progress(&self) -> Option<f64> {
+        let progress = self.directed_progress()?;
+        let timing = self.timing();
+        Some(timing_function_output(
+            timing.easing,
+            progress,
+            easing_epsilon(timing.iteration_duration()),
+        ))
+    }
+}
+
+/// The precision with which timing functions are evaluated for an
+/// iteration duration given in milliseconds.
+pub fn easing_epsilon(duration: f64) -> f64 {
+    1. / (200. * (duration / 1000.).max(0.001).min(1e6))
+}
+
+impl AnimationEffectMethods for AnimationEffect {
+    // https://drafts.csswg.org/web-animations/#dom-animationeffect-gettiming
+    fn GetTiming(&self) -> EffectTiming {
+        self.timing().to_effect_timing()
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-animationeffect-getcomputedtiming
+    fn GetComputedTiming(&self) -> ComputedEffectTiming {
+        let timing = self.timing();
+        let mut parent = timing.to_effect_timing();
+        if parent.fill == FillMode::Auto {
+            parent.fill = FillMode::None;
+        }
+        if timing.duration.is_none() {
+            parent.duration = Some(UnrestrictedDoubleOrString::UnrestrictedDouble(0.));
+        }
+        ComputedEffectTiming {
+            parent,
+            endTime: Some(timing.end_time()),
+            activeDuration: Some(timing.active_duration()),
+            localTime: Some(self.local_time().map(Finite::wrap)),
+            progress: Some(self.transformed_progress().map(Finite::wrap)),
+            currentIteration: Some(self.current_iteration()),
+        }
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-animationeffect-updatetiming
+    fn UpdateTiming(&self, timing: &OptionalEffectTiming) -> Fallible<()> {
+        let mut new_timing = self.timing();
+        new_timing.update(self.global().as_window(), timing)?;
+        self.set_timing(new_timing);
+        Ok(())
+    }
+}
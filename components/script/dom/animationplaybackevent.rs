@@ -0,0 +1,78 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::codegen::Bindings::AnimationPlaybackEventBinding;
+use crate::dom::bindings::codegen::Bindings::AnimationPlaybackEventBinding::{
+    AnimationPlaybackEventInit, AnimationPlaybackEventMethods,
+};
+use crate::dom::bindings::codegen::Bindings::EventBinding::EventMethods;
+use crate::dom::bindings::error::Fallible;
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::num::Finite;
+use crate::dom::bindings::reflector::reflect_dom_object;
+use crate::dom::bindings::root::DomRoot;
+use crate::dom::bindings::str::DOMString;
+use crate::dom::event::Event;
+use crate::dom::window::Window;
+use dom_struct::dom_struct;
+use servo_atoms::Atom;
+
+#[dom_struct]
+pub struct AnimationPlaybackEvent {
+    event: Event,
+    current_time: Option<Finite<f64>>,
+    timeline_time: Option<Finite<f64>>,
+}
+
+impl AnimationPlaybackEvent {
+    fn new_inherited(init: &AnimationPlaybackEventInit) -> AnimationPlaybackEvent {
+        AnimationPlaybackEvent {
+            event: Event::new_inherited(),
+            current_time: init.currentTime,
+            timeline_time: init.timelineTime,
+        }
+    }
+
+    pub fn new(
+        window: &Window,
+        type_: Atom,
+        init: &AnimationPlaybackEventInit,
+    ) -> DomRoot<AnimationPlaybackEvent> {
+        let ev = reflect_dom_object(
+            Box::new(AnimationPlaybackEvent::new_inherited(init)),
+            window,
+            AnimationPlaybackEventBinding::Wrap,
+        );
+        {
+            let event = ev.upcast::<Event>();
+            event.init_event(type_, init.parent.bubbles, init.parent.cancelable);
+        }
+        ev
+    }
+
+    pub fn Constructor(
+        window: &Window,
+        type_: DOMString,
+        init: &AnimationPlaybackEventInit,
+    ) -> Fallible<DomRoot<AnimationPlaybackEvent>> {
+        Ok(AnimationPlaybackEvent::new(window, Atom::from(type_), init))
+    }
+}
+
+impl AnimationPlaybackEventMethods for AnimationPlaybackEvent {
+    // https://drafts.csswg.org/web-animations/#dom-animationplaybackevent-currenttime
+    fn GetCurrentTime(&self) -> Option<Finite<f64>> {
+        self.current_time
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-animationplaybackevent-timelinetime
+    fn GetTimelineTime(&self) -> Option<Finite<f64>> {
+        self.timeline_time
+    }
+
+    // https://dom.spec.whatwg.org/#dom-event-istrusted
+    fn IsTrusted(&self) -> bool {
+        self.upcast::<Event>().IsTrusted()
+    }
+}
@@ -0,0 +1,98 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::animation::Animation;
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::AnimationBinding::AnimationPlayState;
+use crate::dom::bindings::codegen::Bindings::AnimationTimelineBinding::AnimationTimelineMethods;
+use crate::dom::bindings::num::Finite;
+use crate::dom::bindings::reflector::Reflector;
+use crate::dom::bindings::root::{Dom, DomRoot};
+use dom_struct::dom_struct;
+use std::cell::Cell;
+
+/// <https://drafts.csswg.org/web-animations/#timelines>
+#[dom_struct]
+pub struct AnimationTimeline {
+    reflector_: Reflector,
+    /// The current time of the timeline, `None` while it is inactive.
+    current_time: Cell<Option<f64>>,
+    /// The animations associated with this timeline which may still need
+    /// to be updated when the timeline ticks.
+    animations: DomRefCell<Vec<Dom<Animation>>>,
+}
+
+impl AnimationTimeline {
+    pub fn new_inherited(current_time: Option<f64>) -> AnimationTimeline {
+        AnimationTimeline {
+            reflector_: Reflector::new(),
+            current_time: Cell::new(current_time),
+            animations: Default::default(),
+        }
+    }
+
+    pub fn current_time(&self) -> Option<f64> {
+        self.current_time.get()
+    }
+
+    pub fn set_current_time(&self, current_time: Option<f64>) {
+        self.current_time.set(current_time);
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#inactive-timeline>
+    pub fn is_active(&self) -> bool {
+        self.current_time.get().is_some()
+    }
+
+    /// Makes sure the given animation gets updated when this timeline ticks.
+    pub fn associate_animation(&self, animation: &Animation) {
+        let mut animations = self.animations.borrow_mut();
+        if !animations.iter().any(|a| &**a == animation) {
+            animations.push(Dom::from_ref(animation));
+        }
+    }
+
+    pub fn animations(&self) -> Vec<DomRoot<Animation>> {
+        self.animations
+            .borrow()
+            .iter()
+            .map(|animation| DomRoot::from_ref(&**animation))
+            .collect()
+    }
+
+    /// Whether any animation of this timeline needs the timeline to keep ticking.
+    pub fn has_running_animations(&self) -> bool {
+        self.animations.borrow().iter().any(|animation| {
+            animation.is_pending() || animation.play_state() == AnimationPlayState::Running
+        })
+    }
+
+    /// Runs the pending tasks of the animations of this timeline and updates
+    /// their finished state.
+    ///
+    /// <https://drafts.csswg.org/web-animations/#update-animations-and-send-events>
+    pub fn update_animations(&self) {
+        for animation in self.animations() {
+            if let Some(ready_time) = self.current_time() {
+                animation.run_pending_task(ready_time);
+            }
+            animation.update_finished_state(false, false);
+        }
+
+        // Stop tracking animations which do not need to be ticked anymore.
+        self.animations.borrow_mut().retain(|animation| {
+            animation
+                .timeline()
+                .map_or(false, |timeline| &*timeline == self) &&
+                (animation.is_pending() || animation.is_relevant())
+        });
+    }
+}
+
+impl AnimationTimelineMethods for AnimationTimeline {
+    // https://drafts.csswg.org/web-animations/#dom-animationtimeline-currenttime
+    fn GetCurrentTime(&self) -> Option<Finite<f64>> {
+        self.current_time.get().map(Finite::wrap)
+    }
+}
@@ -20,7 +20,7 @@ use js::jsapi::{AddRawValueRoot, IsCallable, JSObject};
 use js::jsapi::{EnterRealm, LeaveRealm, Realm, RemoveRawValueRoot};
 use js::jsval::{JSVal, ObjectValue, UndefinedValue};
 use js::rust::wrappers::{JS_GetProperty, JS_WrapObject};
-use js::rust::{MutableHandleObject, Runtime};
+use js::rust::{HandleObject, MutableHandleObject, Runtime};
 use std::default::Default;
 use std::ffi::CString;
 use std::mem::drop;
@@ -205,9 +205,31 @@ impl CallbackInterface {
     }
 }
 
+/// An object that can be used as the `this` value of a callback call.
+pub trait ThisReflector {
+    /// The JS object to use as `this`.
+    fn jsobject(&self) -> *mut JSObject;
+}
+
+impl<T: DomObject> ThisReflector for T {
+    fn jsobject(&self) -> *mut JSObject {
+        self.reflector().get_jsobject().get()
+    }
+}
+
+impl<'a> ThisReflector for HandleObject<'a> {
+    fn jsobject(&self) -> *mut JSObject {
+        self.get()
+    }
+}
+
 /// Wraps the reflector for `p` into the compartment of `cx`.
-pub fn wrap_call_this_object<T: DomObject>(cx: JSContext, p: &T, mut rval: MutableHandleObject) {
-    rval.set(p.reflector().get_jsobject().get());
+pub fn wrap_call_this_object<T: ThisReflector>(
+    cx: JSContext,
+    p: &T,
+    mut rval: MutableHandleObject,
+) {
+    rval.set(p.jsobject());
     assert!(!rval.get().is_null());
 
     unsafe {
@@ -6052,6 +6052,7 @@ def generate_imports(config, cgthings, descriptors, callbacks=None, dictionaries
         'crate::dom::bindings::callback::CallbackFunction',
         'crate::dom::bindings::callback::CallbackObject',
         'crate::dom::bindings::callback::ExceptionHandling',
+        'crate::dom::bindings::callback::ThisReflector',
         'crate::dom::bindings::callback::wrap_call_this_object',
         'crate::dom::bindings::conversions::ConversionBehavior',
         'crate::dom::bindings::conversions::ConversionResult',
@@ -6957,7 +6958,7 @@ class CGCallback(CGClass):
             })
         return [ClassMethod(method.name + '_', method.returnType, args,
                             bodyInHeader=True,
-                            templateArgs=["T: DomObject"],
+                            templateArgs=["T: ThisReflector"],
                             body=bodyWithThis,
                             visibility='pub'),
                 ClassMethod(method.name + '__', method.returnType, argsWithoutThis,
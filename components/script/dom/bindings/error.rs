@@ -87,6 +87,18 @@ pub enum Error {
     NotReadable,
     /// OperationError DOMException
     Operation,
+    /// UnknownError DOMException
+    Unknown,
+    /// ConstraintError DOMException
+    Constraint,
+    /// DataError DOMException
+    Data,
+    /// TransactionInactiveError DOMException
+    TransactionInactive,
+    /// ReadOnlyError DOMException
+    ReadOnly,
+    /// VersionError DOMException
+    Version,
 
     /// TypeError JavaScript Error
     Type(String),
@@ -140,6 +152,12 @@ pub fn throw_dom_exception(cx: SafeJSContext, global: &GlobalScope, result: Erro
         Error::InvalidModification => DOMErrorName::InvalidModificationError,
         Error::NotReadable => DOMErrorName::NotReadableError,
         Error::Operation => DOMErrorName::OperationError,
+        Error::Unknown => DOMErrorName::UnknownError,
+        Error::Constraint => DOMErrorName::ConstraintError,
+        Error::Data => DOMErrorName::DataError,
+        Error::TransactionInactive => DOMErrorName::TransactionInactiveError,
+        Error::ReadOnly => DOMErrorName::ReadOnlyError,
+        Error::Version => DOMErrorName::VersionError,
         Error::Type(message) => unsafe {
             assert!(!JS_IsExceptionPending(*cx));
             throw_type_error(*cx, &message);
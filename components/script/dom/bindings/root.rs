@@ -284,6 +284,7 @@ where
 ///
 /// This should only be used as a field in other DOM objects.
 #[must_root]
+#[repr(transparent)]
 pub struct Dom<T> {
     ptr: ptr::NonNull<T>,
 }
@@ -344,6 +345,7 @@ unsafe impl<T: DomObject> JSTraceable for Dom<T> {
 /// An unrooted reference to a DOM object for use in layout. `Layout*Helpers`
 /// traits must be implemented on this.
 #[allow_unrooted_interior]
+#[repr(transparent)]
 pub struct LayoutDom<T> {
     ptr: ptr::NonNull<T>,
 }
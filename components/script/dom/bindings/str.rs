@@ -347,10 +347,66 @@ impl DOMString {
     /// https://html.spec.whatwg.org/multipage/#best-representation-of-the-number-as-a-floating-point-number
     pub fn set_best_representation_of_the_floating_point_number(&mut self) {
         if let Ok(val) = parse_floating_point_number(&self.0) {
-            self.0 = val.to_string();
+            // TODO(#19773): need consider `min`, `max`, `step`, when they are implemented
+            self.0 = val.round().to_string();
         }
     }
 
+    /// https://html.spec.whatwg.org/multipage/#rules-for-parsing-floating-point-number-values
+    pub fn parse_floating_point_number(&self) -> Option<f64> {
+        if !self.is_valid_floating_point_number_string() {
+            return None;
+        }
+        parse_floating_point_number(&self.0).ok()
+    }
+
+    /// The number of milliseconds elapsed from midnight UTC on 1970-01-01 to midnight UTC
+    /// of the date this `DOMString` represents.
+    /// https://html.spec.whatwg.org/multipage/#date-state-(type=date):concept-input-value-string-number
+    pub fn date_string_to_number(&self) -> Option<f64> {
+        let (year, month, day) = parse_date_string(&self.0).ok()?;
+        let date = Utc.ymd_opt(year as i32, month, day).single()?;
+        Some(date.and_hms(0, 0, 0).timestamp() as f64 * 1000.0)
+    }
+
+    /// The number of months between January 1970 and the month this `DOMString` represents.
+    /// https://html.spec.whatwg.org/multipage/#month-state-(type=month):concept-input-value-string-number
+    pub fn month_string_to_number(&self) -> Option<f64> {
+        let (year, month) = parse_month_string(&self.0).ok()?;
+        Some((year as f64 - 1970.0) * 12.0 + (month as f64 - 1.0))
+    }
+
+    /// The number of milliseconds elapsed from midnight UTC on 1970-01-01 to midnight UTC
+    /// on the Monday of the week this `DOMString` represents.
+    /// https://html.spec.whatwg.org/multipage/#week-state-(type=week):concept-input-value-string-number
+    pub fn week_string_to_number(&self) -> Option<f64> {
+        let (year, week) = parse_week_string(&self.0).ok()?;
+        let monday = Utc.isoywd_opt(year as i32, week, Weekday::Mon).single()?;
+        Some(monday.and_hms(0, 0, 0).timestamp() as f64 * 1000.0)
+    }
+
+    /// The number of milliseconds elapsed from midnight to the time this `DOMString`
+    /// represents.
+    /// https://html.spec.whatwg.org/multipage/#time-state-(type=time):concept-input-value-string-number
+    pub fn time_string_to_number(&self) -> Option<f64> {
+        if !self.is_valid_time_string() {
+            return None;
+        }
+        let (hour, minute, second) = parse_time_component(&self.0).ok()?;
+        Some(((hour * 60 + minute) * 60) as f64 * 1000.0 + second as f64 * 1000.0)
+    }
+
+    /// The number of milliseconds elapsed from midnight on 1970-01-01 to the local date
+    /// and time this `DOMString` represents, ignoring time zones.
+    /// https://html.spec.whatwg.org/multipage/#local-date-and-time-state-(type=datetime-local):concept-input-value-string-number
+    pub fn local_date_and_time_string_to_number(&self) -> Option<f64> {
+        let ((year, month, day), (hour, minute, second)) =
+            parse_local_date_and_time_string(&self.0).ok()?;
+        let date = Utc.ymd_opt(year as i32, month, day).single()?;
+        let day_start = date.and_hms(0, 0, 0).timestamp() as f64 * 1000.0;
+        Some(day_start + ((hour * 60 + minute) * 60) as f64 * 1000.0 + second as f64 * 1000.0)
+    }
+
     /// A valid normalized local date and time string should be "{date}T{time}"
     /// where date and time are both valid, and the time string must be as short as possible
     /// https://html.spec.whatwg.org/multipage/#valid-normalised-local-date-and-time-string
@@ -729,9 +785,8 @@ fn parse_floating_point_number(input: &str) -> Result<f64, ()> {
                 val.is_infinite() || val.is_nan() || input.ends_with(".") || input.starts_with("+")
             ) =>
         {
-            // TODO(#19773): need consider `min`, `max`, `step`, when they are implemented
-            Ok(val.round())
-        }
+            Ok(val)
+        },
         _ => Err(()),
     }
 }
@@ -4,14 +4,21 @@
 
 //! This module implements structured cloning, as defined by [HTML]
 //! (https://html.spec.whatwg.org/multipage/#safe-passing-of-structured-data).
+//!
+//! `StructuredCloneData` is used for data that is only ever stored as bytes,
+//! while `write` and `read` support transferring objects along with messages.
 
 use crate::compartments::enter_realm;
-use crate::dom::bindings::conversions::root_from_handleobject;
+use crate::dom::bindings::conversions::{root_from_handleobject, ToJSValConvertible};
 use crate::dom::bindings::error::{Error, Fallible};
 use crate::dom::bindings::reflector::DomObject;
 use crate::dom::bindings::root::DomRoot;
 use crate::dom::blob::{Blob, BlobImpl};
 use crate::dom::globalscope::GlobalScope;
+use crate::dom::imagebitmap::ImageBitmap;
+use crate::dom::messageport::MessagePort;
+use crate::dom::offscreencanvas::OffscreenCanvas;
+use crate::script_runtime::JSContext as SafeJSContext;
 use js::glue::CopyJSStructuredCloneData;
 use js::glue::DeleteJSAutoStructuredCloneBuffer;
 use js::glue::GetLengthOfJSStructuredCloneData;
@@ -28,9 +35,16 @@ use js::jsapi::{JSObject, JS_ClearPendingException};
 use js::jsapi::{JSStructuredCloneCallbacks, JSStructuredCloneReader, JSStructuredCloneWriter};
 use js::jsapi::{JS_ReadBytes, JS_WriteBytes};
 use js::jsapi::{JS_ReadUint32Pair, JS_WriteUint32Pair};
+use js::jsval::UndefinedValue;
 use js::rust::wrappers::{JS_ReadStructuredClone, JS_WriteStructuredClone};
-use js::rust::{Handle, HandleValue, MutableHandleValue};
+use js::rust::{CustomAutoRooterGuard, Handle, HandleValue};
+use js::rust::{MutableHandleObject, MutableHandleValue};
 use libc::size_t;
+use msg::constellation_msg::{MessagePortId, MessagePortIndex, PipelineNamespaceId};
+use script_traits::transferable::{ImageBitmapImpl, MessagePortImpl, OffscreenCanvasImpl};
+use script_traits::StructuredSerializedData;
+use std::collections::HashMap;
+use std::num::NonZeroU32;
 use std::os::raw;
 use std::ptr;
 use std::slice;
@@ -44,6 +58,9 @@ enum StructuredCloneTags {
     /// To support additional types, add new tags with values incremented from the last one before Max.
     Min = 0xFFFF8000,
     DomBlob = 0xFFFF8001,
+    MessagePort = 0xFFFF8002,
+    ImageBitmap = 0xFFFF8003,
+    OffscreenCanvas = 0xFFFF8004,
     Max = 0xFFFFFFFF,
 }
 
@@ -155,11 +172,59 @@ unsafe fn write_blob(blob: DomRoot<Blob>, w: *mut JSStructuredCloneWriter) -> Re
     return Ok(());
 }
 
+unsafe fn read_image_bitmap(
+    cx: *mut JSContext,
+    r: *mut JSStructuredCloneReader,
+    origin_clean: u32,
+    sc_holder: &mut StructuredCloneHolder,
+) -> *mut JSObject {
+    let structured_reader = StructuredCloneReader { r: r };
+    let mut width: u32 = 0;
+    let mut height: u32 = 0;
+    assert!(JS_ReadUint32Pair(
+        r,
+        &mut width as *mut u32,
+        &mut height as *mut u32
+    ));
+    let data = structured_reader.read_bytes();
+    let target_global = GlobalScope::from_context(cx);
+    let bitmap = ImageBitmap::new_transferred(
+        &target_global,
+        ImageBitmapImpl {
+            width,
+            height,
+            data,
+            origin_clean: origin_clean != 0,
+        },
+    );
+    let js_object = bitmap.reflector().get_jsobject().get();
+    sc_holder.image_bitmaps.push(bitmap);
+    js_object
+}
+
+/// <https://html.spec.whatwg.org/multipage/#the-imagebitmap-interface:serialization-steps>
+unsafe fn write_image_bitmap(
+    bitmap: DomRoot<ImageBitmap>,
+    w: *mut JSStructuredCloneWriter,
+) -> Result<(), ()> {
+    let structured_writer = StructuredCloneWriter { w: w };
+    // A detached bitmap can't be serialized.
+    let bitmap = bitmap.serialize().ok_or(())?;
+    assert!(JS_WriteUint32Pair(
+        w,
+        StructuredCloneTags::ImageBitmap as u32,
+        bitmap.origin_clean as u32
+    ));
+    assert!(JS_WriteUint32Pair(w, bitmap.width, bitmap.height));
+    structured_writer.write_slice(&bitmap.data);
+    Ok(())
+}
+
 unsafe extern "C" fn read_callback(
     cx: *mut JSContext,
     r: *mut JSStructuredCloneReader,
     tag: u32,
-    _data: u32,
+    data: u32,
     closure: *mut raw::c_void,
 ) -> *mut JSObject {
     assert!(
@@ -173,6 +238,9 @@ unsafe extern "C" fn read_callback(
     if tag == StructuredCloneTags::DomBlob as u32 {
         return read_blob(cx, r, &mut *(closure as *mut StructuredCloneHolder));
     }
+    if tag == StructuredCloneTags::ImageBitmap as u32 {
+        return read_image_bitmap(cx, r, data, &mut *(closure as *mut StructuredCloneHolder));
+    }
     return ptr::null_mut();
 }
 
@@ -185,31 +253,137 @@ unsafe extern "C" fn write_callback(
     if let Ok(blob) = root_from_handleobject::<Blob>(Handle::from_raw(obj), cx) {
         return write_blob(blob, w).is_ok();
     }
+    if let Ok(bitmap) = root_from_handleobject::<ImageBitmap>(Handle::from_raw(obj), cx) {
+        return write_image_bitmap(bitmap, w).is_ok();
+    }
     return false;
 }
 
+/// Packs a port id into the extra data of a transfer.
+fn message_port_id_to_extra_data(id: MessagePortId) -> u64 {
+    let PipelineNamespaceId(namespace_id) = id.namespace_id;
+    let MessagePortIndex(index) = id.index;
+    ((namespace_id as u64) << 32) | index.get() as u64
+}
+
+/// Unpacks a port id from the extra data of a transfer.
+fn message_port_id_from_extra_data(extra_data: u64) -> Option<MessagePortId> {
+    NonZeroU32::new(extra_data as u32).map(|index| MessagePortId {
+        namespace_id: PipelineNamespaceId((extra_data >> 32) as u32),
+        index: MessagePortIndex(index),
+    })
+}
+
 unsafe extern "C" fn read_transfer_callback(
-    _cx: *mut JSContext,
+    cx: *mut JSContext,
     _r: *mut JSStructuredCloneReader,
-    _tag: u32,
+    tag: u32,
     _content: *mut raw::c_void,
-    _extra_data: u64,
-    _closure: *mut raw::c_void,
-    _return_object: RawMutableHandleObject,
+    extra_data: u64,
+    closure: *mut raw::c_void,
+    return_object: RawMutableHandleObject,
 ) -> bool {
-    false
+    let sc_holder = &mut *(closure as *mut StructuredCloneHolder);
+    if tag == StructuredCloneTags::ImageBitmap as u32 {
+        let bitmap_impl = sc_holder
+            .transferred_image_bitmaps
+            .as_mut()
+            .and_then(|bitmaps| bitmaps.remove(&extra_data));
+        let bitmap_impl = match bitmap_impl {
+            Some(bitmap_impl) => bitmap_impl,
+            None => return false,
+        };
+        let owner = GlobalScope::from_context(cx);
+        let bitmap = ImageBitmap::new_transferred(&owner, bitmap_impl);
+        MutableHandleObject::from_raw(return_object).set(bitmap.reflector().get_jsobject().get());
+        sc_holder.image_bitmaps.push(bitmap);
+        return true;
+    }
+    if tag == StructuredCloneTags::OffscreenCanvas as u32 {
+        let canvas_impl = sc_holder
+            .transferred_offscreen_canvases
+            .as_mut()
+            .and_then(|canvases| canvases.remove(&extra_data));
+        let canvas_impl = match canvas_impl {
+            Some(canvas_impl) => canvas_impl,
+            None => return false,
+        };
+        let owner = GlobalScope::from_context(cx);
+        let canvas = OffscreenCanvas::new_transferred(&owner, canvas_impl);
+        MutableHandleObject::from_raw(return_object).set(canvas.reflector().get_jsobject().get());
+        sc_holder.offscreen_canvases.push(canvas);
+        return true;
+    }
+    if tag != StructuredCloneTags::MessagePort as u32 {
+        return false;
+    }
+    let port_impl = message_port_id_from_extra_data(extra_data)
+        .and_then(|id| sc_holder.ports.as_mut().and_then(|ports| ports.remove(&id)));
+    let port_impl = match port_impl {
+        Some(port_impl) => port_impl,
+        None => return false,
+    };
+    let owner = GlobalScope::from_context(cx);
+    let port = MessagePort::new_transferred(&owner, port_impl);
+    MutableHandleObject::from_raw(return_object).set(port.reflector().get_jsobject().get());
+    sc_holder.message_ports.push(port);
+    true
 }
 
+/// <https://html.spec.whatwg.org/multipage/#transfer-steps>
 unsafe extern "C" fn write_transfer_callback(
-    _cx: *mut JSContext,
-    _obj: RawHandleObject,
-    _closure: *mut raw::c_void,
-    _tag: *mut u32,
-    _ownership: *mut TransferableOwnership,
+    cx: *mut JSContext,
+    obj: RawHandleObject,
+    closure: *mut raw::c_void,
+    tag: *mut u32,
+    ownership: *mut TransferableOwnership,
     _content: *mut *mut raw::c_void,
-    _extra_data: *mut u64,
+    extra_data: *mut u64,
 ) -> bool {
-    false
+    let sc_holder = &mut *(closure as *mut StructuredCloneHolder);
+    if let Ok(bitmap) = root_from_handleobject::<ImageBitmap>(Handle::from_raw(obj), cx) {
+        let bitmap_impl = match bitmap.transfer() {
+            Some(bitmap_impl) => bitmap_impl,
+            None => return false,
+        };
+        let bitmaps = sc_holder
+            .transferred_image_bitmaps
+            .get_or_insert_with(HashMap::new);
+        let index = bitmaps.len() as u64;
+        bitmaps.insert(index, bitmap_impl);
+        *tag = StructuredCloneTags::ImageBitmap as u32;
+        *ownership = TransferableOwnership::SCTAG_TMO_CUSTOM;
+        *extra_data = index;
+        return true;
+    }
+    if let Ok(canvas) = root_from_handleobject::<OffscreenCanvas>(Handle::from_raw(obj), cx) {
+        let canvas_impl = match canvas.transfer() {
+            Some(canvas_impl) => canvas_impl,
+            None => return false,
+        };
+        let canvases = sc_holder
+            .transferred_offscreen_canvases
+            .get_or_insert_with(HashMap::new);
+        let index = canvases.len() as u64;
+        canvases.insert(index, canvas_impl);
+        *tag = StructuredCloneTags::OffscreenCanvas as u32;
+        *ownership = TransferableOwnership::SCTAG_TMO_CUSTOM;
+        *extra_data = index;
+        return true;
+    }
+    let port = match root_from_handleobject::<MessagePort>(Handle::from_raw(obj), cx) {
+        Ok(port) => port,
+        Err(()) => return false,
+    };
+    let port_impl = port.transfer();
+    *tag = StructuredCloneTags::MessagePort as u32;
+    *ownership = TransferableOwnership::SCTAG_TMO_CUSTOM;
+    *extra_data = message_port_id_to_extra_data(port_impl.message_port_id);
+    sc_holder
+        .ports
+        .get_or_insert_with(HashMap::new)
+        .insert(port_impl.message_port_id, port_impl);
+    true
 }
 
 unsafe extern "C" fn free_transfer_callback(
@@ -222,11 +396,21 @@ unsafe extern "C" fn free_transfer_callback(
 }
 
 unsafe extern "C" fn can_transfer_callback(
-    _cx: *mut JSContext,
-    _obj: RawHandleObject,
+    cx: *mut JSContext,
+    obj: RawHandleObject,
     _closure: *mut raw::c_void,
 ) -> bool {
-    false
+    // A detached port, bitmap or canvas can't be transferred again.
+    if let Ok(bitmap) = root_from_handleobject::<ImageBitmap>(Handle::from_raw(obj), cx) {
+        return !bitmap.is_detached();
+    }
+    if let Ok(canvas) = root_from_handleobject::<OffscreenCanvas>(Handle::from_raw(obj), cx) {
+        return !canvas.is_detached();
+    }
+    match root_from_handleobject::<MessagePort>(Handle::from_raw(obj), cx) {
+        Ok(port) => !port.detached(),
+        Err(()) => false,
+    }
 }
 
 unsafe extern "C" fn report_error_callback(_cx: *mut JSContext, _errorid: u32) {}
@@ -241,8 +425,72 @@ static STRUCTURED_CLONE_CALLBACKS: JSStructuredCloneCallbacks = JSStructuredClon
     canTransfer: Some(can_transfer_callback),
 };
 
+#[derive(Default)]
 struct StructuredCloneHolder {
     blob: Option<DomRoot<Blob>>,
+    /// The ports transferred along with the message.
+    ports: Option<HashMap<MessagePortId, MessagePortImpl>>,
+    /// The ports created while reading the message.
+    message_ports: Vec<DomRoot<MessagePort>>,
+    /// The image bitmaps transferred along with the message.
+    transferred_image_bitmaps: Option<HashMap<u64, ImageBitmapImpl>>,
+    /// The image bitmaps created while reading the message.
+    image_bitmaps: Vec<DomRoot<ImageBitmap>>,
+    /// The offscreen canvases transferred along with the message.
+    transferred_offscreen_canvases: Option<HashMap<u64, OffscreenCanvasImpl>>,
+    /// The offscreen canvases created while reading the message.
+    offscreen_canvases: Vec<DomRoot<OffscreenCanvas>>,
+}
+
+/// Writes a structured clone, transferring the objects in `transfer`.
+/// Returns a `DataClone` error if that fails.
+///
+/// <https://html.spec.whatwg.org/multipage/#structuredserializewithtransfer>
+pub fn write(
+    cx: SafeJSContext,
+    message: HandleValue,
+    transfer: Option<CustomAutoRooterGuard<Vec<*mut JSObject>>>,
+) -> Fallible<StructuredSerializedData> {
+    rooted!(in(*cx) let mut transfer_list = UndefinedValue());
+    if let Some(transfer) = transfer {
+        unsafe { transfer.to_jsval(*cx, transfer_list.handle_mut()) };
+    }
+    let mut sc_holder = StructuredCloneHolder::default();
+    let serialized = unsafe {
+        StructuredCloneData::write_clone(*cx, message, transfer_list.handle(), &mut sc_holder)?
+    };
+    Ok(StructuredSerializedData {
+        serialized,
+        ports: sc_holder.ports,
+        image_bitmaps: sc_holder.transferred_image_bitmaps,
+        offscreen_canvases: sc_holder.transferred_offscreen_canvases,
+    })
+}
+
+/// Reads a structured clone, returning the ports that were transferred with it.
+///
+/// <https://html.spec.whatwg.org/multipage/#structureddeserializewithtransfer>
+pub fn read(
+    global: &GlobalScope,
+    data: StructuredSerializedData,
+    rval: MutableHandleValue,
+) -> Vec<DomRoot<MessagePort>> {
+    let StructuredSerializedData {
+        mut serialized,
+        ports,
+        image_bitmaps,
+        offscreen_canvases,
+    } = data;
+    let mut sc_holder = StructuredCloneHolder {
+        ports,
+        transferred_image_bitmaps: image_bitmaps,
+        transferred_offscreen_canvases: offscreen_canvases,
+        ..Default::default()
+    };
+    let nbytes = serialized.len();
+    let data = serialized.as_mut_ptr() as *mut u64;
+    StructuredCloneData::read_clone(global, data, nbytes, rval, &mut sc_holder);
+    sc_holder.message_ports
 }
 
 /// A buffer for a structured clone.
@@ -257,40 +505,52 @@ impl StructuredCloneData {
     // TODO: should this be unsafe?
     /// Writes a structured clone. Returns a `DataClone` error if that fails.
     pub fn write(cx: *mut JSContext, message: HandleValue) -> Fallible<StructuredCloneData> {
-        unsafe {
-            let scbuf = NewJSAutoStructuredCloneBuffer(
-                StructuredCloneScope::DifferentProcess,
-                &STRUCTURED_CLONE_CALLBACKS,
-            );
-            let scdata = &mut ((*scbuf).data_);
-            let policy = CloneDataPolicy {
-                // TODO: SAB?
-                sharedArrayBuffer_: false,
-            };
-            let result = JS_WriteStructuredClone(
-                cx,
-                message,
-                scdata,
-                StructuredCloneScope::DifferentProcess,
-                policy,
-                &STRUCTURED_CLONE_CALLBACKS,
-                ptr::null_mut(),
-                HandleValue::undefined(),
-            );
-            if !result {
-                JS_ClearPendingException(cx);
-                return Err(Error::DataClone);
-            }
+        let mut sc_holder = StructuredCloneHolder::default();
+        let transfer = HandleValue::undefined();
+        let data =
+            unsafe { StructuredCloneData::write_clone(cx, message, transfer, &mut sc_holder)? };
+        Ok(StructuredCloneData::Vector(data))
+    }
 
-            let nbytes = GetLengthOfJSStructuredCloneData(scdata);
-            let mut data = Vec::with_capacity(nbytes);
-            CopyJSStructuredCloneData(scdata, data.as_mut_ptr());
-            data.set_len(nbytes);
+    unsafe fn write_clone(
+        cx: *mut JSContext,
+        message: HandleValue,
+        transfer: HandleValue,
+        sc_holder: &mut StructuredCloneHolder,
+    ) -> Fallible<Vec<u8>> {
+        let sc_holder_ptr = sc_holder as *mut _;
+        let scbuf = NewJSAutoStructuredCloneBuffer(
+            StructuredCloneScope::DifferentProcess,
+            &STRUCTURED_CLONE_CALLBACKS,
+        );
+        let scdata = &mut ((*scbuf).data_);
+        let policy = CloneDataPolicy {
+            // TODO: SAB?
+            sharedArrayBuffer_: false,
+        };
+        let result = JS_WriteStructuredClone(
+            cx,
+            message,
+            scdata,
+            StructuredCloneScope::DifferentProcess,
+            policy,
+            &STRUCTURED_CLONE_CALLBACKS,
+            sc_holder_ptr as *mut raw::c_void,
+            transfer,
+        );
+        if !result {
+            JS_ClearPendingException(cx);
+            return Err(Error::DataClone);
+        }
 
-            DeleteJSAutoStructuredCloneBuffer(scbuf);
+        let nbytes = GetLengthOfJSStructuredCloneData(scdata);
+        let mut data = Vec::with_capacity(nbytes);
+        CopyJSStructuredCloneData(scdata, data.as_mut_ptr());
+        data.set_len(nbytes);
 
-            Ok(StructuredCloneData::Vector(data))
-        }
+        DeleteJSAutoStructuredCloneBuffer(scbuf);
+
+        Ok(data)
     }
 
     /// Converts a StructuredCloneData to Vec<u8> for inter-thread sharing
@@ -306,11 +566,16 @@ impl StructuredCloneData {
     /// Reads a structured clone.
     ///
     /// Panics if `JS_ReadStructuredClone` fails.
-    fn read_clone(global: &GlobalScope, data: *mut u64, nbytes: size_t, rval: MutableHandleValue) {
+    fn read_clone(
+        global: &GlobalScope,
+        data: *mut u64,
+        nbytes: size_t,
+        rval: MutableHandleValue,
+        sc_holder: &mut StructuredCloneHolder,
+    ) {
         let cx = global.get_cx();
         let _ac = enter_realm(&*global);
-        let mut sc_holder = StructuredCloneHolder { blob: None };
-        let sc_holder_ptr = &mut sc_holder as *mut _;
+        let sc_holder_ptr = sc_holder as *mut _;
         unsafe {
             let scbuf = NewJSAutoStructuredCloneBuffer(
                 StructuredCloneScope::DifferentProcess,
@@ -340,10 +605,12 @@ impl StructuredCloneData {
             StructuredCloneData::Vector(mut vec_msg) => {
                 let nbytes = vec_msg.len();
                 let data = vec_msg.as_mut_ptr() as *mut u64;
-                StructuredCloneData::read_clone(global, data, nbytes, rval);
+                let mut sc_holder = StructuredCloneHolder::default();
+                StructuredCloneData::read_clone(global, data, nbytes, rval, &mut sc_holder);
             },
             StructuredCloneData::Struct(data, nbytes) => {
-                StructuredCloneData::read_clone(global, data, nbytes, rval)
+                let mut sc_holder = StructuredCloneHolder::default();
+                StructuredCloneData::read_clone(global, data, nbytes, rval, &mut sc_holder)
             },
         }
     }
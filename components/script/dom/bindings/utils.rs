@@ -7,6 +7,7 @@
 use crate::dom::bindings::codegen::InterfaceObjectMap;
 use crate::dom::bindings::codegen::PrototypeList;
 use crate::dom::bindings::codegen::PrototypeList::{MAX_PROTO_CHAIN_LENGTH, PROTO_OR_IFACE_LENGTH};
+use crate::dom::bindings::conversions::ToJSValConvertible;
 use crate::dom::bindings::conversions::{jsstring_to_str, private_from_proto_check};
 use crate::dom::bindings::error::throw_invalid_this;
 use crate::dom::bindings::inheritance::TopTypeId;
@@ -30,6 +31,7 @@ use js::jsapi::{JS_ResolveStandardClass, JS_StringHasLatin1Chars, ObjectOpResult
 use js::jsval::{JSVal, UndefinedValue};
 use js::rust::wrappers::JS_DeletePropertyById;
 use js::rust::wrappers::JS_ForwardGetPropertyTo;
+use js::rust::wrappers::JS_FreezeObject;
 use js::rust::wrappers::JS_GetProperty;
 use js::rust::wrappers::JS_GetPrototype;
 use js::rust::wrappers::JS_HasProperty;
@@ -587,3 +589,14 @@ impl AsCCharPtrPtr for [u8] {
         self as *const [u8] as *const c_char
     }
 }
+
+/// Converts a sequence of values to a frozen JS array.
+///
+/// <https://heycam.github.io/webidl/#es-frozen-array>
+pub fn to_frozen_array<T: ToJSValConvertible>(convertibles: &[T], cx: SafeJSContext) -> JSVal {
+    rooted!(in(*cx) let mut array = UndefinedValue());
+    unsafe { convertibles.to_jsval(*cx, array.handle_mut()) };
+    rooted!(in(*cx) let obj = array.to_object());
+    unsafe { JS_FreezeObject(*cx, obj.handle()) };
+    array.get()
+}
@@ -0,0 +1,96 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::ByteLengthQueuingStrategyBinding;
+use crate::dom::bindings::codegen::Bindings::ByteLengthQueuingStrategyBinding::ByteLengthQueuingStrategyMethods;
+use crate::dom::bindings::codegen::Bindings::FunctionBinding::Function;
+use crate::dom::bindings::codegen::Bindings::QueuingStrategyBinding::QueuingStrategyInit;
+use crate::dom::bindings::error::{throw_dom_exception, Error};
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::DomRoot;
+use crate::dom::bindings::utils::get_dictionary_property;
+use crate::dom::globalscope::GlobalScope;
+use crate::script_runtime::JSContext as SafeJSContext;
+use crate::streams::create_size_function;
+use dom_struct::dom_struct;
+use js::jsapi::{CallArgs, JSContext};
+use js::jsval::JSVal;
+use js::rust::{HandleValue, MutableHandleValue};
+use std::rc::Rc;
+
+/// <https://streams.spec.whatwg.org/#blqs-class>
+#[dom_struct]
+pub struct ByteLengthQueuingStrategy {
+    reflector_: Reflector,
+    high_water_mark: f64,
+    #[ignore_malloc_size_of = "Rc"]
+    size: DomRefCell<Option<Rc<Function>>>,
+}
+
+impl ByteLengthQueuingStrategy {
+    fn new_inherited(high_water_mark: f64) -> ByteLengthQueuingStrategy {
+        ByteLengthQueuingStrategy {
+            reflector_: Reflector::new(),
+            high_water_mark,
+            size: DomRefCell::new(None),
+        }
+    }
+
+    pub fn new(global: &GlobalScope, high_water_mark: f64) -> DomRoot<ByteLengthQueuingStrategy> {
+        reflect_dom_object(
+            Box::new(ByteLengthQueuingStrategy::new_inherited(high_water_mark)),
+            global,
+            ByteLengthQueuingStrategyBinding::Wrap,
+        )
+    }
+
+    // https://streams.spec.whatwg.org/#blqs-constructor
+    pub fn Constructor(
+        global: &GlobalScope,
+        init: &QueuingStrategyInit,
+    ) -> DomRoot<ByteLengthQueuingStrategy> {
+        ByteLengthQueuingStrategy::new(global, init.highWaterMark)
+    }
+}
+
+/// <https://streams.spec.whatwg.org/#byte-length-queuing-strategy-size-function>
+#[allow(unsafe_code)]
+unsafe extern "C" fn byte_length_queuing_strategy_size(
+    cx: *mut JSContext,
+    argc: u32,
+    vp: *mut JSVal,
+) -> bool {
+    let args = CallArgs::from_vp(vp, argc);
+    let chunk = HandleValue::from_raw(args.get(0));
+    if !chunk.is_object() {
+        let global = GlobalScope::from_context(cx);
+        throw_dom_exception(
+            SafeJSContext::from_ptr(cx),
+            &global,
+            Error::Type("The chunk has no byteLength".to_owned()),
+        );
+        return false;
+    }
+    rooted!(in(cx) let chunk = chunk.to_object());
+    let rval = MutableHandleValue::from_raw(args.rval());
+    get_dictionary_property(cx, chunk.handle(), "byteLength", rval).is_ok()
+}
+
+impl ByteLengthQueuingStrategyMethods for ByteLengthQueuingStrategy {
+    // https://streams.spec.whatwg.org/#blqs-high-water-mark
+    fn HighWaterMark(&self) -> f64 {
+        self.high_water_mark
+    }
+
+    // https://streams.spec.whatwg.org/#blqs-size
+    fn Size(&self) -> Rc<Function> {
+        self.size
+            .borrow_mut()
+            .get_or_insert_with(|| {
+                create_size_function(&self.global(), Some(byte_length_queuing_strategy_size))
+            })
+            .clone()
+    }
+}
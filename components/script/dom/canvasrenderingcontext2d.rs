@@ -4,11 +4,14 @@
 
 use crate::dom::bindings::cell::DomRefCell;
 use crate::dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding;
+use crate::dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasDirection;
 use crate::dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasFillRule;
 use crate::dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasImageSource;
 use crate::dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasLineCap;
 use crate::dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasLineJoin;
 use crate::dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasRenderingContext2DMethods;
+use crate::dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasTextAlign;
+use crate::dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasTextBaseline;
 use crate::dom::bindings::codegen::Bindings::ImageDataBinding::ImageDataMethods;
 use crate::dom::bindings::codegen::UnionTypes::StringOrCanvasGradientOrCanvasPattern;
 use crate::dom::bindings::error::{Error, ErrorResult, Fallible};
@@ -23,10 +26,14 @@ use crate::dom::element::Element;
 use crate::dom::globalscope::GlobalScope;
 use crate::dom::htmlcanvaselement::{CanvasContext, HTMLCanvasElement};
 use crate::dom::imagedata::ImageData;
-use crate::dom::node::{Node, NodeDamage};
+use crate::dom::node::{document_from_node, Node, NodeDamage, ShadowIncluding};
+use crate::dom::path2d::Path2D;
+use crate::dom::textmetrics::TextMetrics;
 use crate::unpremultiplytable::UNPREMULTIPLY_TABLE;
+use canvas_traits::canvas::TextMetrics as CanvasTextMetrics;
 use canvas_traits::canvas::{Canvas2dMsg, CanvasId, CanvasMsg};
 use canvas_traits::canvas::{CompositionOrBlending, FillOrStrokeStyle, FillRule};
+use canvas_traits::canvas::{Direction, TextAlign, TextBaseline};
 use canvas_traits::canvas::{LineCapStyle, LineJoinStyle, LinearGradientStyle};
 use canvas_traits::canvas::{RadialGradientStyle, RepetitionStyle};
 use cssparser::Color as CSSColor;
@@ -34,7 +41,7 @@ use cssparser::{Parser, ParserInput, RGBA};
 use dom_struct::dom_struct;
 use euclid::{
     default::{Point2D, Rect, Size2D, Transform2D},
-    vec2,
+    vec2, Scale,
 };
 use ipc_channel::ipc::{self, IpcSender};
 use net_traits::image_cache::CanRequestImages;
@@ -47,10 +54,22 @@ use pixels::PixelFormat;
 use profile_traits::ipc as profiled_ipc;
 use script_traits::ScriptMsg;
 use servo_url::ServoUrl;
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
 use std::str::FromStr;
 use std::sync::Arc;
 use std::{fmt, mem};
+use style::context::QuirksMode;
+use style::font_metrics::get_metrics_provider_for_product;
+use style::media_queries::{Device, MediaType};
+use style::properties::{parse_one_declaration_into, SourcePropertyDeclaration};
+use style::properties::{ComputedValues, Importance, PropertyDeclaration};
+use style::properties::{PropertyDeclarationBlock, PropertyId, ShorthandId, StyleBuilder};
+use style::rule_cache::RuleCacheConditions;
+use style::values::computed::{Context, ToComputedValue};
+use style::values::specified::font::FontSize as SpecifiedFontSize;
+use style::values::specified::font::FontWeight as SpecifiedFontWeight;
+use style::values::specified::LineHeight;
+use style_traits::ParsingMode;
 
 #[must_root]
 #[derive(Clone, JSTraceable, MallocSizeOf)]
@@ -88,6 +107,10 @@ struct CanvasContextState {
     shadow_offset_y: f64,
     shadow_blur: f64,
     shadow_color: RGBA,
+    font: String,
+    text_align: TextAlign,
+    text_baseline: TextBaseline,
+    direction: CanvasDirection,
 }
 
 impl CanvasContextState {
@@ -108,10 +131,28 @@ impl CanvasContextState {
             shadow_offset_y: 0.0,
             shadow_blur: 0.0,
             shadow_color: RGBA::transparent(),
+            font: String::from("10px sans-serif"),
+            text_align: TextAlign::Start,
+            text_baseline: TextBaseline::Alphabetic,
+            direction: CanvasDirection::Inherit,
         }
     }
 }
 
+/// Creates a canvas of the given size in the canvas paint thread, returning the
+/// sender to that thread along with the id of the new canvas.
+pub fn create_canvas(global: &GlobalScope, size: Size2D<u64>) -> (IpcSender<CanvasMsg>, CanvasId) {
+    let (sender, receiver) = profiled_ipc::channel(global.time_profiler_chan().clone()).unwrap();
+    let script_to_constellation_chan = global.script_to_constellation_chan();
+    debug!("Asking constellation to create new canvas thread.");
+    script_to_constellation_chan
+        .send(ScriptMsg::CreateCanvasPaintThread(size, sender))
+        .unwrap();
+    let canvas = receiver.recv().unwrap();
+    debug!("Done.");
+    canvas
+}
+
 #[must_root]
 #[derive(JSTraceable, MallocSizeOf)]
 pub struct CanvasState {
@@ -133,15 +174,16 @@ pub struct CanvasState {
 impl CanvasState {
     pub fn new(global: &GlobalScope, size: Size2D<u64>) -> CanvasState {
         debug!("Creating new canvas rendering context.");
-        let (sender, receiver) =
-            profiled_ipc::channel(global.time_profiler_chan().clone()).unwrap();
-        let script_to_constellation_chan = global.script_to_constellation_chan();
-        debug!("Asking constellation to create new canvas thread.");
-        script_to_constellation_chan
-            .send(ScriptMsg::CreateCanvasPaintThread(size, sender))
-            .unwrap();
-        let (ipc_renderer, canvas_id) = receiver.recv().unwrap();
-        debug!("Done.");
+        let (ipc_renderer, canvas_id) = create_canvas(global, size);
+        CanvasState::new_for_canvas(global, ipc_renderer, canvas_id)
+    }
+
+    /// Creates the state of a context drawing to an existing canvas of the canvas paint thread.
+    pub fn new_for_canvas(
+        global: &GlobalScope,
+        ipc_renderer: IpcSender<CanvasMsg>,
+        canvas_id: CanvasId,
+    ) -> CanvasState {
         CanvasState {
             ipc_renderer: ipc_renderer,
             canvas_id: canvas_id,
@@ -194,6 +236,7 @@ impl CanvasState {
             CanvasImageSource::HTMLImageElement(image) => {
                 image.same_origin(GlobalScope::entry().origin())
             },
+            CanvasImageSource::ImageBitmap(bitmap) => bitmap.origin_is_clean(),
             CanvasImageSource::CSSStyleValue(_) => true,
         }
     }
@@ -209,8 +252,10 @@ impl CanvasState {
         };
 
         let image_size = Size2D::new(img.width, img.height);
+        // Animated images are drawn using their first frame.
+        // https://html.spec.whatwg.org/multipage/#canvasimagesource
         let image_data = match img.format {
-            PixelFormat::BGRA8 => img.bytes.to_vec(),
+            PixelFormat::BGRA8 => img.first_frame().to_vec(),
             pixel_format => unimplemented!("unsupported pixel format ({:?})", pixel_format),
         };
 
@@ -342,6 +387,23 @@ impl CanvasState {
                 let url = image.get_url().ok_or(Error::InvalidState)?;
                 self.fetch_and_draw_image_data(htmlcanvas, url, sx, sy, sw, sh, dx, dy, dw, dh)
             },
+            CanvasImageSource::ImageBitmap(ref bitmap) => {
+                // A bitmap that was closed or transferred is no longer usable.
+                let image_data = bitmap.bitmap_data().ok_or(Error::InvalidState)?;
+                self.draw_image_data(
+                    htmlcanvas,
+                    image_data,
+                    bitmap.size(),
+                    sx,
+                    sy,
+                    sw,
+                    sh,
+                    dx,
+                    dy,
+                    dw,
+                    dh,
+                )
+            },
             CanvasImageSource::CSSStyleValue(ref value) => {
                 let url = value
                     .get_url(self.base_url.clone())
@@ -402,6 +464,21 @@ impl CanvasState {
                         smoothing_enabled,
                     ));
                 },
+                CanvasContext::Placeholder(ref offscreen_canvas) => {
+                    let (ipc_renderer, canvas_id) = offscreen_canvas
+                        .placeholder_canvas()
+                        .ok_or(Error::InvalidState)?;
+                    let msg = Canvas2dMsg::DrawImageInOther(
+                        self.get_canvas_id(),
+                        image_size,
+                        dest_rect,
+                        source_rect,
+                        smoothing_enabled,
+                    );
+                    ipc_renderer
+                        .send(CanvasMsg::Canvas2d(msg, canvas_id))
+                        .unwrap();
+                },
                 _ => return Err(Error::InvalidState),
             }
         } else {
@@ -434,6 +511,26 @@ impl CanvasState {
         debug!("Fetching image {}.", url);
         let (mut image_data, image_size) = self.fetch_image_data(url).ok_or(Error::InvalidState)?;
         pixels::rgba8_premultiply_inplace(&mut image_data);
+        self.draw_image_data(
+            canvas, image_data, image_size, sx, sy, sw, sh, dx, dy, dw, dh,
+        )
+    }
+
+    /// Draws premultiplied BGRA `image_data` of the given size.
+    fn draw_image_data(
+        &self,
+        canvas: Option<&HTMLCanvasElement>,
+        image_data: Vec<u8>,
+        image_size: Size2D<u32>,
+        sx: f64,
+        sy: f64,
+        sw: Option<f64>,
+        sh: Option<f64>,
+        dx: f64,
+        dy: f64,
+        dw: Option<f64>,
+        dh: Option<f64>,
+    ) -> ErrorResult {
         let image_size = image_size.to_f64();
 
         let dw = dw.unwrap_or(image_size.width);
@@ -785,6 +882,10 @@ impl CanvasState {
                     .unwrap_or_else(|| vec![0; size.area() as usize * 4]);
                 (data, size)
             },
+            CanvasImageSource::ImageBitmap(ref bitmap) => {
+                let data = bitmap.bitmap_data().ok_or(Error::InvalidState)?;
+                (data, bitmap.size())
+            },
             CanvasImageSource::CSSStyleValue(ref value) => value
                 .get_url(self.base_url.clone())
                 .and_then(|url| self.fetch_image_data(url))
@@ -867,12 +968,228 @@ impl CanvasState {
         self.state.borrow_mut().image_smoothing_enabled = value;
     }
 
+    /// Resolves the `direction` attribute to the direction text is drawn in,
+    /// looking at the `dir` attribute of the canvas element and its ancestors
+    /// when it is `inherit`.
+    /// <https://html.spec.whatwg.org/multipage/#dom-context-2d-direction>
+    fn resolved_direction(&self, canvas: Option<&HTMLCanvasElement>) -> Direction {
+        match self.state.borrow().direction {
+            CanvasDirection::Ltr => return Direction::Ltr,
+            CanvasDirection::Rtl => return Direction::Rtl,
+            CanvasDirection::Inherit => {},
+        }
+        let canvas = match canvas {
+            Some(canvas) => canvas,
+            None => return Direction::Ltr,
+        };
+        for ancestor in canvas
+            .upcast::<Node>()
+            .inclusive_ancestors(ShadowIncluding::Yes)
+            .filter_map(DomRoot::downcast::<Element>)
+        {
+            let dir = ancestor.get_string_attribute(&local_name!("dir"));
+            if dir.eq_ignore_ascii_case("ltr") {
+                return Direction::Ltr;
+            }
+            if dir.eq_ignore_ascii_case("rtl") {
+                return Direction::Rtl;
+            }
+        }
+        Direction::Ltr
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-font
+    // Relative values are resolved against the computed font of the canvas element, or
+    // against the default 10px sans-serif if it has none, so that the canvas thread
+    // only ever sees absolute values. `line-height` is forced to `normal`.
+    fn resolve_canvas_font(
+        &self,
+        canvas: Option<&HTMLCanvasElement>,
+        block: &PropertyDeclarationBlock,
+    ) -> Option<String> {
+        let device = match canvas {
+            Some(canvas) => document_from_node(canvas).device(),
+            None => Device::new(MediaType::screen(), euclid::Size2D::zero(), Scale::new(1.0)),
+        };
+        let canvas_style = canvas.and_then(|canvas| {
+            canvas
+                .upcast::<Element>()
+                .style()
+                .filter(|style| !style.get_box().clone_display().is_none())
+        });
+        let parent_style = match canvas_style {
+            Some(style) => style,
+            None => {
+                let default_font = parse_canvas_font("10px sans-serif", &self.base_url)?;
+                compute_canvas_font(&default_font, &device, None)
+            },
+        };
+        let font_style = compute_canvas_font(block, &device, Some(&*parent_style));
+
+        let mut resolved = PropertyDeclarationBlock::new();
+        for declaration in block.declarations() {
+            let declaration = match *declaration {
+                PropertyDeclaration::FontSize(_) => {
+                    PropertyDeclaration::FontSize(SpecifiedFontSize::from_computed_value(
+                        &font_style.get_font().clone_font_size(),
+                    ))
+                },
+                PropertyDeclaration::FontWeight(SpecifiedFontWeight::Bolder) |
+                PropertyDeclaration::FontWeight(SpecifiedFontWeight::Lighter) => {
+                    PropertyDeclaration::FontWeight(SpecifiedFontWeight::from_computed_value(
+                        &font_style.get_font().clone_font_weight(),
+                    ))
+                },
+                PropertyDeclaration::LineHeight(_) => {
+                    PropertyDeclaration::LineHeight(LineHeight::normal())
+                },
+                ref declaration => declaration.clone(),
+            };
+            resolved.push(declaration, Importance::Normal);
+        }
+
+        let mut font = String::new();
+        resolved
+            .property_value_to_css(&PropertyId::Shorthand(ShorthandId::Font), &mut font)
+            .ok()?;
+        Some(font)
+    }
+
+    /// Sends the resolved direction to the canvas thread; with `inherit` it
+    /// follows the canvas element and may have changed since the last call.
+    fn update_text_direction(&self, canvas: Option<&HTMLCanvasElement>) {
+        let direction = self.resolved_direction(canvas);
+        self.send_canvas_2d_msg(Canvas2dMsg::SetDirection(direction));
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-filltext
-    pub fn FillText(&self, text: DOMString, x: f64, y: f64, max_width: Option<f64>) {
+    pub fn FillText(
+        &self,
+        canvas: Option<&HTMLCanvasElement>,
+        text: DOMString,
+        x: f64,
+        y: f64,
+        max_width: Option<f64>,
+    ) {
+        if !(x.is_finite() && y.is_finite() && max_width.map_or(true, |w| w.is_finite())) {
+            return;
+        }
+        self.update_text_direction(canvas);
         let parsed_text: String = text.into();
         self.send_canvas_2d_msg(Canvas2dMsg::FillText(parsed_text, x, y, max_width));
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-stroketext
+    pub fn StrokeText(
+        &self,
+        canvas: Option<&HTMLCanvasElement>,
+        text: DOMString,
+        x: f64,
+        y: f64,
+        max_width: Option<f64>,
+    ) {
+        if !(x.is_finite() && y.is_finite() && max_width.map_or(true, |w| w.is_finite())) {
+            return;
+        }
+        self.update_text_direction(canvas);
+        let parsed_text: String = text.into();
+        self.send_canvas_2d_msg(Canvas2dMsg::StrokeText(parsed_text, x, y, max_width));
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-measuretext
+    pub fn MeasureText(
+        &self,
+        global: &GlobalScope,
+        canvas: Option<&HTMLCanvasElement>,
+        text: DOMString,
+    ) -> DomRoot<TextMetrics> {
+        self.update_text_direction(canvas);
+        let (sender, receiver) =
+            profiled_ipc::channel::<CanvasTextMetrics>(global.time_profiler_chan().clone())
+                .unwrap();
+        self.send_canvas_2d_msg(Canvas2dMsg::MeasureText(text.into(), sender));
+        TextMetrics::new(global, receiver.recv().unwrap())
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-font
+    pub fn Font(&self) -> DOMString {
+        DOMString::from(self.state.borrow().font.clone())
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-font
+    pub fn SetFont(&self, canvas: Option<&HTMLCanvasElement>, value: DOMString) {
+        let block = match parse_canvas_font(&value, &self.base_url) {
+            Some(block) => block,
+            None => return,
+        };
+        let font = match self.resolve_canvas_font(canvas, &block) {
+            Some(font) => font,
+            None => return,
+        };
+        self.state.borrow_mut().font = font.clone();
+        self.send_canvas_2d_msg(Canvas2dMsg::SetFont(font));
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-textalign
+    pub fn TextAlign(&self) -> CanvasTextAlign {
+        match self.state.borrow().text_align {
+            TextAlign::Start => CanvasTextAlign::Start,
+            TextAlign::End => CanvasTextAlign::End,
+            TextAlign::Left => CanvasTextAlign::Left,
+            TextAlign::Right => CanvasTextAlign::Right,
+            TextAlign::Center => CanvasTextAlign::Center,
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-textalign
+    pub fn SetTextAlign(&self, value: CanvasTextAlign) {
+        let text_align = match value {
+            CanvasTextAlign::Start => TextAlign::Start,
+            CanvasTextAlign::End => TextAlign::End,
+            CanvasTextAlign::Left => TextAlign::Left,
+            CanvasTextAlign::Right => TextAlign::Right,
+            CanvasTextAlign::Center => TextAlign::Center,
+        };
+        self.state.borrow_mut().text_align = text_align;
+        self.send_canvas_2d_msg(Canvas2dMsg::SetTextAlign(text_align));
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-textbaseline
+    pub fn TextBaseline(&self) -> CanvasTextBaseline {
+        match self.state.borrow().text_baseline {
+            TextBaseline::Top => CanvasTextBaseline::Top,
+            TextBaseline::Hanging => CanvasTextBaseline::Hanging,
+            TextBaseline::Middle => CanvasTextBaseline::Middle,
+            TextBaseline::Alphabetic => CanvasTextBaseline::Alphabetic,
+            TextBaseline::Ideographic => CanvasTextBaseline::Ideographic,
+            TextBaseline::Bottom => CanvasTextBaseline::Bottom,
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-textbaseline
+    pub fn SetTextBaseline(&self, value: CanvasTextBaseline) {
+        let text_baseline = match value {
+            CanvasTextBaseline::Top => TextBaseline::Top,
+            CanvasTextBaseline::Hanging => TextBaseline::Hanging,
+            CanvasTextBaseline::Middle => TextBaseline::Middle,
+            CanvasTextBaseline::Alphabetic => TextBaseline::Alphabetic,
+            CanvasTextBaseline::Ideographic => TextBaseline::Ideographic,
+            CanvasTextBaseline::Bottom => TextBaseline::Bottom,
+        };
+        self.state.borrow_mut().text_baseline = text_baseline;
+        self.send_canvas_2d_msg(Canvas2dMsg::SetTextBaseline(text_baseline));
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-direction
+    pub fn Direction(&self) -> CanvasDirection {
+        self.state.borrow().direction
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-direction
+    pub fn SetDirection(&self, value: CanvasDirection) {
+        self.state.borrow_mut().direction = value;
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-linewidth
     pub fn LineWidth(&self) -> f64 {
         self.state.borrow().line_width
@@ -1178,17 +1495,34 @@ impl CanvasState {
         self.send_canvas_2d_msg(Canvas2dMsg::Fill);
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-fill
+    pub fn Fill_(&self, path: &Path2D, _fill_rule: CanvasFillRule) {
+        // TODO: Process fill rule
+        self.send_canvas_2d_msg(Canvas2dMsg::FillPath(path.segments()));
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-stroke
     pub fn Stroke(&self) {
         self.send_canvas_2d_msg(Canvas2dMsg::Stroke);
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-stroke
+    pub fn Stroke_(&self, path: &Path2D) {
+        self.send_canvas_2d_msg(Canvas2dMsg::StrokePath(path.segments()));
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-clip
     pub fn Clip(&self, _fill_rule: CanvasFillRule) {
         // TODO: Process fill rule
         self.send_canvas_2d_msg(Canvas2dMsg::Clip);
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-clip
+    pub fn Clip_(&self, path: &Path2D, _fill_rule: CanvasFillRule) {
+        // TODO: Process fill rule
+        self.send_canvas_2d_msg(Canvas2dMsg::ClipPath(path.segments()));
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-ispointinpath
     pub fn IsPointInPath(
         &self,
@@ -1207,6 +1541,31 @@ impl CanvasState {
         receiver.recv().unwrap()
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-ispointinpath
+    pub fn IsPointInPath_(
+        &self,
+        global: &GlobalScope,
+        path: &Path2D,
+        x: f64,
+        y: f64,
+        fill_rule: CanvasFillRule,
+    ) -> bool {
+        let fill_rule = match fill_rule {
+            CanvasFillRule::Nonzero => FillRule::Nonzero,
+            CanvasFillRule::Evenodd => FillRule::Evenodd,
+        };
+        let (sender, receiver) =
+            profiled_ipc::channel::<bool>(global.time_profiler_chan().clone()).unwrap();
+        self.send_canvas_2d_msg(Canvas2dMsg::IsPointInPathPath(
+            path.segments(),
+            x,
+            y,
+            fill_rule,
+            sender,
+        ));
+        receiver.recv().unwrap()
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-scale
     pub fn Scale(&self, x: f64, y: f64) {
         if !(x.is_finite() && y.is_finite()) {
@@ -1647,17 +2006,34 @@ impl CanvasRenderingContext2DMethods for CanvasRenderingContext2D {
         self.mark_as_dirty();
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-fill
+    fn Fill_(&self, path: &Path2D, fill_rule: CanvasFillRule) {
+        self.canvas_state.borrow().Fill_(path, fill_rule);
+        self.mark_as_dirty();
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-stroke
     fn Stroke(&self) {
         self.canvas_state.borrow().Stroke();
         self.mark_as_dirty();
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-stroke
+    fn Stroke_(&self, path: &Path2D) {
+        self.canvas_state.borrow().Stroke_(path);
+        self.mark_as_dirty();
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-clip
     fn Clip(&self, fill_rule: CanvasFillRule) {
         self.canvas_state.borrow().Clip(fill_rule)
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-clip
+    fn Clip_(&self, path: &Path2D, fill_rule: CanvasFillRule) {
+        self.canvas_state.borrow().Clip_(path, fill_rule)
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-ispointinpath
     fn IsPointInPath(&self, x: f64, y: f64, fill_rule: CanvasFillRule) -> bool {
         self.canvas_state
@@ -1665,12 +2041,88 @@ impl CanvasRenderingContext2DMethods for CanvasRenderingContext2D {
             .IsPointInPath(&self.global(), x, y, fill_rule)
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-ispointinpath
+    fn IsPointInPath_(&self, path: &Path2D, x: f64, y: f64, fill_rule: CanvasFillRule) -> bool {
+        self.canvas_state
+            .borrow()
+            .IsPointInPath_(&self.global(), path, x, y, fill_rule)
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-filltext
     fn FillText(&self, text: DOMString, x: f64, y: f64, max_width: Option<f64>) {
-        self.canvas_state.borrow().FillText(text, x, y, max_width);
+        self.canvas_state.borrow().FillText(
+            self.canvas.as_ref().map(|c| &**c),
+            text,
+            x,
+            y,
+            max_width,
+        );
+        self.mark_as_dirty();
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-stroketext
+    fn StrokeText(&self, text: DOMString, x: f64, y: f64, max_width: Option<f64>) {
+        self.canvas_state.borrow().StrokeText(
+            self.canvas.as_ref().map(|c| &**c),
+            text,
+            x,
+            y,
+            max_width,
+        );
         self.mark_as_dirty();
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-measuretext
+    fn MeasureText(&self, text: DOMString) -> DomRoot<TextMetrics> {
+        self.canvas_state.borrow().MeasureText(
+            &self.global(),
+            self.canvas.as_ref().map(|c| &**c),
+            text,
+        )
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-font
+    fn Font(&self) -> DOMString {
+        self.canvas_state.borrow().Font()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-font
+    fn SetFont(&self, value: DOMString) {
+        self.canvas_state
+            .borrow()
+            .SetFont(self.canvas.as_ref().map(|c| &**c), value)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-textalign
+    fn TextAlign(&self) -> CanvasTextAlign {
+        self.canvas_state.borrow().TextAlign()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-textalign
+    fn SetTextAlign(&self, value: CanvasTextAlign) {
+        self.canvas_state.borrow().SetTextAlign(value)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-textbaseline
+    fn TextBaseline(&self) -> CanvasTextBaseline {
+        self.canvas_state.borrow().TextBaseline()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-textbaseline
+    fn SetTextBaseline(&self, value: CanvasTextBaseline) {
+        self.canvas_state.borrow().SetTextBaseline(value)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-direction
+    fn Direction(&self) -> CanvasDirection {
+        self.canvas_state.borrow().Direction()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-direction
+    fn SetDirection(&self, value: CanvasDirection) {
+        self.canvas_state.borrow().SetDirection(value)
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-drawimage
     fn DrawImage(&self, image: CanvasImageSource, dx: f64, dy: f64) -> ErrorResult {
         self.canvas_state
@@ -2021,6 +2473,93 @@ pub fn parse_color(string: &str) -> Result<RGBA, ()> {
     }
 }
 
+// https://html.spec.whatwg.org/multipage/#dom-context-2d-font
+// Parses a value of the `font` attribute as the CSS `font` shorthand, or returns None
+// if it must be ignored.
+fn parse_canvas_font(value: &str, base_url: &ServoUrl) -> Option<PropertyDeclarationBlock> {
+    let mut declarations = SourcePropertyDeclaration::new();
+    parse_one_declaration_into(
+        &mut declarations,
+        PropertyId::Shorthand(ShorthandId::Font),
+        value,
+        base_url,
+        None,
+        ParsingMode::DEFAULT,
+        QuirksMode::NoQuirks,
+    )
+    .ok()?;
+    let mut block = PropertyDeclarationBlock::new();
+    block.extend(declarations.drain(), Importance::Normal);
+
+    // CSS-wide keywords and values that depend on variables are ignored too.
+    let is_plain_value = block
+        .declarations()
+        .iter()
+        .all(|declaration| match *declaration {
+            PropertyDeclaration::CSSWideKeyword(..) | PropertyDeclaration::WithVariables(..) => {
+                false
+            },
+            _ => true,
+        });
+    if !is_plain_value {
+        return None;
+    }
+    Some(block)
+}
+
+// Computes the font longhands of a parsed `font` shorthand, inheriting from
+// `parent_style`, or from the initial values if there is none.
+fn compute_canvas_font(
+    block: &PropertyDeclarationBlock,
+    device: &Device,
+    parent_style: Option<&ComputedValues>,
+) -> Arc<ComputedValues> {
+    let mut conditions = RuleCacheConditions::default();
+    let provider = get_metrics_provider_for_product();
+    let mut context = Context {
+        is_root_element: false,
+        builder: StyleBuilder::for_inheritance(device, parent_style, None),
+        font_metrics_provider: &provider,
+        cached_system_font: None,
+        in_media_query: false,
+        quirks_mode: QuirksMode::NoQuirks,
+        for_smil_animation: false,
+        for_non_inherited_property: None,
+        rule_cache_conditions: RefCell::new(&mut conditions),
+    };
+    for declaration in block.declarations() {
+        match *declaration {
+            PropertyDeclaration::FontFamily(ref value) => {
+                let value = value.to_computed_value(&context);
+                context.builder.mutate_font().set_font_family(value);
+            },
+            PropertyDeclaration::FontSize(ref value) => {
+                let value = value.to_computed_value(&context);
+                context.builder.mutate_font().set_font_size(value);
+            },
+            PropertyDeclaration::FontStretch(ref value) => {
+                let value = value.to_computed_value(&context);
+                context.builder.mutate_font().set_font_stretch(value);
+            },
+            PropertyDeclaration::FontStyle(ref value) => {
+                let value = value.to_computed_value(&context);
+                context.builder.mutate_font().set_font_style(value);
+            },
+            PropertyDeclaration::FontVariantCaps(ref value) => {
+                let value = value.to_computed_value(&context);
+                context.builder.mutate_font().set_font_variant_caps(value);
+            },
+            PropertyDeclaration::FontWeight(ref value) => {
+                let value = value.to_computed_value(&context);
+                context.builder.mutate_font().set_font_weight(value);
+            },
+            // `line-height` has no effect on canvas text.
+            _ => {},
+        }
+    }
+    context.builder.build()
+}
+
 // Used by drawImage to determine if a source or destination rectangle is valid
 // Origin coordinates and size cannot be negative. Size has to be greater than zero
 fn is_rect_valid(rect: Rect<f64>) -> bool {
@@ -0,0 +1,81 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::CountQueuingStrategyBinding;
+use crate::dom::bindings::codegen::Bindings::CountQueuingStrategyBinding::CountQueuingStrategyMethods;
+use crate::dom::bindings::codegen::Bindings::FunctionBinding::Function;
+use crate::dom::bindings::codegen::Bindings::QueuingStrategyBinding::QueuingStrategyInit;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::DomRoot;
+use crate::dom::globalscope::GlobalScope;
+use crate::streams::create_size_function;
+use dom_struct::dom_struct;
+use js::jsapi::{CallArgs, JSContext};
+use js::jsval::{Int32Value, JSVal};
+use std::rc::Rc;
+
+/// <https://streams.spec.whatwg.org/#cqs-class>
+#[dom_struct]
+pub struct CountQueuingStrategy {
+    reflector_: Reflector,
+    high_water_mark: f64,
+    #[ignore_malloc_size_of = "Rc"]
+    size: DomRefCell<Option<Rc<Function>>>,
+}
+
+impl CountQueuingStrategy {
+    fn new_inherited(high_water_mark: f64) -> CountQueuingStrategy {
+        CountQueuingStrategy {
+            reflector_: Reflector::new(),
+            high_water_mark,
+            size: DomRefCell::new(None),
+        }
+    }
+
+    pub fn new(global: &GlobalScope, high_water_mark: f64) -> DomRoot<CountQueuingStrategy> {
+        reflect_dom_object(
+            Box::new(CountQueuingStrategy::new_inherited(high_water_mark)),
+            global,
+            CountQueuingStrategyBinding::Wrap,
+        )
+    }
+
+    // https://streams.spec.whatwg.org/#cqs-constructor
+    pub fn Constructor(
+        global: &GlobalScope,
+        init: &QueuingStrategyInit,
+    ) -> DomRoot<CountQueuingStrategy> {
+        CountQueuingStrategy::new(global, init.highWaterMark)
+    }
+}
+
+/// <https://streams.spec.whatwg.org/#count-queuing-strategy-size-function>
+#[allow(unsafe_code)]
+unsafe extern "C" fn count_queuing_strategy_size(
+    _cx: *mut JSContext,
+    argc: u32,
+    vp: *mut JSVal,
+) -> bool {
+    let args = CallArgs::from_vp(vp, argc);
+    *args.rval() = Int32Value(1);
+    true
+}
+
+impl CountQueuingStrategyMethods for CountQueuingStrategy {
+    // https://streams.spec.whatwg.org/#cqs-high-water-mark
+    fn HighWaterMark(&self) -> f64 {
+        self.high_water_mark
+    }
+
+    // https://streams.spec.whatwg.org/#cqs-size
+    fn Size(&self) -> Rc<Function> {
+        self.size
+            .borrow_mut()
+            .get_or_insert_with(|| {
+                create_size_function(&self.global(), Some(count_queuing_strategy_size))
+            })
+            .clone()
+    }
+}
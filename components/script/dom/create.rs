@@ -62,6 +62,7 @@ use crate::dom::htmlprogresselement::HTMLProgressElement;
 use crate::dom::htmlquoteelement::HTMLQuoteElement;
 use crate::dom::htmlscriptelement::HTMLScriptElement;
 use crate::dom::htmlselectelement::HTMLSelectElement;
+use crate::dom::htmlslotelement::HTMLSlotElement;
 use crate::dom::htmlsourceelement::HTMLSourceElement;
 use crate::dom::htmlspanelement::HTMLSpanElement;
 use crate::dom::htmlstyleelement::HTMLStyleElement;
@@ -79,7 +80,21 @@ use crate::dom::htmltrackelement::HTMLTrackElement;
 use crate::dom::htmlulistelement::HTMLUListElement;
 use crate::dom::htmlunknownelement::HTMLUnknownElement;
 use crate::dom::htmlvideoelement::HTMLVideoElement;
+use crate::dom::svgcircleelement::SVGCircleElement;
+use crate::dom::svgdefselement::SVGDefsElement;
+use crate::dom::svgellipseelement::SVGEllipseElement;
+use crate::dom::svggelement::SVGGElement;
+use crate::dom::svglineargradientelement::SVGLinearGradientElement;
+use crate::dom::svglineelement::SVGLineElement;
+use crate::dom::svgpathelement::SVGPathElement;
+use crate::dom::svgpolygonelement::SVGPolygonElement;
+use crate::dom::svgpolylineelement::SVGPolylineElement;
+use crate::dom::svgradialgradientelement::SVGRadialGradientElement;
+use crate::dom::svgrectelement::SVGRectElement;
+use crate::dom::svgstopelement::SVGStopElement;
 use crate::dom::svgsvgelement::SVGSVGElement;
+use crate::dom::svgtextelement::SVGTextElement;
+use crate::dom::svguseelement::SVGUseElement;
 use crate::script_thread::ScriptThread;
 use html5ever::{LocalName, Prefix, QualName};
 use js::jsapi::JSAutoRealm;
@@ -108,7 +123,21 @@ fn create_svg_element(
     }
 
     match name.local {
+        local_name!("circle") => make!(SVGCircleElement),
+        local_name!("defs") => make!(SVGDefsElement),
+        local_name!("ellipse") => make!(SVGEllipseElement),
+        local_name!("g") => make!(SVGGElement),
+        local_name!("line") => make!(SVGLineElement),
+        local_name!("linearGradient") => make!(SVGLinearGradientElement),
+        local_name!("path") => make!(SVGPathElement),
+        local_name!("polygon") => make!(SVGPolygonElement),
+        local_name!("polyline") => make!(SVGPolylineElement),
+        local_name!("radialGradient") => make!(SVGRadialGradientElement),
+        local_name!("rect") => make!(SVGRectElement),
+        local_name!("stop") => make!(SVGStopElement),
         local_name!("svg") => make!(SVGSVGElement),
+        local_name!("text") => make!(SVGTextElement),
+        local_name!("use") => make!(SVGUseElement),
         _ => Element::new(name.local, name.ns, prefix, document),
     }
 }
@@ -332,6 +361,7 @@ pub fn create_native_html_element(
         local_name!("script") => make!(HTMLScriptElement, creator),
         local_name!("section") => make!(HTMLElement),
         local_name!("select") => make!(HTMLSelectElement),
+        local_name!("slot") => make!(HTMLSlotElement),
         local_name!("small") => make!(HTMLElement),
         local_name!("source") => make!(HTMLSourceElement),
         // https://html.spec.whatwg.org/multipage/#other-elements,-attributes-and-apis:spacer
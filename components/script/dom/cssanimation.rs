@@ -0,0 +1,55 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::animation::Animation;
+use crate::dom::animationeffect::AnimationEffect;
+use crate::dom::animationtimeline::AnimationTimeline;
+use crate::dom::bindings::codegen::Bindings::CSSAnimationBinding;
+use crate::dom::bindings::codegen::Bindings::CSSAnimationBinding::CSSAnimationMethods;
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::reflector::reflect_dom_object;
+use crate::dom::bindings::root::DomRoot;
+use crate::dom::bindings::str::DOMString;
+use crate::dom::window::Window;
+use dom_struct::dom_struct;
+
+/// <https://drafts.csswg.org/css-animations-2/#the-CSSAnimation-interface>
+#[dom_struct]
+pub struct CSSAnimation {
+    animation: Animation,
+    animation_name: DOMString,
+}
+
+impl CSSAnimation {
+    fn new_inherited(window: &Window, animation_name: DOMString) -> CSSAnimation {
+        CSSAnimation {
+            animation: Animation::new_inherited(window.upcast()),
+            animation_name,
+        }
+    }
+
+    pub fn new(
+        window: &Window,
+        effect: &AnimationEffect,
+        timeline: &AnimationTimeline,
+        animation_name: DOMString,
+    ) -> DomRoot<CSSAnimation> {
+        let animation = reflect_dom_object(
+            Box::new(CSSAnimation::new_inherited(window, animation_name)),
+            window,
+            CSSAnimationBinding::Wrap,
+        );
+        animation
+            .upcast::<Animation>()
+            .setup(Some(effect), Some(timeline));
+        animation
+    }
+}
+
+impl CSSAnimationMethods for CSSAnimation {
+    // https://drafts.csswg.org/css-animations-2/#dom-cssanimation-animationname
+    fn AnimationName(&self) -> DOMString {
+        self.animation_name.clone()
+    }
+}
@@ -0,0 +1,55 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::animation::Animation;
+use crate::dom::animationeffect::AnimationEffect;
+use crate::dom::animationtimeline::AnimationTimeline;
+use crate::dom::bindings::codegen::Bindings::CSSTransitionBinding;
+use crate::dom::bindings::codegen::Bindings::CSSTransitionBinding::CSSTransitionMethods;
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::reflector::reflect_dom_object;
+use crate::dom::bindings::root::DomRoot;
+use crate::dom::bindings::str::DOMString;
+use crate::dom::window::Window;
+use dom_struct::dom_struct;
+
+/// <https://drafts.csswg.org/css-transitions-2/#the-CSSTransition-interface>
+#[dom_struct]
+pub struct CSSTransition {
+    animation: Animation,
+    transition_property: DOMString,
+}
+
+impl CSSTransition {
+    fn new_inherited(window: &Window, transition_property: DOMString) -> CSSTransition {
+        CSSTransition {
+            animation: Animation::new_inherited(window.upcast()),
+            transition_property,
+        }
+    }
+
+    pub fn new(
+        window: &Window,
+        effect: &AnimationEffect,
+        timeline: &AnimationTimeline,
+        transition_property: DOMString,
+    ) -> DomRoot<CSSTransition> {
+        let transition = reflect_dom_object(
+            Box::new(CSSTransition::new_inherited(window, transition_property)),
+            window,
+            CSSTransitionBinding::Wrap,
+        );
+        transition
+            .upcast::<Animation>()
+            .setup(Some(effect), Some(timeline));
+        transition
+    }
+}
+
+impl CSSTransitionMethods for CSSTransition {
+    // https://drafts.csswg.org/css-transitions-2/#dom-csstransition-transitionproperty
+    fn TransitionProperty(&self) -> DOMString {
+        self.transition_property.clone()
+    }
+}
@@ -10,18 +10,21 @@ use crate::dom::abstractworkerglobalscope::{SendableWorkerScriptChan, WorkerThre
 use crate::dom::bindings::cell::DomRefCell;
 use crate::dom::bindings::codegen::Bindings::DedicatedWorkerGlobalScopeBinding;
 use crate::dom::bindings::codegen::Bindings::DedicatedWorkerGlobalScopeBinding::DedicatedWorkerGlobalScopeMethods;
+use crate::dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
 use crate::dom::bindings::codegen::Bindings::WorkerBinding::WorkerType;
 use crate::dom::bindings::error::{ErrorInfo, ErrorResult};
 use crate::dom::bindings::inheritance::Castable;
 use crate::dom::bindings::reflector::DomObject;
-use crate::dom::bindings::root::{DomRoot, RootCollection, ThreadLocalStackRoots};
+use crate::dom::bindings::root::{Dom, DomRoot, RootCollection, ThreadLocalStackRoots};
 use crate::dom::bindings::str::DOMString;
-use crate::dom::bindings::structuredclone::StructuredCloneData;
+use crate::dom::bindings::structuredclone;
 use crate::dom::errorevent::ErrorEvent;
 use crate::dom::event::{Event, EventBubbles, EventCancelable, EventStatus};
 use crate::dom::eventtarget::EventTarget;
 use crate::dom::globalscope::GlobalScope;
 use crate::dom::messageevent::MessageEvent;
+use crate::dom::offscreencanvas::OffscreenCanvas;
+use crate::dom::window::Window;
 use crate::dom::worker::{TrustedWorkerAddress, Worker};
 use crate::dom::workerglobalscope::WorkerGlobalScope;
 use crate::fetch::load_whole_resource;
@@ -31,16 +34,17 @@ use crate::script_runtime::{
 };
 use crate::task_queue::{QueuedTask, QueuedTaskConversion, TaskQueue};
 use crate::task_source::TaskSourceName;
+use canvas_traits::canvas::CanvasId;
 use crossbeam_channel::{unbounded, Receiver, Sender};
 use devtools_traits::DevtoolScriptControlMsg;
 use dom_struct::dom_struct;
 use ipc_channel::ipc::{self, IpcReceiver, IpcSender};
 use ipc_channel::router::ROUTER;
-use js::jsapi::JSContext;
 use js::jsapi::JS_AddInterruptCallback;
+use js::jsapi::{JSContext, JSObject};
 use js::jsval::UndefinedValue;
-use js::rust::HandleValue;
-use msg::constellation_msg::{PipelineId, TopLevelBrowsingContextId};
+use js::rust::{CustomAutoRooterGuard, HandleValue};
+use msg::constellation_msg::{PipelineId, PipelineNamespace, TopLevelBrowsingContextId};
 use net_traits::image_cache::ImageCache;
 use net_traits::request::{CredentialsMode, Destination, ParserMetadata};
 use net_traits::request::{Referrer, RequestBuilder, RequestMode};
@@ -180,6 +184,9 @@ pub struct DedicatedWorkerGlobalScope {
     parent_sender: Box<dyn ScriptChan + Send>,
     #[ignore_malloc_size_of = "Arc"]
     image_cache: Arc<dyn ImageCache>,
+    /// The offscreen canvases of this worker that commit their frames to a
+    /// placeholder canvas element.
+    offscreen_canvases: DomRefCell<Vec<Dom<OffscreenCanvas>>>,
 }
 
 impl WorkerEventLoopMethods for DedicatedWorkerGlobalScope {
@@ -215,6 +222,10 @@ impl WorkerEventLoopMethods for DedicatedWorkerGlobalScope {
     fn from_devtools_msg(&self, msg: DevtoolScriptControlMsg) -> MixedMessage {
         MixedMessage::FromDevtools(msg)
     }
+
+    fn update_the_rendering(&self) {
+        self.commit_offscreen_canvases();
+    }
 }
 
 impl DedicatedWorkerGlobalScope {
@@ -250,6 +261,7 @@ impl DedicatedWorkerGlobalScope {
             parent_sender: parent_sender,
             worker: DomRefCell::new(None),
             image_cache: image_cache,
+            offscreen_canvases: DomRefCell::new(Vec::new()),
         }
     }
 
@@ -316,6 +328,9 @@ impl DedicatedWorkerGlobalScope {
             .spawn(move || {
                 thread_state::initialize(ThreadState::SCRIPT | ThreadState::IN_WORKER);
 
+                // Ports and channels created in this worker need ids.
+                PipelineNamespace::auto_install();
+
                 if let Some(top_level_browsing_context_id) = top_level_browsing_context_id {
                     TopLevelBrowsingContextId::install(top_level_browsing_context_id);
                 }
@@ -431,6 +446,8 @@ impl DedicatedWorkerGlobalScope {
                             while !scope.is_closing() {
                                 run_worker_event_loop(&*global, Some(&worker));
                             }
+                            // Stop receiving messages for the ports and channels of this worker.
+                            scope.upcast::<GlobalScope>().remove_web_messaging_infra();
                         },
                         reporter_name,
                         parent_sender,
@@ -460,6 +477,50 @@ impl DedicatedWorkerGlobalScope {
         (chan, Box::new(rx))
     }
 
+    /// Commits the frames of `canvas` to its placeholder canvas element whenever
+    /// the rendering of this worker gets updated.
+    pub fn track_offscreen_canvas(&self, canvas: &OffscreenCanvas) {
+        self.offscreen_canvases
+            .borrow_mut()
+            .push(Dom::from_ref(canvas));
+    }
+
+    /// Commits the offscreen canvases drawn to since the last update of the rendering,
+    /// and lets the placeholders of those canvases display the new frames.
+    fn commit_offscreen_canvases(&self) {
+        let placeholder_ids: Vec<CanvasId> = self
+            .offscreen_canvases
+            .borrow()
+            .iter()
+            .filter_map(|canvas| canvas.commit())
+            .collect();
+        if placeholder_ids.is_empty() {
+            return;
+        }
+        let worker = self.worker.borrow().as_ref().unwrap().clone();
+        let pipeline_id = self.upcast::<GlobalScope>().pipeline_id();
+        let task = Box::new(task!(update_offscreen_canvas_placeholders: move || {
+            let worker = worker.root();
+            let global = worker.global();
+            // TODO: the placeholders of canvases that were transferred to a nested
+            // worker live in the document of the outermost worker's owner.
+            if let Some(window) = global.downcast::<Window>() {
+                let document = window.Document();
+                for placeholder_id in placeholder_ids {
+                    document.dirty_offscreen_canvas_placeholder(placeholder_id);
+                }
+            }
+        }));
+        self.parent_sender
+            .send(CommonScriptMsg::Task(
+                WorkerEvent,
+                task,
+                Some(pipeline_id),
+                TaskSourceName::DOMManipulation,
+            ))
+            .unwrap();
+    }
+
     fn handle_script_event(&self, msg: WorkerScriptMsg) {
         match msg {
             WorkerScriptMsg::DOMMessage(data) => {
@@ -467,8 +528,15 @@ impl DedicatedWorkerGlobalScope {
                 let target = self.upcast();
                 let _ac = enter_realm(self);
                 rooted!(in(*scope.get_cx()) let mut message = UndefinedValue());
-                data.read(scope.upcast(), message.handle_mut());
-                MessageEvent::dispatch_jsval(target, scope.upcast(), message.handle(), None, None);
+                let ports = structuredclone::read(scope.upcast(), data, message.handle_mut());
+                MessageEvent::dispatch_jsval(
+                    target,
+                    scope.upcast(),
+                    message.handle(),
+                    None,
+                    None,
+                    ports,
+                );
             },
             WorkerScriptMsg::Common(msg) => {
                 self.upcast::<WorkerGlobalScope>().process_event(msg);
@@ -561,8 +629,13 @@ unsafe extern "C" fn interrupt_callback(cx: *mut JSContext) -> bool {
 
 impl DedicatedWorkerGlobalScopeMethods for DedicatedWorkerGlobalScope {
     // https://html.spec.whatwg.org/multipage/#dom-dedicatedworkerglobalscope-postmessage
-    fn PostMessage(&self, cx: SafeJSContext, message: HandleValue) -> ErrorResult {
-        let data = StructuredCloneData::write(*cx, message)?;
+    fn PostMessage(
+        &self,
+        cx: SafeJSContext,
+        message: HandleValue,
+        transfer: CustomAutoRooterGuard<Vec<*mut JSObject>>,
+    ) -> ErrorResult {
+        let data = structuredclone::write(cx, message, Some(transfer))?;
         let worker = self.worker.borrow().as_ref().unwrap().clone();
         let pipeline_id = self.upcast::<GlobalScope>().pipeline_id();
         let task = Box::new(task!(post_worker_message: move || {
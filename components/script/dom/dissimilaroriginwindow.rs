@@ -7,17 +7,18 @@ use crate::dom::bindings::codegen::Bindings::DissimilarOriginWindowBinding::Diss
 use crate::dom::bindings::error::{Error, ErrorResult};
 use crate::dom::bindings::root::{Dom, DomRoot, MutNullableDom};
 use crate::dom::bindings::str::DOMString;
-use crate::dom::bindings::structuredclone::StructuredCloneData;
+use crate::dom::bindings::structuredclone;
 use crate::dom::dissimilaroriginlocation::DissimilarOriginLocation;
 use crate::dom::globalscope::GlobalScope;
 use crate::dom::windowproxy::WindowProxy;
 use crate::script_runtime::JSContext;
 use dom_struct::dom_struct;
 use ipc_channel::ipc;
+use js::jsapi::JSObject;
 use js::jsval::{JSVal, UndefinedValue};
-use js::rust::HandleValue;
+use js::rust::{CustomAutoRooterGuard, HandleValue};
 use msg::constellation_msg::PipelineId;
-use script_traits::ScriptMsg;
+use script_traits::{ScriptMsg, StructuredSerializedData};
 use servo_url::ImmutableOrigin;
 use servo_url::ServoUrl;
 
@@ -134,7 +135,13 @@ impl DissimilarOriginWindowMethods for DissimilarOriginWindow {
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-window-postmessage
-    fn PostMessage(&self, cx: JSContext, message: HandleValue, origin: DOMString) -> ErrorResult {
+    fn PostMessage(
+        &self,
+        cx: JSContext,
+        message: HandleValue,
+        origin: DOMString,
+        transfer: CustomAutoRooterGuard<Vec<*mut JSObject>>,
+    ) -> ErrorResult {
         // Step 3-5.
         let origin = match &origin[..] {
             "*" => None,
@@ -149,8 +156,7 @@ impl DissimilarOriginWindowMethods for DissimilarOriginWindow {
         };
 
         // Step 1-2, 6-8.
-        // TODO(#12717): Should implement the `transfer` argument.
-        let data = StructuredCloneData::write(*cx, message)?;
+        let data = structuredclone::write(cx, message, Some(transfer))?;
 
         // Step 9.
         self.post_message(origin, data);
@@ -186,7 +192,7 @@ impl DissimilarOriginWindowMethods for DissimilarOriginWindow {
 }
 
 impl DissimilarOriginWindow {
-    pub fn post_message(&self, origin: Option<ImmutableOrigin>, data: StructuredCloneData) {
+    pub fn post_message(&self, origin: Option<ImmutableOrigin>, data: StructuredSerializedData) {
         let incumbent = match GlobalScope::incumbent() {
             None => return warn!("postMessage called with no incumbent global"),
             Some(incumbent) => incumbent,
@@ -195,7 +201,7 @@ impl DissimilarOriginWindow {
             target: self.window_proxy.browsing_context_id(),
             source: incumbent.pipeline_id(),
             target_origin: origin,
-            data: data.move_to_arraybuffer(),
+            data,
         };
         let _ = incumbent.script_to_constellation_chan().send(msg);
     }
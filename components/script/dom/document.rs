@@ -5,6 +5,8 @@
 use crate::compartments::{AlreadyInCompartment, InCompartment};
 use crate::document_loader::{DocumentLoader, LoadType};
 use crate::dom::activation::{synthetic_click_activation, ActivationSource};
+use crate::dom::animation::Animation;
+use crate::dom::animationtimeline::AnimationTimeline;
 use crate::dom::attr::Attr;
 use crate::dom::beforeunloadevent::BeforeUnloadEvent;
 use crate::dom::bindings::callback::ExceptionHandling;
@@ -20,13 +22,14 @@ use crate::dom::bindings::codegen::Bindings::HTMLIFrameElementBinding::HTMLIFram
 use crate::dom::bindings::codegen::Bindings::NodeBinding::NodeMethods;
 use crate::dom::bindings::codegen::Bindings::NodeFilterBinding::NodeFilter;
 use crate::dom::bindings::codegen::Bindings::PerformanceBinding::PerformanceMethods;
+use crate::dom::bindings::codegen::Bindings::SelectionBinding::SelectionMethods;
 use crate::dom::bindings::codegen::Bindings::ShadowRootBinding::ShadowRootMethods;
 use crate::dom::bindings::codegen::Bindings::TouchBinding::TouchMethods;
 use crate::dom::bindings::codegen::Bindings::WindowBinding::{
     FrameRequestCallback, ScrollBehavior, WindowMethods,
 };
 use crate::dom::bindings::codegen::UnionTypes::NodeOrString;
-use crate::dom::bindings::error::{Error, ErrorResult, Fallible};
+use crate::dom::bindings::error::{Error, ErrorInfo, ErrorResult, Fallible};
 use crate::dom::bindings::inheritance::{Castable, ElementTypeId, HTMLElementTypeId, NodeTypeId};
 use crate::dom::bindings::num::Finite;
 use crate::dom::bindings::refcounted::{Trusted, TrustedPromise};
@@ -38,6 +41,7 @@ use crate::dom::bindings::xmlname::{
     namespace_from_domstring, validate_and_extract, xml_name_type,
 };
 use crate::dom::cdatasection::CDATASection;
+use crate::dom::characterdata::CharacterData;
 use crate::dom::closeevent::CloseEvent;
 use crate::dom::comment::Comment;
 use crate::dom::compositionevent::CompositionEvent;
@@ -46,8 +50,10 @@ use crate::dom::customelementregistry::CustomElementDefinition;
 use crate::dom::customevent::CustomEvent;
 use crate::dom::documentfragment::DocumentFragment;
 use crate::dom::documentorshadowroot::{DocumentOrShadowRoot, StyleSheetInDocument};
+use crate::dom::documenttimeline::DocumentTimeline;
 use crate::dom::documenttype::DocumentType;
 use crate::dom::domimplementation::DOMImplementation;
+use crate::dom::editing::{self, EditingCommand, UndoHistory};
 use crate::dom::element::CustomElementCreationMode;
 use crate::dom::element::{
     Element, ElementCreator, ElementPerformFullscreenEnter, ElementPerformFullscreenExit,
@@ -56,12 +62,14 @@ use crate::dom::errorevent::ErrorEvent;
 use crate::dom::event::{Event, EventBubbles, EventCancelable, EventDefault, EventStatus};
 use crate::dom::eventtarget::EventTarget;
 use crate::dom::focusevent::FocusEvent;
+use crate::dom::fontfaceset::FontFaceSet;
 use crate::dom::globalscope::GlobalScope;
 use crate::dom::hashchangeevent::HashChangeEvent;
 use crate::dom::htmlanchorelement::HTMLAnchorElement;
 use crate::dom::htmlareaelement::HTMLAreaElement;
 use crate::dom::htmlbaseelement::HTMLBaseElement;
 use crate::dom::htmlbodyelement::HTMLBodyElement;
+use crate::dom::htmlcanvaselement::HTMLCanvasElement;
 use crate::dom::htmlcollection::{CollectionFilter, HTMLCollection};
 use crate::dom::htmlelement::HTMLElement;
 use crate::dom::htmlembedelement::HTMLEmbedElement;
@@ -70,9 +78,13 @@ use crate::dom::htmlheadelement::HTMLHeadElement;
 use crate::dom::htmlhtmlelement::HTMLHtmlElement;
 use crate::dom::htmliframeelement::HTMLIFrameElement;
 use crate::dom::htmlimageelement::HTMLImageElement;
+use crate::dom::htmlinputelement::HTMLInputElement;
 use crate::dom::htmlscriptelement::{HTMLScriptElement, ScriptResult};
+use crate::dom::htmltextareaelement::HTMLTextAreaElement;
 use crate::dom::htmltitleelement::HTMLTitleElement;
+use crate::dom::intersectionobserver::IntersectionObserver;
 use crate::dom::keyboardevent::KeyboardEvent;
+use crate::dom::keyframeeffect::KeyframeEffect;
 use crate::dom::location::Location;
 use crate::dom::messageevent::MessageEvent;
 use crate::dom::mouseevent::MouseEvent;
@@ -86,6 +98,8 @@ use crate::dom::processinginstruction::ProcessingInstruction;
 use crate::dom::progressevent::ProgressEvent;
 use crate::dom::promise::Promise;
 use crate::dom::range::Range;
+use crate::dom::resizeobserver::ResizeObserver;
+use crate::dom::selection::{char_index_to_utf16_offset, Selection};
 use crate::dom::servoparser::ServoParser;
 use crate::dom::shadowroot::ShadowRoot;
 use crate::dom::storageevent::StorageEvent;
@@ -108,7 +122,9 @@ use crate::script_thread::{MainThreadScriptMsg, ScriptThread};
 use crate::stylesheet_set::StylesheetSetRef;
 use crate::task::TaskBox;
 use crate::task_source::{TaskSource, TaskSourceName};
+use crate::textinput::CMD_OR_CONTROL;
 use crate::timers::OneshotTimerCallback;
+use canvas_traits::canvas::CanvasId;
 use cookie::Cookie;
 use devtools_traits::ScriptToDevtoolsControlMsg;
 use dom_struct::dom_struct;
@@ -119,7 +135,8 @@ use html5ever::{LocalName, Namespace, QualName};
 use hyper_serde::Serde;
 use ipc_channel::ipc::{self, IpcSender};
 use js::jsapi::{JSObject, JSRuntime};
-use keyboard_types::{Key, KeyState, Modifiers};
+use js::rust::HandleValue;
+use keyboard_types::{Key, KeyState, Modifiers, ShortcutMatcher};
 use metrics::{
     InteractiveFlag, InteractiveMetrics, InteractiveWindow, ProfilerMetadataFactory,
     ProgressiveWebMetric,
@@ -155,6 +172,7 @@ use std::mem;
 use std::ptr::NonNull;
 use std::rc::Rc;
 use std::time::{Duration, Instant};
+use style::animation::KeyframeEffectSample;
 use style::attr::AttrValue;
 use style::context::QuirksMode;
 use style::invalidation::element::restyle_hints::RestyleHint;
@@ -394,6 +412,34 @@ pub struct Document {
     /// where `id` needs to match any of the registered ShadowRoots
     /// hosting the media controls UI.
     media_controls: DomRefCell<HashMap<String, Dom<ShadowRoot>>>,
+    /// <https://w3c.github.io/IntersectionObserver/#document-intersectionobservers>
+    intersection_observers: DomRefCell<Vec<Dom<IntersectionObserver>>>,
+    /// <https://w3c.github.io/IntersectionObserver/#document-intersectionobservertaskqueued>
+    intersection_observer_task_queued: Cell<bool>,
+    /// <https://drafts.csswg.org/resize-observer/#dom-document-resizeobservers-slot>
+    resize_observers: DomRefCell<Vec<Dom<ResizeObserver>>>,
+    /// <https://w3c.github.io/selection-api/#dfn-selection>
+    selection: MutNullableDom<Selection>,
+    /// Whether the left mouse button was pressed on text and is extending
+    /// the selection as the mouse moves.
+    selecting_text: Cell<bool>,
+    /// <https://html.spec.whatwg.org/multipage/#designMode>
+    design_mode: Cell<bool>,
+    /// <https://w3c.github.io/editing/docs/execCommand/#undo-history>
+    undo_history: DomRefCell<UndoHistory>,
+    /// The connected placeholder canvas elements of offscreen canvases, keyed by the
+    /// id of the canvas backing them, so that committed frames can get displayed.
+    offscreen_canvas_placeholders: DomRefCell<HashMap<CanvasId, Dom<HTMLCanvasElement>>>,
+    /// <https://drafts.csswg.org/web-animations/#the-documents-default-timeline>
+    timeline: MutNullableDom<DocumentTimeline>,
+    /// The document timelines of this document, which are updated at each
+    /// animation frame.
+    timelines: DomRefCell<Vec<Dom<DocumentTimeline>>>,
+    /// The elements the keyframe effects of the animations of this document
+    /// currently apply to.
+    animated_elements: DomRefCell<Vec<Dom<Element>>>,
+    /// <https://drafts.csswg.org/css-font-loading/#dom-fontfacesource-fonts>
+    fonts: MutNullableDom<FontFaceSet>,
 }
 
 #[derive(JSTraceable, MallocSizeOf)]
@@ -929,7 +975,7 @@ impl Document {
     pub fn handle_mouse_event(
         &self,
         js_runtime: *mut JSRuntime,
-        _button: MouseButton,
+        button: MouseButton,
         client_point: Point2D<f32>,
         mouse_event_type: MouseEventType,
         node_address: Option<UntrustedNodeAddress>,
@@ -943,8 +989,9 @@ impl Document {
         };
         debug!("{}: at {:?}", mouse_event_type_string, client_point);
 
-        let el = node_address.and_then(|address| {
-            let node = unsafe { node::from_untrusted_node_address(js_runtime, address) };
+        let hit_node = node_address
+            .map(|address| unsafe { node::from_untrusted_node_address(js_runtime, address) });
+        let el = hit_node.as_ref().and_then(|node| {
             node.inclusive_ancestors(ShadowIncluding::No)
                 .filter_map(DomRoot::downcast::<Element>)
                 .next()
@@ -1004,12 +1051,22 @@ impl Document {
 
                 let target = node.upcast();
                 event.fire(target);
+
+                if let (MouseButton::Left, false) = (button, event.DefaultPrevented()) {
+                    if let Some(ref hit_node) = hit_node {
+                        self.start_text_selection(hit_node, client_point);
+                    }
+                }
             },
             MouseEventType::MouseUp => {
                 if let Some(a) = activatable {
                     a.exit_formal_activation_state();
                 }
 
+                if let MouseButton::Left = button {
+                    self.selecting_text.set(false);
+                }
+
                 let target = node.upcast();
                 event.fire(target);
             },
@@ -1084,6 +1141,72 @@ impl Document {
         *self.last_click_info.borrow_mut() = Some((now, click_pos));
     }
 
+    /// The offset in UTF-16 code units of the position in `node` closest to
+    /// `client_point`, if `node` is a text node.
+    fn text_offset_at_point(&self, node: &Node, client_point: Point2D<f32>) -> Option<u32> {
+        let text = node.downcast::<Text>()?;
+        let viewport = self.window.current_viewport();
+        let point = Point2D::new(
+            client_point.x + viewport.origin.x.to_f32_px(),
+            client_point.y + viewport.origin.y.to_f32_px(),
+        );
+        let index = self.window.text_index_at_point_query(node, point).0?;
+        let data = text.upcast::<CharacterData>().data();
+        Some(char_index_to_utf16_offset(&data, index))
+    }
+
+    /// Collapse the selection where the left mouse button was pressed, or
+    /// clear it if it was pressed outside of any text.
+    fn start_text_selection(&self, hit_node: &Node, client_point: Point2D<f32>) {
+        let selection = match self.GetSelection() {
+            Some(selection) => selection,
+            None => return,
+        };
+        match self.text_offset_at_point(hit_node, client_point) {
+            Some(offset) => {
+                let _ = selection.Collapse(Some(hit_node), offset);
+                self.selecting_text.set(true);
+            },
+            // Text controls manage their own selection.
+            None if is_text_control(hit_node) => {},
+            None => selection.RemoveAllRanges(),
+        }
+    }
+
+    /// Extend the selection to the text under the mouse while the left mouse
+    /// button is held down.
+    fn extend_text_selection(&self, hit_node: &Node, client_point: Point2D<f32>) {
+        if let Some(offset) = self.text_offset_at_point(hit_node, client_point) {
+            if let Some(selection) = self.GetSelection() {
+                let _ = selection.Extend(hit_node, offset);
+            }
+        }
+    }
+
+    /// Send the text of the selection to the embedder's clipboard.
+    fn copy_selection_to_clipboard(&self) {
+        let text = match self.GetSelection() {
+            Some(ref selection) if !selection.IsCollapsed() => selection.Stringifier(),
+            _ => return,
+        };
+        self.send_to_embedder(EmbedderMsg::SetClipboardContents(String::from(text)));
+    }
+
+    pub fn design_mode(&self) -> bool {
+        self.design_mode.get()
+    }
+
+    pub fn undo_history(&self) -> &DomRefCell<UndoHistory> {
+        &self.undo_history
+    }
+
+    /// Make layout paint the text of the selection as selected.
+    pub fn update_selection_highlights(&self) {
+        if let Some(selection) = self.selection.get() {
+            selection.update_highlighted_nodes();
+        }
+    }
+
     pub fn fire_mouse_event(
         &self,
         client_point: Point2D<f32>,
@@ -1137,8 +1260,20 @@ impl Document {
             Some(client_point) => client_point,
         };
 
-        let maybe_new_target = node_address.and_then(|address| {
-            let node = unsafe { node::from_untrusted_node_address(js_runtime, address) };
+        let hit_node = node_address
+            .map(|address| unsafe { node::from_untrusted_node_address(js_runtime, address) });
+
+        if self.selecting_text.get() {
+            match hit_node {
+                Some(ref hit_node) if pressed_mouse_buttons & 1 != 0 => {
+                    self.extend_text_selection(hit_node, client_point)
+                },
+                // The button was released outside of the document.
+                _ => self.selecting_text.set(false),
+            }
+        }
+
+        let maybe_new_target = hit_node.and_then(|node| {
             node.inclusive_ancestors(ShadowIncluding::No)
                 .filter_map(DomRoot::downcast::<Element>)
                 .next()
@@ -1484,6 +1619,17 @@ impl Document {
                 },
                 _ => (),
             }
+
+            // Text controls copy their own selection.
+            if !target.downcast::<Node>().map_or(false, is_text_control) {
+                ShortcutMatcher::new(
+                    keyboard_event.state,
+                    keyboard_event.key.clone(),
+                    keyboard_event.modifiers,
+                )
+                .shortcut(CMD_OR_CONTROL, 'C', || self.copy_selection_to_clipboard())
+                .otherwise(|| ());
+            }
         }
 
         self.window.reflow(ReflowGoal::Full, ReflowReason::KeyEvent);
@@ -1609,6 +1755,14 @@ impl Document {
             .borrow_mut()
             .push((ident, Some(callback)));
 
+        self.request_animation_ticks();
+
+        ident
+    }
+
+    /// Makes sure the animation frame callbacks of this document run at the
+    /// next frame.
+    pub fn request_animation_ticks(&self) {
         // TODO: Should tick animation only when document is visible
 
         // If we are running 'fake' animation frames, we unconditionally
@@ -1632,8 +1786,6 @@ impl Document {
                 ScriptMsg::ChangeRunningAnimationsState(AnimationState::AnimationCallbacksPresent);
             self.window().send_to_constellation(event);
         }
-
-        ident
     }
 
     /// <https://html.spec.whatwg.org/multipage/#dom-window-cancelanimationframe>
@@ -1656,6 +1808,8 @@ impl Document {
         let was_faking_animation_frames = self.is_faking_animation_frames();
         let timing = self.global().performance().Now();
 
+        self.update_animations_and_send_events(*timing);
+
         for (_, callback) in animation_frame_list.drain(..) {
             if let Some(callback) = callback {
                 callback.call(self, *timing);
@@ -1692,8 +1846,13 @@ impl Document {
         // constellation to stop giving us video refresh callbacks, to save energy. (A spurious
         // animation frame is one in which the callback did not mutate the DOM—that is, an
         // animation frame that wasn't actually used for animation.)
+        //
+        // Running animations keep the animation callbacks present.
         let is_empty = self.animation_frame_list.borrow().is_empty();
-        if is_empty || (!was_faking_animation_frames && self.is_faking_animation_frames()) {
+        let has_running_animations = self.has_running_animations();
+        if (is_empty && !has_running_animations) ||
+            (!was_faking_animation_frames && self.is_faking_animation_frames())
+        {
             if is_empty {
                 // If the current animation frame list in the DOM instance is empty,
                 // we can reuse the original `Vec<T>` that we put on the stack to
@@ -1710,6 +1869,11 @@ impl Document {
             self.window().send_to_constellation(event);
         }
 
+        // When faking animation frames, running animations need a timer to tick them.
+        if has_running_animations && self.is_faking_animation_frames() {
+            self.request_animation_ticks();
+        }
+
         // Update the counter of spurious animation frames.
         if spurious {
             if self.spurious_animation_frames.get() < SPURIOUS_ANIMATION_FRAME_THRESHOLD {
@@ -2483,6 +2647,291 @@ impl Document {
             debug_assert!(false, "Trying to unregister unknown media controls");
         }
     }
+
+    pub fn register_offscreen_canvas_placeholder(
+        &self,
+        canvas_id: CanvasId,
+        placeholder: &HTMLCanvasElement,
+    ) {
+        self.offscreen_canvas_placeholders
+            .borrow_mut()
+            .insert(canvas_id, Dom::from_ref(placeholder));
+    }
+
+    pub fn unregister_offscreen_canvas_placeholder(&self, canvas_id: CanvasId) {
+        self.offscreen_canvas_placeholders
+            .borrow_mut()
+            .remove(&canvas_id);
+    }
+
+    /// Makes layout pick up a frame committed to the canvas backing a placeholder
+    /// canvas element.
+    pub fn dirty_offscreen_canvas_placeholder(&self, canvas_id: CanvasId) {
+        if let Some(placeholder) = self.offscreen_canvas_placeholders.borrow().get(&canvas_id) {
+            placeholder
+                .upcast::<Node>()
+                .dirty(NodeDamage::OtherNodeDamage);
+        }
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#the-documents-default-timeline>
+    pub fn timeline(&self) -> DomRoot<DocumentTimeline> {
+        self.timeline.or_init(|| DocumentTimeline::new(self, 0.))
+    }
+
+    pub fn add_timeline(&self, timeline: &DocumentTimeline) {
+        self.timelines.borrow_mut().push(Dom::from_ref(timeline));
+    }
+
+    fn timelines(&self) -> Vec<DomRoot<DocumentTimeline>> {
+        self.timelines
+            .borrow()
+            .iter()
+            .map(|timeline| DomRoot::from_ref(&**timeline))
+            .collect()
+    }
+
+    /// Whether any animation of this document needs its timeline to keep ticking.
+    fn has_running_animations(&self) -> bool {
+        self.timelines().iter().any(|timeline| {
+            timeline
+                .upcast::<AnimationTimeline>()
+                .has_running_animations()
+        })
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#update-animations-and-send-events>
+    fn update_animations_and_send_events(&self, now: f64) {
+        for timeline in self.timelines() {
+            timeline.update_current_time(now);
+            timeline.upcast::<AnimationTimeline>().update_animations();
+        }
+        self.sample_animations();
+    }
+
+    /// The relevant animations of this document whose keyframe effect targets
+    /// an element, in composite order.
+    ///
+    /// <https://drafts.csswg.org/web-animations/#relevant-animation>
+    pub fn relevant_animations(&self) -> Vec<(DomRoot<Animation>, DomRoot<Element>)> {
+        let mut animations = vec![];
+        for timeline in self.timelines() {
+            for animation in timeline.upcast::<AnimationTimeline>().animations() {
+                if !animation.is_relevant() {
+                    continue;
+                }
+                let target = animation
+                    .effect()
+                    .and_then(DomRoot::downcast::<KeyframeEffect>)
+                    .and_then(|effect| effect.target());
+                if let Some(target) = target {
+                    animations.push((animation, target));
+                }
+            }
+        }
+        animations
+    }
+
+    /// Applies the current values of the keyframe effects of the animations
+    /// of this document to their target elements.
+    pub fn sample_animations(&self) {
+        let mut samples: Vec<(DomRoot<Element>, Vec<KeyframeEffectSample>)> = vec![];
+        for timeline in self.timelines() {
+            for animation in timeline.upcast::<AnimationTimeline>().animations() {
+                let effect = match animation
+                    .effect()
+                    .and_then(DomRoot::downcast::<KeyframeEffect>)
+                {
+                    Some(effect) => effect,
+                    None => continue,
+                };
+                let target = match effect.target() {
+                    Some(target) => target,
+                    None => continue,
+                };
+                let index = match samples
+                    .iter()
+                    .position(|&(ref element, _)| *element == target)
+                {
+                    Some(index) => index,
+                    None => {
+                        samples.push((target, vec![]));
+                        samples.len() - 1
+                    },
+                };
+                effect.sample(&mut samples[index].1);
+            }
+        }
+
+        let previously_animated: Vec<DomRoot<Element>> = self
+            .animated_elements
+            .borrow()
+            .iter()
+            .map(|element| DomRoot::from_ref(&**element))
+            .collect();
+        {
+            let mut animated_elements = self.animated_elements.borrow_mut();
+            animated_elements.clear();
+            for &(ref element, _) in samples.iter() {
+                animated_elements.push(Dom::from_ref(&**element));
+            }
+        }
+        for element in previously_animated {
+            if !samples
+                .iter()
+                .any(|&(ref animated, _)| *animated == element)
+            {
+                element.set_keyframe_effect_samples(vec![]);
+            }
+        }
+        for (element, samples) in samples {
+            element.set_keyframe_effect_samples(samples);
+        }
+    }
+
+    pub fn add_intersection_observer(&self, observer: &IntersectionObserver) {
+        self.intersection_observers
+            .borrow_mut()
+            .push(Dom::from_ref(observer));
+    }
+
+    /// Observers without targets have nothing to report, so they are only
+    /// kept in the document while they observe something.
+    pub fn remove_intersection_observer(&self, observer: &IntersectionObserver) {
+        self.intersection_observers
+            .borrow_mut()
+            .retain(|registered| &**registered != observer);
+    }
+
+    /// <https://w3c.github.io/IntersectionObserver/#run-the-update-intersection-observations-steps>
+    pub fn update_intersection_observations(&self) {
+        // Step 1.
+        let observers: Vec<DomRoot<IntersectionObserver>> = self
+            .intersection_observers
+            .borrow()
+            .iter()
+            .map(|observer| DomRoot::from_ref(&**observer))
+            .collect();
+        if observers.is_empty() {
+            return;
+        }
+
+        // Step 2.
+        let time = *self.window.Performance().Now();
+        let mut queued = false;
+        for observer in observers {
+            queued |= observer.update_intersection_observations(time);
+        }
+        if queued {
+            self.queue_intersection_observer_task();
+        }
+    }
+
+    /// <https://w3c.github.io/IntersectionObserver/#queue-an-intersection-observer-task>
+    fn queue_intersection_observer_task(&self) {
+        // Steps 1-2.
+        if self.intersection_observer_task_queued.get() {
+            return;
+        }
+
+        // Step 3.
+        self.intersection_observer_task_queued.set(true);
+
+        // Step 4.
+        let document = Trusted::new(self);
+        let _ = self
+            .window
+            .task_manager()
+            .dom_manipulation_task_source()
+            .queue(
+                task!(notify_intersection_observers: move || {
+                    document.root().notify_intersection_observers();
+                }),
+                self.window.upcast(),
+            );
+    }
+
+    /// <https://w3c.github.io/IntersectionObserver/#notify-intersection-observers-algo>
+    fn notify_intersection_observers(&self) {
+        // Step 1.
+        self.intersection_observer_task_queued.set(false);
+
+        // Steps 2-3.
+        let observers: Vec<DomRoot<IntersectionObserver>> = self
+            .intersection_observers
+            .borrow()
+            .iter()
+            .map(|observer| DomRoot::from_ref(&**observer))
+            .collect();
+        for observer in observers {
+            observer.invoke_callback_if_necessary();
+        }
+    }
+
+    pub fn add_resize_observer(&self, observer: &ResizeObserver) {
+        self.resize_observers
+            .borrow_mut()
+            .push(Dom::from_ref(observer));
+    }
+
+    pub fn remove_resize_observer(&self, observer: &ResizeObserver) {
+        self.resize_observers
+            .borrow_mut()
+            .retain(|registered| &**registered != observer);
+    }
+
+    fn resize_observers(&self) -> Vec<DomRoot<ResizeObserver>> {
+        self.resize_observers
+            .borrow()
+            .iter()
+            .map(|observer| DomRoot::from_ref(&**observer))
+            .collect()
+    }
+
+    /// <https://drafts.csswg.org/resize-observer/#gather-active-observations-h>
+    pub fn gather_active_resize_observations_at_depth(&self, depth: usize) {
+        for observer in self.resize_observers() {
+            observer.gather_active_observations_at_depth(depth);
+        }
+    }
+
+    /// <https://drafts.csswg.org/resize-observer/#has-active-observations-h>
+    pub fn has_active_resize_observations(&self) -> bool {
+        self.resize_observers
+            .borrow()
+            .iter()
+            .any(|observer| observer.has_active_observations())
+    }
+
+    /// <https://drafts.csswg.org/resize-observer/#has-skipped-observations-h>
+    pub fn has_skipped_resize_observations(&self) -> bool {
+        self.resize_observers
+            .borrow()
+            .iter()
+            .any(|observer| observer.has_skipped_observations())
+    }
+
+    /// <https://drafts.csswg.org/resize-observer/#broadcast-active-observations>
+    pub fn broadcast_active_resize_observations(&self) -> usize {
+        self.resize_observers()
+            .iter()
+            .map(|observer| observer.broadcast_active_observations())
+            .min()
+            .unwrap_or(usize::max_value())
+    }
+
+    /// <https://drafts.csswg.org/resize-observer/#deliver-resize-error>
+    pub fn deliver_resize_loop_error_notification(&self) {
+        let error_info = ErrorInfo {
+            message: "ResizeObserver loop completed with undelivered notifications.".to_owned(),
+            filename: self.url().to_string(),
+            lineno: 0,
+            column: 0,
+        };
+        self.window
+            .upcast::<GlobalScope>()
+            .report_an_error(error_info, HandleValue::null());
+    }
 }
 
 #[derive(MallocSizeOf, PartialEq)]
@@ -2619,6 +3068,12 @@ fn get_registrable_domain_suffix_of_or_is_equal_to(
     Some(host)
 }
 
+/// Whether `node` is an `input` or `textarea` element, which manage their
+/// own selection.
+fn is_text_control(node: &Node) -> bool {
+    node.is::<HTMLInputElement>() || node.is::<HTMLTextAreaElement>()
+}
+
 /// <https://url.spec.whatwg.org/#network-scheme>
 fn url_has_network_scheme(url: &ServoUrl) -> bool {
     match url.scheme() {
@@ -2777,6 +3232,18 @@ impl Document {
             shadow_roots: DomRefCell::new(HashSet::new()),
             shadow_roots_styles_changed: Cell::new(false),
             media_controls: DomRefCell::new(HashMap::new()),
+            intersection_observers: DomRefCell::new(vec![]),
+            intersection_observer_task_queued: Cell::new(false),
+            resize_observers: DomRefCell::new(vec![]),
+            selection: Default::default(),
+            selecting_text: Cell::new(false),
+            design_mode: Cell::new(false),
+            undo_history: Default::default(),
+            offscreen_canvas_placeholders: DomRefCell::new(HashMap::new()),
+            timeline: Default::default(),
+            timelines: DomRefCell::new(vec![]),
+            animated_elements: DomRefCell::new(vec![]),
+            fonts: Default::default(),
         }
     }
 
@@ -3389,6 +3856,63 @@ impl DocumentMethods for Document {
         false
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-document-designmode
+    fn DesignMode(&self) -> DOMString {
+        DOMString::from(if self.design_mode.get() { "on" } else { "off" })
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-document-designmode
+    fn SetDesignMode(&self, value: DOMString) {
+        if value.eq_ignore_ascii_case("on") && !self.design_mode.get() {
+            self.design_mode.set(true);
+            if let Some(selection) = self.GetSelection() {
+                let _ = selection.Collapse(Some(self.upcast()), 0);
+            }
+        } else if value.eq_ignore_ascii_case("off") {
+            self.design_mode.set(false);
+        }
+    }
+
+    // https://w3c.github.io/editing/docs/execCommand/#execcommand()
+    fn ExecCommand(&self, command_id: DOMString, _show_ui: bool, value: DOMString) -> bool {
+        EditingCommand::from_command_id(&command_id).map_or(false, |command| {
+            editing::execute_command(self, command, value, false)
+        })
+    }
+
+    // https://w3c.github.io/editing/docs/execCommand/#querycommandenabled()
+    fn QueryCommandEnabled(&self, command_id: DOMString) -> bool {
+        EditingCommand::from_command_id(&command_id).map_or(false, |command| {
+            editing::query_command_enabled(self, command)
+        })
+    }
+
+    // https://w3c.github.io/editing/docs/execCommand/#querycommandindeterm()
+    fn QueryCommandIndeterm(&self, _command_id: DOMString) -> bool {
+        false
+    }
+
+    // https://w3c.github.io/editing/docs/execCommand/#querycommandstate()
+    fn QueryCommandState(&self, command_id: DOMString) -> bool {
+        EditingCommand::from_command_id(&command_id)
+            .map_or(false, |command| editing::query_command_state(self, command))
+    }
+
+    // https://w3c.github.io/editing/docs/execCommand/#querycommandsupported()
+    fn QueryCommandSupported(&self, command_id: DOMString) -> bool {
+        EditingCommand::from_command_id(&command_id).is_some()
+    }
+
+    // https://w3c.github.io/editing/docs/execCommand/#querycommandvalue()
+    fn QueryCommandValue(&self, command_id: DOMString) -> DOMString {
+        match EditingCommand::from_command_id(&command_id) {
+            Some(command @ EditingCommand::Bold) | Some(command @ EditingCommand::Italic) => {
+                DOMString::from(editing::query_command_state(self, command).to_string())
+            },
+            _ => DOMString::new(),
+        }
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-document-domain
     fn Domain(&self) -> DOMString {
         // Step 1.
@@ -4577,6 +5101,37 @@ impl DocumentMethods for Document {
         self.exit_fullscreen()
     }
 
+    // https://w3c.github.io/selection-api/#dom-document-getselection
+    fn GetSelection(&self) -> Option<DomRoot<Selection>> {
+        if self.has_browsing_context {
+            Some(self.selection.or_init(|| Selection::new(self)))
+        } else {
+            None
+        }
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-document-timeline
+    fn Timeline(&self) -> DomRoot<DocumentTimeline> {
+        self.timeline()
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-documentorshadowroot-getanimations
+    fn GetAnimations(&self) -> Vec<DomRoot<Animation>> {
+        self.relevant_animations()
+            .into_iter()
+            .filter(|&(_, ref target)| {
+                let node = target.upcast::<Node>();
+                node.is_connected() && &*node.owner_doc() == self
+            })
+            .map(|(animation, _)| animation)
+            .collect()
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontfacesource-fonts
+    fn Fonts(&self) -> DomRoot<FontFaceSet> {
+        self.fonts.or_init(|| FontFaceSet::new(&self.window))
+    }
+
     // check-tidy: no specs after this line
     // Servo only API to get an instance of the controls of a specific
     // media element matching the given id.
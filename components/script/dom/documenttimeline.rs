@@ -0,0 +1,104 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::animation::Animation;
+use crate::dom::animationeffect::{AnimationEffect, Timing};
+use crate::dom::animationtimeline::AnimationTimeline;
+use crate::dom::bindings::codegen::Bindings::AnimationEffectBinding::FillMode;
+use crate::dom::bindings::codegen::Bindings::DocumentTimelineBinding;
+use crate::dom::bindings::codegen::Bindings::DocumentTimelineBinding::DocumentTimelineOptions;
+use crate::dom::bindings::codegen::Bindings::PerformanceBinding::PerformanceMethods;
+use crate::dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
+use crate::dom::bindings::error::Fallible;
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject};
+use crate::dom::bindings::root::DomRoot;
+use crate::dom::bindings::str::DOMString;
+use crate::dom::cssanimation::CSSAnimation;
+use crate::dom::csstransition::CSSTransition;
+use crate::dom::document::Document;
+use crate::dom::element::Element;
+use crate::dom::keyframeeffect::KeyframeEffect;
+use crate::dom::window::Window;
+use dom_struct::dom_struct;
+use script_layout_interface::{CSSAnimationKind, NewCSSAnimation};
+
+/// <https://drafts.csswg.org/web-animations/#documenttimeline>
+#[dom_struct]
+pub struct DocumentTimeline {
+    animationtimeline: AnimationTimeline,
+    /// The offset of this timeline's zero time from the time origin of its
+    /// document, in milliseconds.
+    origin_time: f64,
+}
+
+impl DocumentTimeline {
+    fn new_inherited(window: &Window, origin_time: f64) -> DocumentTimeline {
+        let now = *window.Performance().Now();
+        DocumentTimeline {
+            animationtimeline: AnimationTimeline::new_inherited(Some(now - origin_time)),
+            origin_time,
+        }
+    }
+
+    pub fn new(document: &Document, origin_time: f64) -> DomRoot<DocumentTimeline> {
+        let window = document.window();
+        let timeline = reflect_dom_object(
+            Box::new(DocumentTimeline::new_inherited(window, origin_time)),
+            window,
+            DocumentTimelineBinding::Wrap,
+        );
+        document.add_timeline(&timeline);
+        timeline
+    }
+
+    pub fn Constructor(
+        window: &Window,
+        options: &DocumentTimelineOptions,
+    ) -> Fallible<DomRoot<DocumentTimeline>> {
+        Ok(DocumentTimeline::new(
+            &window.Document(),
+            *options.originTime,
+        ))
+    }
+
+    /// Updates the current time of this timeline from the given time, relative
+    /// to the time origin of the document.
+    ///
+    /// <https://drafts.csswg.org/web-animations/#update-animations-and-send-events>
+    pub fn update_current_time(&self, now: f64) {
+        self.upcast::<AnimationTimeline>()
+            .set_current_time(Some(now - self.origin_time));
+    }
+
+    /// Exposes a CSS animation or transition that layout started running for
+    /// the given element as a playing CSSAnimation or CSSTransition.
+    ///
+    /// The returned object only mirrors the timing of the CSS animation, which
+    /// keeps being rendered by layout itself.
+    pub fn track_css_animation(&self, target: &Element, new_animation: &NewCSSAnimation) {
+        let global = self.global();
+        let window = global.as_window();
+        let mut timing = Timing::default();
+        timing.delay = new_animation.delay * 1000.;
+        timing.duration = Some(new_animation.duration * 1000.);
+        timing.fill = FillMode::None;
+        if let CSSAnimationKind::Animation(_, iterations) = new_animation.kind {
+            timing.iterations = iterations;
+        }
+
+        let effect = KeyframeEffect::new(window, Some(target), timing);
+        let effect = effect.upcast::<AnimationEffect>();
+        let timeline = self.upcast::<AnimationTimeline>();
+        let animation = match new_animation.kind {
+            CSSAnimationKind::Animation(ref name, _) => DomRoot::upcast::<Animation>(
+                CSSAnimation::new(window, effect, timeline, DOMString::from(&**name)),
+            ),
+            CSSAnimationKind::Transition(ref property) => DomRoot::upcast::<Animation>(
+                CSSTransition::new(window, effect, timeline, DOMString::from(&**property)),
+            ),
+        };
+        let _ = animation.play(false);
+    }
+}
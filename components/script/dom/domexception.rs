@@ -38,6 +38,12 @@ pub enum DOMErrorName {
     DataCloneError = DOMExceptionConstants::DATA_CLONE_ERR,
     NotReadableError,
     OperationError,
+    UnknownError,
+    ConstraintError,
+    DataError,
+    TransactionInactiveError,
+    ReadOnlyError,
+    VersionError,
 }
 
 impl DOMErrorName {
@@ -66,6 +72,12 @@ impl DOMErrorName {
             "DataCloneError" => Some(DOMErrorName::DataCloneError),
             "NotReadableError" => Some(DOMErrorName::NotReadableError),
             "OperationError" => Some(DOMErrorName::OperationError),
+            "UnknownError" => Some(DOMErrorName::UnknownError),
+            "ConstraintError" => Some(DOMErrorName::ConstraintError),
+            "DataError" => Some(DOMErrorName::DataError),
+            "TransactionInactiveError" => Some(DOMErrorName::TransactionInactiveError),
+            "ReadOnlyError" => Some(DOMErrorName::ReadOnlyError),
+            "VersionError" => Some(DOMErrorName::VersionError),
             _ => None,
         }
     }
@@ -112,6 +124,20 @@ impl DOMException {
             DOMErrorName::OperationError => {
                 "The operation failed for an operation-specific reason."
             },
+            DOMErrorName::UnknownError => "The operation failed for an unknown transient reason.",
+            DOMErrorName::ConstraintError => {
+                "A mutation operation in a transaction failed because a constraint was not satisfied."
+            },
+            DOMErrorName::DataError => "Provided data is inadequate.",
+            DOMErrorName::TransactionInactiveError => {
+                "A request was placed against a transaction which is not active, or which is finished."
+            },
+            DOMErrorName::ReadOnlyError => {
+                "The mutating operation was attempted in a \"readonly\" transaction."
+            },
+            DOMErrorName::VersionError => {
+                "An attempt was made to open a database using a lower version than the existing version."
+            },
         };
 
         (
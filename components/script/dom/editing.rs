@@ -0,0 +1,851 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Editing of the contents of editing hosts and of documents in design mode.
+
+use crate::dom::bindings::codegen::Bindings::CharacterDataBinding::CharacterDataMethods;
+use crate::dom::bindings::codegen::Bindings::ElementBinding::ElementMethods;
+use crate::dom::bindings::codegen::Bindings::EventBinding::EventBinding::EventMethods;
+use crate::dom::bindings::codegen::Bindings::NodeBinding::NodeMethods;
+use crate::dom::bindings::codegen::Bindings::RangeBinding::RangeMethods;
+use crate::dom::bindings::codegen::Bindings::SelectionBinding::SelectionMethods;
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::root::{Dom, DomRoot};
+use crate::dom::bindings::str::DOMString;
+use crate::dom::characterdata::CharacterData;
+use crate::dom::document::Document;
+use crate::dom::element::{CustomElementCreationMode, Element, ElementCreator};
+use crate::dom::event::Event;
+use crate::dom::htmlbrelement::HTMLBRElement;
+use crate::dom::htmlelement::HTMLElement;
+use crate::dom::inputevent::InputEvent;
+use crate::dom::keyboardevent::KeyboardEvent;
+use crate::dom::node::{document_from_node, Node, ShadowIncluding};
+use crate::dom::range::{bp_position, Range};
+use crate::dom::selection::Selection;
+use crate::dom::text::Text;
+use crate::textinput::CMD_OR_CONTROL;
+use html5ever::{LocalName, QualName};
+use keyboard_types::{Key, KeyState, Modifiers, ShortcutMatcher};
+use std::cmp::Ordering;
+
+/// <https://html.spec.whatwg.org/multipage/#attr-contenteditable>
+#[derive(Clone, Copy, PartialEq)]
+pub enum ContentEditableState {
+    True,
+    False,
+    Inherit,
+}
+
+impl ContentEditableState {
+    pub fn of(element: &Element) -> ContentEditableState {
+        if !element.is::<HTMLElement>() {
+            return ContentEditableState::Inherit;
+        }
+        match element.get_attribute(&ns!(), &local_name!("contenteditable")) {
+            Some(attr) => {
+                let value = attr.value();
+                if value.is_empty() || value.eq_ignore_ascii_case("true") {
+                    ContentEditableState::True
+                } else if value.eq_ignore_ascii_case("false") {
+                    ContentEditableState::False
+                } else {
+                    ContentEditableState::Inherit
+                }
+            },
+            None => ContentEditableState::Inherit,
+        }
+    }
+}
+
+/// The editing host `node` belongs to, if any. The editing host of a document
+/// in design mode is its document element.
+///
+/// <https://html.spec.whatwg.org/multipage/#editing-host>
+pub fn editing_host_of(node: &Node) -> Option<DomRoot<Element>> {
+    let mut host = None;
+    for element in node
+        .inclusive_ancestors(ShadowIncluding::No)
+        .filter_map(DomRoot::downcast::<Element>)
+    {
+        match ContentEditableState::of(&element) {
+            ContentEditableState::True => host = Some(element),
+            ContentEditableState::False => return host,
+            ContentEditableState::Inherit => {},
+        }
+    }
+    if host.is_some() {
+        return host;
+    }
+    let document = document_from_node(node);
+    if document.design_mode() && node.is_in_doc() {
+        return document.GetDocumentElement();
+    }
+    None
+}
+
+/// Whether a caret should be painted in `node`, which is the case when it is
+/// in a focused editing host or in a document in design mode.
+pub fn is_caret_visible_in(node: &Node) -> bool {
+    editing_host_of(node).map_or(false, |host| {
+        host.focus_state() || document_from_node(node).design_mode()
+    })
+}
+
+/// <https://w3c.github.io/editing/docs/execCommand/#commands>
+#[derive(Clone, Copy, PartialEq)]
+pub enum EditingCommand {
+    Bold,
+    CreateLink,
+    Delete,
+    ForwardDelete,
+    InsertLineBreak,
+    InsertText,
+    Italic,
+    Redo,
+    SelectAll,
+    Undo,
+}
+
+impl EditingCommand {
+    pub fn from_command_id(command_id: &str) -> Option<EditingCommand> {
+        Some(match_ignore_ascii_case! { command_id,
+            "bold" => EditingCommand::Bold,
+            "createlink" => EditingCommand::CreateLink,
+            "delete" => EditingCommand::Delete,
+            "forwarddelete" => EditingCommand::ForwardDelete,
+            "insertlinebreak" => EditingCommand::InsertLineBreak,
+            "inserttext" => EditingCommand::InsertText,
+            "italic" => EditingCommand::Italic,
+            "redo" => EditingCommand::Redo,
+            "selectall" => EditingCommand::SelectAll,
+            "undo" => EditingCommand::Undo,
+            _ => return None,
+        })
+    }
+
+    /// <https://w3c.github.io/input-events/#interface-InputEvent-Attributes>
+    fn input_type(&self) -> &'static str {
+        match *self {
+            EditingCommand::Bold => "formatBold",
+            EditingCommand::CreateLink => "insertLink",
+            EditingCommand::Delete => "deleteContentBackward",
+            EditingCommand::ForwardDelete => "deleteContentForward",
+            EditingCommand::InsertLineBreak => "insertLineBreak",
+            EditingCommand::InsertText => "insertText",
+            EditingCommand::Italic => "formatItalic",
+            EditingCommand::Redo => "historyRedo",
+            EditingCommand::SelectAll => "",
+            EditingCommand::Undo => "historyUndo",
+        }
+    }
+
+    /// Whether `name` is the name of an element applying the style of this
+    /// command.
+    fn is_style_element_name(&self, name: &LocalName) -> bool {
+        match *self {
+            EditingCommand::Bold => *name == local_name!("b") || *name == local_name!("strong"),
+            EditingCommand::Italic => *name == local_name!("i") || *name == local_name!("em"),
+            EditingCommand::CreateLink => *name == local_name!("a"),
+            _ => false,
+        }
+    }
+
+    /// The name of the element that applies the style of this command.
+    fn style_element_name(&self) -> LocalName {
+        match *self {
+            EditingCommand::Bold => local_name!("b"),
+            EditingCommand::Italic => local_name!("i"),
+            _ => local_name!("a"),
+        }
+    }
+}
+
+/// An edit of an editing host recorded in the undo history, as the markup of
+/// its contents before and after the edit.
+#[derive(JSTraceable, MallocSizeOf)]
+#[must_root]
+struct EditingTransaction {
+    host: Dom<Element>,
+    before: DOMString,
+    after: DOMString,
+}
+
+/// <https://w3c.github.io/editing/docs/execCommand/#undo-history>
+#[derive(Default, JSTraceable, MallocSizeOf)]
+#[must_root]
+pub struct UndoHistory {
+    undo_stack: Vec<EditingTransaction>,
+    redo_stack: Vec<EditingTransaction>,
+}
+
+impl UndoHistory {
+    #[allow(unrooted_must_root)]
+    fn record(&mut self, host: &Element, before: DOMString, after: DOMString) {
+        self.undo_stack.push(EditingTransaction {
+            host: Dom::from_ref(host),
+            before,
+            after,
+        });
+        self.redo_stack.clear();
+    }
+
+    fn next_host(&self, command: EditingCommand) -> Option<DomRoot<Element>> {
+        let stack = match command {
+            EditingCommand::Undo => &self.undo_stack,
+            _ => &self.redo_stack,
+        };
+        stack
+            .last()
+            .map(|transaction| DomRoot::from_ref(&*transaction.host))
+    }
+
+    /// Move the last transaction of the undo stack to the redo stack, or the
+    /// other way around for a redo, and return the markup to restore.
+    #[allow(unrooted_must_root)]
+    fn step(&mut self, command: EditingCommand) -> Option<DOMString> {
+        let (from, to) = match command {
+            EditingCommand::Undo => (&mut self.undo_stack, &mut self.redo_stack),
+            _ => (&mut self.redo_stack, &mut self.undo_stack),
+        };
+        let transaction = from.pop()?;
+        let markup = match command {
+            EditingCommand::Undo => transaction.before.clone(),
+            _ => transaction.after.clone(),
+        };
+        to.push(transaction);
+        Some(markup)
+    }
+}
+
+/// A boundary point, as a node and an offset in it.
+type Position = (DomRoot<Node>, u32);
+
+/// The places between which the caret moves in an editing host.
+enum CaretStop {
+    Text(DomRoot<Text>),
+    LineBreak(DomRoot<Node>),
+}
+
+fn caret_stops(host: &Element) -> Vec<CaretStop> {
+    host.upcast::<Node>()
+        .traverse_preorder(ShadowIncluding::No)
+        .filter_map(|node| {
+            if node.is::<HTMLBRElement>() {
+                return Some(CaretStop::LineBreak(node));
+            }
+            DomRoot::downcast::<Text>(node).map(CaretStop::Text)
+        })
+        .collect()
+}
+
+fn text_data(text: &Text) -> DOMString {
+    text.upcast::<CharacterData>().Data()
+}
+
+fn text_position(text: &Text, offset: u32) -> Position {
+    (DomRoot::from_ref(text.upcast()), offset)
+}
+
+/// The offset in UTF-16 code units of the code point after the one at `offset`.
+fn next_offset(data: &str, offset: u32) -> Option<u32> {
+    let mut code_units = 0;
+    for c in data.chars() {
+        let next = code_units + c.len_utf16() as u32;
+        if code_units >= offset {
+            return Some(next);
+        }
+        code_units = next;
+    }
+    None
+}
+
+/// The offset in UTF-16 code units of the code point before the one at `offset`.
+fn previous_offset(data: &str, offset: u32) -> Option<u32> {
+    let mut code_units = 0;
+    for c in data.chars() {
+        let next = code_units + c.len_utf16() as u32;
+        if next >= offset {
+            return if offset == 0 { None } else { Some(code_units) };
+        }
+        code_units = next;
+    }
+    None
+}
+
+fn position_before(node: &Node) -> Option<Position> {
+    node.GetParentNode().map(|parent| (parent, node.index()))
+}
+
+fn position_after(node: &Node) -> Option<Position> {
+    node.GetParentNode()
+        .map(|parent| (parent, node.index() + 1))
+}
+
+fn first_text(stops: &[CaretStop]) -> Option<&DomRoot<Text>> {
+    stops.iter().find_map(|stop| match *stop {
+        CaretStop::Text(ref text) => Some(text),
+        CaretStop::LineBreak(_) => None,
+    })
+}
+
+fn last_text(stops: &[CaretStop]) -> Option<&DomRoot<Text>> {
+    stops.iter().rev().find_map(|stop| match *stop {
+        CaretStop::Text(ref text) => Some(text),
+        CaretStop::LineBreak(_) => None,
+    })
+}
+
+/// Move `position` into the text node that follows it, or to the end of the
+/// last text node of the editing host, so that it can be moved by one
+/// character.
+fn normalize_position(stops: &[CaretStop], position: Position) -> Position {
+    let (node, offset) = position;
+    if node.is::<Text>() {
+        return (node, offset);
+    }
+    let following_text = stops.iter().find_map(|stop| match *stop {
+        CaretStop::Text(ref text)
+            if bp_position(text.upcast(), 0, &node, offset) != Some(Ordering::Less) =>
+        {
+            Some(text)
+        },
+        _ => None,
+    });
+    match following_text {
+        Some(text) => text_position(text, 0),
+        None => match last_text(stops) {
+            Some(text) => text_position(text, text.upcast::<CharacterData>().Length()),
+            None => (node, offset),
+        },
+    }
+}
+
+fn stop_index(stops: &[CaretStop], node: &Node) -> Option<usize> {
+    stops.iter().position(|stop| match *stop {
+        CaretStop::Text(ref text) => text.upcast::<Node>() == node,
+        CaretStop::LineBreak(_) => false,
+    })
+}
+
+/// The position one character after `position`.
+fn next_position(stops: &[CaretStop], position: &Position) -> Option<Position> {
+    let (ref node, offset) = *position;
+    let index = stop_index(stops, node)?;
+    if let Some(next) = next_offset(&text_data(node.downcast::<Text>()?), offset) {
+        return Some((node.clone(), next));
+    }
+    for (index, stop) in stops.iter().enumerate().skip(index + 1) {
+        match *stop {
+            CaretStop::LineBreak(ref line_break) => {
+                return match stops.get(index + 1) {
+                    Some(&CaretStop::Text(ref text)) => Some(text_position(text, 0)),
+                    _ => position_after(line_break),
+                };
+            },
+            CaretStop::Text(ref text) => {
+                if let Some(next) = next_offset(&text_data(text), 0) {
+                    return Some(text_position(text, next));
+                }
+            },
+        }
+    }
+    None
+}
+
+/// The position one character before `position`.
+fn previous_position(stops: &[CaretStop], position: &Position) -> Option<Position> {
+    let (ref node, offset) = *position;
+    let index = stop_index(stops, node)?;
+    if let Some(previous) = previous_offset(&text_data(node.downcast::<Text>()?), offset) {
+        return Some((node.clone(), previous));
+    }
+    for (index, stop) in stops[..index].iter().enumerate().rev() {
+        match *stop {
+            CaretStop::LineBreak(ref line_break) => {
+                let previous_stop = index.checked_sub(1).and_then(|index| stops.get(index));
+                return match previous_stop {
+                    Some(&CaretStop::Text(ref text)) => {
+                        Some(text_position(text, text.upcast::<CharacterData>().Length()))
+                    },
+                    _ => position_before(line_break),
+                };
+            },
+            CaretStop::Text(ref text) => {
+                let data = text_data(text);
+                let length = text.upcast::<CharacterData>().Length();
+                if let Some(previous) = previous_offset(&data, length) {
+                    return Some(text_position(text, previous));
+                }
+            },
+        }
+    }
+    None
+}
+
+/// The range of the selection of `document`, if it is in `host`. Otherwise,
+/// collapse the selection at the start of `host` first.
+fn range_in_host(selection: &Selection, host: &Element) -> Option<DomRoot<Range>> {
+    if let Ok(range) = selection.GetRangeAt(0) {
+        if host
+            .upcast::<Node>()
+            .is_inclusive_ancestor_of(&range.StartContainer())
+        {
+            return Some(range);
+        }
+    }
+    let stops = caret_stops(host);
+    let (node, offset) = normalize_position(&stops, (DomRoot::from_ref(host.upcast()), 0));
+    selection.Collapse(Some(&node), offset).ok()?;
+    selection.GetRangeAt(0).ok()
+}
+
+/// The closest inclusive ancestor of `node` in `host` that applies the style
+/// of `command`.
+fn enclosing_element(
+    node: &Node,
+    host: &Element,
+    command: EditingCommand,
+) -> Option<DomRoot<Element>> {
+    node.inclusive_ancestors(ShadowIncluding::No)
+        .take_while(|ancestor| &**ancestor != host.upcast::<Node>())
+        .filter_map(DomRoot::downcast::<Element>)
+        .find(|element| {
+            *element.namespace() == ns!(html) && command.is_style_element_name(element.local_name())
+        })
+}
+
+/// The editing host a command applies to, if any.
+fn command_host(document: &Document, command: EditingCommand) -> Option<DomRoot<Element>> {
+    match command {
+        EditingCommand::Undo | EditingCommand::Redo => {
+            document.undo_history().borrow().next_host(command)
+        },
+        _ => {
+            let range = document.GetSelection()?.GetRangeAt(0).ok()?;
+            editing_host_of(&range.StartContainer())
+        },
+    }
+}
+
+/// <https://w3c.github.io/editing/docs/execCommand/#querycommandenabled()>
+pub fn query_command_enabled(document: &Document, command: EditingCommand) -> bool {
+    command == EditingCommand::SelectAll || command_host(document, command).is_some()
+}
+
+/// <https://w3c.github.io/editing/docs/execCommand/#querycommandstate()>
+pub fn query_command_state(document: &Document, command: EditingCommand) -> bool {
+    let host = match command_host(document, command) {
+        Some(host) => host,
+        None => return false,
+    };
+    let range = match document.GetSelection().and_then(|s| s.GetRangeAt(0).ok()) {
+        Some(range) => range,
+        None => return false,
+    };
+    command != EditingCommand::CreateLink &&
+        enclosing_element(&range.StartContainer(), &host, command).is_some()
+}
+
+fn create_element(document: &Document, name: LocalName) -> DomRoot<Element> {
+    Element::create(
+        QualName::new(None, ns!(html), name),
+        None,
+        document,
+        ElementCreator::ScriptCreated,
+        CustomElementCreationMode::Synchronous,
+    )
+}
+
+fn fire_input_event(
+    document: &Document,
+    host: &Element,
+    type_: &str,
+    cancelable: bool,
+    command: EditingCommand,
+    data: Option<DOMString>,
+) -> bool {
+    let window = document.window();
+    let event = InputEvent::new(
+        window,
+        DOMString::from(type_),
+        true,
+        cancelable,
+        Some(window),
+        0,
+        data,
+        false,
+        DOMString::from(command.input_type()),
+    );
+    let event = event.upcast::<Event>();
+    event.fire(host.upcast());
+    !event.DefaultPrevented()
+}
+
+/// Runs `command` in the editing host of the selection, or in the editing
+/// host of the undo history for undo and redo. Edits requested by the user
+/// fire a cancelable `beforeinput` event first.
+///
+/// <https://w3c.github.io/editing/docs/execCommand/#execcommand()>
+pub fn execute_command(
+    document: &Document,
+    command: EditingCommand,
+    value: DOMString,
+    user_initiated: bool,
+) -> bool {
+    let selection = match document.GetSelection() {
+        Some(selection) => selection,
+        None => return false,
+    };
+
+    if command == EditingCommand::SelectAll {
+        let root = command_host(document, command).or_else(|| document.GetBody());
+        return root.map_or(false, |root| {
+            selection.SelectAllChildren(root.upcast()).is_ok()
+        });
+    }
+
+    let host = match command_host(document, command) {
+        Some(host) => host,
+        None => return false,
+    };
+    let data = match command {
+        EditingCommand::InsertText => Some(value.clone()),
+        _ => None,
+    };
+    if user_initiated &&
+        !fire_input_event(document, &host, "beforeinput", true, command, data.clone())
+    {
+        return false;
+    }
+
+    let before = host.GetInnerHTML().unwrap_or_default();
+    let done = match command {
+        EditingCommand::Undo | EditingCommand::Redo => {
+            step_history(document, &selection, &host, command)
+        },
+        _ => match range_in_host(&selection, &host) {
+            Some(range) => run_command(document, &selection, &host, &range, command, value),
+            None => false,
+        },
+    };
+    if !done {
+        return false;
+    }
+    if command != EditingCommand::Undo && command != EditingCommand::Redo {
+        let after = host.GetInnerHTML().unwrap_or_default();
+        if before != after {
+            document
+                .undo_history()
+                .borrow_mut()
+                .record(&host, before, after);
+        }
+    }
+
+    fire_input_event(document, &host, "input", false, command, data);
+    true
+}
+
+fn step_history(
+    document: &Document,
+    selection: &Selection,
+    host: &Element,
+    command: EditingCommand,
+) -> bool {
+    let markup = match document.undo_history().borrow_mut().step(command) {
+        Some(markup) => markup,
+        None => return false,
+    };
+    if host.SetInnerHTML(markup).is_err() {
+        return false;
+    }
+    let stops = caret_stops(host);
+    let end = match last_text(&stops) {
+        Some(text) => text_position(text, text.upcast::<CharacterData>().Length()),
+        None => {
+            let host = host.upcast::<Node>();
+            (DomRoot::from_ref(host), host.children_count())
+        },
+    };
+    selection.Collapse(Some(&end.0), end.1).is_ok()
+}
+
+fn run_command(
+    document: &Document,
+    selection: &Selection,
+    host: &Element,
+    range: &Range,
+    command: EditingCommand,
+    value: DOMString,
+) -> bool {
+    match command {
+        EditingCommand::Bold | EditingCommand::Italic => {
+            match enclosing_element(&range.StartContainer(), host, command) {
+                Some(element) => unwrap_element(selection, &element),
+                None => {
+                    let element = create_element(document, command.style_element_name());
+                    wrap_range(selection, range, &element)
+                },
+            }
+        },
+        EditingCommand::CreateLink => {
+            if value.is_empty() {
+                return false;
+            }
+            if let Some(link) = enclosing_element(&range.StartContainer(), host, command) {
+                link.set_string_attribute(&local_name!("href"), value);
+                return true;
+            }
+            let link = create_element(document, local_name!("a"));
+            link.set_string_attribute(&local_name!("href"), value.clone());
+            if range.Collapsed() {
+                let text = Text::new(value, document);
+                link.upcast::<Node>().AppendChild(text.upcast()).is_ok() &&
+                    range.InsertNode(link.upcast()).is_ok() &&
+                    position_after(link.upcast()).map_or(false, |(node, offset)| {
+                        selection.Collapse(Some(&node), offset).is_ok()
+                    })
+            } else {
+                wrap_range(selection, range, &link)
+            }
+        },
+        EditingCommand::Delete | EditingCommand::ForwardDelete => delete(
+            document,
+            selection,
+            host,
+            range,
+            command == EditingCommand::Delete,
+        ),
+        EditingCommand::InsertLineBreak => insert_line_break(document, selection, range),
+        EditingCommand::InsertText => insert_text(document, selection, range, value),
+        EditingCommand::Redo | EditingCommand::SelectAll | EditingCommand::Undo => false,
+    }
+}
+
+/// Move the contents of `range` into `element`, and put `element` in their place.
+fn wrap_range(selection: &Selection, range: &Range, element: &Element) -> bool {
+    if range.Collapsed() {
+        return false;
+    }
+    let fragment = match range.ExtractContents() {
+        Ok(fragment) => fragment,
+        Err(_) => return false,
+    };
+    element
+        .upcast::<Node>()
+        .AppendChild(fragment.upcast())
+        .is_ok() &&
+        range.InsertNode(element.upcast()).is_ok() &&
+        selection.SelectAllChildren(element.upcast()).is_ok()
+}
+
+/// Replace `element` by its children, and select them.
+fn unwrap_element(selection: &Selection, element: &Element) -> bool {
+    let node = element.upcast::<Node>();
+    let parent = match node.GetParentNode() {
+        Some(parent) => parent,
+        None => return false,
+    };
+    let start = node.index();
+    let count = node.children_count();
+    while let Some(child) = node.GetFirstChild() {
+        if parent.InsertBefore(&child, Some(node)).is_err() {
+            return false;
+        }
+    }
+    parent.RemoveChild(node).is_ok() &&
+        selection
+            .SetBaseAndExtent(&parent, start, &parent, start + count)
+            .is_ok()
+}
+
+fn delete(
+    document: &Document,
+    selection: &Selection,
+    host: &Element,
+    range: &Range,
+    backward: bool,
+) -> bool {
+    let range = if range.Collapsed() {
+        let stops = caret_stops(host);
+        let caret = (range.StartContainer(), range.StartOffset());
+        let normalized = normalize_position(&stops, (caret.0.clone(), caret.1));
+        let (start, end) = if backward {
+            match previous_position(&stops, &normalized) {
+                Some(previous) => (previous, caret),
+                None => return false,
+            }
+        } else {
+            match next_position(&stops, &normalized) {
+                Some(next) => (caret, next),
+                None => return false,
+            }
+        };
+        Range::new(document, &start.0, start.1, &end.0, end.1)
+    } else {
+        DomRoot::from_ref(range)
+    };
+    if range.DeleteContents().is_err() {
+        return false;
+    }
+    selection
+        .Collapse(Some(&range.StartContainer()), range.StartOffset())
+        .is_ok()
+}
+
+fn insert_line_break(document: &Document, selection: &Selection, range: &Range) -> bool {
+    if !range.Collapsed() && range.DeleteContents().is_err() {
+        return false;
+    }
+    let line_break = create_element(document, local_name!("br"));
+    let line_break = line_break.upcast::<Node>();
+    if range.InsertNode(line_break).is_err() {
+        return false;
+    }
+
+    // Keep a text node after the line break for the caret to be painted in.
+    let text_after = line_break
+        .GetNextSibling()
+        .and_then(DomRoot::downcast::<Text>);
+    let text_after = match text_after {
+        Some(text) => text,
+        None => {
+            let text = Text::new(DOMString::new(), document);
+            let parent = match line_break.GetParentNode() {
+                Some(parent) => parent,
+                None => return false,
+            };
+            let next_sibling = line_break.GetNextSibling();
+            if parent
+                .InsertBefore(text.upcast(), next_sibling.as_deref())
+                .is_err()
+            {
+                return false;
+            }
+            text
+        },
+    };
+    selection.Collapse(Some(text_after.upcast()), 0).is_ok()
+}
+
+fn insert_text(
+    document: &Document,
+    selection: &Selection,
+    range: &Range,
+    value: DOMString,
+) -> bool {
+    if !range.Collapsed() && range.DeleteContents().is_err() {
+        return false;
+    }
+    if value.is_empty() {
+        return true;
+    }
+    let length = value.encode_utf16().count() as u32;
+    let node = range.StartContainer();
+    let offset = range.StartOffset();
+    let caret = match node.downcast::<Text>() {
+        Some(text) => {
+            if text
+                .upcast::<CharacterData>()
+                .InsertData(offset, value)
+                .is_err()
+            {
+                return false;
+            }
+            (node.clone(), offset + length)
+        },
+        None => {
+            let text = Text::new(value, document);
+            if range.InsertNode(text.upcast()).is_err() {
+                return false;
+            }
+            text_position(&text, length)
+        },
+    };
+    selection.Collapse(Some(&caret.0), caret.1).is_ok()
+}
+
+/// Move the caret, or the focus of the selection when `extend` is set, one
+/// character forward or backward, or to the start or end of `host`.
+fn move_caret(selection: &Selection, host: &Element, key: &Key, extend: bool) -> bool {
+    let stops = caret_stops(host);
+    let focus = match selection.GetFocusNode() {
+        Some(node) => (node, selection.FocusOffset()),
+        None => return false,
+    };
+    let target = match *key {
+        Key::ArrowLeft | Key::ArrowRight if !extend && !selection.IsCollapsed() => {
+            return match *key {
+                Key::ArrowLeft => selection.CollapseToStart().is_ok(),
+                _ => selection.CollapseToEnd().is_ok(),
+            };
+        },
+        Key::ArrowLeft => previous_position(&stops, &normalize_position(&stops, focus)),
+        Key::ArrowRight => next_position(&stops, &normalize_position(&stops, focus)),
+        Key::Home => first_text(&stops).map(|text| text_position(text, 0)),
+        Key::End => last_text(&stops)
+            .map(|text| text_position(text, text.upcast::<CharacterData>().Length())),
+        _ => None,
+    };
+    let (node, offset) = match target {
+        Some(target) => target,
+        None => return false,
+    };
+    if extend {
+        selection.Extend(&node, offset).is_ok()
+    } else {
+        selection.Collapse(Some(&node), offset).is_ok()
+    }
+}
+
+/// The default action of a keydown event targeted at an element in `host`.
+pub fn handle_keydown(host: &Element, event: &KeyboardEvent) {
+    let document = document_from_node(host);
+    let selection = match document.GetSelection() {
+        Some(selection) => selection,
+        None => return,
+    };
+    if range_in_host(&selection, host).is_none() {
+        return;
+    }
+
+    let key = event.key();
+    let modifiers = event.modifiers();
+    let run = |command, value: &str| {
+        execute_command(&document, command, DOMString::from(value), true);
+    };
+    ShortcutMatcher::new(KeyState::Down, key.clone(), modifiers)
+        .shortcut(CMD_OR_CONTROL, 'A', || run(EditingCommand::SelectAll, ""))
+        .shortcut(CMD_OR_CONTROL, 'B', || run(EditingCommand::Bold, ""))
+        .shortcut(CMD_OR_CONTROL, 'I', || run(EditingCommand::Italic, ""))
+        .shortcut(CMD_OR_CONTROL | Modifiers::SHIFT, 'Z', || {
+            run(EditingCommand::Redo, "")
+        })
+        .shortcut(CMD_OR_CONTROL, 'Y', || run(EditingCommand::Redo, ""))
+        .shortcut(CMD_OR_CONTROL, 'Z', || run(EditingCommand::Undo, ""))
+        .shortcut(Modifiers::empty(), Key::Backspace, || {
+            run(EditingCommand::Delete, "")
+        })
+        .shortcut(Modifiers::empty(), Key::Delete, || {
+            run(EditingCommand::ForwardDelete, "")
+        })
+        .shortcut(Modifiers::empty(), Key::Enter, || {
+            run(EditingCommand::InsertLineBreak, "")
+        })
+        .shortcut(Modifiers::SHIFT, Key::Enter, || {
+            run(EditingCommand::InsertLineBreak, "")
+        })
+        .otherwise(|| match key {
+            Key::ArrowLeft | Key::ArrowRight | Key::Home | Key::End => {
+                let extend = modifiers.contains(Modifiers::SHIFT);
+                move_caret(&selection, host, &key, extend);
+            },
+            Key::Character(ref c)
+                if !modifiers.intersects(Modifiers::CONTROL | Modifiers::META) =>
+            {
+                run(EditingCommand::InsertText, c)
+            },
+            _ => {},
+        });
+}
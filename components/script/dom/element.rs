@@ -5,8 +5,11 @@
 //! Element nodes.
 
 use crate::dom::activation::Activatable;
+use crate::dom::animation::Animation;
+use crate::dom::animationeffect::Timing;
 use crate::dom::attr::{Attr, AttrHelpersForLayout};
 use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::AnimatableBinding::GetAnimationsOptions;
 use crate::dom::bindings::codegen::Bindings::AttrBinding::AttrMethods;
 use crate::dom::bindings::codegen::Bindings::DocumentBinding::DocumentMethods;
 use crate::dom::bindings::codegen::Bindings::ElementBinding;
@@ -14,11 +17,13 @@ use crate::dom::bindings::codegen::Bindings::ElementBinding::ElementMethods;
 use crate::dom::bindings::codegen::Bindings::EventBinding::EventMethods;
 use crate::dom::bindings::codegen::Bindings::FunctionBinding::Function;
 use crate::dom::bindings::codegen::Bindings::HTMLTemplateElementBinding::HTMLTemplateElementMethods;
+use crate::dom::bindings::codegen::Bindings::KeyframeEffectBinding::KeyframeEffectMethods;
 use crate::dom::bindings::codegen::Bindings::NodeBinding::NodeMethods;
 use crate::dom::bindings::codegen::Bindings::ShadowRootBinding::ShadowRootBinding::ShadowRootMethods;
 use crate::dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
 use crate::dom::bindings::codegen::Bindings::WindowBinding::{ScrollBehavior, ScrollToOptions};
 use crate::dom::bindings::codegen::UnionTypes::NodeOrString;
+use crate::dom::bindings::codegen::UnionTypes::UnrestrictedDoubleOrKeyframeAnimationOptions;
 use crate::dom::bindings::conversions::DerivedFrom;
 use crate::dom::bindings::error::{Error, ErrorResult, Fallible};
 use crate::dom::bindings::inheritance::{Castable, ElementTypeId, HTMLElementTypeId, NodeTypeId};
@@ -59,7 +64,9 @@ use crate::dom::htmllegendelement::HTMLLegendElement;
 use crate::dom::htmllinkelement::HTMLLinkElement;
 use crate::dom::htmlobjectelement::HTMLObjectElement;
 use crate::dom::htmloptgroupelement::HTMLOptGroupElement;
+use crate::dom::htmloutputelement::HTMLOutputElement;
 use crate::dom::htmlselectelement::HTMLSelectElement;
+use crate::dom::htmlslotelement::{slot_name_changed, HTMLSlotElement};
 use crate::dom::htmlstyleelement::HTMLStyleElement;
 use crate::dom::htmltablecellelement::{HTMLTableCellElement, HTMLTableCellElementLayoutHelpers};
 use crate::dom::htmltableelement::{HTMLTableElement, HTMLTableElementLayoutHelpers};
@@ -69,6 +76,7 @@ use crate::dom::htmltablesectionelement::{
 };
 use crate::dom::htmltemplateelement::HTMLTemplateElement;
 use crate::dom::htmltextareaelement::{HTMLTextAreaElement, LayoutHTMLTextAreaElementHelpers};
+use crate::dom::keyframeeffect::KeyframeEffect;
 use crate::dom::mutationobserver::{Mutation, MutationObserver};
 use crate::dom::namednodemap::NamedNodeMap;
 use crate::dom::node::{document_from_node, window_from_node};
@@ -79,10 +87,12 @@ use crate::dom::promise::Promise;
 use crate::dom::raredata::ElementRareData;
 use crate::dom::servoparser::ServoParser;
 use crate::dom::shadowroot::{IsUserAgentWidget, ShadowRoot};
+use crate::dom::svgelement::{LayoutSVGElementHelpers, SVGElement};
 use crate::dom::text::Text;
 use crate::dom::validation::Validatable;
 use crate::dom::virtualmethods::{vtable_for, VirtualMethods};
 use crate::dom::window::ReflowReason;
+use crate::script_runtime::JSContext;
 use crate::script_thread::ScriptThread;
 use crate::stylesheet_loader::StylesheetOwner;
 use crate::task::TaskOnce;
@@ -93,7 +103,7 @@ use html5ever::serialize::SerializeOpts;
 use html5ever::serialize::TraversalScope;
 use html5ever::serialize::TraversalScope::{ChildrenOnly, IncludeNode};
 use html5ever::{LocalName, Namespace, Prefix, QualName};
-use js::jsapi::Heap;
+use js::jsapi::{Heap, JSObject};
 use js::jsval::JSVal;
 use msg::constellation_msg::InputMethodType;
 use net_traits::request::CorsSettings;
@@ -112,6 +122,7 @@ use std::fmt;
 use std::mem;
 use std::rc::Rc;
 use std::str::FromStr;
+use style::animation::KeyframeEffectSample;
 use style::applicable_declarations::ApplicableDeclarationBlock;
 use style::attr::{AttrValue, LengthOrPercentageOrAuto};
 use style::context::QuirksMode;
@@ -435,7 +446,7 @@ impl Element {
         })
     }
 
-    fn shadow_root(&self) -> Option<DomRoot<ShadowRoot>> {
+    pub fn shadow_root(&self) -> Option<DomRoot<ShadowRoot>> {
         self.rare_data()
             .as_ref()?
             .shadow_root
@@ -609,6 +620,8 @@ pub trait LayoutElementHelpers {
     /// The shadow root this element is a host of.
     #[allow(unsafe_code)]
     unsafe fn get_shadow_root_for_layout(&self) -> Option<LayoutDom<ShadowRoot>>;
+    #[allow(unsafe_code)]
+    unsafe fn keyframe_effect_samples(&self) -> &'static [KeyframeEffectSample];
 }
 
 impl LayoutElementHelpers for LayoutDom<Element> {
@@ -955,6 +968,15 @@ impl LayoutElementHelpers for LayoutDom<Element> {
                 PropertyDeclaration::BorderRightWidth(width_value),
             ));
         }
+
+        if let Some(this) = self.downcast::<SVGElement>() {
+            if let Some(block) = this.presentation_hints() {
+                hints.push(ApplicableDeclarationBlock::from_declarations(
+                    block,
+                    CascadeLevel::PresHints,
+                ));
+            }
+        }
     }
 
     #[allow(unsafe_code)]
@@ -1065,6 +1087,15 @@ impl LayoutElementHelpers for LayoutDom<Element> {
             .as_ref()
             .map(|sr| sr.to_layout())
     }
+
+    #[allow(unsafe_code)]
+    #[inline]
+    unsafe fn keyframe_effect_samples(&self) -> &'static [KeyframeEffectSample] {
+        match *(*self.unsafe_get()).rare_data_for_layout() {
+            Some(ref data) => &data.keyframe_effect_samples,
+            None => &[],
+        }
+    }
 }
 
 impl Element {
@@ -1149,6 +1180,20 @@ impl Element {
         &self.style_attribute
     }
 
+    /// Replaces the samples of the keyframe effects targeting this element,
+    /// restyling it if they changed.
+    pub fn set_keyframe_effect_samples(&self, samples: Vec<KeyframeEffectSample>) {
+        let unchanged = match *self.rare_data() {
+            Some(ref data) => data.keyframe_effect_samples == samples,
+            None => samples.is_empty(),
+        };
+        if unchanged {
+            return;
+        }
+        self.ensure_rare_data().keyframe_effect_samples = samples;
+        self.upcast::<Node>().dirty(NodeDamage::NodeStyleDamaged);
+    }
+
     pub fn summarize(&self) -> Vec<AttrInfo> {
         self.attrs
             .borrow()
@@ -1832,6 +1877,21 @@ impl ElementMethods for Element {
         self.set_atomic_attribute(&local_name!("id"), id);
     }
 
+    // https://dom.spec.whatwg.org/#dom-element-slot
+    fn Slot(&self) -> DOMString {
+        self.get_string_attribute(&local_name!("slot"))
+    }
+
+    // https://dom.spec.whatwg.org/#dom-element-slot
+    fn SetSlot(&self, slot: DOMString) {
+        self.set_string_attribute(&local_name!("slot"), slot);
+    }
+
+    // https://dom.spec.whatwg.org/#dom-slotable-assignedslot
+    fn GetAssignedSlot(&self) -> Option<DomRoot<HTMLSlotElement>> {
+        HTMLSlotElement::find_a_slot(self.upcast(), true)
+    }
+
     // https://dom.spec.whatwg.org/#dom-element-classname
     fn ClassName(&self) -> DOMString {
         self.get_string_attribute(&local_name!("class"))
@@ -2632,6 +2692,59 @@ impl ElementMethods for Element {
             .map(|_| ())
     }
 
+    // https://drafts.csswg.org/web-animations/#dom-animatable-animate
+    fn Animate(
+        &self,
+        cx: JSContext,
+        keyframes: *mut JSObject,
+        options: UnrestrictedDoubleOrKeyframeAnimationOptions,
+    ) -> Fallible<DomRoot<Animation>> {
+        let window = window_from_node(self);
+
+        // Step 2.
+        let (timing, id) = match options {
+            UnrestrictedDoubleOrKeyframeAnimationOptions::UnrestrictedDouble(duration) => {
+                (Timing::from_duration(&window, duration)?, DOMString::new())
+            },
+            UnrestrictedDoubleOrKeyframeAnimationOptions::KeyframeAnimationOptions(ref options) => {
+                let timing = Timing::from_effect_timing(&window, &options.parent.parent)?;
+                (timing, options.id.clone())
+            },
+        };
+        let effect = KeyframeEffect::new(&window, Some(self), timing);
+        effect.SetKeyframes(cx, keyframes)?;
+
+        // Steps 3-5.
+        let timeline = document_from_node(self).timeline();
+        let animation = Animation::new(&window, Some(effect.upcast()), Some(timeline.upcast()));
+
+        // Step 6.
+        animation.set_id(id);
+
+        // Step 7.
+        animation.play(true)?;
+
+        // Step 8.
+        Ok(animation)
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-animatable-getanimations
+    fn GetAnimations(&self, options: &GetAnimationsOptions) -> Vec<DomRoot<Animation>> {
+        let node = self.upcast::<Node>();
+        if !node.is_connected() {
+            return vec![];
+        }
+        document_from_node(self)
+            .relevant_animations()
+            .into_iter()
+            .filter(|&(_, ref target)| {
+                &**target == self ||
+                    (options.subtree && node.is_inclusive_ancestor_of(target.upcast()))
+            })
+            .map(|(animation, _)| animation)
+            .collect()
+    }
+
     // check-tidy: no specs after this line
     fn EnterFormalActivationState(&self) -> ErrorResult {
         match self.as_maybe_activatable() {
@@ -2770,6 +2883,15 @@ impl VirtualMethods for Element {
                     }
                 }
             },
+            &local_name!("slot") => {
+                // https://dom.spec.whatwg.org/#slotable-name
+                if attr.namespace() == &ns!() && slot_name_changed(attr, mutation) {
+                    if let Some(slot) = node.assigned_slot() {
+                        slot.assign_slottables();
+                    }
+                    HTMLSlotElement::assign_a_slot(node);
+                }
+            },
             _ => {
                 // FIXME(emilio): This is pretty dubious, and should be done in
                 // the relevant super-classes.
@@ -3041,6 +3163,8 @@ impl<'a> SelectorsElement for DomRoot<Element> {
             NonTSPseudoClass::Indeterminate |
             NonTSPseudoClass::ReadWrite |
             NonTSPseudoClass::PlaceholderShown |
+            NonTSPseudoClass::Valid |
+            NonTSPseudoClass::Invalid |
             NonTSPseudoClass::Target => Element::state(self).contains(pseudo_class.state_flag()),
         }
     }
@@ -3083,6 +3207,10 @@ impl<'a> SelectorsElement for DomRoot<Element> {
     fn is_html_slot_element(&self) -> bool {
         self.is_html_element() && self.local_name() == &local_name!("slot")
     }
+
+    fn assigned_slot(&self) -> Option<Self> {
+        self.upcast::<Node>().assigned_slot().map(DomRoot::upcast)
+    }
 }
 
 impl Element {
@@ -3135,7 +3263,7 @@ impl Element {
         None
     }
 
-    // https://html.spec.whatwg.org/multipage/#category-submit
+    // https://html.spec.whatwg.org/multipage/#category-listed
     pub fn as_maybe_validatable(&self) -> Option<&dyn Validatable> {
         let element = match self.upcast::<Node>().type_id() {
             NodeTypeId::Element(ElementTypeId::HTMLElement(
@@ -3168,6 +3296,18 @@ impl Element {
                 let element = self.downcast::<HTMLTextAreaElement>().unwrap();
                 Some(element as &dyn Validatable)
             },
+            NodeTypeId::Element(ElementTypeId::HTMLElement(
+                HTMLElementTypeId::HTMLFieldSetElement,
+            )) => {
+                let element = self.downcast::<HTMLFieldSetElement>().unwrap();
+                Some(element as &dyn Validatable)
+            },
+            NodeTypeId::Element(ElementTypeId::HTMLElement(
+                HTMLElementTypeId::HTMLOutputElement,
+            )) => {
+                let element = self.downcast::<HTMLOutputElement>().unwrap();
+                Some(element as &dyn Validatable)
+            },
             _ => None,
         };
         element
@@ -18,6 +18,8 @@ use crate::dom::bindings::str::DOMString;
 use crate::dom::document::Document;
 use crate::dom::eventtarget::{CompiledEventListener, EventTarget, ListenerPhase};
 use crate::dom::globalscope::GlobalScope;
+use crate::dom::idbrequest::IDBRequest;
+use crate::dom::idbtransaction::IDBTransaction;
 use crate::dom::node::Node;
 use crate::dom::virtualmethods::vtable_for;
 use crate::dom::window::Window;
@@ -138,6 +140,14 @@ impl Event {
                     event_path.push(DomRoot::from_ref(document.window().upcast()));
                 }
             }
+        } else if let Some(request) = target.downcast::<IDBRequest>() {
+            // https://w3c.github.io/IndexedDB/#ref-for-get-the-parent
+            if let Some(transaction) = request.transaction() {
+                event_path.push(DomRoot::from_ref(transaction.upcast()));
+                event_path.push(DomRoot::from_ref(transaction.database().upcast()));
+            }
+        } else if let Some(transaction) = target.downcast::<IDBTransaction>() {
+            event_path.push(DomRoot::from_ref(transaction.database().upcast()));
         }
         event_path
     }
@@ -237,6 +237,7 @@ impl EventSourceContext {
                 DOMString::from(self.origin.clone()),
                 None,
                 event_source.last_event_id.borrow().clone(),
+                vec![],
             )
         };
         // Step 7
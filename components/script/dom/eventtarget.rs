@@ -3,6 +3,7 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
 use crate::compartments::enter_realm;
+use crate::dom::abortsignal::AbortAlgorithm;
 use crate::dom::beforeunloadevent::BeforeUnloadEvent;
 use crate::dom::bindings::callback::{CallbackContainer, CallbackFunction, ExceptionHandling};
 use crate::dom::bindings::cell::DomRefCell;
@@ -24,7 +25,7 @@ use crate::dom::bindings::codegen::UnionTypes::EventOrString;
 use crate::dom::bindings::error::{report_pending_exception, Error, Fallible};
 use crate::dom::bindings::inheritance::Castable;
 use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
-use crate::dom::bindings::root::DomRoot;
+use crate::dom::bindings::root::{Dom, DomRoot};
 use crate::dom::bindings::str::DOMString;
 use crate::dom::element::Element;
 use crate::dom::errorevent::ErrorEvent;
@@ -660,6 +661,7 @@ impl EventTarget {
         event
     }
     // https://dom.spec.whatwg.org/#dom-eventtarget-addeventlistener
+    #[allow(unrooted_must_root)]
     pub fn add_event_listener(
         &self,
         ty: DOMString,
@@ -670,8 +672,15 @@ impl EventTarget {
             Some(l) => l,
             None => return,
         };
+        if options
+            .signal
+            .as_ref()
+            .map_or(false, |signal| signal.aborted())
+        {
+            return;
+        }
         let mut handlers = self.handlers.borrow_mut();
-        let entry = match handlers.entry(Atom::from(ty)) {
+        let entry = match handlers.entry(Atom::from(ty.clone())) {
             Occupied(entry) => entry.into_mut(),
             Vacant(entry) => entry.insert(EventListeners(vec![])),
         };
@@ -683,11 +692,19 @@ impl EventTarget {
         };
         let new_entry = EventListenerEntry {
             phase: phase,
-            listener: EventListenerType::Additive(listener),
+            listener: EventListenerType::Additive(listener.clone()),
             once: options.once,
         };
         if !entry.contains(&new_entry) {
             entry.push(new_entry);
+            if let Some(ref signal) = options.signal {
+                signal.add_algorithm(AbortAlgorithm::DomEventListener {
+                    target: Dom::from_ref(self),
+                    ty,
+                    listener,
+                    capture: options.parent.capture,
+                });
+            }
         }
     }
 
@@ -769,6 +786,7 @@ impl From<AddEventListenerOptionsOrBoolean> for AddEventListenerOptions {
             AddEventListenerOptionsOrBoolean::Boolean(capture) => Self {
                 parent: EventListenerOptions { capture },
                 once: false,
+                signal: None,
             },
         }
     }
@@ -0,0 +1,411 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::DocumentBinding::DocumentMethods;
+use crate::dom::bindings::codegen::Bindings::FontFaceBinding;
+use crate::dom::bindings::codegen::Bindings::FontFaceBinding::FontFaceDescriptors;
+use crate::dom::bindings::codegen::Bindings::FontFaceBinding::{
+    FontFaceLoadStatus, FontFaceMethods,
+};
+use crate::dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
+use crate::dom::bindings::codegen::UnionTypes::StringOrArrayBufferOrArrayBufferView;
+use crate::dom::bindings::error::{Error, ErrorResult, Fallible};
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::refcounted::Trusted;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot};
+use crate::dom::bindings::str::DOMString;
+use crate::dom::fontfaceset::FontFaceSet;
+use crate::dom::promise::Promise;
+use crate::dom::window::{ReflowReason, Window};
+use crate::task_source::TaskSource;
+use cssparser::{Parser, ParserInput, UnicodeRange};
+use dom_struct::dom_struct;
+use ipc_channel::ipc;
+use ipc_channel::router::ROUTER;
+use script_layout_interface::message::{Msg, ReflowGoal, WebFontSource};
+use std::cell::Cell;
+use std::rc::Rc;
+use style::context::QuirksMode;
+use style::font_face::{EffectiveSources, FontDisplay, FontStretchRange, FontStyle};
+use style::font_face::{FontWeightRange, Source};
+use style::parser::{Parse, ParserContext};
+use style::stylesheets::CssRuleType;
+use style::values::computed::font::FamilyName;
+use style_traits::ParsingMode;
+
+/// The data a font face is loaded from.
+enum FontFaceData {
+    /// The sources of the `src` descriptor the font face was created with.
+    Sources(Vec<Source>),
+    /// The binary data the font face was created with.
+    Binary(Vec<u8>),
+}
+
+unsafe_no_jsmanaged_fields!(FontFaceData);
+
+/// <https://drafts.csswg.org/css-font-loading/#fontface-interface>
+#[dom_struct]
+pub struct FontFace {
+    reflector_: Reflector,
+    family: DomRefCell<DOMString>,
+    style: DomRefCell<DOMString>,
+    weight: DomRefCell<DOMString>,
+    stretch: DomRefCell<DOMString>,
+    unicode_range: DomRefCell<DOMString>,
+    variant: DomRefCell<DOMString>,
+    feature_settings: DomRefCell<DOMString>,
+    variation_settings: DomRefCell<DOMString>,
+    display: DomRefCell<DOMString>,
+    status: Cell<FontFaceLoadStatus>,
+    /// <https://drafts.csswg.org/css-font-loading/#dom-fontface-fontstatuspromise-slot>
+    #[ignore_malloc_size_of = "Rc"]
+    font_status_promise: Rc<Promise>,
+    /// The data to load the font face from, `None` if the font face could not
+    /// be created from its arguments.
+    #[ignore_malloc_size_of = "Defined in style"]
+    data: DomRefCell<Option<FontFaceData>>,
+    /// The font face sets this font face was added to, which are notified when
+    /// its status changes.
+    font_face_sets: DomRefCell<Vec<Dom<FontFaceSet>>>,
+}
+
+impl FontFace {
+    fn new_inherited(
+        family: DOMString,
+        descriptors: &FontFaceDescriptors,
+        promise: Rc<Promise>,
+    ) -> FontFace {
+        FontFace {
+            reflector_: Reflector::new(),
+            family: DomRefCell::new(family),
+            style: DomRefCell::new(descriptors.style.clone()),
+            weight: DomRefCell::new(descriptors.weight.clone()),
+            stretch: DomRefCell::new(descriptors.stretch.clone()),
+            unicode_range: DomRefCell::new(descriptors.unicodeRange.clone()),
+            variant: DomRefCell::new(descriptors.variant.clone()),
+            feature_settings: DomRefCell::new(descriptors.featureSettings.clone()),
+            variation_settings: DomRefCell::new(descriptors.variationSettings.clone()),
+            display: DomRefCell::new(descriptors.display.clone()),
+            status: Cell::new(FontFaceLoadStatus::Unloaded),
+            font_status_promise: promise,
+            data: DomRefCell::new(None),
+            font_face_sets: Default::default(),
+        }
+    }
+
+    fn new(
+        window: &Window,
+        family: DOMString,
+        descriptors: &FontFaceDescriptors,
+    ) -> DomRoot<FontFace> {
+        let promise = Promise::new(window.upcast());
+        reflect_dom_object(
+            Box::new(FontFace::new_inherited(family, descriptors, promise)),
+            window,
+            FontFaceBinding::Wrap,
+        )
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-fontface
+    pub fn Constructor(
+        window: &Window,
+        family: DOMString,
+        source: StringOrArrayBufferOrArrayBufferView,
+        descriptors: &FontFaceDescriptors,
+    ) -> Fallible<DomRoot<FontFace>> {
+        // Step 1.
+        let font_face = FontFace::new(window, family, descriptors);
+
+        // Step 2.
+        if font_face.validate_descriptors(window).is_err() {
+            font_face.fail(Error::Syntax);
+            return Ok(font_face);
+        }
+
+        match source {
+            // Step 3.
+            StringOrArrayBufferOrArrayBufferView::String(source) => {
+                match parse_descriptor::<Vec<Source>>(window, &source) {
+                    Ok(sources) => {
+                        *font_face.data.borrow_mut() = Some(FontFaceData::Sources(sources));
+                    },
+                    Err(error) => font_face.fail(error),
+                }
+            },
+            // Step 4.
+            StringOrArrayBufferOrArrayBufferView::ArrayBuffer(buffer) => {
+                *font_face.data.borrow_mut() = Some(FontFaceData::Binary(buffer.to_vec()));
+                font_face.load();
+            },
+            StringOrArrayBufferOrArrayBufferView::ArrayBufferView(view) => {
+                *font_face.data.borrow_mut() = Some(FontFaceData::Binary(view.to_vec()));
+                font_face.load();
+            },
+        }
+
+        // Step 5.
+        Ok(font_face)
+    }
+
+    pub fn status(&self) -> FontFaceLoadStatus {
+        self.status.get()
+    }
+
+    /// The family name of this font face, if it is valid.
+    pub fn family_name(&self) -> Option<FamilyName> {
+        let global = self.global();
+        parse_descriptor(global.as_window(), &self.family.borrow()).ok()
+    }
+
+    pub fn add_font_face_set(&self, font_face_set: &FontFaceSet) {
+        let mut font_face_sets = self.font_face_sets.borrow_mut();
+        if !font_face_sets.iter().any(|set| &**set == font_face_set) {
+            font_face_sets.push(Dom::from_ref(font_face_set));
+        }
+    }
+
+    fn validate_descriptors(&self, window: &Window) -> ErrorResult {
+        parse_descriptor::<FamilyName>(window, &self.family.borrow())?;
+        parse_descriptor::<FontStyle>(window, &self.style.borrow())?;
+        parse_descriptor::<FontWeightRange>(window, &self.weight.borrow())?;
+        parse_descriptor::<FontStretchRange>(window, &self.stretch.borrow())?;
+        parse_descriptor::<Vec<UnicodeRange>>(window, &self.unicode_range.borrow())?;
+        parse_descriptor::<FontDisplay>(window, &self.display.borrow())?;
+        Ok(())
+    }
+
+    fn set_status(&self, status: FontFaceLoadStatus) {
+        self.status.set(status);
+        let font_face_sets: Vec<_> = self
+            .font_face_sets
+            .borrow()
+            .iter()
+            .map(|set| DomRoot::from_ref(&**set))
+            .collect();
+        for font_face_set in font_face_sets {
+            font_face_set.font_face_status_changed(self);
+        }
+    }
+
+    fn fail(&self, error: Error) {
+        self.set_status(FontFaceLoadStatus::Error);
+        self.font_status_promise.reject_error(error);
+    }
+
+    /// Starts loading this font face into the font cache.
+    ///
+    /// <https://drafts.csswg.org/css-font-loading/#font-face-load>
+    fn load(&self) {
+        let source = match *self.data.borrow() {
+            Some(FontFaceData::Sources(ref sources)) => {
+                WebFontSource::Sources(EffectiveSources::new(sources))
+            },
+            Some(FontFaceData::Binary(ref bytes)) => WebFontSource::Data(bytes.clone()),
+            None => return,
+        };
+        let family = match self.family_name() {
+            Some(family) => family,
+            None => {
+                self.fail(Error::Syntax);
+                return;
+            },
+        };
+        self.set_status(FontFaceLoadStatus::Loading);
+
+        let global = self.global();
+        let window = global.as_window();
+        let (task_source, canceller) = window
+            .task_manager()
+            .dom_manipulation_task_source_with_canceller();
+        let (sender, receiver) = ipc::channel().unwrap();
+        let this = Trusted::new(self);
+        ROUTER.add_route(
+            receiver.to_opaque(),
+            Box::new(move |message| {
+                let loaded: bool = message.to().unwrap();
+                let this = this.clone();
+                let _ = task_source.queue_with_canceller(
+                    task!(font_face_loaded: move || {
+                        this.root().finish_loading(loaded);
+                    }),
+                    &canceller,
+                );
+            }),
+        );
+        let _ = window
+            .layout_chan()
+            .send(Msg::AddWebFont(family, source, sender));
+    }
+
+    fn finish_loading(&self, loaded: bool) {
+        if !loaded {
+            self.fail(Error::Network);
+            return;
+        }
+        self.set_status(FontFaceLoadStatus::Loaded);
+        self.font_status_promise.resolve_native(self);
+
+        // The new font may change the rendering of any text in the document.
+        let global = self.global();
+        let window = global.as_window();
+        window.Document().dirty_all_nodes();
+        window.reflow(ReflowGoal::Full, ReflowReason::WebFontLoaded);
+    }
+}
+
+/// Parses the value of a font face descriptor or font family name.
+fn parse_descriptor<T: Parse>(window: &Window, value: &str) -> Fallible<T> {
+    let url = window.Document().base_url();
+    let context = ParserContext::new_for_cssom(
+        &url,
+        Some(CssRuleType::FontFace),
+        ParsingMode::DEFAULT,
+        QuirksMode::NoQuirks,
+        None,
+        None,
+    );
+    let mut input = ParserInput::new(value);
+    let mut parser = Parser::new(&mut input);
+    parser
+        .parse_entirely(|parser| T::parse(&context, parser))
+        .map_err(|_| Error::Syntax)
+}
+
+impl FontFaceMethods for FontFace {
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-family
+    fn Family(&self) -> DOMString {
+        self.family.borrow().clone()
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-family
+    fn SetFamily(&self, value: DOMString) -> ErrorResult {
+        let global = self.global();
+        parse_descriptor::<FamilyName>(global.as_window(), &value)?;
+        *self.family.borrow_mut() = value;
+        Ok(())
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-style
+    fn Style(&self) -> DOMString {
+        self.style.borrow().clone()
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-style
+    fn SetStyle(&self, value: DOMString) -> ErrorResult {
+        let global = self.global();
+        parse_descriptor::<FontStyle>(global.as_window(), &value)?;
+        *self.style.borrow_mut() = value;
+        Ok(())
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-weight
+    fn Weight(&self) -> DOMString {
+        self.weight.borrow().clone()
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-weight
+    fn SetWeight(&self, value: DOMString) -> ErrorResult {
+        let global = self.global();
+        parse_descriptor::<FontWeightRange>(global.as_window(), &value)?;
+        *self.weight.borrow_mut() = value;
+        Ok(())
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-stretch
+    fn Stretch(&self) -> DOMString {
+        self.stretch.borrow().clone()
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-stretch
+    fn SetStretch(&self, value: DOMString) -> ErrorResult {
+        let global = self.global();
+        parse_descriptor::<FontStretchRange>(global.as_window(), &value)?;
+        *self.stretch.borrow_mut() = value;
+        Ok(())
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-unicoderange
+    fn UnicodeRange(&self) -> DOMString {
+        self.unicode_range.borrow().clone()
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-unicoderange
+    fn SetUnicodeRange(&self, value: DOMString) -> ErrorResult {
+        let global = self.global();
+        parse_descriptor::<Vec<UnicodeRange>>(global.as_window(), &value)?;
+        *self.unicode_range.borrow_mut() = value;
+        Ok(())
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-variant
+    fn Variant(&self) -> DOMString {
+        self.variant.borrow().clone()
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-variant
+    fn SetVariant(&self, value: DOMString) {
+        *self.variant.borrow_mut() = value;
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-featuresettings
+    fn FeatureSettings(&self) -> DOMString {
+        self.feature_settings.borrow().clone()
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-featuresettings
+    fn SetFeatureSettings(&self, value: DOMString) {
+        *self.feature_settings.borrow_mut() = value;
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-variationsettings
+    fn VariationSettings(&self) -> DOMString {
+        self.variation_settings.borrow().clone()
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-variationsettings
+    fn SetVariationSettings(&self, value: DOMString) {
+        *self.variation_settings.borrow_mut() = value;
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-display
+    fn Display(&self) -> DOMString {
+        self.display.borrow().clone()
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-display
+    fn SetDisplay(&self, value: DOMString) -> ErrorResult {
+        let global = self.global();
+        parse_descriptor::<FontDisplay>(global.as_window(), &value)?;
+        *self.display.borrow_mut() = value;
+        Ok(())
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-status
+    fn Status(&self) -> FontFaceLoadStatus {
+        self.status.get()
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-load
+    fn Load(&self) -> Rc<Promise> {
+        // Steps 1-3.
+        let has_sources = match *self.data.borrow() {
+            Some(FontFaceData::Sources(_)) => true,
+            _ => false,
+        };
+        if has_sources && self.status.get() == FontFaceLoadStatus::Unloaded {
+            // Steps 4-5.
+            self.load();
+        }
+
+        // Step 6.
+        self.font_status_promise.clone()
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontface-loaded
+    fn Loaded(&self) -> Rc<Promise> {
+        self.font_status_promise.clone()
+    }
+}
@@ -0,0 +1,435 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::FontFaceBinding::{
+    FontFaceLoadStatus, FontFaceMethods,
+};
+use crate::dom::bindings::codegen::Bindings::FontFaceSetBinding;
+use crate::dom::bindings::codegen::Bindings::FontFaceSetBinding::{
+    FontFaceSetLoadStatus, FontFaceSetMethods,
+};
+use crate::dom::bindings::error::{Error, Fallible};
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::refcounted::Trusted;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject};
+use crate::dom::bindings::root::{Dom, DomRoot};
+use crate::dom::bindings::str::DOMString;
+use crate::dom::event::Event;
+use crate::dom::eventtarget::EventTarget;
+use crate::dom::fontface::FontFace;
+use crate::dom::fontfacesetloadevent::FontFaceSetLoadEvent;
+use crate::dom::promise::Promise;
+use crate::dom::window::Window;
+use crate::task_source::TaskSource;
+use dom_struct::dom_struct;
+use servo_atoms::Atom;
+use std::rc::Rc;
+use style::context::QuirksMode;
+use style::properties::{parse_one_declaration_into, SourcePropertyDeclaration};
+use style::properties::{Importance, PropertyDeclaration, PropertyDeclarationBlock};
+use style::properties::{PropertyId, ShorthandId};
+use style::values::computed::font::SingleFontFamily;
+use style::values::specified::font::FontFamily;
+use style_traits::ParsingMode;
+
+/// A promise returned by `FontFaceSet.load()`, waiting for the given font
+/// faces to load.
+#[derive(JSTraceable)]
+#[must_root]
+struct PendingLoad {
+    promise: Rc<Promise>,
+    font_faces: Vec<Dom<FontFace>>,
+}
+
+/// <https://drafts.csswg.org/css-font-loading/#FontFaceSet-interface>
+#[dom_struct]
+pub struct FontFaceSet {
+    eventtarget: EventTarget,
+    /// The font faces in this set, in insertion order.
+    font_faces: DomRefCell<Vec<Dom<FontFace>>>,
+    /// <https://drafts.csswg.org/css-font-loading/#dom-fontfaceset-loadingfonts-slot>
+    loading_fonts: DomRefCell<Vec<Dom<FontFace>>>,
+    /// <https://drafts.csswg.org/css-font-loading/#dom-fontfaceset-loadedfonts-slot>
+    loaded_fonts: DomRefCell<Vec<Dom<FontFace>>>,
+    /// <https://drafts.csswg.org/css-font-loading/#dom-fontfaceset-failedfonts-slot>
+    failed_fonts: DomRefCell<Vec<Dom<FontFace>>>,
+    /// <https://drafts.csswg.org/css-font-loading/#dom-fontfaceset-readypromise-slot>
+    #[ignore_malloc_size_of = "Rc"]
+    ready_promise: DomRefCell<Rc<Promise>>,
+    /// The promises returned by `load()` which are waiting for font faces to load.
+    #[ignore_malloc_size_of = "Rc"]
+    pending_loads: DomRefCell<Vec<PendingLoad>>,
+}
+
+impl FontFaceSet {
+    fn new_inherited(ready_promise: Rc<Promise>) -> FontFaceSet {
+        FontFaceSet {
+            eventtarget: EventTarget::new_inherited(),
+            font_faces: Default::default(),
+            loading_fonts: Default::default(),
+            loaded_fonts: Default::default(),
+            failed_fonts: Default::default(),
+            ready_promise: DomRefCell::new(ready_promise),
+            pending_loads: Default::default(),
+        }
+    }
+
+    pub fn new(window: &Window) -> DomRoot<FontFaceSet> {
+        let ready_promise = Promise::new(window.upcast());
+        let font_face_set = reflect_dom_object(
+            Box::new(FontFaceSet::new_inherited(ready_promise)),
+            window,
+            FontFaceSetBinding::Wrap,
+        );
+        // No font is loading yet, so the set is ready from the start.
+        font_face_set
+            .ready_promise
+            .borrow()
+            .resolve_native(&*font_face_set);
+        font_face_set
+    }
+
+    fn contains(&self, font_face: &FontFace) -> bool {
+        self.font_faces
+            .borrow()
+            .iter()
+            .any(|face| &**face == font_face)
+    }
+
+    /// Updates the loading state of this set after the status of one of its
+    /// font faces changed.
+    pub fn font_face_status_changed(&self, font_face: &FontFace) {
+        if self.contains(font_face) {
+            match font_face.status() {
+                FontFaceLoadStatus::Loading => self.add_loading_font(font_face),
+                FontFaceLoadStatus::Loaded => {
+                    self.remove_loading_font(font_face, Some(&self.loaded_fonts))
+                },
+                FontFaceLoadStatus::Error => {
+                    self.remove_loading_font(font_face, Some(&self.failed_fonts))
+                },
+                FontFaceLoadStatus::Unloaded => {},
+            }
+        }
+        self.settle_pending_loads();
+    }
+
+    fn add_loading_font(&self, font_face: &FontFace) {
+        let was_empty = {
+            let mut loading_fonts = self.loading_fonts.borrow_mut();
+            if loading_fonts.iter().any(|face| &**face == font_face) {
+                return;
+            }
+            loading_fonts.push(Dom::from_ref(font_face));
+            loading_fonts.len() == 1
+        };
+        if was_empty {
+            self.switch_to_loading();
+        }
+    }
+
+    fn remove_loading_font(
+        &self,
+        font_face: &FontFace,
+        settled_fonts: Option<&DomRefCell<Vec<Dom<FontFace>>>>,
+    ) {
+        let is_empty = {
+            let mut loading_fonts = self.loading_fonts.borrow_mut();
+            let index = match loading_fonts.iter().position(|face| &**face == font_face) {
+                Some(index) => index,
+                None => return,
+            };
+            loading_fonts.remove(index);
+            loading_fonts.is_empty()
+        };
+        if let Some(settled_fonts) = settled_fonts {
+            settled_fonts.borrow_mut().push(Dom::from_ref(font_face));
+        }
+        if is_empty {
+            self.switch_to_loaded();
+        }
+    }
+
+    /// <https://drafts.csswg.org/css-font-loading/#switch-the-fontfaceset-to-loading>
+    fn switch_to_loading(&self) {
+        // Step 2.
+        if self.ready_promise.borrow().is_fulfilled() {
+            *self.ready_promise.borrow_mut() = Promise::new(&self.global());
+        }
+
+        // Step 3.
+        let global = self.global();
+        let window = global.as_window();
+        let this = Trusted::new(self);
+        let _ = window.task_manager().dom_manipulation_task_source().queue(
+            task!(fire_font_loading_event: move || {
+                this.root().fire_font_load_event(atom!("loading"), &[]);
+            }),
+            window.upcast(),
+        );
+    }
+
+    /// <https://drafts.csswg.org/css-font-loading/#switch-the-fontfaceset-to-loaded>
+    fn switch_to_loaded(&self) {
+        let loaded_fonts = trusted(&self.loaded_fonts);
+        let failed_fonts = trusted(&self.failed_fonts);
+        self.loaded_fonts.borrow_mut().clear();
+        self.failed_fonts.borrow_mut().clear();
+
+        let global = self.global();
+        let window = global.as_window();
+        let this = Trusted::new(self);
+        let _ = window.task_manager().dom_manipulation_task_source().queue(
+            task!(fire_font_loaded_events: move || {
+                let this = this.root();
+                let loaded_fonts: Vec<_> = loaded_fonts.iter().map(|face| face.root()).collect();
+                let failed_fonts: Vec<_> = failed_fonts.iter().map(|face| face.root()).collect();
+                this.fire_font_load_event(atom!("loadingdone"), &loaded_fonts);
+                if !failed_fonts.is_empty() {
+                    this.fire_font_load_event(atom!("loadingerror"), &failed_fonts);
+                }
+                // Fonts may have started loading again in the meantime.
+                if this.loading_fonts.borrow().is_empty() {
+                    let promise = this.ready_promise.borrow().clone();
+                    promise.resolve_native(&*this);
+                }
+            }),
+            window.upcast(),
+        );
+    }
+
+    /// <https://drafts.csswg.org/css-font-loading/#fire-a-font-load-event>
+    fn fire_font_load_event(&self, type_: Atom, font_faces: &[DomRoot<FontFace>]) {
+        let global = self.global();
+        let event = FontFaceSetLoadEvent::new(global.as_window(), type_, false, false, font_faces);
+        event.upcast::<Event>().fire(self.upcast());
+    }
+
+    /// Settles the promises returned by `load()` whose font faces are all done
+    /// loading.
+    fn settle_pending_loads(&self) {
+        let mut settled = vec![];
+        self.pending_loads.borrow_mut().retain(|load| {
+            if load
+                .font_faces
+                .iter()
+                .any(|face| face.status() == FontFaceLoadStatus::Loading)
+            {
+                return true;
+            }
+            let font_faces: Vec<_> = load
+                .font_faces
+                .iter()
+                .map(|face| DomRoot::from_ref(&**face))
+                .collect();
+            settled.push((load.promise.clone(), font_faces));
+            false
+        });
+
+        for (promise, font_faces) in settled {
+            if font_faces
+                .iter()
+                .any(|face| face.status() == FontFaceLoadStatus::Error)
+            {
+                promise.reject_error(Error::Network);
+            } else {
+                promise.resolve_native(&font_faces);
+            }
+        }
+    }
+
+    /// <https://drafts.csswg.org/css-font-loading/#find-the-matching-font-faces>
+    fn find_matching_font_faces(&self, font: &str) -> Fallible<Vec<DomRoot<FontFace>>> {
+        // Step 1.
+        let global = self.global();
+        let url = global.as_window().get_url();
+        let mut declarations = SourcePropertyDeclaration::new();
+        parse_one_declaration_into(
+            &mut declarations,
+            PropertyId::Shorthand(ShorthandId::Font),
+            font,
+            &url,
+            None,
+            ParsingMode::DEFAULT,
+            QuirksMode::NoQuirks,
+        )
+        .map_err(|_| Error::Syntax)?;
+        let mut block = PropertyDeclarationBlock::new();
+        block.extend(declarations.drain(), Importance::Normal);
+
+        // Step 2.
+        let mut family_names = vec![];
+        for declaration in block.declarations() {
+            match *declaration {
+                PropertyDeclaration::FontFamily(FontFamily::Values(ref families)) => {
+                    family_names.extend(families.iter().filter_map(|family| match *family {
+                        SingleFontFamily::FamilyName(ref name) => Some(name.name.clone()),
+                        SingleFontFamily::Generic(_) => None,
+                    }));
+                },
+                PropertyDeclaration::CSSWideKeyword(..) |
+                PropertyDeclaration::WithVariables(..) => return Err(Error::Syntax),
+                _ => {},
+            }
+        }
+
+        // Steps 3-5.
+        // TODO: Take the unicode-range of the font faces and the given text
+        // into account.
+        Ok(self
+            .font_faces
+            .borrow()
+            .iter()
+            .filter(|face| {
+                face.family_name().map_or(false, |family| {
+                    family_names
+                        .iter()
+                        .any(|name| name.eq_ignore_ascii_case(&family.name))
+                })
+            })
+            .map(|face| DomRoot::from_ref(&**face))
+            .collect())
+    }
+}
+
+fn trusted(font_faces: &DomRefCell<Vec<Dom<FontFace>>>) -> Vec<Trusted<FontFace>> {
+    font_faces
+        .borrow()
+        .iter()
+        .map(|face| Trusted::new(&**face))
+        .collect()
+}
+
+impl FontFaceSetMethods for FontFaceSet {
+    // https://heycam.github.io/webidl/#dom-setlike-size
+    fn Size(&self) -> u32 {
+        self.font_faces.borrow().len() as u32
+    }
+
+    // https://heycam.github.io/webidl/#dom-setlike-has
+    fn Has(&self, font: &FontFace) -> bool {
+        self.contains(font)
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontfaceset-add
+    fn Add(&self, font: &FontFace) -> DomRoot<FontFaceSet> {
+        // Step 1.
+        if !self.contains(font) {
+            // Step 3.
+            self.font_faces.borrow_mut().push(Dom::from_ref(font));
+            font.add_font_face_set(self);
+
+            // Step 4.
+            if font.status() == FontFaceLoadStatus::Loading {
+                self.add_loading_font(font);
+            }
+        }
+
+        // Step 5.
+        DomRoot::from_ref(self)
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontfaceset-delete
+    fn Delete(&self, font: &FontFace) -> bool {
+        // Steps 2-3.
+        let removed = {
+            let mut font_faces = self.font_faces.borrow_mut();
+            match font_faces.iter().position(|face| &**face == font) {
+                Some(index) => {
+                    font_faces.remove(index);
+                    true
+                },
+                None => false,
+            }
+        };
+        if removed {
+            self.loaded_fonts
+                .borrow_mut()
+                .retain(|face| &**face != font);
+            self.failed_fonts
+                .borrow_mut()
+                .retain(|face| &**face != font);
+            self.remove_loading_font(font, None);
+        }
+        removed
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontfaceset-clear
+    fn Clear(&self) {
+        self.font_faces.borrow_mut().clear();
+        self.loaded_fonts.borrow_mut().clear();
+        self.failed_fonts.borrow_mut().clear();
+        let was_loading = !self.loading_fonts.borrow().is_empty();
+        self.loading_fonts.borrow_mut().clear();
+        if was_loading {
+            self.switch_to_loaded();
+        }
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontfaceset-onloading
+    event_handler!(loading, GetOnloading, SetOnloading);
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontfaceset-onloadingdone
+    event_handler!(loadingdone, GetOnloadingdone, SetOnloadingdone);
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontfaceset-onloadingerror
+    event_handler!(loadingerror, GetOnloadingerror, SetOnloadingerror);
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontfaceset-load
+    fn Load(&self, font: DOMString, _text: DOMString) -> Rc<Promise> {
+        // Step 1.
+        let promise = Promise::new(&self.global());
+
+        // Steps 2-3.
+        let font_faces = match self.find_matching_font_faces(&font) {
+            Ok(font_faces) => font_faces,
+            Err(error) => {
+                promise.reject_error(error);
+                return promise;
+            },
+        };
+
+        // Steps 4-5.
+        for font_face in &font_faces {
+            font_face.Load();
+        }
+        self.pending_loads.borrow_mut().push(PendingLoad {
+            promise: promise.clone(),
+            font_faces: font_faces
+                .iter()
+                .map(|face| Dom::from_ref(&**face))
+                .collect(),
+        });
+        self.settle_pending_loads();
+
+        // Step 6.
+        promise
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontfaceset-check
+    fn Check(&self, font: DOMString, _text: DOMString) -> Fallible<bool> {
+        // Steps 1-5.
+        let font_faces = self.find_matching_font_faces(&font)?;
+
+        // Step 6.
+        Ok(font_faces
+            .iter()
+            .all(|face| face.status() == FontFaceLoadStatus::Loaded))
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontfaceset-ready
+    fn Ready(&self) -> Rc<Promise> {
+        self.ready_promise.borrow().clone()
+    }
+
+    // https://drafts.csswg.org/css-font-loading/#dom-fontfaceset-status
+    fn Status(&self) -> FontFaceSetLoadStatus {
+        if self.loading_fonts.borrow().is_empty() {
+            FontFaceSetLoadStatus::Loaded
+        } else {
+            FontFaceSetLoadStatus::Loading
+        }
+    }
+}
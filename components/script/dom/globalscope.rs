@@ -2,6 +2,7 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
+use crate::compartments::enter_realm;
 use crate::dom::bindings::cell::DomRefCell;
 use crate::dom::bindings::codegen::Bindings::EventSourceBinding::EventSourceBinding::EventSourceMethods;
 use crate::dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
@@ -9,17 +10,24 @@ use crate::dom::bindings::codegen::Bindings::WorkerGlobalScopeBinding::WorkerGlo
 use crate::dom::bindings::conversions::{root_from_object, root_from_object_static};
 use crate::dom::bindings::error::{report_pending_exception, ErrorInfo};
 use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::refcounted::Trusted;
 use crate::dom::bindings::reflector::DomObject;
-use crate::dom::bindings::root::{DomRoot, MutNullableDom};
+use crate::dom::bindings::root::{Dom, DomRoot, MutNullableDom};
 use crate::dom::bindings::settings_stack::{entry_global, incumbent_global, AutoEntryScript};
 use crate::dom::bindings::str::DOMString;
+use crate::dom::bindings::structuredclone;
 use crate::dom::bindings::weakref::DOMTracker;
+use crate::dom::broadcastchannel::BroadcastChannel;
 use crate::dom::crypto::Crypto;
 use crate::dom::dedicatedworkerglobalscope::DedicatedWorkerGlobalScope;
 use crate::dom::errorevent::ErrorEvent;
 use crate::dom::event::{Event, EventBubbles, EventCancelable, EventStatus};
 use crate::dom::eventsource::EventSource;
 use crate::dom::eventtarget::EventTarget;
+use crate::dom::idbfactory::IDBFactory;
+use crate::dom::idbtransaction::IDBTransaction;
+use crate::dom::messageevent::MessageEvent;
+use crate::dom::messageport::MessagePort;
 use crate::dom::paintworkletglobalscope::PaintWorkletGlobalScope;
 use crate::dom::performance::Performance;
 use crate::dom::window::Window;
@@ -29,33 +37,39 @@ use crate::microtask::{Microtask, MicrotaskQueue};
 use crate::script_runtime::{CommonScriptMsg, JSContext as SafeJSContext, ScriptChan, ScriptPort};
 use crate::script_thread::{MainThreadScriptChan, ScriptThread};
 use crate::task::TaskCanceller;
+use crate::task_source::database_access::DatabaseAccessTaskSource;
 use crate::task_source::dom_manipulation::DOMManipulationTaskSource;
 use crate::task_source::file_reading::FileReadingTaskSource;
 use crate::task_source::networking::NetworkingTaskSource;
 use crate::task_source::performance_timeline::PerformanceTimelineTaskSource;
+use crate::task_source::port_message::PortMessageQueue;
 use crate::task_source::remote_event::RemoteEventTaskSource;
 use crate::task_source::websocket::WebsocketTaskSource;
-use crate::task_source::TaskSourceName;
+use crate::task_source::{TaskSource, TaskSourceName};
 use crate::timers::{IsInterval, OneshotTimerCallback, OneshotTimerHandle};
 use crate::timers::{OneshotTimers, TimerCallback};
 use devtools_traits::{ScriptToDevtoolsControlMsg, WorkerId};
 use dom_struct::dom_struct;
-use ipc_channel::ipc::IpcSender;
+use ipc_channel::ipc::{self, IpcSender};
+use ipc_channel::router::ROUTER;
 use js::glue::{IsWrapper, UnwrapObjectDynamic};
 use js::jsapi::JSObject;
 use js::jsapi::{CurrentGlobalOrNull, GetNonCCWObjectGlobal};
 use js::jsapi::{HandleObject, Heap};
 use js::jsapi::{JSAutoRealm, JSContext};
+use js::jsval::UndefinedValue;
 use js::panic::maybe_resume_unwind;
 use js::rust::wrappers::EvaluateUtf8;
 use js::rust::{get_object_class, CompileOptionsWrapper, ParentRuntime, Runtime};
 use js::rust::{HandleValue, MutableHandleValue};
 use js::{JSCLASS_IS_DOMJSCLASS, JSCLASS_IS_GLOBAL};
 use msg::constellation_msg::PipelineId;
+use msg::constellation_msg::{BroadcastChannelRouterId, MessagePortId, MessagePortRouterId};
 use net_traits::image_cache::ImageCache;
 use net_traits::{CoreResourceThread, IpcSend, ResourceThreads};
 use profile_traits::{mem as profile_mem, time as profile_time};
-use script_traits::{MsDuration, ScriptToConstellationChan, TimerEvent};
+use script_traits::{BroadcastMsg, MessagePortMsg, PortMessageTask, ScriptMsg};
+use script_traits::{MsDuration, ScriptToConstellationChan, StructuredSerializedData, TimerEvent};
 use script_traits::{TimerEventId, TimerSchedulerMsg, TimerSource};
 use servo_url::{MutableOrigin, ServoUrl};
 use std::borrow::Cow;
@@ -81,6 +95,7 @@ impl Drop for AutoCloseWorker {
 pub struct GlobalScope {
     eventtarget: EventTarget,
     crypto: MutNullableDom<Crypto>,
+    indexed_db: MutNullableDom<IDBFactory>,
     next_worker_id: Cell<WorkerId>,
 
     /// Pipeline id associated with this global.
@@ -140,6 +155,25 @@ pub struct GlobalScope {
     /// Vector storing references of all eventsources.
     event_source_tracker: DOMTracker<EventSource>,
 
+    /// IndexedDB transactions created since the last microtask checkpoint.
+    ///
+    /// <https://w3c.github.io/IndexedDB/#cleanup-indexed-database-transactions>
+    indexeddb_transactions: DomRefCell<Vec<Dom<IDBTransaction>>>,
+
+    /// The router through which the constellation sends messages to the
+    /// ports managed by this global.
+    message_port_router: Cell<Option<MessagePortRouterId>>,
+
+    /// The message-ports managed by this global.
+    message_ports: DomRefCell<HashMap<MessagePortId, Dom<MessagePort>>>,
+
+    /// The router through which the constellation sends broadcast messages
+    /// to the channels of this global.
+    broadcast_channel_router: Cell<Option<BroadcastChannelRouterId>>,
+
+    /// The open broadcast-channels of this global, by name, in creation order.
+    broadcast_channels: DomRefCell<HashMap<DOMString, Vec<Dom<BroadcastChannel>>>>,
+
     /// Storage for watching rejected promises waiting for some client to
     /// consume their rejection.
     /// Promises in this list have been rejected in the last turn of the
@@ -184,6 +218,7 @@ impl GlobalScope {
         Self {
             eventtarget: EventTarget::new_inherited(),
             crypto: Default::default(),
+            indexed_db: Default::default(),
             next_worker_id: Cell::new(WorkerId(0)),
             pipeline_id,
             devtools_wants_updates: Default::default(),
@@ -200,6 +235,11 @@ impl GlobalScope {
             microtask_queue,
             list_auto_close_worker: Default::default(),
             event_source_tracker: DOMTracker::new(),
+            indexeddb_transactions: Default::default(),
+            message_port_router: Default::default(),
+            message_ports: Default::default(),
+            broadcast_channel_router: Default::default(),
+            broadcast_channels: Default::default(),
             uncaught_rejections: Default::default(),
             consumed_rejections: Default::default(),
             is_headless,
@@ -232,6 +272,299 @@ impl GlobalScope {
         canceled_any_fetch
     }
 
+    pub fn track_indexeddb_transaction(&self, transaction: &IDBTransaction) {
+        self.indexeddb_transactions
+            .borrow_mut()
+            .push(Dom::from_ref(transaction));
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#cleanup-indexed-database-transactions>
+    pub fn cleanup_indexeddb_transactions(&self) {
+        let transactions: Vec<_> = self
+            .indexeddb_transactions
+            .borrow_mut()
+            .drain(..)
+            .map(|transaction| DomRoot::from_ref(&*transaction))
+            .collect();
+        for transaction in transactions {
+            transaction.deactivate();
+        }
+    }
+
+    /// The id of the message-port router of this global, created the first
+    /// time a port is managed by it.
+    fn message_port_router_id(&self) -> MessagePortRouterId {
+        if let Some(router_id) = self.message_port_router.get() {
+            return router_id;
+        }
+        let router_id = MessagePortRouterId::new();
+        let (port_control_sender, port_control_receiver) =
+            ipc::channel().expect("ipc channel failure");
+        let context = Trusted::new(self);
+        let task_source = self.port_message_queue();
+        let canceller = self.task_canceller(TaskSourceName::PortMessage);
+        ROUTER.add_route(
+            port_control_receiver.to_opaque(),
+            Box::new(move |message| {
+                let msg = match message.to() {
+                    Ok(msg) => msg,
+                    Err(err) => return warn!("Failed to deserialize MessagePortMsg: {:?}", err),
+                };
+                let context = context.clone();
+                let _ = task_source.queue_with_canceller(
+                    task!(process_message_port_msg: move || {
+                        context.root().handle_message_port_msg(msg);
+                    }),
+                    &canceller,
+                );
+            }),
+        );
+        let _ = self
+            .script_to_constellation_chan()
+            .send(ScriptMsg::NewMessagePortRouter(
+                router_id,
+                port_control_sender,
+            ));
+        self.message_port_router.set(Some(router_id));
+        router_id
+    }
+
+    /// Start managing a port created in, or transferred to, this global.
+    pub fn track_message_port(&self, port: &MessagePort) {
+        let router_id = self.message_port_router_id();
+        self.message_ports
+            .borrow_mut()
+            .insert(port.message_port_id(), Dom::from_ref(port));
+        let _ = self
+            .script_to_constellation_chan()
+            .send(ScriptMsg::NewMessagePort(router_id, port.message_port_id()));
+    }
+
+    /// A port managed by this global is being transferred, messages for it
+    /// will be buffered by the constellation until its transfer completes.
+    pub fn mark_port_as_shipped(&self, port_id: MessagePortId) {
+        self.message_ports.borrow_mut().remove(&port_id);
+        let _ = self
+            .script_to_constellation_chan()
+            .send(ScriptMsg::MessagePortShipped(port_id));
+    }
+
+    /// A port managed by this global was closed.
+    pub fn remove_message_port(&self, port_id: MessagePortId) {
+        self.message_ports.borrow_mut().remove(&port_id);
+        let _ = self
+            .script_to_constellation_chan()
+            .send(ScriptMsg::RemoveMessagePort(port_id));
+    }
+
+    fn managed_message_port(&self, port_id: &MessagePortId) -> Option<DomRoot<MessagePort>> {
+        self.message_ports
+            .borrow()
+            .get(port_id)
+            .map(|port| DomRoot::from_ref(&**port))
+    }
+
+    /// Handle a message sent by the constellation to our message-port router.
+    fn handle_message_port_msg(&self, msg: MessagePortMsg) {
+        match msg {
+            MessagePortMsg::CompleteTransfer(port_id, tasks) => {
+                match self.managed_message_port(&port_id) {
+                    Some(port) => port.complete_transfer(tasks),
+                    None => {
+                        for task in tasks {
+                            self.route_task_to_port(port_id, task);
+                        }
+                    },
+                }
+            },
+            MessagePortMsg::NewTask(port_id, task) => self.route_task_to_port(port_id, task),
+        }
+    }
+
+    /// Route a task to a port, directly if it is managed by this global,
+    /// through the constellation otherwise.
+    pub fn route_task_to_port(&self, port_id: MessagePortId, task: PortMessageTask) {
+        match self.managed_message_port(&port_id) {
+            Some(port) => port.handle_incoming(task),
+            None => {
+                let _ = self
+                    .script_to_constellation_chan()
+                    .send(ScriptMsg::RerouteMessagePort(port_id, task));
+            },
+        }
+    }
+
+    /// The id of the broadcast-channel router of this global, created the
+    /// first time a channel is created in it.
+    fn broadcast_channel_router_id(&self) -> BroadcastChannelRouterId {
+        if let Some(router_id) = self.broadcast_channel_router.get() {
+            return router_id;
+        }
+        let router_id = BroadcastChannelRouterId::new();
+        let (broadcast_control_sender, broadcast_control_receiver) =
+            ipc::channel().expect("ipc channel failure");
+        let context = Trusted::new(self);
+        let task_source = self.dom_manipulation_task_source();
+        let canceller = self.task_canceller(TaskSourceName::DOMManipulation);
+        ROUTER.add_route(
+            broadcast_control_receiver.to_opaque(),
+            Box::new(move |message| {
+                let msg = match message.to() {
+                    Ok(msg) => msg,
+                    Err(err) => return warn!("Failed to deserialize BroadcastMsg: {:?}", err),
+                };
+                let context = context.clone();
+                let _ = task_source.queue_with_canceller(
+                    task!(process_broadcast_msg: move || {
+                        context.root().broadcast_message_event(msg, None);
+                    }),
+                    &canceller,
+                );
+            }),
+        );
+        let _ = self
+            .script_to_constellation_chan()
+            .send(ScriptMsg::NewBroadcastChannelRouter(
+                router_id,
+                broadcast_control_sender,
+                self.origin().immutable().clone(),
+            ));
+        self.broadcast_channel_router.set(Some(router_id));
+        router_id
+    }
+
+    /// Start managing a channel created in this global.
+    pub fn track_broadcast_channel(&self, channel: &BroadcastChannel) {
+        let router_id = self.broadcast_channel_router_id();
+        let mut channels = self.broadcast_channels.borrow_mut();
+        let channels_with_name = channels.entry(channel.name().clone()).or_insert_with(|| {
+            let _ = self.script_to_constellation_chan().send(
+                ScriptMsg::NewBroadcastChannelNameInRouter(
+                    router_id,
+                    channel.name().to_string(),
+                    self.origin().immutable().clone(),
+                ),
+            );
+            vec![]
+        });
+        channels_with_name.push(Dom::from_ref(channel));
+    }
+
+    /// A channel managed by this global was closed.
+    pub fn unregister_broadcast_channel(&self, channel: &BroadcastChannel) {
+        let mut channels = self.broadcast_channels.borrow_mut();
+        if let Entry::Occupied(mut entry) = channels.entry(channel.name().clone()) {
+            entry
+                .get_mut()
+                .retain(|other| &**other as *const BroadcastChannel != channel as *const _);
+            if !entry.get().is_empty() {
+                return;
+            }
+            entry.remove();
+            if let Some(router_id) = self.broadcast_channel_router.get() {
+                let _ = self.script_to_constellation_chan().send(
+                    ScriptMsg::RemoveBroadcastChannelNameInRouter(
+                        router_id,
+                        channel.name().to_string(),
+                        self.origin().immutable().clone(),
+                    ),
+                );
+            }
+        }
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#dom-broadcastchannel-postmessage>
+    pub fn schedule_broadcast(&self, msg: BroadcastMsg, source: &BroadcastChannel) {
+        // Channels in this global are notified directly,
+        let local_msg = BroadcastMsg {
+            origin: msg.origin.clone(),
+            channel_name: msg.channel_name.clone(),
+            data: StructuredSerializedData::without_transfer(msg.data.serialized.clone()),
+        };
+        self.broadcast_message_event(local_msg, Some(source));
+
+        // and the ones in other globals through the constellation.
+        if let Some(router_id) = self.broadcast_channel_router.get() {
+            let _ = self
+                .script_to_constellation_chan()
+                .send(ScriptMsg::ScheduleBroadcast(router_id, msg));
+        }
+    }
+
+    /// Queue a message event on every open channel of this global that has
+    /// the name of the message, except on its source.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#dom-broadcastchannel-postmessage>
+    /// Steps 5-7.
+    fn broadcast_message_event(&self, msg: BroadcastMsg, source: Option<&BroadcastChannel>) {
+        let BroadcastMsg {
+            origin,
+            channel_name,
+            data,
+        } = msg;
+        let destinations: Vec<_> = match self
+            .broadcast_channels
+            .borrow()
+            .get(&DOMString::from(channel_name))
+        {
+            Some(channels) => channels
+                .iter()
+                .filter(|channel| !channel.closed())
+                .filter(|channel| {
+                    source.map_or(true, |source| {
+                        &***channel as *const BroadcastChannel != source as *const _
+                    })
+                })
+                .map(|channel| Trusted::new(&**channel))
+                .collect(),
+            None => return,
+        };
+        let origin = origin.ascii_serialization();
+        for channel in destinations {
+            let serialized = data.serialized.clone();
+            let origin = origin.clone();
+            let _ = self.dom_manipulation_task_source().queue(
+                task!(broadcast_message_event: move || {
+                    let channel = channel.root();
+                    if channel.closed() {
+                        return;
+                    }
+                    let global = channel.global();
+                    let _ac = enter_realm(&*channel);
+                    rooted!(in(*global.get_cx()) let mut message = UndefinedValue());
+                    let data = StructuredSerializedData::without_transfer(serialized);
+                    structuredclone::read(&global, data, message.handle_mut());
+                    MessageEvent::dispatch_jsval(
+                        channel.upcast(),
+                        &global,
+                        message.handle(),
+                        Some(&origin),
+                        None,
+                        vec![],
+                    );
+                }),
+                self,
+            );
+        }
+    }
+
+    /// Stop managing ports and channels, when this global is torn down.
+    pub fn remove_web_messaging_infra(&self) {
+        if let Some(router_id) = self.message_port_router.take() {
+            let _ = self
+                .script_to_constellation_chan()
+                .send(ScriptMsg::RemoveMessagePortRouter(router_id));
+        }
+        self.message_ports.borrow_mut().clear();
+        if let Some(router_id) = self.broadcast_channel_router.take() {
+            let origin = self.origin().immutable().clone();
+            let _ = self
+                .script_to_constellation_chan()
+                .send(ScriptMsg::RemoveBroadcastChannelRouter(router_id, origin));
+        }
+        self.broadcast_channels.borrow_mut().clear();
+    }
+
     /// Returns the global scope of the realm that the given DOM object's reflector
     /// was created in.
     #[allow(unsafe_code)]
@@ -319,6 +652,10 @@ impl GlobalScope {
         self.crypto.or_init(|| Crypto::new(self))
     }
 
+    pub fn indexed_db(&self) -> DomRoot<IDBFactory> {
+        self.indexed_db.or_init(|| IDBFactory::new(self))
+    }
+
     /// Get next worker id.
     pub fn get_next_worker_id(&self) -> WorkerId {
         let worker_id = self.next_worker_id.get();
@@ -730,6 +1067,18 @@ impl GlobalScope {
         unreachable!();
     }
 
+    /// Channel to send messages to the database access task source of
+    /// this global scope.
+    pub fn database_access_task_source(&self) -> DatabaseAccessTaskSource {
+        if let Some(window) = self.downcast::<Window>() {
+            return window.task_manager().database_access_task_source();
+        }
+        if let Some(worker) = self.downcast::<WorkerGlobalScope>() {
+            return worker.database_access_task_source();
+        }
+        unreachable!();
+    }
+
     /// Channel to send messages to the file reading task source of
     /// this of this global scope.
     pub fn file_reading_task_source(&self) -> FileReadingTaskSource {
@@ -805,6 +1154,18 @@ impl GlobalScope {
         unreachable!();
     }
 
+    /// `ScriptChan` to send messages to the port message queue of
+    /// this global scope.
+    pub fn port_message_queue(&self) -> PortMessageQueue {
+        if let Some(window) = self.downcast::<Window>() {
+            return window.task_manager().port_message_queue();
+        }
+        if let Some(worker) = self.downcast::<WorkerGlobalScope>() {
+            return worker.port_message_queue();
+        }
+        unreachable!();
+    }
+
     pub fn is_headless(&self) -> bool {
         self.is_headless
     }
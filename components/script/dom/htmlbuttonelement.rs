@@ -20,7 +20,7 @@ use crate::dom::htmlformelement::{FormControl, FormDatum, FormDatumValue};
 use crate::dom::htmlformelement::{FormSubmitter, ResetFrom, SubmittedFrom};
 use crate::dom::node::{document_from_node, window_from_node, BindContext, Node, UnbindContext};
 use crate::dom::nodelist::NodeList;
-use crate::dom::validation::Validatable;
+use crate::dom::validation::{is_barred_by_datalist_ancestor, Validatable};
 use crate::dom::validitystate::{ValidationFlags, ValidityState};
 use crate::dom::virtualmethods::VirtualMethods;
 use dom_struct::dom_struct;
@@ -42,6 +42,7 @@ pub struct HTMLButtonElement {
     htmlelement: HTMLElement,
     button_type: Cell<ButtonType>,
     form_owner: MutNullableDom<HTMLFormElement>,
+    validity_state: MutNullableDom<ValidityState>,
 }
 
 impl HTMLButtonElement {
@@ -59,6 +60,7 @@ impl HTMLButtonElement {
             ),
             button_type: Cell::new(ButtonType::Submit),
             form_owner: Default::default(),
+            validity_state: Default::default(),
         }
     }
 
@@ -79,12 +81,6 @@ impl HTMLButtonElement {
 }
 
 impl HTMLButtonElementMethods for HTMLButtonElement {
-    // https://html.spec.whatwg.org/multipage/#dom-cva-validity
-    fn Validity(&self) -> DomRoot<ValidityState> {
-        let window = window_from_node(self);
-        ValidityState::new(&window, self.upcast())
-    }
-
     // https://html.spec.whatwg.org/multipage/#dom-fe-disabled
     make_bool_getter!(Disabled, "disabled");
 
@@ -153,6 +149,36 @@ impl HTMLButtonElementMethods for HTMLButtonElement {
     fn Labels(&self) -> DomRoot<NodeList> {
         self.upcast::<HTMLElement>().labels()
     }
+
+    // https://html.spec.whatwg.org/multipage/#dom-cva-willvalidate
+    fn WillValidate(&self) -> bool {
+        self.is_instance_validatable()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-cva-validity
+    fn Validity(&self) -> DomRoot<ValidityState> {
+        self.validity_state()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-cva-checkvalidity
+    fn CheckValidity(&self) -> bool {
+        self.check_validity()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-cva-reportvalidity
+    fn ReportValidity(&self) -> bool {
+        self.report_validity()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-cva-validationmessage
+    fn ValidationMessage(&self) -> DOMString {
+        self.validation_message()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-cva-setcustomvalidity
+    fn SetCustomValidity(&self, error: DOMString) {
+        self.validity_state().set_custom_error_message(error);
+    }
 }
 
 impl HTMLButtonElement {
@@ -210,20 +236,26 @@ impl VirtualMethods for HTMLButtonElement {
                         el.check_ancestors_disabled_state_for_form_control();
                     },
                 }
+                self.validity_state()
+                    .perform_validation_and_update(ValidationFlags::all());
             },
-            &local_name!("type") => match mutation {
-                AttributeMutation::Set(_) => {
-                    let value = match &**attr.value() {
-                        "reset" => ButtonType::Reset,
-                        "button" => ButtonType::Button,
-                        "menu" => ButtonType::Menu,
-                        _ => ButtonType::Submit,
-                    };
-                    self.button_type.set(value);
-                },
-                AttributeMutation::Removed => {
-                    self.button_type.set(ButtonType::Submit);
-                },
+            &local_name!("type") => {
+                match mutation {
+                    AttributeMutation::Set(_) => {
+                        let value = match &**attr.value() {
+                            "reset" => ButtonType::Reset,
+                            "button" => ButtonType::Button,
+                            "menu" => ButtonType::Menu,
+                            _ => ButtonType::Submit,
+                        };
+                        self.button_type.set(value);
+                    },
+                    AttributeMutation::Removed => {
+                        self.button_type.set(ButtonType::Submit);
+                    },
+                }
+                self.validity_state()
+                    .perform_validation_and_update(ValidationFlags::all());
             },
             &local_name!("form") => {
                 self.form_attribute_mutated(mutation);
@@ -239,6 +271,8 @@ impl VirtualMethods for HTMLButtonElement {
 
         self.upcast::<Element>()
             .check_ancestors_disabled_state_for_form_control();
+        self.validity_state()
+            .perform_validation_and_update(ValidationFlags::all());
     }
 
     fn unbind_from_tree(&self, context: &UnbindContext) {
@@ -272,13 +306,22 @@ impl FormControl for HTMLButtonElement {
 }
 
 impl Validatable for HTMLButtonElement {
-    fn is_instance_validatable(&self) -> bool {
-        true
+    fn as_element(&self) -> &Element {
+        self.upcast()
     }
-    fn validate(&self, validate_flags: ValidationFlags) -> bool {
-        if validate_flags.is_empty() {}
-        // Need more flag check for different validation types later
-        true
+
+    fn validity_state(&self) -> DomRoot<ValidityState> {
+        self.validity_state
+            .or_init(|| ValidityState::new(&window_from_node(self), self.upcast()))
+    }
+
+    fn is_instance_validatable(&self) -> bool {
+        // https://html.spec.whatwg.org/multipage/#the-button-element%3Abarred-from-constraint-validation
+        // https://html.spec.whatwg.org/multipage/#enabling-and-disabling-form-controls%3A-the-disabled-attribute%3Abarred-from-constraint-validation
+        // https://html.spec.whatwg.org/multipage/#the-datalist-element%3Abarred-from-constraint-validation
+        self.button_type.get() == ButtonType::Submit &&
+            !self.upcast::<Element>().disabled_state() &&
+            !is_barred_by_datalist_ancestor(self.upcast())
     }
 }
 
@@ -338,7 +381,7 @@ impl Activatable for HTMLButtonElement {
             .find(|r| r.form_owner() == owner)
             .map(|s| {
                 synthetic_click_activation(
-                    s.as_element(),
+                    s.upcast::<Element>(),
                     ctrl_key,
                     shift_key,
                     alt_key,
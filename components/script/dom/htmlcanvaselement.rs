@@ -16,19 +16,21 @@ use crate::dom::bindings::reflector::DomObject;
 use crate::dom::bindings::root::{Dom, DomRoot, LayoutDom};
 use crate::dom::bindings::str::{DOMString, USVString};
 use crate::dom::canvasrenderingcontext2d::{
-    CanvasRenderingContext2D, LayoutCanvasRenderingContext2DHelpers,
+    create_canvas, CanvasRenderingContext2D, LayoutCanvasRenderingContext2DHelpers,
 };
 use crate::dom::document::Document;
 use crate::dom::element::{AttributeMutation, Element, RawLayoutElementHelpers};
 use crate::dom::globalscope::GlobalScope;
 use crate::dom::htmlelement::HTMLElement;
-use crate::dom::node::{window_from_node, Node};
+use crate::dom::node::{document_from_node, window_from_node, BindContext, Node, UnbindContext};
+use crate::dom::offscreencanvas::{LayoutOffscreenCanvasHelpers, OffscreenCanvas};
 use crate::dom::virtualmethods::VirtualMethods;
 use crate::dom::webgl2renderingcontext::WebGL2RenderingContext;
 use crate::dom::webglrenderingcontext::{
     LayoutCanvasWebGLRenderingContextHelpers, WebGLRenderingContext,
 };
 use crate::script_runtime::JSContext;
+use crate::unpremultiplytable::UNPREMULTIPLY_TABLE;
 use base64;
 use canvas_traits::canvas::{CanvasId, CanvasMsg, FromScriptMsg};
 use canvas_traits::webgl::{GLContextAttributes, WebGLVersion};
@@ -55,6 +57,10 @@ pub enum CanvasContext {
     Context2d(Dom<CanvasRenderingContext2D>),
     WebGL(Dom<WebGLRenderingContext>),
     WebGL2(Dom<WebGL2RenderingContext>),
+    /// The element is the placeholder of an `OffscreenCanvas`.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#offscreencanvas-placeholder>
+    Placeholder(Dom<OffscreenCanvas>),
 }
 
 #[dom_struct]
@@ -97,6 +103,8 @@ impl HTMLCanvasElement {
                 CanvasContext::Context2d(ref context) => context.set_bitmap_dimensions(size),
                 CanvasContext::WebGL(ref context) => context.recreate(size),
                 CanvasContext::WebGL2(ref context) => context.recreate(size),
+                // The size of a placeholder follows the frames committed to it.
+                CanvasContext::Placeholder(_) => {},
             }
         }
     }
@@ -105,6 +113,17 @@ impl HTMLCanvasElement {
         Size2D::new(self.Width(), self.Height())
     }
 
+    /// The id of the canvas backing this element, if it is the placeholder of an
+    /// `OffscreenCanvas`.
+    fn placeholder_canvas_id(&self) -> Option<CanvasId> {
+        match *self.context.borrow() {
+            Some(CanvasContext::Placeholder(ref canvas)) => {
+                canvas.placeholder_canvas().map(|(_, canvas_id)| canvas_id)
+            },
+            _ => None,
+        }
+    }
+
     pub fn origin_is_clean(&self) -> bool {
         match *self.context.borrow() {
             Some(CanvasContext::Context2d(ref context)) => context.origin_is_clean(),
@@ -135,6 +154,12 @@ impl LayoutHTMLCanvasElementHelpers for LayoutDom<HTMLCanvasElement> {
                 Some(&CanvasContext::WebGL2(ref context)) => {
                     context.to_layout().canvas_data_source()
                 },
+                Some(&CanvasContext::Placeholder(ref canvas)) => HTMLCanvasDataSource::Image(
+                    canvas
+                        .to_layout()
+                        .get_placeholder_canvas()
+                        .map(|(ipc_renderer, _)| ipc_renderer),
+                ),
                 None => HTMLCanvasDataSource::Image(None),
             };
 
@@ -177,11 +202,13 @@ impl LayoutHTMLCanvasElementHelpers for LayoutDom<HTMLCanvasElement> {
     fn get_canvas_id_for_layout(&self) -> CanvasId {
         unsafe {
             let canvas = &*self.unsafe_get();
-            if let &Some(CanvasContext::Context2d(ref context)) = canvas.context.borrow_for_layout()
-            {
-                context.to_layout().get_canvas_id()
-            } else {
-                CanvasId(0)
+            match canvas.context.borrow_for_layout() {
+                &Some(CanvasContext::Context2d(ref context)) => context.to_layout().get_canvas_id(),
+                &Some(CanvasContext::Placeholder(ref canvas)) => canvas
+                    .to_layout()
+                    .get_placeholder_canvas()
+                    .map_or(CanvasId(0), |(_, canvas_id)| canvas_id),
+                _ => CanvasId(0),
             }
         }
     }
@@ -304,6 +331,15 @@ impl HTMLCanvasElement {
                 // TODO: add a method in WebGL2RenderingContext to get the pixels.
                 return None;
             },
+            Some(&CanvasContext::Placeholder(ref canvas)) => {
+                let (ipc_renderer, canvas_id) = canvas.placeholder_canvas()?;
+                let (sender, receiver) =
+                    ipc::channel(self.global().time_profiler_chan().clone()).unwrap();
+                let msg = CanvasMsg::FromScript(FromScriptMsg::SendPixels(sender), canvas_id);
+                ipc_renderer.send(msg).unwrap();
+
+                Some(receiver.recv().unwrap())
+            },
             None => None,
         };
 
@@ -330,8 +366,13 @@ impl HTMLCanvasElementMethods for HTMLCanvasElement {
         cx: JSContext,
         id: DOMString,
         options: HandleValue,
-    ) -> Option<RenderingContext> {
-        match &*id {
+    ) -> Fallible<Option<RenderingContext>> {
+        // Step 2.
+        if let Some(CanvasContext::Placeholder(_)) = *self.context.borrow() {
+            return Err(Error::InvalidState);
+        }
+
+        Ok(match &*id {
             "2d" => self
                 .get_or_init_2d_context()
                 .map(RenderingContext::CanvasRenderingContext2D),
@@ -342,7 +383,41 @@ impl HTMLCanvasElementMethods for HTMLCanvasElement {
                 .get_or_init_webgl2_context(cx, options)
                 .map(RenderingContext::WebGL2RenderingContext),
             _ => None,
+        })
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-canvas-transfercontroltooffscreen
+    fn TransferControlToOffscreen(&self) -> Fallible<DomRoot<OffscreenCanvas>> {
+        // Step 1.
+        if self.context.borrow().is_some() {
+            return Err(Error::InvalidState);
+        }
+
+        // Step 2 and 3. The placeholder displays the frames committed to a canvas of
+        // the canvas paint thread, through the image key layout gets from it.
+        let global = self.global();
+        let size = self.get_size();
+        let placeholder_canvas =
+            create_canvas(&global, Size2D::new(size.width as u64, size.height as u64));
+        let placeholder_id = placeholder_canvas.1;
+        let offscreen_canvas = OffscreenCanvas::new(
+            &global,
+            size.height as u64,
+            size.width as u64,
+            Some(self),
+            Some(placeholder_canvas),
+        );
+
+        // Step 4.
+        *self.context.borrow_mut() = Some(CanvasContext::Placeholder(Dom::from_ref(
+            &*offscreen_canvas,
+        )));
+        if self.upcast::<Node>().is_connected() {
+            document_from_node(self).register_offscreen_canvas_placeholder(placeholder_id, self);
         }
+
+        // Step 5.
+        Ok(offscreen_canvas)
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-canvas-todataurl
@@ -379,6 +454,21 @@ impl HTMLCanvasElementMethods for HTMLCanvasElement {
                     None => return Ok(USVString("data:,".into())),
                 }
             },
+            Some(CanvasContext::Placeholder(_)) => match self.fetch_all_data() {
+                Some((Some(data), size)) if size == self.get_size() => {
+                    let mut pixels = data.to_vec();
+                    for chunk in pixels.chunks_mut(4) {
+                        let b = chunk[0];
+                        chunk[0] =
+                            UNPREMULTIPLY_TABLE[256 * (chunk[3] as usize) + chunk[2] as usize];
+                        chunk[1] =
+                            UNPREMULTIPLY_TABLE[256 * (chunk[3] as usize) + chunk[1] as usize];
+                        chunk[2] = UNPREMULTIPLY_TABLE[256 * (chunk[3] as usize) + b as usize];
+                    }
+                    pixels
+                },
+                _ => vec![0; (self.Width() * self.Height() * 4) as usize],
+            },
             None => {
                 // Each pixel is fully-transparent black.
                 vec![0; (self.Width() * self.Height() * 4) as usize]
@@ -413,6 +503,28 @@ impl VirtualMethods for HTMLCanvasElement {
         };
     }
 
+    fn bind_to_tree(&self, context: &BindContext) {
+        if let Some(ref s) = self.super_type() {
+            s.bind_to_tree(context);
+        }
+
+        if context.tree_connected {
+            if let Some(canvas_id) = self.placeholder_canvas_id() {
+                document_from_node(self).register_offscreen_canvas_placeholder(canvas_id, self);
+            }
+        }
+    }
+
+    fn unbind_from_tree(&self, context: &UnbindContext) {
+        self.super_type().unwrap().unbind_from_tree(context);
+
+        if context.tree_connected {
+            if let Some(canvas_id) = self.placeholder_canvas_id() {
+                document_from_node(self).unregister_offscreen_canvas_placeholder(canvas_id);
+            }
+        }
+    }
+
     fn parse_plain_attribute(&self, name: &LocalName, value: DOMString) -> AttrValue {
         match name {
             &local_name!("width") => AttrValue::from_u32(value.into(), DEFAULT_WIDTH),
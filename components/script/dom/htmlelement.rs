@@ -5,6 +5,7 @@
 use crate::dom::activation::{synthetic_click_activation, ActivationSource};
 use crate::dom::attr::Attr;
 use crate::dom::bindings::codegen::Bindings::ElementBinding::ElementMethods;
+use crate::dom::bindings::codegen::Bindings::EventBinding::EventBinding::EventMethods;
 use crate::dom::bindings::codegen::Bindings::EventHandlerBinding::EventHandlerNonNull;
 use crate::dom::bindings::codegen::Bindings::HTMLElementBinding;
 use crate::dom::bindings::codegen::Bindings::HTMLElementBinding::HTMLElementMethods;
@@ -19,7 +20,9 @@ use crate::dom::cssstyledeclaration::{CSSModificationAccess, CSSStyleDeclaration
 use crate::dom::document::{Document, FocusType};
 use crate::dom::documentfragment::DocumentFragment;
 use crate::dom::domstringmap::DOMStringMap;
+use crate::dom::editing::{self, ContentEditableState};
 use crate::dom::element::{AttributeMutation, Element};
+use crate::dom::event::Event;
 use crate::dom::eventtarget::EventTarget;
 use crate::dom::htmlbodyelement::HTMLBodyElement;
 use crate::dom::htmlbrelement::HTMLBRElement;
@@ -27,6 +30,8 @@ use crate::dom::htmlframesetelement::HTMLFrameSetElement;
 use crate::dom::htmlhtmlelement::HTMLHtmlElement;
 use crate::dom::htmlinputelement::{HTMLInputElement, InputType};
 use crate::dom::htmllabelelement::HTMLLabelElement;
+use crate::dom::htmltextareaelement::HTMLTextAreaElement;
+use crate::dom::keyboardevent::KeyboardEvent;
 use crate::dom::node::{document_from_node, window_from_node};
 use crate::dom::node::{BindContext, Node, NodeFlags, ShadowIncluding};
 use crate::dom::nodelist::NodeList;
@@ -134,7 +139,9 @@ impl HTMLElement {
                     } else {
                         node.set_flag(NodeFlags::SEQUENTIALLY_FOCUSABLE, false);
                     }
-                    //TODO set SEQUENTIALLY_FOCUSABLE flag if editing host
+                    if ContentEditableState::of(element) == ContentEditableState::True {
+                        node.set_flag(NodeFlags::SEQUENTIALLY_FOCUSABLE, true);
+                    }
                     //TODO set SEQUENTIALLY_FOCUSABLE flag if "sorting interface th elements"
                 },
             }
@@ -358,6 +365,35 @@ impl HTMLElementMethods for HTMLElement {
         Some(item_attr_values.into_iter().collect())
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-contenteditable
+    fn ContentEditable(&self) -> DOMString {
+        DOMString::from(match ContentEditableState::of(self.upcast()) {
+            ContentEditableState::True => "true",
+            ContentEditableState::False => "false",
+            ContentEditableState::Inherit => "inherit",
+        })
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-contenteditable
+    fn SetContentEditable(&self, value: DOMString) -> ErrorResult {
+        let element = self.upcast::<Element>();
+        if value.eq_ignore_ascii_case("inherit") {
+            element.remove_attribute(&ns!(), &local_name!("contenteditable"));
+        } else if value.eq_ignore_ascii_case("true") {
+            element.set_string_attribute(&local_name!("contenteditable"), "true".into());
+        } else if value.eq_ignore_ascii_case("false") {
+            element.set_string_attribute(&local_name!("contenteditable"), "false".into());
+        } else {
+            return Err(Error::Syntax);
+        }
+        Ok(())
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-iscontenteditable
+    fn IsContentEditable(&self) -> bool {
+        editing::editing_host_of(self.upcast()).is_some()
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-click
     fn Click(&self) {
         if !self.upcast::<Element>().disabled_state() {
@@ -736,6 +772,7 @@ impl VirtualMethods for HTMLElement {
                     DOMString::from(&**attr.value()),
                 );
             },
+            (&local_name!("contenteditable"), _) => self.update_sequentially_focusable_status(),
             _ => {},
         }
     }
@@ -747,6 +784,31 @@ impl VirtualMethods for HTMLElement {
         self.update_sequentially_focusable_status();
     }
 
+    fn handle_event(&self, event: &Event) {
+        if let Some(s) = self.super_type() {
+            s.handle_event(event);
+        }
+
+        // Text controls edit their own value.
+        if event.DefaultPrevented() ||
+            self.is::<HTMLInputElement>() ||
+            self.is::<HTMLTextAreaElement>()
+        {
+            return;
+        }
+        let host = match editing::editing_host_of(self.upcast()) {
+            Some(host) => host,
+            None => return,
+        };
+        if event.type_() == atom!("click") {
+            document_from_node(self).request_focus(&host);
+        } else if event.type_() == atom!("keydown") {
+            if let Some(event) = event.downcast::<KeyboardEvent>() {
+                editing::handle_keydown(&host, event);
+            }
+        }
+    }
+
     fn parse_plain_attribute(&self, name: &LocalName, value: DOMString) -> AttrValue {
         match name {
             &local_name!("itemprop") => AttrValue::from_serialized_tokenlist(value.into()),
@@ -45,7 +45,7 @@ use crate::dom::htmltextareaelement::HTMLTextAreaElement;
 use crate::dom::node::{document_from_node, window_from_node};
 use crate::dom::node::{Node, NodeFlags, ShadowIncluding};
 use crate::dom::node::{UnbindContext, VecPreOrderInsertionHelper};
-use crate::dom::validitystate::ValidationFlags;
+use crate::dom::validation::report_invalid_control;
 use crate::dom::virtualmethods::VirtualMethods;
 use crate::dom::window::Window;
 use crate::task_source::TaskSource;
@@ -182,6 +182,16 @@ impl HTMLFormElementMethods for HTMLFormElement {
         self.reset(ResetFrom::FromForm);
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-form-checkvalidity
+    fn CheckValidity(&self) -> bool {
+        self.static_validation().is_ok()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-form-reportvalidity
+    fn ReportValidity(&self) -> bool {
+        self.interactive_validation().is_ok()
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-form-elements
     fn Elements(&self) -> DomRoot<HTMLFormControlsCollection> {
         #[derive(JSTraceable, MallocSizeOf)]
@@ -581,13 +591,21 @@ impl HTMLFormElement {
     /// Interactively validate the constraints of form elements
     /// <https://html.spec.whatwg.org/multipage/#interactively-validate-the-constraints>
     fn interactive_validation(&self) -> Result<(), ()> {
-        // Step 1-3
-        let _unhandled_invalid_controls = match self.static_validation() {
+        // Step 1-2
+        let unhandled_invalid_controls = match self.static_validation() {
             Ok(()) => return Ok(()),
             Err(err) => err,
         };
-        // TODO: Report the problems with the constraints of at least one of
-        //       the elements given in unhandled invalid controls to the user
+
+        // Step 3: Report the problems with the constraints of the first of the
+        //         unhandled invalid controls to the user, focusing it.
+        if let Some(control) = unhandled_invalid_controls.first() {
+            let element = control.as_event_target().downcast::<Element>().unwrap();
+            if let Some(validatable) = element.as_maybe_validatable() {
+                report_invalid_control(element, validatable.validation_message());
+            }
+        }
+
         // Step 4
         Err(())
     }
@@ -612,7 +630,7 @@ impl HTMLFormElement {
                         };
                         if !validatable.is_instance_validatable() {
                             None
-                        } else if validatable.validate(ValidationFlags::empty()) {
+                        } else if validatable.satisfies_constraints() {
                             None
                         } else {
                             Some(FormSubmittableElement::from_element(&el))
@@ -38,8 +38,8 @@ use crate::dom::node::{document_from_node, window_from_node};
 use crate::dom::node::{BindContext, Node, NodeDamage, UnbindContext};
 use crate::dom::nodelist::NodeList;
 use crate::dom::textcontrol::{TextControlElement, TextControlSelection};
-use crate::dom::validation::Validatable;
-use crate::dom::validitystate::ValidationFlags;
+use crate::dom::validation::{is_barred_by_datalist_ancestor, Validatable};
+use crate::dom::validitystate::{ValidationFlags, ValidityState};
 use crate::dom::virtualmethods::VirtualMethods;
 use crate::textinput::KeyReaction::{
     DispatchInput, Nothing, RedrawSelection, TriggerDefaultAction,
@@ -55,9 +55,11 @@ use net_traits::blob_url_store::get_blob_origin;
 use net_traits::filemanager_thread::FileManagerThreadMsg;
 use net_traits::{CoreResourceMsg, IpcSend};
 use profile_traits::ipc;
+use regex::Regex;
 use script_layout_interface::rpc::TextIndexResponse;
 use script_traits::ScriptToConstellationChan;
 use servo_atoms::Atom;
+use servo_url::ServoUrl;
 use std::borrow::ToOwned;
 use std::cell::Cell;
 use std::ops::Range;
@@ -231,9 +233,12 @@ pub struct HTMLInputElement {
     activation_state: DomRefCell<InputActivationState>,
     // https://html.spec.whatwg.org/multipage/#concept-input-value-dirty-flag
     value_dirty: Cell<bool>,
+    // Whether the value was last changed by a user edit, as opposed to by script.
+    value_changed_by_user: Cell<bool>,
 
     filelist: MutNullableDom<FileList>,
     form_owner: MutNullableDom<HTMLFormElement>,
+    validity_state: MutNullableDom<ValidityState>,
 }
 
 #[derive(JSTraceable)]
@@ -301,8 +306,10 @@ impl HTMLInputElement {
             )),
             activation_state: DomRefCell::new(InputActivationState::new()),
             value_dirty: Cell::new(false),
+            value_changed_by_user: Cell::new(false),
             filelist: MutNullableDom::new(None),
             form_owner: Default::default(),
+            validity_state: Default::default(),
         }
     }
 
@@ -636,6 +643,7 @@ impl HTMLInputElementMethods for HTMLInputElement {
             ValueMode::Value => {
                 // Step 3.
                 self.value_dirty.set(true);
+                self.value_changed_by_user.set(false);
 
                 // Step 4.
                 self.sanitize_value(&mut value);
@@ -666,6 +674,8 @@ impl HTMLInputElementMethods for HTMLInputElement {
             },
         }
 
+        self.validity_state()
+            .perform_validation_and_update(ValidationFlags::all());
         self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
         Ok(())
     }
@@ -790,6 +800,36 @@ impl HTMLInputElementMethods for HTMLInputElement {
             .set_state(ElementState::IN_INDETERMINATE_STATE, val)
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-cva-willvalidate
+    fn WillValidate(&self) -> bool {
+        self.is_instance_validatable()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-cva-validity
+    fn Validity(&self) -> DomRoot<ValidityState> {
+        self.validity_state()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-cva-checkvalidity
+    fn CheckValidity(&self) -> bool {
+        self.check_validity()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-cva-reportvalidity
+    fn ReportValidity(&self) -> bool {
+        self.report_validity()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-cva-validationmessage
+    fn ValidationMessage(&self) -> DOMString {
+        self.validation_message()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-cva-setcustomvalidity
+    fn SetCustomValidity(&self, error: DOMString) {
+        self.validity_state().set_custom_error_message(error);
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-lfe-labels
     fn Labels(&self) -> DomRoot<NodeList> {
         if self.input_type() == InputType::Hidden {
@@ -1024,6 +1064,7 @@ impl HTMLInputElement {
             broadcast_radio_checked(self, self.radio_group_name().as_ref());
         }
 
+        self.update_validity();
         self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
         //TODO: dispatch change event
     }
@@ -1047,6 +1088,8 @@ impl HTMLInputElement {
         }
         self.textinput.borrow_mut().set_content(self.DefaultValue());
         self.value_dirty.set(false);
+        self.value_changed_by_user.set(false);
+        self.update_validity();
         self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
     }
 
@@ -1126,6 +1169,8 @@ impl HTMLInputElement {
         } else {
             let filelist = FileList::new(&window, files);
             self.filelist.set(Some(&filelist));
+            self.validity_state()
+                .perform_validation_and_update(ValidationFlags::VALUE_MISSING);
 
             target.fire_bubbling_event(atom!("input"));
             target.fire_bubbling_event(atom!("change"));
@@ -1221,6 +1266,321 @@ impl HTMLInputElement {
     fn selection(&self) -> TextControlSelection<Self> {
         TextControlSelection::new(&self, &self.textinput)
     }
+
+    /// Revalidate this input, along with the rest of its radio button group for radio
+    /// buttons, whose `required` and checkedness affect each other's validity.
+    fn update_validity(&self) {
+        self.validity_state()
+            .perform_validation_and_update(ValidationFlags::all());
+        if self.input_type() != InputType::Radio {
+            return;
+        }
+        for radio in self.radio_group_members() {
+            radio
+                .validity_state()
+                .perform_validation_and_update(ValidationFlags::VALUE_MISSING);
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#radio-button-group
+    fn radio_group_members(&self) -> Vec<DomRoot<HTMLInputElement>> {
+        let group = self.radio_group_name();
+        let owner = self.form_owner();
+        document_from_node(self)
+            .upcast::<Node>()
+            .query_selector_iter(DOMString::from("input[type=radio]"))
+            .unwrap()
+            .filter_map(DomRoot::downcast::<HTMLInputElement>)
+            .filter(|r| in_same_group(&r, owner.as_deref(), group.as_ref()) && self != &**r)
+            .collect()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#the-readonly-attribute
+    fn does_readonly_apply(&self) -> bool {
+        match self.input_type() {
+            InputType::Text |
+            InputType::Search |
+            InputType::Url |
+            InputType::Tel |
+            InputType::Email |
+            InputType::Password |
+            InputType::Date |
+            InputType::Month |
+            InputType::Week |
+            InputType::Time |
+            InputType::DatetimeLocal |
+            InputType::Number => true,
+            _ => false,
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#the-required-attribute
+    fn does_required_apply(&self) -> bool {
+        match self.input_type() {
+            InputType::Checkbox | InputType::Radio | InputType::File => true,
+            _ => self.does_readonly_apply(),
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#the-pattern-attribute
+    // https://html.spec.whatwg.org/multipage/#the-maxlength-and-minlength-attributes
+    fn does_pattern_and_length_apply(&self) -> bool {
+        match self.input_type() {
+            InputType::Text |
+            InputType::Search |
+            InputType::Url |
+            InputType::Tel |
+            InputType::Email |
+            InputType::Password => true,
+            _ => false,
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#concept-input-value-string-number
+    fn convert_string_to_number(&self, value: &DOMString) -> Option<f64> {
+        match self.input_type() {
+            InputType::Date => value.date_string_to_number(),
+            InputType::Month => value.month_string_to_number(),
+            InputType::Week => value.week_string_to_number(),
+            InputType::Time => value.time_string_to_number(),
+            InputType::DatetimeLocal => value.local_date_and_time_string_to_number(),
+            InputType::Number | InputType::Range => value.parse_floating_point_number(),
+            _ => None,
+        }
+    }
+
+    fn attribute_as_number(&self, name: &LocalName) -> Option<f64> {
+        self.upcast::<Element>()
+            .get_attribute(&ns!(), name)
+            .and_then(|attr| self.convert_string_to_number(&DOMString::from(&**attr.value())))
+    }
+
+    // https://html.spec.whatwg.org/multipage/#concept-input-min
+    fn minimum(&self) -> Option<f64> {
+        match self.attribute_as_number(&local_name!("min")) {
+            Some(min) => Some(min),
+            // https://html.spec.whatwg.org/multipage/#range-state-(type=range):concept-input-min-default
+            None if self.input_type() == InputType::Range => Some(0.0),
+            None => None,
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#concept-input-max
+    fn maximum(&self) -> Option<f64> {
+        match self.attribute_as_number(&local_name!("max")) {
+            Some(max) => Some(max),
+            // https://html.spec.whatwg.org/multipage/#range-state-(type=range):concept-input-max-default
+            None if self.input_type() == InputType::Range => Some(100.0),
+            None => None,
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#concept-input-step
+    fn allowed_value_step(&self) -> Option<f64> {
+        // https://html.spec.whatwg.org/multipage/#concept-input-step-default
+        // https://html.spec.whatwg.org/multipage/#concept-input-step-scale
+        let (default_step, step_scale_factor) = match self.input_type() {
+            InputType::Date => (1.0, 86_400_000.0),
+            InputType::Month => (1.0, 1.0),
+            InputType::Week => (1.0, 604_800_000.0),
+            InputType::Time | InputType::DatetimeLocal => (60.0, 1000.0),
+            InputType::Number | InputType::Range => (1.0, 1.0),
+            _ => return None,
+        };
+
+        let step = match self
+            .upcast::<Element>()
+            .get_attribute(&ns!(), &local_name!("step"))
+        {
+            Some(step) => DOMString::from(&**step.value()),
+            None => return Some(default_step * step_scale_factor),
+        };
+        if step.eq_ignore_ascii_case("any") {
+            return None;
+        }
+        match step.parse_floating_point_number() {
+            Some(step) if step > 0.0 => Some(step * step_scale_factor),
+            _ => Some(default_step * step_scale_factor),
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#concept-input-min-zero
+    fn step_base(&self) -> f64 {
+        if let Some(min) = self.attribute_as_number(&local_name!("min")) {
+            return min;
+        }
+        if let Some(value) = self.attribute_as_number(&local_name!("value")) {
+            return value;
+        }
+        // https://html.spec.whatwg.org/multipage/#week-state-(type=week):concept-input-step-default-base
+        if self.input_type() == InputType::Week {
+            return -259_200_000.0;
+        }
+        0.0
+    }
+
+    // https://html.spec.whatwg.org/multipage/#suffering-from-being-missing
+    fn suffers_from_being_missing(&self, value: &DOMString) -> bool {
+        match self.input_type() {
+            // https://html.spec.whatwg.org/multipage/#checkbox-state-(type=checkbox)%3Asuffering-from-being-missing
+            InputType::Checkbox => self.Required() && !self.Checked(),
+            // https://html.spec.whatwg.org/multipage/#radio-button-state-(type=radio)%3Asuffering-from-being-missing
+            InputType::Radio => {
+                let members = self.radio_group_members();
+                let required = self.Required() || members.iter().any(|r| r.Required());
+                let checked = self.Checked() || members.iter().any(|r| r.Checked());
+                required && !checked
+            },
+            // https://html.spec.whatwg.org/multipage/#file-upload-state-(type=file)%3Asuffering-from-being-missing
+            InputType::File => {
+                self.Required() &&
+                    self.filelist
+                        .get()
+                        .map_or(true, |files| files.Length() == 0)
+            },
+            // https://html.spec.whatwg.org/multipage/#the-required-attribute%3Asuffering-from-being-missing
+            _ => {
+                self.does_required_apply() &&
+                    self.Required() &&
+                    self.is_mutable() &&
+                    value.is_empty()
+            },
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#suffering-from-a-type-mismatch
+    fn suffers_from_type_mismatch(&self, value: &DOMString) -> bool {
+        if value.is_empty() {
+            return false;
+        }
+        match self.input_type() {
+            // https://html.spec.whatwg.org/multipage/#url-state-(type=url)%3Asuffering-from-a-type-mismatch
+            InputType::Url => ServoUrl::parse(value).is_err(),
+            // https://html.spec.whatwg.org/multipage/#e-mail-state-(type=email)%3Asuffering-from-a-type-mismatch
+            // https://html.spec.whatwg.org/multipage/#e-mail-state-(type=email)%3Asuffering-from-a-type-mismatch-2
+            InputType::Email if self.Multiple() => !split_commas(value).all(is_valid_email_address),
+            InputType::Email => !is_valid_email_address(value),
+            _ => false,
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#the-pattern-attribute%3Asuffering-from-a-pattern-mismatch
+    fn suffers_from_pattern_mismatch(&self, value: &DOMString) -> bool {
+        if value.is_empty() || !self.does_pattern_and_length_apply() {
+            return false;
+        }
+        let pattern = match self
+            .upcast::<Element>()
+            .get_attribute(&ns!(), &local_name!("pattern"))
+        {
+            Some(pattern) => pattern,
+            None => return false,
+        };
+
+        // The pattern is compiled as a Rust regular expression rather than a JavaScript one,
+        // so patterns relying on unsupported features are ignored like invalid patterns.
+        let regex = match Regex::new(&format!("^(?:{})$", &**pattern.value())) {
+            Ok(regex) => regex,
+            Err(_) => return false,
+        };
+
+        // https://html.spec.whatwg.org/multipage/#the-pattern-attribute%3Asuffering-from-a-pattern-mismatch-2
+        if self.input_type() == InputType::Email && self.Multiple() {
+            !split_commas(value).all(|value| regex.is_match(value))
+        } else {
+            !regex.is_match(value)
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#suffering-from-being-too-long
+    // https://html.spec.whatwg.org/multipage/#suffering-from-being-too-short
+    fn suffers_from_length_issues(&self, value: &DOMString) -> ValidationFlags {
+        let mut failed_flags = ValidationFlags::empty();
+
+        // Only values last changed by a user edit can be too long or too short.
+        if value.is_empty() ||
+            !self.value_changed_by_user.get() ||
+            !self.does_pattern_and_length_apply()
+        {
+            return failed_flags;
+        }
+
+        let UTF16CodeUnits(value_len) = self.textinput.borrow().utf16_len();
+        let max_length = self.MaxLength();
+        if max_length != DEFAULT_MAX_LENGTH && value_len > (max_length as usize) {
+            failed_flags.insert(ValidationFlags::TOO_LONG);
+        }
+        let min_length = self.MinLength();
+        if min_length != DEFAULT_MIN_LENGTH && value_len < (min_length as usize) {
+            failed_flags.insert(ValidationFlags::TOO_SHORT);
+        }
+
+        failed_flags
+    }
+
+    // https://html.spec.whatwg.org/multipage/#suffering-from-an-underflow
+    // https://html.spec.whatwg.org/multipage/#suffering-from-an-overflow
+    // https://html.spec.whatwg.org/multipage/#suffering-from-a-step-mismatch
+    fn suffers_from_range_issues(&self, value: &DOMString) -> ValidationFlags {
+        let mut failed_flags = ValidationFlags::empty();
+
+        let value_as_number = match self.convert_string_to_number(value) {
+            Some(number) => number,
+            None => return failed_flags,
+        };
+
+        if let Some(min) = self.minimum() {
+            if value_as_number < min {
+                failed_flags.insert(ValidationFlags::RANGE_UNDERFLOW);
+            }
+        }
+        if let Some(max) = self.maximum() {
+            if value_as_number > max {
+                failed_flags.insert(ValidationFlags::RANGE_OVERFLOW);
+            }
+        }
+        if let Some(step) = self.allowed_value_step() {
+            let steps = (value_as_number - self.step_base()) / step;
+            if (steps - steps.round()).abs() > 1e-9 {
+                failed_flags.insert(ValidationFlags::STEP_MISMATCH);
+            }
+        }
+
+        failed_flags
+    }
+
+    // https://html.spec.whatwg.org/multipage/#suffering-from-bad-input
+    fn suffers_from_bad_input(&self, value: &DOMString) -> bool {
+        if value.is_empty() {
+            return false;
+        }
+        // Values typed by the user are not sanitized, so these may hold text which
+        // cannot be converted to a value of the input's type.
+        match self.input_type() {
+            InputType::Number => !value.is_valid_floating_point_number_string(),
+            InputType::Date => !value.is_valid_date_string(),
+            InputType::Month => !value.is_valid_month_string(),
+            InputType::Week => !value.is_valid_week_string(),
+            InputType::Time => !value.is_valid_time_string(),
+            InputType::DatetimeLocal => value
+                .clone()
+                .convert_valid_normalized_local_date_and_time_string()
+                .is_err(),
+            _ => false,
+        }
+    }
+}
+
+// https://html.spec.whatwg.org/multipage/#valid-e-mail-address
+fn is_valid_email_address(value: &str) -> bool {
+    lazy_static! {
+        static ref EMAIL_ADDRESS: Regex = Regex::new(
+            "^[a-zA-Z0-9.!#$%&'*+/=?^_`{|}~-]+@[a-zA-Z0-9](?:[a-zA-Z0-9-]{0,61}[a-zA-Z0-9])?\
+             (?:\\.[a-zA-Z0-9](?:[a-zA-Z0-9-]{0,61}[a-zA-Z0-9])?)*$"
+        )
+        .unwrap();
+    }
+    EMAIL_ADDRESS.is_match(value)
 }
 
 impl VirtualMethods for HTMLInputElement {
@@ -1361,6 +1721,7 @@ impl VirtualMethods for HTMLInputElement {
 
                 self.sanitize_value(&mut value);
                 self.textinput.borrow_mut().set_content(value);
+                self.value_changed_by_user.set(false);
                 self.update_placeholder_shown_state();
             },
             &local_name!("name") if self.input_type() == InputType::Radio => {
@@ -1419,6 +1780,24 @@ impl VirtualMethods for HTMLInputElement {
             },
             _ => {},
         }
+
+        match attr.local_name() {
+            &local_name!("disabled") |
+            &local_name!("readonly") |
+            &local_name!("required") |
+            &local_name!("type") |
+            &local_name!("value") |
+            &local_name!("name") |
+            &local_name!("checked") |
+            &local_name!("multiple") |
+            &local_name!("pattern") |
+            &local_name!("min") |
+            &local_name!("max") |
+            &local_name!("step") |
+            &local_name!("maxlength") |
+            &local_name!("minlength") => self.update_validity(),
+            _ => {},
+        }
     }
 
     fn parse_plain_attribute(&self, name: &LocalName, value: DOMString) -> AttrValue {
@@ -1446,6 +1825,7 @@ impl VirtualMethods for HTMLInputElement {
         }
         self.upcast::<Element>()
             .check_ancestors_disabled_state_for_form_control();
+        self.update_validity();
     }
 
     fn unbind_from_tree(&self, context: &UnbindContext) {
@@ -1515,7 +1895,10 @@ impl VirtualMethods for HTMLInputElement {
                     },
                     DispatchInput => {
                         self.value_dirty.set(true);
+                        self.value_changed_by_user.set(true);
                         self.update_placeholder_shown_state();
+                        self.validity_state()
+                            .perform_validation_and_update(ValidationFlags::all());
                         self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
                         event.mark_as_handled();
                     },
@@ -1556,6 +1939,9 @@ impl VirtualMethods for HTMLInputElement {
                         .textinput
                         .borrow_mut()
                         .handle_compositionend(compositionevent);
+                    self.value_changed_by_user.set(true);
+                    self.validity_state()
+                        .perform_validation_and_update(ValidationFlags::all());
                     self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
                 }
                 event.mark_as_handled();
@@ -1579,13 +1965,68 @@ impl FormControl for HTMLInputElement {
 }
 
 impl Validatable for HTMLInputElement {
+    fn as_element(&self) -> &Element {
+        self.upcast()
+    }
+
+    fn validity_state(&self) -> DomRoot<ValidityState> {
+        self.validity_state
+            .or_init(|| ValidityState::new(&window_from_node(self), self.upcast()))
+    }
+
     fn is_instance_validatable(&self) -> bool {
-        // https://html.spec.whatwg.org/multipage/#candidate-for-constraint-validation
-        true
+        match self.input_type() {
+            // https://html.spec.whatwg.org/multipage/#hidden-state-(type%3Dhidden)%3Abarred-from-constraint-validation
+            // https://html.spec.whatwg.org/multipage/#button-state-(type%3Dbutton)%3Abarred-from-constraint-validation
+            // https://html.spec.whatwg.org/multipage/#reset-button-state-(type%3Dreset)%3Abarred-from-constraint-validation
+            InputType::Hidden | InputType::Button | InputType::Reset => false,
+            // https://html.spec.whatwg.org/multipage/#enabling-and-disabling-form-controls%3A-the-disabled-attribute%3Abarred-from-constraint-validation
+            // https://html.spec.whatwg.org/multipage/#the-readonly-attribute%3Abarred-from-constraint-validation
+            // https://html.spec.whatwg.org/multipage/#the-datalist-element%3Abarred-from-constraint-validation
+            _ => {
+                !(self.upcast::<Element>().disabled_state() ||
+                    (self.ReadOnly() && self.does_readonly_apply()) ||
+                    is_barred_by_datalist_ancestor(self.upcast()))
+            },
+        }
     }
-    fn validate(&self, _validate_flags: ValidationFlags) -> bool {
-        // call stub methods defined in validityState.rs file here according to the flags set in validate_flags
-        true
+
+    fn perform_validation(&self, validate_flags: ValidationFlags) -> ValidationFlags {
+        let mut failed_flags = ValidationFlags::empty();
+        let value = self.Value();
+
+        if validate_flags.contains(ValidationFlags::VALUE_MISSING) &&
+            self.suffers_from_being_missing(&value)
+        {
+            failed_flags.insert(ValidationFlags::VALUE_MISSING);
+        }
+        if validate_flags.contains(ValidationFlags::TYPE_MISMATCH) &&
+            self.suffers_from_type_mismatch(&value)
+        {
+            failed_flags.insert(ValidationFlags::TYPE_MISMATCH);
+        }
+        if validate_flags.contains(ValidationFlags::PATTERN_MISMATCH) &&
+            self.suffers_from_pattern_mismatch(&value)
+        {
+            failed_flags.insert(ValidationFlags::PATTERN_MISMATCH);
+        }
+        if validate_flags.contains(ValidationFlags::BAD_INPUT) &&
+            self.suffers_from_bad_input(&value)
+        {
+            failed_flags.insert(ValidationFlags::BAD_INPUT);
+        }
+        if validate_flags.intersects(ValidationFlags::TOO_LONG | ValidationFlags::TOO_SHORT) {
+            failed_flags.insert(self.suffers_from_length_issues(&value));
+        }
+        if validate_flags.intersects(
+            ValidationFlags::RANGE_UNDERFLOW |
+                ValidationFlags::RANGE_OVERFLOW |
+                ValidationFlags::STEP_MISMATCH,
+        ) {
+            failed_flags.insert(self.suffers_from_range_issues(&value));
+        }
+
+        failed_flags & validate_flags
     }
 }
 
@@ -1771,7 +2212,7 @@ impl Activatable for HTMLInputElement {
             Some(ref button) => {
                 if button.is_instance_activatable() {
                     synthetic_click_activation(
-                        button.as_element(),
+                        button.upcast::<Element>(),
                         ctrl_key,
                         shift_key,
                         alt_key,
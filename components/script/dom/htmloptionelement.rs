@@ -21,6 +21,8 @@ use crate::dom::htmlscriptelement::HTMLScriptElement;
 use crate::dom::htmlselectelement::HTMLSelectElement;
 use crate::dom::node::{BindContext, Node, ShadowIncluding, UnbindContext};
 use crate::dom::text::Text;
+use crate::dom::validation::Validatable;
+use crate::dom::validitystate::ValidationFlags;
 use crate::dom::virtualmethods::VirtualMethods;
 use dom_struct::dom_struct;
 use html5ever::{LocalName, Prefix};
@@ -74,6 +76,7 @@ impl HTMLOptionElement {
 
     pub fn set_selectedness(&self, selected: bool) {
         self.selectedness.set(selected);
+        self.update_select_validity();
     }
 
     pub fn set_dirtiness(&self, dirtiness: bool) {
@@ -93,6 +96,20 @@ impl HTMLOptionElement {
             select.ask_for_reset();
         }
     }
+
+    /// A required select's validity depends on the selectedness of its options.
+    fn update_select_validity(&self) {
+        if let Some(select) = self
+            .upcast::<Node>()
+            .ancestors()
+            .filter_map(DomRoot::downcast::<HTMLSelectElement>)
+            .next()
+        {
+            select
+                .validity_state()
+                .perform_validation_and_update(ValidationFlags::VALUE_MISSING);
+        }
+    }
 }
 
 // FIXME(ajeffrey): Provide a way of buffering DOMStrings other than using Strings
@@ -188,7 +205,7 @@ impl HTMLOptionElementMethods for HTMLOptionElement {
     // https://html.spec.whatwg.org/multipage/#dom-option-selected
     fn SetSelected(&self, selected: bool) {
         self.dirtiness.set(true);
-        self.selectedness.set(selected);
+        self.set_selectedness(selected);
         self.pick_if_selected_and_reset();
     }
 }
@@ -220,13 +237,13 @@ impl VirtualMethods for HTMLOptionElement {
                     AttributeMutation::Set(_) => {
                         // https://html.spec.whatwg.org/multipage/#concept-option-selectedness
                         if !self.dirtiness.get() {
-                            self.selectedness.set(true);
+                            self.set_selectedness(true);
                         }
                     },
                     AttributeMutation::Removed => {
                         // https://html.spec.whatwg.org/multipage/#concept-option-selectedness
                         if !self.dirtiness.get() {
-                            self.selectedness.set(false);
+                            self.set_selectedness(false);
                         }
                     },
                 }
@@ -256,6 +273,9 @@ impl VirtualMethods for HTMLOptionElement {
             .next()
         {
             select.ask_for_reset();
+            select
+                .validity_state()
+                .perform_validation_and_update(ValidationFlags::VALUE_MISSING);
         }
 
         let node = self.upcast::<Node>();
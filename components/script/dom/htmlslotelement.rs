@@ -0,0 +1,319 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::attr::Attr;
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::HTMLSlotElementBinding;
+use crate::dom::bindings::codegen::Bindings::HTMLSlotElementBinding::{
+    AssignedNodesOptions, HTMLSlotElementMethods,
+};
+use crate::dom::bindings::codegen::Bindings::ShadowRootBinding::ShadowRootBinding::ShadowRootMethods;
+use crate::dom::bindings::codegen::Bindings::ShadowRootBinding::ShadowRootMode;
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::root::{Dom, DomRoot, LayoutDom};
+use crate::dom::bindings::str::DOMString;
+use crate::dom::document::Document;
+use crate::dom::element::{AttributeMutation, Element};
+use crate::dom::htmlelement::HTMLElement;
+use crate::dom::mutationobserver::MutationObserver;
+use crate::dom::node::{Node, NodeDamage, ShadowIncluding};
+use crate::dom::shadowroot::ShadowRoot;
+use crate::dom::virtualmethods::VirtualMethods;
+use crate::script_thread::ScriptThread;
+use dom_struct::dom_struct;
+use html5ever::{LocalName, Prefix};
+use std::mem;
+
+#[dom_struct]
+pub struct HTMLSlotElement {
+    htmlelement: HTMLElement,
+    /// <https://dom.spec.whatwg.org/#slot-assigned-nodes>
+    assigned_nodes: DomRefCell<Vec<Dom<Node>>>,
+}
+
+impl HTMLSlotElement {
+    fn new_inherited(
+        local_name: LocalName,
+        prefix: Option<Prefix>,
+        document: &Document,
+    ) -> HTMLSlotElement {
+        HTMLSlotElement {
+            htmlelement: HTMLElement::new_inherited(local_name, prefix, document),
+            assigned_nodes: DomRefCell::new(vec![]),
+        }
+    }
+
+    #[allow(unrooted_must_root)]
+    pub fn new(
+        local_name: LocalName,
+        prefix: Option<Prefix>,
+        document: &Document,
+    ) -> DomRoot<HTMLSlotElement> {
+        Node::reflect_node(
+            Box::new(HTMLSlotElement::new_inherited(local_name, prefix, document)),
+            document,
+            HTMLSlotElementBinding::Wrap,
+        )
+    }
+
+    /// <https://dom.spec.whatwg.org/#slot-name>
+    fn name(&self) -> DOMString {
+        self.upcast::<Element>()
+            .get_string_attribute(&local_name!("name"))
+    }
+
+    pub fn has_assigned_nodes(&self) -> bool {
+        !self.assigned_nodes.borrow().is_empty()
+    }
+
+    /// The shadow root this slot is in, if that is the root of its tree.
+    fn root_shadow_root(&self) -> Option<DomRoot<ShadowRoot>> {
+        self.upcast::<Node>()
+            .inclusive_ancestors(ShadowIncluding::No)
+            .last()
+            .and_then(DomRoot::downcast::<ShadowRoot>)
+    }
+
+    /// <https://dom.spec.whatwg.org/#find-slotables>
+    fn find_slottables(&self) -> Vec<DomRoot<Node>> {
+        // Steps 1-2.
+        let shadow_root = match self.root_shadow_root() {
+            Some(shadow_root) => shadow_root,
+            None => return vec![],
+        };
+
+        // Steps 3-4.
+        let host = shadow_root.Host();
+        host.upcast::<Node>()
+            .children()
+            .filter(|child| {
+                child.is_slottable() &&
+                    HTMLSlotElement::find_a_slot(child, false)
+                        .map_or(false, |slot| &*slot == self)
+            })
+            .collect()
+    }
+
+    /// <https://dom.spec.whatwg.org/#find-flattened-slotables>
+    fn find_flattened_slottables(&self) -> Vec<DomRoot<Node>> {
+        // Step 1.
+        let mut result = vec![];
+
+        // Step 2.
+        if self.root_shadow_root().is_none() {
+            return result;
+        }
+
+        // Step 3.
+        let mut slottables = self.find_slottables();
+
+        // Step 4.
+        if slottables.is_empty() {
+            slottables = self
+                .upcast::<Node>()
+                .children()
+                .filter(|child| child.is_slottable())
+                .collect();
+        }
+
+        // Step 5.
+        for node in slottables {
+            if let Some(slot) = node.downcast::<HTMLSlotElement>() {
+                // Step 5.1.
+                if slot.root_shadow_root().is_some() {
+                    result.extend(slot.find_flattened_slottables());
+                    continue;
+                }
+            }
+            // Step 5.2.
+            result.push(node);
+        }
+
+        // Step 6.
+        result
+    }
+
+    /// <https://dom.spec.whatwg.org/#find-a-slot>
+    pub fn find_a_slot(slottable: &Node, open: bool) -> Option<DomRoot<HTMLSlotElement>> {
+        // Step 1.
+        let parent = slottable.GetParentNode()?;
+
+        // Steps 2-3.
+        let shadow = parent.downcast::<Element>()?.shadow_root()?;
+
+        // Step 4.
+        if open && shadow.Mode() != ShadowRootMode::Open {
+            return None;
+        }
+
+        // Step 5.
+        let name = slottable
+            .downcast::<Element>()
+            .map_or(DOMString::new(), |element| {
+                element.get_string_attribute(&local_name!("slot"))
+            });
+        shadow
+            .upcast::<Node>()
+            .traverse_preorder(ShadowIncluding::No)
+            .filter_map(DomRoot::downcast::<HTMLSlotElement>)
+            .find(|slot| slot.name() == name)
+    }
+
+    /// <https://dom.spec.whatwg.org/#assign-slotables>
+    pub fn assign_slottables(&self) {
+        // Step 1.
+        let slottables = self.find_slottables();
+
+        // Step 2.
+        let unchanged = {
+            let assigned_nodes = self.assigned_nodes.borrow();
+            assigned_nodes.len() == slottables.len() &&
+                assigned_nodes
+                    .iter()
+                    .zip(slottables.iter())
+                    .all(|(assigned, slottable)| &**assigned == &**slottable)
+        };
+        if unchanged {
+            return;
+        }
+        self.signal_a_slot_change();
+
+        // Step 3.
+        let old_assigned_nodes: Vec<DomRoot<Node>> = self
+            .assigned_nodes
+            .borrow()
+            .iter()
+            .map(|node| DomRoot::from_ref(&**node))
+            .collect();
+        *self.assigned_nodes.borrow_mut() = slottables
+            .iter()
+            .map(|node| Dom::from_ref(&**node))
+            .collect();
+        for node in old_assigned_nodes {
+            if node.assigned_slot().map_or(false, |slot| &*slot == self) {
+                node.set_assigned_slot(None);
+            }
+            node.dirty(NodeDamage::OtherNodeDamage);
+        }
+
+        // Step 4.
+        for slottable in &slottables {
+            slottable.set_assigned_slot(Some(self));
+            slottable.dirty(NodeDamage::OtherNodeDamage);
+        }
+
+        // The slot now renders different children in the flat tree.
+        self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+    }
+
+    /// <https://dom.spec.whatwg.org/#assign-slotables-for-a-tree>
+    ///
+    /// Runs for the tree `node` belongs to, whichever node is its root.
+    pub fn assign_slottables_for_a_tree(node: &Node) {
+        let root = node
+            .inclusive_ancestors(ShadowIncluding::No)
+            .last()
+            .unwrap();
+        for slot in root
+            .traverse_preorder(ShadowIncluding::No)
+            .filter_map(DomRoot::downcast::<HTMLSlotElement>)
+        {
+            slot.assign_slottables();
+        }
+    }
+
+    /// <https://dom.spec.whatwg.org/#assign-a-slot>
+    pub fn assign_a_slot(slottable: &Node) {
+        // Step 1.
+        if let Some(slot) = HTMLSlotElement::find_a_slot(slottable, false) {
+            // Step 2.
+            slot.assign_slottables();
+        }
+    }
+
+    /// <https://dom.spec.whatwg.org/#signal-a-slot-change>
+    pub fn signal_a_slot_change(&self) {
+        // Step 1.
+        ScriptThread::signal_slot(self);
+
+        // Step 2.
+        MutationObserver::queue_mutation_observer_microtask();
+    }
+}
+
+/// Whether an attribute mutation changes the name it represents, a missing attribute
+/// being the same as an empty one.
+/// <https://dom.spec.whatwg.org/#shadow-tree-slots>
+pub fn slot_name_changed(attr: &Attr, mutation: AttributeMutation) -> bool {
+    let old_name = match mutation {
+        AttributeMutation::Set(old_value) => old_value.map_or("", |value| &**value).to_owned(),
+        AttributeMutation::Removed => String::from(&**attr.value()),
+    };
+    let new_name = mutation
+        .new_value(attr)
+        .map_or(String::new(), |value| String::from(&**value));
+    old_name != new_name
+}
+
+impl HTMLSlotElementMethods for HTMLSlotElement {
+    // https://html.spec.whatwg.org/multipage/#dom-slot-name
+    make_getter!(Name, "name");
+
+    // https://html.spec.whatwg.org/multipage/#dom-slot-name
+    make_setter!(SetName, "name");
+
+    // https://html.spec.whatwg.org/multipage/#dom-slot-assignednodes
+    fn AssignedNodes(&self, options: &AssignedNodesOptions) -> Vec<DomRoot<Node>> {
+        if options.flatten {
+            return self.find_flattened_slottables();
+        }
+        self.assigned_nodes
+            .borrow()
+            .iter()
+            .map(|node| DomRoot::from_ref(&**node))
+            .collect()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-slot-assignedelements
+    fn AssignedElements(&self, options: &AssignedNodesOptions) -> Vec<DomRoot<Element>> {
+        self.AssignedNodes(options)
+            .into_iter()
+            .filter_map(DomRoot::downcast::<Element>)
+            .collect()
+    }
+}
+
+impl VirtualMethods for HTMLSlotElement {
+    fn super_type(&self) -> Option<&dyn VirtualMethods> {
+        Some(self.upcast::<HTMLElement>() as &dyn VirtualMethods)
+    }
+
+    fn attribute_mutated(&self, attr: &Attr, mutation: AttributeMutation) {
+        self.super_type().unwrap().attribute_mutated(attr, mutation);
+
+        // https://dom.spec.whatwg.org/#shadow-tree-slots
+        if attr.local_name() == &local_name!("name") &&
+            attr.namespace() == &ns!() &&
+            slot_name_changed(attr, mutation)
+        {
+            HTMLSlotElement::assign_slottables_for_a_tree(self.upcast());
+        }
+    }
+}
+
+pub trait LayoutHTMLSlotElementHelpers {
+    #[allow(unsafe_code)]
+    unsafe fn assigned_nodes_for_layout(&self) -> &[LayoutDom<Node>];
+}
+
+impl LayoutHTMLSlotElementHelpers for LayoutDom<HTMLSlotElement> {
+    #[inline]
+    #[allow(unsafe_code)]
+    unsafe fn assigned_nodes_for_layout(&self) -> &[LayoutDom<Node>] {
+        let assigned_nodes = (*self.unsafe_get()).assigned_nodes.borrow_for_layout();
+        // `Dom<Node>` and `LayoutDom<Node>` are both a single pointer to the node,
+        // so the list can be handed to layout without copying it.
+        mem::transmute::<&[Dom<Node>], &[LayoutDom<Node>]>(&**assigned_nodes)
+    }
+}
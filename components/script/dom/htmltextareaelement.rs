@@ -29,7 +29,8 @@ use crate::dom::node::{
 };
 use crate::dom::nodelist::NodeList;
 use crate::dom::textcontrol::{TextControlElement, TextControlSelection};
-use crate::dom::validation::Validatable;
+use crate::dom::validation::{is_barred_by_datalist_ancestor, Validatable};
+use crate::dom::validitystate::{ValidationFlags, ValidityState};
 use crate::dom::virtualmethods::VirtualMethods;
 use crate::textinput::{
     Direction, KeyReaction, Lines, SelectionDirection, TextInput, UTF16CodeUnits, UTF8Bytes,
@@ -51,7 +52,10 @@ pub struct HTMLTextAreaElement {
     placeholder: DomRefCell<DOMString>,
     // https://html.spec.whatwg.org/multipage/#concept-textarea-dirty
     value_dirty: Cell<bool>,
+    // Whether the value was last changed by a user edit, as opposed to by script.
+    value_changed_by_user: Cell<bool>,
     form_owner: MutNullableDom<HTMLFormElement>,
+    validity_state: MutNullableDom<ValidityState>,
 }
 
 pub trait LayoutHTMLTextAreaElementHelpers {
@@ -152,7 +156,9 @@ impl HTMLTextAreaElement {
                 SelectionDirection::None,
             )),
             value_dirty: Cell::new(false),
+            value_changed_by_user: Cell::new(false),
             form_owner: Default::default(),
+            validity_state: Default::default(),
         }
     }
 
@@ -300,12 +306,16 @@ impl HTMLTextAreaElementMethods for HTMLTextAreaElement {
 
         // Step 3
         self.value_dirty.set(true);
+        self.value_changed_by_user.set(false);
 
         if old_value != textinput.get_content() {
             // Step 4
             textinput.clear_selection_to_limit(Direction::Forward);
         }
+        drop(textinput);
 
+        self.validity_state()
+            .perform_validation_and_update(ValidationFlags::all());
         self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
     }
 
@@ -320,6 +330,36 @@ impl HTMLTextAreaElementMethods for HTMLTextAreaElement {
         self.upcast::<HTMLElement>().labels()
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-cva-willvalidate
+    fn WillValidate(&self) -> bool {
+        self.is_instance_validatable()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-cva-validity
+    fn Validity(&self) -> DomRoot<ValidityState> {
+        self.validity_state()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-cva-checkvalidity
+    fn CheckValidity(&self) -> bool {
+        self.check_validity()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-cva-reportvalidity
+    fn ReportValidity(&self) -> bool {
+        self.report_validity()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-cva-validationmessage
+    fn ValidationMessage(&self) -> DOMString {
+        self.validation_message()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-cva-setcustomvalidity
+    fn SetCustomValidity(&self, error: DOMString) {
+        self.validity_state().set_custom_error_message(error);
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-textarea/input-select
     fn Select(&self) {
         self.selection().dom_select();
@@ -382,9 +422,11 @@ impl HTMLTextAreaElementMethods for HTMLTextAreaElement {
 impl HTMLTextAreaElement {
     pub fn reset(&self) {
         // https://html.spec.whatwg.org/multipage/#the-textarea-element:concept-form-reset-control
-        let mut textinput = self.textinput.borrow_mut();
-        textinput.set_content(self.DefaultValue());
+        self.textinput.borrow_mut().set_content(self.DefaultValue());
         self.value_dirty.set(false);
+        self.value_changed_by_user.set(false);
+        self.validity_state()
+            .perform_validation_and_update(ValidationFlags::all());
     }
 
     #[allow(unrooted_must_root)]
@@ -420,6 +462,8 @@ impl VirtualMethods for HTMLTextAreaElement {
                         }
                     },
                 }
+                self.validity_state()
+                    .perform_validation_and_update(ValidationFlags::all());
             },
             local_name!("maxlength") => match *attr.value() {
                 AttrValue::Int(_, value) => {
@@ -430,6 +474,9 @@ impl VirtualMethods for HTMLTextAreaElement {
                     } else {
                         textinput.set_max_length(Some(UTF16CodeUnits(value as usize)))
                     }
+                    drop(textinput);
+                    self.validity_state()
+                        .perform_validation_and_update(ValidationFlags::TOO_LONG);
                 },
                 _ => panic!("Expected an AttrValue::Int"),
             },
@@ -442,6 +489,9 @@ impl VirtualMethods for HTMLTextAreaElement {
                     } else {
                         textinput.set_min_length(Some(UTF16CodeUnits(value as usize)))
                     }
+                    drop(textinput);
+                    self.validity_state()
+                        .perform_validation_and_update(ValidationFlags::TOO_SHORT);
                 },
                 _ => panic!("Expected an AttrValue::Int"),
             },
@@ -465,6 +515,12 @@ impl VirtualMethods for HTMLTextAreaElement {
                         el.set_read_write_state(!el.disabled_state());
                     },
                 }
+                self.validity_state()
+                    .perform_validation_and_update(ValidationFlags::all());
+            },
+            local_name!("required") => {
+                self.validity_state()
+                    .perform_validation_and_update(ValidationFlags::VALUE_MISSING);
             },
             local_name!("form") => {
                 self.form_attribute_mutated(mutation);
@@ -480,6 +536,8 @@ impl VirtualMethods for HTMLTextAreaElement {
 
         self.upcast::<Element>()
             .check_ancestors_disabled_state_for_form_control();
+        self.validity_state()
+            .perform_validation_and_update(ValidationFlags::all());
     }
 
     fn parse_plain_attribute(&self, name: &LocalName, value: DOMString) -> AttrValue {
@@ -559,7 +617,10 @@ impl VirtualMethods for HTMLTextAreaElement {
                     KeyReaction::TriggerDefaultAction => (),
                     KeyReaction::DispatchInput => {
                         self.value_dirty.set(true);
+                        self.value_changed_by_user.set(true);
                         self.update_placeholder_shown_state();
+                        self.validity_state()
+                            .perform_validation_and_update(ValidationFlags::all());
                         self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
                         event.mark_as_handled();
                     },
@@ -596,6 +657,9 @@ impl VirtualMethods for HTMLTextAreaElement {
                         .textinput
                         .borrow_mut()
                         .handle_compositionend(compositionevent);
+                    self.value_changed_by_user.set(true);
+                    self.validity_state()
+                        .perform_validation_and_update(ValidationFlags::all());
                     self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
                 }
                 event.mark_as_handled();
@@ -625,4 +689,63 @@ impl FormControl for HTMLTextAreaElement {
     }
 }
 
-impl Validatable for HTMLTextAreaElement {}
+impl Validatable for HTMLTextAreaElement {
+    fn as_element(&self) -> &Element {
+        self.upcast()
+    }
+
+    fn validity_state(&self) -> DomRoot<ValidityState> {
+        self.validity_state
+            .or_init(|| ValidityState::new(&window_from_node(self), self.upcast()))
+    }
+
+    fn is_instance_validatable(&self) -> bool {
+        // https://html.spec.whatwg.org/multipage/#enabling-and-disabling-form-controls%3A-the-disabled-attribute%3Abarred-from-constraint-validation
+        // https://html.spec.whatwg.org/multipage/#the-textarea-element%3Abarred-from-constraint-validation
+        // https://html.spec.whatwg.org/multipage/#the-datalist-element%3Abarred-from-constraint-validation
+        !self.upcast::<Element>().disabled_state() &&
+            !self.ReadOnly() &&
+            !is_barred_by_datalist_ancestor(self.upcast())
+    }
+
+    fn perform_validation(&self, validate_flags: ValidationFlags) -> ValidationFlags {
+        let mut failed_flags = ValidationFlags::empty();
+
+        let textinput = self.textinput.borrow();
+        let UTF16CodeUnits(value_len) = textinput.utf16_len();
+        let is_mutable = !self.upcast::<Element>().disabled_state() && !self.ReadOnly();
+
+        // https://html.spec.whatwg.org/multipage/#suffering-from-being-missing
+        // https://html.spec.whatwg.org/multipage/#the-textarea-element%3Asuffering-from-being-missing
+        if validate_flags.contains(ValidationFlags::VALUE_MISSING) &&
+            self.Required() &&
+            is_mutable &&
+            value_len == 0
+        {
+            failed_flags.insert(ValidationFlags::VALUE_MISSING);
+        }
+
+        // Only values last changed by a user edit can be too long or too short.
+        if self.value_changed_by_user.get() && value_len > 0 {
+            // https://html.spec.whatwg.org/multipage/#suffering-from-being-too-long
+            // https://html.spec.whatwg.org/multipage/#limiting-user-input-length%3A-the-maxlength-attribute%3Asuffering-from-being-too-long
+            if validate_flags.contains(ValidationFlags::TOO_LONG) {
+                let max_length = self.MaxLength();
+                if max_length != DEFAULT_MAX_LENGTH && value_len > (max_length as usize) {
+                    failed_flags.insert(ValidationFlags::TOO_LONG);
+                }
+            }
+
+            // https://html.spec.whatwg.org/multipage/#suffering-from-being-too-short
+            // https://html.spec.whatwg.org/multipage/#setting-minimum-input-length-requirements%3A-the-minlength-attribute%3Asuffering-from-being-too-short
+            if validate_flags.contains(ValidationFlags::TOO_SHORT) {
+                let min_length = self.MinLength();
+                if min_length != DEFAULT_MIN_LENGTH && value_len < (min_length as usize) {
+                    failed_flags.insert(ValidationFlags::TOO_SHORT);
+                }
+            }
+        }
+
+        failed_flags
+    }
+}
@@ -0,0 +1,369 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::IDBCursorBinding;
+use crate::dom::bindings::codegen::Bindings::IDBCursorBinding::IDBCursorDirection;
+use crate::dom::bindings::codegen::Bindings::IDBCursorBinding::IDBCursorMethods;
+use crate::dom::bindings::codegen::Bindings::IDBRequestBinding::IDBRequestMethods;
+use crate::dom::bindings::codegen::Bindings::IDBTransactionBinding::IDBTransactionMode;
+use crate::dom::bindings::codegen::UnionTypes::IDBObjectStoreOrIDBIndex;
+use crate::dom::bindings::codegen::UnionTypes::IDBObjectStoreOrIDBIndexOrIDBCursor;
+use crate::dom::bindings::error::{Error, ErrorResult, Fallible};
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot};
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::idbcursorwithvalue::IDBCursorWithValue;
+use crate::dom::idbobjectstore::IDBObjectStore;
+use crate::dom::idbrequest::{IDBRequest, IDBRequestSource};
+use crate::dom::idbtransaction::IDBTransaction;
+use crate::indexed_db::{convert_value_to_key, extract_key, key_type_to_jsval, ExtractionResult};
+use crate::script_runtime::JSContext;
+use dom_struct::dom_struct;
+use js::jsval::{JSVal, NullValue, ObjectValue, UndefinedValue};
+use js::rust::{HandleValue, MutableHandleValue};
+use net_traits::indexeddb_thread::{AsyncOperation, IndexedDBCursorDirection};
+use net_traits::indexeddb_thread::{IndexedDBKeyRange, IndexedDBKeyType, IndexedDBRecord};
+use std::cell::Cell;
+
+pub fn to_cursor_direction(direction: IDBCursorDirection) -> IndexedDBCursorDirection {
+    match direction {
+        IDBCursorDirection::Next => IndexedDBCursorDirection::Next,
+        IDBCursorDirection::Nextunique => IndexedDBCursorDirection::NextUnique,
+        IDBCursorDirection::Prev => IndexedDBCursorDirection::Prev,
+        IDBCursorDirection::Prevunique => IndexedDBCursorDirection::PrevUnique,
+    }
+}
+
+/// <https://w3c.github.io/IndexedDB/#cursor>
+#[dom_struct]
+pub struct IDBCursor {
+    reflector_: Reflector,
+    request: Dom<IDBRequest>,
+    /// The name of the index the cursor iterates, if its source is an index.
+    index: Option<String>,
+    range: IndexedDBKeyRange,
+    direction: IDBCursorDirection,
+    /// <https://w3c.github.io/IndexedDB/#cursor-key>
+    key: DomRefCell<Option<IndexedDBKeyType>>,
+    /// <https://w3c.github.io/IndexedDB/#cursor-object-store-position>
+    primary_key: DomRefCell<Option<IndexedDBKeyType>>,
+    /// <https://w3c.github.io/IndexedDB/#cursor-got-value-flag>
+    got_value: Cell<bool>,
+}
+
+impl IDBCursor {
+    pub fn new_inherited(
+        request: &IDBRequest,
+        index: Option<String>,
+        range: IndexedDBKeyRange,
+        direction: IDBCursorDirection,
+    ) -> IDBCursor {
+        IDBCursor {
+            reflector_: Reflector::new(),
+            request: Dom::from_ref(request),
+            index: index,
+            range: range,
+            direction: direction,
+            key: DomRefCell::new(None),
+            primary_key: DomRefCell::new(None),
+            got_value: Cell::new(false),
+        }
+    }
+
+    pub fn new(
+        global: &GlobalScope,
+        request: &IDBRequest,
+        index: Option<String>,
+        range: IndexedDBKeyRange,
+        direction: IDBCursorDirection,
+    ) -> DomRoot<IDBCursor> {
+        reflect_dom_object(
+            Box::new(IDBCursor::new_inherited(request, index, range, direction)),
+            global,
+            IDBCursorBinding::Wrap,
+        )
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#cursor-effective-object-store>
+    pub fn object_store(&self) -> DomRoot<IDBObjectStore> {
+        self.request
+            .object_store()
+            .expect("A cursor is always opened on an object store or an index")
+    }
+
+    fn transaction(&self) -> DomRoot<IDBTransaction> {
+        self.object_store().transaction()
+    }
+
+    fn is_forward(&self) -> bool {
+        match self.direction {
+            IDBCursorDirection::Next | IDBCursorDirection::Nextunique => true,
+            IDBCursorDirection::Prev | IDBCursorDirection::Prevunique => false,
+        }
+    }
+
+    /// Updates the position of the cursor from the record found by the indexeddb thread,
+    /// and sets `rval` to the result of the iteration request.
+    pub fn set_record(&self, record: Option<IndexedDBRecord>, mut rval: MutableHandleValue) {
+        let record = match record {
+            Some(record) => record,
+            None => {
+                *self.key.borrow_mut() = None;
+                *self.primary_key.borrow_mut() = None;
+                if let Some(cursor) = self.downcast::<IDBCursorWithValue>() {
+                    cursor.set_value(HandleValue::undefined());
+                }
+                return rval.set(NullValue());
+            },
+        };
+
+        if let Some(cursor) = self.downcast::<IDBCursorWithValue>() {
+            let cx = self.global().get_cx();
+            rooted!(in(*cx) let mut value = UndefinedValue());
+            self.object_store()
+                .read_value(record.value, &record.primary_key, value.handle_mut());
+            cursor.set_value(value.handle());
+        }
+        *self.key.borrow_mut() = Some(record.key);
+        *self.primary_key.borrow_mut() = Some(record.primary_key);
+        self.got_value.set(true);
+        rval.set(ObjectValue(self.reflector().get_jsobject().get()));
+    }
+
+    /// Checks that the source of the cursor has not been deleted.
+    fn check_source(&self) -> ErrorResult {
+        let store = self.object_store();
+        if store.is_deleted() {
+            return Err(Error::InvalidState);
+        }
+        if let Some(ref index) = self.index {
+            if store.index_info(index).is_none() {
+                return Err(Error::InvalidState);
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the state shared by the methods moving the cursor.
+    fn check_iterable(&self) -> ErrorResult {
+        self.transaction().check_active()?;
+        self.check_source()?;
+        if !self.got_value.get() {
+            return Err(Error::InvalidState);
+        }
+        Ok(())
+    }
+
+    /// Checks the state shared by the methods modifying the current record.
+    fn check_modifiable(&self) -> ErrorResult {
+        let transaction = self.transaction();
+        transaction.check_active()?;
+        if transaction.mode() == IDBTransactionMode::Readonly {
+            return Err(Error::ReadOnly);
+        }
+        self.check_source()?;
+        if !self.got_value.get() || !self.is::<IDBCursorWithValue>() {
+            return Err(Error::InvalidState);
+        }
+        Ok(())
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#iterate-a-cursor>
+    fn iterate(&self, target: Option<(IndexedDBKeyType, Option<IndexedDBKeyType>)>, count: u32) {
+        self.got_value.set(false);
+        self.request.reset();
+        let position = match (&*self.key.borrow(), &*self.primary_key.borrow()) {
+            (&Some(ref key), &Some(ref primary_key)) => Some((key.clone(), primary_key.clone())),
+            _ => None,
+        };
+        let store = self.object_store();
+        let operation = AsyncOperation::Iterate {
+            index: self.index.clone(),
+            range: self.range.clone(),
+            direction: to_cursor_direction(self.direction),
+            position,
+            target,
+            count,
+        };
+        self.transaction()
+            .execute(&self.request, Some(self), &store.name(), operation);
+    }
+
+    fn current_key(&self) -> Option<IndexedDBKeyType> {
+        self.key.borrow().clone()
+    }
+
+    fn current_primary_key(&self) -> Option<IndexedDBKeyType> {
+        self.primary_key.borrow().clone()
+    }
+}
+
+impl IDBCursorMethods for IDBCursor {
+    // https://w3c.github.io/IndexedDB/#dom-idbcursor-source
+    fn Source(&self) -> IDBObjectStoreOrIDBIndex {
+        match self.request.GetSource() {
+            Some(IDBObjectStoreOrIDBIndexOrIDBCursor::IDBIndex(index)) => {
+                IDBObjectStoreOrIDBIndex::IDBIndex(index)
+            },
+            _ => IDBObjectStoreOrIDBIndex::IDBObjectStore(self.object_store()),
+        }
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbcursor-direction
+    fn Direction(&self) -> IDBCursorDirection {
+        self.direction
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbcursor-key
+    fn Key(&self, cx: JSContext) -> JSVal {
+        rooted!(in(*cx) let mut key = UndefinedValue());
+        if let Some(ref current) = *self.key.borrow() {
+            key_type_to_jsval(cx, current, key.handle_mut());
+        }
+        key.get()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbcursor-primarykey
+    fn PrimaryKey(&self, cx: JSContext) -> JSVal {
+        rooted!(in(*cx) let mut key = UndefinedValue());
+        if let Some(ref current) = *self.primary_key.borrow() {
+            key_type_to_jsval(cx, current, key.handle_mut());
+        }
+        key.get()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbcursor-request
+    fn Request(&self) -> DomRoot<IDBRequest> {
+        DomRoot::from_ref(&*self.request)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbcursor-advance
+    fn Advance(&self, count: u32) -> ErrorResult {
+        // Step 1.
+        if count == 0 {
+            return Err(Error::Type("The count must not be zero".to_owned()));
+        }
+
+        // Steps 2-4.
+        self.check_iterable()?;
+
+        // Steps 5-7.
+        self.iterate(None, count);
+        Ok(())
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbcursor-continue
+    fn Continue(&self, cx: JSContext, key: HandleValue) -> ErrorResult {
+        // Steps 1-3.
+        self.check_iterable()?;
+
+        // Step 4.
+        let target = if key.is_undefined() {
+            None
+        } else {
+            let key = convert_value_to_key(cx, key, None)?;
+            if let Some(current) = self.current_key() {
+                if (self.is_forward() && key <= current) || (!self.is_forward() && key >= current) {
+                    return Err(Error::Data);
+                }
+            }
+            Some((key, None))
+        };
+
+        // Steps 5-7.
+        self.iterate(target, 1);
+        Ok(())
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbcursor-continueprimarykey
+    fn ContinuePrimaryKey(
+        &self,
+        cx: JSContext,
+        key: HandleValue,
+        primary_key: HandleValue,
+    ) -> ErrorResult {
+        // Step 1.
+        self.transaction().check_active()?;
+
+        // Step 2.
+        self.check_source()?;
+
+        // Steps 3-4.
+        if self.index.is_none() {
+            return Err(Error::InvalidAccess);
+        }
+        match self.direction {
+            IDBCursorDirection::Nextunique | IDBCursorDirection::Prevunique => {
+                return Err(Error::InvalidAccess);
+            },
+            IDBCursorDirection::Next | IDBCursorDirection::Prev => {},
+        }
+
+        // Step 5.
+        if !self.got_value.get() {
+            return Err(Error::InvalidState);
+        }
+
+        // Steps 6-7.
+        let key = convert_value_to_key(cx, key, None)?;
+        let primary_key = convert_value_to_key(cx, primary_key, None)?;
+
+        // Steps 8-9.
+        if let (Some(current), Some(current_primary_key)) =
+            (self.current_key(), self.current_primary_key())
+        {
+            let target = (&key, &primary_key);
+            let position = (&current, &current_primary_key);
+            if (self.is_forward() && target <= position) ||
+                (!self.is_forward() && target >= position)
+            {
+                return Err(Error::Data);
+            }
+        }
+
+        // Steps 10-12.
+        self.iterate(Some((key, Some(primary_key))), 1);
+        Ok(())
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbcursor-update
+    fn Update(&self, cx: JSContext, value: HandleValue) -> Fallible<DomRoot<IDBRequest>> {
+        // Steps 1-5.
+        self.check_modifiable()?;
+        let primary_key = self.current_primary_key().ok_or(Error::InvalidState)?;
+
+        // Steps 6-8.
+        let store = self.object_store();
+        if let Some(key_path) = store.key_path() {
+            match extract_key(cx, value, &key_path, false)? {
+                ExtractionResult::Key(ref key) if *key == primary_key => {},
+                _ => return Err(Error::Data),
+            }
+        }
+
+        // Step 9.
+        store.store_record(
+            cx,
+            value,
+            Some(primary_key),
+            true,
+            IDBRequestSource::Cursor(Dom::from_ref(self)),
+        )
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbcursor-delete
+    fn Delete(&self) -> Fallible<DomRoot<IDBRequest>> {
+        // Steps 1-5.
+        self.check_modifiable()?;
+        let primary_key = self.current_primary_key().ok_or(Error::InvalidState)?;
+
+        // Steps 6-7.
+        Ok(self.object_store().start_request(
+            IDBRequestSource::Cursor(Dom::from_ref(self)),
+            AsyncOperation::RemoveItem(IndexedDBKeyRange::only(primary_key)),
+        ))
+    }
+}
@@ -0,0 +1,67 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::codegen::Bindings::IDBCursorBinding::IDBCursorDirection;
+use crate::dom::bindings::codegen::Bindings::IDBCursorWithValueBinding;
+use crate::dom::bindings::codegen::Bindings::IDBCursorWithValueBinding::IDBCursorWithValueMethods;
+use crate::dom::bindings::reflector::reflect_dom_object;
+use crate::dom::bindings::root::DomRoot;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::idbcursor::IDBCursor;
+use crate::dom::idbrequest::IDBRequest;
+use crate::script_runtime::JSContext;
+use dom_struct::dom_struct;
+use js::jsapi::Heap;
+use js::jsval::JSVal;
+use js::rust::HandleValue;
+use net_traits::indexeddb_thread::IndexedDBKeyRange;
+
+#[dom_struct]
+pub struct IDBCursorWithValue {
+    cursor: IDBCursor,
+    /// <https://w3c.github.io/IndexedDB/#cursor-value>
+    #[ignore_malloc_size_of = "mozjs"]
+    value: Heap<JSVal>,
+}
+
+impl IDBCursorWithValue {
+    fn new_inherited(
+        request: &IDBRequest,
+        index: Option<String>,
+        range: IndexedDBKeyRange,
+        direction: IDBCursorDirection,
+    ) -> IDBCursorWithValue {
+        IDBCursorWithValue {
+            cursor: IDBCursor::new_inherited(request, index, range, direction),
+            value: Heap::default(),
+        }
+    }
+
+    pub fn new(
+        global: &GlobalScope,
+        request: &IDBRequest,
+        index: Option<String>,
+        range: IndexedDBKeyRange,
+        direction: IDBCursorDirection,
+    ) -> DomRoot<IDBCursorWithValue> {
+        reflect_dom_object(
+            Box::new(IDBCursorWithValue::new_inherited(
+                request, index, range, direction,
+            )),
+            global,
+            IDBCursorWithValueBinding::Wrap,
+        )
+    }
+
+    pub fn set_value(&self, value: HandleValue) {
+        self.value.set(value.get());
+    }
+}
+
+impl IDBCursorWithValueMethods for IDBCursorWithValue {
+    // https://w3c.github.io/IndexedDB/#dom-idbcursorwithvalue-value
+    fn Value(&self, _cx: JSContext) -> JSVal {
+        self.value.get()
+    }
+}
@@ -0,0 +1,403 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::IDBDatabaseBinding;
+use crate::dom::bindings::codegen::Bindings::IDBDatabaseBinding::IDBDatabaseMethods;
+use crate::dom::bindings::codegen::Bindings::IDBDatabaseBinding::IDBObjectStoreParameters;
+use crate::dom::bindings::codegen::Bindings::IDBTransactionBinding::IDBTransactionMode;
+use crate::dom::bindings::codegen::UnionTypes::StringOrStringSequence;
+use crate::dom::bindings::error::{Error, Fallible};
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject};
+use crate::dom::bindings::root::{DomRoot, MutNullableDom};
+use crate::dom::bindings::str::DOMString;
+use crate::dom::domstringlist::DOMStringList;
+use crate::dom::eventtarget::EventTarget;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::idbobjectstore::IDBObjectStore;
+use crate::dom::idbtransaction::IDBTransaction;
+use crate::dom::idbversionchangeevent::IDBVersionChangeEvent;
+use crate::indexed_db::{exception_for, is_valid_key_path, key_path_from_union};
+use dom_struct::dom_struct;
+use ipc_channel::ipc::IpcSender;
+use net_traits::indexeddb_thread::{IndexedDBConnectionEvent, IndexedDBConnectionId};
+use net_traits::indexeddb_thread::{IndexedDBError, IndexedDBKeyPath, IndexedDBObjectStoreInfo};
+use net_traits::indexeddb_thread::{IndexedDBThreadMsg, IndexedDBTxnMode};
+use net_traits::IpcSend;
+use profile_traits::ipc;
+use servo_url::ImmutableOrigin;
+use std::cell::Cell;
+
+/// <https://w3c.github.io/IndexedDB/#connection>
+#[dom_struct]
+pub struct IDBDatabase {
+    eventtarget: EventTarget,
+    name: String,
+    version: Cell<u64>,
+    connection: IndexedDBConnectionId,
+    /// <https://w3c.github.io/IndexedDB/#connection-object-store-set>
+    object_stores: DomRefCell<Vec<IndexedDBObjectStoreInfo>>,
+    /// <https://w3c.github.io/IndexedDB/#connection-close-pending-flag>
+    close_pending: Cell<bool>,
+    /// Whether the indexeddb thread has been told the connection is closed.
+    closed: Cell<bool>,
+    /// The number of transactions created on this connection that have not finished yet.
+    live_transactions: Cell<usize>,
+    upgrade_transaction: MutNullableDom<IDBTransaction>,
+    /// The version and object stores to restore if the upgrade transaction is aborted.
+    previous_state: DomRefCell<Option<(u64, Vec<IndexedDBObjectStoreInfo>)>>,
+}
+
+impl IDBDatabase {
+    fn new_inherited(
+        name: String,
+        version: u64,
+        connection: IndexedDBConnectionId,
+        object_stores: Vec<IndexedDBObjectStoreInfo>,
+    ) -> IDBDatabase {
+        IDBDatabase {
+            eventtarget: EventTarget::new_inherited(),
+            name: name,
+            version: Cell::new(version),
+            connection: connection,
+            object_stores: DomRefCell::new(object_stores),
+            close_pending: Cell::new(false),
+            closed: Cell::new(false),
+            live_transactions: Cell::new(0),
+            upgrade_transaction: Default::default(),
+            previous_state: DomRefCell::new(None),
+        }
+    }
+
+    pub fn new(
+        global: &GlobalScope,
+        name: String,
+        version: u64,
+        connection: IndexedDBConnectionId,
+        object_stores: Vec<IndexedDBObjectStoreInfo>,
+    ) -> DomRoot<IDBDatabase> {
+        reflect_dom_object(
+            Box::new(IDBDatabase::new_inherited(
+                name,
+                version,
+                connection,
+                object_stores,
+            )),
+            global,
+            IDBDatabaseBinding::Wrap,
+        )
+    }
+
+    pub fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    pub fn origin(&self) -> ImmutableOrigin {
+        self.global().origin().immutable().clone()
+    }
+
+    pub fn send(&self, message: IndexedDBThreadMsg) {
+        self.global().resource_threads().send(message).unwrap();
+    }
+
+    /// Sends a message expecting a result, and waits for it.
+    pub fn send_sync<F>(&self, message: F) -> Result<(), IndexedDBError>
+    where
+        F: FnOnce(IpcSender<Result<(), IndexedDBError>>) -> IndexedDBThreadMsg,
+    {
+        let (sender, receiver) = ipc::channel(self.global().time_profiler_chan().clone()).unwrap();
+        self.send(message(sender));
+        receiver.recv().unwrap_or(Err(IndexedDBError::Unknown))
+    }
+
+    /// The names of the object stores of this connection, sorted.
+    pub fn object_store_names(&self) -> Vec<DOMString> {
+        let mut names: Vec<DOMString> = self
+            .object_stores
+            .borrow()
+            .iter()
+            .map(|info| DOMString::from(info.name.clone()))
+            .collect();
+        names.sort();
+        names
+    }
+
+    pub fn object_store_info(&self, name: &str) -> Option<IndexedDBObjectStoreInfo> {
+        self.object_stores
+            .borrow()
+            .iter()
+            .find(|info| info.name == name)
+            .cloned()
+    }
+
+    /// Records a change to the indexes of an object store made by the upgrade transaction.
+    pub fn update_object_store_info(&self, info: IndexedDBObjectStoreInfo) {
+        let mut object_stores = self.object_stores.borrow_mut();
+        if let Some(existing) = object_stores.iter_mut().find(|i| i.name == info.name) {
+            *existing = info;
+        }
+    }
+
+    pub fn upgrade_transaction(&self) -> Option<DomRoot<IDBTransaction>> {
+        self.upgrade_transaction.get()
+    }
+
+    pub fn start_upgrade(&self, transaction: &IDBTransaction, old_version: u64) {
+        self.upgrade_transaction.set(Some(transaction));
+        let object_stores = self.object_stores.borrow().clone();
+        *self.previous_state.borrow_mut() = Some((old_version, object_stores));
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#abort-an-upgrade-transaction>
+    pub fn finish_upgrade(&self, aborted: bool) {
+        self.upgrade_transaction.set(None);
+        let previous_state = self.previous_state.borrow_mut().take();
+        if let (true, Some((version, object_stores))) = (aborted, previous_state) {
+            self.version.set(version);
+            *self.object_stores.borrow_mut() = object_stores;
+        }
+    }
+
+    pub fn transaction_started(&self) {
+        self.live_transactions.set(self.live_transactions.get() + 1);
+    }
+
+    pub fn transaction_finished(&self) {
+        self.live_transactions.set(self.live_transactions.get() - 1);
+        if self.close_pending.get() {
+            self.close_connection();
+        }
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#close-a-database-connection>
+    pub fn close_connection(&self) {
+        // Step 1.
+        self.close_pending.set(true);
+
+        // Step 2. Wait for the transactions of the connection to finish.
+        if self.closed.get() || self.live_transactions.get() > 0 {
+            return;
+        }
+        self.closed.set(true);
+        self.send(IndexedDBThreadMsg::CloseConnection(
+            self.origin(),
+            self.name(),
+            self.connection,
+        ));
+    }
+
+    pub fn handle_connection_event(&self, event: IndexedDBConnectionEvent) {
+        match event {
+            IndexedDBConnectionEvent::VersionChange {
+                old_version,
+                new_version,
+            } => {
+                // https://w3c.github.io/IndexedDB/#fire-a-version-change-event
+                if self.close_pending.get() {
+                    return;
+                }
+                IDBVersionChangeEvent::fire(
+                    self.upcast(),
+                    atom!("versionchange"),
+                    old_version,
+                    new_version,
+                );
+            },
+        }
+    }
+
+    /// Checks that a schema change can be made, returning the upgrade transaction.
+    fn check_upgrade_transaction(&self) -> Fallible<DomRoot<IDBTransaction>> {
+        let transaction = self.upgrade_transaction.get().ok_or(Error::InvalidState)?;
+        if !transaction.is_active() {
+            return Err(Error::TransactionInactive);
+        }
+        Ok(transaction)
+    }
+}
+
+impl IDBDatabaseMethods for IDBDatabase {
+    // https://w3c.github.io/IndexedDB/#dom-idbdatabase-name
+    fn Name(&self) -> DOMString {
+        DOMString::from(self.name.clone())
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbdatabase-version
+    fn Version(&self) -> u64 {
+        self.version.get()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbdatabase-objectstorenames
+    fn ObjectStoreNames(&self) -> DomRoot<DOMStringList> {
+        DOMStringList::new(&self.global(), self.object_store_names())
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbdatabase-transaction
+    fn Transaction(
+        &self,
+        store_names: StringOrStringSequence,
+        mode: IDBTransactionMode,
+    ) -> Fallible<DomRoot<IDBTransaction>> {
+        // Step 1.
+        if self.upgrade_transaction.get().is_some() {
+            return Err(Error::InvalidState);
+        }
+
+        // Step 2.
+        if self.close_pending.get() {
+            return Err(Error::InvalidState);
+        }
+
+        // Step 3.
+        let mut scope = match store_names {
+            StringOrStringSequence::String(name) => vec![name],
+            StringOrStringSequence::StringSequence(names) => names,
+        };
+        scope.sort();
+        scope.dedup();
+
+        // Step 4.
+        if scope
+            .iter()
+            .any(|name| self.object_store_info(name).is_none())
+        {
+            return Err(Error::NotFound);
+        }
+
+        // Step 5.
+        if scope.is_empty() {
+            return Err(Error::InvalidAccess);
+        }
+
+        // Step 6.
+        let txn_mode = match mode {
+            IDBTransactionMode::Readonly => IndexedDBTxnMode::Readonly,
+            IDBTransactionMode::Readwrite => IndexedDBTxnMode::Readwrite,
+            IDBTransactionMode::Versionchange => {
+                return Err(Error::Type("Invalid transaction mode".to_owned()));
+            },
+        };
+
+        // Steps 7-9.
+        let global = self.global();
+        let (sender, receiver) = ipc::channel(global.time_profiler_chan().clone()).unwrap();
+        self.send(IndexedDBThreadMsg::StartTransaction(
+            sender,
+            self.origin(),
+            self.name(),
+            txn_mode,
+            scope
+                .iter()
+                .map(|name| String::from(name.clone()))
+                .collect(),
+        ));
+        let id = receiver.recv().map_err(|_| Error::Unknown)?;
+        Ok(IDBTransaction::new(&global, self, mode, scope, id))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbdatabase-close
+    fn Close(&self) {
+        self.close_connection();
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbdatabase-createobjectstore
+    fn CreateObjectStore(
+        &self,
+        name: DOMString,
+        options: &IDBObjectStoreParameters,
+    ) -> Fallible<DomRoot<IDBObjectStore>> {
+        // Steps 1-4.
+        let transaction = self.check_upgrade_transaction()?;
+
+        // Steps 5-6.
+        let key_path = options.keyPath.clone().map(key_path_from_union);
+        if let Some(ref key_path) = key_path {
+            if !is_valid_key_path(key_path) {
+                return Err(Error::Syntax);
+            }
+        }
+
+        // Step 7.
+        if self.object_store_info(&name).is_some() {
+            return Err(Error::Constraint);
+        }
+
+        // Step 8.
+        let auto_increment = options.autoIncrement;
+
+        // Step 9.
+        match key_path {
+            Some(IndexedDBKeyPath::String(ref path)) if auto_increment && path.is_empty() => {
+                return Err(Error::InvalidAccess);
+            },
+            Some(IndexedDBKeyPath::Sequence(_)) if auto_increment => {
+                return Err(Error::InvalidAccess);
+            },
+            _ => {},
+        }
+
+        // Steps 10-12.
+        let info = IndexedDBObjectStoreInfo {
+            name: name.into(),
+            key_path: key_path,
+            auto_increment: auto_increment,
+            indexes: vec![],
+        };
+        self.send_sync(|sender| IndexedDBThreadMsg::CreateObjectStore {
+            sender,
+            origin: self.origin(),
+            name: self.name(),
+            txn: transaction.id(),
+            store_name: info.name.clone(),
+            key_path: info.key_path.clone(),
+            auto_increment,
+        })
+        .map_err(exception_for)?;
+        self.object_stores.borrow_mut().push(info.clone());
+
+        // Step 13.
+        Ok(transaction.object_store_for_info(info))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbdatabase-deleteobjectstore
+    fn DeleteObjectStore(&self, name: DOMString) -> Fallible<()> {
+        // Steps 1-4.
+        let transaction = self.check_upgrade_transaction()?;
+
+        // Step 5.
+        if self.object_store_info(&name).is_none() {
+            return Err(Error::NotFound);
+        }
+
+        // Steps 6-7.
+        self.send_sync(|sender| {
+            IndexedDBThreadMsg::DeleteObjectStore(
+                sender,
+                self.origin(),
+                self.name(),
+                transaction.id(),
+                name.to_string(),
+            )
+        })
+        .map_err(exception_for)?;
+        self.object_stores
+            .borrow_mut()
+            .retain(|info| info.name != *name);
+        transaction.forget_object_store(&name);
+        Ok(())
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbdatabase-onabort
+    event_handler!(abort, GetOnabort, SetOnabort);
+
+    // https://w3c.github.io/IndexedDB/#dom-idbdatabase-onclose
+    event_handler!(close, GetOnclose, SetOnclose);
+
+    // https://w3c.github.io/IndexedDB/#dom-idbdatabase-onerror
+    event_handler!(error, GetOnerror, SetOnerror);
+
+    // https://w3c.github.io/IndexedDB/#dom-idbdatabase-onversionchange
+    event_handler!(versionchange, GetOnversionchange, SetOnversionchange);
+}
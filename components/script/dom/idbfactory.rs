@@ -0,0 +1,134 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::codegen::Bindings::IDBFactoryBinding;
+use crate::dom::bindings::codegen::Bindings::IDBFactoryBinding::IDBFactoryMethods;
+use crate::dom::bindings::error::{Error, Fallible};
+use crate::dom::bindings::refcounted::Trusted;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::DomRoot;
+use crate::dom::bindings::str::DOMString;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::idbopendbrequest::IDBOpenDBRequest;
+use crate::indexed_db::{convert_value_to_key, route_replies};
+use crate::script_runtime::JSContext;
+use dom_struct::dom_struct;
+use ipc_channel::ipc::IpcSender;
+use js::rust::HandleValue;
+use net_traits::indexeddb_thread::IndexedDBThreadMsg;
+use net_traits::indexeddb_thread::{IndexedDBConnectionEvent, IndexedDBOpenReply};
+use net_traits::IpcSend;
+use profile_traits::ipc;
+use std::cmp::Ordering;
+
+#[dom_struct]
+pub struct IDBFactory {
+    reflector_: Reflector,
+}
+
+impl IDBFactory {
+    fn new_inherited() -> IDBFactory {
+        IDBFactory {
+            reflector_: Reflector::new(),
+        }
+    }
+
+    pub fn new(global: &GlobalScope) -> DomRoot<IDBFactory> {
+        reflect_dom_object(
+            Box::new(IDBFactory::new_inherited()),
+            global,
+            IDBFactoryBinding::Wrap,
+        )
+    }
+
+    /// Route the replies to an open or delete request to `request`.
+    fn open_reply_channel(&self, request: &IDBOpenDBRequest) -> IpcSender<IndexedDBOpenReply> {
+        let global = self.global();
+        let (sender, receiver) = ipc::channel(global.time_profiler_chan().clone()).unwrap();
+        let trusted_request = Trusted::new(request);
+        route_replies(&global, receiver, move |reply: IndexedDBOpenReply| {
+            trusted_request.root().handle_open_reply(reply);
+        });
+        sender
+    }
+}
+
+impl IDBFactoryMethods for IDBFactory {
+    // https://w3c.github.io/IndexedDB/#dom-idbfactory-open
+    fn Open(&self, name: DOMString, version: Option<u64>) -> Fallible<DomRoot<IDBOpenDBRequest>> {
+        // Step 1.
+        if version == Some(0) {
+            return Err(Error::Type("The version must not be zero".to_owned()));
+        }
+
+        // Steps 2-3.
+        let global = self.global();
+        let origin = global.origin();
+        if !origin.is_tuple() {
+            return Err(Error::Security);
+        }
+
+        // Steps 4-5.
+        let request = IDBOpenDBRequest::new(&global, name.to_string());
+        let sender = self.open_reply_channel(&request);
+        let (events, events_receiver) = ipc::channel(global.time_profiler_chan().clone()).unwrap();
+        let trusted_request = Trusted::new(&*request);
+        route_replies(
+            &global,
+            events_receiver,
+            move |event: IndexedDBConnectionEvent| {
+                trusted_request.root().handle_connection_event(event);
+            },
+        );
+        global
+            .resource_threads()
+            .send(IndexedDBThreadMsg::OpenDatabase {
+                sender,
+                events,
+                origin: origin.immutable().clone(),
+                name: name.into(),
+                version,
+            })
+            .unwrap();
+
+        // Step 6.
+        Ok(request)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbfactory-deletedatabase
+    fn DeleteDatabase(&self, name: DOMString) -> Fallible<DomRoot<IDBOpenDBRequest>> {
+        // Steps 1-2.
+        let global = self.global();
+        let origin = global.origin();
+        if !origin.is_tuple() {
+            return Err(Error::Security);
+        }
+
+        // Steps 3-4.
+        let request = IDBOpenDBRequest::new(&global, name.to_string());
+        let sender = self.open_reply_channel(&request);
+        global
+            .resource_threads()
+            .send(IndexedDBThreadMsg::DeleteDatabase(
+                sender,
+                origin.immutable().clone(),
+                name.into(),
+            ))
+            .unwrap();
+
+        // Step 5.
+        Ok(request)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbfactory-cmp
+    fn Cmp(&self, cx: JSContext, first: HandleValue, second: HandleValue) -> Fallible<i16> {
+        let first = convert_value_to_key(cx, first, None)?;
+        let second = convert_value_to_key(cx, second, None)?;
+        Ok(match first.cmp(&second) {
+            Ordering::Less => -1,
+            Ordering::Equal => 0,
+            Ordering::Greater => 1,
+        })
+    }
+}
@@ -0,0 +1,225 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::codegen::Bindings::IDBCursorBinding::IDBCursorDirection;
+use crate::dom::bindings::codegen::Bindings::IDBIndexBinding;
+use crate::dom::bindings::codegen::Bindings::IDBIndexBinding::IDBIndexMethods;
+use crate::dom::bindings::error::{Error, ErrorResult, Fallible};
+use crate::dom::bindings::reflector::{reflect_dom_object, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot};
+use crate::dom::bindings::str::DOMString;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::idbobjectstore::IDBObjectStore;
+use crate::dom::idbrequest::{IDBRequest, IDBRequestSource};
+use crate::indexed_db::{convert_value_to_key_range, key_path_to_jsval};
+use crate::script_runtime::JSContext;
+use dom_struct::dom_struct;
+use js::jsval::{JSVal, UndefinedValue};
+use js::rust::HandleValue;
+use net_traits::indexeddb_thread::{AsyncOperation, IndexedDBIndexInfo};
+
+#[dom_struct]
+pub struct IDBIndex {
+    reflector_: Reflector,
+    object_store: Dom<IDBObjectStore>,
+    info: IndexedDBIndexInfo,
+}
+
+impl IDBIndex {
+    fn new_inherited(object_store: &IDBObjectStore, info: IndexedDBIndexInfo) -> IDBIndex {
+        IDBIndex {
+            reflector_: Reflector::new(),
+            object_store: Dom::from_ref(object_store),
+            info: info,
+        }
+    }
+
+    pub fn new(
+        global: &GlobalScope,
+        object_store: &IDBObjectStore,
+        info: IndexedDBIndexInfo,
+    ) -> DomRoot<IDBIndex> {
+        reflect_dom_object(
+            Box::new(IDBIndex::new_inherited(object_store, info)),
+            global,
+            IDBIndexBinding::Wrap,
+        )
+    }
+
+    pub fn object_store(&self) -> DomRoot<IDBObjectStore> {
+        DomRoot::from_ref(&*self.object_store)
+    }
+
+    /// Checks that a new request can be made against this index.
+    fn check_usable(&self) -> ErrorResult {
+        if self.object_store.index_info(&self.info.name).is_none() {
+            return Err(Error::InvalidState);
+        }
+        self.object_store.check_usable()
+    }
+
+    fn start_request(&self, operation: AsyncOperation) -> DomRoot<IDBRequest> {
+        self.object_store
+            .start_request(IDBRequestSource::Index(Dom::from_ref(self)), operation)
+    }
+
+    fn open_cursor(
+        &self,
+        cx: JSContext,
+        query: HandleValue,
+        direction: IDBCursorDirection,
+        key_only: bool,
+    ) -> Fallible<DomRoot<IDBRequest>> {
+        // Steps 1-4.
+        self.check_usable()?;
+
+        // Step 5.
+        let range = convert_value_to_key_range(cx, query, false)?;
+
+        // Steps 6-10.
+        Ok(self.object_store.open_cursor(
+            IDBRequestSource::Index(Dom::from_ref(self)),
+            Some(self.info.name.clone()),
+            range,
+            direction,
+            key_only,
+        ))
+    }
+}
+
+impl IDBIndexMethods for IDBIndex {
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-name
+    fn Name(&self) -> DOMString {
+        DOMString::from(self.info.name.clone())
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-objectstore
+    fn ObjectStore(&self) -> DomRoot<IDBObjectStore> {
+        self.object_store()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-keypath
+    fn KeyPath(&self, cx: JSContext) -> JSVal {
+        rooted!(in(*cx) let mut key_path = UndefinedValue());
+        key_path_to_jsval(cx, &Some(self.info.key_path.clone()), key_path.handle_mut());
+        key_path.get()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-multientry
+    fn MultiEntry(&self) -> bool {
+        self.info.multi_entry
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-unique
+    fn Unique(&self) -> bool {
+        self.info.unique
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-get
+    fn Get(&self, cx: JSContext, query: HandleValue) -> Fallible<DomRoot<IDBRequest>> {
+        // Steps 1-4.
+        self.check_usable()?;
+
+        // Step 5.
+        let range = convert_value_to_key_range(cx, query, true)?;
+
+        // Step 6.
+        Ok(self.start_request(AsyncOperation::GetItem {
+            index: Some(self.info.name.clone()),
+            range,
+        }))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-getkey
+    fn GetKey(&self, cx: JSContext, query: HandleValue) -> Fallible<DomRoot<IDBRequest>> {
+        // Steps 1-4.
+        self.check_usable()?;
+
+        // Step 5.
+        let range = convert_value_to_key_range(cx, query, true)?;
+
+        // Step 6.
+        Ok(self.start_request(AsyncOperation::GetKey {
+            index: Some(self.info.name.clone()),
+            range,
+        }))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-getall
+    fn GetAll(
+        &self,
+        cx: JSContext,
+        query: HandleValue,
+        count: Option<u32>,
+    ) -> Fallible<DomRoot<IDBRequest>> {
+        // Steps 1-4.
+        self.check_usable()?;
+
+        // Step 5.
+        let range = convert_value_to_key_range(cx, query, false)?;
+
+        // Step 6.
+        Ok(self.start_request(AsyncOperation::GetAll {
+            index: Some(self.info.name.clone()),
+            range,
+            count,
+        }))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-getallkeys
+    fn GetAllKeys(
+        &self,
+        cx: JSContext,
+        query: HandleValue,
+        count: Option<u32>,
+    ) -> Fallible<DomRoot<IDBRequest>> {
+        // Steps 1-4.
+        self.check_usable()?;
+
+        // Step 5.
+        let range = convert_value_to_key_range(cx, query, false)?;
+
+        // Step 6.
+        Ok(self.start_request(AsyncOperation::GetAllKeys {
+            index: Some(self.info.name.clone()),
+            range,
+            count,
+        }))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-count
+    fn Count(&self, cx: JSContext, query: HandleValue) -> Fallible<DomRoot<IDBRequest>> {
+        // Steps 1-4.
+        self.check_usable()?;
+
+        // Step 5.
+        let range = convert_value_to_key_range(cx, query, false)?;
+
+        // Step 6.
+        Ok(self.start_request(AsyncOperation::Count {
+            index: Some(self.info.name.clone()),
+            range,
+        }))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-opencursor
+    fn OpenCursor(
+        &self,
+        cx: JSContext,
+        query: HandleValue,
+        direction: IDBCursorDirection,
+    ) -> Fallible<DomRoot<IDBRequest>> {
+        self.open_cursor(cx, query, direction, false)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbindex-openkeycursor
+    fn OpenKeyCursor(
+        &self,
+        cx: JSContext,
+        query: HandleValue,
+        direction: IDBCursorDirection,
+    ) -> Fallible<DomRoot<IDBRequest>> {
+        self.open_cursor(cx, query, direction, true)
+    }
+}
@@ -0,0 +1,152 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::codegen::Bindings::IDBKeyRangeBinding;
+use crate::dom::bindings::codegen::Bindings::IDBKeyRangeBinding::IDBKeyRangeMethods;
+use crate::dom::bindings::error::{Error, Fallible};
+use crate::dom::bindings::reflector::{reflect_dom_object, Reflector};
+use crate::dom::bindings::root::DomRoot;
+use crate::dom::globalscope::GlobalScope;
+use crate::indexed_db::{convert_value_to_key, key_type_to_jsval};
+use crate::script_runtime::JSContext;
+use dom_struct::dom_struct;
+use js::jsval::{JSVal, UndefinedValue};
+use js::rust::HandleValue;
+use net_traits::indexeddb_thread::{IndexedDBKeyRange, IndexedDBKeyType};
+
+#[dom_struct]
+pub struct IDBKeyRange {
+    reflector_: Reflector,
+    inner: IndexedDBKeyRange,
+}
+
+impl IDBKeyRange {
+    fn new_inherited(inner: IndexedDBKeyRange) -> IDBKeyRange {
+        IDBKeyRange {
+            reflector_: Reflector::new(),
+            inner: inner,
+        }
+    }
+
+    pub fn new(global: &GlobalScope, inner: IndexedDBKeyRange) -> DomRoot<IDBKeyRange> {
+        reflect_dom_object(
+            Box::new(IDBKeyRange::new_inherited(inner)),
+            global,
+            IDBKeyRangeBinding::Wrap,
+        )
+    }
+
+    pub fn inner(&self) -> &IndexedDBKeyRange {
+        &self.inner
+    }
+
+    fn bound_to_jsval(cx: JSContext, bound: &Option<IndexedDBKeyType>) -> JSVal {
+        rooted!(in(*cx) let mut value = UndefinedValue());
+        if let Some(ref key) = *bound {
+            key_type_to_jsval(cx, key, value.handle_mut());
+        }
+        value.get()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbkeyrange-only
+    pub fn Only(
+        cx: JSContext,
+        global: &GlobalScope,
+        value: HandleValue,
+    ) -> Fallible<DomRoot<IDBKeyRange>> {
+        let key = convert_value_to_key(cx, value, None)?;
+        Ok(IDBKeyRange::new(global, IndexedDBKeyRange::only(key)))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbkeyrange-lowerbound
+    pub fn LowerBound(
+        cx: JSContext,
+        global: &GlobalScope,
+        lower: HandleValue,
+        open: bool,
+    ) -> Fallible<DomRoot<IDBKeyRange>> {
+        let lower = convert_value_to_key(cx, lower, None)?;
+        let inner = IndexedDBKeyRange {
+            lower: Some(lower),
+            lower_open: open,
+            ..IndexedDBKeyRange::unbounded()
+        };
+        Ok(IDBKeyRange::new(global, inner))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbkeyrange-upperbound
+    pub fn UpperBound(
+        cx: JSContext,
+        global: &GlobalScope,
+        upper: HandleValue,
+        open: bool,
+    ) -> Fallible<DomRoot<IDBKeyRange>> {
+        let upper = convert_value_to_key(cx, upper, None)?;
+        let inner = IndexedDBKeyRange {
+            upper: Some(upper),
+            upper_open: open,
+            ..IndexedDBKeyRange::unbounded()
+        };
+        Ok(IDBKeyRange::new(global, inner))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbkeyrange-bound
+    pub fn Bound(
+        cx: JSContext,
+        global: &GlobalScope,
+        lower: HandleValue,
+        upper: HandleValue,
+        lower_open: bool,
+        upper_open: bool,
+    ) -> Fallible<DomRoot<IDBKeyRange>> {
+        // Steps 1-4.
+        let lower = convert_value_to_key(cx, lower, None)?;
+        let upper = convert_value_to_key(cx, upper, None)?;
+
+        // Step 5.
+        if lower > upper {
+            return Err(Error::Data);
+        }
+        if lower == upper && (lower_open || upper_open) {
+            return Err(Error::Data);
+        }
+
+        // Steps 6-7.
+        let inner = IndexedDBKeyRange {
+            lower: Some(lower),
+            upper: Some(upper),
+            lower_open,
+            upper_open,
+        };
+        Ok(IDBKeyRange::new(global, inner))
+    }
+}
+
+impl IDBKeyRangeMethods for IDBKeyRange {
+    // https://w3c.github.io/IndexedDB/#dom-idbkeyrange-lower
+    fn Lower(&self, cx: JSContext) -> JSVal {
+        IDBKeyRange::bound_to_jsval(cx, &self.inner.lower)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbkeyrange-upper
+    fn Upper(&self, cx: JSContext) -> JSVal {
+        IDBKeyRange::bound_to_jsval(cx, &self.inner.upper)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbkeyrange-loweropen
+    fn LowerOpen(&self) -> bool {
+        self.inner.lower_open
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbkeyrange-upperopen
+    fn UpperOpen(&self) -> bool {
+        self.inner.upper_open
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbkeyrange-includes
+    fn Includes(&self, cx: JSContext, key: HandleValue) -> Fallible<bool> {
+        let key = convert_value_to_key(cx, key, None)?;
+        Ok(self.inner.contains(&key))
+    }
+}
@@ -0,0 +1,686 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::IDBCursorBinding::IDBCursorDirection;
+use crate::dom::bindings::codegen::Bindings::IDBObjectStoreBinding;
+use crate::dom::bindings::codegen::Bindings::IDBObjectStoreBinding::IDBIndexParameters;
+use crate::dom::bindings::codegen::Bindings::IDBObjectStoreBinding::IDBObjectStoreMethods;
+use crate::dom::bindings::codegen::Bindings::IDBTransactionBinding::IDBTransactionMode;
+use crate::dom::bindings::codegen::UnionTypes::StringOrStringSequence;
+use crate::dom::bindings::error::{Error, ErrorResult, Fallible};
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot};
+use crate::dom::bindings::str::DOMString;
+use crate::dom::bindings::structuredclone::StructuredCloneData;
+use crate::dom::domstringlist::DOMStringList;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::idbcursor::{to_cursor_direction, IDBCursor};
+use crate::dom::idbcursorwithvalue::IDBCursorWithValue;
+use crate::dom::idbindex::IDBIndex;
+use crate::dom::idbrequest::{IDBRequest, IDBRequestSource};
+use crate::dom::idbtransaction::IDBTransaction;
+use crate::indexed_db::{can_inject_key_into_value, convert_value_to_key};
+use crate::indexed_db::{convert_value_to_key_range, exception_for, extract_key};
+use crate::indexed_db::{inject_key_into_value, is_valid_key_path, key_path_from_union};
+use crate::indexed_db::{key_path_to_jsval, ExtractionResult};
+use crate::script_runtime::JSContext;
+use dom_struct::dom_struct;
+use js::jsval::{JSVal, UndefinedValue};
+use js::rust::{HandleValue, MutableHandleValue};
+use net_traits::indexeddb_thread::{AsyncOperation, IndexedDBIndexInfo, IndexedDBKeyPath};
+use net_traits::indexeddb_thread::{IndexedDBKeyRange, IndexedDBKeyType};
+use net_traits::indexeddb_thread::{IndexedDBObjectStoreInfo, IndexedDBThreadMsg};
+use profile_traits::ipc;
+use std::collections::HashMap;
+
+#[dom_struct]
+pub struct IDBObjectStore {
+    reflector_: Reflector,
+    transaction: Dom<IDBTransaction>,
+    info: DomRefCell<IndexedDBObjectStoreInfo>,
+    /// The index handles returned by `index()`.
+    indexes: DomRefCell<HashMap<DOMString, Dom<IDBIndex>>>,
+}
+
+impl IDBObjectStore {
+    fn new_inherited(
+        transaction: &IDBTransaction,
+        info: IndexedDBObjectStoreInfo,
+    ) -> IDBObjectStore {
+        IDBObjectStore {
+            reflector_: Reflector::new(),
+            transaction: Dom::from_ref(transaction),
+            info: DomRefCell::new(info),
+            indexes: DomRefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn new(
+        global: &GlobalScope,
+        transaction: &IDBTransaction,
+        info: IndexedDBObjectStoreInfo,
+    ) -> DomRoot<IDBObjectStore> {
+        reflect_dom_object(
+            Box::new(IDBObjectStore::new_inherited(transaction, info)),
+            global,
+            IDBObjectStoreBinding::Wrap,
+        )
+    }
+
+    pub fn name(&self) -> String {
+        self.info.borrow().name.clone()
+    }
+
+    pub fn key_path(&self) -> Option<IndexedDBKeyPath> {
+        self.info.borrow().key_path.clone()
+    }
+
+    pub fn transaction(&self) -> DomRoot<IDBTransaction> {
+        DomRoot::from_ref(&*self.transaction)
+    }
+
+    pub fn index_info(&self, name: &str) -> Option<IndexedDBIndexInfo> {
+        self.info
+            .borrow()
+            .indexes
+            .iter()
+            .find(|info| info.name == name)
+            .cloned()
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#object-store-handle-object-store>
+    pub fn is_deleted(&self) -> bool {
+        self.transaction
+            .database()
+            .object_store_info(&self.name())
+            .is_none()
+    }
+
+    /// Checks that a new request can be made against this object store.
+    pub fn check_usable(&self) -> ErrorResult {
+        if self.is_deleted() {
+            return Err(Error::InvalidState);
+        }
+        self.transaction.check_active()
+    }
+
+    /// Checks that a new request modifying this object store can be made.
+    pub fn check_writable(&self) -> ErrorResult {
+        self.check_usable()?;
+        if self.transaction.mode() == IDBTransactionMode::Readonly {
+            return Err(Error::ReadOnly);
+        }
+        Ok(())
+    }
+
+    /// Creates a request and sends its operation to the indexeddb thread.
+    #[allow(unrooted_must_root)]
+    pub fn start_request(
+        &self,
+        source: IDBRequestSource,
+        operation: AsyncOperation,
+    ) -> DomRoot<IDBRequest> {
+        let request = IDBRequest::new(&self.global(), source, &self.transaction);
+        self.transaction
+            .execute(&request, None, &self.name(), operation);
+        request
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#dom-idbobjectstore-opencursor>
+    /// <https://w3c.github.io/IndexedDB/#dom-idbindex-opencursor>
+    #[allow(unrooted_must_root)]
+    pub fn open_cursor(
+        &self,
+        source: IDBRequestSource,
+        index: Option<String>,
+        range: IndexedDBKeyRange,
+        direction: IDBCursorDirection,
+        key_only: bool,
+    ) -> DomRoot<IDBRequest> {
+        let global = self.global();
+        let request = IDBRequest::new(&global, source, &self.transaction);
+        let cursor = if key_only {
+            IDBCursor::new(&global, &request, index.clone(), range.clone(), direction)
+        } else {
+            DomRoot::upcast(IDBCursorWithValue::new(
+                &global,
+                &request,
+                index.clone(),
+                range.clone(),
+                direction,
+            ))
+        };
+        let operation = AsyncOperation::Iterate {
+            index,
+            range,
+            direction: to_cursor_direction(direction),
+            position: None,
+            target: None,
+            count: 1,
+        };
+        self.transaction
+            .execute(&request, Some(&cursor), &self.name(), operation);
+        request
+    }
+
+    /// Deserializes the value of a record, injecting its key if it was generated.
+    pub fn read_value(
+        &self,
+        value: Vec<u8>,
+        primary_key: &IndexedDBKeyType,
+        rval: MutableHandleValue,
+    ) {
+        let global = self.global();
+        StructuredCloneData::Vector(value).read(&global, rval);
+        let info = self.info.borrow();
+        if let (true, Some(&IndexedDBKeyPath::String(ref path))) =
+            (info.auto_increment, info.key_path.as_ref())
+        {
+            let cx = global.get_cx();
+            if let Err(error) = inject_key_into_value(cx, rval.handle(), primary_key, path) {
+                warn!("Failed to inject a generated key: {:?}", error);
+            }
+        }
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#add-or-put>
+    fn add_or_put(
+        &self,
+        cx: JSContext,
+        value: HandleValue,
+        key: HandleValue,
+        overwrite: bool,
+    ) -> Fallible<DomRoot<IDBRequest>> {
+        // Steps 1-5.
+        self.check_writable()?;
+
+        // Steps 6-7.
+        let (has_key_path, auto_increment) = {
+            let info = self.info.borrow();
+            (info.key_path.is_some(), info.auto_increment)
+        };
+        if has_key_path && !key.is_undefined() {
+            return Err(Error::Data);
+        }
+        if !has_key_path && !auto_increment && key.is_undefined() {
+            return Err(Error::Data);
+        }
+
+        // Step 8.
+        let key = if key.is_undefined() {
+            None
+        } else {
+            Some(convert_value_to_key(cx, key, None)?)
+        };
+
+        // Steps 9-12.
+        self.store_record(
+            cx,
+            value,
+            key,
+            overwrite,
+            IDBRequestSource::ObjectStore(Dom::from_ref(self)),
+        )
+    }
+
+    /// Stores `value` with the given out-of-line key, or the key extracted from it.
+    /// <https://w3c.github.io/IndexedDB/#store-a-record-into-an-object-store>
+    #[allow(unrooted_must_root)]
+    pub fn store_record(
+        &self,
+        cx: JSContext,
+        value: HandleValue,
+        key: Option<IndexedDBKeyType>,
+        overwrite: bool,
+        source: IDBRequestSource,
+    ) -> Fallible<DomRoot<IDBRequest>> {
+        let info = self.info.borrow().clone();
+
+        // Steps 9-10.
+        let serialized = StructuredCloneData::write(*cx, value)?.move_to_arraybuffer();
+
+        // Step 11.
+        let mut key = key;
+        if let Some(ref key_path) = info.key_path {
+            match extract_key(cx, value, key_path, false)? {
+                ExtractionResult::Key(extracted) => key = Some(extracted),
+                ExtractionResult::Invalid => return Err(Error::Data),
+                ExtractionResult::Failure => {
+                    // The key will be generated and injected when the value is read.
+                    let path = match *key_path {
+                        IndexedDBKeyPath::String(ref path) if info.auto_increment => path,
+                        _ => return Err(Error::Data),
+                    };
+                    if !can_inject_key_into_value(cx, value, path)? {
+                        return Err(Error::Data);
+                    }
+                },
+            }
+        }
+
+        // https://w3c.github.io/IndexedDB/#extract-a-key-from-a-value-using-a-key-path
+        let mut index_keys = vec![];
+        for index in &info.indexes {
+            if let ExtractionResult::Key(index_key) =
+                extract_key(cx, value, &index.key_path, index.multi_entry)?
+            {
+                let keys = match index_key {
+                    IndexedDBKeyType::Array(keys) if index.multi_entry => keys,
+                    key => vec![key],
+                };
+                index_keys.push((index.name.clone(), keys));
+            }
+        }
+
+        // Step 12.
+        Ok(self.start_request(
+            source,
+            AsyncOperation::PutItem {
+                key,
+                value: serialized,
+                index_keys,
+                overwrite,
+            },
+        ))
+    }
+
+    /// Checks that the schema of the object store can be changed.
+    fn check_upgrade_transaction(&self) -> ErrorResult {
+        if self.transaction.mode() != IDBTransactionMode::Versionchange {
+            return Err(Error::InvalidState);
+        }
+        if self.is_deleted() {
+            return Err(Error::InvalidState);
+        }
+        self.transaction.check_active()
+    }
+
+    fn update_info<F: FnOnce(&mut IndexedDBObjectStoreInfo)>(&self, f: F) {
+        let mut info = self.info.borrow_mut();
+        f(&mut info);
+        self.transaction
+            .database()
+            .update_object_store_info(info.clone());
+    }
+}
+
+impl IDBObjectStoreMethods for IDBObjectStore {
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-name
+    fn Name(&self) -> DOMString {
+        DOMString::from(self.name())
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-name
+    fn SetName(&self, name: DOMString) -> ErrorResult {
+        // Steps 1-6.
+        self.check_upgrade_transaction()?;
+
+        // Step 7.
+        if *name == *self.name() {
+            return Ok(());
+        }
+
+        // Step 8.
+        if self
+            .transaction
+            .database()
+            .object_store_info(&name)
+            .is_some()
+        {
+            return Err(Error::Constraint);
+        }
+
+        // TODO: Steps 9-10, renaming is not supported by the indexeddb thread yet.
+        Err(Error::NotSupported)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-keypath
+    fn KeyPath(&self, cx: JSContext) -> JSVal {
+        rooted!(in(*cx) let mut key_path = UndefinedValue());
+        key_path_to_jsval(cx, &self.info.borrow().key_path, key_path.handle_mut());
+        key_path.get()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-indexnames
+    fn IndexNames(&self) -> DomRoot<DOMStringList> {
+        let mut names: Vec<DOMString> = self
+            .info
+            .borrow()
+            .indexes
+            .iter()
+            .map(|info| DOMString::from(info.name.clone()))
+            .collect();
+        names.sort();
+        DOMStringList::new(&self.global(), names)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-transaction
+    fn Transaction(&self) -> DomRoot<IDBTransaction> {
+        self.transaction()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-autoincrement
+    fn AutoIncrement(&self) -> bool {
+        self.info.borrow().auto_increment
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-put
+    fn Put(
+        &self,
+        cx: JSContext,
+        value: HandleValue,
+        key: HandleValue,
+    ) -> Fallible<DomRoot<IDBRequest>> {
+        self.add_or_put(cx, value, key, true)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-add
+    fn Add(
+        &self,
+        cx: JSContext,
+        value: HandleValue,
+        key: HandleValue,
+    ) -> Fallible<DomRoot<IDBRequest>> {
+        self.add_or_put(cx, value, key, false)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-delete
+    fn Delete(&self, cx: JSContext, query: HandleValue) -> Fallible<DomRoot<IDBRequest>> {
+        // Steps 1-5.
+        self.check_writable()?;
+
+        // Step 6.
+        let range = convert_value_to_key_range(cx, query, true)?;
+
+        // Step 7.
+        Ok(self.start_request(
+            IDBRequestSource::ObjectStore(Dom::from_ref(self)),
+            AsyncOperation::RemoveItem(range),
+        ))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-clear
+    fn Clear(&self) -> Fallible<DomRoot<IDBRequest>> {
+        // Steps 1-5.
+        self.check_writable()?;
+
+        // Step 6.
+        Ok(self.start_request(
+            IDBRequestSource::ObjectStore(Dom::from_ref(self)),
+            AsyncOperation::Clear,
+        ))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-get
+    fn Get(&self, cx: JSContext, query: HandleValue) -> Fallible<DomRoot<IDBRequest>> {
+        // Steps 1-4.
+        self.check_usable()?;
+
+        // Step 5.
+        let range = convert_value_to_key_range(cx, query, true)?;
+
+        // Step 6.
+        Ok(self.start_request(
+            IDBRequestSource::ObjectStore(Dom::from_ref(self)),
+            AsyncOperation::GetItem { index: None, range },
+        ))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-getkey
+    fn GetKey(&self, cx: JSContext, query: HandleValue) -> Fallible<DomRoot<IDBRequest>> {
+        // Steps 1-4.
+        self.check_usable()?;
+
+        // Step 5.
+        let range = convert_value_to_key_range(cx, query, true)?;
+
+        // Step 6.
+        Ok(self.start_request(
+            IDBRequestSource::ObjectStore(Dom::from_ref(self)),
+            AsyncOperation::GetKey { index: None, range },
+        ))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-getall
+    fn GetAll(
+        &self,
+        cx: JSContext,
+        query: HandleValue,
+        count: Option<u32>,
+    ) -> Fallible<DomRoot<IDBRequest>> {
+        // Steps 1-4.
+        self.check_usable()?;
+
+        // Step 5.
+        let range = convert_value_to_key_range(cx, query, false)?;
+
+        // Step 6.
+        Ok(self.start_request(
+            IDBRequestSource::ObjectStore(Dom::from_ref(self)),
+            AsyncOperation::GetAll {
+                index: None,
+                range,
+                count,
+            },
+        ))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-getallkeys
+    fn GetAllKeys(
+        &self,
+        cx: JSContext,
+        query: HandleValue,
+        count: Option<u32>,
+    ) -> Fallible<DomRoot<IDBRequest>> {
+        // Steps 1-4.
+        self.check_usable()?;
+
+        // Step 5.
+        let range = convert_value_to_key_range(cx, query, false)?;
+
+        // Step 6.
+        Ok(self.start_request(
+            IDBRequestSource::ObjectStore(Dom::from_ref(self)),
+            AsyncOperation::GetAllKeys {
+                index: None,
+                range,
+                count,
+            },
+        ))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-count
+    fn Count(&self, cx: JSContext, query: HandleValue) -> Fallible<DomRoot<IDBRequest>> {
+        // Steps 1-4.
+        self.check_usable()?;
+
+        // Step 5.
+        let range = convert_value_to_key_range(cx, query, false)?;
+
+        // Step 6.
+        Ok(self.start_request(
+            IDBRequestSource::ObjectStore(Dom::from_ref(self)),
+            AsyncOperation::Count { index: None, range },
+        ))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-opencursor
+    fn OpenCursor(
+        &self,
+        cx: JSContext,
+        query: HandleValue,
+        direction: IDBCursorDirection,
+    ) -> Fallible<DomRoot<IDBRequest>> {
+        // Steps 1-4.
+        self.check_usable()?;
+
+        // Step 5.
+        let range = convert_value_to_key_range(cx, query, false)?;
+
+        // Steps 6-10.
+        Ok(self.open_cursor(
+            IDBRequestSource::ObjectStore(Dom::from_ref(self)),
+            None,
+            range,
+            direction,
+            false,
+        ))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-openkeycursor
+    fn OpenKeyCursor(
+        &self,
+        cx: JSContext,
+        query: HandleValue,
+        direction: IDBCursorDirection,
+    ) -> Fallible<DomRoot<IDBRequest>> {
+        // Steps 1-4.
+        self.check_usable()?;
+
+        // Step 5.
+        let range = convert_value_to_key_range(cx, query, false)?;
+
+        // Steps 6-10.
+        Ok(self.open_cursor(
+            IDBRequestSource::ObjectStore(Dom::from_ref(self)),
+            None,
+            range,
+            direction,
+            true,
+        ))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-index
+    fn Index(&self, name: DOMString) -> Fallible<DomRoot<IDBIndex>> {
+        // Steps 1-3.
+        if self.is_deleted() || self.transaction.is_finished() {
+            return Err(Error::InvalidState);
+        }
+
+        // Step 4.
+        let info = self.index_info(&name).ok_or(Error::NotFound)?;
+
+        // Step 5.
+        let mut indexes = self.indexes.borrow_mut();
+        let index = indexes
+            .entry(name)
+            .or_insert_with(|| Dom::from_ref(&*IDBIndex::new(&self.global(), self, info)));
+        Ok(DomRoot::from_ref(&**index))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-createindex
+    fn CreateIndex(
+        &self,
+        name: DOMString,
+        key_path: StringOrStringSequence,
+        options: &IDBIndexParameters,
+    ) -> Fallible<DomRoot<IDBIndex>> {
+        // Steps 1-6.
+        self.check_upgrade_transaction()?;
+
+        // Step 7.
+        if self.index_info(&name).is_some() {
+            return Err(Error::Constraint);
+        }
+
+        // Step 8.
+        let key_path = key_path_from_union(key_path);
+        if !is_valid_key_path(&key_path) {
+            return Err(Error::Syntax);
+        }
+
+        // Steps 9-10.
+        let unique = options.unique;
+        let multi_entry = options.multiEntry;
+
+        // Step 11.
+        if let (&IndexedDBKeyPath::Sequence(_), true) = (&key_path, multi_entry) {
+            return Err(Error::InvalidAccess);
+        }
+
+        // Steps 12-13.
+        let info = IndexedDBIndexInfo {
+            name: name.to_string(),
+            key_path: key_path,
+            unique: unique,
+            multi_entry: multi_entry,
+        };
+
+        // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-createindex
+        // Populate the index with the records already in the object store.
+        let db = self.transaction.database();
+        let global = self.global();
+        let cx = global.get_cx();
+        let (sender, receiver) = ipc::channel(global.time_profiler_chan().clone()).unwrap();
+        db.send(IndexedDBThreadMsg::GetAllRecords(
+            sender,
+            db.origin(),
+            db.name(),
+            self.transaction.id(),
+            self.name(),
+        ));
+        let records = receiver.recv().map_err(|_| Error::Unknown)?;
+        let mut keys = Vec::with_capacity(records.len());
+        for record in records {
+            rooted!(in(*cx) let mut value = UndefinedValue());
+            StructuredCloneData::Vector(record.value).read(&global, value.handle_mut());
+            if let ExtractionResult::Key(index_key) =
+                extract_key(cx, value.handle(), &info.key_path, info.multi_entry)?
+            {
+                let index_keys = match index_key {
+                    IndexedDBKeyType::Array(index_keys) if info.multi_entry => index_keys,
+                    index_key => vec![index_key],
+                };
+                keys.push((record.primary_key, index_keys));
+            }
+        }
+        db.send_sync(|sender| IndexedDBThreadMsg::CreateIndex {
+            sender,
+            origin: db.origin(),
+            name: db.name(),
+            txn: self.transaction.id(),
+            store_name: self.name(),
+            info: info.clone(),
+            keys,
+        })
+        .map_err(exception_for)?;
+
+        // Step 14.
+        self.update_info(|store| store.indexes.push(info.clone()));
+        let index = IDBIndex::new(&global, self, info);
+        self.indexes
+            .borrow_mut()
+            .insert(name, Dom::from_ref(&*index));
+        Ok(index)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbobjectstore-deleteindex
+    fn DeleteIndex(&self, name: DOMString) -> ErrorResult {
+        // Steps 1-6.
+        self.check_upgrade_transaction()?;
+
+        // Step 7.
+        if self.index_info(&name).is_none() {
+            return Err(Error::NotFound);
+        }
+
+        // Steps 8-9.
+        let db = self.transaction.database();
+        db.send_sync(|sender| {
+            IndexedDBThreadMsg::DeleteIndex(
+                sender,
+                db.origin(),
+                db.name(),
+                self.transaction.id(),
+                self.name(),
+                name.to_string(),
+            )
+        })
+        .map_err(exception_for)?;
+        self.update_info(|store| store.indexes.retain(|info| info.name != *name));
+        self.indexes.borrow_mut().remove(&name);
+        Ok(())
+    }
+}
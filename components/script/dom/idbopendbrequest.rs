@@ -0,0 +1,168 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::codegen::Bindings::IDBOpenDBRequestBinding;
+use crate::dom::bindings::codegen::Bindings::IDBOpenDBRequestBinding::IDBOpenDBRequestMethods;
+use crate::dom::bindings::codegen::Bindings::IDBTransactionBinding::IDBTransactionMode;
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject};
+use crate::dom::bindings::root::{DomRoot, MutNullableDom};
+use crate::dom::domexception::DOMErrorName;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::idbdatabase::IDBDatabase;
+use crate::dom::idbrequest::IDBRequest;
+use crate::dom::idbtransaction::IDBTransaction;
+use crate::dom::idbversionchangeevent::IDBVersionChangeEvent;
+use crate::indexed_db::error_name;
+use dom_struct::dom_struct;
+use js::jsval::ObjectValue;
+use js::rust::HandleValue;
+use net_traits::indexeddb_thread::{IndexedDBConnectionEvent, IndexedDBOpenReply};
+
+#[dom_struct]
+pub struct IDBOpenDBRequest {
+    request: IDBRequest,
+    /// The name of the database being opened or deleted.
+    name: String,
+    /// The connection created by this request, once opened.
+    connection: MutNullableDom<IDBDatabase>,
+}
+
+impl IDBOpenDBRequest {
+    fn new_inherited(name: String) -> IDBOpenDBRequest {
+        IDBOpenDBRequest {
+            request: IDBRequest::new_inherited(None, None),
+            name: name,
+            connection: Default::default(),
+        }
+    }
+
+    pub fn new(global: &GlobalScope, name: String) -> DomRoot<IDBOpenDBRequest> {
+        reflect_dom_object(
+            Box::new(IDBOpenDBRequest::new_inherited(name)),
+            global,
+            IDBOpenDBRequestBinding::Wrap,
+        )
+    }
+
+    fn set_connection_result(&self, connection: &IDBDatabase) {
+        let cx = self.global().get_cx();
+        rooted!(in(*cx) let result = ObjectValue(connection.reflector().get_jsobject().get()));
+        self.request.set_result(result.handle());
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#open-a-database>
+    /// <https://w3c.github.io/IndexedDB/#delete-a-database>
+    pub fn handle_open_reply(&self, reply: IndexedDBOpenReply) {
+        let global = self.global();
+        match reply {
+            IndexedDBOpenReply::Blocked {
+                old_version,
+                new_version,
+            } => {
+                IDBVersionChangeEvent::fire(
+                    self.upcast(),
+                    atom!("blocked"),
+                    old_version,
+                    new_version,
+                );
+            },
+            IndexedDBOpenReply::Opened {
+                connection,
+                version,
+                object_stores,
+            } => {
+                let db = IDBDatabase::new(
+                    &global,
+                    self.name.clone(),
+                    version,
+                    connection,
+                    object_stores,
+                );
+                self.connection.set(Some(&db));
+                self.set_connection_result(&db);
+                self.request.fire_success();
+            },
+            IndexedDBOpenReply::Upgrade {
+                connection,
+                transaction,
+                old_version,
+                new_version,
+                object_stores,
+            } => {
+                // https://w3c.github.io/IndexedDB/#run-an-upgrade-transaction
+                let db = IDBDatabase::new(
+                    &global,
+                    self.name.clone(),
+                    new_version,
+                    connection,
+                    object_stores,
+                );
+                self.connection.set(Some(&db));
+                let txn = IDBTransaction::new(
+                    &global,
+                    &db,
+                    IDBTransactionMode::Versionchange,
+                    db.object_store_names(),
+                    transaction,
+                );
+                txn.set_open_request(self);
+                db.start_upgrade(&txn, old_version);
+                self.set_connection_result(&db);
+                self.request.set_transaction(Some(&txn));
+                IDBVersionChangeEvent::fire(
+                    self.upcast(),
+                    atom!("upgradeneeded"),
+                    old_version,
+                    Some(new_version),
+                );
+                txn.deactivate();
+            },
+            IndexedDBOpenReply::Deleted { old_version } => {
+                self.request.set_result(HandleValue::undefined());
+                IDBVersionChangeEvent::fire(self.upcast(), atom!("success"), old_version, None);
+            },
+            IndexedDBOpenReply::Error(error) => {
+                self.request.set_error(error_name(error));
+                self.request.fire_error();
+            },
+        }
+    }
+
+    /// Forwards the events sent to the connection opened by this request.
+    pub fn handle_connection_event(&self, event: IndexedDBConnectionEvent) {
+        if let Some(db) = self.connection.get() {
+            db.handle_connection_event(event);
+        }
+    }
+
+    /// Called once the upgrade transaction started by this request has finished.
+    /// <https://w3c.github.io/IndexedDB/#run-an-upgrade-transaction>
+    pub fn upgrade_finished(&self, aborted: bool) {
+        self.request.set_transaction(None);
+        let db = match self.connection.get() {
+            Some(db) => db,
+            None => return,
+        };
+        db.finish_upgrade(aborted);
+        if !aborted {
+            self.request.fire_success();
+            return;
+        }
+
+        // https://w3c.github.io/IndexedDB/#abort-an-upgrade-transaction
+        db.close_connection();
+        self.connection.set(None);
+        self.request.set_error(DOMErrorName::AbortError);
+        self.request.fire_error();
+    }
+}
+
+impl IDBOpenDBRequestMethods for IDBOpenDBRequest {
+    // https://w3c.github.io/IndexedDB/#dom-idbopendbrequest-onblocked
+    event_handler!(blocked, GetOnblocked, SetOnblocked);
+
+    // https://w3c.github.io/IndexedDB/#dom-idbopendbrequest-onupgradeneeded
+    event_handler!(upgradeneeded, GetOnupgradeneeded, SetOnupgradeneeded);
+}
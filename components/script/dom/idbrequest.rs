@@ -0,0 +1,227 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::codegen::Bindings::IDBRequestBinding;
+use crate::dom::bindings::codegen::Bindings::IDBRequestBinding::IDBRequestMethods;
+use crate::dom::bindings::codegen::Bindings::IDBRequestBinding::IDBRequestReadyState;
+use crate::dom::bindings::codegen::UnionTypes::IDBObjectStoreOrIDBIndexOrIDBCursor;
+use crate::dom::bindings::error::{Error, Fallible};
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject};
+use crate::dom::bindings::root::{Dom, DomRoot, MutNullableDom};
+use crate::dom::domexception::{DOMErrorName, DOMException};
+use crate::dom::event::EventStatus;
+use crate::dom::eventtarget::EventTarget;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::idbcursor::IDBCursor;
+use crate::dom::idbindex::IDBIndex;
+use crate::dom::idbobjectstore::IDBObjectStore;
+use crate::dom::idbtransaction::IDBTransaction;
+use crate::indexed_db::{create_array, error_name, key_type_to_jsval};
+use crate::script_runtime::JSContext;
+use dom_struct::dom_struct;
+use js::jsapi::Heap;
+use js::jsval::{DoubleValue, JSVal, UndefinedValue};
+use js::rust::HandleValue;
+use net_traits::indexeddb_thread::{IndexedDBError, IndexedDBResult};
+use std::cell::Cell;
+
+/// <https://w3c.github.io/IndexedDB/#request-source>
+#[must_root]
+#[derive(JSTraceable, MallocSizeOf)]
+pub enum IDBRequestSource {
+    ObjectStore(Dom<IDBObjectStore>),
+    Index(Dom<IDBIndex>),
+    Cursor(Dom<IDBCursor>),
+}
+
+#[dom_struct]
+pub struct IDBRequest {
+    eventtarget: EventTarget,
+    source: Option<IDBRequestSource>,
+    transaction: MutNullableDom<IDBTransaction>,
+    #[ignore_malloc_size_of = "mozjs"]
+    result: Heap<JSVal>,
+    error: MutNullableDom<DOMException>,
+    ready_state: Cell<IDBRequestReadyState>,
+}
+
+impl IDBRequest {
+    #[allow(unrooted_must_root)]
+    pub fn new_inherited(
+        source: Option<IDBRequestSource>,
+        transaction: Option<&IDBTransaction>,
+    ) -> IDBRequest {
+        IDBRequest {
+            eventtarget: EventTarget::new_inherited(),
+            source: source,
+            transaction: MutNullableDom::new(transaction),
+            result: Heap::default(),
+            error: Default::default(),
+            ready_state: Cell::new(IDBRequestReadyState::Pending),
+        }
+    }
+
+    #[allow(unrooted_must_root)]
+    pub fn new(
+        global: &GlobalScope,
+        source: IDBRequestSource,
+        transaction: &IDBTransaction,
+    ) -> DomRoot<IDBRequest> {
+        reflect_dom_object(
+            Box::new(IDBRequest::new_inherited(Some(source), Some(transaction))),
+            global,
+            IDBRequestBinding::Wrap,
+        )
+    }
+
+    pub fn transaction(&self) -> Option<DomRoot<IDBTransaction>> {
+        self.transaction.get()
+    }
+
+    pub fn set_transaction(&self, transaction: Option<&IDBTransaction>) {
+        self.transaction.set(transaction);
+    }
+
+    /// The object store the request operates on, directly or through an index or a cursor.
+    pub fn object_store(&self) -> Option<DomRoot<IDBObjectStore>> {
+        match self.source {
+            Some(IDBRequestSource::ObjectStore(ref store)) => Some(DomRoot::from_ref(&**store)),
+            Some(IDBRequestSource::Index(ref index)) => Some(index.object_store()),
+            Some(IDBRequestSource::Cursor(ref cursor)) => Some(cursor.object_store()),
+            None => None,
+        }
+    }
+
+    /// Makes the request pending again, when a cursor is iterated.
+    pub fn reset(&self) {
+        self.ready_state.set(IDBRequestReadyState::Pending);
+        self.result.set(UndefinedValue());
+        self.error.set(None);
+    }
+
+    pub fn set_result(&self, result: HandleValue) {
+        self.ready_state.set(IDBRequestReadyState::Done);
+        self.error.set(None);
+        self.result.set(result.get());
+    }
+
+    pub fn set_error(&self, name: DOMErrorName) {
+        self.ready_state.set(IDBRequestReadyState::Done);
+        self.result.set(UndefinedValue());
+        self.error
+            .set(Some(&DOMException::new(&self.global(), name)));
+    }
+
+    /// Sets the result or the error of the request from the reply of the indexeddb thread.
+    /// `cursor` is the cursor being iterated, if any.
+    pub fn handle_result(
+        &self,
+        result: Result<IndexedDBResult, IndexedDBError>,
+        cursor: Option<&IDBCursor>,
+    ) {
+        let result = match result {
+            Ok(result) => result,
+            Err(error) => return self.set_error(error_name(error)),
+        };
+
+        let global = self.global();
+        let cx = global.get_cx();
+        rooted!(in(*cx) let mut value = UndefinedValue());
+        match result {
+            IndexedDBResult::Key(Some(key)) => key_type_to_jsval(cx, &key, value.handle_mut()),
+            IndexedDBResult::Key(None) | IndexedDBResult::Done => {},
+            IndexedDBResult::Keys(keys) => create_array(
+                cx,
+                &keys,
+                |key, rval| key_type_to_jsval(cx, key, rval),
+                value.handle_mut(),
+            ),
+            IndexedDBResult::Count(count) => value.set(DoubleValue(count as f64)),
+            IndexedDBResult::Record(record) => match cursor {
+                Some(cursor) => cursor.set_record(record, value.handle_mut()),
+                None => {
+                    if let (Some(record), Some(store)) = (record, self.object_store()) {
+                        store.read_value(record.value, &record.primary_key, value.handle_mut());
+                    }
+                },
+            },
+            IndexedDBResult::Records(records) => {
+                if let Some(store) = self.object_store() {
+                    create_array(
+                        cx,
+                        &records,
+                        |record, rval| {
+                            store.read_value(record.value.clone(), &record.primary_key, rval)
+                        },
+                        value.handle_mut(),
+                    );
+                }
+            },
+        }
+        self.set_result(value.handle());
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#fire-a-success-event>
+    pub fn fire_success(&self) {
+        self.upcast::<EventTarget>().fire_event(atom!("success"));
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#fire-an-error-event>
+    pub fn fire_error(&self) -> EventStatus {
+        let event = self
+            .upcast::<EventTarget>()
+            .fire_bubbling_cancelable_event(atom!("error"));
+        event.status()
+    }
+}
+
+impl IDBRequestMethods for IDBRequest {
+    // https://w3c.github.io/IndexedDB/#dom-idbrequest-result
+    fn GetResult(&self, _cx: JSContext) -> Fallible<JSVal> {
+        if self.ready_state.get() == IDBRequestReadyState::Pending {
+            return Err(Error::InvalidState);
+        }
+        Ok(self.result.get())
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbrequest-error
+    fn GetError(&self) -> Fallible<Option<DomRoot<DOMException>>> {
+        if self.ready_state.get() == IDBRequestReadyState::Pending {
+            return Err(Error::InvalidState);
+        }
+        Ok(self.error.get())
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbrequest-source
+    fn GetSource(&self) -> Option<IDBObjectStoreOrIDBIndexOrIDBCursor> {
+        self.source.as_ref().map(|source| match *source {
+            IDBRequestSource::ObjectStore(ref store) => {
+                IDBObjectStoreOrIDBIndexOrIDBCursor::IDBObjectStore(DomRoot::from_ref(&**store))
+            },
+            IDBRequestSource::Index(ref index) => {
+                IDBObjectStoreOrIDBIndexOrIDBCursor::IDBIndex(DomRoot::from_ref(&**index))
+            },
+            IDBRequestSource::Cursor(ref cursor) => {
+                IDBObjectStoreOrIDBIndexOrIDBCursor::IDBCursor(DomRoot::from_ref(&**cursor))
+            },
+        })
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbrequest-transaction
+    fn GetTransaction(&self) -> Option<DomRoot<IDBTransaction>> {
+        self.transaction.get()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbrequest-readystate
+    fn ReadyState(&self) -> IDBRequestReadyState {
+        self.ready_state.get()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbrequest-onsuccess
+    event_handler!(success, GetOnsuccess, SetOnsuccess);
+
+    // https://w3c.github.io/IndexedDB/#dom-idbrequest-onerror
+    event_handler!(error, GetOnerror, SetOnerror);
+}
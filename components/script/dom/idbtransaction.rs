@@ -0,0 +1,415 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::IDBTransactionBinding;
+use crate::dom::bindings::codegen::Bindings::IDBTransactionBinding::IDBTransactionMethods;
+use crate::dom::bindings::codegen::Bindings::IDBTransactionBinding::IDBTransactionMode;
+use crate::dom::bindings::error::{Error, ErrorResult, Fallible};
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::refcounted::Trusted;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject};
+use crate::dom::bindings::root::{Dom, DomRoot, MutNullableDom};
+use crate::dom::bindings::str::DOMString;
+use crate::dom::domexception::{DOMErrorName, DOMException};
+use crate::dom::domstringlist::DOMStringList;
+use crate::dom::event::EventStatus;
+use crate::dom::eventtarget::EventTarget;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::idbcursor::IDBCursor;
+use crate::dom::idbdatabase::IDBDatabase;
+use crate::dom::idbobjectstore::IDBObjectStore;
+use crate::dom::idbopendbrequest::IDBOpenDBRequest;
+use crate::dom::idbrequest::IDBRequest;
+use crate::indexed_db::{error_name, route_replies};
+use crate::task_source::{TaskSource, TaskSourceName};
+use dom_struct::dom_struct;
+use ipc_channel::ipc::IpcSender;
+use net_traits::indexeddb_thread::{AsyncOperation, IndexedDBError, IndexedDBObjectStoreInfo};
+use net_traits::indexeddb_thread::{IndexedDBResult, IndexedDBThreadMsg, IndexedDBTxnId};
+use profile_traits::ipc;
+use std::cell::Cell;
+use std::collections::{HashMap, VecDeque};
+
+/// <https://w3c.github.io/IndexedDB/#transaction-lifetime>
+#[derive(Clone, Copy, Debug, JSTraceable, MallocSizeOf, PartialEq)]
+enum TransactionState {
+    Active,
+    Inactive,
+    Committing,
+    Finished,
+}
+
+/// A request waiting for the reply of the indexeddb thread.
+#[must_root]
+#[derive(JSTraceable, MallocSizeOf)]
+struct PendingRequest {
+    request: Dom<IDBRequest>,
+    /// The cursor being iterated by the request, if any.
+    cursor: Option<Dom<IDBCursor>>,
+}
+
+#[dom_struct]
+pub struct IDBTransaction {
+    eventtarget: EventTarget,
+    db: Dom<IDBDatabase>,
+    mode: IDBTransactionMode,
+    /// <https://w3c.github.io/IndexedDB/#transaction-scope>
+    scope: Vec<DOMString>,
+    id: IndexedDBTxnId,
+    state: Cell<TransactionState>,
+    /// <https://w3c.github.io/IndexedDB/#transaction-request-list>
+    requests: DomRefCell<VecDeque<PendingRequest>>,
+    /// The object store handles returned by `objectStore()`.
+    object_stores: DomRefCell<HashMap<DOMString, Dom<IDBObjectStore>>>,
+    error: MutNullableDom<DOMException>,
+    /// The request that started this transaction, if it is an upgrade transaction.
+    open_request: MutNullableDom<IDBOpenDBRequest>,
+    /// The channel on which the indexeddb thread replies to the requests of the transaction,
+    /// in order. Dropped once the transaction has finished.
+    #[ignore_malloc_size_of = "channels are hard"]
+    sender: DomRefCell<Option<IpcSender<Result<IndexedDBResult, IndexedDBError>>>>,
+}
+
+impl IDBTransaction {
+    fn new_inherited(
+        db: &IDBDatabase,
+        mode: IDBTransactionMode,
+        scope: Vec<DOMString>,
+        id: IndexedDBTxnId,
+        sender: IpcSender<Result<IndexedDBResult, IndexedDBError>>,
+    ) -> IDBTransaction {
+        IDBTransaction {
+            eventtarget: EventTarget::new_inherited(),
+            db: Dom::from_ref(db),
+            mode: mode,
+            scope: scope,
+            id: id,
+            state: Cell::new(TransactionState::Active),
+            requests: DomRefCell::new(VecDeque::new()),
+            object_stores: DomRefCell::new(HashMap::new()),
+            error: Default::default(),
+            open_request: Default::default(),
+            sender: DomRefCell::new(Some(sender)),
+        }
+    }
+
+    pub fn new(
+        global: &GlobalScope,
+        db: &IDBDatabase,
+        mode: IDBTransactionMode,
+        scope: Vec<DOMString>,
+        id: IndexedDBTxnId,
+    ) -> DomRoot<IDBTransaction> {
+        let (sender, receiver) = ipc::channel(global.time_profiler_chan().clone()).unwrap();
+        let transaction = reflect_dom_object(
+            Box::new(IDBTransaction::new_inherited(db, mode, scope, id, sender)),
+            global,
+            IDBTransactionBinding::Wrap,
+        );
+        let trusted_transaction = Trusted::new(&*transaction);
+        route_replies(global, receiver, move |result| {
+            trusted_transaction.root().handle_result(result);
+        });
+        db.transaction_started();
+        global.track_indexeddb_transaction(&transaction);
+        transaction
+    }
+
+    pub fn id(&self) -> IndexedDBTxnId {
+        self.id
+    }
+
+    pub fn mode(&self) -> IDBTransactionMode {
+        self.mode
+    }
+
+    pub fn database(&self) -> DomRoot<IDBDatabase> {
+        DomRoot::from_ref(&*self.db)
+    }
+
+    pub fn set_open_request(&self, request: &IDBOpenDBRequest) {
+        self.open_request.set(Some(request));
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.state.get() == TransactionState::Active
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.state.get() == TransactionState::Finished
+    }
+
+    /// Throws a TransactionInactiveError unless the transaction is active.
+    pub fn check_active(&self) -> ErrorResult {
+        if !self.is_active() {
+            return Err(Error::TransactionInactive);
+        }
+        Ok(())
+    }
+
+    /// Makes an active transaction inactive, committing it if it has no pending request.
+    /// <https://w3c.github.io/IndexedDB/#cleanup-indexed-database-transactions>
+    pub fn deactivate(&self) {
+        if self.is_active() {
+            self.state.set(TransactionState::Inactive);
+            self.maybe_commit();
+        }
+    }
+
+    /// Returns the handle of the given object store, creating it if needed.
+    pub fn object_store_for_info(&self, info: IndexedDBObjectStoreInfo) -> DomRoot<IDBObjectStore> {
+        let name = DOMString::from(info.name.clone());
+        let mut object_stores = self.object_stores.borrow_mut();
+        let store = object_stores
+            .entry(name)
+            .or_insert_with(|| Dom::from_ref(&*IDBObjectStore::new(&self.global(), self, info)));
+        DomRoot::from_ref(&**store)
+    }
+
+    pub fn forget_object_store(&self, name: &str) {
+        self.object_stores
+            .borrow_mut()
+            .remove(&DOMString::from(name));
+    }
+
+    /// Sends an operation to the indexeddb thread. The reply will be delivered to `request`.
+    /// <https://w3c.github.io/IndexedDB/#asynchronously-execute-a-request>
+    #[allow(unrooted_must_root)]
+    pub fn execute(
+        &self,
+        request: &IDBRequest,
+        cursor: Option<&IDBCursor>,
+        store_name: &str,
+        operation: AsyncOperation,
+    ) {
+        let sender = match *self.sender.borrow() {
+            Some(ref sender) => sender.clone(),
+            None => return,
+        };
+        self.requests.borrow_mut().push_back(PendingRequest {
+            request: Dom::from_ref(request),
+            cursor: cursor.map(Dom::from_ref),
+        });
+        self.db.send(IndexedDBThreadMsg::Async(
+            sender,
+            self.db.origin(),
+            self.db.name(),
+            store_name.to_owned(),
+            self.id,
+            operation,
+        ));
+    }
+
+    fn handle_result(&self, result: Result<IndexedDBResult, IndexedDBError>) {
+        if self.is_finished() {
+            return;
+        }
+        let (request, cursor) = {
+            let mut requests = self.requests.borrow_mut();
+            let roots = match requests.front() {
+                Some(pending) => (
+                    DomRoot::from_ref(&*pending.request),
+                    pending
+                        .cursor
+                        .as_ref()
+                        .map(|cursor| DomRoot::from_ref(&**cursor)),
+                ),
+                None => return,
+            };
+            requests.pop_front();
+            roots
+        };
+
+        let error = result.as_ref().err().map(|error| error_name(*error));
+        request.handle_result(result, cursor.as_ref().map(|cursor| &**cursor));
+
+        // https://w3c.github.io/IndexedDB/#fire-a-success-event
+        // https://w3c.github.io/IndexedDB/#fire-an-error-event
+        self.state.set(TransactionState::Active);
+        let status = match error {
+            Some(_) => request.fire_error(),
+            None => {
+                request.fire_success();
+                EventStatus::Canceled
+            },
+        };
+        if self.is_active() {
+            self.state.set(TransactionState::Inactive);
+        }
+        if let (Some(error), EventStatus::NotCanceled) = (error, status) {
+            if !self.is_finished() {
+                return self.abort(Some(error));
+            }
+        }
+        self.maybe_commit();
+    }
+
+    fn maybe_commit(&self) {
+        if self.state.get() == TransactionState::Inactive && self.requests.borrow().is_empty() {
+            self.commit();
+        }
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#commit-a-transaction>
+    fn commit(&self) {
+        // Step 1.
+        self.state.set(TransactionState::Committing);
+
+        // Steps 2-4.
+        let global = self.global();
+        let (sender, receiver) = ipc::channel(global.time_profiler_chan().clone()).unwrap();
+        let trusted_transaction = Trusted::new(self);
+        route_replies(&global, receiver, move |result| {
+            trusted_transaction.root().handle_commit(result);
+        });
+        self.db.send(IndexedDBThreadMsg::Commit(
+            sender,
+            self.db.origin(),
+            self.db.name(),
+            self.id,
+        ));
+    }
+
+    fn handle_commit(&self, result: Result<(), IndexedDBError>) {
+        if self.is_finished() {
+            return;
+        }
+        self.finish();
+        match result {
+            Ok(()) => {
+                // Step 5.
+                self.upcast::<EventTarget>().fire_event(atom!("complete"));
+                if let Some(request) = self.open_request.get() {
+                    request.upgrade_finished(false);
+                }
+            },
+            Err(error) => {
+                self.error
+                    .set(Some(&DOMException::new(&self.global(), error_name(error))));
+                self.fire_abort_events();
+            },
+        }
+    }
+
+    fn finish(&self) {
+        self.state.set(TransactionState::Finished);
+        self.sender.borrow_mut().take();
+        self.db.transaction_finished();
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#abort-a-transaction>
+    pub fn abort(&self, error: Option<DOMErrorName>) {
+        // Steps 1-2.
+        let global = self.global();
+        let (sender, receiver) = ipc::channel(global.time_profiler_chan().clone()).unwrap();
+        self.db.send(IndexedDBThreadMsg::Abort(
+            sender,
+            self.db.origin(),
+            self.db.name(),
+            self.id,
+        ));
+        let _ = receiver.recv();
+
+        // Steps 3-4.
+        self.finish();
+        if let Some(error) = error {
+            self.error.set(Some(&DOMException::new(&global, error)));
+        }
+
+        // Steps 5-6.
+        let trusted_transaction = Trusted::new(self);
+        let _ = global.database_access_task_source().queue_with_canceller(
+            task!(abort_indexeddb_transaction: move || {
+                trusted_transaction.root().fire_abort_events();
+            }),
+            &global.task_canceller(TaskSourceName::DatabaseAccess),
+        );
+    }
+
+    fn fire_abort_events(&self) {
+        let requests: Vec<DomRoot<IDBRequest>> = self
+            .requests
+            .borrow_mut()
+            .drain(..)
+            .map(|pending| DomRoot::from_ref(&*pending.request))
+            .collect();
+        for request in requests {
+            request.set_error(DOMErrorName::AbortError);
+            request.fire_error();
+        }
+        self.upcast::<EventTarget>()
+            .fire_bubbling_event(atom!("abort"));
+        if let Some(request) = self.open_request.get() {
+            request.upgrade_finished(true);
+        }
+    }
+}
+
+impl IDBTransactionMethods for IDBTransaction {
+    // https://w3c.github.io/IndexedDB/#dom-idbtransaction-objectstorenames
+    fn ObjectStoreNames(&self) -> DomRoot<DOMStringList> {
+        let names = match self.mode {
+            IDBTransactionMode::Versionchange => self.db.object_store_names(),
+            _ => self.scope.clone(),
+        };
+        DOMStringList::new(&self.global(), names)
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbtransaction-mode
+    fn Mode(&self) -> IDBTransactionMode {
+        self.mode
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbtransaction-db
+    fn Db(&self) -> DomRoot<IDBDatabase> {
+        self.database()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbtransaction-error
+    fn GetError(&self) -> Option<DomRoot<DOMException>> {
+        self.error.get()
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbtransaction-objectstore
+    fn ObjectStore(&self, name: DOMString) -> Fallible<DomRoot<IDBObjectStore>> {
+        // Step 1.
+        if self.is_finished() {
+            return Err(Error::InvalidState);
+        }
+
+        // Step 2.
+        if self.mode != IDBTransactionMode::Versionchange && !self.scope.contains(&name) {
+            return Err(Error::NotFound);
+        }
+        let info = self.db.object_store_info(&name).ok_or(Error::NotFound)?;
+
+        // Step 3.
+        Ok(self.object_store_for_info(info))
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbtransaction-abort
+    fn Abort(&self) -> ErrorResult {
+        // Step 1.
+        match self.state.get() {
+            TransactionState::Committing | TransactionState::Finished => {
+                return Err(Error::InvalidState);
+            },
+            TransactionState::Active | TransactionState::Inactive => {},
+        }
+
+        // Steps 2-3.
+        self.abort(None);
+        Ok(())
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbtransaction-onabort
+    event_handler!(abort, GetOnabort, SetOnabort);
+
+    // https://w3c.github.io/IndexedDB/#dom-idbtransaction-oncomplete
+    event_handler!(complete, GetOncomplete, SetOncomplete);
+
+    // https://w3c.github.io/IndexedDB/#dom-idbtransaction-onerror
+    event_handler!(error, GetOnerror, SetOnerror);
+}
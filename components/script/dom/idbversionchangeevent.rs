@@ -0,0 +1,107 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::codegen::Bindings::EventBinding::EventMethods;
+use crate::dom::bindings::codegen::Bindings::IDBVersionChangeEventBinding;
+use crate::dom::bindings::codegen::Bindings::IDBVersionChangeEventBinding::IDBVersionChangeEventMethods;
+use crate::dom::bindings::error::Fallible;
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject};
+use crate::dom::bindings::root::DomRoot;
+use crate::dom::bindings::str::DOMString;
+use crate::dom::event::{Event, EventBubbles, EventCancelable, EventStatus};
+use crate::dom::eventtarget::EventTarget;
+use crate::dom::globalscope::GlobalScope;
+use dom_struct::dom_struct;
+use servo_atoms::Atom;
+
+#[dom_struct]
+pub struct IDBVersionChangeEvent {
+    event: Event,
+    old_version: u64,
+    new_version: Option<u64>,
+}
+
+impl IDBVersionChangeEvent {
+    fn new_inherited(old_version: u64, new_version: Option<u64>) -> IDBVersionChangeEvent {
+        IDBVersionChangeEvent {
+            event: Event::new_inherited(),
+            old_version: old_version,
+            new_version: new_version,
+        }
+    }
+
+    pub fn new(
+        global: &GlobalScope,
+        type_: Atom,
+        bubbles: EventBubbles,
+        cancelable: EventCancelable,
+        old_version: u64,
+        new_version: Option<u64>,
+    ) -> DomRoot<IDBVersionChangeEvent> {
+        let ev = reflect_dom_object(
+            Box::new(IDBVersionChangeEvent::new_inherited(
+                old_version,
+                new_version,
+            )),
+            global,
+            IDBVersionChangeEventBinding::Wrap,
+        );
+        {
+            let event = ev.upcast::<Event>();
+            event.init_event(type_, bool::from(bubbles), bool::from(cancelable));
+        }
+        ev
+    }
+
+    /// <https://w3c.github.io/IndexedDB/#fire-a-version-change-event>
+    pub fn fire(
+        target: &EventTarget,
+        type_: Atom,
+        old_version: u64,
+        new_version: Option<u64>,
+    ) -> EventStatus {
+        let event = IDBVersionChangeEvent::new(
+            &target.global(),
+            type_,
+            EventBubbles::DoesNotBubble,
+            EventCancelable::NotCancelable,
+            old_version,
+            new_version,
+        );
+        event.upcast::<Event>().fire(target)
+    }
+
+    pub fn Constructor(
+        global: &GlobalScope,
+        type_: DOMString,
+        init: &IDBVersionChangeEventBinding::IDBVersionChangeEventInit,
+    ) -> Fallible<DomRoot<IDBVersionChangeEvent>> {
+        Ok(IDBVersionChangeEvent::new(
+            global,
+            Atom::from(type_),
+            EventBubbles::from(init.parent.bubbles),
+            EventCancelable::from(init.parent.cancelable),
+            init.oldVersion,
+            init.newVersion,
+        ))
+    }
+}
+
+impl IDBVersionChangeEventMethods for IDBVersionChangeEvent {
+    // https://w3c.github.io/IndexedDB/#dom-idbversionchangeevent-oldversion
+    fn OldVersion(&self) -> u64 {
+        self.old_version
+    }
+
+    // https://w3c.github.io/IndexedDB/#dom-idbversionchangeevent-newversion
+    fn GetNewVersion(&self) -> Option<u64> {
+        self.new_version
+    }
+
+    // https://dom.spec.whatwg.org/#dom-event-istrusted
+    fn IsTrusted(&self) -> bool {
+        self.event.IsTrusted()
+    }
+}
@@ -0,0 +1,312 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::compartments::InCompartment;
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::ImageBitmapBinding;
+use crate::dom::bindings::codegen::Bindings::ImageBitmapBinding::ImageBitmapMethods;
+use crate::dom::bindings::codegen::Bindings::ImageBitmapBinding::ImageBitmapOptions;
+use crate::dom::bindings::codegen::Bindings::ImageBitmapBinding::ImageBitmapSource;
+use crate::dom::bindings::codegen::Bindings::ImageBitmapBinding::ImageOrientation;
+use crate::dom::bindings::error::{Error, Fallible};
+use crate::dom::bindings::reflector::{reflect_dom_object, Reflector};
+use crate::dom::bindings::root::DomRoot;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::htmlcanvaselement::utils as canvas_utils;
+use crate::dom::node::window_from_node;
+use crate::dom::promise::Promise;
+use dom_struct::dom_struct;
+use euclid::default::{Point2D, Rect, Size2D};
+use net_traits::image::base::load_from_memory;
+use net_traits::image_cache::ImageResponse;
+use pixels::PixelFormat;
+use script_traits::transferable::ImageBitmapImpl;
+use std::cell::Cell;
+use std::rc::Rc;
+
+// https://html.spec.whatwg.org/multipage/#imagebitmap
+#[dom_struct]
+pub struct ImageBitmap {
+    reflector_: Reflector,
+    width: Cell<u32>,
+    height: Cell<u32>,
+    /// The bitmap data as premultiplied BGRA, or `None` once the bitmap was
+    /// closed or transferred.
+    bitmap_data: DomRefCell<Option<Vec<u8>>>,
+    /// <https://html.spec.whatwg.org/multipage/#concept-imagebitmap-origin-clean>
+    origin_clean: bool,
+}
+
+impl ImageBitmap {
+    fn new_inherited(size: Size2D<u32>, data: Vec<u8>, origin_clean: bool) -> ImageBitmap {
+        ImageBitmap {
+            reflector_: Reflector::new(),
+            width: Cell::new(size.width),
+            height: Cell::new(size.height),
+            bitmap_data: DomRefCell::new(Some(data)),
+            origin_clean,
+        }
+    }
+
+    pub fn new(
+        global: &GlobalScope,
+        size: Size2D<u32>,
+        data: Vec<u8>,
+        origin_clean: bool,
+    ) -> DomRoot<ImageBitmap> {
+        reflect_dom_object(
+            Box::new(ImageBitmap::new_inherited(size, data, origin_clean)),
+            global,
+            ImageBitmapBinding::Wrap,
+        )
+    }
+
+    /// Creates the bitmap received by the global an `ImageBitmap` was transferred to.
+    pub fn new_transferred(global: &GlobalScope, bitmap: ImageBitmapImpl) -> DomRoot<ImageBitmap> {
+        ImageBitmap::new(
+            global,
+            Size2D::new(bitmap.width, bitmap.height),
+            bitmap.data,
+            bitmap.origin_clean,
+        )
+    }
+
+    pub fn size(&self) -> Size2D<u32> {
+        Size2D::new(self.width.get(), self.height.get())
+    }
+
+    /// A copy of the premultiplied BGRA bitmap data, or `None` if the bitmap was detached.
+    pub fn bitmap_data(&self) -> Option<Vec<u8>> {
+        self.bitmap_data.borrow().clone()
+    }
+
+    pub fn origin_is_clean(&self) -> bool {
+        self.origin_clean
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#detached>
+    pub fn is_detached(&self) -> bool {
+        self.bitmap_data.borrow().is_none()
+    }
+
+    /// The data of this bitmap as it is sent to another global, for serialization.
+    pub fn serialize(&self) -> Option<ImageBitmapImpl> {
+        let data = self.bitmap_data()?;
+        Some(ImageBitmapImpl {
+            width: self.width.get(),
+            height: self.height.get(),
+            data,
+            origin_clean: self.origin_clean,
+        })
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#the-imagebitmap-interface:transfer-steps>
+    pub fn transfer(&self) -> Option<ImageBitmapImpl> {
+        let bitmap = self.serialize();
+        self.detach();
+        bitmap
+    }
+
+    fn detach(&self) {
+        *self.bitmap_data.borrow_mut() = None;
+        self.width.set(0);
+        self.height.set(0);
+    }
+}
+
+impl ImageBitmapMethods for ImageBitmap {
+    // https://html.spec.whatwg.org/multipage/#dom-imagebitmap-width
+    fn Width(&self) -> u32 {
+        self.width.get()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-imagebitmap-height
+    fn Height(&self) -> u32 {
+        self.height.get()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-imagebitmap-close
+    fn Close(&self) {
+        self.detach();
+    }
+}
+
+/// The crop rectangle given to `createImageBitmap(image, sx, sy, sw, sh)`.
+pub struct CropRect {
+    pub sx: i32,
+    pub sy: i32,
+    pub sw: i32,
+    pub sh: i32,
+}
+
+/// Shared implementation of `createImageBitmap` for windows and workers.
+///
+/// <https://html.spec.whatwg.org/multipage/#dom-createimagebitmap>
+pub fn create_image_bitmap(
+    global: &GlobalScope,
+    image: ImageBitmapSource,
+    crop: Option<CropRect>,
+    options: &ImageBitmapOptions,
+    comp: InCompartment,
+) -> Rc<Promise> {
+    let promise = Promise::new_in_current_compartment(global, comp);
+    match image_bitmap_from_source(global, image, crop, options) {
+        Ok(bitmap) => promise.resolve_native(&bitmap),
+        Err(error) => promise.reject_error(error),
+    }
+    promise
+}
+
+fn image_bitmap_from_source(
+    global: &GlobalScope,
+    image: ImageBitmapSource,
+    crop: Option<CropRect>,
+    options: &ImageBitmapOptions,
+) -> Fallible<DomRoot<ImageBitmap>> {
+    // Step 1.
+    if let Some(ref crop) = crop {
+        if crop.sw == 0 || crop.sh == 0 {
+            return Err(Error::Range(
+                "The crop width and height must not be 0".to_owned(),
+            ));
+        }
+    }
+
+    // Step 2.
+    if options.resizeWidth == Some(0) || options.resizeHeight == Some(0) {
+        return Err(Error::InvalidState);
+    }
+
+    // Step 3, 4 and 6: check the usability of the image and take a copy of its pixels.
+    let (data, size, origin_clean) = source_pixels(global, &image)?;
+
+    // The source rectangle, which may lie partly outside the image.
+    let source_rect = match crop {
+        Some(crop) => Rect::new(
+            Point2D::new(
+                crop.sx.min(crop.sx + crop.sw),
+                crop.sy.min(crop.sy + crop.sh),
+            ),
+            Size2D::new(crop.sw.abs(), crop.sh.abs()),
+        ),
+        None => Rect::new(Point2D::zero(), size.to_i32()),
+    };
+
+    // https://html.spec.whatwg.org/multipage/#cropped-to-the-source-rectangle-with-formatting
+    let source_width = source_rect.size.width as u32;
+    let source_height = source_rect.size.height as u32;
+    let output_size = match (options.resizeWidth, options.resizeHeight) {
+        (Some(width), Some(height)) => Size2D::new(width, height),
+        (Some(width), None) => Size2D::new(
+            width,
+            (source_height as f64 * width as f64 / source_width as f64).ceil() as u32,
+        ),
+        (None, Some(height)) => Size2D::new(
+            (source_width as f64 * height as f64 / source_height as f64).ceil() as u32,
+            height,
+        ),
+        (None, None) => Size2D::new(source_width, source_height),
+    };
+    if output_size.width == 0 || output_size.height == 0 {
+        return Err(Error::InvalidState);
+    }
+
+    let flip_y = options.imageOrientation == ImageOrientation::FlipY;
+    let data = crop_and_scale(&data, size, source_rect, output_size, flip_y);
+    Ok(ImageBitmap::new(global, output_size, data, origin_clean))
+}
+
+/// Returns the pixels of `image` as premultiplied BGRA, along with their size and
+/// whether they are origin-clean.
+fn source_pixels(
+    global: &GlobalScope,
+    image: &ImageBitmapSource,
+) -> Fallible<(Vec<u8>, Size2D<u32>, bool)> {
+    match *image {
+        ImageBitmapSource::HTMLImageElement(ref image) => {
+            // An image that is not fully decoded is not usable.
+            let url = image.get_url().ok_or(Error::InvalidState)?;
+            let window = window_from_node(&**image);
+            let img = match canvas_utils::request_image_from_cache(&window, url) {
+                ImageResponse::Loaded(img, _) => img,
+                ImageResponse::PlaceholderLoaded(..) |
+                ImageResponse::MetadataLoaded(_) |
+                ImageResponse::None => return Err(Error::InvalidState),
+            };
+            let mut data = match img.format {
+                PixelFormat::BGRA8 => img.first_frame().to_vec(),
+                _ => return Err(Error::InvalidState),
+            };
+            pixels::rgba8_premultiply_inplace(&mut data);
+            let origin_clean = image.same_origin(global.origin());
+            Ok((data, Size2D::new(img.width, img.height), origin_clean))
+        },
+        ImageBitmapSource::HTMLCanvasElement(ref canvas) => {
+            let (data, size) = canvas.fetch_all_data().ok_or(Error::InvalidState)?;
+            let data = data
+                .map(|data| data.to_vec())
+                .unwrap_or_else(|| vec![0; size.area() as usize * 4]);
+            Ok((data, size, canvas.origin_is_clean()))
+        },
+        ImageBitmapSource::ImageBitmap(ref bitmap) => {
+            let data = bitmap.bitmap_data().ok_or(Error::InvalidState)?;
+            Ok((data, bitmap.size(), bitmap.origin_is_clean()))
+        },
+        // TODO: CSS images can only be drawn from paint worklets, which don't
+        // expose createImageBitmap.
+        ImageBitmapSource::CSSStyleValue(_) => Err(Error::NotSupported),
+        ImageBitmapSource::Blob(ref blob) => {
+            let bytes = blob.get_bytes().map_err(|_| Error::InvalidState)?;
+            let img = load_from_memory(&bytes).ok_or(Error::InvalidState)?;
+            let mut data = match img.format {
+                PixelFormat::BGRA8 => img.first_frame().to_vec(),
+                _ => return Err(Error::InvalidState),
+            };
+            pixels::rgba8_premultiply_inplace(&mut data);
+            Ok((data, Size2D::new(img.width, img.height), true))
+        },
+        ImageBitmapSource::ImageData(ref image_data) => {
+            let mut data = image_data.to_shared_memory().to_vec();
+            pixels::rgba8_byte_swap_and_premultiply_inplace(&mut data);
+            Ok((data, image_data.get_size(), true))
+        },
+    }
+}
+
+/// Copies `source_rect` out of the `size` sized BGRA `data`, scaling it to
+/// `output_size` with nearest neighbour sampling. Parts of the source rectangle
+/// outside of the image are transparent black.
+fn crop_and_scale(
+    data: &[u8],
+    size: Size2D<u32>,
+    source_rect: Rect<i32>,
+    output_size: Size2D<u32>,
+    flip_y: bool,
+) -> Vec<u8> {
+    // TODO: honour resizeQuality rather than always sampling the nearest pixel.
+    let mut output = vec![0; output_size.area() as usize * 4];
+    let x_scale = source_rect.size.width as f64 / output_size.width as f64;
+    let y_scale = source_rect.size.height as f64 / output_size.height as f64;
+    for y in 0..output_size.height {
+        let source_y = source_rect.origin.y as i64 + ((y as f64 + 0.5) * y_scale) as i64;
+        if source_y < 0 || source_y >= size.height as i64 {
+            continue;
+        }
+        let output_y = if flip_y {
+            output_size.height - 1 - y
+        } else {
+            y
+        };
+        for x in 0..output_size.width {
+            let source_x = source_rect.origin.x as i64 + ((x as f64 + 0.5) * x_scale) as i64;
+            if source_x < 0 || source_x >= size.width as i64 {
+                continue;
+            }
+            let from = (source_y as usize * size.width as usize + source_x as usize) * 4;
+            let to = (output_y as usize * output_size.width as usize + x as usize) * 4;
+            output[to..to + 4].copy_from_slice(&data[from..from + 4]);
+        }
+    }
+    output
+}
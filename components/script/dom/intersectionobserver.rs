@@ -0,0 +1,453 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::callback::ExceptionHandling;
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::IntersectionObserverBinding;
+use crate::dom::bindings::codegen::Bindings::IntersectionObserverBinding::IntersectionObserverCallback;
+use crate::dom::bindings::codegen::Bindings::IntersectionObserverBinding::IntersectionObserverInit;
+use crate::dom::bindings::codegen::Bindings::IntersectionObserverBinding::IntersectionObserverMethods;
+use crate::dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
+use crate::dom::bindings::codegen::UnionTypes::DoubleOrDoubleSequence;
+use crate::dom::bindings::error::{Error, Fallible};
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot};
+use crate::dom::bindings::str::DOMString;
+use crate::dom::bindings::utils::to_frozen_array;
+use crate::dom::document::Document;
+use crate::dom::element::Element;
+use crate::dom::intersectionobserverentry::IntersectionObserverEntry;
+use crate::dom::node::{window_from_node, Node};
+use crate::dom::window::Window;
+use crate::script_runtime::JSContext;
+use app_units::Au;
+use dom_struct::dom_struct;
+use euclid::default::{Point2D, Rect, Size2D};
+use js::jsval::JSVal;
+use std::cell::Cell;
+use std::rc::Rc;
+
+/// A component of the root margin of an observer.
+#[derive(Clone, Copy, JSTraceable, MallocSizeOf, PartialEq)]
+enum RootMarginLength {
+    Px(f64),
+    Percent(f64),
+}
+
+impl RootMarginLength {
+    fn parse(token: &str) -> Option<RootMarginLength> {
+        if token.ends_with("px") {
+            let value = token[..token.len() - 2].parse::<f64>().ok()?;
+            Some(RootMarginLength::Px(value))
+        } else if token.ends_with('%') {
+            let value = token[..token.len() - 1].parse::<f64>().ok()?;
+            Some(RootMarginLength::Percent(value))
+        } else if token == "0" {
+            Some(RootMarginLength::Px(0.))
+        } else {
+            None
+        }
+    }
+
+    /// Resolve this length against the width or height of the root.
+    fn resolve(&self, basis: f64) -> f64 {
+        match *self {
+            RootMarginLength::Px(value) => value,
+            RootMarginLength::Percent(value) => basis * value / 100.,
+        }
+    }
+
+    fn serialize(&self) -> String {
+        match *self {
+            RootMarginLength::Px(value) => format!("{}px", value),
+            RootMarginLength::Percent(value) => format!("{}%", value),
+        }
+    }
+}
+
+/// <https://w3c.github.io/IntersectionObserver/#parse-a-root-margin>
+fn parse_root_margin(margin: &str) -> Option<[RootMarginLength; 4]> {
+    let lengths = margin
+        .split_whitespace()
+        .map(RootMarginLength::parse)
+        .collect::<Option<Vec<_>>>()?;
+    match *lengths.as_slice() {
+        [all] => Some([all, all, all, all]),
+        [vertical, horizontal] => Some([vertical, horizontal, vertical, horizontal]),
+        [top, horizontal, bottom] => Some([top, horizontal, bottom, horizontal]),
+        [top, right, bottom, left] => Some([top, right, bottom, left]),
+        _ => None,
+    }
+}
+
+/// The intersection of two rectangles, which unlike `Rect::intersection`
+/// includes rectangles that only share an edge.
+fn edge_inclusive_intersection(a: &Rect<f64>, b: &Rect<f64>) -> Option<Rect<f64>> {
+    let left = a.min_x().max(b.min_x());
+    let top = a.min_y().max(b.min_y());
+    let right = a.max_x().min(b.max_x());
+    let bottom = a.max_y().min(b.max_y());
+    if left > right || top > bottom {
+        return None;
+    }
+    Some(Rect::new(
+        Point2D::new(left, top),
+        Size2D::new(right - left, bottom - top),
+    ))
+}
+
+fn rect_to_px(rect: Rect<Au>) -> Rect<f64> {
+    Rect::new(
+        Point2D::new(rect.origin.x.to_f64_px(), rect.origin.y.to_f64_px()),
+        Size2D::new(rect.size.width.to_f64_px(), rect.size.height.to_f64_px()),
+    )
+}
+
+/// The padding box of `element`, relative to the initial containing block
+/// of its document.
+fn padding_box(element: &Element) -> Rect<f64> {
+    let node = element.upcast::<Node>();
+    let border_box = rect_to_px(node.bounding_content_box_or_zero());
+    let client_rect = node.client_rect();
+    Rect::new(
+        Point2D::new(
+            border_box.origin.x + client_rect.origin.x as f64,
+            border_box.origin.y + client_rect.origin.y as f64,
+        ),
+        Size2D::new(
+            client_rect.size.width as f64,
+            client_rect.size.height as f64,
+        ),
+    )
+}
+
+/// <https://w3c.github.io/IntersectionObserver/#intersectionobserverregistration>
+#[derive(JSTraceable, MallocSizeOf)]
+#[must_root]
+struct IntersectionObserverRegistration {
+    target: Dom<Element>,
+    previous_threshold_index: Cell<i32>,
+    previous_is_intersecting: Cell<bool>,
+}
+
+/// The geometry of a target relative to the root of an observer.
+struct IntersectionGeometry {
+    root_bounds: Rect<f64>,
+    target_rect: Rect<f64>,
+    intersection_rect: Option<Rect<f64>>,
+}
+
+/// <https://w3c.github.io/IntersectionObserver/#intersection-observer-interface>
+#[dom_struct]
+pub struct IntersectionObserver {
+    reflector_: Reflector,
+    #[ignore_malloc_size_of = "can't measure Rc values"]
+    callback: Rc<IntersectionObserverCallback>,
+    /// <https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-root>
+    root: Option<Dom<Element>>,
+    /// <https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-rootmargin-slot>
+    root_margin: [RootMarginLength; 4],
+    /// <https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-thresholds>
+    thresholds: Vec<f64>,
+    /// <https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-queuedentries-slot>
+    queued_entries: DomRefCell<Vec<Dom<IntersectionObserverEntry>>>,
+    /// <https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-observationtargets-slot>
+    observation_targets: DomRefCell<Vec<IntersectionObserverRegistration>>,
+}
+
+impl IntersectionObserver {
+    fn new_inherited(
+        callback: Rc<IntersectionObserverCallback>,
+        root: Option<&Element>,
+        root_margin: [RootMarginLength; 4],
+        thresholds: Vec<f64>,
+    ) -> IntersectionObserver {
+        IntersectionObserver {
+            reflector_: Reflector::new(),
+            callback,
+            root: root.map(Dom::from_ref),
+            root_margin,
+            thresholds,
+            queued_entries: DomRefCell::new(vec![]),
+            observation_targets: DomRefCell::new(vec![]),
+        }
+    }
+
+    /// <https://w3c.github.io/IntersectionObserver/#initialize-a-new-intersectionobserver>
+    pub fn Constructor(
+        window: &Window,
+        callback: Rc<IntersectionObserverCallback>,
+        options: &IntersectionObserverInit,
+    ) -> Fallible<DomRoot<IntersectionObserver>> {
+        // Steps 3-4.
+        let root_margin = parse_root_margin(&options.rootMargin).ok_or(Error::Syntax)?;
+
+        // Steps 5-7.
+        let mut thresholds = match options.threshold {
+            None => vec![0.],
+            Some(DoubleOrDoubleSequence::Double(threshold)) => vec![*threshold],
+            Some(DoubleOrDoubleSequence::DoubleSequence(ref thresholds)) => {
+                thresholds.iter().map(|threshold| **threshold).collect()
+            },
+        };
+        if thresholds
+            .iter()
+            .any(|threshold| *threshold < 0. || *threshold > 1.)
+        {
+            return Err(Error::Range(
+                "Thresholds must be in the range 0.0 to 1.0".to_owned(),
+            ));
+        }
+        if thresholds.is_empty() {
+            thresholds.push(0.);
+        }
+        thresholds.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        let observer = reflect_dom_object(
+            Box::new(IntersectionObserver::new_inherited(
+                callback,
+                options.root.as_deref(),
+                root_margin,
+                thresholds,
+            )),
+            window,
+            IntersectionObserverBinding::Wrap,
+        );
+        Ok(observer)
+    }
+
+    /// Apply the root margin of this observer to the bounds of its root.
+    fn apply_root_margin(&self, rect: Rect<f64>) -> Rect<f64> {
+        let [top, right, bottom, left] = self.root_margin;
+        let width = rect.size.width;
+        let height = rect.size.height;
+        let top = top.resolve(height);
+        let right = right.resolve(width);
+        let bottom = bottom.resolve(height);
+        let left = left.resolve(width);
+        Rect::new(
+            Point2D::new(rect.origin.x - left, rect.origin.y - top),
+            Size2D::new(
+                (width + left + right).max(0.),
+                (height + top + bottom).max(0.),
+            ),
+        )
+    }
+
+    /// <https://w3c.github.io/IntersectionObserver/#compute-the-intersection>
+    fn compute_geometry(&self, target: &Element) -> Option<IntersectionGeometry> {
+        let target_node = target.upcast::<Node>();
+        if !target_node.is_connected() {
+            return None;
+        }
+        let target_rect = rect_to_px(target_node.bounding_content_box()?);
+
+        if let Some(ref root) = self.root {
+            // The target must be a descendant of the root, in the same
+            // document, for there to be an intersection.
+            let root_node = root.upcast::<Node>();
+            if !root_node.is_connected() ||
+                root_node == target_node ||
+                !root_node.is_inclusive_ancestor_of(target_node)
+            {
+                return None;
+            }
+            let root_bounds = self.apply_root_margin(padding_box(root));
+            return Some(IntersectionGeometry {
+                root_bounds,
+                target_rect,
+                intersection_rect: edge_inclusive_intersection(&target_rect, &root_bounds),
+            });
+        }
+
+        // With the implicit root, the target is clipped by the viewport of
+        // its document, then by each iframe it is nested in, up to the
+        // top-level document reachable from this script thread.
+        let mut window = window_from_node(target);
+        let mut intersection_rect = Some(target_rect);
+        loop {
+            let viewport = rect_to_px(window.current_viewport());
+            let frame = window.window_proxy().frame_element().map(DomRoot::from_ref);
+            let frame = match frame {
+                Some(frame) => frame,
+                None => {
+                    let root_bounds = self.apply_root_margin(viewport);
+                    return Some(IntersectionGeometry {
+                        root_bounds,
+                        target_rect,
+                        intersection_rect: intersection_rect
+                            .and_then(|rect| edge_inclusive_intersection(&rect, &root_bounds)),
+                    });
+                },
+            };
+
+            // Map the intersection into the coordinates of the document the
+            // iframe lives in, clipping it to the content of the iframe.
+            let frame_box = padding_box(&frame);
+            intersection_rect = intersection_rect
+                .and_then(|rect| edge_inclusive_intersection(&rect, &viewport))
+                .map(|rect| {
+                    rect.translate(frame_box.origin.to_vector() - viewport.origin.to_vector())
+                })
+                .and_then(|rect| edge_inclusive_intersection(&rect, &frame_box));
+            window = window_from_node(&*frame);
+        }
+    }
+
+    /// The document whose observer list holds this observer while it has targets.
+    fn document(&self) -> DomRoot<Document> {
+        self.global().as_window().Document()
+    }
+
+    /// <https://w3c.github.io/IntersectionObserver/#run-the-update-intersection-observations-steps>
+    ///
+    /// Returns whether any entry was queued.
+    pub fn update_intersection_observations(&self, time: f64) -> bool {
+        let window = self.global();
+        let window = window.as_window();
+        let mut queued = false;
+        for registration in self.observation_targets.borrow().iter() {
+            let target = &*registration.target;
+
+            // Steps 2.2.1-2.2.9.
+            let geometry = self.compute_geometry(target);
+            let (root_bounds, target_rect, intersection_rect) = match geometry {
+                Some(geometry) => (
+                    Some(geometry.root_bounds),
+                    geometry.target_rect,
+                    geometry.intersection_rect,
+                ),
+                None => (None, Rect::zero(), None),
+            };
+            let is_intersecting = intersection_rect.is_some();
+            let intersection_rect = intersection_rect.unwrap_or_else(Rect::zero);
+
+            // Step 2.2.10.
+            let target_area = target_rect.size.width * target_rect.size.height;
+            let intersection_ratio = if target_area > 0. {
+                intersection_rect.size.width * intersection_rect.size.height / target_area
+            } else if is_intersecting {
+                1.
+            } else {
+                0.
+            };
+
+            // Step 2.2.11.
+            let threshold_index = self
+                .thresholds
+                .iter()
+                .position(|threshold| *threshold > intersection_ratio)
+                .unwrap_or(self.thresholds.len()) as i32;
+
+            // Steps 2.2.12-2.2.16.
+            if threshold_index != registration.previous_threshold_index.get() ||
+                is_intersecting != registration.previous_is_intersecting.get()
+            {
+                let entry = IntersectionObserverEntry::new(
+                    window,
+                    time,
+                    root_bounds,
+                    target_rect,
+                    intersection_rect,
+                    is_intersecting,
+                    intersection_ratio,
+                    target,
+                );
+                self.queued_entries
+                    .borrow_mut()
+                    .push(Dom::from_ref(&*entry));
+                queued = true;
+            }
+            registration.previous_threshold_index.set(threshold_index);
+            registration.previous_is_intersecting.set(is_intersecting);
+        }
+        queued
+    }
+
+    /// Invoke the callback with the queued entries, if any.
+    /// <https://w3c.github.io/IntersectionObserver/#notify-intersection-observers-algo>
+    pub fn invoke_callback_if_necessary(&self) {
+        // Steps 3.1-3.4.
+        let entries = self.TakeRecords();
+        if entries.is_empty() {
+            return;
+        }
+
+        // Step 3.5.
+        let _ = self
+            .callback
+            .Call_(self, entries, self, ExceptionHandling::Report);
+    }
+}
+
+impl IntersectionObserverMethods for IntersectionObserver {
+    // https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-root
+    fn GetRoot(&self) -> Option<DomRoot<Element>> {
+        self.root.as_ref().map(|root| DomRoot::from_ref(&**root))
+    }
+
+    // https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-rootmargin
+    fn RootMargin(&self) -> DOMString {
+        let margins: Vec<String> = self
+            .root_margin
+            .iter()
+            .map(RootMarginLength::serialize)
+            .collect();
+        DOMString::from_string(margins.join(" "))
+    }
+
+    // https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-thresholds
+    fn Thresholds(&self, cx: JSContext) -> JSVal {
+        to_frozen_array(&self.thresholds, cx)
+    }
+
+    // https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-observe
+    #[allow(unrooted_must_root)]
+    fn Observe(&self, target: &Element) {
+        // Step 1.
+        let mut observation_targets = self.observation_targets.borrow_mut();
+        if observation_targets
+            .iter()
+            .any(|registration| &*registration.target == target)
+        {
+            return;
+        }
+        if observation_targets.is_empty() {
+            self.document().add_intersection_observer(self);
+        }
+
+        // Steps 2-4.
+        observation_targets.push(IntersectionObserverRegistration {
+            target: Dom::from_ref(target),
+            previous_threshold_index: Cell::new(-1),
+            previous_is_intersecting: Cell::new(false),
+        });
+    }
+
+    // https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-unobserve
+    fn Unobserve(&self, target: &Element) {
+        let mut observation_targets = self.observation_targets.borrow_mut();
+        let observed = !observation_targets.is_empty();
+        observation_targets.retain(|registration| &*registration.target != target);
+        if observed && observation_targets.is_empty() {
+            self.document().remove_intersection_observer(self);
+        }
+    }
+
+    // https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-disconnect
+    fn Disconnect(&self) {
+        self.observation_targets.borrow_mut().clear();
+        self.document().remove_intersection_observer(self);
+    }
+
+    // https://w3c.github.io/IntersectionObserver/#dom-intersectionobserver-takerecords
+    fn TakeRecords(&self) -> Vec<DomRoot<IntersectionObserverEntry>> {
+        self.queued_entries
+            .borrow_mut()
+            .drain(..)
+            .map(|entry| DomRoot::from_ref(&*entry))
+            .collect()
+    }
+}
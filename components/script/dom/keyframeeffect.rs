@@ -0,0 +1,613 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::animationeffect::{easing_epsilon, parse_easing, AnimationEffect, Timing};
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::KeyframeEffectBinding;
+use crate::dom::bindings::codegen::Bindings::KeyframeEffectBinding::KeyframeEffectMethods;
+use crate::dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
+use crate::dom::bindings::codegen::UnionTypes::UnrestrictedDoubleOrKeyframeEffectOptions;
+use crate::dom::bindings::conversions::{ConversionResult, FromJSValConvertible};
+use crate::dom::bindings::conversions::{StringificationBehavior, ToJSValConvertible};
+use crate::dom::bindings::error::{Error, ErrorResult, Fallible};
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::mozmap::MozMap;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject};
+use crate::dom::bindings::root::{DomRoot, MutNullableDom};
+use crate::dom::bindings::str::DOMString;
+use crate::dom::bindings::utils::set_dictionary_property;
+use crate::dom::element::Element;
+use crate::dom::window::Window;
+use crate::script_runtime::JSContext;
+use dom_struct::dom_struct;
+use js::jsapi::{HandleValueArray, JSContext as RawJSContext, JSObject};
+use js::jsapi::{JS_NewArrayObject, JS_NewPlainObject};
+use js::jsval::{ObjectValue, UndefinedValue};
+use js::rust::wrappers::JS_IsArrayObject;
+use js::rust::{HandleObject, HandleValue};
+use std::ptr::NonNull;
+use style::animation::{timing_function_output, KeyframeEffectSample};
+use style::properties::{parse_one_declaration_into, SourcePropertyDeclaration};
+use style::properties::{Importance, LonghandId, PropertyDeclaration, PropertyDeclarationBlock};
+use style::properties::{PropertyDeclarationId, PropertyId};
+use style::values::computed::TimingFunction;
+use style::values::generics::easing::{TimingFunction as GenericTimingFunction, TimingKeyword};
+use style_traits::{ParsingMode, ToCss};
+
+/// A processed keyframe.
+///
+/// <https://drafts.csswg.org/web-animations/#keyframe>
+#[derive(Clone, JSTraceable, MallocSizeOf)]
+struct Keyframe {
+    /// <https://drafts.csswg.org/web-animations/#keyframe-offset>
+    offset: Option<f64>,
+    /// <https://drafts.csswg.org/web-animations/#computed-keyframe-offset>
+    computed_offset: f64,
+    easing: TimingFunction,
+    /// The CSS property names and values of this keyframe, as given by script.
+    values: Vec<(DOMString, DOMString)>,
+    /// The declarations parsed from `values`.
+    #[ignore_malloc_size_of = "Defined in style"]
+    declarations: PropertyDeclarationBlock,
+}
+
+impl Keyframe {
+    fn new(offset: Option<f64>, easing: TimingFunction) -> Keyframe {
+        Keyframe {
+            offset,
+            computed_offset: offset.unwrap_or(0.),
+            easing,
+            values: vec![],
+            declarations: PropertyDeclarationBlock::new(),
+        }
+    }
+
+    /// Adds a property value to this keyframe, ignoring properties we don't
+    /// know about or which can't be animated.
+    fn add_value(&mut self, window: &Window, name: &str, value: DOMString) {
+        let name = match idl_name_to_css_name(name) {
+            Some(name) => name,
+            None => return,
+        };
+        let id = match PropertyId::parse_enabled_for_all_content(&name) {
+            Ok(id) => id,
+            Err(()) => return,
+        };
+        if let Some(longhand) = id.longhand_id() {
+            if !longhand.is_animatable() {
+                return;
+            }
+        }
+
+        let mut declarations = SourcePropertyDeclaration::new();
+        let result = parse_one_declaration_into(
+            &mut declarations,
+            id,
+            &value,
+            &window.get_url(),
+            window.css_error_reporter(),
+            ParsingMode::DEFAULT,
+            window.Document().quirks_mode(),
+        );
+        if result.is_ok() {
+            self.declarations
+                .extend(declarations.drain(), Importance::Normal);
+        }
+        self.values.push((DOMString::from(name), value));
+    }
+
+    fn declaration(&self, id: LonghandId) -> Option<&PropertyDeclaration> {
+        self.declarations
+            .get(PropertyDeclarationId::Longhand(id))
+            .map(|(declaration, _)| declaration)
+    }
+}
+
+/// Converts the name of a keyframe member to a CSS property name.
+///
+/// <https://drafts.csswg.org/web-animations/#idl-attribute-name-to-animation-property-name>
+fn idl_name_to_css_name(name: &str) -> Option<String> {
+    match name {
+        "cssFloat" => return Some("float".to_owned()),
+        "float" | "offset" | "easing" | "composite" => return None,
+        "cssOffset" => return Some("offset".to_owned()),
+        _ => {},
+    }
+    if name.contains('-') {
+        return None;
+    }
+    let mut result = String::with_capacity(name.len());
+    for c in name.chars() {
+        if c.is_ascii_uppercase() {
+            result.push('-');
+            result.push(c.to_ascii_lowercase());
+        } else {
+            result.push(c);
+        }
+    }
+    Some(result)
+}
+
+/// Converts a CSS property name to the name of a keyframe member.
+///
+/// <https://drafts.csswg.org/web-animations/#animation-property-name-to-idl-attribute-name>
+fn css_name_to_idl_name(name: &str) -> String {
+    match name {
+        "float" => return "cssFloat".to_owned(),
+        "offset" => return "cssOffset".to_owned(),
+        _ => {},
+    }
+    let mut result = String::with_capacity(name.len());
+    let mut uppercase_next = false;
+    for c in name.chars() {
+        if c == '-' {
+            uppercase_next = true;
+        } else if uppercase_next {
+            result.push(c.to_ascii_uppercase());
+            uppercase_next = false;
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+/// The values of a member of a property-indexed keyframe, which is either a
+/// single value or a list of them.
+struct PropertyIndexedValues(Vec<DOMString>);
+
+#[allow(unsafe_code)]
+impl FromJSValConvertible for PropertyIndexedValues {
+    type Config = ();
+
+    unsafe fn from_jsval(
+        cx: *mut RawJSContext,
+        value: HandleValue,
+        _: (),
+    ) -> Result<ConversionResult<PropertyIndexedValues>, ()> {
+        if value.is_object() {
+            rooted!(in(cx) let object = value.to_object());
+            let mut is_array = false;
+            if !JS_IsArrayObject(cx, object.handle(), &mut is_array) {
+                return Err(());
+            }
+            if is_array {
+                return Ok(
+                    match Vec::<DOMString>::from_jsval(cx, value, StringificationBehavior::Default)?
+                    {
+                        ConversionResult::Success(values) => {
+                            ConversionResult::Success(PropertyIndexedValues(values))
+                        },
+                        ConversionResult::Failure(message) => ConversionResult::Failure(message),
+                    },
+                );
+            }
+        }
+        Ok(
+            match DOMString::from_jsval(cx, value, StringificationBehavior::Default)? {
+                ConversionResult::Success(value) => {
+                    ConversionResult::Success(PropertyIndexedValues(vec![value]))
+                },
+                ConversionResult::Failure(message) => ConversionResult::Failure(message),
+            },
+        )
+    }
+}
+
+/// Sets the member `name` of `object` to `value`.
+#[allow(unsafe_code)]
+fn set_member<T>(cx: JSContext, object: HandleObject, name: &str, value: &T) -> ErrorResult
+where
+    T: ToJSValConvertible + ?Sized,
+{
+    rooted!(in(*cx) let mut jsval = UndefinedValue());
+    unsafe { value.to_jsval(*cx, jsval.handle_mut()) };
+    set_dictionary_property(*cx, object, name, jsval.handle()).map_err(|_| Error::JSFailed)
+}
+
+/// Parses the stringified `offset` member of a keyframe.
+fn parse_offset(offset: &str) -> Fallible<Option<f64>> {
+    match offset {
+        "null" | "undefined" => Ok(None),
+        offset => match offset.parse::<f64>() {
+            Ok(offset) if offset.is_finite() => Ok(Some(offset)),
+            _ => Err(Error::Type(format!("'{}' is not a valid offset", offset))),
+        },
+    }
+}
+
+fn linear() -> TimingFunction {
+    GenericTimingFunction::Keyword(TimingKeyword::Linear)
+}
+
+#[allow(unsafe_code)]
+unsafe fn convert_keyframes<T>(
+    cx: JSContext,
+    object: *mut JSObject,
+    config: T::Config,
+) -> Fallible<T>
+where
+    T: FromJSValConvertible,
+{
+    rooted!(in(*cx) let value = ObjectValue(object));
+    match T::from_jsval(*cx, value.handle(), config) {
+        Ok(ConversionResult::Success(keyframes)) => Ok(keyframes),
+        Ok(ConversionResult::Failure(message)) => Err(Error::Type(message.into_owned())),
+        Err(()) => Err(Error::JSFailed),
+    }
+}
+
+/// <https://drafts.csswg.org/web-animations/#process-a-keyframes-argument>
+#[allow(unsafe_code)]
+fn process_keyframes(
+    cx: JSContext,
+    window: &Window,
+    object: *mut JSObject,
+) -> Fallible<Vec<Keyframe>> {
+    // Step 1.
+    if object.is_null() {
+        return Ok(vec![]);
+    }
+
+    rooted!(in(*cx) let object_root = object);
+    let mut is_array = false;
+    if !unsafe { JS_IsArrayObject(*cx, object_root.handle(), &mut is_array) } {
+        return Err(Error::JSFailed);
+    }
+
+    let mut keyframes = if is_array {
+        // Step 5, the keyframes are given as a list of keyframe objects.
+        let objects: Vec<MozMap<DOMString>> =
+            unsafe { convert_keyframes(cx, object, StringificationBehavior::Default)? };
+        let mut keyframes = Vec::with_capacity(objects.len());
+        for members in objects {
+            let offset = match members.get("offset") {
+                Some(offset) => parse_offset(offset)?,
+                None => None,
+            };
+            let easing = match members.get("easing") {
+                Some(easing) => parse_easing(window, easing)?,
+                None => linear(),
+            };
+            let mut keyframe = Keyframe::new(offset, easing);
+            for (name, value) in members.iter() {
+                keyframe.add_value(window, name, value.clone());
+            }
+            keyframes.push(keyframe);
+        }
+        keyframes
+    } else {
+        // Step 6, the keyframes are given as a property-indexed keyframe.
+        let members: MozMap<PropertyIndexedValues> = unsafe { convert_keyframes(cx, object, ())? };
+        let mut keyframes: Vec<Keyframe> = vec![];
+        for (name, values) in members.iter() {
+            if idl_name_to_css_name(name).is_none() {
+                continue;
+            }
+            let values = &values.0;
+            for (index, value) in values.iter().enumerate() {
+                let offset = if values.len() == 1 {
+                    1.
+                } else {
+                    index as f64 / (values.len() - 1) as f64
+                };
+                let position = keyframes
+                    .iter()
+                    .position(|keyframe| keyframe.computed_offset >= offset);
+                let index = match position {
+                    Some(index) if keyframes[index].computed_offset == offset => index,
+                    Some(index) => {
+                        keyframes.insert(index, Keyframe::new(Some(offset), linear()));
+                        index
+                    },
+                    None => {
+                        keyframes.push(Keyframe::new(Some(offset), linear()));
+                        keyframes.len() - 1
+                    },
+                };
+                keyframes[index].add_value(window, name, value.clone());
+            }
+        }
+
+        // The computed offsets of the keyframes are only used to merge them
+        // above, their offsets are given by the `offset` member.
+        for keyframe in keyframes.iter_mut() {
+            keyframe.offset = None;
+        }
+        if let Some(offsets) = members.get("offset") {
+            for (keyframe, offset) in keyframes.iter_mut().zip(offsets.0.iter()) {
+                keyframe.offset = parse_offset(offset)?;
+            }
+        }
+
+        if let Some(easings) = members.get("easing") {
+            let easings = easings
+                .0
+                .iter()
+                .map(|easing| parse_easing(window, easing))
+                .collect::<Fallible<Vec<_>>>()?;
+            if !easings.is_empty() {
+                for (keyframe, easing) in keyframes.iter_mut().zip(easings.iter().cycle()) {
+                    keyframe.easing = *easing;
+                }
+            }
+        }
+        keyframes
+    };
+
+    // Step 7.
+    let mut previous_offset = 0.;
+    for offset in keyframes.iter().filter_map(|keyframe| keyframe.offset) {
+        if offset < previous_offset {
+            return Err(Error::Type("Keyframe offsets are not sorted".to_owned()));
+        }
+        if offset > 1. {
+            return Err(Error::Type(
+                "Keyframe offsets must be within [0, 1]".to_owned(),
+            ));
+        }
+        previous_offset = offset;
+    }
+
+    compute_missing_keyframe_offsets(&mut keyframes);
+    Ok(keyframes)
+}
+
+/// <https://drafts.csswg.org/web-animations/#compute-missing-keyframe-offsets>
+fn compute_missing_keyframe_offsets(keyframes: &mut [Keyframe]) {
+    let len = keyframes.len();
+    if len == 0 {
+        return;
+    }
+
+    // Steps 1-3.
+    let mut offsets: Vec<Option<f64>> = keyframes.iter().map(|keyframe| keyframe.offset).collect();
+    if len > 1 && offsets[0].is_none() {
+        offsets[0] = Some(0.);
+    }
+    if offsets[len - 1].is_none() {
+        offsets[len - 1] = Some(1.);
+    }
+
+    // Step 4.
+    let mut index = 0;
+    while index < len {
+        if offsets[index].is_some() {
+            index += 1;
+            continue;
+        }
+        let start = index - 1;
+        let end = (index..len).find(|&i| offsets[i].is_some()).unwrap();
+        let start_offset = offsets[start].unwrap();
+        let end_offset = offsets[end].unwrap();
+        for i in index..end {
+            offsets[i] = Some(
+                start_offset +
+                    (end_offset - start_offset) * (i - start) as f64 / (end - start) as f64,
+            );
+        }
+        index = end;
+    }
+
+    for (keyframe, offset) in keyframes.iter_mut().zip(offsets) {
+        keyframe.computed_offset = offset.unwrap();
+    }
+}
+
+/// <https://drafts.csswg.org/web-animations/#the-keyframeeffect-interface>
+#[dom_struct]
+pub struct KeyframeEffect {
+    animationeffect: AnimationEffect,
+    target: MutNullableDom<Element>,
+    keyframes: DomRefCell<Vec<Keyframe>>,
+}
+
+impl KeyframeEffect {
+    fn new_inherited(target: Option<&Element>, timing: Timing) -> KeyframeEffect {
+        KeyframeEffect {
+            animationeffect: AnimationEffect::new_inherited(timing),
+            target: MutNullableDom::new(target),
+            keyframes: DomRefCell::new(vec![]),
+        }
+    }
+
+    pub fn new(
+        window: &Window,
+        target: Option<&Element>,
+        timing: Timing,
+    ) -> DomRoot<KeyframeEffect> {
+        reflect_dom_object(
+            Box::new(KeyframeEffect::new_inherited(target, timing)),
+            window,
+            KeyframeEffectBinding::Wrap,
+        )
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#dom-keyframeeffect-keyframeeffect>
+    pub fn Constructor(
+        cx: JSContext,
+        window: &Window,
+        target: Option<&Element>,
+        keyframes: *mut JSObject,
+        options: UnrestrictedDoubleOrKeyframeEffectOptions,
+    ) -> Fallible<DomRoot<KeyframeEffect>> {
+        let timing = match options {
+            UnrestrictedDoubleOrKeyframeEffectOptions::UnrestrictedDouble(duration) => {
+                Timing::from_duration(window, duration)?
+            },
+            UnrestrictedDoubleOrKeyframeEffectOptions::KeyframeEffectOptions(ref options) => {
+                Timing::from_effect_timing(window, &options.parent)?
+            },
+        };
+        let effect = KeyframeEffect::new(window, target, timing);
+        effect.SetKeyframes(cx, keyframes)?;
+        Ok(effect)
+    }
+
+    /// <https://drafts.csswg.org/web-animations/#dom-keyframeeffect-keyframeeffect-source>
+    pub fn Constructor_(
+        window: &Window,
+        source: &KeyframeEffect,
+    ) -> Fallible<DomRoot<KeyframeEffect>> {
+        let effect = KeyframeEffect::new(
+            window,
+            source.target.get().as_deref(),
+            source.upcast::<AnimationEffect>().timing(),
+        );
+        *effect.keyframes.borrow_mut() = source.keyframes.borrow().clone();
+        Ok(effect)
+    }
+
+    pub fn target(&self) -> Option<DomRoot<Element>> {
+        self.target.get()
+    }
+
+    fn changed(&self) {
+        if let Some(animation) = self.upcast::<AnimationEffect>().animation() {
+            animation.effect_changed();
+        }
+    }
+
+    /// Appends to `samples` the values this effect gives to the properties it
+    /// animates at its current progress, if it is in effect.
+    ///
+    /// <https://drafts.csswg.org/web-animations/#the-effect-value-of-a-keyframe-animation-effect>
+    pub fn sample(&self, samples: &mut Vec<KeyframeEffectSample>) {
+        let effect = self.upcast::<AnimationEffect>();
+        // Step 1.
+        let iteration_progress = match effect.transformed_progress() {
+            Some(progress) => progress,
+            None => return,
+        };
+        let epsilon = easing_epsilon(effect.timing().iteration_duration());
+
+        let keyframes = self.keyframes.borrow();
+        let mut properties: Vec<LonghandId> = vec![];
+        for keyframe in keyframes.iter() {
+            for declaration in keyframe.declarations.declarations() {
+                if let PropertyDeclarationId::Longhand(id) = declaration.id() {
+                    if id.is_animatable() && !properties.contains(&id) {
+                        properties.push(id);
+                    }
+                }
+            }
+        }
+
+        // Step 5.
+        for property in properties {
+            // Steps 5.1-5.5, the neutral keyframes have no value.
+            let mut property_keyframes: Vec<(f64, TimingFunction, Option<_>)> = keyframes
+                .iter()
+                .filter_map(|keyframe| {
+                    keyframe
+                        .declaration(property)
+                        .map(|value| (keyframe.computed_offset, keyframe.easing, Some(value)))
+                })
+                .collect();
+            if property_keyframes.first().map_or(true, |k| k.0 != 0.) {
+                property_keyframes.insert(0, (0., linear(), None));
+            }
+            if property_keyframes.last().map_or(true, |k| k.0 != 1.) {
+                property_keyframes.push((1., linear(), None));
+            }
+
+            // Steps 5.6-5.10.
+            let first_count = property_keyframes.iter().filter(|k| k.0 == 0.).count();
+            let last_count = property_keyframes.iter().filter(|k| k.0 == 1.).count();
+            let len = property_keyframes.len();
+            let (start, end) = if iteration_progress < 0. && first_count > 1 {
+                (0, None)
+            } else if iteration_progress >= 1. && last_count > 1 {
+                (len - 1, None)
+            } else if iteration_progress < 0. {
+                (0, Some(1))
+            } else if iteration_progress >= 1. {
+                (len - 2, Some(len - 1))
+            } else {
+                let start = property_keyframes
+                    .iter()
+                    .rposition(|k| k.0 <= iteration_progress && k.0 < 1.)
+                    .unwrap_or(0);
+                (start, Some(start + 1))
+            };
+
+            let (start_offset, start_easing, from) = property_keyframes[start];
+            let sample = match end {
+                // Step 5.11.
+                None => KeyframeEffectSample {
+                    property,
+                    from: from.cloned(),
+                    to: from.cloned(),
+                    progress: 0.,
+                },
+                // Steps 5.12-5.17.
+                Some(end) => {
+                    let (end_offset, _, to) = property_keyframes[end];
+                    let distance =
+                        (iteration_progress - start_offset) / (end_offset - start_offset);
+                    KeyframeEffectSample {
+                        property,
+                        from: from.cloned(),
+                        to: to.cloned(),
+                        progress: timing_function_output(start_easing, distance, epsilon),
+                    }
+                },
+            };
+            samples.push(sample);
+        }
+    }
+}
+
+impl KeyframeEffectMethods for KeyframeEffect {
+    // https://drafts.csswg.org/web-animations/#dom-keyframeeffect-target
+    fn GetTarget(&self) -> Option<DomRoot<Element>> {
+        self.target.get()
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-keyframeeffect-target
+    fn SetTarget(&self, target: Option<&Element>) {
+        if self.target.get().as_deref() == target {
+            return;
+        }
+        self.target.set(target);
+        self.changed();
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-keyframeeffect-getkeyframes
+    #[allow(unsafe_code)]
+    fn GetKeyframes(&self, cx: JSContext) -> Fallible<Vec<NonNull<JSObject>>> {
+        let keyframes = self.keyframes.borrow();
+        // Keep the keyframe objects alive while we create them.
+        rooted!(in(*cx) let array = unsafe { JS_NewArrayObject(*cx, &HandleValueArray::new()) });
+        let mut result = Vec::with_capacity(keyframes.len());
+        for (index, keyframe) in keyframes.iter().enumerate() {
+            rooted!(in(*cx) let object = unsafe { JS_NewPlainObject(*cx) });
+            let object = object.handle();
+            set_member(cx, object, "offset", &keyframe.offset)?;
+            set_member(cx, object, "computedOffset", &keyframe.computed_offset)?;
+            set_member(cx, object, "easing", &keyframe.easing.to_css_string())?;
+            set_member(cx, object, "composite", "auto")?;
+            for &(ref name, ref value) in keyframe.values.iter() {
+                set_member(cx, object, &css_name_to_idl_name(name), value)?;
+            }
+            set_member(
+                cx,
+                array.handle(),
+                &index.to_string(),
+                &ObjectValue(object.get()),
+            )?;
+            result.push(NonNull::new(object.get()).ok_or(Error::JSFailed)?);
+        }
+        Ok(result)
+    }
+
+    // https://drafts.csswg.org/web-animations/#dom-keyframeeffect-setkeyframes
+    fn SetKeyframes(&self, cx: JSContext, keyframes: *mut JSObject) -> ErrorResult {
+        let global = self.global();
+        let keyframes = process_keyframes(cx, global.as_window(), keyframes)?;
+        *self.keyframes.borrow_mut() = keyframes;
+        self.changed();
+        Ok(())
+    }
+}
@@ -0,0 +1,270 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::compartments::enter_realm;
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::EventHandlerBinding::EventHandlerNonNull;
+use crate::dom::bindings::codegen::Bindings::MessagePortBinding;
+use crate::dom::bindings::codegen::Bindings::MessagePortBinding::MessagePortMethods;
+use crate::dom::bindings::error::{Error, ErrorResult};
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::refcounted::Trusted;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject};
+use crate::dom::bindings::root::DomRoot;
+use crate::dom::bindings::structuredclone;
+use crate::dom::eventtarget::EventTarget;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::messageevent::MessageEvent;
+use crate::script_runtime::JSContext as SafeJSContext;
+use crate::task_source::TaskSource;
+use dom_struct::dom_struct;
+use js::jsapi::JSObject;
+use js::jsval::UndefinedValue;
+use js::rust::{CustomAutoRooterGuard, HandleValue};
+use msg::constellation_msg::MessagePortId;
+use script_traits::transferable::MessagePortImpl;
+use script_traits::PortMessageTask;
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+#[dom_struct]
+/// The MessagePort used in the DOM.
+pub struct MessagePort {
+    eventtarget: EventTarget,
+    message_port_id: MessagePortId,
+    entangled_port: Cell<Option<MessagePortId>>,
+    /// <https://html.spec.whatwg.org/multipage/#port-message-queue>
+    enabled: Cell<bool>,
+    /// <https://html.spec.whatwg.org/multipage/#detached>
+    detached: Cell<bool>,
+    /// Messages received while the port message queue was disabled.
+    #[ignore_malloc_size_of = "Defined in script_traits"]
+    message_buffer: DomRefCell<VecDeque<PortMessageTask>>,
+}
+
+impl MessagePort {
+    fn new_inherited(
+        message_port_id: MessagePortId,
+        entangled_port: Option<MessagePortId>,
+        message_buffer: VecDeque<PortMessageTask>,
+    ) -> MessagePort {
+        MessagePort {
+            eventtarget: EventTarget::new_inherited(),
+            message_port_id,
+            entangled_port: Cell::new(entangled_port),
+            enabled: Cell::new(false),
+            detached: Cell::new(false),
+            message_buffer: DomRefCell::new(message_buffer),
+        }
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#create-a-new-messageport-object>
+    pub fn new(owner: &GlobalScope) -> DomRoot<MessagePort> {
+        let port = reflect_dom_object(
+            Box::new(MessagePort::new_inherited(
+                MessagePortId::new(),
+                None,
+                VecDeque::new(),
+            )),
+            owner,
+            MessagePortBinding::Wrap,
+        );
+        owner.track_message_port(&port);
+        port
+    }
+
+    /// Create a new port for an incoming transfer-received one.
+    /// <https://html.spec.whatwg.org/multipage/#transfer-receiving-steps>
+    pub fn new_transferred(
+        owner: &GlobalScope,
+        port_impl: MessagePortImpl,
+    ) -> DomRoot<MessagePort> {
+        let MessagePortImpl {
+            message_port_id,
+            entangled_port,
+            message_buffer,
+        } = port_impl;
+        let port = reflect_dom_object(
+            Box::new(MessagePort::new_inherited(
+                message_port_id,
+                entangled_port,
+                message_buffer,
+            )),
+            owner,
+            MessagePortBinding::Wrap,
+        );
+        owner.track_message_port(&port);
+        port
+    }
+
+    pub fn message_port_id(&self) -> MessagePortId {
+        self.message_port_id
+    }
+
+    pub fn detached(&self) -> bool {
+        self.detached.get()
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#entangle>
+    pub fn entangle(&self, other: &MessagePort) {
+        self.entangled_port.set(Some(other.message_port_id));
+        other.entangled_port.set(Some(self.message_port_id));
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#transfer-steps>
+    pub fn transfer(&self) -> MessagePortImpl {
+        // Step 1.
+        self.detached.set(true);
+        self.global().mark_port_as_shipped(self.message_port_id);
+
+        // Steps 2-4: the port, its entanglement and its pending messages
+        // are moved to the new owner.
+        MessagePortImpl {
+            message_port_id: self.message_port_id,
+            entangled_port: self.entangled_port.get(),
+            message_buffer: self.message_buffer.borrow_mut().drain(..).collect(),
+        }
+    }
+
+    /// Add messages that were buffered by the constellation while this port
+    /// was being transferred.
+    pub fn complete_transfer(&self, tasks: VecDeque<PortMessageTask>) {
+        for task in tasks {
+            self.handle_incoming(task);
+        }
+    }
+
+    /// A message was received on this port, dispatch it if the port message
+    /// queue is enabled, otherwise keep it until `start` is called.
+    pub fn handle_incoming(&self, task: PortMessageTask) {
+        if self.detached.get() {
+            return;
+        }
+        if !self.enabled.get() {
+            self.message_buffer.borrow_mut().push_back(task);
+            return;
+        }
+        self.queue_message_task(task);
+    }
+
+    fn queue_message_task(&self, task: PortMessageTask) {
+        let global = self.global();
+        let this = Trusted::new(self);
+        let _ = global.port_message_queue().queue(
+            task!(process_port_message: move || {
+                let port = this.root();
+                port.dispatch_message(task);
+            }),
+            &global,
+        );
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#message-port-post-message-steps>
+    fn dispatch_message(&self, task: PortMessageTask) {
+        let global = self.global();
+        if self.detached.get() {
+            // The port was transferred after this task was queued, the message
+            // follows it to its new owner.
+            return global.route_task_to_port(self.message_port_id, task);
+        }
+        let _ac = enter_realm(self);
+        rooted!(in(*global.get_cx()) let mut message = UndefinedValue());
+
+        // Step 7.
+        let ports = structuredclone::read(&global, task.data, message.handle_mut());
+
+        // Steps 8-10.
+        MessageEvent::dispatch_jsval(self.upcast(), &global, message.handle(), None, None, ports);
+    }
+}
+
+impl MessagePortMethods for MessagePort {
+    // https://html.spec.whatwg.org/multipage/#dom-messageport-postmessage
+    fn PostMessage(
+        &self,
+        cx: SafeJSContext,
+        message: HandleValue,
+        transfer: CustomAutoRooterGuard<Vec<*mut JSObject>>,
+    ) -> ErrorResult {
+        if self.detached.get() {
+            return Ok(());
+        }
+
+        // Step 1.
+        let target_port = self.entangled_port.get();
+
+        // Step 3.
+        let self_in_transfer = transfer
+            .iter()
+            .any(|object| *object == self.reflector().get_jsobject().get());
+        if self_in_transfer {
+            return Err(Error::DataClone);
+        }
+
+        // Step 5.
+        let data = structuredclone::write(cx, message, Some(transfer))?;
+
+        // Step 2, 4: the message is doomed if the port has no entangled port,
+        // or if that port was transferred along with it.
+        let target_port = match target_port {
+            Some(target_port) => target_port,
+            None => return Ok(()),
+        };
+        let doomed = data
+            .ports
+            .as_ref()
+            .map_or(false, |ports| ports.contains_key(&target_port));
+        if doomed {
+            return Ok(());
+        }
+
+        // Step 6.
+        self.global()
+            .route_task_to_port(target_port, PortMessageTask { data });
+        Ok(())
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-messageport-start
+    fn Start(&self) {
+        if self.detached.get() || self.enabled.get() {
+            return;
+        }
+        self.enabled.set(true);
+        let buffered: Vec<_> = self.message_buffer.borrow_mut().drain(..).collect();
+        for task in buffered {
+            self.queue_message_task(task);
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-messageport-close
+    fn Close(&self) {
+        if self.detached.get() {
+            return;
+        }
+        // Step 1.
+        self.detached.set(true);
+        self.message_buffer.borrow_mut().clear();
+
+        // Step 2.
+        self.global().remove_message_port(self.message_port_id);
+    }
+
+    // https://html.spec.whatwg.org/multipage/#handler-messageport-onmessage
+    fn GetOnmessage(&self) -> Option<Rc<EventHandlerNonNull>> {
+        self.upcast::<EventTarget>()
+            .get_event_handler_common("message")
+    }
+
+    // https://html.spec.whatwg.org/multipage/#handler-messageport-onmessage
+    fn SetOnmessage(&self, listener: Option<Rc<EventHandlerNonNull>>) {
+        self.upcast::<EventTarget>()
+            .set_event_handler_common("message", listener);
+        // The first time a port's onmessage is set, its queue is enabled.
+        self.Start();
+    }
+
+    // https://html.spec.whatwg.org/multipage/#handler-messageport-onmessageerror
+    event_handler!(messageerror, GetOnmessageerror, SetOnmessageerror);
+}
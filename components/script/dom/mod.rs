@@ -212,10 +212,16 @@ pub mod types {
     include!(concat!(env!("OUT_DIR"), "/build/InterfaceTypes.rs"));
 }
 
+pub mod abortcontroller;
+pub mod abortsignal;
 pub mod abstractworker;
 pub mod abstractworkerglobalscope;
 pub mod activation;
 pub mod analysernode;
+pub mod animation;
+pub mod animationeffect;
+pub mod animationplaybackevent;
+pub mod animationtimeline;
 pub mod attr;
 pub mod audiobuffer;
 pub mod audiobuffersourcenode;
@@ -242,6 +248,8 @@ pub mod bluetoothremotegattdescriptor;
 pub mod bluetoothremotegattserver;
 pub mod bluetoothremotegattservice;
 pub mod bluetoothuuid;
+pub mod broadcastchannel;
+pub mod bytelengthqueuingstrategy;
 pub mod canvasgradient;
 pub mod canvaspattern;
 pub mod canvasrenderingcontext2d;
@@ -255,9 +263,11 @@ pub mod comment;
 pub mod compositionevent;
 pub mod console;
 pub mod constantsourcenode;
+pub mod countqueuingstrategy;
 mod create;
 pub mod crypto;
 pub mod css;
+pub mod cssanimation;
 pub mod cssconditionrule;
 pub mod cssfontfacerule;
 pub mod cssgroupingrule;
@@ -273,6 +283,7 @@ pub mod cssstylerule;
 pub mod cssstylesheet;
 pub mod cssstylevalue;
 pub mod csssupportsrule;
+pub mod csstransition;
 pub mod cssviewportrule;
 pub mod customelementregistry;
 pub mod customevent;
@@ -282,6 +293,7 @@ pub mod dissimilaroriginwindow;
 pub mod document;
 pub mod documentfragment;
 pub mod documentorshadowroot;
+pub mod documenttimeline;
 pub mod documenttype;
 pub mod domexception;
 pub mod domimplementation;
@@ -293,8 +305,10 @@ pub mod dompointreadonly;
 pub mod domquad;
 pub mod domrect;
 pub mod domrectreadonly;
+pub mod domstringlist;
 pub mod domstringmap;
 pub mod domtokenlist;
+pub mod editing;
 pub mod element;
 pub mod errorevent;
 pub mod event;
@@ -308,6 +322,9 @@ pub mod filelist;
 pub mod filereader;
 pub mod filereadersync;
 pub mod focusevent;
+pub mod fontface;
+pub mod fontfaceset;
+pub mod fontfacesetloadevent;
 pub mod formdata;
 pub mod formdataevent;
 pub mod gainnode;
@@ -374,6 +391,7 @@ pub mod htmlprogresselement;
 pub mod htmlquoteelement;
 pub mod htmlscriptelement;
 pub mod htmlselectelement;
+pub mod htmlslotelement;
 pub mod htmlsourceelement;
 pub mod htmlspanelement;
 pub mod htmlstyleelement;
@@ -391,9 +409,24 @@ pub mod htmltrackelement;
 pub mod htmlulistelement;
 pub mod htmlunknownelement;
 pub mod htmlvideoelement;
+pub mod idbcursor;
+pub mod idbcursorwithvalue;
+pub mod idbdatabase;
+pub mod idbfactory;
+pub mod idbindex;
+pub mod idbkeyrange;
+pub mod idbobjectstore;
+pub mod idbopendbrequest;
+pub mod idbrequest;
+pub mod idbtransaction;
+pub mod idbversionchangeevent;
+pub mod imagebitmap;
 pub mod imagedata;
 pub mod inputevent;
+pub mod intersectionobserver;
+pub mod intersectionobserverentry;
 pub mod keyboardevent;
+pub mod keyframeeffect;
 pub mod location;
 pub mod mediadevices;
 pub mod mediaerror;
@@ -403,7 +436,9 @@ pub mod mediaquerylist;
 pub mod mediaquerylistevent;
 pub mod mediastream;
 pub mod mediastreamtrack;
+pub mod messagechannel;
 pub mod messageevent;
+pub mod messageport;
 pub mod mimetype;
 pub mod mimetypearray;
 pub mod mouseevent;
@@ -426,6 +461,7 @@ pub mod paintrenderingcontext2d;
 pub mod paintsize;
 pub mod paintworkletglobalscope;
 pub mod pannernode;
+pub mod path2d;
 pub mod performance;
 pub mod performanceentry;
 pub mod performancemark;
@@ -449,7 +485,16 @@ pub mod promiserejectionevent;
 pub mod radionodelist;
 pub mod range;
 pub mod raredata;
+pub mod readablebytestreamcontroller;
+pub mod readablestream;
+pub mod readablestreambyobreader;
+pub mod readablestreambyobrequest;
+pub mod readablestreamdefaultcontroller;
+pub mod readablestreamdefaultreader;
 pub mod request;
+pub mod resizeobserver;
+pub mod resizeobserverentry;
+pub mod resizeobserversize;
 pub mod response;
 pub mod rtcicecandidate;
 pub mod rtcpeerconnection;
@@ -457,6 +502,7 @@ pub mod rtcpeerconnectioniceevent;
 pub mod rtcsessiondescription;
 pub mod rtctrackevent;
 pub mod screen;
+pub mod selection;
 pub mod serviceworker;
 pub mod serviceworkercontainer;
 pub mod serviceworkerglobalscope;
@@ -469,9 +515,27 @@ pub mod storageevent;
 pub mod stylepropertymapreadonly;
 pub mod stylesheet;
 pub mod stylesheetlist;
+pub mod svgcircleelement;
+pub mod svgdefselement;
 pub mod svgelement;
+pub mod svgellipseelement;
+pub mod svggelement;
+pub mod svggeometryelement;
+pub mod svggradientelement;
 pub mod svggraphicselement;
+pub mod svglineargradientelement;
+pub mod svglineelement;
+pub mod svgpathelement;
+pub mod svgpolygonelement;
+pub mod svgpolylineelement;
+pub mod svgradialgradientelement;
+pub mod svgrectelement;
+pub mod svgstopelement;
 pub mod svgsvgelement;
+pub mod svgtextcontentelement;
+pub mod svgtextelement;
+pub mod svgtextpositioningelement;
+pub mod svguseelement;
 pub mod testbinding;
 pub mod testbindingiterable;
 pub mod testbindingpairiterable;
@@ -483,6 +547,7 @@ pub mod text;
 pub mod textcontrol;
 pub mod textdecoder;
 pub mod textencoder;
+pub mod textmetrics;
 pub mod texttrack;
 pub mod texttrackcue;
 pub mod texttrackcuelist;
@@ -492,6 +557,8 @@ pub mod touch;
 pub mod touchevent;
 pub mod touchlist;
 pub mod trackevent;
+pub mod transformstream;
+pub mod transformstreamdefaultcontroller;
 pub mod transitionevent;
 pub mod treewalker;
 pub mod uievent;
@@ -540,6 +607,9 @@ pub mod workerlocation;
 pub mod workernavigator;
 pub mod worklet;
 pub mod workletglobalscope;
+pub mod writablestream;
+pub mod writablestreamdefaultcontroller;
+pub mod writablestreamdefaultwriter;
 pub mod xmldocument;
 pub mod xmlhttprequest;
 pub mod xmlhttprequesteventtarget;
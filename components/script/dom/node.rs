@@ -47,6 +47,7 @@ use crate::dom::htmlinputelement::{HTMLInputElement, LayoutHTMLInputElementHelpe
 use crate::dom::htmllinkelement::HTMLLinkElement;
 use crate::dom::htmlmediaelement::{HTMLMediaElement, LayoutHTMLMediaElementHelpers};
 use crate::dom::htmlmetaelement::HTMLMetaElement;
+use crate::dom::htmlslotelement::HTMLSlotElement;
 use crate::dom::htmlstyleelement::HTMLStyleElement;
 use crate::dom::htmltextareaelement::{HTMLTextAreaElement, LayoutHTMLTextAreaElementHelpers};
 use crate::dom::mutationobserver::{Mutation, MutationObserver, RegisteredObserver};
@@ -57,7 +58,7 @@ use crate::dom::raredata::NodeRareData;
 use crate::dom::shadowroot::{LayoutShadowRootHelpers, ShadowRoot};
 use crate::dom::stylesheetlist::StyleSheetListOwner;
 use crate::dom::svgsvgelement::{LayoutSVGSVGElementHelpers, SVGSVGElement};
-use crate::dom::text::Text;
+use crate::dom::text::{LayoutTextHelpers, Text};
 use crate::dom::virtualmethods::{vtable_for, VirtualMethods};
 use crate::dom::window::Window;
 use crate::script_runtime::JSContext;
@@ -594,6 +595,12 @@ impl Node {
             if ancestor.is::<Element>() {
                 ancestor.set_flag(NodeFlags::HAS_DIRTY_DESCENDANTS, true);
             }
+
+            // Slotted nodes are traversed through their slot in the flat tree,
+            // so the slot and its ancestors need to know about them too.
+            if let Some(slot) = ancestor.assigned_slot() {
+                slot.upcast::<Node>().note_dirty_descendants();
+            }
         }
     }
 
@@ -971,6 +978,27 @@ impl Node {
         self.ensure_rare_data().containing_shadow_root = shadow_root.map(Dom::from_ref);
     }
 
+    /// <https://dom.spec.whatwg.org/#concept-slotable>
+    pub fn is_slottable(&self) -> bool {
+        self.is::<Element>() || self.is::<Text>()
+    }
+
+    /// <https://dom.spec.whatwg.org/#slotable-assigned-slot>
+    pub fn assigned_slot(&self) -> Option<DomRoot<HTMLSlotElement>> {
+        self.rare_data()
+            .as_ref()?
+            .assigned_slot
+            .as_ref()
+            .map(|slot| DomRoot::from_ref(&**slot))
+    }
+
+    pub fn set_assigned_slot(&self, slot: Option<&HTMLSlotElement>) {
+        if slot.is_none() && self.rare_data().is_none() {
+            return;
+        }
+        self.ensure_rare_data().assigned_slot = slot.map(Dom::from_ref);
+    }
+
     pub fn is_in_html_doc(&self) -> bool {
         self.owner_doc().is_html_document()
     }
@@ -1204,6 +1232,7 @@ pub trait LayoutNodeHelpers {
 
     unsafe fn owner_doc_for_layout(&self) -> LayoutDom<Document>;
     unsafe fn containing_shadow_root_for_layout(&self) -> Option<LayoutDom<ShadowRoot>>;
+    unsafe fn assigned_slot_for_layout(&self) -> Option<LayoutDom<Element>>;
 
     unsafe fn is_element_for_layout(&self) -> bool;
     unsafe fn get_flag(&self, flag: NodeFlags) -> bool;
@@ -1297,6 +1326,17 @@ impl LayoutNodeHelpers for LayoutDom<Node> {
             .map(|sr| sr.to_layout())
     }
 
+    #[inline]
+    #[allow(unsafe_code)]
+    unsafe fn assigned_slot_for_layout(&self) -> Option<LayoutDom<Element>> {
+        (*self.unsafe_get())
+            .rare_data_for_layout()
+            .as_ref()?
+            .assigned_slot
+            .as_ref()
+            .map(|slot| slot.to_layout().upcast())
+    }
+
     #[inline]
     #[allow(unsafe_code)]
     unsafe fn get_flag(&self, flag: NodeFlags) -> bool {
@@ -1372,6 +1412,10 @@ impl LayoutNodeHelpers for LayoutDom<Node> {
             return unsafe { input.selection_for_layout() };
         }
 
+        if let Some(text) = self.downcast::<Text>() {
+            return unsafe { text.selection_for_layout() };
+        }
+
         None
     }
 
@@ -1926,6 +1970,27 @@ impl Node {
         for kid in new_nodes {
             // Step 7.1.
             parent.add_child(*kid, child);
+            // Step 7.4.
+            let parent_is_shadow_host = parent
+                .downcast::<Element>()
+                .map_or(false, |parent| parent.is_shadow_host());
+            if parent_is_shadow_host && kid.is_slottable() {
+                HTMLSlotElement::assign_a_slot(kid);
+            }
+            // Step 7.5.
+            if let Some(slot) = parent.downcast::<HTMLSlotElement>() {
+                if !slot.has_assigned_nodes() && parent.is_in_shadow_tree() {
+                    slot.signal_a_slot_change();
+                }
+            }
+            // Step 7.6.
+            // Only inserting a slot can change the assignment of the other slots.
+            if kid
+                .traverse_preorder(ShadowIncluding::No)
+                .any(|node| node.is::<HTMLSlotElement>())
+            {
+                HTMLSlotElement::assign_slottables_for_a_tree(kid);
+            }
             // Step 7.7.
             for descendant in kid
                 .traverse_preorder(ShadowIncluding::Yes)
@@ -2056,6 +2121,22 @@ impl Node {
         let old_next_sibling = node.GetNextSibling();
         // Steps 9-10 are handled in unbind_from_tree.
         parent.remove_child(node, cached_index);
+        // Slot assignment steps.
+        if let Some(slot) = node.assigned_slot() {
+            slot.assign_slottables();
+        }
+        if let Some(slot) = parent.downcast::<HTMLSlotElement>() {
+            if !slot.has_assigned_nodes() && parent.is_in_shadow_tree() {
+                slot.signal_a_slot_change();
+            }
+        }
+        if node
+            .traverse_preorder(ShadowIncluding::No)
+            .any(|node| node.is::<HTMLSlotElement>())
+        {
+            HTMLSlotElement::assign_slottables_for_a_tree(parent);
+            HTMLSlotElement::assign_slottables_for_a_tree(node);
+        }
         // Step 11. transient registered observers
         // Step 12.
         if let SuppressObserver::Unsuppressed = suppress_observers {
@@ -7,19 +7,25 @@ use crate::dom::bindings::codegen::Bindings::OffscreenCanvasBinding::{
     OffscreenCanvasMethods, OffscreenRenderingContext, Wrap as OffscreenCanvasWrap,
 };
 use crate::dom::bindings::error::Fallible;
+use crate::dom::bindings::inheritance::Castable;
 use crate::dom::bindings::reflector::reflect_dom_object;
 use crate::dom::bindings::reflector::DomObject;
-use crate::dom::bindings::root::{Dom, DomRoot};
+use crate::dom::bindings::root::{Dom, DomRoot, LayoutDom};
 use crate::dom::bindings::str::DOMString;
+use crate::dom::dedicatedworkerglobalscope::DedicatedWorkerGlobalScope;
 use crate::dom::eventtarget::EventTarget;
 use crate::dom::globalscope::GlobalScope;
 use crate::dom::htmlcanvaselement::HTMLCanvasElement;
+use crate::dom::node::{Node, NodeDamage};
 use crate::dom::offscreencanvasrenderingcontext2d::OffscreenCanvasRenderingContext2D;
 use crate::script_runtime::JSContext;
+use canvas_traits::canvas::{CanvasId, CanvasMsg, FromScriptMsg};
 use dom_struct::dom_struct;
 use euclid::default::Size2D;
+use ipc_channel::ipc::IpcSender;
 use js::rust::HandleValue;
 use ref_filter_map;
+use script_traits::transferable::OffscreenCanvasImpl;
 use std::cell::Cell;
 use std::cell::Ref;
 
@@ -38,6 +44,14 @@ pub struct OffscreenCanvas {
     width: Cell<u64>,
     context: DomRefCell<Option<OffscreenCanvasContext>>,
     placeholder: Option<Dom<HTMLCanvasElement>>,
+    /// The canvas paint thread and id of the canvas backing the placeholder `<canvas>`
+    /// element, which the frames of this canvas are committed to.
+    #[ignore_malloc_size_of = "Defined in ipc-channel"]
+    placeholder_canvas: Option<(IpcSender<CanvasMsg>, CanvasId)>,
+    /// Whether the bitmap changed since a frame was last committed to the placeholder.
+    needs_commit: Cell<bool>,
+    /// <https://html.spec.whatwg.org/multipage/#detached>
+    detached: Cell<bool>,
 }
 
 impl OffscreenCanvas {
@@ -45,6 +59,7 @@ impl OffscreenCanvas {
         height: u64,
         width: u64,
         placeholder: Option<&HTMLCanvasElement>,
+        placeholder_canvas: Option<(IpcSender<CanvasMsg>, CanvasId)>,
     ) -> OffscreenCanvas {
         OffscreenCanvas {
             eventtarget: EventTarget::new_inherited(),
@@ -52,6 +67,9 @@ impl OffscreenCanvas {
             width: Cell::new(width),
             context: DomRefCell::new(None),
             placeholder: placeholder.map(Dom::from_ref),
+            placeholder_canvas,
+            needs_commit: Cell::new(false),
+            detached: Cell::new(false),
         }
     }
 
@@ -60,20 +78,47 @@ impl OffscreenCanvas {
         height: u64,
         width: u64,
         placeholder: Option<&HTMLCanvasElement>,
+        placeholder_canvas: Option<(IpcSender<CanvasMsg>, CanvasId)>,
     ) -> DomRoot<OffscreenCanvas> {
         reflect_dom_object(
-            Box::new(OffscreenCanvas::new_inherited(height, width, placeholder)),
+            Box::new(OffscreenCanvas::new_inherited(
+                height,
+                width,
+                placeholder,
+                placeholder_canvas,
+            )),
             global,
             OffscreenCanvasWrap,
         )
     }
 
+    /// Creates the canvas received by the global an `OffscreenCanvas` was transferred to.
+    pub fn new_transferred(
+        global: &GlobalScope,
+        canvas: OffscreenCanvasImpl,
+    ) -> DomRoot<OffscreenCanvas> {
+        let offscreencanvas = OffscreenCanvas::new(
+            global,
+            canvas.height,
+            canvas.width,
+            None,
+            canvas.placeholder,
+        );
+        // TODO: commit the frames of canvases transferred to other kinds of globals.
+        if offscreencanvas.placeholder_canvas.is_some() {
+            if let Some(worker) = global.downcast::<DedicatedWorkerGlobalScope>() {
+                worker.track_offscreen_canvas(&offscreencanvas);
+            }
+        }
+        offscreencanvas
+    }
+
     pub fn Constructor(
         global: &GlobalScope,
         height: u64,
         width: u64,
     ) -> Fallible<DomRoot<OffscreenCanvas>> {
-        let offscreencanvas = OffscreenCanvas::new(global, height, width, None);
+        let offscreencanvas = OffscreenCanvas::new(global, height, width, None, None);
         Ok(offscreencanvas)
     }
 
@@ -85,6 +130,70 @@ impl OffscreenCanvas {
         ref_filter_map::ref_filter_map(self.context.borrow(), |ctx| ctx.as_ref())
     }
 
+    /// The canvas of the canvas paint thread backing the placeholder `<canvas>` element.
+    pub fn placeholder_canvas(&self) -> Option<(IpcSender<CanvasMsg>, CanvasId)> {
+        self.placeholder_canvas.clone()
+    }
+
+    /// Notes that the bitmap changed. The placeholder of a canvas that wasn't transferred
+    /// shows its bitmap directly, so only the frames of a transferred canvas are committed.
+    pub fn mark_as_dirty(&self) {
+        match self.placeholder {
+            Some(ref placeholder) => placeholder
+                .upcast::<Node>()
+                .dirty(NodeDamage::OtherNodeDamage),
+            None => self.needs_commit.set(self.placeholder_canvas.is_some()),
+        }
+    }
+
+    /// Pushes the current bitmap to the placeholder `<canvas>` element, if it changed since
+    /// the last commit, and returns the id of the placeholder's canvas in that case.
+    ///
+    /// <https://html.spec.whatwg.org/multipage/#offscreencanvas-placeholder>
+    pub fn commit(&self) -> Option<CanvasId> {
+        if !self.needs_commit.replace(false) {
+            return None;
+        }
+        let (ref ipc_renderer, placeholder_id) = *self.placeholder_canvas.as_ref()?;
+        let context = match *self.context.borrow() {
+            Some(OffscreenCanvasContext::OffscreenContext2d(ref context)) => {
+                DomRoot::from_ref(&**context)
+            },
+            None => return None,
+        };
+        let msg = CanvasMsg::FromScript(
+            FromScriptMsg::CommitToPlaceholder(placeholder_id),
+            context.get_canvas_id(),
+        );
+        if let Err(err) = ipc_renderer.send(msg) {
+            warn!("Could not commit to the placeholder canvas: {}", err);
+            return None;
+        }
+        Some(placeholder_id)
+    }
+
+    /// <https://html.spec.whatwg.org/multipage/#the-offscreencanvas-interface:transfer-steps>
+    pub fn transfer(&self) -> Option<OffscreenCanvasImpl> {
+        // Step 1.
+        if self.context.borrow().is_some() {
+            return None;
+        }
+        // Step 2.
+        self.detached.set(true);
+        let canvas = OffscreenCanvasImpl {
+            width: self.width.get(),
+            height: self.height.get(),
+            placeholder: self.placeholder_canvas.clone(),
+        };
+        self.width.set(0);
+        self.height.set(0);
+        Some(canvas)
+    }
+
+    pub fn is_detached(&self) -> bool {
+        self.detached.get()
+    }
+
     #[allow(unsafe_code)]
     fn get_or_init_2d_context(&self) -> Option<DomRoot<OffscreenCanvasRenderingContext2D>> {
         if let Some(ctx) = self.context() {
@@ -92,6 +201,9 @@ impl OffscreenCanvas {
                 OffscreenCanvasContext::OffscreenContext2d(ref ctx) => Some(DomRoot::from_ref(ctx)),
             };
         }
+        if self.detached.get() {
+            return None;
+        }
         let size = self.get_size();
         let context = OffscreenCanvasRenderingContext2D::new(
             &self.global(),
@@ -106,6 +218,33 @@ impl OffscreenCanvas {
     }
 }
 
+pub trait LayoutOffscreenCanvasHelpers {
+    #[allow(unsafe_code)]
+    unsafe fn get_placeholder_canvas(&self) -> Option<(IpcSender<CanvasMsg>, CanvasId)>;
+}
+
+impl LayoutOffscreenCanvasHelpers for LayoutDom<OffscreenCanvas> {
+    #[allow(unsafe_code)]
+    unsafe fn get_placeholder_canvas(&self) -> Option<(IpcSender<CanvasMsg>, CanvasId)> {
+        (*self.unsafe_get()).placeholder_canvas.clone()
+    }
+}
+
+impl Drop for OffscreenCanvas {
+    fn drop(&mut self) {
+        // Only the canvas returned by `transferControlToOffscreen` owns the canvas
+        // backing the placeholder, since it lives as long as the placeholder element.
+        if self.placeholder.is_none() {
+            return;
+        }
+        if let Some((ref ipc_renderer, canvas_id)) = self.placeholder_canvas {
+            if let Err(err) = ipc_renderer.send(CanvasMsg::Close(canvas_id)) {
+                warn!("Could not close placeholder canvas: {}", err)
+            }
+        }
+    }
+}
+
 impl OffscreenCanvasMethods for OffscreenCanvas {
     // https://html.spec.whatwg.org/multipage/#dom-offscreencanvas-getcontext
     fn GetContext(
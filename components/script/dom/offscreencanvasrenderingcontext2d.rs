@@ -3,10 +3,13 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
 use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasDirection;
 use crate::dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasFillRule;
 use crate::dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasImageSource;
 use crate::dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasLineCap;
 use crate::dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasLineJoin;
+use crate::dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasTextAlign;
+use crate::dom::bindings::codegen::Bindings::CanvasRenderingContext2DBinding::CanvasTextBaseline;
 use crate::dom::bindings::codegen::Bindings::OffscreenCanvasRenderingContext2DBinding;
 use crate::dom::bindings::codegen::Bindings::OffscreenCanvasRenderingContext2DBinding::OffscreenCanvasRenderingContext2DMethods;
 use crate::dom::bindings::codegen::UnionTypes::StringOrCanvasGradientOrCanvasPattern;
@@ -23,6 +26,9 @@ use crate::dom::globalscope::GlobalScope;
 use crate::dom::htmlcanvaselement::HTMLCanvasElement;
 use crate::dom::imagedata::ImageData;
 use crate::dom::offscreencanvas::OffscreenCanvas;
+use crate::dom::path2d::Path2D;
+use crate::dom::textmetrics::TextMetrics;
+use canvas_traits::canvas::CanvasId;
 use dom_struct::dom_struct;
 use euclid::default::Size2D;
 
@@ -41,14 +47,22 @@ impl OffscreenCanvasRenderingContext2D {
         size: Size2D<u64>,
         htmlcanvas: Option<&HTMLCanvasElement>,
     ) -> OffscreenCanvasRenderingContext2D {
+        // A canvas that wasn't transferred away from its placeholder draws directly to
+        // the canvas backing the placeholder.
+        let placeholder_canvas = canvas
+            .filter(|_| htmlcanvas.is_some())
+            .and_then(|canvas| canvas.placeholder_canvas());
+        let canvas_state = match placeholder_canvas {
+            Some((ipc_renderer, canvas_id)) => {
+                CanvasState::new_for_canvas(global, ipc_renderer, canvas_id)
+            },
+            None => CanvasState::new(global, Size2D::new(size.width as u64, size.height as u64)),
+        };
         OffscreenCanvasRenderingContext2D {
             reflector_: Reflector::new(),
             canvas: canvas.map(Dom::from_ref),
             htmlcanvas: htmlcanvas.map(Dom::from_ref),
-            canvas_state: DomRefCell::new(CanvasState::new(
-                global,
-                Size2D::new(size.width as u64, size.height as u64),
-            )),
+            canvas_state: DomRefCell::new(canvas_state),
         }
     }
 
@@ -70,6 +84,16 @@ impl OffscreenCanvasRenderingContext2D {
             OffscreenCanvasRenderingContext2DBinding::Wrap,
         )
     }
+
+    pub fn get_canvas_id(&self) -> CanvasId {
+        self.canvas_state.borrow().get_canvas_id()
+    }
+
+    fn mark_as_dirty(&self) {
+        if let Some(ref canvas) = self.canvas {
+            canvas.mark_as_dirty();
+        }
+    }
 }
 
 impl OffscreenCanvasRenderingContext2DMethods for OffscreenCanvasRenderingContext2D {
@@ -81,16 +105,19 @@ impl OffscreenCanvasRenderingContext2DMethods for OffscreenCanvasRenderingContex
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-fillrect
     fn FillRect(&self, x: f64, y: f64, width: f64, height: f64) {
         self.canvas_state.borrow().FillRect(x, y, width, height);
+        self.mark_as_dirty();
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-clearrect
     fn ClearRect(&self, x: f64, y: f64, width: f64, height: f64) {
         self.canvas_state.borrow().ClearRect(x, y, width, height);
+        self.mark_as_dirty();
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-strokerect
     fn StrokeRect(&self, x: f64, y: f64, width: f64, height: f64) {
         self.canvas_state.borrow().StrokeRect(x, y, width, height);
+        self.mark_as_dirty();
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-shadowoffsetx
@@ -241,7 +268,77 @@ impl OffscreenCanvasRenderingContext2DMethods for OffscreenCanvasRenderingContex
 
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-filltext
     fn FillText(&self, text: DOMString, x: f64, y: f64, max_width: Option<f64>) {
-        self.canvas_state.borrow().FillText(text, x, y, max_width)
+        self.canvas_state.borrow().FillText(
+            self.htmlcanvas.as_ref().map(|c| &**c),
+            text,
+            x,
+            y,
+            max_width,
+        );
+        self.mark_as_dirty();
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-stroketext
+    fn StrokeText(&self, text: DOMString, x: f64, y: f64, max_width: Option<f64>) {
+        self.canvas_state.borrow().StrokeText(
+            self.htmlcanvas.as_ref().map(|c| &**c),
+            text,
+            x,
+            y,
+            max_width,
+        );
+        self.mark_as_dirty();
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-measuretext
+    fn MeasureText(&self, text: DOMString) -> DomRoot<TextMetrics> {
+        self.canvas_state.borrow().MeasureText(
+            &self.global(),
+            self.htmlcanvas.as_ref().map(|c| &**c),
+            text,
+        )
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-font
+    fn Font(&self) -> DOMString {
+        self.canvas_state.borrow().Font()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-font
+    fn SetFont(&self, value: DOMString) {
+        self.canvas_state
+            .borrow()
+            .SetFont(self.htmlcanvas.as_ref().map(|c| &**c), value)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-textalign
+    fn TextAlign(&self) -> CanvasTextAlign {
+        self.canvas_state.borrow().TextAlign()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-textalign
+    fn SetTextAlign(&self, value: CanvasTextAlign) {
+        self.canvas_state.borrow().SetTextAlign(value)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-textbaseline
+    fn TextBaseline(&self) -> CanvasTextBaseline {
+        self.canvas_state.borrow().TextBaseline()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-textbaseline
+    fn SetTextBaseline(&self, value: CanvasTextBaseline) {
+        self.canvas_state.borrow().SetTextBaseline(value)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-direction
+    fn Direction(&self) -> CanvasDirection {
+        self.canvas_state.borrow().Direction()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-direction
+    fn SetDirection(&self, value: CanvasDirection) {
+        self.canvas_state.borrow().SetDirection(value)
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-linewidth
@@ -317,7 +414,8 @@ impl OffscreenCanvasRenderingContext2DMethods for OffscreenCanvasRenderingContex
             imagedata,
             dx,
             dy,
-        )
+        );
+        self.mark_as_dirty();
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-putimagedata
@@ -341,14 +439,20 @@ impl OffscreenCanvasRenderingContext2DMethods for OffscreenCanvasRenderingContex
             dirty_y,
             dirty_width,
             dirty_height,
-        )
+        );
+        self.mark_as_dirty();
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-drawimage
     fn DrawImage(&self, image: CanvasImageSource, dx: f64, dy: f64) -> ErrorResult {
-        self.canvas_state
-            .borrow()
-            .DrawImage(self.htmlcanvas.as_ref().map(|c| &**c), image, dx, dy)
+        let result = self.canvas_state.borrow().DrawImage(
+            self.htmlcanvas.as_ref().map(|c| &**c),
+            image,
+            dx,
+            dy,
+        );
+        self.mark_as_dirty();
+        result
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-drawimage
@@ -360,14 +464,16 @@ impl OffscreenCanvasRenderingContext2DMethods for OffscreenCanvasRenderingContex
         dw: f64,
         dh: f64,
     ) -> ErrorResult {
-        self.canvas_state.borrow().DrawImage_(
+        let result = self.canvas_state.borrow().DrawImage_(
             self.htmlcanvas.as_ref().map(|c| &**c),
             image,
             dx,
             dy,
             dw,
             dh,
-        )
+        );
+        self.mark_as_dirty();
+        result
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-drawimage
@@ -383,7 +489,7 @@ impl OffscreenCanvasRenderingContext2DMethods for OffscreenCanvasRenderingContex
         dw: f64,
         dh: f64,
     ) -> ErrorResult {
-        self.canvas_state.borrow().DrawImage__(
+        let result = self.canvas_state.borrow().DrawImage__(
             self.htmlcanvas.as_ref().map(|c| &**c),
             image,
             sx,
@@ -394,7 +500,9 @@ impl OffscreenCanvasRenderingContext2DMethods for OffscreenCanvasRenderingContex
             dy,
             dw,
             dh,
-        )
+        );
+        self.mark_as_dirty();
+        result
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-beginpath
@@ -404,12 +512,26 @@ impl OffscreenCanvasRenderingContext2DMethods for OffscreenCanvasRenderingContex
 
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-fill
     fn Fill(&self, fill_rule: CanvasFillRule) {
-        self.canvas_state.borrow().Fill(fill_rule)
+        self.canvas_state.borrow().Fill(fill_rule);
+        self.mark_as_dirty();
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-fill
+    fn Fill_(&self, path: &Path2D, fill_rule: CanvasFillRule) {
+        self.canvas_state.borrow().Fill_(path, fill_rule);
+        self.mark_as_dirty();
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-stroke
     fn Stroke(&self) {
-        self.canvas_state.borrow().Stroke()
+        self.canvas_state.borrow().Stroke();
+        self.mark_as_dirty();
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-stroke
+    fn Stroke_(&self, path: &Path2D) {
+        self.canvas_state.borrow().Stroke_(path);
+        self.mark_as_dirty();
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-clip
@@ -417,6 +539,11 @@ impl OffscreenCanvasRenderingContext2DMethods for OffscreenCanvasRenderingContex
         self.canvas_state.borrow().Clip(fill_rule)
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-clip
+    fn Clip_(&self, path: &Path2D, fill_rule: CanvasFillRule) {
+        self.canvas_state.borrow().Clip_(path, fill_rule)
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-ispointinpath
     fn IsPointInPath(&self, x: f64, y: f64, fill_rule: CanvasFillRule) -> bool {
         self.canvas_state
@@ -424,6 +551,13 @@ impl OffscreenCanvasRenderingContext2DMethods for OffscreenCanvasRenderingContex
             .IsPointInPath(&self.global(), x, y, fill_rule)
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-ispointinpath
+    fn IsPointInPath_(&self, path: &Path2D, x: f64, y: f64, fill_rule: CanvasFillRule) -> bool {
+        self.canvas_state
+            .borrow()
+            .IsPointInPath_(&self.global(), path, x, y, fill_rule)
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-context-2d-scale
     fn Scale(&self, x: f64, y: f64) {
         self.canvas_state.borrow().Scale(x, y)
@@ -0,0 +1,384 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::Path2DBinding;
+use crate::dom::bindings::codegen::Bindings::Path2DBinding::Path2DMethods;
+use crate::dom::bindings::error::{Error, ErrorResult, Fallible};
+use crate::dom::bindings::reflector::{reflect_dom_object, Reflector};
+use crate::dom::bindings::root::DomRoot;
+use crate::dom::bindings::str::DOMString;
+use crate::dom::globalscope::GlobalScope;
+use canvas_traits::canvas::PathSegment;
+use dom_struct::dom_struct;
+use euclid::default::{Point2D, Rect, Size2D};
+use std::f32::consts::PI;
+use style::values::specified::svg_path::{CoordPair, PathCommand, SVGPathData};
+
+// https://html.spec.whatwg.org/multipage/#path2d-objects
+#[dom_struct]
+pub struct Path2D {
+    reflector_: Reflector,
+    path: DomRefCell<Vec<PathSegment>>,
+}
+
+impl Path2D {
+    fn new_inherited(path: Vec<PathSegment>) -> Path2D {
+        Path2D {
+            reflector_: Reflector::new(),
+            path: DomRefCell::new(path),
+        }
+    }
+
+    pub fn new(global: &GlobalScope, path: Vec<PathSegment>) -> DomRoot<Path2D> {
+        reflect_dom_object(
+            Box::new(Path2D::new_inherited(path)),
+            global,
+            Path2DBinding::Wrap,
+        )
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-path2d
+    pub fn Constructor(global: &GlobalScope) -> Fallible<DomRoot<Path2D>> {
+        Ok(Path2D::new(global, vec![]))
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-path2d
+    pub fn Constructor_(global: &GlobalScope, other: &Path2D) -> Fallible<DomRoot<Path2D>> {
+        Ok(Path2D::new(global, other.segments()))
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-path2d
+    pub fn Constructor__(global: &GlobalScope, d: DOMString) -> Fallible<DomRoot<Path2D>> {
+        // A string that fails to parse as SVG path data yields an empty path.
+        let segments = SVGPathData::parse_path_string(&d)
+            .map(|data| segments_from_svg_path(&data))
+            .unwrap_or_default();
+        Ok(Path2D::new(global, segments))
+    }
+
+    /// The recorded path operations, as sent to the canvas paint thread.
+    pub fn segments(&self) -> Vec<PathSegment> {
+        self.path.borrow().clone()
+    }
+
+    fn push(&self, segment: PathSegment) {
+        self.path.borrow_mut().push(segment);
+    }
+}
+
+impl Path2DMethods for Path2D {
+    // https://html.spec.whatwg.org/multipage/#dom-path2d-addpath
+    fn AddPath(&self, path: &Path2D) {
+        // Cloning first keeps `p.addPath(p)` from borrowing the same cell twice.
+        let segments = path.segments();
+        self.path.borrow_mut().extend(segments);
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-closepath
+    fn ClosePath(&self) {
+        self.push(PathSegment::ClosePath);
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-moveto
+    fn MoveTo(&self, x: f64, y: f64) {
+        if !(x.is_finite() && y.is_finite()) {
+            return;
+        }
+        self.push(PathSegment::MoveTo(Point2D::new(x as f32, y as f32)));
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-lineto
+    fn LineTo(&self, x: f64, y: f64) {
+        if !(x.is_finite() && y.is_finite()) {
+            return;
+        }
+        self.push(PathSegment::LineTo(Point2D::new(x as f32, y as f32)));
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-quadraticcurveto
+    fn QuadraticCurveTo(&self, cpx: f64, cpy: f64, x: f64, y: f64) {
+        if !(cpx.is_finite() && cpy.is_finite() && x.is_finite() && y.is_finite()) {
+            return;
+        }
+        self.push(PathSegment::QuadraticCurveTo(
+            Point2D::new(cpx as f32, cpy as f32),
+            Point2D::new(x as f32, y as f32),
+        ));
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-beziercurveto
+    fn BezierCurveTo(&self, cp1x: f64, cp1y: f64, cp2x: f64, cp2y: f64, x: f64, y: f64) {
+        if !([cp1x, cp1y, cp2x, cp2y, x, y].iter().all(|x| x.is_finite())) {
+            return;
+        }
+        self.push(PathSegment::BezierCurveTo(
+            Point2D::new(cp1x as f32, cp1y as f32),
+            Point2D::new(cp2x as f32, cp2y as f32),
+            Point2D::new(x as f32, y as f32),
+        ));
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-arcto
+    fn ArcTo(&self, cp1x: f64, cp1y: f64, cp2x: f64, cp2y: f64, r: f64) -> ErrorResult {
+        if !([cp1x, cp1y, cp2x, cp2y, r].iter().all(|x| x.is_finite())) {
+            return Ok(());
+        }
+        if r < 0.0 {
+            return Err(Error::IndexSize);
+        }
+
+        self.push(PathSegment::ArcTo(
+            Point2D::new(cp1x as f32, cp1y as f32),
+            Point2D::new(cp2x as f32, cp2y as f32),
+            r as f32,
+        ));
+        Ok(())
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-rect
+    fn Rect(&self, x: f64, y: f64, width: f64, height: f64) {
+        if [x, y, width, height].iter().all(|val| val.is_finite()) {
+            self.push(PathSegment::Rect(Rect::new(
+                Point2D::new(x as f32, y as f32),
+                Size2D::new(width as f32, height as f32),
+            )));
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-arc
+    fn Arc(&self, x: f64, y: f64, r: f64, start: f64, end: f64, ccw: bool) -> ErrorResult {
+        if !([x, y, r, start, end].iter().all(|x| x.is_finite())) {
+            return Ok(());
+        }
+        if r < 0.0 {
+            return Err(Error::IndexSize);
+        }
+
+        self.push(PathSegment::Arc(
+            Point2D::new(x as f32, y as f32),
+            r as f32,
+            start as f32,
+            end as f32,
+            ccw,
+        ));
+        Ok(())
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-context-2d-ellipse
+    fn Ellipse(
+        &self,
+        x: f64,
+        y: f64,
+        rx: f64,
+        ry: f64,
+        rotation: f64,
+        start: f64,
+        end: f64,
+        ccw: bool,
+    ) -> ErrorResult {
+        if !([x, y, rx, ry, rotation, start, end]
+            .iter()
+            .all(|x| x.is_finite()))
+        {
+            return Ok(());
+        }
+        if rx < 0.0 || ry < 0.0 {
+            return Err(Error::IndexSize);
+        }
+
+        self.push(PathSegment::Ellipse(
+            Point2D::new(x as f32, y as f32),
+            rx as f32,
+            ry as f32,
+            rotation as f32,
+            start as f32,
+            end as f32,
+            ccw,
+        ));
+        Ok(())
+    }
+}
+
+fn point(coord: &CoordPair) -> Point2D<f32> {
+    Point2D::new(coord.x(), coord.y())
+}
+
+/// Reflects `control` through `pos`, giving the implicit first control point of a
+/// smooth curve command.
+fn reflect(control: Option<Point2D<f32>>, pos: Point2D<f32>) -> Point2D<f32> {
+    control.map_or(pos, |control| pos + (pos - control))
+}
+
+/// Converts SVG path data into canvas path segments. Relative commands are made
+/// absolute first; the implicit control points of smooth curves and the center
+/// parameterization of elliptical arcs are resolved here, since the canvas path
+/// API has no equivalent for them.
+///
+/// https://svgwg.org/svg2-draft/paths.html#PathDataGeneralInformation
+fn segments_from_svg_path(data: &SVGPathData) -> Vec<PathSegment> {
+    let mut segments = vec![];
+    let mut pos = Point2D::zero();
+    let mut subpath_start = Point2D::zero();
+    let mut last_cubic_control = None;
+    let mut last_quad_control = None;
+
+    for command in data.normalize().iter() {
+        let (mut cubic_control, mut quad_control) = (None, None);
+        match *command {
+            PathCommand::Unknown => {},
+            PathCommand::MoveTo { point: ref to, .. } => {
+                pos = point(to);
+                subpath_start = pos;
+                segments.push(PathSegment::MoveTo(pos));
+            },
+            PathCommand::LineTo { point: ref to, .. } => {
+                pos = point(to);
+                segments.push(PathSegment::LineTo(pos));
+            },
+            PathCommand::HorizontalLineTo { x, .. } => {
+                pos.x = x;
+                segments.push(PathSegment::LineTo(pos));
+            },
+            PathCommand::VerticalLineTo { y, .. } => {
+                pos.y = y;
+                segments.push(PathSegment::LineTo(pos));
+            },
+            PathCommand::CurveTo {
+                ref control1,
+                ref control2,
+                point: ref to,
+                ..
+            } => {
+                cubic_control = Some(point(control2));
+                pos = point(to);
+                segments.push(PathSegment::BezierCurveTo(
+                    point(control1),
+                    point(control2),
+                    pos,
+                ));
+            },
+            PathCommand::SmoothCurveTo {
+                ref control2,
+                point: ref to,
+                ..
+            } => {
+                let control1 = reflect(last_cubic_control, pos);
+                cubic_control = Some(point(control2));
+                pos = point(to);
+                segments.push(PathSegment::BezierCurveTo(control1, point(control2), pos));
+            },
+            PathCommand::QuadBezierCurveTo {
+                ref control1,
+                point: ref to,
+                ..
+            } => {
+                quad_control = Some(point(control1));
+                pos = point(to);
+                segments.push(PathSegment::QuadraticCurveTo(point(control1), pos));
+            },
+            PathCommand::SmoothQuadBezierCurveTo { point: ref to, .. } => {
+                let control = reflect(last_quad_control, pos);
+                quad_control = Some(control);
+                pos = point(to);
+                segments.push(PathSegment::QuadraticCurveTo(control, pos));
+            },
+            PathCommand::EllipticalArc {
+                rx,
+                ry,
+                angle,
+                large_arc_flag,
+                sweep_flag,
+                point: ref to,
+                ..
+            } => {
+                let to = point(to);
+                segments.extend(arc_to_segment(
+                    pos,
+                    to,
+                    rx,
+                    ry,
+                    angle,
+                    large_arc_flag.is_set(),
+                    sweep_flag.is_set(),
+                ));
+                pos = to;
+            },
+            PathCommand::ClosePath => {
+                pos = subpath_start;
+                segments.push(PathSegment::ClosePath);
+            },
+        }
+        last_cubic_control = cubic_control;
+        last_quad_control = quad_control;
+    }
+    segments
+}
+
+/// Converts an SVG elliptical arc from its endpoint parameterization to the center
+/// parameterization that `ellipse()` takes.
+///
+/// https://www.w3.org/TR/SVG11/implnote.html#ArcConversionEndpointToCenter
+fn arc_to_segment(
+    from: Point2D<f32>,
+    to: Point2D<f32>,
+    rx: f32,
+    ry: f32,
+    angle: f32,
+    large_arc: bool,
+    sweep: bool,
+) -> Option<PathSegment> {
+    // https://www.w3.org/TR/SVG11/implnote.html#ArcOutOfRangeParameters
+    if from == to {
+        return None;
+    }
+    let (mut rx, mut ry) = (rx.abs(), ry.abs());
+    if rx == 0.0 || ry == 0.0 {
+        return Some(PathSegment::LineTo(to));
+    }
+
+    let phi = angle.to_radians();
+    let (sin_phi, cos_phi) = phi.sin_cos();
+    let half_dx = (from.x - to.x) / 2.0;
+    let half_dy = (from.y - to.y) / 2.0;
+    let x1 = cos_phi * half_dx + sin_phi * half_dy;
+    let y1 = -sin_phi * half_dx + cos_phi * half_dy;
+
+    // Scale the radii up if they are too small to span both endpoints.
+    let lambda = (x1 * x1) / (rx * rx) + (y1 * y1) / (ry * ry);
+    if lambda > 1.0 {
+        rx *= lambda.sqrt();
+        ry *= lambda.sqrt();
+    }
+
+    let numerator = rx * rx * ry * ry - rx * rx * y1 * y1 - ry * ry * x1 * x1;
+    let denominator = rx * rx * y1 * y1 + ry * ry * x1 * x1;
+    let sign = if large_arc == sweep { -1.0 } else { 1.0 };
+    let coefficient = sign * (numerator / denominator).max(0.0).sqrt();
+    let cx1 = coefficient * rx * y1 / ry;
+    let cy1 = -coefficient * ry * x1 / rx;
+
+    let center = Point2D::new(
+        cos_phi * cx1 - sin_phi * cy1 + (from.x + to.x) / 2.0,
+        sin_phi * cx1 + cos_phi * cy1 + (from.y + to.y) / 2.0,
+    );
+
+    let start = ((y1 - cy1) / ry).atan2((x1 - cx1) / rx);
+    let mut sweep_angle = ((-y1 - cy1) / ry).atan2((-x1 - cx1) / rx) - start;
+    if sweep && sweep_angle < 0.0 {
+        sweep_angle += 2.0 * PI;
+    } else if !sweep && sweep_angle > 0.0 {
+        sweep_angle -= 2.0 * PI;
+    }
+
+    Some(PathSegment::Ellipse(
+        center,
+        rx,
+        ry,
+        phi,
+        start,
+        start + sweep_angle,
+        !sweep,
+    ))
+}
@@ -2,6 +2,7 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
+use crate::dom::bindings::cell::DomRefCell;
 use crate::dom::bindings::codegen::Bindings::CharacterDataBinding::CharacterDataMethods;
 use crate::dom::bindings::codegen::Bindings::NodeBinding::NodeConstants;
 use crate::dom::bindings::codegen::Bindings::NodeBinding::NodeMethods;
@@ -24,6 +25,7 @@ use crate::dom::documentfragment::DocumentFragment;
 use crate::dom::element::Element;
 use crate::dom::htmlscriptelement::HTMLScriptElement;
 use crate::dom::node::{Node, ShadowIncluding, UnbindContext};
+use crate::dom::selection::Selection;
 use crate::dom::text::Text;
 use crate::dom::window::Window;
 use dom_struct::dom_struct;
@@ -37,6 +39,8 @@ pub struct Range {
     reflector_: Reflector,
     start: BoundaryPoint,
     end: BoundaryPoint,
+    /// <https://w3c.github.io/selection-api/#dfn-associated>
+    associated_selections: DomRefCell<Vec<Dom<Selection>>>,
 }
 
 impl Range {
@@ -50,6 +54,7 @@ impl Range {
             reflector_: Reflector::new(),
             start: BoundaryPoint::new(start_container, start_offset),
             end: BoundaryPoint::new(end_container, end_offset),
+            associated_selections: DomRefCell::new(vec![]),
         }
     }
 
@@ -173,6 +178,7 @@ impl Range {
             }
         }
         self.start.set(node, offset);
+        self.report_change();
     }
 
     // https://dom.spec.whatwg.org/#concept-range-bp-set
@@ -188,6 +194,26 @@ impl Range {
             }
         }
         self.end.set(node, offset);
+        self.report_change();
+    }
+
+    pub fn associate_selection(&self, selection: &Selection) {
+        self.associated_selections
+            .borrow_mut()
+            .push(Dom::from_ref(selection));
+    }
+
+    pub fn disassociate_selection(&self, selection: &Selection) {
+        self.associated_selections
+            .borrow_mut()
+            .retain(|associated| &**associated != selection);
+    }
+
+    /// Let the selections this range is associated with know it changed.
+    fn report_change(&self) {
+        for selection in self.associated_selections.borrow().iter() {
+            selection.queue_selectionchange_task();
+        }
     }
 
     // https://dom.spec.whatwg.org/#dom-range-comparepointnode-offset
@@ -1057,7 +1083,7 @@ impl PartialEq for BoundaryPoint {
 }
 
 // https://dom.spec.whatwg.org/#concept-range-bp-position
-fn bp_position(a_node: &Node, a_offset: u32, b_node: &Node, b_offset: u32) -> Option<Ordering> {
+pub fn bp_position(a_node: &Node, a_offset: u32, b_node: &Node, b_offset: u32) -> Option<Ordering> {
     if a_node as *const Node == b_node as *const Node {
         // Step 1.
         return Some(a_offset.cmp(&b_offset));
@@ -6,9 +6,11 @@ use crate::dom::bindings::root::Dom;
 use crate::dom::customelementregistry::{
     CustomElementDefinition, CustomElementReaction, CustomElementState,
 };
+use crate::dom::htmlslotelement::HTMLSlotElement;
 use crate::dom::mutationobserver::RegisteredObserver;
 use crate::dom::shadowroot::ShadowRoot;
 use std::rc::Rc;
+use style::animation::KeyframeEffectSample;
 
 //XXX(ferjm) Ideally merge NodeRareData and ElementRareData so they share
 //           storage.
@@ -20,6 +22,10 @@ pub struct NodeRareData {
     /// This is None if the node is not in a shadow tree or
     /// if it is a ShadowRoot.
     pub containing_shadow_root: Option<Dom<ShadowRoot>>,
+    /// <https://dom.spec.whatwg.org/#slotable-assigned-slot>
+    /// The slot this node is assigned to, if it is a slottable
+    /// child of a shadow host.
+    pub assigned_slot: Option<Dom<HTMLSlotElement>>,
     /// Registered observers for this node.
     pub mutation_observers: Vec<RegisteredObserver>,
 }
@@ -39,4 +45,8 @@ pub struct ElementRareData {
     pub custom_element_definition: Option<Rc<CustomElementDefinition>>,
     /// <https://dom.spec.whatwg.org/#concept-element-custom-element-state>
     pub custom_element_state: CustomElementState,
+    /// The current samples of the keyframe effects targeting this element,
+    /// which layout applies when styling it.
+    #[ignore_malloc_size_of = "Defined in style"]
+    pub keyframe_effect_samples: Vec<KeyframeEffectSample>,
 }
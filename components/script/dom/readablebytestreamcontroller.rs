@@ -0,0 +1,1066 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::callback::ExceptionHandling;
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::ReadableByteStreamControllerBinding;
+use crate::dom::bindings::codegen::Bindings::ReadableByteStreamControllerBinding::ReadableByteStreamControllerMethods;
+use crate::dom::bindings::codegen::Bindings::UnderlyingSourceBinding::{
+    UnderlyingSource, UnderlyingSourceCancelCallback, UnderlyingSourcePullCallback,
+};
+use crate::dom::bindings::codegen::UnionTypes::ReadableStreamDefaultControllerOrReadableByteStreamController as ReadableStreamController;
+use crate::dom::bindings::error::{Error, ErrorResult, Fallible};
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot, MutNullableDom};
+use crate::dom::promise::Promise;
+use crate::dom::promisenativehandler::Callback;
+use crate::dom::readablestream::ReadableStream;
+use crate::dom::readablestreambyobrequest::ReadableStreamBYOBRequest;
+use crate::script_runtime::JSContext as SafeJSContext;
+use crate::streams::{error_to_jsval, promise_from_callback_result};
+use crate::streams::{promise_resolved_with_undefined, resolve_read_request, upon_settlement};
+use dom_struct::dom_struct;
+use js::jsapi::JS_NewUint8ClampedArrayWithBuffer;
+use js::jsapi::{Heap, JSContext, JSObject, Type};
+use js::jsapi::{JS_GetArrayBufferViewBuffer, JS_GetArrayBufferViewByteOffset, JS_NewDataView};
+use js::jsapi::{JS_NewFloat32ArrayWithBuffer, JS_NewFloat64ArrayWithBuffer};
+use js::jsapi::{JS_NewInt16ArrayWithBuffer, JS_NewInt32ArrayWithBuffer};
+use js::jsapi::{JS_NewInt8ArrayWithBuffer, JS_NewUint8ArrayWithBuffer};
+use js::jsapi::{JS_NewUint16ArrayWithBuffer, JS_NewUint32ArrayWithBuffer};
+use js::jsval::{ObjectValue, UndefinedValue};
+use js::rust::{CustomAutoRooterGuard, HandleObject, HandleValue, MutableHandleObject};
+use js::typedarray::{ArrayBuffer, ArrayBufferView, CreateWith};
+use std::cell::Cell;
+use std::cmp::min;
+use std::collections::VecDeque;
+use std::ptr;
+use std::rc::Rc;
+
+/// The kind of view a pull-into descriptor fills, used to hand the bytes
+/// back to script in a view of the type it was read into.
+/// <https://streams.spec.whatwg.org/#pull-into-descriptor-view-constructor>
+#[derive(Clone, Copy, JSTraceable, MallocSizeOf)]
+enum ViewConstructor {
+    Int8,
+    Uint8,
+    Uint8Clamped,
+    Int16,
+    Uint16,
+    Int32,
+    Uint32,
+    Float32,
+    Float64,
+    DataView,
+}
+
+impl ViewConstructor {
+    fn from_array_type(array_type: Type) -> ViewConstructor {
+        match array_type {
+            Type::Int8 => ViewConstructor::Int8,
+            Type::Uint8 => ViewConstructor::Uint8,
+            Type::Uint8Clamped => ViewConstructor::Uint8Clamped,
+            Type::Int16 => ViewConstructor::Int16,
+            Type::Uint16 => ViewConstructor::Uint16,
+            Type::Int32 => ViewConstructor::Int32,
+            Type::Uint32 => ViewConstructor::Uint32,
+            Type::Float32 => ViewConstructor::Float32,
+            Type::Float64 => ViewConstructor::Float64,
+            _ => ViewConstructor::DataView,
+        }
+    }
+
+    fn element_size(&self) -> usize {
+        match *self {
+            ViewConstructor::Int8 |
+            ViewConstructor::Uint8 |
+            ViewConstructor::Uint8Clamped |
+            ViewConstructor::DataView => 1,
+            ViewConstructor::Int16 | ViewConstructor::Uint16 => 2,
+            ViewConstructor::Int32 | ViewConstructor::Uint32 | ViewConstructor::Float32 => 4,
+            ViewConstructor::Float64 => 8,
+        }
+    }
+
+    /// Create a view of this type on the `byte_length` bytes of `buffer`
+    /// starting at `byte_offset`.
+    #[allow(unsafe_code)]
+    fn create(
+        &self,
+        cx: SafeJSContext,
+        buffer: HandleObject,
+        byte_offset: usize,
+        byte_length: usize,
+        mut rval: MutableHandleObject,
+    ) {
+        let offset = byte_offset as u32;
+        let length = (byte_length / self.element_size()) as i32;
+        let buffer = buffer.into();
+        let view = unsafe {
+            match *self {
+                ViewConstructor::Int8 => JS_NewInt8ArrayWithBuffer(*cx, buffer, offset, length),
+                ViewConstructor::Uint8 => JS_NewUint8ArrayWithBuffer(*cx, buffer, offset, length),
+                ViewConstructor::Uint8Clamped => {
+                    JS_NewUint8ClampedArrayWithBuffer(*cx, buffer, offset, length)
+                },
+                ViewConstructor::Int16 => JS_NewInt16ArrayWithBuffer(*cx, buffer, offset, length),
+                ViewConstructor::Uint16 => JS_NewUint16ArrayWithBuffer(*cx, buffer, offset, length),
+                ViewConstructor::Int32 => JS_NewInt32ArrayWithBuffer(*cx, buffer, offset, length),
+                ViewConstructor::Uint32 => JS_NewUint32ArrayWithBuffer(*cx, buffer, offset, length),
+                ViewConstructor::Float32 => {
+                    JS_NewFloat32ArrayWithBuffer(*cx, buffer, offset, length)
+                },
+                ViewConstructor::Float64 => {
+                    JS_NewFloat64ArrayWithBuffer(*cx, buffer, offset, length)
+                },
+                ViewConstructor::DataView => JS_NewDataView(*cx, buffer, offset, length),
+            }
+        };
+        assert!(!view.is_null());
+        rval.set(view);
+    }
+}
+
+/// <https://streams.spec.whatwg.org/#pull-into-descriptor-reader-type>
+#[derive(Clone, Copy, JSTraceable, MallocSizeOf, PartialEq)]
+enum ReaderType {
+    Default,
+    Byob,
+}
+
+/// <https://streams.spec.whatwg.org/#pull-into-descriptor>
+///
+/// The buffer of the view being read into is written to in place rather
+/// than transferred.
+#[derive(JSTraceable, MallocSizeOf)]
+struct PullIntoDescriptor {
+    #[ignore_malloc_size_of = "Defined in mozjs"]
+    buffer: Box<Heap<*mut JSObject>>,
+    byte_offset: usize,
+    byte_length: usize,
+    bytes_filled: usize,
+    constructor: ViewConstructor,
+    reader_type: ReaderType,
+}
+
+impl PullIntoDescriptor {
+    fn element_size(&self) -> usize {
+        self.constructor.element_size()
+    }
+
+    /// Copy `bytes` at the end of the bytes already filled in.
+    #[allow(unsafe_code)]
+    fn fill(&mut self, cx: SafeJSContext, bytes: &[u8]) {
+        typedarray!(in(*cx) let buffer: ArrayBuffer = self.buffer.get());
+        if let Ok(mut buffer) = buffer {
+            let start = self.byte_offset + self.bytes_filled;
+            let data = unsafe { buffer.as_mut_slice() };
+            data[start..start + bytes.len()].copy_from_slice(bytes);
+        }
+        self.bytes_filled += bytes.len();
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-convert-pull-into-descriptor>
+    fn convert(&self, cx: SafeJSContext, rval: MutableHandleObject) {
+        rooted!(in(*cx) let buffer = self.buffer.get());
+        self.constructor.create(
+            cx,
+            buffer.handle(),
+            self.byte_offset,
+            self.bytes_filled,
+            rval,
+        );
+    }
+}
+
+/// <https://streams.spec.whatwg.org/#rbs-controller-class>
+#[dom_struct]
+pub struct ReadableByteStreamController {
+    reflector_: Reflector,
+    stream: MutNullableDom<ReadableStream>,
+    /// The object the methods of the underlying source are called on.
+    #[ignore_malloc_size_of = "Defined in mozjs"]
+    source: Heap<*mut JSObject>,
+    /// <https://streams.spec.whatwg.org/#readablebytestreamcontroller-pullalgorithm>
+    #[ignore_malloc_size_of = "Rc"]
+    pull: DomRefCell<Option<Rc<UnderlyingSourcePullCallback>>>,
+    /// <https://streams.spec.whatwg.org/#readablebytestreamcontroller-cancelalgorithm>
+    #[ignore_malloc_size_of = "Rc"]
+    cancel: DomRefCell<Option<Rc<UnderlyingSourceCancelCallback>>>,
+    /// <https://streams.spec.whatwg.org/#readablebytestreamcontroller-queue>
+    queue: DomRefCell<VecDeque<Vec<u8>>>,
+    queue_total_size: Cell<usize>,
+    /// <https://streams.spec.whatwg.org/#readablebytestreamcontroller-pendingpullintos>
+    pending_pull_intos: DomRefCell<VecDeque<PullIntoDescriptor>>,
+    byob_request: MutNullableDom<ReadableStreamBYOBRequest>,
+    auto_allocate_chunk_size: Option<u64>,
+    started: Cell<bool>,
+    close_requested: Cell<bool>,
+    pull_again: Cell<bool>,
+    pulling: Cell<bool>,
+    strategy_hwm: f64,
+}
+
+impl ReadableByteStreamController {
+    fn new_inherited(
+        source: &UnderlyingSource,
+        auto_allocate_chunk_size: Option<u64>,
+        strategy_hwm: f64,
+    ) -> ReadableByteStreamController {
+        ReadableByteStreamController {
+            reflector_: Reflector::new(),
+            stream: Default::default(),
+            source: Heap::default(),
+            pull: DomRefCell::new(source.pull.clone()),
+            cancel: DomRefCell::new(source.cancel.clone()),
+            queue: DomRefCell::new(VecDeque::new()),
+            queue_total_size: Cell::new(0),
+            pending_pull_intos: DomRefCell::new(VecDeque::new()),
+            byob_request: Default::default(),
+            auto_allocate_chunk_size,
+            started: Cell::new(false),
+            close_requested: Cell::new(false),
+            pull_again: Cell::new(false),
+            pulling: Cell::new(false),
+            strategy_hwm,
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#set-up-readable-byte-stream-controller-from-underlying-source>
+    #[allow(unrooted_must_root)]
+    pub fn set_up_from_underlying_source(
+        stream: &ReadableStream,
+        source_object: HandleObject,
+        source: &UnderlyingSource,
+        high_water_mark: f64,
+    ) -> ErrorResult {
+        // Step 8.
+        if source.autoAllocateChunkSize == Some(0) {
+            return Err(Error::Type(
+                "autoAllocateChunkSize must be greater than 0".to_owned(),
+            ));
+        }
+
+        // Steps 1-7 and 9, and steps 1-11 of
+        // https://streams.spec.whatwg.org/#set-up-readable-byte-stream-controller
+        let global = stream.global();
+        let controller = reflect_dom_object(
+            Box::new(ReadableByteStreamController::new_inherited(
+                source,
+                source.autoAllocateChunkSize,
+                high_water_mark,
+            )),
+            &*global,
+            ReadableByteStreamControllerBinding::Wrap,
+        );
+        controller.source.set(source_object.get());
+        controller.stream.set(Some(stream));
+        stream.set_byte_controller(&controller);
+
+        // Step 12.
+        let start_result = match source.start {
+            Some(ref start) => start.Call_(
+                &source_object,
+                ReadableStreamController::ReadableByteStreamController(DomRoot::from_ref(
+                    &*controller,
+                )),
+                ExceptionHandling::Rethrow,
+            )?,
+            None => UndefinedValue(),
+        };
+
+        // Steps 13-16.
+        let start_promise = promise_from_callback_result(&global, Ok(start_result));
+        upon_settlement(
+            &global,
+            &start_promise,
+            Box::new(ByteControllerReaction {
+                controller: Dom::from_ref(&*controller),
+                step: ByteControllerStep::StartFulfilled,
+            }),
+            Box::new(ByteControllerReaction {
+                controller: Dom::from_ref(&*controller),
+                step: ByteControllerStep::StartRejected,
+            }),
+        );
+        Ok(())
+    }
+
+    fn stream(&self) -> DomRoot<ReadableStream> {
+        self.stream
+            .get()
+            .expect("A controller is always set up with a stream")
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-call-pull-if-needed>
+    #[allow(unrooted_must_root)]
+    fn call_pull_if_needed(&self) {
+        // Steps 1-2.
+        if !self.should_call_pull() {
+            return;
+        }
+
+        // Step 3.
+        if self.pulling.get() {
+            self.pull_again.set(true);
+            return;
+        }
+
+        // Step 5.
+        self.pulling.set(true);
+
+        // Step 6.
+        let global = self.global();
+        let pull = self.pull.borrow().clone();
+        let pull_promise = match pull {
+            Some(pull) => {
+                rooted!(in(*global.get_cx()) let this = self.source.get());
+                let result = pull.Call_(
+                    &this.handle(),
+                    ReadableStreamController::ReadableByteStreamController(DomRoot::from_ref(self)),
+                    ExceptionHandling::Rethrow,
+                );
+                promise_from_callback_result(&global, result)
+            },
+            None => promise_resolved_with_undefined(&global),
+        };
+
+        // Steps 7-8.
+        upon_settlement(
+            &global,
+            &pull_promise,
+            Box::new(ByteControllerReaction {
+                controller: Dom::from_ref(self),
+                step: ByteControllerStep::PullFulfilled,
+            }),
+            Box::new(ByteControllerReaction {
+                controller: Dom::from_ref(self),
+                step: ByteControllerStep::PullRejected,
+            }),
+        );
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-should-call-pull>
+    fn should_call_pull(&self) -> bool {
+        let stream = self.stream();
+
+        // Steps 2-4.
+        if !stream.is_readable() || self.close_requested.get() || !self.started.get() {
+            return false;
+        }
+
+        // Step 5.
+        if stream.has_default_reader() && stream.get_num_read_requests() > 0 {
+            return true;
+        }
+
+        // Step 6.
+        if stream.has_byob_reader() && stream.get_num_read_into_requests() > 0 {
+            return true;
+        }
+
+        // Steps 7-9.
+        self.get_desired_size().map_or(false, |size| size > 0.)
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-clear-algorithms>
+    fn clear_algorithms(&self) {
+        *self.pull.borrow_mut() = None;
+        *self.cancel.borrow_mut() = None;
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-clear-pending-pull-intos>
+    fn clear_pending_pull_intos(&self) {
+        self.invalidate_byob_request();
+        self.pending_pull_intos.borrow_mut().clear();
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-invalidate-byob-request>
+    fn invalidate_byob_request(&self) {
+        if let Some(request) = self.byob_request.get() {
+            request.invalidate();
+        }
+        self.byob_request.set(None);
+    }
+
+    /// <https://streams.spec.whatwg.org/#reset-queue>
+    fn reset_queue(&self) {
+        self.queue.borrow_mut().clear();
+        self.queue_total_size.set(0);
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-close>
+    pub fn close(&self) -> ErrorResult {
+        let stream = self.stream();
+
+        // Steps 1-2.
+        if self.close_requested.get() || !stream.is_readable() {
+            return Ok(());
+        }
+
+        // Step 3.
+        if self.queue_total_size.get() > 0 {
+            self.close_requested.set(true);
+            return Ok(());
+        }
+
+        // Step 4.
+        let partially_filled = self
+            .pending_pull_intos
+            .borrow()
+            .front()
+            .map_or(false, |first| {
+                first.bytes_filled % first.element_size() != 0
+            });
+        if partially_filled {
+            let global = self.global();
+            let cx = global.get_cx();
+            let error = Error::Type("The stream closed in the middle of an element".to_owned());
+            rooted!(in(*cx) let mut reason = UndefinedValue());
+            error_to_jsval(&global, error.clone(), reason.handle_mut());
+            self.error(reason.handle());
+            return Err(error);
+        }
+
+        // Steps 5-6.
+        self.clear_algorithms();
+        stream.close();
+        Ok(())
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-enqueue>
+    pub fn enqueue(&self, chunk: Vec<u8>) {
+        let stream = self.stream();
+        let global = self.global();
+        let cx = global.get_cx();
+
+        // Steps 1-2.
+        if self.close_requested.get() || !stream.is_readable() {
+            return;
+        }
+
+        // Step 9.
+        if !self.pending_pull_intos.borrow().is_empty() {
+            self.invalidate_byob_request();
+        }
+
+        if stream.has_default_reader() {
+            // Step 10.
+            if stream.get_num_read_requests() == 0 {
+                self.enqueue_chunk_to_queue(chunk);
+            } else {
+                assert!(self.queue.borrow().is_empty());
+                self.pending_pull_intos.borrow_mut().pop_front();
+                rooted!(in(*cx) let mut view = ptr::null_mut::<JSObject>());
+                create_uint8_array(cx, &chunk, view.handle_mut());
+                rooted!(in(*cx) let view = ObjectValue(view.get()));
+                stream.fulfill_read_request(view.handle(), false);
+            }
+        } else if stream.has_byob_reader() {
+            // Step 11.
+            self.enqueue_chunk_to_queue(chunk);
+            self.process_pull_into_descriptors_using_queue();
+        } else {
+            // Step 12.
+            self.enqueue_chunk_to_queue(chunk);
+        }
+
+        // Step 13.
+        self.call_pull_if_needed();
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-enqueue-chunk-to-queue>
+    fn enqueue_chunk_to_queue(&self, chunk: Vec<u8>) {
+        self.queue_total_size
+            .set(self.queue_total_size.get() + chunk.len());
+        self.queue.borrow_mut().push_back(chunk);
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-error>
+    pub fn error(&self, error: HandleValue) {
+        let stream = self.stream();
+
+        // Step 2.
+        if !stream.is_readable() {
+            return;
+        }
+
+        // Steps 3-6.
+        self.clear_pending_pull_intos();
+        self.reset_queue();
+        self.clear_algorithms();
+        stream.error(error);
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-get-desired-size>
+    pub fn get_desired_size(&self) -> Option<f64> {
+        let stream = self.stream();
+        if stream.is_errored() {
+            return None;
+        }
+        if stream.is_closed() {
+            return Some(0.);
+        }
+        Some(self.strategy_hwm - self.queue_total_size.get() as f64)
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-handle-queue-drain>
+    fn handle_queue_drain(&self) {
+        if self.queue_total_size.get() == 0 && self.close_requested.get() {
+            self.clear_algorithms();
+            self.stream().close();
+        } else {
+            self.call_pull_if_needed();
+        }
+    }
+
+    /// Dequeue up to `length` bytes from the front of the queue.
+    fn dequeue_bytes(&self, length: usize) -> Vec<u8> {
+        let mut queue = self.queue.borrow_mut();
+        let mut bytes = Vec::with_capacity(length);
+        while bytes.len() < length {
+            let needed = length - bytes.len();
+            let head = queue
+                .front_mut()
+                .expect("Dequeued more bytes than the queue holds");
+            if head.len() <= needed {
+                bytes.extend(head.drain(..));
+                queue.pop_front();
+            } else {
+                bytes.extend(head.drain(..needed));
+            }
+        }
+        self.queue_total_size
+            .set(self.queue_total_size.get() - bytes.len());
+        bytes
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-fill-pull-into-descriptor-from-queue>
+    fn fill_pull_into_descriptor_from_queue(&self, descriptor: &mut PullIntoDescriptor) -> bool {
+        let element_size = descriptor.element_size();
+
+        // Steps 1-5.
+        let current_aligned_bytes =
+            descriptor.bytes_filled - descriptor.bytes_filled % element_size;
+        let max_bytes_to_copy = min(
+            self.queue_total_size.get(),
+            descriptor.byte_length - descriptor.bytes_filled,
+        );
+        let max_bytes_filled = descriptor.bytes_filled + max_bytes_to_copy;
+        let max_aligned_bytes = max_bytes_filled - max_bytes_filled % element_size;
+
+        // Steps 6-8.
+        let (total_bytes_to_copy, ready) = if max_aligned_bytes > current_aligned_bytes {
+            (max_aligned_bytes - descriptor.bytes_filled, true)
+        } else {
+            (max_bytes_to_copy, false)
+        };
+
+        // Steps 9-10.
+        let bytes = self.dequeue_bytes(total_bytes_to_copy);
+        descriptor.fill(self.global().get_cx(), &bytes);
+
+        // Step 11.
+        if !ready {
+            assert_eq!(self.queue_total_size.get(), 0);
+            assert!(descriptor.bytes_filled < element_size);
+        }
+
+        // Step 12.
+        ready
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-process-pull-into-descriptors-using-queue>
+    fn process_pull_into_descriptors_using_queue(&self) {
+        loop {
+            if self.queue_total_size.get() == 0 {
+                return;
+            }
+            let descriptor = {
+                let mut pending_pull_intos = self.pending_pull_intos.borrow_mut();
+                let ready = match pending_pull_intos.front_mut() {
+                    Some(first) => self.fill_pull_into_descriptor_from_queue(first),
+                    None => return,
+                };
+                if !ready {
+                    continue;
+                }
+                pending_pull_intos.pop_front().unwrap()
+            };
+            self.invalidate_byob_request();
+            self.commit_pull_into_descriptor(descriptor);
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-commit-pull-into-descriptor>
+    fn commit_pull_into_descriptor(&self, descriptor: PullIntoDescriptor) {
+        let stream = self.stream();
+        let cx = self.global().get_cx();
+
+        // Steps 2-3.
+        let done = stream.is_closed();
+
+        // Steps 4-6.
+        rooted!(in(*cx) let mut view = ptr::null_mut::<JSObject>());
+        descriptor.convert(cx, view.handle_mut());
+        rooted!(in(*cx) let view = ObjectValue(view.get()));
+        match descriptor.reader_type {
+            ReaderType::Default => stream.fulfill_read_request(view.handle(), done),
+            ReaderType::Byob => stream.fulfill_read_into_request(view.handle(), done),
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-pull-into>
+    #[allow(unsafe_code)]
+    pub fn pull_into(&self, view: HandleObject, promise: &Rc<Promise>) {
+        let stream = self.stream();
+        let global = self.global();
+        let cx = global.get_cx();
+
+        // Steps 1-6.
+        typedarray!(in(*cx) let typed_view: ArrayBufferView = view.get());
+        let (constructor, byte_length) = match typed_view {
+            Ok(typed_view) => (
+                ViewConstructor::from_array_type(typed_view.get_array_type()),
+                unsafe { typed_view.as_slice().len() },
+            ),
+            Err(()) => return,
+        };
+        let (buffer, byte_offset) = view_buffer_and_offset(cx, view);
+
+        // Step 7.
+        let mut descriptor = PullIntoDescriptor {
+            buffer: Heap::boxed(buffer.get()),
+            byte_offset,
+            byte_length,
+            bytes_filled: 0,
+            constructor,
+            reader_type: ReaderType::Byob,
+        };
+
+        // Step 8.
+        if !self.pending_pull_intos.borrow().is_empty() {
+            self.pending_pull_intos.borrow_mut().push_back(descriptor);
+            stream.add_read_into_request(promise);
+            return;
+        }
+
+        // Step 9.
+        if stream.is_closed() {
+            rooted!(in(*cx) let mut empty_view = ptr::null_mut::<JSObject>());
+            descriptor.convert(cx, empty_view.handle_mut());
+            rooted!(in(*cx) let empty_view = ObjectValue(empty_view.get()));
+            resolve_read_request(&global, promise, empty_view.handle(), true);
+            return;
+        }
+
+        // Step 10.
+        if self.queue_total_size.get() > 0 {
+            if self.fill_pull_into_descriptor_from_queue(&mut descriptor) {
+                rooted!(in(*cx) let mut filled_view = ptr::null_mut::<JSObject>());
+                descriptor.convert(cx, filled_view.handle_mut());
+                rooted!(in(*cx) let filled_view = ObjectValue(filled_view.get()));
+                self.handle_queue_drain();
+                resolve_read_request(&global, promise, filled_view.handle(), false);
+                return;
+            }
+            if self.close_requested.get() {
+                let error = Error::Type("The stream closed in the middle of an element".to_owned());
+                rooted!(in(*cx) let mut reason = UndefinedValue());
+                error_to_jsval(&global, error, reason.handle_mut());
+                self.error(reason.handle());
+                promise.reject(cx, reason.handle());
+                return;
+            }
+        }
+
+        // Steps 11-13.
+        self.pending_pull_intos.borrow_mut().push_back(descriptor);
+        stream.add_read_into_request(promise);
+        self.call_pull_if_needed();
+    }
+
+    /// <https://streams.spec.whatwg.org/#rbs-controller-private-pull>
+    pub fn pull_steps(&self, promise: &Rc<Promise>) {
+        let stream = self.stream();
+        let global = self.global();
+        let cx = global.get_cx();
+
+        // Step 3.
+        if self.queue_total_size.get() > 0 {
+            // https://streams.spec.whatwg.org/#readable-byte-stream-controller-fill-read-request-from-queue
+            let entry = self
+                .queue
+                .borrow_mut()
+                .pop_front()
+                .expect("The queue holds bytes");
+            self.queue_total_size
+                .set(self.queue_total_size.get() - entry.len());
+            self.handle_queue_drain();
+            rooted!(in(*cx) let mut view = ptr::null_mut::<JSObject>());
+            create_uint8_array(cx, &entry, view.handle_mut());
+            rooted!(in(*cx) let view = ObjectValue(view.get()));
+            resolve_read_request(&global, promise, view.handle(), false);
+            return;
+        }
+
+        // Step 5.
+        if let Some(chunk_size) = self.auto_allocate_chunk_size {
+            rooted!(in(*cx) let mut buffer = ptr::null_mut::<JSObject>());
+            if create_array_buffer(cx, chunk_size as usize, buffer.handle_mut()).is_err() {
+                let error = Error::Range("Could not allocate a chunk".to_owned());
+                promise.reject_error(error);
+                return;
+            }
+            self.pending_pull_intos
+                .borrow_mut()
+                .push_back(PullIntoDescriptor {
+                    buffer: Heap::boxed(buffer.get()),
+                    byte_offset: 0,
+                    byte_length: chunk_size as usize,
+                    bytes_filled: 0,
+                    constructor: ViewConstructor::Uint8,
+                    reader_type: ReaderType::Default,
+                });
+        }
+
+        // Steps 6-7.
+        stream.add_read_request(promise);
+        self.call_pull_if_needed();
+    }
+
+    /// <https://streams.spec.whatwg.org/#rbs-controller-private-cancel>
+    pub fn cancel_steps(&self, reason: HandleValue) -> Rc<Promise> {
+        let global = self.global();
+
+        // Steps 1-2.
+        self.clear_pending_pull_intos();
+        self.reset_queue();
+
+        // Step 3.
+        let cancel = self.cancel.borrow().clone();
+        let result = match cancel {
+            Some(cancel) => {
+                rooted!(in(*global.get_cx()) let this = self.source.get());
+                let result = cancel.Call_(&this.handle(), reason, ExceptionHandling::Rethrow);
+                promise_from_callback_result(&global, result)
+            },
+            None => promise_resolved_with_undefined(&global),
+        };
+
+        // Steps 4-5.
+        self.clear_algorithms();
+        result
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-respond>
+    pub fn respond(&self, bytes_written: usize) -> ErrorResult {
+        let stream = self.stream();
+
+        {
+            let pending_pull_intos = self.pending_pull_intos.borrow();
+            let first = match pending_pull_intos.front() {
+                Some(first) => first,
+                None => return Err(Error::Type("There is no pending read".to_owned())),
+            };
+
+            if stream.is_closed() {
+                // Step 4.1.
+                if bytes_written != 0 {
+                    return Err(Error::Type(
+                        "bytesWritten must be 0 once the stream is closed".to_owned(),
+                    ));
+                }
+            } else {
+                // Steps 5.1-2.
+                if bytes_written == 0 {
+                    return Err(Error::Type("bytesWritten must not be 0".to_owned()));
+                }
+                if first.bytes_filled + bytes_written > first.byte_length {
+                    return Err(Error::Range(
+                        "bytesWritten is larger than the view".to_owned(),
+                    ));
+                }
+            }
+        }
+
+        // Step 6.
+        self.respond_internal(bytes_written);
+        Ok(())
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-respond-with-new-view>
+    #[allow(unsafe_code)]
+    pub fn respond_with_new_view(&self, view: HandleObject) -> ErrorResult {
+        let cx = self.global().get_cx();
+        let stream = self.stream();
+
+        typedarray!(in(*cx) let typed_view: ArrayBufferView = view.get());
+        let view_byte_length = match typed_view {
+            Ok(typed_view) => unsafe { typed_view.as_slice().len() },
+            Err(()) => return Err(Error::Type("Expected an ArrayBufferView".to_owned())),
+        };
+        let (buffer, view_byte_offset) = view_buffer_and_offset(cx, view);
+
+        {
+            let mut pending_pull_intos = self.pending_pull_intos.borrow_mut();
+            let first = match pending_pull_intos.front_mut() {
+                Some(first) => first,
+                None => return Err(Error::Type("There is no pending read".to_owned())),
+            };
+
+            // Steps 4-5.
+            if stream.is_closed() {
+                if view_byte_length != 0 {
+                    return Err(Error::Type(
+                        "The view must be empty once the stream is closed".to_owned(),
+                    ));
+                }
+            } else if view_byte_length == 0 {
+                return Err(Error::Type("The view must not be empty".to_owned()));
+            }
+
+            // Steps 6-8.
+            if first.byte_offset + first.bytes_filled != view_byte_offset {
+                return Err(Error::Range(
+                    "The view does not start where the request view does".to_owned(),
+                ));
+            }
+            if first.bytes_filled + view_byte_length > first.byte_length {
+                return Err(Error::Range(
+                    "The view is larger than the request view".to_owned(),
+                ));
+            }
+
+            // Step 10.
+            first.buffer.set(buffer.get());
+        }
+
+        // Step 11.
+        self.respond_internal(view_byte_length);
+        Ok(())
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-respond-internal>
+    fn respond_internal(&self, bytes_written: usize) {
+        let stream = self.stream();
+
+        // Step 2.
+        self.invalidate_byob_request();
+
+        if stream.is_closed() {
+            // https://streams.spec.whatwg.org/#readable-byte-stream-controller-respond-in-closed-state
+            let first_is_default = self
+                .pending_pull_intos
+                .borrow()
+                .front()
+                .map_or(false, |first| first.reader_type == ReaderType::Default);
+            if first_is_default {
+                self.pending_pull_intos.borrow_mut().pop_front();
+            }
+            if stream.has_byob_reader() {
+                while stream.get_num_read_into_requests() > 0 {
+                    let descriptor = match self.pending_pull_intos.borrow_mut().pop_front() {
+                        Some(descriptor) => descriptor,
+                        None => break,
+                    };
+                    self.commit_pull_into_descriptor(descriptor);
+                }
+            }
+        } else {
+            // https://streams.spec.whatwg.org/#readable-byte-stream-controller-respond-in-readable-state
+            let descriptor = {
+                let mut pending_pull_intos = self.pending_pull_intos.borrow_mut();
+                let first = pending_pull_intos
+                    .front_mut()
+                    .expect("Responding without a pending read");
+
+                // Step 2.
+                first.bytes_filled += bytes_written;
+
+                // Step 4.
+                if first.bytes_filled < first.element_size() {
+                    return;
+                }
+
+                // Step 5.
+                pending_pull_intos.pop_front().unwrap()
+            };
+
+            // Steps 6-8.
+            let remainder_size = descriptor.bytes_filled % descriptor.element_size();
+            let mut descriptor = descriptor;
+            if remainder_size > 0 {
+                let end = descriptor.byte_offset + descriptor.bytes_filled;
+                let remainder = copy_from_buffer(
+                    self.global().get_cx(),
+                    &descriptor.buffer,
+                    end - remainder_size,
+                    remainder_size,
+                );
+                self.enqueue_chunk_to_queue(remainder);
+            }
+            descriptor.bytes_filled -= remainder_size;
+
+            // Steps 9-10.
+            self.commit_pull_into_descriptor(descriptor);
+            self.process_pull_into_descriptors_using_queue();
+        }
+
+        // Step 5.
+        self.call_pull_if_needed();
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-get-byob-request>
+    fn get_byob_request(&self) -> Option<DomRoot<ReadableStreamBYOBRequest>> {
+        if self.byob_request.get().is_none() {
+            let pending_pull_intos = self.pending_pull_intos.borrow();
+            let first = pending_pull_intos.front()?;
+            let cx = self.global().get_cx();
+            rooted!(in(*cx) let buffer = first.buffer.get());
+            rooted!(in(*cx) let mut view = ptr::null_mut::<JSObject>());
+            ViewConstructor::Uint8.create(
+                cx,
+                buffer.handle(),
+                first.byte_offset + first.bytes_filled,
+                first.byte_length - first.bytes_filled,
+                view.handle_mut(),
+            );
+            let request = ReadableStreamBYOBRequest::new(&self.global(), self, view.handle());
+            self.byob_request.set(Some(&request));
+        }
+        self.byob_request.get()
+    }
+
+    fn start_fulfilled(&self) {
+        self.started.set(true);
+        self.call_pull_if_needed();
+    }
+
+    fn pull_fulfilled(&self) {
+        self.pulling.set(false);
+        if self.pull_again.get() {
+            self.pull_again.set(false);
+            self.call_pull_if_needed();
+        }
+    }
+}
+
+/// The buffer `view` is a view on and the byte offset of `view` in it.
+#[allow(unsafe_code)]
+fn view_buffer_and_offset(
+    cx: SafeJSContext,
+    view: HandleObject,
+) -> (Box<Heap<*mut JSObject>>, usize) {
+    let mut is_shared = false;
+    let buffer = unsafe { JS_GetArrayBufferViewBuffer(*cx, view.into(), &mut is_shared) };
+    let offset = unsafe { JS_GetArrayBufferViewByteOffset(view.get()) };
+    (Heap::boxed(buffer), offset as usize)
+}
+
+/// Copy `length` bytes of `buffer` starting at `offset`.
+#[allow(unsafe_code)]
+fn copy_from_buffer(
+    cx: SafeJSContext,
+    buffer: &Heap<*mut JSObject>,
+    offset: usize,
+    length: usize,
+) -> Vec<u8> {
+    typedarray!(in(*cx) let buffer: ArrayBuffer = buffer.get());
+    match buffer {
+        Ok(buffer) => unsafe { buffer.as_slice()[offset..offset + length].to_vec() },
+        Err(()) => vec![],
+    }
+}
+
+#[allow(unsafe_code)]
+fn create_uint8_array(cx: SafeJSContext, bytes: &[u8], rval: MutableHandleObject) {
+    unsafe {
+        assert!(js::typedarray::Uint8Array::create(*cx, CreateWith::Slice(bytes), rval).is_ok());
+    }
+}
+
+#[allow(unsafe_code)]
+fn create_array_buffer(
+    cx: SafeJSContext,
+    length: usize,
+    rval: MutableHandleObject,
+) -> Fallible<()> {
+    unsafe {
+        ArrayBuffer::create(*cx, CreateWith::Length(length as u32), rval)
+            .map_err(|()| Error::JSFailed)
+    }
+}
+
+#[derive(Clone, Copy, JSTraceable, MallocSizeOf)]
+enum ByteControllerStep {
+    StartFulfilled,
+    StartRejected,
+    PullFulfilled,
+    PullRejected,
+}
+
+/// The steps run when the promise returned by the start or pull method of
+/// the underlying byte source settles.
+#[derive(JSTraceable, MallocSizeOf)]
+#[must_root]
+struct ByteControllerReaction {
+    controller: Dom<ReadableByteStreamController>,
+    step: ByteControllerStep,
+}
+
+impl Callback for ByteControllerReaction {
+    fn callback(&self, _cx: *mut JSContext, v: HandleValue) {
+        match self.step {
+            ByteControllerStep::StartFulfilled => self.controller.start_fulfilled(),
+            ByteControllerStep::PullFulfilled => self.controller.pull_fulfilled(),
+            ByteControllerStep::StartRejected | ByteControllerStep::PullRejected => {
+                self.controller.error(v)
+            },
+        }
+    }
+}
+
+impl ReadableByteStreamControllerMethods for ReadableByteStreamController {
+    // https://streams.spec.whatwg.org/#rbs-controller-byob-request
+    fn GetByobRequest(&self) -> Option<DomRoot<ReadableStreamBYOBRequest>> {
+        self.get_byob_request()
+    }
+
+    // https://streams.spec.whatwg.org/#rbs-controller-desired-size
+    fn GetDesiredSize(&self) -> Option<f64> {
+        self.get_desired_size()
+    }
+
+    // https://streams.spec.whatwg.org/#rbs-controller-close
+    fn Close(&self) -> ErrorResult {
+        if self.close_requested.get() {
+            return Err(Error::Type("The stream is already closing".to_owned()));
+        }
+        if !self.stream().is_readable() {
+            return Err(Error::Type("The stream is not readable".to_owned()));
+        }
+        self.close()
+    }
+
+    // https://streams.spec.whatwg.org/#rbs-controller-enqueue
+    fn Enqueue(&self, chunk: CustomAutoRooterGuard<ArrayBufferView>) -> ErrorResult {
+        let bytes = chunk.to_vec();
+        if bytes.is_empty() {
+            return Err(Error::Type("Cannot enqueue an empty chunk".to_owned()));
+        }
+        if self.close_requested.get() {
+            return Err(Error::Type("The stream is already closing".to_owned()));
+        }
+        if !self.stream().is_readable() {
+            return Err(Error::Type("The stream is not readable".to_owned()));
+        }
+        self.enqueue(bytes);
+        Ok(())
+    }
+
+    // https://streams.spec.whatwg.org/#rbs-controller-error
+    fn Error(&self, _cx: SafeJSContext, e: HandleValue) {
+        self.error(e);
+    }
+}
@@ -0,0 +1,1146 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::abortsignal::AbortAlgorithm;
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::QueuingStrategyBinding::QueuingStrategy;
+use crate::dom::bindings::codegen::Bindings::ReadableStreamBinding;
+use crate::dom::bindings::codegen::Bindings::ReadableStreamBinding::{
+    ReadableStreamGetReaderOptions, ReadableStreamMethods, ReadableStreamReaderMode,
+};
+use crate::dom::bindings::codegen::Bindings::ReadableStreamBinding::{
+    ReadableWritablePair, StreamPipeOptions,
+};
+use crate::dom::bindings::codegen::Bindings::UnderlyingSourceBinding::{
+    ReadableStreamType, UnderlyingSource,
+};
+use crate::dom::bindings::codegen::UnionTypes::ReadableStreamDefaultReaderOrReadableStreamBYOBReader as ReadableStreamReader;
+use crate::dom::bindings::conversions::{ConversionResult, ToJSValConvertible};
+use crate::dom::bindings::error::{Error, Fallible};
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot, MutNullableDom};
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::promise::Promise;
+use crate::dom::promisenativehandler::Callback;
+use crate::dom::readablebytestreamcontroller::ReadableByteStreamController;
+use crate::dom::readablestreambyobreader::ReadableStreamBYOBReader;
+use crate::dom::readablestreamdefaultcontroller::{
+    ReadableStreamDefaultController, UnderlyingSourceType,
+};
+use crate::dom::readablestreamdefaultreader::ReadableStreamDefaultReader;
+use crate::dom::writablestream::WritableStream;
+use crate::dom::writablestreamdefaultwriter::WritableStreamDefaultWriter;
+use crate::script_runtime::JSContext as SafeJSContext;
+use crate::streams::{error_to_jsval, mark_as_handled, promise_rejected_with};
+use crate::streams::{extract_high_water_mark, read_result_value, upon_settlement};
+use crate::streams::{promise_rejected_with_error, promise_resolved_with_undefined};
+use dom_struct::dom_struct;
+use js::jsapi::{Heap, JSContext, JSObject};
+use js::jsval::{JSVal, ObjectValue, UndefinedValue};
+use js::rust::{HandleValue, MutableHandleValue};
+use js::typedarray::{CreateWith, Uint8Array};
+use std::cell::Cell;
+use std::ptr;
+use std::rc::Rc;
+
+/// <https://streams.spec.whatwg.org/#readablestream-state>
+#[derive(Clone, Copy, Debug, JSTraceable, MallocSizeOf, PartialEq)]
+pub enum ReadableStreamState {
+    Readable,
+    Closed,
+    Errored,
+}
+
+#[dom_struct]
+pub struct ReadableStream {
+    reflector_: Reflector,
+    state: Cell<ReadableStreamState>,
+    disturbed: Cell<bool>,
+    #[ignore_malloc_size_of = "Defined in mozjs"]
+    stored_error: Heap<JSVal>,
+    /// The controller of a stream whose chunks are arbitrary values.
+    default_controller: MutNullableDom<ReadableStreamDefaultController>,
+    /// The controller of a readable byte stream.
+    byte_controller: MutNullableDom<ReadableByteStreamController>,
+    default_reader: MutNullableDom<ReadableStreamDefaultReader>,
+    byob_reader: MutNullableDom<ReadableStreamBYOBReader>,
+}
+
+impl ReadableStream {
+    fn new_inherited() -> ReadableStream {
+        ReadableStream {
+            reflector_: Reflector::new(),
+            state: Cell::new(ReadableStreamState::Readable),
+            disturbed: Cell::new(false),
+            stored_error: Heap::default(),
+            default_controller: Default::default(),
+            byte_controller: Default::default(),
+            default_reader: Default::default(),
+            byob_reader: Default::default(),
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#initialize-readable-stream>
+    pub fn new(global: &GlobalScope) -> DomRoot<ReadableStream> {
+        reflect_dom_object(
+            Box::new(ReadableStream::new_inherited()),
+            global,
+            ReadableStreamBinding::Wrap,
+        )
+    }
+
+    /// <https://streams.spec.whatwg.org/#create-readable-stream>
+    pub fn new_with_source(
+        global: &GlobalScope,
+        source: UnderlyingSourceType,
+        high_water_mark: f64,
+    ) -> DomRoot<ReadableStream> {
+        let stream = ReadableStream::new(global);
+        ReadableStreamDefaultController::set_up(&stream, source, None, high_water_mark, None)
+            .expect("Setting up a stream without a start algorithm cannot fail");
+        stream
+    }
+
+    /// A stream whose chunks are `Uint8Array`s enqueued by the user agent,
+    /// as used for the body of a request or response.
+    pub fn new_from_bytes(global: &GlobalScope) -> DomRoot<ReadableStream> {
+        ReadableStream::new_with_source(global, UnderlyingSourceType::Native, 1.)
+    }
+
+    /// Enqueue `bytes` as a `Uint8Array` chunk of a stream created by
+    /// `new_from_bytes`.
+    #[allow(unsafe_code)]
+    pub fn enqueue_native(&self, bytes: Vec<u8>) {
+        let controller = match self.default_controller.get() {
+            Some(controller) => controller,
+            None => return,
+        };
+        if !controller.can_close_or_enqueue() {
+            return;
+        }
+        let cx = self.global().get_cx();
+        rooted!(in(*cx) let mut array = ptr::null_mut::<JSObject>());
+        unsafe {
+            assert!(Uint8Array::create(*cx, CreateWith::Slice(&bytes), array.handle_mut()).is_ok());
+        }
+        rooted!(in(*cx) let chunk = ObjectValue(array.get()));
+        let _ = controller.enqueue(chunk.handle());
+    }
+
+    /// Close a stream created by `new_from_bytes` once all of its bytes have
+    /// been enqueued.
+    pub fn close_native(&self) {
+        if let Some(controller) = self.default_controller.get() {
+            if controller.can_close_or_enqueue() {
+                controller.close();
+            }
+        }
+    }
+
+    /// Error a stream created by `new_from_bytes`, e.g. after a network error.
+    pub fn error_native(&self, error: Error) {
+        if let Some(controller) = self.default_controller.get() {
+            let cx = self.global().get_cx();
+            rooted!(in(*cx) let mut reason = UndefinedValue());
+            error_to_jsval(&self.global(), error, reason.handle_mut());
+            controller.error(reason.handle());
+        }
+    }
+
+    pub fn set_default_controller(&self, controller: &ReadableStreamDefaultController) {
+        self.default_controller.set(Some(controller));
+    }
+
+    pub fn set_byte_controller(&self, controller: &ReadableByteStreamController) {
+        self.byte_controller.set(Some(controller));
+    }
+
+    pub fn byte_controller(&self) -> Option<DomRoot<ReadableByteStreamController>> {
+        self.byte_controller.get()
+    }
+
+    pub fn default_reader(&self) -> Option<DomRoot<ReadableStreamDefaultReader>> {
+        self.default_reader.get()
+    }
+
+    pub fn byob_reader(&self) -> Option<DomRoot<ReadableStreamBYOBReader>> {
+        self.byob_reader.get()
+    }
+
+    pub fn set_default_reader(&self, reader: Option<&ReadableStreamDefaultReader>) {
+        self.default_reader.set(reader);
+    }
+
+    pub fn set_byob_reader(&self, reader: Option<&ReadableStreamBYOBReader>) {
+        self.byob_reader.set(reader);
+    }
+
+    pub fn state(&self) -> ReadableStreamState {
+        self.state.get()
+    }
+
+    pub fn is_readable(&self) -> bool {
+        self.state.get() == ReadableStreamState::Readable
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.state.get() == ReadableStreamState::Closed
+    }
+
+    pub fn is_errored(&self) -> bool {
+        self.state.get() == ReadableStreamState::Errored
+    }
+
+    pub fn stored_error(&self, mut rval: MutableHandleValue) {
+        rval.set(self.stored_error.get());
+    }
+
+    /// <https://streams.spec.whatwg.org/#is-readable-stream-disturbed>
+    pub fn is_disturbed(&self) -> bool {
+        self.disturbed.get()
+    }
+
+    pub fn set_disturbed(&self) {
+        self.disturbed.set(true);
+    }
+
+    /// <https://streams.spec.whatwg.org/#is-readable-stream-locked>
+    pub fn is_locked(&self) -> bool {
+        self.default_reader.get().is_some() || self.byob_reader.get().is_some()
+    }
+
+    /// <https://streams.spec.whatwg.org/#acquire-readable-stream-reader>
+    pub fn acquire_default_reader(&self) -> Fallible<DomRoot<ReadableStreamDefaultReader>> {
+        ReadableStreamDefaultReader::new(&self.global(), self)
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-cancel>
+    pub fn cancel(&self, reason: HandleValue) -> Rc<Promise> {
+        let global = self.global();
+
+        // Step 1.
+        self.disturbed.set(true);
+
+        // Step 2.
+        if self.is_closed() {
+            return promise_resolved_with_undefined(&global);
+        }
+
+        // Step 3.
+        if self.is_errored() {
+            let cx = global.get_cx();
+            rooted!(in(*cx) let mut error = UndefinedValue());
+            self.stored_error(error.handle_mut());
+            return promise_rejected_with(&global, error.handle());
+        }
+
+        // Step 4.
+        self.close();
+
+        // Steps 5-6.
+        let source_cancel_promise = if let Some(controller) = self.default_controller.get() {
+            controller.cancel_steps(reason)
+        } else if let Some(controller) = self.byte_controller.get() {
+            controller.cancel_steps(reason)
+        } else {
+            unreachable!("A readable stream always has a controller");
+        };
+        let promise = Promise::new(&global);
+        upon_settlement(
+            &global,
+            &source_cancel_promise,
+            Box::new(ResolveWithUndefined(promise.clone())),
+            Box::new(RejectWithReason(promise.clone())),
+        );
+        promise
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-close>
+    pub fn close(&self) {
+        // Step 1.
+        assert!(self.is_readable());
+
+        // Step 2.
+        self.state.set(ReadableStreamState::Closed);
+
+        // Steps 3-6.
+        if let Some(reader) = self.default_reader.get() {
+            reader.close_read_requests();
+            reader.resolve_closed_promise();
+        }
+        if let Some(reader) = self.byob_reader.get() {
+            reader.resolve_closed_promise();
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-error>
+    pub fn error(&self, error: HandleValue) {
+        // Step 1.
+        assert!(self.is_readable());
+
+        // Step 2.
+        self.state.set(ReadableStreamState::Errored);
+
+        // Step 3.
+        self.stored_error.set(error.get());
+
+        // Steps 4-9.
+        if let Some(reader) = self.default_reader.get() {
+            reader.error_read_requests(error);
+            reader.reject_closed_promise(error);
+        }
+        if let Some(reader) = self.byob_reader.get() {
+            reader.error_read_into_requests(error);
+            reader.reject_closed_promise(error);
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-add-read-request>
+    pub fn add_read_request(&self, promise: &Rc<Promise>) {
+        self.default_reader
+            .get()
+            .expect("Adding a read request to a stream without a default reader")
+            .add_read_request(promise);
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-add-read-into-request>
+    pub fn add_read_into_request(&self, promise: &Rc<Promise>) {
+        self.byob_reader
+            .get()
+            .expect("Adding a read-into request to a stream without a BYOB reader")
+            .add_read_into_request(promise);
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-fulfill-read-request>
+    pub fn fulfill_read_request(&self, chunk: HandleValue, done: bool) {
+        if let Some(reader) = self.default_reader.get() {
+            reader.fulfill_read_request(chunk, done);
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-fulfill-read-into-request>
+    pub fn fulfill_read_into_request(&self, chunk: HandleValue, done: bool) {
+        if let Some(reader) = self.byob_reader.get() {
+            reader.fulfill_read_into_request(chunk, done);
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-get-num-read-requests>
+    pub fn get_num_read_requests(&self) -> usize {
+        self.default_reader
+            .get()
+            .map_or(0, |reader| reader.num_read_requests())
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-get-num-read-into-requests>
+    pub fn get_num_read_into_requests(&self) -> usize {
+        self.byob_reader
+            .get()
+            .map_or(0, |reader| reader.num_read_into_requests())
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-has-default-reader>
+    pub fn has_default_reader(&self) -> bool {
+        self.default_reader.get().is_some()
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-has-byob-reader>
+    pub fn has_byob_reader(&self) -> bool {
+        self.byob_reader.get().is_some()
+    }
+
+    /// Run the pull steps of the stream's controller for a read request.
+    pub fn perform_pull_steps(&self, promise: &Rc<Promise>) {
+        if let Some(controller) = self.default_controller.get() {
+            controller.pull_steps(promise);
+        } else if let Some(controller) = self.byte_controller.get() {
+            controller.pull_steps(promise);
+        }
+    }
+
+    /// Error the default controller of a stream this engine created.
+    pub fn error_default_controller(&self, error: HandleValue) {
+        if let Some(controller) = self.default_controller.get() {
+            controller.error(error);
+        }
+    }
+
+    /// Enqueue into the default controller of a stream this engine created.
+    pub fn enqueue_default_controller(&self, chunk: HandleValue) -> Fallible<()> {
+        match self.default_controller.get() {
+            Some(controller) => controller.enqueue(chunk),
+            None => Ok(()),
+        }
+    }
+
+    /// Close the default controller of a stream this engine created.
+    pub fn close_default_controller(&self) {
+        if let Some(controller) = self.default_controller.get() {
+            if controller.can_close_or_enqueue() {
+                controller.close();
+            }
+        }
+    }
+
+    pub fn default_controller(&self) -> Option<DomRoot<ReadableStreamDefaultController>> {
+        self.default_controller.get()
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-tee>
+    #[allow(unrooted_must_root)]
+    pub fn tee(&self) -> Fallible<(DomRoot<ReadableStream>, DomRoot<ReadableStream>)> {
+        let global = self.global();
+
+        // Steps 1-2. Readable byte streams are teed through a default
+        // reader as well, yielding two default streams of their chunks.
+        let reader = self.acquire_default_reader()?;
+
+        // Steps 3-13.
+        let state = Rc::new(TeeState {
+            stream: Dom::from_ref(self),
+            reader: Dom::from_ref(&*reader),
+            reading: Cell::new(false),
+            canceled_1: Cell::new(false),
+            canceled_2: Cell::new(false),
+            reason_1: Heap::boxed(UndefinedValue()),
+            reason_2: Heap::boxed(UndefinedValue()),
+            branch_1: Default::default(),
+            branch_2: Default::default(),
+            cancel_promise: Promise::new(&global),
+        });
+
+        // Steps 14-19.
+        let branch_1 = ReadableStream::new_with_source(
+            &global,
+            UnderlyingSourceType::Tee(state.clone(), TeeBranch::First),
+            1.,
+        );
+        let branch_2 = ReadableStream::new_with_source(
+            &global,
+            UnderlyingSourceType::Tee(state.clone(), TeeBranch::Second),
+            1.,
+        );
+        state.branch_1.set(Some(&branch_1));
+        state.branch_2.set(Some(&branch_2));
+
+        // Step 20.
+        upon_settlement(
+            &global,
+            &reader.closed_promise(),
+            Box::new(TeeReaction {
+                state: state.clone(),
+                step: TeeStep::Ignore,
+            }),
+            Box::new(TeeReaction {
+                state: state.clone(),
+                step: TeeStep::ReaderClosedRejected,
+            }),
+        );
+
+        // Step 21.
+        Ok((branch_1, branch_2))
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-pipe-to>
+    #[allow(unrooted_must_root)]
+    fn pipe_to(&self, dest: &WritableStream, options: &StreamPipeOptions) -> Rc<Promise> {
+        let global = self.global();
+
+        // Steps 1-6.
+        if self.is_locked() {
+            return promise_rejected_with_error(
+                &global,
+                Error::Type("The source stream is locked".to_owned()),
+            );
+        }
+        if dest.is_locked() {
+            return promise_rejected_with_error(
+                &global,
+                Error::Type("The destination stream is locked".to_owned()),
+            );
+        }
+
+        // Steps 7-8.
+        let reader = match self.acquire_default_reader() {
+            Ok(reader) => reader,
+            Err(error) => return promise_rejected_with_error(&global, error),
+        };
+        let writer = match dest.acquire_writer() {
+            Ok(writer) => writer,
+            Err(error) => {
+                reader.release();
+                return promise_rejected_with_error(&global, error);
+            },
+        };
+
+        // Step 9.
+        self.disturbed.set(true);
+
+        // Steps 10-12.
+        let state = Rc::new(PipeState {
+            source: Dom::from_ref(self),
+            dest: Dom::from_ref(dest),
+            reader: Dom::from_ref(&*reader),
+            writer: Dom::from_ref(&*writer),
+            prevent_close: options.preventClose,
+            prevent_abort: options.preventAbort,
+            prevent_cancel: options.preventCancel,
+            shutting_down: Cell::new(false),
+            pending_write: DomRefCell::new(None),
+            promise: Promise::new(&global),
+        });
+
+        // Step 13. The signal aborts the pipe.
+        if let Some(ref signal) = options.signal {
+            if signal.aborted() {
+                state.abort();
+                return state.promise.clone();
+            }
+            signal.add_algorithm(AbortAlgorithm::StreamPipe(state.clone()));
+        }
+
+        // Step 14. Errors and closure of either end are observed through
+        // the closed promises of the reader and the writer.
+        upon_settlement(
+            &global,
+            &reader.closed_promise(),
+            Box::new(PipeReaction::new(&state, PipeStep::SourceClosed)),
+            Box::new(PipeReaction::new(&state, PipeStep::SourceErrored)),
+        );
+        upon_settlement(
+            &global,
+            &writer.closed_promise(),
+            Box::new(PipeReaction::new(&state, PipeStep::Ignore)),
+            Box::new(PipeReaction::new(&state, PipeStep::DestErrored)),
+        );
+        state.check_dest_closed();
+        state.pipe_step();
+
+        // Step 15.
+        state.promise.clone()
+    }
+}
+
+impl ReadableStreamMethods for ReadableStream {
+    // https://streams.spec.whatwg.org/#rs-locked
+    fn Locked(&self) -> bool {
+        self.is_locked()
+    }
+
+    // https://streams.spec.whatwg.org/#rs-cancel
+    fn Cancel(&self, _cx: SafeJSContext, reason: HandleValue) -> Rc<Promise> {
+        if self.is_locked() {
+            return promise_rejected_with_error(
+                &self.global(),
+                Error::Type("Cannot cancel a locked stream".to_owned()),
+            );
+        }
+        self.cancel(reason)
+    }
+
+    // https://streams.spec.whatwg.org/#rs-get-reader
+    fn GetReader(
+        &self,
+        options: &ReadableStreamGetReaderOptions,
+    ) -> Fallible<ReadableStreamReader> {
+        match options.mode {
+            None => Ok(ReadableStreamReader::ReadableStreamDefaultReader(
+                self.acquire_default_reader()?,
+            )),
+            Some(ReadableStreamReaderMode::Byob) => {
+                Ok(ReadableStreamReader::ReadableStreamBYOBReader(
+                    ReadableStreamBYOBReader::new(&self.global(), self)?,
+                ))
+            },
+        }
+    }
+
+    // https://streams.spec.whatwg.org/#rs-pipe-through
+    fn PipeThrough(
+        &self,
+        transform: &ReadableWritablePair,
+        options: &StreamPipeOptions,
+    ) -> Fallible<DomRoot<ReadableStream>> {
+        // Steps 1-2.
+        if self.is_locked() {
+            return Err(Error::Type("The source stream is locked".to_owned()));
+        }
+        if transform.writable.is_locked() {
+            return Err(Error::Type("The writable side is locked".to_owned()));
+        }
+
+        // Steps 3-4.
+        let promise = self.pipe_to(&transform.writable, options);
+        mark_as_handled(&self.global(), &promise);
+
+        // Step 5.
+        Ok(DomRoot::from_ref(&*transform.readable))
+    }
+
+    // https://streams.spec.whatwg.org/#rs-pipe-to
+    fn PipeTo(&self, destination: &WritableStream, options: &StreamPipeOptions) -> Rc<Promise> {
+        self.pipe_to(destination, options)
+    }
+
+    // https://streams.spec.whatwg.org/#rs-tee
+    fn Tee(&self) -> Fallible<Vec<DomRoot<ReadableStream>>> {
+        let (branch_1, branch_2) = self.tee()?;
+        Ok(vec![branch_1, branch_2])
+    }
+}
+
+impl ReadableStream {
+    // https://streams.spec.whatwg.org/#rs-constructor
+    pub fn Constructor(
+        cx: SafeJSContext,
+        global: &GlobalScope,
+        underlying_source: Option<*mut JSObject>,
+        strategy: &QueuingStrategy,
+    ) -> Fallible<DomRoot<ReadableStream>> {
+        // Step 1.
+        rooted!(in(*cx) let mut source_object = ptr::null_mut::<JSObject>());
+        rooted!(in(*cx) let mut source_value = UndefinedValue());
+        if let Some(object) = underlying_source {
+            source_object.set(object);
+            source_value.set(ObjectValue(object));
+        }
+        let source = match UnderlyingSource::new(cx, source_value.handle()) {
+            Ok(ConversionResult::Success(source)) => source,
+            Ok(ConversionResult::Failure(error)) => return Err(Error::Type(error.into_owned())),
+            Err(_) => return Err(Error::JSFailed),
+        };
+
+        // Step 2.
+        let stream = ReadableStream::new(global);
+
+        match source.type_ {
+            // Step 3.
+            Some(ReadableStreamType::Bytes) => {
+                // Step 3.1.
+                if strategy.size.is_some() {
+                    return Err(Error::Range(
+                        "A readable byte stream cannot have a size algorithm".to_owned(),
+                    ));
+                }
+
+                // Step 3.2.
+                let high_water_mark = extract_high_water_mark(strategy, 0.)?;
+
+                // Step 3.3.
+                ReadableByteStreamController::set_up_from_underlying_source(
+                    &stream,
+                    source_object.handle(),
+                    &source,
+                    high_water_mark,
+                )?;
+            },
+            // Step 4.
+            None => {
+                // Step 4.2.
+                let size = strategy.size.clone();
+
+                // Step 4.3.
+                let high_water_mark = extract_high_water_mark(strategy, 1.)?;
+
+                // Step 4.4.
+                ReadableStreamDefaultController::set_up_from_underlying_source(
+                    &stream,
+                    source_object.handle(),
+                    &source,
+                    high_water_mark,
+                    size,
+                )?;
+            },
+        }
+
+        Ok(stream)
+    }
+}
+
+/// Resolve a promise with undefined once the promise it watches is fulfilled.
+#[derive(JSTraceable, MallocSizeOf)]
+pub struct ResolveWithUndefined(#[ignore_malloc_size_of = "Rc"] pub Rc<Promise>);
+
+impl Callback for ResolveWithUndefined {
+    fn callback(&self, _cx: *mut JSContext, _v: HandleValue) {
+        self.0.resolve_native(&());
+    }
+}
+
+/// Reject a promise with the reason of the promise it watches.
+#[derive(JSTraceable, MallocSizeOf)]
+pub struct RejectWithReason(#[ignore_malloc_size_of = "Rc"] pub Rc<Promise>);
+
+impl Callback for RejectWithReason {
+    #[allow(unsafe_code)]
+    fn callback(&self, cx: *mut JSContext, v: HandleValue) {
+        self.0.reject(unsafe { SafeJSContext::from_ptr(cx) }, v);
+    }
+}
+
+/// Which branch of a teed stream an underlying source feeds.
+#[derive(Clone, Copy, JSTraceable, MallocSizeOf, PartialEq)]
+pub enum TeeBranch {
+    First,
+    Second,
+}
+
+/// The state shared by the two branches of a teed stream.
+/// <https://streams.spec.whatwg.org/#readable-stream-default-tee>
+#[derive(JSTraceable, MallocSizeOf)]
+#[must_root]
+#[allow_unrooted_in_rc]
+pub struct TeeState {
+    stream: Dom<ReadableStream>,
+    reader: Dom<ReadableStreamDefaultReader>,
+    reading: Cell<bool>,
+    canceled_1: Cell<bool>,
+    canceled_2: Cell<bool>,
+    #[ignore_malloc_size_of = "Defined in mozjs"]
+    reason_1: Box<Heap<JSVal>>,
+    #[ignore_malloc_size_of = "Defined in mozjs"]
+    reason_2: Box<Heap<JSVal>>,
+    branch_1: MutNullableDom<ReadableStream>,
+    branch_2: MutNullableDom<ReadableStream>,
+    #[ignore_malloc_size_of = "Rc"]
+    cancel_promise: Rc<Promise>,
+}
+
+impl TeeState {
+    /// The pull algorithm of both branches.
+    #[allow(unrooted_must_root)]
+    pub fn pull(self: &Rc<Self>) -> Rc<Promise> {
+        let global = self.stream.global();
+
+        // Step 12.1.
+        if self.reading.get() {
+            return promise_resolved_with_undefined(&global);
+        }
+
+        // Step 12.2.
+        self.reading.set(true);
+
+        // Steps 12.3-4.
+        let read_promise = self.reader.read();
+        upon_settlement(
+            &global,
+            &read_promise,
+            Box::new(TeeReaction {
+                state: self.clone(),
+                step: TeeStep::ReadFulfilled,
+            }),
+            Box::new(TeeReaction {
+                state: self.clone(),
+                step: TeeStep::Ignore,
+            }),
+        );
+
+        // Step 12.5.
+        promise_resolved_with_undefined(&global)
+    }
+
+    /// The cancel algorithm of either branch.
+    #[allow(unsafe_code)]
+    pub fn cancel(&self, branch: TeeBranch, reason: HandleValue) -> Rc<Promise> {
+        let global = self.stream.global();
+        let cx = global.get_cx();
+
+        // Steps 13.1-2 and 14.1-2.
+        let other_canceled = match branch {
+            TeeBranch::First => {
+                self.canceled_1.set(true);
+                self.reason_1.set(reason.get());
+                self.canceled_2.get()
+            },
+            TeeBranch::Second => {
+                self.canceled_2.set(true);
+                self.reason_2.set(reason.get());
+                self.canceled_1.get()
+            },
+        };
+
+        // Step 13.3 and 14.3.
+        if other_canceled {
+            rooted!(in(*cx) let mut composite_reason = UndefinedValue());
+            unsafe {
+                vec![self.reason_1.get(), self.reason_2.get()]
+                    .to_jsval(*cx, composite_reason.handle_mut());
+            }
+            let cancel_result = self.stream.cancel(composite_reason.handle());
+            upon_settlement(
+                &global,
+                &cancel_result,
+                Box::new(ResolveWithUndefined(self.cancel_promise.clone())),
+                Box::new(RejectWithReason(self.cancel_promise.clone())),
+            );
+        }
+
+        // Step 13.4 and 14.4.
+        self.cancel_promise.clone()
+    }
+
+    /// The fulfillment steps of a read from the teed stream.
+    fn read_fulfilled(&self, result: HandleValue) {
+        let global = self.stream.global();
+        let cx = global.get_cx();
+
+        // Step 12.4.1.
+        self.reading.set(false);
+
+        // Steps 12.4.2-5.
+        rooted!(in(*cx) let mut value = UndefinedValue());
+        let done = read_result_value(&global, result, value.handle_mut());
+
+        let branches = [
+            (self.canceled_1.get(), self.branch_1.get()),
+            (self.canceled_2.get(), self.branch_2.get()),
+        ];
+        for &(canceled, ref branch) in branches.iter() {
+            let branch = match *branch {
+                Some(ref branch) if !canceled => branch,
+                _ => continue,
+            };
+            if done {
+                // Step 12.4.6.
+                branch.close_default_controller();
+            } else {
+                // Steps 12.4.7-11.
+                let _ = branch.enqueue_default_controller(value.handle());
+            }
+        }
+    }
+
+    /// Step 20: propagate an error of the teed stream to both branches.
+    fn reader_closed_rejected(&self, reason: HandleValue) {
+        if let Some(branch) = self.branch_1.get() {
+            branch.error_default_controller(reason);
+        }
+        if let Some(branch) = self.branch_2.get() {
+            branch.error_default_controller(reason);
+        }
+    }
+}
+
+#[derive(Clone, Copy, JSTraceable, MallocSizeOf)]
+enum TeeStep {
+    ReadFulfilled,
+    ReaderClosedRejected,
+    Ignore,
+}
+
+#[derive(JSTraceable, MallocSizeOf)]
+struct TeeReaction {
+    #[ignore_malloc_size_of = "Rc"]
+    state: Rc<TeeState>,
+    step: TeeStep,
+}
+
+impl Callback for TeeReaction {
+    fn callback(&self, _cx: *mut JSContext, v: HandleValue) {
+        match self.step {
+            TeeStep::ReadFulfilled => self.state.read_fulfilled(v),
+            TeeStep::ReaderClosedRejected => self.state.reader_closed_rejected(v),
+            TeeStep::Ignore => {},
+        }
+    }
+}
+
+/// The state of a pipe between a readable and a writable stream.
+/// <https://streams.spec.whatwg.org/#readable-stream-pipe-to>
+#[derive(JSTraceable, MallocSizeOf)]
+#[must_root]
+#[allow_unrooted_in_rc]
+pub struct PipeState {
+    source: Dom<ReadableStream>,
+    dest: Dom<WritableStream>,
+    reader: Dom<ReadableStreamDefaultReader>,
+    writer: Dom<WritableStreamDefaultWriter>,
+    prevent_close: bool,
+    prevent_abort: bool,
+    prevent_cancel: bool,
+    shutting_down: Cell<bool>,
+    /// The promise of the last write, which shutdown waits for.
+    #[ignore_malloc_size_of = "Rc"]
+    pending_write: DomRefCell<Option<Rc<Promise>>>,
+    #[ignore_malloc_size_of = "Rc"]
+    promise: Rc<Promise>,
+}
+
+/// The action to perform on the other end once one end of a pipe closes or
+/// errors.
+#[derive(Clone, Copy, JSTraceable, MallocSizeOf)]
+enum ShutdownAction {
+    AbortDest,
+    AbortDestAndCancelSource,
+    CancelSource,
+    CloseDest,
+}
+
+#[derive(Clone, Copy, JSTraceable, MallocSizeOf)]
+enum PipeStep {
+    WriterReady,
+    ReadFulfilled,
+    SourceClosed,
+    SourceErrored,
+    DestErrored,
+    /// The last write settled, perform the action of the shutdown.
+    WriteSettled(Option<ShutdownAction>),
+    /// The shutdown action settled, finalize the pipe.
+    ActionFulfilled,
+    ActionRejected,
+    Ignore,
+}
+
+#[derive(JSTraceable, MallocSizeOf)]
+struct PipeReaction {
+    #[ignore_malloc_size_of = "Rc"]
+    state: Rc<PipeState>,
+    step: PipeStep,
+    /// The error the pipe is shut down with, if any.
+    #[ignore_malloc_size_of = "Defined in mozjs"]
+    error: Box<Heap<JSVal>>,
+    has_error: bool,
+}
+
+impl PipeReaction {
+    fn new(state: &Rc<PipeState>, step: PipeStep) -> PipeReaction {
+        PipeReaction {
+            state: state.clone(),
+            step,
+            error: Heap::boxed(UndefinedValue()),
+            has_error: false,
+        }
+    }
+
+    fn with_error(
+        state: &Rc<PipeState>,
+        step: PipeStep,
+        error: Option<HandleValue>,
+    ) -> PipeReaction {
+        PipeReaction {
+            state: state.clone(),
+            step,
+            error: Heap::boxed(error.map_or(UndefinedValue(), |error| error.get())),
+            has_error: error.is_some(),
+        }
+    }
+}
+
+impl Callback for PipeReaction {
+    fn callback(&self, cx: *mut JSContext, v: HandleValue) {
+        let state = &self.state;
+        rooted!(in(cx) let error = self.error.get());
+        let error = if self.has_error {
+            Some(error.handle())
+        } else {
+            None
+        };
+        match self.step {
+            PipeStep::WriterReady => state.read_chunk(),
+            PipeStep::ReadFulfilled => state.write_chunk(v),
+            PipeStep::SourceClosed => state.source_closed(),
+            PipeStep::SourceErrored => state.source_errored(v),
+            PipeStep::DestErrored => state.dest_errored(v),
+            PipeStep::WriteSettled(action) => state.perform_action(action, error),
+            PipeStep::ActionFulfilled => state.finalize(error),
+            PipeStep::ActionRejected => state.finalize(Some(v)),
+            PipeStep::Ignore => {},
+        }
+    }
+}
+
+impl PipeState {
+    /// Wait for the destination to be ready, then read the next chunk.
+    #[allow(unrooted_must_root)]
+    fn pipe_step(self: &Rc<Self>) {
+        if self.shutting_down.get() {
+            return;
+        }
+        let global = self.source.global();
+        upon_settlement(
+            &global,
+            &self.writer.ready_promise(),
+            Box::new(PipeReaction::new(self, PipeStep::WriterReady)),
+            Box::new(PipeReaction::new(self, PipeStep::Ignore)),
+        );
+    }
+
+    #[allow(unrooted_must_root)]
+    fn read_chunk(self: &Rc<Self>) {
+        if self.shutting_down.get() {
+            return;
+        }
+        let global = self.source.global();
+        let read_promise = self.reader.read();
+        upon_settlement(
+            &global,
+            &read_promise,
+            Box::new(PipeReaction::new(self, PipeStep::ReadFulfilled)),
+            Box::new(PipeReaction::new(self, PipeStep::Ignore)),
+        );
+    }
+
+    fn write_chunk(self: &Rc<Self>, result: HandleValue) {
+        if self.shutting_down.get() {
+            return;
+        }
+        let global = self.source.global();
+        let cx = global.get_cx();
+        rooted!(in(*cx) let mut chunk = UndefinedValue());
+        if read_result_value(&global, result, chunk.handle_mut()) {
+            // The source closed, which the closed promise of the reader reports.
+            return;
+        }
+        let write_promise = self.writer.write(chunk.handle());
+        mark_as_handled(&global, &write_promise);
+        *self.pending_write.borrow_mut() = Some(write_promise);
+        self.check_dest_closed();
+        self.pipe_step();
+    }
+
+    /// Errors must be propagated forward: an errored source aborts the
+    /// destination.
+    fn source_errored(self: &Rc<Self>, error: HandleValue) {
+        if self.prevent_abort {
+            self.shutdown(None, Some(error));
+        } else {
+            self.shutdown(Some(ShutdownAction::AbortDest), Some(error));
+        }
+    }
+
+    /// Errors must be propagated backward: an errored destination cancels the
+    /// source.
+    fn dest_errored(self: &Rc<Self>, error: HandleValue) {
+        if self.prevent_cancel {
+            self.shutdown(None, Some(error));
+        } else {
+            self.shutdown(Some(ShutdownAction::CancelSource), Some(error));
+        }
+    }
+
+    /// Shut down the pipe with an `AbortError` once the signal it was
+    /// created with is aborted.
+    pub fn abort(self: &Rc<Self>) {
+        if self.shutting_down.get() {
+            return;
+        }
+        let global = self.source.global();
+        let cx = global.get_cx();
+        rooted!(in(*cx) let mut error = UndefinedValue());
+        error_to_jsval(&global, Error::Abort, error.handle_mut());
+        let abort_dest = !self.prevent_abort && self.dest.is_writable();
+        let cancel_source = !self.prevent_cancel && self.source.is_readable();
+        let action = match (abort_dest, cancel_source) {
+            (true, true) => Some(ShutdownAction::AbortDestAndCancelSource),
+            (true, false) => Some(ShutdownAction::AbortDest),
+            (false, true) => Some(ShutdownAction::CancelSource),
+            (false, false) => None,
+        };
+        self.shutdown(action, Some(error.handle()));
+    }
+
+    /// Closing must be propagated forward: a closed source closes the
+    /// destination.
+    fn source_closed(self: &Rc<Self>) {
+        if self.prevent_close {
+            self.shutdown(None, None);
+        } else {
+            self.shutdown(Some(ShutdownAction::CloseDest), None);
+        }
+    }
+
+    /// Closing must be propagated backward: a destination that is closing
+    /// or closed cancels the source.
+    fn check_dest_closed(self: &Rc<Self>) {
+        if self.shutting_down.get() || !self.dest.is_closing_or_closed() {
+            return;
+        }
+        let global = self.source.global();
+        let cx = global.get_cx();
+        rooted!(in(*cx) let mut error = UndefinedValue());
+        error_to_jsval(
+            &global,
+            Error::Type("The destination stream closed".to_owned()),
+            error.handle_mut(),
+        );
+        if self.prevent_cancel {
+            self.shutdown(None, Some(error.handle()));
+        } else {
+            self.shutdown(Some(ShutdownAction::CancelSource), Some(error.handle()));
+        }
+    }
+
+    /// Shutdown with an optional action, waiting for the pending write first.
+    #[allow(unrooted_must_root)]
+    fn shutdown(self: &Rc<Self>, action: Option<ShutdownAction>, error: Option<HandleValue>) {
+        if self.shutting_down.get() {
+            return;
+        }
+        self.shutting_down.set(true);
+        let global = self.source.global();
+        let pending_write = self.pending_write.borrow_mut().take();
+        match pending_write {
+            Some(write) if self.dest.is_writable() => upon_settlement(
+                &global,
+                &write,
+                Box::new(PipeReaction::with_error(
+                    self,
+                    PipeStep::WriteSettled(action),
+                    error,
+                )),
+                Box::new(PipeReaction::with_error(
+                    self,
+                    PipeStep::WriteSettled(action),
+                    error,
+                )),
+            ),
+            _ => self.perform_action(action, error),
+        }
+    }
+
+    #[allow(unrooted_must_root)]
+    fn perform_action(self: &Rc<Self>, action: Option<ShutdownAction>, error: Option<HandleValue>) {
+        let global = self.source.global();
+        let cx = global.get_cx();
+        rooted!(in(*cx) let mut reason = UndefinedValue());
+        if let Some(error) = error {
+            reason.set(error.get());
+        }
+        let action_promise = match action {
+            None => return self.finalize(error),
+            Some(ShutdownAction::AbortDest) => self.dest.abort(reason.handle()),
+            Some(ShutdownAction::AbortDestAndCancelSource) => {
+                // Only the abort of the destination is waited for.
+                let cancel_promise = self.source.cancel(reason.handle());
+                mark_as_handled(&global, &cancel_promise);
+                self.dest.abort(reason.handle())
+            },
+            Some(ShutdownAction::CancelSource) => self.source.cancel(reason.handle()),
+            Some(ShutdownAction::CloseDest) => self.writer.close_with_error_propagation(),
+        };
+        upon_settlement(
+            &global,
+            &action_promise,
+            Box::new(PipeReaction::with_error(
+                self,
+                PipeStep::ActionFulfilled,
+                error,
+            )),
+            Box::new(PipeReaction::new(self, PipeStep::ActionRejected)),
+        );
+    }
+
+    /// <https://streams.spec.whatwg.org/#rs-pipeTo-finalize>
+    fn finalize(&self, error: Option<HandleValue>) {
+        // Steps 1-2.
+        self.writer.release();
+        self.reader.release();
+
+        // Steps 3-4.
+        match error {
+            Some(error) => self.promise.reject(self.source.global().get_cx(), error),
+            None => self.promise.resolve_native(&()),
+        }
+    }
+}
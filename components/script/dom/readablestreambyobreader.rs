@@ -0,0 +1,256 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::ReadableStreamBYOBReaderBinding;
+use crate::dom::bindings::codegen::Bindings::ReadableStreamBYOBReaderBinding::ReadableStreamBYOBReaderMethods;
+use crate::dom::bindings::error::{Error, ErrorResult, Fallible};
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{DomRoot, MutNullableDom};
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::promise::Promise;
+use crate::dom::readablestream::{ReadableStream, ReadableStreamState};
+use crate::script_runtime::JSContext as SafeJSContext;
+use crate::streams::{error_to_jsval, mark_as_handled};
+use crate::streams::{promise_rejected_with_error, resolve_read_request};
+use dom_struct::dom_struct;
+use js::jsval::UndefinedValue;
+use js::rust::{CustomAutoRooterGuard, HandleValue};
+use js::typedarray::ArrayBufferView;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+#[dom_struct]
+pub struct ReadableStreamBYOBReader {
+    reflector_: Reflector,
+    /// <https://streams.spec.whatwg.org/#readablestreamgenericreader-stream>
+    stream: MutNullableDom<ReadableStream>,
+    /// <https://streams.spec.whatwg.org/#readablestreamgenericreader-closedpromise>
+    #[ignore_malloc_size_of = "Rc"]
+    closed_promise: DomRefCell<Rc<Promise>>,
+    /// <https://streams.spec.whatwg.org/#readablestreambyobreader-readintorequests>
+    #[ignore_malloc_size_of = "Rc"]
+    read_into_requests: DomRefCell<VecDeque<Rc<Promise>>>,
+}
+
+impl ReadableStreamBYOBReader {
+    fn new_inherited(closed_promise: Rc<Promise>) -> ReadableStreamBYOBReader {
+        ReadableStreamBYOBReader {
+            reflector_: Reflector::new(),
+            stream: Default::default(),
+            closed_promise: DomRefCell::new(closed_promise),
+            read_into_requests: DomRefCell::new(VecDeque::new()),
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#set-up-readable-stream-byob-reader>
+    pub fn new(
+        global: &GlobalScope,
+        stream: &ReadableStream,
+    ) -> Fallible<DomRoot<ReadableStreamBYOBReader>> {
+        // Step 1.
+        if stream.is_locked() {
+            return Err(Error::Type("The stream is already locked".to_owned()));
+        }
+
+        // Step 2.
+        if stream.byte_controller().is_none() {
+            return Err(Error::Type(
+                "A BYOB reader can only read from a readable byte stream".to_owned(),
+            ));
+        }
+
+        let reader = reflect_dom_object(
+            Box::new(ReadableStreamBYOBReader::new_inherited(Promise::new(
+                global,
+            ))),
+            global,
+            ReadableStreamBYOBReaderBinding::Wrap,
+        );
+
+        // Step 3.
+        reader.generic_initialize(stream);
+
+        // Step 4 is done by new_inherited.
+        Ok(reader)
+    }
+
+    // https://streams.spec.whatwg.org/#byob-reader-constructor
+    pub fn Constructor(
+        global: &GlobalScope,
+        stream: &ReadableStream,
+    ) -> Fallible<DomRoot<ReadableStreamBYOBReader>> {
+        ReadableStreamBYOBReader::new(global, stream)
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-reader-generic-initialize>
+    fn generic_initialize(&self, stream: &ReadableStream) {
+        // Steps 1-2.
+        self.stream.set(Some(stream));
+        stream.set_byob_reader(Some(self));
+
+        match stream.state() {
+            // Step 3.
+            ReadableStreamState::Readable => {},
+            // Step 4.
+            ReadableStreamState::Closed => self.resolve_closed_promise(),
+            // Step 5.
+            ReadableStreamState::Errored => {
+                let cx = self.global().get_cx();
+                rooted!(in(*cx) let mut error = UndefinedValue());
+                stream.stored_error(error.handle_mut());
+                self.reject_closed_promise(error.handle());
+            },
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-reader-generic-release>
+    pub fn release(&self) {
+        let global = self.global();
+        let stream = match self.stream.get() {
+            Some(stream) => stream,
+            None => return,
+        };
+
+        // Steps 3-4.
+        let error = Error::Type("The reader's lock was released".to_owned());
+        if stream.is_readable() {
+            let cx = global.get_cx();
+            rooted!(in(*cx) let mut reason = UndefinedValue());
+            error_to_jsval(&global, error, reason.handle_mut());
+            self.reject_closed_promise(reason.handle());
+        } else {
+            let closed_promise = promise_rejected_with_error(&global, error);
+            mark_as_handled(&global, &closed_promise);
+            *self.closed_promise.borrow_mut() = closed_promise;
+        }
+
+        // Steps 5-6.
+        stream.set_byob_reader(None);
+        self.stream.set(None);
+    }
+
+    pub fn closed_promise(&self) -> Rc<Promise> {
+        self.closed_promise.borrow().clone()
+    }
+
+    pub fn resolve_closed_promise(&self) {
+        self.closed_promise.borrow().resolve_native(&());
+    }
+
+    pub fn reject_closed_promise(&self, reason: HandleValue) {
+        let global = self.global();
+        let closed_promise = self.closed_promise();
+        closed_promise.reject(global.get_cx(), reason);
+        mark_as_handled(&global, &closed_promise);
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-add-read-into-request>
+    pub fn add_read_into_request(&self, promise: &Rc<Promise>) {
+        self.read_into_requests
+            .borrow_mut()
+            .push_back(promise.clone());
+    }
+
+    pub fn num_read_into_requests(&self) -> usize {
+        self.read_into_requests.borrow().len()
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-fulfill-read-into-request>
+    pub fn fulfill_read_into_request(&self, chunk: HandleValue, done: bool) {
+        let promise = self
+            .read_into_requests
+            .borrow_mut()
+            .pop_front()
+            .expect("Fulfilling a read-into request that does not exist");
+        resolve_read_request(&self.global(), &promise, chunk, done);
+    }
+
+    /// Reject every pending read-into request, as the stream errored.
+    pub fn error_read_into_requests(&self, error: HandleValue) {
+        let cx = self.global().get_cx();
+        let requests: Vec<_> = self.read_into_requests.borrow_mut().drain(..).collect();
+        for promise in requests {
+            promise.reject(cx, error);
+        }
+    }
+}
+
+impl ReadableStreamBYOBReaderMethods for ReadableStreamBYOBReader {
+    // https://streams.spec.whatwg.org/#byob-reader-closed
+    fn Closed(&self) -> Rc<Promise> {
+        self.closed_promise()
+    }
+
+    // https://streams.spec.whatwg.org/#byob-reader-cancel
+    fn Cancel(&self, _cx: SafeJSContext, reason: HandleValue) -> Rc<Promise> {
+        match self.stream.get() {
+            Some(stream) => stream.cancel(reason),
+            None => promise_rejected_with_error(
+                &self.global(),
+                Error::Type("The reader has no stream".to_owned()),
+            ),
+        }
+    }
+
+    // https://streams.spec.whatwg.org/#byob-reader-read
+    #[allow(unsafe_code)]
+    fn Read(&self, view: CustomAutoRooterGuard<ArrayBufferView>) -> Rc<Promise> {
+        let global = self.global();
+
+        // Step 1.
+        let stream = match self.stream.get() {
+            Some(stream) => stream,
+            None => {
+                return promise_rejected_with_error(
+                    &global,
+                    Error::Type("The reader has no stream".to_owned()),
+                );
+            },
+        };
+
+        // Step 2.
+        if unsafe { view.as_slice() }.is_empty() {
+            return promise_rejected_with_error(
+                &global,
+                Error::Type("Cannot read into an empty view".to_owned()),
+            );
+        }
+
+        // Step 3.
+        stream.set_disturbed();
+
+        // Step 4.
+        let promise = Promise::new(&global);
+        if stream.is_errored() {
+            let cx = global.get_cx();
+            rooted!(in(*cx) let mut error = UndefinedValue());
+            stream.stored_error(error.handle_mut());
+            promise.reject(cx, error.handle());
+            return promise;
+        }
+
+        // Step 5.
+        let controller = stream
+            .byte_controller()
+            .expect("A BYOB reader is only created for a readable byte stream");
+        rooted!(in(*global.get_cx()) let view = unsafe { *view.underlying_object() });
+        controller.pull_into(view.handle(), &promise);
+        promise
+    }
+
+    // https://streams.spec.whatwg.org/#byob-reader-release-lock
+    fn ReleaseLock(&self) -> ErrorResult {
+        if self.stream.get().is_none() {
+            return Ok(());
+        }
+        if !self.read_into_requests.borrow().is_empty() {
+            return Err(Error::Type(
+                "Cannot release a reader with pending read-into requests".to_owned(),
+            ));
+        }
+        self.release();
+        Ok(())
+    }
+}
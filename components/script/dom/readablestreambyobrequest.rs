@@ -0,0 +1,92 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::codegen::Bindings::ReadableStreamBYOBRequestBinding;
+use crate::dom::bindings::codegen::Bindings::ReadableStreamBYOBRequestBinding::ReadableStreamBYOBRequestMethods;
+use crate::dom::bindings::error::{Error, ErrorResult};
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{DomRoot, MutNullableDom};
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::readablebytestreamcontroller::ReadableByteStreamController;
+use crate::script_runtime::JSContext as SafeJSContext;
+use dom_struct::dom_struct;
+use js::jsapi::{Heap, JSObject};
+use js::rust::{CustomAutoRooterGuard, HandleObject};
+use js::typedarray::ArrayBufferView;
+use std::ptr::{self, NonNull};
+
+/// <https://streams.spec.whatwg.org/#rs-byob-request-class>
+#[dom_struct]
+pub struct ReadableStreamBYOBRequest {
+    reflector_: Reflector,
+    /// <https://streams.spec.whatwg.org/#readablestreambyobrequest-controller>
+    controller: MutNullableDom<ReadableByteStreamController>,
+    /// <https://streams.spec.whatwg.org/#readablestreambyobrequest-view>
+    #[ignore_malloc_size_of = "Defined in mozjs"]
+    view: Heap<*mut JSObject>,
+}
+
+impl ReadableStreamBYOBRequest {
+    fn new_inherited(controller: &ReadableByteStreamController) -> ReadableStreamBYOBRequest {
+        ReadableStreamBYOBRequest {
+            reflector_: Reflector::new(),
+            controller: MutNullableDom::new(Some(controller)),
+            view: Heap::default(),
+        }
+    }
+
+    pub fn new(
+        global: &GlobalScope,
+        controller: &ReadableByteStreamController,
+        view: HandleObject,
+    ) -> DomRoot<ReadableStreamBYOBRequest> {
+        let request = reflect_dom_object(
+            Box::new(ReadableStreamBYOBRequest::new_inherited(controller)),
+            global,
+            ReadableStreamBYOBRequestBinding::Wrap,
+        );
+        request.view.set(view.get());
+        request
+    }
+
+    /// Detach this request from its controller once the pending read it was
+    /// created for is answered.
+    /// <https://streams.spec.whatwg.org/#readable-byte-stream-controller-invalidate-byob-request>
+    pub fn invalidate(&self) {
+        self.controller.set(None);
+        self.view.set(ptr::null_mut());
+    }
+}
+
+impl ReadableStreamBYOBRequestMethods for ReadableStreamBYOBRequest {
+    // https://streams.spec.whatwg.org/#rs-byob-request-view
+    fn GetView(&self, _cx: SafeJSContext) -> Option<NonNull<JSObject>> {
+        NonNull::new(self.view.get())
+    }
+
+    // https://streams.spec.whatwg.org/#rs-byob-request-respond
+    fn Respond(&self, bytes_written: u64) -> ErrorResult {
+        match self.controller.get() {
+            Some(controller) => controller.respond(bytes_written as usize),
+            None => Err(Error::Type(
+                "The request was already responded to".to_owned(),
+            )),
+        }
+    }
+
+    // https://streams.spec.whatwg.org/#rs-byob-request-respond-with-new-view
+    #[allow(unsafe_code)]
+    fn RespondWithNewView(&self, view: CustomAutoRooterGuard<ArrayBufferView>) -> ErrorResult {
+        let controller = match self.controller.get() {
+            Some(controller) => controller,
+            None => {
+                return Err(Error::Type(
+                    "The request was already responded to".to_owned(),
+                ))
+            },
+        };
+        rooted!(in(*self.global().get_cx()) let view = unsafe { *view.underlying_object() });
+        controller.respond_with_new_view(view.handle())
+    }
+}
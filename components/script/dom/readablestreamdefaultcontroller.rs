@@ -0,0 +1,488 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::callback::ExceptionHandling;
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::QueuingStrategyBinding::QueuingStrategySize;
+use crate::dom::bindings::codegen::Bindings::ReadableStreamDefaultControllerBinding;
+use crate::dom::bindings::codegen::Bindings::ReadableStreamDefaultControllerBinding::ReadableStreamDefaultControllerMethods;
+use crate::dom::bindings::codegen::Bindings::UnderlyingSourceBinding::{
+    UnderlyingSource, UnderlyingSourceCancelCallback, UnderlyingSourcePullCallback,
+    UnderlyingSourceStartCallback,
+};
+use crate::dom::bindings::codegen::UnionTypes::ReadableStreamDefaultControllerOrReadableByteStreamController as ReadableStreamController;
+use crate::dom::bindings::error::{Error, ErrorResult};
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot, MutNullableDom};
+use crate::dom::promise::Promise;
+use crate::dom::promisenativehandler::Callback;
+use crate::dom::readablestream::{ReadableStream, TeeBranch, TeeState};
+use crate::dom::transformstream::TransformStream;
+use crate::script_runtime::JSContext as SafeJSContext;
+use crate::streams::{chunk_size, error_to_jsval, promise_from_callback_result, rethrow};
+use crate::streams::{promise_resolved_with_undefined, resolve_read_request, upon_settlement};
+use crate::streams::{QueueWithSizes, QueuedValue};
+use dom_struct::dom_struct;
+use js::jsapi::{Heap, JSContext, JSObject};
+use js::jsval::UndefinedValue;
+use js::rust::{HandleObject, HandleValue};
+use std::cell::Cell;
+use std::rc::Rc;
+
+/// The algorithms a readable stream pulls its chunks with and cancels its
+/// source through.
+#[allow_unrooted_in_rc]
+#[derive(JSTraceable)]
+#[must_root]
+pub enum UnderlyingSourceType {
+    /// A source passed by script to the `ReadableStream` constructor, and
+    /// the object its methods are called on.
+    Js(
+        Box<Heap<*mut JSObject>>,
+        Option<Rc<UnderlyingSourcePullCallback>>,
+        Option<Rc<UnderlyingSourceCancelCallback>>,
+    ),
+    /// A source whose chunks the user agent enqueues, e.g. a fetch body.
+    Native,
+    /// The readable side of a transform stream.
+    Transform(Dom<TransformStream>),
+    /// A branch of a teed stream.
+    Tee(Rc<TeeState>, TeeBranch),
+}
+
+/// <https://streams.spec.whatwg.org/#rs-default-controller-class>
+#[dom_struct]
+pub struct ReadableStreamDefaultController {
+    reflector_: Reflector,
+    stream: MutNullableDom<ReadableStream>,
+    /// <https://streams.spec.whatwg.org/#readablestreamdefaultcontroller-pullalgorithm>
+    /// and <https://streams.spec.whatwg.org/#readablestreamdefaultcontroller-cancelalgorithm>,
+    /// `None` once the algorithms are cleared.
+    #[ignore_malloc_size_of = "Rc and mozjs"]
+    source: DomRefCell<Option<Rc<UnderlyingSourceType>>>,
+    queue: DomRefCell<QueueWithSizes>,
+    started: Cell<bool>,
+    close_requested: Cell<bool>,
+    pull_again: Cell<bool>,
+    pulling: Cell<bool>,
+    strategy_hwm: f64,
+    #[ignore_malloc_size_of = "Rc"]
+    strategy_size: DomRefCell<Option<Rc<QueuingStrategySize>>>,
+}
+
+impl ReadableStreamDefaultController {
+    #[allow(unrooted_must_root)]
+    fn new_inherited(
+        source: UnderlyingSourceType,
+        strategy_hwm: f64,
+        strategy_size: Option<Rc<QueuingStrategySize>>,
+    ) -> ReadableStreamDefaultController {
+        ReadableStreamDefaultController {
+            reflector_: Reflector::new(),
+            stream: Default::default(),
+            source: DomRefCell::new(Some(Rc::new(source))),
+            queue: Default::default(),
+            started: Cell::new(false),
+            close_requested: Cell::new(false),
+            pull_again: Cell::new(false),
+            pulling: Cell::new(false),
+            strategy_hwm,
+            strategy_size: DomRefCell::new(strategy_size),
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#set-up-readable-stream-default-controller>
+    #[allow(unrooted_must_root)]
+    pub fn set_up(
+        stream: &ReadableStream,
+        source: UnderlyingSourceType,
+        start: Option<(HandleObject, Rc<UnderlyingSourceStartCallback>)>,
+        high_water_mark: f64,
+        size: Option<Rc<QueuingStrategySize>>,
+    ) -> ErrorResult {
+        let global = stream.global();
+
+        // Steps 1-10.
+        let controller = reflect_dom_object(
+            Box::new(ReadableStreamDefaultController::new_inherited(
+                source,
+                high_water_mark,
+                size,
+            )),
+            &*global,
+            ReadableStreamDefaultControllerBinding::Wrap,
+        );
+        controller.stream.set(Some(stream));
+        stream.set_default_controller(&controller);
+
+        // Step 11.
+        let start_result = match start {
+            Some((this, start)) => start.Call_(
+                &this,
+                ReadableStreamController::ReadableStreamDefaultController(DomRoot::from_ref(
+                    &*controller,
+                )),
+                ExceptionHandling::Rethrow,
+            )?,
+            None => UndefinedValue(),
+        };
+
+        // Steps 12-14.
+        let start_promise = promise_from_callback_result(&global, Ok(start_result));
+        upon_settlement(
+            &global,
+            &start_promise,
+            Box::new(ControllerReaction {
+                controller: Dom::from_ref(&*controller),
+                step: ControllerStep::StartFulfilled,
+            }),
+            Box::new(ControllerReaction {
+                controller: Dom::from_ref(&*controller),
+                step: ControllerStep::StartRejected,
+            }),
+        );
+        Ok(())
+    }
+
+    /// <https://streams.spec.whatwg.org/#set-up-readable-stream-default-controller-from-underlying-source>
+    #[allow(unrooted_must_root)]
+    pub fn set_up_from_underlying_source(
+        stream: &ReadableStream,
+        source_object: HandleObject,
+        source: &UnderlyingSource,
+        high_water_mark: f64,
+        size: Option<Rc<QueuingStrategySize>>,
+    ) -> ErrorResult {
+        // Steps 2-6.
+        let algorithms = UnderlyingSourceType::Js(
+            Heap::boxed(source_object.get()),
+            source.pull.clone(),
+            source.cancel.clone(),
+        );
+        let start = source.start.clone().map(|start| (source_object, start));
+
+        // Step 7.
+        ReadableStreamDefaultController::set_up(stream, algorithms, start, high_water_mark, size)
+    }
+
+    fn stream(&self) -> DomRoot<ReadableStream> {
+        self.stream
+            .get()
+            .expect("A controller is always set up with a stream")
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-default-controller-call-pull-if-needed>
+    #[allow(unrooted_must_root)]
+    fn call_pull_if_needed(&self) {
+        // Steps 1-2.
+        if !self.should_call_pull() {
+            return;
+        }
+
+        // Step 3.
+        if self.pulling.get() {
+            self.pull_again.set(true);
+            return;
+        }
+
+        // Step 5.
+        self.pulling.set(true);
+
+        // Steps 6-8.
+        let pull_promise = self.run_pull_algorithm();
+        upon_settlement(
+            &self.global(),
+            &pull_promise,
+            Box::new(ControllerReaction {
+                controller: Dom::from_ref(self),
+                step: ControllerStep::PullFulfilled,
+            }),
+            Box::new(ControllerReaction {
+                controller: Dom::from_ref(self),
+                step: ControllerStep::PullRejected,
+            }),
+        );
+    }
+
+    fn run_pull_algorithm(&self) -> Rc<Promise> {
+        let global = self.global();
+        // The pull method may close or error the controller, which clears
+        // the algorithms, so it is called without borrowing them.
+        let source = self.source.borrow().clone();
+        match source.as_ref().map(|source| &**source) {
+            Some(&UnderlyingSourceType::Js(ref this, Some(ref pull), _)) => {
+                let cx = global.get_cx();
+                rooted!(in(*cx) let this = this.get());
+                let result = pull.Call_(
+                    &this.handle(),
+                    ReadableStreamController::ReadableStreamDefaultController(DomRoot::from_ref(
+                        self,
+                    )),
+                    ExceptionHandling::Rethrow,
+                );
+                promise_from_callback_result(&global, result)
+            },
+            Some(&UnderlyingSourceType::Transform(ref stream)) => stream.source_pull(),
+            Some(&UnderlyingSourceType::Tee(ref state, _)) => state.pull(),
+            _ => promise_resolved_with_undefined(&global),
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-default-controller-should-call-pull>
+    fn should_call_pull(&self) -> bool {
+        let stream = self.stream();
+
+        // Steps 2-3.
+        if !self.can_close_or_enqueue() || !self.started.get() {
+            return false;
+        }
+
+        // Step 4.
+        if stream.is_locked() && stream.get_num_read_requests() > 0 {
+            return true;
+        }
+
+        // Steps 5-7.
+        self.get_desired_size().map_or(false, |size| size > 0.)
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-default-controller-clear-algorithms>
+    fn clear_algorithms(&self) {
+        *self.source.borrow_mut() = None;
+        *self.strategy_size.borrow_mut() = None;
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-default-controller-close>
+    pub fn close(&self) {
+        // Step 1.
+        if !self.can_close_or_enqueue() {
+            return;
+        }
+
+        // Step 3.
+        self.close_requested.set(true);
+
+        // Step 4.
+        if self.queue.borrow().is_empty() {
+            self.clear_algorithms();
+            self.stream().close();
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-default-controller-enqueue>
+    pub fn enqueue(&self, chunk: HandleValue) -> ErrorResult {
+        let stream = self.stream();
+
+        // Step 1.
+        if !self.can_close_or_enqueue() {
+            return Ok(());
+        }
+
+        // Step 3.
+        if stream.is_locked() && stream.get_num_read_requests() > 0 {
+            stream.fulfill_read_request(chunk, false);
+        } else {
+            // Step 4.1.
+            let strategy_size = self.strategy_size.borrow().clone();
+            let size = chunk_size(&strategy_size, chunk);
+
+            // Steps 4.2-3.
+            let enqueued = size.and_then(|size| {
+                self.queue
+                    .borrow_mut()
+                    .enqueue_value_with_size(QueuedValue::Chunk(Heap::boxed(chunk.get())), size)
+            });
+            if let Err(error) = enqueued {
+                let global = self.global();
+                let cx = global.get_cx();
+                rooted!(in(*cx) let mut reason = UndefinedValue());
+                error_to_jsval(&global, error, reason.handle_mut());
+                self.error(reason.handle());
+                return Err(rethrow(&global, reason.handle()));
+            }
+        }
+
+        // Step 5.
+        self.call_pull_if_needed();
+        Ok(())
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-default-controller-error>
+    pub fn error(&self, error: HandleValue) {
+        let stream = self.stream();
+
+        // Step 2.
+        if !stream.is_readable() {
+            return;
+        }
+
+        // Steps 3-5.
+        self.queue.borrow_mut().reset();
+        self.clear_algorithms();
+        stream.error(error);
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-default-controller-get-desired-size>
+    pub fn get_desired_size(&self) -> Option<f64> {
+        let stream = self.stream();
+        if stream.is_errored() {
+            return None;
+        }
+        if stream.is_closed() {
+            return Some(0.);
+        }
+        Some(self.strategy_hwm - self.queue.borrow().total_size())
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-default-controller-has-backpressure>
+    pub fn has_backpressure(&self) -> bool {
+        !self.should_call_pull()
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-default-controller-can-close-or-enqueue>
+    pub fn can_close_or_enqueue(&self) -> bool {
+        !self.close_requested.get() && self.stream().is_readable()
+    }
+
+    /// <https://streams.spec.whatwg.org/#rs-default-controller-private-cancel>
+    pub fn cancel_steps(&self, reason: HandleValue) -> Rc<Promise> {
+        let global = self.global();
+
+        // Step 1.
+        self.queue.borrow_mut().reset();
+
+        // Step 2.
+        let source = self.source.borrow_mut().take();
+        let result = match source.as_ref().map(|source| &**source) {
+            Some(&UnderlyingSourceType::Js(ref this, _, Some(ref cancel))) => {
+                let cx = global.get_cx();
+                rooted!(in(*cx) let this = this.get());
+                let result = cancel.Call_(&this.handle(), reason, ExceptionHandling::Rethrow);
+                promise_from_callback_result(&global, result)
+            },
+            Some(&UnderlyingSourceType::Transform(ref stream)) => stream.source_cancel(reason),
+            Some(&UnderlyingSourceType::Tee(ref state, branch)) => state.cancel(branch, reason),
+            _ => promise_resolved_with_undefined(&global),
+        };
+
+        // Step 3.
+        self.clear_algorithms();
+
+        // Step 4.
+        result
+    }
+
+    /// <https://streams.spec.whatwg.org/#rs-default-controller-private-pull>
+    pub fn pull_steps(&self, promise: &Rc<Promise>) {
+        let stream = self.stream();
+        let global = self.global();
+        let cx = global.get_cx();
+
+        // Step 2.
+        if !self.queue.borrow().is_empty() {
+            // Step 2.1.
+            let chunk = match self.queue.borrow_mut().dequeue_value() {
+                QueuedValue::Chunk(chunk) => chunk,
+                QueuedValue::CloseSentinel => unreachable!(),
+            };
+            rooted!(in(*cx) let chunk = chunk.get());
+
+            // Step 2.2.
+            if self.close_requested.get() && self.queue.borrow().is_empty() {
+                self.clear_algorithms();
+                stream.close();
+            } else {
+                // Step 2.3.
+                self.call_pull_if_needed();
+            }
+
+            // Step 2.4.
+            resolve_read_request(&global, promise, chunk.handle(), false);
+            return;
+        }
+
+        // Step 3.
+        stream.add_read_request(promise);
+
+        // Step 4.
+        self.call_pull_if_needed();
+    }
+
+    fn start_fulfilled(&self) {
+        // Step 12.1.
+        self.started.set(true);
+
+        // Steps 12.2-3.
+        self.call_pull_if_needed();
+    }
+
+    fn pull_fulfilled(&self) {
+        // Step 7.1.
+        self.pulling.set(false);
+
+        // Step 7.2.
+        if self.pull_again.get() {
+            self.pull_again.set(false);
+            self.call_pull_if_needed();
+        }
+    }
+}
+
+#[derive(Clone, Copy, JSTraceable, MallocSizeOf)]
+enum ControllerStep {
+    StartFulfilled,
+    StartRejected,
+    PullFulfilled,
+    PullRejected,
+}
+
+/// The steps run when the promise returned by the start or pull algorithm
+/// of the underlying source settles.
+#[derive(JSTraceable, MallocSizeOf)]
+#[must_root]
+struct ControllerReaction {
+    controller: Dom<ReadableStreamDefaultController>,
+    step: ControllerStep,
+}
+
+impl Callback for ControllerReaction {
+    fn callback(&self, _cx: *mut JSContext, v: HandleValue) {
+        match self.step {
+            ControllerStep::StartFulfilled => self.controller.start_fulfilled(),
+            ControllerStep::PullFulfilled => self.controller.pull_fulfilled(),
+            ControllerStep::StartRejected | ControllerStep::PullRejected => {
+                self.controller.error(v)
+            },
+        }
+    }
+}
+
+impl ReadableStreamDefaultControllerMethods for ReadableStreamDefaultController {
+    // https://streams.spec.whatwg.org/#rs-default-controller-desired-size
+    fn GetDesiredSize(&self) -> Option<f64> {
+        self.get_desired_size()
+    }
+
+    // https://streams.spec.whatwg.org/#rs-default-controller-close
+    fn Close(&self) -> ErrorResult {
+        if !self.can_close_or_enqueue() {
+            return Err(Error::Type("The stream cannot be closed".to_owned()));
+        }
+        self.close();
+        Ok(())
+    }
+
+    // https://streams.spec.whatwg.org/#rs-default-controller-enqueue
+    fn Enqueue(&self, _cx: SafeJSContext, chunk: HandleValue) -> ErrorResult {
+        if !self.can_close_or_enqueue() {
+            return Err(Error::Type("The stream cannot be enqueued to".to_owned()));
+        }
+        self.enqueue(chunk)
+    }
+
+    // https://streams.spec.whatwg.org/#rs-default-controller-error
+    fn Error(&self, _cx: SafeJSContext, e: HandleValue) {
+        self.error(e);
+    }
+}
@@ -0,0 +1,257 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::ReadableStreamDefaultReaderBinding;
+use crate::dom::bindings::codegen::Bindings::ReadableStreamDefaultReaderBinding::ReadableStreamDefaultReaderMethods;
+use crate::dom::bindings::error::{Error, ErrorResult, Fallible};
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{DomRoot, MutNullableDom};
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::promise::Promise;
+use crate::dom::readablestream::{ReadableStream, ReadableStreamState};
+use crate::script_runtime::JSContext as SafeJSContext;
+use crate::streams::{error_to_jsval, mark_as_handled};
+use crate::streams::{promise_rejected_with_error, resolve_read_request};
+use dom_struct::dom_struct;
+use js::jsval::UndefinedValue;
+use js::rust::HandleValue;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+#[dom_struct]
+pub struct ReadableStreamDefaultReader {
+    reflector_: Reflector,
+    /// <https://streams.spec.whatwg.org/#readablestreamgenericreader-stream>
+    stream: MutNullableDom<ReadableStream>,
+    /// <https://streams.spec.whatwg.org/#readablestreamgenericreader-closedpromise>
+    #[ignore_malloc_size_of = "Rc"]
+    closed_promise: DomRefCell<Rc<Promise>>,
+    /// <https://streams.spec.whatwg.org/#readablestreamdefaultreader-readrequests>
+    #[ignore_malloc_size_of = "Rc"]
+    read_requests: DomRefCell<VecDeque<Rc<Promise>>>,
+}
+
+impl ReadableStreamDefaultReader {
+    fn new_inherited(closed_promise: Rc<Promise>) -> ReadableStreamDefaultReader {
+        ReadableStreamDefaultReader {
+            reflector_: Reflector::new(),
+            stream: Default::default(),
+            closed_promise: DomRefCell::new(closed_promise),
+            read_requests: DomRefCell::new(VecDeque::new()),
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#set-up-readable-stream-default-reader>
+    pub fn new(
+        global: &GlobalScope,
+        stream: &ReadableStream,
+    ) -> Fallible<DomRoot<ReadableStreamDefaultReader>> {
+        // Step 1.
+        if stream.is_locked() {
+            return Err(Error::Type("The stream is already locked".to_owned()));
+        }
+
+        let reader = reflect_dom_object(
+            Box::new(ReadableStreamDefaultReader::new_inherited(Promise::new(
+                global,
+            ))),
+            global,
+            ReadableStreamDefaultReaderBinding::Wrap,
+        );
+
+        // Step 2.
+        reader.generic_initialize(stream);
+
+        // Step 3 is done by new_inherited.
+        Ok(reader)
+    }
+
+    // https://streams.spec.whatwg.org/#default-reader-constructor
+    pub fn Constructor(
+        global: &GlobalScope,
+        stream: &ReadableStream,
+    ) -> Fallible<DomRoot<ReadableStreamDefaultReader>> {
+        ReadableStreamDefaultReader::new(global, stream)
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-reader-generic-initialize>
+    fn generic_initialize(&self, stream: &ReadableStream) {
+        // Steps 1-2.
+        self.stream.set(Some(stream));
+        stream.set_default_reader(Some(self));
+
+        match stream.state() {
+            // Step 3.
+            ReadableStreamState::Readable => {},
+            // Step 4.
+            ReadableStreamState::Closed => self.resolve_closed_promise(),
+            // Step 5.
+            ReadableStreamState::Errored => {
+                let cx = self.global().get_cx();
+                rooted!(in(*cx) let mut error = UndefinedValue());
+                stream.stored_error(error.handle_mut());
+                self.reject_closed_promise(error.handle());
+            },
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-reader-generic-release>
+    pub fn release(&self) {
+        let global = self.global();
+        let stream = match self.stream.get() {
+            Some(stream) => stream,
+            None => return,
+        };
+
+        // Steps 3-4.
+        let error = Error::Type("The reader's lock was released".to_owned());
+        if stream.is_readable() {
+            let cx = global.get_cx();
+            rooted!(in(*cx) let mut reason = UndefinedValue());
+            error_to_jsval(&global, error, reason.handle_mut());
+            self.reject_closed_promise(reason.handle());
+        } else {
+            let closed_promise = promise_rejected_with_error(&global, error);
+            mark_as_handled(&global, &closed_promise);
+            *self.closed_promise.borrow_mut() = closed_promise;
+        }
+
+        // Steps 5-6.
+        stream.set_default_reader(None);
+        self.stream.set(None);
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-default-reader-read>
+    pub fn read(&self) -> Rc<Promise> {
+        let global = self.global();
+
+        // Step 1.
+        let stream = self
+            .stream
+            .get()
+            .expect("Reading from a reader without a stream");
+
+        // Step 2.
+        stream.set_disturbed();
+
+        let promise = Promise::new(&global);
+        match stream.state() {
+            // Step 3.
+            ReadableStreamState::Closed => {
+                let cx = global.get_cx();
+                rooted!(in(*cx) let undefined = UndefinedValue());
+                resolve_read_request(&global, &promise, undefined.handle(), true);
+            },
+            // Step 4.
+            ReadableStreamState::Errored => {
+                let cx = global.get_cx();
+                rooted!(in(*cx) let mut error = UndefinedValue());
+                stream.stored_error(error.handle_mut());
+                promise.reject(cx, error.handle());
+            },
+            // Step 5.
+            ReadableStreamState::Readable => stream.perform_pull_steps(&promise),
+        }
+        promise
+    }
+
+    pub fn closed_promise(&self) -> Rc<Promise> {
+        self.closed_promise.borrow().clone()
+    }
+
+    pub fn resolve_closed_promise(&self) {
+        self.closed_promise.borrow().resolve_native(&());
+    }
+
+    pub fn reject_closed_promise(&self, reason: HandleValue) {
+        let global = self.global();
+        let closed_promise = self.closed_promise();
+        closed_promise.reject(global.get_cx(), reason);
+        mark_as_handled(&global, &closed_promise);
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-add-read-request>
+    pub fn add_read_request(&self, promise: &Rc<Promise>) {
+        self.read_requests.borrow_mut().push_back(promise.clone());
+    }
+
+    pub fn num_read_requests(&self) -> usize {
+        self.read_requests.borrow().len()
+    }
+
+    /// <https://streams.spec.whatwg.org/#readable-stream-fulfill-read-request>
+    pub fn fulfill_read_request(&self, chunk: HandleValue, done: bool) {
+        let promise = self
+            .read_requests
+            .borrow_mut()
+            .pop_front()
+            .expect("Fulfilling a read request that does not exist");
+        resolve_read_request(&self.global(), &promise, chunk, done);
+    }
+
+    /// Resolve every pending read request with a done result, as the stream
+    /// was closed.
+    pub fn close_read_requests(&self) {
+        let global = self.global();
+        let cx = global.get_cx();
+        rooted!(in(*cx) let undefined = UndefinedValue());
+        let requests: Vec<_> = self.read_requests.borrow_mut().drain(..).collect();
+        for promise in requests {
+            resolve_read_request(&global, &promise, undefined.handle(), true);
+        }
+    }
+
+    /// Reject every pending read request, as the stream errored.
+    pub fn error_read_requests(&self, error: HandleValue) {
+        let cx = self.global().get_cx();
+        let requests: Vec<_> = self.read_requests.borrow_mut().drain(..).collect();
+        for promise in requests {
+            promise.reject(cx, error);
+        }
+    }
+}
+
+impl ReadableStreamDefaultReaderMethods for ReadableStreamDefaultReader {
+    // https://streams.spec.whatwg.org/#default-reader-closed
+    fn Closed(&self) -> Rc<Promise> {
+        self.closed_promise()
+    }
+
+    // https://streams.spec.whatwg.org/#default-reader-cancel
+    fn Cancel(&self, _cx: SafeJSContext, reason: HandleValue) -> Rc<Promise> {
+        match self.stream.get() {
+            Some(stream) => stream.cancel(reason),
+            None => promise_rejected_with_error(
+                &self.global(),
+                Error::Type("The reader has no stream".to_owned()),
+            ),
+        }
+    }
+
+    // https://streams.spec.whatwg.org/#default-reader-read
+    fn Read(&self) -> Rc<Promise> {
+        if self.stream.get().is_none() {
+            return promise_rejected_with_error(
+                &self.global(),
+                Error::Type("The reader has no stream".to_owned()),
+            );
+        }
+        self.read()
+    }
+
+    // https://streams.spec.whatwg.org/#default-reader-release-lock
+    fn ReleaseLock(&self) -> ErrorResult {
+        if self.stream.get().is_none() {
+            return Ok(());
+        }
+        if !self.read_requests.borrow().is_empty() {
+            return Err(Error::Type(
+                "Cannot release a reader with pending read requests".to_owned(),
+            ));
+        }
+        self.release();
+        Ok(())
+    }
+}
@@ -3,6 +3,7 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
 use crate::body::{consume_body, BodyOperations, BodyType};
+use crate::dom::abortsignal::AbortSignal;
 use crate::dom::bindings::cell::DomRefCell;
 use crate::dom::bindings::codegen::Bindings::HeadersBinding::{HeadersInit, HeadersMethods};
 use crate::dom::bindings::codegen::Bindings::RequestBinding;
@@ -23,6 +24,7 @@ use crate::dom::bindings::trace::RootedTraceableBox;
 use crate::dom::globalscope::GlobalScope;
 use crate::dom::headers::{Guard, Headers};
 use crate::dom::promise::Promise;
+use crate::dom::readablestream::ReadableStream;
 use crate::dom::xmlhttprequest::Extractable;
 use dom_struct::dom_struct;
 use http::method::InvalidMethod;
@@ -50,6 +52,10 @@ pub struct Request {
     mime_type: DomRefCell<Vec<u8>>,
     #[ignore_malloc_size_of = "Rc"]
     body_promise: DomRefCell<Option<(Rc<Promise>, BodyType)>>,
+    /// <https://fetch.spec.whatwg.org/#concept-body-stream>
+    stream: MutNullableDom<ReadableStream>,
+    /// <https://fetch.spec.whatwg.org/#request-signal>
+    signal: MutNullableDom<AbortSignal>,
 }
 
 impl Request {
@@ -61,6 +67,8 @@ impl Request {
             headers: Default::default(),
             mime_type: DomRefCell::new("".to_string().into_bytes()),
             body_promise: DomRefCell::new(None),
+            stream: Default::default(),
+            signal: Default::default(),
         }
     }
 
@@ -90,6 +98,9 @@ impl Request {
         // Step 4
         let base_url = global.api_base_url();
 
+        // The signal r's signal follows, if any.
+        let mut input_signal = None;
+
         match input {
             // Step 5
             RequestInfo::USVString(USVString(ref usv_string)) => {
@@ -119,6 +130,7 @@ impl Request {
                 }
                 // Step 6.2
                 temporary_request = input_request.request.borrow().clone();
+                input_signal = Some(input_request.Signal());
             },
         }
 
@@ -293,9 +305,16 @@ impl Request {
             request.method = method;
         }
 
+        if let Some(ref init_signal) = init.signal {
+            input_signal = init_signal.clone();
+        }
+
         // Step 26
         let r = Request::from_net_request(global, request);
         r.headers.or_init(|| Headers::for_request(&r.global()));
+        if let Some(signal) = input_signal {
+            r.Signal().follow(&signal);
+        }
 
         // Step 27
         let mut headers_copy = r.Headers();
@@ -429,9 +448,7 @@ impl Request {
 
     // https://fetch.spec.whatwg.org/#concept-body-locked
     fn locked(&self) -> bool {
-        // TODO: ReadableStream is unimplemented. Just return false
-        // for now.
-        false
+        self.stream.get().map_or(false, |stream| stream.is_locked())
     }
 }
 
@@ -515,16 +532,17 @@ fn includes_credentials(input: &ServoUrl) -> bool {
     !input.username().is_empty() || input.password().is_some()
 }
 
-// TODO: `Readable Stream` object is not implemented in Servo yet.
 // https://fetch.spec.whatwg.org/#concept-body-disturbed
-fn request_is_disturbed(_input: &Request) -> bool {
-    false
+fn request_is_disturbed(input: &Request) -> bool {
+    input
+        .stream
+        .get()
+        .map_or(false, |stream| stream.is_disturbed())
 }
 
-// TODO: `Readable Stream` object is not implemented in Servo yet.
 // https://fetch.spec.whatwg.org/#concept-body-locked
-fn request_is_locked(_input: &Request) -> bool {
-    false
+fn request_is_locked(input: &Request) -> bool {
+    input.locked()
 }
 
 impl RequestMethods for Request {
@@ -601,9 +619,29 @@ impl RequestMethods for Request {
         DOMString::from_string(r.integrity_metadata.clone())
     }
 
+    // https://fetch.spec.whatwg.org/#dom-request-signal
+    fn Signal(&self) -> DomRoot<AbortSignal> {
+        self.signal.or_init(|| AbortSignal::new(&self.global()))
+    }
+
+    // https://fetch.spec.whatwg.org/#dom-body-body
+    fn GetBody(&self) -> Option<DomRoot<ReadableStream>> {
+        if let Some(stream) = self.stream.get() {
+            return Some(stream);
+        }
+        let bytes = self.request.borrow().body.clone()?;
+        let stream = ReadableStream::new_from_bytes(&self.global());
+        if !bytes.is_empty() {
+            stream.enqueue_native(bytes);
+        }
+        stream.close_native();
+        self.stream.set(Some(&stream));
+        Some(stream)
+    }
+
     // https://fetch.spec.whatwg.org/#dom-body-bodyused
     fn BodyUsed(&self) -> bool {
-        self.body_used.get()
+        self.body_used.get() || request_is_disturbed(self)
     }
 
     // https://fetch.spec.whatwg.org/#dom-request-clone
@@ -617,7 +655,15 @@ impl RequestMethods for Request {
         }
 
         // Step 2
-        Request::clone_from(self)
+        let new_request = Request::clone_from(self)?;
+        new_request.Signal().follow(&self.Signal());
+        // https://fetch.spec.whatwg.org/#concept-body-clone
+        if let Some(stream) = self.stream.get() {
+            let (branch_1, branch_2) = stream.tee()?;
+            self.stream.set(Some(&branch_1));
+            new_request.stream.set(Some(&branch_2));
+        }
+        Ok(new_request)
     }
 
     // https://fetch.spec.whatwg.org/#dom-body-text
@@ -670,6 +716,10 @@ impl BodyOperations for Request {
     fn get_mime_type(&self) -> Ref<Vec<u8>> {
         self.mime_type.borrow()
     }
+
+    fn get_stream(&self) -> Option<DomRoot<ReadableStream>> {
+        self.stream.get()
+    }
 }
 
 impl Into<NetTraitsRequestCache> for RequestCache {
@@ -0,0 +1,292 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::callback::ExceptionHandling;
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::ResizeObserverBinding;
+use crate::dom::bindings::codegen::Bindings::ResizeObserverBinding::ResizeObserverBoxOptions;
+use crate::dom::bindings::codegen::Bindings::ResizeObserverBinding::ResizeObserverCallback;
+use crate::dom::bindings::codegen::Bindings::ResizeObserverBinding::ResizeObserverMethods;
+use crate::dom::bindings::codegen::Bindings::ResizeObserverBinding::ResizeObserverOptions;
+use crate::dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
+use crate::dom::bindings::error::Fallible;
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot};
+use crate::dom::document::Document;
+use crate::dom::element::Element;
+use crate::dom::node::{window_from_node, Node, ShadowIncluding};
+use crate::dom::resizeobserverentry::ResizeObserverEntry;
+use crate::dom::resizeobserversize::ResizeObserverSizeImpl;
+use crate::dom::window::Window;
+use dom_struct::dom_struct;
+use euclid::default::{Point2D, Rect, Size2D};
+use std::cell::Cell;
+use std::rc::Rc;
+use style::properties::{LonghandId, PropertyId};
+
+/// The sizes of the boxes of an element, as computed by layout.
+struct BoxSizes {
+    content_rect: Rect<f64>,
+    border_box: ResizeObserverSizeImpl,
+    content_box: ResizeObserverSizeImpl,
+    device_pixel_content_box: ResizeObserverSizeImpl,
+}
+
+impl BoxSizes {
+    /// <https://drafts.csswg.org/resize-observer/#calculate-box-size>
+    fn calculate(target: &Element) -> BoxSizes {
+        let window = window_from_node(target);
+        let node = target.upcast::<Node>();
+
+        // Elements without a layout box have a size of zero.
+        let (style, border_box) = match (target.style(), window.content_box_query(node)) {
+            (Some(style), Some(border_box)) => (style, border_box),
+            _ => {
+                return BoxSizes {
+                    content_rect: Rect::zero(),
+                    border_box: ResizeObserverSizeImpl::zero(),
+                    content_box: ResizeObserverSizeImpl::zero(),
+                    device_pixel_content_box: ResizeObserverSizeImpl::zero(),
+                };
+            },
+        };
+
+        // The resolved values of these properties are the used values
+        // computed by layout, serialized in pixels.
+        let used_value = |longhand: LonghandId| -> f64 {
+            let value = window.resolved_style_query(
+                node.to_trusted_node_address(),
+                None,
+                PropertyId::Longhand(longhand),
+            );
+            value.trim_end_matches("px").parse().unwrap_or(0.)
+        };
+        let content_rect = Rect::new(
+            Point2D::new(
+                used_value(LonghandId::PaddingLeft),
+                used_value(LonghandId::PaddingTop),
+            ),
+            Size2D::new(
+                used_value(LonghandId::Width),
+                used_value(LonghandId::Height),
+            ),
+        );
+
+        let is_vertical = style.writing_mode.is_vertical();
+        let logical_size = |width: f64, height: f64| {
+            if is_vertical {
+                ResizeObserverSizeImpl::new(height, width)
+            } else {
+                ResizeObserverSizeImpl::new(width, height)
+            }
+        };
+        let device_pixel_ratio = *window.DevicePixelRatio();
+        BoxSizes {
+            content_rect,
+            border_box: logical_size(
+                border_box.size.width.to_f64_px(),
+                border_box.size.height.to_f64_px(),
+            ),
+            content_box: logical_size(content_rect.size.width, content_rect.size.height),
+            device_pixel_content_box: logical_size(
+                (content_rect.size.width * device_pixel_ratio).round(),
+                (content_rect.size.height * device_pixel_ratio).round(),
+            ),
+        }
+    }
+
+    fn size_of_box(&self, observed_box: ResizeObserverBoxOptions) -> ResizeObserverSizeImpl {
+        match observed_box {
+            ResizeObserverBoxOptions::Content_box => self.content_box,
+            ResizeObserverBoxOptions::Border_box => self.border_box,
+            ResizeObserverBoxOptions::Device_pixel_content_box => self.device_pixel_content_box,
+        }
+    }
+}
+
+/// <https://drafts.csswg.org/resize-observer/#calculate-depth-for-node>
+fn depth_of_node(target: &Element) -> usize {
+    target
+        .upcast::<Node>()
+        .inclusive_ancestors(ShadowIncluding::Yes)
+        .count()
+}
+
+/// Which list of the observer, if any, an observation currently belongs to.
+#[derive(Clone, Copy, JSTraceable, MallocSizeOf, PartialEq)]
+enum ObservationState {
+    Inactive,
+    /// <https://drafts.csswg.org/resize-observer/#dom-resizeobserver-activetargets-slot>
+    Active,
+    /// <https://drafts.csswg.org/resize-observer/#dom-resizeobserver-skippedtargets-slot>
+    Skipped,
+}
+
+/// <https://drafts.csswg.org/resize-observer/#resizeobservation>
+#[derive(JSTraceable, MallocSizeOf)]
+#[must_root]
+struct ResizeObservation {
+    target: Dom<Element>,
+    observed_box: ResizeObserverBoxOptions,
+    last_reported_size: Cell<ResizeObserverSizeImpl>,
+    state: Cell<ObservationState>,
+}
+
+impl ResizeObservation {
+    /// <https://drafts.csswg.org/resize-observer/#dom-resizeobservation-isactive>
+    fn is_active(&self) -> bool {
+        let current_size = BoxSizes::calculate(&self.target).size_of_box(self.observed_box);
+        current_size != self.last_reported_size.get()
+    }
+}
+
+/// <https://drafts.csswg.org/resize-observer/#resize-observer-interface>
+#[dom_struct]
+pub struct ResizeObserver {
+    reflector_: Reflector,
+    #[ignore_malloc_size_of = "can't measure Rc values"]
+    callback: Rc<ResizeObserverCallback>,
+    /// <https://drafts.csswg.org/resize-observer/#dom-resizeobserver-observationtargets-slot>
+    observation_targets: DomRefCell<Vec<ResizeObservation>>,
+}
+
+impl ResizeObserver {
+    fn new_inherited(callback: Rc<ResizeObserverCallback>) -> ResizeObserver {
+        ResizeObserver {
+            reflector_: Reflector::new(),
+            callback,
+            observation_targets: DomRefCell::new(vec![]),
+        }
+    }
+
+    /// <https://drafts.csswg.org/resize-observer/#dom-resizeobserver-resizeobserver>
+    pub fn Constructor(
+        window: &Window,
+        callback: Rc<ResizeObserverCallback>,
+    ) -> Fallible<DomRoot<ResizeObserver>> {
+        let observer = reflect_dom_object(
+            Box::new(ResizeObserver::new_inherited(callback)),
+            window,
+            ResizeObserverBinding::Wrap,
+        );
+        Ok(observer)
+    }
+
+    /// The document whose observer list holds this observer while it has targets.
+    fn document(&self) -> DomRoot<Document> {
+        self.global().as_window().Document()
+    }
+
+    /// <https://drafts.csswg.org/resize-observer/#gather-active-observations-h>
+    pub fn gather_active_observations_at_depth(&self, depth: usize) {
+        for observation in self.observation_targets.borrow().iter() {
+            let state = if !observation.is_active() {
+                ObservationState::Inactive
+            } else if depth_of_node(&observation.target) > depth {
+                ObservationState::Active
+            } else {
+                ObservationState::Skipped
+            };
+            observation.state.set(state);
+        }
+    }
+
+    pub fn has_active_observations(&self) -> bool {
+        self.has_observations_in_state(ObservationState::Active)
+    }
+
+    pub fn has_skipped_observations(&self) -> bool {
+        self.has_observations_in_state(ObservationState::Skipped)
+    }
+
+    fn has_observations_in_state(&self, state: ObservationState) -> bool {
+        self.observation_targets
+            .borrow()
+            .iter()
+            .any(|observation| observation.state.get() == state)
+    }
+
+    /// Deliver the active observations of this observer, returning the depth
+    /// of the shallowest target that was reported.
+    /// <https://drafts.csswg.org/resize-observer/#broadcast-active-observations>
+    pub fn broadcast_active_observations(&self) -> usize {
+        let window = self.global();
+        let window = window.as_window();
+        let mut shallowest_target_depth = usize::max_value();
+
+        // Step 2.2-2.3.
+        let entries: Vec<DomRoot<ResizeObserverEntry>> = self
+            .observation_targets
+            .borrow()
+            .iter()
+            .filter(|observation| observation.state.get() == ObservationState::Active)
+            .map(|observation| {
+                let target = &*observation.target;
+                let sizes = BoxSizes::calculate(target);
+                observation
+                    .last_reported_size
+                    .set(sizes.size_of_box(observation.observed_box));
+                observation.state.set(ObservationState::Inactive);
+                shallowest_target_depth = shallowest_target_depth.min(depth_of_node(target));
+                ResizeObserverEntry::new(
+                    window,
+                    target,
+                    sizes.content_rect,
+                    sizes.border_box,
+                    sizes.content_box,
+                    sizes.device_pixel_content_box,
+                )
+            })
+            .collect();
+        if entries.is_empty() {
+            return shallowest_target_depth;
+        }
+
+        // Step 2.4.
+        let _ = self
+            .callback
+            .Call_(self, entries, self, ExceptionHandling::Report);
+
+        shallowest_target_depth
+    }
+}
+
+impl ResizeObserverMethods for ResizeObserver {
+    // https://drafts.csswg.org/resize-observer/#dom-resizeobserver-observe
+    #[allow(unrooted_must_root)]
+    fn Observe(&self, target: &Element, options: &ResizeObserverOptions) {
+        // Step 1.
+        self.Unobserve(target);
+        if self.observation_targets.borrow().is_empty() {
+            self.document().add_resize_observer(self);
+        }
+
+        // Steps 2-3.
+        self.observation_targets
+            .borrow_mut()
+            .push(ResizeObservation {
+                target: Dom::from_ref(target),
+                observed_box: options.box_,
+                last_reported_size: Cell::new(ResizeObserverSizeImpl::zero()),
+                state: Cell::new(ObservationState::Inactive),
+            });
+    }
+
+    // https://drafts.csswg.org/resize-observer/#dom-resizeobserver-unobserve
+    fn Unobserve(&self, target: &Element) {
+        let mut observation_targets = self.observation_targets.borrow_mut();
+        let observed = !observation_targets.is_empty();
+        observation_targets.retain(|observation| &*observation.target != target);
+        if observed && observation_targets.is_empty() {
+            self.document().remove_resize_observer(self);
+        }
+    }
+
+    // https://drafts.csswg.org/resize-observer/#dom-resizeobserver-disconnect
+    fn Disconnect(&self) {
+        self.observation_targets.borrow_mut().clear();
+        self.document().remove_resize_observer(self);
+    }
+}
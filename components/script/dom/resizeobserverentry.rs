@@ -0,0 +1,126 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::codegen::Bindings::ResizeObserverEntryBinding;
+use crate::dom::bindings::codegen::Bindings::ResizeObserverEntryBinding::ResizeObserverEntryMethods;
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::reflector::{reflect_dom_object, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot};
+use crate::dom::bindings::utils::to_frozen_array;
+use crate::dom::domrectreadonly::DOMRectReadOnly;
+use crate::dom::element::Element;
+use crate::dom::resizeobserversize::{ResizeObserverSize, ResizeObserverSizeImpl};
+use crate::dom::window::Window;
+use crate::script_runtime::JSContext;
+use dom_struct::dom_struct;
+use euclid::default::Rect;
+use js::jsval::JSVal;
+
+/// <https://drafts.csswg.org/resize-observer/#resize-observer-entry-interface>
+#[dom_struct]
+pub struct ResizeObserverEntry {
+    reflector_: Reflector,
+    target: Dom<Element>,
+    content_rect: Dom<DOMRectReadOnly>,
+    border_box_size: Vec<Dom<ResizeObserverSize>>,
+    content_box_size: Vec<Dom<ResizeObserverSize>>,
+    device_pixel_content_box_size: Vec<Dom<ResizeObserverSize>>,
+}
+
+impl ResizeObserverEntry {
+    fn new_inherited(
+        target: &Element,
+        content_rect: &DOMRectReadOnly,
+        border_box_size: &[&ResizeObserverSize],
+        content_box_size: &[&ResizeObserverSize],
+        device_pixel_content_box_size: &[&ResizeObserverSize],
+    ) -> ResizeObserverEntry {
+        let to_dom = |sizes: &[&ResizeObserverSize]| -> Vec<Dom<ResizeObserverSize>> {
+            sizes.iter().map(|size| Dom::from_ref(*size)).collect()
+        };
+        ResizeObserverEntry {
+            reflector_: Reflector::new(),
+            target: Dom::from_ref(target),
+            content_rect: Dom::from_ref(content_rect),
+            border_box_size: to_dom(border_box_size),
+            content_box_size: to_dom(content_box_size),
+            device_pixel_content_box_size: to_dom(device_pixel_content_box_size),
+        }
+    }
+
+    /// <https://drafts.csswg.org/resize-observer/#create-and-populate-a-resizeobserverentry>
+    pub fn new(
+        window: &Window,
+        target: &Element,
+        content_rect: Rect<f64>,
+        border_box_size: ResizeObserverSizeImpl,
+        content_box_size: ResizeObserverSizeImpl,
+        device_pixel_content_box_size: ResizeObserverSizeImpl,
+    ) -> DomRoot<ResizeObserverEntry> {
+        let content_rect = DOMRectReadOnly::new(
+            window.upcast(),
+            content_rect.origin.x,
+            content_rect.origin.y,
+            content_rect.size.width,
+            content_rect.size.height,
+        );
+        let border_box_size = ResizeObserverSize::new(window, border_box_size);
+        let content_box_size = ResizeObserverSize::new(window, content_box_size);
+        let device_pixel_content_box_size =
+            ResizeObserverSize::new(window, device_pixel_content_box_size);
+        reflect_dom_object(
+            Box::new(ResizeObserverEntry::new_inherited(
+                target,
+                &content_rect,
+                &[&border_box_size],
+                &[&content_box_size],
+                &[&device_pixel_content_box_size],
+            )),
+            window,
+            ResizeObserverEntryBinding::Wrap,
+        )
+    }
+}
+
+impl ResizeObserverEntryMethods for ResizeObserverEntry {
+    // https://drafts.csswg.org/resize-observer/#dom-resizeobserverentry-target
+    fn Target(&self) -> DomRoot<Element> {
+        DomRoot::from_ref(&*self.target)
+    }
+
+    // https://drafts.csswg.org/resize-observer/#dom-resizeobserverentry-contentrect
+    fn ContentRect(&self) -> DomRoot<DOMRectReadOnly> {
+        DomRoot::from_ref(&*self.content_rect)
+    }
+
+    // https://drafts.csswg.org/resize-observer/#dom-resizeobserverentry-borderboxsize
+    fn BorderBoxSize(&self, cx: JSContext) -> JSVal {
+        let sizes: Vec<DomRoot<ResizeObserverSize>> = self
+            .border_box_size
+            .iter()
+            .map(|size| DomRoot::from_ref(&**size))
+            .collect();
+        to_frozen_array(&sizes, cx)
+    }
+
+    // https://drafts.csswg.org/resize-observer/#dom-resizeobserverentry-contentboxsize
+    fn ContentBoxSize(&self, cx: JSContext) -> JSVal {
+        let sizes: Vec<DomRoot<ResizeObserverSize>> = self
+            .content_box_size
+            .iter()
+            .map(|size| DomRoot::from_ref(&**size))
+            .collect();
+        to_frozen_array(&sizes, cx)
+    }
+
+    // https://drafts.csswg.org/resize-observer/#dom-resizeobserverentry-devicepixelcontentboxsize
+    fn DevicePixelContentBoxSize(&self, cx: JSContext) -> JSVal {
+        let sizes: Vec<DomRoot<ResizeObserverSize>> = self
+            .device_pixel_content_box_size
+            .iter()
+            .map(|size| DomRoot::from_ref(&**size))
+            .collect();
+        to_frozen_array(&sizes, cx)
+    }
+}
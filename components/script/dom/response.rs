@@ -18,6 +18,7 @@ use crate::dom::globalscope::GlobalScope;
 use crate::dom::headers::{is_obs_text, is_vchar};
 use crate::dom::headers::{Guard, Headers};
 use crate::dom::promise::Promise;
+use crate::dom::readablestream::ReadableStream;
 use crate::dom::xmlhttprequest::Extractable;
 use dom_struct::dom_struct;
 use http::header::HeaderMap as HyperHeaders;
@@ -48,6 +49,11 @@ pub struct Response {
     body: DomRefCell<NetTraitsResponseBody>,
     #[ignore_malloc_size_of = "Rc"]
     body_promise: DomRefCell<Option<(Rc<Promise>, BodyType)>>,
+    /// <https://fetch.spec.whatwg.org/#concept-body-stream>
+    stream: MutNullableDom<ReadableStream>,
+    /// The stream the chunks of the body are enqueued into as they arrive.
+    /// This differs from `stream` once the response has been cloned.
+    source_stream: MutNullableDom<ReadableStream>,
 }
 
 impl Response {
@@ -64,6 +70,8 @@ impl Response {
             url_list: DomRefCell::new(vec![]),
             body: DomRefCell::new(NetTraitsResponseBody::Empty),
             body_promise: DomRefCell::new(None),
+            stream: Default::default(),
+            source_stream: Default::default(),
         }
     }
 
@@ -210,9 +218,14 @@ impl Response {
 
     // https://fetch.spec.whatwg.org/#concept-body-locked
     fn locked(&self) -> bool {
-        // TODO: ReadableStream is unimplemented. Just return false
-        // for now.
-        false
+        self.stream.get().map_or(false, |stream| stream.is_locked())
+    }
+
+    // https://fetch.spec.whatwg.org/#concept-body-disturbed
+    fn disturbed(&self) -> bool {
+        self.stream
+            .get()
+            .map_or(false, |stream| stream.is_disturbed())
     }
 }
 
@@ -245,6 +258,10 @@ impl BodyOperations for Response {
     fn get_mime_type(&self) -> Ref<Vec<u8>> {
         self.mime_type.borrow()
     }
+
+    fn get_stream(&self) -> Option<DomRoot<ReadableStream>> {
+        self.stream.get()
+    }
 }
 
 // https://fetch.spec.whatwg.org/#redirect-status
@@ -326,7 +343,7 @@ impl ResponseMethods for Response {
     // https://fetch.spec.whatwg.org/#dom-response-clone
     fn Clone(&self) -> Fallible<DomRoot<Response>> {
         // Step 1
-        if self.is_locked() || self.body_used.get() {
+        if self.is_locked() || self.BodyUsed() {
             return Err(Error::Type("cannot clone a disturbed response".to_string()));
         }
 
@@ -351,15 +368,51 @@ impl ResponseMethods for Response {
         }
 
         // Step 3
-        // TODO: This step relies on promises, which are still unimplemented.
+        // https://fetch.spec.whatwg.org/#concept-body-clone
+        // A body that is still being received can only be shared through
+        // its stream.
+        let receiving = match *self.body.borrow() {
+            NetTraitsResponseBody::Receiving(_) => true,
+            _ => false,
+        };
+        if receiving {
+            self.GetBody();
+        }
+        if let Some(stream) = self.stream.get() {
+            let (branch_1, branch_2) = stream.tee()?;
+            self.stream.set(Some(&branch_1));
+            new_response.stream.set(Some(&branch_2));
+        }
 
         // Step 4
         Ok(new_response)
     }
 
+    // https://fetch.spec.whatwg.org/#dom-body-body
+    fn GetBody(&self) -> Option<DomRoot<ReadableStream>> {
+        if let Some(stream) = self.stream.get() {
+            return Some(stream);
+        }
+        let (bytes, done) = match *self.body.borrow() {
+            NetTraitsResponseBody::Empty => return None,
+            NetTraitsResponseBody::Receiving(ref bytes) => (bytes.clone(), false),
+            NetTraitsResponseBody::Done(ref bytes) => (bytes.clone(), true),
+        };
+        let stream = ReadableStream::new_from_bytes(&self.global());
+        if !bytes.is_empty() {
+            stream.enqueue_native(bytes);
+        }
+        if done {
+            stream.close_native();
+        }
+        self.stream.set(Some(&stream));
+        self.source_stream.set(Some(&stream));
+        Some(stream)
+    }
+
     // https://fetch.spec.whatwg.org/#dom-body-bodyused
     fn BodyUsed(&self) -> bool {
-        self.body_used.get()
+        self.body_used.get() || self.disturbed()
     }
 
     // https://fetch.spec.whatwg.org/#dom-body-text
@@ -412,11 +465,48 @@ impl Response {
         *self.url.borrow_mut() = Some(final_url);
     }
 
+    /// Start receiving the body of the response from the network.
+    pub fn start_receiving(&self) {
+        *self.body.borrow_mut() = NetTraitsResponseBody::Receiving(vec![]);
+    }
+
+    /// Deliver a chunk of the body received from the network. Once a stream
+    /// has been handed out, the chunk is enqueued into it rather than
+    /// buffered, unless the whole body is already being waited on.
+    pub fn stream_chunk(&self, chunk: Vec<u8>) {
+        let stream = self.source_stream.get();
+        if stream.is_none() || self.body_promise.borrow().is_some() {
+            if let NetTraitsResponseBody::Receiving(ref mut bytes) = *self.body.borrow_mut() {
+                bytes.extend_from_slice(&chunk);
+            }
+        }
+        if let Some(stream) = stream {
+            stream.enqueue_native(chunk);
+        }
+    }
+
     #[allow(unrooted_must_root)]
-    pub fn finish(&self, body: Vec<u8>) {
-        *self.body.borrow_mut() = NetTraitsResponseBody::Done(body);
+    pub fn finish(&self) {
+        let body = mem::replace(&mut *self.body.borrow_mut(), NetTraitsResponseBody::Empty);
+        *self.body.borrow_mut() = match body {
+            NetTraitsResponseBody::Receiving(bytes) => NetTraitsResponseBody::Done(bytes),
+            body => body,
+        };
+        if let Some(stream) = self.source_stream.get() {
+            stream.close_native();
+        }
         if let Some((p, body_type)) = self.body_promise.borrow_mut().take() {
             consume_body_with_promise(self, body_type, &p);
         }
     }
+
+    /// Error the body of the response after a network error.
+    pub fn error_body(&self, error: Error) {
+        if let Some(stream) = self.source_stream.get() {
+            stream.error_native(error.clone());
+        }
+        if let Some((p, _)) = self.body_promise.borrow_mut().take() {
+            p.reject_error(error);
+        }
+    }
 }
@@ -0,0 +1,485 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::CharacterDataBinding::CharacterDataMethods;
+use crate::dom::bindings::codegen::Bindings::RangeBinding::RangeMethods;
+use crate::dom::bindings::codegen::Bindings::SelectionBinding;
+use crate::dom::bindings::codegen::Bindings::SelectionBinding::SelectionMethods;
+use crate::dom::bindings::error::{Error, ErrorResult, Fallible};
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::refcounted::Trusted;
+use crate::dom::bindings::reflector::{reflect_dom_object, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot, MutNullableDom};
+use crate::dom::bindings::str::DOMString;
+use crate::dom::characterdata::CharacterData;
+use crate::dom::document::Document;
+use crate::dom::editing;
+use crate::dom::eventtarget::EventTarget;
+use crate::dom::node::{Node, ShadowIncluding};
+use crate::dom::range::{bp_position, Range};
+use crate::dom::text::Text;
+use crate::task_source::TaskSource;
+use dom_struct::dom_struct;
+use std::cell::Cell;
+use std::cmp::Ordering;
+
+/// <https://w3c.github.io/selection-api/#dfn-direction>
+#[derive(Clone, Copy, JSTraceable, MallocSizeOf, PartialEq)]
+enum Direction {
+    Forwards,
+    Backwards,
+    Directionless,
+}
+
+/// Convert an offset in UTF-16 code units into `s` to an offset in bytes,
+/// clamping it to the length of `s`.
+fn utf16_offset_to_byte_offset(s: &str, offset: u32) -> usize {
+    let mut code_units = 0;
+    for (index, c) in s.char_indices() {
+        if code_units >= offset {
+            return index;
+        }
+        code_units += c.len_utf16() as u32;
+    }
+    s.len()
+}
+
+/// Convert an index in characters into `s` to an offset in UTF-16 code units,
+/// clamping it to the length of `s`.
+pub fn char_index_to_utf16_offset(s: &str, index: usize) -> u32 {
+    s.chars().take(index).map(|c| c.len_utf16() as u32).sum()
+}
+
+/// <https://w3c.github.io/selection-api/#selection-interface>
+#[dom_struct]
+pub struct Selection {
+    reflector_: Reflector,
+    document: Dom<Document>,
+    /// <https://w3c.github.io/selection-api/#dfn-range>
+    range: MutNullableDom<Range>,
+    direction: Cell<Direction>,
+    /// Whether a task to fire `selectionchange` is already queued.
+    task_queued: Cell<bool>,
+    /// The text nodes currently painted as selected by layout.
+    highlighted_nodes: DomRefCell<Vec<Dom<Text>>>,
+}
+
+impl Selection {
+    fn new_inherited(document: &Document) -> Selection {
+        Selection {
+            reflector_: Reflector::new(),
+            document: Dom::from_ref(document),
+            range: MutNullableDom::new(None),
+            direction: Cell::new(Direction::Directionless),
+            task_queued: Cell::new(false),
+            highlighted_nodes: DomRefCell::new(vec![]),
+        }
+    }
+
+    pub fn new(document: &Document) -> DomRoot<Selection> {
+        reflect_dom_object(
+            Box::new(Selection::new_inherited(document)),
+            document.window(),
+            SelectionBinding::Wrap,
+        )
+    }
+
+    /// Whether `node` is in the same tree as the document of this selection.
+    fn is_in_document(&self, node: &Node) -> bool {
+        node.inclusive_ancestors(ShadowIncluding::No)
+            .last()
+            .map_or(false, |root| &*root == self.document.upcast::<Node>())
+    }
+
+    fn set_range(&self, range: Option<&Range>, direction: Direction) {
+        if let Some(old_range) = self.range.get() {
+            old_range.disassociate_selection(self);
+        }
+        if let Some(range) = range {
+            range.associate_selection(self);
+        }
+        self.range.set(range);
+        self.direction.set(direction);
+        self.queue_selectionchange_task();
+    }
+
+    /// Replace the range of this selection by a new one going from `anchor`
+    /// to `focus`, in either direction.
+    fn set_range_between(&self, anchor: (&Node, u32), focus: (&Node, u32)) {
+        let (anchor_node, anchor_offset) = anchor;
+        let (focus_node, focus_offset) = focus;
+        let is_backwards = bp_position(anchor_node, anchor_offset, focus_node, focus_offset) ==
+            Some(Ordering::Greater);
+        let range = if is_backwards {
+            Range::new(
+                &self.document,
+                focus_node,
+                focus_offset,
+                anchor_node,
+                anchor_offset,
+            )
+        } else {
+            Range::new(
+                &self.document,
+                anchor_node,
+                anchor_offset,
+                focus_node,
+                focus_offset,
+            )
+        };
+        let direction = if is_backwards {
+            Direction::Backwards
+        } else {
+            Direction::Forwards
+        };
+        self.set_range(Some(&range), direction);
+    }
+
+    /// <https://w3c.github.io/selection-api/#selectionchange-event>
+    pub fn queue_selectionchange_task(&self) {
+        if self.task_queued.get() {
+            return;
+        }
+        self.task_queued.set(true);
+        let this = Trusted::new(self);
+        let window = self.document.window();
+        let _ = window.task_manager().user_interaction_task_source().queue(
+            task!(selectionchange_task_steps: move || {
+                let this = this.root();
+                this.task_queued.set(false);
+                this.document.upcast::<EventTarget>().fire_event(atom!("selectionchange"));
+            }),
+            window.upcast(),
+        );
+    }
+
+    /// Make layout paint the text covered by the range of this selection, and
+    /// only that text, as selected, or paint the caret of a collapsed
+    /// selection in editable text.
+    pub fn update_highlighted_nodes(&self) {
+        let mut selected_nodes = vec![];
+        let caret = self
+            .range
+            .get()
+            .filter(|range| range.Collapsed())
+            .and_then(|range| {
+                let node = range.StartContainer();
+                if !editing::is_caret_visible_in(&node) {
+                    return None;
+                }
+                let text = DomRoot::downcast::<Text>(node)?;
+                let data = text.upcast::<CharacterData>().data().clone();
+                let offset = utf16_offset_to_byte_offset(&data, range.StartOffset());
+                Some((text, offset..offset))
+            });
+        selected_nodes.extend(caret);
+        if let Some(range) = self.range.get().filter(|range| !range.Collapsed()) {
+            let start_node = range.StartContainer();
+            let end_node = range.EndContainer();
+            let ancestor = range.CommonAncestorContainer();
+            for text in ancestor
+                .traverse_preorder(ShadowIncluding::No)
+                .filter_map(DomRoot::downcast::<Text>)
+            {
+                let node = text.upcast::<Node>();
+                if !range.IntersectsNode(node) {
+                    continue;
+                }
+                let data = text.upcast::<CharacterData>().data();
+                let start = if node == &*start_node {
+                    utf16_offset_to_byte_offset(&data, range.StartOffset())
+                } else {
+                    0
+                };
+                let end = if node == &*end_node {
+                    utf16_offset_to_byte_offset(&data, range.EndOffset())
+                } else {
+                    data.len()
+                };
+                if start < end {
+                    selected_nodes.push((DomRoot::from_ref(&*text), start..end));
+                }
+            }
+        }
+
+        let mut highlighted_nodes = self.highlighted_nodes.borrow_mut();
+        for text in highlighted_nodes.iter() {
+            if !selected_nodes.iter().any(|(node, _)| &**node == &**text) {
+                text.set_selected_bytes(None);
+            }
+        }
+        *highlighted_nodes = selected_nodes
+            .into_iter()
+            .map(|(text, selected_bytes)| {
+                text.set_selected_bytes(Some(selected_bytes));
+                Dom::from_ref(&*text)
+            })
+            .collect();
+    }
+}
+
+impl SelectionMethods for Selection {
+    // https://w3c.github.io/selection-api/#dom-selection-anchornode
+    fn GetAnchorNode(&self) -> Option<DomRoot<Node>> {
+        self.range.get().map(|range| match self.direction.get() {
+            Direction::Backwards => range.EndContainer(),
+            _ => range.StartContainer(),
+        })
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-anchoroffset
+    fn AnchorOffset(&self) -> u32 {
+        self.range
+            .get()
+            .map_or(0, |range| match self.direction.get() {
+                Direction::Backwards => range.EndOffset(),
+                _ => range.StartOffset(),
+            })
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-focusnode
+    fn GetFocusNode(&self) -> Option<DomRoot<Node>> {
+        self.range.get().map(|range| match self.direction.get() {
+            Direction::Backwards => range.StartContainer(),
+            _ => range.EndContainer(),
+        })
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-focusoffset
+    fn FocusOffset(&self) -> u32 {
+        self.range
+            .get()
+            .map_or(0, |range| match self.direction.get() {
+                Direction::Backwards => range.StartOffset(),
+                _ => range.EndOffset(),
+            })
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-iscollapsed
+    fn IsCollapsed(&self) -> bool {
+        self.range.get().map_or(true, |range| range.Collapsed())
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-rangecount
+    fn RangeCount(&self) -> u32 {
+        if self.range.get().is_some() {
+            1
+        } else {
+            0
+        }
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-type
+    fn Type(&self) -> DOMString {
+        match self.range.get() {
+            None => DOMString::from("None"),
+            Some(ref range) if range.Collapsed() => DOMString::from("Caret"),
+            Some(_) => DOMString::from("Range"),
+        }
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-getrangeat
+    fn GetRangeAt(&self, index: u32) -> Fallible<DomRoot<Range>> {
+        match self.range.get() {
+            Some(range) if index == 0 => Ok(range),
+            _ => Err(Error::IndexSize),
+        }
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-addrange
+    fn AddRange(&self, range: &Range) {
+        // Step 1.
+        if !self.is_in_document(&range.StartContainer()) {
+            return;
+        }
+
+        // Step 2.
+        if self.range.get().is_some() {
+            return;
+        }
+
+        // Step 3.
+        self.set_range(Some(range), Direction::Forwards);
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-removerange
+    fn RemoveRange(&self, range: &Range) -> ErrorResult {
+        match self.range.get() {
+            Some(ref current) if &**current == range => {
+                self.set_range(None, Direction::Directionless);
+                Ok(())
+            },
+            _ => Err(Error::NotFound),
+        }
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-removeallranges
+    fn RemoveAllRanges(&self) {
+        if self.range.get().is_some() {
+            self.set_range(None, Direction::Directionless);
+        }
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-empty
+    fn Empty(&self) {
+        self.RemoveAllRanges()
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-collapse
+    fn Collapse(&self, node: Option<&Node>, offset: u32) -> ErrorResult {
+        // Step 1.
+        let node = match node {
+            Some(node) => node,
+            None => {
+                self.RemoveAllRanges();
+                return Ok(());
+            },
+        };
+
+        // Step 2.
+        if node.is_doctype() {
+            return Err(Error::InvalidNodeType);
+        }
+
+        // Step 3.
+        if offset > node.len() {
+            return Err(Error::IndexSize);
+        }
+
+        // Step 4.
+        if !self.is_in_document(node) {
+            return Ok(());
+        }
+
+        // Steps 5-7.
+        let range = Range::new(&self.document, node, offset, node, offset);
+        self.set_range(Some(&range), Direction::Directionless);
+        Ok(())
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-setposition
+    fn SetPosition(&self, node: Option<&Node>, offset: u32) -> ErrorResult {
+        self.Collapse(node, offset)
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-collapsetostart
+    fn CollapseToStart(&self) -> ErrorResult {
+        let range = self.range.get().ok_or(Error::InvalidState)?;
+        self.Collapse(Some(&range.StartContainer()), range.StartOffset())
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-collapsetoend
+    fn CollapseToEnd(&self) -> ErrorResult {
+        let range = self.range.get().ok_or(Error::InvalidState)?;
+        self.Collapse(Some(&range.EndContainer()), range.EndOffset())
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-extend
+    fn Extend(&self, node: &Node, offset: u32) -> ErrorResult {
+        // Step 1.
+        if !self.is_in_document(node) {
+            return Ok(());
+        }
+
+        // Step 2.
+        if self.range.get().is_none() {
+            return Err(Error::InvalidState);
+        }
+
+        // Step 3.
+        if node.is_doctype() {
+            return Err(Error::InvalidNodeType);
+        }
+        if offset > node.len() {
+            return Err(Error::IndexSize);
+        }
+
+        // Steps 4-9.
+        let anchor_node = self.GetAnchorNode().unwrap();
+        let anchor_offset = self.AnchorOffset();
+        self.set_range_between((&anchor_node, anchor_offset), (node, offset));
+        Ok(())
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-setbaseandextent
+    fn SetBaseAndExtent(
+        &self,
+        anchor_node: &Node,
+        anchor_offset: u32,
+        focus_node: &Node,
+        focus_offset: u32,
+    ) -> ErrorResult {
+        // Step 1.
+        if anchor_offset > anchor_node.len() || focus_offset > focus_node.len() {
+            return Err(Error::IndexSize);
+        }
+
+        // Step 2.
+        if !self.is_in_document(anchor_node) || !self.is_in_document(focus_node) {
+            return Ok(());
+        }
+
+        // Steps 3-9.
+        self.set_range_between((anchor_node, anchor_offset), (focus_node, focus_offset));
+        Ok(())
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-selectallchildren
+    fn SelectAllChildren(&self, node: &Node) -> ErrorResult {
+        // Step 1.
+        if node.is_doctype() {
+            return Err(Error::InvalidNodeType);
+        }
+
+        // Step 2.
+        if !self.is_in_document(node) {
+            return Ok(());
+        }
+
+        // Steps 3-6.
+        let range = Range::new(&self.document, node, 0, node, node.children_count());
+        self.set_range(Some(&range), Direction::Forwards);
+        Ok(())
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-deletefromdocument
+    fn DeleteFromDocument(&self) -> ErrorResult {
+        match self.range.get() {
+            Some(range) => range.DeleteContents(),
+            None => Ok(()),
+        }
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-containsnode
+    fn ContainsNode(&self, node: &Node, allow_partial_containment: bool) -> bool {
+        // Step 1.
+        if !self.is_in_document(node) {
+            return false;
+        }
+        let range = match self.range.get() {
+            Some(range) => range,
+            None => return false,
+        };
+
+        // Step 2.
+        if allow_partial_containment {
+            return range.IntersectsNode(node);
+        }
+        let (start_node, start_offset) = (range.StartContainer(), range.StartOffset());
+        let (end_node, end_offset) = (range.EndContainer(), range.EndOffset());
+        let starts_after_start =
+            bp_position(node, 0, &start_node, start_offset) != Some(Ordering::Less);
+        let ends_before_end =
+            bp_position(node, node.len(), &end_node, end_offset) != Some(Ordering::Greater);
+        starts_after_start && ends_before_end
+    }
+
+    // https://w3c.github.io/selection-api/#dom-selection-stringifier
+    fn Stringifier(&self) -> DOMString {
+        self.range
+            .get()
+            .map_or_else(DOMString::new, |range| range.Stringifier())
+    }
+}
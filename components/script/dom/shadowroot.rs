@@ -2,6 +2,7 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
+use crate::dom::animation::Animation;
 use crate::dom::bindings::cell::DomRefCell;
 use crate::dom::bindings::codegen::Bindings::ShadowRootBinding::ShadowRootBinding::ShadowRootMethods;
 use crate::dom::bindings::codegen::Bindings::ShadowRootBinding::{self, ShadowRootMode};
@@ -79,6 +80,12 @@ impl ShadowRoot {
 
     pub fn detach(&self) {
         self.document.unregister_shadow_root(&self);
+        if let Some(host) = self.host.get() {
+            // Nothing in the host's light tree can be slotted anymore.
+            for child in host.upcast::<Node>().children() {
+                child.set_assigned_slot(None);
+            }
+        }
         let node = self.upcast::<Node>();
         node.set_containing_shadow_root(None);
         Node::complete_remove_subtree(&node, &UnbindContext::new(node, None, None, None));
@@ -237,6 +244,21 @@ impl ShadowRootMethods for ShadowRoot {
             )
         })
     }
+
+    // https://drafts.csswg.org/web-animations/#dom-documentorshadowroot-getanimations
+    fn GetAnimations(&self) -> Vec<DomRoot<Animation>> {
+        self.document
+            .relevant_animations()
+            .into_iter()
+            .filter(|&(_, ref target)| {
+                target
+                    .upcast::<Node>()
+                    .containing_shadow_root()
+                    .map_or(false, |shadow_root| &*shadow_root == self)
+            })
+            .map(|(animation, _)| animation)
+            .collect()
+    }
 }
 
 #[allow(unsafe_code)]
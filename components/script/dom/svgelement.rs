@@ -2,20 +2,104 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
+use crate::dom::attr::Attr;
+use crate::dom::bindings::cell::DomRefCell;
 use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::root::LayoutDom;
 use crate::dom::document::Document;
-use crate::dom::element::Element;
+use crate::dom::element::{AttributeMutation, Element};
+use crate::dom::node::{document_from_node, window_from_node, Node, NodeDamage};
 use crate::dom::virtualmethods::VirtualMethods;
 use dom_struct::dom_struct;
 use html5ever::{LocalName, Prefix};
+use servo_arc::Arc;
 use style::element_state::ElementState;
+use style::properties::{parse_one_declaration_into, SourcePropertyDeclaration};
+use style::properties::{Importance, PropertyDeclarationBlock, PropertyId};
+use style::shared_lock::Locked;
+use style_traits::ParsingMode;
+
+/// The presentation attributes of SVG elements, each of which sets the CSS
+/// property of the same name.
+///
+/// <https://www.w3.org/TR/SVG2/styling.html#PresentationAttributes>
+const PRESENTATION_ATTRIBUTES: &[&str] = &[
+    "alignment-baseline",
+    "baseline-shift",
+    "clip-path",
+    "clip-rule",
+    "color",
+    "color-interpolation",
+    "color-interpolation-filters",
+    "cursor",
+    "direction",
+    "display",
+    "dominant-baseline",
+    "fill",
+    "fill-opacity",
+    "fill-rule",
+    "filter",
+    "flood-color",
+    "flood-opacity",
+    "font-family",
+    "font-size",
+    "font-size-adjust",
+    "font-stretch",
+    "font-style",
+    "font-variant",
+    "font-weight",
+    "image-rendering",
+    "letter-spacing",
+    "lighting-color",
+    "marker-end",
+    "marker-mid",
+    "marker-start",
+    "mask",
+    "opacity",
+    "overflow",
+    "paint-order",
+    "pointer-events",
+    "shape-rendering",
+    "stop-color",
+    "stop-opacity",
+    "stroke",
+    "stroke-dasharray",
+    "stroke-dashoffset",
+    "stroke-linecap",
+    "stroke-linejoin",
+    "stroke-miterlimit",
+    "stroke-opacity",
+    "stroke-width",
+    "text-anchor",
+    "text-decoration",
+    "text-overflow",
+    "text-rendering",
+    "unicode-bidi",
+    "vector-effect",
+    "visibility",
+    "white-space",
+    "word-spacing",
+    "writing-mode",
+];
 
 #[dom_struct]
 pub struct SVGElement {
     element: Element,
+    /// The declarations of the presentation attributes of the element, if it
+    /// has any.
+    #[ignore_malloc_size_of = "Arc"]
+    presentation_hints: DomRefCell<Option<Arc<Locked<PropertyDeclarationBlock>>>>,
 }
 
 impl SVGElement {
+    pub fn new_inherited(
+        tag_name: LocalName,
+        prefix: Option<Prefix>,
+        document: &Document,
+    ) -> SVGElement {
+        SVGElement::new_inherited_with_state(ElementState::empty(), tag_name, prefix, document)
+    }
+
     pub fn new_inherited_with_state(
         state: ElementState,
         tag_name: LocalName,
@@ -24,6 +108,63 @@ impl SVGElement {
     ) -> SVGElement {
         SVGElement {
             element: Element::new_inherited_with_state(state, tag_name, ns!(svg), prefix, document),
+            presentation_hints: DomRefCell::new(None),
+        }
+    }
+
+    fn is_presentation_attribute(attr: &Attr) -> bool {
+        *attr.namespace() == ns!() && PRESENTATION_ATTRIBUTES.contains(&&**attr.local_name())
+    }
+
+    /// Parses the presentation attributes of the element into a single
+    /// declaration block. Values that don't parse are ignored, and unitless
+    /// lengths are allowed, as in SVG 1.1.
+    fn update_presentation_hints(&self) {
+        let document = document_from_node(self);
+        let window = window_from_node(self);
+        let mut block = PropertyDeclarationBlock::new();
+        for attr in self.upcast::<Element>().attrs().iter() {
+            if !SVGElement::is_presentation_attribute(attr) {
+                continue;
+            }
+            let id = match PropertyId::parse_enabled_for_all_content(attr.local_name()) {
+                Ok(id) => id,
+                Err(..) => continue,
+            };
+            let mut declarations = SourcePropertyDeclaration::new();
+            let result = parse_one_declaration_into(
+                &mut declarations,
+                id,
+                &attr.value(),
+                &document.base_url(),
+                window.css_error_reporter(),
+                ParsingMode::ALLOW_UNITLESS_LENGTH,
+                document.quirks_mode(),
+            );
+            if result.is_ok() {
+                block.extend(declarations.drain(), Importance::Normal);
+            }
+        }
+        *self.presentation_hints.borrow_mut() = if block.len() > 0 {
+            Some(Arc::new(document.style_shared_lock().wrap(block)))
+        } else {
+            None
+        };
+    }
+}
+
+pub trait LayoutSVGElementHelpers {
+    fn presentation_hints(&self) -> Option<Arc<Locked<PropertyDeclarationBlock>>>;
+}
+
+impl LayoutSVGElementHelpers for LayoutDom<SVGElement> {
+    #[allow(unsafe_code)]
+    fn presentation_hints(&self) -> Option<Arc<Locked<PropertyDeclarationBlock>>> {
+        unsafe {
+            (*self.unsafe_get())
+                .presentation_hints
+                .borrow_for_layout()
+                .clone()
         }
     }
 }
@@ -32,4 +173,24 @@ impl VirtualMethods for SVGElement {
     fn super_type(&self) -> Option<&dyn VirtualMethods> {
         Some(self.upcast::<Element>() as &dyn VirtualMethods)
     }
+
+    fn attribute_affects_presentational_hints(&self, attr: &Attr) -> bool {
+        if SVGElement::is_presentation_attribute(attr) {
+            return true;
+        }
+
+        self.super_type()
+            .unwrap()
+            .attribute_affects_presentational_hints(attr)
+    }
+
+    fn attribute_mutated(&self, attr: &Attr, mutation: AttributeMutation) {
+        self.super_type().unwrap().attribute_mutated(attr, mutation);
+        if SVGElement::is_presentation_attribute(attr) {
+            self.update_presentation_hints();
+        }
+        // The geometry of SVG content depends on attributes that aren't
+        // mapped to properties, and it is painted with its `<svg>` ancestor.
+        self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+    }
 }
@@ -0,0 +1,43 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::codegen::Bindings::SVGTextElementBinding;
+use crate::dom::bindings::root::DomRoot;
+use crate::dom::document::Document;
+use crate::dom::node::Node;
+use crate::dom::svgtextpositioningelement::SVGTextPositioningElement;
+use dom_struct::dom_struct;
+use html5ever::{LocalName, Prefix};
+
+#[dom_struct]
+pub struct SVGTextElement {
+    svgtextpositioningelement: SVGTextPositioningElement,
+}
+
+impl SVGTextElement {
+    fn new_inherited(
+        local_name: LocalName,
+        prefix: Option<Prefix>,
+        document: &Document,
+    ) -> SVGTextElement {
+        SVGTextElement {
+            svgtextpositioningelement: SVGTextPositioningElement::new_inherited(
+                local_name, prefix, document,
+            ),
+        }
+    }
+
+    #[allow(unrooted_must_root)]
+    pub fn new(
+        local_name: LocalName,
+        prefix: Option<Prefix>,
+        document: &Document,
+    ) -> DomRoot<SVGTextElement> {
+        Node::reflect_node(
+            Box::new(SVGTextElement::new_inherited(local_name, prefix, document)),
+            document,
+            SVGTextElementBinding::Wrap,
+        )
+    }
+}
@@ -2,6 +2,7 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
+use crate::dom::bindings::cell::DomRefCell;
 use crate::dom::bindings::codegen::Bindings::CharacterDataBinding::CharacterDataMethods;
 use crate::dom::bindings::codegen::Bindings::DocumentBinding::DocumentMethods;
 use crate::dom::bindings::codegen::Bindings::NodeBinding::NodeMethods;
@@ -9,24 +10,30 @@ use crate::dom::bindings::codegen::Bindings::TextBinding::{self, TextMethods};
 use crate::dom::bindings::codegen::Bindings::WindowBinding::WindowMethods;
 use crate::dom::bindings::error::{Error, Fallible};
 use crate::dom::bindings::inheritance::Castable;
-use crate::dom::bindings::root::DomRoot;
+use crate::dom::bindings::root::{DomRoot, LayoutDom};
 use crate::dom::bindings::str::DOMString;
 use crate::dom::characterdata::CharacterData;
 use crate::dom::document::Document;
-use crate::dom::node::Node;
+use crate::dom::htmlslotelement::HTMLSlotElement;
+use crate::dom::node::{Node, NodeDamage};
 use crate::dom::window::Window;
 use dom_struct::dom_struct;
+use std::ops::Range;
 
 /// An HTML text node.
 #[dom_struct]
 pub struct Text {
     characterdata: CharacterData,
+    /// The bytes of the data of this node which are part of the selection of
+    /// its document, as painted by layout.
+    selected_bytes: DomRefCell<Option<Range<usize>>>,
 }
 
 impl Text {
     pub fn new_inherited(text: DOMString, document: &Document) -> Text {
         Text {
             characterdata: CharacterData::new_inherited(text, document),
+            selected_bytes: DomRefCell::new(None),
         }
     }
 
@@ -42,6 +49,15 @@ impl Text {
         let document = window.Document();
         Ok(Text::new(text, &document))
     }
+
+    /// Update the bytes of this node that layout paints as selected.
+    pub fn set_selected_bytes(&self, selected_bytes: Option<Range<usize>>) {
+        if *self.selected_bytes.borrow() == selected_bytes {
+            return;
+        }
+        *self.selected_bytes.borrow_mut() = selected_bytes;
+        self.upcast::<Node>().dirty(NodeDamage::OtherNodeDamage);
+    }
 }
 
 impl TextMethods for Text {
@@ -100,4 +116,24 @@ impl TextMethods for Text {
         }
         DOMString::from(text)
     }
+
+    // https://dom.spec.whatwg.org/#dom-slotable-assignedslot
+    fn GetAssignedSlot(&self) -> Option<DomRoot<HTMLSlotElement>> {
+        HTMLSlotElement::find_a_slot(self.upcast(), true)
+    }
+}
+
+pub trait LayoutTextHelpers {
+    #[allow(unsafe_code)]
+    unsafe fn selection_for_layout(self) -> Option<Range<usize>>;
+}
+
+impl LayoutTextHelpers for LayoutDom<Text> {
+    #[allow(unsafe_code)]
+    unsafe fn selection_for_layout(self) -> Option<Range<usize>> {
+        (*self.unsafe_get())
+            .selected_bytes
+            .borrow_for_layout()
+            .clone()
+    }
 }
@@ -0,0 +1,411 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::callback::ExceptionHandling;
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::QueuingStrategyBinding::{
+    QueuingStrategy, QueuingStrategySize,
+};
+use crate::dom::bindings::codegen::Bindings::TransformStreamBinding;
+use crate::dom::bindings::codegen::Bindings::TransformStreamBinding::TransformStreamMethods;
+use crate::dom::bindings::codegen::Bindings::TransformerBinding::Transformer;
+use crate::dom::bindings::conversions::ConversionResult;
+use crate::dom::bindings::error::{Error, Fallible};
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot, MutNullableDom};
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::promise::Promise;
+use crate::dom::promisenativehandler::Callback;
+use crate::dom::readablestream::{ReadableStream, RejectWithReason, ResolveWithUndefined};
+use crate::dom::readablestreamdefaultcontroller::{
+    ReadableStreamDefaultController, UnderlyingSourceType,
+};
+use crate::dom::transformstreamdefaultcontroller::TransformStreamDefaultController;
+use crate::dom::writablestream::{WritableStream, WritableStreamState};
+use crate::dom::writablestreamdefaultcontroller::{
+    UnderlyingSinkType, WritableStreamDefaultController,
+};
+use crate::script_runtime::JSContext as SafeJSContext;
+use crate::streams::extract_high_water_mark;
+use crate::streams::{promise_resolved_with_undefined, upon_settlement};
+use dom_struct::dom_struct;
+use js::jsapi::{Heap, JSContext, JSObject};
+use js::jsval::{JSVal, ObjectValue, UndefinedValue};
+use js::rust::HandleValue;
+use std::cell::Cell;
+use std::ptr;
+use std::rc::Rc;
+
+/// <https://streams.spec.whatwg.org/#ts-class>
+#[dom_struct]
+pub struct TransformStream {
+    reflector_: Reflector,
+    readable: MutNullableDom<ReadableStream>,
+    writable: MutNullableDom<WritableStream>,
+    /// <https://streams.spec.whatwg.org/#transformstream-backpressure>
+    backpressure: Cell<bool>,
+    /// <https://streams.spec.whatwg.org/#transformstream-backpressurechangepromise>
+    #[ignore_malloc_size_of = "Rc"]
+    backpressure_change_promise: DomRefCell<Option<Rc<Promise>>>,
+    controller: MutNullableDom<TransformStreamDefaultController>,
+}
+
+impl TransformStream {
+    fn new_inherited() -> TransformStream {
+        TransformStream {
+            reflector_: Reflector::new(),
+            readable: Default::default(),
+            writable: Default::default(),
+            backpressure: Cell::new(false),
+            backpressure_change_promise: DomRefCell::new(None),
+            controller: Default::default(),
+        }
+    }
+
+    // https://streams.spec.whatwg.org/#ts-constructor
+    #[allow(unsafe_code)]
+    pub fn Constructor(
+        cx: SafeJSContext,
+        global: &GlobalScope,
+        transformer: Option<*mut JSObject>,
+        writable_strategy: &QueuingStrategy,
+        readable_strategy: &QueuingStrategy,
+    ) -> Fallible<DomRoot<TransformStream>> {
+        // Steps 1-2.
+        rooted!(in(*cx) let transformer_object = transformer.unwrap_or(ptr::null_mut()));
+        rooted!(in(*cx) let transformer_value = if transformer_object.is_null() {
+            UndefinedValue()
+        } else {
+            ObjectValue(transformer_object.get())
+        });
+        let transformer = match unsafe { Transformer::new(cx, transformer_value.handle()) } {
+            Ok(ConversionResult::Success(transformer)) => transformer,
+            Ok(ConversionResult::Failure(error)) => return Err(Error::Type(error.into_owned())),
+            Err(()) => return Err(Error::JSFailed),
+        };
+
+        // Steps 3-4.
+        if !transformer.readableType.get().is_undefined() {
+            return Err(Error::Range(
+                "Invalid readableType for the transformer".to_owned(),
+            ));
+        }
+        if !transformer.writableType.get().is_undefined() {
+            return Err(Error::Range(
+                "Invalid writableType for the transformer".to_owned(),
+            ));
+        }
+
+        // Steps 5-8.
+        let readable_high_water_mark = extract_high_water_mark(readable_strategy, 0.)?;
+        let readable_size = readable_strategy.size.clone();
+        let writable_high_water_mark = extract_high_water_mark(writable_strategy, 1.)?;
+        let writable_size = writable_strategy.size.clone();
+
+        // Steps 9-10.
+        let start_promise = Promise::new(global);
+        let stream = reflect_dom_object(
+            Box::new(TransformStream::new_inherited()),
+            global,
+            TransformStreamBinding::Wrap,
+        );
+        stream.initialize(
+            &start_promise,
+            writable_high_water_mark,
+            writable_size,
+            readable_high_water_mark,
+            readable_size,
+        )?;
+
+        // Step 11.
+        let controller = TransformStreamDefaultController::set_up_from_transformer(
+            &stream,
+            transformer_object.handle(),
+            &transformer,
+        );
+
+        // Step 12.
+        match transformer.start {
+            Some(ref start) => {
+                let result = start.Call_(
+                    &transformer_object.handle(),
+                    &*controller,
+                    ExceptionHandling::Rethrow,
+                )?;
+                rooted!(in(*cx) let result = result);
+                start_promise.resolve(cx, result.handle());
+            },
+            None => start_promise.resolve_native(&()),
+        }
+
+        Ok(stream)
+    }
+
+    /// <https://streams.spec.whatwg.org/#initialize-transform-stream>
+    #[allow(unrooted_must_root)]
+    fn initialize(
+        &self,
+        start_promise: &Rc<Promise>,
+        writable_high_water_mark: f64,
+        writable_size: Option<Rc<QueuingStrategySize>>,
+        readable_high_water_mark: f64,
+        readable_size: Option<Rc<QueuingStrategySize>>,
+    ) -> Fallible<()> {
+        let global = self.global();
+
+        // Steps 1-5.
+        let writable = WritableStream::new(&global);
+        WritableStreamDefaultController::set_up(
+            &writable,
+            UnderlyingSinkType::Transform(Dom::from_ref(self)),
+            writable_high_water_mark,
+            writable_size,
+            |_| Ok(start_promise.clone()),
+        )?;
+        self.writable.set(Some(&writable));
+
+        // Steps 6-10.
+        let readable = ReadableStream::new(&global);
+        ReadableStreamDefaultController::set_up(
+            &readable,
+            UnderlyingSourceType::Transform(Dom::from_ref(self)),
+            None,
+            readable_high_water_mark,
+            readable_size,
+        )?;
+        self.readable.set(Some(&readable));
+
+        // Steps 11-13.
+        self.set_backpressure(true);
+        Ok(())
+    }
+
+    pub fn set_controller(&self, controller: &TransformStreamDefaultController) {
+        self.controller.set(Some(controller));
+    }
+
+    fn controller(&self) -> DomRoot<TransformStreamDefaultController> {
+        self.controller
+            .get()
+            .expect("A transform stream is always set up with a controller")
+    }
+
+    pub fn readable(&self) -> DomRoot<ReadableStream> {
+        self.readable
+            .get()
+            .expect("A transform stream always has a readable side")
+    }
+
+    pub fn writable(&self) -> DomRoot<WritableStream> {
+        self.writable
+            .get()
+            .expect("A transform stream always has a writable side")
+    }
+
+    pub fn backpressure(&self) -> bool {
+        self.backpressure.get()
+    }
+
+    /// <https://streams.spec.whatwg.org/#transform-stream-error>
+    pub fn error(&self, error: HandleValue) {
+        self.readable().error_default_controller(error);
+        self.error_writable_and_unblock_write(error);
+    }
+
+    /// <https://streams.spec.whatwg.org/#transform-stream-error-writable-and-unblock-write>
+    pub fn error_writable_and_unblock_write(&self, error: HandleValue) {
+        self.controller().clear_algorithms();
+        self.writable().controller().error_if_needed(error);
+        if self.backpressure.get() {
+            self.set_backpressure(false);
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#transform-stream-set-backpressure>
+    pub fn set_backpressure(&self, backpressure: bool) {
+        if let Some(promise) = self.backpressure_change_promise.borrow_mut().take() {
+            promise.resolve_native(&());
+        }
+        *self.backpressure_change_promise.borrow_mut() = Some(Promise::new(&self.global()));
+        self.backpressure.set(backpressure);
+    }
+
+    fn backpressure_change_promise(&self) -> Rc<Promise> {
+        self.backpressure_change_promise
+            .borrow()
+            .clone()
+            .expect("The backpressure change promise is set on initialization")
+    }
+
+    /// <https://streams.spec.whatwg.org/#transform-stream-default-sink-write-algorithm>
+    #[allow(unrooted_must_root)]
+    pub fn sink_write(&self, chunk: HandleValue) -> Rc<Promise> {
+        // Step 3.
+        if !self.backpressure.get() {
+            return self.controller().perform_transform(chunk);
+        }
+
+        // Step 2.
+        let global = self.global();
+        let promise = Promise::new(&global);
+        upon_settlement(
+            &global,
+            &self.backpressure_change_promise(),
+            Box::new(TransformStreamReaction {
+                stream: Dom::from_ref(self),
+                chunk: Heap::boxed(chunk.get()),
+                promise: promise.clone(),
+                step: TransformStreamStep::BackpressureRelieved,
+            }),
+            Box::new(RejectWithReason(promise.clone())),
+        );
+        promise
+    }
+
+    fn write_after_backpressure(&self, chunk: HandleValue, promise: &Rc<Promise>) {
+        let global = self.global();
+        let writable = self.writable();
+
+        // Step 2.2.
+        if writable.state() == WritableStreamState::Erroring {
+            let cx = global.get_cx();
+            rooted!(in(*cx) let mut stored_error = UndefinedValue());
+            writable.stored_error(stored_error.handle_mut());
+            promise.reject(cx, stored_error.handle());
+            return;
+        }
+
+        // Step 2.4.
+        let transform_promise = self.controller().perform_transform(chunk);
+        upon_settlement(
+            &global,
+            &transform_promise,
+            Box::new(ResolveWithUndefined(promise.clone())),
+            Box::new(RejectWithReason(promise.clone())),
+        );
+    }
+
+    /// <https://streams.spec.whatwg.org/#transform-stream-default-sink-abort-algorithm>
+    pub fn sink_abort(&self, reason: HandleValue) -> Rc<Promise> {
+        self.error(reason);
+        promise_resolved_with_undefined(&self.global())
+    }
+
+    /// <https://streams.spec.whatwg.org/#transform-stream-default-sink-close-algorithm>
+    #[allow(unrooted_must_root)]
+    pub fn sink_close(&self) -> Rc<Promise> {
+        let global = self.global();
+
+        // Steps 1-4.
+        let controller = self.controller();
+        let flush_promise = controller.perform_flush();
+        controller.clear_algorithms();
+
+        // Step 5.
+        let promise = Promise::new(&global);
+        upon_settlement(
+            &global,
+            &flush_promise,
+            Box::new(TransformStreamReaction {
+                stream: Dom::from_ref(self),
+                chunk: Heap::boxed(UndefinedValue()),
+                promise: promise.clone(),
+                step: TransformStreamStep::FlushFulfilled,
+            }),
+            Box::new(TransformStreamReaction {
+                stream: Dom::from_ref(self),
+                chunk: Heap::boxed(UndefinedValue()),
+                promise: promise.clone(),
+                step: TransformStreamStep::FlushRejected,
+            }),
+        );
+        promise
+    }
+
+    fn flush_fulfilled(&self, promise: &Rc<Promise>) {
+        let readable = self.readable();
+        if readable.is_errored() {
+            let cx = self.global().get_cx();
+            rooted!(in(*cx) let mut stored_error = UndefinedValue());
+            readable.stored_error(stored_error.handle_mut());
+            promise.reject(cx, stored_error.handle());
+            return;
+        }
+        readable.close_default_controller();
+        promise.resolve_native(&());
+    }
+
+    fn flush_rejected(&self, reason: HandleValue, promise: &Rc<Promise>) {
+        self.error(reason);
+        let cx = self.global().get_cx();
+        rooted!(in(*cx) let mut stored_error = UndefinedValue());
+        self.readable().stored_error(stored_error.handle_mut());
+        promise.reject(cx, stored_error.handle());
+    }
+
+    /// <https://streams.spec.whatwg.org/#transform-stream-default-source-pull>
+    pub fn source_pull(&self) -> Rc<Promise> {
+        self.set_backpressure(false);
+        self.backpressure_change_promise()
+    }
+
+    /// <https://streams.spec.whatwg.org/#transform-stream-default-source-cancel>
+    pub fn source_cancel(&self, reason: HandleValue) -> Rc<Promise> {
+        self.error_writable_and_unblock_write(reason);
+        promise_resolved_with_undefined(&self.global())
+    }
+
+    /// Error the whole stream with `reason`, the transform algorithm having
+    /// rejected with it, then reject `promise` with it.
+    pub fn error_and_reject(&self, reason: HandleValue, promise: &Promise) {
+        self.error(reason);
+        promise.reject(self.global().get_cx(), reason);
+    }
+}
+
+#[derive(Clone, Copy, JSTraceable, MallocSizeOf)]
+enum TransformStreamStep {
+    BackpressureRelieved,
+    FlushFulfilled,
+    FlushRejected,
+}
+
+/// The steps run once the backpressure of the readable side is relieved,
+/// or the flush algorithm of the transformer settles.
+#[derive(JSTraceable, MallocSizeOf)]
+#[must_root]
+struct TransformStreamReaction {
+    stream: Dom<TransformStream>,
+    #[ignore_malloc_size_of = "Defined in mozjs"]
+    chunk: Box<Heap<JSVal>>,
+    #[ignore_malloc_size_of = "Rc"]
+    promise: Rc<Promise>,
+    step: TransformStreamStep,
+}
+
+impl Callback for TransformStreamReaction {
+    fn callback(&self, cx: *mut JSContext, v: HandleValue) {
+        match self.step {
+            TransformStreamStep::BackpressureRelieved => {
+                rooted!(in(cx) let chunk = self.chunk.get());
+                self.stream
+                    .write_after_backpressure(chunk.handle(), &self.promise);
+            },
+            TransformStreamStep::FlushFulfilled => self.stream.flush_fulfilled(&self.promise),
+            TransformStreamStep::FlushRejected => self.stream.flush_rejected(v, &self.promise),
+        }
+    }
+}
+
+impl TransformStreamMethods for TransformStream {
+    // https://streams.spec.whatwg.org/#ts-readable
+    fn Readable(&self) -> DomRoot<ReadableStream> {
+        self.readable()
+    }
+
+    // https://streams.spec.whatwg.org/#ts-writable
+    fn Writable(&self) -> DomRoot<WritableStream> {
+        self.writable()
+    }
+}
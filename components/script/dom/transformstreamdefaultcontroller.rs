@@ -0,0 +1,226 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::callback::ExceptionHandling;
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::TransformStreamDefaultControllerBinding;
+use crate::dom::bindings::codegen::Bindings::TransformStreamDefaultControllerBinding::TransformStreamDefaultControllerMethods;
+use crate::dom::bindings::codegen::Bindings::TransformerBinding::{
+    Transformer, TransformerFlushCallback, TransformerTransformCallback,
+};
+use crate::dom::bindings::error::{Error, ErrorResult};
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot, MutNullableDom};
+use crate::dom::promise::Promise;
+use crate::dom::promisenativehandler::Callback;
+use crate::dom::readablestream::ResolveWithUndefined;
+use crate::dom::transformstream::TransformStream;
+use crate::script_runtime::JSContext as SafeJSContext;
+use crate::streams::{error_to_jsval, promise_from_callback_result, promise_rejected_with_error};
+use crate::streams::{promise_resolved_with_undefined, rethrow, upon_settlement};
+use dom_struct::dom_struct;
+use js::jsapi::{Heap, JSContext, JSObject};
+use js::jsval::UndefinedValue;
+use js::rust::{HandleObject, HandleValue};
+use std::rc::Rc;
+
+/// <https://streams.spec.whatwg.org/#ts-default-controller-class>
+#[dom_struct]
+pub struct TransformStreamDefaultController {
+    reflector_: Reflector,
+    stream: MutNullableDom<TransformStream>,
+    /// The object the methods of the transformer are called on.
+    #[ignore_malloc_size_of = "Defined in mozjs"]
+    transformer: Heap<*mut JSObject>,
+    /// <https://streams.spec.whatwg.org/#transformstreamdefaultcontroller-transformalgorithm>
+    #[ignore_malloc_size_of = "Rc"]
+    transform: DomRefCell<Option<Rc<TransformerTransformCallback>>>,
+    /// <https://streams.spec.whatwg.org/#transformstreamdefaultcontroller-flushalgorithm>
+    #[ignore_malloc_size_of = "Rc"]
+    flush: DomRefCell<Option<Rc<TransformerFlushCallback>>>,
+}
+
+impl TransformStreamDefaultController {
+    fn new_inherited(transformer: &Transformer) -> TransformStreamDefaultController {
+        TransformStreamDefaultController {
+            reflector_: Reflector::new(),
+            stream: Default::default(),
+            transformer: Heap::default(),
+            transform: DomRefCell::new(transformer.transform.clone()),
+            flush: DomRefCell::new(transformer.flush.clone()),
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#set-up-transform-stream-default-controller-from-transformer>
+    pub fn set_up_from_transformer(
+        stream: &TransformStream,
+        transformer_object: HandleObject,
+        transformer: &Transformer,
+    ) -> DomRoot<TransformStreamDefaultController> {
+        let controller = reflect_dom_object(
+            Box::new(TransformStreamDefaultController::new_inherited(transformer)),
+            &*stream.global(),
+            TransformStreamDefaultControllerBinding::Wrap,
+        );
+        controller.transformer.set(transformer_object.get());
+
+        // https://streams.spec.whatwg.org/#set-up-transform-stream-default-controller
+        controller.stream.set(Some(stream));
+        stream.set_controller(&controller);
+        controller
+    }
+
+    fn stream(&self) -> DomRoot<TransformStream> {
+        self.stream
+            .get()
+            .expect("A controller is always set up with a stream")
+    }
+
+    /// <https://streams.spec.whatwg.org/#transform-stream-default-controller-clear-algorithms>
+    pub fn clear_algorithms(&self) {
+        *self.transform.borrow_mut() = None;
+        *self.flush.borrow_mut() = None;
+    }
+
+    /// <https://streams.spec.whatwg.org/#transform-stream-default-controller-enqueue>
+    pub fn enqueue(&self, chunk: HandleValue) -> ErrorResult {
+        let stream = self.stream();
+        let readable = stream.readable();
+        let controller = readable
+            .default_controller()
+            .expect("The readable side of a transform stream has a default controller");
+
+        // Step 3.
+        if !controller.can_close_or_enqueue() {
+            return Err(Error::Type(
+                "The readable side cannot be enqueued to".to_owned(),
+            ));
+        }
+
+        // Step 4.
+        if let Err(error) = controller.enqueue(chunk) {
+            let global = self.global();
+            let cx = global.get_cx();
+            rooted!(in(*cx) let mut reason = UndefinedValue());
+            error_to_jsval(&global, error, reason.handle_mut());
+            rooted!(in(*cx) let mut stored_error = UndefinedValue());
+            readable.stored_error(stored_error.handle_mut());
+            stream.error_writable_and_unblock_write(reason.handle());
+            return Err(rethrow(&global, stored_error.handle()));
+        }
+
+        // Steps 6-7.
+        let backpressure = controller.has_backpressure();
+        if backpressure != stream.backpressure() {
+            assert!(backpressure);
+            stream.set_backpressure(true);
+        }
+        Ok(())
+    }
+
+    /// <https://streams.spec.whatwg.org/#transform-stream-default-controller-terminate>
+    pub fn terminate(&self) {
+        let stream = self.stream();
+        stream.readable().close_default_controller();
+
+        let global = self.global();
+        rooted!(in(*global.get_cx()) let mut error = UndefinedValue());
+        error_to_jsval(
+            &global,
+            Error::Type("The transform stream was terminated".to_owned()),
+            error.handle_mut(),
+        );
+        stream.error_writable_and_unblock_write(error.handle());
+    }
+
+    /// <https://streams.spec.whatwg.org/#transform-stream-default-controller-perform-transform>
+    #[allow(unrooted_must_root)]
+    pub fn perform_transform(&self, chunk: HandleValue) -> Rc<Promise> {
+        let global = self.global();
+
+        // Step 1.
+        let transform = self.transform.borrow().clone();
+        let transform_promise = match transform {
+            Some(transform) => {
+                rooted!(in(*global.get_cx()) let this = self.transformer.get());
+                let result =
+                    transform.Call_(&this.handle(), chunk, self, ExceptionHandling::Rethrow);
+                promise_from_callback_result(&global, result)
+            },
+            // https://streams.spec.whatwg.org/#set-up-transform-stream-default-controller-from-transformer
+            // Step 2.
+            None => match self.enqueue(chunk) {
+                Ok(()) => promise_resolved_with_undefined(&global),
+                Err(error) => promise_rejected_with_error(&global, error),
+            },
+        };
+
+        // Step 2.
+        let promise = Promise::new(&global);
+        upon_settlement(
+            &global,
+            &transform_promise,
+            Box::new(ResolveWithUndefined(promise.clone())),
+            Box::new(TransformRejected {
+                stream: Dom::from_ref(&*self.stream()),
+                promise: promise.clone(),
+            }),
+        );
+        promise
+    }
+
+    /// Run the flush algorithm of the transformer.
+    pub fn perform_flush(&self) -> Rc<Promise> {
+        let global = self.global();
+        let flush = self.flush.borrow().clone();
+        match flush {
+            Some(flush) => {
+                rooted!(in(*global.get_cx()) let this = self.transformer.get());
+                let result = flush.Call_(&this.handle(), self, ExceptionHandling::Rethrow);
+                promise_from_callback_result(&global, result)
+            },
+            None => promise_resolved_with_undefined(&global),
+        }
+    }
+}
+
+/// Error the transform stream once its transform algorithm rejects.
+#[derive(JSTraceable, MallocSizeOf)]
+#[must_root]
+struct TransformRejected {
+    stream: Dom<TransformStream>,
+    #[ignore_malloc_size_of = "Rc"]
+    promise: Rc<Promise>,
+}
+
+impl Callback for TransformRejected {
+    fn callback(&self, _cx: *mut JSContext, v: HandleValue) {
+        self.stream.error_and_reject(v, &self.promise);
+    }
+}
+
+impl TransformStreamDefaultControllerMethods for TransformStreamDefaultController {
+    // https://streams.spec.whatwg.org/#ts-default-controller-desired-size
+    fn GetDesiredSize(&self) -> Option<f64> {
+        self.stream()
+            .readable()
+            .default_controller()
+            .and_then(|controller| controller.get_desired_size())
+    }
+
+    // https://streams.spec.whatwg.org/#ts-default-controller-enqueue
+    fn Enqueue(&self, _cx: SafeJSContext, chunk: HandleValue) -> ErrorResult {
+        self.enqueue(chunk)
+    }
+
+    // https://streams.spec.whatwg.org/#ts-default-controller-error
+    fn Error(&self, _cx: SafeJSContext, reason: HandleValue) {
+        self.stream().error(reason);
+    }
+
+    // https://streams.spec.whatwg.org/#ts-default-controller-terminate
+    fn Terminate(&self) {
+        self.terminate();
+    }
+}
@@ -1,13 +1,104 @@
 /* This Source Code Form is subject to the terms of the Mozilla Public
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
-use crate::dom::validitystate::ValidationFlags;
+use crate::dom::bindings::codegen::Bindings::EventBinding::EventMethods;
+use crate::dom::bindings::codegen::Bindings::HTMLElementBinding::HTMLElementMethods;
+use crate::dom::bindings::inheritance::Castable;
+use crate::dom::bindings::root::DomRoot;
+use crate::dom::bindings::str::DOMString;
+use crate::dom::element::Element;
+use crate::dom::eventtarget::EventTarget;
+use crate::dom::htmldatalistelement::HTMLDataListElement;
+use crate::dom::htmlelement::HTMLElement;
+use crate::dom::node::{window_from_node, Node};
+use crate::dom::validitystate::{ValidationFlags, ValidityState};
+use embedder_traits::EmbedderMsg;
 
+/// Trait for elements with constraint validation support
 pub trait Validatable {
-    fn is_instance_validatable(&self) -> bool {
-        true
+    fn as_element(&self) -> &Element;
+
+    /// The element's ValidityState, created on first use
+    fn validity_state(&self) -> DomRoot<ValidityState>;
+
+    // https://html.spec.whatwg.org/multipage/#candidate-for-constraint-validation
+    fn is_instance_validatable(&self) -> bool;
+
+    /// Check the constraints in `validate_flags` against the element's current state,
+    /// returning the flags of those which are not satisfied
+    fn perform_validation(&self, _validate_flags: ValidationFlags) -> ValidationFlags {
+        ValidationFlags::empty()
+    }
+
+    /// Re-run validation for `validate_flags` and return every unsatisfied constraint
+    fn validate(&self, validate_flags: ValidationFlags) -> ValidationFlags {
+        let validity_state = self.validity_state();
+        validity_state.perform_validation_and_update(validate_flags);
+        validity_state.invalid_flags()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#concept-fv-valid
+    fn satisfies_constraints(&self) -> bool {
+        self.validate(ValidationFlags::all()).is_empty()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#check-validity-steps
+    fn check_validity(&self) -> bool {
+        if !self.is_instance_validatable() || self.satisfies_constraints() {
+            return true;
+        }
+
+        // Step 1.1
+        self.as_element()
+            .upcast::<EventTarget>()
+            .fire_cancelable_event(atom!("invalid"));
+
+        // Step 1.2
+        false
     }
-    fn validate(&self, _validate_flags: ValidationFlags) -> bool {
-        true
+
+    // https://html.spec.whatwg.org/multipage/#report-validity-steps
+    fn report_validity(&self) -> bool {
+        if !self.is_instance_validatable() || self.satisfies_constraints() {
+            return true;
+        }
+
+        // Step 1.1
+        let event = self
+            .as_element()
+            .upcast::<EventTarget>()
+            .fire_cancelable_event(atom!("invalid"));
+
+        // Step 1.2
+        if !event.DefaultPrevented() {
+            report_invalid_control(self.as_element(), self.validation_message());
+        }
+
+        // Step 1.3
+        false
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-cva-validationmessage
+    fn validation_message(&self) -> DOMString {
+        if !self.is_instance_validatable() {
+            return DOMString::new();
+        }
+        self.validity_state().validation_message()
     }
 }
+
+/// Report the problems with an element's constraints to the user, by focusing it and
+/// asking the embedder to show its validation message.
+pub fn report_invalid_control(element: &Element, message: DOMString) {
+    if let Some(html_element) = element.downcast::<HTMLElement>() {
+        html_element.Focus();
+    }
+    let window = window_from_node(element);
+    window.send_to_embedder(EmbedderMsg::ShowValidationMessage(String::from(message)));
+}
+
+// https://html.spec.whatwg.org/multipage/#the-datalist-element%3Abarred-from-constraint-validation
+pub fn is_barred_by_datalist_ancestor(node: &Node) -> bool {
+    node.ancestors()
+        .any(|ancestor| ancestor.is::<HTMLDataListElement>())
+}
@@ -2,32 +2,21 @@
  * License, v. 2.0. If a copy of the MPL was not distributed with this
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
+use crate::dom::bindings::cell::DomRefCell;
 use crate::dom::bindings::codegen::Bindings::ValidityStateBinding;
 use crate::dom::bindings::codegen::Bindings::ValidityStateBinding::ValidityStateMethods;
 use crate::dom::bindings::reflector::{reflect_dom_object, Reflector};
 use crate::dom::bindings::root::{Dom, DomRoot};
+use crate::dom::bindings::str::DOMString;
 use crate::dom::element::Element;
 use crate::dom::window::Window;
 use dom_struct::dom_struct;
+use std::cell::Cell;
+use style::element_state::ElementState;
 
 // https://html.spec.whatwg.org/multipage/#validity-states
-#[derive(JSTraceable, MallocSizeOf)]
-#[allow(dead_code)]
-pub enum ValidityStatus {
-    ValueMissing,
-    TypeMismatch,
-    PatternMismatch,
-    TooLong,
-    TooShort,
-    RangeUnderflow,
-    RangeOverflow,
-    StepMismatch,
-    BadInput,
-    CustomError,
-    Valid,
-}
-
 bitflags! {
+    #[derive(JSTraceable, MallocSizeOf)]
     pub struct ValidationFlags: u32 {
         const VALUE_MISSING    = 0b0000000001;
         const TYPE_MISMATCH    = 0b0000000010;
@@ -42,12 +31,40 @@ bitflags! {
     }
 }
 
+impl ValidationFlags {
+    /// The message shown to the user for the first failing constraint in these flags.
+    fn message(&self) -> &'static str {
+        if self.contains(ValidationFlags::VALUE_MISSING) {
+            "Please fill out this field."
+        } else if self.contains(ValidationFlags::TYPE_MISMATCH) {
+            "Please enter a value of the expected type."
+        } else if self.contains(ValidationFlags::PATTERN_MISMATCH) {
+            "Please match the requested format."
+        } else if self.contains(ValidationFlags::TOO_LONG) {
+            "Please shorten this text."
+        } else if self.contains(ValidationFlags::TOO_SHORT) {
+            "Please lengthen this text."
+        } else if self.contains(ValidationFlags::RANGE_UNDERFLOW) {
+            "Please select a higher value."
+        } else if self.contains(ValidationFlags::RANGE_OVERFLOW) {
+            "Please select a lower value."
+        } else if self.contains(ValidationFlags::STEP_MISMATCH) {
+            "Please select a valid value."
+        } else if self.contains(ValidationFlags::BAD_INPUT) {
+            "Please enter a valid value."
+        } else {
+            ""
+        }
+    }
+}
+
 // https://html.spec.whatwg.org/multipage/#validitystate
 #[dom_struct]
 pub struct ValidityState {
     reflector_: Reflector,
     element: Dom<Element>,
-    state: ValidityStatus,
+    custom_error_message: DomRefCell<DOMString>,
+    invalid_flags: Cell<ValidationFlags>,
 }
 
 impl ValidityState {
@@ -55,7 +72,8 @@ impl ValidityState {
         ValidityState {
             reflector_: Reflector::new(),
             element: Dom::from_ref(element),
-            state: ValidityStatus::Valid,
+            custom_error_message: DomRefCell::new(DOMString::new()),
+            invalid_flags: Cell::new(ValidationFlags::empty()),
         }
     }
 
@@ -66,61 +84,122 @@ impl ValidityState {
             ValidityStateBinding::Wrap,
         )
     }
+
+    pub fn invalid_flags(&self) -> ValidationFlags {
+        self.invalid_flags.get()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#custom-validity-error-message
+    pub fn custom_error_message(&self) -> DOMString {
+        self.custom_error_message.borrow().clone()
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-cva-setcustomvalidity
+    pub fn set_custom_error_message(&self, error: DOMString) {
+        *self.custom_error_message.borrow_mut() = error;
+        self.perform_validation_and_update(ValidationFlags::CUSTOM_ERROR);
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-cva-validationmessage
+    pub fn validation_message(&self) -> DOMString {
+        let flags = self.invalid_flags.get();
+        if flags.contains(ValidationFlags::CUSTOM_ERROR) {
+            return self.custom_error_message();
+        }
+        DOMString::from(flags.message())
+    }
+
+    /// Re-run the checks for `update_flags` on the element, keeping the results of the
+    /// other constraints, and update the `:valid` and `:invalid` element states.
+    pub fn perform_validation_and_update(&self, update_flags: ValidationFlags) {
+        let mut invalid_flags = self.invalid_flags.get();
+        invalid_flags.remove(update_flags);
+
+        let validatable = self.element.as_maybe_validatable();
+        if let Some(validatable) = validatable {
+            let new_flags = validatable.perform_validation(update_flags);
+            invalid_flags.insert(new_flags & update_flags);
+        }
+
+        let has_custom_error = !self.custom_error_message.borrow().is_empty();
+        invalid_flags.set(ValidationFlags::CUSTOM_ERROR, has_custom_error);
+
+        self.invalid_flags.set(invalid_flags);
+        self.update_pseudo_classes();
+    }
+
+    fn update_pseudo_classes(&self) {
+        let candidate = self
+            .element
+            .as_maybe_validatable()
+            .map_or(false, |validatable| validatable.is_instance_validatable());
+        let valid = self.invalid_flags.get().is_empty();
+        self.element
+            .set_state(ElementState::IN_VALID_STATE, candidate && valid);
+        self.element
+            .set_state(ElementState::IN_INVALID_STATE, candidate && !valid);
+    }
 }
 
 impl ValidityStateMethods for ValidityState {
     // https://html.spec.whatwg.org/multipage/#dom-validitystate-valuemissing
     fn ValueMissing(&self) -> bool {
-        false
+        self.invalid_flags()
+            .contains(ValidationFlags::VALUE_MISSING)
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-validitystate-typemismatch
     fn TypeMismatch(&self) -> bool {
-        false
+        self.invalid_flags()
+            .contains(ValidationFlags::TYPE_MISMATCH)
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-validitystate-patternmismatch
     fn PatternMismatch(&self) -> bool {
-        false
+        self.invalid_flags()
+            .contains(ValidationFlags::PATTERN_MISMATCH)
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-validitystate-toolong
     fn TooLong(&self) -> bool {
-        false
+        self.invalid_flags().contains(ValidationFlags::TOO_LONG)
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-validitystate-tooshort
     fn TooShort(&self) -> bool {
-        false
+        self.invalid_flags().contains(ValidationFlags::TOO_SHORT)
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-validitystate-rangeunderflow
     fn RangeUnderflow(&self) -> bool {
-        false
+        self.invalid_flags()
+            .contains(ValidationFlags::RANGE_UNDERFLOW)
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-validitystate-rangeoverflow
     fn RangeOverflow(&self) -> bool {
-        false
+        self.invalid_flags()
+            .contains(ValidationFlags::RANGE_OVERFLOW)
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-validitystate-stepmismatch
     fn StepMismatch(&self) -> bool {
-        false
+        self.invalid_flags()
+            .contains(ValidationFlags::STEP_MISMATCH)
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-validitystate-badinput
     fn BadInput(&self) -> bool {
-        false
+        self.invalid_flags().contains(ValidationFlags::BAD_INPUT)
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-validitystate-customerror
     fn CustomError(&self) -> bool {
-        false
+        self.invalid_flags().contains(ValidationFlags::CUSTOM_ERROR)
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-validitystate-valid
     fn Valid(&self) -> bool {
-        false
+        self.invalid_flags().is_empty()
     }
 }
@@ -556,6 +556,18 @@ impl WebGLRenderingContext {
 
     fn get_image_pixels(&self, source: TexImageSource) -> Fallible<Option<TexPixels>> {
         Ok(Some(match source {
+            TexImageSource::ImageBitmap(bitmap) => {
+                if !bitmap.origin_is_clean() {
+                    return Err(Error::Security);
+                }
+                let data = bitmap.bitmap_data().ok_or(Error::InvalidState)?;
+                TexPixels::new(
+                    IpcSharedMemory::from_bytes(&data),
+                    bitmap.size(),
+                    PixelFormat::BGRA8,
+                    true,
+                )
+            },
             TexImageSource::ImageData(image_data) => TexPixels::new(
                 image_data.to_shared_memory(),
                 image_data.get_size(),
@@ -583,8 +595,13 @@ impl WebGLRenderingContext {
                 };
 
                 let size = Size2D::new(img.width, img.height);
+                let bytes = if img.is_animated() {
+                    IpcSharedMemory::from_bytes(img.first_frame())
+                } else {
+                    img.bytes.clone()
+                };
 
-                TexPixels::new(img.bytes.clone(), size, img.format, false)
+                TexPixels::new(bytes, size, img.format, false)
             },
             // TODO(emilio): Getting canvas data is implemented in CanvasRenderingContext2D,
             // but we need to refactor it moving it to `HTMLCanvasElement` and support
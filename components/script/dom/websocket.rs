@@ -598,6 +598,7 @@ impl TaskOnce for MessageReceivedTask {
                 message.handle(),
                 Some(&ws.origin().ascii_serialization()),
                 None,
+                vec![],
             );
         }
     }
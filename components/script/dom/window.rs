@@ -9,6 +9,9 @@ use crate::dom::bindings::codegen::Bindings::DocumentBinding::{
 };
 use crate::dom::bindings::codegen::Bindings::FunctionBinding::Function;
 use crate::dom::bindings::codegen::Bindings::HistoryBinding::HistoryBinding::HistoryMethods;
+use crate::dom::bindings::codegen::Bindings::ImageBitmapBinding::{
+    ImageBitmapOptions, ImageBitmapSource,
+};
 use crate::dom::bindings::codegen::Bindings::MediaQueryListBinding::MediaQueryListBinding::MediaQueryListMethods;
 use crate::dom::bindings::codegen::Bindings::PermissionStatusBinding::PermissionState;
 use crate::dom::bindings::codegen::Bindings::RequestBinding::RequestInit;
@@ -24,7 +27,7 @@ use crate::dom::bindings::refcounted::Trusted;
 use crate::dom::bindings::reflector::DomObject;
 use crate::dom::bindings::root::{Dom, DomRoot, MutNullableDom};
 use crate::dom::bindings::str::{DOMString, USVString};
-use crate::dom::bindings::structuredclone::StructuredCloneData;
+use crate::dom::bindings::structuredclone;
 use crate::dom::bindings::trace::RootedTraceableBox;
 use crate::dom::bindings::utils::{GlobalStaticData, WindowProxyHandler};
 use crate::dom::bindings::weakref::DOMTracker;
@@ -39,6 +42,8 @@ use crate::dom::eventtarget::EventTarget;
 use crate::dom::globalscope::GlobalScope;
 use crate::dom::hashchangeevent::HashChangeEvent;
 use crate::dom::history::History;
+use crate::dom::idbfactory::IDBFactory;
+use crate::dom::imagebitmap::{self, CropRect};
 use crate::dom::location::Location;
 use crate::dom::mediaquerylist::{MediaQueryList, MediaQueryListMatchState};
 use crate::dom::mediaquerylistevent::MediaQueryListEvent;
@@ -48,6 +53,7 @@ use crate::dom::node::{document_from_node, from_untrusted_node_address, Node, No
 use crate::dom::performance::Performance;
 use crate::dom::promise::Promise;
 use crate::dom::screen::Screen;
+use crate::dom::selection::Selection;
 use crate::dom::storage::Storage;
 use crate::dom::testrunner::TestRunner;
 use crate::dom::webglrenderingcontext::WebGLCommandSender;
@@ -81,11 +87,11 @@ use ipc_channel::ipc::{channel, IpcSender};
 use ipc_channel::router::ROUTER;
 use js::jsapi::JSAutoRealm;
 use js::jsapi::JSPROP_ENUMERATE;
-use js::jsapi::{GCReason, JS_GC};
+use js::jsapi::{GCReason, JSObject, JS_GC};
 use js::jsval::JSVal;
 use js::jsval::UndefinedValue;
 use js::rust::wrappers::JS_DefineProperty;
-use js::rust::HandleValue;
+use js::rust::{CustomAutoRooterGuard, HandleValue};
 use media::WindowGLContext;
 use msg::constellation_msg::PipelineId;
 use net_traits::image_cache::{ImageCache, ImageResponder, ImageResponse};
@@ -104,7 +110,8 @@ use script_layout_interface::rpc::{
 use script_layout_interface::{PendingImageState, TrustedNodeAddress};
 use script_traits::webdriver_msg::{WebDriverJSError, WebDriverJSResult};
 use script_traits::{ConstellationControlMsg, DocumentState, HistoryEntryReplacement, LoadData};
-use script_traits::{ScriptMsg, ScriptToConstellationChan, ScrollState, TimerEvent, TimerEventId};
+use script_traits::{ScriptMsg, ScriptToConstellationChan, ScrollState, StructuredSerializedData};
+use script_traits::{TimerEvent, TimerEventId};
 use script_traits::{TimerSchedulerMsg, WindowSizeData, WindowSizeType};
 use selectors::attr::CaseSensitivity;
 use servo_geometry::{f32_rect_to_au_rect, MaxRect};
@@ -166,6 +173,7 @@ pub enum ReflowReason {
     IFrameLoadEvent,
     MissingExplicitReflow,
     ElementStateChanged,
+    ResizeObserver,
 }
 
 #[dom_struct]
@@ -722,6 +730,11 @@ impl WindowMethods for Window {
         self.upcast::<GlobalScope>().crypto()
     }
 
+    // https://w3c.github.io/IndexedDB/#dom-windoworworkerglobalscope-indexeddb
+    fn IndexedDB(&self) -> DomRoot<IDBFactory> {
+        self.upcast::<GlobalScope>().indexed_db()
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-frameelement
     fn GetFrameElement(&self) -> Option<DomRoot<Element>> {
         // Steps 1-3.
@@ -921,7 +934,13 @@ impl WindowMethods for Window {
     }
 
     // https://html.spec.whatwg.org/multipage/#dom-window-postmessage
-    fn PostMessage(&self, cx: JSContext, message: HandleValue, origin: DOMString) -> ErrorResult {
+    fn PostMessage(
+        &self,
+        cx: JSContext,
+        message: HandleValue,
+        origin: DOMString,
+        transfer: CustomAutoRooterGuard<Vec<*mut JSObject>>,
+    ) -> ErrorResult {
         let source_global = GlobalScope::incumbent().expect("no incumbent global??");
         let source = source_global.as_window();
 
@@ -936,8 +955,7 @@ impl WindowMethods for Window {
         };
 
         // Step 1-2, 6-8.
-        // TODO(#12717): Should implement the `transfer` argument.
-        let data = StructuredCloneData::write(*cx, message)?;
+        let data = structuredclone::write(cx, message, Some(transfer))?;
 
         // Step 9.
         self.post_message(origin, &*source.window_proxy(), data);
@@ -954,6 +972,11 @@ impl WindowMethods for Window {
         // This method intentionally does nothing
     }
 
+    // https://w3c.github.io/selection-api/#dom-window-getselection
+    fn GetSelection(&self) -> Option<DomRoot<Selection>> {
+        self.Document().GetSelection()
+    }
+
     // check-tidy: no specs after this line
     fn Debug(&self, message: DOMString) {
         debug!("{}", message);
@@ -1207,6 +1230,31 @@ impl WindowMethods for Window {
         fetch::Fetch(&self.upcast(), input, init, comp)
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-createimagebitmap
+    fn CreateImageBitmap(
+        &self,
+        image: ImageBitmapSource,
+        options: &ImageBitmapOptions,
+        comp: InCompartment,
+    ) -> Rc<Promise> {
+        imagebitmap::create_image_bitmap(self.upcast(), image, None, options, comp)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-createimagebitmap
+    fn CreateImageBitmap_(
+        &self,
+        image: ImageBitmapSource,
+        sx: i32,
+        sy: i32,
+        sw: i32,
+        sh: i32,
+        options: &ImageBitmapOptions,
+        comp: InCompartment,
+    ) -> Rc<Promise> {
+        let crop = CropRect { sx, sy, sw, sh };
+        imagebitmap::create_image_bitmap(self.upcast(), image, Some(crop), options, comp)
+    }
+
     fn TestRunner(&self) -> DomRoot<TestRunner> {
         self.test_runner.or_init(|| TestRunner::new(self.upcast()))
     }
@@ -1305,6 +1353,9 @@ impl Window {
         // script.
         // TODO: ensure that this doesn't happen!
 
+        // Stop receiving messages for the ports and channels of this window.
+        self.upcast::<GlobalScope>().remove_web_messaging_infra();
+
         self.current_state.set(WindowState::Zombie);
         *self.js_runtime.borrow_mut() = None;
         self.window_proxy.set(None);
@@ -1550,6 +1601,18 @@ impl Window {
             ScriptThread::note_newly_transitioning_nodes(complete.newly_transitioning_nodes);
         }
 
+        if !complete.new_css_animations.is_empty() {
+            let timeline = self.Document().timeline();
+            let js_runtime = self.js_runtime.borrow();
+            let js_runtime = js_runtime.as_ref().unwrap();
+            for animation in complete.new_css_animations {
+                let node = unsafe { from_untrusted_node_address(js_runtime.rt(), animation.node) };
+                if let Some(element) = node.downcast::<Element>() {
+                    timeline.track_css_animation(element, &animation);
+                }
+            }
+        }
+
         true
     }
 
@@ -1570,6 +1633,10 @@ impl Window {
         self.Document().ensure_safe_to_run_script_or_layout();
         let for_display = reflow_goal == ReflowGoal::Full;
 
+        if for_display {
+            self.Document().update_selection_highlights();
+        }
+
         let mut issued_reflow = false;
         if !for_display || self.Document().needs_reflow() {
             issued_reflow = self.force_reflow(reflow_goal, reason);
@@ -1749,6 +1816,19 @@ impl Window {
         self.layout_rpc.text_index()
     }
 
+    /// Find the index of the character of the text node `node` closest to
+    /// `point`, which is in the coordinates of the initial containing block.
+    pub fn text_index_at_point_query(
+        &self,
+        node: &Node,
+        point: UntypedPoint2D<f32>,
+    ) -> TextIndexResponse {
+        if !self.layout_reflow(QueryMsg::TextIndexAtPointQuery(node.to_opaque(), point)) {
+            return TextIndexResponse(None);
+        }
+        self.layout_rpc.text_index()
+    }
+
     #[allow(unsafe_code)]
     pub fn init_window_proxy(&self, window_proxy: &WindowProxy) {
         assert!(self.window_proxy.get().is_none());
@@ -2242,6 +2322,7 @@ fn debug_reflow_events(id: PipelineId, reflow_goal: &ReflowGoal, reason: &Reflow
             &QueryMsg::OffsetParentQuery(_n) => "\tOffsetParentQuery",
             &QueryMsg::StyleQuery(_n) => "\tStyleQuery",
             &QueryMsg::TextIndexQuery(..) => "\tTextIndexQuery",
+            &QueryMsg::TextIndexAtPointQuery(..) => "\tTextIndexAtPointQuery",
             &QueryMsg::ElementInnerTextQuery(_) => "\tElementInnerTextQuery",
         },
     });
@@ -2267,6 +2348,7 @@ fn debug_reflow_events(id: PipelineId, reflow_goal: &ReflowGoal, reason: &Reflow
         ReflowReason::IFrameLoadEvent => "\tIFrameLoadEvent",
         ReflowReason::MissingExplicitReflow => "\tMissingExplicitReflow",
         ReflowReason::ElementStateChanged => "\tElementStateChanged",
+        ReflowReason::ResizeObserver => "\tResizeObserver",
     });
 
     println!("{}", debug_msg);
@@ -2278,7 +2360,7 @@ impl Window {
         &self,
         target_origin: Option<ImmutableOrigin>,
         source: &WindowProxy,
-        serialize_with_transfer_result: StructuredCloneData,
+        serialize_with_transfer_result: StructuredSerializedData,
     ) {
         let this = Trusted::new(self);
         let source = Trusted::new(source);
@@ -2298,14 +2380,13 @@ impl Window {
             let obj = this.reflector().get_jsobject();
             let _ac = JSAutoRealm::new(*cx, obj.get());
             rooted!(in(*cx) let mut message_clone = UndefinedValue());
-            serialize_with_transfer_result.read(
+            // Step 7.6.
+            let new_ports = structuredclone::read(
                 this.upcast(),
+                serialize_with_transfer_result,
                 message_clone.handle_mut(),
             );
 
-            // Step 7.6.
-            // TODO: MessagePort array.
-
             // Step 7.7.
             // TODO(#12719): Set the other attributes.
             MessageEvent::dispatch_jsval(
@@ -2314,6 +2395,7 @@ impl Window {
                 message_clone.handle(),
                 None,
                 Some(&*source),
+                new_ports,
             );
         });
         // FIXME(nox): Why are errors silenced here?
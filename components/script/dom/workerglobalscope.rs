@@ -5,6 +5,9 @@
 use crate::compartments::InCompartment;
 use crate::dom::bindings::cell::DomRefCell;
 use crate::dom::bindings::codegen::Bindings::FunctionBinding::Function;
+use crate::dom::bindings::codegen::Bindings::ImageBitmapBinding::{
+    ImageBitmapOptions, ImageBitmapSource,
+};
 use crate::dom::bindings::codegen::Bindings::RequestBinding::RequestInit;
 use crate::dom::bindings::codegen::Bindings::WorkerBinding::WorkerType;
 use crate::dom::bindings::codegen::Bindings::WorkerGlobalScopeBinding::WorkerGlobalScopeMethods;
@@ -19,6 +22,8 @@ use crate::dom::bindings::trace::RootedTraceableBox;
 use crate::dom::crypto::Crypto;
 use crate::dom::dedicatedworkerglobalscope::DedicatedWorkerGlobalScope;
 use crate::dom::globalscope::GlobalScope;
+use crate::dom::idbfactory::IDBFactory;
+use crate::dom::imagebitmap::{self, CropRect};
 use crate::dom::performance::Performance;
 use crate::dom::promise::Promise;
 use crate::dom::serviceworkerglobalscope::ServiceWorkerGlobalScope;
@@ -29,10 +34,12 @@ use crate::fetch;
 use crate::script_runtime::JSContext;
 use crate::script_runtime::{get_reports, CommonScriptMsg, Runtime, ScriptChan, ScriptPort};
 use crate::task::TaskCanceller;
+use crate::task_source::database_access::DatabaseAccessTaskSource;
 use crate::task_source::dom_manipulation::DOMManipulationTaskSource;
 use crate::task_source::file_reading::FileReadingTaskSource;
 use crate::task_source::networking::NetworkingTaskSource;
 use crate::task_source::performance_timeline::PerformanceTimelineTaskSource;
+use crate::task_source::port_message::PortMessageQueue;
 use crate::task_source::remote_event::RemoteEventTaskSource;
 use crate::task_source::websocket::WebsocketTaskSource;
 use crate::timers::{IsInterval, TimerCallback};
@@ -282,6 +289,11 @@ impl WorkerGlobalScopeMethods for WorkerGlobalScope {
         self.upcast::<GlobalScope>().crypto()
     }
 
+    // https://w3c.github.io/IndexedDB/#dom-windoworworkerglobalscope-indexeddb
+    fn IndexedDB(&self) -> DomRoot<IDBFactory> {
+        self.upcast::<GlobalScope>().indexed_db()
+    }
+
     // https://html.spec.whatwg.org/multipage/#dom-windowbase64-btoa
     fn Btoa(&self, btoa: DOMString) -> Fallible<DOMString> {
         base64_btoa(btoa)
@@ -378,6 +390,31 @@ impl WorkerGlobalScopeMethods for WorkerGlobalScope {
         fetch::Fetch(self.upcast(), input, init, comp)
     }
 
+    // https://html.spec.whatwg.org/multipage/#dom-createimagebitmap
+    fn CreateImageBitmap(
+        &self,
+        image: ImageBitmapSource,
+        options: &ImageBitmapOptions,
+        comp: InCompartment,
+    ) -> Rc<Promise> {
+        imagebitmap::create_image_bitmap(self.upcast(), image, None, options, comp)
+    }
+
+    // https://html.spec.whatwg.org/multipage/#dom-createimagebitmap
+    fn CreateImageBitmap_(
+        &self,
+        image: ImageBitmapSource,
+        sx: i32,
+        sy: i32,
+        sw: i32,
+        sh: i32,
+        options: &ImageBitmapOptions,
+        comp: InCompartment,
+    ) -> Rc<Promise> {
+        let crop = CropRect { sx, sy, sw, sh };
+        imagebitmap::create_image_bitmap(self.upcast(), image, Some(crop), options, comp)
+    }
+
     // https://w3c.github.io/hr-time/#the-performance-attribute
     fn Performance(&self) -> DomRoot<Performance> {
         self.performance.or_init(|| {
@@ -441,6 +478,10 @@ impl WorkerGlobalScope {
         }
     }
 
+    pub fn database_access_task_source(&self) -> DatabaseAccessTaskSource {
+        DatabaseAccessTaskSource(self.script_chan(), self.pipeline_id())
+    }
+
     pub fn dom_manipulation_task_source(&self) -> DOMManipulationTaskSource {
         DOMManipulationTaskSource(self.script_chan(), self.pipeline_id())
     }
@@ -457,6 +498,10 @@ impl WorkerGlobalScope {
         PerformanceTimelineTaskSource(self.script_chan(), self.pipeline_id())
     }
 
+    pub fn port_message_queue(&self) -> PortMessageQueue {
+        PortMessageQueue(self.script_chan(), self.pipeline_id())
+    }
+
     pub fn remote_event_task_source(&self) -> RemoteEventTaskSource {
         RemoteEventTaskSource(self.script_chan(), self.pipeline_id())
     }
@@ -0,0 +1,550 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::QueuingStrategyBinding::QueuingStrategy;
+use crate::dom::bindings::codegen::Bindings::UnderlyingSinkBinding::UnderlyingSink;
+use crate::dom::bindings::codegen::Bindings::WritableStreamBinding;
+use crate::dom::bindings::codegen::Bindings::WritableStreamBinding::WritableStreamMethods;
+use crate::dom::bindings::conversions::ConversionResult;
+use crate::dom::bindings::error::{Error, Fallible};
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot, MutNullableDom};
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::promise::Promise;
+use crate::dom::promisenativehandler::Callback;
+use crate::dom::writablestreamdefaultcontroller::WritableStreamDefaultController;
+use crate::dom::writablestreamdefaultwriter::WritableStreamDefaultWriter;
+use crate::script_runtime::JSContext as SafeJSContext;
+use crate::streams::{extract_high_water_mark, promise_rejected_with_error};
+use crate::streams::{promise_resolved_with_undefined, upon_settlement};
+use dom_struct::dom_struct;
+use js::jsapi::{Heap, JSContext, JSObject};
+use js::jsval::{JSVal, ObjectValue, UndefinedValue};
+use js::rust::{HandleValue, MutableHandleValue};
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::ptr;
+use std::rc::Rc;
+
+/// <https://streams.spec.whatwg.org/#writablestream-state>
+#[derive(Clone, Copy, Debug, JSTraceable, MallocSizeOf, PartialEq)]
+pub enum WritableStreamState {
+    Writable,
+    Closed,
+    Erroring,
+    Errored,
+}
+
+/// <https://streams.spec.whatwg.org/#pending-abort-request>
+#[derive(JSTraceable)]
+struct PendingAbortRequest {
+    promise: Rc<Promise>,
+    reason: Box<Heap<JSVal>>,
+    was_already_erroring: bool,
+}
+
+/// <https://streams.spec.whatwg.org/#ws-class>
+#[dom_struct]
+pub struct WritableStream {
+    reflector_: Reflector,
+    state: Cell<WritableStreamState>,
+    #[ignore_malloc_size_of = "Defined in mozjs"]
+    stored_error: Heap<JSVal>,
+    writer: MutNullableDom<WritableStreamDefaultWriter>,
+    controller: MutNullableDom<WritableStreamDefaultController>,
+    backpressure: Cell<bool>,
+    #[ignore_malloc_size_of = "Rc"]
+    close_request: DomRefCell<Option<Rc<Promise>>>,
+    #[ignore_malloc_size_of = "Rc"]
+    in_flight_write_request: DomRefCell<Option<Rc<Promise>>>,
+    #[ignore_malloc_size_of = "Rc"]
+    in_flight_close_request: DomRefCell<Option<Rc<Promise>>>,
+    #[ignore_malloc_size_of = "Rc"]
+    pending_abort_request: DomRefCell<Option<PendingAbortRequest>>,
+    #[ignore_malloc_size_of = "Rc"]
+    write_requests: DomRefCell<VecDeque<Rc<Promise>>>,
+}
+
+impl WritableStream {
+    fn new_inherited() -> WritableStream {
+        WritableStream {
+            reflector_: Reflector::new(),
+            state: Cell::new(WritableStreamState::Writable),
+            stored_error: Heap::default(),
+            writer: Default::default(),
+            controller: Default::default(),
+            backpressure: Cell::new(false),
+            close_request: DomRefCell::new(None),
+            in_flight_write_request: DomRefCell::new(None),
+            in_flight_close_request: DomRefCell::new(None),
+            pending_abort_request: DomRefCell::new(None),
+            write_requests: DomRefCell::new(VecDeque::new()),
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#initialize-writable-stream>
+    pub fn new(global: &GlobalScope) -> DomRoot<WritableStream> {
+        reflect_dom_object(
+            Box::new(WritableStream::new_inherited()),
+            global,
+            WritableStreamBinding::Wrap,
+        )
+    }
+
+    // https://streams.spec.whatwg.org/#ws-constructor
+    #[allow(unsafe_code)]
+    pub fn Constructor(
+        cx: SafeJSContext,
+        global: &GlobalScope,
+        underlying_sink: Option<*mut JSObject>,
+        strategy: &QueuingStrategy,
+    ) -> Fallible<DomRoot<WritableStream>> {
+        // Steps 1-2.
+        rooted!(in(*cx) let sink_object = underlying_sink.unwrap_or(ptr::null_mut()));
+        rooted!(in(*cx) let sink_value = if sink_object.is_null() {
+            UndefinedValue()
+        } else {
+            ObjectValue(sink_object.get())
+        });
+        let sink = match unsafe { UnderlyingSink::new(cx, sink_value.handle()) } {
+            Ok(ConversionResult::Success(sink)) => sink,
+            Ok(ConversionResult::Failure(error)) => return Err(Error::Type(error.into_owned())),
+            Err(()) => return Err(Error::JSFailed),
+        };
+
+        // Step 3.
+        if !sink.type_.get().is_undefined() {
+            return Err(Error::Range(
+                "Invalid type for the underlying sink".to_owned(),
+            ));
+        }
+
+        // Step 4.
+        let stream = WritableStream::new(global);
+
+        // Steps 5-6.
+        let size = strategy.size.clone();
+        let high_water_mark = extract_high_water_mark(strategy, 1.)?;
+
+        // Step 7.
+        WritableStreamDefaultController::set_up_from_underlying_sink(
+            &stream,
+            sink_object.handle(),
+            &sink,
+            high_water_mark,
+            size,
+        )?;
+        Ok(stream)
+    }
+
+    pub fn state(&self) -> WritableStreamState {
+        self.state.get()
+    }
+
+    pub fn is_writable(&self) -> bool {
+        self.state.get() == WritableStreamState::Writable
+    }
+
+    pub fn stored_error(&self, mut rval: MutableHandleValue) {
+        rval.set(self.stored_error.get());
+    }
+
+    pub fn backpressure(&self) -> bool {
+        self.backpressure.get()
+    }
+
+    pub fn set_controller(&self, controller: &WritableStreamDefaultController) {
+        self.controller.set(Some(controller));
+    }
+
+    pub fn controller(&self) -> DomRoot<WritableStreamDefaultController> {
+        self.controller
+            .get()
+            .expect("A writable stream is always set up with a controller")
+    }
+
+    pub fn writer(&self) -> Option<DomRoot<WritableStreamDefaultWriter>> {
+        self.writer.get()
+    }
+
+    pub fn set_writer(&self, writer: Option<&WritableStreamDefaultWriter>) {
+        self.writer.set(writer);
+    }
+
+    /// <https://streams.spec.whatwg.org/#is-writable-stream-locked>
+    pub fn is_locked(&self) -> bool {
+        self.writer.get().is_some()
+    }
+
+    /// <https://streams.spec.whatwg.org/#acquire-writable-stream-default-writer>
+    pub fn acquire_writer(&self) -> Fallible<DomRoot<WritableStreamDefaultWriter>> {
+        WritableStreamDefaultWriter::new(&self.global(), self)
+    }
+
+    /// Whether the stream is closed or will be once the queued writes are
+    /// done, as used to propagate closing backward through a pipe.
+    pub fn is_closing_or_closed(&self) -> bool {
+        self.close_queued_or_in_flight() || self.state.get() == WritableStreamState::Closed
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-abort>
+    pub fn abort(&self, reason: HandleValue) -> Rc<Promise> {
+        let global = self.global();
+
+        // Steps 1-3.
+        match self.state.get() {
+            WritableStreamState::Closed | WritableStreamState::Errored => {
+                return promise_resolved_with_undefined(&global);
+            },
+            _ => {},
+        }
+
+        // Step 4.
+        if let Some(ref request) = *self.pending_abort_request.borrow() {
+            return request.promise.clone();
+        }
+
+        // Steps 5-6.
+        let cx = global.get_cx();
+        rooted!(in(*cx) let mut reason = reason.get());
+        let was_already_erroring = self.state.get() == WritableStreamState::Erroring;
+        if was_already_erroring {
+            reason.set(UndefinedValue());
+        }
+
+        // Steps 7-8.
+        let promise = Promise::new(&global);
+        *self.pending_abort_request.borrow_mut() = Some(PendingAbortRequest {
+            promise: promise.clone(),
+            reason: Heap::boxed(reason.get()),
+            was_already_erroring,
+        });
+
+        // Step 9.
+        if !was_already_erroring {
+            self.start_erroring(reason.handle());
+        }
+
+        // Step 10.
+        promise
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-close>
+    pub fn close(&self) -> Rc<Promise> {
+        let global = self.global();
+
+        // Step 2.
+        match self.state.get() {
+            WritableStreamState::Closed | WritableStreamState::Errored => {
+                return promise_rejected_with_error(
+                    &global,
+                    Error::Type("The stream is already closed".to_owned()),
+                );
+            },
+            _ => {},
+        }
+
+        // Steps 4-5.
+        let promise = Promise::new(&global);
+        *self.close_request.borrow_mut() = Some(promise.clone());
+
+        // Step 6.
+        if let Some(writer) = self.writer.get() {
+            if self.backpressure.get() && self.state.get() == WritableStreamState::Writable {
+                writer.resolve_ready_promise();
+            }
+        }
+
+        // Step 7.
+        self.controller().close();
+
+        // Step 8.
+        promise
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-add-write-request>
+    pub fn add_write_request(&self) -> Rc<Promise> {
+        assert!(self.is_locked());
+        assert!(self.is_writable());
+        let promise = Promise::new(&self.global());
+        self.write_requests.borrow_mut().push_back(promise.clone());
+        promise
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-close-queued-or-in-flight>
+    pub fn close_queued_or_in_flight(&self) -> bool {
+        self.close_request.borrow().is_some() || self.in_flight_close_request.borrow().is_some()
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-deal-with-rejection>
+    pub fn deal_with_rejection(&self, error: HandleValue) {
+        if self.is_writable() {
+            self.start_erroring(error);
+            return;
+        }
+        assert_eq!(self.state.get(), WritableStreamState::Erroring);
+        self.finish_erroring();
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-start-erroring>
+    pub fn start_erroring(&self, reason: HandleValue) {
+        // Steps 1-4.
+        assert!(self.is_writable());
+        self.state.set(WritableStreamState::Erroring);
+        self.stored_error.set(reason.get());
+
+        // Steps 5-6.
+        if let Some(writer) = self.writer.get() {
+            writer.ensure_ready_promise_rejected(reason);
+        }
+
+        // Step 7.
+        if !self.has_operation_marked_in_flight() && self.controller().started() {
+            self.finish_erroring();
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-finish-erroring>
+    #[allow(unrooted_must_root)]
+    pub fn finish_erroring(&self) {
+        let global = self.global();
+        let cx = global.get_cx();
+
+        // Steps 1-3.
+        assert_eq!(self.state.get(), WritableStreamState::Erroring);
+        assert!(!self.has_operation_marked_in_flight());
+        self.state.set(WritableStreamState::Errored);
+
+        // Step 4.
+        self.controller().error_steps();
+
+        // Steps 5-7.
+        rooted!(in(*cx) let mut stored_error = UndefinedValue());
+        self.stored_error(stored_error.handle_mut());
+        let write_requests: Vec<_> = self.write_requests.borrow_mut().drain(..).collect();
+        for request in write_requests {
+            request.reject(cx, stored_error.handle());
+        }
+
+        // Steps 8-10.
+        let abort_request = match self.pending_abort_request.borrow_mut().take() {
+            Some(abort_request) => abort_request,
+            None => return self.reject_close_and_closed_promise_if_needed(),
+        };
+
+        // Step 11.
+        if abort_request.was_already_erroring {
+            abort_request.promise.reject(cx, stored_error.handle());
+            self.reject_close_and_closed_promise_if_needed();
+            return;
+        }
+
+        // Steps 12-14.
+        rooted!(in(*cx) let reason = abort_request.reason.get());
+        let abort_promise = self.controller().abort_steps(reason.handle());
+        upon_settlement(
+            &global,
+            &abort_promise,
+            Box::new(WritableStreamReaction {
+                stream: Dom::from_ref(self),
+                promise: abort_request.promise.clone(),
+                step: WritableStreamStep::AbortFulfilled,
+            }),
+            Box::new(WritableStreamReaction {
+                stream: Dom::from_ref(self),
+                promise: abort_request.promise,
+                step: WritableStreamStep::AbortRejected,
+            }),
+        );
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-finish-in-flight-write>
+    pub fn finish_in_flight_write(&self) {
+        let request = self
+            .in_flight_write_request
+            .borrow_mut()
+            .take()
+            .expect("Finishing a write that is not in flight");
+        request.resolve_native(&());
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-finish-in-flight-write-with-error>
+    pub fn finish_in_flight_write_with_error(&self, error: HandleValue) {
+        let request = self
+            .in_flight_write_request
+            .borrow_mut()
+            .take()
+            .expect("Finishing a write that is not in flight");
+        request.reject(self.global().get_cx(), error);
+        self.deal_with_rejection(error);
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-finish-in-flight-close>
+    pub fn finish_in_flight_close(&self) {
+        // Steps 1-2.
+        let request = self
+            .in_flight_close_request
+            .borrow_mut()
+            .take()
+            .expect("Finishing a close that is not in flight");
+        request.resolve_native(&());
+
+        // Step 4.
+        if self.state.get() == WritableStreamState::Erroring {
+            self.stored_error.set(UndefinedValue());
+            if let Some(abort_request) = self.pending_abort_request.borrow_mut().take() {
+                abort_request.promise.resolve_native(&());
+            }
+        }
+
+        // Steps 5-6.
+        self.state.set(WritableStreamState::Closed);
+        if let Some(writer) = self.writer.get() {
+            writer.resolve_closed_promise();
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-finish-in-flight-close-with-error>
+    pub fn finish_in_flight_close_with_error(&self, error: HandleValue) {
+        let cx = self.global().get_cx();
+
+        // Steps 1-2.
+        let request = self
+            .in_flight_close_request
+            .borrow_mut()
+            .take()
+            .expect("Finishing a close that is not in flight");
+        request.reject(cx, error);
+
+        // Step 4.
+        if let Some(abort_request) = self.pending_abort_request.borrow_mut().take() {
+            abort_request.promise.reject(cx, error);
+        }
+
+        // Step 5.
+        self.deal_with_rejection(error);
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-has-operation-marked-in-flight>
+    pub fn has_operation_marked_in_flight(&self) -> bool {
+        self.in_flight_write_request.borrow().is_some() ||
+            self.in_flight_close_request.borrow().is_some()
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-mark-close-request-in-flight>
+    pub fn mark_close_request_in_flight(&self) {
+        let request = self.close_request.borrow_mut().take();
+        *self.in_flight_close_request.borrow_mut() = request;
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-mark-first-write-request-in-flight>
+    pub fn mark_first_write_request_in_flight(&self) {
+        let request = self.write_requests.borrow_mut().pop_front();
+        *self.in_flight_write_request.borrow_mut() = request;
+    }
+
+    pub fn has_in_flight_write_request(&self) -> bool {
+        self.in_flight_write_request.borrow().is_some()
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-reject-close-and-closed-promise-if-needed>
+    fn reject_close_and_closed_promise_if_needed(&self) {
+        let cx = self.global().get_cx();
+        rooted!(in(*cx) let mut stored_error = UndefinedValue());
+        self.stored_error(stored_error.handle_mut());
+
+        // Step 3.
+        if let Some(request) = self.close_request.borrow_mut().take() {
+            request.reject(cx, stored_error.handle());
+        }
+
+        // Step 5.
+        if let Some(writer) = self.writer.get() {
+            writer.reject_closed_promise(stored_error.handle());
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-update-backpressure>
+    pub fn update_backpressure(&self, backpressure: bool) {
+        if let Some(writer) = self.writer.get() {
+            if backpressure != self.backpressure.get() {
+                if backpressure {
+                    writer.reset_ready_promise();
+                } else {
+                    writer.resolve_ready_promise();
+                }
+            }
+        }
+        self.backpressure.set(backpressure);
+    }
+}
+
+#[derive(Clone, Copy, JSTraceable, MallocSizeOf)]
+enum WritableStreamStep {
+    AbortFulfilled,
+    AbortRejected,
+}
+
+/// The steps run once the abort algorithm of the sink settles.
+#[derive(JSTraceable, MallocSizeOf)]
+#[must_root]
+struct WritableStreamReaction {
+    stream: Dom<WritableStream>,
+    #[ignore_malloc_size_of = "Rc"]
+    promise: Rc<Promise>,
+    step: WritableStreamStep,
+}
+
+impl Callback for WritableStreamReaction {
+    fn callback(&self, _cx: *mut JSContext, v: HandleValue) {
+        match self.step {
+            WritableStreamStep::AbortFulfilled => self.promise.resolve_native(&()),
+            WritableStreamStep::AbortRejected => {
+                self.promise.reject(self.stream.global().get_cx(), v)
+            },
+        }
+        self.stream.reject_close_and_closed_promise_if_needed();
+    }
+}
+
+impl WritableStreamMethods for WritableStream {
+    // https://streams.spec.whatwg.org/#ws-locked
+    fn Locked(&self) -> bool {
+        self.is_locked()
+    }
+
+    // https://streams.spec.whatwg.org/#ws-abort
+    fn Abort(&self, _cx: SafeJSContext, reason: HandleValue) -> Rc<Promise> {
+        if self.is_locked() {
+            return promise_rejected_with_error(
+                &self.global(),
+                Error::Type("Cannot abort a locked stream".to_owned()),
+            );
+        }
+        self.abort(reason)
+    }
+
+    // https://streams.spec.whatwg.org/#ws-close
+    fn Close(&self) -> Rc<Promise> {
+        if self.is_locked() {
+            return promise_rejected_with_error(
+                &self.global(),
+                Error::Type("Cannot close a locked stream".to_owned()),
+            );
+        }
+        if self.close_queued_or_in_flight() {
+            return promise_rejected_with_error(
+                &self.global(),
+                Error::Type("The stream is already closing".to_owned()),
+            );
+        }
+        self.close()
+    }
+
+    // https://streams.spec.whatwg.org/#ws-get-writer
+    fn GetWriter(&self) -> Fallible<DomRoot<WritableStreamDefaultWriter>> {
+        self.acquire_writer()
+    }
+}
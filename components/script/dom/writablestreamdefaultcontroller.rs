@@ -0,0 +1,470 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::callback::ExceptionHandling;
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::QueuingStrategyBinding::QueuingStrategySize;
+use crate::dom::bindings::codegen::Bindings::UnderlyingSinkBinding::{
+    UnderlyingSink, UnderlyingSinkAbortCallback, UnderlyingSinkCloseCallback,
+    UnderlyingSinkWriteCallback,
+};
+use crate::dom::bindings::codegen::Bindings::WritableStreamDefaultControllerBinding;
+use crate::dom::bindings::codegen::Bindings::WritableStreamDefaultControllerBinding::WritableStreamDefaultControllerMethods;
+use crate::dom::bindings::error::{ErrorResult, Fallible};
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{Dom, DomRoot, MutNullableDom};
+use crate::dom::promise::Promise;
+use crate::dom::promisenativehandler::Callback;
+use crate::dom::transformstream::TransformStream;
+use crate::dom::writablestream::WritableStream;
+use crate::script_runtime::JSContext as SafeJSContext;
+use crate::streams::{chunk_size, error_to_jsval, promise_from_callback_result};
+use crate::streams::{promise_resolved_with, promise_resolved_with_undefined, upon_settlement};
+use crate::streams::{QueueWithSizes, QueuedValue};
+use dom_struct::dom_struct;
+use js::jsapi::{Heap, JSContext, JSObject};
+use js::jsval::UndefinedValue;
+use js::rust::{HandleObject, HandleValue};
+use std::cell::Cell;
+use std::rc::Rc;
+
+/// The algorithms a writable stream writes its chunks with and closes and
+/// aborts its sink through.
+#[allow_unrooted_in_rc]
+#[derive(JSTraceable)]
+#[must_root]
+pub enum UnderlyingSinkType {
+    /// A sink passed by script to the `WritableStream` constructor, and the
+    /// object its methods are called on.
+    Js(
+        Box<Heap<*mut JSObject>>,
+        Option<Rc<UnderlyingSinkWriteCallback>>,
+        Option<Rc<UnderlyingSinkCloseCallback>>,
+        Option<Rc<UnderlyingSinkAbortCallback>>,
+    ),
+    /// The writable side of a transform stream.
+    Transform(Dom<TransformStream>),
+}
+
+/// <https://streams.spec.whatwg.org/#ws-default-controller-class>
+#[dom_struct]
+pub struct WritableStreamDefaultController {
+    reflector_: Reflector,
+    stream: MutNullableDom<WritableStream>,
+    /// <https://streams.spec.whatwg.org/#writablestreamdefaultcontroller-writealgorithm>,
+    /// <https://streams.spec.whatwg.org/#writablestreamdefaultcontroller-closealgorithm>
+    /// and <https://streams.spec.whatwg.org/#writablestreamdefaultcontroller-abortalgorithm>,
+    /// `None` once the algorithms are cleared.
+    #[ignore_malloc_size_of = "Rc and mozjs"]
+    sink: DomRefCell<Option<Rc<UnderlyingSinkType>>>,
+    queue: DomRefCell<QueueWithSizes>,
+    started: Cell<bool>,
+    strategy_hwm: f64,
+    #[ignore_malloc_size_of = "Rc"]
+    strategy_size: DomRefCell<Option<Rc<QueuingStrategySize>>>,
+}
+
+impl WritableStreamDefaultController {
+    #[allow(unrooted_must_root)]
+    fn new_inherited(
+        sink: UnderlyingSinkType,
+        strategy_hwm: f64,
+        strategy_size: Option<Rc<QueuingStrategySize>>,
+    ) -> WritableStreamDefaultController {
+        WritableStreamDefaultController {
+            reflector_: Reflector::new(),
+            stream: Default::default(),
+            sink: DomRefCell::new(Some(Rc::new(sink))),
+            queue: Default::default(),
+            started: Cell::new(false),
+            strategy_hwm,
+            strategy_size: DomRefCell::new(strategy_size),
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#set-up-writable-stream-default-controller>
+    ///
+    /// `start` runs the start algorithm and returns the promise the stream
+    /// waits for before writing its first chunk.
+    #[allow(unrooted_must_root)]
+    pub fn set_up<F>(
+        stream: &WritableStream,
+        sink: UnderlyingSinkType,
+        high_water_mark: f64,
+        size: Option<Rc<QueuingStrategySize>>,
+        start: F,
+    ) -> ErrorResult
+    where
+        F: FnOnce(&WritableStreamDefaultController) -> Fallible<Rc<Promise>>,
+    {
+        let global = stream.global();
+
+        // Steps 1-14.
+        let controller = reflect_dom_object(
+            Box::new(WritableStreamDefaultController::new_inherited(
+                sink,
+                high_water_mark,
+                size,
+            )),
+            &*global,
+            WritableStreamDefaultControllerBinding::Wrap,
+        );
+        controller.stream.set(Some(stream));
+        stream.set_controller(&controller);
+
+        // Steps 15-16.
+        stream.update_backpressure(controller.get_backpressure());
+
+        // Steps 17-20.
+        let start_promise = start(&controller)?;
+        upon_settlement(
+            &global,
+            &start_promise,
+            Box::new(WritableControllerReaction {
+                controller: Dom::from_ref(&*controller),
+                step: WritableControllerStep::StartFulfilled,
+            }),
+            Box::new(WritableControllerReaction {
+                controller: Dom::from_ref(&*controller),
+                step: WritableControllerStep::StartRejected,
+            }),
+        );
+        Ok(())
+    }
+
+    /// <https://streams.spec.whatwg.org/#set-up-writable-stream-default-controller-from-underlying-sink>
+    #[allow(unrooted_must_root)]
+    pub fn set_up_from_underlying_sink(
+        stream: &WritableStream,
+        sink_object: HandleObject,
+        sink: &UnderlyingSink,
+        high_water_mark: f64,
+        size: Option<Rc<QueuingStrategySize>>,
+    ) -> ErrorResult {
+        let global = stream.global();
+
+        // Steps 2-8.
+        let algorithms = UnderlyingSinkType::Js(
+            Heap::boxed(sink_object.get()),
+            sink.write.clone(),
+            sink.close.clone(),
+            sink.abort.clone(),
+        );
+
+        // Step 9.
+        WritableStreamDefaultController::set_up(
+            stream,
+            algorithms,
+            high_water_mark,
+            size,
+            |controller| {
+                let start_result = match sink.start {
+                    Some(ref start) => {
+                        start.Call_(&sink_object, controller, ExceptionHandling::Rethrow)?
+                    },
+                    None => UndefinedValue(),
+                };
+                let cx = global.get_cx();
+                rooted!(in(*cx) let start_result = start_result);
+                Ok(promise_resolved_with(&global, start_result.handle()))
+            },
+        )
+    }
+
+    fn stream(&self) -> DomRoot<WritableStream> {
+        self.stream
+            .get()
+            .expect("A controller is always set up with a stream")
+    }
+
+    pub fn started(&self) -> bool {
+        self.started.get()
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-controller-clear-algorithms>
+    fn clear_algorithms(&self) {
+        *self.sink.borrow_mut() = None;
+        *self.strategy_size.borrow_mut() = None;
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-controller-close>
+    pub fn close(&self) {
+        self.queue
+            .borrow_mut()
+            .enqueue_value_with_size(QueuedValue::CloseSentinel, 0.)
+            .expect("The close sentinel has a valid size");
+        self.advance_queue_if_needed();
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-controller-get-chunk-size>
+    pub fn get_chunk_size(&self, chunk: HandleValue) -> f64 {
+        let strategy_size = self.strategy_size.borrow().clone();
+        let result = chunk_size(&strategy_size, chunk);
+        match result {
+            Ok(size) => size,
+            Err(error) => {
+                let global = self.global();
+                rooted!(in(*global.get_cx()) let mut reason = UndefinedValue());
+                error_to_jsval(&global, error, reason.handle_mut());
+                self.error_if_needed(reason.handle());
+                1.
+            },
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-controller-get-desired-size>
+    pub fn get_desired_size(&self) -> f64 {
+        self.strategy_hwm - self.queue.borrow().total_size()
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-controller-get-backpressure>
+    fn get_backpressure(&self) -> bool {
+        self.get_desired_size() <= 0.
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-controller-write>
+    pub fn write(&self, chunk: HandleValue, chunk_size: f64) {
+        let stream = self.stream();
+
+        // Steps 1-2.
+        let enqueued = self
+            .queue
+            .borrow_mut()
+            .enqueue_value_with_size(QueuedValue::Chunk(Heap::boxed(chunk.get())), chunk_size);
+        if let Err(error) = enqueued {
+            let global = self.global();
+            rooted!(in(*global.get_cx()) let mut reason = UndefinedValue());
+            error_to_jsval(&global, error, reason.handle_mut());
+            self.error_if_needed(reason.handle());
+            return;
+        }
+
+        // Step 3.
+        if !stream.close_queued_or_in_flight() && stream.is_writable() {
+            stream.update_backpressure(self.get_backpressure());
+        }
+
+        // Step 4.
+        self.advance_queue_if_needed();
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-controller-advance-queue-if-needed>
+    fn advance_queue_if_needed(&self) {
+        let stream = self.stream();
+
+        // Steps 2-3.
+        if !self.started.get() || stream.has_in_flight_write_request() {
+            return;
+        }
+
+        // Step 6.
+        if !stream.is_writable() {
+            stream.finish_erroring();
+            return;
+        }
+
+        // Step 7.
+        if self.queue.borrow().is_empty() {
+            return;
+        }
+
+        // Steps 8-9.
+        if self.queue.borrow().peek_is_close_sentinel() {
+            self.process_close();
+        } else {
+            let cx = self.global().get_cx();
+            rooted!(in(*cx) let mut chunk = UndefinedValue());
+            self.queue.borrow().peek_chunk(chunk.handle_mut());
+            self.process_write(chunk.handle());
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-controller-process-close>
+    #[allow(unrooted_must_root)]
+    fn process_close(&self) {
+        let stream = self.stream();
+        let global = self.global();
+
+        // Steps 2-4.
+        stream.mark_close_request_in_flight();
+        self.queue.borrow_mut().dequeue_value();
+        assert!(self.queue.borrow().is_empty());
+
+        // Step 5.
+        let sink = self.sink.borrow_mut().take();
+        let sink_close_promise = match sink.as_ref().map(|sink| &**sink) {
+            Some(&UnderlyingSinkType::Js(ref this, _, Some(ref close), _)) => {
+                rooted!(in(*global.get_cx()) let this = this.get());
+                let result = close.Call_(&this.handle(), ExceptionHandling::Rethrow);
+                promise_from_callback_result(&global, result)
+            },
+            Some(&UnderlyingSinkType::Transform(ref stream)) => stream.sink_close(),
+            _ => promise_resolved_with_undefined(&global),
+        };
+
+        // Step 6.
+        self.clear_algorithms();
+
+        // Step 7.
+        upon_settlement(
+            &global,
+            &sink_close_promise,
+            Box::new(WritableControllerReaction {
+                controller: Dom::from_ref(self),
+                step: WritableControllerStep::CloseFulfilled,
+            }),
+            Box::new(WritableControllerReaction {
+                controller: Dom::from_ref(self),
+                step: WritableControllerStep::CloseRejected,
+            }),
+        );
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-controller-process-write>
+    #[allow(unrooted_must_root)]
+    fn process_write(&self, chunk: HandleValue) {
+        let stream = self.stream();
+        let global = self.global();
+
+        // Step 2.
+        stream.mark_first_write_request_in_flight();
+
+        // Step 3. The write method may error the controller, which clears
+        // the algorithms, so it is called without borrowing them.
+        let sink = self.sink.borrow().clone();
+        let sink_write_promise = match sink.as_ref().map(|sink| &**sink) {
+            Some(&UnderlyingSinkType::Js(ref this, Some(ref write), _, _)) => {
+                rooted!(in(*global.get_cx()) let this = this.get());
+                let result = write.Call_(&this.handle(), chunk, self, ExceptionHandling::Rethrow);
+                promise_from_callback_result(&global, result)
+            },
+            Some(&UnderlyingSinkType::Transform(ref stream)) => stream.sink_write(chunk),
+            _ => promise_resolved_with_undefined(&global),
+        };
+
+        // Step 4.
+        upon_settlement(
+            &global,
+            &sink_write_promise,
+            Box::new(WritableControllerReaction {
+                controller: Dom::from_ref(self),
+                step: WritableControllerStep::WriteFulfilled,
+            }),
+            Box::new(WritableControllerReaction {
+                controller: Dom::from_ref(self),
+                step: WritableControllerStep::WriteRejected,
+            }),
+        );
+    }
+
+    fn write_fulfilled(&self) {
+        let stream = self.stream();
+
+        // Steps 4.1-3.
+        stream.finish_in_flight_write();
+        self.queue.borrow_mut().dequeue_value();
+
+        // Step 4.5.
+        if !stream.close_queued_or_in_flight() && stream.is_writable() {
+            stream.update_backpressure(self.get_backpressure());
+        }
+
+        // Step 4.6.
+        self.advance_queue_if_needed();
+    }
+
+    fn write_rejected(&self, reason: HandleValue) {
+        let stream = self.stream();
+        if stream.is_writable() {
+            self.clear_algorithms();
+        }
+        stream.finish_in_flight_write_with_error(reason);
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-controller-error-if-needed>
+    pub fn error_if_needed(&self, error: HandleValue) {
+        if self.stream().is_writable() {
+            self.error(error);
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-controller-error>
+    pub fn error(&self, error: HandleValue) {
+        self.clear_algorithms();
+        self.stream().start_erroring(error);
+    }
+
+    /// <https://streams.spec.whatwg.org/#ws-default-controller-private-abort>
+    pub fn abort_steps(&self, reason: HandleValue) -> Rc<Promise> {
+        let global = self.global();
+        let sink = self.sink.borrow_mut().take();
+        let result = match sink.as_ref().map(|sink| &**sink) {
+            Some(&UnderlyingSinkType::Js(ref this, _, _, Some(ref abort))) => {
+                rooted!(in(*global.get_cx()) let this = this.get());
+                let result = abort.Call_(&this.handle(), reason, ExceptionHandling::Rethrow);
+                promise_from_callback_result(&global, result)
+            },
+            Some(&UnderlyingSinkType::Transform(ref stream)) => stream.sink_abort(reason),
+            _ => promise_resolved_with_undefined(&global),
+        };
+        self.clear_algorithms();
+        result
+    }
+
+    /// <https://streams.spec.whatwg.org/#ws-default-controller-private-error>
+    pub fn error_steps(&self) {
+        self.queue.borrow_mut().reset();
+    }
+}
+
+#[derive(Clone, Copy, JSTraceable, MallocSizeOf)]
+enum WritableControllerStep {
+    StartFulfilled,
+    StartRejected,
+    WriteFulfilled,
+    WriteRejected,
+    CloseFulfilled,
+    CloseRejected,
+}
+
+/// The steps run when the promise returned by the start, write or close
+/// algorithm of the sink settles.
+#[derive(JSTraceable, MallocSizeOf)]
+#[must_root]
+struct WritableControllerReaction {
+    controller: Dom<WritableStreamDefaultController>,
+    step: WritableControllerStep,
+}
+
+impl Callback for WritableControllerReaction {
+    fn callback(&self, _cx: *mut JSContext, v: HandleValue) {
+        let controller = &self.controller;
+        match self.step {
+            WritableControllerStep::StartFulfilled => {
+                controller.started.set(true);
+                controller.advance_queue_if_needed();
+            },
+            WritableControllerStep::StartRejected => {
+                controller.started.set(true);
+                controller.stream().deal_with_rejection(v);
+            },
+            WritableControllerStep::WriteFulfilled => controller.write_fulfilled(),
+            WritableControllerStep::WriteRejected => controller.write_rejected(v),
+            WritableControllerStep::CloseFulfilled => controller.stream().finish_in_flight_close(),
+            WritableControllerStep::CloseRejected => {
+                controller.stream().finish_in_flight_close_with_error(v)
+            },
+        }
+    }
+}
+
+impl WritableStreamDefaultControllerMethods for WritableStreamDefaultController {
+    // https://streams.spec.whatwg.org/#ws-default-controller-error
+    fn Error(&self, _cx: SafeJSContext, e: HandleValue) {
+        if !self.stream().is_writable() {
+            return;
+        }
+        self.error(e);
+    }
+}
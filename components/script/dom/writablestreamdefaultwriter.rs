@@ -0,0 +1,342 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+use crate::dom::bindings::cell::DomRefCell;
+use crate::dom::bindings::codegen::Bindings::WritableStreamDefaultWriterBinding;
+use crate::dom::bindings::codegen::Bindings::WritableStreamDefaultWriterBinding::WritableStreamDefaultWriterMethods;
+use crate::dom::bindings::error::{Error, Fallible};
+use crate::dom::bindings::reflector::{reflect_dom_object, DomObject, Reflector};
+use crate::dom::bindings::root::{DomRoot, MutNullableDom};
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::promise::Promise;
+use crate::dom::writablestream::{WritableStream, WritableStreamState};
+use crate::script_runtime::JSContext as SafeJSContext;
+use crate::streams::{error_to_jsval, promise_resolved_with_undefined};
+use crate::streams::{mark_as_handled, promise_rejected_with, promise_rejected_with_error};
+use dom_struct::dom_struct;
+use js::jsval::UndefinedValue;
+use js::rust::HandleValue;
+use std::rc::Rc;
+
+/// <https://streams.spec.whatwg.org/#default-writer-class>
+#[dom_struct]
+pub struct WritableStreamDefaultWriter {
+    reflector_: Reflector,
+    /// <https://streams.spec.whatwg.org/#writablestreamdefaultwriter-stream>
+    stream: MutNullableDom<WritableStream>,
+    /// <https://streams.spec.whatwg.org/#writablestreamdefaultwriter-closedpromise>
+    #[ignore_malloc_size_of = "Rc"]
+    closed_promise: DomRefCell<Rc<Promise>>,
+    /// <https://streams.spec.whatwg.org/#writablestreamdefaultwriter-readypromise>
+    #[ignore_malloc_size_of = "Rc"]
+    ready_promise: DomRefCell<Rc<Promise>>,
+}
+
+impl WritableStreamDefaultWriter {
+    fn new_inherited(
+        closed_promise: Rc<Promise>,
+        ready_promise: Rc<Promise>,
+    ) -> WritableStreamDefaultWriter {
+        WritableStreamDefaultWriter {
+            reflector_: Reflector::new(),
+            stream: Default::default(),
+            closed_promise: DomRefCell::new(closed_promise),
+            ready_promise: DomRefCell::new(ready_promise),
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#set-up-writable-stream-default-writer>
+    pub fn new(
+        global: &GlobalScope,
+        stream: &WritableStream,
+    ) -> Fallible<DomRoot<WritableStreamDefaultWriter>> {
+        // Step 1.
+        if stream.is_locked() {
+            return Err(Error::Type("The stream is already locked".to_owned()));
+        }
+
+        let cx = global.get_cx();
+        rooted!(in(*cx) let mut stored_error = UndefinedValue());
+        stream.stored_error(stored_error.handle_mut());
+
+        // Steps 4-8.
+        let (ready_promise, closed_promise) = match stream.state() {
+            WritableStreamState::Writable => {
+                let ready_promise = if !stream.close_queued_or_in_flight() && stream.backpressure()
+                {
+                    Promise::new(global)
+                } else {
+                    promise_resolved_with_undefined(global)
+                };
+                (ready_promise, Promise::new(global))
+            },
+            WritableStreamState::Erroring => {
+                let ready_promise = promise_rejected_with(global, stored_error.handle());
+                mark_as_handled(global, &ready_promise);
+                (ready_promise, Promise::new(global))
+            },
+            WritableStreamState::Closed => (
+                promise_resolved_with_undefined(global),
+                promise_resolved_with_undefined(global),
+            ),
+            WritableStreamState::Errored => {
+                let ready_promise = promise_rejected_with(global, stored_error.handle());
+                mark_as_handled(global, &ready_promise);
+                let closed_promise = promise_rejected_with(global, stored_error.handle());
+                mark_as_handled(global, &closed_promise);
+                (ready_promise, closed_promise)
+            },
+        };
+
+        // Steps 2-3.
+        let writer = reflect_dom_object(
+            Box::new(WritableStreamDefaultWriter::new_inherited(
+                closed_promise,
+                ready_promise,
+            )),
+            global,
+            WritableStreamDefaultWriterBinding::Wrap,
+        );
+        writer.stream.set(Some(stream));
+        stream.set_writer(Some(&writer));
+        Ok(writer)
+    }
+
+    // https://streams.spec.whatwg.org/#default-writer-constructor
+    pub fn Constructor(
+        global: &GlobalScope,
+        stream: &WritableStream,
+    ) -> Fallible<DomRoot<WritableStreamDefaultWriter>> {
+        WritableStreamDefaultWriter::new(global, stream)
+    }
+
+    fn no_stream_error(&self) -> Rc<Promise> {
+        promise_rejected_with_error(
+            &self.global(),
+            Error::Type("The writer has no stream".to_owned()),
+        )
+    }
+
+    pub fn closed_promise(&self) -> Rc<Promise> {
+        self.closed_promise.borrow().clone()
+    }
+
+    pub fn ready_promise(&self) -> Rc<Promise> {
+        self.ready_promise.borrow().clone()
+    }
+
+    pub fn resolve_closed_promise(&self) {
+        self.closed_promise.borrow().resolve_native(&());
+    }
+
+    pub fn reject_closed_promise(&self, reason: HandleValue) {
+        let global = self.global();
+        let closed_promise = self.closed_promise();
+        closed_promise.reject(global.get_cx(), reason);
+        mark_as_handled(&global, &closed_promise);
+    }
+
+    pub fn resolve_ready_promise(&self) {
+        self.ready_promise.borrow().resolve_native(&());
+    }
+
+    /// Replace the ready promise with a pending one, as the stream now has
+    /// backpressure.
+    pub fn reset_ready_promise(&self) {
+        *self.ready_promise.borrow_mut() = Promise::new(&self.global());
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-writer-ensure-closed-promise-rejected>
+    pub fn ensure_closed_promise_rejected(&self, error: HandleValue) {
+        let global = self.global();
+        let closed_promise = self.closed_promise();
+        if closed_promise.is_fulfilled() {
+            *self.closed_promise.borrow_mut() = promise_rejected_with(&global, error);
+        } else {
+            closed_promise.reject(global.get_cx(), error);
+        }
+        mark_as_handled(&global, &self.closed_promise());
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-writer-ensure-ready-promise-rejected>
+    pub fn ensure_ready_promise_rejected(&self, error: HandleValue) {
+        let global = self.global();
+        let ready_promise = self.ready_promise();
+        if ready_promise.is_fulfilled() {
+            *self.ready_promise.borrow_mut() = promise_rejected_with(&global, error);
+        } else {
+            ready_promise.reject(global.get_cx(), error);
+        }
+        mark_as_handled(&global, &self.ready_promise());
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-writer-get-desired-size>
+    fn get_desired_size(&self, stream: &WritableStream) -> Option<f64> {
+        match stream.state() {
+            WritableStreamState::Errored | WritableStreamState::Erroring => None,
+            WritableStreamState::Closed => Some(0.),
+            WritableStreamState::Writable => Some(stream.controller().get_desired_size()),
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-writer-close>
+    pub fn close(&self) -> Rc<Promise> {
+        match self.stream.get() {
+            Some(stream) => stream.close(),
+            None => self.no_stream_error(),
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-writer-close-with-error-propagation>
+    pub fn close_with_error_propagation(&self) -> Rc<Promise> {
+        let global = self.global();
+        let stream = match self.stream.get() {
+            Some(stream) => stream,
+            None => return self.no_stream_error(),
+        };
+
+        // Step 4.
+        if stream.close_queued_or_in_flight() || stream.state() == WritableStreamState::Closed {
+            return promise_resolved_with_undefined(&global);
+        }
+
+        // Step 5.
+        if stream.state() == WritableStreamState::Errored {
+            let cx = global.get_cx();
+            rooted!(in(*cx) let mut stored_error = UndefinedValue());
+            stream.stored_error(stored_error.handle_mut());
+            return promise_rejected_with(&global, stored_error.handle());
+        }
+
+        // Step 7.
+        self.close()
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-writer-release>
+    pub fn release(&self) {
+        let stream = match self.stream.get() {
+            Some(stream) => stream,
+            None => return,
+        };
+        let global = self.global();
+        let cx = global.get_cx();
+
+        // Steps 3-5.
+        rooted!(in(*cx) let mut release_error = UndefinedValue());
+        error_to_jsval(
+            &global,
+            Error::Type("The writer's lock was released".to_owned()),
+            release_error.handle_mut(),
+        );
+        self.ensure_ready_promise_rejected(release_error.handle());
+        self.ensure_closed_promise_rejected(release_error.handle());
+
+        // Steps 6-7.
+        stream.set_writer(None);
+        self.stream.set(None);
+    }
+
+    /// <https://streams.spec.whatwg.org/#writable-stream-default-writer-write>
+    pub fn write(&self, chunk: HandleValue) -> Rc<Promise> {
+        let global = self.global();
+        let cx = global.get_cx();
+        let stream = match self.stream.get() {
+            Some(stream) => stream,
+            None => return self.no_stream_error(),
+        };
+
+        // Steps 2-3.
+        let controller = stream.controller();
+        let chunk_size = controller.get_chunk_size(chunk);
+
+        // Step 4.
+        if !self
+            .stream
+            .get()
+            .map_or(false, |current| &*current == &*stream)
+        {
+            return promise_rejected_with_error(
+                &global,
+                Error::Type("The writer's lock was released".to_owned()),
+            );
+        }
+
+        // Steps 5-8.
+        rooted!(in(*cx) let mut stored_error = UndefinedValue());
+        stream.stored_error(stored_error.handle_mut());
+        match stream.state() {
+            WritableStreamState::Errored | WritableStreamState::Erroring => {
+                return promise_rejected_with(&global, stored_error.handle());
+            },
+            WritableStreamState::Closed => {
+                return promise_rejected_with_error(
+                    &global,
+                    Error::Type("The stream is closed".to_owned()),
+                );
+            },
+            WritableStreamState::Writable if stream.close_queued_or_in_flight() => {
+                return promise_rejected_with_error(
+                    &global,
+                    Error::Type("The stream is closing".to_owned()),
+                );
+            },
+            WritableStreamState::Writable => {},
+        }
+
+        // Steps 9-11.
+        let promise = stream.add_write_request();
+        controller.write(chunk, chunk_size);
+        promise
+    }
+}
+
+impl WritableStreamDefaultWriterMethods for WritableStreamDefaultWriter {
+    // https://streams.spec.whatwg.org/#default-writer-closed
+    fn Closed(&self) -> Rc<Promise> {
+        self.closed_promise()
+    }
+
+    // https://streams.spec.whatwg.org/#default-writer-desired-size
+    fn GetDesiredSize(&self) -> Fallible<Option<f64>> {
+        match self.stream.get() {
+            Some(stream) => Ok(self.get_desired_size(&stream)),
+            None => Err(Error::Type("The writer has no stream".to_owned())),
+        }
+    }
+
+    // https://streams.spec.whatwg.org/#default-writer-ready
+    fn Ready(&self) -> Rc<Promise> {
+        self.ready_promise()
+    }
+
+    // https://streams.spec.whatwg.org/#default-writer-abort
+    fn Abort(&self, _cx: SafeJSContext, reason: HandleValue) -> Rc<Promise> {
+        match self.stream.get() {
+            Some(stream) => stream.abort(reason),
+            None => self.no_stream_error(),
+        }
+    }
+
+    // https://streams.spec.whatwg.org/#default-writer-close
+    fn Close(&self) -> Rc<Promise> {
+        match self.stream.get() {
+            Some(ref stream) if stream.close_queued_or_in_flight() => promise_rejected_with_error(
+                &self.global(),
+                Error::Type("The stream is already closing".to_owned()),
+            ),
+            Some(_) => self.close(),
+            None => self.no_stream_error(),
+        }
+    }
+
+    // https://streams.spec.whatwg.org/#default-writer-release-lock
+    fn ReleaseLock(&self) {
+        self.release();
+    }
+
+    // https://streams.spec.whatwg.org/#default-writer-write
+    fn Write(&self, _cx: SafeJSContext, chunk: HandleValue) -> Rc<Promise> {
+        self.write(chunk)
+    }
+}
@@ -3,15 +3,17 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
 use crate::compartments::{enter_realm, InCompartment};
+use crate::dom::abortsignal::{AbortAlgorithm, AbortSignal};
 use crate::dom::bindings::codegen::Bindings::RequestBinding::RequestInfo;
 use crate::dom::bindings::codegen::Bindings::RequestBinding::RequestInit;
+use crate::dom::bindings::codegen::Bindings::RequestBinding::RequestMethods;
 use crate::dom::bindings::codegen::Bindings::ResponseBinding::ResponseBinding::ResponseMethods;
 use crate::dom::bindings::codegen::Bindings::ResponseBinding::ResponseType as DOMResponseType;
 use crate::dom::bindings::error::Error;
 use crate::dom::bindings::inheritance::Castable;
 use crate::dom::bindings::refcounted::{Trusted, TrustedPromise};
 use crate::dom::bindings::reflector::DomObject;
-use crate::dom::bindings::root::DomRoot;
+use crate::dom::bindings::root::{Dom, DomRoot};
 use crate::dom::bindings::trace::RootedTraceableBox;
 use crate::dom::globalscope::GlobalScope;
 use crate::dom::headers::Guard;
@@ -34,14 +36,14 @@ use net_traits::{FetchChannels, FetchResponseListener, NetworkError};
 use net_traits::{FetchMetadata, FilteredMetadata, Metadata};
 use net_traits::{ResourceFetchTiming, ResourceTimingType};
 use servo_url::ServoUrl;
-use std::mem;
 use std::rc::Rc;
 use std::sync::{Arc, Mutex};
 
 struct FetchContext {
     fetch_promise: Option<TrustedPromise>,
     response_object: Trusted<Response>,
-    body: Vec<u8>,
+    /// The signal of the request, which holds the algorithm aborting this fetch.
+    signal: Trusted<AbortSignal>,
     resource_timing: ResourceFetchTiming,
 }
 
@@ -142,13 +144,21 @@ pub fn Fetch(
     let response = Response::new(global);
 
     // Step 2
-    let request = match Request::Constructor(global, input, init) {
+    let request_object = match Request::Constructor(global, input, init) {
         Err(e) => {
             promise.reject_error(e);
             return promise;
         },
-        Ok(r) => r.get_request(),
+        Ok(r) => r,
     };
+    let request = request_object.get_request();
+
+    // The request's signal may already be aborted.
+    let signal = request_object.Signal();
+    if signal.aborted() {
+        promise.reject_error(Error::Abort);
+        return promise;
+    }
     let timing_type = request.timing_type();
 
     let mut request_init = request_init_from_request(request);
@@ -163,10 +173,17 @@ pub fn Fetch(
 
     // Step 5
     let (action_sender, action_receiver) = ipc::channel().unwrap();
+    // Aborting the signal cancels the fetch in the resource thread.
+    let (cancel_sender, cancel_receiver) = ipc::channel().unwrap();
+    signal.add_algorithm(AbortAlgorithm::Fetch {
+        promise: promise.clone(),
+        response: Dom::from_ref(&*response),
+        cancel_chan: cancel_sender,
+    });
     let fetch_context = Arc::new(Mutex::new(FetchContext {
         fetch_promise: Some(TrustedPromise::new(promise.clone())),
         response_object: Trusted::new(&*response),
-        body: vec![],
+        signal: Trusted::new(&*signal),
         resource_timing: ResourceFetchTiming::new(timing_type),
     }));
     let listener = NetworkListener {
@@ -184,7 +201,7 @@ pub fn Fetch(
     core_resource_thread
         .send(NetTraitsFetch(
             request_init,
-            FetchChannels::ResponseMsg(action_sender, None),
+            FetchChannels::ResponseMsg(action_sender, Some(cancel_receiver)),
         ))
         .unwrap();
 
@@ -248,18 +265,26 @@ impl FetchResponseListener for FetchContext {
             },
         }
         // Step 4.3
+        self.response_object.root().start_receiving();
         promise.resolve_native(&self.response_object.root());
         self.fetch_promise = Some(TrustedPromise::new(promise));
     }
 
-    fn process_response_chunk(&mut self, mut chunk: Vec<u8>) {
-        self.body.append(&mut chunk);
-    }
-
-    fn process_response_eof(&mut self, _response: Result<ResourceFetchTiming, NetworkError>) {
+    fn process_response_chunk(&mut self, chunk: Vec<u8>) {
         let response = self.response_object.root();
         let _ac = enter_realm(&*response);
-        response.finish(mem::replace(&mut self.body, vec![]));
+        response.stream_chunk(chunk);
+    }
+
+    fn process_response_eof(&mut self, response: Result<ResourceFetchTiming, NetworkError>) {
+        let response_object = self.response_object.root();
+        let _ac = enter_realm(&*response_object);
+        match response {
+            Ok(_) => response_object.finish(),
+            Err(_) => response_object.error_body(Error::Type("Network error occurred".to_string())),
+        }
+        // The fetch is over, so aborting the signal must not affect it anymore.
+        self.signal.root().remove_fetch_algorithm(&response_object);
         // TODO
         // ... trailerObject is not supported in Servo yet.
     }
@@ -0,0 +1,518 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Conversions between JS values and IndexedDB keys, and key path evaluation.
+//! <https://w3c.github.io/IndexedDB/#key-construct>
+
+use crate::dom::bindings::codegen::UnionTypes::StringOrStringSequence;
+use crate::dom::bindings::conversions::{root_from_handlevalue, ConversionBehavior};
+use crate::dom::bindings::conversions::{ConversionResult, FromJSValConvertible};
+use crate::dom::bindings::conversions::{StringificationBehavior, ToJSValConvertible};
+use crate::dom::bindings::error::{Error, Fallible};
+use crate::dom::bindings::str::DOMString;
+use crate::dom::domexception::DOMErrorName;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::idbkeyrange::IDBKeyRange;
+use crate::script_runtime::JSContext;
+use crate::task_source::{TaskSource, TaskSourceName};
+use ipc_channel::router::ROUTER;
+use js::jsapi::{ClippedTime, HandleValueArray, JSObject, JSPROP_ENUMERATE};
+use js::jsapi::{JS_IsExceptionPending, JS_NewArrayObject, JS_NewPlainObject, NewDateObject};
+use js::jsval::{DoubleValue, NullValue, ObjectValue, UndefinedValue};
+use js::rust::wrappers::{DateGetMsecSinceEpoch, JS_DefineProperty, JS_GetProperty};
+use js::rust::wrappers::{JS_HasOwnProperty, JS_IsArrayObject, ObjectIsDate};
+use js::rust::{HandleObject, HandleValue, MutableHandleValue};
+use js::typedarray::{ArrayBuffer, ArrayBufferView, CreateWith};
+use net_traits::indexeddb_thread::{IndexedDBError, IndexedDBKeyPath};
+use net_traits::indexeddb_thread::{IndexedDBKeyRange, IndexedDBKeyType};
+use profile_traits::ipc::IpcReceiver;
+use serde::{Deserialize, Serialize};
+use std::ffi::CString;
+use std::ptr;
+
+/// The result of extracting a key from a value using a key path.
+/// <https://w3c.github.io/IndexedDB/#extract-a-key-from-a-value-using-a-key-path>
+pub enum ExtractionResult {
+    Key(IndexedDBKeyType),
+    Invalid,
+    Failure,
+}
+
+/// The name of the DOMException reported for an error of the indexeddb thread.
+pub fn error_name(error: IndexedDBError) -> DOMErrorName {
+    match error {
+        IndexedDBError::Abort => DOMErrorName::AbortError,
+        IndexedDBError::Constraint => DOMErrorName::ConstraintError,
+        IndexedDBError::Data => DOMErrorName::DataError,
+        IndexedDBError::NotFound => DOMErrorName::NotFoundError,
+        IndexedDBError::Version => DOMErrorName::VersionError,
+        IndexedDBError::Unknown => DOMErrorName::UnknownError,
+    }
+}
+
+/// The exception thrown when the indexeddb thread rejects a synchronous request.
+pub fn exception_for(error: IndexedDBError) -> Error {
+    match error {
+        IndexedDBError::Abort => Error::Abort,
+        IndexedDBError::Constraint => Error::Constraint,
+        IndexedDBError::Data => Error::Data,
+        IndexedDBError::NotFound => Error::NotFound,
+        IndexedDBError::Version => Error::Version,
+        IndexedDBError::Unknown => Error::Unknown,
+    }
+}
+
+/// Run `handler` for every message received from the indexeddb thread,
+/// as a task queued on the database access task source of `global`.
+pub fn route_replies<T, F>(global: &GlobalScope, receiver: IpcReceiver<T>, handler: F)
+where
+    T: for<'de> Deserialize<'de> + Serialize + Send + 'static,
+    F: Fn(T) + Clone + Send + 'static,
+{
+    let task_source = global.database_access_task_source();
+    let canceller = global.task_canceller(TaskSourceName::DatabaseAccess);
+    ROUTER.add_route(
+        receiver.to_opaque(),
+        Box::new(move |message| {
+            let message: T = match message.to() {
+                Ok(message) => message,
+                Err(_) => return warn!("Received an invalid message from the indexeddb thread"),
+            };
+            let handler = handler.clone();
+            let _ = task_source.queue_with_canceller(
+                task!(handle_indexeddb_message: move || {
+                    handler(message);
+                }),
+                &canceller,
+            );
+        }),
+    );
+}
+
+#[allow(unsafe_code)]
+fn get_own_property(
+    cx: JSContext,
+    object: HandleObject,
+    name: &str,
+    rval: MutableHandleValue,
+) -> Fallible<bool> {
+    let name = CString::new(name).map_err(|_| Error::Data)?;
+    let mut found = false;
+    unsafe {
+        if !JS_HasOwnProperty(*cx, object, name.as_ptr(), &mut found) {
+            return Err(Error::JSFailed);
+        }
+        if !found {
+            return Ok(false);
+        }
+        if !JS_GetProperty(*cx, object, name.as_ptr(), rval) {
+            return Err(Error::JSFailed);
+        }
+    }
+    Ok(true)
+}
+
+#[allow(unsafe_code)]
+fn define_property(
+    cx: JSContext,
+    object: HandleObject,
+    name: &str,
+    value: HandleValue,
+) -> Fallible<()> {
+    let name = CString::new(name).map_err(|_| Error::Data)?;
+    unsafe {
+        if !JS_DefineProperty(*cx, object, name.as_ptr(), value, JSPROP_ENUMERATE as u32) {
+            return Err(Error::JSFailed);
+        }
+    }
+    Ok(())
+}
+
+/// Create a JS array holding the values produced by `f` for each item.
+#[allow(unsafe_code)]
+pub fn create_array<T, F>(cx: JSContext, items: &[T], f: F, mut rval: MutableHandleValue)
+where
+    F: Fn(&T, MutableHandleValue),
+{
+    unsafe {
+        rooted!(in(*cx) let array = JS_NewArrayObject(*cx, &HandleValueArray::new()));
+        for (index, item) in items.iter().enumerate() {
+            rooted!(in(*cx) let mut value = UndefinedValue());
+            f(item, value.handle_mut());
+            let _ = define_property(cx, array.handle(), &index.to_string(), value.handle());
+        }
+        rval.set(ObjectValue(array.get()));
+    }
+}
+
+/// <https://w3c.github.io/IndexedDB/#convert-a-key-to-a-value>
+#[allow(unsafe_code)]
+pub fn key_type_to_jsval(cx: JSContext, key: &IndexedDBKeyType, mut rval: MutableHandleValue) {
+    match *key {
+        IndexedDBKeyType::Number(number) => rval.set(DoubleValue(number)),
+        IndexedDBKeyType::Date(time) => unsafe {
+            rooted!(in(*cx) let date = NewDateObject(*cx, ClippedTime { t: time }));
+            rval.set(ObjectValue(date.get()));
+        },
+        IndexedDBKeyType::String(ref string) => unsafe { string.to_jsval(*cx, rval) },
+        IndexedDBKeyType::Binary(ref bytes) => unsafe {
+            rooted!(in(*cx) let mut array_buffer = ptr::null_mut::<JSObject>());
+            assert!(
+                ArrayBuffer::create(*cx, CreateWith::Slice(bytes), array_buffer.handle_mut())
+                    .is_ok()
+            );
+            rval.set(ObjectValue(array_buffer.get()));
+        },
+        IndexedDBKeyType::Array(ref keys) => {
+            create_array(cx, keys, |key, rval| key_type_to_jsval(cx, key, rval), rval)
+        },
+    }
+}
+
+/// <https://w3c.github.io/IndexedDB/#convert-a-value-to-a-key>
+#[allow(unsafe_code)]
+pub fn convert_value_to_key(
+    cx: JSContext,
+    input: HandleValue,
+    seen: Option<Vec<*mut JSObject>>,
+) -> Fallible<IndexedDBKeyType> {
+    // Step 1.
+    let mut seen = seen.unwrap_or_default();
+
+    // Steps 2-3.
+    if input.is_number() {
+        let number = input.to_number();
+        if number.is_nan() {
+            return Err(Error::Data);
+        }
+        return Ok(IndexedDBKeyType::Number(number));
+    }
+
+    if input.is_string() {
+        let string = unsafe {
+            match DOMString::from_jsval(*cx, input, StringificationBehavior::Default) {
+                Ok(ConversionResult::Success(string)) => string,
+                _ => return Err(Error::JSFailed),
+            }
+        };
+        return Ok(IndexedDBKeyType::String(string.into()));
+    }
+
+    if !input.is_object() {
+        return Err(Error::Data);
+    }
+
+    unsafe {
+        rooted!(in(*cx) let object = input.to_object());
+
+        let mut is_date = false;
+        if !ObjectIsDate(*cx, object.handle(), &mut is_date) {
+            return Err(Error::JSFailed);
+        }
+        if is_date {
+            let mut time = 0.;
+            if !DateGetMsecSinceEpoch(*cx, object.handle(), &mut time) {
+                return Err(Error::JSFailed);
+            }
+            if time.is_nan() {
+                return Err(Error::Data);
+            }
+            return Ok(IndexedDBKeyType::Date(time));
+        }
+
+        if let Ok(buffer) = ArrayBuffer::from(object.get()) {
+            return Ok(IndexedDBKeyType::Binary(buffer.to_vec()));
+        }
+        if let Ok(view) = ArrayBufferView::from(object.get()) {
+            return Ok(IndexedDBKeyType::Binary(view.to_vec()));
+        }
+
+        let mut is_array = false;
+        if !JS_IsArrayObject(*cx, input, &mut is_array) {
+            return Err(Error::JSFailed);
+        }
+        if !is_array {
+            return Err(Error::Data);
+        }
+
+        // Step 1 of the array case.
+        if seen.contains(&object.get()) {
+            return Err(Error::Data);
+        }
+        seen.push(object.get());
+
+        rooted!(in(*cx) let mut length = UndefinedValue());
+        get_own_property(cx, object.handle(), "length", length.handle_mut())?;
+        let length = match u32::from_jsval(*cx, length.handle(), ConversionBehavior::Default) {
+            Ok(ConversionResult::Success(length)) => length,
+            _ => return Err(Error::JSFailed),
+        };
+
+        let mut keys = Vec::with_capacity(length as usize);
+        for index in 0..length {
+            rooted!(in(*cx) let mut entry = UndefinedValue());
+            if !get_own_property(cx, object.handle(), &index.to_string(), entry.handle_mut())? {
+                return Err(Error::Data);
+            }
+            keys.push(convert_value_to_key(
+                cx,
+                entry.handle(),
+                Some(seen.clone()),
+            )?);
+        }
+        Ok(IndexedDBKeyType::Array(keys))
+    }
+}
+
+/// <https://w3c.github.io/IndexedDB/#convert-a-value-to-a-multientry-key>
+#[allow(unsafe_code)]
+pub fn convert_value_to_multi_entry_key(
+    cx: JSContext,
+    input: HandleValue,
+) -> Fallible<IndexedDBKeyType> {
+    let mut is_array = false;
+    unsafe {
+        if input.is_object() && !JS_IsArrayObject(*cx, input, &mut is_array) {
+            return Err(Error::JSFailed);
+        }
+    }
+    if !is_array {
+        return convert_value_to_key(cx, input, None);
+    }
+
+    // Invalid subkeys and duplicates are skipped.
+    let mut keys = vec![];
+    if let IndexedDBKeyType::Array(subkeys) =
+        convert_value_to_key(cx, input, None).unwrap_or(IndexedDBKeyType::Array(vec![]))
+    {
+        for key in subkeys {
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+    }
+    Ok(IndexedDBKeyType::Array(keys))
+}
+
+/// <https://w3c.github.io/IndexedDB/#convert-a-value-to-a-key-range>
+pub fn convert_value_to_key_range(
+    cx: JSContext,
+    input: HandleValue,
+    null_disallowed: bool,
+) -> Fallible<IndexedDBKeyRange> {
+    // Step 1.
+    if let Ok(range) = root_from_handlevalue::<IDBKeyRange>(input, *cx) {
+        return Ok(range.inner().clone());
+    }
+
+    // Step 2.
+    if input.is_null_or_undefined() {
+        if null_disallowed {
+            return Err(Error::Data);
+        }
+        return Ok(IndexedDBKeyRange::unbounded());
+    }
+
+    // Steps 3-5.
+    let key = convert_value_to_key(cx, input, None)?;
+    Ok(IndexedDBKeyRange::only(key))
+}
+
+/// <https://w3c.github.io/IndexedDB/#valid-key-path>
+pub fn is_valid_key_path(key_path: &IndexedDBKeyPath) -> bool {
+    fn is_identifier(name: &str) -> bool {
+        let mut chars = name.chars();
+        match chars.next() {
+            Some(c) if c.is_alphabetic() || c == '$' || c == '_' => {},
+            _ => return false,
+        }
+        chars.all(|c| c.is_alphanumeric() || c == '$' || c == '_')
+    }
+    fn is_valid_string(path: &str) -> bool {
+        path.is_empty() || path.split('.').all(is_identifier)
+    }
+    match *key_path {
+        IndexedDBKeyPath::String(ref path) => is_valid_string(path),
+        IndexedDBKeyPath::Sequence(ref paths) => {
+            !paths.is_empty() && paths.iter().all(|path| is_valid_string(path))
+        },
+    }
+}
+
+pub fn key_path_from_union(key_path: StringOrStringSequence) -> IndexedDBKeyPath {
+    match key_path {
+        StringOrStringSequence::String(path) => IndexedDBKeyPath::String(path.into()),
+        StringOrStringSequence::StringSequence(paths) => {
+            IndexedDBKeyPath::Sequence(paths.into_iter().map(String::from).collect())
+        },
+    }
+}
+
+/// Convert a key path to the value exposed by the `keyPath` attributes.
+#[allow(unsafe_code)]
+pub fn key_path_to_jsval(
+    cx: JSContext,
+    key_path: &Option<IndexedDBKeyPath>,
+    mut rval: MutableHandleValue,
+) {
+    match *key_path {
+        None => rval.set(NullValue()),
+        Some(IndexedDBKeyPath::String(ref path)) => unsafe { path.to_jsval(*cx, rval) },
+        Some(IndexedDBKeyPath::Sequence(ref paths)) => create_array(
+            cx,
+            paths,
+            |path, rval| unsafe { path.to_jsval(*cx, rval) },
+            rval,
+        ),
+    }
+}
+
+/// Returns `false` if the key path does not resolve to a value.
+/// <https://w3c.github.io/IndexedDB/#evaluate-a-key-path-on-a-value>
+#[allow(unsafe_code)]
+pub fn evaluate_key_path_on_value(
+    cx: JSContext,
+    value: HandleValue,
+    key_path: &IndexedDBKeyPath,
+    mut rval: MutableHandleValue,
+) -> Fallible<bool> {
+    let path = match *key_path {
+        IndexedDBKeyPath::Sequence(ref paths) => {
+            rooted!(in(*cx) let array = unsafe {
+                JS_NewArrayObject(*cx, &HandleValueArray::new())
+            });
+            for (index, path) in paths.iter().enumerate() {
+                rooted!(in(*cx) let mut item = UndefinedValue());
+                let path = IndexedDBKeyPath::String(path.clone());
+                if !evaluate_key_path_on_value(cx, value, &path, item.handle_mut())? {
+                    return Ok(false);
+                }
+                define_property(cx, array.handle(), &index.to_string(), item.handle())?;
+            }
+            rval.set(ObjectValue(array.get()));
+            return Ok(true);
+        },
+        IndexedDBKeyPath::String(ref path) => path,
+    };
+
+    rval.set(value.get());
+    if path.is_empty() {
+        return Ok(true);
+    }
+
+    for identifier in path.split('.') {
+        if rval.is_string() && identifier == "length" {
+            let string = unsafe {
+                match DOMString::from_jsval(*cx, rval.handle(), StringificationBehavior::Default) {
+                    Ok(ConversionResult::Success(string)) => string,
+                    _ => return Err(Error::JSFailed),
+                }
+            };
+            let length = string.encode_utf16().count();
+            rval.set(DoubleValue(length as f64));
+            continue;
+        }
+        if !rval.is_object() {
+            return Ok(false);
+        }
+        rooted!(in(*cx) let object = rval.to_object());
+        if !get_own_property(cx, object.handle(), identifier, rval)? {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// <https://w3c.github.io/IndexedDB/#extract-a-key-from-a-value-using-a-key-path>
+pub fn extract_key(
+    cx: JSContext,
+    value: HandleValue,
+    key_path: &IndexedDBKeyPath,
+    multi_entry: bool,
+) -> Fallible<ExtractionResult> {
+    // Steps 1-2.
+    rooted!(in(*cx) let mut result = UndefinedValue());
+    if !evaluate_key_path_on_value(cx, value, key_path, result.handle_mut())? {
+        return Ok(ExtractionResult::Failure);
+    }
+
+    // Steps 3-5.
+    let key = if multi_entry {
+        convert_value_to_multi_entry_key(cx, result.handle())
+    } else {
+        convert_value_to_key(cx, result.handle(), None)
+    };
+    match key {
+        Ok(key) => Ok(ExtractionResult::Key(key)),
+        Err(Error::JSFailed) => Err(Error::JSFailed),
+        Err(_) => Ok(ExtractionResult::Invalid),
+    }
+}
+
+/// <https://w3c.github.io/IndexedDB/#check-that-a-key-could-be-injected-into-a-value>
+#[allow(unsafe_code)]
+pub fn can_inject_key_into_value(
+    cx: JSContext,
+    value: HandleValue,
+    key_path: &str,
+) -> Fallible<bool> {
+    let mut identifiers: Vec<&str> = key_path.split('.').collect();
+    identifiers.pop();
+
+    rooted!(in(*cx) let mut current = value.get());
+    for identifier in identifiers {
+        if !current.is_object() {
+            return Ok(false);
+        }
+        rooted!(in(*cx) let object = current.to_object());
+        if !get_own_property(cx, object.handle(), identifier, current.handle_mut())? {
+            return Ok(true);
+        }
+    }
+    Ok(current.is_object())
+}
+
+/// <https://w3c.github.io/IndexedDB/#inject-a-key-into-a-value-using-a-key-path>
+#[allow(unsafe_code)]
+pub fn inject_key_into_value(
+    cx: JSContext,
+    value: HandleValue,
+    key: &IndexedDBKeyType,
+    key_path: &str,
+) -> Fallible<()> {
+    let mut identifiers: Vec<&str> = key_path.split('.').collect();
+    let last = match identifiers.pop() {
+        Some(last) => last,
+        None => return Ok(()),
+    };
+
+    rooted!(in(*cx) let mut current = value.get());
+    for identifier in identifiers {
+        if !current.is_object() {
+            return Err(Error::Data);
+        }
+        rooted!(in(*cx) let object = current.to_object());
+        if !get_own_property(cx, object.handle(), identifier, current.handle_mut())? {
+            unsafe {
+                rooted!(in(*cx) let child = JS_NewPlainObject(*cx));
+                current.set(ObjectValue(child.get()));
+            }
+            define_property(cx, object.handle(), identifier, current.handle())?;
+        }
+    }
+
+    if !current.is_object() {
+        return Err(Error::Data);
+    }
+    rooted!(in(*cx) let object = current.to_object());
+    rooted!(in(*cx) let mut key_value = UndefinedValue());
+    key_type_to_jsval(cx, key, key_value.handle_mut());
+    define_property(cx, object.handle(), last, key_value.handle())?;
+    unsafe {
+        if JS_IsExceptionPending(*cx) {
+            return Err(Error::JSFailed);
+        }
+    }
+    Ok(())
+}
@@ -58,6 +58,7 @@ mod dom;
 mod compartments;
 pub mod fetch;
 mod image_listener;
+mod indexed_db;
 mod layout_image;
 mod mem;
 mod microtask;
@@ -67,6 +68,7 @@ pub mod script_runtime;
 pub mod script_thread;
 mod serviceworker_manager;
 mod serviceworkerjob;
+mod streams;
 mod stylesheet_loader;
 mod stylesheet_set;
 mod task_manager;
@@ -92,6 +94,7 @@ pub mod layout_exports {
     pub use crate::dom::characterdata::LayoutCharacterDataHelpers;
     pub use crate::dom::document::{Document, LayoutDocumentHelpers, PendingRestyle};
     pub use crate::dom::element::{Element, LayoutElementHelpers, RawLayoutElementHelpers};
+    pub use crate::dom::htmlslotelement::{HTMLSlotElement, LayoutHTMLSlotElementHelpers};
     pub use crate::dom::node::NodeFlags;
     pub use crate::dom::node::{LayoutNodeHelpers, Node};
     pub use crate::dom::shadowroot::{LayoutShadowRootHelpers, ShadowRoot};
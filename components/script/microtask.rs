@@ -6,6 +6,7 @@
 //! microtask queues. It is up to implementations of event loops to store a queue and
 //! perform checkpoints at appropriate times, as well as enqueue microtasks as required.
 
+use crate::dom::animation::AnimationMicrotask;
 use crate::dom::bindings::callback::ExceptionHandling;
 use crate::dom::bindings::cell::DomRefCell;
 use crate::dom::bindings::codegen::Bindings::PromiseBinding::PromiseJobCallback;
@@ -36,6 +37,7 @@ pub enum Microtask {
     Promise(EnqueuedPromiseCallback),
     MediaElement(MediaElementMicrotask),
     ImageElement(ImageElementMicrotask),
+    Animation(AnimationMicrotask),
     CustomElementReaction,
     NotifyMutationObservers,
 }
@@ -101,6 +103,9 @@ impl MicrotaskQueue {
                     Microtask::ImageElement(ref task) => {
                         task.handler();
                     },
+                    Microtask::Animation(ref task) => {
+                        task.handler();
+                    },
                     Microtask::CustomElementReaction => {
                         ScriptThread::invoke_backup_element_queue();
                     },
@@ -112,11 +117,14 @@ impl MicrotaskQueue {
         }
 
         // Step 3
-        for global in globalscopes.into_iter() {
-            notify_about_rejected_promises(&global);
+        for global in globalscopes.iter() {
+            notify_about_rejected_promises(global);
         }
 
-        // TODO: Step 4 - Cleanup Indexed Database transactions.
+        // Step 4
+        for global in globalscopes.iter() {
+            global.cleanup_indexeddb_transactions();
+        }
 
         // Step 5
         self.performing_a_microtask_checkpoint.set(false);
@@ -106,6 +106,7 @@ pub trait ScriptChan: JSTraceable {
 pub enum ScriptThreadEventCategory {
     AttachLayout,
     ConstellationMsg,
+    DatabaseAccessEvent,
     DevtoolsMsg,
     DocumentEvent,
     DomEvent,
@@ -130,6 +131,7 @@ pub enum ScriptThreadEventCategory {
     ExitFullscreen,
     WebVREvent,
     PerformanceTimelineTask,
+    PortMessage,
 }
 
 /// An interface for receiving ScriptMsg values in an event loop. Used for synchronous DOM
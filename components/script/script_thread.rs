@@ -38,7 +38,6 @@ use crate::dom::bindings::reflector::DomObject;
 use crate::dom::bindings::root::ThreadLocalStackRoots;
 use crate::dom::bindings::root::{Dom, DomRoot, MutNullableDom, RootCollection};
 use crate::dom::bindings::str::DOMString;
-use crate::dom::bindings::structuredclone::StructuredCloneData;
 use crate::dom::bindings::trace::JSTraceable;
 use crate::dom::bindings::utils::WRAP_CALLBACKS;
 use crate::dom::customelementregistry::{
@@ -52,6 +51,7 @@ use crate::dom::event::{Event, EventBubbles, EventCancelable};
 use crate::dom::globalscope::GlobalScope;
 use crate::dom::htmlanchorelement::HTMLAnchorElement;
 use crate::dom::htmliframeelement::{HTMLIFrameElement, NavigationType};
+use crate::dom::htmlslotelement::HTMLSlotElement;
 use crate::dom::mutationobserver::MutationObserver;
 use crate::dom::node::{
     from_untrusted_node_address, window_from_node, Node, NodeDamage, ShadowIncluding,
@@ -75,12 +75,14 @@ use crate::script_runtime::{CommonScriptMsg, ScriptChan, ScriptThreadEventCatego
 use crate::serviceworkerjob::{Job, JobQueue};
 use crate::task_manager::TaskManager;
 use crate::task_queue::{QueuedTask, QueuedTaskConversion, TaskQueue};
+use crate::task_source::database_access::DatabaseAccessTaskSource;
 use crate::task_source::dom_manipulation::DOMManipulationTaskSource;
 use crate::task_source::file_reading::FileReadingTaskSource;
 use crate::task_source::history_traversal::HistoryTraversalTaskSource;
 use crate::task_source::media_element::MediaElementTaskSource;
 use crate::task_source::networking::NetworkingTaskSource;
 use crate::task_source::performance_timeline::PerformanceTimelineTaskSource;
+use crate::task_source::port_message::PortMessageQueue;
 use crate::task_source::remote_event::RemoteEventTaskSource;
 use crate::task_source::user_interaction::UserInteractionTaskSource;
 use crate::task_source::websocket::WebsocketTaskSource;
@@ -139,7 +141,8 @@ use script_traits::{
 use script_traits::{InitialScriptState, JsEvalResult, LayoutMsg, LoadData, LoadOrigin};
 use script_traits::{MouseButton, MouseEventType, NewLayoutInfo};
 use script_traits::{Painter, ProgressiveWebMetricType, ScriptMsg, ScriptThreadFactory};
-use script_traits::{ScriptToConstellationChan, TimerEvent, TimerSchedulerMsg};
+use script_traits::{ScriptToConstellationChan, StructuredSerializedData};
+use script_traits::{TimerEvent, TimerSchedulerMsg};
 use script_traits::{TimerSource, TouchEventType, TouchId, UntrustedNodeAddress, WheelDelta};
 use script_traits::{UpdatePipelineIdReason, WindowSizeData, WindowSizeType};
 use servo_atoms::Atom;
@@ -564,8 +567,12 @@ pub struct ScriptThread {
 
     file_reading_task_sender: Box<dyn ScriptChan>,
 
+    database_access_task_sender: Box<dyn ScriptChan>,
+
     performance_timeline_task_sender: Box<dyn ScriptChan>,
 
+    port_message_sender: Box<dyn ScriptChan>,
+
     remote_event_task_sender: Box<dyn ScriptChan>,
 
     /// A channel to hand out to threads that need to respond to a message from the script thread.
@@ -623,6 +630,9 @@ pub struct ScriptThread {
     /// The unit of related similar-origin browsing contexts' list of MutationObserver objects
     mutation_observers: DomRefCell<Vec<Dom<MutationObserver>>>,
 
+    /// <https://dom.spec.whatwg.org/#signal-slot-list>
+    signal_slots: DomRefCell<Vec<Dom<HTMLSlotElement>>>,
+
     /// A handle to the WebGL thread
     webgl_chan: Option<WebGLPipeline>,
 
@@ -872,6 +882,29 @@ impl ScriptThread {
         })
     }
 
+    pub fn signal_slot(slot: &HTMLSlotElement) {
+        SCRIPT_THREAD_ROOT.with(|root| {
+            let script_thread = unsafe { &*root.get().unwrap() };
+            let mut signal_slots = script_thread.signal_slots.borrow_mut();
+            if !signal_slots.iter().any(|signalled| &**signalled == slot) {
+                signal_slots.push(Dom::from_ref(slot));
+            }
+        })
+    }
+
+    pub fn take_signal_slots() -> Vec<DomRoot<HTMLSlotElement>> {
+        SCRIPT_THREAD_ROOT.with(|root| {
+            let script_thread = unsafe { &*root.get().unwrap() };
+            let mut signal_slots = script_thread.signal_slots.borrow_mut();
+            let slots = signal_slots
+                .iter()
+                .map(|slot| DomRoot::from_ref(&**slot))
+                .collect();
+            signal_slots.clear();
+            slots
+        })
+    }
+
     pub fn mark_document_with_no_blocked_loads(doc: &Document) {
         SCRIPT_THREAD_ROOT.with(|root| {
             if let Some(script_thread) = root.get() {
@@ -1276,7 +1309,9 @@ impl ScriptThread {
             user_interaction_task_sender: chan.clone(),
             networking_task_sender: boxed_script_sender.clone(),
             file_reading_task_sender: boxed_script_sender.clone(),
+            database_access_task_sender: boxed_script_sender.clone(),
             performance_timeline_task_sender: boxed_script_sender.clone(),
+            port_message_sender: boxed_script_sender.clone(),
             remote_event_task_sender: boxed_script_sender.clone(),
 
             history_traversal_task_sender: chan.clone(),
@@ -1307,6 +1342,8 @@ impl ScriptThread {
 
             mutation_observers: Default::default(),
 
+            signal_slots: Default::default(),
+
             layout_to_constellation_chan: state.layout_to_constellation_chan,
 
             webgl_chan: state.webgl_chan,
@@ -1575,6 +1612,35 @@ impl ScriptThread {
             }
         }
 
+        let documents: Vec<DomRoot<Document>> = self
+            .documents
+            .borrow()
+            .iter()
+            .map(|(_, document)| document)
+            .filter(|document| document.is_fully_active())
+            .collect();
+
+        // https://drafts.csswg.org/resize-observer/#html-event-loop
+        for document in &documents {
+            let mut depth = 0;
+            document.gather_active_resize_observations_at_depth(depth);
+            while document.has_active_resize_observations() {
+                depth = document.broadcast_active_resize_observations();
+                document
+                    .window()
+                    .reflow(ReflowGoal::Full, ReflowReason::ResizeObserver);
+                document.gather_active_resize_observations_at_depth(depth);
+            }
+            if document.has_skipped_resize_observations() {
+                document.deliver_resize_loop_error_notification();
+            }
+        }
+
+        // https://html.spec.whatwg.org/multipage/#event-loop-processing-model step 7.14
+        for document in documents {
+            document.update_intersection_observations();
+        }
+
         true
     }
 
@@ -1602,6 +1668,9 @@ impl ScriptThread {
         let hang_annotation = match category {
             ScriptThreadEventCategory::AttachLayout => ScriptHangAnnotation::AttachLayout,
             ScriptThreadEventCategory::ConstellationMsg => ScriptHangAnnotation::ConstellationMsg,
+            ScriptThreadEventCategory::DatabaseAccessEvent => {
+                ScriptHangAnnotation::DatabaseAccessEvent
+            },
             ScriptThreadEventCategory::DevtoolsMsg => ScriptHangAnnotation::DevtoolsMsg,
             ScriptThreadEventCategory::DocumentEvent => ScriptHangAnnotation::DocumentEvent,
             ScriptThreadEventCategory::DomEvent => ScriptHangAnnotation::DomEvent,
@@ -1634,6 +1703,7 @@ impl ScriptThread {
             ScriptThreadEventCategory::PerformanceTimelineTask => {
                 ScriptHangAnnotation::PerformanceTimelineTask
             },
+            ScriptThreadEventCategory::PortMessage => ScriptHangAnnotation::PortMessage,
         };
         self.background_hang_monitor
             .notify_activity(HangAnnotation::Script(hang_annotation));
@@ -1723,6 +1793,9 @@ impl ScriptThread {
                 ScriptThreadEventCategory::ConstellationMsg => {
                     ProfilerCategory::ScriptConstellationMsg
                 },
+                ScriptThreadEventCategory::DatabaseAccessEvent => {
+                    ProfilerCategory::ScriptDatabaseAccessEvent
+                },
                 ScriptThreadEventCategory::DevtoolsMsg => ProfilerCategory::ScriptDevtoolsMsg,
                 ScriptThreadEventCategory::DocumentEvent => ProfilerCategory::ScriptDocumentEvent,
                 ScriptThreadEventCategory::DomEvent => ProfilerCategory::ScriptDomEvent,
@@ -1757,6 +1830,7 @@ impl ScriptThread {
                 ScriptThreadEventCategory::PerformanceTimelineTask => {
                     ProfilerCategory::ScriptPerformanceEvent
                 },
+                ScriptThreadEventCategory::PortMessage => ProfilerCategory::ScriptPortMessage,
             };
             profile(profiler_cat, None, self.time_profiler_chan.clone(), f)
         } else {
@@ -2481,7 +2555,7 @@ impl ScriptThread {
         source_pipeline_id: PipelineId,
         source_browsing_context: TopLevelBrowsingContextId,
         origin: Option<ImmutableOrigin>,
-        data: Vec<u8>,
+        data: StructuredSerializedData,
     ) {
         match { self.documents.borrow().find_window(pipeline_id) } {
             None => return warn!("postMessage after target pipeline {} closed.", pipeline_id),
@@ -2503,7 +2577,7 @@ impl ScriptThread {
                     Some(source) => source,
                 };
                 // FIXME(#22512): enqueues a task; unnecessary delay.
-                window.post_message(origin, &*source, StructuredCloneData::Vector(data))
+                window.post_message(origin, &*source, data)
             },
         }
     }
@@ -2724,6 +2798,10 @@ impl ScriptThread {
         PerformanceTimelineTaskSource(self.performance_timeline_task_sender.clone(), pipeline_id)
     }
 
+    pub fn port_message_queue(&self, pipeline_id: PipelineId) -> PortMessageQueue {
+        PortMessageQueue(self.port_message_sender.clone(), pipeline_id)
+    }
+
     pub fn history_traversal_task_source(
         &self,
         pipeline_id: PipelineId,
@@ -2746,6 +2824,10 @@ impl ScriptThread {
         FileReadingTaskSource(self.file_reading_task_sender.clone(), pipeline_id)
     }
 
+    pub fn database_access_task_source(&self, pipeline_id: PipelineId) -> DatabaseAccessTaskSource {
+        DatabaseAccessTaskSource(self.database_access_task_sender.clone(), pipeline_id)
+    }
+
     pub fn remote_event_task_source(&self, pipeline_id: PipelineId) -> RemoteEventTaskSource {
         RemoteEventTaskSource(self.remote_event_task_sender.clone(), pipeline_id)
     }
@@ -3132,6 +3214,7 @@ impl ScriptThread {
         };
 
         let task_manager = TaskManager::new(
+            self.database_access_task_source(incomplete.pipeline_id),
             self.dom_manipulation_task_source(incomplete.pipeline_id),
             self.file_reading_task_source(incomplete.pipeline_id),
             self.history_traversal_task_source(incomplete.pipeline_id),
@@ -3139,6 +3222,7 @@ impl ScriptThread {
             self.networking_task_source(incomplete.pipeline_id),
             self.performance_timeline_task_source(incomplete.pipeline_id)
                 .clone(),
+            self.port_message_queue(incomplete.pipeline_id),
             self.user_interaction_task_source(incomplete.pipeline_id),
             self.remote_event_task_source(incomplete.pipeline_id),
             self.websocket_task_source(incomplete.pipeline_id),
@@ -0,0 +1,266 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Abstract operations shared by readable, writable and transform streams.
+//! <https://streams.spec.whatwg.org/>
+
+use crate::dom::bindings::callback::ExceptionHandling;
+use crate::dom::bindings::codegen::Bindings::FunctionBinding::Function;
+use crate::dom::bindings::codegen::Bindings::QueuingStrategyBinding::QueuingStrategy;
+use crate::dom::bindings::codegen::Bindings::QueuingStrategyBinding::QueuingStrategySize;
+use crate::dom::bindings::codegen::Bindings::ReadableStreamDefaultReaderBinding::ReadableStreamReadResult;
+use crate::dom::bindings::conversions::ToJSValConvertible;
+use crate::dom::bindings::error::{Error, Fallible};
+use crate::dom::bindings::utils::get_dictionary_property;
+use crate::dom::globalscope::GlobalScope;
+use crate::dom::promise::Promise;
+use crate::dom::promisenativehandler::{Callback, PromiseNativeHandler};
+use js::jsapi::{Heap, JSNative, JS_GetFunctionObject, JS_NewFunction};
+use js::jsval::{JSVal, UndefinedValue};
+use js::rust::wrappers::JS_SetPendingException;
+use js::rust::{HandleValue, MutableHandleValue};
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+/// An entry of a queue-with-sizes.
+#[derive(JSTraceable)]
+pub enum QueuedValue {
+    /// A chunk enqueued by script or by the user agent.
+    Chunk(Box<Heap<JSVal>>),
+    /// The marker a writable stream controller enqueues once it is closed.
+    /// <https://streams.spec.whatwg.org/#close-sentinel>
+    CloseSentinel,
+}
+
+#[derive(JSTraceable)]
+struct ValueWithSize {
+    value: QueuedValue,
+    size: f64,
+}
+
+/// <https://streams.spec.whatwg.org/#queue-with-sizes>
+#[derive(Default, JSTraceable, MallocSizeOf)]
+pub struct QueueWithSizes {
+    #[ignore_malloc_size_of = "Defined in mozjs"]
+    queue: VecDeque<ValueWithSize>,
+    total_size: f64,
+}
+
+impl QueueWithSizes {
+    /// <https://streams.spec.whatwg.org/#enqueue-value-with-size>
+    pub fn enqueue_value_with_size(&mut self, value: QueuedValue, size: f64) -> Fallible<()> {
+        if !size.is_finite() || size < 0. {
+            return Err(Error::Range(
+                "The size of a chunk must be a finite, non-negative number".to_owned(),
+            ));
+        }
+        self.queue.push_back(ValueWithSize { value, size });
+        self.total_size += size;
+        Ok(())
+    }
+
+    /// <https://streams.spec.whatwg.org/#dequeue-value>
+    pub fn dequeue_value(&mut self) -> QueuedValue {
+        let pair = self
+            .queue
+            .pop_front()
+            .expect("Dequeued a value from an empty queue");
+        self.total_size -= pair.size;
+        // Guard against rounding errors leaving a negative total.
+        if self.total_size < 0. {
+            self.total_size = 0.;
+        }
+        pair.value
+    }
+
+    /// <https://streams.spec.whatwg.org/#peek-queue-value>
+    pub fn peek_is_close_sentinel(&self) -> bool {
+        match self.queue.front() {
+            Some(pair) => match pair.value {
+                QueuedValue::CloseSentinel => true,
+                QueuedValue::Chunk(_) => false,
+            },
+            None => false,
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#peek-queue-value>
+    pub fn peek_chunk(&self, mut rval: MutableHandleValue) {
+        if let Some(&ValueWithSize {
+            value: QueuedValue::Chunk(ref chunk),
+            ..
+        }) = self.queue.front()
+        {
+            rval.set(chunk.get());
+        }
+    }
+
+    /// <https://streams.spec.whatwg.org/#reset-queue>
+    pub fn reset(&mut self) {
+        self.queue.clear();
+        self.total_size = 0.;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    pub fn total_size(&self) -> f64 {
+        self.total_size
+    }
+}
+
+/// <https://streams.spec.whatwg.org/#validate-and-normalize-high-water-mark>
+pub fn extract_high_water_mark(strategy: &QueuingStrategy, default_hwm: f64) -> Fallible<f64> {
+    match strategy.highWaterMark {
+        None => Ok(default_hwm),
+        Some(hwm) if hwm.is_nan() || hwm < 0. => Err(Error::Range(
+            "The high water mark must be a non-negative number".to_owned(),
+        )),
+        Some(hwm) => Ok(hwm),
+    }
+}
+
+/// Run the size algorithm of a queuing strategy on `chunk`, a missing size
+/// algorithm counting every chunk as 1.
+/// <https://streams.spec.whatwg.org/#make-size-algorithm-from-size-function>
+pub fn chunk_size(size: &Option<Rc<QueuingStrategySize>>, chunk: HandleValue) -> Fallible<f64> {
+    match *size {
+        Some(ref size) => size.Call__(chunk, ExceptionHandling::Rethrow),
+        None => Ok(1.),
+    }
+}
+
+/// Wrap `native` in a `size` function for a built-in queuing strategy.
+#[allow(unsafe_code)]
+pub fn create_size_function(global: &GlobalScope, native: JSNative) -> Rc<Function> {
+    let cx = global.get_cx();
+    unsafe {
+        let func = JS_NewFunction(*cx, native, 1, 0, b"size\0".as_ptr() as *const libc::c_char);
+        assert!(!func.is_null());
+        rooted!(in(*cx) let obj = JS_GetFunctionObject(func));
+        assert!(!obj.is_null());
+        Function::new(cx, obj.get())
+    }
+}
+
+/// Convert `error` into the JS value it would be thrown as.
+#[allow(unsafe_code)]
+pub fn error_to_jsval(global: &GlobalScope, error: Error, rval: MutableHandleValue) {
+    let cx = global.get_cx();
+    unsafe { error.to_jsval(*cx, global, rval) };
+}
+
+/// Make `value` the pending exception again, e.g. after it was taken by
+/// `error_to_jsval` to error a stream, and return the matching error.
+#[allow(unsafe_code)]
+pub fn rethrow(global: &GlobalScope, value: HandleValue) -> Error {
+    let cx = global.get_cx();
+    unsafe { JS_SetPendingException(*cx, value) };
+    Error::JSFailed
+}
+
+/// A promise resolved with `value`.
+pub fn promise_resolved_with(global: &GlobalScope, value: HandleValue) -> Rc<Promise> {
+    Promise::new_resolved(global, global.get_cx(), value).expect("Could not create a promise")
+}
+
+/// A promise resolved with undefined.
+pub fn promise_resolved_with_undefined(global: &GlobalScope) -> Rc<Promise> {
+    let cx = global.get_cx();
+    rooted!(in(*cx) let undefined = UndefinedValue());
+    promise_resolved_with(global, undefined.handle())
+}
+
+/// A promise rejected with `reason`.
+pub fn promise_rejected_with(global: &GlobalScope, reason: HandleValue) -> Rc<Promise> {
+    Promise::new_rejected(global, global.get_cx(), reason).expect("Could not create a promise")
+}
+
+/// A promise rejected with the exception corresponding to `error`.
+pub fn promise_rejected_with_error(global: &GlobalScope, error: Error) -> Rc<Promise> {
+    let cx = global.get_cx();
+    rooted!(in(*cx) let mut reason = UndefinedValue());
+    error_to_jsval(global, error, reason.handle_mut());
+    promise_rejected_with(global, reason.handle())
+}
+
+/// Wrap the completion of an underlying source, sink or transformer method
+/// in a promise, turning a thrown exception into a rejection.
+/// <https://streams.spec.whatwg.org/#promise-call>
+pub fn promise_from_callback_result(global: &GlobalScope, result: Fallible<JSVal>) -> Rc<Promise> {
+    let cx = global.get_cx();
+    match result {
+        Ok(value) => {
+            rooted!(in(*cx) let value = value);
+            promise_resolved_with(global, value.handle())
+        },
+        Err(error) => promise_rejected_with_error(global, error),
+    }
+}
+
+/// Run `on_fulfilled` or `on_rejected` once `promise` settles.
+/// <https://streams.spec.whatwg.org/#upon-settlement>
+pub fn upon_settlement(
+    global: &GlobalScope,
+    promise: &Promise,
+    on_fulfilled: Box<dyn Callback>,
+    on_rejected: Box<dyn Callback>,
+) {
+    let handler = PromiseNativeHandler::new(global, Some(on_fulfilled), Some(on_rejected));
+    promise.append_native_handler(&handler);
+}
+
+/// Set `promise.[[PromiseIsHandled]]` so that a rejection that nobody
+/// observes is not reported as unhandled.
+/// <https://streams.spec.whatwg.org/#set-promise-is-handled-to-true>
+pub fn mark_as_handled(global: &GlobalScope, promise: &Promise) {
+    let handler = PromiseNativeHandler::new(global, None, None);
+    promise.append_native_handler(&handler);
+}
+
+/// <https://streams.spec.whatwg.org/#readable-stream-create-read-result>
+#[allow(unsafe_code)]
+pub fn create_read_result(
+    global: &GlobalScope,
+    value: HandleValue,
+    done: bool,
+    rval: MutableHandleValue,
+) {
+    let cx = global.get_cx();
+    let mut result = ReadableStreamReadResult::empty();
+    result.value.set(value.get());
+    result.done = Some(done);
+    unsafe { result.to_jsval(*cx, rval) };
+}
+
+/// Resolve `promise` with a read result for `value`.
+pub fn resolve_read_request(
+    global: &GlobalScope,
+    promise: &Promise,
+    value: HandleValue,
+    done: bool,
+) {
+    let cx = global.get_cx();
+    rooted!(in(*cx) let mut result = UndefinedValue());
+    create_read_result(global, value, done, result.handle_mut());
+    promise.resolve(cx, result.handle());
+}
+
+/// Split a read result into its value, returning whether the stream is done.
+pub fn read_result_value(
+    global: &GlobalScope,
+    result: HandleValue,
+    rval: MutableHandleValue,
+) -> bool {
+    let cx = global.get_cx();
+    if !result.is_object() {
+        return true;
+    }
+    rooted!(in(*cx) let object = result.to_object());
+    rooted!(in(*cx) let mut done = UndefinedValue());
+    let _ = get_dictionary_property(*cx, object.handle(), "done", done.handle_mut());
+    let _ = get_dictionary_property(*cx, object.handle(), "value", rval);
+    done.is_boolean() && done.to_boolean()
+}
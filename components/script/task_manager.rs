@@ -4,12 +4,14 @@
 
 use crate::dom::bindings::cell::DomRefCell;
 use crate::task::TaskCanceller;
+use crate::task_source::database_access::DatabaseAccessTaskSource;
 use crate::task_source::dom_manipulation::DOMManipulationTaskSource;
 use crate::task_source::file_reading::FileReadingTaskSource;
 use crate::task_source::history_traversal::HistoryTraversalTaskSource;
 use crate::task_source::media_element::MediaElementTaskSource;
 use crate::task_source::networking::NetworkingTaskSource;
 use crate::task_source::performance_timeline::PerformanceTimelineTaskSource;
+use crate::task_source::port_message::PortMessageQueue;
 use crate::task_source::remote_event::RemoteEventTaskSource;
 use crate::task_source::user_interaction::UserInteractionTaskSource;
 use crate::task_source::websocket::WebsocketTaskSource;
@@ -35,6 +37,8 @@ pub struct TaskManager {
     #[ignore_malloc_size_of = "task sources are hard"]
     pub task_cancellers: DomRefCell<HashMap<TaskSourceName, Arc<AtomicBool>>>,
     #[ignore_malloc_size_of = "task sources are hard"]
+    database_access_task_source: DatabaseAccessTaskSource,
+    #[ignore_malloc_size_of = "task sources are hard"]
     dom_manipulation_task_source: DOMManipulationTaskSource,
     #[ignore_malloc_size_of = "task sources are hard"]
     file_reading_task_source: FileReadingTaskSource,
@@ -47,6 +51,8 @@ pub struct TaskManager {
     #[ignore_malloc_size_of = "task sources are hard"]
     performance_timeline_task_source: PerformanceTimelineTaskSource,
     #[ignore_malloc_size_of = "task sources are hard"]
+    port_message_queue: PortMessageQueue,
+    #[ignore_malloc_size_of = "task sources are hard"]
     user_interaction_task_source: UserInteractionTaskSource,
     #[ignore_malloc_size_of = "task sources are hard"]
     remote_event_task_source: RemoteEventTaskSource,
@@ -56,23 +62,27 @@ pub struct TaskManager {
 
 impl TaskManager {
     pub fn new(
+        database_access_task_source: DatabaseAccessTaskSource,
         dom_manipulation_task_source: DOMManipulationTaskSource,
         file_reading_task_source: FileReadingTaskSource,
         history_traversal_task_source: HistoryTraversalTaskSource,
         media_element_task_source: MediaElementTaskSource,
         networking_task_source: NetworkingTaskSource,
         performance_timeline_task_source: PerformanceTimelineTaskSource,
+        port_message_queue: PortMessageQueue,
         user_interaction_task_source: UserInteractionTaskSource,
         remote_event_task_source: RemoteEventTaskSource,
         websocket_task_source: WebsocketTaskSource,
     ) -> Self {
         TaskManager {
+            database_access_task_source,
             dom_manipulation_task_source,
             file_reading_task_source,
             history_traversal_task_source,
             media_element_task_source,
             networking_task_source,
             performance_timeline_task_source,
+            port_message_queue,
             user_interaction_task_source,
             remote_event_task_source,
             websocket_task_source,
@@ -80,6 +90,14 @@ impl TaskManager {
         }
     }
 
+    task_source_functions!(
+        self,
+        database_access_task_source_with_canceller,
+        database_access_task_source,
+        DatabaseAccessTaskSource,
+        DatabaseAccess
+    );
+
     task_source_functions!(
         self,
         dom_manipulation_task_source_with_canceller,
@@ -136,6 +154,14 @@ impl TaskManager {
         PerformanceTimeline
     );
 
+    task_source_functions!(
+        self,
+        port_message_queue_with_canceller,
+        port_message_queue,
+        PortMessageQueue,
+        PortMessage
+    );
+
     task_source_functions!(
         self,
         remote_event_task_source_with_canceller,
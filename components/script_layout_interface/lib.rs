@@ -140,6 +140,25 @@ pub struct PendingImage {
     pub id: PendingImageId,
 }
 
+/// The kind of a CSS animation that was started by layout.
+pub enum CSSAnimationKind {
+    /// A keyframes animation with the given name and iteration count.
+    Animation(String, f64),
+    /// A transition of the given property.
+    Transition(String),
+}
+
+/// A CSS animation or transition that layout started running, reported back to
+/// the script thread so that it can be exposed through the Web Animations API.
+pub struct NewCSSAnimation {
+    pub node: UntrustedNodeAddress,
+    pub kind: CSSAnimationKind,
+    /// The time until the animation starts, in seconds.
+    pub delay: f64,
+    /// The duration of a single iteration, in seconds.
+    pub duration: f64,
+}
+
 pub struct HTMLMediaData {
     pub current_frame: Option<(webrender_api::ImageKey, i32, i32)>,
 }
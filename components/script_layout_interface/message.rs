@@ -3,7 +3,7 @@
  * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
 
 use crate::rpc::LayoutRPC;
-use crate::{OpaqueStyleAndLayoutData, PendingImage, TrustedNodeAddress};
+use crate::{NewCSSAnimation, OpaqueStyleAndLayoutData, PendingImage, TrustedNodeAddress};
 use app_units::Au;
 use crossbeam_channel::{Receiver, Sender};
 use euclid::default::{Point2D, Rect};
@@ -23,9 +23,11 @@ use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
 use style::context::QuirksMode;
 use style::dom::OpaqueNode;
+use style::font_face::EffectiveSources;
 use style::properties::PropertyId;
 use style::selector_parser::PseudoElement;
 use style::stylesheets::Stylesheet;
+use style::values::computed::font::FamilyName;
 
 /// Asynchronous messages that script can send to layout.
 pub enum Msg {
@@ -103,6 +105,18 @@ pub enum Msg {
 
     /// Request the current number of animations that are running.
     GetRunningAnimations(IpcSender<usize>),
+
+    /// Adds a web font created from script to the font cache. The sender is told
+    /// whether the font could be loaded.
+    AddWebFont(FamilyName, WebFontSource, IpcSender<bool>),
+}
+
+/// Where the data of a web font created from script comes from.
+pub enum WebFontSource {
+    /// The sources of the `src` descriptor of the font face.
+    Sources(EffectiveSources),
+    /// The binary data of the font.
+    Data(Vec<u8>),
 }
 
 #[derive(Debug, PartialEq)]
@@ -119,6 +133,8 @@ pub enum QueryMsg {
     NodeScrollGeometryQuery(OpaqueNode),
     OffsetParentQuery(OpaqueNode),
     TextIndexQuery(OpaqueNode, Point2D<f32>),
+    /// The index of the character of a text node closest to a point, in page coordinates.
+    TextIndexAtPointQuery(OpaqueNode, Point2D<f32>),
     NodesFromPointQuery(Point2D<f32>, NodesFromPointQueryType),
 
     // FIXME(nox): The following queries use the TrustedNodeAddress to
@@ -147,6 +163,7 @@ impl ReflowGoal {
             ReflowGoal::LayoutQuery(ref querymsg, _) => match *querymsg {
                 QueryMsg::NodesFromPointQuery(..) |
                 QueryMsg::TextIndexQuery(..) |
+                QueryMsg::TextIndexAtPointQuery(..) |
                 QueryMsg::ElementInnerTextQuery(_) => true,
                 QueryMsg::ContentBoxQuery(_) |
                 QueryMsg::ContentBoxesQuery(_) |
@@ -168,6 +185,7 @@ impl ReflowGoal {
             ReflowGoal::LayoutQuery(ref querymsg, _) => match *querymsg {
                 QueryMsg::NodesFromPointQuery(..) |
                 QueryMsg::TextIndexQuery(..) |
+                QueryMsg::TextIndexAtPointQuery(..) |
                 QueryMsg::ElementInnerTextQuery(_) => true,
                 QueryMsg::ContentBoxQuery(_) |
                 QueryMsg::ContentBoxesQuery(_) |
@@ -195,6 +213,8 @@ pub struct ReflowComplete {
     pub pending_images: Vec<PendingImage>,
     /// The list of nodes that initiated a CSS transition.
     pub newly_transitioning_nodes: Vec<UntrustedNodeAddress>,
+    /// The CSS animations and transitions that started running.
+    pub new_css_animations: Vec<NewCSSAnimation>,
 }
 
 /// Information needed for a script-initiated reflow.
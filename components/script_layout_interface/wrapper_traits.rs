@@ -191,6 +191,11 @@ pub trait ThreadSafeLayoutNode:
     /// the parent until all the children have been processed.
     fn parent_style(&self) -> Arc<ComputedValues>;
 
+    /// Returns the style of the `::selection` pseudo-element of the parent of
+    /// this node, falling back to its primary style. Subject to the same
+    /// caveats as `parent_style`.
+    fn parent_selected_style(&self) -> Arc<ComputedValues>;
+
     fn get_before_pseudo(&self) -> Option<Self> {
         self.as_element()
             .and_then(|el| el.get_before_pseudo())
@@ -248,8 +253,7 @@ pub trait ThreadSafeLayoutNode:
             el.selected_style()
         } else {
             debug_assert!(self.is_text_node());
-            // TODO(stshine): What should the selected style be for text?
-            self.parent_style()
+            self.parent_selected_style()
         }
     }
 
@@ -17,8 +17,10 @@ extern crate malloc_size_of_derive;
 extern crate serde;
 
 mod script_msg;
+pub mod transferable;
 pub mod webdriver_msg;
 
+use crate::transferable::{ImageBitmapImpl, MessagePortImpl, OffscreenCanvasImpl};
 use crate::webdriver_msg::{LoadStatus, WebDriverScriptCommand};
 use bluetooth_traits::BluetoothRequest;
 use canvas_traits::webgl::WebGLPipeline;
@@ -36,7 +38,7 @@ use keyboard_types::{CompositionEvent, KeyboardEvent};
 use libc::c_void;
 use media::WindowGLContext;
 use msg::constellation_msg::BackgroundHangMonitorRegister;
-use msg::constellation_msg::{BrowsingContextId, HistoryStateId, PipelineId};
+use msg::constellation_msg::{BrowsingContextId, HistoryStateId, MessagePortId, PipelineId};
 use msg::constellation_msg::{PipelineNamespaceId, TopLevelBrowsingContextId, TraversalDirection};
 use net_traits::image::base::Image;
 use net_traits::image_cache::ImageCache;
@@ -51,7 +53,7 @@ use servo_atoms::Atom;
 use servo_url::ImmutableOrigin;
 use servo_url::ServoUrl;
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::fmt;
 use std::sync::atomic::AtomicBool;
 use std::sync::Arc;
@@ -325,7 +327,7 @@ pub enum ConstellationControlMsg {
         /// The expected origin of the target.
         target_origin: Option<ImmutableOrigin>,
         /// The data to be posted.
-        data: Vec<u8>,
+        data: StructuredSerializedData,
     },
     /// Updates the current pipeline ID of a given iframe.
     /// First PipelineId is for the parent, second is the new PipelineId for the frame.
@@ -1013,3 +1015,60 @@ impl ScriptToConstellationChan {
         self.sender.send((self.pipeline_id, msg))
     }
 }
+
+/// The result of a structured serialization, along with the data of the
+/// objects that were transferred with it.
+///
+/// <https://html.spec.whatwg.org/multipage/#structuredserializewithtransfer>
+#[derive(Debug, Deserialize, Serialize)]
+pub struct StructuredSerializedData {
+    /// Data serialized by SpiderMonkey.
+    pub serialized: Vec<u8>,
+    /// Transferred ports.
+    pub ports: Option<HashMap<MessagePortId, MessagePortImpl>>,
+    /// Transferred image bitmaps, keyed by the index recorded in the serialized data.
+    pub image_bitmaps: Option<HashMap<u64, ImageBitmapImpl>>,
+    /// Transferred offscreen canvases, keyed by the index recorded in the serialized data.
+    pub offscreen_canvases: Option<HashMap<u64, OffscreenCanvasImpl>>,
+}
+
+impl StructuredSerializedData {
+    /// Serialized data that has no transferred objects.
+    pub fn without_transfer(serialized: Vec<u8>) -> StructuredSerializedData {
+        StructuredSerializedData {
+            serialized,
+            ports: None,
+            image_bitmaps: None,
+            offscreen_canvases: None,
+        }
+    }
+}
+
+/// A task on the <https://html.spec.whatwg.org/multipage/#port-message-queue>
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PortMessageTask {
+    /// A data-holder for serialized data and transferred objects.
+    pub data: StructuredSerializedData,
+}
+
+/// Messages from the constellation to the message-port router of a global.
+#[derive(Debug, Deserialize, Serialize)]
+pub enum MessagePortMsg {
+    /// A port that was transferred to this global is now managed by it; these
+    /// messages were buffered by the constellation while it was in transit.
+    CompleteTransfer(MessagePortId, VecDeque<PortMessageTask>),
+    /// A new task for a port managed by this global.
+    NewTask(MessagePortId, PortMessageTask),
+}
+
+/// A message posted to a `BroadcastChannel`, routed by the constellation to
+/// the globals with channels of the same name and origin.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct BroadcastMsg {
+    /// The origin of this message.
+    pub origin: ImmutableOrigin,
+    /// The name of the channel.
+    pub channel_name: String,
+    /// A data-holder for serialized data.
+    pub data: StructuredSerializedData,
+}
@@ -0,0 +1,50 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! This module contains the data of objects that can be transferred between
+//! globals. They live here instead of in script so that the constellation,
+//! which routes the transfers, doesn't have to depend on script.
+
+use crate::PortMessageTask;
+use canvas_traits::canvas::{CanvasId, CanvasMsg};
+use ipc_channel::ipc::IpcSender;
+use msg::constellation_msg::MessagePortId;
+use std::collections::VecDeque;
+
+/// The state of a `MessagePort` while it is on its way to another global.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct MessagePortImpl {
+    /// The id of the transferred port.
+    pub message_port_id: MessagePortId,
+    /// <https://html.spec.whatwg.org/multipage/#entangle>
+    pub entangled_port: Option<MessagePortId>,
+    /// Messages the port received, but which were not dispatched yet because
+    /// its port message queue was disabled.
+    pub message_buffer: VecDeque<PortMessageTask>,
+}
+
+/// The state of an `ImageBitmap` while it is on its way to another global.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ImageBitmapImpl {
+    /// The width of the bitmap, in pixels.
+    pub width: u32,
+    /// The height of the bitmap, in pixels.
+    pub height: u32,
+    /// The bitmap data, as premultiplied BGRA.
+    pub data: Vec<u8>,
+    /// <https://html.spec.whatwg.org/multipage/#concept-imagebitmap-origin-clean>
+    pub origin_clean: bool,
+}
+
+/// The state of an `OffscreenCanvas` while it is on its way to another global.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct OffscreenCanvasImpl {
+    /// The width of the canvas, in pixels.
+    pub width: u64,
+    /// The height of the canvas, in pixels.
+    pub height: u64,
+    /// The canvas paint thread and id of the canvas backing the placeholder
+    /// `<canvas>` element, if the canvas has one.
+    pub placeholder: Option<(IpcSender<CanvasMsg>, CanvasId)>,
+}
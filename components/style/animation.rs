@@ -15,7 +15,7 @@ use crate::font_metrics::FontMetricsProvider;
 use crate::properties::animated_properties::AnimatedProperty;
 use crate::properties::longhands::animation_direction::computed_value::single_value::T as AnimationDirection;
 use crate::properties::longhands::animation_play_state::computed_value::single_value::T as AnimationPlayState;
-use crate::properties::{self, CascadeMode, ComputedValues, LonghandId};
+use crate::properties::{self, CascadeMode, ComputedValues, LonghandId, PropertyDeclaration};
 use crate::rule_tree::CascadeLevel;
 use crate::stylesheets::keyframes_rule::{KeyframesAnimation, KeyframesStep, KeyframesStepValue};
 use crate::timer::Timer;
@@ -29,6 +29,7 @@ use crate::Atom;
 use crossbeam_channel::Sender;
 use servo_arc::Arc;
 use std::fmt;
+use std::iter;
 #[cfg(feature = "gecko")]
 use std::sync::mpsc::Sender;
 
@@ -342,51 +343,7 @@ impl PropertyAnimation {
     /// Update the given animation at a given point of progress.
     pub fn update(&self, style: &mut ComputedValues, time: f64) {
         let epsilon = 1. / (200. * (self.duration.seconds() as f64));
-        let progress = match self.timing_function {
-            GenericTimingFunction::CubicBezier { x1, y1, x2, y2 } => {
-                Bezier::new(x1, y1, x2, y2).solve(time, epsilon)
-            },
-            GenericTimingFunction::Steps(steps, pos) => {
-                let mut current_step = (time * (steps as f64)).floor() as i32;
-
-                if pos == StepPosition::Start ||
-                    pos == StepPosition::JumpStart ||
-                    pos == StepPosition::JumpBoth
-                {
-                    current_step = current_step + 1;
-                }
-
-                // FIXME: We should update current_step according to the "before flag".
-                // In order to get the before flag, we have to know the current animation phase
-                // and whether the iteration is reversed. For now, we skip this calculation.
-                // (i.e. Treat before_flag is unset,)
-                // https://drafts.csswg.org/css-easing/#step-timing-function-algo
-
-                if time >= 0.0 && current_step < 0 {
-                    current_step = 0;
-                }
-
-                let jumps = match pos {
-                    StepPosition::JumpBoth => steps + 1,
-                    StepPosition::JumpNone => steps - 1,
-                    StepPosition::JumpStart |
-                    StepPosition::JumpEnd |
-                    StepPosition::Start |
-                    StepPosition::End => steps,
-                };
-
-                if time <= 1.0 && current_step > jumps {
-                    current_step = jumps;
-                }
-
-                (current_step as f64) / (jumps as f64)
-            },
-            GenericTimingFunction::Keyword(keyword) => {
-                let (x1, x2, y1, y2) = keyword.to_bezier();
-                Bezier::new(x1, x2, y1, y2).solve(time, epsilon)
-            },
-        };
-
+        let progress = timing_function_output(self.timing_function, time, epsilon);
         self.property.update(style, progress);
     }
 
@@ -402,6 +359,75 @@ impl PropertyAnimation {
     }
 }
 
+/// A property of a keyframe effect driven from script through the Web
+/// Animations API, sampled at the current time.
+///
+/// The script thread runs the timing model and picks the keyframes surrounding
+/// the current iteration progress, so all that is left here is computing both
+/// keyframe values and interpolating between them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyframeEffectSample {
+    /// The longhand being animated.
+    pub property: LonghandId,
+    /// The declaration of the keyframe the property animates from, or `None`
+    /// to animate from the underlying value.
+    pub from: Option<PropertyDeclaration>,
+    /// The declaration of the keyframe the property animates to, or `None`
+    /// to animate to the underlying value.
+    pub to: Option<PropertyDeclaration>,
+    /// The progress between both keyframes, with easing already applied.
+    pub progress: f64,
+}
+
+/// Computes the output progress of a timing function at the given input
+/// progress, `epsilon` being the precision used when solving bezier curves.
+pub fn timing_function_output(timing_function: TimingFunction, time: f64, epsilon: f64) -> f64 {
+    match timing_function {
+        GenericTimingFunction::CubicBezier { x1, y1, x2, y2 } => {
+            Bezier::new(x1, y1, x2, y2).solve(time, epsilon)
+        },
+        GenericTimingFunction::Steps(steps, pos) => {
+            let mut current_step = (time * (steps as f64)).floor() as i32;
+
+            if pos == StepPosition::Start ||
+                pos == StepPosition::JumpStart ||
+                pos == StepPosition::JumpBoth
+            {
+                current_step = current_step + 1;
+            }
+
+            // FIXME: We should update current_step according to the "before flag".
+            // In order to get the before flag, we have to know the current animation phase
+            // and whether the iteration is reversed. For now, we skip this calculation.
+            // (i.e. Treat before_flag is unset,)
+            // https://drafts.csswg.org/css-easing/#step-timing-function-algo
+
+            if time >= 0.0 && current_step < 0 {
+                current_step = 0;
+            }
+
+            let jumps = match pos {
+                StepPosition::JumpBoth => steps + 1,
+                StepPosition::JumpNone => steps - 1,
+                StepPosition::JumpStart |
+                StepPosition::JumpEnd |
+                StepPosition::Start |
+                StepPosition::End => steps,
+            };
+
+            if time <= 1.0 && current_step > jumps {
+                current_step = jumps;
+            }
+
+            (current_step as f64) / (jumps as f64)
+        },
+        GenericTimingFunction::Keyword(keyword) => {
+            let (x1, x2, y1, y2) = keyword.to_bezier();
+            Bezier::new(x1, x2, y1, y2).solve(time, epsilon)
+        },
+    }
+}
+
 /// Inserts transitions into the queue of running animations as applicable for
 /// the given style difference. This is called from the layout worker threads.
 /// Returns true if any animations were kicked off and false otherwise.
@@ -519,6 +545,79 @@ where
     }
 }
 
+fn compute_style_for_keyframe_value<E>(
+    context: &SharedStyleContext,
+    declaration: Option<&PropertyDeclaration>,
+    style: &Arc<ComputedValues>,
+    font_metrics_provider: &dyn FontMetricsProvider,
+) -> Arc<ComputedValues>
+where
+    E: TElement,
+{
+    let declaration = match declaration {
+        Some(declaration) => declaration,
+        None => return style.clone(),
+    };
+
+    properties::apply_declarations::<E, _, _>(
+        context.stylist.device(),
+        /* pseudo = */ None,
+        style.rules(),
+        &context.guards,
+        || iter::once((declaration, CascadeLevel::Animations)),
+        Some(&**style),
+        Some(&**style),
+        Some(&**style),
+        font_metrics_provider,
+        CascadeMode::Unvisited {
+            visited_rules: None,
+        },
+        context.quirks_mode(),
+        /* rule_cache = */ None,
+        &mut Default::default(),
+        /* element = */ None,
+    )
+}
+
+/// Updates a computed style with the current samples of the keyframe effects
+/// that script is running on the element, in composite order.
+pub fn update_style_for_keyframe_effect_samples<E>(
+    context: &SharedStyleContext,
+    samples: &[KeyframeEffectSample],
+    style: &mut Arc<ComputedValues>,
+    font_metrics_provider: &dyn FontMetricsProvider,
+) where
+    E: TElement,
+{
+    for sample in samples {
+        let from_style = compute_style_for_keyframe_value::<E>(
+            context,
+            sample.from.as_ref(),
+            style,
+            font_metrics_provider,
+        );
+        let to_style = compute_style_for_keyframe_value::<E>(
+            context,
+            sample.to.as_ref(),
+            style,
+            font_metrics_provider,
+        );
+
+        let property =
+            match AnimatedProperty::from_longhand(sample.property, &from_style, &to_style) {
+                Some(property) => property,
+                None => {
+                    debug!(
+                        "update_style_for_keyframe_effect_samples: {:?} is not animatable",
+                        sample.property
+                    );
+                    continue;
+                },
+            };
+        property.update(Arc::make_mut(style), sample.progress);
+    }
+}
+
 /// Triggers animations for a given node looking at the animation property
 /// values.
 pub fn maybe_start_animations<E>(
@@ -7,6 +7,7 @@
 #![allow(unsafe_code)]
 #![deny(missing_docs)]
 
+use crate::animation::KeyframeEffectSample;
 use crate::applicable_declarations::ApplicableDeclarationBlock;
 #[cfg(feature = "gecko")]
 use crate::context::PostAnimationTasks;
@@ -514,6 +515,12 @@ pub trait TElement:
         None
     }
 
+    /// Get the samples of the keyframe effects script runs on this element,
+    /// in composite order.
+    fn keyframe_effect_samples(&self) -> &[KeyframeEffectSample] {
+        &[]
+    }
+
     /// Get this element's state, for non-tree-structural pseudos.
     fn state(&self) -> ElementState;
 
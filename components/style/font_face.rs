@@ -324,24 +324,28 @@ pub struct EffectiveSources(Vec<Source>);
 
 #[cfg(feature = "servo")]
 impl<'a> FontFace<'a> {
-    /// Returns the list of effective sources for that font-face, that is the
-    /// sources which don't list any format hint, or the ones which list at
-    /// least "truetype" or "opentype".
+    /// Returns the list of effective sources for that font-face.
     pub fn effective_sources(&self) -> EffectiveSources {
+        EffectiveSources::new(self.sources())
+    }
+}
+
+#[cfg(feature = "servo")]
+impl EffectiveSources {
+    /// Returns the effective sources among the given ones, that is the sources
+    /// which don't list any format hint, or the ones which list at least one
+    /// supported format.
+    pub fn new(sources: &[Source]) -> Self {
         EffectiveSources(
-            self.sources()
+            sources
                 .iter()
                 .rev()
                 .filter(|source| {
                     if let Source::Url(ref url_source) = **source {
                         let hints = &url_source.format_hints;
-                        // We support only opentype fonts and truetype is an alias for
-                        // that format. Sources without format hints need to be
-                        // downloaded in case we support them.
-                        hints.is_empty() ||
-                            hints.iter().any(|hint| {
-                                hint == "truetype" || hint == "opentype" || hint == "woff"
-                            })
+                        // Sources without format hints need to be downloaded in
+                        // case we support them.
+                        hints.is_empty() || hints.iter().any(|hint| is_supported_format(hint))
                     } else {
                         true
                     }
@@ -352,6 +356,19 @@ impl<'a> FontFace<'a> {
     }
 }
 
+/// Whether the given format hint names a font format we can load.
+///
+/// We support only opentype fonts, which can be wrapped in WOFF or WOFF2, and
+/// truetype is an alias for that format.
+///
+/// <https://drafts.csswg.org/css-fonts/#font-format-values>
+#[cfg(feature = "servo")]
+fn is_supported_format(hint: &str) -> bool {
+    ["truetype", "opentype", "woff", "woff2"]
+        .iter()
+        .any(|format| hint.eq_ignore_ascii_case(format))
+}
+
 #[cfg(feature = "servo")]
 impl Iterator for EffectiveSources {
     type Item = Source;
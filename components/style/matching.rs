@@ -439,6 +439,16 @@ trait PrivateMatchMethods: TElement {
 
         let mut possibly_expired_animations = vec![];
         let shared_context = context.shared;
+
+        // Apply the animations script runs through the Web Animations API
+        // first, so that they don't trigger transitions.
+        animation::update_style_for_keyframe_effect_samples::<Self>(
+            shared_context,
+            self.keyframe_effect_samples(),
+            new_values,
+            &context.thread_local.font_metrics_provider,
+        );
+
         if let Some(ref mut old) = *old_values {
             // FIXME(emilio, #20116): This makes no sense.
             self.update_animations_for_cascade(
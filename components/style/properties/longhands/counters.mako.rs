@@ -11,7 +11,6 @@ ${helpers.predefined_type(
     "Content",
     "computed::Content::normal()",
     engines="gecko servo-2013 servo-2020",
-    servo_2020_pref="layout.2020.unimplemented",
     initial_specified_value="specified::Content::normal()",
     animation_value_type="discrete",
     spec="https://drafts.csswg.org/css-content/#propdef-content",
@@ -22,7 +22,7 @@ ${helpers.single_keyword(
 ${helpers.single_keyword(
     "text-anchor",
     "start middle end",
-    engines="gecko",
+    engines="gecko servo-2013",
     animation_value_type="discrete",
     spec="https://www.w3.org/TR/SVG/text.html#TextAnchorProperty",
 )}
@@ -49,7 +49,7 @@ ${helpers.predefined_type(
     "fill",
     "SVGPaint",
     "crate::values::computed::SVGPaint::black()",
-    engines="gecko",
+    engines="gecko servo-2013",
     animation_value_type="IntermediateSVGPaint",
     boxed=True,
     spec="https://www.w3.org/TR/SVG2/painting.html#SpecifyingFillPaint",
@@ -59,7 +59,7 @@ ${helpers.predefined_type(
     "fill-opacity",
     "SVGOpacity",
     "Default::default()",
-    engines="gecko",
+    engines="gecko servo-2013",
     animation_value_type="ComputedValue",
     spec="https://svgwg.org/svg2-draft/painting.html#FillOpacity",
 )}
@@ -68,7 +68,7 @@ ${helpers.predefined_type(
     "fill-rule",
     "FillRule",
     "Default::default()",
-    engines="gecko",
+    engines="gecko servo-2013",
     needs_context=False,
     animation_value_type="discrete",
     spec="https://www.w3.org/TR/SVG11/painting.html#FillRuleProperty",
@@ -86,7 +86,7 @@ ${helpers.predefined_type(
     "stroke",
     "SVGPaint",
     "Default::default()",
-    engines="gecko",
+    engines="gecko servo-2013",
     animation_value_type="IntermediateSVGPaint",
     boxed=True,
     spec="https://www.w3.org/TR/SVG2/painting.html#SpecifyingStrokePaint",
@@ -96,7 +96,7 @@ ${helpers.predefined_type(
     "stroke-width",
     "SVGWidth",
     "computed::SVGWidth::one()",
-    engines="gecko",
+    engines="gecko servo-2013",
     animation_value_type="crate::values::computed::SVGWidth",
     spec="https://www.w3.org/TR/SVG2/painting.html#StrokeWidth",
 )}
@@ -104,7 +104,7 @@ ${helpers.predefined_type(
 ${helpers.single_keyword(
     "stroke-linecap",
     "butt round square",
-    engines="gecko",
+    engines="gecko servo-2013",
     animation_value_type="discrete",
     spec="https://www.w3.org/TR/SVG11/painting.html#StrokeLinecapProperty",
 )}
@@ -112,7 +112,7 @@ ${helpers.single_keyword(
 ${helpers.single_keyword(
     "stroke-linejoin",
     "miter round bevel",
-    engines="gecko",
+    engines="gecko servo-2013",
     animation_value_type="discrete",
     spec="https://www.w3.org/TR/SVG11/painting.html#StrokeLinejoinProperty",
 )}
@@ -121,7 +121,7 @@ ${helpers.predefined_type(
     "stroke-miterlimit",
     "NonNegativeNumber",
     "From::from(4.0)",
-    engines="gecko",
+    engines="gecko servo-2013",
     animation_value_type="crate::values::computed::NonNegativeNumber",
     spec="https://www.w3.org/TR/SVG2/painting.html#StrokeMiterlimitProperty",
 )}
@@ -130,7 +130,7 @@ ${helpers.predefined_type(
     "stroke-opacity",
     "SVGOpacity",
     "Default::default()",
-    engines="gecko",
+    engines="gecko servo-2013",
     animation_value_type="ComputedValue",
     spec="https://svgwg.org/svg2-draft/painting.html#StrokeOpacity",
 )}
@@ -139,7 +139,7 @@ ${helpers.predefined_type(
     "stroke-dasharray",
     "SVGStrokeDashArray",
     "Default::default()",
-    engines="gecko",
+    engines="gecko servo-2013",
     animation_value_type="crate::values::computed::SVGStrokeDashArray",
     spec="https://www.w3.org/TR/SVG2/painting.html#StrokeDashing",
 )}
@@ -148,7 +148,7 @@ ${helpers.predefined_type(
     "stroke-dashoffset",
     "SVGLength",
     "computed::SVGLength::zero()",
-    engines="gecko",
+    engines="gecko servo-2013",
     animation_value_type="ComputedValue",
     spec="https://www.w3.org/TR/SVG2/painting.html#StrokeDashing",
 )}
@@ -15,7 +15,6 @@
         "LengthPercentageOrAuto",
         "computed::LengthPercentageOrAuto::auto()",
         engines="gecko servo-2013 servo-2020",
-        servo_2020_pref="layout.2020.unimplemented",
         spec="https://www.w3.org/TR/CSS2/visuren.html#propdef-%s" % side,
         animation_value_type="ComputedValue",
         allow_quirks="Yes",
@@ -30,7 +29,6 @@
         "LengthPercentageOrAuto",
         "computed::LengthPercentageOrAuto::auto()",
         engines="gecko servo-2013 servo-2020",
-        servo_2020_pref="layout.2020.unimplemented",
         spec="https://drafts.csswg.org/css-logical-props/#propdef-inset-%s" % side,
         alias="offset-%s:layout.css.offset-logical-properties.enabled" % side,
         animation_value_type="ComputedValue",
@@ -75,7 +73,7 @@ ${helpers.predefined_type(
 ${helpers.single_keyword(
     "flex-direction",
     "row row-reverse column column-reverse",
-    engines="gecko servo-2013",
+    engines="gecko servo-2013 servo-2020",
     spec="https://drafts.csswg.org/css-flexbox/#flex-direction-property",
     extra_prefixes="webkit",
     animation_value_type="discrete",
@@ -86,19 +84,19 @@ ${helpers.single_keyword(
 ${helpers.single_keyword(
     "flex-wrap",
     "nowrap wrap wrap-reverse",
-    engines="gecko servo-2013",
+    engines="gecko servo-2013 servo-2020",
     spec="https://drafts.csswg.org/css-flexbox/#flex-wrap-property",
     extra_prefixes="webkit",
     animation_value_type="discrete",
     servo_restyle_damage = "reflow",
 )}
 
-% if engine == "servo-2013":
+% if engine in ["servo-2013", "servo-2020"]:
     // FIXME: Update Servo to support the same Syntax as Gecko.
     ${helpers.single_keyword(
         "justify-content",
         "flex-start stretch flex-end center space-between space-around",
-        engines="servo-2013",
+        engines="servo-2013 servo-2020",
         extra_prefixes="webkit",
         spec="https://drafts.csswg.org/css-align/#propdef-justify-content",
         animation_value_type="discrete",
@@ -123,7 +121,7 @@ ${helpers.single_keyword(
     ${helpers.single_keyword(
         "align-content",
         "stretch flex-start flex-end center space-between space-around",
-        engines="servo-2013",
+        engines="servo-2013 servo-2020",
         extra_prefixes="webkit",
         spec="https://drafts.csswg.org/css-align/#propdef-align-content",
         animation_value_type="discrete",
@@ -134,13 +132,23 @@ ${helpers.single_keyword(
         "align-items",
         "stretch flex-start flex-end center baseline",
         engines="servo-2013 servo-2020",
-        servo_2020_pref="layout.2020.unimplemented",
         extra_prefixes="webkit",
         spec="https://drafts.csswg.org/css-flexbox/#align-items-property",
         animation_value_type="discrete",
         servo_restyle_damage="reflow",
     )}
 % endif
+% if engine == "servo-2013":
+    // FIXME: Update Servo to support the same syntax as Gecko.
+    ${helpers.single_keyword(
+        "justify-items",
+        "stretch start end center",
+        engines="servo-2013",
+        spec="https://drafts.csswg.org/css-align/#propdef-justify-items",
+        animation_value_type="discrete",
+        servo_restyle_damage="reflow",
+    )}
+% endif
 % if engine == "gecko":
     ${helpers.predefined_type(
         "align-content",
@@ -185,7 +193,7 @@ ${helpers.predefined_type(
     "flex-grow",
     "NonNegativeNumber",
     "From::from(0.0)",
-    engines="gecko servo-2013",
+    engines="gecko servo-2013 servo-2020",
     spec="https://drafts.csswg.org/css-flexbox/#flex-grow-property",
     extra_prefixes="webkit",
     animation_value_type="NonNegativeNumber",
@@ -196,7 +204,7 @@ ${helpers.predefined_type(
     "flex-shrink",
     "NonNegativeNumber",
     "From::from(1.0)",
-    engines="gecko servo-2013",
+    engines="gecko servo-2013 servo-2020",
     spec="https://drafts.csswg.org/css-flexbox/#flex-shrink-property",
     extra_prefixes="webkit",
     animation_value_type="NonNegativeNumber",
@@ -210,13 +218,23 @@ ${helpers.predefined_type(
         "align-self",
         "auto stretch flex-start flex-end center baseline",
         engines="servo-2013 servo-2020",
-        servo_2020_pref="layout.2020.unimplemented",
         extra_prefixes="webkit",
         spec="https://drafts.csswg.org/css-flexbox/#propdef-align-self",
         animation_value_type="discrete",
         servo_restyle_damage = "reflow",
     )}
 % endif
+% if engine == "servo-2013":
+    // FIXME: Update Servo to support the same syntax as Gecko.
+    ${helpers.single_keyword(
+        "justify-self",
+        "auto stretch start end center",
+        engines="servo-2013",
+        spec="https://drafts.csswg.org/css-align/#justify-self-property",
+        animation_value_type="discrete",
+        servo_restyle_damage="reflow",
+    )}
+% endif
 % if engine == "gecko":
     ${helpers.predefined_type(
         "align-self",
@@ -246,7 +264,7 @@ ${helpers.predefined_type(
     "order",
     "Integer",
     "0",
-    engines="gecko servo-2013",
+    engines="gecko servo-2013 servo-2020",
     extra_prefixes="webkit",
     animation_value_type="ComputedValue",
     spec="https://drafts.csswg.org/css-flexbox/#order-property",
@@ -257,7 +275,7 @@ ${helpers.predefined_type(
     "flex-basis",
     "FlexBasis",
     "computed::FlexBasis::auto()",
-    engines="gecko servo-2013",
+    engines="gecko servo-2013 servo-2020",
     spec="https://drafts.csswg.org/css-flexbox/#flex-basis-property",
     extra_prefixes="webkit",
     animation_value_type="FlexBasis",
@@ -277,7 +295,6 @@ ${helpers.predefined_type(
         "Size",
         "computed::Size::auto()",
         engines="gecko servo-2013 servo-2020",
-        servo_2020_pref="layout.2020.unimplemented",
         logical=logical,
         logical_group="size",
         allow_quirks="No" if logical else "Yes",
@@ -291,7 +308,6 @@ ${helpers.predefined_type(
         "Size",
         "computed::Size::auto()",
         engines="gecko servo-2013 servo-2020",
-        servo_2020_pref="layout.2020.unimplemented",
         logical=logical,
         logical_group="min-size",
         allow_quirks="No" if logical else "Yes",
@@ -304,7 +320,6 @@ ${helpers.predefined_type(
         "MaxSize",
         "computed::MaxSize::none()",
         engines="gecko servo-2013 servo-2020",
-        servo_2020_pref="layout.2020.unimplemented",
         logical=logical,
         logical_group="max-size",
         allow_quirks="No" if logical else "Yes",
@@ -318,7 +333,6 @@ ${helpers.single_keyword(
     "box-sizing",
     "content-box border-box",
     engines="gecko servo-2013 servo-2020",
-    servo_2020_pref="layout.2020.unimplemented",
     extra_prefixes="moz:layout.css.prefixes.box-sizing webkit",
     spec="https://drafts.csswg.org/css-ui/#propdef-box-sizing",
     gecko_enum_prefix="StyleBoxSizing",
@@ -330,7 +344,7 @@ ${helpers.single_keyword(
 ${helpers.single_keyword(
     "object-fit",
     "fill contain cover none scale-down",
-    engines="gecko",
+    engines="gecko servo-2013",
     animation_value_type="discrete",
     spec="https://drafts.csswg.org/css-images/#propdef-object-fit",
 )}
@@ -339,7 +353,7 @@ ${helpers.predefined_type(
     "object-position",
     "Position",
     "computed::Position::zero()",
-    engines="gecko",
+    engines="gecko servo-2013",
     boxed=True,
     spec="https://drafts.csswg.org/css-images-3/#the-object-position",
     animation_value_type="ComputedValue",
@@ -351,8 +365,9 @@ ${helpers.predefined_type(
             "grid-%s-%s" % (kind, range),
             "GridLine",
             "Default::default()",
-            engines="gecko",
+            engines="gecko servo-2013",
             animation_value_type="discrete",
+            servo_restyle_damage="reflow",
             spec="https://drafts.csswg.org/css-grid/#propdef-grid-%s-%s" % (kind, range),
         )}
     % endfor
@@ -361,8 +376,9 @@ ${helpers.predefined_type(
         "grid-auto-%ss" % kind,
         "ImplicitGridTracks",
         "Default::default()",
-        engines="gecko",
+        engines="gecko servo-2013",
         animation_value_type="discrete",
+        servo_restyle_damage="reflow",
         spec="https://drafts.csswg.org/css-grid/#propdef-grid-auto-%ss" % kind,
     )}
 
@@ -370,9 +386,10 @@ ${helpers.predefined_type(
         "grid-template-%ss" % kind,
         "GridTemplateComponent",
         "specified::GenericGridTemplateComponent::None",
-        engines="gecko",
+        engines="gecko servo-2013",
         spec="https://drafts.csswg.org/css-grid/#propdef-grid-template-%ss" % kind,
         animation_value_type="ComputedValue",
+        servo_restyle_damage="reflow",
     )}
 
 % endfor
@@ -381,8 +398,9 @@ ${helpers.predefined_type(
     "grid-auto-flow",
     "GridAutoFlow",
     "computed::GridAutoFlow::row()",
-    engines="gecko",
+    engines="gecko servo-2013",
     animation_value_type="discrete",
+    servo_restyle_damage="reflow",
     spec="https://drafts.csswg.org/css-grid/#propdef-grid-auto-flow",
 )}
 
@@ -390,8 +408,9 @@ ${helpers.predefined_type(
     "grid-template-areas",
     "GridTemplateAreas",
     "computed::GridTemplateAreas::none()",
-    engines="gecko",
+    engines="gecko servo-2013",
     animation_value_type="discrete",
+    servo_restyle_damage="reflow",
     spec="https://drafts.csswg.org/css-grid/#propdef-grid-template-areas",
 )}
 
@@ -413,7 +432,7 @@ ${helpers.predefined_type(
     "row-gap",
     "length::NonNegativeLengthPercentageOrNormal",
     "computed::length::NonNegativeLengthPercentageOrNormal::normal()",
-    engines="gecko",
+    engines="gecko servo-2013",
     alias="grid-row-gap",
     spec="https://drafts.csswg.org/css-align-3/#propdef-row-gap",
     animation_value_type="NonNegativeLengthPercentageOrNormal",
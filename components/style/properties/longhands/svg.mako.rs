@@ -20,7 +20,7 @@ ${helpers.predefined_type(
     "stop-color",
     "Color",
     "RGBA::new(0, 0, 0, 255).into()",
-    engines="gecko",
+    engines="gecko servo-2013",
     animation_value_type="AnimatedRGBA",
     spec="https://www.w3.org/TR/SVGTiny12/painting.html#StopColorProperty",
 )}
@@ -29,7 +29,7 @@ ${helpers.predefined_type(
     "stop-opacity",
     "Opacity",
     "1.0",
-    engines="gecko",
+    engines="gecko servo-2013",
     animation_value_type="ComputedValue",
     spec="https://svgwg.org/svg2-draft/pservers.html#StopOpacityProperty",
 )}
@@ -77,7 +77,7 @@ ${helpers.predefined_type(
     "clip-path",
     "basic_shape::ClippingShape",
     "generics::basic_shape::ShapeSource::None",
-    engines="gecko",
+    engines="gecko servo-2013",
     animation_value_type="basic_shape::ClippingShape",
     flags="CREATES_STACKING_CONTEXT",
     spec="https://drafts.fxtf.org/css-masking/#propdef-clip-path",
@@ -86,7 +86,7 @@ ${helpers.predefined_type(
 ${helpers.single_keyword(
     "mask-mode",
     "match-source alpha luminance",
-    engines="gecko",
+    engines="gecko servo-2013",
     gecko_enum_prefix="StyleMaskMode",
     vector=True,
     animation_value_type="discrete",
@@ -97,7 +97,7 @@ ${helpers.predefined_type(
     "mask-repeat",
     "BackgroundRepeat",
     "computed::BackgroundRepeat::repeat()",
-    engines="gecko",
+    engines="gecko servo-2013",
     initial_specified_value="specified::BackgroundRepeat::repeat()",
     extra_prefixes="webkit",
     animation_value_type="discrete",
@@ -110,7 +110,7 @@ ${helpers.predefined_type(
         "mask-position-" + axis,
         "position::" + direction + "Position",
         "computed::LengthPercentage::zero()",
-        engines="gecko",
+        engines="gecko servo-2013",
         extra_prefixes="webkit",
         initial_specified_value="specified::PositionComponent::Center",
         spec="https://drafts.fxtf.org/css-masking/#propdef-mask-position",
@@ -123,7 +123,7 @@ ${helpers.predefined_type(
 ${helpers.single_keyword(
     "mask-clip",
     "border-box content-box padding-box",
-    engines="gecko",
+    engines="gecko servo-2013",
     extra_gecko_values="fill-box stroke-box view-box no-clip",
     vector=True,
     extra_prefixes="webkit",
@@ -136,7 +136,7 @@ ${helpers.single_keyword(
 ${helpers.single_keyword(
     "mask-origin",
     "border-box content-box padding-box",
-    engines="gecko",
+    engines="gecko servo-2013",
     extra_gecko_values="fill-box stroke-box view-box",
     vector=True,
     extra_prefixes="webkit",
@@ -150,7 +150,7 @@ ${helpers.predefined_type(
     "mask-size",
     "background::BackgroundSize",
     "computed::BackgroundSize::auto()",
-    engines="gecko",
+    engines="gecko servo-2013",
     initial_specified_value="specified::BackgroundSize::auto()",
     extra_prefixes="webkit",
     spec="https://drafts.fxtf.org/css-masking/#propdef-mask-size",
@@ -172,7 +172,7 @@ ${helpers.single_keyword(
 ${helpers.predefined_type(
     "mask-image",
     "ImageLayer",
-    engines="gecko",
+    engines="gecko servo-2013",
     initial_value="computed::ImageLayer::none()",
     initial_specified_value="specified::ImageLayer::none()",
     parse_method="parse_with_cors_anonymous",
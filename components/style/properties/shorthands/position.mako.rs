@@ -5,7 +5,7 @@
 <%namespace name="helpers" file="/helpers.mako.rs" />
 
 <%helpers:shorthand name="flex-flow"
-                    engines="gecko servo-2013"
+                    engines="gecko servo-2013 servo-2020"
                     sub_properties="flex-direction flex-wrap"
                     extra_prefixes="webkit"
                     derive_serialize="True"
@@ -45,7 +45,7 @@
 </%helpers:shorthand>
 
 <%helpers:shorthand name="flex"
-                    engines="gecko servo-2013"
+                    engines="gecko servo-2013 servo-2020"
                     sub_properties="flex-grow flex-shrink flex-basis"
                     extra_prefixes="webkit"
                     derive_serialize="True"
@@ -112,7 +112,7 @@
 
 <%helpers:shorthand
     name="gap"
-    engines="gecko"
+    engines="gecko servo-2013"
     alias="grid-gap"
     sub_properties="row-gap column-gap"
     spec="https://drafts.csswg.org/css-align-3/#gap-shorthand"
@@ -148,7 +148,7 @@
 <%helpers:shorthand
     name="grid-${kind}"
     sub_properties="grid-${kind}-start grid-${kind}-end"
-    engines="gecko",
+    engines="gecko servo-2013",
     spec="https://drafts.csswg.org/css-grid/#propdef-grid-${kind}"
 >
     use crate::values::specified::GridLine;
@@ -192,7 +192,7 @@
 
 <%helpers:shorthand
     name="grid-area"
-    engines="gecko"
+    engines="gecko servo-2013"
     sub_properties="grid-row-start grid-row-end grid-column-start grid-column-end"
     spec="https://drafts.csswg.org/css-grid/#propdef-grid-area"
 >
@@ -262,7 +262,7 @@
 
 <%helpers:shorthand
     name="grid-template"
-    engines="gecko"
+    engines="gecko servo-2013"
     sub_properties="grid-template-rows grid-template-columns grid-template-areas"
     spec="https://drafts.csswg.org/css-grid/#propdef-grid-template"
 >
@@ -488,7 +488,7 @@
 
 <%helpers:shorthand
     name="grid"
-    engines="gecko"
+    engines="gecko servo-2013"
     sub_properties="grid-template-rows grid-template-columns grid-template-areas
                     grid-auto-rows grid-auto-columns grid-auto-flow"
     spec="https://drafts.csswg.org/css-grid/#propdef-grid"
@@ -4,13 +4,18 @@
 
 <%namespace name="helpers" file="/helpers.mako.rs" />
 
-<%helpers:shorthand name="mask" engines="gecko" extra_prefixes="webkit"
+<%helpers:shorthand name="mask" engines="gecko servo-2013" extra_prefixes="webkit"
                     flags="SHORTHAND_IN_GETCS"
-                    sub_properties="mask-mode mask-repeat mask-clip mask-origin mask-composite mask-position-x
-                                    mask-position-y mask-size mask-image"
+                    sub_properties="mask-mode mask-repeat mask-clip mask-origin
+                                    ${'mask-composite' if engine == 'gecko' else ''}
+                                    mask-position-x mask-position-y mask-size mask-image"
                     spec="https://drafts.fxtf.org/css-masking/#propdef-mask">
-    use crate::properties::longhands::{mask_mode, mask_repeat, mask_clip, mask_origin, mask_composite, mask_position_x,
+    <% composite = ["composite"] if engine == "gecko" else [] %>
+    use crate::properties::longhands::{mask_mode, mask_repeat, mask_clip, mask_origin, mask_position_x,
                                 mask_position_y};
+    % if engine == "gecko":
+    use crate::properties::longhands::mask_composite;
+    % endif
     use crate::properties::longhands::{mask_size, mask_image};
     use crate::values::specified::{Position, PositionComponent};
     use crate::parser::Parse;
@@ -41,7 +46,7 @@
         context: &ParserContext,
         input: &mut Parser<'i, 't>,
     ) -> Result<Longhands, ParseError<'i>> {
-        % for name in "image mode position_x position_y size repeat origin clip composite".split():
+        % for name in "image mode position_x position_y size repeat origin clip".split() + composite:
         // Vec grows from 0 to 4 by default on first push().  So allocate with
         // capacity 1, so in the common case of only one item we don't way
         // overallocate, then shrink.  Note that we always push at least one
@@ -50,7 +55,7 @@
         % endfor
 
         input.parse_comma_separated(|input| {
-            % for name in "image mode position size repeat origin clip composite".split():
+            % for name in "image mode position size repeat origin clip".split() + composite:
                 let mut ${name} = None;
             % endfor
             loop {
@@ -74,7 +79,7 @@
                         continue
                     }
                 }
-                % for name in "repeat origin clip composite mode".split():
+                % for name in "repeat origin clip".split() + composite + ["mode"]:
                     if ${name}.is_none() {
                         if let Ok(value) = input.try(|input| mask_${name}::single_value
                                                                                ::parse(context, input)) {
@@ -91,7 +96,7 @@
                 }
             }
             let mut any = false;
-            % for name in "image mode position size repeat origin clip composite".split():
+            % for name in "image mode position size repeat origin clip".split() + composite:
                 any = any || ${name}.is_some();
             % endfor
             if any {
@@ -102,7 +107,7 @@
                     mask_position_x.push(PositionComponent::zero());
                     mask_position_y.push(PositionComponent::zero());
                 }
-                % for name in "image mode size repeat origin clip composite".split():
+                % for name in "image mode size repeat origin clip".split() + composite:
                     if let Some(m_${name}) = ${name} {
                         mask_${name}.push(m_${name});
                     } else {
@@ -117,7 +122,7 @@
         })?;
 
         Ok(expanded! {
-            % for name in "image mode position_x position_y size repeat origin clip composite".split():
+            % for name in "image mode position_x position_y size repeat origin clip".split() + composite:
                 mask_${name}: mask_${name}::SpecifiedValue(mask_${name}.into()),
             % endfor
          })
@@ -132,7 +137,7 @@
             if len == 0 {
                 return Ok(());
             }
-            % for name in "mode position_x position_y size repeat origin clip composite".split():
+            % for name in "mode position_x position_y size repeat origin clip".split() + composite:
                 if self.mask_${name}.0.len() != len {
                     return Ok(());
                 }
@@ -143,7 +148,7 @@
                     dest.write_str(", ")?;
                 }
 
-                % for name in "image mode position_x position_y size repeat origin clip composite".split():
+                % for name in "image mode position_x position_y size repeat origin clip".split() + composite:
                     let ${name} = &self.mask_${name}.0[i];
                 % endfor
 
@@ -184,10 +189,12 @@
                     }
                 }
 
+                % if engine == "gecko":
                 if *composite != mask_composite::single_value::get_initial_specified_value() {
                     dest.write_str(" ")?;
                     composite.to_css(dest)?;
                 }
+                % endif
             }
 
             Ok(())
@@ -195,7 +202,7 @@
     }
 </%helpers:shorthand>
 
-<%helpers:shorthand name="mask-position" engines="gecko" extra_prefixes="webkit"
+<%helpers:shorthand name="mask-position" engines="gecko servo-2013" extra_prefixes="webkit"
                     flags="SHORTHAND_IN_GETCS"
                     sub_properties="mask-position-x mask-position-y"
                     spec="https://drafts.csswg.org/css-masks-4/#the-mask-position">
@@ -66,6 +66,16 @@ pub const PSEUDO_COUNT: usize = PseudoElement::ServoInlineAbsolute as usize + 1;
 
 impl ::selectors::parser::PseudoElement for PseudoElement {
     type Impl = SelectorImpl;
+
+    // ::slotted() should support all tree-abiding pseudo-elements, see
+    // https://drafts.csswg.org/css-scoping/#slotted-pseudo
+    #[inline]
+    fn valid_after_slotted(&self) -> bool {
+        match *self {
+            PseudoElement::Before | PseudoElement::After => true,
+            _ => false,
+        }
+    }
 }
 
 impl ToCss for PseudoElement {
@@ -278,6 +288,7 @@ pub enum NonTSPseudoClass {
     Fullscreen,
     Hover,
     Indeterminate,
+    Invalid,
     Lang(Lang),
     Link,
     PlaceholderShown,
@@ -285,6 +296,7 @@ pub enum NonTSPseudoClass {
     ReadOnly,
     ServoNonZeroBorder,
     Target,
+    Valid,
     Visited,
 }
 
@@ -327,12 +339,14 @@ impl ToCss for NonTSPseudoClass {
             Fullscreen => ":fullscreen",
             Hover => ":hover",
             Indeterminate => ":indeterminate",
+            Invalid => ":invalid",
             Link => ":link",
             PlaceholderShown => ":placeholder-shown",
             ReadWrite => ":read-write",
             ReadOnly => ":read-only",
             ServoNonZeroBorder => ":-servo-nonzero-border",
             Target => ":target",
+            Valid => ":valid",
             Visited => ":visited",
             Lang(_) => unreachable!(),
         })
@@ -367,6 +381,8 @@ impl NonTSPseudoClass {
             ReadOnly | ReadWrite => ElementState::IN_READ_WRITE_STATE,
             PlaceholderShown => ElementState::IN_PLACEHOLDER_SHOWN_STATE,
             Target => ElementState::IN_TARGET_STATE,
+            Valid => ElementState::IN_VALID_STATE,
+            Invalid => ElementState::IN_INVALID_STATE,
 
             AnyLink | Lang(_) | Link | Visited | ServoNonZeroBorder => ElementState::empty(),
         }
@@ -415,6 +431,11 @@ impl<'a, 'i> ::selectors::Parser<'i> for SelectorParser<'a> {
     type Impl = SelectorImpl;
     type Error = StyleParseErrorKind<'i>;
 
+    #[inline]
+    fn parse_slotted(&self) -> bool {
+        true
+    }
+
     fn parse_non_ts_pseudo_class(
         &self,
         location: SourceLocation,
@@ -431,11 +452,13 @@ impl<'a, 'i> ::selectors::Parser<'i> for SelectorParser<'a> {
             "fullscreen" => Fullscreen,
             "hover" => Hover,
             "indeterminate" => Indeterminate,
+            "invalid" => Invalid,
             "link" => Link,
             "placeholder-shown" => PlaceholderShown,
             "read-write" => ReadWrite,
             "read-only" => ReadOnly,
             "target" => Target,
+            "valid" => Valid,
             "visited" => Visited,
             "-servo-nonzero-border" => {
                 if !self.in_user_agent_stylesheet() {
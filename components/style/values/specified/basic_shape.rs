@@ -62,7 +62,8 @@ fn is_clip_path_path_enabled(context: &ParserContext) -> bool {
 }
 #[cfg(feature = "servo")]
 fn is_clip_path_path_enabled(_: &ParserContext) -> bool {
-    false
+    use servo_config::pref;
+    pref!(layout.css.clip_path_path.enabled)
 }
 
 impl Parse for ClippingShape {
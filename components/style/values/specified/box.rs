@@ -63,7 +63,7 @@ pub enum DisplayInside {
     FlowRoot,
     Inline,
     Flex,
-    #[cfg(feature = "gecko")]
+    #[cfg(any(feature = "gecko", feature = "servo-layout-2013"))]
     Grid,
     Table,
     TableRowGroup,
@@ -147,9 +147,9 @@ impl Display {
     pub const FlowRoot: Self = Self::new(DisplayOutside::Block, DisplayInside::FlowRoot);
     pub const Flex: Self = Self::new(DisplayOutside::Block, DisplayInside::Flex);
     pub const InlineFlex: Self = Self::new(DisplayOutside::Inline, DisplayInside::Flex);
-    #[cfg(feature = "gecko")]
+    #[cfg(any(feature = "gecko", feature = "servo-layout-2013"))]
     pub const Grid: Self = Self::new(DisplayOutside::Block, DisplayInside::Grid);
-    #[cfg(feature = "gecko")]
+    #[cfg(any(feature = "gecko", feature = "servo-layout-2013"))]
     pub const InlineGrid: Self = Self::new(DisplayOutside::Inline, DisplayInside::Grid);
     pub const Table: Self = Self::new(DisplayOutside::Block, DisplayInside::Table);
     pub const InlineTable: Self = Self::new(DisplayOutside::Inline, DisplayInside::Table);
@@ -308,6 +308,8 @@ impl Display {
         match *self {
             Display::InlineBlock => true,
             Display::InlineFlex | Display::InlineTable => true,
+            #[cfg(feature = "servo-layout-2013")]
+            Display::InlineGrid => true,
             _ => false,
         }
     }
@@ -319,7 +321,7 @@ impl Display {
     pub fn is_item_container(&self) -> bool {
         match self.inside() {
             DisplayInside::Flex => true,
-            #[cfg(feature = "gecko")]
+            #[cfg(any(feature = "gecko", feature = "servo-layout-2013"))]
             DisplayInside::Grid => true,
             _ => false,
         }
@@ -429,7 +431,7 @@ impl ToCss for Display {
             Display::MozInlineStack => dest.write_str("-moz-inline-stack"),
             Display::TableCaption => dest.write_str("table-caption"),
             _ => match (outside, inside) {
-                #[cfg(feature = "gecko")]
+                #[cfg(any(feature = "gecko", feature = "servo-layout-2013"))]
                 (DisplayOutside::Inline, DisplayInside::Grid) => dest.write_str("inline-grid"),
                 (DisplayOutside::Inline, DisplayInside::Flex) |
                 (DisplayOutside::Inline, DisplayInside::Table) => {
@@ -469,7 +471,7 @@ fn parse_display_inside<'i, 't>(
         "flow-root" => DisplayInside::FlowRoot,
         "table" => DisplayInside::Table,
         "flex" => DisplayInside::Flex,
-        #[cfg(feature = "gecko")]
+        #[cfg(any(feature = "gecko", feature = "servo-layout-2013"))]
         "grid" => DisplayInside::Grid,
         #[cfg(feature = "gecko")]
         "ruby" => DisplayInside::Ruby,
@@ -572,7 +574,7 @@ impl Parse for Display {
             "inline-table" => Display::InlineTable,
             "-webkit-flex" => Display::Flex,
             "inline-flex" | "-webkit-inline-flex" => Display::InlineFlex,
-            #[cfg(feature = "gecko")]
+            #[cfg(any(feature = "gecko", feature = "servo-layout-2013"))]
             "inline-grid" => Display::InlineGrid,
             "table-caption" => Display::TableCaption,
             "table-row-group" => Display::TableRowGroup,
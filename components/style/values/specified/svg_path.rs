@@ -44,9 +44,28 @@ impl SVGPathData {
         &self.0
     }
 
+    /// Parse a bare SVG path string, i.e. the contents of a `d` attribute
+    /// rather than a CSS string token.
+    pub fn parse_path_string(path_string: &str) -> Result<Self, ()> {
+        if path_string.is_empty() {
+            // Treat an empty string as invalid, so we will not set it.
+            return Err(());
+        }
+
+        // Parse the svg path string as multiple sub-paths.
+        let mut path_parser = PathParser::new(path_string);
+        while skip_wsp(&mut path_parser.chars) {
+            path_parser.parse_subpath()?;
+        }
+
+        Ok(SVGPathData(crate::ArcSlice::from_iter(
+            path_parser.path.into_iter(),
+        )))
+    }
+
     /// Create a normalized copy of this path by converting each relative
     /// command to an absolute command.
-    fn normalize(&self) -> Box<[PathCommand]> {
+    pub fn normalize(&self) -> Box<[PathCommand]> {
         let mut state = PathTraversalState {
             subpath_start: CoordPair::new(0.0, 0.0),
             pos: CoordPair::new(0.0, 0.0),
@@ -89,22 +108,8 @@ impl Parse for SVGPathData {
     ) -> Result<Self, ParseError<'i>> {
         let location = input.current_source_location();
         let path_string = input.expect_string()?.as_ref();
-        if path_string.is_empty() {
-            // Treat an empty string as invalid, so we will not set it.
-            return Err(location.new_custom_error(StyleParseErrorKind::UnspecifiedError));
-        }
-
-        // Parse the svg path string as multiple sub-paths.
-        let mut path_parser = PathParser::new(path_string);
-        while skip_wsp(&mut path_parser.chars) {
-            if path_parser.parse_subpath().is_err() {
-                return Err(location.new_custom_error(StyleParseErrorKind::UnspecifiedError));
-            }
-        }
-
-        Ok(SVGPathData(crate::ArcSlice::from_iter(
-            path_parser.path.into_iter(),
-        )))
+        SVGPathData::parse_path_string(path_string)
+            .map_err(|()| location.new_custom_error(StyleParseErrorKind::UnspecifiedError))
     }
 }
 
@@ -524,6 +529,18 @@ impl CoordPair {
     pub fn new(x: CSSFloat, y: CSSFloat) -> Self {
         CoordPair(x, y)
     }
+
+    /// The x coordinate.
+    #[inline]
+    pub fn x(&self) -> CSSFloat {
+        self.0
+    }
+
+    /// The y coordinate.
+    #[inline]
+    pub fn y(&self) -> CSSFloat {
+        self.1
+    }
 }
 
 /// The EllipticalArc flag type.
@@ -531,6 +548,14 @@ impl CoordPair {
 #[repr(C)]
 pub struct ArcFlag(bool);
 
+impl ArcFlag {
+    /// Whether the flag is set.
+    #[inline]
+    pub fn is_set(&self) -> bool {
+        self.0
+    }
+}
+
 impl ToCss for ArcFlag {
     #[inline]
     fn to_css<W>(&self, dest: &mut CssWriter<W>) -> fmt::Result
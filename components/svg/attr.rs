@@ -0,0 +1,395 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Parsing of the values of SVG attributes.
+
+use crate::tree::AspectRatio;
+use cssparser::{Color, ParseError, Parser, ParserInput, RGBA};
+use euclid::default::{Point2D, Rect, Size2D, Transform2D};
+
+/// A length, made of an absolute part in user units and of a percentage of a
+/// reference length.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Length {
+    pub absolute: f32,
+    pub percentage: f32,
+}
+
+impl Length {
+    pub fn new(absolute: f32) -> Length {
+        Length {
+            absolute,
+            percentage: 0.,
+        }
+    }
+
+    pub fn resolve(&self, reference: f32) -> f32 {
+        self.absolute + self.percentage * reference
+    }
+}
+
+/// A scanner over the numbers of an attribute value.
+struct Scanner<'a> {
+    input: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Scanner<'a> {
+    fn new(input: &'a str) -> Scanner<'a> {
+        Scanner {
+            input: input.as_bytes(),
+            position: 0,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.position).cloned()
+    }
+
+    fn at_end(&self) -> bool {
+        self.position >= self.input.len()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ') | Some(b'\t') | Some(b'\n') | Some(b'\r') | Some(b'\x0c') = self.peek()
+        {
+            self.position += 1;
+        }
+    }
+
+    /// Skips whitespace and at most one comma.
+    fn skip_separator(&mut self) {
+        self.skip_whitespace();
+        if self.peek() == Some(b',') {
+            self.position += 1;
+            self.skip_whitespace();
+        }
+    }
+
+    /// <https://www.w3.org/TR/SVG2/types.html#InterfaceSVGNumber>
+    fn number(&mut self) -> Option<f32> {
+        let start = self.position;
+        if let Some(b'+') | Some(b'-') = self.peek() {
+            self.position += 1;
+        }
+        let digits_start = self.position;
+        self.skip_digits();
+        let mut has_digits = self.position > digits_start;
+        if self.peek() == Some(b'.') {
+            self.position += 1;
+            let fraction_start = self.position;
+            self.skip_digits();
+            has_digits |= self.position > fraction_start;
+        }
+        if !has_digits {
+            self.position = start;
+            return None;
+        }
+        // Don't mistake the `e` of an `em` or `ex` unit for an exponent.
+        if let Some(b'e') | Some(b'E') = self.peek() {
+            let mantissa_end = self.position;
+            self.position += 1;
+            if let Some(b'+') | Some(b'-') = self.peek() {
+                self.position += 1;
+            }
+            let exponent_start = self.position;
+            self.skip_digits();
+            if self.position == exponent_start {
+                self.position = mantissa_end;
+            }
+        }
+        let number = std::str::from_utf8(&self.input[start..self.position]).ok()?;
+        number
+            .parse::<f32>()
+            .ok()
+            .filter(|number| number.is_finite())
+    }
+
+    fn skip_digits(&mut self) {
+        while let Some(b'0'..=b'9') = self.peek() {
+            self.position += 1;
+        }
+    }
+
+    /// The identifier or unit at the current position.
+    fn ident(&mut self) -> &'a str {
+        let start = self.position;
+        while let Some(b'a'..=b'z') | Some(b'A'..=b'Z') | Some(b'%') = self.peek() {
+            self.position += 1;
+        }
+        std::str::from_utf8(&self.input[start..self.position]).unwrap_or("")
+    }
+}
+
+pub fn parse_number(value: &str) -> Option<f32> {
+    let mut scanner = Scanner::new(value);
+    scanner.skip_whitespace();
+    let number = scanner.number()?;
+    scanner.skip_whitespace();
+    if scanner.at_end() {
+        Some(number)
+    } else {
+        None
+    }
+}
+
+/// Parses a number or a percentage, like the value of the `offset` attribute
+/// of `<stop>` elements, returning a fraction.
+pub fn parse_number_or_percentage(value: &str) -> Option<f32> {
+    let mut scanner = Scanner::new(value);
+    scanner.skip_whitespace();
+    let mut number = scanner.number()?;
+    if scanner.peek() == Some(b'%') {
+        scanner.position += 1;
+        number /= 100.;
+    }
+    scanner.skip_whitespace();
+    if scanner.at_end() {
+        Some(number)
+    } else {
+        None
+    }
+}
+
+/// A list of numbers separated by whitespace or commas, stopping at the
+/// first error.
+pub fn parse_number_list(value: &str) -> Vec<f32> {
+    let mut scanner = Scanner::new(value);
+    let mut numbers = vec![];
+    scanner.skip_whitespace();
+    while let Some(number) = scanner.number() {
+        numbers.push(number);
+        scanner.skip_separator();
+    }
+    numbers
+}
+
+/// <https://www.w3.org/TR/SVG2/shapes.html#DataTypePoints>
+pub fn parse_points(value: &str) -> Vec<Point2D<f32>> {
+    parse_number_list(value)
+        .chunks(2)
+        .filter(|pair| pair.len() == 2)
+        .map(|pair| Point2D::new(pair[0], pair[1]))
+        .collect()
+}
+
+/// <https://www.w3.org/TR/SVG2/types.html#InterfaceSVGLength>
+///
+/// `font_size` is used to resolve `em` and `ex` units.
+pub fn parse_length(value: &str, font_size: f32) -> Option<Length> {
+    let mut scanner = Scanner::new(value);
+    scanner.skip_whitespace();
+    let length = scan_length(&mut scanner, font_size)?;
+    scanner.skip_whitespace();
+    if scanner.at_end() {
+        Some(length)
+    } else {
+        None
+    }
+}
+
+/// A list of lengths, like the value of the `x` attribute of `<text>`
+/// elements.
+pub fn parse_length_list(value: &str, font_size: f32) -> Vec<Length> {
+    let mut scanner = Scanner::new(value);
+    let mut lengths = vec![];
+    scanner.skip_whitespace();
+    while let Some(length) = scan_length(&mut scanner, font_size) {
+        lengths.push(length);
+        scanner.skip_separator();
+    }
+    lengths
+}
+
+fn scan_length(scanner: &mut Scanner, font_size: f32) -> Option<Length> {
+    let number = scanner.number()?;
+    // https://drafts.csswg.org/css-values/#absolute-lengths
+    let absolute = match &*scanner.ident().to_ascii_lowercase() {
+        "" | "px" => number,
+        "%" => {
+            return Some(Length {
+                absolute: 0.,
+                percentage: number / 100.,
+            });
+        },
+        "em" => number * font_size,
+        "ex" => number * font_size / 2.,
+        "in" => number * 96.,
+        "cm" => number * 96. / 2.54,
+        "mm" => number * 96. / 25.4,
+        "pt" => number * 96. / 72.,
+        "pc" => number * 16.,
+        _ => return None,
+    };
+    Some(Length::new(absolute))
+}
+
+/// <https://www.w3.org/TR/SVG2/coords.html#ViewBoxAttribute>
+pub fn parse_view_box(value: &str) -> Option<Rect<f32>> {
+    let numbers = parse_number_list(value);
+    if numbers.len() != 4 || numbers[2] < 0. || numbers[3] < 0. {
+        return None;
+    }
+    Some(Rect::new(
+        Point2D::new(numbers[0], numbers[1]),
+        Size2D::new(numbers[2], numbers[3]),
+    ))
+}
+
+/// <https://www.w3.org/TR/SVG2/coords.html#PreserveAspectRatioAttribute>
+pub fn parse_aspect_ratio(value: &str) -> AspectRatio {
+    let mut words = value.split_whitespace();
+    let align = match words.next() {
+        Some("none") => None,
+        Some(align) if align.len() == 8 && align.is_ascii() => {
+            let fraction = |value: &str| match value {
+                "Min" => Some(0.),
+                "Mid" => Some(0.5),
+                "Max" => Some(1.),
+                _ => None,
+            };
+            match (
+                &align[..1],
+                fraction(&align[1..4]),
+                &align[4..5],
+                fraction(&align[5..]),
+            ) {
+                ("x", Some(x), "Y", Some(y)) => Some((x, y)),
+                _ => return AspectRatio::default(),
+            }
+        },
+        _ => return AspectRatio::default(),
+    };
+    AspectRatio {
+        align,
+        slice: words.next() == Some("slice"),
+    }
+}
+
+/// <https://www.w3.org/TR/css-transforms-1/#svg-transform>
+pub fn parse_transform(value: &str) -> Option<Transform2D<f32>> {
+    let mut scanner = Scanner::new(value);
+    let mut transform = Transform2D::identity();
+    scanner.skip_whitespace();
+    while !scanner.at_end() {
+        let name = scanner.ident();
+        scanner.skip_whitespace();
+        if scanner.peek() != Some(b'(') {
+            return None;
+        }
+        scanner.position += 1;
+        scanner.skip_whitespace();
+        let mut arguments = vec![];
+        while let Some(number) = scanner.number() {
+            arguments.push(number);
+            scanner.skip_separator();
+        }
+        if scanner.peek() != Some(b')') {
+            return None;
+        }
+        scanner.position += 1;
+        let function = match (name, &*arguments) {
+            ("matrix", &[a, b, c, d, e, f]) => Transform2D::row_major(a, b, c, d, e, f),
+            ("translate", &[x]) => Transform2D::create_translation(x, 0.),
+            ("translate", &[x, y]) => Transform2D::create_translation(x, y),
+            ("scale", &[x]) => Transform2D::create_scale(x, x),
+            ("scale", &[x, y]) => Transform2D::create_scale(x, y),
+            ("rotate", &[angle]) => rotation(angle),
+            ("rotate", &[angle, x, y]) => Transform2D::create_translation(-x, -y)
+                .post_transform(&rotation(angle))
+                .post_transform(&Transform2D::create_translation(x, y)),
+            ("skewX", &[angle]) => {
+                Transform2D::row_major(1., 0., angle.to_radians().tan(), 1., 0., 0.)
+            },
+            ("skewY", &[angle]) => {
+                Transform2D::row_major(1., angle.to_radians().tan(), 0., 1., 0., 0.)
+            },
+            _ => return None,
+        };
+        // Each function applies to the coordinate system established by the
+        // functions before it.
+        transform = function.post_transform(&transform);
+        scanner.skip_separator();
+    }
+    Some(transform)
+}
+
+/// A clockwise rotation by an angle in degrees, in a coordinate system whose
+/// y axis points down.
+fn rotation(angle: f32) -> Transform2D<f32> {
+    let (sin, cos) = angle.to_radians().sin_cos();
+    Transform2D::row_major(cos, sin, -sin, cos, 0., 0.)
+}
+
+/// The fragment identifier referenced by an `href` attribute or a `url()`
+/// function, for references within the same document.
+pub fn parse_reference(value: &str) -> Option<&str> {
+    let value = value.trim();
+    if value.starts_with('#') {
+        Some(&value[1..])
+    } else {
+        None
+    }
+}
+
+/// Parses a CSS color, returning `None` for `currentColor`.
+pub fn parse_color(value: &str) -> Option<Option<RGBA>> {
+    let mut input = ParserInput::new(value);
+    let mut parser = Parser::new(&mut input);
+    let color = parser
+        .parse_entirely(|parser| Color::parse(parser).map_err(ParseError::<()>::from))
+        .ok()?;
+    match color {
+        Color::RGBA(rgba) => Some(Some(rgba)),
+        Color::CurrentColor => Some(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_maps(transform: &str, from: (f32, f32), to: (f32, f32)) {
+        let transform = parse_transform(transform).unwrap();
+        let actual = transform.transform_point(Point2D::new(from.0, from.1));
+        assert!(
+            (actual - Point2D::new(to.0, to.1)).length() < 1e-4,
+            "{:?} maps to {:?}, expected {:?}",
+            from,
+            actual,
+            to
+        );
+    }
+
+    #[test]
+    fn test_transform_functions() {
+        assert_maps("matrix(1 2 3 4 5 6)", (1., 1.), (9., 12.));
+        assert_maps("translate(10)", (1., 1.), (11., 1.));
+        assert_maps("translate(10, 20)", (1., 1.), (11., 21.));
+        assert_maps("scale(2)", (1., 3.), (2., 6.));
+        assert_maps("scale(2 3)", (1., 1.), (2., 3.));
+        assert_maps("rotate(90)", (1., 0.), (0., 1.));
+        assert_maps("rotate(90 10 10)", (20., 10.), (10., 20.));
+        assert_maps("skewX(45)", (0., 1.), (1., 1.));
+        assert_maps("skewY(45)", (1., 0.), (1., 1.));
+    }
+
+    #[test]
+    fn test_transform_list() {
+        // The functions apply from right to left to the coordinates.
+        assert_maps("translate(10,20)scale(2)", (1., 1.), (12., 22.));
+        assert_maps(" scale(2) , translate(10 20) ", (1., 1.), (22., 42.));
+        assert_maps("", (1., 1.), (1., 1.));
+    }
+
+    #[test]
+    fn test_invalid_transforms() {
+        assert!(parse_transform("translate(10").is_none());
+        assert!(parse_transform("translate 10").is_none());
+        assert!(parse_transform("scale()").is_none());
+        assert!(parse_transform("matrix(1 2 3)").is_none());
+        assert!(parse_transform("rotate(90 10)").is_none());
+        assert!(parse_transform("spin(90)").is_none());
+    }
+}
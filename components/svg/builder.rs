@@ -0,0 +1,644 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Construction of render trees from SVG elements.
+
+use crate::attr::{self, Length};
+use crate::path::Path;
+use crate::tree::{AspectRatio, Fill, FillRule, Gradient, GradientStop, Group, LineCap};
+use crate::tree::{LineJoin, LinearGradient, Node, Paint, PathNode, RadialGradient};
+use crate::tree::{SpreadMethod, Stroke, TextAnchor, TextNode, Tree, ViewBox};
+use cssparser::RGBA;
+use euclid::default::{Point2D, Rect, Size2D, Transform2D};
+use std::collections::HashMap;
+use std::sync::Arc;
+use style::values::specified::svg_path::SVGPathData;
+
+/// How deep `<use>` elements and gradient references can be nested, which
+/// also stops reference cycles.
+const MAX_REFERENCE_DEPTH: usize = 16;
+
+/// An element of an SVG document, as seen by the builder.
+pub trait SvgElement: Clone {
+    fn local_name(&self) -> &str;
+
+    /// The value of an attribute in the null namespace.
+    fn attribute(&self, name: &str) -> Option<&str>;
+
+    /// The value of the `href` attribute, or of the legacy `xlink:href` one.
+    fn href(&self) -> Option<&str>;
+
+    /// The child elements of this element.
+    fn children(&self) -> Vec<Self>;
+
+    /// The concatenation of the text of the descendants of this element.
+    fn text_content(&self) -> String;
+
+    /// The computed style of this element, given the one of the element it
+    /// inherits from.
+    fn style(&self, parent: &Style) -> Style;
+}
+
+/// The computed values of the properties that affect the rendering of SVG
+/// content.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Style {
+    /// Whether `display` isn't `none`.
+    pub displayed: bool,
+    /// Whether `visibility` is `visible`.
+    pub visible: bool,
+    pub opacity: f32,
+    pub color: RGBA,
+    pub fill: PaintValue,
+    pub fill_opacity: f32,
+    pub fill_rule: FillRule,
+    pub stroke: PaintValue,
+    pub stroke_opacity: f32,
+    pub stroke_width: Length,
+    pub stroke_line_cap: LineCap,
+    pub stroke_line_join: LineJoin,
+    pub stroke_miter_limit: f32,
+    pub stroke_dash_array: Vec<Length>,
+    pub stroke_dash_offset: Length,
+    pub stop_color: RGBA,
+    pub stop_opacity: f32,
+    pub text_anchor: TextAnchor,
+    pub font_size: f32,
+}
+
+impl Default for Style {
+    /// The initial values of the properties.
+    fn default() -> Style {
+        let black = RGBA::new(0, 0, 0, 255);
+        Style {
+            displayed: true,
+            visible: true,
+            opacity: 1.,
+            color: black,
+            fill: PaintValue::Color(black),
+            fill_opacity: 1.,
+            fill_rule: FillRule::NonZero,
+            stroke: PaintValue::None,
+            stroke_opacity: 1.,
+            stroke_width: Length::new(1.),
+            stroke_line_cap: LineCap::Butt,
+            stroke_line_join: LineJoin::Miter,
+            stroke_miter_limit: 4.,
+            stroke_dash_array: vec![],
+            stroke_dash_offset: Length::default(),
+            stop_color: black,
+            stop_opacity: 1.,
+            text_anchor: TextAnchor::Start,
+            font_size: 16.,
+        }
+    }
+}
+
+/// The computed value of the `fill` and `stroke` properties.
+///
+/// <https://www.w3.org/TR/SVG2/painting.html#SpecifyingPaint>
+#[derive(Clone, Debug, PartialEq)]
+pub enum PaintValue {
+    None,
+    Color(RGBA),
+    /// A reference to a paint server element by its id, with the color to
+    /// use if the reference is invalid, if any.
+    Url(String, Option<RGBA>),
+}
+
+/// Builds the render tree of an `<svg>` element, whose viewport has the given
+/// size.
+///
+/// The elements of the `<text>` nodes of the tree are returned along with it,
+/// in the order of their `index`.
+pub fn build<E: SvgElement>(root: &E, viewport: Size2D<f32>) -> (Tree, Vec<E>) {
+    let mut builder = Builder {
+        ids: HashMap::new(),
+        texts: vec![],
+        depth: 0,
+    };
+    builder.collect_ids(root);
+
+    let view_box = root
+        .attribute("viewBox")
+        .and_then(attr::parse_view_box)
+        .map(|rect| ViewBox {
+            rect,
+            aspect_ratio: root
+                .attribute("preserveAspectRatio")
+                .map_or(AspectRatio::default(), attr::parse_aspect_ratio),
+        });
+    let viewport = view_box.map_or(viewport, |view_box| view_box.rect.size);
+    let style = root.style(&Style::default());
+    let mut children = vec![];
+    builder.build_children(root, &style, viewport, &mut children);
+    let tree = Tree { view_box, children };
+    (tree, builder.texts)
+}
+
+struct Builder<E> {
+    /// The elements of the document by id.
+    ids: HashMap<String, E>,
+    texts: Vec<E>,
+    /// How many `<use>` elements are being expanded.
+    depth: usize,
+}
+
+impl<E: SvgElement> Builder<E> {
+    fn collect_ids(&mut self, element: &E) {
+        if let Some(id) = element.attribute("id") {
+            self.ids
+                .entry(id.to_owned())
+                .or_insert_with(|| element.clone());
+        }
+        for child in element.children() {
+            self.collect_ids(&child);
+        }
+    }
+
+    fn build_children(
+        &mut self,
+        element: &E,
+        style: &Style,
+        viewport: Size2D<f32>,
+        nodes: &mut Vec<Node>,
+    ) {
+        for child in element.children() {
+            self.build_element(&child, style, viewport, nodes);
+        }
+    }
+
+    /// <https://www.w3.org/TR/SVG2/render.html#Rendering>
+    fn build_element(
+        &mut self,
+        element: &E,
+        parent_style: &Style,
+        viewport: Size2D<f32>,
+        nodes: &mut Vec<Node>,
+    ) {
+        let style = element.style(parent_style);
+        if !style.displayed {
+            return;
+        }
+
+        let mut children = vec![];
+        let mut transform = element
+            .attribute("transform")
+            .and_then(attr::parse_transform)
+            .unwrap_or_else(Transform2D::identity);
+        match element.local_name() {
+            "g" | "a" => self.build_children(element, &style, viewport, &mut children),
+            "svg" => {
+                let (view_box_transform, viewport, clip) =
+                    nested_viewport(element, None, &style, viewport);
+                let mut svg_children = vec![];
+                self.build_children(element, &style, viewport, &mut svg_children);
+                push_group(&mut children, view_box_transform, 1., clip, svg_children);
+            },
+            "use" => {
+                let target = match element.href().and_then(attr::parse_reference) {
+                    Some(id) => self.ids.get(id).cloned(),
+                    None => None,
+                };
+                let target = match target {
+                    Some(target) if self.depth < MAX_REFERENCE_DEPTH => target,
+                    _ => return,
+                };
+                let x = length_attribute(element, "x", &style, viewport.width);
+                let y = length_attribute(element, "y", &style, viewport.height);
+                transform = Transform2D::create_translation(x, y).post_transform(&transform);
+                self.depth += 1;
+                if target.local_name() == "symbol" {
+                    // https://www.w3.org/TR/SVG2/struct.html#SymbolNotes
+                    let target_style = target.style(&style);
+                    if target_style.displayed {
+                        let (view_box_transform, viewport, clip) =
+                            nested_viewport(&target, Some(element), &target_style, viewport);
+                        let mut symbol_children = vec![];
+                        self.build_children(&target, &target_style, viewport, &mut symbol_children);
+                        push_group(&mut children, view_box_transform, 1., clip, symbol_children);
+                    }
+                } else {
+                    self.build_element(&target, &style, viewport, &mut children);
+                }
+                self.depth -= 1;
+            },
+            "rect" | "circle" | "ellipse" | "line" | "polyline" | "polygon" | "path" => {
+                if style.visible {
+                    if let Some(node) = self.build_shape(element, &style, viewport) {
+                        children.push(Node::Path(node));
+                    }
+                }
+            },
+            "text" => {
+                if style.visible {
+                    children.push(Node::Text(self.build_text(element, &style, viewport)));
+                }
+            },
+            // Other elements, like `<defs>` and paint servers, are never
+            // rendered directly.
+            _ => return,
+        }
+        push_group(nodes, transform, style.opacity, None, children);
+    }
+
+    /// <https://www.w3.org/TR/SVG2/shapes.html>
+    fn build_shape(&self, element: &E, style: &Style, viewport: Size2D<f32>) -> Option<PathNode> {
+        let length = |name, reference| length_attribute(element, name, style, reference);
+        let diagonal = diagonal(viewport);
+        let path = match element.local_name() {
+            "rect" => {
+                let rect = Rect::new(
+                    Point2D::new(length("x", viewport.width), length("y", viewport.height)),
+                    Size2D::new(
+                        length("width", viewport.width),
+                        length("height", viewport.height),
+                    ),
+                );
+                if !(rect.size.width > 0. && rect.size.height > 0.) {
+                    return None;
+                }
+                let radii = auto_radii(
+                    optional_length(element, "rx", style, viewport.width),
+                    optional_length(element, "ry", style, viewport.height),
+                );
+                let radii = Size2D::new(
+                    radii.width.min(rect.size.width / 2.),
+                    radii.height.min(rect.size.height / 2.),
+                );
+                Path::rect(rect, radii)
+            },
+            "circle" => {
+                let radius = length("r", diagonal);
+                if !(radius > 0.) {
+                    return None;
+                }
+                let center =
+                    Point2D::new(length("cx", viewport.width), length("cy", viewport.height));
+                Path::ellipse(center, Size2D::new(radius, radius))
+            },
+            "ellipse" => {
+                let radii = auto_radii(
+                    optional_length(element, "rx", style, viewport.width),
+                    optional_length(element, "ry", style, viewport.height),
+                );
+                if !(radii.width > 0. && radii.height > 0.) {
+                    return None;
+                }
+                let center =
+                    Point2D::new(length("cx", viewport.width), length("cy", viewport.height));
+                Path::ellipse(center, radii)
+            },
+            "line" => {
+                let mut path = Path::new();
+                path.move_to(Point2D::new(
+                    length("x1", viewport.width),
+                    length("y1", viewport.height),
+                ));
+                path.line_to(Point2D::new(
+                    length("x2", viewport.width),
+                    length("y2", viewport.height),
+                ));
+                path
+            },
+            "polyline" | "polygon" => {
+                let points = element
+                    .attribute("points")
+                    .map_or(vec![], attr::parse_points);
+                Path::polyline(&points, element.local_name() == "polygon")
+            },
+            "path" => {
+                let data = element.attribute("d")?;
+                Path::from_path_data(&SVGPathData::parse_path_string(data).ok()?)
+            },
+            _ => return None,
+        };
+        if path.is_empty() {
+            return None;
+        }
+
+        let fill = self.paint(&style.fill, style, viewport).map(|paint| Fill {
+            paint,
+            opacity: style.fill_opacity,
+            rule: style.fill_rule,
+        });
+        let stroke_width = style.stroke_width.resolve(diagonal);
+        let stroke = match self.paint(&style.stroke, style, viewport) {
+            Some(paint) if stroke_width > 0. => Some(Stroke {
+                paint,
+                opacity: style.stroke_opacity,
+                width: stroke_width,
+                line_cap: style.stroke_line_cap,
+                line_join: style.stroke_line_join,
+                miter_limit: style.stroke_miter_limit,
+                dash_array: dash_array(&style.stroke_dash_array, diagonal),
+                dash_offset: style.stroke_dash_offset.resolve(diagonal),
+            }),
+            _ => None,
+        };
+        if fill.is_none() && stroke.is_none() {
+            return None;
+        }
+        Some(PathNode { path, fill, stroke })
+    }
+
+    /// <https://www.w3.org/TR/SVG2/text.html#TextElement>
+    fn build_text(&mut self, element: &E, style: &Style, viewport: Size2D<f32>) -> TextNode {
+        let first = |name, reference| {
+            element
+                .attribute(name)
+                .and_then(|value| {
+                    attr::parse_length_list(value, style.font_size)
+                        .first()
+                        .cloned()
+                })
+                .map_or(0., |length: Length| length.resolve(reference))
+        };
+        let position = Point2D::new(first("x", viewport.width), first("y", viewport.height));
+        let fill = self.paint(&style.fill, style, viewport).map(|paint| Fill {
+            paint,
+            opacity: style.fill_opacity,
+            rule: style.fill_rule,
+        });
+        let index = self.texts.len();
+        self.texts.push(element.clone());
+        TextNode {
+            index,
+            position,
+            anchor: style.text_anchor,
+            text: collapse_whitespace(&element.text_content()),
+            fill,
+        }
+    }
+
+    /// Resolves the value of the `fill` or `stroke` property of an element
+    /// with the given style and viewport.
+    fn paint(&self, value: &PaintValue, style: &Style, viewport: Size2D<f32>) -> Option<Paint> {
+        match *value {
+            PaintValue::None => None,
+            PaintValue::Color(color) => Some(Paint::Color(color)),
+            PaintValue::Url(ref id, fallback) => {
+                let server = self.ids.get(id).filter(|server| {
+                    let name = server.local_name();
+                    name == "linearGradient" || name == "radialGradient"
+                });
+                match server {
+                    Some(server) => self.gradient(server, style, viewport),
+                    None => fallback.map(Paint::Color),
+                }
+            },
+        }
+    }
+
+    /// <https://www.w3.org/TR/SVG2/pservers.html#Gradients>
+    fn gradient(&self, element: &E, style: &Style, viewport: Size2D<f32>) -> Option<Paint> {
+        // The gradients whose attributes and stops this one inherits, through
+        // its `href` attribute.
+        let mut chain = vec![element.clone()];
+        while chain.len() < MAX_REFERENCE_DEPTH {
+            let next = chain
+                .last()
+                .and_then(|last| last.href())
+                .and_then(attr::parse_reference)
+                .and_then(|id| self.ids.get(id));
+            match next {
+                Some(next) if next.local_name().ends_with("Gradient") => chain.push(next.clone()),
+                _ => break,
+            }
+        }
+        let attribute = |name| {
+            chain
+                .iter()
+                .filter_map(|element| element.attribute(name))
+                .next()
+        };
+
+        let mut stops = vec![];
+        if let Some(element) = chain.iter().find(|element| has_stops(*element)) {
+            let gradient_style = element.style(&Style::default());
+            for stop in element.children() {
+                if stop.local_name() != "stop" {
+                    continue;
+                }
+                let stop_style = stop.style(&gradient_style);
+                let offset = stop
+                    .attribute("offset")
+                    .and_then(attr::parse_number_or_percentage)
+                    .unwrap_or(0.);
+                let mut color = stop_style.stop_color;
+                color.alpha = (color.alpha as f32 * stop_style.stop_opacity.max(0.).min(1.)) as u8;
+                stops.push(GradientStop {
+                    offset: offset.max(0.).min(1.),
+                    color,
+                });
+            }
+        }
+        match stops.len() {
+            0 => return None,
+            1 => return Some(Paint::Color(stops[0].color)),
+            _ => {},
+        }
+
+        let bounding_box_units = attribute("gradientUnits") != Some("userSpaceOnUse");
+        let gradient = Gradient {
+            bounding_box_units,
+            transform: attribute("gradientTransform")
+                .and_then(attr::parse_transform)
+                .unwrap_or_else(Transform2D::identity),
+            spread: match attribute("spreadMethod") {
+                Some("reflect") => SpreadMethod::Reflect,
+                Some("repeat") => SpreadMethod::Repeat,
+                _ => SpreadMethod::Pad,
+            },
+            stops,
+        };
+
+        // Percentages are fractions of the bounding box, or of the viewport of
+        // the painted element.
+        let viewport = if bounding_box_units {
+            Size2D::new(1., 1.)
+        } else {
+            viewport
+        };
+        let length = |name, default: f32, reference| {
+            attribute(name)
+                .and_then(|value| attr::parse_length(value, style.font_size))
+                .unwrap_or(Length {
+                    absolute: 0.,
+                    percentage: default,
+                })
+                .resolve(reference)
+        };
+        let (width, height) = (viewport.width, viewport.height);
+        Some(if element.local_name() == "linearGradient" {
+            Paint::LinearGradient(Arc::new(LinearGradient {
+                start: Point2D::new(length("x1", 0., width), length("y1", 0., height)),
+                end: Point2D::new(length("x2", 1., width), length("y2", 0., height)),
+                gradient,
+            }))
+        } else {
+            let center = Point2D::new(length("cx", 0.5, width), length("cy", 0.5, height));
+            let focal_point = Point2D::new(
+                attribute("fx").map_or(center.x, |_| length("fx", 0.5, width)),
+                attribute("fy").map_or(center.y, |_| length("fy", 0.5, height)),
+            );
+            Paint::RadialGradient(Arc::new(RadialGradient {
+                center,
+                radius: length("r", 0.5, diagonal(viewport)),
+                focal_point,
+                gradient,
+            }))
+        })
+    }
+}
+
+fn has_stops<E: SvgElement>(element: &E) -> bool {
+    element
+        .children()
+        .iter()
+        .any(|child| child.local_name() == "stop")
+}
+
+/// Wraps nodes in a group when they have a transform, an opacity or a clip.
+fn push_group(
+    nodes: &mut Vec<Node>,
+    transform: Transform2D<f32>,
+    opacity: f32,
+    clip: Option<Rect<f32>>,
+    children: Vec<Node>,
+) {
+    if children.is_empty() || !(opacity > 0.) {
+        return;
+    }
+    if transform == Transform2D::identity() && opacity >= 1. && clip.is_none() {
+        nodes.extend(children);
+    } else {
+        nodes.push(Node::Group(Group {
+            transform,
+            opacity: opacity.min(1.),
+            clip,
+            children,
+        }));
+    }
+}
+
+/// The transform, the size and the clip of the viewport established by a
+/// nested `<svg>` element, or by a `<symbol>` referenced by a `<use>` element.
+/// The size and the clip are in the coordinate system of the children.
+///
+/// <https://www.w3.org/TR/SVG2/coords.html#EstablishingANewSVGViewport>
+fn nested_viewport<E: SvgElement>(
+    element: &E,
+    use_element: Option<&E>,
+    style: &Style,
+    viewport: Size2D<f32>,
+) -> (Transform2D<f32>, Size2D<f32>, Option<Rect<f32>>) {
+    // The size of a symbol comes from the `<use>` element referencing it.
+    let sized = use_element.unwrap_or(element);
+    let size = |name, reference| {
+        sized
+            .attribute(name)
+            .and_then(|value| attr::parse_length(value, style.font_size))
+            .unwrap_or(Length {
+                absolute: 0.,
+                percentage: 1.,
+            })
+            .resolve(reference)
+    };
+    let size = Size2D::new(
+        size("width", viewport.width),
+        size("height", viewport.height),
+    );
+    let origin = if use_element.is_some() {
+        Point2D::zero()
+    } else {
+        Point2D::new(
+            length_attribute(element, "x", style, viewport.width),
+            length_attribute(element, "y", style, viewport.height),
+        )
+    };
+    let translation = Transform2D::create_translation(origin.x, origin.y);
+    let view_box = element
+        .attribute("viewBox")
+        .and_then(attr::parse_view_box)
+        .map(|rect| ViewBox {
+            rect,
+            aspect_ratio: element
+                .attribute("preserveAspectRatio")
+                .map_or(AspectRatio::default(), attr::parse_aspect_ratio),
+        });
+    let viewport_rect = Rect::new(Point2D::zero(), size);
+    match view_box {
+        Some(view_box) => {
+            let view_box_transform = view_box.transform(size);
+            let clip = view_box_transform
+                .inverse()
+                .map(|inverse| inverse.transform_rect(&viewport_rect));
+            (
+                view_box_transform.post_transform(&translation),
+                view_box.rect.size,
+                clip,
+            )
+        },
+        None => (translation, size, Some(viewport_rect)),
+    }
+}
+
+/// The value of a length attribute in user units, or zero.
+fn length_attribute<E: SvgElement>(element: &E, name: &str, style: &Style, reference: f32) -> f32 {
+    optional_length(element, name, style, reference).unwrap_or(0.)
+}
+
+fn optional_length<E: SvgElement>(
+    element: &E,
+    name: &str,
+    style: &Style,
+    reference: f32,
+) -> Option<f32> {
+    element
+        .attribute(name)
+        .and_then(|value| attr::parse_length(value, style.font_size))
+        .map(|length| length.resolve(reference))
+}
+
+/// Resolves `auto` radii, where a missing or negative radius takes the value
+/// of the other one.
+///
+/// <https://www.w3.org/TR/SVG2/geometry.html#RX>
+fn auto_radii(rx: Option<f32>, ry: Option<f32>) -> Size2D<f32> {
+    match (rx.filter(|rx| *rx >= 0.), ry.filter(|ry| *ry >= 0.)) {
+        (Some(rx), Some(ry)) => Size2D::new(rx, ry),
+        (Some(radius), None) | (None, Some(radius)) => Size2D::new(radius, radius),
+        (None, None) => Size2D::zero(),
+    }
+}
+
+/// The length that percentages of lengths that are neither horizontal nor
+/// vertical resolve against.
+///
+/// <https://www.w3.org/TR/SVG2/coords.html#Units>
+fn diagonal(viewport: Size2D<f32>) -> f32 {
+    ((viewport.width * viewport.width + viewport.height * viewport.height) / 2.).sqrt()
+}
+
+/// <https://www.w3.org/TR/SVG2/painting.html#StrokeDasharrayProperty>
+fn dash_array(lengths: &[Length], reference: f32) -> Vec<f32> {
+    let mut dashes: Vec<f32> = lengths
+        .iter()
+        .map(|length| length.resolve(reference))
+        .collect();
+    let sum: f32 = dashes.iter().sum();
+    if dashes.iter().any(|dash| *dash < 0.) || !(sum > 0.) {
+        return vec![];
+    }
+    if dashes.len() % 2 == 1 {
+        dashes.extend_from_slice(&dashes.clone());
+    }
+    dashes
+}
+
+/// Collapses the whitespace of text content, as with `white-space: normal`.
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
@@ -0,0 +1,465 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Standalone SVG documents, like the ones used as images.
+//!
+//! Documents are parsed without scripting, and the style of their elements
+//! only comes from presentation attributes and `style` attributes.
+
+use crate::attr::{self, Length};
+use crate::builder::{self, PaintValue, Style, SvgElement};
+use crate::raster::Pixmap;
+use crate::tree::{FillRule, LineCap, LineJoin, TextAnchor, Tree};
+use euclid::default::Size2D;
+use std::rc::Rc;
+use xml5ever::buffer_queue::BufferQueue;
+use xml5ever::tendril::StrTendril;
+use xml5ever::tokenizer::{CharacterTokens, EmptyTag, EndTag, ShortTag, StartTag, TagToken};
+use xml5ever::tokenizer::{Token, TokenSink, XmlTokenizer};
+
+/// The size of images without an intrinsic size.
+///
+/// <https://drafts.csswg.org/css-images-3/#default-object-size>
+const DEFAULT_SIZE: (f32, f32) = (300., 150.);
+
+/// The largest width or height an image is rasterized at.
+const MAX_SIZE: f32 = 2048.;
+
+/// A parsed SVG document.
+pub struct Document {
+    pub tree: Tree,
+    /// The intrinsic size of the document, in CSS pixels.
+    pub size: Size2D<f32>,
+}
+
+impl Document {
+    /// Parses an SVG document, returning `None` if its root isn't an `<svg>`
+    /// element.
+    pub fn parse(data: &[u8]) -> Option<Document> {
+        let text = String::from_utf8_lossy(data);
+        let mut input = BufferQueue::new();
+        input.push_back(StrTendril::from_slice(&text));
+        let mut tokenizer = XmlTokenizer::new(Sink::default(), Default::default());
+        tokenizer.feed(&mut input);
+        tokenizer.end();
+        let root = tokenizer.sink.finish()?;
+        if root.local_name() != "svg" {
+            return None;
+        }
+        let size = intrinsic_size(&root);
+        let (tree, _) = builder::build(&root, size);
+        Some(Document { tree, size })
+    }
+
+    /// Rasterizes the document at its intrinsic size.
+    pub fn render(&self) -> Pixmap {
+        self.tree.render(self.size, 1.)
+    }
+}
+
+/// Whether some data looks like an SVG document.
+pub fn is_svg(data: &[u8]) -> bool {
+    let data = if data.starts_with(b"\xEF\xBB\xBF") {
+        &data[3..]
+    } else {
+        data
+    };
+    let start = match data.iter().position(|byte| !byte.is_ascii_whitespace()) {
+        Some(start) => start,
+        None => return false,
+    };
+    if data[start] != b'<' {
+        return false;
+    }
+    // The root element can be preceded by an XML declaration, a doctype and
+    // comments.
+    let head = &data[start..data.len().min(start + 4096)];
+    head.windows(4).any(|window| window == b"<svg")
+}
+
+/// <https://www.w3.org/TR/SVG2/coords.html#SizingSVGInCSS>
+fn intrinsic_size(root: &Element) -> Size2D<f32> {
+    let absolute = |name| {
+        root.attribute(name)
+            .and_then(|value| attr::parse_length(value, Style::default().font_size))
+            .filter(|length| length.percentage == 0. && length.absolute > 0.)
+            .map(|length| length.absolute)
+    };
+    let ratio = root
+        .attribute("viewBox")
+        .and_then(attr::parse_view_box)
+        .filter(|view_box| view_box.size.width > 0. && view_box.size.height > 0.)
+        .map(|view_box| view_box.size.width / view_box.size.height);
+    let (width, height) = match (absolute("width"), absolute("height"), ratio) {
+        (Some(width), Some(height), _) => (width, height),
+        (Some(width), None, Some(ratio)) => (width, width / ratio),
+        (None, Some(height), Some(ratio)) => (height * ratio, height),
+        (None, None, Some(ratio)) => (DEFAULT_SIZE.0, DEFAULT_SIZE.0 / ratio),
+        (width, height, None) => (
+            width.unwrap_or(DEFAULT_SIZE.0),
+            height.unwrap_or(DEFAULT_SIZE.1),
+        ),
+    };
+    // Keep the aspect ratio of huge images.
+    let scale = (MAX_SIZE / width.max(height)).min(1.);
+    Size2D::new(
+        (width * scale).max(1.).round(),
+        (height * scale).max(1.).round(),
+    )
+}
+
+#[derive(Debug)]
+struct ElementData {
+    local_name: String,
+    /// The attributes of the element, by prefix and local name.
+    attributes: Vec<(Option<String>, String, String)>,
+    children: Vec<Content>,
+}
+
+#[derive(Debug)]
+enum Content {
+    Element(Element),
+    Text(String),
+}
+
+#[derive(Clone, Debug)]
+struct Element(Rc<ElementData>);
+
+/// Builds the element tree of a document from the tokens of the XML
+/// tokenizer.
+#[derive(Default)]
+struct Sink {
+    /// The elements that have been opened but not closed yet.
+    open_elements: Vec<ElementData>,
+    root: Option<Element>,
+}
+
+impl Sink {
+    fn close_element(&mut self) {
+        let element = match self.open_elements.pop() {
+            Some(element) => Element(Rc::new(element)),
+            None => return,
+        };
+        match self.open_elements.last_mut() {
+            Some(parent) => parent.children.push(Content::Element(element)),
+            None => {
+                if self.root.is_none() {
+                    self.root = Some(element);
+                }
+            },
+        }
+    }
+
+    fn finish(mut self) -> Option<Element> {
+        while !self.open_elements.is_empty() {
+            self.close_element();
+        }
+        self.root
+    }
+}
+
+impl TokenSink for Sink {
+    fn process_token(&mut self, token: Token) {
+        match token {
+            TagToken(tag) => match tag.kind {
+                StartTag | EmptyTag => {
+                    if self.root.is_some() {
+                        return;
+                    }
+                    let attributes = tag
+                        .attrs
+                        .iter()
+                        .map(|attribute| {
+                            (
+                                attribute
+                                    .name
+                                    .prefix
+                                    .as_ref()
+                                    .map(|prefix| prefix.to_string()),
+                                attribute.name.local.to_string(),
+                                attribute.value.to_string(),
+                            )
+                        })
+                        .collect();
+                    self.open_elements.push(ElementData {
+                        local_name: tag.name.local.to_string(),
+                        attributes,
+                        children: vec![],
+                    });
+                    if tag.kind == EmptyTag {
+                        self.close_element();
+                    }
+                },
+                EndTag => {
+                    // Close the matching element, along with the elements that
+                    // are still open inside it.
+                    let local_name = &*tag.name.local;
+                    if let Some(index) = self
+                        .open_elements
+                        .iter()
+                        .rposition(|element| element.local_name == local_name)
+                    {
+                        while self.open_elements.len() > index {
+                            self.close_element();
+                        }
+                    }
+                },
+                ShortTag => self.close_element(),
+            },
+            CharacterTokens(text) => {
+                if let Some(element) = self.open_elements.last_mut() {
+                    element.children.push(Content::Text(text.to_string()));
+                }
+            },
+            _ => {},
+        }
+    }
+}
+
+/// The presentation attributes supported by the builder.
+///
+/// <https://www.w3.org/TR/SVG2/styling.html#PresentationAttributes>
+const PRESENTATION_ATTRIBUTES: &[&str] = &[
+    "color",
+    "display",
+    "fill",
+    "fill-opacity",
+    "fill-rule",
+    "font-size",
+    "opacity",
+    "stop-color",
+    "stop-opacity",
+    "stroke",
+    "stroke-dasharray",
+    "stroke-dashoffset",
+    "stroke-linecap",
+    "stroke-linejoin",
+    "stroke-miterlimit",
+    "stroke-opacity",
+    "stroke-width",
+    "text-anchor",
+    "visibility",
+];
+
+impl Element {
+    /// The declarations that apply to this element, in cascade order.
+    fn declarations(&self) -> Vec<(&str, &str)> {
+        let mut declarations: Vec<_> = self
+            .0
+            .attributes
+            .iter()
+            .filter(|(prefix, name, _)| {
+                prefix.is_none() && PRESENTATION_ATTRIBUTES.contains(&&**name)
+            })
+            .map(|(_, name, value)| (&**name, &**value))
+            .collect();
+        if let Some(style) = self.attribute("style") {
+            declarations.extend(style.split(';').filter_map(|declaration| {
+                let mut parts = declaration.splitn(2, ':');
+                let name = parts.next()?.trim();
+                let value = parts.next()?.trim();
+                let value = value.trim_end_matches("!important").trim();
+                Some((name, value))
+            }));
+        }
+        declarations
+    }
+}
+
+impl SvgElement for Element {
+    fn local_name(&self) -> &str {
+        &self.0.local_name
+    }
+
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.0
+            .attributes
+            .iter()
+            .find(|(prefix, local_name, _)| prefix.is_none() && local_name == name)
+            .map(|(_, _, value)| &**value)
+    }
+
+    fn href(&self) -> Option<&str> {
+        self.attribute("href").or_else(|| {
+            self.0
+                .attributes
+                .iter()
+                .find(|(prefix, local_name, _)| {
+                    prefix.as_ref().map_or(false, |prefix| prefix == "xlink") &&
+                        local_name == "href"
+                })
+                .map(|(_, _, value)| &**value)
+        })
+    }
+
+    fn children(&self) -> Vec<Element> {
+        self.0
+            .children
+            .iter()
+            .filter_map(|child| match *child {
+                Content::Element(ref element) => Some(element.clone()),
+                Content::Text(_) => None,
+            })
+            .collect()
+    }
+
+    fn text_content(&self) -> String {
+        let mut text = String::new();
+        for child in &self.0.children {
+            match *child {
+                Content::Element(ref element) => text.push_str(&element.text_content()),
+                Content::Text(ref content) => text.push_str(content),
+            }
+        }
+        text
+    }
+
+    /// A minimal cascade of the declarations of the element.
+    fn style(&self, parent: &Style) -> Style {
+        // Inherit the inherited properties, and reset the others.
+        let initial = Style::default();
+        let mut style = Style {
+            displayed: initial.displayed,
+            opacity: initial.opacity,
+            stop_color: initial.stop_color,
+            stop_opacity: initial.stop_opacity,
+            ..parent.clone()
+        };
+        let declarations = self.declarations();
+        // The values of other properties can depend on `font-size` and
+        // `color`, so those are computed first.
+        let (first, rest): (Vec<_>, Vec<_>) = declarations
+            .into_iter()
+            .partition(|&(name, _)| name == "font-size" || name == "color");
+        for (name, value) in first.into_iter().chain(rest) {
+            if value == "inherit" {
+                inherit(&mut style, parent, name);
+            } else {
+                apply(&mut style, parent, name, value);
+            }
+        }
+        style
+    }
+}
+
+fn inherit(style: &mut Style, parent: &Style, name: &str) {
+    match name {
+        "display" => style.displayed = parent.displayed,
+        "opacity" => style.opacity = parent.opacity,
+        "stop-color" => style.stop_color = parent.stop_color,
+        "stop-opacity" => style.stop_opacity = parent.stop_opacity,
+        // The other properties are inherited by default.
+        _ => {},
+    }
+}
+
+/// Sets the value of a property, ignoring invalid values.
+fn apply(style: &mut Style, parent: &Style, name: &str, value: &str) {
+    let opacity = || attr::parse_number_or_percentage(value).map(|opacity| opacity.max(0.).min(1.));
+    let length = || attr::parse_length(value, style.font_size);
+    match name {
+        "color" => {
+            if let Some(color) = attr::parse_color(value) {
+                style.color = color.unwrap_or(parent.color);
+            }
+        },
+        "display" => style.displayed = value != "none",
+        "fill" => {
+            if let Some(paint) = parse_paint(value, style) {
+                style.fill = paint;
+            }
+        },
+        "fill-opacity" => style.fill_opacity = opacity().unwrap_or(style.fill_opacity),
+        "fill-rule" => match value {
+            "nonzero" => style.fill_rule = FillRule::NonZero,
+            "evenodd" => style.fill_rule = FillRule::EvenOdd,
+            _ => {},
+        },
+        "font-size" => {
+            if let Some(size) = attr::parse_length(value, parent.font_size) {
+                style.font_size = size.resolve(parent.font_size).max(0.);
+            }
+        },
+        "opacity" => style.opacity = opacity().unwrap_or(style.opacity),
+        "stop-color" => {
+            if let Some(color) = attr::parse_color(value) {
+                style.stop_color = color.unwrap_or(style.color);
+            }
+        },
+        "stop-opacity" => style.stop_opacity = opacity().unwrap_or(style.stop_opacity),
+        "stroke" => {
+            if let Some(paint) = parse_paint(value, style) {
+                style.stroke = paint;
+            }
+        },
+        "stroke-dasharray" => {
+            if value == "none" {
+                style.stroke_dash_array = vec![];
+            } else {
+                let lengths = attr::parse_length_list(value, style.font_size);
+                if !lengths.is_empty() {
+                    style.stroke_dash_array = lengths;
+                }
+            }
+        },
+        "stroke-dashoffset" => {
+            style.stroke_dash_offset = length().unwrap_or(style.stroke_dash_offset)
+        },
+        "stroke-linecap" => match value {
+            "butt" => style.stroke_line_cap = LineCap::Butt,
+            "round" => style.stroke_line_cap = LineCap::Round,
+            "square" => style.stroke_line_cap = LineCap::Square,
+            _ => {},
+        },
+        "stroke-linejoin" => match value {
+            "miter" | "miter-clip" | "arcs" => style.stroke_line_join = LineJoin::Miter,
+            "round" => style.stroke_line_join = LineJoin::Round,
+            "bevel" => style.stroke_line_join = LineJoin::Bevel,
+            _ => {},
+        },
+        "stroke-miterlimit" => {
+            if let Some(limit) = attr::parse_number(value).filter(|limit| *limit >= 1.) {
+                style.stroke_miter_limit = limit;
+            }
+        },
+        "stroke-opacity" => style.stroke_opacity = opacity().unwrap_or(style.stroke_opacity),
+        "stroke-width" => {
+            if let Some(width) = length().filter(|width: &Length| width.absolute >= 0.) {
+                style.stroke_width = width;
+            }
+        },
+        "text-anchor" => match value {
+            "start" => style.text_anchor = TextAnchor::Start,
+            "middle" => style.text_anchor = TextAnchor::Middle,
+            "end" => style.text_anchor = TextAnchor::End,
+            _ => {},
+        },
+        "visibility" => match value {
+            "visible" => style.visible = true,
+            "hidden" | "collapse" => style.visible = false,
+            _ => {},
+        },
+        _ => {},
+    }
+}
+
+/// <https://www.w3.org/TR/SVG2/painting.html#SpecifyingPaint>
+fn parse_paint(value: &str, style: &Style) -> Option<PaintValue> {
+    if value == "none" {
+        return Some(PaintValue::None);
+    }
+    if value.starts_with("url(") {
+        let end = value.find(')')?;
+        let url = value[4..end].trim().trim_matches(|c| c == '"' || c == '\'');
+        let id = attr::parse_reference(url)?.to_owned();
+        let fallback = value[end + 1..].trim();
+        let fallback = match fallback {
+            "" | "none" => None,
+            fallback => Some(attr::parse_color(fallback)?.unwrap_or(style.color)),
+        };
+        return Some(PaintValue::Url(id, fallback));
+    }
+    let color = attr::parse_color(value)?;
+    Some(PaintValue::Color(color.unwrap_or(style.color)))
+}
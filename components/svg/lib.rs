@@ -0,0 +1,24 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Rendering of SVG content.
+//!
+//! SVG elements are turned into a render tree by the builder, either from the
+//! DOM of a page for inline `<svg>` elements, or from a standalone document
+//! for SVG images. The shapes of the tree are then rasterized in software.
+
+#![deny(unsafe_code)]
+
+pub mod attr;
+mod builder;
+mod document;
+pub mod path;
+mod raster;
+mod stroke;
+pub mod tree;
+
+pub use crate::builder::{build, PaintValue, Style, SvgElement};
+pub use crate::document::{is_svg, Document};
+pub use crate::raster::{fill_mask, Pixmap};
+pub use crate::tree::Tree;
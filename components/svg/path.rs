@@ -0,0 +1,650 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! The geometry of SVG shapes.
+
+use euclid::default::{Point2D, Rect, Size2D, Transform2D, Vector2D};
+use std::f32::consts::PI;
+use std::mem;
+use style::values::specified::svg_path::{PathCommand, SVGPathData};
+
+/// The distance from the ends of the control points of a cubic Bézier curve
+/// approximating a quarter of a unit circle.
+const KAPPA: f32 = 0.552_284_8;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PathSegment {
+    MoveTo(Point2D<f32>),
+    LineTo(Point2D<f32>),
+    CubicTo(Point2D<f32>, Point2D<f32>, Point2D<f32>),
+    Close,
+}
+
+/// A path made of straight lines and cubic Bézier curves, in user units.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Path {
+    segments: Vec<PathSegment>,
+    start: Point2D<f32>,
+    current: Point2D<f32>,
+}
+
+/// A flattened subpath.
+#[derive(Clone, Debug)]
+pub struct Polyline {
+    pub points: Vec<Point2D<f32>>,
+    pub closed: bool,
+}
+
+impl Path {
+    pub fn new() -> Path {
+        Path::default()
+    }
+
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.segments
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    pub fn move_to(&mut self, point: Point2D<f32>) {
+        self.segments.push(PathSegment::MoveTo(point));
+        self.start = point;
+        self.current = point;
+    }
+
+    pub fn line_to(&mut self, point: Point2D<f32>) {
+        self.ensure_subpath();
+        self.segments.push(PathSegment::LineTo(point));
+        self.current = point;
+    }
+
+    pub fn quad_to(&mut self, control: Point2D<f32>, point: Point2D<f32>) {
+        self.ensure_subpath();
+        let control1 = self.current + (control - self.current) * (2. / 3.);
+        let control2 = point + (control - point) * (2. / 3.);
+        self.cubic_to(control1, control2, point);
+    }
+
+    pub fn cubic_to(
+        &mut self,
+        control1: Point2D<f32>,
+        control2: Point2D<f32>,
+        point: Point2D<f32>,
+    ) {
+        self.ensure_subpath();
+        self.segments
+            .push(PathSegment::CubicTo(control1, control2, point));
+        self.current = point;
+    }
+
+    pub fn close(&mut self) {
+        if self.segments.is_empty() {
+            return;
+        }
+        self.segments.push(PathSegment::Close);
+        self.current = self.start;
+    }
+
+    /// Adds an elliptical arc from the current point, as described by the
+    /// parameters of an SVG path arc command.
+    ///
+    /// <https://www.w3.org/TR/SVG2/implnote.html#ArcImplementationNotes>
+    pub fn arc_to(
+        &mut self,
+        radii: Vector2D<f32>,
+        x_axis_rotation: f32,
+        large_arc: bool,
+        sweep: bool,
+        point: Point2D<f32>,
+    ) {
+        self.ensure_subpath();
+        let from = self.current;
+        let (mut rx, mut ry) = (radii.x.abs(), radii.y.abs());
+        if from == point {
+            return;
+        }
+        if rx == 0. || ry == 0. {
+            return self.line_to(point);
+        }
+
+        // Compute the center of the ellipse (F.6.5).
+        let (sin, cos) = x_axis_rotation.to_radians().sin_cos();
+        let half = (from - point) / 2.;
+        let x1 = cos * half.x + sin * half.y;
+        let y1 = -sin * half.x + cos * half.y;
+
+        // Scale the radii up if they can't span the endpoints (F.6.6).
+        let lambda = (x1 * x1) / (rx * rx) + (y1 * y1) / (ry * ry);
+        if lambda > 1. {
+            rx *= lambda.sqrt();
+            ry *= lambda.sqrt();
+        }
+
+        let numerator = rx * rx * ry * ry - rx * rx * y1 * y1 - ry * ry * x1 * x1;
+        let denominator = rx * rx * y1 * y1 + ry * ry * x1 * x1;
+        let mut factor = (numerator / denominator).max(0.).sqrt();
+        if large_arc == sweep {
+            factor = -factor;
+        }
+        let cx1 = factor * rx * y1 / ry;
+        let cy1 = -factor * ry * x1 / rx;
+        let mid = from + (point - from) / 2.;
+        let center = Point2D::new(cos * cx1 - sin * cy1 + mid.x, sin * cx1 + cos * cy1 + mid.y);
+
+        let angle = |ux: f32, uy: f32, vx: f32, vy: f32| {
+            let sign = if ux * vy - uy * vx < 0. { -1. } else { 1. };
+            let dot = (ux * vx + uy * vy) / ((ux * ux + uy * uy) * (vx * vx + vy * vy)).sqrt();
+            sign * dot.max(-1.).min(1.).acos()
+        };
+        let start_angle = angle(1., 0., (x1 - cx1) / rx, (y1 - cy1) / ry);
+        let mut sweep_angle = angle(
+            (x1 - cx1) / rx,
+            (y1 - cy1) / ry,
+            (-x1 - cx1) / rx,
+            (-y1 - cy1) / ry,
+        );
+        if !sweep && sweep_angle > 0. {
+            sweep_angle -= 2. * PI;
+        } else if sweep && sweep_angle < 0. {
+            sweep_angle += 2. * PI;
+        }
+
+        // Approximate the arc with one cubic Bézier curve per quarter turn at
+        // most.
+        let transform = Transform2D::create_scale(rx, ry).post_transform(&Transform2D::row_major(
+            cos, sin, -sin, cos, center.x, center.y,
+        ));
+        let count = (sweep_angle.abs() / (PI / 2.)).ceil().max(1.);
+        let step = sweep_angle / count;
+        let handle = 4. / 3. * (step / 4.).tan();
+        let mut theta = start_angle;
+        for index in 0..count as usize {
+            let (sin1, cos1) = theta.sin_cos();
+            let (sin2, cos2) = (theta + step).sin_cos();
+            let control1 = Point2D::new(cos1 - handle * sin1, sin1 + handle * cos1);
+            let control2 = Point2D::new(cos2 + handle * sin2, sin2 - handle * cos2);
+            let end = if index + 1 == count as usize {
+                point
+            } else {
+                transform.transform_point(Point2D::new(cos2, sin2))
+            };
+            self.cubic_to(
+                transform.transform_point(control1),
+                transform.transform_point(control2),
+                end,
+            );
+            theta += step;
+        }
+    }
+
+    /// Path data that doesn't start with a moveto command draws from the
+    /// origin.
+    fn ensure_subpath(&mut self) {
+        if self.segments.is_empty() {
+            self.move_to(Point2D::zero());
+        }
+    }
+
+    /// Builds the path described by the `d` attribute of a `<path>` element.
+    ///
+    /// <https://www.w3.org/TR/SVG2/paths.html#PathData>
+    pub fn from_path_data(data: &SVGPathData) -> Path {
+        let mut path = Path::new();
+        // The control point reflected by smooth curve commands.
+        let mut last_cubic_control = None;
+        let mut last_quad_control = None;
+        for command in data.normalize().iter() {
+            let current = path.current;
+            let mut cubic_control = None;
+            let mut quad_control = None;
+            match *command {
+                PathCommand::MoveTo { point, .. } => {
+                    path.move_to(Point2D::new(point.x(), point.y()));
+                },
+                PathCommand::LineTo { point, .. } => {
+                    path.line_to(Point2D::new(point.x(), point.y()));
+                },
+                PathCommand::HorizontalLineTo { x, .. } => {
+                    path.line_to(Point2D::new(x, current.y));
+                },
+                PathCommand::VerticalLineTo { y, .. } => {
+                    path.line_to(Point2D::new(current.x, y));
+                },
+                PathCommand::CurveTo {
+                    control1,
+                    control2,
+                    point,
+                    ..
+                } => {
+                    let control2 = Point2D::new(control2.x(), control2.y());
+                    path.cubic_to(
+                        Point2D::new(control1.x(), control1.y()),
+                        control2,
+                        Point2D::new(point.x(), point.y()),
+                    );
+                    cubic_control = Some(control2);
+                },
+                PathCommand::SmoothCurveTo {
+                    control2, point, ..
+                } => {
+                    let control1 = last_cubic_control.map_or(current, |control: Point2D<f32>| {
+                        current + (current - control)
+                    });
+                    let control2 = Point2D::new(control2.x(), control2.y());
+                    path.cubic_to(control1, control2, Point2D::new(point.x(), point.y()));
+                    cubic_control = Some(control2);
+                },
+                PathCommand::QuadBezierCurveTo {
+                    control1, point, ..
+                } => {
+                    let control = Point2D::new(control1.x(), control1.y());
+                    path.quad_to(control, Point2D::new(point.x(), point.y()));
+                    quad_control = Some(control);
+                },
+                PathCommand::SmoothQuadBezierCurveTo { point, .. } => {
+                    let control = last_quad_control.map_or(current, |control: Point2D<f32>| {
+                        current + (current - control)
+                    });
+                    path.quad_to(control, Point2D::new(point.x(), point.y()));
+                    quad_control = Some(control);
+                },
+                PathCommand::EllipticalArc {
+                    rx,
+                    ry,
+                    angle,
+                    large_arc_flag,
+                    sweep_flag,
+                    point,
+                    ..
+                } => {
+                    path.arc_to(
+                        Vector2D::new(rx, ry),
+                        angle,
+                        large_arc_flag.is_set(),
+                        sweep_flag.is_set(),
+                        Point2D::new(point.x(), point.y()),
+                    );
+                },
+                PathCommand::ClosePath => path.close(),
+                PathCommand::Unknown => {},
+            }
+            last_cubic_control = cubic_control;
+            last_quad_control = quad_control;
+        }
+        path
+    }
+
+    /// <https://www.w3.org/TR/SVG2/shapes.html#RectElement>
+    pub fn rect(rect: Rect<f32>, radii: Size2D<f32>) -> Path {
+        let mut path = Path::new();
+        if rect.size.width <= 0. || rect.size.height <= 0. {
+            return path;
+        }
+        let rx = radii.width.max(0.).min(rect.size.width / 2.);
+        let ry = radii.height.max(0.).min(rect.size.height / 2.);
+        let (left, top) = (rect.min_x(), rect.min_y());
+        let (right, bottom) = (rect.max_x(), rect.max_y());
+        if rx == 0. || ry == 0. {
+            path.move_to(Point2D::new(left, top));
+            path.line_to(Point2D::new(right, top));
+            path.line_to(Point2D::new(right, bottom));
+            path.line_to(Point2D::new(left, bottom));
+            path.close();
+            return path;
+        }
+        let (kx, ky) = (rx * KAPPA, ry * KAPPA);
+        path.move_to(Point2D::new(left + rx, top));
+        path.line_to(Point2D::new(right - rx, top));
+        path.cubic_to(
+            Point2D::new(right - rx + kx, top),
+            Point2D::new(right, top + ry - ky),
+            Point2D::new(right, top + ry),
+        );
+        path.line_to(Point2D::new(right, bottom - ry));
+        path.cubic_to(
+            Point2D::new(right, bottom - ry + ky),
+            Point2D::new(right - rx + kx, bottom),
+            Point2D::new(right - rx, bottom),
+        );
+        path.line_to(Point2D::new(left + rx, bottom));
+        path.cubic_to(
+            Point2D::new(left + rx - kx, bottom),
+            Point2D::new(left, bottom - ry + ky),
+            Point2D::new(left, bottom - ry),
+        );
+        path.line_to(Point2D::new(left, top + ry));
+        path.cubic_to(
+            Point2D::new(left, top + ry - ky),
+            Point2D::new(left + rx - kx, top),
+            Point2D::new(left + rx, top),
+        );
+        path.close();
+        path
+    }
+
+    /// <https://www.w3.org/TR/SVG2/shapes.html#EllipseElement>
+    pub fn ellipse(center: Point2D<f32>, radii: Size2D<f32>) -> Path {
+        let mut path = Path::new();
+        let (rx, ry) = (radii.width, radii.height);
+        if rx <= 0. || ry <= 0. {
+            return path;
+        }
+        let (kx, ky) = (rx * KAPPA, ry * KAPPA);
+        let (x, y) = (center.x, center.y);
+        path.move_to(Point2D::new(x + rx, y));
+        path.cubic_to(
+            Point2D::new(x + rx, y + ky),
+            Point2D::new(x + kx, y + ry),
+            Point2D::new(x, y + ry),
+        );
+        path.cubic_to(
+            Point2D::new(x - kx, y + ry),
+            Point2D::new(x - rx, y + ky),
+            Point2D::new(x - rx, y),
+        );
+        path.cubic_to(
+            Point2D::new(x - rx, y - ky),
+            Point2D::new(x - kx, y - ry),
+            Point2D::new(x, y - ry),
+        );
+        path.cubic_to(
+            Point2D::new(x + kx, y - ry),
+            Point2D::new(x + rx, y - ky),
+            Point2D::new(x + rx, y),
+        );
+        path.close();
+        path
+    }
+
+    /// The path of a `<line>`, `<polyline>` or `<polygon>` element.
+    pub fn polyline(points: &[Point2D<f32>], closed: bool) -> Path {
+        let mut path = Path::new();
+        let mut points = points.iter();
+        if let Some(&first) = points.next() {
+            path.move_to(first);
+            for &point in points {
+                path.line_to(point);
+            }
+            if closed {
+                path.close();
+            }
+        }
+        path
+    }
+
+    /// Approximates the path with straight lines, after applying the given
+    /// transform, so that the error is at most `tolerance`.
+    pub fn flatten(&self, transform: &Transform2D<f32>, tolerance: f32) -> Vec<Polyline> {
+        let mut polylines = vec![];
+        let mut points = vec![];
+        let mut start = Point2D::zero();
+        // Whether the current subpath has segments. A lone moveto draws
+        // nothing, not even line caps.
+        let mut drawn = false;
+        for segment in &self.segments {
+            match *segment {
+                PathSegment::MoveTo(point) => {
+                    if drawn {
+                        polylines.push(Polyline {
+                            points: mem::replace(&mut points, vec![]),
+                            closed: false,
+                        });
+                    }
+                    start = transform.transform_point(point);
+                    points = vec![start];
+                    drawn = false;
+                },
+                PathSegment::LineTo(point) => {
+                    points.push(transform.transform_point(point));
+                    drawn = true;
+                },
+                PathSegment::CubicTo(control1, control2, point) => {
+                    let from = points.last().cloned().unwrap_or(start);
+                    flatten_cubic(
+                        from,
+                        transform.transform_point(control1),
+                        transform.transform_point(control2),
+                        transform.transform_point(point),
+                        tolerance,
+                        &mut points,
+                    );
+                    drawn = true;
+                },
+                PathSegment::Close => {
+                    polylines.push(Polyline {
+                        points: mem::replace(&mut points, vec![start]),
+                        closed: true,
+                    });
+                    drawn = false;
+                },
+            }
+        }
+        if drawn {
+            polylines.push(Polyline {
+                points,
+                closed: false,
+            });
+        }
+        polylines
+    }
+
+    /// The bounding box of the path, in user units.
+    ///
+    /// <https://www.w3.org/TR/SVG2/coords.html#BoundingBoxes>
+    pub fn bounds(&self) -> Rect<f32> {
+        let polylines = self.flatten(&Transform2D::identity(), 0.01);
+        let mut points = polylines.iter().flat_map(|polyline| polyline.points.iter());
+        let first = match points.next() {
+            Some(&first) => first,
+            None => return Rect::zero(),
+        };
+        let (mut min, mut max) = (first, first);
+        for point in points {
+            min = Point2D::new(min.x.min(point.x), min.y.min(point.y));
+            max = Point2D::new(max.x.max(point.x), max.y.max(point.y));
+        }
+        Rect::new(min, (max - min).to_size())
+    }
+}
+
+/// Approximates a cubic Bézier curve with lines, appending the points after
+/// the starting one to `points`.
+fn flatten_cubic(
+    from: Point2D<f32>,
+    control1: Point2D<f32>,
+    control2: Point2D<f32>,
+    to: Point2D<f32>,
+    tolerance: f32,
+    points: &mut Vec<Point2D<f32>>,
+) {
+    // The distance between the curve and its chords shrinks with the square
+    // of the number of chords, proportionally to the second derivative.
+    let deviation1 = (from.to_vector() - control1.to_vector() * 2. + control2.to_vector()).length();
+    let deviation2 = (control1.to_vector() - control2.to_vector() * 2. + to.to_vector()).length();
+    let deviation = deviation1.max(deviation2);
+    let count = (deviation * 0.75 / tolerance).sqrt().ceil();
+    let count = if count.is_finite() {
+        count.max(1.).min(1000.) as usize
+    } else {
+        1
+    };
+    for index in 1..count {
+        let t = index as f32 / count as f32;
+        let u = 1. - t;
+        let point = from.to_vector() * (u * u * u) +
+            control1.to_vector() * (3. * u * u * t) +
+            control2.to_vector() * (3. * u * t * t) +
+            to.to_vector() * (t * t * t);
+        points.push(point.to_point());
+    }
+    points.push(to);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(data: &str) -> Path {
+        Path::from_path_data(&SVGPathData::parse_path_string(data).unwrap())
+    }
+
+    fn assert_near(actual: Point2D<f32>, expected: Point2D<f32>) {
+        assert!(
+            (actual - expected).length() < 1e-3,
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_lines() {
+        let path = parse("M 10 10 h 20 v 20 H 10 z");
+        assert_eq!(
+            path.segments(),
+            &[
+                PathSegment::MoveTo(Point2D::new(10., 10.)),
+                PathSegment::LineTo(Point2D::new(30., 10.)),
+                PathSegment::LineTo(Point2D::new(30., 30.)),
+                PathSegment::LineTo(Point2D::new(10., 30.)),
+                PathSegment::Close,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_relative_commands() {
+        let path = parse("m 5 5 l 10 0 m 0 10 l -10 0 z l 5 5");
+        assert_eq!(
+            path.segments(),
+            &[
+                PathSegment::MoveTo(Point2D::new(5., 5.)),
+                PathSegment::LineTo(Point2D::new(15., 5.)),
+                PathSegment::MoveTo(Point2D::new(15., 15.)),
+                PathSegment::LineTo(Point2D::new(5., 15.)),
+                PathSegment::Close,
+                // Commands after a closepath start from the start of the
+                // closed subpath.
+                PathSegment::LineTo(Point2D::new(20., 20.)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_smooth_cubic_reflects_control_point() {
+        let path = parse("M 0 0 C 10 0 20 10 20 20 S 30 40 40 40");
+        assert_eq!(
+            path.segments()[2],
+            PathSegment::CubicTo(
+                Point2D::new(20., 30.),
+                Point2D::new(30., 40.),
+                Point2D::new(40., 40.)
+            )
+        );
+
+        // Without a previous curve, the first control point is the current
+        // point.
+        let path = parse("M 0 0 L 10 0 S 20 10 20 20");
+        assert_eq!(
+            path.segments()[2],
+            PathSegment::CubicTo(
+                Point2D::new(10., 0.),
+                Point2D::new(20., 10.),
+                Point2D::new(20., 20.)
+            )
+        );
+    }
+
+    #[test]
+    fn test_quadratic_curves() {
+        let path = parse("M 0 0 Q 30 0 30 30 T 60 60");
+        match path.segments()[1..] {
+            [PathSegment::CubicTo(a1, a2, a), PathSegment::CubicTo(b1, b2, b)] => {
+                assert_near(a1, Point2D::new(20., 0.));
+                assert_near(a2, Point2D::new(30., 10.));
+                assert_near(a, Point2D::new(30., 30.));
+                // The control point of the smooth curve is (30, 60).
+                assert_near(b1, Point2D::new(30., 50.));
+                assert_near(b2, Point2D::new(40., 60.));
+                assert_near(b, Point2D::new(60., 60.));
+            },
+            ref segments => panic!("unexpected segments {:?}", segments),
+        }
+    }
+
+    #[test]
+    fn test_arc_bounds() {
+        // A half circle above the x axis, since the positive-angle direction
+        // is clockwise when the y axis points down.
+        let bounds = parse("M 0 0 A 10 10 0 0 1 20 0").bounds();
+        assert_near(bounds.origin, Point2D::new(0., -10.));
+        assert_near(bounds.max(), Point2D::new(20., 0.));
+
+        // Radii too small to span the endpoints are scaled up.
+        let bounds = parse("M 0 0 A 1 1 0 0 0 20 0").bounds();
+        assert_near(bounds.origin, Point2D::new(0., 0.));
+        assert_near(bounds.max(), Point2D::new(20., 10.));
+
+        // An arc with a zero radius is a straight line.
+        let path = parse("M 0 0 A 0 10 0 0 1 20 0");
+        assert_eq!(
+            path.segments()[1],
+            PathSegment::LineTo(Point2D::new(20., 0.))
+        );
+    }
+
+    #[test]
+    fn test_basic_shapes() {
+        let rect = Rect::new(Point2D::new(1., 2.), Size2D::new(10., 4.));
+        assert_eq!(Path::rect(rect, Size2D::zero()).segments().len(), 5);
+        // The radii are clamped to half the size of the rectangle.
+        let rounded = Path::rect(rect, Size2D::new(10., 10.));
+        assert_near(rounded.bounds().origin, rect.origin);
+        assert_near(rounded.bounds().max(), rect.max());
+        assert!(Path::rect(rect.inflate(-5., 0.), Size2D::zero()).is_empty());
+
+        let ellipse = Path::ellipse(Point2D::new(10., 10.), Size2D::new(5., 2.));
+        assert_near(ellipse.bounds().origin, Point2D::new(5., 8.));
+        assert_near(ellipse.bounds().max(), Point2D::new(15., 12.));
+        assert!(Path::ellipse(Point2D::zero(), Size2D::new(0., 2.)).is_empty());
+
+        let points = [
+            Point2D::new(0., 0.),
+            Point2D::new(10., 0.),
+            Point2D::new(10., 5.),
+        ];
+        assert_eq!(Path::polyline(&points, false).segments().len(), 3);
+        assert_eq!(
+            Path::polyline(&points, true).segments().last(),
+            Some(&PathSegment::Close)
+        );
+    }
+
+    #[test]
+    fn test_flatten() {
+        let mut path = Path::new();
+        path.move_to(Point2D::new(0., 0.));
+        path.line_to(Point2D::new(10., 0.));
+        path.move_to(Point2D::new(20., 0.));
+        path.move_to(Point2D::new(0., 10.));
+        path.line_to(Point2D::new(10., 10.));
+        path.close();
+
+        let polylines = path.flatten(&Transform2D::create_scale(2., 1.), 0.1);
+        // The lone moveto doesn't produce a polyline.
+        assert_eq!(polylines.len(), 2);
+        assert!(!polylines[0].closed);
+        assert_eq!(
+            polylines[0].points,
+            vec![Point2D::new(0., 0.), Point2D::new(20., 0.)]
+        );
+        assert!(polylines[1].closed);
+        assert_eq!(
+            polylines[1].points,
+            vec![Point2D::new(0., 10.), Point2D::new(20., 10.)]
+        );
+    }
+}
@@ -0,0 +1,684 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Anti-aliased rasterization of render trees.
+
+use crate::path::Path;
+use crate::stroke;
+use crate::tree::{FillRule, Gradient, Node, Paint, PathNode, SpreadMethod};
+use euclid::default::{Point2D, Rect, Transform2D};
+use std::cmp::Ordering;
+
+/// The number of samples taken vertically in each pixel row. The horizontal
+/// coverage of each sample is computed exactly.
+const SUBSAMPLES: usize = 16;
+
+/// The maximum distance between a curve and the lines approximating it, in
+/// device pixels.
+const TOLERANCE: f32 = 0.1;
+
+/// Rasterized pixels, in premultiplied RGBA order.
+#[derive(Clone, Debug)]
+pub struct Pixmap {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+impl Pixmap {
+    /// Converts the pixels to non-premultiplied RGBA.
+    pub fn unpremultiply(&mut self) {
+        for pixel in self.data.chunks_mut(4) {
+            let alpha = pixel[3] as u32;
+            if alpha == 0 || alpha == 255 {
+                continue;
+            }
+            for component in &mut pixel[..3] {
+                *component = ((*component as u32 * 255 + alpha / 2) / alpha).min(255) as u8;
+            }
+        }
+    }
+}
+
+/// A premultiplied RGBA color.
+type Color = [f32; 4];
+
+/// A surface that nodes are painted onto, with floating-point premultiplied
+/// pixels.
+pub struct Canvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+}
+
+impl Canvas {
+    pub fn new(width: u32, height: u32) -> Canvas {
+        Canvas {
+            width: width as usize,
+            height: height as usize,
+            pixels: vec![[0.; 4]; width as usize * height as usize],
+        }
+    }
+
+    pub fn into_pixmap(self) -> Pixmap {
+        let data = self
+            .pixels
+            .iter()
+            .flat_map(|pixel| pixel.iter())
+            .map(|component| (component.max(0.).min(1.) * 255. + 0.5) as u8)
+            .collect();
+        Pixmap {
+            width: self.width as u32,
+            height: self.height as u32,
+            data,
+        }
+    }
+
+    pub fn draw_nodes(&mut self, nodes: &[Node], transform: &Transform2D<f32>) {
+        for node in nodes {
+            match *node {
+                Node::Group(ref group) => {
+                    let transform = group.transform.post_transform(transform);
+                    let clip = group.clip.map(|clip| {
+                        let corners = vec![
+                            Point2D::new(clip.min_x(), clip.min_y()),
+                            Point2D::new(clip.max_x(), clip.min_y()),
+                            Point2D::new(clip.max_x(), clip.max_y()),
+                            Point2D::new(clip.min_x(), clip.max_y()),
+                        ];
+                        let polygon = corners
+                            .into_iter()
+                            .map(|corner| transform.transform_point(corner))
+                            .collect();
+                        Coverage::new(&[polygon], FillRule::NonZero, self.width, self.height)
+                    });
+                    if group.opacity >= 1. && clip.is_none() {
+                        self.draw_nodes(&group.children, &transform);
+                    } else if group.opacity > 0. {
+                        // https://www.w3.org/TR/css-color-3/#transparency
+                        let mut layer = Canvas::new(self.width as u32, self.height as u32);
+                        layer.draw_nodes(&group.children, &transform);
+                        self.composite(&layer, group.opacity, clip.as_ref());
+                    }
+                },
+                Node::Path(ref path) => self.draw_path(path, transform),
+                Node::Text(_) => {},
+            }
+        }
+    }
+
+    /// <https://www.w3.org/TR/SVG2/render.html#PaintingShapesAndText>
+    fn draw_path(&mut self, node: &PathNode, transform: &Transform2D<f32>) {
+        // Degenerate transforms, like a zero scale, paint nothing.
+        let scale = transform_scale(transform);
+        if !(scale > 0.) {
+            return;
+        }
+        let bounds = node.path.bounds();
+
+        if let Some(ref fill) = node.fill {
+            let polylines = node.path.flatten(transform, TOLERANCE);
+            let polygons: Vec<_> = polylines
+                .into_iter()
+                .map(|polyline| polyline.points)
+                .collect();
+            let coverage = Coverage::new(&polygons, fill.rule, self.width, self.height);
+            self.fill(&coverage, &fill.paint, fill.opacity, transform, &bounds);
+        }
+
+        if let Some(ref stroke) = node.stroke {
+            // Stroke in user space, so that the stroke is transformed along
+            // with the path.
+            let polylines = node
+                .path
+                .flatten(&Transform2D::identity(), TOLERANCE / scale);
+            let polygons: Vec<_> = stroke::stroke_polygons(&polylines, stroke, TOLERANCE / scale)
+                .into_iter()
+                .map(|polygon| {
+                    polygon
+                        .into_iter()
+                        .map(|point| transform.transform_point(point))
+                        .collect()
+                })
+                .collect();
+            let coverage = Coverage::new(&polygons, FillRule::NonZero, self.width, self.height);
+            self.fill(&coverage, &stroke.paint, stroke.opacity, transform, &bounds);
+        }
+    }
+
+    /// Paints the given coverage with a paint server.
+    fn fill(
+        &mut self,
+        coverage: &Coverage,
+        paint: &Paint,
+        opacity: f32,
+        transform: &Transform2D<f32>,
+        bounds: &Rect<f32>,
+    ) {
+        let shader = match Shader::new(paint, opacity, transform, bounds) {
+            Some(shader) => shader,
+            None => return,
+        };
+        for y in coverage.top..coverage.bottom {
+            let row = &coverage.rows[(y - coverage.top) * self.width..][..self.width];
+            for (x, &alpha) in row.iter().enumerate() {
+                if alpha <= 0. {
+                    continue;
+                }
+                let source = shader.color_at(x as f32 + 0.5, y as f32 + 0.5);
+                let destination = &mut self.pixels[y * self.width + x];
+                let alpha = alpha.min(1.);
+                let inverse = 1. - source[3] * alpha;
+                for (destination, source) in destination.iter_mut().zip(source.iter()) {
+                    *destination = source * alpha + *destination * inverse;
+                }
+            }
+        }
+    }
+
+    /// Paints another canvas of the same size over this one with the given
+    /// opacity, only where the given coverage is, if any.
+    fn composite(&mut self, layer: &Canvas, opacity: f32, mask: Option<&Coverage>) {
+        let width = self.width;
+        let pixels = self.pixels.iter_mut().zip(layer.pixels.iter());
+        for (index, (destination, source)) in pixels.enumerate() {
+            let opacity = match mask {
+                Some(mask) => opacity * mask.at(index % width, index / width, width),
+                None => opacity,
+            };
+            let inverse = 1. - source[3] * opacity;
+            for (destination, source) in destination.iter_mut().zip(source.iter()) {
+                *destination = source * opacity + *destination * inverse;
+            }
+        }
+    }
+}
+
+/// Rasterizes the area a path fills, after applying the given transform, into
+/// an image of the given size with one byte of coverage per pixel, as used by
+/// clip masks.
+pub fn fill_mask(
+    path: &Path,
+    rule: FillRule,
+    transform: &Transform2D<f32>,
+    width: u32,
+    height: u32,
+) -> Vec<u8> {
+    let (width, height) = (width as usize, height as usize);
+    let polygons: Vec<_> = path
+        .flatten(transform, TOLERANCE)
+        .into_iter()
+        .map(|polyline| polyline.points)
+        .collect();
+    let coverage = Coverage::new(&polygons, rule, width, height);
+    (0..width * height)
+        .map(|index| (coverage.at(index % width, index / width, width) * 255. + 0.5) as u8)
+        .collect()
+}
+
+/// How much a transform scales lengths, on average.
+fn transform_scale(transform: &Transform2D<f32>) -> f32 {
+    transform.determinant().abs().sqrt()
+}
+
+/// The fraction of each pixel covered by some polygons, for the rows they
+/// span.
+struct Coverage {
+    top: usize,
+    bottom: usize,
+    rows: Vec<f32>,
+}
+
+/// An edge of a polygon, going downwards.
+struct Edge {
+    top: f32,
+    bottom: f32,
+    x: f32,
+    slope: f32,
+    winding: i32,
+}
+
+impl Coverage {
+    fn new(
+        polygons: &[Vec<Point2D<f32>>],
+        rule: FillRule,
+        width: usize,
+        height: usize,
+    ) -> Coverage {
+        let mut edges = vec![];
+        for polygon in polygons {
+            for index in 0..polygon.len() {
+                let from = polygon[index];
+                let to = polygon[(index + 1) % polygon.len()];
+                if from.y == to.y || !from.y.is_finite() || !to.y.is_finite() {
+                    continue;
+                }
+                let (top, bottom, winding) = if from.y < to.y {
+                    (from, to, 1)
+                } else {
+                    (to, from, -1)
+                };
+                let slope = (bottom.x - top.x) / (bottom.y - top.y);
+                if !top.x.is_finite() || !bottom.x.is_finite() || !slope.is_finite() {
+                    continue;
+                }
+                edges.push(Edge {
+                    top: top.y,
+                    bottom: bottom.y,
+                    x: top.x,
+                    slope,
+                    winding,
+                });
+            }
+        }
+        edges.sort_by(|a, b| a.top.partial_cmp(&b.top).unwrap_or(Ordering::Equal));
+
+        let top = edges
+            .first()
+            .map_or(0., |edge| edge.top.floor().max(0.).min(height as f32))
+            as usize;
+        let bottom = edges
+            .iter()
+            .fold(0f32, |bottom, edge| bottom.max(edge.bottom))
+            .ceil()
+            .max(top as f32)
+            .min(height as f32) as usize;
+
+        let mut rows = vec![0.; (bottom - top) * width];
+        // The changes of coverage accumulated along each row, which spans
+        // add to in constant time.
+        let mut deltas = vec![0f32; width + 1];
+        let mut active: Vec<usize> = vec![];
+        let mut next_edge = 0;
+        let mut crossings = vec![];
+        let weight = 1. / SUBSAMPLES as f32;
+        for y in top..bottom {
+            let row = &mut rows[(y - top) * width..][..width];
+            for delta in deltas.iter_mut() {
+                *delta = 0.;
+            }
+            for sample in 0..SUBSAMPLES {
+                let sample_y = y as f32 + (sample as f32 + 0.5) / SUBSAMPLES as f32;
+                while next_edge < edges.len() && edges[next_edge].top <= sample_y {
+                    active.push(next_edge);
+                    next_edge += 1;
+                }
+                active.retain(|&index| edges[index].bottom > sample_y);
+
+                crossings.clear();
+                for &index in &active {
+                    let edge = &edges[index];
+                    if edge.top <= sample_y {
+                        let x = edge.x + (sample_y - edge.top) * edge.slope;
+                        crossings.push((x, edge.winding));
+                    }
+                }
+                crossings.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+
+                let mut winding = 0;
+                for pair in crossings.windows(2) {
+                    winding += pair[0].1;
+                    let inside = match rule {
+                        FillRule::NonZero => winding != 0,
+                        FillRule::EvenOdd => winding % 2 != 0,
+                    };
+                    if inside {
+                        add_span(row, &mut deltas, pair[0].0, pair[1].0, weight);
+                    }
+                }
+            }
+            let mut accumulated = 0.;
+            for (coverage, delta) in row.iter_mut().zip(deltas.iter()) {
+                accumulated += delta;
+                *coverage += accumulated;
+            }
+        }
+
+        Coverage { top, bottom, rows }
+    }
+
+    /// The coverage of a pixel, for a coverage computed with the given width.
+    fn at(&self, x: usize, y: usize, width: usize) -> f32 {
+        if y < self.top || y >= self.bottom {
+            return 0.;
+        }
+        self.rows[(y - self.top) * width + x].min(1.)
+    }
+}
+
+/// Adds the coverage of a horizontal span of a sample line to a row.
+fn add_span(row: &mut [f32], deltas: &mut [f32], start: f32, end: f32, weight: f32) {
+    let width = row.len() as f32;
+    let (start, end) = (start.max(0.).min(width), end.max(0.).min(width));
+    if start >= end {
+        return;
+    }
+    let (first, last) = (start.floor(), end.floor());
+    let (first_index, last_index) = (first as usize, last as usize);
+    if first_index == last_index {
+        row[first_index] += (end - start) * weight;
+        return;
+    }
+    row[first_index] += (first + 1. - start) * weight;
+    deltas[first_index + 1] += weight;
+    deltas[last_index] -= weight;
+    if last_index < row.len() {
+        row[last_index] += (end - last) * weight;
+    }
+}
+
+/// Computes the color of a paint server at each point of the device space.
+enum Shader {
+    Solid(Color),
+    Linear {
+        /// The transform from device space to the gradient space, where the
+        /// gradient vector goes from (0, 0) to (1, 0).
+        inverse: Transform2D<f32>,
+        gradient: ColorRamp,
+    },
+    Radial {
+        /// The transform from device space to the gradient space, where the
+        /// focal point is at (0, 0), the center at (x, 0) and the radius 1.
+        inverse: Transform2D<f32>,
+        center_x: f32,
+        gradient: ColorRamp,
+    },
+}
+
+impl Shader {
+    fn new(
+        paint: &Paint,
+        opacity: f32,
+        transform: &Transform2D<f32>,
+        bounds: &Rect<f32>,
+    ) -> Option<Shader> {
+        let opacity = opacity.max(0.).min(1.);
+        match *paint {
+            Paint::Color(color) => Some(Shader::Solid(premultiply(
+                [
+                    color.red_f32(),
+                    color.green_f32(),
+                    color.blue_f32(),
+                    color.alpha_f32(),
+                ],
+                opacity,
+            ))),
+            Paint::LinearGradient(ref linear) => {
+                let to_user = gradient_transform(&linear.gradient, transform, bounds)?;
+                let vector = linear.end - linear.start;
+                // A gradient whose vector has no length paints the color of
+                // its last stop.
+                if vector.square_length() == 0. {
+                    return ColorRamp::new(&linear.gradient, opacity).map(|ramp| ramp.last());
+                }
+                let to_gradient = Transform2D::row_major(
+                    vector.x,
+                    vector.y,
+                    -vector.y,
+                    vector.x,
+                    linear.start.x,
+                    linear.start.y,
+                );
+                let inverse = to_gradient.post_transform(&to_user).inverse()?;
+                Some(Shader::Linear {
+                    inverse,
+                    gradient: ColorRamp::new(&linear.gradient, opacity)?,
+                })
+            },
+            Paint::RadialGradient(ref radial) => {
+                let to_user = gradient_transform(&radial.gradient, transform, bounds)?;
+                if radial.radius <= 0. {
+                    return ColorRamp::new(&radial.gradient, opacity).map(|ramp| ramp.last());
+                }
+                // A focal point outside of the circle is moved onto it.
+                let mut focal_vector = radial.focal_point - radial.center;
+                let max_distance = radial.radius * 0.999;
+                if focal_vector.length() > max_distance {
+                    focal_vector = focal_vector.normalize() * max_distance;
+                }
+                let focal_point = radial.center + focal_vector;
+                let axis = if focal_vector.square_length() > 0. {
+                    -focal_vector.normalize() * radial.radius
+                } else {
+                    euclid::default::Vector2D::new(radial.radius, 0.)
+                };
+                let to_gradient = Transform2D::row_major(
+                    axis.x,
+                    axis.y,
+                    -axis.y,
+                    axis.x,
+                    focal_point.x,
+                    focal_point.y,
+                );
+                let inverse = to_gradient.post_transform(&to_user).inverse()?;
+                Some(Shader::Radial {
+                    inverse,
+                    center_x: focal_vector.length() / radial.radius,
+                    gradient: ColorRamp::new(&radial.gradient, opacity)?,
+                })
+            },
+        }
+    }
+
+    fn color_at(&self, x: f32, y: f32) -> Color {
+        match *self {
+            Shader::Solid(color) => color,
+            Shader::Linear {
+                ref inverse,
+                ref gradient,
+            } => {
+                let point = inverse.transform_point(Point2D::new(x, y));
+                gradient.color_at(point.x)
+            },
+            Shader::Radial {
+                ref inverse,
+                center_x,
+                ref gradient,
+            } => {
+                // Find the circle, interpolated between the focal point and
+                // the end circle, that goes through the point.
+                let point = inverse.transform_point(Point2D::new(x, y));
+                let a = center_x * center_x - 1.;
+                let b = point.x * center_x;
+                let c = point.x * point.x + point.y * point.y;
+                let t = if a.abs() < 1e-6 {
+                    if b == 0. {
+                        0.
+                    } else {
+                        c / (2. * b)
+                    }
+                } else {
+                    (b - (b * b - a * c).max(0.).sqrt()) / a
+                };
+                gradient.color_at(t)
+            },
+        }
+    }
+}
+
+/// The transform from the coordinates of a gradient to device space.
+fn gradient_transform(
+    gradient: &Gradient,
+    transform: &Transform2D<f32>,
+    bounds: &Rect<f32>,
+) -> Option<Transform2D<f32>> {
+    let mut to_user = gradient.transform;
+    if gradient.bounding_box_units {
+        // Gradients relative to the bounding box of elements without width
+        // or height aren't painted.
+        if bounds.size.width <= 0. || bounds.size.height <= 0. {
+            return None;
+        }
+        let bounding_box = Transform2D::row_major(
+            bounds.size.width,
+            0.,
+            0.,
+            bounds.size.height,
+            bounds.origin.x,
+            bounds.origin.y,
+        );
+        to_user = to_user.post_transform(&bounding_box);
+    }
+    Some(to_user.post_transform(transform))
+}
+
+/// The stops of a gradient, with premultiplied colors.
+struct ColorRamp {
+    stops: Vec<(f32, Color)>,
+    spread: SpreadMethod,
+}
+
+impl ColorRamp {
+    /// <https://www.w3.org/TR/SVG2/pservers.html#StopNotes>
+    fn new(gradient: &Gradient, opacity: f32) -> Option<ColorRamp> {
+        let mut stops: Vec<(f32, Color)> = vec![];
+        for stop in &gradient.stops {
+            let previous = stops.last().map_or(0., |stop| stop.0);
+            let offset = stop.offset.max(0.).min(1.).max(previous);
+            let color = [
+                stop.color.red_f32(),
+                stop.color.green_f32(),
+                stop.color.blue_f32(),
+                stop.color.alpha_f32(),
+            ];
+            stops.push((offset, premultiply(color, opacity)));
+        }
+        // Gradients without stops paint nothing.
+        if stops.is_empty() {
+            return None;
+        }
+        Some(ColorRamp {
+            stops,
+            spread: gradient.spread,
+        })
+    }
+
+    fn last(&self) -> Shader {
+        Shader::Solid(self.stops[self.stops.len() - 1].1)
+    }
+
+    fn color_at(&self, t: f32) -> Color {
+        let t = match self.spread {
+            SpreadMethod::Pad => t,
+            SpreadMethod::Repeat => t - t.floor(),
+            SpreadMethod::Reflect => {
+                let t = t.abs() % 2.;
+                if t > 1. {
+                    2. - t
+                } else {
+                    t
+                }
+            },
+        };
+        let first = self.stops[0];
+        if !(t > first.0) {
+            return first.1;
+        }
+        for pair in self.stops.windows(2) {
+            let ((start, from), (end, to)) = (pair[0], pair[1]);
+            if t <= end {
+                if end <= start {
+                    return to;
+                }
+                let ratio = (t - start) / (end - start);
+                let mut color = [0.; 4];
+                for index in 0..4 {
+                    color[index] = from[index] + (to[index] - from[index]) * ratio;
+                }
+                return color;
+            }
+        }
+        self.stops[self.stops.len() - 1].1
+    }
+}
+
+fn premultiply(color: Color, opacity: f32) -> Color {
+    let alpha = color[3] * opacity;
+    [color[0] * alpha, color[1] * alpha, color[2] * alpha, alpha]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Document;
+    use euclid::default::Size2D;
+
+    fn rect(x: f32, y: f32, width: f32, height: f32) -> Path {
+        Path::rect(
+            Rect::new(Point2D::new(x, y), Size2D::new(width, height)),
+            Size2D::zero(),
+        )
+    }
+
+    fn render(svg: &str) -> Pixmap {
+        Document::parse(svg.as_bytes()).unwrap().render()
+    }
+
+    fn pixel(pixmap: &Pixmap, x: usize, y: usize) -> &[u8] {
+        let offset = (y * pixmap.width as usize + x) * 4;
+        &pixmap.data[offset..offset + 4]
+    }
+
+    #[test]
+    fn test_fill_mask() {
+        let identity = Transform2D::identity();
+        let mask = fill_mask(&rect(0., 0., 2., 2.), FillRule::NonZero, &identity, 4, 2);
+        assert_eq!(mask, vec![255, 255, 0, 0, 255, 255, 0, 0]);
+
+        // Partially covered pixels are anti-aliased.
+        let mask = fill_mask(&rect(0., 0., 1.5, 1.), FillRule::NonZero, &identity, 2, 1);
+        assert_eq!(mask, vec![255, 128]);
+
+        // The transform applies before rasterization.
+        let scale = Transform2D::create_scale(2., 2.);
+        let mask = fill_mask(&rect(0., 0., 1., 1.), FillRule::NonZero, &scale, 3, 3);
+        assert_eq!(mask, vec![255, 255, 0, 255, 255, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_fill_rules() {
+        // Two overlapping subpaths with the same direction.
+        let mut path = Path::new();
+        for &(left, right) in &[(0., 3.), (1., 2.)] {
+            path.move_to(Point2D::new(left, 0.));
+            path.line_to(Point2D::new(right, 0.));
+            path.line_to(Point2D::new(right, 1.));
+            path.line_to(Point2D::new(left, 1.));
+            path.close();
+        }
+        let identity = Transform2D::identity();
+        let mask = fill_mask(&path, FillRule::NonZero, &identity, 3, 1);
+        assert_eq!(mask, vec![255, 255, 255]);
+        let mask = fill_mask(&path, FillRule::EvenOdd, &identity, 3, 1);
+        assert_eq!(mask, vec![255, 0, 255]);
+    }
+
+    #[test]
+    fn test_render_shapes() {
+        let pixmap = render(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="10" height="10">
+                 <circle cx="5" cy="5" r="4" fill="lime"/>
+                 <line x1="0" y1="1" x2="10" y2="1" stroke="blue" stroke-width="2"/>
+               </svg>"#,
+        );
+        assert_eq!((pixmap.width, pixmap.height), (10, 10));
+        assert_eq!(pixel(&pixmap, 5, 5), &[0, 255, 0, 255]);
+        assert_eq!(pixel(&pixmap, 5, 0), &[0, 0, 255, 255]);
+        assert_eq!(pixel(&pixmap, 5, 1), &[0, 0, 255, 255]);
+        assert_eq!(pixel(&pixmap, 0, 9), &[0, 0, 0, 0]);
+        assert_eq!(pixel(&pixmap, 9, 9), &[0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_render_view_box() {
+        let pixmap = render(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="4" height="4" viewBox="0 0 2 2">
+                 <rect width="1" height="1" fill="red" fill-opacity="0.5"/>
+               </svg>"#,
+        );
+        assert_eq!(pixel(&pixmap, 0, 0), &[128, 0, 0, 128]);
+        assert_eq!(pixel(&pixmap, 1, 1), &[128, 0, 0, 128]);
+        assert_eq!(pixel(&pixmap, 2, 2), &[0, 0, 0, 0]);
+    }
+}
@@ -0,0 +1,276 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! Conversion of stroked paths into the polygons covered by their stroke.
+//!
+//! <https://www.w3.org/TR/SVG2/painting.html#StrokeShape>
+
+use crate::path::Polyline;
+use crate::tree::{LineCap, LineJoin, Stroke};
+use euclid::default::{Point2D, Vector2D};
+use std::f32::consts::PI;
+
+/// The maximum number of dashes and gaps a subpath can be split into.
+const MAX_DASHES: usize = 100_000;
+
+/// Returns the polygons whose union, with the non-zero fill rule, is the
+/// stroke of the given flattened subpaths.
+///
+/// `tolerance` is the maximum error of the approximation of round joins and
+/// caps, in user units.
+pub fn stroke_polygons(
+    polylines: &[Polyline],
+    stroke: &Stroke,
+    tolerance: f32,
+) -> Vec<Vec<Point2D<f32>>> {
+    let half_width = stroke.width / 2.;
+    let mut stroker = Stroker {
+        stroke,
+        half_width,
+        tolerance,
+        polygons: vec![],
+    };
+    if half_width <= 0. {
+        return stroker.polygons;
+    }
+    for polyline in polylines {
+        let mut points = polyline.points.clone();
+        points.dedup();
+        if polyline.closed && points.len() > 1 && points.first() == points.last() {
+            points.pop();
+        }
+        let dashes = if stroke.dash_array.is_empty() {
+            None
+        } else {
+            dash_polyline(&points, polyline.closed, stroke)
+        };
+        match dashes {
+            Some(dashes) => {
+                for dash in dashes {
+                    stroker.stroke_polyline(&dash, false);
+                }
+            },
+            None => stroker.stroke_polyline(&points, polyline.closed),
+        }
+    }
+    stroker.polygons
+}
+
+/// Splits a subpath into the dashes of the stroke, or returns `None` if the
+/// subpath should be stroked without dashes.
+///
+/// <https://www.w3.org/TR/SVG2/painting.html#StrokeDashing>
+fn dash_polyline(
+    points: &[Point2D<f32>],
+    closed: bool,
+    stroke: &Stroke,
+) -> Option<Vec<Vec<Point2D<f32>>>> {
+    let pattern_length: f32 = stroke.dash_array.iter().sum();
+    let mut dashes = vec![];
+    if points.is_empty() || !(pattern_length > 0.) {
+        return Some(dashes);
+    }
+    let mut points = points.to_vec();
+    if closed {
+        points.push(points[0]);
+    }
+    let length: f32 = points
+        .windows(2)
+        .map(|segment| (segment[1] - segment[0]).length())
+        .sum();
+    if length / pattern_length * stroke.dash_array.len() as f32 > MAX_DASHES as f32 {
+        // Like in Skia, patterns too small to be dashed paint a solid stroke.
+        return None;
+    }
+
+    // Find where in the dash pattern the subpath starts.
+    let mut offset = stroke.dash_offset % pattern_length;
+    if offset < 0. {
+        offset += pattern_length;
+    }
+    let mut index = 0;
+    while offset >= stroke.dash_array[index] {
+        offset -= stroke.dash_array[index];
+        index = (index + 1) % stroke.dash_array.len();
+    }
+    let mut remaining = stroke.dash_array[index] - offset;
+    let mut on = index % 2 == 0;
+    let starts_on = on;
+
+    let mut dash = if on { vec![points[0]] } else { vec![] };
+    for segment in points.windows(2) {
+        let (mut from, to) = (segment[0], segment[1]);
+        let mut length = (to - from).length();
+        while length > remaining {
+            let point = from + (to - from) * (remaining / length);
+            if on {
+                dash.push(point);
+                dashes.push(dash);
+                dash = vec![];
+            } else {
+                dash = vec![point];
+            }
+            length -= remaining;
+            from = point;
+            index = (index + 1) % stroke.dash_array.len();
+            remaining = stroke.dash_array[index];
+            on = !on;
+        }
+        remaining -= length;
+        if on {
+            dash.push(to);
+        }
+    }
+    if on && !dash.is_empty() {
+        // On closed subpaths, a dash crossing the start point is a single
+        // dash, joined at that point.
+        if closed && starts_on && !dashes.is_empty() {
+            dash.extend(dashes[0].drain(..).skip(1));
+            dashes[0] = dash;
+        } else {
+            dashes.push(dash);
+        }
+    }
+    Some(dashes)
+}
+
+struct Stroker<'a> {
+    stroke: &'a Stroke,
+    half_width: f32,
+    tolerance: f32,
+    polygons: Vec<Vec<Point2D<f32>>>,
+}
+
+impl<'a> Stroker<'a> {
+    fn stroke_polyline(&mut self, points: &[Point2D<f32>], closed: bool) {
+        match points.len() {
+            0 => return,
+            // Zero-length subpaths only get their caps painted.
+            // https://www.w3.org/TR/SVG2/painting.html#LineCaps
+            1 => {
+                match self.stroke.line_cap {
+                    LineCap::Butt => {},
+                    LineCap::Round => self.circle(points[0]),
+                    LineCap::Square => {
+                        self.square_cap(points[0], Vector2D::new(1., 0.));
+                        self.square_cap(points[0], Vector2D::new(-1., 0.));
+                    },
+                }
+                return;
+            },
+            _ => {},
+        }
+
+        let count = points.len();
+        let segments = if closed { count } else { count - 1 };
+        for index in 0..segments {
+            let (from, to) = (points[index], points[(index + 1) % count]);
+            let normal = normal(from, to) * self.half_width;
+            self.push(vec![from + normal, to + normal, to - normal, from - normal]);
+        }
+
+        let joins = if closed { 0..count } else { 1..count - 1 };
+        for index in joins {
+            let previous = points[(index + count - 1) % count];
+            let next = points[(index + 1) % count];
+            self.join(previous, points[index], next);
+        }
+
+        if !closed {
+            let direction = (points[count - 1] - points[count - 2]).normalize();
+            self.cap(points[count - 1], direction);
+            let direction = (points[0] - points[1]).normalize();
+            self.cap(points[0], direction);
+        }
+    }
+
+    /// <https://www.w3.org/TR/SVG2/painting.html#LineJoin>
+    fn join(&mut self, previous: Point2D<f32>, point: Point2D<f32>, next: Point2D<f32>) {
+        let incoming = (point - previous).normalize();
+        let outgoing = (next - point).normalize();
+        let cross = incoming.cross(outgoing);
+        if cross == 0. && incoming.dot(outgoing) > 0. {
+            return;
+        }
+        if self.stroke.line_join == LineJoin::Round {
+            return self.circle(point);
+        }
+
+        // The join fills the gap on the outer side of the turn.
+        let side = if cross > 0. { -1. } else { 1. };
+        let before = point + normal(previous, point) * (self.half_width * side);
+        let after = point + normal(point, next) * (self.half_width * side);
+        let cos = incoming.dot(outgoing).max(-1.).min(1.);
+        // The ratio of the miter length to the stroke width is
+        // 1 / sin(θ / 2), where θ is the angle between the segments.
+        let miter_ratio = 1. / ((1. + cos) / 2.).sqrt();
+        if self.stroke.line_join == LineJoin::Miter && miter_ratio <= self.stroke.miter_limit {
+            let bisector = ((before - point) + (after - point)).normalize();
+            let tip = point + bisector * (self.half_width * miter_ratio);
+            return self.push(vec![point, before, tip, after]);
+        }
+        self.push(vec![point, before, after]);
+    }
+
+    /// <https://www.w3.org/TR/SVG2/painting.html#LineCaps>
+    fn cap(&mut self, point: Point2D<f32>, direction: Vector2D<f32>) {
+        match self.stroke.line_cap {
+            LineCap::Butt => {},
+            LineCap::Round => self.circle(point),
+            LineCap::Square => self.square_cap(point, direction),
+        }
+    }
+
+    fn square_cap(&mut self, point: Point2D<f32>, direction: Vector2D<f32>) {
+        let normal = Vector2D::new(-direction.y, direction.x) * self.half_width;
+        let extent = direction * self.half_width;
+        self.push(vec![
+            point + normal,
+            point + normal + extent,
+            point - normal + extent,
+            point - normal,
+        ]);
+    }
+
+    fn circle(&mut self, center: Point2D<f32>) {
+        let radius = self.half_width;
+        // The largest angle between the vertices that keeps the chords within
+        // the tolerance of the circle.
+        let step = 2. * (1. - self.tolerance / radius).max(0.).min(1.).acos();
+        let count = (2. * PI / step).ceil();
+        let count = if count.is_finite() {
+            count.max(8.).min(512.) as usize
+        } else {
+            8
+        };
+        let polygon = (0..count)
+            .map(|index| {
+                let (sin, cos) = (2. * PI * index as f32 / count as f32).sin_cos();
+                center + Vector2D::new(cos, sin) * radius
+            })
+            .collect();
+        self.push(polygon);
+    }
+
+    /// Adds a polygon to the stroke, making sure that it winds in the same
+    /// direction as the others so that they don't cancel each other out.
+    fn push(&mut self, mut polygon: Vec<Point2D<f32>>) {
+        let area: f32 = (0..polygon.len())
+            .map(|index| {
+                let next = polygon[(index + 1) % polygon.len()];
+                polygon[index].to_vector().cross(next.to_vector())
+            })
+            .sum();
+        if area < 0. {
+            polygon.reverse();
+        }
+        self.polygons.push(polygon);
+    }
+}
+
+/// The unit vector to the left of the direction from `from` to `to`.
+fn normal(from: Point2D<f32>, to: Point2D<f32>) -> Vector2D<f32> {
+    let direction = (to - from).normalize();
+    Vector2D::new(-direction.y, direction.x)
+}
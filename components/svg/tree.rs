@@ -0,0 +1,268 @@
+/* This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this
+ * file, You can obtain one at https://mozilla.org/MPL/2.0/. */
+
+//! The render tree of SVG content: what is left of the SVG elements once their
+//! geometry and style have been resolved.
+
+use crate::path::Path;
+use crate::raster::{Canvas, Pixmap};
+use cssparser::RGBA;
+use euclid::default::{Point2D, Rect, Size2D, Transform2D};
+use std::sync::Arc;
+
+/// The content of an `<svg>` element.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tree {
+    /// The `viewBox` attribute of the `<svg>` element, along with its
+    /// `preserveAspectRatio` attribute.
+    pub view_box: Option<ViewBox>,
+    pub children: Vec<Node>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Node {
+    Group(Group),
+    Path(PathNode),
+    Text(TextNode),
+}
+
+/// A group of nodes sharing a transform and an opacity, like the content of a
+/// `<g>` element.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Group {
+    pub transform: Transform2D<f32>,
+    pub opacity: f32,
+    /// The rectangle the children are clipped to, in their coordinate system,
+    /// like the viewport of a nested `<svg>` element.
+    pub clip: Option<Rect<f32>>,
+    pub children: Vec<Node>,
+}
+
+/// A shape, like a `<path>` or `<rect>` element.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PathNode {
+    pub path: Path,
+    pub fill: Option<Fill>,
+    pub stroke: Option<Stroke>,
+}
+
+/// A `<text>` element. Text isn't rasterized along with shapes: the embedder
+/// is expected to draw it, with the font of the element.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextNode {
+    /// The index of the element in the list of text elements returned along
+    /// with the tree.
+    pub index: usize,
+    /// The position of the start of the text, in user units.
+    pub position: Point2D<f32>,
+    pub anchor: TextAnchor,
+    pub text: String,
+    pub fill: Option<Fill>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TextAnchor {
+    Start,
+    Middle,
+    End,
+}
+
+/// <https://www.w3.org/TR/SVG2/coords.html#ViewBoxAttribute>
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ViewBox {
+    pub rect: Rect<f32>,
+    pub aspect_ratio: AspectRatio,
+}
+
+/// <https://www.w3.org/TR/SVG2/coords.html#PreserveAspectRatioAttribute>
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AspectRatio {
+    /// How the view box is aligned along each axis, as a fraction of the free
+    /// space, or `None` to stretch it to the viewport.
+    pub align: Option<(f32, f32)>,
+    pub slice: bool,
+}
+
+impl Default for AspectRatio {
+    fn default() -> AspectRatio {
+        AspectRatio {
+            align: Some((0.5, 0.5)),
+            slice: false,
+        }
+    }
+}
+
+impl ViewBox {
+    /// The transform from the user space of the view box to a viewport of the
+    /// given size.
+    ///
+    /// <https://www.w3.org/TR/SVG2/coords.html#ComputingAViewportsTransform>
+    pub fn transform(&self, viewport: Size2D<f32>) -> Transform2D<f32> {
+        let rect = self.rect;
+        if rect.size.width <= 0. || rect.size.height <= 0. {
+            return Transform2D::create_scale(0., 0.);
+        }
+        let mut scale_x = viewport.width / rect.size.width;
+        let mut scale_y = viewport.height / rect.size.height;
+        let (align_x, align_y) = match self.aspect_ratio.align {
+            Some(align) => {
+                let scale = if self.aspect_ratio.slice {
+                    scale_x.max(scale_y)
+                } else {
+                    scale_x.min(scale_y)
+                };
+                scale_x = scale;
+                scale_y = scale;
+                align
+            },
+            None => (0., 0.),
+        };
+        let translate_x =
+            -rect.origin.x * scale_x + (viewport.width - rect.size.width * scale_x) * align_x;
+        let translate_y =
+            -rect.origin.y * scale_y + (viewport.height - rect.size.height * scale_y) * align_y;
+        Transform2D::row_major(scale_x, 0., 0., scale_y, translate_x, translate_y)
+    }
+}
+
+/// <https://www.w3.org/TR/SVG2/painting.html#SpecifyingFillPaint>
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fill {
+    pub paint: Paint,
+    pub opacity: f32,
+    pub rule: FillRule,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FillRule {
+    NonZero,
+    EvenOdd,
+}
+
+/// <https://www.w3.org/TR/SVG2/painting.html#SpecifyingStrokePaint>
+#[derive(Clone, Debug, PartialEq)]
+pub struct Stroke {
+    pub paint: Paint,
+    pub opacity: f32,
+    pub width: f32,
+    pub line_cap: LineCap,
+    pub line_join: LineJoin,
+    pub miter_limit: f32,
+    /// The lengths of the dashes and gaps, or an empty list for a solid
+    /// stroke.
+    pub dash_array: Vec<f32>,
+    pub dash_offset: f32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LineCap {
+    Butt,
+    Round,
+    Square,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LineJoin {
+    Miter,
+    Round,
+    Bevel,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Paint {
+    Color(RGBA),
+    LinearGradient(Arc<LinearGradient>),
+    RadialGradient(Arc<RadialGradient>),
+}
+
+/// <https://www.w3.org/TR/SVG2/pservers.html#LinearGradientElement>
+#[derive(Clone, Debug, PartialEq)]
+pub struct LinearGradient {
+    pub start: Point2D<f32>,
+    pub end: Point2D<f32>,
+    pub gradient: Gradient,
+}
+
+/// <https://www.w3.org/TR/SVG2/pservers.html#RadialGradientElement>
+#[derive(Clone, Debug, PartialEq)]
+pub struct RadialGradient {
+    pub center: Point2D<f32>,
+    pub radius: f32,
+    pub focal_point: Point2D<f32>,
+    pub gradient: Gradient,
+}
+
+/// The attributes shared by both kinds of gradients.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gradient {
+    /// Whether the coordinates of the gradient are fractions of the bounding
+    /// box of the painted element rather than user units.
+    pub bounding_box_units: bool,
+    pub transform: Transform2D<f32>,
+    pub spread: SpreadMethod,
+    pub stops: Vec<GradientStop>,
+}
+
+/// <https://www.w3.org/TR/SVG2/pservers.html#StopElement>
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GradientStop {
+    pub offset: f32,
+    pub color: RGBA,
+}
+
+/// <https://www.w3.org/TR/SVG2/pservers.html#LinearGradientElementSpreadMethodAttribute>
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SpreadMethod {
+    Pad,
+    Reflect,
+    Repeat,
+}
+
+impl Tree {
+    /// The transform from the user space of the `<svg>` element to its
+    /// viewport.
+    pub fn viewport_transform(&self, viewport: Size2D<f32>) -> Transform2D<f32> {
+        self.view_box.map_or(Transform2D::identity(), |view_box| {
+            view_box.transform(viewport)
+        })
+    }
+
+    /// Rasterizes the shapes of the tree for a viewport of the given size, in
+    /// CSS pixels, with `scale` device pixels per CSS pixel.
+    pub fn render(&self, viewport: Size2D<f32>, scale: f32) -> Pixmap {
+        let width = (viewport.width * scale).ceil().max(0.) as u32;
+        let height = (viewport.height * scale).ceil().max(0.) as u32;
+        let transform = self
+            .viewport_transform(viewport)
+            .post_transform(&Transform2D::create_scale(scale, scale));
+        let mut canvas = Canvas::new(width, height);
+        canvas.draw_nodes(&self.children, &transform);
+        canvas.into_pixmap()
+    }
+
+    /// Calls `f` with each text node of the tree and the position of the start
+    /// of its text in the viewport, in CSS pixels.
+    pub fn for_each_text<F>(&self, viewport: Size2D<f32>, mut f: F)
+    where
+        F: FnMut(&TextNode, Point2D<f32>),
+    {
+        fn visit<F>(nodes: &[Node], transform: &Transform2D<f32>, f: &mut F)
+        where
+            F: FnMut(&TextNode, Point2D<f32>),
+        {
+            for node in nodes {
+                match *node {
+                    Node::Group(ref group) if group.opacity > 0. => visit(
+                        &group.children,
+                        &group.transform.post_transform(transform),
+                        f,
+                    ),
+                    Node::Text(ref text) => f(text, transform.transform_point(text.position)),
+                    _ => {},
+                }
+            }
+        }
+        visit(&self.children, &self.viewport_transform(viewport), &mut f)
+    }
+}
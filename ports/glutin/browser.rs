@@ -440,6 +440,21 @@ where
                 EmbedderMsg::HideIME => {
                     debug!("HideIME received");
                 },
+                EmbedderMsg::ShowValidationMessage(message) => {
+                    if opts::get().headless {
+                        info!("Validation message: {}", message);
+                    } else {
+                        let _ = thread::Builder::new()
+                            .name("display validation message".to_owned())
+                            .spawn(move || {
+                                tinyfiledialogs::message_box_ok(
+                                    "Invalid form field",
+                                    &message,
+                                    MessageBoxIcon::Warning,
+                                );
+                            });
+                    }
+                },
                 EmbedderMsg::ReportProfile(bytes) => {
                     let filename = env::var("PROFILE_OUTPUT").unwrap_or("samples.json".to_string());
                     let result = File::create(&filename).and_then(|mut f| f.write_all(&bytes));
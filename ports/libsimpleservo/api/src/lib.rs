@@ -575,6 +575,7 @@ impl ServoGlue {
                 EmbedderMsg::HeadParsed |
                 EmbedderMsg::SetFullscreenState(..) |
                 EmbedderMsg::Panic(..) |
+                EmbedderMsg::ShowValidationMessage(..) |
                 EmbedderMsg::ReportProfile(..) => {},
             }
         }
@@ -7,10 +7,15 @@ importScripts("interfaces.js");
 
 // IMPORTANT: Do not change the list below without review from a DOM peer!
 test_interfaces([
+  "AbortController",
+  "AbortSignal",
   "Blob",
+  "BroadcastChannel",
+  "ByteLengthQueuingStrategy",
   "CanvasGradient",
   "CanvasPattern",
   "CloseEvent",
+  "CountQueuingStrategy",
   "DOMMatrix",
   "DOMMatrixReadOnly",
   "DOMPoint",
@@ -21,6 +26,7 @@ test_interfaces([
   "CustomEvent",
   "DedicatedWorkerGlobalScope",
   "DOMException",
+  "DOMStringList",
   "ErrorEvent",
   "Event",
   "EventSource",
@@ -32,8 +38,23 @@ test_interfaces([
   "FormData",
   "Headers",
   "History",
+  "IDBCursor",
+  "IDBCursorWithValue",
+  "IDBDatabase",
+  "IDBFactory",
+  "IDBIndex",
+  "IDBKeyRange",
+  "IDBObjectStore",
+  "IDBOpenDBRequest",
+  "IDBRequest",
+  "IDBTransaction",
+  "IDBVersionChangeEvent",
+  "ImageBitmap",
   "ImageData",
+  "MessageChannel",
   "MessageEvent",
+  "MessagePort",
+  "Path2D",
   "Performance",
   "PerformanceEntry",
   "PerformanceMark",
@@ -44,10 +65,19 @@ test_interfaces([
   "PerformanceResourceTiming",
   "ProgressEvent",
   "PromiseRejectionEvent",
+  "ReadableByteStreamController",
+  "ReadableStream",
+  "ReadableStreamBYOBReader",
+  "ReadableStreamBYOBRequest",
+  "ReadableStreamDefaultController",
+  "ReadableStreamDefaultReader",
   "Request",
   "Response",
   "TextDecoder",
   "TextEncoder",
+  "TextMetrics",
+  "TransformStream",
+  "TransformStreamDefaultController",
   "URL",
   "URLSearchParams",
   "WebSocket",
@@ -55,6 +85,9 @@ test_interfaces([
   "WorkerGlobalScope",
   "WorkerLocation",
   "WorkerNavigator",
+  "WritableStream",
+  "WritableStreamDefaultController",
+  "WritableStreamDefaultWriter",
   "XMLHttpRequest",
   "XMLHttpRequestEventTarget",
   "XMLHttpRequestUpload",
@@ -0,0 +1,17 @@
+onmessage = function(e) {
+  switch (e.data) {
+    case "port":
+      // Echo every message received on the transferred port.
+      var port = e.ports[0];
+      port.onmessage = function(e) {
+        port.postMessage("echo: " + e.data);
+      };
+      postMessage("ready");
+      break;
+    case "broadcast":
+      var channel = new BroadcastChannel("webmessaging-worker");
+      channel.postMessage("from worker");
+      channel.close();
+      break;
+  }
+};